@@ -0,0 +1,119 @@
+//! Text edits and code actions
+
+use covenant_ast::Span;
+use thiserror::Error;
+
+/// Edits are defined next to the symbol graph's rename, which returns them
+pub use covenant_symbols::TextEdit;
+
+/// What kind of action an edit set represents (mirrors LSP `CodeActionKind`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeActionKind {
+    /// Fixes a diagnostic
+    QuickFix,
+    /// Behaviour-preserving restructuring
+    Refactor,
+    /// Whitespace and layout only
+    Format,
+}
+
+/// A titled set of edits to offer as a single action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeAction {
+    pub title: String,
+    pub kind: CodeActionKind,
+    /// Diagnostic code this action fixes, if any
+    pub fixes: Option<String>,
+    pub edits: Vec<TextEdit>,
+}
+
+/// Errors applying edits to a source string
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EditError {
+    #[error("edit {start}..{end} is outside the source (length {len})")]
+    OutOfBounds { start: usize, end: usize, len: usize },
+
+    #[error("edit {start}..{end} does not fall on a character boundary")]
+    NotCharBoundary { start: usize, end: usize },
+
+    #[error("edits {first_start}..{first_end} and {second_start}..{second_end} overlap")]
+    Overlapping {
+        first_start: usize,
+        first_end: usize,
+        second_start: usize,
+        second_end: usize,
+    },
+}
+
+/// Apply a set of edits to `source`
+///
+/// Edit spans refer to the original source, so edits may be given in any
+/// order. Insertions at the same offset are applied in the order given.
+/// Overlapping replacements are rejected rather than guessed at.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> Result<String, EditError> {
+    let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+    sorted.sort_by_key(|edit| (edit.span.start, edit.span.end));
+
+    for edit in &sorted {
+        let Span { start, end } = edit.span;
+        if start > end || end > source.len() {
+            return Err(EditError::OutOfBounds { start, end, len: source.len() });
+        }
+        if !source.is_char_boundary(start) || !source.is_char_boundary(end) {
+            return Err(EditError::NotCharBoundary { start, end });
+        }
+    }
+
+    for pair in sorted.windows(2) {
+        let (first, second) = (pair[0].span, pair[1].span);
+        if second.start < first.end {
+            return Err(EditError::Overlapping {
+                first_start: first.start,
+                first_end: first.end,
+                second_start: second.start,
+                second_end: second.end,
+            });
+        }
+    }
+
+    let mut result = String::with_capacity(source.len());
+    let mut cursor = 0;
+    for edit in sorted {
+        result.push_str(&source[cursor..edit.span.start]);
+        result.push_str(&edit.new_text);
+        cursor = edit.span.end;
+    }
+    result.push_str(&source[cursor..]);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_edits_in_any_order() {
+        let edits = vec![
+            TextEdit::replace(Span::new(6, 11), "there"),
+            TextEdit::insert(0, ">> "),
+            TextEdit::insert(11, "!"),
+        ];
+        assert_eq!(apply_edits("hello world", &edits).unwrap(), ">> hello there!");
+    }
+
+    #[test]
+    fn test_overlapping_edits_rejected() {
+        let edits = vec![
+            TextEdit::replace(Span::new(0, 5), "a"),
+            TextEdit::replace(Span::new(3, 8), "b"),
+        ];
+        assert!(matches!(
+            apply_edits("hello world", &edits),
+            Err(EditError::Overlapping { .. })
+        ));
+        assert!(matches!(
+            apply_edits("hi", &[TextEdit::insert(3, "x")]),
+            Err(EditError::OutOfBounds { .. })
+        ));
+    }
+}
@@ -0,0 +1,192 @@
+//! Quick fixes for effect diagnostics
+//!
+//! Missing effects (E-EFFECT-001, E-EFFECT-002) are fixed by declaring
+//! them on the offending snippet. Edits are anchored at section start
+//! offsets and line boundaries found in the source text, since section
+//! span ends run on into the following token.
+
+use covenant_ast::{Program, Section, Snippet};
+use covenant_checker::{check_effects, EffectCheckResult, EffectError};
+use covenant_parser::parse;
+use covenant_symbols::build_symbol_graph;
+
+use crate::{apply_edits, ActionError, CodeAction, CodeActionKind, TextEdit};
+
+/// Quick fixes for the effect violations in `result`
+///
+/// `program` must be the parse of `source`. Violations whose function has
+/// no snippet in this program (or no sections to anchor on) get no fix.
+pub fn effect_fixes(source: &str, program: &Program, result: &EffectCheckResult) -> Vec<CodeAction> {
+    let Program::Snippets { snippets, .. } = program else {
+        return Vec::new();
+    };
+
+    result.violations.iter().filter_map(|violation| {
+        let (code, function, missing) = match violation {
+            EffectError::PureCallsEffectful { function, effects, .. } => ("E-EFFECT-001", function, effects),
+            EffectError::MissingEffect { function, missing, .. } => ("E-EFFECT-002", function, missing),
+            EffectError::ParameterNotCovered { .. }
+            | EffectError::UnknownEffect { .. }
+            | EffectError::PolicyViolation { .. } => return None,
+        };
+        let snippet = snippets.iter().find(|s| &s.id == function)?;
+        let edit = declare_effects_edit(source, snippet, missing)?;
+
+        Some(CodeAction {
+            title: format!("Declare effect(s) {} on `{}`", missing.join(", "), function),
+            kind: CodeActionKind::QuickFix,
+            fixes: Some(code.to_string()),
+            edits: vec![edit],
+        })
+    }).collect()
+}
+
+/// Apply every effect fix until the file checks cleanly
+///
+/// Declaring an effect on a function can make its callers miss it in turn,
+/// so fixes are recomputed and reapplied until none remain. Each pass
+/// settles at least one level of the call graph, so the number of passes
+/// is bounded by the number of snippets.
+pub fn fix_effects(source: &str) -> Result<String, ActionError> {
+    let max_passes = match parse(source)? {
+        Program::Snippets { snippets, .. } => snippets.len() + 1,
+        Program::Legacy { .. } => 0,
+    };
+    let mut current = source.to_string();
+
+    for _ in 0..max_passes {
+        let program = parse(&current)?;
+        let graph = build_symbol_graph(&program).map_err(ActionError::Symbols)?.graph;
+        let result = check_effects(&graph);
+
+        let edits: Vec<TextEdit> = effect_fixes(&current, &program, &result)
+            .into_iter()
+            .flat_map(|action| action.edits)
+            .collect();
+        if edits.is_empty() {
+            break;
+        }
+        current = apply_edits(&current, &edits)?;
+    }
+
+    Ok(current)
+}
+
+/// Insert `effect` lines for `missing` into the snippet's effects section,
+/// creating the section before the first existing one if needed
+fn declare_effects_edit(source: &str, snippet: &Snippet, missing: &[String]) -> Option<TextEdit> {
+    let existing = snippet.sections.iter().find_map(|section| match section {
+        Section::Effects(effects) => Some(effects),
+        _ => None,
+    });
+
+    if let Some(effects) = existing {
+        let indent = match effects.effects.first() {
+            Some(first) => indent_at(source, first.span.start).to_string(),
+            None => format!("{}  ", indent_at(source, effects.span.start)),
+        };
+        let lines: String = missing.iter()
+            .map(|effect| format!("{}effect {}\n", indent, effect))
+            .collect();
+        let offset = next_line_start(source, effects.span.start);
+        let text = if offset == source.len() && !source.ends_with('\n') {
+            format!("\n{}", lines)
+        } else {
+            lines
+        };
+        return Some(TextEdit::insert(offset, text));
+    }
+
+    let anchor = snippet.sections.first()?.span().start;
+    let indent = indent_at(source, anchor);
+    let mut text = format!("{}effects\n", indent);
+    for effect in missing {
+        text.push_str(&format!("{}  effect {}\n", indent, effect));
+    }
+    text.push_str(&format!("{}end\n\n", indent));
+    Some(TextEdit::insert(line_start(source, anchor), text))
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+fn next_line_start(source: &str, offset: usize) -> usize {
+    source[offset..].find('\n').map_or(source.len(), |i| offset + i + 1)
+}
+
+/// Leading whitespace of the line containing `offset`, up to `offset`
+fn indent_at(source: &str, offset: usize) -> &str {
+    let start = line_start(source, offset);
+    let prefix = &source[start..offset];
+    let len = prefix.len() - prefix.trim_start().len();
+    &prefix[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRINTLN: &str = r#"snippet id="console.println" kind="fn"
+effects
+  effect console
+end
+signature
+  fn name="println"
+    param name="message" type="String"
+    returns type="Unit"
+  end
+end
+end
+"#;
+
+    fn caller(effects: &str) -> String {
+        format!(r#"{}
+snippet id="app.greet" kind="fn"
+{}signature
+  fn name="greet"
+    returns type="Unit"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="console.println"
+    arg name="message" lit="hi"
+    as="_"
+  end
+end
+end
+"#, PRINTLN, effects)
+    }
+
+    fn violations(source: &str) -> usize {
+        let program = parse(source).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+        check_effects(&graph).violations.len()
+    }
+
+    #[test]
+    fn test_fix_adds_effects_section() {
+        let source = caller("");
+        let program = parse(&source).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+        let actions = effect_fixes(&source, &program, &check_effects(&graph));
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].fixes.as_deref(), Some("E-EFFECT-001"));
+
+        let fixed = apply_edits(&source, &actions[0].edits).unwrap();
+        assert!(fixed.contains("snippet id=\"app.greet\" kind=\"fn\"\neffects\n  effect console\nend\n\nsignature"));
+        assert_eq!(violations(&fixed), 0);
+    }
+
+    #[test]
+    fn test_fix_extends_existing_section() {
+        let source = caller("effects\n  effect filesystem\nend\n");
+        let fixed = fix_effects(&source).unwrap();
+        assert!(fixed.contains("effects\n  effect console\n  effect filesystem\nend"));
+        assert_eq!(violations(&fixed), 0);
+
+        // Fixing fixed code is a no-op
+        assert_eq!(fix_effects(&fixed).unwrap(), fixed);
+    }
+}
@@ -0,0 +1,55 @@
+//! Formatting as a whole-document edit
+
+use covenant_ast::printer::to_cov;
+use covenant_ast::Span;
+use covenant_parser::parse;
+
+use crate::{ActionError, CodeAction, CodeActionKind, TextEdit};
+
+/// Reformat `source` into canonical form
+///
+/// Returns `None` when the source is already canonical, so formatting
+/// formatted code yields no edits.
+pub fn format_document(source: &str) -> Result<Option<CodeAction>, ActionError> {
+    let program = parse(source)?;
+    let canonical = to_cov(&program);
+
+    if is_canonical(source, &canonical) {
+        return Ok(None);
+    }
+
+    Ok(Some(CodeAction {
+        title: "Format document".to_string(),
+        kind: CodeActionKind::Format,
+        fixes: None,
+        edits: vec![TextEdit::replace(Span::new(0, source.len()), canonical)],
+    }))
+}
+
+/// Compare ignoring trailing whitespace, matching `covenant format --check`
+pub fn is_canonical(source: &str, canonical: &str) -> bool {
+    let normalize = |s: &str| -> String {
+        s.lines()
+            .map(|line| line.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    normalize(source) == normalize(canonical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply_edits;
+
+    #[test]
+    fn test_format_is_idempotent() {
+        let source = "snippet id=\"math.one\" kind=\"fn\"\nsignature\n fn name=\"one\"\n returns type=\"Int\"\n end\nend\nbody\nstep id=\"s1\" kind=\"compute\"\n op=add\n input lit=1\n input lit=0\n as=\"_\"\nend\nend\nend\n";
+
+        let action = format_document(source).unwrap().expect("source is not canonical");
+        assert_eq!(action.kind, CodeActionKind::Format);
+        let formatted = apply_edits(source, &action.edits).unwrap();
+
+        assert!(format_document(&formatted).unwrap().is_none());
+    }
+}
@@ -0,0 +1,43 @@
+//! Covenant Code Actions
+//!
+//! Refactorings, auto-fixes, and formatting expressed as structured text
+//! edits. Nothing in this crate writes files: every action returns
+//! [`TextEdit`]s (a byte range plus replacement text) derived from AST
+//! spans, so an editor can offer them as code actions and the CLI's
+//! `--fix` can apply them with [`apply_edits`].
+//!
+//! Actions are idempotent: running one against its own output produces
+//! no further edits.
+
+mod edit;
+mod fixes;
+mod format;
+mod rename;
+
+pub use edit::*;
+pub use fixes::*;
+pub use format::*;
+pub use rename::*;
+
+use covenant_parser::ParseError;
+use covenant_symbols::SymbolError;
+use thiserror::Error;
+
+/// Errors raised while computing code actions
+#[derive(Debug, Error)]
+pub enum ActionError {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+
+    #[error("{} symbol error(s)", .0.len())]
+    Symbols(Vec<SymbolError>),
+
+    #[error("unknown snippet `{0}`")]
+    UnknownSnippet(String),
+
+    #[error("a snippet named `{0}` already exists")]
+    NameTaken(String),
+
+    #[error(transparent)]
+    Edit(#[from] EditError),
+}
@@ -0,0 +1,240 @@
+//! Rename a snippet and the references to it
+
+use covenant_ast::{walk_type, Program, Section, SignatureKind, Snippet, Span, Type, TypeKind, Visitor};
+use covenant_symbols::RenameError;
+
+use crate::{ActionError, CodeAction, CodeActionKind, TextEdit};
+
+/// Rename snippet `old` to `new`, updating its `id=`, every `fn=` call,
+/// relation target and test `covers=` link to it within `program` (see
+/// [`covenant_symbols::rename`]). Renaming an alias snippet whose name is
+/// the last segment of its ID also renames the alias and every type that
+/// names it.
+///
+/// `program` must be the parse of `source`. Calls from other files are not
+/// visible here; callers renaming across a project apply this per file.
+pub fn rename_snippet(source: &str, program: &Program, old: &str, new: &str) -> Result<CodeAction, ActionError> {
+    let Program::Snippets { snippets, .. } = program else {
+        return Err(ActionError::UnknownSnippet(old.to_string()));
+    };
+    if snippets.iter().any(|s| s.id == new) {
+        return Err(ActionError::NameTaken(new.to_string()));
+    }
+    let target = snippets.iter()
+        .find(|s| s.id == old)
+        .ok_or_else(|| ActionError::UnknownSnippet(old.to_string()))?;
+
+    let graph = covenant_symbols::build_symbol_graph(program).map_err(ActionError::Symbols)?.graph;
+    let mut edits = covenant_symbols::rename(&graph, program, source, old, new).map_err(|err| match err {
+        RenameError::UnknownSymbol(name) => ActionError::UnknownSnippet(name),
+        RenameError::NameTaken(name) => ActionError::NameTaken(name),
+    })?;
+    edits.extend(alias_edits(source, snippets, target, old, new));
+    edits.sort_by_key(|edit| edit.span.start);
+
+    Ok(CodeAction {
+        title: format!("Rename `{}` to `{}`", old, new),
+        kind: CodeActionKind::Refactor,
+        fixes: None,
+        edits,
+    })
+}
+
+/// Edits renaming the alias `target` declares and its uses as a type, when
+/// the alias is named after the last segment of its ID
+fn alias_edits(source: &str, snippets: &[Snippet], target: &Snippet, old: &str, new: &str) -> Vec<TextEdit> {
+    let old_name = last_segment(old);
+    let new_name = last_segment(new);
+    let alias = target.sections.iter().find_map(|section| match section {
+        Section::Signature(sig) => match &sig.kind {
+            SignatureKind::Alias(alias) if alias.name == old_name => Some(alias),
+            _ => None,
+        },
+        _ => None,
+    });
+    let Some(alias) = alias else { return Vec::new() };
+
+    let mut edits: Vec<TextEdit> = attribute_value_edit(source, alias.span, "name", old_name, new_name)
+        .into_iter()
+        .collect();
+    let mut uses = TypeUses { old_name, new_name, renamed: Vec::new() };
+    for snippet in snippets {
+        uses.visit_snippet(snippet);
+    }
+    for (span, written, renamed) in uses.renamed {
+        edits.extend(
+            attribute_value_edit(source, span, "type", &written, &renamed)
+                .or_else(|| attribute_value_edit(source, span, "of", &written, &renamed)),
+        );
+    }
+    edits
+}
+
+fn last_segment(id: &str) -> &str {
+    id.rsplit('.').next().unwrap_or(id)
+}
+
+/// Named types that mention an alias, with the text written and its
+/// renamed form; inline generics such as `List<UserId>` are rewritten too
+struct TypeUses<'a> {
+    old_name: &'a str,
+    new_name: &'a str,
+    renamed: Vec<(Span, String, String)>,
+}
+
+impl Visitor for TypeUses<'_> {
+    fn visit_type(&mut self, ty: &Type) {
+        if let TypeKind::Named(path) = &ty.kind {
+            let written = path.name();
+            let mut renamed = String::new();
+            let mut word = String::new();
+            for c in written.chars().chain(std::iter::once(' ')) {
+                if c.is_alphanumeric() || c == '_' || c == '.' {
+                    word.push(c);
+                    continue;
+                }
+                renamed.push_str(if word == self.old_name { self.new_name } else { &word });
+                word.clear();
+                renamed.push(c);
+            }
+            renamed.pop();
+            if renamed != written {
+                self.renamed.push((ty.span, written.to_string(), renamed));
+            }
+        }
+        walk_type(self, ty);
+    }
+}
+
+/// Edit replacing the first `attr="value"` inside `within` with `new`
+///
+/// The node's own attributes precede any nested steps, so the first match
+/// belongs to the node itself.
+fn attribute_value_edit(source: &str, within: Span, attr: &str, value: &str, new: &str) -> Option<TextEdit> {
+    let end = within.end.min(source.len());
+    let needle = format!("{}=\"{}\"", attr, value);
+    let found = source.get(within.start..end)?.find(&needle)?;
+    let start = within.start + found + attr.len() + 2;
+    Some(TextEdit::replace(Span::new(start, start + value.len()), new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply_edits;
+    use covenant_parser::parse;
+
+    const SOURCE: &str = r#"snippet id="math.double" kind="fn"
+signature
+  fn name="double"
+    param name="x" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=add
+    input var="x"
+    input var="x"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+
+snippet id="math.quad" kind="fn"
+signature
+  fn name="quad"
+    param name="x" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="math.double"
+    arg name="x" from="x"
+    as="twice"
+  end
+  step id="s2" kind="call"
+    fn="math.double"
+    arg name="x" from="twice"
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+
+    #[test]
+    fn test_rename_updates_definition_and_calls() {
+        let program = parse(SOURCE).unwrap();
+        let action = rename_snippet(SOURCE, &program, "math.double", "math.twice").unwrap();
+        assert_eq!(action.edits.len(), 3);
+
+        let renamed = apply_edits(SOURCE, &action.edits).unwrap();
+        assert!(!renamed.contains("math.double"));
+        assert_eq!(renamed.matches("fn=\"math.twice\"").count(), 2);
+        assert!(renamed.contains("snippet id=\"math.twice\""));
+
+        // The old name is gone, so repeating the rename is rejected
+        let program = parse(&renamed).unwrap();
+        assert!(matches!(
+            rename_snippet(&renamed, &program, "math.double", "math.twice"),
+            Err(ActionError::NameTaken(_))
+        ));
+    }
+
+    #[test]
+    fn test_rename_alias_updates_type_uses() {
+        let source = r#"snippet id="ids.UserId" kind="alias"
+signature
+  alias name="UserId" type="Int"
+end
+end
+
+snippet id="users.lookup" kind="fn"
+signature
+  fn name="lookup"
+    param name="id" type="UserId"
+    param name="all" type="List<UserId>"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="id"
+    as="_"
+  end
+end
+end
+"#;
+        let program = parse(source).unwrap();
+        let action = rename_snippet(source, &program, "ids.UserId", "ids.AccountId").unwrap();
+        assert_eq!(action.edits.len(), 4);
+
+        let renamed = apply_edits(source, &action.edits).unwrap();
+        assert!(!renamed.contains("UserId"));
+        assert!(renamed.contains("alias name=\"AccountId\" type=\"Int\""));
+        assert!(renamed.contains("param name=\"id\" type=\"AccountId\""));
+        assert!(renamed.contains("type=\"List<AccountId>\""));
+    }
+
+    #[test]
+    fn test_rename_rejects_existing_name() {
+        let program = parse(SOURCE).unwrap();
+        assert!(matches!(
+            rename_snippet(SOURCE, &program, "math.double", "math.quad"),
+            Err(ActionError::NameTaken(_))
+        ));
+        assert!(matches!(
+            rename_snippet(SOURCE, &program, "math.half", "math.third"),
+            Err(ActionError::UnknownSnippet(_))
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+//! Binary serialization of programs and other artifacts
+//!
+//! JSON is the interchange format; the binary form is for caches and
+//! storage, where size and speed matter more than readability. An encoded
+//! artifact is a header (the `COVB` magic, a tag naming the kind of
+//! artifact, and the schema version it was written at) followed by the
+//! value as MessagePack. MessagePack records field names, so a value
+//! written before an optional field was added still decodes.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{check_supported, Program, SchemaError, AST_SCHEMA_VERSION};
+
+const MAGIC: &[u8; 4] = b"COVB";
+
+/// Magic, artifact tag and little-endian schema version
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4;
+
+/// The kind of value a binary artifact holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryArtifact {
+    Program,
+    SymbolGraph,
+}
+
+impl BinaryArtifact {
+    fn tag(self) -> u8 {
+        match self {
+            BinaryArtifact::Program => 1,
+            BinaryArtifact::SymbolGraph => 2,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            BinaryArtifact::Program => "program",
+            BinaryArtifact::SymbolGraph => "symbol graph",
+        }
+    }
+}
+
+/// Encode a value as a binary artifact of the given kind and schema version
+pub fn encode_binary<T: Serialize + ?Sized>(artifact: BinaryArtifact, version: u32, value: &T) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(artifact.tag());
+    bytes.extend_from_slice(&version.to_le_bytes());
+    rmp_serde::encode::write_named(&mut bytes, value).expect("artifact serializes to MessagePack");
+    bytes
+}
+
+/// Decode a binary artifact written by [`encode_binary`] at schema version
+/// `supported`
+///
+/// The binary form has no migrations: artifacts from other versions are
+/// rejected, and callers holding a cache rebuild it from the source.
+pub fn decode_binary<T: DeserializeOwned>(artifact: BinaryArtifact, supported: u32, bytes: &[u8]) -> Result<T, SchemaError> {
+    let name = artifact.name();
+    let invalid = |message: String| SchemaError::Invalid { artifact: name, message };
+
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(invalid("missing binary header".to_string()));
+    }
+    let tag = bytes[MAGIC.len()];
+    if tag != artifact.tag() {
+        return Err(invalid(format!("binary artifact has tag {}, expected {}", tag, artifact.tag())));
+    }
+    let version_bytes: [u8; 4] = bytes[MAGIC.len() + 1..HEADER_LEN].try_into().expect("four version bytes");
+    let version = u32::from_le_bytes(version_bytes);
+    check_supported(name, version, supported)?;
+    if version < supported {
+        return Err(invalid(format!("no migration from binary schema version {}", version)));
+    }
+
+    rmp_serde::from_slice(&bytes[HEADER_LEN..]).map_err(|e| invalid(e.to_string()))
+}
+
+impl Program {
+    /// Serialize in the compact binary form, for caches and storage
+    pub fn to_binary(&self) -> Vec<u8> {
+        encode_binary(BinaryArtifact::Program, AST_SCHEMA_VERSION, self)
+    }
+
+    /// Load a program written by [`Program::to_binary`] at the current
+    /// schema version
+    pub fn from_binary(bytes: &[u8]) -> Result<Program, SchemaError> {
+        decode_binary(BinaryArtifact::Program, AST_SCHEMA_VERSION, bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::printer::ToCov;
+    use crate::*;
+
+    fn program() -> Program {
+        let step = Step {
+            id: "s1".into(),
+            kind: StepKind::Return(ReturnStep { value: ReturnValue::Lit(Literal::Float(0.1)), span: Span::new(30, 60) }),
+            output_binding: "_".into(),
+            doc: None,
+            node_id: None,
+            span: Span::new(30, 60),
+        };
+        Program::Snippets {
+            snippets: vec![Snippet {
+                id: "a.f".into(),
+                kind: SnippetKind::Function,
+                notes: Vec::new(),
+                doc: None,
+                sections: vec![Section::Body(BodySection { steps: vec![step], span: Span::new(20, 70) })],
+                implements: None,
+                platform: None,
+                node_id: None,
+                span: Span::new(0, 80),
+            }],
+            span: Span::new(0, 80),
+        }
+    }
+
+    #[test]
+    fn test_program_round_trips() {
+        let program = program();
+        let bytes = program.to_binary();
+        assert!(bytes.len() < program.to_json().len());
+
+        let decoded = Program::from_binary(&bytes).unwrap();
+        assert_eq!(decoded.to_cov(0), program.to_cov(0));
+        assert_eq!(decoded.to_json(), program.to_json());
+    }
+
+    #[test]
+    fn test_header_is_checked() {
+        let bytes = program().to_binary();
+
+        assert!(matches!(Program::from_binary(&bytes[..4]), Err(SchemaError::Invalid { .. })));
+        assert!(matches!(Program::from_binary(b"{\"schema_version\":1}"), Err(SchemaError::Invalid { .. })));
+
+        let mut other_kind = bytes.clone();
+        other_kind[4] = BinaryArtifact::SymbolGraph.tag();
+        assert!(matches!(Program::from_binary(&other_kind), Err(SchemaError::Invalid { .. })));
+
+        let mut newer = bytes;
+        newer[5..9].copy_from_slice(&(AST_SCHEMA_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            Program::from_binary(&newer).unwrap_err(),
+            SchemaError::UnsupportedVersion { artifact: "program", found: AST_SCHEMA_VERSION + 1, supported: AST_SCHEMA_VERSION }
+        );
+    }
+}
@@ -1,16 +1,17 @@
 //! Declaration AST nodes (top-level items)
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::{Span, Type, TypePath, Block, Expr};
 
 /// A top-level declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Declaration {
     pub kind: DeclarationKind,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum DeclarationKind {
     /// Module-level import: `import { foo, bar } from baz`
     Import(ImportDecl),
@@ -38,7 +39,7 @@ pub enum DeclarationKind {
 }
 
 /// Import declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ImportDecl {
     pub names: Vec<String>,
     pub source: String,
@@ -46,7 +47,7 @@ pub struct ImportDecl {
 }
 
 /// Module declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModuleDecl {
     pub name: String,
     pub declarations: Vec<Declaration>,
@@ -54,7 +55,7 @@ pub struct ModuleDecl {
 }
 
 /// Struct declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StructDecl {
     pub name: String,
     pub generics: Vec<String>,
@@ -63,7 +64,7 @@ pub struct StructDecl {
 }
 
 /// Field in a struct
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FieldDecl {
     pub name: String,
     pub ty: Type,
@@ -72,7 +73,7 @@ pub struct FieldDecl {
 }
 
 /// Enum declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EnumDecl {
     pub name: String,
     pub generics: Vec<String>,
@@ -81,14 +82,14 @@ pub struct EnumDecl {
 }
 
 /// Enum variant
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct VariantDecl {
     pub name: String,
     pub fields: VariantFields,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum VariantFields {
     /// Unit variant: `None`
     Unit,
@@ -99,7 +100,7 @@ pub enum VariantFields {
 }
 
 /// Type alias declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TypeAliasDecl {
     pub name: String,
     pub generics: Vec<String>,
@@ -108,7 +109,7 @@ pub struct TypeAliasDecl {
 }
 
 /// Function declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FunctionDecl {
     pub name: String,
     pub generics: Vec<String>,
@@ -121,7 +122,7 @@ pub struct FunctionDecl {
 }
 
 /// Function parameter
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Parameter {
     pub name: String,
     pub ty: Type,
@@ -129,7 +130,7 @@ pub struct Parameter {
 }
 
 /// Function-level import clause (declares effects)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ImportClause {
     pub names: Vec<String>,
     pub source: String,
@@ -137,7 +138,7 @@ pub struct ImportClause {
 }
 
 /// External binding declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ExternDecl {
     pub name: String,
     pub params: Vec<Parameter>,
@@ -148,7 +149,7 @@ pub struct ExternDecl {
 }
 
 /// Database declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DatabaseDecl {
     pub name: String,
     pub connection: Option<String>,
@@ -157,7 +158,7 @@ pub struct DatabaseDecl {
 }
 
 /// Table declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TableDecl {
     pub name: String,
     pub columns: Vec<ColumnDecl>,
@@ -166,7 +167,7 @@ pub struct TableDecl {
 }
 
 /// Column declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ColumnDecl {
     pub name: String,
     pub ty: ColumnType,
@@ -175,7 +176,7 @@ pub struct ColumnDecl {
 }
 
 /// Column type (database-specific)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum ColumnType {
     Int,
     String,
@@ -188,7 +189,7 @@ pub enum ColumnType {
 }
 
 /// Column attributes
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct ColumnAttrs {
     pub primary: bool,
     pub unique: bool,
@@ -197,7 +198,7 @@ pub struct ColumnAttrs {
 }
 
 /// Table constraint
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum TableConstraint {
     Unique(Vec<String>),
     Index(Vec<String>),
@@ -0,0 +1,265 @@
+//! Structural diffs between programs
+//!
+//! Snippets are matched by id, and so are the body steps within a snippet.
+//! Snippets are compared by content hash and steps by their canonical text,
+//! so an edit that only moves code (changing spans) is not a change. A step
+//! counts as modified when the step itself changes; edits inside its nested
+//! steps are reported on those steps instead.
+
+use std::collections::HashMap;
+
+use crate::printer::ToCov;
+use crate::*;
+
+/// How a node changed between two programs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A snippet that was added, removed or modified
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnippetChange {
+    pub id: String,
+    pub kind: ChangeKind,
+    /// Step changes within a modified snippet; empty for added and removed
+    /// snippets, and when only non-body sections changed
+    pub steps: Vec<StepChange>,
+}
+
+/// A body step that was added, removed or modified
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepChange {
+    pub id: String,
+    pub kind: ChangeKind,
+}
+
+/// Changes from one program to another
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AstDiff {
+    /// Removed and modified snippets in their old order, then added
+    /// snippets in their new order
+    pub snippets: Vec<SnippetChange>,
+}
+
+impl AstDiff {
+    pub fn is_empty(&self) -> bool {
+        self.snippets.is_empty()
+    }
+
+    /// The change to a snippet, if it changed
+    pub fn snippet(&self, id: &str) -> Option<&SnippetChange> {
+        self.snippets.iter().find(|change| change.id == id)
+    }
+}
+
+/// Diff two programs by snippet and step id. Legacy programs have no ids,
+/// so they contribute no snippets.
+pub fn diff(old: &Program, new: &Program) -> AstDiff {
+    let old_snippets = program_snippets(old);
+    let new_snippets = program_snippets(new);
+    let new_by_id: HashMap<&str, &Snippet> =
+        new_snippets.iter().map(|s| (s.id.as_str(), s)).collect();
+    let mut snippets = Vec::new();
+
+    for old_snippet in old_snippets {
+        match new_by_id.get(old_snippet.id.as_str()) {
+            None => snippets.push(SnippetChange {
+                id: old_snippet.id.clone(),
+                kind: ChangeKind::Removed,
+                steps: Vec::new(),
+            }),
+            Some(new_snippet) => {
+                if old_snippet.content_hash() != new_snippet.content_hash() {
+                    snippets.push(SnippetChange {
+                        id: old_snippet.id.clone(),
+                        kind: ChangeKind::Modified,
+                        steps: diff_steps(old_snippet, new_snippet),
+                    });
+                }
+            }
+        }
+    }
+
+    for new_snippet in new_snippets {
+        if !old_snippets.iter().any(|s| s.id == new_snippet.id) {
+            snippets.push(SnippetChange {
+                id: new_snippet.id.clone(),
+                kind: ChangeKind::Added,
+                steps: Vec::new(),
+            });
+        }
+    }
+
+    AstDiff { snippets }
+}
+
+fn program_snippets(program: &Program) -> &[Snippet] {
+    match program {
+        Program::Snippets { snippets, .. } => snippets,
+        Program::Legacy { .. } => &[],
+    }
+}
+
+/// Step changes between two versions of a snippet
+fn diff_steps(old: &Snippet, new: &Snippet) -> Vec<StepChange> {
+    let old_steps = body_steps(old);
+    let new_steps = body_steps(new);
+    let new_by_id: HashMap<&str, &String> =
+        new_steps.iter().map(|(id, text)| (id.as_str(), text)).collect();
+    let mut changes = Vec::new();
+
+    for (id, old_text) in &old_steps {
+        let kind = match new_by_id.get(id.as_str()) {
+            None => ChangeKind::Removed,
+            Some(new_text) if *new_text != old_text => ChangeKind::Modified,
+            Some(_) => continue,
+        };
+        changes.push(StepChange { id: id.clone(), kind });
+    }
+
+    for (id, _) in &new_steps {
+        if !old_steps.iter().any(|(old_id, _)| old_id == id) {
+            changes.push(StepChange { id: id.clone(), kind: ChangeKind::Added });
+        }
+    }
+
+    changes
+}
+
+/// Every body step of a snippet, nested ones included, with the canonical
+/// text of the step alone
+fn body_steps(snippet: &Snippet) -> Vec<(String, String)> {
+    let mut collector = BodySteps::default();
+    collector.visit_snippet(snippet);
+    collector.steps
+}
+
+#[derive(Default)]
+struct BodySteps {
+    steps: Vec<(String, String)>,
+}
+
+impl Visitor for BodySteps {
+    fn visit_section(&mut self, section: &Section) {
+        // Test steps reuse ids like "s1", so only the body is keyed by id
+        if let Section::Body(_) = section {
+            walk_section(self, section);
+        }
+    }
+
+    fn visit_step(&mut self, step: &Step) {
+        let mut own = step.clone();
+        walk_step_mut(&mut ClearNested, &mut own);
+        self.steps.push((step.id.clone(), own.to_cov(0)));
+        walk_step(self, step);
+    }
+}
+
+/// Empties every block of nested steps it reaches
+struct ClearNested;
+
+impl VisitorMut for ClearNested {
+    fn visit_steps_mut(&mut self, steps: &mut Vec<Step>) {
+        steps.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(id: &str, steps: Vec<Step>) -> Snippet {
+        Snippet {
+            id: id.into(),
+            kind: SnippetKind::Function,
+            notes: Vec::new(),
+            doc: None,
+            sections: vec![Section::Body(BodySection { steps, span: Span::dummy() })],
+            implements: None,
+            platform: None,
+            node_id: None,
+            span: Span::dummy(),
+        }
+    }
+
+    fn program(snippets: Vec<Snippet>) -> Program {
+        Program::Snippets { snippets, span: Span::dummy() }
+    }
+
+    fn bind(id: &str, value: i64, span: Span) -> Step {
+        Step {
+            id: id.into(),
+            kind: StepKind::Bind(BindStep { source: BindSource::Lit(Literal::Int(value)), span }),
+            output_binding: "x".into(),
+            doc: None,
+            node_id: None,
+            span,
+        }
+    }
+
+    fn for_each(id: &str, steps: Vec<Step>) -> Step {
+        Step {
+            id: id.into(),
+            kind: StepKind::For(ForStep {
+                var: "item".into(),
+                collection: "items".into(),
+                steps,
+                span: Span::dummy(),
+            }),
+            output_binding: "_".into(),
+            doc: None,
+            node_id: None,
+            span: Span::dummy(),
+        }
+    }
+
+    #[test]
+    fn test_identical_programs_have_no_diff() {
+        let old = program(vec![snippet("a.f", vec![bind("s1", 1, Span::new(10, 20))])]);
+        // Moved code is not a change
+        let new = program(vec![snippet("a.f", vec![bind("s1", 1, Span::new(40, 50))])]);
+        assert!(diff(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_snippets_added_removed_and_modified() {
+        let old = program(vec![
+            snippet("a.kept", vec![bind("s1", 1, Span::dummy())]),
+            snippet("a.gone", Vec::new()),
+            snippet("a.changed", vec![bind("s1", 1, Span::dummy()), bind("s2", 2, Span::dummy())]),
+        ]);
+        let new = program(vec![
+            snippet("a.new", Vec::new()),
+            snippet("a.kept", vec![bind("s1", 1, Span::dummy())]),
+            snippet("a.changed", vec![bind("s1", 5, Span::dummy()), bind("s3", 3, Span::dummy())]),
+        ]);
+
+        let changes = diff(&old, &new);
+        let kinds: Vec<(&str, ChangeKind)> =
+            changes.snippets.iter().map(|c| (c.id.as_str(), c.kind)).collect();
+        assert_eq!(kinds, [
+            ("a.gone", ChangeKind::Removed),
+            ("a.changed", ChangeKind::Modified),
+            ("a.new", ChangeKind::Added),
+        ]);
+        assert_eq!(changes.snippet("a.changed").unwrap().steps, [
+            StepChange { id: "s1".into(), kind: ChangeKind::Modified },
+            StepChange { id: "s2".into(), kind: ChangeKind::Removed },
+            StepChange { id: "s3".into(), kind: ChangeKind::Added },
+        ]);
+    }
+
+    #[test]
+    fn test_nested_change_is_reported_on_the_nested_step() {
+        let old = program(vec![snippet("a.f", vec![for_each("s1", vec![bind("s1.1", 1, Span::dummy())])])]);
+        let new = program(vec![snippet("a.f", vec![for_each("s1", vec![bind("s1.1", 2, Span::dummy())])])]);
+        let changes = diff(&old, &new);
+        assert_eq!(changes.snippet("a.f").unwrap().steps, [
+            StepChange { id: "s1.1".into(), kind: ChangeKind::Modified },
+        ]);
+    }
+}
@@ -1,16 +1,17 @@
 //! Expression AST nodes
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::{Span, Type, TypePath, Block, MatchArm, QueryBody};
 
 /// An expression
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Expr {
     pub kind: ExprKind,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum ExprKind {
     /// Literal value: `42`, `"hello"`, `true`, `none`
     Literal(Literal),
@@ -119,17 +120,33 @@ pub enum ExprKind {
 }
 
 /// A literal value
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Literal {
     Int(i64),
     Float(f64),
+    /// Exact decimal literal: `19.99d`, kept as its canonical text (`19.99`)
+    Decimal(String),
+    /// Arbitrary-precision integer literal: `123n`, kept as its digits (`123`)
+    BigInt(String),
+    /// DateTime literal: `datetime("2024-01-01T00:00:00Z")`, kept as Unix seconds
+    DateTime(i64),
+    /// Duration literal: `duration("5m")`, kept as whole seconds
+    Duration(i64),
     String(String),
     Bool(bool),
     None,
+    /// List literal: `[1, 2, 3]`
+    List(Vec<Literal>),
+    /// Map literal: `{"a": 1}`, entries in source order
+    Map(Vec<(Literal, Literal)>),
+    /// Set literal: `{1, 2, 3}` (`{}` is an empty map)
+    Set(Vec<Literal>),
+    /// Tuple literal: `(1, "a")`
+    Tuple(Vec<Literal>),
 }
 
 /// Binary operators
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum BinaryOp {
     // Arithmetic
     Add,
@@ -155,14 +172,14 @@ pub enum BinaryOp {
 }
 
 /// Unary operators
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum UnaryOp {
     Neg,
     Not,
 }
 
 /// Field initialization in struct literal
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FieldInit {
     pub name: String,
     /// None means shorthand: `{ name }` same as `{ name: name }`
@@ -171,7 +188,7 @@ pub struct FieldInit {
 }
 
 /// Closure parameter
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ClosureParam {
     pub name: String,
     pub ty: Option<Type>,
@@ -0,0 +1,779 @@
+//! Random snippet programs for property tests and fuzzing
+//!
+//! Each generator draws from an [`Unstructured`] byte source and builds its
+//! node in the form the parser produces, so printing a generated program
+//! with [`crate::printer::to_cov`] and parsing the text gives back the same
+//! program, spans aside (every generated span is [`Span::dummy`]).
+//!
+//! That form is narrower than the types allow:
+//! - Types are single-segment named paths holding the type text, as the
+//!   snippet parser reads `type="..."` attributes
+//! - Bindings that the printer writes as `from="a.b"` never contain a dot
+//!   themselves, since the parser reads the dot as a field access
+//! - Set literals are never empty, since `{}` is an empty map
+//! - `break` and `continue` appear only inside `for` bodies
+//! - Tools sections are never generated: the parser does not read them
+//!
+//! Names come from small fixed pools so failing cases stay readable.
+
+use arbitrary::{Result, Unstructured};
+
+use crate::*;
+
+/// How deep steps, conditions and literals nest
+const MAX_DEPTH: usize = 3;
+
+const NAMES: &[&str] = &["a", "b", "x", "total", "user", "items", "count", "result", "order_id", "name"];
+const TYPES: &[&str] = &["Int", "String", "Bool", "Float", "User", "Int[]", "String?", "Int | DbError", "List<Int>", "Map<String, Int>", "(Int, String)", "Array<Int, 4>", "Decimal", "BigInt", "Self"];
+const RELATION_TYPES: &[&str] = &["describes", "described_by", "calls", "implements", "contains"];
+const EFFECTS: &[&str] = &["console", "network", "filesystem", "database", "query", "std.storage", "database.read"];
+const METADATA_KEYS: &[&str] = &["author", "tags", "flag", "count", "type", "database", "connection", "dialect", "trigger.schedule", "trigger.event", "allow.cycle.group"];
+const SQL_WORDS: &[&str] = &["SELECT", "ID", "NAME", "FROM", "USERS", "WHERE", "LIMIT", "ORDER", "BY"];
+
+/// Characters of attribute values, which the printer writes unescaped
+const ATTR_CHARS: &[char] = &['a', 'b', 'z', 'Q', '0', '7', ' ', '.', '-', '_', '/', ':', '*', 'é'];
+/// Characters of escaped strings: string literals, note and requirement text,
+/// messages, metadata values and content
+const TEXT_CHARS: &[char] = &['a', 'z', 'Q', '0', ' ', '.', '"', '\\', '\n', '\t', '\r', '{', '}', 'é', '🙂'];
+
+const OPERATIONS: &[Operation] = &[
+    Operation::Add, Operation::Sub, Operation::Mul, Operation::Div, Operation::Mod,
+    Operation::AddWrapping, Operation::SubWrapping, Operation::MulWrapping,
+    Operation::AddSaturating, Operation::SubSaturating, Operation::MulSaturating,
+    Operation::AddChecked, Operation::SubChecked, Operation::MulChecked,
+    Operation::Equals, Operation::NotEquals, Operation::Less, Operation::Greater,
+    Operation::LessEq, Operation::GreaterEq,
+    Operation::And, Operation::Or, Operation::Not, Operation::Neg,
+    Operation::Abs, Operation::Min, Operation::Max, Operation::Clamp, Operation::Pow,
+    Operation::Sqrt, Operation::Floor, Operation::Ceil, Operation::Round, Operation::Trunc,
+    Operation::Sign,
+    Operation::BitAnd, Operation::BitOr, Operation::BitXor, Operation::BitNot,
+    Operation::BitShl, Operation::BitShr, Operation::BitUshr,
+    Operation::ToInt, Operation::ToFloat, Operation::ToString, Operation::ParseInt,
+    Operation::ParseFloat,
+    Operation::RegexMatch, Operation::RegexFindAll, Operation::RegexCapture, Operation::RegexReplace,
+    Operation::ArrayOf, Operation::ArrayFill, Operation::ArrayGet, Operation::ArraySet,
+    Operation::ArrayLen,
+    Operation::VecAdd, Operation::VecSub, Operation::VecScale, Operation::Dot, Operation::Matmul,
+    Operation::MapLen, Operation::MapHas, Operation::MapInsert, Operation::MapRemove,
+    Operation::MapKeys, Operation::MapValues, Operation::MapEntries, Operation::MapMerge,
+    Operation::MapIsEmpty,
+    Operation::SetLen, Operation::SetHas, Operation::SetAdd, Operation::SetRemove,
+    Operation::SetUnion, Operation::SetIntersect, Operation::SetDiff,
+    Operation::SetSymmetricDiff, Operation::SetIsSubset, Operation::SetIsSuperset,
+    Operation::SetIsEmpty, Operation::SetToList,
+    Operation::IsSome, Operation::IsNone, Operation::UnwrapOr,
+    Operation::DtYear, Operation::DtMonth, Operation::DtDay, Operation::DtHour,
+    Operation::DtMinute, Operation::DtSecond, Operation::DtWeekday, Operation::DtUnix,
+    Operation::DtAdd, Operation::DtSub, Operation::DtDiff, Operation::DtFormat,
+    Operation::DurSeconds, Operation::DurMinutes, Operation::DurHours, Operation::DurDays,
+    Operation::DurTotalSeconds, Operation::DurAdd, Operation::DurSub, Operation::DurScale,
+    Operation::DurFormat,
+    Operation::DecAdd, Operation::DecSub, Operation::DecMul, Operation::DecDiv, Operation::DecRound,
+    Operation::DecCmp, Operation::DecFromInt, Operation::DecFormat,
+    Operation::BigAdd, Operation::BigSub, Operation::BigMul, Operation::BigDiv, Operation::BigMod,
+    Operation::BigCmp, Operation::BigFromInt, Operation::BigFormat,
+    Operation::JsonParse, Operation::JsonStringify, Operation::JsonGet, Operation::JsonSet,
+    Operation::JsonKeys,
+    Operation::BytesLen, Operation::BytesGet, Operation::BytesSlice, Operation::BytesConcat,
+    Operation::BytesToString, Operation::BytesToBase64, Operation::BytesToHex,
+    Operation::BytesIsEmpty,
+    Operation::BytesFromBase64, Operation::BytesToBase64Url, Operation::BytesFromBase64Url,
+    Operation::BytesFromHex, Operation::UrlEncode, Operation::UrlDecode,
+];
+
+const SNIPPET_KINDS: &[SnippetKind] = &[
+    SnippetKind::Function, SnippetKind::Workflow, SnippetKind::Struct, SnippetKind::Enum,
+    SnippetKind::Module, SnippetKind::Database, SnippetKind::Extern,
+    SnippetKind::ExternAbstract, SnippetKind::ExternImpl, SnippetKind::Test,
+    SnippetKind::Data, SnippetKind::Subscriber, SnippetKind::Const, SnippetKind::Interface,
+    SnippetKind::Alias,
+];
+
+/// Where a step list sits, which decides the steps it may hold
+#[derive(Clone, Copy)]
+struct Scope {
+    depth: usize,
+    in_loop: bool,
+}
+
+impl Scope {
+    fn nested(self, in_loop: bool) -> Scope {
+        Scope { depth: self.depth + 1, in_loop }
+    }
+}
+
+// ===== Program and snippets =====
+
+/// A snippet program of one to four snippets
+pub fn program(u: &mut Unstructured) -> Result<Program> {
+    let mut snippets = vec![snippet(u)?];
+    snippets.extend(list(u, 3, snippet)?);
+    Ok(Program::Snippets { snippets, span: Span::dummy() })
+}
+
+pub fn snippet(u: &mut Unstructured) -> Result<Snippet> {
+    let kind = *u.choose(SNIPPET_KINDS)?;
+    let implements = match kind {
+        SnippetKind::ExternImpl | SnippetKind::Function | SnippetKind::Workflow | SnippetKind::Struct => {
+            option(u, attr_text)?
+        }
+        _ => None,
+    };
+    let platform = match kind {
+        SnippetKind::ExternImpl => option(u, attr_text)?,
+        _ => None,
+    };
+    let mut sections = Vec::new();
+    for _ in 0..u.int_in_range(0..=4)? {
+        sections.push(section(u, kind)?);
+    }
+
+    Ok(Snippet {
+        id: dotted(u)?,
+        kind,
+        notes: list(u, 2, note)?,
+        doc: option(u, doc)?,
+        sections,
+        implements,
+        platform,
+        node_id: None,
+        span: Span::dummy(),
+    })
+}
+
+pub fn note(u: &mut Unstructured) -> Result<Note> {
+    Ok(Note {
+        lang: option(u, |u| Ok(u.choose(&["en", "fr"])?.to_string()))?,
+        content: text(u)?,
+        span: Span::dummy(),
+    })
+}
+
+pub fn doc(u: &mut Unstructured) -> Result<Doc> {
+    Ok(Doc { content: triple_text(u)?, span: Span::dummy() })
+}
+
+/// A section a snippet of `kind` may contain
+pub fn section(u: &mut Unstructured, kind: SnippetKind) -> Result<Section> {
+    let mut choices = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+    match kind {
+        SnippetKind::Module => choices.push(10),
+        SnippetKind::Subscriber => choices.push(11),
+        SnippetKind::Struct => choices.push(12),
+        _ => {}
+    }
+    let scope = Scope { depth: 0, in_loop: false };
+    Ok(match u.choose(&choices)? {
+        0 => Section::Effects(EffectsSection { effects: list(u, 3, effect_decl)?, span: Span::dummy() }),
+        1 => Section::Requires(RequiresSection { requirements: list(u, 2, requirement)?, span: Span::dummy() }),
+        2 => Section::Signature(SignatureSection { kind: signature_kind(u)?, span: Span::dummy() }),
+        3 => Section::Body(BodySection { steps: steps(u, scope)?, span: Span::dummy() }),
+        4 => Section::Tests(TestsSection { tests: list(u, 2, test_decl)?, span: Span::dummy() }),
+        5 => Section::Metadata(MetadataSection { entries: list(u, 3, metadata_entry)?, span: Span::dummy() }),
+        6 => Section::Relations(RelationsSection { relations: list(u, 3, relation_decl)?, span: Span::dummy() }),
+        7 => Section::Content(ContentSection { content: text(u)?, span: Span::dummy() }),
+        8 => Section::Schema(SchemaSection { tables: list(u, 2, table_decl)?, span: Span::dummy() }),
+        9 => Section::Types(TypesSection { types: list(u, 2, type_decl)?, span: Span::dummy() }),
+        10 => Section::Contains(ContainsSection {
+            members: list(u, 3, |u| Ok(MemberDecl { id: dotted(u)?, span: Span::dummy() }))?,
+            span: Span::dummy(),
+        }),
+        11 => Section::Subscribe(SubscribeSection { event: attr_text(u)?, handler: dotted(u)?, span: Span::dummy() }),
+        _ => Section::Invariant(InvariantSection { checks: list(u, 2, invariant_check)?, span: Span::dummy() }),
+    })
+}
+
+// ===== Sections =====
+
+pub fn effect_decl(u: &mut Unstructured) -> Result<EffectDecl> {
+    Ok(EffectDecl {
+        name: u.choose(EFFECTS)?.to_string(),
+        params: list(u, 2, |u| {
+            Ok(EffectParam { name: name(u)?, value: literal(u)?, span: Span::dummy() })
+        })?,
+        span: Span::dummy(),
+    })
+}
+
+pub fn requirement(u: &mut Unstructured) -> Result<Requirement> {
+    Ok(Requirement {
+        id: attr_text(u)?,
+        text: option(u, text)?,
+        priority: option(u, |u| {
+            Ok(*u.choose(&[Priority::Critical, Priority::High, Priority::Medium, Priority::Low])?)
+        })?,
+        status: option(u, |u| {
+            Ok(*u.choose(&[ReqStatus::Draft, ReqStatus::Approved, ReqStatus::Implemented, ReqStatus::Tested])?)
+        })?,
+        span: Span::dummy(),
+    })
+}
+
+pub fn test_decl(u: &mut Unstructured) -> Result<TestDecl> {
+    Ok(TestDecl {
+        id: attr_text(u)?,
+        kind: *u.choose(&[TestKind::Unit, TestKind::Integration, TestKind::Golden, TestKind::Property])?,
+        // The printer joins covers with commas, which the parser keeps as one ID
+        covers: option(u, attr_text)?.into_iter().collect(),
+        steps: steps(u, Scope { depth: 0, in_loop: false })?,
+        span: Span::dummy(),
+    })
+}
+
+pub fn metadata_entry(u: &mut Unstructured) -> Result<MetadataEntry> {
+    Ok(MetadataEntry { key: u.choose(METADATA_KEYS)?.to_string(), value: text(u)?, span: Span::dummy() })
+}
+
+pub fn relation_decl(u: &mut Unstructured) -> Result<RelationDecl> {
+    Ok(RelationDecl {
+        kind: *u.choose(&[RelationKind::To, RelationKind::From])?,
+        target: dotted(u)?,
+        rel_type: option(u, relation_type)?,
+        span: Span::dummy(),
+    })
+}
+
+pub fn table_decl(u: &mut Unstructured) -> Result<SnippetTableDecl> {
+    Ok(SnippetTableDecl { name: attr_text(u)?, fields: list(u, 3, field_decl)?, span: Span::dummy() })
+}
+
+/// A types section entry: a struct or enum declared in place, or an alias
+pub fn type_decl(u: &mut Unstructured) -> Result<TypeDecl> {
+    let definition = match u.int_in_range(0..=2)? {
+        0 => SignatureKind::Struct(struct_signature(u)?),
+        1 => SignatureKind::Enum(enum_signature(u)?),
+        _ => {
+            return Ok(TypeDecl {
+                name: attr_text(u)?,
+                ty: ty(u)?,
+                definition: None,
+                unit: option(u, attr_text)?,
+                span: Span::dummy(),
+            })
+        }
+    };
+    let name = match &definition {
+        SignatureKind::Struct(s) => s.name.clone(),
+        SignatureKind::Enum(e) => e.name.clone(),
+        _ => unreachable!("types sections declare structs and enums"),
+    };
+    Ok(TypeDecl {
+        ty: named_type(name.clone()),
+        name,
+        definition: Some(definition),
+        unit: None,
+        span: Span::dummy(),
+    })
+}
+
+pub fn invariant_check(u: &mut Unstructured) -> Result<InvariantCheck> {
+    Ok(InvariantCheck {
+        op: operation(u)?,
+        inputs: list(u, 3, input)?,
+        message: option(u, text)?,
+        span: Span::dummy(),
+    })
+}
+
+// ===== Signatures =====
+
+pub fn signature_kind(u: &mut Unstructured) -> Result<SignatureKind> {
+    Ok(match u.int_in_range(0..=5)? {
+        0 => SignatureKind::Function(function_signature(u)?),
+        1 => SignatureKind::Struct(struct_signature(u)?),
+        2 => SignatureKind::Enum(enum_signature(u)?),
+        3 => SignatureKind::Const(ConstSignature {
+            name: attr_text(u)?,
+            ty: ty(u)?,
+            value: literal(u)?,
+            span: Span::dummy(),
+        }),
+        4 => SignatureKind::Interface(InterfaceSignature {
+            name: attr_text(u)?,
+            methods: list(u, 2, function_signature)?,
+            span: Span::dummy(),
+        }),
+        _ => SignatureKind::Alias(AliasSignature { name: attr_text(u)?, ty: ty(u)?, span: Span::dummy() }),
+    })
+}
+
+pub fn function_signature(u: &mut Unstructured) -> Result<FunctionSignature> {
+    Ok(FunctionSignature {
+        name: attr_text(u)?,
+        params: list(u, 3, param_decl)?,
+        returns: option(u, return_type)?,
+        generics: list(u, 2, |u| Ok(GenericParam { name: attr_text(u)?, span: Span::dummy() }))?,
+        span: Span::dummy(),
+    })
+}
+
+pub fn param_decl(u: &mut Unstructured) -> Result<ParamDecl> {
+    Ok(ParamDecl {
+        name: attr_text(u)?,
+        ty: ty(u)?,
+        sensitive: u.arbitrary()?,
+        doc: option(u, doc)?,
+        span: Span::dummy(),
+    })
+}
+
+pub fn return_type(u: &mut Unstructured) -> Result<ReturnType> {
+    Ok(match u.int_in_range(0..=2)? {
+        0 => ReturnType::Single { ty: ty(u)?, optional: u.arbitrary()? },
+        1 => ReturnType::Collection { of: ty(u)? },
+        _ => ReturnType::Union {
+            types: list(u, 3, |u| Ok(UnionMember { ty: ty(u)?, optional: u.arbitrary()? }))?,
+        },
+    })
+}
+
+pub fn struct_signature(u: &mut Unstructured) -> Result<StructSignature> {
+    Ok(StructSignature {
+        name: attr_text(u)?,
+        fields: list(u, 3, field_decl)?,
+        newtype: u.arbitrary()?,
+        span: Span::dummy(),
+    })
+}
+
+pub fn enum_signature(u: &mut Unstructured) -> Result<EnumSignature> {
+    Ok(EnumSignature {
+        name: attr_text(u)?,
+        variants: list(u, 3, |u| {
+            // A variant without fields prints the same as one with none
+            let fields = list(u, 2, field_decl)?;
+            Ok(SnippetVariantDecl {
+                name: attr_text(u)?,
+                fields: (!fields.is_empty()).then_some(fields),
+                span: Span::dummy(),
+            })
+        })?,
+        span: Span::dummy(),
+    })
+}
+
+pub fn field_decl(u: &mut Unstructured) -> Result<SnippetFieldDecl> {
+    Ok(SnippetFieldDecl {
+        name: attr_text(u)?,
+        ty: ty(u)?,
+        primary: u.arbitrary()?,
+        auto: u.arbitrary()?,
+        unique: u.arbitrary()?,
+        optional: u.arbitrary()?,
+        foreign_key: option(u, |u| {
+            Ok(ForeignKey { table: dotted(u)?, field: name(u)?, span: Span::dummy() })
+        })?,
+        sensitive: u.arbitrary()?,
+        span: Span::dummy(),
+    })
+}
+
+// ===== Steps =====
+
+fn steps(u: &mut Unstructured, scope: Scope) -> Result<Vec<Step>> {
+    if scope.depth >= MAX_DEPTH {
+        return Ok(Vec::new());
+    }
+    list(u, 3, |u| step_in(u, scope))
+}
+
+/// A step at the top of a body
+pub fn step(u: &mut Unstructured) -> Result<Step> {
+    step_in(u, Scope { depth: 0, in_loop: false })
+}
+
+fn step_in(u: &mut Unstructured, scope: Scope) -> Result<Step> {
+    let kind = step_kind(u, scope)?;
+    let output_binding = match &kind {
+        // An expectation names the call's binding
+        StepKind::Call(call) if !call.expects.is_empty() => {
+            call.expects[0].field.split('.').next().unwrap_or_default().to_string()
+        }
+        _ if u.arbitrary()? => "_".to_string(),
+        _ => name(u)?,
+    };
+
+    Ok(Step { id: attr_text(u)?, kind, output_binding, doc: option(u, doc)?, node_id: None, span: Span::dummy() })
+}
+
+fn step_kind(u: &mut Unstructured, scope: Scope) -> Result<StepKind> {
+    let max = if scope.in_loop { 21 } else { 19 };
+    Ok(match u.int_in_range(0..=max)? {
+        0 => StepKind::Compute(ComputeStep { op: operation(u)?, inputs: list(u, 3, input)?, span: Span::dummy() }),
+        1 => StepKind::Call(call_step(u, scope)?),
+        2 => StepKind::Query(query_step(u)?),
+        3 => StepKind::Bind(BindStep {
+            source: match u.int_in_range(0..=3)? {
+                0 => BindSource::Var(name(u)?),
+                1 => BindSource::Lit(literal(u)?),
+                2 => BindSource::Field { of: name(u)?, field: dotted(u)? },
+                _ => BindSource::Variant(variant_construction(u)?),
+            },
+            span: Span::dummy(),
+        }),
+        4 => StepKind::Return(ReturnStep {
+            value: match u.int_in_range(0..=3)? {
+                0 => ReturnValue::Var(attr_text(u)?),
+                1 => ReturnValue::Lit(literal(u)?),
+                2 => ReturnValue::Struct(StructConstruction {
+                    ty: ty(u)?,
+                    from_base: None,
+                    fields: list(u, 2, field_assignment)?,
+                    span: Span::dummy(),
+                }),
+                _ => ReturnValue::Variant(variant_construction(u)?),
+            },
+            span: Span::dummy(),
+        }),
+        5 => StepKind::Raise(RaiseStep { variant: variant_construction(u)?, span: Span::dummy() }),
+        6 => StepKind::If(IfStep {
+            condition: if u.arbitrary()? {
+                InputSource::Var(name(u)?)
+            } else {
+                InputSource::Field { of: name(u)?, field: dotted(u)? }
+            },
+            then_steps: steps(u, scope.nested(scope.in_loop))?,
+            else_steps: option(u, |u| steps(u, scope.nested(scope.in_loop)))?,
+            span: Span::dummy(),
+        }),
+        7 => StepKind::Match(MatchStep {
+            on: attr_text(u)?,
+            cases: list(u, 3, |u| {
+                Ok(MatchCase {
+                    pattern: match u.int_in_range(0..=2)? {
+                        0 => MatchPattern::Variant { variant: attr_text(u)?, bindings: list(u, 2, attr_text)? },
+                        1 => MatchPattern::Type { ty: ty(u)? },
+                        _ => MatchPattern::Wildcard,
+                    },
+                    steps: steps(u, scope.nested(scope.in_loop))?,
+                    span: Span::dummy(),
+                })
+            })?,
+            span: Span::dummy(),
+        }),
+        8 => StepKind::For(ForStep {
+            var: attr_text(u)?,
+            collection: attr_text(u)?,
+            steps: steps(u, scope.nested(true))?,
+            span: Span::dummy(),
+        }),
+        9 => StepKind::Lambda(LambdaStep {
+            params: list(u, 2, param_decl)?,
+            returns: option(u, ty)?,
+            steps: steps(u, scope.nested(false))?,
+            span: Span::dummy(),
+        }),
+        10 => StepKind::Using(UsingStep {
+            var: attr_text(u)?,
+            acquire: CallStep {
+                fn_name: dotted(u)?,
+                type_args: Vec::new(),
+                args: list(u, 2, call_arg)?,
+                handle: None,
+                expects: Vec::new(),
+                span: Span::dummy(),
+            },
+            release: dotted(u)?,
+            steps: steps(u, scope.nested(scope.in_loop))?,
+            span: Span::dummy(),
+        }),
+        11 => StepKind::Assert(AssertStep {
+            op: operation(u)?,
+            inputs: list(u, 3, input)?,
+            message: option(u, text)?,
+            span: Span::dummy(),
+        }),
+        12 => StepKind::Insert(InsertStep {
+            target: attr_text(u)?,
+            assignments: list(u, 2, field_assignment)?,
+            span: Span::dummy(),
+        }),
+        13 => StepKind::Update(UpdateStep {
+            target: attr_text(u)?,
+            assignments: list(u, 2, field_assignment)?,
+            where_clause: option(u, |u| condition(u, 0))?,
+            span: Span::dummy(),
+        }),
+        14 => StepKind::Delete(DeleteStep {
+            target: attr_text(u)?,
+            where_clause: option(u, |u| condition(u, 0))?,
+            span: Span::dummy(),
+        }),
+        15 => StepKind::Transaction(TransactionStep {
+            target: option(u, attr_text)?,
+            isolation: option(u, |u| {
+                Ok(*u.choose(&[
+                    IsolationLevel::ReadUncommitted,
+                    IsolationLevel::ReadCommitted,
+                    IsolationLevel::RepeatableRead,
+                    IsolationLevel::Serializable,
+                ])?)
+            })?,
+            steps: steps(u, scope.nested(scope.in_loop))?,
+            span: Span::dummy(),
+        }),
+        16 => StepKind::Traverse(TraverseStep {
+            target: attr_text(u)?,
+            from: attr_text(u)?,
+            relation_type: relation_type(u)?,
+            depth: if u.arbitrary()? { TraverseDepth::Bounded(u.arbitrary()?) } else { TraverseDepth::Unbounded },
+            direction: *u.choose(&[TraverseDirection::Outgoing, TraverseDirection::Incoming, TraverseDirection::Both])?,
+            span: Span::dummy(),
+        }),
+        17 => StepKind::Construct(StructConstruction {
+            ty: ty(u)?,
+            from_base: option(u, attr_text)?,
+            fields: list(u, 2, field_assignment)?,
+            span: Span::dummy(),
+        }),
+        18 => StepKind::Parallel(ParallelStep {
+            branches: list(u, 2, |u| branch(u, scope))?,
+            on_error: option(u, attr_text)?,
+            timeout: option(u, attr_text)?,
+            span: Span::dummy(),
+        }),
+        19 => StepKind::Race(RaceStep {
+            branches: list(u, 2, |u| branch(u, scope))?,
+            on_timeout: option(u, attr_text)?,
+            timeout: option(u, attr_text)?,
+            span: Span::dummy(),
+        }),
+        20 => StepKind::Break(BreakStep { span: Span::dummy() }),
+        _ => StepKind::Continue(ContinueStep { span: Span::dummy() }),
+    })
+}
+
+fn call_step(u: &mut Unstructured, scope: Scope) -> Result<CallStep> {
+    let fn_name = dotted(u)?;
+    let type_args = list(u, 2, ty)?;
+    let args = list(u, 3, call_arg)?;
+    let handle = option(u, |u| {
+        Ok(HandleBlock {
+            cases: list(u, 2, |u| {
+                Ok(HandleCase {
+                    error_type: attr_text(u)?,
+                    steps: steps(u, scope.nested(scope.in_loop))?,
+                    span: Span::dummy(),
+                })
+            })?,
+            span: Span::dummy(),
+        })
+    })?;
+    let binding = name(u)?;
+    let expects = list(u, 2, |u| {
+        let field = match option(u, name)? {
+            Some(field) => format!("{}.{}", binding, field),
+            None => binding.clone(),
+        };
+        Ok(Expectation { field, value: literal(u)?, span: Span::dummy() })
+    })?;
+
+    Ok(CallStep { fn_name, type_args, args, handle, expects, span: Span::dummy() })
+}
+
+fn call_arg(u: &mut Unstructured) -> Result<CallArg> {
+    Ok(CallArg { name: attr_text(u)?, source: input_source(u)?, span: Span::dummy() })
+}
+
+fn query_step(u: &mut Unstructured) -> Result<QueryStep> {
+    let dialect = option(u, |u| Ok(u.choose(&["postgres", "sqlite"])?.to_string()))?;
+    let content = if dialect.is_some() {
+        let words = list(u, 6, |u| Ok(u.choose(SQL_WORDS)?.to_string()))?;
+        QueryContent::Dialect(DialectQuery {
+            body: words.join(" "),
+            params: list(u, 2, |u| {
+                Ok(ParamBinding { name: attr_text(u)?, from: attr_text(u)?, span: Span::dummy() })
+            })?,
+            returns: return_type(u)?,
+            span: Span::dummy(),
+        })
+    } else {
+        QueryContent::Covenant(CovenantQuery {
+            select: if u.arbitrary()? { SnippetSelectClause::All } else { SnippetSelectClause::Field(attr_text(u)?) },
+            from: attr_text(u)?,
+            where_clause: option(u, |u| condition(u, 0))?,
+            order: option(u, |u| {
+                Ok(OrderClause {
+                    field: attr_text(u)?,
+                    direction: *u.choose(&[SnippetOrderDirection::Asc, SnippetOrderDirection::Desc])?,
+                    span: Span::dummy(),
+                })
+            })?,
+            limit: option(u, |u| u.arbitrary())?,
+            span: Span::dummy(),
+        })
+    };
+
+    Ok(QueryStep { dialect, target: attr_text(u)?, content, span: Span::dummy() })
+}
+
+fn condition(u: &mut Unstructured, depth: usize) -> Result<Condition> {
+    let max = if depth < MAX_DEPTH { 6 } else { 4 };
+    let kind = match u.int_in_range(0..=max)? {
+        0 => ConditionKind::Equals { field: attr_text(u)?, value: input_source(u)? },
+        1 => ConditionKind::Contains { field: attr_text(u)?, value: input_source(u)? },
+        2 => ConditionKind::NotEquals { field: attr_text(u)?, value: input_source(u)? },
+        3 => ConditionKind::RelTo { target: name(u)?, rel_type: relation_type(u)? },
+        4 => ConditionKind::RelFrom { source: name(u)?, rel_type: relation_type(u)? },
+        5 => ConditionKind::And(Box::new(condition(u, depth + 1)?), Box::new(condition(u, depth + 1)?)),
+        _ => ConditionKind::Or(Box::new(condition(u, depth + 1)?), Box::new(condition(u, depth + 1)?)),
+    };
+    Ok(Condition { kind, span: Span::dummy() })
+}
+
+fn branch(u: &mut Unstructured, scope: Scope) -> Result<Branch> {
+    // Branches run concurrently, so break and continue cannot reach an outer loop
+    Ok(Branch { id: attr_text(u)?, steps: steps(u, scope.nested(false))?, span: Span::dummy() })
+}
+
+fn variant_construction(u: &mut Unstructured) -> Result<VariantConstruction> {
+    Ok(VariantConstruction { ty: attr_text(u)?, fields: list(u, 2, field_assignment)?, span: Span::dummy() })
+}
+
+fn field_assignment(u: &mut Unstructured) -> Result<FieldAssignment> {
+    Ok(FieldAssignment { name: attr_text(u)?, value: input_source(u)?, span: Span::dummy() })
+}
+
+fn input(u: &mut Unstructured) -> Result<Input> {
+    Ok(Input { source: input_source(u)?, span: Span::dummy() })
+}
+
+pub fn input_source(u: &mut Unstructured) -> Result<InputSource> {
+    Ok(match u.int_in_range(0..=2)? {
+        0 => InputSource::Var(name(u)?),
+        1 => InputSource::Lit(literal(u)?),
+        _ => InputSource::Field { of: name(u)?, field: dotted(u)? },
+    })
+}
+
+pub fn operation(u: &mut Unstructured) -> Result<Operation> {
+    Ok(*u.choose(OPERATIONS)?)
+}
+
+// ===== Types and literals =====
+
+pub fn ty(u: &mut Unstructured) -> Result<Type> {
+    Ok(named_type(u.choose(TYPES)?.to_string()))
+}
+
+fn named_type(text: String) -> Type {
+    Type {
+        kind: TypeKind::Named(TypePath { segments: vec![text], generics: Vec::new(), span: Span::dummy() }),
+        span: Span::dummy(),
+    }
+}
+
+pub fn literal(u: &mut Unstructured) -> Result<Literal> {
+    literal_at(u, 0)
+}
+
+fn literal_at(u: &mut Unstructured, depth: usize) -> Result<Literal> {
+    let max = if depth < MAX_DEPTH { 12 } else { 8 };
+    let items = |u: &mut Unstructured, min: usize| -> Result<Vec<Literal>> {
+        let mut items = Vec::new();
+        for _ in 0..u.int_in_range(min..=3)? {
+            items.push(literal_at(u, depth + 1)?);
+        }
+        Ok(items)
+    };
+    Ok(match u.int_in_range(0..=max)? {
+        0 => Literal::Int(u.arbitrary()?),
+        1 => {
+            let value: f64 = u.arbitrary()?;
+            Literal::Float(if value.is_finite() { value } else { 0.5 })
+        }
+        2 => Literal::String(text(u)?),
+        3 => Literal::Bool(u.arbitrary()?),
+        4 => Literal::None,
+        5 => Literal::Decimal(decimal_text(u.arbitrary()?, u.int_in_range(0..=3)?)),
+        6 => Literal::BigInt(u.arbitrary::<i128>()?.to_string()),
+        7 => Literal::DateTime(u.int_in_range(crate::time::MIN_DATETIME..=crate::time::MAX_DATETIME)?),
+        8 => Literal::Duration(u.arbitrary()?),
+        9 => Literal::List(items(u, 0)?),
+        10 => Literal::Set(items(u, 1)?),
+        11 => Literal::Tuple(items(u, 0)?),
+        _ => {
+            let mut entries = Vec::new();
+            for _ in 0..u.int_in_range(0..=3)? {
+                entries.push((literal_at(u, depth + 1)?, literal_at(u, depth + 1)?));
+            }
+            Literal::Map(entries)
+        }
+    })
+}
+
+/// The canonical text of `units` hundredths, thousandths... at `places`
+/// decimal places, as the parser writes Decimal literals
+fn decimal_text(units: i64, places: usize) -> String {
+    let digits = units.unsigned_abs().to_string();
+    let digits = format!("{:0>width$}", digits, width = places + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - places);
+    let sign = if units < 0 { "-" } else { "" };
+    if places == 0 {
+        format!("{}{}", sign, whole)
+    } else {
+        format!("{}{}.{}", sign, whole, fraction)
+    }
+}
+
+// ===== Names and text =====
+
+fn name(u: &mut Unstructured) -> Result<String> {
+    Ok(u.choose(NAMES)?.to_string())
+}
+
+/// A name or a dotted pair of names (`user.total`)
+fn dotted(u: &mut Unstructured) -> Result<String> {
+    let first = name(u)?;
+    Ok(match option(u, name)? {
+        Some(second) => format!("{}.{}", first, second),
+        None => first,
+    })
+}
+
+fn relation_type(u: &mut Unstructured) -> Result<String> {
+    Ok(u.choose(RELATION_TYPES)?.to_string())
+}
+
+/// Text for an attribute the printer writes without escaping
+fn attr_text(u: &mut Unstructured) -> Result<String> {
+    chars(u, ATTR_CHARS, 1)
+}
+
+/// Text for a string the printer escapes
+fn text(u: &mut Unstructured) -> Result<String> {
+    chars(u, TEXT_CHARS, 0)
+}
+
+/// Text for a triple-quoted string, which is taken verbatim
+fn triple_text(u: &mut Unstructured) -> Result<String> {
+    let mut text = String::new();
+    for _ in 0..u.int_in_range(0..=3)? {
+        text.push_str(&chars(u, ATTR_CHARS, 0)?);
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+fn chars(u: &mut Unstructured, pool: &[char], min: usize) -> Result<String> {
+    let mut text = String::new();
+    for _ in 0..u.int_in_range(min..=8)? {
+        text.push(*u.choose(pool)?);
+    }
+    Ok(text)
+}
+
+// ===== Helpers =====
+
+fn list<T>(u: &mut Unstructured, max: usize, mut item: impl FnMut(&mut Unstructured) -> Result<T>) -> Result<Vec<T>> {
+    let mut items = Vec::new();
+    for _ in 0..u.int_in_range(0..=max)? {
+        items.push(item(u)?);
+    }
+    Ok(items)
+}
+
+fn option<T>(u: &mut Unstructured, item: impl FnOnce(&mut Unstructured) -> Result<T>) -> Result<Option<T>> {
+    Ok(if u.arbitrary()? { Some(item(u)?) } else { None })
+}
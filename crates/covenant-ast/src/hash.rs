@@ -0,0 +1,58 @@
+//! Content hashes of snippets
+//!
+//! A snippet's hash covers its canonical printed form, so it changes with
+//! what the snippet says but not with its spans, layout or comments. Caches
+//! and change detection key off it instead of comparing source text.
+
+use sha2::{Digest, Sha256};
+
+use crate::printer::ToCov;
+use crate::Snippet;
+
+impl Snippet {
+    /// SHA-256 of the snippet's canonical text, as lowercase hex
+    pub fn content_hash(&self) -> String {
+        format!("{:x}", Sha256::digest(self.to_cov(0).as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn snippet(value: i64) -> Snippet {
+        let step = Step {
+            id: "s1".into(),
+            kind: StepKind::Return(ReturnStep { value: ReturnValue::Lit(Literal::Int(value)), span: Span::dummy() }),
+            output_binding: "_".into(),
+            doc: None,
+            node_id: None,
+            span: Span::new(30, 60),
+        };
+        Snippet {
+            id: "a.f".into(),
+            kind: SnippetKind::Function,
+            notes: Vec::new(),
+            doc: None,
+            sections: vec![Section::Body(BodySection { steps: vec![step], span: Span::new(20, 70) })],
+            implements: None,
+            platform: None,
+            node_id: None,
+            span: Span::new(0, 80),
+        }
+    }
+
+    #[test]
+    fn test_hash_ignores_spans() {
+        let original = snippet(1);
+        let mut moved = original.clone();
+        moved.shift_spans(100);
+        assert_eq!(original.content_hash(), moved.content_hash());
+        assert_eq!(original.content_hash().len(), 64);
+    }
+
+    #[test]
+    fn test_hash_follows_content() {
+        assert_ne!(snippet(1).content_hash(), snippet(2).content_hash());
+    }
+}
@@ -4,6 +4,9 @@
 //! and metadata structures for bidirectional references.
 
 mod span;
+mod line_index;
+mod cancel;
+mod limits;
 mod types;
 mod expr;
 mod stmt;
@@ -11,9 +14,23 @@ mod decl;
 mod query;
 mod metadata;
 mod snippet;
+mod schema;
+mod shift;
+mod visit;
+mod diff;
+mod hash;
+mod binary;
+mod node_id;
+mod sensitive;
 pub mod printer;
+pub mod time;
+#[cfg(feature = "fuzzing")]
+pub mod generate;
 
 pub use span::*;
+pub use line_index::*;
+pub use cancel::*;
+pub use limits::*;
 pub use types::*;
 pub use expr::*;
 pub use stmt::*;
@@ -21,11 +38,18 @@ pub use decl::*;
 pub use query::*;
 pub use metadata::*;
 pub use snippet::*;
+pub use schema::*;
+pub use visit::*;
+pub use diff::*;
+pub use binary::*;
+pub use node_id::*;
+pub use sensitive::*;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// A complete Covenant program
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum Program {
     /// Legacy mode - traditional declarations
     Legacy {
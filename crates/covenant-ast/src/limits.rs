@@ -0,0 +1,45 @@
+//! Structural limits applied while parsing and checking
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Default maximum step nesting depth (if/match/for/... inside each other)
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 64;
+
+/// Default maximum number of steps (including nested steps) in one body
+pub const DEFAULT_MAX_STEPS_PER_BODY: usize = 50;
+
+/// Default maximum number of snippets in one source file
+pub const DEFAULT_MAX_SNIPPETS_PER_FILE: usize = 200;
+
+/// Default maximum number of parameters of one function
+pub const DEFAULT_MAX_PARAMS_PER_FN: usize = 8;
+
+/// Configurable structural limits
+///
+/// Exceeding `max_nesting_depth` is reported as E-LIMIT-001 rather than
+/// overflowing the parser's or checker's stack. The size limits only
+/// produce warnings (W-LIMIT-001..003), so they can be tightened gradually.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Limits {
+    /// Maximum depth of nested step lists in a single body
+    pub max_nesting_depth: usize,
+    /// Maximum number of steps in a single body, nested steps included
+    pub max_steps_per_body: usize,
+    /// Maximum number of snippets in a single file
+    pub max_snippets_per_file: usize,
+    /// Maximum number of parameters of a single function
+    pub max_params_per_fn: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_steps_per_body: DEFAULT_MAX_STEPS_PER_BODY,
+            max_snippets_per_file: DEFAULT_MAX_SNIPPETS_PER_FILE,
+            max_params_per_fn: DEFAULT_MAX_PARAMS_PER_FN,
+        }
+    }
+}
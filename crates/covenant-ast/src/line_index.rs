@@ -0,0 +1,112 @@
+//! Line and column positions
+//!
+//! Spans are byte offsets. A `LineIndex` records where each line of a source
+//! starts, so offsets convert to line/column positions (and back) with a
+//! binary search instead of a scan from the start of the file.
+
+use crate::Span;
+use std::fmt;
+
+/// A zero-based line, and a zero-based byte column within that line (the
+/// LSP `utf-8` position encoding). Displays one-based, as `line:column`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for LineCol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line + 1, self.column + 1)
+    }
+}
+
+/// Start offsets of the lines of a source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// Byte offset of each line's first byte; the first is always 0
+    line_starts: Vec<usize>,
+    /// Length of the source in bytes
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { line_starts, len: source.len() }
+    }
+
+    /// Number of lines; a trailing newline starts an empty last line
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Position of a byte offset; offsets past the end clamp to the end
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        LineCol { line, column: offset - self.line_starts[line] }
+    }
+
+    /// Positions of a span's start and end
+    pub fn span(&self, span: Span) -> (LineCol, LineCol) {
+        (self.line_col(span.start), self.line_col(span.end))
+    }
+
+    /// Byte offset of a position, or None when the line does not exist or
+    /// the column runs past the end of it
+    pub fn offset(&self, pos: LineCol) -> Option<usize> {
+        let start = *self.line_starts.get(pos.line)?;
+        let end = self.line_starts.get(pos.line + 1).map_or(self.len, |next| next - 1);
+        let offset = start + pos.column;
+        (offset <= end).then_some(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_col_round_trip() {
+        let source = "snippet\n  body\n\nend\n";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 5);
+
+        assert_eq!(index.line_col(0), LineCol { line: 0, column: 0 });
+        assert_eq!(index.line_col(7), LineCol { line: 0, column: 7 });
+        assert_eq!(index.line_col(10), LineCol { line: 1, column: 2 });
+        assert_eq!(index.line_col(15), LineCol { line: 2, column: 0 });
+        assert_eq!(index.line_col(16), LineCol { line: 3, column: 0 });
+        assert_eq!(index.line_col(source.len()), LineCol { line: 4, column: 0 });
+        assert_eq!(index.line_col(usize::MAX), LineCol { line: 4, column: 0 });
+
+        for offset in 0..=source.len() {
+            assert_eq!(index.offset(index.line_col(offset)), Some(offset));
+        }
+        assert_eq!(index.offset(LineCol { line: 1, column: 7 }), None);
+        assert_eq!(index.offset(LineCol { line: 5, column: 0 }), None);
+    }
+
+    #[test]
+    fn test_span_positions_and_display() {
+        let source = "a\né = 1\n";
+        let index = LineIndex::new(source);
+        let (start, end) = index.span(Span::new(2, source.len() - 1));
+        // Columns count bytes, so `é` is two wide
+        assert_eq!(start, LineCol { line: 1, column: 0 });
+        assert_eq!(end, LineCol { line: 1, column: 6 });
+        assert_eq!(start.to_string(), "2:1");
+        assert_eq!(end.to_string(), "2:7");
+    }
+
+    #[test]
+    fn test_empty_source() {
+        let index = LineIndex::new("");
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(index.line_col(0), LineCol { line: 0, column: 0 });
+        assert_eq!(index.offset(LineCol { line: 0, column: 0 }), Some(0));
+    }
+}
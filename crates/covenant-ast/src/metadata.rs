@@ -3,18 +3,40 @@
 //! This metadata is computed by the checker and graph passes,
 //! not during parsing.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::Span;
+
 /// Unique identifier for a symbol
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub struct SymbolId(pub u32);
 
 /// Unique identifier for an effect
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub struct EffectId(pub u32);
 
+/// One place a function calls another
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CallSite {
+    /// ID of the calling step, in snippet bodies and tests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
+    /// Span of the call
+    pub span: Span,
+}
+
+/// Every call along one edge of the call graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CallEdge {
+    /// The symbol at the other end of the edge
+    pub symbol: SymbolId,
+    /// Where the calls are, in source order
+    pub sites: Vec<CallSite>,
+}
+
 /// Metadata computed for each symbol in the AST
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct AstMetadata {
     /// The symbol's unique ID
     pub id: Option<SymbolId>,
@@ -25,6 +47,14 @@ pub struct AstMetadata {
     /// Functions that call this function
     pub called_by: Vec<SymbolId>,
 
+    /// Where this function calls each of `calls`
+    #[serde(default)]
+    pub call_sites: Vec<CallEdge>,
+
+    /// Where each of `called_by` calls this function
+    #[serde(default)]
+    pub caller_sites: Vec<CallEdge>,
+
     /// Types/symbols this references
     pub references: Vec<SymbolId>,
 
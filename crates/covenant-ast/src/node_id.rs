@@ -0,0 +1,250 @@
+//! Node ids and parent links
+//!
+//! Step ids such as `s1` are unique only within a snippet, and the AST has no
+//! links upward. [`Program::assign_node_ids`] numbers every snippet and step
+//! of a program once it is parsed, and a [`ParentMap`] built from the
+//! numbered program finds what encloses a step with a lookup instead of
+//! another walk of the tree. Ids are not printed, so an edit that adds or
+//! moves nodes needs them assigned again.
+
+use std::collections::HashMap;
+
+use crate::*;
+
+/// An id unique across one program, given to each snippet and step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+pub struct NodeId(pub u32);
+
+/// A node that can carry a [`NodeId`]
+#[derive(Debug, Clone, Copy)]
+pub enum Node<'a> {
+    Snippet(&'a Snippet),
+    Step(&'a Step),
+}
+
+impl<'a> Node<'a> {
+    pub fn id(&self) -> Option<NodeId> {
+        match self {
+            Node::Snippet(snippet) => snippet.node_id,
+            Node::Step(step) => step.node_id,
+        }
+    }
+
+    pub fn as_step(&self) -> Option<&'a Step> {
+        match self {
+            Node::Step(step) => Some(step),
+            Node::Snippet(_) => None,
+        }
+    }
+}
+
+impl Program {
+    /// Number every snippet and step in source order, nested steps and test
+    /// steps included, replacing earlier ids. Legacy programs have no
+    /// snippets and are left as they are.
+    pub fn assign_node_ids(&mut self) {
+        if let Program::Snippets { snippets, .. } = self {
+            let mut numbering = Numbering::default();
+            for snippet in snippets {
+                numbering.visit_snippet_mut(snippet);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Numbering {
+    next: u32,
+}
+
+impl Numbering {
+    fn next_id(&mut self) -> Option<NodeId> {
+        let id = NodeId(self.next);
+        self.next += 1;
+        Some(id)
+    }
+}
+
+impl VisitorMut for Numbering {
+    fn visit_snippet_mut(&mut self, snippet: &mut Snippet) {
+        snippet.node_id = self.next_id();
+        walk_snippet_mut(self, snippet);
+    }
+
+    fn visit_step_mut(&mut self, step: &mut Step) {
+        step.node_id = self.next_id();
+        walk_step_mut(self, step);
+    }
+}
+
+/// The node behind each id of a numbered program, and each node's parent
+///
+/// A step's parent is the step whose branch, loop body or other block holds
+/// it, or its snippet for steps at the top of a body or test. Snippets have
+/// no parent. Nodes without an id are left out, along with everything
+/// beneath them.
+#[derive(Debug, Default)]
+pub struct ParentMap<'a> {
+    nodes: HashMap<NodeId, Node<'a>>,
+    parents: HashMap<NodeId, NodeId>,
+}
+
+impl<'a> ParentMap<'a> {
+    pub fn new(program: &'a Program) -> Self {
+        let mut map = ParentMap::default();
+        if let Program::Snippets { snippets, .. } = program {
+            for snippet in snippets {
+                let Some(id) = snippet.node_id else { continue };
+                map.nodes.insert(id, Node::Snippet(snippet));
+                for section in &snippet.sections {
+                    match section {
+                        Section::Body(body) => map.add_steps(&body.steps, id),
+                        Section::Tests(tests) => {
+                            for test in &tests.tests {
+                                map.add_steps(&test.steps, id);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    fn add_steps(&mut self, steps: &'a [Step], parent: NodeId) {
+        for step in steps {
+            let Some(id) = step.node_id else { continue };
+            self.nodes.insert(id, Node::Step(step));
+            self.parents.insert(id, parent);
+            for nested in step.nested_steps() {
+                self.add_steps(nested, id);
+            }
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<Node<'a>> {
+        self.nodes.get(&id).copied()
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.parents.get(&id).copied()
+    }
+
+    /// The nodes enclosing `id`, innermost first and ending at its snippet
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = Node<'a>> + '_ {
+        std::iter::successors(self.parent(id), |&parent| self.parent(parent))
+            .filter_map(|parent| self.node(parent))
+    }
+
+    /// The snippet holding `id`, or the snippet itself
+    pub fn snippet_of(&self, id: NodeId) -> Option<&'a Snippet> {
+        let root = std::iter::successors(Some(id), |&node| self.parent(node)).last()?;
+        match self.node(root)? {
+            Node::Snippet(snippet) => Some(snippet),
+            Node::Step(_) => None,
+        }
+    }
+
+    /// The innermost step enclosing `id` that `accept` accepts, such as the
+    /// nearest `for` around a `break`
+    pub fn enclosing_step(&self, id: NodeId, accept: impl Fn(&Step) -> bool) -> Option<&'a Step> {
+        self.ancestors(id)
+            .filter_map(|node| node.as_step())
+            .find(|step| accept(step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(id: &str, kind: StepKind) -> Step {
+        Step {
+            id: id.into(),
+            kind,
+            output_binding: "_".into(),
+            doc: None,
+            node_id: None,
+            span: Span::dummy(),
+        }
+    }
+
+    fn brk(id: &str) -> Step {
+        step(id, StepKind::Break(BreakStep { span: Span::dummy() }))
+    }
+
+    fn program() -> Program {
+        let inner = step("s1.1", StepKind::If(IfStep {
+            condition: InputSource::Var("done".into()),
+            then_steps: vec![brk("s1.1.1")],
+            else_steps: None,
+            span: Span::dummy(),
+        }));
+        let outer = step("s1", StepKind::For(ForStep {
+            var: "item".into(),
+            collection: "items".into(),
+            steps: vec![inner],
+            span: Span::dummy(),
+        }));
+        let snippet = |id: &str, steps: Vec<Step>| Snippet {
+            id: id.into(),
+            kind: SnippetKind::Function,
+            notes: Vec::new(),
+            doc: None,
+            sections: vec![Section::Body(BodySection { steps, span: Span::dummy() })],
+            implements: None,
+            platform: None,
+            node_id: None,
+            span: Span::dummy(),
+        };
+        Program::Snippets {
+            snippets: vec![snippet("a.f", vec![outer]), snippet("a.g", vec![brk("s1")])],
+            span: Span::dummy(),
+        }
+    }
+
+    fn body(snippet: &Snippet) -> &[Step] {
+        match &snippet.sections[0] {
+            Section::Body(body) => &body.steps,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_ids_are_unique_in_source_order() {
+        let mut program = program();
+        program.assign_node_ids();
+        let Program::Snippets { snippets, .. } = &program else { unreachable!() };
+
+        assert_eq!(snippets[0].node_id, Some(NodeId(0)));
+        assert_eq!(body(&snippets[0])[0].node_id, Some(NodeId(1)));
+        // Both snippets have a step "s1", but not the same node id
+        assert_eq!(snippets[1].node_id, Some(NodeId(4)));
+        assert_eq!(body(&snippets[1])[0].node_id, Some(NodeId(5)));
+    }
+
+    #[test]
+    fn test_parent_map_walks_upward() {
+        let mut program = program();
+        program.assign_node_ids();
+        let parents = ParentMap::new(&program);
+        let brk = NodeId(3);
+
+        let ancestors: Vec<Option<NodeId>> = parents.ancestors(brk).map(|node| node.id()).collect();
+        assert_eq!(ancestors, [Some(NodeId(2)), Some(NodeId(1)), Some(NodeId(0))]);
+
+        let enclosing_for = parents.enclosing_step(brk, |step| matches!(step.kind, StepKind::For(_)));
+        assert_eq!(enclosing_for.map(|step| step.id.as_str()), Some("s1"));
+        assert_eq!(parents.snippet_of(brk).map(|s| s.id.as_str()), Some("a.f"));
+        assert_eq!(parents.snippet_of(NodeId(5)).map(|s| s.id.as_str()), Some("a.g"));
+        assert!(parents.parent(NodeId(0)).is_none());
+    }
+
+    #[test]
+    fn test_unnumbered_program_has_no_nodes() {
+        let program = program();
+        let parents = ParentMap::new(&program);
+        assert!(parents.node(NodeId(0)).is_none());
+    }
+}
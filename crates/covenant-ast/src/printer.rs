@@ -4,19 +4,20 @@
 //! Uses two-space indentation and follows the grammar from docs/design/grammar.ebnf.
 
 use crate::{
-    BodySection, Branch, CallArg, CallStep, Condition, ConditionKind, ComputeStep,
-    ContentSection, CovenantQuery, DeleteStep, DialectQuery, EffectDecl, EffectsSection,
-    EnumSignature, FieldAssignment, ForStep, FunctionSignature, HandleBlock, HandleCase,
-    IfStep, Input, InputSource, InsertStep, IsolationLevel, MatchCase, MatchPattern,
+    AssertStep, BodySection, Branch, CallArg, CallStep, Condition, ConditionKind, ComputeStep,
+    AliasSignature, ConstSignature, InterfaceSignature,
+    ContainsSection, ContentSection, CovenantQuery, DeleteStep, DialectQuery, Doc, EffectDecl,
+    EffectsSection, EnumSignature, FieldAssignment, ForStep, FunctionSignature, HandleBlock, HandleCase,
+    IfStep, Input, InputSource, InvariantCheck, InvariantSection, InsertStep, IsolationLevel, LambdaStep, MatchCase, MatchPattern,
     MatchStep, MetadataEntry, MetadataSection, Note, Operation, ParallelStep, ParamBinding,
-    ParamDecl, Priority, Program, QueryContent, QueryStep, RaceStep, RelationDecl,
+    ParamDecl, PipelineStage, PipelineStep, Priority, Program, QueryContent, QueryStep, RaceStep, RelationDecl,
     RelationKind, RelationsSection, ReqStatus, Requirement, RequiresSection, ReturnStep,
     ReturnType, ReturnValue, SchemaSection, Section, SignatureKind, SignatureSection,
     Snippet, SnippetFieldDecl, SnippetKind, SnippetOrderDirection, SnippetSelectClause,
     SnippetTableDecl, SnippetVariantDecl, Step, StepKind, StructConstruction,
-    StructSignature, TestDecl, TestKind, TestsSection, ToolDecl, ToolsSection,
+    StructSignature, SubscribeSection, TestDecl, TestKind, TestsSection, ToolDecl, ToolsSection,
     TransactionStep, TraverseDepth, TraverseDirection, TraverseStep, TypeDecl, TypesSection,
-    UnionMember, UpdateStep, VariantConstruction, BindStep, BindSource,
+    UnionMember, UpdateStep, UsingStep, VariantConstruction, BindStep, BindSource,
 };
 use crate::{Literal, Type, TypeKind, TypePath};
 
@@ -79,19 +80,32 @@ impl ToCov for Snippet {
 
         // Snippet header
         let kind_str = self.kind.to_cov(0);
-        lines.push(format!("{}snippet id=\"{}\" kind=\"{}\"", ind, self.id, kind_str));
+        let mut header = format!("{}snippet id=\"{}\" kind=\"{}\"", ind, self.id, kind_str);
+        if let Some(implements) = &self.implements {
+            header.push_str(&format!(" implements=\"{}\"", implements));
+        }
+        if let Some(platform) = &self.platform {
+            header.push_str(&format!(" platform=\"{}\"", platform));
+        }
+        lines.push(header);
 
         // Notes (after header, before sections)
         for note in &self.notes {
             lines.push(note.to_cov(indent));
         }
+        if let Some(doc) = &self.doc {
+            lines.push(doc.to_cov(indent));
+        }
 
-        // Sections in canonical order: effects, requires, types, tools, signature, body, tests, metadata, relations, content, schema
+        // Sections in canonical order: effects, requires, types, tools, contains, subscribe, signature, body, tests, metadata, relations, content, schema
         let mut effects = Vec::new();
         let mut requires = Vec::new();
         let mut types = Vec::new();
         let mut tools = Vec::new();
+        let mut contains = Vec::new();
+        let mut subscribe = Vec::new();
         let mut signature = Vec::new();
+        let mut invariant = Vec::new();
         let mut body = Vec::new();
         let mut tests = Vec::new();
         let mut metadata = Vec::new();
@@ -105,7 +119,10 @@ impl ToCov for Snippet {
                 Section::Requires(s) => requires.push(s),
                 Section::Types(s) => types.push(s),
                 Section::Tools(s) => tools.push(s),
+                Section::Contains(s) => contains.push(s),
+                Section::Subscribe(s) => subscribe.push(s),
                 Section::Signature(s) => signature.push(s),
+                Section::Invariant(s) => invariant.push(s),
                 Section::Body(s) => body.push(s),
                 Section::Tests(s) => tests.push(s),
                 Section::Metadata(s) => metadata.push(s),
@@ -128,9 +145,18 @@ impl ToCov for Snippet {
         for s in tools {
             lines.push(s.to_cov(indent));
         }
+        for s in contains {
+            lines.push(s.to_cov(indent));
+        }
+        for s in subscribe {
+            lines.push(s.to_cov(indent));
+        }
         for s in signature {
             lines.push(s.to_cov(indent));
         }
+        for s in invariant {
+            lines.push(s.to_cov(indent));
+        }
         for s in body {
             lines.push(s.to_cov(indent));
         }
@@ -159,6 +185,7 @@ impl ToCov for SnippetKind {
     fn to_cov(&self, _indent: usize) -> String {
         match self {
             SnippetKind::Function => "fn".to_string(),
+            SnippetKind::Workflow => "workflow".to_string(),
             SnippetKind::Struct => "struct".to_string(),
             SnippetKind::Enum => "enum".to_string(),
             SnippetKind::Module => "module".to_string(),
@@ -168,6 +195,10 @@ impl ToCov for SnippetKind {
             SnippetKind::ExternImpl => "extern-impl".to_string(),
             SnippetKind::Test => "test".to_string(),
             SnippetKind::Data => "data".to_string(),
+            SnippetKind::Subscriber => "subscriber".to_string(),
+            SnippetKind::Const => "const".to_string(),
+            SnippetKind::Interface => "interface".to_string(),
+            SnippetKind::Alias => "alias".to_string(),
         }
     }
 }
@@ -178,9 +209,9 @@ impl ToCov for Note {
         if let Some(lang) = &self.lang {
             format!("{}note lang=\"{}\" \"{}\"", ind, lang, escape_string(&self.content))
         } else {
-            // Check if content has newlines - use triple quotes
-            if self.content.contains('\n') {
-                format!("{}note \"\"\"\n{}\n{}\"\"\"", ind, self.content, ind)
+            // Multi-line notes are triple-quoted, which is taken verbatim and closed by `end`
+            if self.content.contains('\n') && !self.content.contains('"') {
+                format!("{}note \"\"\"{}\"\"\"\n{}end", ind, self.content, ind)
             } else {
                 format!("{}note \"{}\"", ind, escape_string(&self.content))
             }
@@ -188,6 +219,12 @@ impl ToCov for Note {
     }
 }
 
+impl ToCov for Doc {
+    fn to_cov(&self, indent: usize) -> String {
+        format!("{}doc \"\"\"{}\"\"\"", indent_str(indent), self.content)
+    }
+}
+
 // ===== Effects Section =====
 
 impl ToCov for EffectsSection {
@@ -215,7 +252,7 @@ impl ToCov for EffectDecl {
                 .iter()
                 .map(|p| format!("{}={}", p.name, p.value.to_cov(0)))
                 .collect();
-            format!("{}effect {}({})", ind, self.name, params.join(" "))
+            format!("{}effect {}({})", ind, self.name, params.join(", "))
         }
     }
 }
@@ -297,8 +334,22 @@ impl ToCov for TypesSection {
 
 impl ToCov for TypeDecl {
     fn to_cov(&self, indent: usize) -> String {
-        let ind = indent_str(indent);
-        format!("{}alias name=\"{}\" type=\"{}\"", ind, self.name, self.ty.to_cov(0))
+        match &self.definition {
+            Some(SignatureKind::Struct(s)) => s.to_cov(indent),
+            Some(SignatureKind::Enum(e)) => e.to_cov(indent),
+            Some(SignatureKind::Function(f)) => f.to_cov(indent),
+            Some(SignatureKind::Const(c)) => c.to_cov(indent),
+            Some(SignatureKind::Interface(i)) => i.to_cov(indent),
+            Some(SignatureKind::Alias(a)) => a.to_cov(indent),
+            None => {
+                let ind = indent_str(indent);
+                let mut line = format!("{}alias name=\"{}\" type=\"{}\"", ind, self.name, self.ty.to_cov(0));
+                if let Some(unit) = &self.unit {
+                    line.push_str(&format!(" unit=\"{}\"", escape_string(unit)));
+                }
+                line
+            }
+        }
     }
 }
 
@@ -328,6 +379,61 @@ impl ToCov for ToolDecl {
     }
 }
 
+// ===== Contains Section =====
+
+impl ToCov for ContainsSection {
+    fn to_cov(&self, indent: usize) -> String {
+        let ind = indent_str(indent);
+        let mut lines = vec![format!("{}contains", ind)];
+
+        for member in &self.members {
+            lines.push(format!("{}member id=\"{}\"", indent_str(indent + 1), member.id));
+        }
+
+        lines.push(format!("{}end", ind));
+        lines.join("\n")
+    }
+}
+
+// ===== Subscribe Section =====
+
+impl ToCov for SubscribeSection {
+    fn to_cov(&self, indent: usize) -> String {
+        let ind = indent_str(indent);
+        let inner = indent_str(indent + 1);
+        [
+            format!("{}subscribe", ind),
+            format!("{}event type=\"{}\"", inner, self.event),
+            format!("{}handler fn=\"{}\"", inner, self.handler),
+            format!("{}end", ind),
+        ]
+        .join("\n")
+    }
+}
+
+impl ToCov for InvariantSection {
+    fn to_cov(&self, indent: usize) -> String {
+        let ind = indent_str(indent);
+        let mut lines = vec![format!("{}invariant", ind)];
+        for check in &self.checks {
+            lines.push(check.to_cov(indent + 1));
+        }
+        lines.push(format!("{}end", ind));
+        lines.join("\n")
+    }
+}
+
+impl ToCov for InvariantCheck {
+    fn to_cov(&self, indent: usize) -> String {
+        let inputs: Vec<String> = self.inputs.iter().map(|i| i.to_cov(0)).collect();
+        let mut line = format!("{}check op={} {}", indent_str(indent), self.op.to_cov(0), inputs.join(" "));
+        if let Some(message) = &self.message {
+            line.push_str(&format!(" message=\"{}\"", escape_string(message)));
+        }
+        line
+    }
+}
+
 // ===== Signature Section =====
 
 impl ToCov for SignatureSection {
@@ -348,7 +454,40 @@ impl ToCov for SignatureKind {
             SignatureKind::Function(f) => f.to_cov(indent),
             SignatureKind::Struct(s) => s.to_cov(indent),
             SignatureKind::Enum(e) => e.to_cov(indent),
+            SignatureKind::Const(c) => c.to_cov(indent),
+            SignatureKind::Interface(i) => i.to_cov(indent),
+            SignatureKind::Alias(a) => a.to_cov(indent),
+        }
+    }
+}
+
+impl ToCov for AliasSignature {
+    fn to_cov(&self, indent: usize) -> String {
+        format!("{}alias name=\"{}\" type=\"{}\"", indent_str(indent), self.name, self.ty.to_cov(0))
+    }
+}
+
+impl ToCov for ConstSignature {
+    fn to_cov(&self, indent: usize) -> String {
+        format!(
+            "{}const name=\"{}\" type=\"{}\" lit={}",
+            indent_str(indent),
+            self.name,
+            self.ty.to_cov(0),
+            self.value.to_cov(0)
+        )
+    }
+}
+
+impl ToCov for InterfaceSignature {
+    fn to_cov(&self, indent: usize) -> String {
+        let ind = indent_str(indent);
+        let mut lines = vec![format!("{}interface name=\"{}\"", ind, self.name)];
+        for method in &self.methods {
+            lines.push(method.to_cov(indent + 1));
         }
+        lines.push(format!("{}end", ind));
+        lines.join("\n")
     }
 }
 
@@ -378,7 +517,12 @@ impl ToCov for FunctionSignature {
 impl ToCov for ParamDecl {
     fn to_cov(&self, indent: usize) -> String {
         let ind = indent_str(indent);
-        format!("{}param name=\"{}\" type=\"{}\"", ind, self.name, self.ty.to_cov(0))
+        let sensitive = if self.sensitive { " sensitive" } else { "" };
+        let param = format!("{}param name=\"{}\" type=\"{}\"{}", ind, self.name, self.ty.to_cov(0), sensitive);
+        match &self.doc {
+            Some(doc) => format!("{}\n{}", doc.to_cov(indent), param),
+            None => param,
+        }
     }
 }
 
@@ -423,7 +567,8 @@ impl ToCov for UnionMember {
 impl ToCov for StructSignature {
     fn to_cov(&self, indent: usize) -> String {
         let ind = indent_str(indent);
-        let mut lines = vec![format!("{}struct name=\"{}\"", ind, self.name)];
+        let newtype = if self.newtype { " newtype" } else { "" };
+        let mut lines = vec![format!("{}struct name=\"{}\"{}", ind, self.name, newtype)];
 
         for field in &self.fields {
             lines.push(field.to_cov(indent + 1));
@@ -439,11 +584,9 @@ impl ToCov for SnippetFieldDecl {
         let ind = indent_str(indent);
         let mut attrs = Vec::new();
 
+        // Flags in the order the parser accepts them
         if self.primary {
-            attrs.push("primary_key=true".to_string());
-        }
-        if self.optional {
-            attrs.push("optional".to_string());
+            attrs.push("primary".to_string());
         }
         if self.auto {
             attrs.push("auto".to_string());
@@ -451,6 +594,15 @@ impl ToCov for SnippetFieldDecl {
         if self.unique {
             attrs.push("unique".to_string());
         }
+        if self.optional {
+            attrs.push("optional".to_string());
+        }
+        if let Some(fk) = &self.foreign_key {
+            attrs.push(format!("foreign_key=\"{}.{}\"", fk.table, fk.field));
+        }
+        if self.sensitive {
+            attrs.push("sensitive".to_string());
+        }
 
         let attr_str = if attrs.is_empty() {
             String::new()
@@ -523,14 +675,25 @@ impl ToCov for Step {
     fn to_cov(&self, indent: usize) -> String {
         let ind = indent_str(indent);
         let kind_str = step_kind_name(&self.kind);
-        let mut lines = vec![format!("{}step id=\"{}\" kind=\"{}\"", ind, self.id, kind_str)];
+        let mut lines = Vec::new();
+        if let Some(doc) = &self.doc {
+            lines.push(doc.to_cov(indent));
+        }
+        lines.push(format!("{}step id=\"{}\" kind=\"{}\"", ind, self.id, kind_str));
 
-        // Add step-specific content
-        lines.push(self.kind.to_cov(indent + 1));
+        // Add step-specific content (break/continue have none)
+        let content = self.kind.to_cov(indent + 1);
+        if !content.is_empty() {
+            lines.push(content);
+        }
 
         // Add output binding
         lines.push(format!("{}as=\"{}\"", indent_str(indent + 1), self.output_binding));
 
+        if let StepKind::Call(CallStep { handle: Some(handle), .. }) = &self.kind {
+            lines.push(handle.to_cov(indent + 1));
+        }
+
         lines.push(format!("{}end", ind));
         lines.join("\n")
     }
@@ -540,12 +703,19 @@ fn step_kind_name(kind: &StepKind) -> &'static str {
     match kind {
         StepKind::Compute(_) => "compute",
         StepKind::Call(_) => "call",
+        StepKind::Pipeline(_) => "pipeline",
         StepKind::Query(_) => "query",
         StepKind::Bind(_) => "bind",
         StepKind::Return(_) => "return",
+        StepKind::Raise(_) => "raise",
         StepKind::If(_) => "if",
         StepKind::Match(_) => "match",
         StepKind::For(_) => "for",
+        StepKind::Break(_) => "break",
+        StepKind::Continue(_) => "continue",
+        StepKind::Lambda(_) => "lambda",
+        StepKind::Using(_) => "using",
+        StepKind::Assert(_) => "assert",
         StepKind::Insert(_) => "insert",
         StepKind::Update(_) => "update",
         StepKind::Delete(_) => "delete",
@@ -562,12 +732,18 @@ impl ToCov for StepKind {
         match self {
             StepKind::Compute(c) => c.to_cov(indent),
             StepKind::Call(c) => c.to_cov(indent),
+            StepKind::Pipeline(p) => p.to_cov(indent),
             StepKind::Query(q) => q.to_cov(indent),
             StepKind::Bind(b) => b.to_cov(indent),
             StepKind::Return(r) => r.to_cov(indent),
+            StepKind::Raise(r) => r.variant.to_cov(indent),
             StepKind::If(i) => i.to_cov(indent),
             StepKind::Match(m) => m.to_cov(indent),
             StepKind::For(f) => f.to_cov(indent),
+            StepKind::Break(_) | StepKind::Continue(_) => String::new(),
+            StepKind::Lambda(l) => l.to_cov(indent),
+            StepKind::Using(u) => u.to_cov(indent),
+            StepKind::Assert(a) => a.to_cov(indent),
             StepKind::Insert(i) => i.to_cov(indent),
             StepKind::Update(u) => u.to_cov(indent),
             StepKind::Delete(d) => d.to_cov(indent),
@@ -600,6 +776,15 @@ impl ToCov for Operation {
             Operation::Mul => "mul".to_string(),
             Operation::Div => "div".to_string(),
             Operation::Mod => "mod".to_string(),
+            Operation::AddWrapping => "add_wrapping".to_string(),
+            Operation::SubWrapping => "sub_wrapping".to_string(),
+            Operation::MulWrapping => "mul_wrapping".to_string(),
+            Operation::AddSaturating => "add_saturating".to_string(),
+            Operation::SubSaturating => "sub_saturating".to_string(),
+            Operation::MulSaturating => "mul_saturating".to_string(),
+            Operation::AddChecked => "add_checked".to_string(),
+            Operation::SubChecked => "sub_checked".to_string(),
+            Operation::MulChecked => "mul_checked".to_string(),
 
             // Comparison
             Operation::Equals => "equals".to_string(),
@@ -644,6 +829,12 @@ impl ToCov for Operation {
             Operation::ParseInt => "parse_int".to_string(),
             Operation::ParseFloat => "parse_float".to_string(),
 
+            // Regex
+            Operation::RegexMatch => "regex_match".to_string(),
+            Operation::RegexFindAll => "regex_find_all".to_string(),
+            Operation::RegexCapture => "regex_capture".to_string(),
+            Operation::RegexReplace => "regex_replace".to_string(),
+
             // Map operations
             Operation::MapLen => "map_len".to_string(),
             Operation::MapHas => "map_has".to_string(),
@@ -655,6 +846,20 @@ impl ToCov for Operation {
             Operation::MapMerge => "map_merge".to_string(),
             Operation::MapIsEmpty => "map_is_empty".to_string(),
 
+            // Fixed-size array operations
+            Operation::ArrayOf => "array_of".to_string(),
+            Operation::ArrayFill => "array_fill".to_string(),
+            Operation::ArrayGet => "array_get".to_string(),
+            Operation::ArraySet => "array_set".to_string(),
+            Operation::ArrayLen => "array_len".to_string(),
+
+            // Numeric vector operations
+            Operation::VecAdd => "vec_add".to_string(),
+            Operation::VecSub => "vec_sub".to_string(),
+            Operation::VecScale => "vec_scale".to_string(),
+            Operation::Dot => "dot".to_string(),
+            Operation::Matmul => "matmul".to_string(),
+
             // Set operations
             Operation::SetLen => "set_len".to_string(),
             Operation::SetHas => "set_has".to_string(),
@@ -669,6 +874,11 @@ impl ToCov for Operation {
             Operation::SetIsEmpty => "set_is_empty".to_string(),
             Operation::SetToList => "set_to_list".to_string(),
 
+            // Optional operations
+            Operation::IsSome => "is_some".to_string(),
+            Operation::IsNone => "is_none".to_string(),
+            Operation::UnwrapOr => "unwrap_or".to_string(),
+
             // DateTime operations
             Operation::DtYear => "dt_year".to_string(),
             Operation::DtMonth => "dt_month".to_string(),
@@ -678,13 +888,49 @@ impl ToCov for Operation {
             Operation::DtSecond => "dt_second".to_string(),
             Operation::DtWeekday => "dt_weekday".to_string(),
             Operation::DtUnix => "dt_unix".to_string(),
-            Operation::DtAddDays => "dt_add_days".to_string(),
-            Operation::DtAddHours => "dt_add_hours".to_string(),
-            Operation::DtAddMinutes => "dt_add_minutes".to_string(),
-            Operation::DtAddSeconds => "dt_add_seconds".to_string(),
+            Operation::DtAdd => "dt_add".to_string(),
+            Operation::DtSub => "dt_sub".to_string(),
             Operation::DtDiff => "dt_diff".to_string(),
             Operation::DtFormat => "dt_format".to_string(),
 
+            // Duration operations
+            Operation::DurSeconds => "dur_seconds".to_string(),
+            Operation::DurMinutes => "dur_minutes".to_string(),
+            Operation::DurHours => "dur_hours".to_string(),
+            Operation::DurDays => "dur_days".to_string(),
+            Operation::DurTotalSeconds => "dur_total_seconds".to_string(),
+            Operation::DurAdd => "dur_add".to_string(),
+            Operation::DurSub => "dur_sub".to_string(),
+            Operation::DurScale => "dur_scale".to_string(),
+            Operation::DurFormat => "dur_format".to_string(),
+
+            // Decimal operations
+            Operation::DecAdd => "dec_add".to_string(),
+            Operation::DecSub => "dec_sub".to_string(),
+            Operation::DecMul => "dec_mul".to_string(),
+            Operation::DecDiv => "dec_div".to_string(),
+            Operation::DecRound => "dec_round".to_string(),
+            Operation::DecCmp => "dec_cmp".to_string(),
+            Operation::DecFromInt => "dec_from_int".to_string(),
+            Operation::DecFormat => "dec_format".to_string(),
+
+            // BigInt operations
+            Operation::BigAdd => "big_add".to_string(),
+            Operation::BigSub => "big_sub".to_string(),
+            Operation::BigMul => "big_mul".to_string(),
+            Operation::BigDiv => "big_div".to_string(),
+            Operation::BigMod => "big_mod".to_string(),
+            Operation::BigCmp => "big_cmp".to_string(),
+            Operation::BigFromInt => "big_from_int".to_string(),
+            Operation::BigFormat => "big_format".to_string(),
+
+            // Json operations
+            Operation::JsonParse => "json_parse".to_string(),
+            Operation::JsonStringify => "json_stringify".to_string(),
+            Operation::JsonGet => "json_get".to_string(),
+            Operation::JsonSet => "json_set".to_string(),
+            Operation::JsonKeys => "json_keys".to_string(),
+
             // Bytes operations
             Operation::BytesLen => "bytes_len".to_string(),
             Operation::BytesGet => "bytes_get".to_string(),
@@ -694,6 +940,14 @@ impl ToCov for Operation {
             Operation::BytesToBase64 => "bytes_to_base64".to_string(),
             Operation::BytesToHex => "bytes_to_hex".to_string(),
             Operation::BytesIsEmpty => "bytes_is_empty".to_string(),
+
+            // Encoding operations
+            Operation::BytesFromBase64 => "bytes_from_base64".to_string(),
+            Operation::BytesToBase64Url => "bytes_to_base64url".to_string(),
+            Operation::BytesFromBase64Url => "bytes_from_base64url".to_string(),
+            Operation::BytesFromHex => "bytes_from_hex".to_string(),
+            Operation::UrlEncode => "url_encode".to_string(),
+            Operation::UrlDecode => "url_decode".to_string(),
         }
     }
 }
@@ -721,14 +975,47 @@ impl ToCov for CallStep {
         let ind = indent_str(indent);
         let mut lines = vec![format!("{}fn=\"{}\"", ind, self.fn_name)];
 
+        for ty in &self.type_args {
+            lines.push(format!("{}type arg=\"{}\"", ind, ty.to_cov(0)));
+        }
         for arg in &self.args {
             lines.push(arg.to_cov(indent));
         }
+        for expect in &self.expects {
+            lines.push(format!("{}expect field=\"{}\" lit={}", ind, expect.field, expect.value.to_cov(0)));
+        }
 
-        if let Some(handle) = &self.handle {
-            lines.push(handle.to_cov(indent));
+        // The handle block follows the step's output binding; see `Step`
+        lines.join("\n")
+    }
+}
+
+impl ToCov for PipelineStep {
+    fn to_cov(&self, indent: usize) -> String {
+        let ind = indent_str(indent);
+        let from = match &self.from {
+            InputSource::Field { of, field } => format!("{}.{}", of, field),
+            InputSource::Var(v) => v.clone(),
+            InputSource::Lit(l) => l.to_cov(0),
+        };
+        let mut lines = vec![format!("{}from=\"{}\"", ind, from)];
+        for stage in &self.stages {
+            lines.push(stage.to_cov(indent));
         }
+        lines.join("\n")
+    }
+}
 
+impl ToCov for PipelineStage {
+    fn to_cov(&self, indent: usize) -> String {
+        let ind = indent_str(indent);
+        let mut header = format!("{}stage fn=\"{}\"", ind, self.call.fn_name);
+        if self.into != "value" {
+            header.push_str(&format!(" into=\"{}\"", self.into));
+        }
+        let mut lines = vec![header];
+        // The rest of the call, without its `fn=` line
+        lines.extend(self.call.to_cov(indent + 1).lines().skip(1).map(str::to_string));
         lines.join("\n")
     }
 }
@@ -851,7 +1138,7 @@ impl ToCov for ConditionKind {
                 let val_str = match value {
                     InputSource::Var(v) => format!("var=\"{}\"", v),
                     InputSource::Lit(l) => format!("lit={}", l.to_cov(0)),
-                    InputSource::Field { of, field } => format!("field=\"{}.{}\"", of, field),
+                    InputSource::Field { of, field } => format!("var=\"{}.{}\"", of, field),
                 };
                 format!("{}equals field=\"{}\" {}", ind, field, val_str)
             }
@@ -859,7 +1146,7 @@ impl ToCov for ConditionKind {
                 let val_str = match value {
                     InputSource::Var(v) => format!("var=\"{}\"", v),
                     InputSource::Lit(l) => format!("lit={}", l.to_cov(0)),
-                    InputSource::Field { of, field } => format!("field=\"{}.{}\"", of, field),
+                    InputSource::Field { of, field } => format!("var=\"{}.{}\"", of, field),
                 };
                 format!("{}not_equals field=\"{}\" {}", ind, field, val_str)
             }
@@ -867,29 +1154,48 @@ impl ToCov for ConditionKind {
                 let val_str = match value {
                     InputSource::Var(v) => format!("var=\"{}\"", v),
                     InputSource::Lit(l) => format!("lit={}", l.to_cov(0)),
-                    InputSource::Field { of, field } => format!("field=\"{}.{}\"", of, field),
+                    InputSource::Field { of, field } => format!("var=\"{}.{}\"", of, field),
                 };
                 format!("{}contains field=\"{}\" {}", ind, field, val_str)
             }
-            ConditionKind::And(left, right) => {
-                let mut lines = vec![format!("{}and", ind)];
-                lines.push(left.to_cov(indent + 1));
-                lines.push(right.to_cov(indent + 1));
-                lines.push(format!("{}end", ind));
-                lines.join("\n")
-            }
-            ConditionKind::Or(left, right) => {
-                let mut lines = vec![format!("{}or", ind)];
-                lines.push(left.to_cov(indent + 1));
-                lines.push(right.to_cov(indent + 1));
+            ConditionKind::And(..) | ConditionKind::Or(..) => {
+                let keyword = if matches!(self, ConditionKind::And(..)) { "and" } else { "or" };
+                let mut lines = vec![format!("{}{}", ind, keyword)];
+                for operand in self.operands() {
+                    lines.push(operand.to_cov(indent + 1));
+                }
                 lines.push(format!("{}end", ind));
                 lines.join("\n")
             }
             ConditionKind::RelTo { target, rel_type } => {
-                format!("{}rel_to target=\"{}\" type=\"{}\"", ind, target, rel_type)
+                format!("{}rel_to target={} type={}", ind, target, rel_type)
             }
             ConditionKind::RelFrom { source, rel_type } => {
-                format!("{}rel_from source=\"{}\" type=\"{}\"", ind, source, rel_type)
+                format!("{}rel_from source={} type={}", ind, source, rel_type)
+            }
+        }
+    }
+}
+
+impl ConditionKind {
+    /// The operands of an `and` or `or` block
+    ///
+    /// The parser nests `and a b c end` to the right as `And(a, And(b, c))`,
+    /// so those chains print back as one flat block.
+    fn operands(&self) -> Vec<&ConditionKind> {
+        let mut operands = Vec::new();
+        let mut current = self;
+        loop {
+            match (self, current) {
+                (ConditionKind::And(..), ConditionKind::And(left, right))
+                | (ConditionKind::Or(..), ConditionKind::Or(left, right)) => {
+                    operands.push(&left.kind);
+                    current = &right.kind;
+                }
+                _ => {
+                    operands.push(current);
+                    return operands;
+                }
             }
         }
     }
@@ -937,6 +1243,7 @@ impl ToCov for BindStep {
             BindSource::Var(v) => format!("{}from=\"{}\"", ind, v),
             BindSource::Lit(l) => format!("{}lit={}", ind, l.to_cov(0)),
             BindSource::Field { of, field } => format!("{}from=\"{}.{}\"", ind, of, field),
+            BindSource::Variant(v) => v.to_cov(indent),
         }
     }
 }
@@ -953,7 +1260,14 @@ impl ToCov for ReturnValue {
         match self {
             ReturnValue::Var(v) => format!("{}from=\"{}\"", ind, v),
             ReturnValue::Lit(l) => format!("{}lit={}", ind, l.to_cov(0)),
-            ReturnValue::Struct(s) => s.to_cov(indent),
+            ReturnValue::Struct(s) => {
+                let mut lines = vec![format!("{}struct type=\"{}\"", ind, s.ty.to_cov(0))];
+                for field in &s.fields {
+                    lines.push(field.to_cov(indent + 1));
+                }
+                lines.push(format!("{}end", ind));
+                lines.join("\n")
+            }
             ReturnValue::Variant(v) => v.to_cov(indent),
         }
     }
@@ -963,6 +1277,9 @@ impl ToCov for StructConstruction {
     fn to_cov(&self, indent: usize) -> String {
         let ind = indent_str(indent);
         let mut lines = vec![format!("{}type=\"{}\"", ind, self.ty.to_cov(0))];
+        if let Some(base) = &self.from_base {
+            lines.push(format!("{}from_base=\"{}\"", ind, base));
+        }
 
         for field in &self.fields {
             lines.push(field.to_cov(indent));
@@ -1019,6 +1336,7 @@ impl ToCov for IfStep {
         for step in &self.then_steps {
             lines.push(step.to_cov(indent + 1));
         }
+        lines.push(format!("{}end", ind));
 
         // Else branch
         if let Some(else_steps) = &self.else_steps {
@@ -1026,6 +1344,7 @@ impl ToCov for IfStep {
             for step in else_steps {
                 lines.push(step.to_cov(indent + 1));
             }
+            lines.push(format!("{}end", ind));
         }
 
         lines.join("\n")
@@ -1068,6 +1387,7 @@ impl ToCov for MatchPattern {
     fn to_cov(&self, _indent: usize) -> String {
         match self {
             MatchPattern::Wildcard => "wildcard".to_string(),
+            MatchPattern::Type { ty } => format!("type=\"{}\"", ty.to_cov(0)),
             MatchPattern::Variant { variant, bindings } => {
                 if bindings.is_empty() {
                     format!("variant type=\"{}\"", variant)
@@ -1077,7 +1397,7 @@ impl ToCov for MatchPattern {
                     format!(
                         "variant type=\"{}\" bindings=({})",
                         variant,
-                        bindings_str.join(",")
+                        bindings_str.join(", ")
                     )
                 }
             }
@@ -1098,6 +1418,54 @@ impl ToCov for ForStep {
     }
 }
 
+impl ToCov for UsingStep {
+    fn to_cov(&self, indent: usize) -> String {
+        let ind = indent_str(indent);
+        let mut lines = vec![
+            format!("{}var=\"{}\"", ind, self.var),
+            format!("{}acquire fn=\"{}\"", ind, self.acquire.fn_name),
+        ];
+        for arg in &self.acquire.args {
+            lines.push(arg.to_cov(indent + 1));
+        }
+        lines.push(format!("{}release fn=\"{}\"", ind, self.release));
+
+        for step in &self.steps {
+            lines.push(step.to_cov(indent));
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl ToCov for AssertStep {
+    fn to_cov(&self, indent: usize) -> String {
+        let ind = indent_str(indent);
+        let inputs: Vec<String> = self.inputs.iter().map(|i| i.to_cov(0)).collect();
+        let mut lines = vec![format!("{}op={} {}", ind, self.op.to_cov(0), inputs.join(" "))];
+        if let Some(message) = &self.message {
+            lines.push(format!("{}message=\"{}\"", ind, escape_string(message)));
+        }
+        lines.join("\n")
+    }
+}
+
+impl ToCov for LambdaStep {
+    fn to_cov(&self, indent: usize) -> String {
+        let mut lines: Vec<String> = self.params.iter().map(|p| p.to_cov(indent)).collect();
+
+        if let Some(ret) = &self.returns {
+            lines.push(format!("{}returns type=\"{}\"", indent_str(indent), ret.to_cov(0)));
+        }
+
+        for step in &self.steps {
+            lines.push(step.to_cov(indent));
+        }
+
+        lines.join("\n")
+    }
+}
+
 impl ToCov for InsertStep {
     fn to_cov(&self, indent: usize) -> String {
         let ind = indent_str(indent);
@@ -1160,6 +1528,9 @@ impl ToCov for TransactionStep {
         let ind = indent_str(indent);
         let mut lines = Vec::new();
 
+        if let Some(target) = &self.target {
+            lines.push(format!("{}target=\"{}\"", ind, target));
+        }
         if let Some(iso) = &self.isolation {
             lines.push(format!("{}isolation=\"{}\"", ind, iso.to_cov(0)));
         }
@@ -1189,7 +1560,7 @@ impl ToCov for TraverseStep {
         let mut lines = vec![
             format!("{}target=\"{}\"", ind, self.target),
             format!("{}from=\"{}\"", ind, self.from),
-            format!("{}follow type=\"{}\"", ind, self.relation_type),
+            format!("{}follow type={}", ind, self.relation_type),
         ];
 
         match &self.depth {
@@ -1223,7 +1594,7 @@ impl ToCov for ParallelStep {
         }
 
         if let Some(timeout) = &self.timeout {
-            lines.push(format!("{}timeout={}", ind, timeout));
+            lines.push(format!("{}timeout=\"{}\"", ind, timeout));
         }
 
         for branch in &self.branches {
@@ -1240,7 +1611,7 @@ impl ToCov for RaceStep {
         let mut lines = Vec::new();
 
         if let Some(timeout) = &self.timeout {
-            lines.push(format!("{}timeout={}", ind, timeout));
+            lines.push(format!("{}timeout=\"{}\"", ind, timeout));
         }
 
         if let Some(on_timeout) = &self.on_timeout {
@@ -1340,7 +1711,16 @@ impl ToCov for MetadataSection {
 impl ToCov for MetadataEntry {
     fn to_cov(&self, indent: usize) -> String {
         let ind = indent_str(indent);
-        format!("{}{}=\"{}\"", ind, self.key, escape_string(&self.value))
+        // Trigger keys are stored as `trigger.<kind>` and written `trigger <kind>=`,
+        // allowances as `allow.<what>.<attr>` and written `allow <what> <attr>=`
+        let key = if let Some(kind) = self.key.strip_prefix("trigger.") {
+            format!("trigger {}", kind)
+        } else if self.key.starts_with("allow.") {
+            self.key.replace('.', " ")
+        } else {
+            self.key.clone()
+        };
+        format!("{}{}=\"{}\"", ind, key, escape_string(&self.value))
     }
 }
 
@@ -1369,7 +1749,7 @@ impl ToCov for RelationDecl {
         };
 
         if let Some(rel_type) = &self.rel_type {
-            format!("{}rel {}=\"{}\" type=\"{}\"", ind, dir, self.target, rel_type)
+            format!("{}rel {}=\"{}\" type={}", ind, dir, self.target, rel_type)
         } else {
             format!("{}rel {}=\"{}\"", ind, dir, self.target)
         }
@@ -1382,9 +1762,9 @@ impl ToCov for ContentSection {
     fn to_cov(&self, indent: usize) -> String {
         let ind = indent_str(indent);
 
-        // Check if content has newlines - use appropriate quoting
-        if self.content.contains('\n') {
-            format!("{}content\n\"\"\"\n{}\n\"\"\"\n{}end", ind, self.content, ind)
+        // Multi-line content is a triple-quoted string, which is taken verbatim
+        if self.content.contains('\n') && !self.content.contains('"') {
+            format!("{}content\n{}  \"\"\"{}\"\"\"\n{}end", ind, ind, self.content, ind)
         } else {
             format!("{}content\n{}  \"{}\"\n{}end", ind, ind, escape_string(&self.content), ind)
         }
@@ -1497,13 +1877,33 @@ impl ToCov for Literal {
                     n.to_string()
                 }
             }
+            Literal::Decimal(text) => format!("{}d", text),
+            Literal::BigInt(digits) => format!("{}n", digits),
+            Literal::DateTime(seconds) => format!("datetime(\"{}\")", crate::time::format_datetime(*seconds)),
+            Literal::Duration(seconds) => format!("duration(\"{}\")", crate::time::format_duration(*seconds)),
             Literal::String(s) => format!("\"{}\"", escape_string(s)),
             Literal::Bool(b) => b.to_string(),
             Literal::None => "none".to_string(),
+            Literal::List(items) => format!("[{}]", join_literals(items)),
+            Literal::Map(entries) => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k.to_cov(0), v.to_cov(0)))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Literal::Set(items) => format!("{{{}}}", join_literals(items)),
+            // A one-element tuple keeps its trailing comma
+            Literal::Tuple(items) if items.len() == 1 => format!("({},)", items[0].to_cov(0)),
+            Literal::Tuple(items) => format!("({})", join_literals(items)),
         }
     }
 }
 
+fn join_literals(items: &[Literal]) -> String {
+    items.iter().map(|l| l.to_cov(0)).collect::<Vec<_>>().join(", ")
+}
+
 // ===== Convenience function =====
 
 /// Convert a program to canonical Covenant text format.
@@ -1516,6 +1916,129 @@ pub fn snippet_to_cov(snippet: &Snippet) -> String {
     snippet.to_cov(0)
 }
 
+// ===== Compact syntax =====
+
+/// Convert a program to the compact snippet syntax.
+///
+/// Same AST as `to_cov`, with positional header attributes
+/// (`step "s1" compute`, `param "x" "Int"`, `input "x"`) and discarded
+/// `as="_"` bindings left implicit. Parse it back with the parser's compact mode.
+pub fn to_cov_compact(program: &Program) -> String {
+    compact(&program.to_cov(0))
+}
+
+/// Contract canonical output line by line, leaving triple-quoted text untouched.
+fn compact(canonical: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_triple = false;
+
+    // `lines()` would also drop a `\r` that triple-quoted text keeps
+    for line in canonical.split('\n') {
+        let starts_in_triple = in_triple;
+        if line.matches("\"\"\"").count() % 2 == 1 {
+            in_triple = !in_triple;
+        }
+        if starts_in_triple {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let rest = line.trim_start();
+        let ind = &line[..line.len() - rest.len()];
+        if rest == "as=\"_\"" {
+            continue;
+        }
+        lines.push(format!("{}{}", ind, compact_line(rest)));
+    }
+
+    lines.join("\n")
+}
+
+fn compact_line(line: &str) -> String {
+    if let Some((id, kind)) = header_attrs(line, "snippet", "id", "kind") {
+        return format!("snippet \"{}\" {}", id, compact_kind(kind));
+    }
+    if let Some((id, kind)) = header_attrs(line, "step", "id", "kind") {
+        return format!("step \"{}\" {}", id, compact_kind(kind));
+    }
+    if let Some((name, ty)) = header_attrs(line, "param", "name", "type") {
+        return format!("param \"{}\" \"{}\"", name, ty);
+    }
+    if let Some((name, rest)) = line.strip_prefix("fn ").and_then(|l| attr(l, "name")) {
+        if rest.is_empty() {
+            return format!("fn \"{}\"", name);
+        }
+    }
+    if let Some((ty, rest)) = line.strip_prefix("returns ").and_then(|l| attr(l, "type")) {
+        return format!("returns \"{}\"{}", ty, rest);
+    }
+    if line.starts_with("op=") {
+        return replace_outside_strings(line, "input var=\"", "input \"");
+    }
+    line.to_string()
+}
+
+/// Split `keyword first="a" second="b"` into `("a", "b")`
+fn header_attrs<'a>(line: &'a str, keyword: &str, first: &str, second: &str) -> Option<(&'a str, &'a str)> {
+    let line = line.strip_prefix(keyword)?.strip_prefix(' ')?;
+    let (a, rest) = attr(line, first)?;
+    let (b, rest) = attr(rest.strip_prefix(' ')?, second)?;
+    rest.is_empty().then_some((a, b))
+}
+
+/// Split `name="value" rest` into `("value", " rest")`; escaped values are left alone
+fn attr<'a>(line: &'a str, name: &str) -> Option<(&'a str, &'a str)> {
+    let value = line.strip_prefix(name)?.strip_prefix("=\"")?;
+    let end = value.find('"')?;
+    if value[..end].contains('\\') {
+        return None;
+    }
+    Some((&value[..end], &value[end + 1..]))
+}
+
+/// Kinds that lex as a single word are written bare; others stay quoted
+fn compact_kind(kind: &str) -> String {
+    if !kind.is_empty() && kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        kind.to_string()
+    } else {
+        format!("\"{}\"", kind)
+    }
+}
+
+fn replace_outside_strings(line: &str, from: &str, to: &str) -> String {
+    let mut result = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut i = 0;
+
+    while i < line.len() {
+        let rest = &line[i..];
+        if !in_string && rest.starts_with(from) {
+            result.push_str(to);
+            i += from.len();
+            // The replacement ends inside the opened string
+            in_string = true;
+            continue;
+        }
+        let c = rest.chars().next().expect("non-empty remainder");
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        }
+        result.push(c);
+        i += c.len_utf8();
+    }
+
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1529,9 +2052,23 @@ mod tests {
     fn test_literal_to_cov() {
         assert_eq!(Literal::Int(42).to_cov(0), "42");
         assert_eq!(Literal::Float(3.14).to_cov(0), "3.14");
+        assert_eq!(Literal::Decimal("-19.90".to_string()).to_cov(0), "-19.90d");
+        assert_eq!(Literal::BigInt("18446744073709551616".to_string()).to_cov(0), "18446744073709551616n");
+        assert_eq!(Literal::DateTime(1_704_067_200).to_cov(0), "datetime(\"2024-01-01T00:00:00Z\")");
+        assert_eq!(Literal::Duration(-5_400).to_cov(0), "duration(\"-1h30m\")");
         assert_eq!(Literal::String("hello".to_string()).to_cov(0), "\"hello\"");
         assert_eq!(Literal::Bool(true).to_cov(0), "true");
         assert_eq!(Literal::None.to_cov(0), "none");
+        assert_eq!(
+            Literal::List(vec![Literal::Int(1), Literal::Int(2)]).to_cov(0),
+            "[1, 2]"
+        );
+        assert_eq!(
+            Literal::Map(vec![(Literal::String("a".to_string()), Literal::Int(1))]).to_cov(0),
+            "{\"a\": 1}"
+        );
+        assert_eq!(Literal::Set(vec![Literal::Bool(true)]).to_cov(0), "{true}");
+        assert_eq!(Literal::Tuple(vec![Literal::Int(1)]).to_cov(0), "(1,)");
     }
 
     #[test]
@@ -1575,4 +2112,16 @@ mod tests {
         };
         assert_eq!(effect.to_cov(0), "effect console");
     }
+
+    #[test]
+    fn test_compact_contracts_canonical_lines() {
+        let canonical = "snippet id=\"io.read\" kind=\"extern-abstract\"\n  step id=\"s1\" kind=\"compute\"\n    op=add input var=\"a\" input lit=\"input var=\\\"x\\\"\"\n    as=\"_\"\n  end\nend";
+        assert_eq!(
+            compact(canonical),
+            "snippet \"io.read\" \"extern-abstract\"\n  step \"s1\" compute\n    op=add input \"a\" input lit=\"input var=\\\"x\\\"\"\n  end\nend"
+        );
+        assert_eq!(compact_line("param name=\"x\" type=\"Int\""), "param \"x\" \"Int\"");
+        assert_eq!(compact_line("returns type=\"Int\" optional"), "returns \"Int\" optional");
+        assert_eq!(compact("content\n\"\"\"\nstep id=\"a\" kind=\"b\"\n\"\"\""), "content\n\"\"\"\nstep id=\"a\" kind=\"b\"\n\"\"\"");
+    }
 }
@@ -1,10 +1,11 @@
 //! Query expression AST nodes
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::{Span, Expr};
 
 /// Body of a query expression
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct QueryBody {
     pub select: SelectClause,
     pub from: Option<FromClause>,
@@ -17,13 +18,13 @@ pub struct QueryBody {
 }
 
 /// SELECT clause
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SelectClause {
     pub items: SelectItems,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum SelectItems {
     /// SELECT *
     Star,
@@ -31,7 +32,7 @@ pub enum SelectItems {
     List(Vec<SelectItem>),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SelectItem {
     pub expr: Expr,
     pub alias: Option<String>,
@@ -39,7 +40,7 @@ pub struct SelectItem {
 }
 
 /// FROM clause
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FromClause {
     pub table: String,
     pub alias: Option<String>,
@@ -47,7 +48,7 @@ pub struct FromClause {
 }
 
 /// JOIN clause
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct JoinClause {
     pub kind: JoinKind,
     pub table: String,
@@ -55,7 +56,7 @@ pub struct JoinClause {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum JoinKind {
     Inner,
     Left,
@@ -64,14 +65,14 @@ pub enum JoinKind {
 }
 
 /// ORDER BY item
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct OrderItem {
     pub expr: Expr,
     pub direction: OrderDirection,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub enum OrderDirection {
     #[default]
     Asc,
@@ -0,0 +1,253 @@
+//! Schema versions and migrations for serialized artifacts
+//!
+//! Serialized Programs, embedded WASM symbol metadata, binary symbol graphs,
+//! and storage databases each carry a schema version. Readers pass older artifacts through the
+//! migrations here, which upgrade them one version at a time to the current
+//! schema. Artifacts written before versioning existed are version 0.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Program;
+
+/// Current schema version of serialized Program JSON
+pub const AST_SCHEMA_VERSION: u32 = 1;
+
+/// Current schema version of the symbol metadata embedded in WASM modules
+pub const SYMBOL_METADATA_VERSION: u32 = 1;
+
+/// Current schema version of symbol graphs in the binary form
+pub const SYMBOL_GRAPH_VERSION: u32 = 1;
+
+/// Current schema version of storage databases
+pub const STORAGE_SCHEMA_VERSION: u32 = 2;
+
+/// A Program tagged with the schema version it was serialized with
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VersionedProgram {
+    pub schema_version: u32,
+    pub program: Program,
+}
+
+impl VersionedProgram {
+    /// Wrap a program at the current schema version
+    pub fn new(program: Program) -> Self {
+        Self {
+            schema_version: AST_SCHEMA_VERSION,
+            program,
+        }
+    }
+}
+
+impl Program {
+    /// Serialize as versioned JSON, the interchange format for tools that
+    /// edit programs without going through snippet text
+    ///
+    /// Loading the JSON with [`Program::from_json`] gives back this program,
+    /// and printing that with `printer::to_cov` gives text that parses to it
+    /// again, spans aside.
+    pub fn to_json(&self) -> String {
+        program_to_json(self)
+    }
+
+    /// Serialize as indented versioned JSON
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(&VersionedProgram::new(self.clone()))
+            .expect("program serializes to JSON")
+    }
+
+    /// Load a program from JSON written by [`Program::to_json`], by any
+    /// release back to schema version 0
+    pub fn from_json(json: &str) -> Result<Program, SchemaError> {
+        program_from_json(json)
+    }
+}
+
+/// Errors reading a versioned artifact
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// The artifact was written by a newer release
+    UnsupportedVersion {
+        artifact: &'static str,
+        found: u32,
+        supported: u32,
+    },
+    /// The artifact does not have the shape of any known version
+    Invalid {
+        artifact: &'static str,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::UnsupportedVersion { artifact, found, supported } => write!(
+                f,
+                "{} schema version {} is newer than the supported version {}",
+                artifact, found, supported
+            ),
+            SchemaError::Invalid { artifact, message } => {
+                write!(f, "invalid {}: {}", artifact, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Serialize a program as versioned JSON
+pub fn program_to_json(program: &Program) -> String {
+    serde_json::to_string(&VersionedProgram::new(program.clone()))
+        .expect("program serializes to JSON")
+}
+
+/// Load a program from JSON of any supported schema version
+pub fn program_from_json(json: &str) -> Result<Program, SchemaError> {
+    let value: Value = serde_json::from_str(json).map_err(|e| invalid("program", e))?;
+    let versioned: VersionedProgram =
+        serde_json::from_value(migrate_program_json(value)?).map_err(|e| invalid("program", e))?;
+    Ok(versioned.program)
+}
+
+/// Upgrade serialized Program JSON to the current schema version
+///
+/// Version 0 is a bare `Program`; version 1 wraps it with its `schema_version`.
+pub fn migrate_program_json(value: Value) -> Result<Value, SchemaError> {
+    let mut version = schema_version_of(&value, "program")?;
+    check_supported("program", version, AST_SCHEMA_VERSION)?;
+
+    let mut value = value;
+    while version < AST_SCHEMA_VERSION {
+        value = match version {
+            0 => serde_json::json!({ "schema_version": 1, "program": value }),
+            _ => unreachable!("no migration from program schema version {}", version),
+        };
+        version += 1;
+    }
+    Ok(value)
+}
+
+/// Upgrade embedded symbol metadata JSON to the current schema version
+///
+/// Version 0 is a bare array of symbols; version 1 is an object holding
+/// `schema_version` and `symbols`.
+pub fn migrate_symbol_metadata_json(value: Value) -> Result<Value, SchemaError> {
+    let mut version = if value.is_array() {
+        0
+    } else {
+        schema_version_of(&value, "symbol metadata")?
+    };
+    check_supported("symbol metadata", version, SYMBOL_METADATA_VERSION)?;
+
+    let mut value = value;
+    while version < SYMBOL_METADATA_VERSION {
+        value = match version {
+            0 => serde_json::json!({ "schema_version": 1, "symbols": value }),
+            _ => unreachable!("no migration from symbol metadata schema version {}", version),
+        };
+        version += 1;
+    }
+    Ok(value)
+}
+
+/// Fail if an artifact is newer than this release can read
+pub fn check_supported(artifact: &'static str, found: u32, supported: u32) -> Result<(), SchemaError> {
+    if found > supported {
+        return Err(SchemaError::UnsupportedVersion { artifact, found, supported });
+    }
+    Ok(())
+}
+
+/// The `schema_version` of a JSON object, or 0 if it has none
+fn schema_version_of(value: &Value, artifact: &'static str) -> Result<u32, SchemaError> {
+    let Some(object) = value.as_object() else {
+        return Err(SchemaError::Invalid {
+            artifact,
+            message: "expected a JSON object".to_string(),
+        });
+    };
+    match object.get("schema_version") {
+        None => Ok(0),
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| SchemaError::Invalid {
+                artifact,
+                message: format!("schema_version must be an unsigned integer, found {}", v),
+            }),
+    }
+}
+
+fn invalid(artifact: &'static str, e: serde_json::Error) -> SchemaError {
+    SchemaError::Invalid {
+        artifact,
+        message: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Span;
+
+    fn empty_program() -> Program {
+        Program::Snippets {
+            snippets: Vec::new(),
+            span: Span::dummy(),
+        }
+    }
+
+    #[test]
+    fn test_program_round_trips_at_current_version() {
+        let json = program_to_json(&empty_program());
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["schema_version"], AST_SCHEMA_VERSION);
+        assert!(matches!(program_from_json(&json), Ok(Program::Snippets { .. })));
+    }
+
+    #[test]
+    fn test_pretty_json_loads() {
+        let json = empty_program().to_json_pretty();
+        assert!(json.contains('\n'));
+        assert!(matches!(Program::from_json(&json), Ok(Program::Snippets { .. })));
+    }
+
+    #[test]
+    fn test_unversioned_program_is_migrated() {
+        let bare = serde_json::to_string(&empty_program()).unwrap();
+        let migrated = migrate_program_json(serde_json::from_str(&bare).unwrap()).unwrap();
+        assert_eq!(migrated["schema_version"], AST_SCHEMA_VERSION);
+        assert!(matches!(program_from_json(&bare), Ok(Program::Snippets { .. })));
+    }
+
+    #[test]
+    fn test_newer_program_is_rejected() {
+        let json = serde_json::json!({ "schema_version": AST_SCHEMA_VERSION + 1, "program": {} });
+        assert_eq!(
+            migrate_program_json(json),
+            Err(SchemaError::UnsupportedVersion {
+                artifact: "program",
+                found: AST_SCHEMA_VERSION + 1,
+                supported: AST_SCHEMA_VERSION,
+            })
+        );
+    }
+
+    #[test]
+    fn test_symbol_metadata_array_is_migrated() {
+        let migrated = migrate_symbol_metadata_json(serde_json::json!([{ "id": "a.b" }])).unwrap();
+        assert_eq!(migrated["schema_version"], SYMBOL_METADATA_VERSION);
+        assert_eq!(migrated["symbols"][0]["id"], "a.b");
+    }
+
+    #[test]
+    fn test_invalid_schema_version_is_rejected() {
+        let json = serde_json::json!({ "schema_version": "one", "symbols": [] });
+        assert!(matches!(
+            migrate_symbol_metadata_json(json),
+            Err(SchemaError::Invalid { .. })
+        ));
+    }
+}
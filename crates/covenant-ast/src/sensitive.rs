@@ -0,0 +1,164 @@
+//! Values marked `sensitive`
+//!
+//! A struct field or function param written with the `sensitive` flag holds
+//! a secret (`field name="api_key" type="String" sensitive`). The runtime
+//! redacts such arguments from audit logs and traces, explanations never
+//! send their literal values to the LLM, and the checker warns when one is
+//! passed to a logging call. [`Sensitive`] indexes the flags of a set of
+//! snippets for those passes.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{Section, SignatureKind, Snippet, Type, TypeKind};
+
+/// The sensitive fields and params declared by a set of snippets
+#[derive(Debug, Clone, Default)]
+pub struct Sensitive {
+    /// Sensitive field names by struct name
+    fields: HashMap<String, BTreeSet<String>>,
+    /// Sensitive param names by function, under both its snippet ID and
+    /// signature name
+    params: HashMap<String, BTreeSet<String>>,
+}
+
+impl Sensitive {
+    pub fn from_snippets<'a>(snippets: impl IntoIterator<Item = &'a Snippet>) -> Self {
+        let mut sensitive = Self::default();
+        for snippet in snippets {
+            for section in &snippet.sections {
+                let Section::Signature(sig) = section else { continue };
+                match &sig.kind {
+                    SignatureKind::Struct(s) => {
+                        let fields: BTreeSet<String> =
+                            s.fields.iter().filter(|f| f.sensitive).map(|f| f.name.clone()).collect();
+                        if !fields.is_empty() {
+                            sensitive.fields.insert(snippet.id.clone(), fields.clone());
+                            sensitive.fields.insert(s.name.clone(), fields);
+                        }
+                    }
+                    SignatureKind::Function(f) => {
+                        let params: BTreeSet<String> =
+                            f.params.iter().filter(|p| p.sensitive).map(|p| p.name.clone()).collect();
+                        if !params.is_empty() {
+                            sensitive.params.insert(snippet.id.clone(), params.clone());
+                            sensitive.params.entry(f.name.clone()).or_insert(params);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        sensitive
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.params.is_empty()
+    }
+
+    /// Whether `field` of the struct named `struct_name` is sensitive
+    pub fn is_field(&self, struct_name: &str, field: &str) -> bool {
+        self.fields.get(struct_name).is_some_and(|fields| fields.contains(field))
+    }
+
+    /// Whether param `param` of `function` (snippet ID or name) is sensitive
+    pub fn is_param(&self, function: &str, param: &str) -> bool {
+        self.params.get(function).is_some_and(|params| params.contains(param))
+    }
+
+    /// The struct `ty` names, through optionals and lists, if it has
+    /// sensitive fields
+    pub fn struct_of<'a>(&self, ty: &'a Type) -> Option<&'a str> {
+        match &ty.kind {
+            TypeKind::Named(path) => self.fields.contains_key(path.name()).then(|| path.name()),
+            TypeKind::Optional(inner) | TypeKind::List(inner) => self.struct_of(inner),
+            _ => None,
+        }
+    }
+
+    /// Whether a value of type `ty` holds a sensitive field
+    pub fn is_type(&self, ty: &Type) -> bool {
+        self.struct_of(ty).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FunctionSignature, ParamDecl, SignatureSection, SnippetFieldDecl, SnippetKind, Span, StructSignature, TypePath,
+    };
+
+    fn named(name: &str) -> Type {
+        Type { kind: TypeKind::Named(TypePath::simple(name, Span::dummy())), span: Span::dummy() }
+    }
+
+    fn snippet(id: &str, kind: SnippetKind, signature: SignatureKind) -> Snippet {
+        Snippet {
+            id: id.to_string(),
+            kind,
+            notes: vec![],
+            doc: None,
+            sections: vec![Section::Signature(SignatureSection { kind: signature, span: Span::dummy() })],
+            implements: None,
+            platform: None,
+            node_id: None,
+            span: Span::dummy(),
+        }
+    }
+
+    #[test]
+    fn test_sensitive_fields_and_params() {
+        let field = |name: &str, sensitive| SnippetFieldDecl {
+            name: name.to_string(),
+            ty: named("String"),
+            primary: false,
+            auto: false,
+            unique: false,
+            optional: false,
+            foreign_key: None,
+            sensitive,
+            span: Span::dummy(),
+        };
+        let param = |name: &str, sensitive| ParamDecl {
+            name: name.to_string(),
+            ty: named("String"),
+            sensitive,
+            doc: None,
+            span: Span::dummy(),
+        };
+        let snippets = [
+            snippet(
+                "auth.Credentials",
+                SnippetKind::Struct,
+                SignatureKind::Struct(StructSignature {
+                    name: "Credentials".to_string(),
+                    fields: vec![field("user", false), field("password", true)],
+                    newtype: false,
+                    span: Span::dummy(),
+                }),
+            ),
+            snippet(
+                "auth.login",
+                SnippetKind::Function,
+                SignatureKind::Function(FunctionSignature {
+                    name: "login".to_string(),
+                    params: vec![param("user", false), param("token", true)],
+                    returns: None,
+                    generics: vec![],
+                    span: Span::dummy(),
+                }),
+            ),
+        ];
+        let sensitive = Sensitive::from_snippets(&snippets);
+
+        assert!(sensitive.is_field("Credentials", "password"));
+        assert!(sensitive.is_field("auth.Credentials", "password"));
+        assert!(!sensitive.is_field("Credentials", "user"));
+        assert!(sensitive.is_param("auth.login", "token") && sensitive.is_param("login", "token"));
+        assert!(!sensitive.is_param("auth.login", "user"));
+
+        let list = Type { kind: TypeKind::List(Box::new(named("Credentials"))), span: Span::dummy() };
+        assert_eq!(sensitive.struct_of(&list), Some("Credentials"));
+        assert!(!sensitive.is_type(&named("String")));
+    }
+}
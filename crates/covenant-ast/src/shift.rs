@@ -0,0 +1,463 @@
+//! Span shifting
+//!
+//! Spans are absolute byte offsets into the source, so an edit moves every
+//! node after it. Incremental reparsing reuses those nodes by shifting their
+//! spans rather than parsing them again.
+
+use crate::*;
+
+impl Span {
+    /// This span moved by `delta` bytes
+    pub fn shifted(self, delta: isize) -> Span {
+        Span {
+            start: self.start.saturating_add_signed(delta),
+            end: self.end.saturating_add_signed(delta),
+        }
+    }
+}
+
+impl Snippet {
+    /// Move every span in the snippet, nested nodes included, by `delta` bytes
+    pub fn shift_spans(&mut self, delta: isize) {
+        if delta != 0 {
+            self.shift(delta);
+        }
+    }
+}
+
+/// Nodes whose spans can be moved
+trait Shift {
+    fn shift(&mut self, delta: isize);
+}
+
+impl Shift for Span {
+    fn shift(&mut self, delta: isize) {
+        *self = self.shifted(delta);
+    }
+}
+
+impl<T: Shift> Shift for Vec<T> {
+    fn shift(&mut self, delta: isize) {
+        for item in self {
+            item.shift(delta);
+        }
+    }
+}
+
+impl<T: Shift> Shift for Option<T> {
+    fn shift(&mut self, delta: isize) {
+        if let Some(item) = self {
+            item.shift(delta);
+        }
+    }
+}
+
+impl<T: Shift> Shift for Box<T> {
+    fn shift(&mut self, delta: isize) {
+        (**self).shift(delta);
+    }
+}
+
+// ===== Snippets and sections =====
+
+impl Shift for Snippet {
+    fn shift(&mut self, delta: isize) {
+        self.notes.shift(delta);
+        self.doc.shift(delta);
+        self.sections.shift(delta);
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for Note {
+    fn shift(&mut self, delta: isize) {
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for Doc {
+    fn shift(&mut self, delta: isize) {
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for Section {
+    fn shift(&mut self, delta: isize) {
+        match self {
+            Section::Effects(s) => {
+                s.effects.shift(delta);
+                s.span.shift(delta);
+            }
+            Section::Requires(s) => {
+                for requirement in &mut s.requirements {
+                    requirement.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            Section::Signature(s) => {
+                s.kind.shift(delta);
+                s.span.shift(delta);
+            }
+            Section::Body(s) => {
+                s.steps.shift(delta);
+                s.span.shift(delta);
+            }
+            Section::Tests(s) => {
+                for test in &mut s.tests {
+                    test.steps.shift(delta);
+                    test.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            Section::Metadata(s) => {
+                for entry in &mut s.entries {
+                    entry.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            Section::Relations(s) => {
+                for relation in &mut s.relations {
+                    relation.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            Section::Content(s) => s.span.shift(delta),
+            Section::Schema(s) => {
+                for table in &mut s.tables {
+                    table.fields.shift(delta);
+                    table.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            Section::Types(s) => {
+                for decl in &mut s.types {
+                    decl.ty.shift(delta);
+                    decl.definition.shift(delta);
+                    decl.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            Section::Tools(s) => {
+                for tool in &mut s.tools {
+                    tool.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            Section::Contains(s) => {
+                for member in &mut s.members {
+                    member.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            Section::Subscribe(s) => s.span.shift(delta),
+            Section::Invariant(s) => {
+                for check in &mut s.checks {
+                    for input in &mut check.inputs {
+                        input.span.shift(delta);
+                    }
+                    check.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+        }
+    }
+}
+
+impl Shift for EffectDecl {
+    fn shift(&mut self, delta: isize) {
+        for param in &mut self.params {
+            param.span.shift(delta);
+        }
+        self.span.shift(delta);
+    }
+}
+
+// ===== Signatures and types =====
+
+impl Shift for SignatureKind {
+    fn shift(&mut self, delta: isize) {
+        match self {
+            SignatureKind::Function(f) => f.shift(delta),
+            SignatureKind::Struct(s) => {
+                s.fields.shift(delta);
+                s.span.shift(delta);
+            }
+            SignatureKind::Enum(e) => {
+                for variant in &mut e.variants {
+                    variant.fields.shift(delta);
+                    variant.span.shift(delta);
+                }
+                e.span.shift(delta);
+            }
+            SignatureKind::Const(c) => {
+                c.ty.shift(delta);
+                c.span.shift(delta);
+            }
+            SignatureKind::Alias(a) => {
+                a.ty.shift(delta);
+                a.span.shift(delta);
+            }
+            SignatureKind::Interface(i) => {
+                for method in &mut i.methods {
+                    method.shift(delta);
+                }
+                i.span.shift(delta);
+            }
+        }
+    }
+}
+
+impl Shift for FunctionSignature {
+    fn shift(&mut self, delta: isize) {
+        self.params.shift(delta);
+        self.returns.shift(delta);
+        for generic in &mut self.generics {
+            generic.span.shift(delta);
+        }
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for ParamDecl {
+    fn shift(&mut self, delta: isize) {
+        self.ty.shift(delta);
+        self.doc.shift(delta);
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for ReturnType {
+    fn shift(&mut self, delta: isize) {
+        match self {
+            ReturnType::Single { ty, .. } => ty.shift(delta),
+            ReturnType::Collection { of } => of.shift(delta),
+            ReturnType::Union { types } => {
+                for member in types {
+                    member.ty.shift(delta);
+                }
+            }
+        }
+    }
+}
+
+impl Shift for SnippetFieldDecl {
+    fn shift(&mut self, delta: isize) {
+        self.ty.shift(delta);
+        if let Some(foreign_key) = &mut self.foreign_key {
+            foreign_key.span.shift(delta);
+        }
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for Type {
+    fn shift(&mut self, delta: isize) {
+        match &mut self.kind {
+            TypeKind::Named(path) => {
+                path.generics.shift(delta);
+                path.span.shift(delta);
+            }
+            TypeKind::Optional(inner) | TypeKind::List(inner) => inner.shift(delta),
+            TypeKind::Union(types) | TypeKind::Tuple(types) => types.shift(delta),
+            TypeKind::Function { params, ret } => {
+                params.shift(delta);
+                ret.shift(delta);
+            }
+            TypeKind::Struct(fields) => {
+                for field in fields {
+                    field.ty.shift(delta);
+                    field.span.shift(delta);
+                }
+            }
+        }
+        self.span.shift(delta);
+    }
+}
+
+// ===== Steps =====
+
+impl Shift for CallStep {
+    fn shift(&mut self, delta: isize) {
+        self.type_args.shift(delta);
+        for arg in &mut self.args {
+            arg.span.shift(delta);
+        }
+        for expect in &mut self.expects {
+            expect.span.shift(delta);
+        }
+        if let Some(handle) = &mut self.handle {
+            for case in &mut handle.cases {
+                case.steps.shift(delta);
+                case.span.shift(delta);
+            }
+            handle.span.shift(delta);
+        }
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for Step {
+    fn shift(&mut self, delta: isize) {
+        match &mut self.kind {
+            StepKind::Compute(s) => {
+                for input in &mut s.inputs {
+                    input.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            StepKind::Call(s) => s.shift(delta),
+            StepKind::Pipeline(s) => {
+                for stage in &mut s.stages {
+                    stage.call.shift(delta);
+                    stage.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            StepKind::Query(s) => {
+                match &mut s.content {
+                    QueryContent::Covenant(q) => {
+                        q.where_clause.shift(delta);
+                        if let Some(order) = &mut q.order {
+                            order.span.shift(delta);
+                        }
+                        q.span.shift(delta);
+                    }
+                    QueryContent::Dialect(q) => {
+                        for param in &mut q.params {
+                            param.span.shift(delta);
+                        }
+                        q.returns.shift(delta);
+                        q.span.shift(delta);
+                    }
+                }
+                s.span.shift(delta);
+            }
+            StepKind::Bind(s) => {
+                if let BindSource::Variant(variant) = &mut s.source {
+                    variant.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            StepKind::Return(s) => {
+                match &mut s.value {
+                    ReturnValue::Struct(construction) => construction.shift(delta),
+                    ReturnValue::Variant(variant) => variant.shift(delta),
+                    ReturnValue::Var(_) | ReturnValue::Lit(_) => {}
+                }
+                s.span.shift(delta);
+            }
+            StepKind::Raise(s) => {
+                s.variant.shift(delta);
+                s.span.shift(delta);
+            }
+            StepKind::If(s) => {
+                s.then_steps.shift(delta);
+                s.else_steps.shift(delta);
+                s.span.shift(delta);
+            }
+            StepKind::Match(s) => {
+                for case in &mut s.cases {
+                    if let MatchPattern::Type { ty } = &mut case.pattern {
+                        ty.shift(delta);
+                    }
+                    case.steps.shift(delta);
+                    case.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            StepKind::For(s) => {
+                s.steps.shift(delta);
+                s.span.shift(delta);
+            }
+            StepKind::Break(s) => s.span.shift(delta),
+            StepKind::Continue(s) => s.span.shift(delta),
+            StepKind::Using(s) => {
+                for arg in &mut s.acquire.args {
+                    arg.span.shift(delta);
+                }
+                s.acquire.span.shift(delta);
+                s.steps.shift(delta);
+                s.span.shift(delta);
+            }
+            StepKind::Assert(s) => {
+                for input in &mut s.inputs {
+                    input.span.shift(delta);
+                }
+                s.span.shift(delta);
+            }
+            StepKind::Lambda(s) => {
+                s.params.shift(delta);
+                s.returns.shift(delta);
+                s.steps.shift(delta);
+                s.span.shift(delta);
+            }
+            StepKind::Insert(s) => {
+                s.assignments.shift(delta);
+                s.span.shift(delta);
+            }
+            StepKind::Update(s) => {
+                s.assignments.shift(delta);
+                s.where_clause.shift(delta);
+                s.span.shift(delta);
+            }
+            StepKind::Delete(s) => {
+                s.where_clause.shift(delta);
+                s.span.shift(delta);
+            }
+            StepKind::Transaction(s) => {
+                s.steps.shift(delta);
+                s.span.shift(delta);
+            }
+            StepKind::Traverse(s) => s.span.shift(delta),
+            StepKind::Construct(s) => s.shift(delta),
+            StepKind::Parallel(s) => {
+                s.branches.shift(delta);
+                s.span.shift(delta);
+            }
+            StepKind::Race(s) => {
+                s.branches.shift(delta);
+                s.span.shift(delta);
+            }
+        }
+        self.doc.shift(delta);
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for Branch {
+    fn shift(&mut self, delta: isize) {
+        self.steps.shift(delta);
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for Condition {
+    fn shift(&mut self, delta: isize) {
+        if let ConditionKind::And(left, right) | ConditionKind::Or(left, right) = &mut self.kind {
+            left.shift(delta);
+            right.shift(delta);
+        }
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for StructConstruction {
+    fn shift(&mut self, delta: isize) {
+        self.ty.shift(delta);
+        self.fields.shift(delta);
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for VariantConstruction {
+    fn shift(&mut self, delta: isize) {
+        self.fields.shift(delta);
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for FieldAssignment {
+    fn shift(&mut self, delta: isize) {
+        self.span.shift(delta);
+    }
+}
@@ -3,27 +3,37 @@
 //! Snippets are the top-level constructs in Covenant IR. Every piece of code
 //! is wrapped in a snippet with explicit sections (effects, requires, signature, body, tests, etc.)
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use crate::{Literal, Span, Type};
+use crate::{Literal, NodeId, Span, Type};
 
 /// A complete snippet (top-level IR construct)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Snippet {
     pub id: String,
     pub kind: SnippetKind,
     pub notes: Vec<Note>,
+    /// `doc """..."""` block after the header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<Doc>,
     pub sections: Vec<Section>,
-    /// For extern-impl: the abstract snippet ID this implements
+    /// For extern-impl: the abstract snippet ID this implements; for fn,
+    /// workflow and struct snippets: the interface snippet ID
     pub implements: Option<String>,
     /// For extern-impl: the target platform
     pub platform: Option<String>,
+    /// Set by [`Program::assign_node_ids`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<NodeId>,
     pub span: Span,
 }
 
 /// Snippet kinds
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum SnippetKind {
     Function,
+    /// Long-running function whose effectful steps are checkpointed so it can resume after a crash
+    Workflow,
     Struct,
     Enum,
     Module,
@@ -35,18 +45,44 @@ pub enum SnippetKind {
     ExternImpl,
     Test,
     Data,
+    /// Binds a handler function to an event type (a struct snippet)
+    Subscriber,
+    /// A typed compile-time constant, referenced by its snippet ID
+    Const,
+    /// Method signatures that fn and struct snippets declare they implement
+    Interface,
+    /// Another name for a type, usable wherever a type is written
+    Alias,
+}
+
+impl SnippetKind {
+    /// Whether snippets of this kind declare a function signature and body
+    /// (`fn` and `workflow`)
+    pub fn is_function(self) -> bool {
+        matches!(self, SnippetKind::Function | SnippetKind::Workflow)
+    }
 }
 
 /// A note annotation (can be multilingual)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Note {
     pub lang: Option<String>,
     pub content: String,
     pub span: Span,
 }
 
+/// Documentation for a snippet, parameter or step (`doc """..."""`)
+///
+/// Unlike notes, docs describe the API and are what explain and doc
+/// generation show. The content is kept verbatim.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Doc {
+    pub content: String,
+    pub span: Span,
+}
+
 /// Section types within a snippet
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum Section {
     Effects(EffectsSection),
     Requires(RequiresSection),
@@ -59,25 +95,50 @@ pub enum Section {
     Schema(SchemaSection),
     Types(TypesSection),
     Tools(ToolsSection),
+    Contains(ContainsSection),
+    Subscribe(SubscribeSection),
+    Invariant(InvariantSection),
+}
+
+impl Section {
+    /// Source span of the section, starting at its keyword
+    pub fn span(&self) -> Span {
+        match self {
+            Section::Effects(s) => s.span,
+            Section::Requires(s) => s.span,
+            Section::Signature(s) => s.span,
+            Section::Body(s) => s.span,
+            Section::Tests(s) => s.span,
+            Section::Metadata(s) => s.span,
+            Section::Relations(s) => s.span,
+            Section::Content(s) => s.span,
+            Section::Schema(s) => s.span,
+            Section::Types(s) => s.span,
+            Section::Tools(s) => s.span,
+            Section::Contains(s) => s.span,
+            Section::Subscribe(s) => s.span,
+            Section::Invariant(s) => s.span,
+        }
+    }
 }
 
 // ===== Effects Section =====
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EffectsSection {
     pub effects: Vec<EffectDecl>,
     pub span: Span,
 }
 
 /// An effect parameter (key=value pair)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct EffectParam {
     pub name: String,
     pub value: Literal,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EffectDecl {
     pub name: String,
     pub params: Vec<EffectParam>,
@@ -98,13 +159,13 @@ impl EffectDecl {
 
 // ===== Requirements Section =====
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RequiresSection {
     pub requirements: Vec<Requirement>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Requirement {
     pub id: String,
     pub text: Option<String>,
@@ -113,7 +174,7 @@ pub struct Requirement {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum Priority {
     Critical,
     High,
@@ -121,7 +182,7 @@ pub enum Priority {
     Low,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum ReqStatus {
     Draft,
     Approved,
@@ -131,20 +192,23 @@ pub enum ReqStatus {
 
 // ===== Signature Section =====
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SignatureSection {
     pub kind: SignatureKind,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum SignatureKind {
     Function(FunctionSignature),
     Struct(StructSignature),
     Enum(EnumSignature),
+    Const(ConstSignature),
+    Interface(InterfaceSignature),
+    Alias(AliasSignature),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FunctionSignature {
     pub name: String,
     pub params: Vec<ParamDecl>,
@@ -153,40 +217,49 @@ pub struct FunctionSignature {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParamDecl {
     pub name: String,
     pub ty: Type,
+    /// `sensitive`: a secret, redacted from audit logs, traces and explain prompts
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sensitive: bool,
+    /// `doc` block written just before the param
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<Doc>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum ReturnType {
     Single { ty: Type, optional: bool },
     Collection { of: Type },
     Union { types: Vec<UnionMember> },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UnionMember {
     pub ty: Type,
     pub optional: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GenericParam {
     pub name: String,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StructSignature {
     pub name: String,
     pub fields: Vec<SnippetFieldDecl>,
+    /// A single-field wrapper (`struct name="UserId" newtype`): a distinct
+    /// type to the checker, represented as its field at runtime
+    pub newtype: bool,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SnippetFieldDecl {
     pub name: String,
     pub ty: Type,
@@ -194,17 +267,56 @@ pub struct SnippetFieldDecl {
     pub auto: bool,
     pub unique: bool,
     pub optional: bool,
+    /// For schema fields: `foreign_key="table.field"`
+    pub foreign_key: Option<ForeignKey>,
+    /// `sensitive`: a secret, redacted from audit logs, traces and explain prompts
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sensitive: bool,
+    pub span: Span,
+}
+
+/// A schema field's reference to a field in another table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ForeignKey {
+    pub table: String,
+    pub field: String,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The type an alias snippet names (`alias name="UserId" type="Int"`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AliasSignature {
+    pub name: String,
+    pub ty: Type,
+    pub span: Span,
+}
+
+/// The value of a const snippet (`const name="MAX_RETRIES" type="Int" lit=3`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConstSignature {
+    pub name: String,
+    pub ty: Type,
+    pub value: Literal,
+    pub span: Span,
+}
+
+/// The methods of an interface snippet. A param or return of type `Self`
+/// stands for the implementing struct.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InterfaceSignature {
+    pub name: String,
+    pub methods: Vec<FunctionSignature>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EnumSignature {
     pub name: String,
     pub variants: Vec<SnippetVariantDecl>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SnippetVariantDecl {
     pub name: String,
     pub fields: Option<Vec<SnippetFieldDecl>>,
@@ -213,30 +325,148 @@ pub struct SnippetVariantDecl {
 
 // ===== Body Section =====
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BodySection {
     pub steps: Vec<Step>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Step {
     pub id: String,
     pub kind: StepKind,
     pub output_binding: String, // "as" attribute
+    /// `doc` block written just before the step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<Doc>,
+    /// Set by [`Program::assign_node_ids`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<NodeId>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Step {
+    /// Mutable access to the step lists of [`Step::nested_steps`]
+    pub fn nested_steps_mut(&mut self) -> Vec<&mut Vec<Step>> {
+        match &mut self.kind {
+            StepKind::If(if_step) => {
+                let mut lists = vec![&mut if_step.then_steps];
+                lists.extend(if_step.else_steps.as_mut());
+                lists
+            }
+            StepKind::Match(m) => m.cases.iter_mut().map(|c| &mut c.steps).collect(),
+            StepKind::For(f) => vec![&mut f.steps],
+            StepKind::Lambda(l) => vec![&mut l.steps],
+            StepKind::Using(u) => vec![&mut u.steps],
+            StepKind::Transaction(tx) => vec![&mut tx.steps],
+            StepKind::Call(call) => call
+                .handle
+                .iter_mut()
+                .flat_map(|h| h.cases.iter_mut().map(|c| &mut c.steps))
+                .collect(),
+            StepKind::Parallel(p) => p.branches.iter_mut().map(|b| &mut b.steps).collect(),
+            StepKind::Race(r) => r.branches.iter_mut().map(|b| &mut b.steps).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Step lists nested directly inside this step (branches, loop bodies,
+    /// lambda bodies, match arms, handle cases, transaction bodies)
+    pub fn nested_steps(&self) -> Vec<&[Step]> {
+        match &self.kind {
+            StepKind::If(if_step) => {
+                let mut lists = vec![if_step.then_steps.as_slice()];
+                lists.extend(if_step.else_steps.as_deref());
+                lists
+            }
+            StepKind::Match(m) => m.cases.iter().map(|c| c.steps.as_slice()).collect(),
+            StepKind::For(f) => vec![f.steps.as_slice()],
+            StepKind::Lambda(l) => vec![l.steps.as_slice()],
+            StepKind::Using(u) => vec![u.steps.as_slice()],
+            StepKind::Transaction(tx) => vec![tx.steps.as_slice()],
+            StepKind::Call(call) => call
+                .handle
+                .iter()
+                .flat_map(|h| h.cases.iter().map(|c| c.steps.as_slice()))
+                .collect(),
+            StepKind::Parallel(p) => p.branches.iter().map(|b| b.steps.as_slice()).collect(),
+            StepKind::Race(r) => r.branches.iter().map(|b| b.steps.as_slice()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Total number of steps in a step list, nested steps included
+pub fn step_count(steps: &[Step]) -> usize {
+    let mut count = 0;
+    let mut pending: Vec<&[Step]> = vec![steps];
+
+    while let Some(list) = pending.pop() {
+        count += list.len();
+        for step in list {
+            pending.extend(step.nested_steps());
+        }
+    }
+
+    count
+}
+
+/// The first step, in source order, nested more than `limit` levels deep
+///
+/// Like [`step_nesting_depth`], walks with an explicit stack.
+pub fn step_beyond_depth(steps: &[Step], limit: usize) -> Option<&Step> {
+    let mut stack: Vec<(&Step, usize)> = steps.iter().rev().map(|step| (step, 1)).collect();
+    while let Some((step, depth)) = stack.pop() {
+        if depth > limit {
+            return Some(step);
+        }
+        for nested in step.nested_steps().into_iter().rev() {
+            stack.extend(nested.iter().rev().map(|step| (step, depth + 1)));
+        }
+    }
+    None
+}
+
+/// Deepest step nesting in a step list (a flat body has depth 1)
+///
+/// Walks the tree with an explicit stack, so arbitrarily deep input cannot
+/// overflow the call stack.
+pub fn step_nesting_depth(steps: &[Step]) -> usize {
+    let mut max_depth = 0;
+    let mut stack: Vec<(&[Step], usize)> = vec![(steps, 1)];
+
+    while let Some((list, depth)) = stack.pop() {
+        if list.is_empty() {
+            continue;
+        }
+        max_depth = max_depth.max(depth);
+        for step in list {
+            for nested in step.nested_steps() {
+                stack.push((nested, depth + 1));
+            }
+        }
+    }
+
+    max_depth
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum StepKind {
     Compute(ComputeStep),
     Call(CallStep),
+    Pipeline(PipelineStep),
     Query(QueryStep),
     Bind(BindStep),
     Return(ReturnStep),
+    Raise(RaiseStep),
     If(IfStep),
     Match(MatchStep),
     For(ForStep),
+    Break(BreakStep),
+    Continue(ContinueStep),
+    Lambda(LambdaStep),
+    Using(UsingStep),
+    Assert(AssertStep),
     Insert(InsertStep),
     Update(UpdateStep),
     Delete(DeleteStep),
@@ -249,14 +479,14 @@ pub enum StepKind {
 
 // ===== Step Types =====
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ComputeStep {
     pub op: Operation,
     pub inputs: Vec<Input>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum Operation {
     // Arithmetic
     Add,
@@ -265,6 +495,19 @@ pub enum Operation {
     Div,
     Mod,
 
+    // Int arithmetic with explicit overflow behavior. Plain add/sub/mul trap
+    // on overflow in dev builds and wrap at O2 and above
+    AddWrapping,
+    SubWrapping,
+    MulWrapping,
+    AddSaturating,
+    SubSaturating,
+    MulSaturating,
+    /// Returns `Int?`: none when the result overflows
+    AddChecked,
+    SubChecked,
+    MulChecked,
+
     // Comparison
     Equals,
     NotEquals,
@@ -313,11 +556,55 @@ pub enum Operation {
     ParseInt,
     ParseFloat,
 
+    // Regex operations. The pattern is the second input and must be a String
+    // literal: the checker validates it, and the runtime matches it in time
+    // linear in the input
+    /// `regex_match(s, pattern) -> Bool`: whether the pattern matches anywhere in `s`
+    RegexMatch,
+    /// `regex_find_all(s, pattern) -> String[]`: every match, left to right
+    /// and not overlapping; an empty match right after another is skipped
+    RegexFindAll,
+    /// `regex_capture(s, pattern) -> String[]`: the first match followed by its
+    /// groups (empty for groups that did not take part), or `[]` with no match
+    RegexCapture,
+    /// `regex_replace(s, pattern, replacement) -> String`: replaces every
+    /// match; `$0`-`$9` insert groups and `$$` a literal `$`
+    RegexReplace,
+
     // List operations — now extern-abstract calls (list.len, list.get, etc.)
     // Removed: ListLen, ListGet, ListFirst, ListLast, ListAppend, ListPrepend,
     // ListConcat, ListSlice, ListReverse, ListTake, ListDrop, ListContains,
     // ListIndexOf, ListIsEmpty, ListSort, ListDedup, ListFlatten
 
+    // Fixed-size array operations. An `Array<T, N>` has its length in its
+    // type; indexes are bounds-checked at runtime unless the index is a
+    // constant known to be in range
+    /// `array_of(a, b, ...) -> Array<T, N>`: an array of the inputs
+    ArrayOf,
+    /// `array_fill(value, n) -> Array<T, n>`: `n` copies of the value; `n`
+    /// must be an Int literal
+    ArrayFill,
+    /// `array_get(arr, i) -> T`
+    ArrayGet,
+    /// `array_set(arr, i, value) -> Array<T, N>`: a copy with element `i` replaced
+    ArraySet,
+    /// `array_len(arr) -> Int`
+    ArrayLen,
+
+    // Numeric vector operations over `List<Float>` and `Array<Float, N>`.
+    // Vectors must have equal lengths, checked at compile time for arrays
+    // and at runtime (trapping) for lists. Matrices are vectors of rows
+    /// `vec_add(a, b)`: elementwise sum, the type of `a`
+    VecAdd,
+    /// `vec_sub(a, b)`: elementwise difference, the type of `a`
+    VecSub,
+    /// `vec_scale(a, k)`: each element times the Float `k`
+    VecScale,
+    /// `dot(a, b) -> Float`
+    Dot,
+    /// `matmul(a, b)`: an M×K matrix times a K×N matrix is an M×N matrix
+    Matmul,
+
     // Map operations (partially converted — MapGet is now map.get extern-abstract)
     MapLen,
     MapHas,
@@ -343,6 +630,12 @@ pub enum Operation {
     SetIsEmpty,
     SetToList,
 
+    // Optional operations
+    IsSome,
+    IsNone,
+    /// The value, or the second input when it is none
+    UnwrapOr,
+
     // DateTime operations
     DtYear,
     DtMonth,
@@ -352,13 +645,55 @@ pub enum Operation {
     DtSecond,
     DtWeekday,
     DtUnix,
-    DtAddDays,
-    DtAddHours,
-    DtAddMinutes,
-    DtAddSeconds,
+    /// DateTime plus a Duration
+    DtAdd,
+    /// DateTime minus a Duration
+    DtSub,
+    /// Duration from the second DateTime to the first
     DtDiff,
     DtFormat,
 
+    // Duration operations (a Duration is a signed count of whole seconds)
+    DurSeconds,
+    DurMinutes,
+    DurHours,
+    DurDays,
+    DurTotalSeconds,
+    DurAdd,
+    DurSub,
+    /// Duration times an Int
+    DurScale,
+    /// ISO 8601 text such as `P1DT2H30M`
+    DurFormat,
+
+    // Decimal operations. A Decimal is exact and keeps its places: sums
+    // have the places of the wider input and products their total
+    DecAdd,
+    DecSub,
+    DecMul,
+    /// `dec_div(a, b, places)`: the quotient rounded half to even to
+    /// `places` decimal places, which must be an Int literal
+    DecDiv,
+    /// `dec_round(a, places)`: rounded half to even to `places` decimal places
+    DecRound,
+    /// `dec_cmp(a, b) -> Int`: -1, 0 or 1; `1.5d` and `1.50d` compare equal
+    DecCmp,
+    DecFromInt,
+    /// Text such as `-19.90`
+    DecFormat,
+
+    // BigInt operations (arbitrary-precision integers)
+    BigAdd,
+    BigSub,
+    BigMul,
+    /// Truncates toward zero, like Int division
+    BigDiv,
+    BigMod,
+    /// `big_cmp(a, b) -> Int`: -1, 0 or 1
+    BigCmp,
+    BigFromInt,
+    BigFormat,
+
     // Bytes operations
     BytesLen,
     BytesGet,
@@ -368,50 +703,114 @@ pub enum Operation {
     BytesToBase64,
     BytesToHex,
     BytesIsEmpty,
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+    // Json operations. A `Json` value is any JSON document, held as its
+    // compact text, so extern snippets can pass payloads without declaring
+    // structs for them
+    /// `json_parse(s) -> Json | DecodeError`
+    JsonParse,
+    /// `json_stringify(j) -> String`: the document's compact text
+    JsonStringify,
+    /// `json_get(j, key) -> Json?`: an object's field, or an array's element
+    /// at the index `key` spells; none when there is no such field or element
+    JsonGet,
+    /// `json_set(j, key, value) -> Json`: a copy with the field set, or the
+    /// array element replaced (appended at the array's length); other
+    /// documents are returned unchanged
+    JsonSet,
+    /// `json_keys(j) -> String[]`: an object's keys in order, else `[]`
+    JsonKeys,
+
+    // Encoding operations (decoders return `T | DecodeError`)
+    BytesFromBase64,
+    BytesToBase64Url,
+    BytesFromBase64Url,
+    BytesFromHex,
+    UrlEncode,
+    UrlDecode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Input {
     pub source: InputSource,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum InputSource {
     Var(String),
     Lit(Literal),
     Field { of: String, field: String },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CallStep {
     pub fn_name: String,
+    /// Type arguments for the callee's generic params, in declaration order
+    /// (`type arg="Int"`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub type_args: Vec<Type>,
     pub args: Vec<CallArg>,
     pub handle: Option<HandleBlock>,
+    /// Golden values the result must match (`expect field="total" lit=5`),
+    /// used by tests in place of assert steps
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expects: Vec<Expectation>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An expected value for a call's result, or a field of it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Expectation {
+    /// The call's output binding, or a dotted path into it (`result.total`)
+    pub field: String,
+    pub value: Literal,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CallArg {
     pub name: String,
     pub source: InputSource,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A value threaded through a chain of calls (`kind="pipeline"`)
+///
+/// `from` is the first argument of the first stage, and each stage's result
+/// the first argument of the next. Lowering expands a pipeline into plain
+/// call steps before checking.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PipelineStep {
+    pub from: InputSource,
+    pub stages: Vec<PipelineStage>,
+    pub span: Span,
+}
+
+/// One call of a pipeline (`stage fn="..." [into="..."]`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PipelineStage {
+    /// Name of the argument the threaded value is passed as
+    pub into: String,
+    /// The call without the threaded argument
+    pub call: CallStep,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HandleBlock {
     pub cases: Vec<HandleCase>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HandleCase {
     pub error_type: String,
     pub steps: Vec<Step>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct QueryStep {
     pub dialect: Option<String>, // None = Covenant, Some("postgres") = SQL
     pub target: String,
@@ -419,13 +818,13 @@ pub struct QueryStep {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum QueryContent {
     Covenant(CovenantQuery),
     Dialect(DialectQuery),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CovenantQuery {
     pub select: SnippetSelectClause,
     pub from: String,
@@ -435,19 +834,19 @@ pub struct CovenantQuery {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum SnippetSelectClause {
     All,
     Field(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Condition {
     pub kind: ConditionKind,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum ConditionKind {
     Equals { field: String, value: InputSource },
     Contains { field: String, value: InputSource },
@@ -458,20 +857,20 @@ pub enum ConditionKind {
     RelFrom { source: String, rel_type: String },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct OrderClause {
     pub field: String,
     pub direction: SnippetOrderDirection,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum SnippetOrderDirection {
     Asc,
     Desc,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DialectQuery {
     pub body: String, // Raw SQL
     pub params: Vec<ParamBinding>,
@@ -479,33 +878,34 @@ pub struct DialectQuery {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParamBinding {
     pub name: String,
     pub from: String,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BindStep {
     pub source: BindSource,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum BindSource {
     Var(String),
     Lit(Literal),
     Field { of: String, field: String },
+    Variant(VariantConstruction),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReturnStep {
     pub value: ReturnValue,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum ReturnValue {
     Var(String),
     Lit(Literal),
@@ -513,28 +913,40 @@ pub enum ReturnValue {
     Variant(VariantConstruction),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StructConstruction {
     pub ty: Type,
+    /// Binding of the same struct type whose fields are copied before the
+    /// listed fields override them (`from_base="order"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_base: Option<String>,
     pub fields: Vec<FieldAssignment>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FieldAssignment {
     pub name: String,
     pub value: InputSource,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct VariantConstruction {
     pub ty: String,
     pub fields: Vec<FieldAssignment>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Exits the function with an error: a variant of one of the error members
+/// of its union return type, which are the members after the first
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RaiseStep {
+    pub variant: VariantConstruction,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IfStep {
     pub condition: InputSource,
     pub then_steps: Vec<Step>,
@@ -542,27 +954,29 @@ pub struct IfStep {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MatchStep {
     pub on: String,
     pub cases: Vec<MatchCase>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MatchCase {
     pub pattern: MatchPattern,
     pub steps: Vec<Step>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum MatchPattern {
     Variant { variant: String, bindings: Vec<String> },
+    /// A member of a union; the matched binding has this type inside the case
+    Type { ty: Type },
     Wildcard,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ForStep {
     pub var: String,
     pub collection: String,
@@ -570,14 +984,64 @@ pub struct ForStep {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Exits the innermost enclosing loop
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BreakStep {
+    pub span: Span,
+}
+
+/// Skips to the next iteration of the innermost enclosing loop
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ContinueStep {
+    pub span: Span,
+}
+
+/// Anonymous function value; a `return` in `steps` produces its result.
+/// Bindings of the enclosing body are captured by value when it is created.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LambdaStep {
+    pub params: Vec<ParamDecl>,
+    pub returns: Option<Type>,
+    pub steps: Vec<Step>,
+    pub span: Span,
+}
+
+/// Acquires a resource, runs `steps` with it bound to `var`, and calls
+/// `release` with it on every exit from `steps`: falling off the end,
+/// `return` (including from a `handle` case), `break` and `continue`.
+/// The step's value is the value of the last step in `steps`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UsingStep {
+    pub var: String,
+    /// Call producing the resource (`acquire fn="db.open"` plus its args)
+    pub acquire: CallStep,
+    /// Function taking the resource as its only argument (`release fn="db.close"`)
+    pub release: String,
+    pub steps: Vec<Step>,
+    pub span: Span,
+}
+
+/// Traps with `message` when the comparison is false. Written like a
+/// compute step whose result must be Bool (`op=less` / `input var="n"` /
+/// `input lit=10`). Checked in debug builds only: the optimizer removes
+/// assert steps at O2 and above.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AssertStep {
+    pub op: Operation,
+    pub inputs: Vec<Input>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct InsertStep {
     pub target: String,
     pub assignments: Vec<FieldAssignment>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UpdateStep {
     pub target: String,
     pub assignments: Vec<FieldAssignment>,
@@ -585,21 +1049,24 @@ pub struct UpdateStep {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DeleteStep {
     pub target: String,
     pub where_clause: Option<Condition>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TransactionStep {
+    /// Database binding the transaction runs against; nested query steps
+    /// without their own `target=` inherit it
+    pub target: Option<String>,
     pub isolation: Option<IsolationLevel>,
     pub steps: Vec<Step>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum IsolationLevel {
     ReadUncommitted,
     ReadCommitted,
@@ -607,7 +1074,7 @@ pub enum IsolationLevel {
     Serializable,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TraverseStep {
     pub target: String,
     pub from: String,
@@ -617,13 +1084,13 @@ pub struct TraverseStep {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum TraverseDepth {
     Bounded(u32),
     Unbounded,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum TraverseDirection {
     Outgoing,
     Incoming,
@@ -632,14 +1099,14 @@ pub enum TraverseDirection {
 
 // ===== Structured Concurrency =====
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Branch {
     pub id: String,
     pub steps: Vec<Step>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParallelStep {
     pub branches: Vec<Branch>,
     pub on_error: Option<String>,  // "fail_fast", "collect_all", "ignore_errors"
@@ -647,7 +1114,7 @@ pub struct ParallelStep {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RaceStep {
     pub branches: Vec<Branch>,
     pub on_timeout: Option<String>,  // "cancel", "return_partial"
@@ -657,13 +1124,13 @@ pub struct RaceStep {
 
 // ===== Other Sections (stubs for now) =====
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TestsSection {
     pub tests: Vec<TestDecl>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TestDecl {
     pub id: String,
     pub kind: TestKind,
@@ -672,7 +1139,7 @@ pub struct TestDecl {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum TestKind {
     Unit,
     Integration,
@@ -680,26 +1147,26 @@ pub enum TestKind {
     Property,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MetadataSection {
     pub entries: Vec<MetadataEntry>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MetadataEntry {
     pub key: String,
     pub value: String,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RelationsSection {
     pub relations: Vec<RelationDecl>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RelationDecl {
     pub kind: RelationKind,
     pub target: String,
@@ -707,53 +1174,122 @@ pub struct RelationDecl {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum RelationKind {
     To,
     From,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ContentSection {
     pub content: String,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SchemaSection {
     pub tables: Vec<SnippetTableDecl>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SnippetTableDecl {
     pub name: String,
     pub fields: Vec<SnippetFieldDecl>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl SchemaSection {
+    /// Look up a declared table by name
+    pub fn table(&self, name: &str) -> Option<&SnippetTableDecl> {
+        self.tables.iter().find(|t| t.name == name)
+    }
+}
+
+impl SnippetTableDecl {
+    /// Fields marked as primary key
+    pub fn primary_key(&self) -> impl Iterator<Item = &SnippetFieldDecl> {
+        self.fields.iter().filter(|f| f.primary)
+    }
+
+    /// Look up a field by name
+    pub fn field(&self, name: &str) -> Option<&SnippetFieldDecl> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TypesSection {
     pub types: Vec<TypeDecl>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TypeDecl {
     pub name: String,
     pub ty: Type,
+    /// The struct or enum declared in place, with its fields or variants
+    #[serde(default)]
+    pub definition: Option<SignatureKind>,
+    /// Unit of measure for an alias (`unit="USD"`); a unit alias is a distinct
+    /// type that only mixes with itself in arithmetic
+    #[serde(default)]
+    pub unit: Option<String>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ToolsSection {
     pub tools: Vec<ToolDecl>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ToolDecl {
     pub name: String,
     pub contract: String,
     pub span: Span,
 }
+
+/// Conditions every value of a struct must satisfy, checked after each
+/// construct step of the struct in debug builds
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InvariantSection {
+    pub checks: Vec<InvariantCheck>,
+    pub span: Span,
+}
+
+/// A condition over the struct's fields, written like an assert step whose
+/// `input var=` names a field (`check op=less_eq` / `input var="min"` / `input var="max"`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InvariantCheck {
+    pub op: Operation,
+    pub inputs: Vec<Input>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub span: Span,
+}
+
+/// Member snippets of a module snippet
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ContainsSection {
+    pub members: Vec<MemberDecl>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemberDecl {
+    /// Snippet ID of the member, which may itself be a module
+    pub id: String,
+    pub span: Span,
+}
+
+/// The event a subscriber snippet listens for and the function it runs
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SubscribeSection {
+    /// Struct type of the event (`event type="OrderPlaced"`)
+    pub event: String,
+    /// Function called with each published event (`handler fn="notify"`)
+    pub handler: String,
+    pub span: Span,
+}
@@ -1,9 +1,10 @@
 //! Source location tracking
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// A span representing a range in source code
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Span {
     /// Byte offset of the start
     pub start: usize,
@@ -36,7 +37,7 @@ impl Default for Span {
 }
 
 /// A value with an associated span
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Spanned<T> {
     pub node: T,
     pub span: Span,
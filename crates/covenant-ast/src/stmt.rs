@@ -1,23 +1,24 @@
 //! Statement AST nodes
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::{Span, Type, Expr};
 
 /// A block of statements
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Block {
     pub statements: Vec<Statement>,
     pub span: Span,
 }
 
 /// A statement
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Statement {
     pub kind: StatementKind,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum StatementKind {
     /// Let binding: `let x = 5` or `let mut x: Int = 5`
     Let {
@@ -42,13 +43,13 @@ pub enum StatementKind {
 }
 
 /// Pattern for match expressions
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Pattern {
     pub kind: PatternKind,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum PatternKind {
     /// Wildcard: `_`
     Wildcard,
@@ -66,7 +67,7 @@ pub enum PatternKind {
     },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum PatternFields {
     /// Positional: `Some(x, y)`
     Positional(Vec<Pattern>),
@@ -77,7 +78,7 @@ pub enum PatternFields {
 }
 
 /// A match arm: `pattern => expr`
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MatchArm {
     pub pattern: Pattern,
     pub body: Expr,
@@ -0,0 +1,229 @@
+//! Text of DateTime and Duration literals
+//!
+//! A DateTime literal is RFC 3339 text with whole seconds, such as
+//! `datetime("2024-01-01T09:30:00+02:00")`, kept as Unix seconds in UTC.
+//! Years run from 0000 to 9999, the ones the text can write. A Duration
+//! literal counts days, hours, minutes and seconds in that order, each at
+//! most once, such as `duration("1h30m")` or `duration("-5m")`, and is kept
+//! as whole seconds.
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// The earliest DateTime a literal can write, 0000-01-01T00:00:00Z
+pub const MIN_DATETIME: i64 = -62_167_219_200;
+
+/// The latest DateTime a literal can write, 9999-12-31T23:59:59Z
+pub const MAX_DATETIME: i64 = 253_402_300_799;
+
+/// Days from 1970-01-01 to the given proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01, as (year, month, day)
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parse `width` ASCII digits
+fn digits(text: &str, width: usize) -> Option<i64> {
+    if text.len() != width || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Parse the text of a DateTime literal into Unix seconds
+pub fn parse_datetime(text: &str) -> Result<i64, String> {
+    let expected = || "expected RFC 3339 text such as 2024-01-01T00:00:00Z".to_string();
+    let (date, rest) = text.split_once(['T', 't']).ok_or_else(expected)?;
+    let (time, offset) = match rest.find(['Z', 'z', '+', '-']) {
+        Some(at) => rest.split_at(at),
+        None => return Err(format!("{}; the offset is missing", expected())),
+    };
+    if time.contains('.') {
+        return Err("a DateTime has whole seconds".to_string());
+    }
+
+    let field = |part: Option<&str>| part.and_then(|p| digits(p, 2));
+    let mut date_parts = date.splitn(3, '-');
+    let year = date_parts.next().and_then(|p| digits(p, 4)).ok_or_else(expected)?;
+    let month = field(date_parts.next()).ok_or_else(expected)?;
+    let day = field(date_parts.next()).ok_or_else(expected)?;
+    let mut time_parts = time.splitn(3, ':');
+    let hour = field(time_parts.next()).ok_or_else(expected)?;
+    let minute = field(time_parts.next()).ok_or_else(expected)?;
+    let second = field(time_parts.next()).ok_or_else(expected)?;
+
+    let offset_seconds = match offset {
+        "Z" | "z" => 0,
+        _ => {
+            let (sign, hours_minutes) = offset.split_at(1);
+            let (hours, minutes) = hours_minutes.split_once(':').ok_or_else(expected)?;
+            let (hours, minutes) = (digits(hours, 2).ok_or_else(expected)?, digits(minutes, 2).ok_or_else(expected)?);
+            if hours > 23 || minutes > 59 {
+                return Err(format!("offset {} is out of range", offset));
+            }
+            let seconds = hours * 3_600 + minutes * 60;
+            if sign == "-" { -seconds } else { seconds }
+        }
+    };
+
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(format!("{} is not a date", date));
+    }
+    if hour > 23 || minute > 59 || second > 59 {
+        return Err(format!("{} is not a time of day", time));
+    }
+
+    let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3_600 + minute * 60 + second
+        - offset_seconds;
+    if !(MIN_DATETIME..=MAX_DATETIME).contains(&seconds) {
+        return Err("the time in UTC is outside the years 0000 to 9999".to_string());
+    }
+    Ok(seconds)
+}
+
+/// The RFC 3339 text of a DateTime in UTC, such as `2024-01-01T00:00:00Z`
+///
+/// Only DateTimes from [`MIN_DATETIME`] to [`MAX_DATETIME`] have text the
+/// parser reads back.
+pub fn format_datetime(seconds: i64) -> String {
+    let (days, time) = (seconds.div_euclid(SECONDS_PER_DAY), seconds.rem_euclid(SECONDS_PER_DAY));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Parse the text of a Duration literal into whole seconds
+pub fn parse_duration(text: &str) -> Result<i64, String> {
+    let (negative, mut rest) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    if rest.is_empty() {
+        return Err("expected a count and unit such as 5m or 1h30m".to_string());
+    }
+
+    let units = [('d', SECONDS_PER_DAY), ('h', 3_600), ('m', 60), ('s', 1)];
+    let mut next_unit = 0;
+    let mut total: i128 = 0;
+    while !rest.is_empty() {
+        let count_len = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let unit = rest[count_len..].chars().next();
+        let count: i128 = match rest[..count_len].parse() {
+            Ok(count) if unit.is_some() => count,
+            _ => return Err(format!("expected a count and unit at '{}'", rest)),
+        };
+        let unit = unit.unwrap_or_default();
+        let Some(index) = units.iter().position(|(u, _)| *u == unit) else {
+            return Err(format!("unknown unit '{}'; use d, h, m or s", unit));
+        };
+        if index < next_unit {
+            return Err("units must be in the order d, h, m, s, each at most once".to_string());
+        }
+        total = total.saturating_add(count.saturating_mul(units[index].1 as i128));
+        next_unit = index + 1;
+        rest = &rest[count_len + unit.len_utf8()..];
+    }
+
+    let total = if negative { -total } else { total };
+    i64::try_from(total).map_err(|_| "the Duration is outside the Int range of seconds".to_string())
+}
+
+/// The text of a Duration, largest unit first with zero units left out,
+/// such as `1h30m`, `-2d` or `0s`
+pub fn format_duration(seconds: i64) -> String {
+    let total = seconds.unsigned_abs();
+    let mut text = String::from(if seconds < 0 { "-" } else { "" });
+    let mut rest = total;
+    for (unit, size) in [('d', 86_400u64), ('h', 3_600), ('m', 60), ('s', 1)] {
+        let count = rest / size;
+        rest %= size;
+        if count > 0 || (total == 0 && unit == 's') {
+            text.push_str(&format!("{}{}", count, unit));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_datetime_round_trip() {
+        assert_eq!(parse_datetime("1970-01-01T00:00:00Z"), Ok(0));
+        assert_eq!(parse_datetime("2024-01-01T00:00:00Z"), Ok(1_704_067_200));
+        assert_eq!(parse_datetime("2024-01-01T02:00:00+02:00"), Ok(1_704_067_200));
+        assert_eq!(parse_datetime("1969-12-31T23:59:59Z"), Ok(-1));
+        assert_eq!(parse_datetime("0000-01-01T00:00:00Z"), Ok(MIN_DATETIME));
+        assert_eq!(parse_datetime("9999-12-31T23:59:59Z"), Ok(MAX_DATETIME));
+        for seconds in [0, -1, 951_782_400, 1_704_067_200, MIN_DATETIME, MAX_DATETIME] {
+            assert_eq!(parse_datetime(&format_datetime(seconds)), Ok(seconds));
+        }
+        assert_eq!(format_datetime(951_782_400), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_invalid_datetimes() {
+        for text in [
+            "2024-01-01",
+            "2024-01-01T00:00:00",
+            "2024-13-01T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T00:00:00.5Z",
+            "24-01-01T00:00:00Z",
+            "0000-01-01T00:00:00+01:00",
+        ] {
+            assert!(parse_datetime(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_duration_round_trip() {
+        assert_eq!(parse_duration("5m"), Ok(300));
+        assert_eq!(parse_duration("1h30m"), Ok(5_400));
+        assert_eq!(parse_duration("-2d"), Ok(-172_800));
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert_eq!(format_duration(90), "1m30s");
+        assert_eq!(format_duration(0), "0s");
+        for seconds in [0, 1, -93_784, i64::MAX, i64::MIN] {
+            assert_eq!(parse_duration(&format_duration(seconds)), Ok(seconds));
+        }
+        for text in ["", "-", "5", "m", "5w", "30m1h", "1h1h", "9999999999999999999d"] {
+            assert!(parse_duration(text).is_err(), "{}", text);
+        }
+    }
+}
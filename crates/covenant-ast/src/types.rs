@@ -1,16 +1,17 @@
 //! Type representations in Covenant
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::Span;
 
 /// A type expression
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Type {
     pub kind: TypeKind,
     pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum TypeKind {
     /// Named type: `User`, `Int`, `String`
     Named(TypePath),
@@ -38,7 +39,7 @@ pub enum TypeKind {
 }
 
 /// A path to a type: `User` or `module::User`
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TypePath {
     pub segments: Vec<String>,
     pub generics: Vec<Type>,
@@ -60,7 +61,7 @@ impl TypePath {
 }
 
 /// A field in a struct type
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FieldType {
     pub name: String,
     pub ty: Type,
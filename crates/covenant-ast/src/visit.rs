@@ -0,0 +1,769 @@
+//! Snippet traversal
+//!
+//! `Visitor` walks a snippet by reference and `VisitorMut` by mutable
+//! reference. Every `visit_*` method defaults to the matching `walk_*`
+//! function, which visits the node's children; override a method to act on
+//! that node, and call the walk function from it to keep descending.
+//!
+//! Reads of a binding (`InputSource::Var`, the `on` of a match, the
+//! collection of a for, ...) all reach `visit_binding_ref`, so an analysis
+//! that only cares about names never has to match on step kinds.
+
+use crate::*;
+
+/// Read-only traversal of a snippet
+pub trait Visitor {
+    fn visit_snippet(&mut self, snippet: &Snippet) {
+        walk_snippet(self, snippet);
+    }
+
+    fn visit_section(&mut self, section: &Section) {
+        walk_section(self, section);
+    }
+
+    fn visit_signature(&mut self, signature: &SignatureKind) {
+        walk_signature(self, signature);
+    }
+
+    fn visit_function_signature(&mut self, function: &FunctionSignature) {
+        walk_function_signature(self, function);
+    }
+
+    /// A block of steps: a body, a branch, a loop body, a test, ...
+    fn visit_steps(&mut self, steps: &[Step]) {
+        walk_steps(self, steps);
+    }
+
+    fn visit_step(&mut self, step: &Step) {
+        walk_step(self, step);
+    }
+
+    fn visit_call(&mut self, call: &CallStep) {
+        walk_call(self, call);
+    }
+
+    fn visit_condition(&mut self, condition: &Condition) {
+        walk_condition(self, condition);
+    }
+
+    fn visit_input_source(&mut self, source: &InputSource) {
+        walk_input_source(self, source);
+    }
+
+    fn visit_type(&mut self, ty: &Type) {
+        walk_type(self, ty);
+    }
+
+    /// A read of the binding `name`
+    fn visit_binding_ref(&mut self, _name: &str) {}
+}
+
+pub fn walk_snippet<V: Visitor + ?Sized>(visitor: &mut V, snippet: &Snippet) {
+    for section in &snippet.sections {
+        visitor.visit_section(section);
+    }
+}
+
+pub fn walk_section<V: Visitor + ?Sized>(visitor: &mut V, section: &Section) {
+    match section {
+        Section::Signature(s) => visitor.visit_signature(&s.kind),
+        Section::Body(s) => visitor.visit_steps(&s.steps),
+        Section::Tests(s) => {
+            for test in &s.tests {
+                visitor.visit_steps(&test.steps);
+            }
+        }
+        Section::Schema(s) => {
+            for table in &s.tables {
+                for field in &table.fields {
+                    visitor.visit_type(&field.ty);
+                }
+            }
+        }
+        Section::Types(s) => {
+            for decl in &s.types {
+                visitor.visit_type(&decl.ty);
+                if let Some(definition) = &decl.definition {
+                    visitor.visit_signature(definition);
+                }
+            }
+        }
+        Section::Invariant(s) => {
+            for check in &s.checks {
+                for input in &check.inputs {
+                    visitor.visit_input_source(&input.source);
+                }
+            }
+        }
+        Section::Effects(_)
+        | Section::Requires(_)
+        | Section::Metadata(_)
+        | Section::Relations(_)
+        | Section::Content(_)
+        | Section::Tools(_)
+        | Section::Contains(_)
+        | Section::Subscribe(_) => {}
+    }
+}
+
+pub fn walk_signature<V: Visitor + ?Sized>(visitor: &mut V, signature: &SignatureKind) {
+    match signature {
+        SignatureKind::Function(f) => visitor.visit_function_signature(f),
+        SignatureKind::Struct(s) => {
+            for field in &s.fields {
+                visitor.visit_type(&field.ty);
+            }
+        }
+        SignatureKind::Enum(e) => {
+            for field in e.variants.iter().flat_map(|v| v.fields.iter().flatten()) {
+                visitor.visit_type(&field.ty);
+            }
+        }
+        SignatureKind::Const(c) => visitor.visit_type(&c.ty),
+        SignatureKind::Alias(a) => visitor.visit_type(&a.ty),
+        SignatureKind::Interface(i) => {
+            for method in &i.methods {
+                visitor.visit_function_signature(method);
+            }
+        }
+    }
+}
+
+pub fn walk_function_signature<V: Visitor + ?Sized>(visitor: &mut V, function: &FunctionSignature) {
+    for param in &function.params {
+        visitor.visit_type(&param.ty);
+    }
+    if let Some(returns) = &function.returns {
+        walk_return_type(visitor, returns);
+    }
+}
+
+fn walk_return_type<V: Visitor + ?Sized>(visitor: &mut V, returns: &ReturnType) {
+    match returns {
+        ReturnType::Single { ty, .. } => visitor.visit_type(ty),
+        ReturnType::Collection { of } => visitor.visit_type(of),
+        ReturnType::Union { types } => {
+            for member in types {
+                visitor.visit_type(&member.ty);
+            }
+        }
+    }
+}
+
+pub fn walk_steps<V: Visitor + ?Sized>(visitor: &mut V, steps: &[Step]) {
+    for step in steps {
+        visitor.visit_step(step);
+    }
+}
+
+pub fn walk_step<V: Visitor + ?Sized>(visitor: &mut V, step: &Step) {
+    match &step.kind {
+        StepKind::Compute(s) => {
+            for input in &s.inputs {
+                visitor.visit_input_source(&input.source);
+            }
+        }
+        StepKind::Call(s) => visitor.visit_call(s),
+        StepKind::Pipeline(s) => {
+            visitor.visit_input_source(&s.from);
+            for stage in &s.stages {
+                visitor.visit_call(&stage.call);
+            }
+        }
+        StepKind::Query(s) => match &s.content {
+            QueryContent::Covenant(q) => {
+                if let Some(condition) = &q.where_clause {
+                    visitor.visit_condition(condition);
+                }
+            }
+            QueryContent::Dialect(q) => {
+                for param in &q.params {
+                    visitor.visit_binding_ref(&param.from);
+                }
+                walk_return_type(visitor, &q.returns);
+            }
+        },
+        StepKind::Bind(s) => match &s.source {
+            BindSource::Var(name) | BindSource::Field { of: name, .. } => {
+                visitor.visit_binding_ref(name);
+            }
+            BindSource::Lit(_) => {}
+            BindSource::Variant(variant) => walk_assignments(visitor, &variant.fields),
+        },
+        StepKind::Return(s) => match &s.value {
+            ReturnValue::Var(name) => visitor.visit_binding_ref(name),
+            ReturnValue::Lit(_) => {}
+            ReturnValue::Struct(construction) => walk_construction(visitor, construction),
+            ReturnValue::Variant(variant) => walk_assignments(visitor, &variant.fields),
+        },
+        StepKind::Raise(s) => walk_assignments(visitor, &s.variant.fields),
+        StepKind::If(s) => {
+            visitor.visit_input_source(&s.condition);
+            visitor.visit_steps(&s.then_steps);
+            if let Some(else_steps) = &s.else_steps {
+                visitor.visit_steps(else_steps);
+            }
+        }
+        StepKind::Match(s) => {
+            visitor.visit_binding_ref(&s.on);
+            for case in &s.cases {
+                if let MatchPattern::Type { ty } = &case.pattern {
+                    visitor.visit_type(ty);
+                }
+                visitor.visit_steps(&case.steps);
+            }
+        }
+        StepKind::For(s) => {
+            visitor.visit_binding_ref(&s.collection);
+            visitor.visit_steps(&s.steps);
+        }
+        StepKind::Break(_) | StepKind::Continue(_) => {}
+        StepKind::Lambda(s) => {
+            for param in &s.params {
+                visitor.visit_type(&param.ty);
+            }
+            if let Some(returns) = &s.returns {
+                visitor.visit_type(returns);
+            }
+            visitor.visit_steps(&s.steps);
+        }
+        StepKind::Using(s) => {
+            visitor.visit_call(&s.acquire);
+            visitor.visit_steps(&s.steps);
+        }
+        StepKind::Assert(s) => {
+            for input in &s.inputs {
+                visitor.visit_input_source(&input.source);
+            }
+        }
+        StepKind::Insert(s) => walk_assignments(visitor, &s.assignments),
+        StepKind::Update(s) => {
+            walk_assignments(visitor, &s.assignments);
+            if let Some(condition) = &s.where_clause {
+                visitor.visit_condition(condition);
+            }
+        }
+        StepKind::Delete(s) => {
+            if let Some(condition) = &s.where_clause {
+                visitor.visit_condition(condition);
+            }
+        }
+        StepKind::Transaction(s) => visitor.visit_steps(&s.steps),
+        StepKind::Traverse(s) => visitor.visit_binding_ref(&s.from),
+        StepKind::Construct(s) => walk_construction(visitor, s),
+        StepKind::Parallel(s) => {
+            for branch in &s.branches {
+                visitor.visit_steps(&branch.steps);
+            }
+        }
+        StepKind::Race(s) => {
+            for branch in &s.branches {
+                visitor.visit_steps(&branch.steps);
+            }
+        }
+    }
+}
+
+fn walk_construction<V: Visitor + ?Sized>(visitor: &mut V, construction: &StructConstruction) {
+    visitor.visit_type(&construction.ty);
+    if let Some(base) = &construction.from_base {
+        visitor.visit_binding_ref(base);
+    }
+    walk_assignments(visitor, &construction.fields);
+}
+
+fn walk_assignments<V: Visitor + ?Sized>(visitor: &mut V, fields: &[FieldAssignment]) {
+    for field in fields {
+        visitor.visit_input_source(&field.value);
+    }
+}
+
+pub fn walk_call<V: Visitor + ?Sized>(visitor: &mut V, call: &CallStep) {
+    for ty in &call.type_args {
+        visitor.visit_type(ty);
+    }
+    for arg in &call.args {
+        visitor.visit_input_source(&arg.source);
+    }
+    if let Some(handle) = &call.handle {
+        for case in &handle.cases {
+            visitor.visit_steps(&case.steps);
+        }
+    }
+}
+
+pub fn walk_condition<V: Visitor + ?Sized>(visitor: &mut V, condition: &Condition) {
+    match &condition.kind {
+        ConditionKind::Equals { value, .. }
+        | ConditionKind::Contains { value, .. }
+        | ConditionKind::NotEquals { value, .. } => visitor.visit_input_source(value),
+        ConditionKind::And(left, right) | ConditionKind::Or(left, right) => {
+            visitor.visit_condition(left);
+            visitor.visit_condition(right);
+        }
+        ConditionKind::RelTo { .. } | ConditionKind::RelFrom { .. } => {}
+    }
+}
+
+pub fn walk_input_source<V: Visitor + ?Sized>(visitor: &mut V, source: &InputSource) {
+    match source {
+        InputSource::Var(name) | InputSource::Field { of: name, .. } => visitor.visit_binding_ref(name),
+        InputSource::Lit(_) => {}
+    }
+}
+
+pub fn walk_type<V: Visitor + ?Sized>(visitor: &mut V, ty: &Type) {
+    match &ty.kind {
+        TypeKind::Named(path) => {
+            for generic in &path.generics {
+                visitor.visit_type(generic);
+            }
+        }
+        TypeKind::Optional(inner) | TypeKind::List(inner) => visitor.visit_type(inner),
+        TypeKind::Union(types) | TypeKind::Tuple(types) => {
+            for member in types {
+                visitor.visit_type(member);
+            }
+        }
+        TypeKind::Function { params, ret } => {
+            for param in params {
+                visitor.visit_type(param);
+            }
+            visitor.visit_type(ret);
+        }
+        TypeKind::Struct(fields) => {
+            for field in fields {
+                visitor.visit_type(&field.ty);
+            }
+        }
+    }
+}
+
+/// In-place traversal of a snippet
+///
+/// Blocks are visited as `&mut Vec<Step>`, so a pass can remove, insert or
+/// reorder the steps of any block it reaches.
+pub trait VisitorMut {
+    fn visit_snippet_mut(&mut self, snippet: &mut Snippet) {
+        walk_snippet_mut(self, snippet);
+    }
+
+    fn visit_section_mut(&mut self, section: &mut Section) {
+        walk_section_mut(self, section);
+    }
+
+    fn visit_signature_mut(&mut self, signature: &mut SignatureKind) {
+        walk_signature_mut(self, signature);
+    }
+
+    fn visit_function_signature_mut(&mut self, function: &mut FunctionSignature) {
+        walk_function_signature_mut(self, function);
+    }
+
+    fn visit_steps_mut(&mut self, steps: &mut Vec<Step>) {
+        walk_steps_mut(self, steps);
+    }
+
+    fn visit_step_mut(&mut self, step: &mut Step) {
+        walk_step_mut(self, step);
+    }
+
+    fn visit_call_mut(&mut self, call: &mut CallStep) {
+        walk_call_mut(self, call);
+    }
+
+    fn visit_condition_mut(&mut self, condition: &mut Condition) {
+        walk_condition_mut(self, condition);
+    }
+
+    fn visit_input_source_mut(&mut self, source: &mut InputSource) {
+        walk_input_source_mut(self, source);
+    }
+
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        walk_type_mut(self, ty);
+    }
+
+    /// A read of the binding `name`
+    fn visit_binding_ref_mut(&mut self, _name: &mut String) {}
+}
+
+pub fn walk_snippet_mut<V: VisitorMut + ?Sized>(visitor: &mut V, snippet: &mut Snippet) {
+    for section in &mut snippet.sections {
+        visitor.visit_section_mut(section);
+    }
+}
+
+pub fn walk_section_mut<V: VisitorMut + ?Sized>(visitor: &mut V, section: &mut Section) {
+    match section {
+        Section::Signature(s) => visitor.visit_signature_mut(&mut s.kind),
+        Section::Body(s) => visitor.visit_steps_mut(&mut s.steps),
+        Section::Tests(s) => {
+            for test in &mut s.tests {
+                visitor.visit_steps_mut(&mut test.steps);
+            }
+        }
+        Section::Schema(s) => {
+            for table in &mut s.tables {
+                for field in &mut table.fields {
+                    visitor.visit_type_mut(&mut field.ty);
+                }
+            }
+        }
+        Section::Types(s) => {
+            for decl in &mut s.types {
+                visitor.visit_type_mut(&mut decl.ty);
+                if let Some(definition) = &mut decl.definition {
+                    visitor.visit_signature_mut(definition);
+                }
+            }
+        }
+        Section::Invariant(s) => {
+            for check in &mut s.checks {
+                for input in &mut check.inputs {
+                    visitor.visit_input_source_mut(&mut input.source);
+                }
+            }
+        }
+        Section::Effects(_)
+        | Section::Requires(_)
+        | Section::Metadata(_)
+        | Section::Relations(_)
+        | Section::Content(_)
+        | Section::Tools(_)
+        | Section::Contains(_)
+        | Section::Subscribe(_) => {}
+    }
+}
+
+pub fn walk_signature_mut<V: VisitorMut + ?Sized>(visitor: &mut V, signature: &mut SignatureKind) {
+    match signature {
+        SignatureKind::Function(f) => visitor.visit_function_signature_mut(f),
+        SignatureKind::Struct(s) => {
+            for field in &mut s.fields {
+                visitor.visit_type_mut(&mut field.ty);
+            }
+        }
+        SignatureKind::Enum(e) => {
+            for field in e.variants.iter_mut().flat_map(|v| v.fields.iter_mut().flatten()) {
+                visitor.visit_type_mut(&mut field.ty);
+            }
+        }
+        SignatureKind::Const(c) => visitor.visit_type_mut(&mut c.ty),
+        SignatureKind::Alias(a) => visitor.visit_type_mut(&mut a.ty),
+        SignatureKind::Interface(i) => {
+            for method in &mut i.methods {
+                visitor.visit_function_signature_mut(method);
+            }
+        }
+    }
+}
+
+pub fn walk_function_signature_mut<V: VisitorMut + ?Sized>(visitor: &mut V, function: &mut FunctionSignature) {
+    for param in &mut function.params {
+        visitor.visit_type_mut(&mut param.ty);
+    }
+    if let Some(returns) = &mut function.returns {
+        walk_return_type_mut(visitor, returns);
+    }
+}
+
+fn walk_return_type_mut<V: VisitorMut + ?Sized>(visitor: &mut V, returns: &mut ReturnType) {
+    match returns {
+        ReturnType::Single { ty, .. } => visitor.visit_type_mut(ty),
+        ReturnType::Collection { of } => visitor.visit_type_mut(of),
+        ReturnType::Union { types } => {
+            for member in types {
+                visitor.visit_type_mut(&mut member.ty);
+            }
+        }
+    }
+}
+
+pub fn walk_steps_mut<V: VisitorMut + ?Sized>(visitor: &mut V, steps: &mut Vec<Step>) {
+    for step in steps {
+        visitor.visit_step_mut(step);
+    }
+}
+
+pub fn walk_step_mut<V: VisitorMut + ?Sized>(visitor: &mut V, step: &mut Step) {
+    match &mut step.kind {
+        StepKind::Compute(s) => {
+            for input in &mut s.inputs {
+                visitor.visit_input_source_mut(&mut input.source);
+            }
+        }
+        StepKind::Call(s) => visitor.visit_call_mut(s),
+        StepKind::Pipeline(s) => {
+            visitor.visit_input_source_mut(&mut s.from);
+            for stage in &mut s.stages {
+                visitor.visit_call_mut(&mut stage.call);
+            }
+        }
+        StepKind::Query(s) => match &mut s.content {
+            QueryContent::Covenant(q) => {
+                if let Some(condition) = &mut q.where_clause {
+                    visitor.visit_condition_mut(condition);
+                }
+            }
+            QueryContent::Dialect(q) => {
+                for param in &mut q.params {
+                    visitor.visit_binding_ref_mut(&mut param.from);
+                }
+                walk_return_type_mut(visitor, &mut q.returns);
+            }
+        },
+        StepKind::Bind(s) => match &mut s.source {
+            BindSource::Var(name) | BindSource::Field { of: name, .. } => {
+                visitor.visit_binding_ref_mut(name);
+            }
+            BindSource::Lit(_) => {}
+            BindSource::Variant(variant) => walk_assignments_mut(visitor, &mut variant.fields),
+        },
+        StepKind::Return(s) => match &mut s.value {
+            ReturnValue::Var(name) => visitor.visit_binding_ref_mut(name),
+            ReturnValue::Lit(_) => {}
+            ReturnValue::Struct(construction) => walk_construction_mut(visitor, construction),
+            ReturnValue::Variant(variant) => walk_assignments_mut(visitor, &mut variant.fields),
+        },
+        StepKind::Raise(s) => walk_assignments_mut(visitor, &mut s.variant.fields),
+        StepKind::If(s) => {
+            visitor.visit_input_source_mut(&mut s.condition);
+            visitor.visit_steps_mut(&mut s.then_steps);
+            if let Some(else_steps) = &mut s.else_steps {
+                visitor.visit_steps_mut(else_steps);
+            }
+        }
+        StepKind::Match(s) => {
+            visitor.visit_binding_ref_mut(&mut s.on);
+            for case in &mut s.cases {
+                if let MatchPattern::Type { ty } = &mut case.pattern {
+                    visitor.visit_type_mut(ty);
+                }
+                visitor.visit_steps_mut(&mut case.steps);
+            }
+        }
+        StepKind::For(s) => {
+            visitor.visit_binding_ref_mut(&mut s.collection);
+            visitor.visit_steps_mut(&mut s.steps);
+        }
+        StepKind::Break(_) | StepKind::Continue(_) => {}
+        StepKind::Lambda(s) => {
+            for param in &mut s.params {
+                visitor.visit_type_mut(&mut param.ty);
+            }
+            if let Some(returns) = &mut s.returns {
+                visitor.visit_type_mut(returns);
+            }
+            visitor.visit_steps_mut(&mut s.steps);
+        }
+        StepKind::Using(s) => {
+            visitor.visit_call_mut(&mut s.acquire);
+            visitor.visit_steps_mut(&mut s.steps);
+        }
+        StepKind::Assert(s) => {
+            for input in &mut s.inputs {
+                visitor.visit_input_source_mut(&mut input.source);
+            }
+        }
+        StepKind::Insert(s) => walk_assignments_mut(visitor, &mut s.assignments),
+        StepKind::Update(s) => {
+            walk_assignments_mut(visitor, &mut s.assignments);
+            if let Some(condition) = &mut s.where_clause {
+                visitor.visit_condition_mut(condition);
+            }
+        }
+        StepKind::Delete(s) => {
+            if let Some(condition) = &mut s.where_clause {
+                visitor.visit_condition_mut(condition);
+            }
+        }
+        StepKind::Transaction(s) => visitor.visit_steps_mut(&mut s.steps),
+        StepKind::Traverse(s) => visitor.visit_binding_ref_mut(&mut s.from),
+        StepKind::Construct(s) => walk_construction_mut(visitor, s),
+        StepKind::Parallel(s) => {
+            for branch in &mut s.branches {
+                visitor.visit_steps_mut(&mut branch.steps);
+            }
+        }
+        StepKind::Race(s) => {
+            for branch in &mut s.branches {
+                visitor.visit_steps_mut(&mut branch.steps);
+            }
+        }
+    }
+}
+
+fn walk_construction_mut<V: VisitorMut + ?Sized>(visitor: &mut V, construction: &mut StructConstruction) {
+    visitor.visit_type_mut(&mut construction.ty);
+    if let Some(base) = &mut construction.from_base {
+        visitor.visit_binding_ref_mut(base);
+    }
+    walk_assignments_mut(visitor, &mut construction.fields);
+}
+
+fn walk_assignments_mut<V: VisitorMut + ?Sized>(visitor: &mut V, fields: &mut [FieldAssignment]) {
+    for field in fields {
+        visitor.visit_input_source_mut(&mut field.value);
+    }
+}
+
+pub fn walk_call_mut<V: VisitorMut + ?Sized>(visitor: &mut V, call: &mut CallStep) {
+    for ty in &mut call.type_args {
+        visitor.visit_type_mut(ty);
+    }
+    for arg in &mut call.args {
+        visitor.visit_input_source_mut(&mut arg.source);
+    }
+    if let Some(handle) = &mut call.handle {
+        for case in &mut handle.cases {
+            visitor.visit_steps_mut(&mut case.steps);
+        }
+    }
+}
+
+pub fn walk_condition_mut<V: VisitorMut + ?Sized>(visitor: &mut V, condition: &mut Condition) {
+    match &mut condition.kind {
+        ConditionKind::Equals { value, .. }
+        | ConditionKind::Contains { value, .. }
+        | ConditionKind::NotEquals { value, .. } => visitor.visit_input_source_mut(value),
+        ConditionKind::And(left, right) | ConditionKind::Or(left, right) => {
+            visitor.visit_condition_mut(left);
+            visitor.visit_condition_mut(right);
+        }
+        ConditionKind::RelTo { .. } | ConditionKind::RelFrom { .. } => {}
+    }
+}
+
+pub fn walk_input_source_mut<V: VisitorMut + ?Sized>(visitor: &mut V, source: &mut InputSource) {
+    match source {
+        InputSource::Var(name) | InputSource::Field { of: name, .. } => visitor.visit_binding_ref_mut(name),
+        InputSource::Lit(_) => {}
+    }
+}
+
+pub fn walk_type_mut<V: VisitorMut + ?Sized>(visitor: &mut V, ty: &mut Type) {
+    match &mut ty.kind {
+        TypeKind::Named(path) => {
+            for generic in &mut path.generics {
+                visitor.visit_type_mut(generic);
+            }
+        }
+        TypeKind::Optional(inner) | TypeKind::List(inner) => visitor.visit_type_mut(inner),
+        TypeKind::Union(types) | TypeKind::Tuple(types) => {
+            for member in types {
+                visitor.visit_type_mut(member);
+            }
+        }
+        TypeKind::Function { params, ret } => {
+            for param in params {
+                visitor.visit_type_mut(param);
+            }
+            visitor.visit_type_mut(ret);
+        }
+        TypeKind::Struct(fields) => {
+            for field in fields {
+                visitor.visit_type_mut(&mut field.ty);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(id: &str, kind: StepKind) -> Step {
+        Step { id: id.into(), kind, output_binding: "_".into(), doc: None, node_id: None, span: Span::dummy() }
+    }
+
+    fn var(name: &str) -> InputSource {
+        InputSource::Var(name.into())
+    }
+
+    /// `if cond` over a compute in one branch and a parallel branch in the other
+    fn nested_steps() -> Vec<Step> {
+        let compute = step(
+            "s1.1",
+            StepKind::Compute(ComputeStep {
+                op: Operation::Add,
+                inputs: vec![
+                    Input { source: var("a"), span: Span::dummy() },
+                    Input { source: InputSource::Field { of: "b".into(), field: "x".into() }, span: Span::dummy() },
+                ],
+                span: Span::dummy(),
+            }),
+        );
+        let ret = step(
+            "s1.2.1",
+            StepKind::Return(ReturnStep { value: ReturnValue::Var("c".into()), span: Span::dummy() }),
+        );
+        let parallel = step(
+            "s1.2",
+            StepKind::Parallel(ParallelStep {
+                branches: vec![Branch { id: "b1".into(), steps: vec![ret], span: Span::dummy() }],
+                on_error: None,
+                timeout: None,
+                span: Span::dummy(),
+            }),
+        );
+        vec![step(
+            "s1",
+            StepKind::If(IfStep {
+                condition: var("cond"),
+                then_steps: vec![compute],
+                else_steps: Some(vec![parallel]),
+                span: Span::dummy(),
+            }),
+        )]
+    }
+
+    #[derive(Default)]
+    struct Collect {
+        steps: Vec<String>,
+        refs: Vec<String>,
+    }
+
+    impl Visitor for Collect {
+        fn visit_step(&mut self, step: &Step) {
+            self.steps.push(step.id.clone());
+            walk_step(self, step);
+        }
+
+        fn visit_binding_ref(&mut self, name: &str) {
+            self.refs.push(name.to_string());
+        }
+    }
+
+    #[test]
+    fn test_visitor_reaches_nested_steps_and_refs() {
+        let mut collect = Collect::default();
+        collect.visit_steps(&nested_steps());
+        assert_eq!(collect.steps, ["s1", "s1.1", "s1.2", "s1.2.1"]);
+        assert_eq!(collect.refs, ["cond", "a", "b", "c"]);
+    }
+
+    struct Rename;
+
+    impl VisitorMut for Rename {
+        fn visit_steps_mut(&mut self, steps: &mut Vec<Step>) {
+            steps.retain(|s| !matches!(s.kind, StepKind::Parallel(_)));
+            walk_steps_mut(self, steps);
+        }
+
+        fn visit_binding_ref_mut(&mut self, name: &mut String) {
+            name.insert_str(0, "renamed_");
+        }
+    }
+
+    #[test]
+    fn test_visitor_mut_edits_blocks_and_refs() {
+        let mut steps = nested_steps();
+        Rename.visit_steps_mut(&mut steps);
+
+        let mut collect = Collect::default();
+        collect.visit_steps(&steps);
+        assert_eq!(collect.steps, ["s1", "s1.1"]);
+        assert_eq!(collect.refs, ["renamed_cond", "renamed_a", "renamed_b"]);
+    }
+}
@@ -0,0 +1,46 @@
+//! Backward compatibility of serialized artifacts
+//!
+//! The fixtures were written by releases before schema versioning (version 0)
+//! and must keep loading through the migrations in `covenant_ast::schema`.
+
+use covenant_ast::{
+    migrate_program_json, migrate_symbol_metadata_json, program_from_json, Program,
+    AST_SCHEMA_VERSION, SYMBOL_METADATA_VERSION,
+};
+use serde_json::Value;
+
+const PROGRAM_V0: &str = include_str!("fixtures/program_v0.json");
+const SYMBOL_METADATA_V0: &str = include_str!("fixtures/symbol_metadata_v0.json");
+
+#[test]
+fn test_program_v0_loads() {
+    let program = program_from_json(PROGRAM_V0).expect("version 0 program should load");
+    let Program::Snippets { snippets, .. } = &program else {
+        panic!("Expected Snippets program");
+    };
+    assert!(snippets.iter().any(|s| s.id == "json.Json"));
+
+    // Loading must not lose anything the old release wrote
+    let original: Value = serde_json::from_str(PROGRAM_V0).unwrap();
+    assert_eq!(serde_json::to_value(&program).unwrap(), original);
+}
+
+#[test]
+fn test_program_v0_migrates_to_current_version() {
+    let original: Value = serde_json::from_str(PROGRAM_V0).unwrap();
+    let migrated = migrate_program_json(original.clone()).unwrap();
+    assert_eq!(migrated["schema_version"], AST_SCHEMA_VERSION);
+    assert_eq!(migrated["program"], original);
+
+    // Migrating an already current artifact is a no-op
+    assert_eq!(migrate_program_json(migrated.clone()).unwrap(), migrated);
+}
+
+#[test]
+fn test_symbol_metadata_v0_migrates_to_current_version() {
+    let original: Value = serde_json::from_str(SYMBOL_METADATA_V0).unwrap();
+    let migrated = migrate_symbol_metadata_json(original.clone()).unwrap();
+    assert_eq!(migrated["schema_version"], SYMBOL_METADATA_VERSION);
+    assert_eq!(migrated["symbols"], original);
+    assert_eq!(migrated["symbols"][0]["id"], "http.post");
+}
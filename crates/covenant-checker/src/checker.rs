@@ -34,6 +34,7 @@ impl Checker {
             Ok(CheckResult {
                 symbols: std::mem::take(&mut self.symbols),
                 effects: std::mem::take(&mut self.effects),
+                warnings: Vec::new(),
             })
         } else {
             Err(std::mem::take(&mut self.errors))
@@ -250,13 +251,7 @@ impl Checker {
 
     fn infer_expr(&mut self, expr: &Expr) -> ResolvedType {
         match &expr.kind {
-            ExprKind::Literal(lit) => match lit {
-                Literal::Int(_) => ResolvedType::Int,
-                Literal::Float(_) => ResolvedType::Float,
-                Literal::String(_) => ResolvedType::String,
-                Literal::Bool(_) => ResolvedType::Bool,
-                Literal::None => ResolvedType::None,
-            },
+            ExprKind::Literal(lit) => ResolvedType::of_literal(lit),
             ExprKind::Ident(name) => {
                 if let Some(symbol) = self.symbols.lookup(name) {
                     symbol.ty.clone()
@@ -435,6 +430,9 @@ impl Checker {
                     "Char" => ResolvedType::Char,
                     "Bytes" => ResolvedType::Bytes,
                     "DateTime" => ResolvedType::DateTime,
+                    "Duration" => ResolvedType::Duration,
+                    "Decimal" => ResolvedType::Decimal,
+                    "BigInt" => ResolvedType::BigInt,
                     _ => ResolvedType::Named {
                         name: name.to_string(),
                         id: SymbolId(0),
@@ -482,6 +480,9 @@ impl Checker {
             (ResolvedType::Char, ResolvedType::Char) => true,
             (ResolvedType::Bytes, ResolvedType::Bytes) => true,
             (ResolvedType::DateTime, ResolvedType::DateTime) => true,
+            (ResolvedType::Duration, ResolvedType::Duration) => true,
+            (ResolvedType::Decimal, ResolvedType::Decimal) => true,
+            (ResolvedType::BigInt, ResolvedType::BigInt) => true,
             (ResolvedType::None, ResolvedType::None) => true,
 
             // Optional types
@@ -495,6 +496,9 @@ impl Checker {
             // Set types
             (ResolvedType::Set(e), ResolvedType::Set(f)) => self.types_compatible(e, f),
 
+            // Array types, which must also agree in length
+            (ResolvedType::Array(e, n), ResolvedType::Array(f, m)) => n == m && self.types_compatible(e, f),
+
             // Union types - value must be compatible with at least one member
             (ResolvedType::Union(members), found) => {
                 members.iter().any(|m| self.types_compatible(m, found))
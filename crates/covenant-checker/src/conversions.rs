@@ -0,0 +1,85 @@
+//! Numeric conversion warnings
+//!
+//! `to_int` on a Float drops the fraction by truncating toward zero, which
+//! is rarely what was meant and is easy to miss in generated code. The
+//! rounding ops `floor`, `ceil`, `round` and `trunc` convert a Float to Int
+//! with the rounding mode spelled out, so only `to_int` is flagged.
+//! `to_float` of an Int literal is flagged when the literal has no exact
+//! Float representation. These are warnings rather than errors: the
+//! conversions are well defined, just lossy.
+
+use covenant_ast::Span;
+
+/// Which conversion may lose information
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionKind {
+    /// `to_int` of a Float that may have a fraction (W-CONV-001)
+    Truncating,
+    /// `to_float` of an Int literal with no exact Float value (W-CONV-002)
+    PrecisionLoss,
+}
+
+/// A lossy numeric conversion in a compute step
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionWarning {
+    /// Which conversion was flagged
+    pub kind: ConversionKind,
+    /// Snippet containing the step
+    pub snippet: String,
+    /// ID of the compute step
+    pub step: String,
+    /// Source span of the step
+    pub span: Span,
+}
+
+impl ConversionWarning {
+    /// Warning code for machine-readable output
+    pub fn code(&self) -> &'static str {
+        match self.kind {
+            ConversionKind::Truncating => "W-CONV-001",
+            ConversionKind::PrecisionLoss => "W-CONV-002",
+        }
+    }
+
+    /// Human-readable description of the problem
+    pub fn message(&self) -> String {
+        match self.kind {
+            ConversionKind::Truncating => format!(
+                "step '{}' in `{}` converts a Float to Int with to_int, which truncates any fraction",
+                self.step, self.snippet
+            ),
+            ConversionKind::PrecisionLoss => format!(
+                "step '{}' in `{}` converts an Int beyond 2^53 to Float, which rounds it",
+                self.step, self.snippet
+            ),
+        }
+    }
+
+    /// Suggested fix
+    pub fn suggestion(&self) -> &'static str {
+        match self.kind {
+            ConversionKind::Truncating => {
+                "use op=floor, op=ceil, op=round or op=trunc to choose how the fraction is dropped"
+            }
+            ConversionKind::PrecisionLoss => "use a Float literal, or keep the value as Int",
+        }
+    }
+}
+
+/// Whether `to_int` of the Float literal `f` drops a fraction or overflows
+pub(crate) fn to_int_truncates(f: f64) -> bool {
+    float_to_int(f).is_none_or(|n| n as f64 != f)
+}
+
+/// Whether `to_float` of the Int literal `n` rounds it
+pub(crate) fn to_float_rounds(n: i64) -> bool {
+    n as f64 as i128 != n as i128
+}
+
+/// Truncate `f` toward zero, or `None` if the result is out of Int range or
+/// `f` is NaN
+fn float_to_int(f: f64) -> Option<i64> {
+    let t = f.trunc();
+    // i64::MIN is exactly -2^63; 2^63 itself is out of range
+    (t >= i64::MIN as f64 && t < -(i64::MIN as f64)).then_some(t as i64)
+}
@@ -34,6 +34,8 @@ pub enum DiagnosticKind {
     Type(TypeDiagnostic),
     /// Symbol-related error
     Symbol(SymbolDiagnostic),
+    /// Declared effect missing from the effect registry
+    UnknownEffect(UnknownEffectDiagnostic),
 }
 
 /// Effect-specific diagnostic information
@@ -74,6 +76,15 @@ pub struct SymbolDiagnostic {
     pub context: String,
 }
 
+/// Unknown-effect diagnostic information
+#[derive(Debug, Clone)]
+pub struct UnknownEffectDiagnostic {
+    /// The symbol declaring the effect
+    pub function: String,
+    /// The unregistered effect name
+    pub effect: String,
+}
+
 /// A suggested fix for an error
 #[derive(Debug, Clone)]
 pub enum FixSuggestion {
@@ -109,6 +120,15 @@ pub enum FixSuggestion {
         /// Snippet ID
         snippet_id: String,
     },
+    /// Replace a misspelled effect with a registered one
+    RenameEffect {
+        /// The declared, unknown effect
+        from: String,
+        /// The registered effect to use instead
+        to: String,
+        /// Location of the effect declaration
+        location: Span,
+    },
 }
 
 /// A related source location with context
@@ -201,6 +221,9 @@ impl Diagnostic {
             DiagnosticKind::Symbol(s) => {
                 format!("undefined symbol: `{}` ({})", s.name, s.context)
             }
+            DiagnosticKind::UnknownEffect(u) => {
+                format!("`{}` declares unknown effect `{}`", u.function, u.effect)
+            }
         }
     }
 
@@ -258,13 +281,16 @@ impl FixSuggestion {
                     effects.join(", ")
                 )
             }
+            FixSuggestion::RenameEffect { from, to, .. } => {
+                format!("Replace `effect {}` with `effect {}`", from, to)
+            }
         }
     }
 
     /// Get the code snippet for this fix (if applicable)
     pub fn code_snippet(&self) -> Option<String> {
         match self {
-            FixSuggestion::AddEffect { effect, .. } => {
+            FixSuggestion::AddEffect { effect, .. } | FixSuggestion::RenameEffect { to: effect, .. } => {
                 Some(format!("effect {}", effect))
             }
             FixSuggestion::DeclareEffectsSection { effects, .. } => {
@@ -364,6 +390,20 @@ impl From<CheckError> for Diagnostic {
                     ),
                 )
             }
+            CheckError::UnsupportedTypeMatch { on } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: "variant or wildcard arms".to_string(),
+                        found: format!("a `case type=` arm on {}", on),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-016",
+                    format!(
+                        "Cannot match `{}` by type: compiled union values do not record which member they hold. Match on an enum's variants instead.",
+                        on
+                    ),
+                )
+            }
             CheckError::UnknownQueryTarget { target } => {
                 Diagnostic::new(
                     DiagnosticKind::Symbol(SymbolDiagnostic {
@@ -434,6 +474,381 @@ impl From<CheckError> for Diagnostic {
                     ),
                 )
             }
+            CheckError::Cancelled => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: String::new(),
+                        context: "checking cancelled".to_string(),
+                    }),
+                    Span::dummy(),
+                    "E-CANCEL-001",
+                    "Type checking was cancelled before it completed; no results are available.",
+                )
+            }
+            CheckError::NestingTooDeep { snippet, depth, limit, span } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: snippet.clone(),
+                        context: format!("steps nested {} deep", depth),
+                    }),
+                    span,
+                    "E-LIMIT-001",
+                    format!(
+                        "`{}` nests steps {} levels deep, but the limit is {}. Extract the inner steps into a separate function snippet.",
+                        snippet, depth, limit
+                    ),
+                )
+            }
+            CheckError::LoopControlOutsideLoop { kind } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: kind.clone(),
+                        context: "outside of a loop".to_string(),
+                    }),
+                    Span::dummy(),
+                    "E-LOOP-001",
+                    format!(
+                        "A `{}` step must be inside the body of a `for` step. Remove it or move it into a loop.",
+                        kind
+                    ),
+                )
+            }
+            CheckError::ArgumentCountMismatch { name, expected, found } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: format!("{} argument(s)", expected),
+                        found: format!("{} argument(s)", found),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-005",
+                    format!(
+                        "`{}` takes {} argument(s) but the call passes {}. Add or remove `arg` lines to match its parameters.",
+                        name, expected, found
+                    ),
+                )
+            }
+            CheckError::NonReplayableStep { workflow, step, context } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: workflow.clone(),
+                        context: format!("effectful step inside '{}'", context),
+                    }),
+                    Span::dummy(),
+                    "E-WORKFLOW-001",
+                    format!(
+                        "Step `{}` of workflow `{}` performs an effect inside a `{}` step, whose branches may finish in a different order when the workflow is resumed. Move the step out of the `{}` step.",
+                        step, workflow, context, context
+                    ),
+                )
+            }
+            CheckError::DuplicateWorkflowStep { workflow, step } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: workflow.clone(),
+                        context: format!("duplicate step '{}'", step),
+                    }),
+                    Span::dummy(),
+                    "E-WORKFLOW-002",
+                    format!(
+                        "Workflow `{}` has more than one step with id `{}`. Checkpoints are recorded per step, so give each step a unique id.",
+                        workflow, step
+                    ),
+                )
+            }
+            CheckError::InvalidSchedule { snippet, schedule, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: snippet.clone(),
+                        context: format!("schedule \"{}\"", schedule),
+                    }),
+                    Span::dummy(),
+                    "E-SCHEDULE-001",
+                    format!(
+                        "The schedule of `{}` is not a valid cron expression: {}. Use five fields: minute hour day month weekday, e.g. \"0 * * * *\" for hourly.",
+                        snippet, reason
+                    ),
+                )
+            }
+            CheckError::UnschedulableSnippet { snippet, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: snippet.clone(),
+                        context: "scheduled snippet".to_string(),
+                    }),
+                    Span::dummy(),
+                    "E-SCHEDULE-002",
+                    format!(
+                        "`{}` cannot be scheduled: {}. Schedule a kind=\"fn\" snippet without parameters.",
+                        snippet, reason
+                    ),
+                )
+            }
+            CheckError::InvalidRoute { snippet, route, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: snippet.clone(),
+                        context: format!("route \"{}\"", route),
+                    }),
+                    Span::dummy(),
+                    "E-ROUTE-001",
+                    format!(
+                        "The route of `{}` is not valid: {}. Give an optional method and a path, e.g. \"GET /users/:id\".",
+                        snippet, reason
+                    ),
+                )
+            }
+            CheckError::UnroutableSnippet { snippet, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: snippet.clone(),
+                        context: "route handler".to_string(),
+                    }),
+                    Span::dummy(),
+                    "E-ROUTE-002",
+                    format!(
+                        "`{}` cannot handle a route: {}. Route a kind=\"fn\" snippet whose params can be read from the request.",
+                        snippet, reason
+                    ),
+                )
+            }
+            CheckError::InvalidShedPolicy { snippet, key, value, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: snippet.clone(),
+                        context: format!("{} \"{}\"", key, value),
+                    }),
+                    Span::dummy(),
+                    "E-SHED-001",
+                    format!(
+                        "The {} of `{}` is not valid: {}. Give a count and a duration, e.g. rate_limit=\"100/m\" or circuit_breaker=\"5/30s\".",
+                        key, snippet, reason
+                    ),
+                )
+            }
+            CheckError::UnsheddableSnippet { snippet, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: snippet.clone(),
+                        context: "rate-limited extern".to_string(),
+                    }),
+                    Span::dummy(),
+                    "E-SHED-002",
+                    format!(
+                        "Calls to `{}` cannot be shed: {}. Limit an effectful extern whose result union includes ShedError.",
+                        snippet, reason
+                    ),
+                )
+            }
+            CheckError::ReleaseMismatch { acquire, release, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: release.clone(),
+                        context: format!("release of '{}'", acquire),
+                    }),
+                    Span::dummy(),
+                    "E-RESOURCE-001",
+                    format!(
+                        "`{}` cannot release what `{}` acquires: {}. A `using` step passes the acquired resource as the release function's only argument.",
+                        release, acquire, reason
+                    ),
+                )
+            }
+            CheckError::InvalidSubscription { subscriber, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: subscriber.clone(),
+                        context: "subscriber snippet".to_string(),
+                    }),
+                    Span::dummy(),
+                    "E-EVENT-001",
+                    format!(
+                        "Subscriber `{}` is invalid: {}. Subscribe to a struct type with a function that takes one parameter of that type.",
+                        subscriber, reason
+                    ),
+                )
+            }
+            CheckError::InvalidEventPublish { event_type, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: event_type.clone(),
+                        context: "events.publish".to_string(),
+                    }),
+                    Span::dummy(),
+                    "E-EVENT-002",
+                    format!(
+                        "Cannot publish event `{}`: {}. Pass the event's struct name as a literal `event_type` and a value of that struct as `event`.",
+                        event_type, reason
+                    ),
+                )
+            }
+            CheckError::NonBoolCondition { context, found } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: "Bool".to_string(),
+                        found: found.clone(),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-006",
+                    format!(
+                        "The {} evaluates to {}, not Bool. Use a comparison or logic `op` such as `less_eq` or `and`.",
+                        context, found
+                    ),
+                )
+            }
+            CheckError::InvalidRaise { raised, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: "an error member of the return union".to_string(),
+                        found: raised.clone(),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-007",
+                    format!(
+                        "Cannot raise `{}`: {}. Add the error type to the function's `returns union` after the success type, or return the value instead.",
+                        raised, reason
+                    ),
+                )
+            }
+            CheckError::TypeArgumentCountMismatch { name, expected, found } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: format!("{} type argument(s)", expected),
+                        found: format!("{} type argument(s)", found),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-008",
+                    format!(
+                        "`{}` takes {} type argument(s) but the call gives {}. Pass one `type arg=` per generic param in declaration order, or omit them to infer from the arguments.",
+                        name, expected, found
+                    ),
+                )
+            }
+            CheckError::InvalidImplementation { snippet, interface, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Symbol(SymbolDiagnostic {
+                        name: snippet.clone(),
+                        context: format!("implements=\"{}\"", interface),
+                    }),
+                    Span::dummy(),
+                    "E-INTERFACE-001",
+                    format!(
+                        "`{}` does not implement `{}`: {}. Match the interface method's name, parameters and return type, with `Self` standing for the implementing struct.",
+                        snippet, interface, reason
+                    ),
+                )
+            }
+            CheckError::UnitMismatch { left, right } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: left.clone(),
+                        found: right.clone(),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-009",
+                    format!(
+                        "Cannot mix {} and {} without an explicit conversion. Wrap plain numbers with a construct step, and call a conversion fn to change units.",
+                        left, right
+                    ),
+                )
+            }
+            CheckError::RecursiveAlias { name } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: "a type that does not name itself".to_string(),
+                        found: name.clone(),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-010",
+                    format!(
+                        "Type alias `{}` refers to itself. An alias must name a type that does not lead back to it; use a struct for recursive data.",
+                        name
+                    ),
+                )
+            }
+            CheckError::InvalidNewtype { name, fields } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: "exactly one field".to_string(),
+                        found: format!("{} fields", fields),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-011",
+                    format!(
+                        "Newtype `{}` must have exactly one field, found {}. Wrap a single value, or drop `newtype` to declare an ordinary struct.",
+                        name, fields
+                    ),
+                )
+            }
+            CheckError::ArrayIndexOutOfBounds { index, len } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: format!("an index from 0 below {}", len),
+                        found: index.to_string(),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-012",
+                    format!(
+                        "Array index {} is out of bounds for length {}. Indexes start at 0 and must be less than the length in the array's type.",
+                        index, len
+                    ),
+                )
+            }
+            CheckError::InvalidArrayLength { found } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: "a non-negative Int literal".to_string(),
+                        found: found.clone(),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-013",
+                    format!(
+                        "Array length must be a non-negative Int literal, found {}. The length is part of the array's type, so it must be known when the program is checked.",
+                        found
+                    ),
+                )
+            }
+            CheckError::MatrixShapeMismatch { left, right } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: format!("a matrix with as many rows as {} has columns", left),
+                        found: right.clone(),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-014",
+                    format!(
+                        "Cannot multiply {} by {}. An M×K matrix can only be multiplied by a K×N matrix: the left's row length must equal the right's number of rows.",
+                        left, right
+                    ),
+                )
+            }
+            CheckError::InvalidDecimalPlaces { found, max } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: format!("an Int literal from 0 to {}", max),
+                        found: found.clone(),
+                    }),
+                    Span::dummy(),
+                    "E-TYPE-015",
+                    format!(
+                        "Decimal places must be an Int literal from 0 to {}, found {}. The places fix the result's precision, so they must be known when the program is checked.",
+                        max, found
+                    ),
+                )
+            }
+            CheckError::InvalidRegex { pattern, reason } => {
+                Diagnostic::new(
+                    DiagnosticKind::Type(TypeDiagnostic {
+                        expected: "a valid regex literal".to_string(),
+                        found: format!("\"{}\"", pattern),
+                    }),
+                    Span::dummy(),
+                    "E-REGEX-001",
+                    format!(
+                        "Invalid regex pattern \"{}\": {}. Pass the pattern as a String literal using the supported syntax.",
+                        pattern, reason
+                    ),
+                )
+            }
         }
     }
 }
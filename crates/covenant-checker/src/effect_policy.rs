@@ -0,0 +1,65 @@
+//! Effect policy
+//!
+//! Effect parameters scope what a declaration grants: a function declaring
+//! `effect network.http host="api.example.com"` may only reach that host. A
+//! policy limits the values a project may declare, so a security review can
+//! pin where the program reaches without reading every function. Projects
+//! set it in `covenant.json`, as allowed values by effect and parameter:
+//!
+//! ```json
+//! { "policy": { "network": { "host": ["api.example.com", "*.stripe.com"] } } }
+//! ```
+//!
+//! A rule applies to declarations of its effect and of the effects above
+//! and below it in the registry: restricting `network` hosts restricts
+//! `network.http` too, and a bare `effect network`, which covers
+//! `network.http`, must name an allowed host as well. A declaration
+//! satisfies a rule when it gives each restricted parameter a value that
+//! one of the allowed values covers, as a declared parameter covers a
+//! required one (`path` directories, `*.` host wildcards).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Allowed effect parameter values, by effect and parameter
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EffectPolicy {
+    rules: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+impl EffectPolicy {
+    /// A policy allowing everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow `param` of `effect` only values that `allowed` covers
+    pub fn allow<S: Into<String>>(
+        mut self,
+        effect: impl Into<String>,
+        param: impl Into<String>,
+        allowed: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.rules
+            .entry(effect.into())
+            .or_default()
+            .insert(param.into(), allowed.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Rules as (effect, parameter, allowed values), sorted by effect
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &[String])> {
+        self.rules.iter().flat_map(|(effect, params)| {
+            params
+                .iter()
+                .map(move |(param, allowed)| (effect.as_str(), param.as_str(), allowed.as_slice()))
+        })
+    }
+}
+
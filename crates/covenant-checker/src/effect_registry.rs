@@ -0,0 +1,249 @@
+//! Effect registry
+//!
+//! Effects are named by free-form strings in `effects` sections, so a typo
+//! such as `netwrok` would otherwise introduce a new effect. The registry
+//! lists the effects a project may declare, each with a description and an
+//! optional parent. Declaring a parent covers its descendants: a function
+//! that declares `network` may call one that declares `http_server`.
+//!
+//! [`EffectRegistry::std`] holds the effects of the standard library; projects
+//! add their own in `covenant.json`:
+//!
+//! ```json
+//! { "effects": { "payments": { "description": "Charge cards", "parent": "network" } } }
+//! ```
+//!
+//! The registry also holds the project's [`EffectPolicy`], limiting the
+//! parameters effects may be declared with.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::EffectPolicy;
+
+/// Effects shipped with the standard library: (name, description, parent)
+const STD_EFFECTS: &[(&str, &str, Option<&str>)] = &[
+    ("console", "Read from and write to the console", None),
+    ("database", "Query and modify databases", None),
+    ("datetime", "Read the current date and time", None),
+    ("events", "Publish and subscribe to events", None),
+    ("filesystem", "Read and write files", None),
+    ("filesystem.read", "Read files and list directories", Some("filesystem")),
+    ("filesystem.write", "Create, write, and delete files and directories", Some("filesystem")),
+    ("locale", "Apply host Unicode and locale data to text", None),
+    ("http_server", "Serve HTTP requests", Some("network")),
+    ("meta", "Query and rewrite the program's own symbols", None),
+    ("network", "Make network requests", None),
+    ("network.http", "Make HTTP requests", Some("network")),
+    ("os", "Read operating system information", None),
+    ("process", "Spawn processes and read process state", None),
+    ("random", "Generate random values", None),
+    ("storage", "Persistent key-value and document storage", None),
+    ("timers", "Schedule timers", None),
+    ("std.crypto", "Hash, sign, and verify data", None),
+    ("std.random", "Generate random values via std.random", Some("random")),
+    ("std.storage", "Key-value and document storage via std.storage", Some("storage")),
+    ("std.time", "Read the clock and sleep via std.time", Some("datetime")),
+];
+
+/// A registered effect
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EffectDef {
+    /// What the effect allows a function to do
+    pub description: String,
+    /// Effect that covers this one when declared
+    pub parent: Option<String>,
+}
+
+/// Errors building an effect registry
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EffectRegistryError {
+    #[error("effect '{effect}' has unknown parent '{parent}'")]
+    UnknownParent { effect: String, parent: String },
+
+    #[error("effect '{effect}' is its own ancestor")]
+    Cycle { effect: String },
+}
+
+/// The effects a project may declare
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EffectRegistry {
+    effects: BTreeMap<String, EffectDef>,
+    policy: EffectPolicy,
+}
+
+impl EffectRegistry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The standard library's effects
+    pub fn std() -> Self {
+        let effects = STD_EFFECTS
+            .iter()
+            .map(|(name, description, parent)| {
+                let def = EffectDef {
+                    description: description.to_string(),
+                    parent: parent.map(str::to_string),
+                };
+                (name.to_string(), def)
+            })
+            .collect();
+        Self { effects, policy: EffectPolicy::new() }
+    }
+
+    /// Add project effects, replacing any registered effect of the same name
+    ///
+    /// Fails if a parent is not registered or the hierarchy has a cycle.
+    pub fn with_effects(
+        mut self,
+        effects: impl IntoIterator<Item = (String, EffectDef)>,
+    ) -> Result<Self, EffectRegistryError> {
+        self.effects.extend(effects);
+
+        for (name, def) in &self.effects {
+            if let Some(parent) = &def.parent {
+                if !self.effects.contains_key(parent) {
+                    return Err(EffectRegistryError::UnknownParent {
+                        effect: name.clone(),
+                        parent: parent.clone(),
+                    });
+                }
+            }
+            if self.ancestors(name).any(|a| a == name) {
+                return Err(EffectRegistryError::Cycle { effect: name.clone() });
+            }
+        }
+        Ok(self)
+    }
+
+    /// Restrict the parameter values effects may be declared with
+    pub fn with_policy(mut self, policy: EffectPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> &EffectPolicy {
+        &self.policy
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EffectDef> {
+        self.effects.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.effects.contains_key(name)
+    }
+
+    /// Registered effects, sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &EffectDef)> {
+        self.effects.iter().map(|(name, def)| (name.as_str(), def))
+    }
+
+    /// Parent, grandparent, and so on of an effect
+    ///
+    /// Stops after visiting as many effects as are registered, so a cyclic
+    /// hierarchy cannot loop forever.
+    pub fn ancestors<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a str> + 'a {
+        let mut current = self.effects.get(name).and_then(|def| def.parent.as_deref());
+        std::iter::from_fn(move || {
+            let effect = current?;
+            current = self.effects.get(effect).and_then(|def| def.parent.as_deref());
+            Some(effect)
+        })
+        .take(self.effects.len())
+    }
+
+    /// Whether declaring `declared` covers a callee's `required` effect
+    pub fn covers(&self, declared: &str, required: &str) -> bool {
+        declared == required || self.ancestors(required).any(|a| a == declared)
+    }
+
+    /// The registered effect closest to an unknown name, if any is close
+    pub fn suggest(&self, name: &str) -> Option<&str> {
+        let max_distance = (name.chars().count() / 3).max(1);
+        self.effects
+            .keys()
+            .map(|known| (edit_distance(name, known), known))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, known)| known.as_str())
+    }
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(parent: Option<&str>) -> EffectDef {
+        EffectDef {
+            description: String::new(),
+            parent: parent.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_std_effects_have_known_parents() {
+        assert!(EffectRegistry::std().with_effects([]).is_ok());
+    }
+
+    #[test]
+    fn test_parent_covers_descendants() {
+        let registry = EffectRegistry::std()
+            .with_effects([("webhooks".to_string(), custom(Some("http_server")))])
+            .unwrap();
+        assert!(registry.covers("network", "webhooks"));
+        assert!(registry.covers("http_server", "webhooks"));
+        assert!(registry.covers("webhooks", "webhooks"));
+        assert!(!registry.covers("webhooks", "network"));
+        assert!(!registry.covers("console", "webhooks"));
+    }
+
+    #[test]
+    fn test_unknown_parent_is_rejected() {
+        let result = EffectRegistry::std().with_effects([("payments".to_string(), custom(Some("netwrok")))]);
+        assert_eq!(
+            result,
+            Err(EffectRegistryError::UnknownParent {
+                effect: "payments".to_string(),
+                parent: "netwrok".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_cycle_is_rejected() {
+        let result = EffectRegistry::new().with_effects([
+            ("a".to_string(), custom(Some("b"))),
+            ("b".to_string(), custom(Some("a"))),
+        ]);
+        assert!(matches!(result, Err(EffectRegistryError::Cycle { .. })));
+    }
+
+    #[test]
+    fn test_suggest_closest_effect() {
+        let registry = EffectRegistry::std();
+        assert_eq!(registry.suggest("netwrok"), Some("network"));
+        assert_eq!(registry.suggest("filesytem"), Some("filesystem"));
+        assert_eq!(registry.suggest("payments"), None);
+    }
+}
@@ -7,13 +7,15 @@
 //! - Parameterized effect validation (effect subsumption)
 //! - Rich diagnostic generation for effect violations
 
-use std::collections::{HashMap, HashSet};
-use covenant_ast::{EffectDecl, Literal, SymbolId, EffectId, Span};
-use covenant_symbols::{SymbolGraph, SymbolInfo};
+use std::collections::{HashMap, HashSet, VecDeque};
+use covenant_ast::{EffectDecl, EffectParam, Literal, SymbolId, EffectId, Span};
+use covenant_ast::printer::ToCov;
+use covenant_symbols::{SymbolGraph, SymbolInfo, SymbolKind};
 
 use crate::diagnostics::{
     Diagnostic, EffectDiagnosticBuilder, FixSuggestion, RelatedLocation, CallChainEntry,
 };
+use crate::EffectRegistry;
 
 /// Table of effects and their relationships
 #[derive(Debug, Default)]
@@ -191,6 +193,45 @@ pub enum EffectError {
         /// Source span of the function
         span: Span,
     },
+    /// Declared effect is not in the effect registry (E-EFFECT-004)
+    UnknownEffect {
+        /// Name of the declaring symbol
+        function: String,
+        /// The unknown effect name
+        effect: String,
+        /// Closest registered effect, if any
+        suggestion: Option<String>,
+        /// Source span of the effect declaration
+        span: Span,
+    },
+    /// Declared effect parameter outside the project's policy (E-EFFECT-005)
+    PolicyViolation {
+        /// Name of the declaring symbol
+        function: String,
+        /// The declared effect
+        effect: String,
+        /// The restricted parameter
+        param_name: String,
+        /// The declared value, or `None` when the declaration leaves it out
+        declared_value: Option<String>,
+        /// Values the policy allows
+        allowed: Vec<String>,
+        /// Source span of the effect declaration
+        span: Span,
+    },
+}
+
+impl EffectError {
+    /// Name of the function or symbol the error is reported against
+    pub fn function(&self) -> &str {
+        match self {
+            EffectError::PureCallsEffectful { function, .. }
+            | EffectError::MissingEffect { function, .. }
+            | EffectError::ParameterNotCovered { function, .. }
+            | EffectError::UnknownEffect { function, .. }
+            | EffectError::PolicyViolation { function, .. } => function,
+        }
+    }
 }
 
 /// Compute effect closures for all symbols in the graph and validate I2 invariant.
@@ -199,15 +240,105 @@ pub enum EffectError {
 /// - `declared_effects ⊇ computed_effects`
 /// - If a function declares no effects (pure), it cannot call effectful code
 pub fn check_effects(graph: &SymbolGraph) -> EffectCheckResult {
+    check_effects_of(graph, graph.iter(), None)
+}
+
+/// Check effects against an effect registry
+///
+/// Like [`check_effects`], but declared effects must be registered, and a
+/// declared effect also covers its descendants in the registry's hierarchy.
+pub fn check_effects_with_registry(graph: &SymbolGraph, registry: &EffectRegistry) -> EffectCheckResult {
+    let mut result = check_effects_of(graph, graph.iter(), Some(registry));
+    let mut declarations = check_effect_names(graph, registry);
+    declarations.extend(check_effect_policy(graph, registry));
+    result.violations.splice(0..0, declarations);
+    result
+}
+
+/// Report every declared effect that the registry doesn't know
+pub fn check_effect_names(graph: &SymbolGraph, registry: &EffectRegistry) -> Vec<EffectError> {
+    graph
+        .iter()
+        .flat_map(|symbol| symbol.declared_effects.iter().map(move |effect| (symbol, effect)))
+        .filter(|(_, effect)| !registry.contains(&effect.name))
+        .map(|(symbol, effect)| EffectError::UnknownEffect {
+            function: symbol.name.clone(),
+            effect: effect.name.clone(),
+            suggestion: registry.suggest(&effect.name).map(str::to_string),
+            span: effect.span,
+        })
+        .collect()
+}
+
+/// Report every declared effect parameter outside the registry's policy
+///
+/// Externs are skipped: they declare the effects they need, and the
+/// functions calling them declare where those effects may reach.
+pub fn check_effect_policy(graph: &SymbolGraph, registry: &EffectRegistry) -> Vec<EffectError> {
+    let policy = registry.policy();
+    let mut violations = Vec::new();
+    if policy.is_empty() {
+        return violations;
+    }
+
+    let declarers = graph.iter().filter(|symbol| {
+        !matches!(symbol.kind, SymbolKind::Extern | SymbolKind::ExternAbstract | SymbolKind::ExternImpl)
+    });
+    for symbol in declarers {
+        for effect in &symbol.declared_effects {
+            let mut checked = HashSet::new();
+            let rules = policy.iter().filter(|(ruled, _, _)| {
+                registry.covers(ruled, &effect.name) || registry.covers(&effect.name, ruled)
+            });
+            for (_, param_name, allowed) in rules {
+                if !checked.insert(param_name) {
+                    continue;
+                }
+                let declared = effect.get_param(param_name).map(|p| &p.value);
+                let permitted = declared.is_some_and(|value| {
+                    allowed
+                        .iter()
+                        .any(|a| param_value_subsumes(&Literal::String(a.clone()), value, param_name))
+                });
+                if !permitted {
+                    violations.push(EffectError::PolicyViolation {
+                        function: symbol.name.clone(),
+                        effect: effect.name.clone(),
+                        param_name: param_name.to_string(),
+                        declared_value: declared.map(literal_to_string),
+                        allowed: allowed.to_vec(),
+                        span: effect.span,
+                    });
+                }
+            }
+        }
+    }
+    violations
+}
+
+/// Check effects for a subset of symbols (by name)
+///
+/// Used by incremental re-checking to validate one priority batch at a
+/// time. Closures are still computed over the whole graph; only the named
+/// symbols are validated and included in the result.
+pub fn check_effects_for(graph: &SymbolGraph, names: &[String]) -> EffectCheckResult {
+    check_effects_of(graph, names.iter().filter_map(|name| graph.get_by_name(name)), None)
+}
+
+fn check_effects_of<'a>(
+    graph: &SymbolGraph,
+    symbols: impl Iterator<Item = &'a SymbolInfo>,
+    registry: Option<&EffectRegistry>,
+) -> EffectCheckResult {
     let mut closures = HashMap::new();
     let mut violations = Vec::new();
 
     // Process all callable symbols (functions and externs)
-    for symbol in graph.iter().filter(|s| s.is_callable()) {
+    for symbol in symbols.filter(|s| s.is_callable()) {
         let closure = compute_closure_for_symbol(symbol, graph);
 
         // Validate: declared must cover computed
-        if let Some(error) = validate_closure(symbol, &closure, graph) {
+        if let Some(error) = validate_closure(symbol, &closure, graph, registry) {
             violations.push(error);
         }
 
@@ -277,14 +408,22 @@ fn collect_transitive_effects(
 }
 
 /// Validate that declared effects cover computed effects
+///
+/// Without a registry an effect is only covered by declaring it by name.
 fn validate_closure(
     symbol: &SymbolInfo,
     closure: &EffectClosure,
     graph: &SymbolGraph,
+    registry: Option<&EffectRegistry>,
 ) -> Option<EffectError> {
-    // First check: find effects by name that are in computed but not in declared
+    // First check: find effects by name that are in computed but not covered by declared
+    let covered = |required: &str| match registry {
+        Some(registry) => closure.declared.iter().any(|declared| registry.covers(declared, required)),
+        None => closure.declared.contains(required),
+    };
     let missing: Vec<String> = closure.computed
-        .difference(&closure.declared)
+        .iter()
+        .filter(|required| !covered(required))
         .cloned()
         .collect();
 
@@ -310,9 +449,13 @@ fn validate_closure(
         }
     }
 
-    // Second check: for effects with the same name, validate parameters
-    // The declared effect must subsume (cover) the required effect
-    // For each required effect with params, find if any declared effect covers it
+    // Second check: parameterized effects must be covered by a declaration
+    // whose parameters subsume them. Declaring a parent effect such as
+    // `network(host="api.stripe.com")` also scopes its descendants.
+    let declaration_covers = |declared: &str, required: &str| match registry {
+        Some(registry) => registry.covers(declared, required),
+        None => declared == required,
+    };
     for required in &closure.computed_full {
         if !required.has_params() {
             continue; // No parameters to check
@@ -329,83 +472,84 @@ fn validate_closure(
             continue;
         }
 
-        // Find the declared effect with matching name
-        if let Some(declared) = closure.declared_full.iter().find(|d| d.name == required.name) {
-            // Check if declared subsumes required
-            if let Some(error) = check_effect_subsumption(symbol, declared, required, graph) {
-                return Some(error);
+        // Any covering declaration may grant the resource; report the first
+        // one's mismatch if none does
+        let mut first_mismatch = None;
+        for declared in closure.declared_full.iter().filter(|d| declaration_covers(&d.name, &required.name)) {
+            match uncovered_param(declared, required) {
+                None => {
+                    first_mismatch = None;
+                    break;
+                }
+                Some(mismatch) => {
+                    first_mismatch.get_or_insert(mismatch);
+                }
             }
         }
+        if let Some((req_param, decl_param)) = first_mismatch {
+            let source_callee = find_effect_source_for_param(
+                symbol, &required.name, &req_param.name, graph
+            ).unwrap_or_else(|| "unknown".to_string());
+
+            return Some(EffectError::ParameterNotCovered {
+                function: symbol.name.clone(),
+                effect_name: required.name.clone(),
+                param_name: req_param.name.clone(),
+                required_value: literal_to_string(&req_param.value),
+                declared_value: Some(literal_to_string(&decl_param.value)),
+                source_callee,
+                span: symbol.span,
+            });
+        }
     }
 
     None
 }
 
-/// Check if declared effect subsumes required effect (parameter-aware matching)
+/// Find a required parameter the declared effect does not subsume
+///
+/// Returns the required parameter and the declared one that fails to cover it.
 ///
 /// Rules:
-/// - If required has no parameters, any declaration with same name is fine
+/// - If required has no parameters, any covering declaration is fine
 /// - If declared has no parameters, it covers any parameterized version (wildcard)
-/// - If both have parameters, declared must subsume required for each param:
-///   - For path parameters: declared path must be a prefix of required path
-///   - For other parameters: declared must equal required (exact match)
-fn check_effect_subsumption(
-    symbol: &SymbolInfo,
-    declared: &EffectDecl,
-    required: &EffectDecl,
-    graph: &SymbolGraph,
-) -> Option<EffectError> {
+/// - A parameter the declaration leaves out is unrestricted
+/// - Otherwise the declared value must subsume the required one:
+///   - `path`: the declared path is the required path or one of its parent directories
+///   - `host`: the declared host equals the required one, or is a `*.` wildcard
+///     matching it (`*.stripe.com` covers `api.stripe.com`)
+///   - Other parameters: declared must equal required (exact match)
+fn uncovered_param<'a>(
+    declared: &'a EffectDecl,
+    required: &'a EffectDecl,
+) -> Option<(&'a EffectParam, &'a EffectParam)> {
     // If declared has no parameters, it's a wildcard - covers everything
     if !declared.has_params() {
         return None;
     }
 
-    // If required has no parameters but declared does, that's fine too
-    if !required.has_params() {
-        return None;
-    }
-
-    // Both have parameters - check each required parameter
-    for req_param in &required.params {
-        match declared.get_param(&req_param.name) {
-            Some(decl_param) => {
-                // Check if declared value subsumes required value
-                if !param_value_subsumes(&decl_param.value, &req_param.value, &req_param.name) {
-                    let source_callee = find_effect_source_for_param(
-                        symbol, &required.name, &req_param.name, graph
-                    ).unwrap_or_else(|| "unknown".to_string());
-
-                    return Some(EffectError::ParameterNotCovered {
-                        function: symbol.name.clone(),
-                        effect_name: required.name.clone(),
-                        param_name: req_param.name.clone(),
-                        required_value: literal_to_string(&req_param.value),
-                        declared_value: Some(literal_to_string(&decl_param.value)),
-                        source_callee,
-                        span: symbol.span,
-                    });
-                }
-            }
-            None => {
-                // Declared doesn't have this parameter - that's OK, it means unrestricted
-            }
+    required.params.iter().find_map(|req_param| {
+        let decl_param = declared.get_param(&req_param.name)?;
+        if param_value_subsumes(&decl_param.value, &req_param.value, &req_param.name) {
+            None
+        } else {
+            Some((req_param, decl_param))
         }
-    }
-
-    None
+    })
 }
 
 /// Check if a declared parameter value subsumes a required parameter value
 ///
-/// For "path" parameters: declared must be a prefix of required (path="/data" subsumes path="/data/users")
+/// For "path" parameters: declared must be the required path or a directory
+/// containing it (path="/data" subsumes path="/data/users" but not path="/database")
+/// For "host" parameters: exact match, or a `*.` wildcard covering subdomains
 /// For other parameters: exact match required
 fn param_value_subsumes(declared: &Literal, required: &Literal, param_name: &str) -> bool {
-    // Special handling for path parameters
-    if param_name == "path" {
-        if let (Literal::String(decl_path), Literal::String(req_path)) = (declared, required) {
-            // Declared path must be a prefix of required path
-            // "/data" subsumes "/data/users" and "/data" but not "/other"
-            return req_path.starts_with(decl_path.as_str());
+    if let (Literal::String(decl), Literal::String(req)) = (declared, required) {
+        match param_name {
+            "path" => return path_within(decl, req),
+            "host" => return host_matches(decl, req),
+            _ => {}
         }
     }
 
@@ -416,10 +560,34 @@ fn param_value_subsumes(declared: &Literal, required: &Literal, param_name: &str
         (Literal::String(d), Literal::String(r)) => d == r,
         (Literal::Bool(d), Literal::Bool(r)) => d == r,
         (Literal::None, Literal::None) => true,
+        (Literal::Decimal(_) | Literal::BigInt(_) | Literal::DateTime(_) | Literal::Duration(_), _) => {
+            declared == required
+        }
+        (Literal::List(_) | Literal::Map(_) | Literal::Set(_) | Literal::Tuple(_), _) => declared == required,
         _ => false,
     }
 }
 
+/// Whether `path` is `dir` or lies beneath it
+fn path_within(dir: &str, path: &str) -> bool {
+    match path.strip_prefix(dir) {
+        Some(rest) => rest.is_empty() || dir.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// Whether a host pattern covers a host (or a narrower pattern)
+///
+/// `*.stripe.com` covers `api.stripe.com` and `*.api.stripe.com` but not
+/// `stripe.com` itself. Host names compare case-insensitively.
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let (pattern, host) = (pattern.to_ascii_lowercase(), host.to_ascii_lowercase());
+    match pattern.strip_prefix('*') {
+        Some(suffix) if suffix.starts_with('.') => host.len() > suffix.len() && host.ends_with(suffix),
+        _ => pattern == host,
+    }
+}
+
 /// Convert a Literal to a displayable string
 fn literal_to_string(lit: &Literal) -> String {
     match lit {
@@ -428,6 +596,8 @@ fn literal_to_string(lit: &Literal) -> String {
         Literal::String(s) => format!("\"{}\"", s),
         Literal::Bool(b) => b.to_string(),
         Literal::None => "none".to_string(),
+        Literal::Decimal(_) | Literal::BigInt(_) | Literal::DateTime(_) | Literal::Duration(_) => lit.to_cov(0),
+        Literal::List(_) | Literal::Map(_) | Literal::Set(_) | Literal::Tuple(_) => lit.to_cov(0),
     }
 }
 
@@ -466,6 +636,100 @@ fn find_effect_source_for_param(
     None
 }
 
+// =============================================================================
+// Effect Provenance
+// =============================================================================
+
+/// Most call chains [`explain_effect_provenance`] reports
+pub const MAX_PROVENANCE_CHAINS: usize = 10;
+
+/// Explain why a function's computed closure contains an effect
+///
+/// Returns call chains from `function` to a symbol that introduces
+/// `effect`, shortest first. A symbol introduces the effect when it declares
+/// it and either is an extern or has no callee whose closure carries it.
+/// Only callees whose computed closure contains the effect are followed, so
+/// the search stays within the relevant part of the graph.
+///
+/// Dense graphs have exponentially many chains, so at most
+/// [`MAX_PROVENANCE_CHAINS`] are returned, and each symbol ends at most that
+/// many of the partial chains explored; the search is then polynomial in
+/// the size of the graph.
+pub fn explain_effect_provenance(
+    function: &str,
+    effect: &str,
+    graph: &SymbolGraph,
+    result: &EffectCheckResult,
+) -> Vec<Vec<CallChainEntry>> {
+    let carries = |name: &str| {
+        result
+            .closures
+            .get(name)
+            .is_some_and(|c| c.computed.contains(effect))
+    };
+
+    let mut chains = Vec::new();
+    if !carries(function) {
+        return chains;
+    }
+
+    let mut queue: VecDeque<Vec<&SymbolInfo>> = VecDeque::new();
+    if let Some(start) = graph.get_by_name(function) {
+        queue.push_back(vec![start]);
+    }
+
+    let mut expanded: HashMap<&str, usize> = HashMap::new();
+    while let Some(path) = queue.pop_front() {
+        let current = *path.last().expect("paths are never empty");
+        let times = expanded.entry(current.name.as_str()).or_default();
+        if *times == MAX_PROVENANCE_CHAINS {
+            continue;
+        }
+        *times += 1;
+
+        let mut next: Vec<&SymbolInfo> = current
+            .calls
+            .iter()
+            .filter(|callee| carries(callee))
+            .filter_map(|callee| graph.get_by_name(callee))
+            .filter(|callee| !path.iter().any(|s| s.id == callee.id))
+            .collect();
+        next.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let declares = current.declared_effects.iter().any(|e| e.name == effect);
+        let is_extern = matches!(
+            current.kind,
+            SymbolKind::Extern | SymbolKind::ExternAbstract | SymbolKind::ExternImpl
+        );
+
+        if declares && (is_extern || next.is_empty()) {
+            chains.push(
+                path.iter()
+                    .map(|sym| CallChainEntry {
+                        name: sym.name.clone(),
+                        effects: sym.declared_effects.iter().map(|e| e.name.clone()).collect(),
+                        span: sym.span,
+                    })
+                    .collect(),
+            );
+            if chains.len() == MAX_PROVENANCE_CHAINS {
+                break;
+            }
+            if is_extern {
+                continue;
+            }
+        }
+
+        for callee in next {
+            let mut extended = path.clone();
+            extended.push(callee);
+            queue.push_back(extended);
+        }
+    }
+
+    chains
+}
+
 // =============================================================================
 // Rich Diagnostic Generation
 // =============================================================================
@@ -544,6 +808,63 @@ pub fn explain_effect_violation(error: &EffectError, graph: &SymbolGraph) -> Dia
             )
             .with_suggestion(suggestion)
         }
+        EffectError::UnknownEffect { function, effect, suggestion, span } => {
+            let explanation = match suggestion {
+                Some(known) => format!(
+                    "`{}` declares effect `{}`, which is not registered. Did you mean `{}`?",
+                    function, effect, known
+                ),
+                None => format!(
+                    "`{}` declares effect `{}`, which is not registered. \
+                     Custom effects must be declared in the project's covenant.json.",
+                    function, effect
+                ),
+            };
+
+            let diagnostic = Diagnostic::new(
+                crate::diagnostics::DiagnosticKind::UnknownEffect(crate::diagnostics::UnknownEffectDiagnostic {
+                    function: function.clone(),
+                    effect: effect.clone(),
+                }),
+                *span,
+                "E-EFFECT-004",
+                explanation,
+            );
+            match suggestion {
+                Some(known) => diagnostic.with_suggestion(FixSuggestion::RenameEffect {
+                    from: effect.clone(),
+                    to: known.clone(),
+                    location: *span,
+                }),
+                None => diagnostic,
+            }
+        }
+        EffectError::PolicyViolation { function, effect, param_name, declared_value, allowed, span } => {
+            let declared = match declared_value {
+                Some(value) => format!("with `{}` {}", param_name, value),
+                None => format!("without a `{}`", param_name),
+            };
+            let explanation = format!(
+                "`{}` declares effect `{}` {}, but the project's policy only allows `{}` values {}.",
+                function,
+                effect,
+                declared,
+                param_name,
+                allowed.iter().map(|a| format!("\"{}\"", a)).collect::<Vec<_>>().join(", ")
+            );
+
+            Diagnostic::new(
+                crate::diagnostics::DiagnosticKind::Effect(crate::diagnostics::EffectDiagnostic {
+                    function: function.clone(),
+                    callee: None,
+                    effects: vec![effect.clone()],
+                    call_chain: Vec::new(),
+                }),
+                *span,
+                "E-EFFECT-005",
+                explanation,
+            )
+        }
     }
 }
 
@@ -6,17 +6,38 @@ mod types;
 mod symbols;
 mod checker;
 mod effects;
+mod effect_registry;
+mod effect_policy;
 mod snippet_checker;
 mod diagnostics;
+mod limits;
+mod conversions;
+mod regex;
+mod workflow;
+mod schedule;
+mod route;
+mod shedding;
+mod sensitive;
+#[cfg(test)]
+mod test_support;
 
 pub use types::*;
 pub use symbols::*;
 pub use checker::*;
 pub use effects::*;
+pub use effect_registry::*;
+pub use effect_policy::*;
 pub use snippet_checker::SnippetChecker;
 pub use diagnostics::*;
+pub use limits::*;
+pub use conversions::*;
+pub use workflow::*;
+pub use schedule::*;
+pub use route::*;
+pub use shedding::*;
+pub use sensitive::*;
 
-use covenant_ast::Program;
+use covenant_ast::{CancellationToken, Limits, Program, Span};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -45,6 +66,9 @@ pub enum CheckError {
         matched_type: String,
     },
 
+    #[error("cannot match '{on}' by type: union values carry no runtime tag to dispatch on")]
+    UnsupportedTypeMatch { on: String },
+
     #[error("unknown query target: {target}")]
     UnknownQueryTarget { target: String },
 
@@ -65,6 +89,126 @@ pub enum CheckError {
 
     #[error("extern snippet '{id}' must have a namespaced ID (e.g., 'module.function')")]
     InvalidExternId { id: String },
+
+    #[error("type checking cancelled")]
+    Cancelled,
+
+    #[error("'{snippet}' nests steps {depth} deep, exceeding the limit of {limit}")]
+    NestingTooDeep {
+        snippet: String,
+        depth: usize,
+        limit: usize,
+        /// The first step past the limit
+        span: Span,
+    },
+
+    #[error("'{kind}' step outside of a loop")]
+    LoopControlOutsideLoop { kind: String },
+
+    #[error("'{name}' takes {expected} argument(s) but {found} were given")]
+    ArgumentCountMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("workflow '{workflow}': effectful step '{step}' runs inside a '{context}' step and cannot be replayed")]
+    NonReplayableStep {
+        workflow: String,
+        step: String,
+        context: String,
+    },
+
+    #[error("workflow '{workflow}' has more than one step with id '{step}'")]
+    DuplicateWorkflowStep { workflow: String, step: String },
+
+    #[error("'{snippet}' has an invalid schedule \"{schedule}\": {reason}")]
+    InvalidSchedule {
+        snippet: String,
+        schedule: String,
+        reason: String,
+    },
+
+    #[error("'{snippet}' cannot be scheduled: {reason}")]
+    UnschedulableSnippet { snippet: String, reason: String },
+
+    #[error("'{snippet}' has an invalid route \"{route}\": {reason}")]
+    InvalidRoute {
+        snippet: String,
+        route: String,
+        reason: String,
+    },
+
+    #[error("'{snippet}' cannot handle a route: {reason}")]
+    UnroutableSnippet { snippet: String, reason: String },
+
+    #[error("'{snippet}' has an invalid {key} \"{value}\": {reason}")]
+    InvalidShedPolicy {
+        snippet: String,
+        key: String,
+        value: String,
+        reason: String,
+    },
+
+    #[error("calls to '{snippet}' cannot be shed: {reason}")]
+    UnsheddableSnippet { snippet: String, reason: String },
+
+    #[error("'{release}' cannot release the resource acquired by '{acquire}': {reason}")]
+    ReleaseMismatch {
+        acquire: String,
+        release: String,
+        reason: String,
+    },
+
+    #[error("subscriber '{subscriber}' is invalid: {reason}")]
+    InvalidSubscription { subscriber: String, reason: String },
+
+    #[error("invalid publish of event '{event_type}': {reason}")]
+    InvalidEventPublish { event_type: String, reason: String },
+
+    #[error("{context} must be Bool, found {found}")]
+    NonBoolCondition { context: String, found: String },
+
+    #[error("cannot raise {raised}: {reason}")]
+    InvalidRaise { raised: String, reason: String },
+
+    #[error("'{name}' takes {expected} type argument(s) but {found} were given")]
+    TypeArgumentCountMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("invalid regex pattern \"{pattern}\": {reason}")]
+    InvalidRegex { pattern: String, reason: String },
+
+    #[error("newtype '{name}' must have exactly one field, found {fields}")]
+    InvalidNewtype { name: String, fields: usize },
+
+    #[error("type alias '{name}' refers to itself")]
+    RecursiveAlias { name: String },
+
+    #[error("array index {index} is out of bounds for length {len}")]
+    ArrayIndexOutOfBounds { index: i64, len: usize },
+
+    #[error("array length must be a non-negative Int literal, found {found}")]
+    InvalidArrayLength { found: String },
+
+    #[error("cannot multiply {left} by {right}: the left matrix's columns must match the right's rows")]
+    MatrixShapeMismatch { left: String, right: String },
+
+    #[error("decimal places must be an Int literal from 0 to {max}, found {found}")]
+    InvalidDecimalPlaces { found: String, max: u32 },
+
+    #[error("cannot mix {left} and {right} without an explicit conversion")]
+    UnitMismatch { left: String, right: String },
+
+    #[error("'{snippet}' does not implement '{interface}': {reason}")]
+    InvalidImplementation {
+        snippet: String,
+        interface: String,
+        reason: String,
+    },
 }
 
 /// Check a program and return the typed/annotated version
@@ -81,6 +225,41 @@ pub fn check(program: &Program) -> Result<CheckResult, Vec<CheckError>> {
     }
 }
 
+/// Check a program with custom structural limits
+pub fn check_with_limits(program: &Program, limits: Limits) -> Result<CheckResult, Vec<CheckError>> {
+    match program {
+        Program::Legacy { declarations, .. } => {
+            let mut checker = Checker::new();
+            checker.check_declarations(declarations)
+        }
+        Program::Snippets { snippets, .. } => {
+            let checker = SnippetChecker::new().with_limits(limits);
+            checker.check_snippets(snippets)
+        }
+    }
+}
+
+/// Check a program, stopping early with `CheckError::Cancelled` if `token`
+/// is cancelled
+pub fn check_with_cancel(
+    program: &Program,
+    token: &CancellationToken,
+) -> Result<CheckResult, Vec<CheckError>> {
+    if token.is_cancelled() {
+        return Err(vec![CheckError::Cancelled]);
+    }
+    match program {
+        Program::Legacy { declarations, .. } => {
+            let mut checker = Checker::new();
+            checker.check_declarations(declarations)
+        }
+        Program::Snippets { snippets, .. } => {
+            let checker = SnippetChecker::new().with_cancellation(token.clone());
+            checker.check_snippets(snippets)
+        }
+    }
+}
+
 /// Result of type checking
 #[derive(Debug, Default)]
 pub struct CheckResult {
@@ -88,4 +267,6 @@ pub struct CheckResult {
     pub symbols: SymbolTable,
     /// Effect information
     pub effects: EffectTable,
+    /// Lossy numeric conversions
+    pub warnings: Vec<ConversionWarning>,
 }
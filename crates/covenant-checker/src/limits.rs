@@ -0,0 +1,199 @@
+//! Structural size limits
+//!
+//! Flags bodies with too many steps, files with too many snippets, and
+//! functions with too many parameters. These are warnings rather than
+//! errors: generated code tends to grow large, and the limits in
+//! `covenant_ast::Limits` can be tightened gradually per project.
+
+use covenant_ast::{step_count, Limits, Section, SignatureKind, Snippet, Span};
+
+/// Which size limit was exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    /// Too many steps in a single body (W-LIMIT-001)
+    StepsPerBody,
+    /// Too many snippets in a single file (W-LIMIT-002)
+    SnippetsPerFile,
+    /// Too many parameters on a single function (W-LIMIT-003)
+    ParamsPerFunction,
+}
+
+/// A size limit exceeded by a snippet or file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitWarning {
+    /// Which limit was exceeded
+    pub kind: LimitKind,
+    /// Offending snippet ID (`None` for file-level limits)
+    pub snippet: Option<String>,
+    /// Measured size
+    pub actual: usize,
+    /// Configured limit
+    pub limit: usize,
+    /// Source span of the offending snippet
+    pub span: Span,
+}
+
+impl LimitWarning {
+    /// Warning code for machine-readable output
+    pub fn code(&self) -> &'static str {
+        match self.kind {
+            LimitKind::StepsPerBody => "W-LIMIT-001",
+            LimitKind::SnippetsPerFile => "W-LIMIT-002",
+            LimitKind::ParamsPerFunction => "W-LIMIT-003",
+        }
+    }
+
+    /// Human-readable description of the problem
+    pub fn message(&self) -> String {
+        let snippet = self.snippet.as_deref().unwrap_or("file");
+        match self.kind {
+            LimitKind::StepsPerBody => format!(
+                "`{}` has {} steps (limit {})",
+                snippet, self.actual, self.limit
+            ),
+            LimitKind::SnippetsPerFile => format!(
+                "file has {} snippets (limit {})",
+                self.actual, self.limit
+            ),
+            LimitKind::ParamsPerFunction => format!(
+                "`{}` takes {} parameters (limit {})",
+                snippet, self.actual, self.limit
+            ),
+        }
+    }
+
+    /// Suggested refactoring
+    pub fn suggestion(&self) -> &'static str {
+        match self.kind {
+            LimitKind::StepsPerBody => {
+                "extract groups of related steps into separate fn snippets and call them"
+            }
+            LimitKind::SnippetsPerFile => {
+                "split the file by module, moving each snippet ID prefix into its own file"
+            }
+            LimitKind::ParamsPerFunction => {
+                "group related parameters into a struct snippet and pass that instead"
+            }
+        }
+    }
+}
+
+/// Check snippets from a single file against the size limits
+pub fn check_limits(snippets: &[Snippet], limits: &Limits) -> Vec<LimitWarning> {
+    let mut warnings = Vec::new();
+
+    if snippets.len() > limits.max_snippets_per_file {
+        warnings.push(LimitWarning {
+            kind: LimitKind::SnippetsPerFile,
+            snippet: None,
+            actual: snippets.len(),
+            limit: limits.max_snippets_per_file,
+            span: snippets[limits.max_snippets_per_file].span,
+        });
+    }
+
+    for snippet in snippets {
+        for section in &snippet.sections {
+            match section {
+                Section::Body(body) => {
+                    let steps = step_count(&body.steps);
+                    if steps > limits.max_steps_per_body {
+                        warnings.push(LimitWarning {
+                            kind: LimitKind::StepsPerBody,
+                            snippet: Some(snippet.id.clone()),
+                            actual: steps,
+                            limit: limits.max_steps_per_body,
+                            span: body.span,
+                        });
+                    }
+                }
+                Section::Signature(sig) => {
+                    if let SignatureKind::Function(f) = &sig.kind {
+                        if f.params.len() > limits.max_params_per_fn {
+                            warnings.push(LimitWarning {
+                                kind: LimitKind::ParamsPerFunction,
+                                snippet: Some(snippet.id.clone()),
+                                actual: f.params.len(),
+                                limit: limits.max_params_per_fn,
+                                span: f.span,
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::snippets;
+
+    const SOURCE: &str = r#"
+snippet id="app.wide" kind="fn"
+signature
+  fn name="wide"
+    param name="a" type="Int"
+    param name="b" type="Int"
+    param name="c" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="if"
+    condition="a"
+    then
+      step id="s2" kind="return"
+        from="b"
+        as="_"
+      end
+    end
+    as="_"
+  end
+  step id="s3" kind="return"
+    from="c"
+    as="_"
+  end
+end
+end
+
+snippet id="app.small" kind="fn"
+signature
+  fn name="small"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=1
+    as="_"
+  end
+end
+end
+"#;
+
+    #[test]
+    fn test_within_default_limits() {
+        assert!(check_limits(&snippets(SOURCE), &Limits::default()).is_empty());
+    }
+
+    #[test]
+    fn test_exceeded_limits() {
+        let limits = Limits {
+            max_steps_per_body: 2,
+            max_snippets_per_file: 1,
+            max_params_per_fn: 2,
+            ..Limits::default()
+        };
+        let warnings = check_limits(&snippets(SOURCE), &limits);
+        let codes: Vec<&str> = warnings.iter().map(LimitWarning::code).collect();
+
+        assert_eq!(codes, vec!["W-LIMIT-002", "W-LIMIT-003", "W-LIMIT-001"]);
+        assert_eq!(warnings[2].actual, 3);
+        assert_eq!(warnings[2].snippet.as_deref(), Some("app.wide"));
+    }
+}
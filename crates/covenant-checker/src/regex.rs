@@ -0,0 +1,118 @@
+//! Regex pattern validation
+//!
+//! The pattern of `regex_match`, `regex_find_all`, `regex_capture` and
+//! `regex_replace` must be a String literal so it can be validated here rather than failing at
+//! runtime. Patterns are parsed with the syntax of Rust's `regex` crate,
+//! which has no backreferences or look-around, and then limited to the
+//! subset the runtime's linear-time matcher implements:
+//!
+//! - literals, escapes (`\.`, `\n`, `\t`, `\x41`, `\u{263A}`) and `.`
+//! - classes `[a-z_]`, `[^0-9]` and the ASCII Perl classes `\d`, `\w`, `\s`
+//!   and their negations
+//! - groups `(a)`, `(?:a)`, `(?<name>a)`, alternation `a|b`
+//! - repetition `*`, `+`, `?`, `{n}`, `{n,}`, `{n,m}`, with lazy forms
+//! - assertions `^`, `$`, `\A`, `\z`, `\b`, `\B`
+//!
+//! Unicode classes, flags and nested or combined classes are rejected.
+
+use regex_syntax::ast::{
+    parse::Parser, Assertion, AssertionKind, Ast, ClassSet, ClassSetItem, GroupKind, LiteralKind,
+    RepetitionKind, RepetitionRange,
+};
+
+/// Largest count accepted in a counted repetition such as `a{3,5}`
+pub(crate) const MAX_REPETITION: u32 = 1000;
+
+/// Check `pattern` is a regex the runtime can match, returning the reason
+/// it is not
+pub(crate) fn validate_pattern(pattern: &str) -> Result<(), String> {
+    let ast = Parser::new()
+        .parse(pattern)
+        .map_err(|e| format!("{} at offset {}", e.kind(), e.span().start.offset))?;
+    check_supported(&ast)
+}
+
+fn check_supported(ast: &Ast) -> Result<(), String> {
+    match ast {
+        Ast::Empty(_) | Ast::Dot(_) | Ast::ClassPerl(_) => Ok(()),
+        Ast::Flags(_) => Err("inline flags such as `(?i)` are not supported".to_string()),
+        Ast::Literal(lit) => check_literal(&lit.kind),
+        Ast::Assertion(assertion) => check_assertion(assertion),
+        Ast::ClassUnicode(_) => Err(unicode_class()),
+        Ast::ClassBracketed(class) => check_class_set(&class.kind),
+        Ast::Repetition(rep) => {
+            if let RepetitionKind::Range(range) = &rep.op.kind {
+                let max = match range {
+                    RepetitionRange::Exactly(n) | RepetitionRange::AtLeast(n) => *n,
+                    RepetitionRange::Bounded(_, n) => *n,
+                };
+                if max > MAX_REPETITION {
+                    return Err(format!(
+                        "repetition count {} is above the limit of {}",
+                        max, MAX_REPETITION
+                    ));
+                }
+            }
+            check_supported(&rep.ast)
+        }
+        Ast::Group(group) => {
+            if let GroupKind::NonCapturing(flags) = &group.kind {
+                if !flags.items.is_empty() {
+                    return Err("inline flags such as `(?i:...)` are not supported".to_string());
+                }
+            }
+            check_supported(&group.ast)
+        }
+        Ast::Alternation(alt) => alt.asts.iter().try_for_each(check_supported),
+        Ast::Concat(concat) => concat.asts.iter().try_for_each(check_supported),
+    }
+}
+
+fn check_literal(kind: &LiteralKind) -> Result<(), String> {
+    match kind {
+        LiteralKind::Octal => Err("octal escapes are not supported; use `\\x` escapes".to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn check_assertion(assertion: &Assertion) -> Result<(), String> {
+    match assertion.kind {
+        AssertionKind::StartLine
+        | AssertionKind::EndLine
+        | AssertionKind::StartText
+        | AssertionKind::EndText
+        | AssertionKind::WordBoundary
+        | AssertionKind::NotWordBoundary => Ok(()),
+        _ => Err("only the `\\b` and `\\B` word boundaries are supported".to_string()),
+    }
+}
+
+fn check_class_set(set: &ClassSet) -> Result<(), String> {
+    match set {
+        ClassSet::BinaryOp(_) => {
+            Err("class set operations (`&&`, `--`, `~~`) are not supported".to_string())
+        }
+        ClassSet::Item(item) => check_class_item(item),
+    }
+}
+
+fn check_class_item(item: &ClassSetItem) -> Result<(), String> {
+    match item {
+        ClassSetItem::Empty(_) | ClassSetItem::Perl(_) => Ok(()),
+        ClassSetItem::Literal(lit) => check_literal(&lit.kind),
+        ClassSetItem::Range(range) => {
+            check_literal(&range.start.kind)?;
+            check_literal(&range.end.kind)
+        }
+        ClassSetItem::Ascii(_) => {
+            Err("POSIX classes such as `[[:alpha:]]` are not supported; use ranges".to_string())
+        }
+        ClassSetItem::Unicode(_) => Err(unicode_class()),
+        ClassSetItem::Bracketed(_) => Err("nested classes are not supported".to_string()),
+        ClassSetItem::Union(union) => union.items.iter().try_for_each(check_class_item),
+    }
+}
+
+fn unicode_class() -> String {
+    "Unicode classes such as `\\p{L}` are not supported".to_string()
+}
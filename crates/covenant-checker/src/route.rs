@@ -0,0 +1,171 @@
+//! HTTP route triggers
+//!
+//! A `kind="fn"` snippet can handle HTTP requests when the program is
+//! deployed as a service, by naming a route in its metadata:
+//!
+//! ```text
+//! metadata
+//!   trigger route="GET /users/:id"
+//! end
+//! ```
+//!
+//! The method is optional (`trigger route="/health"` matches any method).
+//! A path segment starting with `:` captures that segment of the request
+//! path. Each of the handler's params is filled by name: from a captured
+//! segment, else from the query string, else, for a param named `body`,
+//! from the request body. The handler returns the response: a String or
+//! Json body, an Int status, or Unit for an empty response, and none of an
+//! optional result is a 404.
+//!
+//! The checker validates routes and handler shapes here;
+//! `covenant compile --target worker` maps them to a fetch handler.
+
+use covenant_ast::{ReturnType, Section, SignatureKind, Snippet, SnippetKind, Type, TypeKind};
+
+use crate::CheckError;
+
+/// Metadata key for a route trigger (written `trigger route=...`)
+pub const ROUTE_TRIGGER_KEY: &str = "trigger.route";
+
+/// Methods a route may name
+pub const ROUTE_METHODS: &[&str] = &["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+/// Types a handler's params may have, as they are read from text
+const PARAM_TYPES: &[&str] = &["String", "Int", "Bool"];
+
+/// Types a handler may return
+const RETURN_TYPES: &[&str] = &["String", "Json", "Int", "Unit"];
+
+/// A parsed route: an optional method and a path pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// Upper-case method, or `None` to match any
+    pub method: Option<String>,
+    /// Path pattern, e.g. `/users/:id`
+    pub path: String,
+}
+
+impl Route {
+    /// Parse a route, describing the problem if it is invalid
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts = text.split_whitespace();
+        let (method, path) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(path), None, None) => (None, path),
+            (Some(method), Some(path), None) => {
+                if !ROUTE_METHODS.contains(&method) {
+                    return Err(format!("unknown method '{}'; use one of {}", method, ROUTE_METHODS.join(", ")));
+                }
+                (Some(method.to_string()), path)
+            }
+            _ => return Err("expected an optional method and a path, e.g. \"GET /users/:id\"".to_string()),
+        };
+
+        if !path.starts_with('/') {
+            return Err(format!("path '{}' must start with '/'", path));
+        }
+        for segment in path.split('/').skip(1) {
+            if let Some(name) = segment.strip_prefix(':') {
+                let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if !valid {
+                    return Err(format!("':{}' does not name a path parameter", name));
+                }
+            } else if segment.contains(['?', '#', ':', '*']) {
+                return Err(format!("path segment '{}' may not contain '?', '#', ':' or '*'", segment));
+            }
+        }
+
+        Ok(Self { method, path: path.to_string() })
+    }
+
+    /// Names of the path segments the route captures, in order
+    pub fn captures(&self) -> impl Iterator<Item = &str> {
+        self.path.split('/').filter_map(|segment| segment.strip_prefix(':'))
+    }
+}
+
+/// The route a snippet handles, if any
+pub fn snippet_route(snippet: &Snippet) -> Option<&str> {
+    snippet.sections.iter().find_map(|section| match section {
+        Section::Metadata(metadata) => metadata
+            .entries
+            .iter()
+            .find(|entry| entry.key == ROUTE_TRIGGER_KEY)
+            .map(|entry| entry.value.as_str()),
+        _ => None,
+    })
+}
+
+/// Check a snippet's route trigger, if it has one
+pub fn check_route(snippet: &Snippet) -> Vec<CheckError> {
+    let Some(route) = snippet_route(snippet) else {
+        return Vec::new();
+    };
+    let mut errors = Vec::new();
+
+    if let Err(reason) = Route::parse(route) {
+        errors.push(CheckError::InvalidRoute {
+            snippet: snippet.id.clone(),
+            route: route.to_string(),
+            reason,
+        });
+    }
+
+    let reason = if snippet.kind != SnippetKind::Function {
+        Some("only kind=\"fn\" snippets can handle routes".to_string())
+    } else {
+        snippet.sections.iter().find_map(|section| match section {
+            Section::Signature(sig) => match &sig.kind {
+                SignatureKind::Function(f) => f
+                    .params
+                    .iter()
+                    .find(|param| !is_named(&param.ty, PARAM_TYPES))
+                    .map(|param| format!("param '{}' must be String, Int or Bool to be read from the request", param.name))
+                    .or_else(|| {
+                        (!returns_response(&f.returns))
+                            .then(|| format!("function '{}' must return String, Json, Int or Unit", f.name))
+                    }),
+                _ => None,
+            },
+            _ => None,
+        })
+    };
+    if let Some(reason) = reason {
+        errors.push(CheckError::UnroutableSnippet {
+            snippet: snippet.id.clone(),
+            reason,
+        });
+    }
+
+    errors
+}
+
+fn is_named(ty: &Type, names: &[&str]) -> bool {
+    matches!(&ty.kind, TypeKind::Named(path) if names.contains(&path.name()))
+}
+
+/// Whether a handler's result can be sent as a response
+fn returns_response(returns: &Option<ReturnType>) -> bool {
+    match returns {
+        None => true,
+        Some(ReturnType::Single { ty, .. }) => is_named(ty, RETURN_TYPES),
+        Some(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_routes() {
+        let route = Route::parse("GET /users/:id").unwrap();
+        assert_eq!(route.method.as_deref(), Some("GET"));
+        assert_eq!(route.captures().collect::<Vec<_>>(), vec!["id"]);
+        assert_eq!(Route::parse("/health").unwrap().method, None);
+
+        for text in ["", "users", "FETCH /users", "GET /users/:", "GET /a/:1x", "GET /a?b", "GET /a extra"] {
+            assert!(Route::parse(text).is_err(), "expected '{}' to be rejected", text);
+        }
+    }
+}
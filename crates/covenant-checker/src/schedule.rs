@@ -0,0 +1,291 @@
+//! Scheduled function triggers
+//!
+//! A `kind="fn"` snippet can ask to be run on a schedule with a cron
+//! expression in its metadata:
+//!
+//! ```text
+//! metadata
+//!   trigger schedule="0 * * * *"
+//! end
+//! ```
+//!
+//! The checker validates the expression and the function's shape here;
+//! `covenant serve` uses [`CronSchedule`] to decide when to run it.
+//! Schedules are evaluated in UTC.
+
+use covenant_ast::{Section, SignatureKind, Snippet, SnippetKind};
+
+use crate::CheckError;
+
+/// Metadata key for a schedule trigger (written `trigger schedule=...`)
+pub const SCHEDULE_TRIGGER_KEY: &str = "trigger.schedule";
+
+/// The cron expression a snippet is scheduled with, if any
+pub fn snippet_schedule(snippet: &Snippet) -> Option<&str> {
+    snippet.sections.iter().find_map(|section| match section {
+        Section::Metadata(metadata) => metadata
+            .entries
+            .iter()
+            .find(|entry| entry.key == SCHEDULE_TRIGGER_KEY)
+            .map(|entry| entry.value.as_str()),
+        _ => None,
+    })
+}
+
+/// Check a snippet's schedule trigger, if it has one
+///
+/// Scheduled snippets must be functions without parameters, since nothing
+/// supplies arguments when the schedule fires.
+pub fn check_schedule(snippet: &Snippet) -> Vec<CheckError> {
+    let Some(schedule) = snippet_schedule(snippet) else {
+        return Vec::new();
+    };
+    let mut errors = Vec::new();
+
+    if let Err(reason) = CronSchedule::parse(schedule) {
+        errors.push(CheckError::InvalidSchedule {
+            snippet: snippet.id.clone(),
+            schedule: schedule.to_string(),
+            reason,
+        });
+    }
+
+    let reason = if snippet.kind != SnippetKind::Function {
+        Some("only kind=\"fn\" snippets can be scheduled".to_string())
+    } else {
+        snippet.sections.iter().find_map(|section| match section {
+            Section::Signature(sig) => match &sig.kind {
+                SignatureKind::Function(f) if !f.params.is_empty() => Some(format!(
+                    "function '{}' takes {} parameter(s), but a schedule passes none",
+                    f.name,
+                    f.params.len()
+                )),
+                _ => None,
+            },
+            _ => None,
+        })
+    };
+    if let Some(reason) = reason {
+        errors.push(CheckError::UnschedulableSnippet {
+            snippet: snippet.id.clone(),
+            reason,
+        });
+    }
+
+    errors
+}
+
+/// A parsed five-field cron expression: minute, hour, day of month, month,
+/// day of week
+///
+/// Each field is `*`, a number, a range `a-b`, or a comma-separated list of
+/// those, optionally with a step (`*/15`, `1-10/2`). Day of week runs 0-7
+/// with both 0 and 7 meaning Sunday. As in standard cron, when both day
+/// fields are restricted a day matches if either one does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    /// Parse a cron expression, describing the problem if it is invalid
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "expected 5 fields (minute hour day month weekday), found {}",
+                fields.len()
+            ));
+        }
+
+        let weekdays = parse_field(fields[4], "weekday", 0, 7)?;
+        Ok(Self {
+            minutes: parse_field(fields[0], "minute", 0, 59)?,
+            hours: parse_field(fields[1], "hour", 0, 23)? as u32,
+            days: parse_field(fields[2], "day", 1, 31)? as u32,
+            months: parse_field(fields[3], "month", 1, 12)? as u16,
+            // Fold 7 into 0 so both mean Sunday
+            weekdays: ((weekdays | (weekdays >> 7)) & 0x7f) as u8,
+            any_day: fields[2].starts_with('*'),
+            any_weekday: fields[4].starts_with('*'),
+        })
+    }
+
+    /// The first time strictly after `after` (Unix seconds) that the
+    /// schedule fires, or None if it never does (e.g. `0 0 31 2 *`)
+    pub fn next_after(&self, after: i64) -> Option<i64> {
+        let start_minute = after.div_euclid(60) + 1;
+        let first_day = start_minute.div_euclid(1440);
+
+        // Every month/day/weekday combination recurs within 28 years
+        for day in first_day..first_day + 366 * 28 {
+            if !self.matches_day(day) {
+                continue;
+            }
+            let from = if day == first_day { start_minute.rem_euclid(1440) } else { 0 };
+            for minute_of_day in from..1440 {
+                let (hour, minute) = (minute_of_day / 60, minute_of_day % 60);
+                if self.hours & (1 << hour) != 0 && self.minutes & (1 << minute) != 0 {
+                    return Some((day * 1440 + minute_of_day) * 60);
+                }
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, day: i64) -> bool {
+        let (_, month, day_of_month) = civil_from_days(day);
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+        // 1970-01-01 was a Thursday
+        let weekday = (day + 4).rem_euclid(7);
+        let day_matches = self.days & (1 << day_of_month) != 0;
+        let weekday_matches = self.weekdays & (1 << weekday) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day_matches || weekday_matches,
+            _ => day_matches && weekday_matches,
+        }
+    }
+}
+
+/// Parse one cron field into a bitmask of allowed values
+fn parse_field(field: &str, name: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid step '{}' in {} field", step, name))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_value(a, name, min, max)?, parse_value(b, name, min, max)?)
+        } else {
+            let value = parse_value(range, name, min, max)?;
+            // `5/10` means every 10 starting at 5
+            (value, if step > 1 { max } else { value })
+        };
+        if start > end {
+            return Err(format!("range {}-{} in {} field runs backwards", start, end, name));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(text: &str, name: &str, min: u32, max: u32) -> Result<u32, String> {
+    match text.parse::<u32>() {
+        Ok(value) if (min..=max).contains(&value) => Ok(value),
+        Ok(value) => Err(format!("{} {} is out of range {}-{}", name, value, min, max)),
+        Err(_) => Err(format!("invalid {} '{}'", name, text)),
+    }
+}
+
+/// Format Unix seconds as an ISO 8601 UTC timestamp
+pub fn format_utc(unix: i64) -> String {
+    let (year, month, day) = civil_from_days(unix.div_euclid(86_400));
+    let secs = unix.rem_euclid(86_400);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Convert days since 1970-01-01 to (year, month, day)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's days-to-civil algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01T00:00:00Z, a Monday
+    const NEW_YEAR_2024: i64 = 1_704_067_200;
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc(NEW_YEAR_2024 + 3_723), "2024-01-01T01:02:03Z");
+    }
+
+    #[test]
+    fn test_hourly_schedule() {
+        let cron = CronSchedule::parse("0 * * * *").unwrap();
+        assert_eq!(cron.next_after(NEW_YEAR_2024), Some(NEW_YEAR_2024 + 3_600));
+        assert_eq!(cron.next_after(NEW_YEAR_2024 - 1), Some(NEW_YEAR_2024));
+    }
+
+    #[test]
+    fn test_steps_lists_and_weekdays() {
+        let cron = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+        assert_eq!(format_utc(cron.next_after(NEW_YEAR_2024).unwrap()), "2024-01-01T09:00:00Z");
+        assert_eq!(format_utc(cron.next_after(NEW_YEAR_2024 + 9 * 3600).unwrap()), "2024-01-01T09:15:00Z");
+
+        // Saturday 2024-01-06 skips to Monday
+        let saturday = NEW_YEAR_2024 + 5 * 86_400;
+        assert_eq!(format_utc(cron.next_after(saturday).unwrap()), "2024-01-08T09:00:00Z");
+
+        // 7 is Sunday as well as 0
+        let sunday = CronSchedule::parse("30 6 * * 7").unwrap();
+        assert_eq!(format_utc(sunday.next_after(NEW_YEAR_2024).unwrap()), "2024-01-07T06:30:00Z");
+    }
+
+    #[test]
+    fn test_day_fields_match_either() {
+        // The 15th, or any Friday
+        let cron = CronSchedule::parse("0 0 15 * 5").unwrap();
+        assert_eq!(format_utc(cron.next_after(NEW_YEAR_2024).unwrap()), "2024-01-05T00:00:00Z");
+        assert_eq!(
+            format_utc(cron.next_after(NEW_YEAR_2024 + 12 * 86_400).unwrap()),
+            "2024-01-15T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn test_impossible_and_leap_dates() {
+        assert_eq!(CronSchedule::parse("0 0 31 2 *").unwrap().next_after(NEW_YEAR_2024), None);
+        let leap = CronSchedule::parse("0 0 29 2 *").unwrap();
+        assert_eq!(format_utc(leap.next_after(NEW_YEAR_2024 + 366 * 86_400).unwrap()), "2028-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for expr in ["", "* * * *", "60 * * * *", "* 24 * * *", "* * 0 * *", "5-1 * * * *", "*/0 * * * *", "a * * * *"] {
+            assert!(CronSchedule::parse(expr).is_err(), "expected '{}' to be rejected", expr);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Sensitive values passed to logging calls
+//!
+//! Params and struct fields marked `sensitive` hold secrets. The runtime
+//! keeps them out of audit logs and traces, but a function can still print
+//! one through a call that declares a [`LOG_EFFECTS`] effect, such as
+//! `console.println`. [`check_sensitive_flows`] follows sensitive values
+//! through each body and warns where one reaches such a call.
+//!
+//! A value is sensitive when it is a sensitive param, a sensitive field of
+//! a struct binding, or a struct with sensitive fields. Sensitivity follows
+//! binds, compute steps and calls into `text.*`, which build strings from
+//! their inputs; any other call's result is clean, so logging a hash of a
+//! secret is not flagged.
+
+use std::collections::{HashMap, HashSet};
+
+use covenant_ast::{
+    walk_step, BindSource, InputSource, ReturnType, Section, Sensitive, SignatureKind, Snippet, Span, Step,
+    StepKind, Type, Visitor,
+};
+
+/// Effects whose calls write their arguments somewhere people read
+pub const LOG_EFFECTS: &[&str] = &["console", "log"];
+
+/// A sensitive value passed to a logging call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SensitiveFlowWarning {
+    /// Snippet containing the call
+    pub snippet: String,
+    /// ID of the call step
+    pub step: String,
+    /// The logging function called
+    pub callee: String,
+    /// The sensitive value passed, e.g. "token" or "creds.password"
+    pub value: String,
+    /// Source span of the call step
+    pub span: Span,
+}
+
+impl SensitiveFlowWarning {
+    /// Warning code for machine-readable output
+    pub fn code(&self) -> &'static str {
+        "W-SENS-001"
+    }
+
+    /// Human-readable description of the problem
+    pub fn message(&self) -> String {
+        format!(
+            "step '{}' in `{}` passes sensitive value `{}` to `{}`, which writes to a log",
+            self.step, self.snippet, self.value, self.callee
+        )
+    }
+
+    /// Suggested fix
+    pub fn suggestion(&self) -> &'static str {
+        "log a non-sensitive field instead, or mask the value before logging it"
+    }
+}
+
+/// Check the bodies and tests of `snippets` for sensitive values passed to
+/// logging calls
+pub fn check_sensitive_flows(snippets: &[Snippet]) -> Vec<SensitiveFlowWarning> {
+    let sensitive = Sensitive::from_snippets(snippets);
+    if sensitive.is_empty() {
+        return Vec::new();
+    }
+    let snippets = &*covenant_lower::lower_snippets(snippets);
+
+    let mut loggers = HashSet::new();
+    let mut returns = HashMap::new();
+    let std_console = covenant_parser::parse(include_str!("../../../runtime/std/console/console.cov"));
+    let std_snippets = match &std_console {
+        Ok(covenant_ast::Program::Snippets { snippets, .. }) => snippets.as_slice(),
+        _ => &[],
+    };
+    for snippet in std_snippets.iter().chain(snippets) {
+        let Some((name, f)) = function_signature(snippet) else { continue };
+        if declares_log_effect(snippet) {
+            loggers.insert(snippet.id.clone());
+            loggers.insert(name.to_string());
+        }
+        if let Some(ReturnType::Single { ty, .. }) = f {
+            returns.insert(snippet.id.clone(), ty.clone());
+            returns.entry(name.to_string()).or_insert_with(|| ty.clone());
+        }
+    }
+
+    let mut warnings = Vec::new();
+    for snippet in snippets {
+        let mut flow = Flow {
+            sensitive: &sensitive,
+            loggers: &loggers,
+            returns: &returns,
+            snippet: &snippet.id,
+            types: HashMap::new(),
+            tainted: HashMap::new(),
+            warnings: &mut warnings,
+        };
+        for section in &snippet.sections {
+            if let Section::Signature(sig) = section {
+                if let SignatureKind::Function(f) = &sig.kind {
+                    for param in &f.params {
+                        flow.types.insert(param.name.clone(), param.ty.clone());
+                        if param.sensitive {
+                            flow.tainted.insert(param.name.clone(), param.name.clone());
+                        }
+                    }
+                }
+            }
+        }
+        flow.visit_snippet(snippet);
+    }
+    warnings
+}
+
+/// A snippet's function name and declared result, if it has a function signature
+fn function_signature(snippet: &Snippet) -> Option<(&str, Option<&ReturnType>)> {
+    snippet.sections.iter().find_map(|section| match section {
+        Section::Signature(sig) => match &sig.kind {
+            SignatureKind::Function(f) => Some((f.name.as_str(), f.returns.as_ref())),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+fn declares_log_effect(snippet: &Snippet) -> bool {
+    snippet.sections.iter().any(|section| match section {
+        Section::Effects(effects) => effects.effects.iter().any(|e| LOG_EFFECTS.contains(&e.name.as_str())),
+        _ => false,
+    })
+}
+
+/// Sensitive bindings of one snippet, followed in step order
+struct Flow<'a> {
+    sensitive: &'a Sensitive,
+    loggers: &'a HashSet<String>,
+    returns: &'a HashMap<String, Type>,
+    snippet: &'a str,
+    /// Declared or inferred type of each binding, where known
+    types: HashMap<String, Type>,
+    /// Bindings holding a sensitive value, with the value they came from
+    tainted: HashMap<String, String>,
+    warnings: &'a mut Vec<SensitiveFlowWarning>,
+}
+
+impl Flow<'_> {
+    /// The sensitive value `source` reads, if any
+    fn sensitive_value(&self, source: &InputSource) -> Option<String> {
+        match source {
+            InputSource::Var(var) => self.var_value(var),
+            InputSource::Field { of, field } => {
+                let sensitive_field = self
+                    .types
+                    .get(of)
+                    .and_then(|ty| self.sensitive.struct_of(ty))
+                    .is_some_and(|name| self.sensitive.is_field(name, field));
+                if sensitive_field {
+                    Some(format!("{}.{}", of, field))
+                } else {
+                    self.tainted.get(of).cloned()
+                }
+            }
+            InputSource::Lit(_) => None,
+        }
+    }
+
+    fn var_value(&self, var: &str) -> Option<String> {
+        if let Some(value) = self.tainted.get(var) {
+            return Some(value.clone());
+        }
+        let ty = self.types.get(var)?;
+        self.sensitive.is_type(ty).then(|| var.to_string())
+    }
+
+    fn bind(&mut self, binding: &str, value: Option<String>, ty: Option<Type>) {
+        if binding == "_" {
+            return;
+        }
+        match value {
+            Some(value) => self.tainted.insert(binding.to_string(), value),
+            None => self.tainted.remove(binding),
+        };
+        match ty {
+            Some(ty) => self.types.insert(binding.to_string(), ty),
+            None => self.types.remove(binding),
+        };
+    }
+}
+
+impl Visitor for Flow<'_> {
+    fn visit_step(&mut self, step: &Step) {
+        let binding = step.output_binding.as_str();
+        match &step.kind {
+            StepKind::Bind(bind) => {
+                let (value, ty) = match &bind.source {
+                    BindSource::Var(var) => (self.var_value(var), self.types.get(var).cloned()),
+                    BindSource::Field { of, field } => {
+                        (self.sensitive_value(&InputSource::Field { of: of.clone(), field: field.clone() }), None)
+                    }
+                    _ => (None, None),
+                };
+                self.bind(binding, value, ty);
+            }
+            StepKind::Compute(compute) => {
+                let value = compute.inputs.iter().find_map(|input| self.sensitive_value(&input.source));
+                self.bind(binding, value, None);
+            }
+            StepKind::Construct(construct) => self.bind(binding, None, Some(construct.ty.clone())),
+            StepKind::Call(call) => {
+                let values: Vec<String> =
+                    call.args.iter().filter_map(|arg| self.sensitive_value(&arg.source)).collect();
+                if self.loggers.contains(&call.fn_name) {
+                    for value in &values {
+                        self.warnings.push(SensitiveFlowWarning {
+                            snippet: self.snippet.to_string(),
+                            step: step.id.clone(),
+                            callee: call.fn_name.clone(),
+                            value: value.clone(),
+                            span: step.span,
+                        });
+                    }
+                }
+                let value = values.into_iter().next().filter(|_| call.fn_name.starts_with("text."));
+                self.bind(binding, value, self.returns.get(&call.fn_name).cloned());
+            }
+            _ => {}
+        }
+        walk_step(self, step);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::snippets;
+
+    const SOURCE: &str = r#"snippet id="auth.Credentials" kind="struct"
+signature
+  struct name="Credentials"
+    field name="user" type="String"
+    field name="password" type="String" sensitive
+  end
+end
+end
+
+snippet id="auth.login" kind="fn"
+effects
+  effect console
+end
+signature
+  fn name="login"
+    param name="creds" type="Credentials"
+    param name="token" type="String" sensitive
+    returns type="Unit"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="console.println"
+    arg name="message" from="creds.user"
+    as="_"
+  end
+  step id="s2" kind="call"
+    fn="console.println"
+    arg name="message" from="creds.password"
+    as="_"
+  end
+  step id="s3" kind="call"
+    fn="text.concat"
+    arg name="a" lit="token: "
+    arg name="b" from="token"
+    as="line"
+  end
+  step id="s4" kind="call"
+    fn="console.println"
+    arg name="message" from="line"
+    as="_"
+  end
+  step id="s5" kind="call"
+    fn="crypto.sha256"
+    arg name="data" from="token"
+    as="digest"
+  end
+  step id="s6" kind="call"
+    fn="console.println"
+    arg name="message" from="digest"
+    as="_"
+  end
+  step id="s7" kind="return"
+    lit=none
+    as="_"
+  end
+end
+end
+"#;
+
+    #[test]
+    fn test_sensitive_values_reaching_logs() {
+        let warnings = check_sensitive_flows(&snippets(SOURCE));
+        let flagged: Vec<(&str, &str)> = warnings.iter().map(|w| (w.step.as_str(), w.value.as_str())).collect();
+        assert_eq!(flagged, vec![("s2", "creds.password"), ("s4", "token")]);
+        assert_eq!(warnings[0].callee, "console.println");
+        assert_eq!(warnings[0].code(), "W-SENS-001");
+    }
+
+    #[test]
+    fn test_no_sensitive_declarations() {
+        let source = SOURCE.replace(" sensitive\n", "\n");
+        assert!(check_sensitive_flows(&snippets(&source)).is_empty());
+    }
+}
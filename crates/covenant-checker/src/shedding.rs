@@ -0,0 +1,243 @@
+//! Rate limits and circuit breakers on externs
+//!
+//! An `extern-abstract` or `extern` snippet can protect the service behind
+//! it by declaring limits in its metadata:
+//!
+//! ```text
+//! metadata
+//!   rate_limit="100/m"
+//!   circuit_breaker="5/30s"
+//! end
+//! ```
+//!
+//! `rate_limit` admits at most that many calls per window (`s`, `m` or `h`,
+//! optionally with a count: `10/15s`). `circuit_breaker` opens after that
+//! many consecutive failed calls and sheds every call for the cooldown, then
+//! lets one trial call through. The runtime keeps this state per extern.
+//!
+//! A shed call never reaches the host: it returns the [`SHED_ERROR_TYPE`]
+//! member of the extern's result union instead, so the extern must declare
+//! that member and its callers handle shedding like any other error.
+
+use covenant_ast::{ReturnType, Section, SignatureKind, Snippet, SnippetKind, TypeKind};
+
+use crate::CheckError;
+
+/// Metadata key for an extern's rate limit
+pub const RATE_LIMIT_KEY: &str = "rate_limit";
+
+/// Metadata key for an extern's circuit breaker
+pub const CIRCUIT_BREAKER_KEY: &str = "circuit_breaker";
+
+/// The union member a shed call returns
+pub const SHED_ERROR_TYPE: &str = "ShedError";
+
+/// At most `calls` calls in any `window_secs` seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub calls: u32,
+    pub window_secs: u32,
+}
+
+impl RateLimit {
+    /// Parse `<calls>/<window>`, e.g. "100/m" or "10/15s"
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (calls, window_secs) = parse_policy(text, "calls")?;
+        Ok(Self { calls, window_secs })
+    }
+}
+
+/// Open after `failures` consecutive failed calls, for `cooldown_secs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    pub failures: u32,
+    pub cooldown_secs: u32,
+}
+
+impl CircuitBreaker {
+    /// Parse `<failures>/<cooldown>`, e.g. "5/30s"
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (failures, cooldown_secs) = parse_policy(text, "failures")?;
+        Ok(Self { failures, cooldown_secs })
+    }
+}
+
+/// A snippet's metadata value for `key`, if any
+fn metadata_value<'a>(snippet: &'a Snippet, key: &str) -> Option<&'a str> {
+    snippet.sections.iter().find_map(|section| match section {
+        Section::Metadata(metadata) => {
+            metadata.entries.iter().find(|entry| entry.key == key).map(|entry| entry.value.as_str())
+        }
+        _ => None,
+    })
+}
+
+/// The rate limit a snippet declares, if any
+pub fn snippet_rate_limit(snippet: &Snippet) -> Option<&str> {
+    metadata_value(snippet, RATE_LIMIT_KEY)
+}
+
+/// The circuit breaker a snippet declares, if any
+pub fn snippet_circuit_breaker(snippet: &Snippet) -> Option<&str> {
+    metadata_value(snippet, CIRCUIT_BREAKER_KEY)
+}
+
+/// Check a snippet's rate limit and circuit breaker, if it has either
+///
+/// Only externs that declare effects are called through the runtime's
+/// audited imports, so only those can be shed, and their result must be a
+/// union with a `ShedError` member for the shed call to return.
+pub fn check_shedding(snippet: &Snippet) -> Vec<CheckError> {
+    let rate_limit = snippet_rate_limit(snippet);
+    let circuit_breaker = snippet_circuit_breaker(snippet);
+    if rate_limit.is_none() && circuit_breaker.is_none() {
+        return Vec::new();
+    }
+    let mut errors = Vec::new();
+
+    let invalid = [
+        rate_limit.and_then(|value| RateLimit::parse(value).err().map(|reason| (RATE_LIMIT_KEY, value, reason))),
+        circuit_breaker
+            .and_then(|value| CircuitBreaker::parse(value).err().map(|reason| (CIRCUIT_BREAKER_KEY, value, reason))),
+    ];
+    for (key, value, reason) in invalid.into_iter().flatten() {
+        errors.push(CheckError::InvalidShedPolicy {
+            snippet: snippet.id.clone(),
+            key: key.to_string(),
+            value: value.to_string(),
+            reason,
+        });
+    }
+
+    if let Some(reason) = unsheddable_reason(snippet) {
+        errors.push(CheckError::UnsheddableSnippet {
+            snippet: snippet.id.clone(),
+            reason,
+        });
+    }
+
+    errors
+}
+
+/// Why calls to `snippet` cannot be shed, if they cannot
+fn unsheddable_reason(snippet: &Snippet) -> Option<String> {
+    if !matches!(snippet.kind, SnippetKind::Extern | SnippetKind::ExternAbstract) {
+        return Some("only kind=\"extern\" and kind=\"extern-abstract\" snippets can be shed".to_string());
+    }
+    let has_effects = snippet.sections.iter().any(|section| match section {
+        Section::Effects(effects) => !effects.effects.is_empty(),
+        _ => false,
+    });
+    if !has_effects {
+        return Some("it declares no effects, so the runtime does not intercept its calls".to_string());
+    }
+
+    let returns = snippet.sections.iter().find_map(|section| match section {
+        Section::Signature(sig) => match &sig.kind {
+            SignatureKind::Function(f) => Some(&f.returns),
+            _ => None,
+        },
+        _ => None,
+    });
+    let has_shed_member = match returns {
+        Some(Some(ReturnType::Union { types })) => types.iter().any(|member| {
+            matches!(&member.ty.kind, TypeKind::Named(path) if path.name() == SHED_ERROR_TYPE)
+        }),
+        _ => false,
+    };
+    if !has_shed_member {
+        return Some(format!("its result must be a union with a {} member for shed calls to return", SHED_ERROR_TYPE));
+    }
+    None
+}
+
+/// Parse `<count>/<duration>` into the count and the duration in seconds
+fn parse_policy(text: &str, what: &str) -> Result<(u32, u32), String> {
+    let (count, duration) = text
+        .split_once('/')
+        .ok_or_else(|| format!("expected <{}>/<duration>, e.g. \"10/s\"", what))?;
+    let count: u32 = count
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number of {}", count.trim(), what))?;
+    if count == 0 {
+        return Err(format!("the number of {} must be at least 1", what));
+    }
+    Ok((count, parse_duration(duration.trim())?))
+}
+
+/// Parse a duration such as "s", "30s", "m" or "2h" into seconds
+fn parse_duration(text: &str) -> Result<u32, String> {
+    let unit_secs = match text.chars().last() {
+        Some('s') => 1,
+        Some('m') => 60,
+        Some('h') => 3600,
+        _ => return Err(format!("duration '{}' must end in s, m or h", text)),
+    };
+    let amount = &text[..text.len() - 1];
+    let amount: u32 = if amount.is_empty() {
+        1
+    } else {
+        amount.parse().map_err(|_| format!("'{}' is not a number in duration '{}'", amount, text))?
+    };
+    if amount == 0 {
+        return Err(format!("duration '{}' must be longer than zero", text));
+    }
+    amount
+        .checked_mul(unit_secs)
+        .ok_or_else(|| format!("duration '{}' is too long", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_snippet(source: &str) -> Snippet {
+        match covenant_parser::parse(source).unwrap() {
+            covenant_ast::Program::Snippets { mut snippets, .. } => snippets.remove(0),
+            _ => panic!("expected snippets"),
+        }
+    }
+
+    fn extern_snippet(kind: &str, effects: &str, returns: &str, metadata: &str) -> Snippet {
+        first_snippet(&format!(
+            "snippet id=\"weather.fetch\" kind=\"{kind}\"\n{effects}signature\n  fn name=\"fetch\"\n    param name=\"city\" type=\"String\"\n{returns}  end\nend\nmetadata\n{metadata}end\nend\n"
+        ))
+    }
+
+    const NETWORK: &str = "effects\n  effect network\nend\n";
+    const SHED_UNION: &str = "    returns union\n      type=\"String\"\n      type=\"ShedError\"\n    end\n";
+
+    #[test]
+    fn test_parse_policies() {
+        assert_eq!(RateLimit::parse("100/m").unwrap(), RateLimit { calls: 100, window_secs: 60 });
+        assert_eq!(RateLimit::parse("10/15s").unwrap(), RateLimit { calls: 10, window_secs: 15 });
+        assert_eq!(CircuitBreaker::parse("5/30s").unwrap(), CircuitBreaker { failures: 5, cooldown_secs: 30 });
+        assert_eq!(CircuitBreaker::parse("3/2h").unwrap().cooldown_secs, 7200);
+
+        for text in ["", "100", "0/s", "x/s", "10/", "10/5", "10/0s", "10/5d", "-1/s"] {
+            assert!(RateLimit::parse(text).is_err(), "expected '{}' to be rejected", text);
+        }
+    }
+
+    #[test]
+    fn test_check_shedding() {
+        let ok = extern_snippet("extern-abstract", NETWORK, SHED_UNION, "  rate_limit=\"2/s\"\n  circuit_breaker=\"3/30s\"\n");
+        assert!(check_shedding(&ok).is_empty());
+
+        let invalid = extern_snippet("extern", NETWORK, SHED_UNION, "  rate_limit=\"fast\"\n");
+        assert!(matches!(
+            check_shedding(&invalid).as_slice(),
+            [CheckError::InvalidShedPolicy { key, .. }] if key == RATE_LIMIT_KEY
+        ));
+
+        let no_member = extern_snippet("extern", NETWORK, "    returns type=\"String\"\n", "  circuit_breaker=\"5/m\"\n");
+        assert!(matches!(check_shedding(&no_member).as_slice(), [CheckError::UnsheddableSnippet { .. }]));
+
+        let pure = extern_snippet("extern", "", SHED_UNION, "  rate_limit=\"1/s\"\n");
+        assert!(matches!(check_shedding(&pure).as_slice(), [CheckError::UnsheddableSnippet { .. }]));
+
+        let unlimited = extern_snippet("extern", NETWORK, "    returns type=\"String\"\n", "  contract=\"x@1\"\n");
+        assert!(check_shedding(&unlimited).is_empty());
+    }
+}
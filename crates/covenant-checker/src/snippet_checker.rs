@@ -4,27 +4,56 @@
 
 use std::collections::{HashMap, HashSet};
 use covenant_ast::{
-    Snippet, SnippetKind, Section, SignatureKind, BodySection,
+    step_beyond_depth, step_nesting_depth, CancellationToken, Limits, Snippet, SnippetKind, Section, SignatureKind, BodySection,
     Step, StepKind, ComputeStep, Operation, Input, InputSource, CallStep,
     ReturnStep, ReturnValue, IfStep, ForStep, BindStep, BindSource, MatchStep, MatchPattern,
     FunctionSignature, ReturnType, Type, TypeKind, Literal, QueryStep, QueryContent,
-    StructSignature, EnumSignature, StructConstruction,
+    StructSignature, EnumSignature, StructConstruction, TransactionStep, VariantConstruction, LambdaStep,
+    UsingStep, Span, RaiseStep, ConstSignature, InterfaceSignature, AliasSignature, Visitor, walk_type,
 };
-use crate::{CheckError, CheckResult, ResolvedType, SymbolTable, SymbolKind, EffectTable, TypeRegistry, VariantDef};
+use covenant_ast::printer::ToCov;
+use crate::conversions::{to_float_rounds, to_int_truncates};
+use crate::regex::validate_pattern;
+use crate::{check_replay_safety, check_route, check_schedule, check_shedding, CheckError, MAX_DECIMAL_PLACES, CheckResult, ConversionKind, ConversionWarning, ResolvedType, SymbolTable, SymbolKind, EffectTable, TypeRegistry, VariantDef};
 
 /// Checker for snippet-mode programs
 pub struct SnippetChecker {
     symbols: SymbolTable,
     effects: EffectTable,
     errors: Vec<CheckError>,
+    /// Lossy numeric conversions, reported alongside a successful check
+    warnings: Vec<ConversionWarning>,
+    /// ID of the snippet being checked
+    current_snippet: String,
     /// Local scope for current function body
     locals: HashMap<String, ResolvedType>,
+    /// Bool bindings of `is_some`/`is_none` steps: the optional binding they
+    /// check, and whether the check is `is_some`
+    optional_checks: HashMap<String, (String, bool)>,
     /// Map of function names to their return types (for recursive calls)
     function_returns: HashMap<String, ResolvedType>,
+    /// Generic param names of generic functions, by function name
+    function_generics: HashMap<String, Vec<String>>,
     /// Registry of struct and enum type definitions
     type_registry: TypeRegistry,
+    /// Declared types and values of const snippets, by snippet ID
+    constants: HashMap<String, (ResolvedType, Literal)>,
+    /// Method signatures of interface snippets, by snippet ID
+    interfaces: HashMap<String, Vec<FunctionSignature>>,
+    /// Target types of alias snippets, by alias name
+    type_aliases: HashMap<String, Type>,
+    /// Base type and unit of each unit alias declared in a types section
+    unit_types: HashMap<String, (ResolvedType, String)>,
     /// Expected return type for current function being checked
     current_return_type: Option<ResolvedType>,
+    /// Effects declared by the current function being checked
+    current_effects: Vec<String>,
+    /// Number of `for` bodies enclosing the step being checked
+    loop_depth: usize,
+    /// Polled between snippets; checking stops once it is cancelled
+    cancel: Option<CancellationToken>,
+    /// Structural limits; bodies nested too deeply are rejected unchecked
+    limits: Limits,
 }
 
 impl SnippetChecker {
@@ -33,15 +62,43 @@ impl SnippetChecker {
             symbols: SymbolTable::new(),
             effects: EffectTable::new(),
             errors: Vec::new(),
+            warnings: Vec::new(),
+            current_snippet: String::new(),
             locals: HashMap::new(),
+            optional_checks: HashMap::new(),
             function_returns: HashMap::new(),
+            function_generics: HashMap::new(),
             type_registry: TypeRegistry::new(),
+            constants: HashMap::new(),
+            interfaces: HashMap::new(),
+            type_aliases: HashMap::new(),
+            unit_types: HashMap::new(),
             current_return_type: None,
+            current_effects: Vec::new(),
+            loop_depth: 0,
+            cancel: None,
+            limits: Limits::default(),
         };
         checker.register_stdlib();
         checker
     }
 
+    /// Stop checking with `CheckError::Cancelled` once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Override the default structural limits
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+
     /// Load and register standard library function signatures from embedded .cov files
     fn register_stdlib(&mut self) {
         const STDLIB_SOURCES: &[&str] = &[
@@ -50,7 +107,10 @@ impl SnippetChecker {
             include_str!("../../../runtime/std/path/path.cov"),
             include_str!("../../../runtime/std/text/regex.cov"),
             include_str!("../../../runtime/std/text/text.cov"),
+            include_str!("../../../runtime/std/text/locale.cov"),
+            include_str!("../../../runtime/std/crypto/crypto.cov"),
             include_str!("../../../runtime/std/list/list.cov"),
+            include_str!("../../../runtime/std/events/events.cov"),
         ];
 
         for source in STDLIB_SOURCES {
@@ -69,10 +129,19 @@ impl SnippetChecker {
         }
     }
 
-    /// Check all snippets and return the result
+    /// Lower and check all snippets, and return the result
     pub fn check_snippets(mut self, snippets: &[Snippet]) -> Result<CheckResult, Vec<CheckError>> {
+        let snippets = &*covenant_lower::lower_snippets(snippets);
+
+        // Aliases first, so every signature can name them
+        self.register_type_aliases(snippets);
+
         // First pass: register all types and function signatures
         for snippet in snippets {
+            if self.is_cancelled() {
+                return Err(vec![CheckError::Cancelled]);
+            }
+            self.register_unit_types(snippet);
             match snippet.kind {
                 SnippetKind::Extern | SnippetKind::ExternAbstract => {
                     // Extern snippets must have namespaced IDs (contain a dot)
@@ -83,29 +152,42 @@ impl SnippetChecker {
                     }
                     self.register_function_signature(snippet);
                 }
-                SnippetKind::Function => {
+                SnippetKind::Function | SnippetKind::Workflow => {
                     self.register_function_signature(snippet);
                 }
                 SnippetKind::Struct => self.register_struct_type(snippet),
                 SnippetKind::Enum => self.register_enum_type(snippet),
+                SnippetKind::Const => self.register_constant(snippet),
+                SnippetKind::Interface => self.register_interface(snippet),
                 _ => {}
             }
         }
 
         // Second pass: type check bodies
         for snippet in snippets {
+            if self.is_cancelled() {
+                return Err(vec![CheckError::Cancelled]);
+            }
+            self.errors.extend(check_schedule(snippet));
+            self.errors.extend(check_route(snippet));
+            self.errors.extend(check_shedding(snippet));
             match snippet.kind {
-                SnippetKind::Function => self.check_function_snippet(snippet),
+                SnippetKind::Function | SnippetKind::Workflow => self.check_function_snippet(snippet),
                 SnippetKind::Struct => self.check_struct_snippet(snippet),
                 SnippetKind::Enum => self.check_enum_snippet(snippet),
+                SnippetKind::Subscriber => self.check_subscriber_snippet(snippet),
                 _ => {} // Skip other kinds for now
             }
+            if snippet.kind != SnippetKind::ExternImpl {
+                self.check_implementation(snippet, snippets);
+            }
         }
 
         if self.errors.is_empty() {
             Ok(CheckResult {
                 symbols: self.symbols,
                 effects: self.effects,
+                warnings: self.warnings,
             })
         } else {
             Err(self.errors)
@@ -115,7 +197,7 @@ impl SnippetChecker {
     /// Register a function's signature (first pass)
     fn register_function_signature(&mut self, snippet: &Snippet) {
         // Extract signature info without holding references
-        let (fn_name, snippet_id, params, return_type, effects) = {
+        let (fn_name, snippet_id, params, return_type, effects, generics) = {
             let sig = match find_function_signature(snippet) {
                 Some(s) => s,
                 None => return,
@@ -130,10 +212,16 @@ impl SnippetChecker {
                 .collect();
 
             let effects = collect_snippet_effects(snippet);
+            let generics: Vec<String> = sig.generics.iter().map(|g| g.name.clone()).collect();
 
-            (sig.name.clone(), snippet.id.clone(), params, return_type, effects)
+            (sig.name.clone(), snippet.id.clone(), params, return_type, effects, generics)
         };
 
+        if !generics.is_empty() {
+            self.function_generics.insert(fn_name.clone(), generics.clone());
+            self.function_generics.insert(snippet_id.clone(), generics);
+        }
+
         // Register by short name (for backwards compatibility)
         self.symbols.define(
             fn_name.clone(),
@@ -172,6 +260,20 @@ impl SnippetChecker {
                 None => return, // External functions may not have bodies
             };
 
+            // Step checking recurses per nesting level; refuse bodies that would
+            // overflow the stack instead of crashing
+            let depth = step_nesting_depth(&body.steps);
+            if depth > self.limits.max_nesting_depth {
+                let limit = self.limits.max_nesting_depth;
+                self.errors.push(CheckError::NestingTooDeep {
+                    snippet: snippet.id.clone(),
+                    depth,
+                    limit,
+                    span: step_beyond_depth(&body.steps, limit).map_or(body.span, |step| step.span),
+                });
+                return;
+            }
+
             let params_info: Vec<(String, ResolvedType)> = sig.params.iter()
                 .map(|p| (p.name.clone(), self.resolve_type(&p.ty)))
                 .collect();
@@ -184,20 +286,249 @@ impl SnippetChecker {
 
         // Set up local scope with parameters
         self.locals.clear();
+        self.optional_checks.clear();
         for (name, ty) in params_info {
             self.locals.insert(name, ty);
         }
 
-        // Set expected return type for this function
+        // Set expected return type and declared effects for this function
         self.current_return_type = expected_return;
+        self.current_effects = collect_snippet_effects(snippet);
+        self.current_snippet = snippet.id.clone();
 
         // Check each step
         for step in &steps_cloned {
             self.check_step(step);
         }
 
-        // Clear expected return type after checking
+        if snippet.kind == SnippetKind::Workflow {
+            let errors = check_replay_safety(&snippet.id, &steps_cloned, |name| {
+                matches!(
+                    self.symbols.lookup(name).map(|s| &s.kind),
+                    Some(SymbolKind::Function { effects, .. }) if !effects.is_empty()
+                )
+            });
+            self.errors.extend(errors);
+        }
+
+        // Clear per-function state after checking
         self.current_return_type = None;
+        self.current_effects.clear();
+        self.loop_depth = 0;
+    }
+
+    /// Check a subscriber snippet: the event must be a struct and the handler
+    /// a function taking exactly one parameter of that struct type
+    fn check_subscriber_snippet(&mut self, snippet: &Snippet) {
+        let Some(subscribe) = snippet.sections.iter().find_map(|section| match section {
+            Section::Subscribe(s) => Some(s),
+            _ => None,
+        }) else {
+            self.errors.push(CheckError::InvalidSubscription {
+                subscriber: snippet.id.clone(),
+                reason: "missing subscribe section".to_string(),
+            });
+            return;
+        };
+
+        let Some(event_ty) = self.event_struct_type(&subscribe.event) else {
+            self.errors.push(CheckError::InvalidSubscription {
+                subscriber: snippet.id.clone(),
+                reason: format!("event type '{}' is not a struct", subscribe.event),
+            });
+            return;
+        };
+
+        let params = match self.symbols.lookup(&subscribe.handler).map(|s| &s.kind) {
+            Some(SymbolKind::Function { params, .. }) => params.clone(),
+            _ => {
+                self.errors.push(CheckError::UndefinedSymbol {
+                    name: subscribe.handler.clone(),
+                });
+                return;
+            }
+        };
+
+        let reason = match params.as_slice() {
+            [(_, ty)] if self.types_compatible(ty, &event_ty) => None,
+            [(name, ty)] => Some(format!(
+                "handler '{}' takes '{}' as {}, not {}",
+                subscribe.handler,
+                name,
+                ty.display(),
+                event_ty.display()
+            )),
+            _ => Some(format!(
+                "handler '{}' takes {} parameter(s), but an event delivers one",
+                subscribe.handler,
+                params.len()
+            )),
+        };
+        if let Some(reason) = reason {
+            self.errors.push(CheckError::InvalidSubscription {
+                subscriber: snippet.id.clone(),
+                reason,
+            });
+        }
+    }
+
+    /// Register the method signatures of an interface snippet (first pass)
+    fn register_interface(&mut self, snippet: &Snippet) {
+        if let Some(iface) = find_interface_signature(snippet) {
+            self.interfaces.insert(snippet.id.clone(), iface.methods.clone());
+        }
+    }
+
+    /// Check a fn or struct snippet against the interface it implements
+    ///
+    /// A function must match the interface method of the same name, with
+    /// `Self` standing for one type throughout. A struct must have, for each
+    /// method, a function implementing it with `Self` bound to the struct.
+    fn check_implementation(&mut self, snippet: &Snippet, snippets: &[Snippet]) {
+        let Some(interface) = &snippet.implements else {
+            return;
+        };
+        let error = |reason: String| CheckError::InvalidImplementation {
+            snippet: snippet.id.clone(),
+            interface: interface.clone(),
+            reason,
+        };
+        let Some(methods) = self.interfaces.get(interface).cloned() else {
+            self.errors.push(error(format!("'{}' is not an interface", interface)));
+            return;
+        };
+
+        if snippet.kind == SnippetKind::Struct {
+            let Some(struct_sig) = find_struct_signature(snippet) else {
+                return;
+            };
+            let struct_ty = self.resolve_type_name(&struct_sig.name);
+            for method in &methods {
+                let implemented = snippets
+                    .iter()
+                    .filter(|s| s.kind.is_function() && s.implements.as_ref() == Some(interface))
+                    .filter_map(find_function_signature)
+                    .filter(|sig| sig.name == method.name)
+                    .any(|sig| {
+                        self.method_conformance(method, sig)
+                            .is_ok_and(|bound| bound.is_none_or(|ty| self.same_type(&ty, &struct_ty)))
+                    });
+                if !implemented {
+                    self.errors.push(error(format!(
+                        "no function implements method '{}' for {}",
+                        method.name, struct_sig.name
+                    )));
+                }
+            }
+            return;
+        }
+
+        let Some(sig) = find_function_signature(snippet) else {
+            return;
+        };
+        let reason = match methods.iter().find(|m| m.name == sig.name) {
+            Some(method) => self.method_conformance(method, sig).err(),
+            None => Some(format!(
+                "the interface has no method '{}' (it declares {})",
+                sig.name,
+                methods.iter().map(|m| format!("'{}'", m.name)).collect::<Vec<_>>().join(", ")
+            )),
+        };
+        if let Some(reason) = reason {
+            self.errors.push(error(reason));
+        }
+    }
+
+    /// Whether `sig` matches the interface method `method`, returning the
+    /// type bound to `Self` (by its first use) or why it does not match
+    fn method_conformance(
+        &self,
+        method: &FunctionSignature,
+        sig: &FunctionSignature,
+    ) -> Result<Option<ResolvedType>, String> {
+        if method.params.len() != sig.params.len() {
+            return Err(format!(
+                "'{}' takes {} parameter(s), but the interface method takes {}",
+                sig.name,
+                sig.params.len(),
+                method.params.len()
+            ));
+        }
+
+        let returns = |sig: &FunctionSignature| {
+            sig.returns.as_ref()
+                .map(|r| self.resolve_return_type(r))
+                .unwrap_or(ResolvedType::None)
+        };
+        let mut pairs: Vec<(String, ResolvedType, ResolvedType)> = method.params.iter()
+            .zip(&sig.params)
+            .map(|(m, p)| (format!("parameter '{}'", p.name), self.resolve_type(&m.ty), self.resolve_type(&p.ty)))
+            .collect();
+        pairs.push(("the return type".to_string(), returns(method), returns(sig)));
+
+        let bound = pairs.iter()
+            .find(|(_, expected, _)| matches!(expected, ResolvedType::Named { name, args, .. } if name == "Self" && args.is_empty()))
+            .map(|(_, _, found)| found.clone());
+        let bindings: HashMap<String, ResolvedType> = bound.iter()
+            .map(|ty| ("Self".to_string(), ty.clone()))
+            .collect();
+        for (what, expected, found) in &pairs {
+            let expected = expected.substitute(&bindings);
+            if !self.same_type(&expected, found) {
+                return Err(format!(
+                    "{} is {}, but the interface method has {}",
+                    what,
+                    found.display(),
+                    expected.display()
+                ));
+            }
+        }
+        Ok(bound)
+    }
+
+    /// Whether two types are interchangeable
+    fn same_type(&self, a: &ResolvedType, b: &ResolvedType) -> bool {
+        self.types_compatible(a, b) && self.types_compatible(b, a)
+    }
+
+    /// Check an `events.publish` call: `event_type` must be a literal naming
+    /// a struct, and `event` a value of that struct
+    fn check_event_publish(&mut self, call: &CallStep) {
+        let arg = |name: &str| call.args.iter().find(|a| a.name == name);
+
+        let Some(InputSource::Lit(Literal::String(event_type))) = arg("event_type").map(|a| &a.source) else {
+            self.errors.push(CheckError::InvalidEventPublish {
+                event_type: "?".to_string(),
+                reason: "event_type must be a string literal".to_string(),
+            });
+            return;
+        };
+
+        let Some(event_ty) = self.event_struct_type(event_type) else {
+            self.errors.push(CheckError::InvalidEventPublish {
+                event_type: event_type.clone(),
+                reason: "not a struct type".to_string(),
+            });
+            return;
+        };
+
+        if let Some(event) = arg("event") {
+            let found = self.resolve_source_type(&event.source);
+            if !self.types_compatible(&event_ty, &found) {
+                self.errors.push(CheckError::InvalidEventPublish {
+                    event_type: event_type.clone(),
+                    reason: format!("event is {}", found.display()),
+                });
+            }
+        }
+    }
+
+    /// The type of an event, if `name` (short or snippet-qualified) names a struct
+    fn event_struct_type(&self, name: &str) -> Option<ResolvedType> {
+        let short = name.rsplit('.').next().unwrap_or(name);
+        self.type_registry
+            .get_struct(short)
+            .map(|def| self.resolve_type_name(&def.name))
     }
 
     /// Check a single step and add its binding to locals
@@ -207,42 +538,97 @@ impl SnippetChecker {
         // Add binding to locals if not discarded
         if step.output_binding != "_" {
             self.locals.insert(step.output_binding.clone(), step_type);
+            self.record_optional_check(step);
         }
     }
 
+    /// Remember which optional binding an `is_some`/`is_none` step checks, so
+    /// an `if` on its result can narrow that binding
+    fn record_optional_check(&mut self, step: &Step) {
+        self.optional_checks.remove(&step.output_binding);
+        let StepKind::Compute(compute) = &step.kind else { return };
+        let (Operation::IsSome | Operation::IsNone, [input]) = (compute.op, compute.inputs.as_slice()) else { return };
+        let InputSource::Var(checked) = &input.source else { return };
+        if self.locals.get(checked).is_some_and(ResolvedType::is_optional) {
+            self.optional_checks.insert(
+                step.output_binding.clone(),
+                (checked.clone(), compute.op == Operation::IsSome),
+            );
+        }
+    }
+
+    /// Warn when a `to_int` or `to_float` step may lose information
+    fn check_conversion(&mut self, step: &Step, compute: &ComputeStep) {
+        let [input] = compute.inputs.as_slice() else {
+            return;
+        };
+        let kind = match (compute.op, &input.source) {
+            (Operation::ToInt, InputSource::Lit(Literal::Float(f))) if to_int_truncates(*f) => ConversionKind::Truncating,
+            (Operation::ToInt, InputSource::Var(name)) if self.locals.get(name) == Some(&ResolvedType::Float) => {
+                ConversionKind::Truncating
+            }
+            (Operation::ToFloat, InputSource::Lit(Literal::Int(n))) if to_float_rounds(*n) => ConversionKind::PrecisionLoss,
+            _ => return,
+        };
+        self.warnings.push(ConversionWarning {
+            kind,
+            snippet: self.current_snippet.clone(),
+            step: step.id.clone(),
+            span: step.span,
+        });
+    }
+
     /// Infer the type of a step
     fn infer_step_type(&mut self, step: &Step) -> ResolvedType {
         match &step.kind {
-            StepKind::Compute(compute) => self.infer_compute_step(compute),
+            StepKind::Compute(compute) => {
+                self.check_conversion(step, compute);
+                self.infer_compute_step(compute)
+            }
             StepKind::Call(call) => self.infer_call_step(call),
+            StepKind::Pipeline(_) => unreachable!("pipelines are lowered before checking"),
             StepKind::Return(ret) => self.infer_return_step(ret),
+            StepKind::Raise(raise) => self.infer_raise_step(raise),
             StepKind::If(if_step) => self.infer_if_step(if_step),
             StepKind::Bind(bind) => self.infer_bind_step(bind),
             StepKind::Match(match_step) => self.infer_match_step(match_step),
             StepKind::For(for_step) => self.infer_for_step(for_step),
+            StepKind::Break(_) => self.check_loop_control("break"),
+            StepKind::Continue(_) => self.check_loop_control("continue"),
+            StepKind::Lambda(lambda) => self.infer_lambda_step(lambda),
+            StepKind::Using(using) => self.infer_using_step(using),
+            StepKind::Assert(assert) => {
+                self.check_condition(assert.op, &assert.inputs, format!("assert step '{}'", step.id));
+                ResolvedType::None
+            }
             StepKind::Query(query) => self.infer_query_step(query),
             StepKind::Insert(_) => ResolvedType::Unknown, // TODO: infer inserted type
             StepKind::Update(_) => ResolvedType::Unknown, // TODO: infer update count
             StepKind::Delete(_) => ResolvedType::None,
-            StepKind::Transaction(_) => ResolvedType::Unknown,
+            StepKind::Transaction(tx) => self.infer_transaction_step(tx),
             StepKind::Traverse(_) => ResolvedType::Unknown,
             StepKind::Construct(construct) => self.infer_construct_step(construct),
             StepKind::Parallel(parallel) => {
-                // For parallel, check all branches and return a collection of their results
+                // For parallel, check all branches and return a collection of their results.
+                // Branches run concurrently, so they cannot break out of an enclosing loop.
+                let loop_depth = std::mem::take(&mut self.loop_depth);
                 for branch in &parallel.branches {
                     for step in &branch.steps {
                         self.check_step(step);
                     }
                 }
+                self.loop_depth = loop_depth;
                 ResolvedType::Unknown // TODO: infer tuple/struct of branch results
             }
             StepKind::Race(race) => {
                 // For race, the result is whichever branch finishes first
+                let loop_depth = std::mem::take(&mut self.loop_depth);
                 for branch in &race.branches {
                     for step in &branch.steps {
                         self.check_step(step);
                     }
                 }
+                self.loop_depth = loop_depth;
                 ResolvedType::Unknown // TODO: infer union of branch result types
             }
         }
@@ -258,6 +644,11 @@ impl SnippetChecker {
         match compute.op {
             // Arithmetic operations: return numeric type
             Operation::Add | Operation::Sub | Operation::Mul | Operation::Div | Operation::Mod => {
+                self.check_no_time_inputs(&input_types);
+                self.check_no_exact_inputs(&input_types);
+                if let Some(unit_type) = self.unit_arithmetic(compute.op, &input_types) {
+                    return unit_type;
+                }
                 // If any input is Float, result is Float; otherwise Int
                 if input_types.iter().any(|t| matches!(t, ResolvedType::Float)) {
                     ResolvedType::Float
@@ -266,10 +657,24 @@ impl SnippetChecker {
                 }
             }
 
+            // Overflow-explicit arithmetic is Int only; checked ops yield none on overflow
+            Operation::AddWrapping | Operation::SubWrapping | Operation::MulWrapping |
+            Operation::AddSaturating | Operation::SubSaturating | Operation::MulSaturating => {
+                self.check_int_inputs(&input_types);
+                ResolvedType::Int
+            }
+            Operation::AddChecked | Operation::SubChecked | Operation::MulChecked => {
+                self.check_int_inputs(&input_types);
+                ResolvedType::Optional(Box::new(ResolvedType::Int))
+            }
+
             // Comparison operations: return Bool
             Operation::Equals | Operation::NotEquals |
             Operation::Less | Operation::Greater |
-            Operation::LessEq | Operation::GreaterEq => ResolvedType::Bool,
+            Operation::LessEq | Operation::GreaterEq => {
+                self.check_comparison(&input_types);
+                ResolvedType::Bool
+            }
 
             // Boolean operations: require Bool inputs, return Bool
             Operation::And | Operation::Or => {
@@ -296,13 +701,24 @@ impl SnippetChecker {
             }
 
             // Negation: same as input type
-            Operation::Neg => input_types.first().cloned().unwrap_or(ResolvedType::Int),
+            Operation::Neg => {
+                self.check_no_exact_inputs(&input_types);
+                input_types.first().cloned().unwrap_or(ResolvedType::Int)
+            }
 
             // String operations removed — now extern-abstract calls (text.concat, etc.)
 
             // Numeric operations: Abs, Sign preserve type; Min/Max preserve type
-            Operation::Abs | Operation::Sign => input_types.first().cloned().unwrap_or(ResolvedType::Int),
+            Operation::Abs | Operation::Sign => {
+                self.check_no_exact_inputs(&input_types);
+                input_types.first().cloned().unwrap_or(ResolvedType::Int)
+            }
             Operation::Min | Operation::Max | Operation::Clamp => {
+                self.check_no_exact_inputs(&input_types);
+                if let Some(unit_type) = input_types.iter().find(|t| self.unit_base(t).is_some()).cloned() {
+                    self.check_same_unit(&unit_type, &input_types);
+                    return unit_type;
+                }
                 if input_types.iter().any(|t| matches!(t, ResolvedType::Float)) {
                     ResolvedType::Float
                 } else {
@@ -333,8 +749,111 @@ impl SnippetChecker {
                 ResolvedType::Named { name: "ParseError".to_string(), id: covenant_ast::SymbolId(0), args: vec![] }
             ]),
 
+            // Regex operations: String inputs and a pattern literal checked here
+            Operation::RegexMatch => {
+                self.check_regex_inputs(compute, &input_types);
+                ResolvedType::Bool
+            }
+            Operation::RegexFindAll | Operation::RegexCapture => {
+                self.check_regex_inputs(compute, &input_types);
+                ResolvedType::List(Box::new(ResolvedType::String))
+            }
+            Operation::RegexReplace => {
+                self.check_regex_inputs(compute, &input_types);
+                ResolvedType::String
+            }
+
             // List operations removed — now extern-abstract calls (list.len, etc.)
 
+            // Fixed-size array operations
+            Operation::ArrayOf => {
+                let elem = input_types.first().cloned().unwrap_or(ResolvedType::Unknown);
+                self.check_inputs_are(&elem, &input_types);
+                ResolvedType::Array(Box::new(elem), input_types.len())
+            }
+            Operation::ArrayFill => {
+                let elem = input_types.first().cloned().unwrap_or(ResolvedType::Unknown);
+                let len = compute.inputs.get(1).map(|input| (self.constant_input(&input.source), input));
+                match len {
+                    Some((Some(Literal::Int(n)), _)) if n >= 0 => ResolvedType::Array(Box::new(elem), n as usize),
+                    other => {
+                        let found = match other {
+                            Some((_, input)) => input_source_text(&input.source),
+                            None => "nothing".to_string(),
+                        };
+                        self.errors.push(CheckError::InvalidArrayLength { found });
+                        ResolvedType::Error
+                    }
+                }
+            }
+            Operation::ArrayGet | Operation::ArraySet => {
+                let array = self.array_input(compute, &input_types);
+                self.check_int_inputs(input_types.get(1..2).unwrap_or(&[]));
+                let Some((elem, _)) = array else {
+                    return ResolvedType::Unknown;
+                };
+                if compute.op == Operation::ArrayGet {
+                    return elem;
+                }
+                if let Some(value_type) = input_types.get(2) {
+                    if !self.types_compatible(&elem, value_type) {
+                        self.errors.push(CheckError::TypeMismatch {
+                            expected: elem.display(),
+                            found: value_type.display(),
+                        });
+                    }
+                }
+                input_types[0].clone()
+            }
+            Operation::ArrayLen => {
+                self.array_input(compute, &input_types);
+                ResolvedType::Int
+            }
+
+            // Numeric vector operations: both vectors have the first's type
+            Operation::VecAdd | Operation::VecSub | Operation::Dot => {
+                let vector = input_types.first().cloned().unwrap_or(ResolvedType::Unknown);
+                self.vector_shape(&vector);
+                self.check_inputs_are(&vector, input_types.get(1..2).unwrap_or(&[]));
+                if compute.op == Operation::Dot {
+                    ResolvedType::Float
+                } else {
+                    vector
+                }
+            }
+            Operation::VecScale => {
+                let vector = input_types.first().cloned().unwrap_or(ResolvedType::Unknown);
+                self.vector_shape(&vector);
+                self.check_inputs_are(&ResolvedType::Float, input_types.get(1..2).unwrap_or(&[]));
+                vector
+            }
+            Operation::Matmul => {
+                let left = input_types.first().cloned().unwrap_or(ResolvedType::Unknown);
+                let right = input_types.get(1).cloned().unwrap_or(ResolvedType::Unknown);
+                match (self.matrix_shape(&left), self.matrix_shape(&right)) {
+                    (Some((rows, inner)), Some((right_rows, cols))) => {
+                        if inner != right_rows {
+                            self.errors.push(CheckError::MatrixShapeMismatch {
+                                left: left.display(),
+                                right: right.display(),
+                            });
+                        }
+                        ResolvedType::Array(Box::new(ResolvedType::Array(Box::new(ResolvedType::Float), cols)), rows)
+                    }
+                    // A fixed-size matrix only multiplies another
+                    (Some(_), None) | (None, Some(_)) => {
+                        if !self.types_compatible(&left, &right) {
+                            self.errors.push(CheckError::TypeMismatch {
+                                expected: left.display(),
+                                found: right.display(),
+                            });
+                        }
+                        ResolvedType::Unknown
+                    }
+                    (None, None) => ResolvedType::List(Box::new(ResolvedType::List(Box::new(ResolvedType::Float)))),
+                }
+            }
+
             // Map operations that return Int
             Operation::MapLen => ResolvedType::Int,
 
@@ -395,61 +914,687 @@ impl SnippetChecker {
                 }
             }
 
-            // DateTime operations that return Int
-            Operation::DtYear | Operation::DtMonth | Operation::DtDay |
-            Operation::DtHour | Operation::DtMinute | Operation::DtSecond |
-            Operation::DtWeekday | Operation::DtUnix | Operation::DtDiff => ResolvedType::Int,
+            // Optional operations
+            Operation::IsSome | Operation::IsNone => {
+                if let Some(input_type) = input_types.first() {
+                    self.check_optional_input(input_type);
+                }
+                ResolvedType::Bool
+            }
+            Operation::UnwrapOr => {
+                let Some(input_type) = input_types.first() else {
+                    return ResolvedType::Unknown;
+                };
+                self.check_optional_input(input_type);
+                let inner = match input_type {
+                    ResolvedType::Optional(inner) => (**inner).clone(),
+                    other => other.clone(),
+                };
+                if let Some(default_type) = input_types.get(1) {
+                    if !self.types_compatible(&inner, default_type) {
+                        self.errors.push(CheckError::TypeMismatch {
+                            expected: inner.display(),
+                            found: default_type.display(),
+                        });
+                    }
+                }
+                inner
+            }
+
+            // DateTime operations that return Int
+            Operation::DtYear | Operation::DtMonth | Operation::DtDay |
+            Operation::DtHour | Operation::DtMinute | Operation::DtSecond |
+            Operation::DtWeekday | Operation::DtUnix => ResolvedType::Int,
+
+            // DateTime and Duration arithmetic keeps the two apart
+            Operation::DtAdd | Operation::DtSub => {
+                self.check_inputs_in_order(&[ResolvedType::DateTime, ResolvedType::Duration], &input_types);
+                ResolvedType::DateTime
+            }
+            Operation::DtDiff => {
+                self.check_inputs_are(&ResolvedType::DateTime, &input_types);
+                ResolvedType::Duration
+            }
+
+            // Duration constructors take a count of the unit
+            Operation::DurSeconds | Operation::DurMinutes | Operation::DurHours | Operation::DurDays => {
+                self.check_inputs_are(&ResolvedType::Int, &input_types);
+                ResolvedType::Duration
+            }
+            Operation::DurTotalSeconds => {
+                self.check_inputs_are(&ResolvedType::Duration, &input_types);
+                ResolvedType::Int
+            }
+            Operation::DurAdd | Operation::DurSub => {
+                self.check_inputs_are(&ResolvedType::Duration, &input_types);
+                ResolvedType::Duration
+            }
+            Operation::DurScale => {
+                self.check_inputs_in_order(&[ResolvedType::Duration, ResolvedType::Int], &input_types);
+                ResolvedType::Duration
+            }
+            Operation::DurFormat => {
+                self.check_inputs_are(&ResolvedType::Duration, &input_types);
+                ResolvedType::String
+            }
+
+            // Decimal operations; dividing and rounding take their places
+            // as a literal so the result's precision is fixed
+            Operation::DecAdd | Operation::DecSub | Operation::DecMul => {
+                self.check_inputs_are(&ResolvedType::Decimal, &input_types);
+                ResolvedType::Decimal
+            }
+            Operation::DecDiv => {
+                self.check_inputs_in_order(&[ResolvedType::Decimal, ResolvedType::Decimal, ResolvedType::Int], &input_types);
+                self.check_decimal_places(compute, 2);
+                ResolvedType::Decimal
+            }
+            Operation::DecRound => {
+                self.check_inputs_in_order(&[ResolvedType::Decimal, ResolvedType::Int], &input_types);
+                self.check_decimal_places(compute, 1);
+                ResolvedType::Decimal
+            }
+            Operation::DecCmp => {
+                self.check_inputs_are(&ResolvedType::Decimal, &input_types);
+                ResolvedType::Int
+            }
+            Operation::DecFromInt => {
+                self.check_inputs_are(&ResolvedType::Int, &input_types);
+                ResolvedType::Decimal
+            }
+            Operation::DecFormat => {
+                self.check_inputs_are(&ResolvedType::Decimal, &input_types);
+                ResolvedType::String
+            }
+
+            // BigInt operations
+            Operation::BigAdd | Operation::BigSub | Operation::BigMul |
+            Operation::BigDiv | Operation::BigMod => {
+                self.check_inputs_are(&ResolvedType::BigInt, &input_types);
+                ResolvedType::BigInt
+            }
+            Operation::BigCmp => {
+                self.check_inputs_are(&ResolvedType::BigInt, &input_types);
+                ResolvedType::Int
+            }
+            Operation::BigFromInt => {
+                self.check_inputs_are(&ResolvedType::Int, &input_types);
+                ResolvedType::BigInt
+            }
+            Operation::BigFormat => {
+                self.check_inputs_are(&ResolvedType::BigInt, &input_types);
+                ResolvedType::String
+            }
+
+            // Json operations; keys are Strings, including array indexes
+            Operation::JsonParse => {
+                self.check_inputs_are(&ResolvedType::String, &input_types);
+                ResolvedType::Union(vec![json_type(), decode_error_type()])
+            }
+            Operation::JsonStringify => {
+                self.check_inputs_are(&json_type(), &input_types);
+                ResolvedType::String
+            }
+            Operation::JsonGet => {
+                self.check_inputs_in_order(&[json_type(), ResolvedType::String], &input_types);
+                ResolvedType::Optional(Box::new(json_type()))
+            }
+            Operation::JsonSet => {
+                self.check_inputs_in_order(&[json_type(), ResolvedType::String, json_type()], &input_types);
+                json_type()
+            }
+            Operation::JsonKeys => {
+                self.check_inputs_are(&json_type(), &input_types);
+                ResolvedType::List(Box::new(ResolvedType::String))
+            }
+
+            // DateTime format returns String
+            Operation::DtFormat => ResolvedType::String,
+
+            // Bytes operations that return Int
+            Operation::BytesLen => ResolvedType::Int,
+
+            // Bytes operations that return Bool
+            Operation::BytesIsEmpty => ResolvedType::Bool,
+
+            // Bytes operations that return Optional Int
+            Operation::BytesGet => ResolvedType::Optional(Box::new(ResolvedType::Int)),
+
+            // Bytes operations that return Bytes
+            Operation::BytesSlice | Operation::BytesConcat => ResolvedType::Bytes,
+
+            // Bytes to string can fail
+            Operation::BytesToString => ResolvedType::Union(vec![
+                ResolvedType::String,
+                decode_error_type(),
+            ]),
+
+            // Bytes to Base64/Hex return String
+            Operation::BytesToBase64 | Operation::BytesToBase64Url | Operation::BytesToHex => {
+                self.check_inputs_are(&ResolvedType::Bytes, &input_types);
+                ResolvedType::String
+            }
+
+            // Decoding Bytes can fail on malformed input
+            Operation::BytesFromBase64 | Operation::BytesFromBase64Url | Operation::BytesFromHex => {
+                self.check_inputs_are(&ResolvedType::String, &input_types);
+                ResolvedType::Union(vec![ResolvedType::Bytes, decode_error_type()])
+            }
+
+            // Percent-encoding works on the String's UTF-8 bytes
+            Operation::UrlEncode => {
+                self.check_inputs_are(&ResolvedType::String, &input_types);
+                ResolvedType::String
+            }
+            Operation::UrlDecode => {
+                self.check_inputs_are(&ResolvedType::String, &input_types);
+                ResolvedType::Union(vec![ResolvedType::String, decode_error_type()])
+            }
+        }
+    }
+
+    /// Report inputs that are known not to be of the `expected` type
+    fn check_inputs_are(&mut self, expected: &ResolvedType, input_types: &[ResolvedType]) {
+        for input_type in input_types {
+            if !self.types_compatible(expected, input_type) {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: expected.display(),
+                    found: input_type.display(),
+                });
+            }
+        }
+    }
+
+    /// Report inputs that are known not to match `expected`, position by position
+    fn check_inputs_in_order(&mut self, expected: &[ResolvedType], input_types: &[ResolvedType]) {
+        for (expected, input_type) in expected.iter().zip(input_types) {
+            if !self.types_compatible(expected, input_type) {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: expected.display(),
+                    found: input_type.display(),
+                });
+            }
+        }
+    }
+
+    /// Report DateTime and Duration inputs to plain arithmetic, which would
+    /// drop their units; the dt_ and dur_ operations take them instead
+    fn check_no_time_inputs(&mut self, input_types: &[ResolvedType]) {
+        for input_type in input_types {
+            if matches!(input_type, ResolvedType::DateTime | ResolvedType::Duration) {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: "Int or Float".to_string(),
+                    found: input_type.display(),
+                });
+            }
+        }
+    }
+
+    /// Report Decimal and BigInt inputs to the operations on machine
+    /// numbers, which would see only their runtime handles; the dec_ and
+    /// big_ operations take them instead
+    fn check_no_exact_inputs(&mut self, input_types: &[ResolvedType]) {
+        for input_type in input_types {
+            if matches!(input_type, ResolvedType::Decimal | ResolvedType::BigInt) {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: "Int or Float".to_string(),
+                    found: input_type.display(),
+                });
+            }
+        }
+    }
+
+    /// Report a comparison between a DateTime, Duration or unit alias and a
+    /// value of another type, such as a Duration against a bare Int, and
+    /// any comparison of Decimals or BigInts, which compare with dec_cmp
+    /// and big_cmp
+    fn check_comparison(&mut self, input_types: &[ResolvedType]) {
+        self.check_no_exact_inputs(input_types);
+        let [lhs, rhs] = input_types else { return };
+        if self.unit_base(lhs).is_some() || self.unit_base(rhs).is_some() {
+            self.check_same_unit(lhs, input_types);
+            return;
+        }
+        let is_time = |t: &ResolvedType| matches!(t, ResolvedType::DateTime | ResolvedType::Duration);
+        if (is_time(lhs) || is_time(rhs)) && !self.types_compatible(lhs, rhs) {
+            self.errors.push(CheckError::TypeMismatch {
+                expected: lhs.display(),
+                found: rhs.display(),
+            });
+        }
+    }
+
+    /// Base type of a unit alias, or None for any other type
+    fn unit_base(&self, ty: &ResolvedType) -> Option<ResolvedType> {
+        match ty {
+            ResolvedType::Named { name, .. } => self.unit_types.get(name).map(|(base, _)| base.clone()),
+            _ => None,
+        }
+    }
+
+    /// A type as shown in unit errors: `Money (USD)` for a unit alias
+    fn unit_display(&self, ty: &ResolvedType) -> String {
+        match ty {
+            ResolvedType::Named { name, .. } if self.unit_types.contains_key(name) => {
+                format!("{} ({})", name, self.unit_types[name].1)
+            }
+            _ => ty.display(),
+        }
+    }
+
+    /// Report inputs that are not values of the unit alias `unit_type`
+    fn check_same_unit(&mut self, unit_type: &ResolvedType, input_types: &[ResolvedType]) {
+        for input_type in input_types {
+            if !self.same_type(unit_type, input_type) {
+                self.errors.push(CheckError::UnitMismatch {
+                    left: self.unit_display(unit_type),
+                    right: self.unit_display(input_type),
+                });
+            }
+        }
+    }
+
+    /// Result of plain arithmetic on a unit alias, or None when neither input
+    /// has a unit. Values add, subtract and take remainders only with the same
+    /// unit; they scale by plain numbers, and dividing two values of the same
+    /// unit gives a plain ratio of the base type.
+    fn unit_arithmetic(&mut self, op: Operation, input_types: &[ResolvedType]) -> Option<ResolvedType> {
+        let [lhs, rhs] = input_types else { return None };
+        let (lhs_base, rhs_base) = (self.unit_base(lhs), self.unit_base(rhs));
+        let unit_type = if lhs_base.is_some() { lhs } else { rhs };
+        match (&lhs_base, &rhs_base) {
+            (None, None) => return None,
+            (Some(base), Some(_)) if self.same_type(lhs, rhs) => match op {
+                Operation::Div => return Some(base.clone()),
+                Operation::Mul => {
+                    self.errors.push(CheckError::TypeMismatch {
+                        expected: base.display(),
+                        found: self.unit_display(rhs),
+                    });
+                }
+                _ => {}
+            },
+            (Some(_), None) if matches!(op, Operation::Mul | Operation::Div) => {
+                self.check_inputs_in_order(&[lhs.clone(), ResolvedType::Float], input_types);
+            }
+            (None, Some(_)) if op == Operation::Mul => {
+                self.check_inputs_in_order(&[ResolvedType::Float, rhs.clone()], input_types);
+            }
+            _ => {
+                let unit_type = unit_type.clone();
+                self.check_same_unit(&unit_type, input_types);
+            }
+        }
+        Some(unit_type.clone())
+    }
+
+    /// Report an input to an optional operation that can never be none
+    fn check_optional_input(&mut self, input_type: &ResolvedType) {
+        if !matches!(input_type, ResolvedType::Optional(_) | ResolvedType::None | ResolvedType::Unknown | ResolvedType::Error) {
+            self.errors.push(CheckError::TypeMismatch {
+                expected: format!("{}?", input_type.display()),
+                found: input_type.display(),
+            });
+        }
+    }
+
+    /// Report inputs to an Int-only operation that are known not to be Int
+    fn check_int_inputs(&mut self, input_types: &[ResolvedType]) {
+        for input_type in input_types {
+            if !matches!(input_type, ResolvedType::Int | ResolvedType::Unknown | ResolvedType::Error) {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: "Int".to_string(),
+                    found: input_type.display(),
+                });
+            }
+        }
+    }
+
+    /// The element type and length of an array operation's first input.
+    /// Reports inputs that are not arrays, and constant indexes (the second
+    /// input) outside the array's length.
+    fn array_input(&mut self, compute: &ComputeStep, input_types: &[ResolvedType]) -> Option<(ResolvedType, usize)> {
+        let (elem, len) = match input_types.first() {
+            Some(ResolvedType::Array(elem, len)) => ((**elem).clone(), *len),
+            Some(ResolvedType::Unknown | ResolvedType::Error) | None => return None,
+            Some(other) => {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: "Array".to_string(),
+                    found: other.display(),
+                });
+                return None;
+            }
+        };
+        if compute.op != Operation::ArrayLen {
+            let index = compute.inputs.get(1).and_then(|input| self.constant_input(&input.source));
+            if let Some(Literal::Int(index)) = index {
+                if index < 0 || index as u64 >= len as u64 {
+                    self.errors.push(CheckError::ArrayIndexOutOfBounds { index, len });
+                }
+            }
+        }
+        Some((elem, len))
+    }
+
+    /// The length of a Float vector input: `Some(n)` for an `Array<Float, n>`,
+    /// and `None` for a `List<Float>` or an input of unknown type. Reports
+    /// inputs of any other type.
+    fn vector_shape(&mut self, ty: &ResolvedType) -> Option<usize> {
+        match ty {
+            ResolvedType::Array(elem, len) if is_float_element(elem) => Some(*len),
+            ResolvedType::List(elem) if is_float_element(elem) => None,
+            ResolvedType::Unknown | ResolvedType::Error => None,
+            other => {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: "List<Float> or Array<Float, N>".to_string(),
+                    found: other.display(),
+                });
+                None
+            }
+        }
+    }
+
+    /// The rows and columns of a fixed-size matrix input, an
+    /// `Array<Array<Float, columns>, rows>`; `None` for a `List<List<Float>>`
+    /// or an input of unknown type. Reports inputs of any other type.
+    fn matrix_shape(&mut self, ty: &ResolvedType) -> Option<(usize, usize)> {
+        match ty {
+            ResolvedType::Array(row, rows) => match &**row {
+                ResolvedType::Array(elem, cols) if is_float_element(elem) => Some((*rows, *cols)),
+                _ => {
+                    self.errors.push(CheckError::TypeMismatch {
+                        expected: "Array<Array<Float, K>, M>".to_string(),
+                        found: ty.display(),
+                    });
+                    None
+                }
+            },
+            ResolvedType::List(row) if matches!(&**row, ResolvedType::List(elem) if is_float_element(elem)) => None,
+            ResolvedType::Unknown | ResolvedType::Error => None,
+            other => {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: "List<List<Float>> or Array<Array<Float, K>, M>".to_string(),
+                    found: other.display(),
+                });
+                None
+            }
+        }
+    }
+
+    /// The value of an input known before running: a literal, or a const
+    /// that no local shadows
+    fn constant_input(&self, source: &InputSource) -> Option<Literal> {
+        match source {
+            InputSource::Lit(lit) => Some(lit.clone()),
+            InputSource::Var(name) if !self.locals.contains_key(name) => {
+                self.constants.get(name).map(|(_, value)| value.clone())
+            }
+            InputSource::Field { of, field } if !self.locals.contains_key(of) => {
+                self.constants.get(&format!("{}.{}", of, field)).map(|(_, value)| value.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Check that the input at `index` is a number of decimal places known
+    /// when the program is checked: an Int literal or const in range
+    fn check_decimal_places(&mut self, compute: &ComputeStep, index: usize) {
+        let Some(input) = compute.inputs.get(index) else {
+            return;
+        };
+        match self.constant_input(&input.source) {
+            Some(Literal::Int(places)) if (0..=MAX_DECIMAL_PLACES as i64).contains(&places) => {}
+            Some(Literal::Int(places)) => self.errors.push(CheckError::InvalidDecimalPlaces {
+                found: places.to_string(),
+                max: MAX_DECIMAL_PLACES,
+            }),
+            _ => self.errors.push(CheckError::InvalidDecimalPlaces {
+                found: input_source_text(&input.source),
+                max: MAX_DECIMAL_PLACES,
+            }),
+        }
+    }
+
+    /// Check the inputs of a regex operation: all are Strings, and the
+    /// second is a pattern literal (or a String const) the runtime can match
+    fn check_regex_inputs(&mut self, compute: &ComputeStep, input_types: &[ResolvedType]) {
+        for input_type in input_types {
+            if !self.types_compatible(&ResolvedType::String, input_type) {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: "String".to_string(),
+                    found: input_type.display(),
+                });
+            }
+        }
+
+        let pattern = compute.inputs.get(1).map(|input| self.constant_input(&input.source));
+        let (pattern, reason) = match pattern {
+            Some(Some(Literal::String(pattern))) => match validate_pattern(&pattern) {
+                Ok(()) => return,
+                Err(reason) => (pattern, reason),
+            },
+            Some(_) => (
+                input_source_text(&compute.inputs[1].source),
+                "the pattern must be a String literal so it can be validated before running".to_string(),
+            ),
+            None => (String::new(), "the pattern input is missing".to_string()),
+        };
+        self.errors.push(CheckError::InvalidRegex { pattern, reason });
+    }
+
+    /// Infer type of a call step
+    fn infer_call_step(&mut self, call: &CallStep) -> ResolvedType {
+        // A lambda bound in scope shadows functions of the same name
+        if let Some(ResolvedType::Function { params, ret }) = self.locals.get(&call.fn_name).cloned() {
+            return self.infer_function_value_call(call, &params, *ret);
+        }
+
+        if call.fn_name == "events.publish" {
+            self.check_event_publish(call);
+        }
+
+        // Generic functions are instantiated per call
+        if let Some(generics) = self.function_generics.get(&call.fn_name).cloned() {
+            return self.infer_generic_call(call, &generics);
+        }
+
+        // Look up function return type
+        if let Some(return_type) = self.function_returns.get(&call.fn_name) {
+            if !call.type_args.is_empty() {
+                self.errors.push(CheckError::TypeArgumentCountMismatch {
+                    name: call.fn_name.clone(),
+                    expected: 0,
+                    found: call.type_args.len(),
+                });
+            }
+            return_type.clone()
+        } else if let Some(symbol) = self.symbols.lookup(&call.fn_name) {
+            symbol.ty.clone()
+        } else if self.locals.contains_key(&call.fn_name) {
+            // Calling a value whose type is not known to be a function (e.g. an `Any` parameter)
+            ResolvedType::Unknown
+        } else {
+            self.errors.push(CheckError::UndefinedSymbol {
+                name: call.fn_name.clone(),
+            });
+            ResolvedType::Error
+        }
+    }
+
+    /// Check a call to a generic function
+    ///
+    /// The call's type arguments bind the function's generic params in
+    /// order. Without any, each generic is inferred from an argument passed
+    /// for a param of exactly that type, and is otherwise left unknown. The
+    /// bindings are substituted into the param types the arguments are
+    /// checked against and into the return type.
+    fn infer_generic_call(&mut self, call: &CallStep, generics: &[String]) -> ResolvedType {
+        let params = match self.symbols.lookup(&call.fn_name).map(|s| &s.kind) {
+            Some(SymbolKind::Function { params, .. }) => params.clone(),
+            _ => Vec::new(),
+        };
+        let ret = self.function_returns.get(&call.fn_name).cloned().unwrap_or(ResolvedType::Unknown);
+        let args: Vec<(ResolvedType, ResolvedType)> = call.args.iter()
+            .filter_map(|arg| {
+                let (_, param_type) = params.iter().find(|(name, _)| *name == arg.name)?;
+                Some((param_type.clone(), self.resolve_source_type(&arg.source)))
+            })
+            .collect();
+
+        let mut bindings = HashMap::new();
+        if call.type_args.is_empty() {
+            for (param_type, arg_type) in &args {
+                if let ResolvedType::Named { name, args, .. } = param_type {
+                    if args.is_empty() && generics.contains(name) {
+                        bindings.entry(name.clone()).or_insert_with(|| arg_type.clone());
+                    }
+                }
+            }
+        } else if call.type_args.len() == generics.len() {
+            for (generic, ty) in generics.iter().zip(&call.type_args) {
+                bindings.insert(generic.clone(), self.resolve_type(ty));
+            }
+        } else {
+            self.errors.push(CheckError::TypeArgumentCountMismatch {
+                name: call.fn_name.clone(),
+                expected: generics.len(),
+                found: call.type_args.len(),
+            });
+            return ResolvedType::Error;
+        }
+        for generic in generics {
+            bindings.entry(generic.clone()).or_insert(ResolvedType::Unknown);
+        }
+
+        for (param_type, arg_type) in &args {
+            let expected = param_type.substitute(&bindings);
+            if !self.types_compatible(&expected, arg_type) {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: expected.display(),
+                    found: arg_type.display(),
+                });
+            }
+        }
+        ret.substitute(&bindings)
+    }
+
+    /// Check the arguments of a call to a function value against its parameters
+    fn infer_function_value_call(&mut self, call: &CallStep, params: &[ResolvedType], ret: ResolvedType) -> ResolvedType {
+        if params.len() != call.args.len() {
+            self.errors.push(CheckError::ArgumentCountMismatch {
+                name: call.fn_name.clone(),
+                expected: params.len(),
+                found: call.args.len(),
+            });
+            return ret;
+        }
 
-            // DateTime operations that return DateTime
-            Operation::DtAddDays | Operation::DtAddHours |
-            Operation::DtAddMinutes | Operation::DtAddSeconds => ResolvedType::DateTime,
+        for (param, arg) in params.iter().zip(&call.args) {
+            let found = self.resolve_source_type(&arg.source);
+            if !self.types_compatible(param, &found) {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: param.display(),
+                    found: found.display(),
+                });
+            }
+        }
 
-            // DateTime format returns String
-            Operation::DtFormat => ResolvedType::String,
+        ret
+    }
 
-            // Bytes operations that return Int
-            Operation::BytesLen => ResolvedType::Int,
+    /// A lambda checks its body with its parameters added to the enclosing
+    /// scope and has a function type. A `return` in the body exits the lambda,
+    /// so the return type is the declared one or that of its first top-level return.
+    fn infer_lambda_step(&mut self, lambda: &LambdaStep) -> ResolvedType {
+        let params: Vec<ResolvedType> = lambda.params.iter()
+            .map(|p| self.resolve_type(&p.ty))
+            .collect();
+        let declared = lambda.returns.as_ref().map(|ty| self.resolve_type(ty));
 
-            // Bytes operations that return Bool
-            Operation::BytesIsEmpty => ResolvedType::Bool,
+        // The body's bindings, return type, and loop context don't leak out
+        let outer_locals = self.locals.clone();
+        let outer_return = std::mem::replace(&mut self.current_return_type, declared.clone());
+        let loop_depth = std::mem::take(&mut self.loop_depth);
 
-            // Bytes operations that return Optional Int
-            Operation::BytesGet => ResolvedType::Optional(Box::new(ResolvedType::Int)),
+        for (param, ty) in lambda.params.iter().zip(&params) {
+            self.locals.insert(param.name.clone(), ty.clone());
+        }
 
-            // Bytes operations that return Bytes
-            Operation::BytesSlice | Operation::BytesConcat => ResolvedType::Bytes,
+        let mut returned = None;
+        for step in &lambda.steps {
+            let step_type = self.infer_step_type(step);
+            if matches!(step.kind, StepKind::Return(_)) && returned.is_none() {
+                returned = Some(step_type.clone());
+            }
+            if step.output_binding != "_" {
+                self.locals.insert(step.output_binding.clone(), step_type);
+            }
+        }
 
-            // Bytes to string can fail
-            Operation::BytesToString => ResolvedType::Union(vec![
-                ResolvedType::String,
-                ResolvedType::Named { name: "DecodeError".to_string(), id: covenant_ast::SymbolId(0), args: vec![] }
-            ]),
+        self.locals = outer_locals;
+        self.current_return_type = outer_return;
+        self.loop_depth = loop_depth;
 
-            // Bytes to Base64/Hex return String
-            Operation::BytesToBase64 | Operation::BytesToHex => ResolvedType::String,
+        ResolvedType::Function {
+            params,
+            ret: Box::new(declared.or(returned).unwrap_or(ResolvedType::Unknown)),
         }
     }
 
-    /// Infer type of a call step
-    fn infer_call_step(&mut self, call: &CallStep) -> ResolvedType {
-        // Look up function return type
-        if let Some(return_type) = self.function_returns.get(&call.fn_name) {
-            return_type.clone()
-        } else if let Some(symbol) = self.symbols.lookup(&call.fn_name) {
-            symbol.ty.clone()
-        } else {
-            self.errors.push(CheckError::UndefinedSymbol {
-                name: call.fn_name.clone(),
+    /// A using step binds the acquired resource for its body and has the type
+    /// of the body's last step. The release function must take exactly the
+    /// resource the acquire call returns.
+    fn infer_using_step(&mut self, using: &UsingStep) -> ResolvedType {
+        let resource = self.infer_call_step(&using.acquire);
+        let acquire = &using.acquire.fn_name;
+
+        let reason = match self.symbols.lookup(&using.release).map(|s| &s.kind) {
+            Some(SymbolKind::Function { params, .. }) => match params.as_slice() {
+                _ if is_unit(&resource) => Some(format!("'{}' returns no resource", acquire)),
+                [(_, ty)] if self.types_compatible(ty, &resource) => None,
+                [(name, ty)] => Some(format!(
+                    "'{}' takes '{}' as {}, but '{}' returns {}",
+                    using.release,
+                    name,
+                    ty.display(),
+                    acquire,
+                    resource.display()
+                )),
+                _ => Some(format!(
+                    "'{}' takes {} parameter(s), but only the resource is passed",
+                    using.release,
+                    params.len()
+                )),
+            },
+            _ => {
+                self.errors.push(CheckError::UndefinedSymbol { name: using.release.clone() });
+                None
+            }
+        };
+        if let Some(reason) = reason {
+            self.errors.push(CheckError::ReleaseMismatch {
+                acquire: acquire.clone(),
+                release: using.release.clone(),
+                reason,
             });
-            ResolvedType::Error
         }
+
+        self.locals.insert(using.var.clone(), resource);
+        let mut result = ResolvedType::None;
+        for step in &using.steps {
+            result = self.infer_step_type(step);
+            if step.output_binding != "_" {
+                self.locals.insert(step.output_binding.clone(), result.clone());
+            }
+        }
+        result
     }
 
     /// Infer type of a return step
     fn infer_return_step(&mut self, ret: &ReturnStep) -> ResolvedType {
         let inferred = match &ret.value {
             ReturnValue::Var(name) => {
-                self.locals.get(name).cloned().unwrap_or_else(|| {
+                self.local_or_constant(name).unwrap_or_else(|| {
                     self.errors.push(CheckError::UndefinedSymbol { name: name.clone() });
                     ResolvedType::Error
                 })
@@ -460,12 +1605,18 @@ impl SnippetChecker {
                 self.resolve_type(&s.ty)
             }
             ReturnValue::Variant(v) => {
-                // Return a named type for the variant
-                ResolvedType::Named {
-                    name: v.ty.clone(),
-                    id: covenant_ast::SymbolId(0), // Placeholder
-                    args: vec![],
+                let variant_type = self.variant_type(v);
+                // A variant must name one of the members of a union return type
+                if let Some(ResolvedType::Union(members)) = &self.current_return_type {
+                    if !self.union_has_variant(members, &v.ty) {
+                        self.errors.push(CheckError::IncompatibleUnion {
+                            value_type: v.ty.clone(),
+                            union_type: ResolvedType::Union(members.clone()).display(),
+                        });
+                        return ResolvedType::Error;
+                    }
                 }
+                variant_type
             }
         };
 
@@ -482,13 +1633,48 @@ impl SnippetChecker {
         inferred
     }
 
-    /// Infer type of an if step
+    /// Check a raise step: the function must return a union, and the raised
+    /// variant must belong to one of its error members (all but the first)
+    fn infer_raise_step(&mut self, raise: &RaiseStep) -> ResolvedType {
+        let variant_type = self.variant_type(&raise.variant);
+        let raised = &raise.variant.ty;
+
+        let reason = match &self.current_return_type {
+            Some(ResolvedType::Union(members)) => match members.split_first() {
+                Some((_, errors)) if self.union_has_variant(errors, raised) => None,
+                Some((success, _)) if self.union_has_variant(std::slice::from_ref(success), raised) => {
+                    Some(format!("{} is the success type of {}", raised, ResolvedType::Union(members.clone()).display()))
+                }
+                _ => Some(format!("{} is not a member of {}", raised, ResolvedType::Union(members.clone()).display())),
+            },
+            Some(other) => Some(format!("the function returns {}, not a union with error members", other.display())),
+            None => Some("the function has no return type".to_string()),
+        };
+        if let Some(reason) = reason {
+            self.errors.push(CheckError::InvalidRaise {
+                raised: raised.clone(),
+                reason,
+            });
+            return ResolvedType::Error;
+        }
+        variant_type
+    }
+
+    /// `break` and `continue` produce no value and are only valid in a loop body
+    fn check_loop_control(&mut self, kind: &str) -> ResolvedType {
+        if self.loop_depth == 0 {
+            self.errors.push(CheckError::LoopControlOutsideLoop { kind: kind.to_string() });
+        }
+        ResolvedType::None
+    }
+
+    /// Infer type of a for step
     fn infer_for_step(&mut self, for_step: &ForStep) -> ResolvedType {
         // Determine element type from the collection being iterated
         let collection_type = self.locals.get(&for_step.collection).cloned()
             .unwrap_or(ResolvedType::Unknown);
         let element_type = match &collection_type {
-            ResolvedType::List(inner) => *inner.clone(),
+            ResolvedType::List(inner) | ResolvedType::Array(inner, _) => *inner.clone(),
             _ => ResolvedType::Unknown,
         };
 
@@ -500,12 +1686,14 @@ impl SnippetChecker {
 
         // Type-check body steps and track the last step's type
         let mut body_type = ResolvedType::None;
+        self.loop_depth += 1;
         for step in &body_steps {
             self.check_step(step);
             body_type = self.locals.get(&step.output_binding)
                 .cloned()
                 .unwrap_or(ResolvedType::None);
         }
+        self.loop_depth -= 1;
 
         // For-loop collects body results into a List
         if matches!(body_type, ResolvedType::None) {
@@ -515,6 +1703,30 @@ impl SnippetChecker {
         }
     }
 
+    /// A transaction checks its nested steps in the enclosing scope. The
+    /// transaction is a database operation, so its nested queries require the
+    /// enclosing function to declare the `database` effect.
+    fn infer_transaction_step(&mut self, tx: &TransactionStep) -> ResolvedType {
+        let declares_database = self.current_effects.iter()
+            .any(|e| e == "database" || e.starts_with("database."));
+        if !declares_database {
+            self.errors.push(CheckError::EffectNotAllowed {
+                effect: "database".to_string(),
+            });
+        }
+
+        // The transaction's binding holds its last step's result once committed
+        let mut result = ResolvedType::None;
+        for step in &tx.steps {
+            result = self.infer_step_type(step);
+            if step.output_binding != "_" {
+                self.locals.insert(step.output_binding.clone(), result.clone());
+                self.record_optional_check(step);
+            }
+        }
+        result
+    }
+
     fn infer_if_step(&mut self, if_step: &IfStep) -> ResolvedType {
         // Check condition exists and is bool
         match &if_step.condition {
@@ -546,29 +1758,23 @@ impl SnippetChecker {
             }
         }
 
-        // Clone steps to avoid borrow issues
-        let then_steps = if_step.then_steps.clone();
-        let else_steps = if_step.else_steps.clone();
+        // An `is_some` condition narrows the checked optional in the then
+        // branch, and an `is_none` condition in the else branch
+        let narrowing = match &if_step.condition {
+            InputSource::Var(name) => self.optional_checks.get(name).cloned(),
+            _ => None,
+        };
+        let then_narrowing = narrowing.as_ref().filter(|(_, is_some)| *is_some);
+        let else_narrowing = narrowing.as_ref().filter(|(_, is_some)| !*is_some);
 
         // Check then branch and get last step's type
-        let mut then_type = ResolvedType::None;
-        for step in &then_steps {
-            self.check_step(step);
-            then_type = self.locals.get(&step.output_binding)
-                .cloned()
-                .unwrap_or(ResolvedType::None);
-        }
+        let then_type = self.check_branch(&if_step.then_steps, then_narrowing.map(|(name, _)| name.as_str()));
 
         // Check else branch if present
-        let mut else_type = ResolvedType::None;
-        if let Some(else_steps) = &else_steps {
-            for step in else_steps {
-                self.check_step(step);
-                else_type = self.locals.get(&step.output_binding)
-                    .cloned()
-                    .unwrap_or(ResolvedType::None);
-            }
-        }
+        let else_type = match &if_step.else_steps {
+            Some(else_steps) => self.check_branch(else_steps, else_narrowing.map(|(name, _)| name.as_str())),
+            None => ResolvedType::None,
+        };
 
         // Unify branch types if both produce values
         match (&then_type, &else_type) {
@@ -580,17 +1786,43 @@ impl SnippetChecker {
         }
     }
 
+    /// Check the steps of a branch and return its last step's type. A
+    /// `narrowed` optional binding has its inner type within the branch.
+    fn check_branch(&mut self, steps: &[Step], narrowed: Option<&str>) -> ResolvedType {
+        let saved = narrowed.and_then(|name| {
+            let ResolvedType::Optional(inner) = self.locals.get(name)?.clone() else { return None };
+            self.locals.insert(name.to_string(), *inner.clone());
+            Some((name.to_string(), ResolvedType::Optional(inner)))
+        });
+
+        let mut branch_type = ResolvedType::None;
+        for step in steps {
+            self.check_step(step);
+            branch_type = self.locals.get(&step.output_binding)
+                .cloned()
+                .unwrap_or(ResolvedType::None);
+        }
+
+        if let Some((name, optional)) = saved {
+            self.locals.insert(name, optional);
+        }
+        branch_type
+    }
+
     /// Infer type of a bind step
     fn infer_bind_step(&mut self, bind: &BindStep) -> ResolvedType {
         match &bind.source {
             BindSource::Var(name) => {
-                self.locals.get(name).cloned().unwrap_or_else(|| {
+                self.local_or_constant(name).unwrap_or_else(|| {
                     self.errors.push(CheckError::UndefinedSymbol { name: name.clone() });
                     ResolvedType::Error
                 })
             }
             BindSource::Lit(lit) => self.literal_type(lit),
             BindSource::Field { of, field } => {
+                if self.locals.get(of).is_some_and(|t| self.unit_base(t).is_some()) {
+                    return self.resolve_source_type(&InputSource::Field { of: of.clone(), field: field.clone() });
+                }
                 // Look up the struct type and field
                 if let Some(struct_type) = self.locals.get(of) {
                     if let ResolvedType::Struct(fields) = struct_type {
@@ -601,11 +1833,49 @@ impl SnippetChecker {
                     } else {
                         ResolvedType::Unknown
                     }
+                } else if let Some((ty, _)) = self.constants.get(&format!("{}.{}", of, field)) {
+                    ty.clone()
                 } else {
                     self.errors.push(CheckError::UndefinedSymbol { name: of.clone() });
                     ResolvedType::Error
                 }
             }
+            BindSource::Variant(v) => self.variant_type(v),
+        }
+    }
+
+    /// Whether a union return type admits a variant construction. The variant
+    /// may name a member directly (`Error`) or be a variant of a member enum
+    /// (`ParseError::MissingField`, or bare `MissingField`).
+    fn union_has_variant(&self, members: &[ResolvedType], variant: &str) -> bool {
+        let variant_name = extract_variant_name(variant);
+        let enum_path = variant.rsplit_once("::").map(|(path, _)| path);
+        members.iter().any(|member| match member {
+            ResolvedType::Named { name, .. } => {
+                name == variant
+                    || extract_variant_name(name) == variant_name
+                    || enum_path == Some(name.as_str())
+                    || self.type_registry.get_enum_variants(name)
+                        .is_some_and(|variants| variants.contains(&variant_name))
+            }
+            ResolvedType::Unknown | ResolvedType::Error => true,
+            _ => false,
+        })
+    }
+
+    /// Infer type of a variant construction, checking its field inputs
+    fn variant_type(&mut self, v: &VariantConstruction) -> ResolvedType {
+        for field in &v.fields {
+            if let InputSource::Var(name) | InputSource::Field { of: name, .. } = &field.value {
+                if !self.locals.contains_key(name) {
+                    self.errors.push(CheckError::UndefinedSymbol { name: name.clone() });
+                }
+            }
+        }
+        ResolvedType::Named {
+            name: v.ty.clone(),
+            id: covenant_ast::SymbolId(0), // Placeholder
+            args: vec![],
         }
     }
 
@@ -624,6 +1894,12 @@ impl SnippetChecker {
         // Check exhaustiveness
         self.check_match_exhaustiveness(match_step, &matched_type);
 
+        // Codegen keeps no tag telling which member a union holds, so type
+        // arms could never be dispatched; the arms are still checked below
+        if match_step.cases.iter().any(|case| matches!(case.pattern, MatchPattern::Type { .. })) {
+            self.errors.push(CheckError::UnsupportedTypeMatch { on: match_step.on.clone() });
+        }
+
         // Clone cases to avoid borrow issues
         let cases = match_step.cases.clone();
 
@@ -643,6 +1919,15 @@ impl SnippetChecker {
                 }
             }
 
+            // A type arm narrows the matched binding to that union member
+            let saved = match &case.pattern {
+                MatchPattern::Type { ty } => self.union_member(&matched_type, ty).map(|member| {
+                    let saved = self.locals.insert(match_step.on.clone(), member);
+                    (match_step.on.clone(), saved)
+                }),
+                _ => None,
+            };
+
             // Check case steps
             for step in &case.steps {
                 self.check_step(step);
@@ -654,6 +1939,10 @@ impl SnippetChecker {
                     case_types.push(ty.clone());
                 }
             }
+
+            if let Some((name, Some(original))) = saved {
+                self.locals.insert(name, original);
+            }
         }
 
         // Return the result type of the match
@@ -669,9 +1958,76 @@ impl SnippetChecker {
     }
 
     /// Infer type of a construct step
+    ///
+    /// With `from_base`, the base must be a value of the constructed struct
+    /// type and each listed field must be a field of it, given a value of
+    /// the field's type.
     fn infer_construct_step(&mut self, construct: &StructConstruction) -> ResolvedType {
         // The type of a construct step is the struct type being constructed
-        self.resolve_type(&construct.ty)
+        let struct_type = self.resolve_type(&construct.ty);
+        if let Some(base) = self.unit_base(&struct_type) {
+            self.check_unit_construction(construct, &struct_type, &base);
+            return struct_type;
+        }
+        let Some(base) = &construct.from_base else {
+            return struct_type;
+        };
+
+        let base_type = self.resolve_source_type(&InputSource::Var(base.clone()));
+        if !self.types_compatible(&struct_type, &base_type) {
+            self.errors.push(CheckError::TypeMismatch {
+                expected: struct_type.display(),
+                found: base_type.display(),
+            });
+        }
+        if let ResolvedType::Named { name, .. } = &struct_type {
+            if self.type_registry.get_struct(name).is_some() {
+                for field in &construct.fields {
+                    let Some(field_type) = self.type_registry.get_struct_field(name, &field.name).cloned() else {
+                        self.errors.push(CheckError::UnknownField {
+                            field: field.name.clone(),
+                            type_name: name.clone(),
+                        });
+                        continue;
+                    };
+                    let value_type = self.resolve_source_type(&field.value);
+                    if !self.types_compatible(&field_type, &value_type) {
+                        self.errors.push(CheckError::TypeMismatch {
+                            expected: field_type.display(),
+                            found: value_type.display(),
+                        });
+                    }
+                }
+            }
+        }
+        struct_type
+    }
+
+    /// Check that a construct step wraps a single base-typed `value` in a unit alias
+    fn check_unit_construction(&mut self, construct: &StructConstruction, unit_type: &ResolvedType, base: &ResolvedType) {
+        let ResolvedType::Named { name, .. } = unit_type else { return };
+        if construct.fields.iter().all(|f| f.name != "value") {
+            self.errors.push(CheckError::TypeMismatch {
+                expected: base.display(),
+                found: "no value field".to_string(),
+            });
+        }
+        for field in &construct.fields {
+            if field.name != "value" {
+                self.errors.push(CheckError::UnknownField {
+                    field: field.name.clone(),
+                    type_name: name.clone(),
+                });
+                continue;
+            }
+            let value_type = self.resolve_source_type(&field.value);
+            if !self.types_compatible(base, &value_type) {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: base.display(),
+                    found: self.unit_display(&value_type),
+                });
+            }
+        }
     }
 
     /// Get the binding type for a variant pattern
@@ -717,10 +2073,14 @@ impl SnippetChecker {
 
     /// Resolve an input's type
     fn resolve_input_type(&mut self, input: &Input) -> ResolvedType {
-        match &input.source {
+        self.resolve_source_type(&input.source)
+    }
+
+    fn resolve_source_type(&mut self, source: &InputSource) -> ResolvedType {
+        match source {
             InputSource::Var(name) => {
-                match self.locals.get(name) {
-                    Some(ty) => ty.clone(),
+                match self.local_or_constant(name) {
+                    Some(ty) => ty,
                     None => {
                         self.errors.push(CheckError::UndefinedSymbol {
                             name: name.clone(),
@@ -731,7 +2091,16 @@ impl SnippetChecker {
             }
             InputSource::Lit(lit) => self.literal_type(lit),
             InputSource::Field { of, field } => {
-                if let Some(struct_type) = self.locals.get(of) {
+                if let Some(base) = self.locals.get(of).and_then(|t| self.unit_base(t)) {
+                    // A unit alias unwraps to its base type through `value`
+                    if field != "value" {
+                        self.errors.push(CheckError::UnknownField {
+                            field: field.clone(),
+                            type_name: self.locals[of].display(),
+                        });
+                    }
+                    base
+                } else if let Some(struct_type) = self.locals.get(of) {
                     if let ResolvedType::Struct(fields) = struct_type {
                         fields.iter()
                             .find(|(name, _)| name == field)
@@ -740,6 +2109,8 @@ impl SnippetChecker {
                     } else {
                         ResolvedType::Unknown
                     }
+                } else if let Some((ty, _)) = self.constants.get(&format!("{}.{}", of, field)) {
+                    ty.clone()
                 } else {
                     ResolvedType::Unknown
                 }
@@ -747,15 +2118,15 @@ impl SnippetChecker {
         }
     }
 
+    /// Type of a local binding, or of the const snippet with that ID when no
+    /// local shadows it
+    fn local_or_constant(&self, name: &str) -> Option<ResolvedType> {
+        self.locals.get(name).or_else(|| self.constants.get(name).map(|(ty, _)| ty)).cloned()
+    }
+
     /// Get the type of a literal
     fn literal_type(&self, lit: &Literal) -> ResolvedType {
-        match lit {
-            Literal::Int(_) => ResolvedType::Int,
-            Literal::Float(_) => ResolvedType::Float,
-            Literal::Bool(_) => ResolvedType::Bool,
-            Literal::String(_) => ResolvedType::String,
-            Literal::None => ResolvedType::None,
-        }
+        ResolvedType::of_literal(lit)
     }
 
     /// Check if two types are compatible
@@ -778,6 +2149,9 @@ impl SnippetChecker {
             (ResolvedType::Char, ResolvedType::Char) => true,
             (ResolvedType::Bytes, ResolvedType::Bytes) => true,
             (ResolvedType::DateTime, ResolvedType::DateTime) => true,
+            (ResolvedType::Duration, ResolvedType::Duration) => true,
+            (ResolvedType::Decimal, ResolvedType::Decimal) => true,
+            (ResolvedType::BigInt, ResolvedType::BigInt) => true,
             (ResolvedType::None, ResolvedType::None) => true,
 
             // Optional types
@@ -791,6 +2165,9 @@ impl SnippetChecker {
             // Set types
             (ResolvedType::Set(e), ResolvedType::Set(f)) => self.types_compatible(e, f),
 
+            // Array types, which must also agree in length
+            (ResolvedType::Array(e, n), ResolvedType::Array(f, m)) => n == m && self.types_compatible(e, f),
+
             // Union types - value must be compatible with at least one member
             (ResolvedType::Union(members), found) => {
                 members.iter().any(|m| self.types_compatible(m, found))
@@ -856,10 +2233,21 @@ impl SnippetChecker {
             "Char" => ResolvedType::Char,
             "Bytes" => ResolvedType::Bytes,
             "DateTime" => ResolvedType::DateTime,
+            "Duration" => ResolvedType::Duration,
+            "Decimal" => ResolvedType::Decimal,
+            "BigInt" => ResolvedType::BigInt,
             _ => {
-                if let Some(bracket_pos) = name.find('<') {
+                if let Some((params, ret)) = split_function_type(name) {
+                    ResolvedType::Function {
+                        params: params.into_iter().map(|p| self.resolve_type_name(p)).collect(),
+                        ret: Box::new(self.resolve_type_name(ret)),
+                    }
+                } else if let Some(bracket_pos) = name.find('<') {
                     let base_name = &name[..bracket_pos];
                     let args_str = &name[bracket_pos + 1..name.len() - 1];
+                    if base_name == "Array" {
+                        return self.resolve_array_type(args_str);
+                    }
                     let inner_type = self.resolve_type_name(args_str);
                     match base_name {
                         "List" => ResolvedType::List(Box::new(inner_type)),
@@ -871,6 +2259,8 @@ impl SnippetChecker {
                             args: vec![inner_type],
                         },
                     }
+                } else if let Some(target) = self.type_aliases.get(name) {
+                    self.resolve_type(target)
                 } else {
                     ResolvedType::Named {
                         name: name.to_string(),
@@ -882,6 +2272,21 @@ impl SnippetChecker {
         }
     }
 
+    /// Resolve the arguments of inline `Array<T, N>` syntax. A length that
+    /// is not a non-negative integer leaves an unresolved named type.
+    fn resolve_array_type(&self, args: &str) -> ResolvedType {
+        let parts = args.rsplit_once(',')
+            .and_then(|(elem, len)| Some((elem.trim(), len.trim().parse::<usize>().ok()?)));
+        match parts {
+            Some((elem, len)) => ResolvedType::Array(Box::new(self.resolve_type_name(elem)), len),
+            None => ResolvedType::Named {
+                name: format!("Array<{}>", args),
+                id: covenant_ast::SymbolId(0),
+                args: vec![],
+            },
+        }
+    }
+
     /// Resolve an AST Type to a ResolvedType
     fn resolve_type(&self, ty: &Type) -> ResolvedType {
         match &ty.kind {
@@ -895,6 +2300,9 @@ impl SnippetChecker {
                     "Char" => ResolvedType::Char,
                     "Bytes" => ResolvedType::Bytes,
                     "DateTime" => ResolvedType::DateTime,
+                    "Duration" => ResolvedType::Duration,
+                    "Decimal" => ResolvedType::Decimal,
+                    "BigInt" => ResolvedType::BigInt,
                     "List" => {
                         let inner = path.generics.first()
                             .map(|t| self.resolve_type(t))
@@ -914,10 +2322,18 @@ impl SnippetChecker {
                         ResolvedType::Set(Box::new(inner))
                     }
                     _ => {
+                        if let Some(target) = self.type_aliases.get(name) {
+                            self.resolve_type(target)
+                        // Handle inline function syntax (e.g. "(Int) -> Int" from attribute parsing)
+                        } else if split_function_type(name).is_some() {
+                            self.resolve_type_name(name)
                         // Handle inline generic syntax (e.g. "List<String>" from attribute parsing)
-                        if let Some(bracket_pos) = name.find('<') {
+                        } else if let Some(bracket_pos) = name.find('<') {
                             let base_name = &name[..bracket_pos];
                             let args_str = &name[bracket_pos + 1..name.len() - 1]; // strip < and >
+                            if base_name == "Array" {
+                                return self.resolve_array_type(args_str);
+                            }
                             let inner_type = self.resolve_type_name(args_str);
                             match base_name {
                                 "List" => ResolvedType::List(Box::new(inner_type)),
@@ -1023,6 +2439,51 @@ impl SnippetChecker {
         );
     }
 
+    /// Register the unit aliases declared in a snippet's types section (first
+    /// pass); units apply to numbers only
+    fn register_unit_types(&mut self, snippet: &Snippet) {
+        for section in &snippet.sections {
+            let Section::Types(types) = section else { continue };
+            for decl in &types.types {
+                let Some(unit) = &decl.unit else { continue };
+                let base = self.resolve_type(&decl.ty);
+                if !matches!(base, ResolvedType::Int | ResolvedType::Float) {
+                    self.errors.push(CheckError::TypeMismatch {
+                        expected: "Int or Float".to_string(),
+                        found: base.display(),
+                    });
+                }
+                self.unit_types.insert(decl.name.clone(), (base, unit.clone()));
+            }
+        }
+    }
+
+    /// Register the alias snippets, dropping any alias that refers to itself
+    /// (directly or through other aliases) so resolution terminates
+    fn register_type_aliases(&mut self, snippets: &[Snippet]) {
+        for snippet in snippets {
+            if snippet.kind != SnippetKind::Alias {
+                continue;
+            }
+            let Some(alias_sig) = find_alias_signature(snippet) else { continue };
+            if self.type_aliases.contains_key(&alias_sig.name) {
+                self.errors.push(CheckError::DuplicateDefinition { name: alias_sig.name.clone() });
+                continue;
+            }
+            self.type_aliases.insert(alias_sig.name.clone(), alias_sig.ty.clone());
+        }
+
+        let mut recursive: Vec<String> = self.type_aliases.keys()
+            .filter(|name| alias_reaches(&self.type_aliases, name, name, &mut HashSet::new()))
+            .cloned()
+            .collect();
+        recursive.sort();
+        for name in recursive {
+            self.type_aliases.remove(&name);
+            self.errors.push(CheckError::RecursiveAlias { name });
+        }
+    }
+
     /// Register an enum type (first pass)
     fn register_enum_type(&mut self, snippet: &Snippet) {
         let enum_sig = match find_enum_signature(snippet) {
@@ -1060,10 +2521,70 @@ impl SnippetChecker {
         );
     }
 
+    /// Register a const snippet (first pass), checking its value against
+    /// the declared type
+    fn register_constant(&mut self, snippet: &Snippet) {
+        let const_sig = match find_const_signature(snippet) {
+            Some(c) => c,
+            None => return,
+        };
+
+        let declared = self.resolve_type(&const_sig.ty);
+        let value_type = self.literal_type(&const_sig.value);
+        if !self.types_compatible(&declared, &value_type) {
+            self.errors.push(CheckError::TypeMismatch {
+                expected: declared.display(),
+                found: value_type.display(),
+            });
+        }
+        self.constants.insert(snippet.id.clone(), (declared, const_sig.value.clone()));
+    }
+
     /// Check a struct snippet (second pass - validate field types)
-    fn check_struct_snippet(&mut self, _snippet: &Snippet) {
-        // Struct validation happens during registration
-        // Future: could validate field type references exist
+    ///
+    /// Field types are validated during registration; invariant checks are
+    /// checked here with the struct's fields in scope, as is the single
+    /// field of a newtype.
+    fn check_struct_snippet(&mut self, snippet: &Snippet) {
+        let Some(struct_sig) = find_struct_signature(snippet) else {
+            return;
+        };
+        if struct_sig.newtype && struct_sig.fields.len() != 1 {
+            self.errors.push(CheckError::InvalidNewtype {
+                name: struct_sig.name.clone(),
+                fields: struct_sig.fields.len(),
+            });
+        }
+        let fields: Vec<(String, ResolvedType)> = struct_sig.fields.iter()
+            .map(|f| (f.name.clone(), self.resolve_type(&f.ty)))
+            .collect();
+        let context = format!("invariant of '{}'", struct_sig.name);
+
+        self.locals.clear();
+        self.locals.extend(fields);
+        for section in &snippet.sections {
+            if let Section::Invariant(invariant) = section {
+                for check in &invariant.checks {
+                    self.check_condition(check.op, &check.inputs, context.clone());
+                }
+            }
+        }
+        self.locals.clear();
+    }
+
+    /// Check that an assert or invariant condition evaluates to Bool
+    fn check_condition(&mut self, op: Operation, inputs: &[Input], context: String) {
+        let found = self.infer_compute_step(&ComputeStep {
+            op,
+            inputs: inputs.to_vec(),
+            span: Span::dummy(),
+        });
+        if !matches!(found, ResolvedType::Bool | ResolvedType::Error | ResolvedType::Unknown) {
+            self.errors.push(CheckError::NonBoolCondition {
+                context,
+                found: found.display(),
+            });
+        }
     }
 
     /// Check an enum snippet (second pass - validate variant types)
@@ -1072,6 +2593,30 @@ impl SnippetChecker {
         // Future: could validate variant field type references exist
     }
 
+    /// The member of a union-typed match target named by a `case type=` arm,
+    /// or None (with an error) when the arm names no member of it
+    fn union_member(&mut self, matched_type: &ResolvedType, ty: &Type) -> Option<ResolvedType> {
+        let member = self.resolve_type(ty);
+        match matched_type {
+            ResolvedType::Union(members) if members.contains(&member) => Some(member),
+            ResolvedType::Union(_) => {
+                self.errors.push(CheckError::IncompatibleUnion {
+                    value_type: member.display(),
+                    union_type: matched_type.display(),
+                });
+                None
+            }
+            ResolvedType::Error | ResolvedType::Unknown => None,
+            _ => {
+                self.errors.push(CheckError::TypeMismatch {
+                    expected: "a union type".to_string(),
+                    found: matched_type.display(),
+                });
+                None
+            }
+        }
+    }
+
     /// Get variant names for a type (for match exhaustiveness)
     fn get_type_variants(&self, ty: &ResolvedType) -> Option<Vec<String>> {
         match ty {
@@ -1108,6 +2653,9 @@ impl SnippetChecker {
                     let variant_name = extract_variant_name(variant);
                     covered.insert(variant_name);
                 }
+                MatchPattern::Type { ty } => {
+                    covered.insert(self.resolve_type(ty).display());
+                }
                 MatchPattern::Wildcard => {
                     has_wildcard = true;
                 }
@@ -1224,6 +2772,54 @@ impl Default for SnippetChecker {
 
 // Free functions to avoid borrow issues
 
+/// Whether a call result carries no value (`Unit`, or no declared return)
+fn is_unit(ty: &ResolvedType) -> bool {
+    match ty {
+        ResolvedType::None => true,
+        ResolvedType::Named { name, .. } => name == "Unit",
+        _ => false,
+    }
+}
+
+/// The built-in dynamic JSON document type
+fn json_type() -> ResolvedType {
+    ResolvedType::Named { name: "Json".to_string(), id: covenant_ast::SymbolId(0), args: vec![] }
+}
+
+/// The error member of a decoding operation's result union
+fn decode_error_type() -> ResolvedType {
+    ResolvedType::Named { name: "DecodeError".to_string(), id: covenant_ast::SymbolId(0), args: vec![] }
+}
+
+/// Split an inline function type such as `(Int, List<Int>) -> Bool` into
+/// its parameter and return type names
+fn split_function_type(name: &str) -> Option<(Vec<&str>, &str)> {
+    let rest = name.trim().strip_prefix('(')?;
+    let mut depth = 0usize;
+    let mut params = Vec::new();
+    let mut param_start = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '(' | '<' => depth += 1,
+            '>' | ')' if depth > 0 => depth -= 1,
+            ',' if depth == 0 => {
+                params.push(rest[param_start..i].trim());
+                param_start = i + 1;
+            }
+            ')' => {
+                let last = rest[param_start..i].trim();
+                if !last.is_empty() {
+                    params.push(last);
+                }
+                let ret = rest[i + 1..].trim().strip_prefix("->")?.trim();
+                return (!ret.is_empty()).then_some((params, ret));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
 /// Find the function signature in a snippet
 fn find_function_signature(snippet: &Snippet) -> Option<&FunctionSignature> {
     for section in &snippet.sections {
@@ -1270,6 +2866,18 @@ fn find_struct_signature(snippet: &Snippet) -> Option<&StructSignature> {
     None
 }
 
+/// Find the interface signature in a snippet
+fn find_interface_signature(snippet: &Snippet) -> Option<&InterfaceSignature> {
+    for section in &snippet.sections {
+        if let Section::Signature(sig) = section {
+            if let SignatureKind::Interface(iface) = &sig.kind {
+                return Some(iface);
+            }
+        }
+    }
+    None
+}
+
 /// Find the enum signature in a snippet
 fn find_enum_signature(snippet: &Snippet) -> Option<&EnumSignature> {
     for section in &snippet.sections {
@@ -1282,6 +2890,75 @@ fn find_enum_signature(snippet: &Snippet) -> Option<&EnumSignature> {
     None
 }
 
+/// Find the const signature in a snippet
+fn find_const_signature(snippet: &Snippet) -> Option<&ConstSignature> {
+    for section in &snippet.sections {
+        if let Section::Signature(sig) = section {
+            if let SignatureKind::Const(const_sig) = &sig.kind {
+                return Some(const_sig);
+            }
+        }
+    }
+    None
+}
+
+/// Find the alias signature in a snippet
+fn find_alias_signature(snippet: &Snippet) -> Option<&AliasSignature> {
+    for section in &snippet.sections {
+        if let Section::Signature(sig) = section {
+            if let SignatureKind::Alias(alias_sig) = &sig.kind {
+                return Some(alias_sig);
+            }
+        }
+    }
+    None
+}
+
+/// Whether the target of alias `from` names alias `to`, directly or through
+/// other aliases
+fn alias_reaches(aliases: &HashMap<String, Type>, from: &str, to: &str, seen: &mut HashSet<String>) -> bool {
+    if !seen.insert(from.to_string()) {
+        return false;
+    }
+    let Some(target) = aliases.get(from) else { return false };
+    let mut names = TypeNames::default();
+    names.visit_type(target);
+    names.0.iter().any(|name| name == to || alias_reaches(aliases, name, to, seen))
+}
+
+/// Every type name a type mentions, including those inside inline generic
+/// syntax such as `List<UserId>`
+#[derive(Default)]
+struct TypeNames(Vec<String>);
+
+impl Visitor for TypeNames {
+    fn visit_type(&mut self, ty: &Type) {
+        if let TypeKind::Named(path) = &ty.kind {
+            self.0.extend(
+                path.name()
+                    .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                    .filter(|part| !part.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        walk_type(self, ty);
+    }
+}
+
+/// The source text of an input (`x`, `x.field` or a literal)
+fn input_source_text(source: &InputSource) -> String {
+    match source {
+        InputSource::Var(name) => name.clone(),
+        InputSource::Field { of, field } => format!("{}.{}", of, field),
+        InputSource::Lit(lit) => lit.to_cov(0),
+    }
+}
+
+/// Whether a vector's element type can be a Float
+fn is_float_element(elem: &ResolvedType) -> bool {
+    matches!(elem, ResolvedType::Float | ResolvedType::Unknown | ResolvedType::Error)
+}
+
 /// Extract variant name from full path (e.g., "Json::String" -> "String")
 fn extract_variant_name(full_name: &str) -> String {
     full_name
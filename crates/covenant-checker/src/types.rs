@@ -1,7 +1,10 @@
 //! Resolved type representations
 
 use std::collections::HashMap;
-use covenant_ast::SymbolId;
+use covenant_ast::{Literal, SymbolId};
+
+/// Most decimal places `dec_div` and `dec_round` may round to
+pub const MAX_DECIMAL_PLACES: u32 = 38;
 
 /// A resolved type (after type checking)
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -9,11 +12,17 @@ pub enum ResolvedType {
     /// Primitive types
     Int,
     Float,
+    /// Exact decimal number
+    Decimal,
+    /// Arbitrary-precision integer
+    BigInt,
     Bool,
     String,
     Char,
     Bytes,
     DateTime,
+    /// Signed span of time in whole seconds
+    Duration,
     None,
 
     /// Named type with resolved ID
@@ -32,6 +41,9 @@ pub enum ResolvedType {
     /// Set type
     Set(Box<ResolvedType>),
 
+    /// Fixed-size array type: element type and length
+    Array(Box<ResolvedType>, usize),
+
     /// Union type
     Union(Vec<ResolvedType>),
 
@@ -63,15 +75,73 @@ impl ResolvedType {
         matches!(self, ResolvedType::Optional(_))
     }
 
+    /// The type of a literal, inferring element types of collection literals
+    pub fn of_literal(lit: &Literal) -> ResolvedType {
+        match lit {
+            Literal::Int(_) => ResolvedType::Int,
+            Literal::Float(_) => ResolvedType::Float,
+            Literal::Decimal(_) => ResolvedType::Decimal,
+            Literal::BigInt(_) => ResolvedType::BigInt,
+            Literal::DateTime(_) => ResolvedType::DateTime,
+            Literal::Duration(_) => ResolvedType::Duration,
+            Literal::Bool(_) => ResolvedType::Bool,
+            Literal::String(_) => ResolvedType::String,
+            Literal::None => ResolvedType::None,
+            Literal::List(items) => ResolvedType::List(Box::new(element_type(items))),
+            Literal::Set(items) => ResolvedType::Set(Box::new(element_type(items))),
+            Literal::Map(entries) => {
+                let (keys, values): (Vec<_>, Vec<_>) = entries.iter().cloned().unzip();
+                ResolvedType::Named {
+                    name: "Map".to_string(),
+                    id: SymbolId(0),
+                    args: vec![element_type(&keys), element_type(&values)],
+                }
+            }
+            Literal::Tuple(items) => ResolvedType::Tuple(items.iter().map(Self::of_literal).collect()),
+        }
+    }
+
+    /// Replace the generic params bound in `bindings` with their types
+    pub fn substitute(&self, bindings: &HashMap<String, ResolvedType>) -> ResolvedType {
+        let all = |types: &[ResolvedType]| types.iter().map(|t| t.substitute(bindings)).collect();
+        match self {
+            ResolvedType::Named { name, args, .. } if args.is_empty() && bindings.contains_key(name) => {
+                bindings[name].clone()
+            }
+            ResolvedType::Named { name, id, args } => ResolvedType::Named {
+                name: name.clone(),
+                id: *id,
+                args: all(args),
+            },
+            ResolvedType::Optional(inner) => ResolvedType::Optional(Box::new(inner.substitute(bindings))),
+            ResolvedType::List(inner) => ResolvedType::List(Box::new(inner.substitute(bindings))),
+            ResolvedType::Set(inner) => ResolvedType::Set(Box::new(inner.substitute(bindings))),
+            ResolvedType::Array(inner, len) => ResolvedType::Array(Box::new(inner.substitute(bindings)), *len),
+            ResolvedType::Union(types) => ResolvedType::Union(all(types)),
+            ResolvedType::Tuple(types) => ResolvedType::Tuple(all(types)),
+            ResolvedType::Function { params, ret } => ResolvedType::Function {
+                params: all(params),
+                ret: Box::new(ret.substitute(bindings)),
+            },
+            ResolvedType::Struct(fields) => ResolvedType::Struct(
+                fields.iter().map(|(n, t)| (n.clone(), t.substitute(bindings))).collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
     pub fn display(&self) -> String {
         match self {
             ResolvedType::Int => "Int".to_string(),
             ResolvedType::Float => "Float".to_string(),
+            ResolvedType::Decimal => "Decimal".to_string(),
+            ResolvedType::BigInt => "BigInt".to_string(),
             ResolvedType::Bool => "Bool".to_string(),
             ResolvedType::String => "String".to_string(),
             ResolvedType::Char => "Char".to_string(),
             ResolvedType::Bytes => "Bytes".to_string(),
             ResolvedType::DateTime => "DateTime".to_string(),
+            ResolvedType::Duration => "Duration".to_string(),
             ResolvedType::None => "none".to_string(),
             ResolvedType::Named { name, args, .. } => {
                 if args.is_empty() {
@@ -87,6 +157,7 @@ impl ResolvedType {
             ResolvedType::Optional(inner) => format!("{}?", inner.display()),
             ResolvedType::List(inner) => format!("{}[]", inner.display()),
             ResolvedType::Set(inner) => format!("Set<{}>", inner.display()),
+            ResolvedType::Array(inner, len) => format!("Array<{}, {}>", inner.display(), len),
             ResolvedType::Union(types) => {
                 types.iter().map(|t| t.display()).collect::<Vec<_>>().join(" | ")
             }
@@ -119,6 +190,37 @@ impl ResolvedType {
     }
 }
 
+/// The common type of a collection literal's elements
+///
+/// Ints widen to Float alongside floats, `none` makes the type optional, and
+/// otherwise mixed elements give a union. An empty collection is Unknown.
+fn element_type(items: &[Literal]) -> ResolvedType {
+    let mut types: Vec<ResolvedType> = Vec::new();
+    for ty in items.iter().map(ResolvedType::of_literal) {
+        if !types.contains(&ty) {
+            types.push(ty);
+        }
+    }
+    if types.contains(&ResolvedType::Float) {
+        types.retain(|t| *t != ResolvedType::Int);
+    }
+    let optional = types.len() > 1 && types.contains(&ResolvedType::None);
+    if optional {
+        types.retain(|t| *t != ResolvedType::None);
+    }
+
+    let ty = match types.len() {
+        0 => ResolvedType::Unknown,
+        1 => types.remove(0),
+        _ => ResolvedType::Union(types),
+    };
+    if optional {
+        ResolvedType::Optional(Box::new(ty))
+    } else {
+        ty
+    }
+}
+
 // ============================================================================
 // Type Registry
 // ============================================================================
@@ -0,0 +1,72 @@
+//! Replay-safety checks for workflow snippets
+//!
+//! A workflow resumes after a crash by running again from the start, with
+//! each effectful call answered from its checkpoint instead of being made
+//! again. That is only sound if every run makes the same effectful calls in
+//! the same order, so the checker rejects workflows where the order can vary
+//! or where a checkpoint cannot be traced back to a single step.
+
+use std::collections::HashSet;
+
+use covenant_ast::{QueryContent, Step, StepKind};
+
+use crate::CheckError;
+
+/// Check a workflow body for steps that would not replay deterministically
+///
+/// `is_effectful` reports whether a called function declares effects.
+pub fn check_replay_safety(
+    workflow: &str,
+    steps: &[Step],
+    is_effectful: impl Fn(&str) -> bool,
+) -> Vec<CheckError> {
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+    // Each step list with the concurrent step (if any) it runs under
+    let mut pending: Vec<(&[Step], Option<&'static str>)> = vec![(steps, None)];
+
+    while let Some((list, concurrent)) = pending.pop() {
+        for step in list {
+            // Checkpoints name the step that made the call
+            if !seen.insert(step.id.as_str()) {
+                errors.push(CheckError::DuplicateWorkflowStep {
+                    workflow: workflow.to_string(),
+                    step: step.id.clone(),
+                });
+            }
+
+            // Concurrent branches can finish in a different order on replay
+            if let Some(context) = concurrent {
+                if is_effectful_step(step, &is_effectful) {
+                    errors.push(CheckError::NonReplayableStep {
+                        workflow: workflow.to_string(),
+                        step: step.id.clone(),
+                        context: context.to_string(),
+                    });
+                }
+            }
+
+            let nested_context = match step.kind {
+                StepKind::Parallel(_) => Some("parallel"),
+                StepKind::Race(_) => Some("race"),
+                _ => concurrent,
+            };
+            for nested in step.nested_steps() {
+                pending.push((nested, nested_context));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Whether a step performs an effect itself (nested steps are not included)
+fn is_effectful_step(step: &Step, is_effectful: &impl Fn(&str) -> bool) -> bool {
+    match &step.kind {
+        StepKind::Call(call) => is_effectful(&call.fn_name),
+        StepKind::Using(using) => is_effectful(&using.acquire.fn_name) || is_effectful(&using.release),
+        StepKind::Query(query) => matches!(query.content, QueryContent::Dialect(_)),
+        StepKind::Insert(_) | StepKind::Update(_) | StepKind::Delete(_) => true,
+        _ => false,
+    }
+}
@@ -1,6 +1,9 @@
 //! Tests for effect checking (Phase 3)
 
-use covenant_checker::{check_effects, EffectError};
+use covenant_checker::{
+    check_effects, check_effects_for, check_effects_with_registry, explain_effect_provenance, EffectDef, EffectError,
+    EffectPolicy, EffectRegistry, MAX_PROVENANCE_CHAINS,
+};
 use covenant_symbols::build_symbol_graph;
 use covenant_parser::parse;
 
@@ -1810,3 +1813,422 @@ end
     assert!(result.violations.is_empty(),
         "Exact path match should work");
 }
+
+// ==========================================================================
+// EFFECT PROVENANCE
+// ==========================================================================
+
+#[test]
+fn effect_provenance_traces_chain_to_extern() {
+    let source = r#"
+snippet id="http.get" kind="extern"
+
+effects
+  effect network
+end
+
+signature
+  fn name="get"
+    param name="url" type="String"
+    returns type="String"
+  end
+end
+
+end
+
+snippet id="api.fetch" kind="fn"
+
+effects
+  effect network
+end
+
+signature
+  fn name="fetch"
+    returns type="String"
+  end
+end
+
+body
+  step id="s1" kind="call"
+    fn="http.get"
+    arg name="url" lit="https://example.com"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+
+end
+
+snippet id="app.main" kind="fn"
+
+effects
+  effect network
+end
+
+signature
+  fn name="main"
+    returns type="String"
+  end
+end
+
+body
+  step id="s1" kind="call"
+    fn="api.fetch"
+    as="a"
+  end
+  step id="s2" kind="call"
+    fn="http.get"
+    arg name="url" lit="https://example.org"
+    as="b"
+  end
+  step id="s3" kind="return"
+    from="a"
+    as="_"
+  end
+end
+
+end
+"#;
+
+    let program = parse(source).expect("parse failed");
+    let graph = build_symbol_graph(&program).expect("symbol graph failed").graph;
+    let result = check_effects(&graph);
+
+    let chains = explain_effect_provenance("app.main", "network", &graph, &result);
+    let names: Vec<Vec<&str>> = chains
+        .iter()
+        .map(|chain| chain.iter().map(|e| e.name.as_str()).collect())
+        .collect();
+
+    assert_eq!(
+        names,
+        vec![
+            vec!["app.main", "http.get"],
+            vec!["app.main", "api.fetch", "http.get"],
+        ]
+    );
+
+    assert!(explain_effect_provenance("app.main", "database", &graph, &result).is_empty());
+}
+
+#[test]
+fn effect_provenance_is_bounded_on_dense_graphs() {
+    // Twelve layers of four functions, each calling every function of the
+    // next layer, over one extern: 4^12 chains from the top
+    let mut source = String::from(
+        "snippet id=\"net.send\" kind=\"extern\"\neffects\n  effect network\nend\nsignature\n  fn name=\"send\"\n    returns type=\"Int\"\n  end\nend\nend\n",
+    );
+    for layer in 0..12 {
+        for i in 0..4 {
+            let callees: Vec<String> = if layer == 11 {
+                vec!["net.send".to_string()]
+            } else {
+                (0..4).map(|j| format!("app.f{}_{}", layer + 1, j)).collect()
+            };
+            let calls: String = callees
+                .iter()
+                .enumerate()
+                .map(|(c, callee)| format!("  step id=\"c{c}\" kind=\"call\"\n    fn=\"{callee}\"\n    as=\"r{c}\"\n  end\n"))
+                .collect();
+            source.push_str(&format!(
+                "snippet id=\"app.f{layer}_{i}\" kind=\"fn\"\neffects\n  effect network\nend\nsignature\n  fn name=\"f{layer}_{i}\"\n    returns type=\"Int\"\n  end\nend\nbody\n{calls}  step id=\"s\" kind=\"return\"\n    lit=1\n    as=\"_\"\n  end\nend\nend\n"
+            ));
+        }
+    }
+
+    let program = parse(&source).expect("parse failed");
+    let graph = build_symbol_graph(&program).expect("symbol graph failed").graph;
+    let result = check_effects(&graph);
+
+    let chains = explain_effect_provenance("app.f0_0", "network", &graph, &result);
+    assert_eq!(chains.len(), MAX_PROVENANCE_CHAINS);
+    assert!(chains.iter().all(|chain| chain.len() == 13 && chain.last().unwrap().name == "net.send"));
+}
+
+#[test]
+fn check_effects_for_subset_only_reports_named_symbols() {
+    let source = r#"
+snippet id="io.print" kind="extern"
+
+effects
+  effect console
+end
+
+signature
+  fn name="print"
+    param name="msg" type="String"
+    returns type="Bool"
+  end
+end
+
+end
+
+snippet id="app.a" kind="fn"
+
+signature
+  fn name="a"
+    returns type="Bool"
+  end
+end
+
+body
+  step id="s1" kind="call"
+    fn="io.print"
+    arg name="msg" lit="a"
+    as="r"
+  end
+  step id="s2" kind="return"
+    from="r"
+    as="_"
+  end
+end
+
+end
+
+snippet id="app.b" kind="fn"
+
+signature
+  fn name="b"
+    returns type="Bool"
+  end
+end
+
+body
+  step id="s1" kind="call"
+    fn="io.print"
+    arg name="msg" lit="b"
+    as="r"
+  end
+  step id="s2" kind="return"
+    from="r"
+    as="_"
+  end
+end
+
+end
+"#;
+
+    let program = parse(source).expect("parse failed");
+    let graph = build_symbol_graph(&program).expect("symbol graph failed").graph;
+
+    assert_eq!(check_effects(&graph).violations.len(), 2);
+
+    let subset = check_effects_for(&graph, &["app.b".to_string()]);
+    assert_eq!(subset.violations.len(), 1);
+    assert!(subset.closures.contains_key("app.b"));
+    assert!(!subset.closures.contains_key("app.a"));
+}
+
+// ==========================================================================
+// EFFECT REGISTRY
+// ==========================================================================
+
+/// An extern declaring `CALLEE` and a function declaring `CALLER` that calls it
+const REGISTRY_SOURCE: &str = r#"
+snippet id="srv.listen" kind="extern"
+
+effects
+  effect CALLEE
+end
+
+signature
+  fn name="listen"
+    returns type="Int"
+  end
+end
+
+end
+
+snippet id="app.main" kind="fn"
+
+effects
+  effect CALLER
+end
+
+signature
+  fn name="main"
+    returns type="Int"
+  end
+end
+
+body
+  step id="s1" kind="call"
+    fn="srv.listen"
+    as="r"
+  end
+  step id="s2" kind="return"
+    from="r"
+    as="_"
+  end
+end
+
+end
+"#;
+
+fn registry_graph(caller: &str, callee: &str) -> covenant_symbols::SymbolGraph {
+    let source = REGISTRY_SOURCE.replace("CALLEE", callee).replace("CALLER", caller);
+    let program = parse(&source).expect("parse failed");
+    build_symbol_graph(&program).expect("symbol graph failed").graph
+}
+
+#[test]
+fn registry_reports_unknown_effect_with_suggestion() {
+    let graph = registry_graph("netwrok", "network");
+    let result = check_effects_with_registry(&graph, &EffectRegistry::std());
+
+    let unknown: Vec<_> = result
+        .violations
+        .iter()
+        .filter_map(|v| match v {
+            EffectError::UnknownEffect { function, effect, suggestion, .. } => Some((function, effect, suggestion)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(unknown[0].0, "app.main");
+    assert_eq!(unknown[0].1, "netwrok");
+    assert_eq!(unknown[0].2.as_deref(), Some("network"));
+
+    // Without a registry the typo is just another effect
+    assert!(check_effects(&graph)
+        .violations
+        .iter()
+        .all(|v| !matches!(v, EffectError::UnknownEffect { .. })));
+}
+
+#[test]
+fn registry_parent_covers_child_effect() {
+    let graph = registry_graph("network", "http_server");
+
+    assert!(check_effects_with_registry(&graph, &EffectRegistry::std()).violations.is_empty());
+    assert_eq!(check_effects(&graph).violations.len(), 1, "names alone do not cover children");
+
+    // The child does not cover its parent
+    let graph = registry_graph("http_server", "network");
+    assert!(matches!(
+        check_effects_with_registry(&graph, &EffectRegistry::std()).violations.as_slice(),
+        [EffectError::MissingEffect { .. }]
+    ));
+}
+
+#[test]
+fn registry_accepts_custom_effects() {
+    let graph = registry_graph("network", "payments");
+    let registry = EffectRegistry::std()
+        .with_effects([(
+            "payments".to_string(),
+            EffectDef {
+                description: "Charge cards".to_string(),
+                parent: Some("network".to_string()),
+            },
+        )])
+        .unwrap();
+
+    assert!(check_effects_with_registry(&graph, &registry).violations.is_empty());
+    assert!(matches!(
+        check_effects_with_registry(&graph, &EffectRegistry::std()).violations.as_slice(),
+        [EffectError::UnknownEffect { suggestion: None, .. }, EffectError::MissingEffect { .. }]
+    ));
+}
+
+// ==========================================================================
+// RESOURCE-SCOPED EFFECTS
+// ==========================================================================
+
+fn scoped_violations(caller: &str, callee: &str) -> Vec<EffectError> {
+    check_effects_with_registry(&registry_graph(caller, callee), &EffectRegistry::std()).violations
+}
+
+#[test]
+fn scoped_host_wildcard_covers_subdomains() {
+    let callee = r#"network.http(host="api.stripe.com")"#;
+    assert!(scoped_violations(r#"network.http(host="api.stripe.com")"#, callee).is_empty());
+    assert!(scoped_violations(r#"network.http(host="*.stripe.com")"#, callee).is_empty());
+    assert!(scoped_violations(r#"network.http(host="API.Stripe.com")"#, callee).is_empty());
+
+    assert!(matches!(
+        scoped_violations(r#"network.http(host="*.api.stripe.com")"#, callee).as_slice(),
+        [EffectError::ParameterNotCovered { param_name, .. }] if param_name == "host"
+    ));
+    assert_eq!(scoped_violations(r#"network.http(host="stripe.com")"#, callee).len(), 1);
+    assert_eq!(scoped_violations(r#"network.http(host="*.stripe.com")"#, r#"network.http(host="stripe.com")"#).len(), 1);
+}
+
+#[test]
+fn scoped_path_matches_whole_directories() {
+    let callee = r#"filesystem.read(path="/data/users")"#;
+    assert!(scoped_violations(r#"filesystem.read(path="/data")"#, callee).is_empty());
+    assert!(scoped_violations(r#"filesystem.read(path="/data/")"#, callee).is_empty());
+
+    match scoped_violations(r#"filesystem.read(path="/data/user")"#, callee).as_slice() {
+        [EffectError::ParameterNotCovered { effect_name, required_value, declared_value, .. }] => {
+            assert_eq!(effect_name, "filesystem.read");
+            assert_eq!(required_value, "\"/data/users\"");
+            assert_eq!(declared_value.as_deref(), Some("\"/data/user\""));
+        }
+        other => panic!("Expected ParameterNotCovered, got {:?}", other),
+    }
+}
+
+#[test]
+fn scoped_parent_effect_scopes_children() {
+    // `filesystem` is the parent of `filesystem.read`, so its path applies
+    assert!(scoped_violations(r#"filesystem(path="/data")"#, r#"filesystem.read(path="/data/x")"#).is_empty());
+    assert_eq!(scoped_violations(r#"filesystem(path="/tmp")"#, r#"filesystem.read(path="/data/x")"#).len(), 1);
+    assert!(scoped_violations("network", r#"network.http(host="api.stripe.com")"#).is_empty());
+
+    // The child does not cover its parent
+    assert!(matches!(
+        scoped_violations(r#"filesystem.read(path="/data")"#, r#"filesystem(path="/data")"#).as_slice(),
+        [EffectError::MissingEffect { .. }]
+    ));
+}
+
+#[test]
+fn scoped_any_declaration_may_grant_resource() {
+    let caller = "network.http(host=\"api.github.com\")\n  effect network.http(host=\"*.stripe.com\")";
+    assert!(scoped_violations(caller, r#"network.http(host="api.stripe.com")"#).is_empty());
+    assert_eq!(scoped_violations(caller, r#"network.http(host="example.com")"#).len(), 1);
+}
+
+// ==========================================================================
+// EFFECT POLICY
+// ==========================================================================
+
+fn policy_violations(caller: &str, callee: &str) -> Vec<EffectError> {
+    let registry = EffectRegistry::std()
+        .with_policy(EffectPolicy::new().allow("network", "host", ["api.example.com", "*.stripe.com"]));
+    check_effects_with_registry(&registry_graph(caller, callee), &registry).violations
+}
+
+#[test]
+fn policy_allows_covered_hosts() {
+    assert!(policy_violations(r#"network.http host="api.example.com""#, "network.http").is_empty());
+    assert!(policy_violations(r#"network.http host="checkout.stripe.com""#, "network.http").is_empty());
+
+    // The extern's own unscoped declaration is not held to the policy
+    match policy_violations(r#"network.http host="evil.example.org""#, "network.http").as_slice() {
+        [EffectError::PolicyViolation { function, effect, param_name, declared_value, allowed, .. }] => {
+            assert_eq!(function, "app.main");
+            assert_eq!(effect, "network.http");
+            assert_eq!(param_name, "host");
+            assert_eq!(declared_value.as_deref(), Some("\"evil.example.org\""));
+            assert_eq!(allowed.len(), 2);
+        }
+        other => panic!("Expected PolicyViolation, got {:?}", other),
+    }
+}
+
+#[test]
+fn policy_requires_restricted_params() {
+    // A bare parent declaration would reach any host
+    assert!(matches!(
+        policy_violations("network", "network.http").as_slice(),
+        [EffectError::PolicyViolation { declared_value: None, .. }]
+    ));
+    // Unrelated effects are unrestricted
+    assert!(policy_violations("console", "console").is_empty());
+}
@@ -63,6 +63,52 @@ end
     check_source_ok(source);
 }
 
+#[test]
+fn test_list_literal_element_type() {
+    let source = r#"
+snippet id="test.fn" kind="fn"
+signature
+  fn name="test_fn"
+    returns type="List<Float>"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=[1, 2.5]
+    as="_"
+  end
+end
+end
+"#;
+    // Ints widen to Float alongside floats
+    check_source_ok(source);
+}
+
+#[test]
+fn test_list_literal_element_mismatch() {
+    let source = r#"
+snippet id="test.fn" kind="fn"
+signature
+  fn name="test_fn"
+    returns type="List<Int>"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=["a", "b"]
+    as="_"
+  end
+end
+end
+"#;
+    let errors = check_source_has_errors(source);
+    assert!(
+        errors.iter().any(|e| e.to_string().contains("String[]")),
+        "Expected mismatch on String[], got {:?}",
+        errors
+    );
+}
+
 // === Undefined Variable Tests ===
 
 #[test]
@@ -971,6 +1017,103 @@ end
     check_source_ok(source);
 }
 
+const NARROW_FN: &str = r#"
+snippet id="test.fn" kind="fn"
+signature
+  fn name="test_fn"
+    param name="x" type="Optional<Int>"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=CHECK
+    input var="x"
+    as="present"
+  end
+  step id="s2" kind="if"
+    condition="present"
+    then
+      step id="s2a" kind="return"
+        from="THEN"
+        as="_"
+      end
+    end
+    else
+      step id="s2b" kind="return"
+        from="ELSE"
+        as="_"
+      end
+    end
+    as="_"
+  end
+end
+end
+"#;
+
+fn narrow_source(check: &str, then_from: &str, else_from: &str) -> String {
+    NARROW_FN.replace("CHECK", check).replace("THEN", then_from).replace("ELSE", else_from)
+}
+
+#[test]
+fn test_none_check_narrows_optional() {
+    let zero = r#"step id="s0" kind="bind"
+    lit=0
+    as="zero"
+  end
+  "#;
+    let source = narrow_source("is_some", "x", "zero").replacen("step id=\"s1\"", &format!("{}step id=\"s1\"", zero), 1);
+    check_source_ok(&source);
+    let source = narrow_source("is_none", "zero", "x").replacen("step id=\"s1\"", &format!("{}step id=\"s1\"", zero), 1);
+    check_source_ok(&source);
+}
+
+#[test]
+fn test_narrowing_applies_only_in_checked_branch() {
+    // After is_none, x is still optional in the then branch
+    let errors = check_source_has_errors(&narrow_source("is_none", "x", "x"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int" && found == "Int?")));
+}
+
+#[test]
+fn test_unwrap_or_yields_inner_type() {
+    let source = r#"
+snippet id="test.fn" kind="fn"
+signature
+  fn name="test_fn"
+    param name="x" type="Optional<Int>"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=unwrap_or
+    input var="x"
+    input DEFAULT
+    as="value"
+  end
+  step id="s2" kind="return"
+    from="value"
+    as="_"
+  end
+end
+end
+"#;
+    check_source_ok(&source.replace("DEFAULT", "lit=0"));
+    let errors = check_source_has_errors(&source.replace("DEFAULT", "lit=\"zero\""));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int" && found == "String")));
+}
+
+#[test]
+fn test_none_check_on_non_optional() {
+    let source = narrow_source("is_some", "x", "x").replace("Optional<Int>", "Int");
+    let errors = check_source_has_errors(&source);
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int?" && found == "Int")));
+}
+
 // === Union Type Tests ===
 
 #[test]
@@ -1020,7 +1163,6 @@ end
 }
 
 #[test]
-#[ignore = "Union type member checking not yet implemented"]
 fn test_union_return_non_member_is_error() {
     let source = r#"
 snippet id="test.fn" kind="fn"
@@ -1044,6 +1186,80 @@ end
     assert!(!errors.is_empty(), "Should error when returning Bool for union of Int|String");
 }
 
+const UNION_MATCH_FN: &str = r#"
+snippet id="test.fn" kind="fn"
+signature
+  fn name="test_fn"
+    param name="text" type="String"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=parse_int
+    input var="text"
+    as="parsed"
+  end
+  step id="s2" kind="match"
+    on="MATCHED"
+    case type="ARM"
+      step id="s2a" kind="return"
+        from="MATCHED"
+        as="_"
+      end
+    end
+    OTHER_ARMS
+    as="_"
+  end
+end
+end
+"#;
+
+const PARSE_ERROR_ARM: &str = r#"case type="ParseError"
+      step id="s2b" kind="return"
+        lit=0
+        as="_"
+      end
+    end"#;
+
+fn union_match_source(matched: &str, arm: &str, other_arms: &str) -> String {
+    UNION_MATCH_FN.replace("MATCHED", matched).replace("OTHER_ARMS", other_arms).replace("ARM", arm)
+}
+
+#[test]
+fn test_type_arm_narrows_union() {
+    // `parsed` is Int inside the Int arm, so it can be returned as Int; the
+    // only error is that compiled unions cannot be matched by type
+    let errors = check_source_has_errors(&union_match_source("parsed", "Int", PARSE_ERROR_ARM));
+    assert!(matches!(errors.as_slice(),
+        [covenant_checker::CheckError::UnsupportedTypeMatch { on }] if on == "parsed"),
+        "Expected only the unsupported type match error, got: {:?}", errors);
+}
+
+#[test]
+fn test_type_arm_must_name_a_member() {
+    let errors = check_source_has_errors(&union_match_source("parsed", "String", PARSE_ERROR_ARM));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::IncompatibleUnion { value_type, .. } if value_type == "String")),
+        "Expected incompatible union error, got: {:?}", errors);
+}
+
+#[test]
+fn test_type_arms_must_cover_union() {
+    let errors = check_source_has_errors(&union_match_source("parsed", "Int", ""));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::NonExhaustiveMatch { missing, .. } if missing == &["ParseError"])),
+        "Expected non-exhaustive match error, got: {:?}", errors);
+}
+
+#[test]
+fn test_type_arm_requires_union() {
+    let errors = check_source_has_errors(&union_match_source("text", "String", ""));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "a union type" && found == "String")),
+        "Expected type mismatch, got: {:?}", errors);
+}
+
 // === List Type Tests ===
 
 #[test]
@@ -1310,3 +1526,2262 @@ end
 "#;
     check_source_ok(source);
 }
+
+// === Cancellation ===
+
+#[test]
+fn test_cancelled_check_stops_early() {
+    let source = r#"
+snippet id="test.fn" kind="fn"
+signature
+  fn name="test_fn"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=1
+    as="_"
+  end
+end
+end
+"#;
+    let program = parse(source).expect("parse failed");
+    let token = covenant_ast::CancellationToken::new();
+    assert!(covenant_checker::check_with_cancel(&program, &token).is_ok());
+
+    token.cancel();
+    let errors = covenant_checker::check_with_cancel(&program, &token).unwrap_err();
+    assert!(matches!(errors.as_slice(), [covenant_checker::CheckError::Cancelled]));
+}
+
+// === Structural Limits ===
+
+#[test]
+fn test_nesting_depth_limit_reports_e_limit_001() {
+    let source = r#"
+snippet id="test.deep" kind="fn"
+signature
+  fn name="deep"
+    param name="c" type="Bool"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="if"
+    condition="c"
+    then
+      step id="s2" kind="if"
+        condition="c"
+        then
+          step id="s3" kind="return"
+            lit=1
+            as="_"
+          end
+        end
+        as="_"
+      end
+    end
+    as="_"
+  end
+  step id="s4" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+"#;
+    let program = parse(source).expect("parse failed");
+    let limits = covenant_ast::Limits { max_nesting_depth: 2, ..Default::default() };
+    let errors = covenant_checker::check_with_limits(&program, limits).unwrap_err();
+    assert!(matches!(
+        errors.as_slice(),
+        [covenant_checker::CheckError::NestingTooDeep { depth: 3, limit: 2, .. }]
+    ));
+
+    let diagnostic = covenant_checker::Diagnostic::from(errors.into_iter().next().unwrap());
+    assert_eq!(diagnostic.code, "E-LIMIT-001");
+    // Points at the first step past the limit
+    assert_eq!(diagnostic.span.start, source.find("step id=\"s3\"").unwrap());
+}
+
+// === Transactions ===
+
+const TRANSACTION_FN: &str = r#"
+snippet id="db.transfer" kind="fn"
+EFFECTS
+signature
+  fn name="transfer"
+    param name="amount" type="Int"
+    returns type="Bool"
+  end
+end
+body
+  step id="s1" kind="transaction"
+    target="bank_db"
+    isolation="serializable"
+    step id="t1" kind="query"
+      dialect="postgres"
+      body
+        UPDATE accounts SET balance = balance - :amount
+      end
+      params
+        param name="amount" from="amount"
+      end
+      as="_"
+    end
+    as="tx"
+  end
+  step id="s2" kind="return"
+    lit=true
+    as="_"
+  end
+end
+end
+"#;
+
+#[test]
+fn test_transaction_with_database_effect() {
+    check_source_ok(&TRANSACTION_FN.replace("EFFECTS", "effects\n  effect database\nend"));
+}
+
+#[test]
+fn test_transaction_requires_database_effect() {
+    let errors = check_source_has_errors(&TRANSACTION_FN.replace("EFFECTS", ""));
+    assert!(
+        errors.iter().any(|e| matches!(
+            e,
+            covenant_checker::CheckError::EffectNotAllowed { effect } if effect == "database"
+        )),
+        "Expected database effect error, got: {:?}",
+        errors
+    );
+}
+
+const TRANSACTION_RESULT_FN: &str = r#"
+snippet id="db.deposit" kind="fn"
+effects
+  effect database
+end
+signature
+  fn name="deposit"
+    param name="amount" type="Int"
+    returns type="RETURNS"
+  end
+end
+body
+  step id="s1" kind="transaction"
+    target="bank_db"
+    step id="t1" kind="compute"
+      op=add
+      input var="amount"
+      input lit=1
+      as="total"
+    end
+    as="tx"
+  end
+  step id="s2" kind="return"
+    from="tx"
+    as="_"
+  end
+end
+end
+"#;
+
+#[test]
+fn test_transaction_result_is_last_step_type() {
+    check_source_ok(&TRANSACTION_RESULT_FN.replace("RETURNS", "Int"));
+
+    let errors = check_source_has_errors(&TRANSACTION_RESULT_FN.replace("RETURNS", "String"));
+    assert!(
+        errors.iter().any(|e| matches!(
+            e,
+            covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "String" && found == "Int"
+        )),
+        "Expected the transaction's Int result to mismatch String, got: {:?}",
+        errors
+    );
+}
+
+// === Variant Construction ===
+
+const VARIANT_RETURN_FN: &str = r#"
+snippet id="test.variant" kind="fn"
+signature
+  fn name="get_result"
+    returns union
+      type="Int"
+      type="Error"
+    end
+  end
+end
+body
+  step id="s1" kind="return"
+    variant type="VARIANT"
+    field name="message" lit="Something went wrong"
+    as="_"
+  end
+end
+end
+"#;
+
+#[test]
+fn test_return_variant_in_union() {
+    check_source_ok(&VARIANT_RETURN_FN.replace("VARIANT", "Error"));
+}
+
+#[test]
+fn test_return_variant_not_in_union() {
+    let errors = check_source_has_errors(&VARIANT_RETURN_FN.replace("VARIANT", "Timeout"));
+    assert!(
+        errors.iter().any(|e| matches!(
+            e,
+            covenant_checker::CheckError::IncompatibleUnion { value_type, .. } if value_type == "Timeout"
+        )),
+        "Expected incompatible union error, got: {:?}",
+        errors
+    );
+}
+
+// === Loop Control ===
+
+const LOOP_CONTROL_FN: &str = r#"
+snippet id="test.loop" kind="fn"
+signature
+  fn name="sum_until_negative"
+    param name="items" type="List<Int>"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="for"
+    var="item" in="items"
+    step id="s1a" kind="compute"
+      op=less
+      input var="item"
+      input lit=0
+      as="negative"
+    end
+    step id="s1b" kind="if"
+      condition="negative"
+      then
+        step id="s1c" kind="break"
+          as="_"
+        end
+      end
+      as="_"
+    end
+    as="_"
+  end
+  step id="s2" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+"#;
+
+#[test]
+fn test_break_inside_loop() {
+    check_source_ok(LOOP_CONTROL_FN);
+}
+
+#[test]
+fn test_break_outside_loop_is_error() {
+    // The parser rejects this too; the checker guards ASTs built elsewhere
+    let mut program = parse(LOOP_CONTROL_FN).expect("parse failed");
+    let covenant_ast::Program::Snippets { snippets, .. } = &mut program else {
+        panic!("Expected Snippets program");
+    };
+    let body = snippets[0].sections.iter_mut().find_map(|s| {
+        if let covenant_ast::Section::Body(b) = s { Some(b) } else { None }
+    }).expect("body section not found");
+    let covenant_ast::StepKind::For(for_step) = &body.steps[0].kind else {
+        panic!("Expected for step");
+    };
+    let covenant_ast::StepKind::If(if_step) = &for_step.steps[1].kind else {
+        panic!("Expected if step");
+    };
+    let break_step = if_step.then_steps[0].clone();
+    body.steps[0] = break_step;
+
+    let errors = check(&program).unwrap_err();
+    assert!(
+        errors.iter().any(|e| matches!(
+            e,
+            covenant_checker::CheckError::LoopControlOutsideLoop { kind } if kind == "break"
+        )),
+        "Expected loop control error, got: {:?}",
+        errors
+    );
+}
+
+// === Lambdas ===
+
+const LAMBDA_FN: &str = r#"
+snippet id="test.lambda" kind="fn"
+signature
+  fn name="add_offset"
+    param name="n" type="Int"
+    param name="offset" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="lambda"
+    param name="x" type="Int"
+    returns type="Int"
+    step id="s1a" kind="compute"
+      op=add
+      input var="x"
+      input var="offset"
+      as="shifted"
+    end
+    step id="s1b" kind="return"
+      from="shifted"
+      as="_"
+    end
+    as="shift"
+  end
+  step id="s2" kind="call"
+    fn="shift"
+    ARGS
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+
+#[test]
+fn test_lambda_call_captures_enclosing_binding() {
+    check_source_ok(&LAMBDA_FN.replace("ARGS", r#"arg name="x" from="n""#));
+}
+
+#[test]
+fn test_lambda_call_argument_count_mismatch() {
+    let args = r#"arg name="x" from="n"
+    arg name="y" lit=1"#;
+    let errors = check_source_has_errors(&LAMBDA_FN.replace("ARGS", args));
+    assert!(
+        errors.iter().any(|e| matches!(
+            e,
+            covenant_checker::CheckError::ArgumentCountMismatch { name, expected: 1, found: 2 } if name == "shift"
+        )),
+        "Expected argument count error, got: {:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_lambda_call_argument_type_mismatch() {
+    let errors = check_source_has_errors(&LAMBDA_FN.replace("ARGS", r#"arg name="x" lit="one""#));
+    assert!(
+        errors.iter().any(|e| matches!(e, covenant_checker::CheckError::TypeMismatch { .. })),
+        "Expected type mismatch error, got: {:?}",
+        errors
+    );
+}
+
+const HIGHER_ORDER_FNS: &str = r#"
+snippet id="test.apply_twice" kind="fn"
+signature
+  fn name="apply_twice"
+    param name="f" type="(Int) -> Int"
+    param name="x" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="f"
+    arg name="x" from="x"
+    as="once"
+  end
+  step id="s2" kind="call"
+    fn="f"
+    arg name="x" from="once"
+    as="twice"
+  end
+  step id="s3" kind="return"
+    from="twice"
+    as="_"
+  end
+end
+end
+
+snippet id="test.quadruple" kind="fn"
+signature
+  fn name="quadruple"
+    param name="n" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="lambda"
+    param name="x" type="Int"
+    step id="s1a" kind="compute"
+      op=mul
+      input var="x"
+      input lit=2
+      as="doubled"
+    end
+    step id="s1b" kind="return"
+      from="doubled"
+      as="_"
+    end
+    as="double"
+  end
+  step id="s2" kind="call"
+    fn="apply_twice"
+    arg name="f" from="double"
+    arg name="x" from="n"
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+
+#[test]
+fn test_lambda_passed_to_higher_order_function() {
+    check_source_ok(HIGHER_ORDER_FNS);
+}
+
+// === Workflows ===
+
+const WORKFLOW: &str = r#"
+snippet id="payments.charge" kind="extern"
+effects
+  effect network
+end
+signature
+  fn name="charge"
+    param name="order_id" type="String"
+    returns type="String"
+  end
+end
+end
+
+snippet id="orders.fulfill" kind="workflow"
+effects
+  effect network
+end
+signature
+  fn name="fulfill"
+    param name="order_id" type="String"
+    returns type="String"
+  end
+end
+body
+  STEPS
+  step id="done" kind="return"
+    lit="ok"
+    as="_"
+  end
+end
+end
+"#;
+
+const CHARGE_STEP: &str = r#"step id="charge" kind="call"
+    fn="payments.charge"
+    arg name="order_id" from="order_id"
+    as="payment"
+  end"#;
+
+#[test]
+fn test_workflow_with_sequential_effects() {
+    check_source_ok(&WORKFLOW.replace("STEPS", CHARGE_STEP));
+}
+
+#[test]
+fn test_workflow_effect_inside_race_is_error() {
+    let steps = format!(
+        r#"step id="first" kind="race"
+    branch id="b1"
+      {}
+    end
+    as="winner"
+  end"#,
+        CHARGE_STEP
+    );
+    let errors = check_source_has_errors(&WORKFLOW.replace("STEPS", &steps));
+    assert!(
+        errors.iter().any(|e| matches!(
+            e,
+            covenant_checker::CheckError::NonReplayableStep { step, context, .. }
+                if step == "charge" && context == "race"
+        )),
+        "Expected non-replayable step error, got: {:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_workflow_duplicate_step_id_is_error() {
+    let steps = format!("{}\n  {}", CHARGE_STEP, CHARGE_STEP);
+    let errors = check_source_has_errors(&WORKFLOW.replace("STEPS", &steps));
+    assert!(
+        errors.iter().any(|e| matches!(
+            e,
+            covenant_checker::CheckError::DuplicateWorkflowStep { workflow, step }
+                if workflow == "orders.fulfill" && step == "charge"
+        )),
+        "Expected duplicate step error, got: {:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_pure_steps_inside_parallel_are_allowed_in_workflows() {
+    let steps = r#"step id="both" kind="parallel"
+    branch id="b1"
+      step id="upper" kind="call"
+        fn="text.upper"
+        arg name="s" from="order_id"
+        as="upper"
+      end
+    end
+    as="_"
+  end"#;
+    check_source_ok(&WORKFLOW.replace("STEPS", steps));
+}
+
+#[test]
+fn test_locale_steps_inside_parallel_are_not_replayable() {
+    // Unlike text.upper, locale.upper depends on host data and declares an effect
+    let steps = r#"step id="both" kind="parallel"
+    branch id="b1"
+      step id="upper" kind="call"
+        fn="locale.upper"
+        arg name="s" from="order_id"
+        arg name="locale" lit="tr"
+        as="upper"
+      end
+    end
+    as="_"
+  end"#;
+    let errors = check_source_has_errors(&WORKFLOW.replace("STEPS", steps));
+    assert!(
+        errors.iter().any(|e| matches!(
+            e,
+            covenant_checker::CheckError::NonReplayableStep { step, .. } if step == "upper"
+        )),
+        "Expected non-replayable step error, got: {:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_random_steps_inside_parallel_are_not_replayable() {
+    // Hashes are pure; a UUID draws from the random effect
+    let steps = r#"step id="both" kind="parallel"
+    branch id="b1"
+      step id="digest" kind="call"
+        fn="crypto.blake3"
+        arg name="input" from="order_id"
+        as="digest"
+      end
+    end
+    branch id="b2"
+      step id="token" kind="call"
+        fn="crypto.uuid_v4"
+        as="token"
+      end
+    end
+    as="_"
+  end"#;
+    let errors = check_source_has_errors(&WORKFLOW.replace("STEPS", steps));
+    let steps: Vec<&str> = errors.iter().filter_map(|e| match e {
+        covenant_checker::CheckError::NonReplayableStep { step, .. } => Some(step.as_str()),
+        _ => None,
+    }).collect();
+    assert_eq!(steps, ["token"], "Expected only the UUID step to be non-replayable, got: {:?}", errors);
+}
+
+// === Schedule Tests ===
+
+#[test]
+fn test_valid_schedule() {
+    let source = r#"
+snippet id="jobs.cleanup" kind="fn"
+metadata
+  trigger schedule="*/15 9-17 * * 1-5"
+end
+signature
+  fn name="cleanup"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+"#;
+    check_source_ok(source);
+}
+
+#[test]
+fn test_invalid_schedule() {
+    let source = r#"
+snippet id="jobs.cleanup" kind="fn"
+metadata
+  trigger schedule="0 25 * * *"
+end
+signature
+  fn name="cleanup"
+    param name="x" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="x"
+    as="_"
+  end
+end
+end
+"#;
+    let errors = check_source_has_errors(source);
+    assert!(errors.iter().any(|e| matches!(e, covenant_checker::CheckError::InvalidSchedule { .. })));
+    assert!(errors.iter().any(|e| matches!(e, covenant_checker::CheckError::UnschedulableSnippet { .. })));
+}
+
+#[test]
+fn test_route_handler() {
+    let source = r#"
+snippet id="users.get" kind="fn"
+metadata
+  trigger route="GET /users/:id"
+end
+signature
+  fn name="get_user"
+    param name="id" type="Int"
+    returns type="String" optional
+  end
+end
+body
+  step id="s1" kind="return"
+    lit="user"
+    as="_"
+  end
+end
+end
+"#;
+    check_source_ok(source);
+}
+
+#[test]
+fn test_invalid_route() {
+    let source = r#"
+snippet id="users.list" kind="fn"
+metadata
+  trigger route="FETCH users"
+end
+signature
+  fn name="list_users"
+    param name="ids" type="Int[]"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+"#;
+    let errors = check_source_has_errors(source);
+    assert!(errors.iter().any(|e| matches!(e, covenant_checker::CheckError::InvalidRoute { .. })));
+    assert!(errors.iter().any(|e| matches!(e, covenant_checker::CheckError::UnroutableSnippet { .. })));
+}
+
+// === Event Tests ===
+
+const EVENTS: &str = r#"
+snippet id="orders.OrderPlaced" kind="struct"
+signature
+  struct name="OrderPlaced"
+    field name="id" type="Int"
+  end
+end
+end
+
+snippet id="orders.notify" kind="fn"
+signature
+  fn name="notify"
+    param name="evt" type="HANDLER_PARAM"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+
+snippet id="orders.place" kind="fn"
+effects
+  effect events
+end
+signature
+  fn name="place"
+    param name="order" type="OrderPlaced"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="events.publish"
+    arg name="event_type" lit="EVENT_TYPE"
+    arg name="event" from="order"
+    as="_"
+  end
+  step id="s2" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+
+snippet id="orders.on_placed" kind="subscriber"
+subscribe
+  event type="orders.OrderPlaced"
+  handler fn="orders.notify"
+end
+end
+"#;
+
+fn events_source(handler_param: &str, event_type: &str) -> String {
+    EVENTS.replace("HANDLER_PARAM", handler_param).replace("EVENT_TYPE", event_type)
+}
+
+#[test]
+fn test_valid_subscriber_and_publish() {
+    check_source_ok(&events_source("OrderPlaced", "OrderPlaced"));
+}
+
+#[test]
+fn test_subscriber_handler_param_mismatch() {
+    let errors = check_source_has_errors(&events_source("String", "OrderPlaced"));
+    assert!(errors.iter().any(|e| matches!(e, covenant_checker::CheckError::InvalidSubscription { .. })));
+}
+
+#[test]
+fn test_publish_unknown_event_type() {
+    let errors = check_source_has_errors(&events_source("OrderPlaced", "OrderShipped"));
+    assert!(errors.iter().any(|e| matches!(e, covenant_checker::CheckError::InvalidEventPublish { .. })));
+}
+
+// === Resource Tests ===
+
+const USING: &str = r#"
+snippet id="pool.open" kind="fn"
+signature
+  fn name="open"
+    param name="size" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="size"
+    as="_"
+  end
+end
+end
+
+snippet id="pool.close" kind="fn"
+signature
+  fn name="close"
+    param name="handle" type="RELEASE_PARAM"
+    returns type="Bool"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=true
+    as="_"
+  end
+end
+end
+
+snippet id="pool.use" kind="fn"
+signature
+  fn name="use_pool"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="using"
+    var="handle"
+    acquire fn="pool.open"
+      arg name="size" lit=4
+    release fn="pool.close"
+    step id="s1a" kind="compute"
+      op=add
+      input var="handle"
+      input lit=1
+      as="next"
+    end
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+
+#[test]
+fn test_valid_using_step() {
+    check_source_ok(&USING.replace("RELEASE_PARAM", "Int"));
+}
+
+#[test]
+fn test_using_release_param_mismatch() {
+    let errors = check_source_has_errors(&USING.replace("RELEASE_PARAM", "String"));
+    assert!(errors.iter().any(|e| matches!(e, covenant_checker::CheckError::ReleaseMismatch { .. })));
+}
+
+// === Assertion Tests ===
+
+const CHECKS: &str = r#"
+snippet id="shop.Range" kind="struct"
+signature
+  struct name="Range"
+    field name="min" type="Int"
+    field name="max" type="Int"
+  end
+end
+invariant
+  check op=INVARIANT_OP input var="min" input var="max" message="min must not exceed max"
+end
+end
+
+snippet id="shop.span" kind="fn"
+signature
+  fn name="span"
+    param name="n" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="assert"
+    op=ASSERT_OP
+    input var="n"
+    input lit=0
+    message="n must not be negative"
+    as="_"
+  end
+  step id="s2" kind="return"
+    from="n"
+    as="_"
+  end
+end
+end
+"#;
+
+fn checks_source(invariant_op: &str, assert_op: &str) -> String {
+    CHECKS.replace("INVARIANT_OP", invariant_op).replace("ASSERT_OP", assert_op)
+}
+
+#[test]
+fn test_valid_assert_and_invariant() {
+    check_source_ok(&checks_source("less_eq", "greater_eq"));
+}
+
+#[test]
+fn test_assert_condition_must_be_bool() {
+    let errors = check_source_has_errors(&checks_source("less_eq", "add"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::NonBoolCondition { context, .. } if context == "assert step 's1'")));
+}
+
+#[test]
+fn test_invariant_condition_must_be_bool() {
+    let errors = check_source_has_errors(&checks_source("sub", "greater_eq"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::NonBoolCondition { context, .. } if context == "invariant of 'Range'")));
+}
+
+// === Raise Tests ===
+
+const RAISE: &str = r#"
+snippet id="users.find" kind="fn"
+signature
+  fn name="find"
+    param name="id" type="Int"
+    RETURNS
+  end
+end
+body
+  step id="s1" kind="raise"
+    variant type="RAISED"
+      field name="id" from="id"
+    end
+    as="_"
+  end
+end
+end
+"#;
+
+const UNION_RETURN: &str = r#"returns union
+      type="User"
+      type="NotFound"
+      type="DbError"
+    end"#;
+
+fn raise_source(returns: &str, raised: &str) -> String {
+    RAISE.replace("RETURNS", returns).replace("RAISED", raised)
+}
+
+#[test]
+fn test_valid_raise_of_error_member() {
+    check_source_ok(&raise_source(UNION_RETURN, "NotFound"));
+}
+
+#[test]
+fn test_raise_of_success_type_is_error() {
+    let errors = check_source_has_errors(&raise_source(UNION_RETURN, "User"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::InvalidRaise { raised, reason } if raised == "User" && reason.contains("success type"))));
+}
+
+#[test]
+fn test_raise_of_non_member_is_error() {
+    let errors = check_source_has_errors(&raise_source(UNION_RETURN, "Timeout"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::InvalidRaise { raised, .. } if raised == "Timeout")));
+}
+
+#[test]
+fn test_raise_without_union_return_is_error() {
+    let errors = check_source_has_errors(&raise_source(r#"returns type="Int""#, "NotFound"));
+    assert!(errors.iter().any(|e| matches!(e, covenant_checker::CheckError::InvalidRaise { .. })));
+}
+
+// === Overflow-Explicit Arithmetic ===
+
+const OVERFLOW_OP: &str = r#"
+snippet id="test.fn" kind="fn"
+signature
+  fn name="test"
+    param name="a" type="PARAM"
+    returns type="Int"RETURNS
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=OP
+    input var="a"
+    input lit=1
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+
+fn overflow_source(op: &str, param: &str, returns: &str) -> String {
+    OVERFLOW_OP.replace("OP", op).replace("PARAM", param).replace("RETURNS", returns)
+}
+
+#[test]
+fn test_wrapping_and_saturating_ops_return_int() {
+    check_source_ok(&overflow_source("add_wrapping", "Int", ""));
+    check_source_ok(&overflow_source("mul_saturating", "Int", ""));
+}
+
+#[test]
+fn test_checked_op_returns_optional_int() {
+    check_source_ok(&overflow_source("sub_checked", "Int", " optional"));
+    check_source_has_errors(&overflow_source("sub_checked", "Int", ""));
+}
+
+#[test]
+fn test_overflow_explicit_op_rejects_float() {
+    let errors = check_source_has_errors(&overflow_source("add_wrapping", "Float", ""));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int" && found == "Float")));
+}
+
+// === Record Updates ===
+
+const RECORD_UPDATE: &str = r#"
+snippet id="shop.Order" kind="struct"
+signature
+  struct name="Order"
+    field name="id" type="Int"
+    field name="status" type="String"
+  end
+end
+end
+
+snippet id="shop.Invoice" kind="struct"
+signature
+  struct name="Invoice"
+    field name="id" type="Int"
+  end
+end
+end
+
+snippet id="shop.ship" kind="fn"
+signature
+  fn name="ship"
+    param name="order" type="Order"
+    param name="invoice" type="Invoice"
+    returns type="Order"
+  end
+end
+body
+  step id="s1" kind="construct"
+    type="Order"
+    from_base="BASE"
+    field name="FIELD" lit=VALUE
+    as="shipped"
+  end
+  step id="s2" kind="return"
+    from="shipped"
+    as="_"
+  end
+end
+end
+"#;
+
+fn record_update_source(base: &str, field: &str, value: &str) -> String {
+    RECORD_UPDATE.replace("BASE", base).replace("FIELD", field).replace("VALUE", value)
+}
+
+#[test]
+fn test_valid_construct_from_base() {
+    check_source_ok(&record_update_source("order", "status", "\"shipped\""));
+}
+
+#[test]
+fn test_construct_from_base_of_other_type_is_error() {
+    let errors = check_source_has_errors(&record_update_source("invoice", "status", "\"shipped\""));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Order" && found == "Invoice")));
+}
+
+#[test]
+fn test_construct_from_base_unknown_field_is_error() {
+    let errors = check_source_has_errors(&record_update_source("order", "total", "1"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::UnknownField { field, .. } if field == "total")));
+}
+
+#[test]
+fn test_construct_from_base_field_type_mismatch() {
+    let errors = check_source_has_errors(&record_update_source("order", "status", "1"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "String" && found == "Int")));
+}
+
+// === Generic Calls ===
+
+const GENERIC_CALL: &str = r#"
+snippet id="util.first_or" kind="fn"
+signature
+  fn name="first_or"
+    generic name="T"
+    param name="items" type="List<T>"
+    param name="fallback" type="T"
+    returns type="T"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="fallback"
+    as="_"
+  end
+end
+end
+
+snippet id="app.top" kind="fn"
+signature
+  fn name="top"
+    param name="scores" type="List<Int>"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="first_or"
+    TYPE_ARGS
+    arg name="items" from="scores"
+    arg name="fallback" lit=FALLBACK
+    as="best"
+  end
+  step id="s2" kind="return"
+    from="best"
+    as="_"
+  end
+end
+end
+"#;
+
+fn generic_call_source(type_args: &str, fallback: &str) -> String {
+    GENERIC_CALL.replace("TYPE_ARGS", type_args).replace("FALLBACK", fallback)
+}
+
+#[test]
+fn test_valid_generic_call_with_type_arg() {
+    check_source_ok(&generic_call_source("type arg=\"Int\"", "0"));
+}
+
+#[test]
+fn test_generic_call_infers_type_from_args() {
+    check_source_ok(&generic_call_source("", "0"));
+}
+
+#[test]
+fn test_generic_call_inferred_type_is_checked() {
+    // T is inferred as String from fallback, so List<Int> does not fit List<T>
+    let errors = check_source_has_errors(&generic_call_source("", "\"none\""));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "String[]" && found == "Int[]")));
+}
+
+#[test]
+fn test_generic_call_arg_mismatch_after_substitution() {
+    let errors = check_source_has_errors(&generic_call_source("type arg=\"Int\"", "\"none\""));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int" && found == "String")));
+}
+
+#[test]
+fn test_generic_call_substitutes_return_type() {
+    // T = String, so the call returns String where top returns Int
+    let errors = check_source_has_errors(&generic_call_source("type arg=\"String\"", "\"none\""));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int" && found == "String")));
+}
+
+#[test]
+fn test_generic_call_type_arg_count_mismatch() {
+    let errors = check_source_has_errors(&generic_call_source("type arg=\"Int\"\n    type arg=\"String\"", "0"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeArgumentCountMismatch { name, expected: 1, found: 2 } if name == "first_or")));
+}
+
+// === Numeric Conversions ===
+
+const CONVERSION: &str = r#"
+snippet id="math.convert" kind="fn"
+signature
+  fn name="convert"
+    param name="ratio" type="Float"
+    param name="count" type="Int"
+    returns type="TYPE"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=OP
+    input INPUT
+    as="converted"
+  end
+  step id="s2" kind="return"
+    from="converted"
+    as="_"
+  end
+end
+end
+"#;
+
+fn conversion_warnings(ty: &str, op: &str, input: &str) -> Vec<covenant_checker::ConversionWarning> {
+    let source = CONVERSION.replace("TYPE", ty).replace("OP", op).replace("INPUT", input);
+    let program = parse(&source).expect("parse failed");
+    check(&program).expect("check failed").warnings
+}
+
+#[test]
+fn test_to_int_of_float_warns() {
+    let warnings = conversion_warnings("Int", "to_int", "var=\"ratio\"");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, covenant_checker::ConversionKind::Truncating);
+    assert_eq!(warnings[0].code(), "W-CONV-001");
+    assert_eq!(warnings[0].step, "s1");
+    assert_eq!(warnings[0].snippet, "math.convert");
+}
+
+#[test]
+fn test_explicit_rounding_does_not_warn() {
+    for op in ["floor", "ceil", "round", "trunc"] {
+        assert!(conversion_warnings("Int", op, "var=\"ratio\"").is_empty(), "{} warned", op);
+    }
+}
+
+#[test]
+fn test_exact_conversions_do_not_warn() {
+    assert!(conversion_warnings("Int", "to_int", "var=\"count\"").is_empty());
+    assert!(conversion_warnings("Int", "to_int", "lit=3.0").is_empty());
+    assert!(conversion_warnings("Float", "to_float", "var=\"count\"").is_empty());
+    assert!(conversion_warnings("Float", "to_float", "lit=9007199254740992").is_empty());
+}
+
+#[test]
+fn test_to_int_of_fractional_literal_warns() {
+    let warnings = conversion_warnings("Int", "to_int", "lit=2.5");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, covenant_checker::ConversionKind::Truncating);
+}
+
+#[test]
+fn test_to_float_of_large_int_literal_warns() {
+    let warnings = conversion_warnings("Float", "to_float", "lit=9007199254740993");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, covenant_checker::ConversionKind::PrecisionLoss);
+    assert_eq!(warnings[0].code(), "W-CONV-002");
+}
+
+// === Named Constants ===
+
+const NAMED_CONST: &str = r#"
+snippet id="consts.MAX_RETRIES" kind="const"
+signature
+  const name="MAX_RETRIES" type="CONST_TYPE" lit=CONST_VALUE
+end
+end
+
+snippet id="net.retries_left" kind="fn"
+signature
+  fn name="retries_left"
+    param name="attempts" type="Int"
+    returns type="RETURN_TYPE"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=sub
+    input var="consts.MAX_RETRIES"
+    input var="attempts"
+    as="left"
+  end
+  step id="s2" kind="return"
+    from="left"
+    as="_"
+  end
+end
+end
+"#;
+
+fn named_const_source(const_type: &str, value: &str, return_type: &str) -> String {
+    NAMED_CONST
+        .replace("CONST_TYPE", const_type)
+        .replace("CONST_VALUE", value)
+        .replace("RETURN_TYPE", return_type)
+}
+
+#[test]
+fn test_const_usable_in_compute_step() {
+    check_source_ok(&named_const_source("Int", "3", "Int"));
+}
+
+#[test]
+fn test_const_value_must_match_declared_type() {
+    let errors = check_source_has_errors(&named_const_source("Int", "\"three\"", "Int"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int" && found == "String")));
+}
+
+#[test]
+fn test_const_use_has_declared_type() {
+    let errors = check_source_has_errors(&named_const_source("Int", "3", "String"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "String" && found == "Int")));
+}
+
+// === Regex Operations ===
+
+const REGEX_OP: &str = r#"
+snippet id="text.find_order_ids" kind="fn"
+signature
+  fn name="find_order_ids"
+    param name="s" type="String"
+    param name="p" type="String"
+    returns type="RETURNS"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=OP
+    input var="s"
+    input PATTERN
+    input lit="<$1>"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+
+fn regex_source(op: &str, pattern: &str, returns: &str) -> String {
+    REGEX_OP.replace("OP", op).replace("PATTERN", pattern).replace("RETURNS", returns)
+}
+
+fn regex_error(op: &str, pattern: &str, returns: &str) -> (String, String) {
+    let errors = check_source_has_errors(&regex_source(op, pattern, returns));
+    errors.into_iter().find_map(|e| match e {
+        covenant_checker::CheckError::InvalidRegex { pattern, reason } => Some((pattern, reason)),
+        _ => None,
+    }).expect("expected an InvalidRegex error")
+}
+
+#[test]
+fn test_valid_regex_ops() {
+    check_source_ok(&regex_source("regex_replace", r#"lit="ORD-(?<n>[0-9]{4,8})\\b""#, "String"));
+    check_source_ok(&regex_source("regex_capture", r#"lit="^(\\w+)@(\\w+)$""#, "List<String>").replace("    input lit=\"<$1>\"\n", ""));
+    check_source_ok(&regex_source("regex_match", r#"lit="a+?|b*""#, "Bool").replace("    input lit=\"<$1>\"\n", ""));
+    check_source_ok(&regex_source("regex_find_all", r#"lit="[0-9]+""#, "List<String>").replace("    input lit=\"<$1>\"\n", ""));
+}
+
+#[test]
+fn test_invalid_regex_syntax_is_error() {
+    let (pattern, reason) = regex_error("regex_replace", r#"lit="ORD-([0-9]+""#, "String");
+    assert_eq!(pattern, "ORD-([0-9]+");
+    assert!(reason.contains("unclosed group"), "{}", reason);
+}
+
+#[test]
+fn test_unsupported_regex_features_are_errors() {
+    let (_, reason) = regex_error("regex_replace", r#"lit="(a)\\1""#, "String");
+    assert!(reason.contains("backreferences"), "{}", reason);
+    let (_, reason) = regex_error("regex_replace", r#"lit="\\p{L}+""#, "String");
+    assert!(reason.contains("Unicode classes"), "{}", reason);
+    let (_, reason) = regex_error("regex_replace", r#"lit="(?i)ord""#, "String");
+    assert!(reason.contains("flags"), "{}", reason);
+}
+
+#[test]
+fn test_regex_pattern_must_be_literal() {
+    let (pattern, reason) = regex_error("regex_replace", r#"var="p""#, "String");
+    assert_eq!(pattern, "p");
+    assert!(reason.contains("String literal"), "{}", reason);
+}
+
+#[test]
+fn test_regex_capture_returns_string_list() {
+    for op in ["regex_capture", "regex_find_all"] {
+        let source = regex_source(op, r#"lit="(a)(b)""#, "String").replace("    input lit=\"<$1>\"\n", "");
+        let errors = check_source_has_errors(&source);
+        assert!(errors.iter().any(|e| matches!(e,
+            covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "String" && found == "String[]")), "{}", op);
+    }
+}
+
+// === Encoding Operations ===
+
+const DECODE_OP: &str = r#"
+snippet id="codec.decode_token" kind="fn"
+signature
+  fn name="decode_token"
+    param name="token" type="String"
+    returns type="String"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=OP
+    input var="token"
+    as="decoded"
+  end
+  STEPS
+end
+end
+"#;
+
+fn decode_source(op: &str, steps: &str) -> String {
+    DECODE_OP.replace("OP", op).replace("STEPS", steps)
+}
+
+#[test]
+fn test_decoded_bytes_must_be_matched() {
+    let encode_directly = r#"step id="s2" kind="compute"
+    op=bytes_to_base64url
+    input var="decoded"
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end"#;
+    let errors = check_source_has_errors(&decode_source("bytes_from_hex", encode_directly));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Bytes" && found == "Bytes | DecodeError")));
+
+    let matched = r#"step id="s2" kind="match"
+    on="decoded"
+    case variant type="Bytes" bindings=("data")
+      step id="s2a" kind="compute"
+        op=bytes_to_base64url
+        input var="data"
+        as="result"
+      end
+      step id="s2b" kind="return"
+        from="result"
+        as="_"
+      end
+    end
+    case variant type="DecodeError"
+      step id="s2c" kind="return"
+        lit=""
+        as="_"
+      end
+    end
+    as="_"
+  end"#;
+    for op in ["bytes_from_base64", "bytes_from_base64url", "bytes_from_hex"] {
+        check_source_ok(&decode_source(op, matched));
+    }
+}
+
+#[test]
+fn test_url_decode_returns_union() {
+    let return_directly = r#"step id="s2" kind="return"
+    from="decoded"
+    as="_"
+  end"#;
+    let errors = check_source_has_errors(&decode_source("url_decode", return_directly));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "String" && found == "String | DecodeError")));
+    check_source_ok(&decode_source("url_encode", return_directly));
+}
+
+#[test]
+fn test_encoder_input_must_be_bytes() {
+    let encode_token = r#"step id="s2" kind="compute"
+    op=bytes_to_base64
+    input var="token"
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end"#;
+    let errors = check_source_has_errors(&decode_source("url_encode", encode_token));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Bytes" && found == "String")));
+}
+
+// === Durations ===
+
+const DURATION_FN: &str = r#"
+snippet id="time.deadline" kind="fn"
+signature
+  fn name="deadline"
+    param name="start" type="DateTime"
+    param name="grace" type="Duration"
+    param name="days" type="Int"
+    returns type="RETURNS"
+  end
+end
+body
+  STEPS
+end
+end
+"#;
+
+fn duration_source(returns: &str, steps: &str) -> String {
+    DURATION_FN.replace("RETURNS", returns).replace("STEPS", steps)
+}
+
+#[test]
+fn test_datetime_duration_arithmetic() {
+    let deadline = r#"step id="s1" kind="compute"
+    op=dur_days
+    input var="days"
+    as="span"
+  end
+  step id="s2" kind="compute"
+    op=dur_add
+    input var="span"
+    input var="grace"
+    as="total"
+  end
+  step id="s3" kind="compute"
+    op=dt_add
+    input var="start"
+    input var="total"
+    as="result"
+  end
+  step id="s4" kind="return"
+    from="result"
+    as="_"
+  end"#;
+    check_source_ok(&duration_source("DateTime", deadline));
+
+    let elapsed = r#"step id="s1" kind="compute"
+    op=dt_diff
+    input var="start"
+    input var="start"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end"#;
+    check_source_ok(&duration_source("Duration", elapsed));
+    let errors = check_source_has_errors(&duration_source("Int", elapsed));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int" && found == "Duration")));
+}
+
+#[test]
+fn test_int_offset_is_not_a_duration() {
+    let add_days = r#"step id="s1" kind="compute"
+    op=dt_add
+    input var="start"
+    input var="days"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end"#;
+    let errors = check_source_has_errors(&duration_source("DateTime", add_days));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Duration" && found == "Int")));
+
+    // Plain arithmetic would drop the unit
+    let plain_add = add_days.replace("op=dt_add", "op=add").replace("var=\"days\"", "var=\"grace\"");
+    let errors = check_source_has_errors(&duration_source("Int", &plain_add));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int or Float" && found == "DateTime")));
+}
+
+#[test]
+fn test_duration_comparison_needs_durations() {
+    let compare = |other: &str| format!(r#"step id="s1" kind="compute"
+    op=less
+    input var="grace"
+    input {}
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end"#, other);
+    check_source_ok(&duration_source("Bool", &compare(r#"var="grace""#)));
+
+    let errors = check_source_has_errors(&duration_source("Bool", &compare("lit=60")));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Duration" && found == "Int")));
+    check_source_ok(&duration_source("Bool", &compare(r#"lit=duration("5m")"#)));
+}
+
+#[test]
+fn test_datetime_and_duration_literals() {
+    let renew = r#"step id="s1" kind="compute"
+    op=dt_add
+    input lit=datetime("2024-01-01T00:00:00Z")
+    input lit=duration("30d")
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end"#;
+    check_source_ok(&duration_source("DateTime", renew));
+
+    let errors = check_source_has_errors(&duration_source("Duration", renew));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Duration" && found == "DateTime")));
+
+    // A Duration literal is not a DateTime
+    let swapped = renew.replace(r#"datetime("2024-01-01T00:00:00Z")"#, r#"duration("1h")"#);
+    let errors = check_source_has_errors(&duration_source("DateTime", &swapped));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "DateTime" && found == "Duration")));
+}
+
+// === Decimals and BigInts ===
+
+const DECIMAL_FN: &str = r#"
+snippet id="billing.line" kind="fn"
+signature
+  fn name="line"
+    param name="price" type="Decimal"
+    param name="qty" type="Int"
+    param name="places" type="Int"
+    param name="count" type="BigInt"
+    returns type="RETURNS"
+  end
+end
+body
+  STEPS
+end
+end
+"#;
+
+fn decimal_source(returns: &str, steps: &str) -> String {
+    DECIMAL_FN.replace("RETURNS", returns).replace("STEPS", steps)
+}
+
+/// A `dec_div` of the price by 3 at the given places, returned
+fn split_three(places: &str) -> String {
+    format!(r#"step id="s1" kind="compute"
+    op=dec_div
+    input var="price"
+    input lit=3d
+    input {}
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end"#, places)
+}
+
+#[test]
+fn test_decimal_arithmetic() {
+    let total = r#"step id="s1" kind="compute"
+    op=dec_from_int
+    input var="qty"
+    as="qty_d"
+  end
+  step id="s2" kind="compute"
+    op=dec_mul
+    input var="price"
+    input var="qty_d"
+    as="subtotal"
+  end
+  step id="s3" kind="compute"
+    op=dec_add
+    input var="subtotal"
+    input lit=4.99d
+    as="result"
+  end
+  step id="s4" kind="return"
+    from="result"
+    as="_"
+  end"#;
+    check_source_ok(&decimal_source("Decimal", total));
+    let errors = check_source_has_errors(&decimal_source("Float", total));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Float" && found == "Decimal")));
+
+    check_source_ok(&decimal_source("Decimal", &split_three("lit=2")));
+}
+
+#[test]
+fn test_decimal_places_must_be_literal() {
+    let errors = check_source_has_errors(&decimal_source("Decimal", &split_three(r#"var="places""#)));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::InvalidDecimalPlaces { found, .. } if found == "places")));
+
+    let errors = check_source_has_errors(&decimal_source("Decimal", &split_three("lit=39")));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::InvalidDecimalPlaces { found, max: 38 } if found == "39")));
+}
+
+#[test]
+fn test_exact_numbers_need_their_own_ops() {
+    let plain = |op: &str, a: &str, b: &str| format!(r#"step id="s1" kind="compute"
+    op={}
+    input {}
+    input {}
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end"#, op, a, b);
+
+    let errors = check_source_has_errors(&decimal_source("Decimal", &plain("add", r#"var="price""#, "lit=1.5d")));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int or Float" && found == "Decimal")));
+
+    let errors = check_source_has_errors(&decimal_source("Bool", &plain("less", r#"var="count""#, "lit=10n")));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int or Float" && found == "BigInt")));
+
+    check_source_ok(&decimal_source("Int", &plain("big_cmp", r#"var="count""#, "lit=10n")));
+    check_source_ok(&decimal_source("BigInt", &plain("big_mul", r#"var="count""#, "lit=18446744073709551616n")));
+    let errors = check_source_has_errors(&decimal_source("BigInt", &plain("big_mul", r#"var="count""#, r#"var="qty""#)));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "BigInt" && found == "Int")));
+}
+
+// === Json ===
+
+const JSON_OP: &str = r#"
+snippet id="api.read" kind="fn"
+signature
+  fn name="read"
+    param name="payload" type="Json"
+    param name="body" type="String"
+    returns RETURNS
+  end
+end
+body
+  STEPS
+end
+end
+"#;
+
+fn json_source(returns: &str, op: &str, inputs: &[&str]) -> String {
+    let inputs: String = inputs.iter().map(|input| format!("    input {}\n", input)).collect();
+    let steps = format!(r#"step id="s1" kind="compute"
+    op={}
+{}    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end"#, op, inputs);
+    JSON_OP.replace("RETURNS", returns).replace("STEPS", &steps)
+}
+
+#[test]
+fn test_json_ops() {
+    check_source_ok(&json_source(r#"type="Json" optional"#, "json_get", &[r#"var="payload""#, r#"lit="user""#]));
+    check_source_ok(&json_source(r#"type="Json""#, "json_set", &[r#"var="payload""#, r#"lit="0""#, r#"var="payload""#]));
+    check_source_ok(&json_source(r#"type="List<String>""#, "json_keys", &[r#"var="payload""#]));
+    check_source_ok(&json_source(r#"type="String""#, "json_stringify", &[r#"var="payload""#]));
+
+    // A field may be missing
+    let errors = check_source_has_errors(&json_source(r#"type="Json""#, "json_get", &[r#"var="payload""#, r#"lit="user""#]));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Json" && found == "Json?")));
+
+    // Text must be parsed before use
+    let errors = check_source_has_errors(&json_source(r#"type="List<String>""#, "json_keys", &[r#"var="body""#]));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Json" && found == "String")));
+}
+
+#[test]
+fn test_json_parse_returns_union() {
+    let errors = check_source_has_errors(&json_source(r#"type="Json""#, "json_parse", &[r#"var="body""#]));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Json" && found == "Json | DecodeError")));
+}
+
+// === Unit Aliases ===
+
+const MONEY_FN: &str = r#"
+snippet id="billing.types" kind="module"
+types
+  alias name="Money" type="Float" unit="USD"
+  alias name="Euros" type="Float" unit="EUR"
+end
+end
+
+snippet id="billing.total" kind="fn"
+signature
+  fn name="total"
+    param name="price" type="Money"
+    param name="fee" type="Euros"
+    param name="qty" type="Int"
+    returns type="RETURNS"
+  end
+end
+body
+  STEPS
+end
+end
+"#;
+
+fn money_source(returns: &str, steps: &str) -> String {
+    MONEY_FN.replace("RETURNS", returns).replace("STEPS", steps)
+}
+
+fn money_compute(op: &str, lhs: &str, rhs: &str) -> String {
+    format!(r#"step id="s1" kind="compute"
+    op={}
+    input {}
+    input {}
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end"#, op, lhs, rhs)
+}
+
+#[test]
+fn test_unit_arithmetic_keeps_the_unit() {
+    check_source_ok(&money_source("Money", &money_compute("add", r#"var="price""#, r#"var="price""#)));
+    check_source_ok(&money_source("Money", &money_compute("mul", r#"var="price""#, r#"var="qty""#)));
+    check_source_ok(&money_source("Money", &money_compute("mul", r#"var="qty""#, r#"var="price""#)));
+    check_source_ok(&money_source("Money", &money_compute("div", r#"var="price""#, "lit=2.0")));
+    // A ratio of two amounts in the same unit is a plain number
+    check_source_ok(&money_source("Float", &money_compute("div", r#"var="price""#, r#"var="price""#)));
+    check_source_ok(&money_source("Bool", &money_compute("less", r#"var="price""#, r#"var="price""#)));
+}
+
+#[test]
+fn test_unit_arithmetic_rejects_mixed_units() {
+    let errors = check_source_has_errors(&money_source("Money", &money_compute("add", r#"var="price""#, r#"var="fee""#)));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::UnitMismatch { left, right } if left == "Money (USD)" && right == "Euros (EUR)")));
+
+    // A bare number has no unit to add
+    let errors = check_source_has_errors(&money_source("Money", &money_compute("sub", r#"var="price""#, "lit=1.5")));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::UnitMismatch { left, right } if left == "Money (USD)" && right == "Float")));
+
+    let errors = check_source_has_errors(&money_source("Bool", &money_compute("greater", r#"var="fee""#, r#"var="price""#)));
+    assert!(errors.iter().any(|e| matches!(e, covenant_checker::CheckError::UnitMismatch { .. })));
+
+    // Squaring an amount has no unit to name
+    let errors = check_source_has_errors(&money_source("Money", &money_compute("mul", r#"var="price""#, r#"var="price""#)));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Float" && found == "Money (USD)")));
+}
+
+#[test]
+fn test_unit_conversion_wraps_and_unwraps_explicitly() {
+    let convert = r#"step id="s0" kind="bind"
+    from="price.value"
+    as="usd"
+  end
+  step id="s1" kind="compute"
+    op=mul
+    input var="usd"
+    input lit=0.92
+    as="amount"
+  end
+  step id="s2" kind="construct"
+    type="Euros"
+    field name="value" from="amount"
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end"#;
+    check_source_ok(&money_source("Euros", convert));
+    let errors = check_source_has_errors(&money_source("Euros", &convert.replace("price.value", "price.amount")));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::UnknownField { field, type_name } if field == "amount" && type_name == "Money")));
+
+    // Passing the amount through without converting it keeps its unit
+    let errors = check_source_has_errors(&money_source("Euros", r#"step id="s1" kind="return"
+    from="price"
+    as="_"
+  end"#));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Euros" && found == "Money")));
+
+    let errors = check_source_has_errors(&money_source("Euros", &convert.replace("from=\"amount\"", "from=\"fee\"")));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Float" && found == "Euros (EUR)")));
+}
+
+// === Interfaces ===
+
+const SHAPE_INTERFACE: &str = r#"
+snippet id="shapes.Shape" kind="interface"
+signature
+  interface name="Shape"
+    fn name="area"
+      param name="shape" type="Self"
+      returns type="Float"
+    end
+  end
+end
+end
+
+snippet id="shapes.Circle" kind="struct" implements="shapes.Shape"
+signature
+  struct name="Circle"
+    field name="radius" type="Float"
+  end
+end
+end
+
+snippet id="shapes.circle_area" kind="fn" implements="IMPLEMENTS"
+signature
+  fn name="NAME"
+    param name="shape" type="Circle"
+    returns type="RETURNS"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=1.0
+    as="_"
+  end
+end
+end
+"#;
+
+fn shape_source(implements: &str, name: &str, returns: &str) -> String {
+    SHAPE_INTERFACE
+        .replace("IMPLEMENTS", implements)
+        .replace("NAME", name)
+        .replace("RETURNS", returns)
+}
+
+/// The reasons of all InvalidImplementation errors, joined
+fn implementation_error(source: &str) -> String {
+    let errors = check_source_has_errors(source);
+    let reasons: Vec<String> = errors.into_iter().filter_map(|e| match e {
+        covenant_checker::CheckError::InvalidImplementation { reason, .. } => Some(reason),
+        _ => None,
+    }).collect();
+    assert!(!reasons.is_empty(), "expected an InvalidImplementation error");
+    reasons.join("; ")
+}
+
+#[test]
+fn test_conforming_implementation() {
+    check_source_ok(&shape_source("shapes.Shape", "area", "Float"));
+}
+
+#[test]
+fn test_implementation_return_type_must_match() {
+    let reason = implementation_error(&shape_source("shapes.Shape", "area", "Int"));
+    assert!(reason.contains("the return type is Int"), "{}", reason);
+}
+
+#[test]
+fn test_implementation_must_name_a_method() {
+    let reason = implementation_error(&shape_source("shapes.Shape", "perimeter", "Float"));
+    assert!(reason.contains("no method 'perimeter'"), "{}", reason);
+}
+
+#[test]
+fn test_struct_missing_method_implementation() {
+    // circle_area no longer implements the interface, so Circle lacks `area`
+    let source = shape_source("shapes.Shape", "area", "Float")
+        .replacen(" implements=\"shapes.Shape\"", "", 2)
+        .replace("kind=\"struct\"", "kind=\"struct\" implements=\"shapes.Shape\"");
+    let reason = implementation_error(&source);
+    assert!(reason.contains("no function implements method 'area' for Circle"), "{}", reason);
+}
+
+#[test]
+fn test_implements_must_name_an_interface() {
+    let reason = implementation_error(&shape_source("shapes.Circle", "area", "Float"));
+    assert!(reason.contains("'shapes.Circle' is not an interface"), "{}", reason);
+}
+
+// === Type Aliases ===
+
+const TYPE_ALIAS: &str = r#"
+snippet id="ids.UserId" kind="alias"
+signature
+  alias name="UserId" type="USER_TARGET"
+end
+end
+
+snippet id="ids.AccountId" kind="alias"
+signature
+  alias name="AccountId" type="ACCOUNT_TARGET"
+end
+end
+
+snippet id="accounts.owner" kind="fn"
+signature
+  fn name="owner"
+    param name="account" type="AccountId"
+    returns type="RETURN_TYPE"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="account"
+    as="_"
+  end
+end
+end
+"#;
+
+fn type_alias_source(user_target: &str, account_target: &str, return_type: &str) -> String {
+    TYPE_ALIAS
+        .replace("USER_TARGET", user_target)
+        .replace("ACCOUNT_TARGET", account_target)
+        .replace("RETURN_TYPE", return_type)
+}
+
+#[test]
+fn test_alias_resolves_through_other_aliases() {
+    check_source_ok(&type_alias_source("Int", "UserId", "Int"));
+    check_source_ok(&type_alias_source("Int", "List<UserId>", "List<Int>"));
+}
+
+#[test]
+fn test_alias_mismatch_shows_target_type() {
+    let errors = check_source_has_errors(&type_alias_source("Int", "UserId", "String"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "String" && found == "Int")));
+}
+
+#[test]
+fn test_recursive_alias_is_rejected() {
+    let errors = check_source_has_errors(&type_alias_source("Optional<AccountId>", "UserId", "Int"));
+    let names: Vec<&str> = errors.iter().filter_map(|e| match e {
+        covenant_checker::CheckError::RecursiveAlias { name } => Some(name.as_str()),
+        _ => None,
+    }).collect();
+    assert_eq!(names, ["AccountId", "UserId"]);
+}
+
+// === Newtypes ===
+
+const NEWTYPE: &str = r#"
+snippet id="ids.UserId" kind="struct"
+signature
+  struct name="UserId" newtype
+    NEWTYPE_FIELDS
+  end
+end
+end
+
+snippet id="ids.find" kind="fn"
+signature
+  fn name="find"
+    param name="id" type="UserId"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="bind"
+    from="id.raw"
+    as="raw"
+  end
+  step id="s2" kind="return"
+    from="raw"
+    as="_"
+  end
+end
+end
+
+snippet id="ids.first" kind="fn"
+signature
+  fn name="first"
+    param name="raw" type="Int"
+    returns type="UserId"
+  end
+end
+body
+  step id="s1" kind="construct"
+    type="UserId"
+    field name="raw" from="raw"
+    as="id"
+  end
+  step id="s2" kind="return"
+    from="RETURNED"
+    as="_"
+  end
+end
+end
+"#;
+
+fn newtype_source(fields: &str, returned: &str) -> String {
+    NEWTYPE
+        .replace("NEWTYPE_FIELDS", fields)
+        .replace("RETURNED", returned)
+}
+
+#[test]
+fn test_newtype_wraps_its_field() {
+    check_source_ok(&newtype_source(r#"field name="raw" type="Int""#, "id"));
+}
+
+#[test]
+fn test_newtype_is_distinct_from_its_field_type() {
+    let errors = check_source_has_errors(&newtype_source(r#"field name="raw" type="Int""#, "raw"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "UserId" && found == "Int")));
+}
+
+#[test]
+fn test_newtype_must_have_one_field() {
+    let fields = "field name=\"raw\" type=\"Int\"\n    field name=\"tenant\" type=\"Int\"";
+    let errors = check_source_has_errors(&newtype_source(fields, "id"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::InvalidNewtype { name, fields: 2 } if name == "UserId")));
+}
+
+// === Fixed-Size Arrays ===
+
+const ARRAY: &str = r#"
+snippet id="m.corner" kind="fn"
+signature
+  fn name="corner"
+    param name="grid" type="ARRAY_TYPE"
+    param name="i" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=array_fill
+    input lit=0
+    input FILL_LEN
+    as="zeros"
+  end
+  step id="s2" kind="compute"
+    op=array_get
+    input var="grid"
+    input INDEX
+    as="x"
+  end
+  step id="s3" kind="return"
+    from="x"
+    as="_"
+  end
+end
+end
+"#;
+
+fn array_source(array_type: &str, fill_len: &str, index: &str) -> String {
+    ARRAY
+        .replace("ARRAY_TYPE", array_type)
+        .replace("FILL_LEN", fill_len)
+        .replace("INDEX", index)
+}
+
+#[test]
+fn test_array_index_in_range() {
+    check_source_ok(&array_source("Array<Int, 4>", "lit=3", "lit=3"));
+    // Indexes only known at runtime are checked when the program runs
+    check_source_ok(&array_source("Array<Int, 4>", "lit=3", "var=\"i\""));
+}
+
+#[test]
+fn test_array_constant_index_out_of_bounds() {
+    let errors = check_source_has_errors(&array_source("Array<Int, 4>", "lit=3", "lit=4"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::ArrayIndexOutOfBounds { index: 4, len: 4 })));
+
+    let errors = check_source_has_errors(&array_source("Array<Int, 4>", "lit=3", "lit=-1"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::ArrayIndexOutOfBounds { index: -1, len: 4 })));
+}
+
+#[test]
+fn test_array_element_type_is_checked() {
+    let errors = check_source_has_errors(&array_source("Array<String, 4>", "lit=3", "lit=0"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found } if expected == "Int" && found == "String")));
+}
+
+#[test]
+fn test_array_fill_length_must_be_literal() {
+    let errors = check_source_has_errors(&array_source("Array<Int, 4>", "var=\"i\"", "lit=0"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::InvalidArrayLength { found } if found == "i")));
+}
+
+#[test]
+fn test_array_lengths_must_match() {
+    let source = r#"
+snippet id="m.ones" kind="fn"
+signature
+  fn name="ones"
+    returns type="Array<Int, 3>"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=array_of
+    input lit=1
+    input lit=1
+    as="xs"
+  end
+  step id="s2" kind="return"
+    from="xs"
+    as="_"
+  end
+end
+end
+"#;
+    let errors = check_source_has_errors(source);
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found }
+            if expected == "Array<Int, 3>" && found == "Array<Int, 2>")));
+}
+
+// === Numeric Vector Operations ===
+
+const NUMERIC: &str = r#"
+snippet id="m.numeric" kind="fn"
+signature
+  fn name="numeric"
+    param name="a" type="LEFT_TYPE"
+    param name="b" type="RIGHT_TYPE"
+    returns type="RESULT_TYPE"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=OP
+    input var="a"
+    input var="b"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+
+fn numeric_source(op: &str, left: &str, right: &str, result: &str) -> String {
+    NUMERIC
+        .replace("OP", op)
+        .replace("LEFT_TYPE", left)
+        .replace("RIGHT_TYPE", right)
+        .replace("RESULT_TYPE", result)
+}
+
+#[test]
+fn test_vector_ops_over_lists_and_arrays() {
+    check_source_ok(&numeric_source("vec_add", "List<Float>", "List<Float>", "List<Float>"));
+    check_source_ok(&numeric_source("vec_sub", "Array<Float, 4>", "Array<Float, 4>", "Array<Float, 4>"));
+    check_source_ok(&numeric_source("vec_scale", "Array<Float, 4>", "Float", "Array<Float, 4>"));
+    check_source_ok(&numeric_source("dot", "List<Float>", "List<Float>", "Float"));
+}
+
+#[test]
+fn test_vector_ops_need_float_vectors_of_one_length() {
+    let errors = check_source_has_errors(&numeric_source("dot", "List<Int>", "List<Int>", "Float"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found }
+            if expected == "List<Float> or Array<Float, N>" && found == "Int[]")));
+
+    let errors = check_source_has_errors(&numeric_source("vec_add", "Array<Float, 4>", "Array<Float, 3>", "Array<Float, 4>"));
+    assert!(errors.iter().any(|e| matches!(e,
+        covenant_checker::CheckError::TypeMismatch { expected, found }
+            if expected == "Array<Float, 4>" && found == "Array<Float, 3>")));
+}
+
+#[test]
+fn test_matmul_shapes() {
+    check_source_ok(&numeric_source(
+        "matmul", "Array<Array<Float, 3>, 2>", "Array<Array<Float, 4>, 3>", "Array<Array<Float, 4>, 2>",
+    ));
+    check_source_ok(&numeric_source("matmul", "List<List<Float>>", "List<List<Float>>", "List<List<Float>>"));
+
+    let errors = check_source_has_errors(&numeric_source(
+        "matmul", "Array<Array<Float, 3>, 2>", "Array<Array<Float, 2>, 2>", "Array<Array<Float, 2>, 2>",
+    ));
+    assert!(errors.iter().any(|e| matches!(e, covenant_checker::CheckError::MatrixShapeMismatch { .. })));
+}
@@ -1,26 +1,44 @@
 //! Covenant CLI - Command line interface for the Covenant compiler
 
+mod runner;
+mod scaffold;
+mod serve;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
 
 use clap::{Parser, Subcommand};
 use ariadne::{Color, Label, Report, ReportKind, Source};
 
-use covenant_parser::parse;
-use covenant_ast::printer::to_cov;
-use covenant_symbols::build_symbol_graph;
+use covenant_parser::{parse, parse_compact};
+use covenant_parser::grammar::{self, GrammarFormat};
+use covenant_ast::printer::{to_cov, to_cov_compact};
+use covenant_ast::{CallSite, LineIndex, Program, Snippet, Span};
+use covenant_symbols::{build_from_snippets, changed_snippets, DiagramOptions, ReferenceKind, SymbolError, SymbolGraph};
 use covenant_checker::{
-    check, check_effects, check_effects_with_diagnostics, EffectError,
-    Diagnostic,
+    check, check_effects, check_effects_for, check_effects_with_registry, explain_effect_provenance, explain_effect_violation,
+    EffectError, Diagnostic, MAX_PROVENANCE_CHAINS,
 };
-use covenant_graph::{GraphBuilder, execute_query, parse_query};
-use covenant_codegen::{compile_pure, compile_with_symbols};
+use covenant_graph::{GraphBuilder, execute_query, parse_paths_query, parse_query, parse_references_query, PathsQuery, ReferencesQuery};
+use covenant_codegen::compile_pure;
 use covenant_llm::{
-    ExplainGenerator, ExplanationCache, LlmClient,
-    Verbosity, ExplainFormat, format_explanation,
+    explain::ExplainError, BatchItem, BatchState, ExplainGenerator, Explanation, ExplanationCache, LlmClient,
+    LlmError, LlmPolicy, Provider, Verbosity, ExplainFormat, format_explanation, extract_code, redact_sensitive,
+    BATCH_STATE_PATH,
 };
 use covenant_requirements::{validate_program, format_report, ReportFormat, filter_uncovered, has_coverage_errors};
-use covenant_optimizer::{optimize, OptSettings, OptLevel};
+use covenant_optimizer::{optimize_program, OptSettings, OptLevel};
+use covenant_actions::{fix_effects, is_canonical, ActionError};
+use covenant_examples::{compare_reports, EvalRecord, EvalReport, Stage};
+use covenant_driver::{
+    find_llm_policy, find_project_config_path, DriverError, MemoryLimits, Platform, Project, Target, WasmFeatures, WORKER_SHIM,
+};
+use runner::Runner;
+use covenant_storage::{
+    Checkpoint, ExplanationStore, RedbStorage, RunStatus, StoredExplanation, WorkflowRun, WorkflowStore,
+};
 
 #[derive(Parser)]
 #[command(name = "covenant")]
@@ -52,23 +70,63 @@ enum Commands {
     Compile {
         /// Input file
         file: PathBuf,
-        /// Output file
+        /// Output file (a directory for --target worker)
         #[arg(short, long)]
         output: Option<PathBuf>,
-        /// Target platform (deno, node, browser, wasi). Defaults to deno.
+        /// Target platform (deno, node, bun, browser, wasi, worker). Defaults to deno. A worker target writes a Cloudflare Workers bundle directory.
         #[arg(long, default_value = "deno")]
         target: String,
+        /// Optimization level (0=none, 1=basic, 2=standard, 3=aggressive); 2 and above drop asserts, invariants and overflow traps
+        #[arg(long, default_value = "0")]
+        optimize: u8,
+        /// WASM proposals the module may use, comma-separated (simd, relaxed-simd, bulk-memory, gc). Defaults to none.
+        #[arg(long, value_name = "FEATURES", default_value = "")]
+        wasm_features: String,
+        /// Most 64 KiB pages the module's memory may grow to; allocating past them traps. Defaults to 256 (16 MiB).
+        #[arg(long, value_name = "PAGES", default_value = "256")]
+        max_memory_pages: u32,
+    },
+    /// Package a program for deployment to a serverless platform or as a container image
+    Package {
+        /// Input file
+        file: PathBuf,
+        /// Platform to package for (lambda, oci). An oci target writes the build context of a container image.
+        #[arg(long, required_unless_present = "oci")]
+        target: Option<String>,
+        /// Shorthand for `--target oci`
+        #[arg(long, conflicts_with = "target")]
+        oci: bool,
+        /// Snippet ID of the function each invocation calls (default: the function named main); lambda only
+        #[arg(long, value_name = "ID")]
+        entry: Option<String>,
+        /// Output directory (default: <file>-<target> next to the source)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
         /// Optimization level (0=none, 1=basic, 2=standard, 3=aggressive)
         #[arg(long, default_value = "0")]
         optimize: u8,
     },
+    /// Generate the JavaScript host glue for a project's extern imports
+    EmitHost {
+        /// Input file(s)
+        files: Vec<PathBuf>,
+        /// Host to generate for (deno, node, bun)
+        #[arg(long, default_value = "node")]
+        target: String,
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
     /// Query the codebase
     Query {
         /// Input file(s) to analyze
         files: Vec<PathBuf>,
-        /// Query string (e.g., "select * from functions where is_pure = true")
+        /// Query string (e.g., "select * from functions where is_pure = true", "paths from app.main to db.query [max depth N]", or "references to app.User [kind call]")
         #[arg(short, long)]
         query: String,
+        /// Only return members of this module snippet, nested modules included
+        #[arg(long, value_name = "MODULE")]
+        module: Option<String>,
     },
     /// Show information about a file
     Info {
@@ -77,8 +135,9 @@ enum Commands {
     },
     /// Generate AI explanation for code
     Explain {
-        /// Input file
-        file: PathBuf,
+        /// Input file, or with --project the directories to search (default: .)
+        #[arg(required_unless_present = "project")]
+        files: Vec<PathBuf>,
         /// Output format (json, text, markdown, compact)
         #[arg(short, long, default_value = "text")]
         format: String,
@@ -88,6 +147,30 @@ enum Commands {
         /// Disable caching
         #[arg(long)]
         no_cache: bool,
+        /// Stop before LLM spending could exceed this many dollars
+        #[arg(long, value_name = "DOLLARS")]
+        max_cost: Option<f64>,
+        /// Explain every .cov file in the project, writing `<file>.explain.<ext>` next to each
+        #[arg(long)]
+        project: bool,
+        /// Requests to run at once with --project
+        #[arg(short, long, default_value_t = 1, requires = "project")]
+        jobs: usize,
+        /// Continue the interrupted --project batch instead of starting over
+        #[arg(long, requires = "project")]
+        resume: bool,
+    },
+    /// Show the stored explanations of a file's snippets, without calling the LLM
+    Doc {
+        /// Input file(s)
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Only this snippet
+        #[arg(short, long)]
+        snippet: Option<String>,
+        /// Output format (json, text, markdown, compact)
+        #[arg(short, long, default_value = "text")]
+        format: String,
     },
     /// Analyze effect declarations and compute transitive closures
     Effects {
@@ -99,6 +182,15 @@ enum Commands {
         /// Show verbose diagnostics with call chains and fix suggestions
         #[arg(long)]
         explain: bool,
+        /// Explain why a function has an effect (e.g., --why app.main network)
+        #[arg(long, num_args = 2, value_names = ["FUNCTION", "EFFECT"])]
+        why: Option<Vec<String>>,
+        /// Declare missing effects in place
+        #[arg(long)]
+        fix: bool,
+        /// Only report members of this module snippet, nested modules included
+        #[arg(long, value_name = "MODULE", conflicts_with_all = ["why", "fix"])]
+        module: Option<String>,
     },
     /// Analyze requirement coverage
     Requirements {
@@ -118,8 +210,9 @@ enum Commands {
         strict: bool,
     },
     /// Format a file to canonical form
+    #[command(visible_alias = "fmt")]
     Format {
-        /// Input file
+        /// Input file (.cov, or a .json AST from `covenant parse`)
         file: PathBuf,
         /// Output file (default: stdout)
         #[arg(short, long)]
@@ -127,6 +220,23 @@ enum Commands {
         /// Check only - exit with error if file is not canonical
         #[arg(long)]
         check: bool,
+        /// Emit the compact snippet syntax instead of the canonical form
+        #[arg(long, conflicts_with = "check")]
+        compact: bool,
+    },
+    /// Print the snippet grammar for constrained decoding and validators
+    Grammar {
+        /// Output format (ebnf, json-schema)
+        #[arg(long, default_value = "ebnf")]
+        format: String,
+    },
+    /// Create a new project from a template
+    New {
+        /// Project directory to create (its name becomes the project name)
+        path: PathBuf,
+        /// Project template (service, cli, library)
+        #[arg(long, default_value = "service")]
+        template: String,
     },
     /// Interactive REPL
     Repl,
@@ -134,12 +244,162 @@ enum Commands {
     Run {
         /// Input file
         file: PathBuf,
-        /// Optimization level (0=none, 1=basic, 2=standard, 3=aggressive)
+        /// Optimization level (0=none, 1=basic, 2=standard, 3=aggressive); 2 and above drop asserts, invariants and overflow traps
+        #[arg(long, default_value = "0")]
+        optimize: u8,
+        /// Append a JSON line per effectful extern call to this file
+        #[arg(long, value_name = "FILE")]
+        audit: Option<PathBuf>,
+        /// Seed the `random` effect's generator so runs are reproducible
+        #[arg(long)]
+        seed: Option<u32>,
+        /// Export the run as an OpenTelemetry trace to this OTLP/HTTP endpoint (e.g., http://localhost:4318)
+        #[arg(long, value_name = "URL")]
+        otlp: Option<String>,
+        /// Runtime to run under (deno, node, bun); default Deno when installed, else Node.js
+        #[arg(long)]
+        runner: Option<String>,
+    },
+    /// Find call paths between two symbols, with accumulated effects
+    Path {
+        /// Source symbol
+        from: String,
+        /// Target symbol
+        to: String,
+        /// Input file(s) to analyze
+        files: Vec<PathBuf>,
+        /// Show only the K shortest paths (default: all)
+        #[arg(short, long)]
+        k: Option<usize>,
+    },
+    /// Show the module dependency graph with layers and fan-in/fan-out
+    Modules {
+        /// Input file(s) to analyze
+        files: Vec<PathBuf>,
+        /// Exit with error if module-level cycles exist
+        #[arg(long)]
+        deny_cycles: bool,
+    },
+    /// Draw the symbol graph as a GraphViz (dot) or Mermaid diagram, or export it for graph databases
+    Graph {
+        /// Input file(s) to draw
+        files: Vec<PathBuf>,
+        /// Output format (dot, mermaid, json-ld, cypher)
+        #[arg(short, long, default_value = "dot")]
+        format: String,
+        /// Only symbols in this module or below it (e.g. app.orders)
+        #[arg(long)]
+        module: Option<String>,
+        /// Only these edge kinds, comma-separated (call, type_reference, relation, test_coverage, extern_contract, implementation)
+        #[arg(long, value_delimiter = ',')]
+        edges: Vec<String>,
+        /// Also draw symbols up to this many edges away from the module's
+        #[arg(long, default_value = "0")]
+        depth: usize,
+    },
+    /// Watch files and re-check changed snippets first, then their dependents
+    Watch {
+        /// Input file(s) to watch
+        files: Vec<PathBuf>,
+        /// Polling interval in milliseconds
+        #[arg(long, default_value = "300")]
+        interval: u64,
+    },
+    /// Parse, check, and compile the bundled example corpus
+    ValidateExamples {
+        /// Only validate these examples (e.g. syntax-fundamentals/hello-world)
+        names: Vec<String>,
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Evaluate an LLM on the example corpus (generate, parse, check, compile)
+    EvalModel {
+        /// LLM provider (anthropic, openai); defaults to whichever API key is set
+        #[arg(long)]
+        provider: Option<String>,
+        /// Model name (default: the provider's default model)
+        #[arg(long)]
+        model: Option<String>,
+        /// Only evaluate these examples (repeatable)
+        #[arg(long = "example")]
+        examples: Vec<String>,
+        /// Where to write the JSON report
+        #[arg(short, long, default_value = "eval-results.json")]
+        output: PathBuf,
+        /// Compare two report files instead of running an evaluation
+        #[arg(long, num_args = 2, value_names = ["BASELINE", "CANDIDATE"])]
+        compare: Option<Vec<PathBuf>>,
+        /// Stop before LLM spending could exceed this many dollars
+        #[arg(long, value_name = "DOLLARS")]
+        max_cost: Option<f64>,
+    },
+    /// Inspect the symbol graph
+    Sym {
+        #[command(subcommand)]
+        command: SymCommands,
+    },
+    /// List and resume workflow runs
+    Workflows {
+        #[command(subcommand)]
+        command: WorkflowCommands,
+    },
+    /// Run functions with `trigger schedule=...` metadata on their cron schedules
+    Serve {
+        /// Input file
+        file: PathBuf,
+        /// Port for the status endpoints (GET /status, /healthz, /metrics) on 127.0.0.1
+        #[arg(long, default_value = "8787")]
+        port: u16,
+        /// Optimization level (0=none, 1=basic, 2=standard, 3=aggressive); 2 and above drop asserts, invariants and overflow traps
+        #[arg(long, default_value = "0")]
+        optimize: u8,
+        /// Export each scheduled run as an OpenTelemetry trace to this OTLP/HTTP endpoint
+        #[arg(long, value_name = "URL")]
+        otlp: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorkflowCommands {
+    /// List workflow runs with their status and checkpoints
+    List,
+    /// Resume a run, replaying its checkpoints before running the remaining steps
+    Resume {
+        /// Run ID (as shown by `workflows list`)
+        run: String,
+        /// Optimization level (0=none, 1=basic, 2=standard, 3=aggressive); 2 and above drop asserts, invariants and overflow traps
         #[arg(long, default_value = "0")]
         optimize: u8,
     },
 }
 
+#[derive(Subcommand)]
+enum SymCommands {
+    /// Find symbols by glob (`app.*`) or fuzzy (`getusr`) pattern
+    Find {
+        /// Search pattern
+        pattern: String,
+        /// Input file(s) to search
+        files: Vec<PathBuf>,
+        /// Maximum number of results to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+    /// Show functions referencing a type and types embedding it
+    Uses {
+        /// Type name (short name or snippet ID)
+        type_name: String,
+        /// Input file(s) to analyze
+        files: Vec<PathBuf>,
+    },
+    /// List types in dependency order (embedded types first)
+    TypeOrder {
+        /// Input file(s) to analyze
+        files: Vec<PathBuf>,
+    },
+}
+
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
@@ -147,19 +407,61 @@ async fn main() {
     match cli.command {
         Commands::Parse { file, pretty } => cmd_parse(&file, pretty),
         Commands::Check { files, requirements } => cmd_check(&files, requirements),
-        Commands::Compile { file, output, target, optimize: opt_level } => cmd_compile(&file, output, &target, opt_level),
-        Commands::Query { files, query } => cmd_query(&files, &query),
+        Commands::Compile { file, output, target, optimize: opt_level, wasm_features, max_memory_pages } => {
+            cmd_compile(&file, output, &target, opt_level, &wasm_features, max_memory_pages)
+        }
+        Commands::Package { file, target, oci, entry, output, optimize } => {
+            let target = if oci { "oci" } else { target.as_deref().unwrap_or_default() };
+            cmd_package(&file, target, entry.as_deref(), output, optimize)
+        }
+        Commands::EmitHost { files, target, output } => cmd_emit_host(&files, &target, output),
+        Commands::Query { files, query, module } => cmd_query(&files, &query, module.as_deref()),
         Commands::Info { file } => cmd_info(&file),
-        Commands::Explain { file, format, verbosity, no_cache } => {
-            cmd_explain(&file, &format, &verbosity, no_cache).await;
+        Commands::Explain { files, format, verbosity, no_cache, max_cost, project, jobs, resume } => {
+            if project {
+                cmd_explain_project(&files, &format, &verbosity, no_cache, max_cost, jobs, resume).await;
+            } else if let [file] = files.as_slice() {
+                cmd_explain(file, &format, &verbosity, no_cache, max_cost).await;
+            } else {
+                eprintln!("Error: explain takes one file; use --project to explain several");
+                std::process::exit(1);
+            }
         }
-        Commands::Effects { files, violations_only, explain } => cmd_effects(&files, violations_only, explain),
+        Commands::Doc { files, snippet, format } => cmd_doc(&files, snippet.as_deref(), &format),
+        Commands::Effects { files, violations_only, explain, why, fix, module } => match why {
+            Some(why) => cmd_effects_why(&files, &why[0], &why[1]),
+            None if fix => cmd_effects_fix(&files),
+            None => cmd_effects(&files, violations_only, explain, module.as_deref()),
+        },
         Commands::Requirements { files, report, uncovered_only, min_coverage, strict } => {
             cmd_requirements(&files, &report, uncovered_only, min_coverage, strict);
         }
-        Commands::Format { file, output, check } => cmd_format(&file, output, check),
+        Commands::Format { file, output, check, compact } => cmd_format(&file, output, check, compact),
+        Commands::Grammar { format } => cmd_grammar(&format),
+        Commands::New { path, template } => cmd_new(&path, &template),
         Commands::Repl => cmd_repl(),
-        Commands::Run { file, optimize: opt_level } => cmd_run(&file, opt_level),
+        Commands::Run { file, optimize: opt_level, audit, seed, otlp, runner } => {
+            cmd_run(&file, opt_level, audit.as_deref(), seed, otlp.as_deref(), runner.as_deref());
+        }
+        Commands::Path { from, to, files, k } => cmd_path(&from, &to, &files, k),
+        Commands::Modules { files, deny_cycles } => cmd_modules(&files, deny_cycles),
+        Commands::Graph { files, format, module, edges, depth } => cmd_graph(&files, &format, module, &edges, depth),
+        Commands::Watch { files, interval } => cmd_watch(&files, interval),
+        Commands::ValidateExamples { names, json } => cmd_validate_examples(&names, json),
+        Commands::EvalModel { provider, model, examples, output, compare, max_cost } => match compare {
+            Some(files) => cmd_eval_compare(&files[0], &files[1]),
+            None => cmd_eval_model(provider.as_deref(), model, &examples, &output, max_cost).await,
+        },
+        Commands::Sym { command } => match command {
+            SymCommands::Find { pattern, files, limit } => cmd_sym_find(&pattern, &files, limit),
+            SymCommands::Uses { type_name, files } => cmd_sym_uses(&type_name, &files),
+            SymCommands::TypeOrder { files } => cmd_sym_type_order(&files),
+        },
+        Commands::Workflows { command } => match command {
+            WorkflowCommands::List => cmd_workflows_list(),
+            WorkflowCommands::Resume { run, optimize: opt_level } => cmd_workflows_resume(&run, opt_level),
+        },
+        Commands::Serve { file, port, optimize: opt_level, otlp } => cmd_serve(&file, port, opt_level, otlp.as_deref()),
     }
 }
 
@@ -174,11 +476,7 @@ fn cmd_parse(file: &PathBuf, pretty: bool) {
 
     match parse(&source) {
         Ok(program) => {
-            let json = if pretty {
-                serde_json::to_string_pretty(&program).unwrap()
-            } else {
-                serde_json::to_string(&program).unwrap()
-            };
+            let json = if pretty { program.to_json_pretty() } else { program.to_json() };
             println!("{}", json);
         }
         Err(e) => {
@@ -189,209 +487,303 @@ fn cmd_parse(file: &PathBuf, pretty: bool) {
 }
 
 fn cmd_check(files: &[PathBuf], validate_requirements: bool) {
-    let mut all_ok = true;
+    let project = load_project(files).with_requirement_validation(validate_requirements);
+    let report = project.check();
 
-    for file in files {
-        let source = match fs::read_to_string(file) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("Error reading {}: {}", file.display(), e);
-                all_ok = false;
+    for (file, file_report) in project.files().iter().zip(&report.files) {
+        // Size limits and undefined references are advisory; report them first
+        for warning in file_report
+            .diagnostics
+            .iter()
+            .filter(|d| !d.is_error() && d.stage != covenant_driver::Stage::Requirements)
+        {
+            match (&warning.code, warning.stage) {
+                (Some(code), covenant_driver::Stage::Limits) => eprintln!("  warning: {}: {}", code, warning.message),
+                _ => eprintln!("  warning: {}", warning.message),
+            }
+            if let Some(help) = &warning.help {
+                eprintln!("    help: {}", help);
+            }
+        }
+
+        // Requirement errors are summarized in the coverage status line
+        let errors: Vec<_> = file_report
+            .diagnostics
+            .iter()
+            .filter(|d| d.is_error() && d.stage != covenant_driver::Stage::Requirements)
+            .collect();
+        if let Some(parse_error) = errors.iter().find(|d| d.stage == covenant_driver::Stage::Parse) {
+            report_diagnostic(&file.source, parse_error);
+            continue;
+        }
+        for (stage, kind) in [
+            (covenant_driver::Stage::Symbols, "symbol"),
+            (covenant_driver::Stage::Effects, "effect"),
+            (covenant_driver::Stage::Types, "type"),
+        ] {
+            let stage_errors: Vec<_> = errors.iter().filter(|d| d.stage == stage).collect();
+            if stage_errors.is_empty() {
                 continue;
             }
+            eprintln!("✗ {} - {} {} errors:", file.path.display(), stage_errors.len(), kind);
+            for err in stage_errors {
+                match &err.code {
+                    Some(code) => eprintln!("  {}: {}", code, err.message),
+                    None => eprintln!("  {}", err.message),
+                }
+                if let Some(help) = &err.help {
+                    eprintln!("    help: {}", help);
+                }
+            }
+        }
+        let (Some(summary), true) = (file_report.summary, errors.is_empty()) else {
+            continue;
         };
 
-        match parse(&source) {
-            Ok(program) => {
-                // Phase 2: Symbol graph building
-                let symbol_result = match build_symbol_graph(&program) {
-                    Ok(result) => {
-                        // Report deferred errors (undefined references) as warnings
-                        for err in &result.deferred_errors {
-                            eprintln!("  warning: {}", err);
-                        }
-                        result
-                    }
-                    Err(errors) => {
-                        eprintln!("✗ {} - {} symbol errors:", file.display(), errors.len());
-                        for err in &errors {
-                            eprintln!("  {}: {}", err.code(), err);
-                        }
-                        all_ok = false;
-                        continue;
-                    }
-                };
-
-                // Phase 3-4: Type checking
-                match check(&program) {
-                    Ok(result) => {
-                        let fn_count = result.symbols.functions().count();
-                        let pure_count = result.symbols.functions()
-                            .filter(|s| result.effects.is_pure(s.id))
-                            .count();
-                        let symbol_count = symbol_result.graph.len();
-
-                        // Phase 5: Requirement validation (optional)
-                        let req_info = if validate_requirements {
-                            let req_report = validate_program(&program, None);
-                            let has_errors = has_coverage_errors(&req_report);
-                            if has_errors {
-                                all_ok = false;
-                            }
-                            Some((req_report.summary.coverage_percent, has_errors))
-                        } else {
-                            None
-                        };
-
-                        // Print status line
-                        if let Some((coverage, has_errors)) = req_info {
-                            if has_errors {
-                                eprintln!(
-                                    "✗ {} - {} symbols, {} functions ({} pure), requirements: {:.0}% coverage (errors)",
-                                    file.display(),
-                                    symbol_count,
-                                    fn_count,
-                                    pure_count,
-                                    coverage
-                                );
-                            } else {
-                                println!(
-                                    "✓ {} - {} symbols, {} functions ({} pure), requirements: {:.0}% coverage",
-                                    file.display(),
-                                    symbol_count,
-                                    fn_count,
-                                    pure_count,
-                                    coverage
-                                );
-                            }
-                        } else {
-                            println!(
-                                "✓ {} - {} symbols, {} functions ({} pure)",
-                                file.display(),
-                                symbol_count,
-                                fn_count,
-                                pure_count
-                            );
-                        }
-                    }
-                    Err(errors) => {
-                        eprintln!("✗ {} - {} type errors:", file.display(), errors.len());
-                        for err in errors {
-                            eprintln!("  {}", err);
-                        }
-                        all_ok = false;
-                    }
-                }
+        let counts = format!(
+            "{} symbols, {} functions ({} pure)",
+            summary.symbols, summary.functions, summary.pure_functions
+        );
+        match summary.requirement_coverage {
+            Some(coverage) if file_report.is_ok() => {
+                println!("✓ {} - {}, requirements: {:.0}% coverage", file.path.display(), counts, coverage);
             }
-            Err(e) => {
-                report_parse_error(&source, file, &e);
-                all_ok = false;
+            Some(coverage) => {
+                eprintln!("✗ {} - {}, requirements: {:.0}% coverage (errors)", file.path.display(), counts, coverage);
             }
+            None => println!("✓ {} - {}", file.path.display(), counts),
         }
     }
 
-    if !all_ok {
+    if !report.is_ok() {
         std::process::exit(1);
     }
 }
 
-fn cmd_compile(file: &PathBuf, output: Option<PathBuf>, target: &str, opt_level: u8) {
-    // Validate target platform
-    let valid_targets = ["deno", "node", "browser", "wasi"];
-    if !valid_targets.contains(&target) {
-        eprintln!("Invalid target '{}'. Valid targets: deno, node, browser, wasi", target);
-        std::process::exit(1);
-    }
-
-    // Map optimization level
-    let opt_level = match opt_level {
-        0 => OptLevel::O0,
-        1 => OptLevel::O1,
-        2 => OptLevel::O2,
-        _ => OptLevel::O3,
+fn cmd_compile(
+    file: &PathBuf,
+    output: Option<PathBuf>,
+    target: &str,
+    opt_level: u8,
+    wasm_features: &str,
+    max_memory_pages: u32,
+) {
+    let platform: Platform = match target.parse() {
+        Ok(platform) => platform,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
     };
-
-    let source = match fs::read_to_string(file) {
-        Ok(s) => s,
+    let features: WasmFeatures = match wasm_features.parse() {
+        Ok(features) => features,
         Err(e) => {
-            eprintln!("Error reading file: {}", e);
+            eprintln!("{}", e);
             std::process::exit(1);
         }
     };
-
-    let mut program = match parse(&source) {
-        Ok(p) => p,
+    let memory = match MemoryLimits::with_max_pages(max_memory_pages) {
+        Ok(memory) => memory,
         Err(e) => {
-            report_parse_error(&source, file, &e);
+            eprintln!("{}", e);
             std::process::exit(1);
         }
     };
 
-    let result = match check(&program) {
-        Ok(r) => r,
-        Err(errors) => {
-            eprintln!("Type check errors:");
-            for err in errors {
-                eprintln!("  {}", err);
+    let project = load_project(std::slice::from_ref(file));
+    let target = Target::new(platform)
+        .with_opt_level(opt_level)
+        .with_features(features)
+        .with_memory_limits(memory);
+    if platform == Platform::Worker {
+        compile_worker(&project, file, output, target);
+        return;
+    }
+    match project.compile(target) {
+        Ok(artifact) => {
+            for warning in &artifact.warnings {
+                eprintln!("{}: {}", warning.code.as_deref().unwrap_or("warning"), warning.message);
+            }
+            let out_path = output.unwrap_or_else(|| {
+                let mut p = file.clone();
+                p.set_extension("wasm");
+                p
+            });
+            fs::write(&out_path, &artifact.wasm).expect("Failed to write output");
+            println!("Compiled to {} ({} bytes)", out_path.display(), artifact.wasm.len());
+        }
+        Err(e) => exit_on_compile_error(&project, e),
+    }
+}
+
+/// Write a Worker bundle into the `output` directory (default: `<file>-worker`
+/// next to the source): the module, `worker.mjs` and `wrangler.toml`
+fn compile_worker(project: &Project, file: &Path, output: Option<PathBuf>, target: Target) {
+    let name = file.file_stem().and_then(|s| s.to_str()).unwrap_or("worker").to_string();
+    let out_dir = output.unwrap_or_else(|| file.with_file_name(format!("{}-worker", name)));
+    match project.worker_bundle(target, &name) {
+        Ok(bundle) => {
+            for warning in &bundle.artifact.warnings {
+                eprintln!("{}: {}", warning.code.as_deref().unwrap_or("warning"), warning.message);
+            }
+            fs::create_dir_all(&out_dir).expect("Failed to create output directory");
+            fs::write(out_dir.join(format!("{}.wasm", name)), &bundle.artifact.wasm).expect("Failed to write output");
+            fs::write(out_dir.join(WORKER_SHIM), &bundle.shim).expect("Failed to write output");
+            fs::write(out_dir.join("wrangler.toml"), &bundle.wrangler).expect("Failed to write output");
+            println!(
+                "Compiled Worker bundle to {} ({} bytes of WASM)",
+                out_dir.display(),
+                bundle.artifact.wasm.len()
+            );
+        }
+        Err(e) => exit_on_compile_error(project, e),
+    }
+}
+
+fn cmd_package(file: &PathBuf, target: &str, entry: Option<&str>, output: Option<PathBuf>, opt_level: u8) {
+    if target != "lambda" && target != "oci" {
+        eprintln!("Invalid package target '{}'. Valid targets: lambda, oci", target);
+        std::process::exit(1);
+    }
+
+    let project = load_project(std::slice::from_ref(file));
+    let name = file.file_stem().and_then(|s| s.to_str()).unwrap_or(target).to_string();
+    let out_dir = output.unwrap_or_else(|| file.with_file_name(format!("{}-{}", name, target)));
+    if target == "oci" {
+        package_oci(&project, &name, &out_dir, opt_level);
+        return;
+    }
+
+    // Lambda runs the handler on its Node.js runtime
+    let target = Target::new(Platform::Node).with_opt_level(opt_level);
+    match project.lambda_package(target, &name, entry) {
+        Ok(package) => {
+            for warning in &package.artifact.warnings {
+                eprintln!("{}: {}", warning.code.as_deref().unwrap_or("warning"), warning.message);
             }
+            fs::create_dir_all(&out_dir).expect("Failed to create output directory");
+            fs::write(out_dir.join(format!("{}.zip", name)), &package.zip).expect("Failed to write output");
+            fs::write(out_dir.join("template.yaml"), &package.sam).expect("Failed to write output");
+            fs::write(out_dir.join("main.tf"), &package.terraform).expect("Failed to write output");
+            println!(
+                "Packaged Lambda function to {} ({} byte zip)",
+                out_dir.display(),
+                package.zip.len()
+            );
+        }
+        Err(e) => exit_on_compile_error(&project, e),
+    }
+}
+
+/// Write the build context of an OCI image into `out_dir`: the
+/// `Containerfile`, the module and the Deno host runner in `host/`. The
+/// image runs with the Deno permissions the program's effects call for.
+fn package_oci(project: &Project, name: &str, out_dir: &Path, opt_level: u8) {
+    let Some(host_dir) = runner::deno_host_dir() else {
+        eprintln!("Error: Could not find runtime/host/run.deno.ts");
+        eprintln!("Make sure you're running from the covenant project directory");
+        std::process::exit(1);
+    };
+    let program = match parse(&project.files()[0].source) {
+        Ok(program) => program,
+        Err(e) => {
+            report_parse_error(&project.files()[0].source, &project.files()[0].path, &e);
             std::process::exit(1);
         }
     };
 
-    // Run optimizer if level > 0
-    if opt_level != OptLevel::O0 {
-        let settings = OptSettings {
-            level: opt_level,
-            emit_warnings: true,
-        };
+    let wasm_path = Path::new(covenant_codegen::IMAGE_APP_DIR).join(format!("{}.wasm", name));
+    let permissions = runner::deno_permissions(&program, &wasm_path, &[]);
+    let target = Target::new(Platform::Deno).with_opt_level(opt_level);
+    match project.oci_package(target, name, &permissions) {
+        Ok(package) => {
+            for warning in &package.artifact.warnings {
+                eprintln!("{}: {}", warning.code.as_deref().unwrap_or("warning"), warning.message);
+            }
+            fs::create_dir_all(out_dir).expect("Failed to create output directory");
+            fs::write(out_dir.join(format!("{}.wasm", name)), &package.artifact.wasm).expect("Failed to write output");
+            fs::write(out_dir.join(covenant_codegen::CONTAINERFILE), &package.containerfile)
+                .expect("Failed to write output");
+            copy_host_runner(&host_dir, &out_dir.join("host")).expect("Failed to copy the host runner");
+            println!(
+                "Packaged OCI image context to {} ({} bytes of WASM); build with `docker build -f {} {}`",
+                out_dir.display(),
+                package.artifact.wasm.len(),
+                out_dir.join(covenant_codegen::CONTAINERFILE).display(),
+                out_dir.display()
+            );
+        }
+        Err(e) => exit_on_compile_error(project, e),
+    }
+}
 
-        // Optimize each snippet's body
-        if let covenant_ast::Program::Snippets { ref mut snippets, .. } = program {
-            for snippet in snippets.iter_mut() {
-                // Find the body section and optimize its steps
-                for section in snippet.sections.iter_mut() {
-                    if let covenant_ast::Section::Body(ref mut body) = section {
-                        let opt_result = optimize(&mut body.steps, &settings);
-
-                        // Report warnings
-                        for warning in &opt_result.warnings {
-                            eprintln!("{}: {}", warning.code, warning.message);
-                        }
-                    }
-                }
+/// Copy the Deno runner script and its `src/` modules from `host_dir`
+fn copy_host_runner(host_dir: &Path, dest: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dest.join("src"))?;
+    fs::copy(host_dir.join("run.deno.ts"), dest.join("run.deno.ts"))?;
+    for entry in fs::read_dir(host_dir.join("src"))? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            fs::copy(entry.path(), dest.join("src").join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+fn exit_on_compile_error(project: &Project, error: DriverError) -> ! {
+    match error {
+        DriverError::Diagnostics(diagnostics) => {
+            for diagnostic in &diagnostics {
+                report_diagnostic(&project.files()[0].source, diagnostic);
             }
         }
+        e => eprintln!("Compilation error: {}", e),
     }
+    std::process::exit(1);
+}
 
-    // Build symbol graph and run effect checking for symbol metadata embedding
-    let symbol_result = build_symbol_graph(&program);
-    let empty_graph = covenant_symbols::SymbolGraph::new();
-    let symbol_graph = match &symbol_result {
-        Ok(result) => &result.graph,
-        Err(_) => &empty_graph,
+fn cmd_emit_host(files: &[PathBuf], target: &str, output: Option<PathBuf>) {
+    let platform: Platform = match target.parse() {
+        Ok(platform) => platform,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
     };
 
-    let effect_result = check_effects(symbol_graph);
-
-    // Compile with symbol metadata embedding
-    match compile_with_symbols(&program, &result.symbols, symbol_graph, &effect_result) {
-        Ok(wasm) => {
-            let out_path = output.unwrap_or_else(|| {
-                let mut p = file.clone();
-                p.set_extension("wasm");
-                p
-            });
-            fs::write(&out_path, &wasm).expect("Failed to write output");
-            println!("Compiled to {} ({} bytes)", out_path.display(), wasm.len());
+    let project = load_project(files);
+    match project.host_glue(platform) {
+        Ok(glue) => match output {
+            Some(path) => {
+                fs::write(&path, &glue).expect("Failed to write output");
+                println!("Wrote host glue to {}", path.display());
+            }
+            None => print!("{}", glue),
+        },
+        Err(DriverError::Diagnostics(diagnostics)) => {
+            for diagnostic in &diagnostics {
+                eprintln!("{}", diagnostic);
+            }
+            std::process::exit(1);
         }
         Err(e) => {
-            eprintln!("Compilation error: {}", e);
+            eprintln!("{}", e);
             std::process::exit(1);
         }
     }
 }
 
-fn cmd_query(files: &[PathBuf], query_str: &str) {
+fn cmd_query(files: &[PathBuf], query_str: &str, module: Option<&str>) {
+    let scope = module_scope(files, module);
+    if let Some(query) = parse_paths_query(query_str) {
+        query_paths(files, &query, scope.as_ref(), None);
+        return;
+    }
+    if let Some(query) = parse_references_query(query_str) {
+        query_references(files, &query, scope.as_ref());
+        return;
+    }
+
     // Parse and check all files
     let mut all_programs = Vec::new();
 
@@ -435,7 +827,10 @@ fn cmd_query(files: &[PathBuf], query_str: &str) {
         let graph_builder = GraphBuilder::new(&result.symbols);
         let graph = graph_builder.build(program);
 
-        let query_result = execute_query(&query, &result.symbols, &graph);
+        let mut query_result = execute_query(&query, &result.symbols, &graph);
+        if let Some(scope) = &scope {
+            query_result.symbols.retain(|sym| scope.contains(&sym.name));
+        }
 
         if !query_result.symbols.is_empty() {
             println!("Results:");
@@ -445,10 +840,10 @@ fn cmd_query(files: &[PathBuf], query_str: &str) {
                     println!("    effects: {:?}", sym.effects);
                 }
                 if !sym.calls.is_empty() {
-                    println!("    calls: {:?}", sym.calls);
+                    println!("    calls: {}", weighted_edges(&sym.calls, &sym.call_sites));
                 }
                 if !sym.called_by.is_empty() {
-                    println!("    called_by: {:?}", sym.called_by);
+                    println!("    called_by: {}", weighted_edges(&sym.called_by, &sym.caller_sites));
                 }
             }
         } else {
@@ -457,6 +852,108 @@ fn cmd_query(files: &[PathBuf], query_str: &str) {
     }
 }
 
+/// Call edge names, each followed by its call count when above one
+fn weighted_edges(names: &[String], sites: &BTreeMap<String, Vec<CallSite>>) -> String {
+    let edges: Vec<String> = names
+        .iter()
+        .map(|name| match sites.get(name).map_or(0, Vec::len) {
+            0 | 1 => name.clone(),
+            count => format!("{} ×{}", name, count),
+        })
+        .collect();
+    edges.join(", ")
+}
+
+/// Print the call paths a `paths from X to Y` query asks for, over one
+/// graph of every file, with the effects each path declares; only the
+/// `limit` shortest when given
+fn query_paths(files: &[PathBuf], query: &PathsQuery, scope: Option<&BTreeSet<String>>, limit: Option<usize>) {
+    let graph = load_symbol_graph(files);
+    let id_of = |name: &str| match graph.id_of(name) {
+        Some(id) => id,
+        None => {
+            eprintln!("Unknown symbol: {}", name);
+            std::process::exit(1);
+        }
+    };
+    let (from, to) = (id_of(&query.from), id_of(&query.to));
+
+    let mut paths = if query.shortest {
+        graph.shortest_call_path(from, to).into_iter().collect()
+    } else {
+        graph.call_paths(from, to, query.max_depth)
+    };
+    if let Some(scope) = scope {
+        paths.retain(|path| path.iter().all(|id| graph.get(*id).is_some_and(|s| scope.contains(&s.name))));
+    }
+    // Paths come shortest first
+    if let Some(limit) = limit {
+        paths.truncate(limit);
+    }
+    if paths.is_empty() {
+        println!("No call path from {} to {}", query.from, query.to);
+        return;
+    }
+
+    println!("Results:");
+    for (i, path) in paths.iter().enumerate() {
+        let symbols: Vec<_> = path.iter().filter_map(|id| graph.get(*id)).collect();
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        let effects: BTreeSet<&str> =
+            symbols.iter().flat_map(|s| s.declared_effects.iter().map(|e| e.name.as_str())).collect();
+        println!("  {}. {}", i + 1, names.join(" -> "));
+        if effects.is_empty() {
+            println!("     effects: (pure)");
+        } else {
+            println!("     effects: {}", effects.into_iter().collect::<Vec<_>>().join(", "));
+        }
+    }
+}
+
+/// Print the references a `references to X` query asks for, over one graph
+/// of every file, each with its kind and location
+fn query_references(files: &[PathBuf], query: &ReferencesQuery, scope: Option<&BTreeSet<String>>) {
+    let graph = load_symbol_graph(files);
+    let Some(id) = graph.id_of(&query.to) else {
+        eprintln!("Unknown symbol: {}", query.to);
+        std::process::exit(1);
+    };
+
+    let mut references = graph.references_to(id);
+    references.retain(|r| query.kind.as_deref().is_none_or(|kind| r.kind.as_str() == kind));
+    if let Some(scope) = scope {
+        references.retain(|r| graph.get(r.from).is_some_and(|s| scope.contains(&s.name)));
+    }
+    if references.is_empty() {
+        println!("No references to {}", query.to);
+        return;
+    }
+
+    let mut line_indexes: HashMap<PathBuf, covenant_ast::LineIndex> = HashMap::new();
+    let mut locate = |file: Option<&PathBuf>, span: Span| match file {
+        Some(file) => {
+            let index = line_indexes.entry(file.clone()).or_insert_with(|| {
+                covenant_ast::LineIndex::new(&fs::read_to_string(file).unwrap_or_default())
+            });
+            format!("{}:{}", file.display(), index.line_col(span.start))
+        }
+        None => format!("bytes {}..{}", span.start, span.end),
+    };
+    println!("Results:");
+    for reference in &references {
+        let Some(from) = graph.get(reference.from) else { continue };
+        let location = locate(reference.file.as_ref(), reference.span);
+        match reference.call_sites.len() {
+            0 | 1 => println!("  {:<16} {} ({})", reference.kind, from.name, location),
+            count => println!("  {:<16} {} ({}) ×{}", reference.kind, from.name, location, count),
+        }
+        for site in &reference.call_sites {
+            let step = site.step.as_deref().unwrap_or("?");
+            println!("    step {} ({})", step, locate(reference.file.as_ref(), site.span));
+        }
+    }
+}
+
 fn cmd_info(file: &PathBuf) {
     let source = match fs::read_to_string(file) {
         Ok(s) => s,
@@ -501,7 +998,13 @@ fn cmd_info(file: &PathBuf) {
         if !callees.is_empty() {
             let names: Vec<_> = callees
                 .iter()
-                .filter_map(|&id| result.symbols.get(id).map(|s| s.name.as_str()))
+                .filter_map(|&id| {
+                    let name = &result.symbols.get(id)?.name;
+                    Some(match graph.call_count(func.id, id) {
+                        1 => name.clone(),
+                        count => format!("{} ×{}", name, count),
+                    })
+                })
                 .collect();
             println!("    calls: {}", names.join(", "));
         }
@@ -511,10 +1014,14 @@ fn cmd_info(file: &PathBuf) {
     println!("Legend: ○ = pure, ● = effectful");
 }
 
-fn cmd_effects(files: &[PathBuf], violations_only: bool, explain: bool) {
+fn cmd_effects(files: &[PathBuf], violations_only: bool, explain: bool, module: Option<&str>) {
     let mut all_ok = true;
-    let mut total_violations = 0;
+    let registry = load_project(files).effects().clone();
+    let scope = module_scope(files, module);
 
+    // One graph over every file, so calls across files resolve
+    let mut project = covenant_symbols::Project::new();
+    let mut sources = Vec::new();
     for file in files {
         let source = match fs::read_to_string(file) {
             Ok(s) => s,
@@ -525,35 +1032,40 @@ fn cmd_effects(files: &[PathBuf], violations_only: bool, explain: bool) {
             }
         };
 
-        let program = match parse(&source) {
-            Ok(p) => p,
+        match parse(&source) {
+            Ok(program) => {
+                project.add_file(file, &program);
+                sources.push((file, source));
+            }
             Err(e) => {
                 report_parse_error(&source, file, &e);
                 all_ok = false;
-                continue;
             }
-        };
+        }
+    }
 
-        // Build symbol graph (Phase 2)
-        let symbol_result = match build_symbol_graph(&program) {
-            Ok(result) => result,
-            Err(errors) => {
-                eprintln!("✗ {} - {} symbol errors:", file.display(), errors.len());
-                for err in &errors {
-                    eprintln!("  {}: {}", err.code(), err);
-                }
-                all_ok = false;
-                continue;
+    // Build symbol graph (Phase 2)
+    let symbols = match project.build() {
+        Ok(symbols) => symbols,
+        Err(errors) => {
+            eprintln!("✗ {} symbol errors:", errors.len());
+            for err in &errors {
+                eprintln!("  {}: {}", err.code(), err);
             }
-        };
+            std::process::exit(1);
+        }
+    };
 
-        // Run effect checking (Phase 3)
-        let (result, diagnostics) = if explain {
-            check_effects_with_diagnostics(&symbol_result.graph)
-        } else {
-            (check_effects(&symbol_result.graph), Vec::new())
-        };
-        total_violations += result.violations.len();
+    // Run effect checking (Phase 3)
+    let mut result = check_effects_with_registry(&symbols.graph, &registry);
+    if let Some(scope) = &scope {
+        result.closures.retain(|name, _| scope.contains(name));
+        result.violations.retain(|err| scope.contains(err.function()));
+    }
+    let total_violations = result.violations.len();
+
+    for (file, source) in &sources {
+        let in_file = |name: &str| symbols.path_of(name) == Some(file.as_path());
 
         if !violations_only {
             println!("File: {}", file.display());
@@ -563,7 +1075,7 @@ fn cmd_effects(files: &[PathBuf], violations_only: bool, explain: bool) {
             let mut pure_fns = Vec::new();
             let mut effectful_fns = Vec::new();
 
-            for (name, closure) in &result.closures {
+            for (name, closure) in result.closures.iter().filter(|(name, _)| in_file(name)) {
                 if closure.is_pure {
                     pure_fns.push((name, closure));
                 } else {
@@ -593,19 +1105,22 @@ fn cmd_effects(files: &[PathBuf], violations_only: bool, explain: bool) {
         }
 
         // Report violations
-        if !result.violations.is_empty() {
+        let violations: Vec<&EffectError> = result.violations.iter().filter(|err| in_file(err.function())).collect();
+        if !violations.is_empty() {
             all_ok = false;
 
             if explain {
                 // Use rich diagnostics with ariadne
-                for (error, diagnostic) in result.violations.iter().zip(diagnostics.iter()) {
-                    report_effect_error_rich(&source, file, error, diagnostic);
+                for error in violations {
+                    let diagnostic = explain_effect_violation(error, &symbols.graph);
+                    report_effect_error_rich(source, file, error, &diagnostic);
                 }
             } else {
                 // Use concise format
                 eprintln!("Effect violations in {}:", file.display());
-                for error in &result.violations {
-                    report_effect_error_concise(error);
+                let lines = LineIndex::new(source);
+                for error in violations {
+                    report_effect_error_concise(error, Some(&lines));
                 }
                 eprintln!();
             }
@@ -626,27 +1141,114 @@ fn cmd_effects(files: &[PathBuf], violations_only: bool, explain: bool) {
     }
 }
 
-/// Report an effect error in concise format
-fn report_effect_error_concise(error: &EffectError) {
+fn cmd_effects_fix(files: &[PathBuf]) {
+    let mut all_ok = true;
+
+    for file in files {
+        let source = match fs::read_to_string(file) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file.display(), e);
+                all_ok = false;
+                continue;
+            }
+        };
+
+        let fixed = match fix_effects(&source) {
+            Ok(fixed) => fixed,
+            Err(ActionError::Parse(e)) => {
+                report_parse_error(&source, file, &e);
+                all_ok = false;
+                continue;
+            }
+            Err(e) => {
+                eprintln!("✗ {} - {}", file.display(), e);
+                all_ok = false;
+                continue;
+            }
+        };
+
+        if fixed == source {
+            println!("✓ {} - nothing to fix", file.display());
+        } else if let Err(e) = fs::write(file, &fixed) {
+            eprintln!("Error writing {}: {}", file.display(), e);
+            all_ok = false;
+        } else {
+            println!("✓ {} - declared missing effects", file.display());
+        }
+    }
+
+    if !all_ok {
+        std::process::exit(1);
+    }
+}
+
+fn cmd_effects_why(files: &[PathBuf], function: &str, effect: &str) {
+    let graph = load_symbol_graph(files);
+
+    if !graph.contains(function) {
+        eprintln!("Unknown function '{}'", function);
+        std::process::exit(1);
+    }
+
+    let result = check_effects(&graph);
+    let chains = explain_effect_provenance(function, effect, &graph, &result);
+
+    if chains.is_empty() {
+        println!("`{}` does not have effect `{}`", function, effect);
+        return;
+    }
+
+    if chains.len() == MAX_PROVENANCE_CHAINS {
+        println!("`{}` has effect `{}` via many call chains; the {} shortest:", function, effect, chains.len());
+    } else {
+        println!("`{}` has effect `{}` via {} call chain(s):", function, effect, chains.len());
+    }
+    for (i, chain) in chains.iter().enumerate() {
+        let names: Vec<_> = chain.iter().map(|entry| entry.name.as_str()).collect();
+        println!("  {}. {}", i + 1, names.join(" -> "));
+    }
+}
+
+/// Report an effect error in concise format, located by line and column
+/// when the source is known
+fn report_effect_error_concise(error: &EffectError, lines: Option<&LineIndex>) {
+    let at = |span: &Span| match lines {
+        Some(lines) => lines.line_col(span.start).to_string(),
+        None => format!("{}..{}", span.start, span.end),
+    };
     match error {
         EffectError::PureCallsEffectful { function, callee, effects, span } => {
             eprintln!(
-                "  E-EFFECT-001 [{}:{}]: pure function `{}` calls effectful `{}` (effects: {:?})",
-                span.start, span.end, function, callee, effects
+                "  E-EFFECT-001 [{}]: pure function `{}` calls effectful `{}` (effects: {:?})",
+                at(span), function, callee, effects
             );
         }
         EffectError::MissingEffect { function, missing, source_callee, span } => {
             eprintln!(
-                "  E-EFFECT-002 [{}:{}]: function `{}` missing effect declarations {:?} (from `{}`)",
-                span.start, span.end, function, missing, source_callee
+                "  E-EFFECT-002 [{}]: function `{}` missing effect declarations {:?} (from `{}`)",
+                at(span), function, missing, source_callee
             );
         }
         EffectError::ParameterNotCovered {
             function, effect_name, param_name, required_value, declared_value, source_callee, span
         } => {
             eprintln!(
-                "  E-EFFECT-003 [{}:{}]: function `{}` effect `{}` parameter `{}` not covered (required: {}, declared: {:?}, from `{}`)",
-                span.start, span.end, function, effect_name, param_name, required_value, declared_value, source_callee
+                "  E-EFFECT-003 [{}]: function `{}` effect `{}` parameter `{}` not covered (required: {}, declared: {:?}, from `{}`)",
+                at(span), function, effect_name, param_name, required_value, declared_value, source_callee
+            );
+        }
+        EffectError::UnknownEffect { function, effect, suggestion, span } => {
+            let hint = suggestion.as_ref().map(|s| format!(" (did you mean `{}`?)", s)).unwrap_or_default();
+            eprintln!(
+                "  E-EFFECT-004 [{}]: `{}` declares unknown effect `{}`{}",
+                at(span), function, effect, hint
+            );
+        }
+        EffectError::PolicyViolation { function, effect, param_name, declared_value, allowed, span } => {
+            eprintln!(
+                "  E-EFFECT-005 [{}]: `{}` effect `{}` parameter `{}` is outside the policy (declared: {:?}, allowed: {:?})",
+                at(span), function, effect, param_name, declared_value, allowed
             );
         }
     }
@@ -659,6 +1261,8 @@ fn report_effect_error_rich(source: &str, file: &PathBuf, error: &EffectError, d
         EffectError::PureCallsEffectful { span, .. } => span,
         EffectError::MissingEffect { span, .. } => span,
         EffectError::ParameterNotCovered { span, .. } => span,
+        EffectError::UnknownEffect { span, .. } => span,
+        EffectError::PolicyViolation { span, .. } => span,
     };
 
     // Build the main report
@@ -780,7 +1384,7 @@ fn cmd_requirements(
     }
 }
 
-fn cmd_format(file: &PathBuf, output: Option<PathBuf>, check: bool) {
+fn cmd_format(file: &PathBuf, output: Option<PathBuf>, check: bool, compact: bool) {
     let source = match fs::read_to_string(file) {
         Ok(s) => s,
         Err(e) => {
@@ -789,31 +1393,37 @@ fn cmd_format(file: &PathBuf, output: Option<PathBuf>, check: bool) {
         }
     };
 
-    let program = match parse(&source) {
-        Ok(p) => p,
-        Err(e) => {
-            report_parse_error(&source, file, &e);
-            std::process::exit(1);
+    let from_json = file.extension().is_some_and(|ext| ext == "json");
+    if from_json && check {
+        eprintln!("--check applies to snippet text, not a JSON AST");
+        std::process::exit(1);
+    }
+
+    let program = if from_json {
+        match Program::from_json(&source) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        // Compact input is accepted too, so formatting expands it back to canonical form
+        match parse(&source).or_else(|e| parse_compact(&source).map_err(|_| e)) {
+            Ok(p) => p,
+            Err(e) => {
+                report_parse_error(&source, file, &e);
+                std::process::exit(1);
+            }
         }
     };
 
-    // Convert to canonical form
-    let canonical = to_cov(&program);
+    // Convert to canonical (or compact) form
+    let canonical = if compact { to_cov_compact(&program) } else { to_cov(&program) };
 
     if check {
-        // Check mode - compare with original
-        // Normalize both for comparison (trim trailing whitespace from lines)
-        let normalize = |s: &str| -> String {
-            s.lines()
-                .map(|line| line.trim_end())
-                .collect::<Vec<_>>()
-                .join("\n")
-        };
-
-        let original_normalized = normalize(&source);
-        let canonical_normalized = normalize(&canonical);
-
-        if original_normalized != canonical_normalized {
+        // Check mode - compare with original, ignoring trailing whitespace
+        if !is_canonical(&source, &canonical) {
             eprintln!("{} is not in canonical form", file.display());
             std::process::exit(1);
         }
@@ -831,7 +1441,251 @@ fn cmd_format(file: &PathBuf, output: Option<PathBuf>, check: bool) {
     }
 }
 
-async fn cmd_explain(file: &PathBuf, format: &str, verbosity: &str, no_cache: bool) {
+fn cmd_validate_examples(names: &[String], json: bool) {
+    let mut examples = Vec::new();
+    for name in names {
+        match covenant_examples::get(name) {
+            Some(example) => examples.push(example),
+            None => {
+                eprintln!("Unknown example '{}'", name);
+                std::process::exit(1);
+            }
+        }
+    }
+    if names.is_empty() {
+        examples.extend(covenant_examples::all());
+    }
+
+    let total = examples.len();
+    let mut failed = 0;
+    let mut results = Vec::new();
+
+    for example in examples {
+        let validation = covenant_examples::validate(example);
+        let mismatches = validation.mismatches(&example.expected);
+        if !mismatches.is_empty() {
+            failed += 1;
+        }
+
+        if json {
+            results.push(serde_json::json!({
+                "name": example.name,
+                "path": example.path(),
+                "result": validation,
+                "mismatches": mismatches,
+            }));
+        } else if mismatches.is_empty() {
+            println!("✓ {}", example.name);
+        } else {
+            eprintln!("✗ {}", example.name);
+            for mismatch in &mismatches {
+                eprintln!("  {}", mismatch);
+            }
+            if let Some(error) = &validation.error {
+                eprintln!("  {}", error);
+            }
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results).unwrap());
+    } else {
+        println!();
+        println!("{} examples, {} failed", total, failed);
+    }
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+async fn cmd_eval_model(
+    provider: Option<&str>,
+    model: Option<String>,
+    names: &[String],
+    output: &Path,
+    max_cost: Option<f64>,
+) {
+    let policy = match find_llm_policy(Path::new(".")) {
+        Ok(policy) => policy,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let provider = match provider {
+        Some(name) => match Provider::from_name(name) {
+            Some(provider) => provider,
+            None => {
+                eprintln!("Unknown provider '{}' (expected anthropic or openai)", name);
+                std::process::exit(1);
+            }
+        },
+        None => match LlmClient::from_policy(&policy) {
+            Ok(client) => client.provider(),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+    let llm = LlmClient::for_provider(provider, model)
+        .and_then(|client| client.with_policy(policy))
+        .and_then(|client| match max_cost {
+            Some(max_cost) => client.with_max_cost(max_cost),
+            None => Ok(client),
+        });
+    let llm = match llm {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            if matches!(e, LlmError::NoApiKey) {
+                eprintln!("Set the {} environment variable", provider.api_key_var());
+            }
+            std::process::exit(1);
+        }
+    };
+
+    let mut examples = Vec::new();
+    for name in names {
+        match covenant_examples::get(name) {
+            Some(example) => examples.push(example),
+            None => {
+                eprintln!("Unknown example '{}'", name);
+                std::process::exit(1);
+            }
+        }
+    }
+    if names.is_empty() {
+        examples.extend(covenant_examples::all());
+    }
+
+    println!("Evaluating {} / {} on {} examples", llm.provider().name(), llm.model(), examples.len());
+
+    let mut results = Vec::new();
+    let mut over_budget = false;
+    for example in examples {
+        let record = match llm.generate_code(example.description).await {
+            Ok(response) => EvalRecord::from_generated(example, &extract_code(&response)),
+            Err(e @ LlmError::BudgetExceeded { .. }) => {
+                println!("  {}", e);
+                over_budget = true;
+                break;
+            }
+            Err(e) => EvalRecord::generation_failed(example, e.to_string()),
+        };
+        let status = if record.checked { "✓" } else { "✗" };
+        println!("  {} {}", status, example.name);
+        if let Some(error) = &record.error {
+            println!("      {}", error);
+        }
+        results.push(record);
+    }
+
+    print_llm_usage(&llm);
+
+    let report = EvalReport::new(llm.provider().name(), llm.model(), results);
+    println!();
+    for stage in Stage::ALL {
+        println!(
+            "{:>14}: {}/{} ({:.1}%)",
+            stage.name(),
+            report.summary.count(stage),
+            report.summary.total,
+            report.summary.rate(stage)
+        );
+    }
+
+    let json = serde_json::to_string_pretty(&report).expect("eval report serializes");
+    if let Err(e) = fs::write(output, json) {
+        eprintln!("Error writing {}: {}", output.display(), e);
+        std::process::exit(1);
+    }
+    println!("\nResults saved to {}", output.display());
+    if over_budget {
+        std::process::exit(1);
+    }
+}
+
+fn cmd_eval_compare(baseline: &Path, candidate: &Path) {
+    let load = |path: &Path| -> EvalReport {
+        let parsed = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    };
+    let baseline = load(baseline);
+    let candidate = load(candidate);
+    let comparison = compare_reports(&baseline, &candidate);
+
+    println!(
+        "{:>14}  {:>12}  {:>12}  {:>7}",
+        "stage",
+        format!("{}/{}", baseline.provider, baseline.model),
+        format!("{}/{}", candidate.provider, candidate.model),
+        "delta"
+    );
+    for (stage, base, cand) in &comparison.rates {
+        println!("{:>14}  {:>11.1}%  {:>11.1}%  {:>+6.1}%", stage.name(), base, cand, cand - base);
+    }
+
+    if !comparison.regressions.is_empty() {
+        println!("\nRegressions (checked in baseline, not in candidate):");
+        for name in &comparison.regressions {
+            println!("  {}", name);
+        }
+    }
+    if !comparison.improvements.is_empty() {
+        println!("\nImprovements (checked in candidate, not in baseline):");
+        for name in &comparison.improvements {
+            println!("  {}", name);
+        }
+    }
+}
+
+fn cmd_grammar(format: &str) {
+    match format.parse::<GrammarFormat>() {
+        Ok(format) => println!("{}", grammar::render(format)),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn cmd_new(path: &Path, template: &str) {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        eprintln!("Invalid project path: {}", path.display());
+        std::process::exit(1);
+    };
+
+    let Some(files) = scaffold::render(name, template) else {
+        eprintln!(
+            "Unknown template '{}' (expected one of: {})",
+            template,
+            scaffold::TEMPLATES.join(", ")
+        );
+        std::process::exit(1);
+    };
+
+    if let Err(e) = scaffold::write(path, &files) {
+        eprintln!("Error creating {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+
+    println!("Created {} project `{}`:", template, name);
+    for (file, _) in &files {
+        println!("  {}", path.join(file).display());
+    }
+}
+
+async fn cmd_explain(file: &PathBuf, format: &str, verbosity: &str, no_cache: bool, max_cost: Option<f64>) {
     // Read and parse the file
     let source = match fs::read_to_string(file) {
         Ok(s) => s,
@@ -853,95 +1707,417 @@ async fn cmd_explain(file: &PathBuf, format: &str, verbosity: &str, no_cache: bo
     let verbosity: Verbosity = verbosity.parse().unwrap_or_default();
     let format: ExplainFormat = format.parse().unwrap_or_default();
 
-    // Create LLM client
-    let llm = match LlmClient::new() {
-        Ok(client) => client,
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            eprintln!("Set ANTHROPIC_API_KEY or OPENAI_API_KEY environment variable");
-            std::process::exit(1);
-        }
-    };
-
-    // Create generator with optional cache
-    let generator = if no_cache {
-        ExplainGenerator::new(llm)
-    } else {
-        let cache = ExplanationCache::new();
-        ExplainGenerator::with_cache(llm, cache)
-    };
+    // Create LLM client, within the project's policy
+    let policy = load_project(std::slice::from_ref(file)).llm_policy().clone();
+    let generator = explain_generator(&policy, no_cache, max_cost);
 
     // Generate explanations for all snippets in the file
     let snippets = match &program {
-        covenant_ast::Program::Snippets { snippets, .. } => snippets,
-        covenant_ast::Program::Legacy { .. } => {
+        Program::Snippets { snippets, .. } => snippets,
+        Program::Legacy { .. } => {
             eprintln!("Error: explain command only works with snippet-based files");
             eprintln!("The file uses legacy declaration syntax");
             std::process::exit(1);
         }
     };
 
+    // Literal values of sensitive params and fields never reach the LLM
+    let source = redact_sensitive(&source, snippets);
+    let mut store = open_explanation_store();
+    let mut over_budget = false;
     for snippet in snippets {
         match generator.explain(snippet, &source, verbosity).await {
             Ok(explanation) => {
+                store_explanation(&mut store, generator.llm().model(), file, snippet, &explanation);
                 let output = format_explanation(&explanation, format);
                 println!("{}", output);
             }
+            Err(ExplainError::Llm(e @ LlmError::BudgetExceeded { .. })) => {
+                eprintln!("{}", e);
+                over_budget = true;
+                break;
+            }
             Err(e) => {
                 eprintln!("Error generating explanation for {}: {}", snippet.id, e);
             }
         }
     }
+    print_llm_usage(generator.llm());
+    if over_budget {
+        std::process::exit(1);
+    }
 }
 
-fn cmd_repl() {
-    use rustyline::DefaultEditor;
+/// An explanation generator calling the LLM `policy` allows, caching
+/// explanations unless `no_cache`
+fn explain_generator(policy: &LlmPolicy, no_cache: bool, max_cost: Option<f64>) -> ExplainGenerator {
+    let llm = LlmClient::from_policy(policy).and_then(|client| match max_cost {
+        Some(max_cost) => client.with_max_cost(max_cost),
+        None => Ok(client),
+    });
+    let llm = match llm {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            if matches!(e, LlmError::NoApiKey) {
+                eprintln!("Set ANTHROPIC_API_KEY or OPENAI_API_KEY environment variable");
+            }
+            std::process::exit(1);
+        }
+    };
 
-    println!("Covenant REPL v0.1.0");
-    println!("Type :help for help, :quit to exit");
-    println!();
+    if no_cache {
+        ExplainGenerator::new(llm)
+    } else {
+        ExplainGenerator::with_cache(llm, ExplanationCache::new())
+    }
+}
 
-    let mut rl = DefaultEditor::new().expect("Failed to create REPL");
-    let mut loaded_source = String::new();
+/// Explain every snippet under `dirs`, `jobs` requests at a time
+///
+/// Progress is saved to [`BATCH_STATE_PATH`] after each explanation, so
+/// `--resume` picks an interrupted batch up with the same files, skipping
+/// snippets explained since they last changed. Each file's explanations are
+/// written next to it once all its snippets are done.
+async fn cmd_explain_project(
+    dirs: &[PathBuf],
+    format: &str,
+    verbosity: &str,
+    no_cache: bool,
+    max_cost: Option<f64>,
+    jobs: usize,
+    resume: bool,
+) {
+    let verbosity: Verbosity = verbosity.parse().unwrap_or_default();
+    let format: ExplainFormat = format.parse().unwrap_or_default();
+    let state_path = Path::new(BATCH_STATE_PATH);
 
-    loop {
-        let readline = rl.readline("covenant> ");
-        match readline {
-            Ok(line) => {
-                let _ = rl.add_history_entry(&line);
-                let trimmed = line.trim();
+    let mut state = if resume {
+        match BatchState::load(state_path) {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("Error: no batch to resume in {}: {}", state_path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let dirs = if dirs.is_empty() { vec![PathBuf::from(".")] } else { dirs.to_vec() };
+        let mut files = Vec::new();
+        for dir in &dirs {
+            collect_cov_files(dir, &mut files);
+        }
+        files.sort();
+        BatchState::new(files)
+    };
+    if state.files.is_empty() {
+        eprintln!("Error: no .cov files found");
+        std::process::exit(1);
+    }
 
-                if trimmed.starts_with(':') {
-                    match trimmed {
-                        ":quit" | ":q" => break,
-                        ":help" | ":h" => {
-                            println!("Commands:");
-                            println!("  :load <file>  - Load a file");
-                            println!("  :parse        - Show parsed AST");
-                            println!("  :check        - Type check loaded code");
-                            println!("  :info         - Show info about loaded code");
-                            println!("  :clear        - Clear loaded code");
-                            println!("  :quit         - Exit REPL");
-                        }
-                        cmd if cmd.starts_with(":load ") => {
-                            let path = &cmd[6..].trim();
-                            match fs::read_to_string(path) {
-                                Ok(s) => {
-                                    loaded_source = s;
-                                    println!("Loaded {}", path);
-                                }
-                                Err(e) => {
-                                    eprintln!("Error: {}", e);
-                                }
-                            }
-                        }
+    let policy = match find_llm_policy(Path::new(".")) {
+        Ok(policy) => policy,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let generator = explain_generator(&policy, no_cache, max_cost);
+
+    // Sources with sensitive literals redacted, and their snippets
+    let mut sources: Vec<(PathBuf, String, Vec<Snippet>)> = Vec::new();
+    for file in &state.files {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        match parse(&source) {
+            Ok(Program::Snippets { snippets, .. }) => {
+                sources.push((file.clone(), redact_sensitive(&source, &snippets), snippets));
+            }
+            Ok(Program::Legacy { .. }) => eprintln!("Skipping {}: legacy declaration syntax", file.display()),
+            Err(e) => report_parse_error(&source, file, &e),
+        }
+    }
+
+    let items: Vec<BatchItem> = sources
+        .iter()
+        .flat_map(|(_, code, snippets)| snippets.iter().map(move |snippet| BatchItem { snippet, code }))
+        .filter(|item| state.get(item.snippet).is_none())
+        .collect();
+    if let Err(e) = state.save(state_path) {
+        eprintln!("Error saving {}: {}", state_path.display(), e);
+        std::process::exit(1);
+    }
+
+    let files_by_snippet: HashMap<&str, &PathBuf> = sources
+        .iter()
+        .flat_map(|(file, _, snippets)| snippets.iter().map(move |snippet| (snippet.id.as_str(), file)))
+        .collect();
+    let mut store = open_explanation_store();
+    let mut finished = 0;
+    let mut over_budget = false;
+    generator
+        .explain_batch(&items, verbosity, jobs, |item, result| {
+            finished += 1;
+            match result {
+                Ok(explanation) => {
+                    eprintln!("[{}/{}] {}", finished, items.len(), item.snippet.id);
+                    let file = files_by_snippet[item.snippet.id.as_str()];
+                    store_explanation(&mut store, generator.llm().model(), file, item.snippet, &explanation);
+                    state.done.insert(item.snippet.id.clone(), explanation);
+                    if let Err(e) = state.save(state_path) {
+                        eprintln!("Error saving {}: {}", state_path.display(), e);
+                        return ControlFlow::Break(());
+                    }
+                }
+                Err(ExplainError::Llm(e @ LlmError::BudgetExceeded { .. })) => {
+                    eprintln!("{}", e);
+                    over_budget = true;
+                    return ControlFlow::Break(());
+                }
+                Err(e) => eprintln!("[{}/{}] {}: {}", finished, items.len(), item.snippet.id, e),
+            }
+            ControlFlow::Continue(())
+        })
+        .await;
+    print_llm_usage(generator.llm());
+
+    let extension = match format {
+        ExplainFormat::Json => "json",
+        ExplainFormat::Markdown => "md",
+        ExplainFormat::Text | ExplainFormat::Compact => "txt",
+    };
+    let mut remaining = 0;
+    for (file, _, snippets) in &sources {
+        let explanations: Option<Vec<&Explanation>> = snippets.iter().map(|snippet| state.get(snippet)).collect();
+        let Some(explanations) = explanations else {
+            remaining += snippets.iter().filter(|snippet| state.get(snippet).is_none()).count();
+            continue;
+        };
+        let output = match format {
+            ExplainFormat::Json => serde_json::to_string_pretty(&explanations).unwrap_or_default(),
+            _ => {
+                let sections: Vec<String> =
+                    explanations.iter().map(|explanation| format_explanation(explanation, format)).collect();
+                sections.join("\n\n")
+            }
+        };
+        let mut path = file.clone().into_os_string();
+        path.push(format!(".explain.{}", extension));
+        match fs::write(&path, output + "\n") {
+            Ok(()) => println!("Wrote {}", Path::new(&path).display()),
+            Err(e) => {
+                eprintln!("Error writing {}: {}", Path::new(&path).display(), e);
+                remaining += snippets.len();
+            }
+        }
+    }
+
+    if remaining == 0 {
+        let _ = fs::remove_file(state_path);
+    } else {
+        eprintln!("{} snippet(s) left to explain; rerun with --resume to continue", remaining);
+        std::process::exit(1);
+    }
+}
+
+/// Add the `.cov` files under `dir` to `files`, skipping hidden directories
+/// and build output
+fn collect_cov_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    if dir.is_file() {
+        files.push(dir.to_path_buf());
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        eprintln!("Error reading directory {}", dir.display());
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" && name != "node_modules" {
+                collect_cov_files(&path, files);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "cov") {
+            files.push(path);
+        }
+    }
+}
+
+/// Print the tokens an LLM client used, and what they cost when its
+/// model's prices are known, to stderr
+fn print_llm_usage(llm: &LlmClient) {
+    let usage = llm.usage();
+    if usage.requests == 0 {
+        return;
+    }
+    match llm.cost() {
+        Some(cost) => eprintln!("LLM usage: {}, ${:.4} ({})", usage, cost, llm.model()),
+        None => eprintln!("LLM usage: {} ({}, prices unknown)", usage, llm.model()),
+    }
+}
+
+/// Store of the explanations `covenant explain` generated, read by `covenant doc`
+const EXPLANATION_STORE: &str = ".covenant/explanations.redb";
+
+fn open_explanation_store() -> RedbStorage {
+    match RedbStorage::new(EXPLANATION_STORE) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Error opening explanation store: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Keep an explanation of `snippet` in `file` for `covenant doc`; failing
+/// to is only worth a warning, as the explanation was already printed or saved
+fn store_explanation(
+    store: &mut RedbStorage,
+    model: &str,
+    file: &Path,
+    snippet: &Snippet,
+    explanation: &Explanation,
+) {
+    let stored = serde_json::to_string(explanation).map(|json| StoredExplanation {
+        snippet: snippet.id.clone(),
+        source_file: file.display().to_string(),
+        snippet_hash: snippet.content_hash(),
+        model: model.to_string(),
+        generator_version: explanation.generator_version.clone(),
+        explanation: json,
+    });
+    let result = match stored {
+        Ok(stored) => store.put_explanation(&stored).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("Warning: could not store the explanation of {}: {}", snippet.id, e);
+    }
+}
+
+/// Print the stored explanations of the snippets in `files`, flagging those
+/// whose snippet changed since it was explained
+fn cmd_doc(files: &[PathBuf], only: Option<&str>, format: &str) {
+    let format: ExplainFormat = format.parse().unwrap_or_default();
+    if !Path::new(EXPLANATION_STORE).exists() {
+        eprintln!("No stored explanations; run `covenant explain` first");
+        std::process::exit(1);
+    }
+    let store = open_explanation_store();
+
+    let mut shown = 0;
+    for file in files {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+        };
+        let snippets = match parse(&source) {
+            Ok(Program::Snippets { snippets, .. }) => snippets,
+            Ok(Program::Legacy { .. }) => {
+                eprintln!("Skipping {}: legacy declaration syntax", file.display());
+                continue;
+            }
+            Err(e) => {
+                report_parse_error(&source, file, &e);
+                std::process::exit(1);
+            }
+        };
+
+        for snippet in snippets.iter().filter(|snippet| only.is_none_or(|id| snippet.id == id)) {
+            shown += 1;
+            let stored = match store.get_explanation(&snippet.id) {
+                Ok(Some(stored)) => stored,
+                Ok(None) => {
+                    eprintln!("{}: not explained yet; run `covenant explain {}`", snippet.id, file.display());
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("Error reading the explanation of {}: {}", snippet.id, e);
+                    continue;
+                }
+            };
+            let explanation: Explanation = match serde_json::from_str(&stored.explanation) {
+                Ok(explanation) => explanation,
+                Err(e) => {
+                    eprintln!("Error reading the explanation of {}: {}", snippet.id, e);
+                    continue;
+                }
+            };
+            if stored.is_stale(&snippet.content_hash()) {
+                eprintln!(
+                    "{}: stale, the snippet changed since {} explained it; run `covenant explain {}` to refresh",
+                    snippet.id,
+                    stored.model,
+                    file.display()
+                );
+            }
+            println!("{}", format_explanation(&explanation, format));
+        }
+    }
+
+    if let (Some(id), 0) = (only, shown) {
+        eprintln!("Error: no snippet {} in the given files", id);
+        std::process::exit(1);
+    }
+}
+
+fn cmd_repl() {
+    use rustyline::DefaultEditor;
+
+    println!("Covenant REPL v0.1.0");
+    println!("Type :help for help, :quit to exit");
+    println!();
+
+    let mut rl = DefaultEditor::new().expect("Failed to create REPL");
+    let mut loaded_source = String::new();
+
+    loop {
+        let readline = rl.readline("covenant> ");
+        match readline {
+            Ok(line) => {
+                let _ = rl.add_history_entry(&line);
+                let trimmed = line.trim();
+
+                if trimmed.starts_with(':') {
+                    match trimmed {
+                        ":quit" | ":q" => break,
+                        ":help" | ":h" => {
+                            println!("Commands:");
+                            println!("  :load <file>  - Load a file");
+                            println!("  :parse        - Show parsed AST");
+                            println!("  :check        - Type check loaded code");
+                            println!("  :info         - Show info about loaded code");
+                            println!("  :clear        - Clear loaded code");
+                            println!("  :quit         - Exit REPL");
+                        }
+                        cmd if cmd.starts_with(":load ") => {
+                            let path = &cmd[6..].trim();
+                            match fs::read_to_string(path) {
+                                Ok(s) => {
+                                    loaded_source = s;
+                                    println!("Loaded {}", path);
+                                }
+                                Err(e) => {
+                                    eprintln!("Error: {}", e);
+                                }
+                            }
+                        }
                         ":parse" => {
                             if loaded_source.is_empty() {
                                 println!("No code loaded. Use :load <file>");
                             } else {
                                 match parse(&loaded_source) {
                                     Ok(program) => {
-                                        println!("{}", serde_json::to_string_pretty(&program).unwrap());
+                                        println!("{}", program.to_json_pretty());
                                     }
                                     Err(e) => {
                                         eprintln!("Parse error: {}", e);
@@ -1032,18 +2208,82 @@ fn cmd_repl() {
     println!("Goodbye!");
 }
 
-fn command_exists(cmd: &str) -> bool {
-    std::process::Command::new(cmd)
-        .arg("--version")
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .status()
-        .is_ok()
-}
+fn cmd_run(
+    file: &PathBuf,
+    opt_level: u8,
+    audit: Option<&Path>,
+    seed: Option<u32>,
+    otlp: Option<&str>,
+    runner: Option<&str>,
+) {
+    let runner: Option<Runner> = match runner.map(str::parse).transpose() {
+        Ok(runner) => runner,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let (program, wasm) = compile_for_run(file, opt_level);
+
+    // Write to temp file
+    let temp_wasm = std::env::temp_dir().join("covenant_run.wasm");
+    if let Err(e) = fs::write(&temp_wasm, &wasm) {
+        eprintln!("Error writing temp file: {}", e);
+        std::process::exit(1);
+    }
+
+    // A workflow entry point runs with its checkpoints kept in storage
+    let workflow_run = entry_workflow(&program).map(|workflow| {
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        WorkflowRun {
+            id: format!("run-{:x}", millis),
+            workflow,
+            source_file: fs::canonicalize(file).unwrap_or_else(|_| file.clone()).display().to_string(),
+            status: RunStatus::Running,
+            checkpoints: Vec::new(),
+            error: None,
+        }
+    });
 
-fn cmd_run(file: &PathBuf, opt_level: u8) {
-    use std::process::Command;
+    let mut writes: Vec<PathBuf> = audit.map(Path::to_path_buf).into_iter().collect();
+    if let Some(run) = &workflow_run {
+        writes.push(journal_path(&run.id));
+    }
+    let mut permissions = runner::deno_permissions(&program, &temp_wasm, &writes);
+    if let Some(otlp) = otlp {
+        runner::allow_otlp_endpoint(&mut permissions, otlp);
+    }
+    let (runner, mut command) = runner::runner_command(&temp_wasm, runner, &permissions);
+    if let Some(audit) = audit {
+        command.arg(format!("--audit={}", audit.display()));
+    }
+    if let Some(seed) = seed {
+        command.arg(format!("--seed={}", seed));
+    }
+    if let Some(otlp) = otlp {
+        command.arg(format!("--otlp={}", otlp));
+    }
 
+    let status = match workflow_run {
+        Some(mut run) => {
+            let mut store = open_workflow_store();
+            eprintln!("Workflow run {} ({})", run.id, run.workflow);
+            run_workflow(&mut store, &mut run, command)
+        }
+        None => command.status(),
+    };
+
+    // Clean up temp file
+    let _ = fs::remove_file(&temp_wasm);
+
+    runner::exit_on_runner_failure(runner, status);
+}
+
+/// Parse, check, optimize, and compile a file for `run`, exiting on errors
+fn compile_for_run(file: &PathBuf, opt_level: u8) -> (Program, Vec<u8>) {
     // Map optimization level
     let opt_level = match opt_level {
         0 => OptLevel::O0,
@@ -1060,13 +2300,15 @@ fn cmd_run(file: &PathBuf, opt_level: u8) {
         }
     };
 
-    let mut program = match parse(&source) {
+    let program = match parse(&source) {
         Ok(p) => p,
         Err(e) => {
             report_parse_error(&source, file, &e);
             std::process::exit(1);
         }
     };
+    // The optimizer works on core steps
+    let mut program = covenant_lower::lower(&program).into_owned();
 
     let result = match check(&program) {
         Ok(r) => r,
@@ -1087,15 +2329,9 @@ fn cmd_run(file: &PathBuf, opt_level: u8) {
         };
 
         if let covenant_ast::Program::Snippets { ref mut snippets, .. } = program {
-            for snippet in snippets.iter_mut() {
-                for section in snippet.sections.iter_mut() {
-                    if let covenant_ast::Section::Body(ref mut body) = section {
-                        let opt_result = optimize(&mut body.steps, &settings);
-                        for warning in &opt_result.warnings {
-                            eprintln!("{}: {}", warning.code, warning.message);
-                        }
-                    }
-                }
+            let opt_result = optimize_program(snippets, &settings);
+            for warning in &opt_result.warnings {
+                eprintln!("{}: {}", warning.code, warning.message);
             }
         }
     }
@@ -1109,89 +2345,664 @@ fn cmd_run(file: &PathBuf, opt_level: u8) {
         }
     };
 
-    // Write to temp file
-    let temp_wasm = std::env::temp_dir().join("covenant_run.wasm");
-    if let Err(e) = fs::write(&temp_wasm, &wasm) {
+    (program, wasm)
+}
+
+// ===== Scheduled functions =====
+
+fn cmd_serve(file: &PathBuf, port: u16, opt_level: u8, otlp: Option<&str>) {
+    use std::sync::{Arc, Mutex};
+
+    // Checking validates every schedule before anything runs
+    let (program, wasm) = compile_for_run(file, opt_level);
+    let functions = serve::scheduled_functions(&program, serve::unix_now());
+    if functions.is_empty() {
+        eprintln!("No scheduled functions in {}", file.display());
+        eprintln!("Add `trigger schedule=\"<cron>\"` to a fn snippet's metadata section");
+        std::process::exit(1);
+    }
+
+    let wasm_path = std::env::temp_dir().join(format!("covenant_serve_{}.wasm", std::process::id()));
+    if let Err(e) = fs::write(&wasm_path, &wasm) {
         eprintln!("Error writing temp file: {}", e);
         std::process::exit(1);
     }
 
-    // Find the runner script
-    // First try relative to executable, then relative to current directory
-    let exe_dir = std::env::current_exe()
-        .ok()
-        .and_then(|p| p.parent().map(|p| p.to_path_buf()));
-
-    // Try Deno runner first, fall back to Node.js
-    let deno_runner_paths = [
-        exe_dir.as_ref().map(|d| d.join("../../runtime/host/run.deno.ts")).unwrap_or_default(),
-        PathBuf::from("runtime/host/run.deno.ts"),
-        PathBuf::from("../runtime/host/run.deno.ts"),
-    ];
-
-    let node_runner_paths = [
-        exe_dir.as_ref().map(|d| d.join("../../runtime/host/run.mjs")).unwrap_or_default(),
-        PathBuf::from("runtime/host/run.mjs"),
-        PathBuf::from("../runtime/host/run.mjs"),
-    ];
-
-    let deno_runner = deno_runner_paths.iter().find(|p| p.exists()).cloned();
-    let node_runner = node_runner_paths.iter().find(|p| p.exists()).cloned();
-
-    let (runtime, runner) = if let Some(r) = deno_runner {
-        if command_exists("deno") {
-            ("deno", r)
-        } else if let Some(nr) = node_runner.clone() {
-            ("node", nr)
-        } else {
-            eprintln!("Error: Deno is not installed and run.mjs was not found");
-            eprintln!("Install Deno (https://deno.land) or ensure run.mjs exists");
+    let listener = match std::net::TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Error binding status endpoint to port {}: {}", port, e);
+            std::process::exit(1);
+        }
+    };
+
+    for f in &functions {
+        let next = f.next_run.map(covenant_checker::format_utc).unwrap_or_else(|| "never".to_string());
+        eprintln!("  {} ({}) schedule \"{}\", next run {}", f.snippet, f.function, f.schedule, next);
+    }
+    eprintln!("Status at http://127.0.0.1:{}/status, metrics at /metrics", port);
+
+    let functions = Arc::new(Mutex::new(functions));
+    let status_functions = Arc::clone(&functions);
+    std::thread::spawn(move || serve::serve_status(listener, status_functions));
+    let audit_path = std::env::temp_dir().join(format!("covenant_serve_{}.audit.jsonl", std::process::id()));
+    let mut permissions = runner::deno_permissions(&program, &wasm_path, std::slice::from_ref(&audit_path));
+    if let Some(otlp) = otlp {
+        runner::allow_otlp_endpoint(&mut permissions, otlp);
+    }
+    serve::run_scheduler(&wasm_path, &permissions, &audit_path, otlp, &functions);
+}
+
+// ===== Workflows =====
+
+/// Directory holding the workflow run store and checkpoint journals
+const WORKFLOW_DIR: &str = ".covenant/workflows";
+
+/// The snippet ID of the entry point if it is a workflow
+fn entry_workflow(program: &Program) -> Option<String> {
+    let Program::Snippets { snippets, .. } = program else {
+        return None;
+    };
+    snippets
+        .iter()
+        .filter(|s| s.kind == covenant_ast::SnippetKind::Workflow)
+        .find(|s| {
+            s.sections.iter().any(|section| matches!(
+                section,
+                covenant_ast::Section::Signature(sig)
+                    if matches!(&sig.kind, covenant_ast::SignatureKind::Function(f) if f.name == "main")
+            ))
+        })
+        .map(|s| s.id.clone())
+}
+
+fn open_workflow_store() -> RedbStorage {
+    match RedbStorage::new(Path::new(WORKFLOW_DIR).join("runs.redb")) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Error opening workflow store: {}", e);
             std::process::exit(1);
         }
-    } else if let Some(r) = node_runner {
-        ("node", r)
+    }
+}
+
+fn journal_path(run: &str) -> PathBuf {
+    Path::new(WORKFLOW_DIR).join(format!("{}.jsonl", run))
+}
+
+/// Run a workflow with its checkpoints replayed, storing each new checkpoint
+/// as the runner appends it to the run's journal
+fn run_workflow(
+    store: &mut RedbStorage,
+    run: &mut WorkflowRun,
+    mut command: std::process::Command,
+) -> std::io::Result<std::process::ExitStatus> {
+    let journal = journal_path(&run.id);
+
+    // Pick up checkpoints a crashed CLI journaled but never stored
+    import_checkpoints(store, run, &journal);
+
+    // Seed the journal so the runner replays the stored checkpoints
+    let mut seeded = String::new();
+    for checkpoint in &run.checkpoints {
+        match checkpoint.to_journal_line() {
+            Ok(line) => {
+                seeded.push_str(&line);
+                seeded.push('\n');
+            }
+            Err(e) => return Err(std::io::Error::other(e.to_string())),
+        }
+    }
+    fs::write(&journal, seeded)?;
+    command.arg(format!("--journal={}", journal.display()));
+
+    run.status = RunStatus::Running;
+    run.error = None;
+    save_run(store, run);
+
+    let mut child = command.spawn()?;
+    let status = loop {
+        let exited = child.try_wait();
+        import_checkpoints(store, run, &journal);
+        match exited {
+            Ok(Some(status)) => break status,
+            Ok(None) => std::thread::sleep(std::time::Duration::from_millis(50)),
+            Err(e) => return Err(e),
+        }
+    };
+
+    if status.success() {
+        run.status = RunStatus::Completed;
+        let _ = fs::remove_file(&journal);
     } else {
-        eprintln!("Error: Could not find runtime/host/run.deno.ts or runtime/host/run.mjs");
-        eprintln!("Make sure you're running from the covenant project directory");
+        run.status = RunStatus::Failed;
+        run.error = Some(format!("runner exited with {}", status));
+        eprintln!("Workflow run {} failed; resume it with `covenant workflows resume {}`", run.id, run.id);
+    }
+    save_run(store, run);
+    Ok(status)
+}
+
+/// Store the complete journal lines past the run's stored checkpoints
+fn import_checkpoints(store: &mut RedbStorage, run: &mut WorkflowRun, journal: &Path) {
+    let Ok(text) = fs::read_to_string(journal) else {
+        return;
+    };
+    // The last line may still be being written
+    let complete = &text[..text.rfind('\n').map_or(0, |i| i + 1)];
+    for line in complete.lines().skip(run.checkpoints.len()) {
+        let checkpoint = match Checkpoint::from_journal_line(line) {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                eprintln!("Error reading checkpoint journal {}: {}", journal.display(), e);
+                return;
+            }
+        };
+        if let Err(e) = store.append_checkpoint(&run.id, checkpoint.clone()) {
+            eprintln!("Error storing checkpoint: {}", e);
+            std::process::exit(1);
+        }
+        run.checkpoints.push(checkpoint);
+    }
+}
+
+fn save_run(store: &mut RedbStorage, run: &WorkflowRun) {
+    if let Err(e) = store.put_run(run) {
+        eprintln!("Error storing workflow run {}: {}", run.id, e);
         std::process::exit(1);
+    }
+}
+
+fn cmd_workflows_list() {
+    let store = open_workflow_store();
+    let runs = match store.list_runs() {
+        Ok(runs) => runs,
+        Err(e) => {
+            eprintln!("Error reading workflow runs: {}", e);
+            std::process::exit(1);
+        }
     };
 
-    // Run with detected runtime
-    let status = if runtime == "deno" {
-        Command::new("deno")
-            .arg("run")
-            .arg("--allow-read")
-            .arg("--allow-write")
-            .arg(&runner)
-            .arg(&temp_wasm)
-            .status()
-    } else {
-        Command::new("node")
-            .arg(&runner)
-            .arg(&temp_wasm)
-            .status()
+    if runs.is_empty() {
+        println!("No workflow runs");
+        return;
+    }
+
+    for run in &runs {
+        let last_step = run.checkpoints.last()
+            .and_then(|c| c.step.as_deref())
+            .map(|step| format!(", last step {}", step))
+            .unwrap_or_default();
+        println!(
+            "{}  {}  {}  {} checkpoint(s){}",
+            run.id, run.workflow, run.status, run.checkpoints.len(), last_step
+        );
+        if let Some(error) = &run.error {
+            println!("    {}", error);
+        }
+    }
+}
+
+fn cmd_workflows_resume(id: &str, opt_level: u8) {
+    let mut store = open_workflow_store();
+    let mut run = match store.get_run(id) {
+        Ok(Some(run)) => run,
+        Ok(None) => {
+            eprintln!("No workflow run '{}'", id);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error reading workflow run '{}': {}", id, e);
+            std::process::exit(1);
+        }
     };
+    if run.status == RunStatus::Completed {
+        eprintln!("Workflow run '{}' has already completed", id);
+        std::process::exit(1);
+    }
 
-    // Clean up temp file
+    let file = PathBuf::from(&run.source_file);
+    let (program, wasm) = compile_for_run(&file, opt_level);
+    if entry_workflow(&program).as_deref() != Some(run.workflow.as_str()) {
+        eprintln!("{} no longer has workflow '{}' as its entry point", file.display(), run.workflow);
+        std::process::exit(1);
+    }
+
+    let temp_wasm = std::env::temp_dir().join("covenant_run.wasm");
+    if let Err(e) = fs::write(&temp_wasm, &wasm) {
+        eprintln!("Error writing temp file: {}", e);
+        std::process::exit(1);
+    }
+
+    eprintln!("Resuming workflow run {} ({}) after {} checkpoint(s)", run.id, run.workflow, run.checkpoints.len());
+    let permissions = runner::deno_permissions(&program, &temp_wasm, &[journal_path(&run.id)]);
+    let (runner, command) = runner::runner_command(&temp_wasm, None, &permissions);
+    let status = run_workflow(&mut store, &mut run, command);
     let _ = fs::remove_file(&temp_wasm);
 
-    match status {
-        Ok(s) if s.success() => {}
-        Ok(s) => {
-            std::process::exit(s.code().unwrap_or(1));
+    runner::exit_on_runner_failure(runner, status);
+}
+
+/// Print the call paths from `from` to `to` over one graph of every file,
+/// only the `k` shortest when given
+///
+/// A `ReferenceGraph` (and its `call_paths`) covers a single checked
+/// program, so paths crossing files are found over the project's
+/// `SymbolGraph` instead, the same way `query "paths from X to Y"` does.
+fn cmd_path(from: &str, to: &str, files: &[PathBuf], k: Option<usize>) {
+    let query = PathsQuery { from: from.to_string(), to: to.to_string(), max_depth: None, shortest: k == Some(1) };
+    query_paths(files, &query, None, k);
+}
+
+fn cmd_modules(files: &[PathBuf], deny_cycles: bool) {
+    let graph = load_symbol_graph(files);
+    let modules = graph.module_graph();
+
+    for (layer, members) in modules.layers().iter().enumerate() {
+        println!("Layer {}:", layer);
+        for module in members {
+            println!(
+                "  {} ({} symbols, fan-in {}, fan-out {})",
+                module.name,
+                module.symbols.len(),
+                module.fan_in(),
+                module.fan_out()
+            );
+            for (dep, count) in &module.edge_counts {
+                println!("    -> {} ({} edges)", dep, count);
+            }
+        }
+        println!();
+    }
+
+    if modules.is_acyclic() {
+        println!("No module-level cycles");
+    } else {
+        eprintln!("Module-level cycles:");
+        for cycle in &modules.cycles {
+            eprintln!("  {}", cycle.join(" <-> "));
+        }
+        if deny_cycles {
+            std::process::exit(1);
+        }
+    }
+}
+
+fn cmd_graph(files: &[PathBuf], format: &str, module: Option<String>, edges: &[String], depth: usize) {
+    let mut edge_kinds = Vec::new();
+    for name in edges {
+        match ReferenceKind::from_name(name) {
+            Some(kind) => edge_kinds.push(kind),
+            None => {
+                let known: Vec<&str> = ReferenceKind::ALL.iter().map(|kind| kind.as_str()).collect();
+                eprintln!("Unknown edge kind '{}' (expected one of: {})", name, known.join(", "));
+                std::process::exit(1);
+            }
+        }
+    }
+    let options = DiagramOptions { module_prefix: module, edge_kinds, depth };
+
+    let graph = load_symbol_graph(files);
+    match format {
+        "dot" => print!("{}", graph.to_dot(&options)),
+        "mermaid" => print!("{}", graph.to_mermaid(&options)),
+        "json-ld" => println!("{}", graph.to_json_ld(&options)),
+        "cypher" => print!("{}", graph.to_cypher(&options)),
+        _ => {
+            eprintln!("Unknown format '{}' (expected dot, mermaid, json-ld or cypher)", format);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn cmd_watch(files: &[PathBuf], interval_ms: u64) {
+    use std::io::Write;
+    use std::time::{Duration, SystemTime};
+
+    let modified = |file: &PathBuf| -> Option<SystemTime> {
+        fs::metadata(file).and_then(|m| m.modified()).ok()
+    };
+
+    let mut stamps: Vec<Option<SystemTime>> = files.iter().map(modified).collect();
+    let mut previous: Vec<covenant_ast::Snippet> = Vec::new();
+    let mut graph: Option<SymbolGraph> = None;
+    let mut deferred: Vec<SymbolError> = Vec::new();
+    let mut first_run = true;
+
+    println!("Watching {} file(s). Press Ctrl+C to stop.", files.len());
+
+    loop {
+        if !first_run {
+            std::thread::sleep(Duration::from_millis(interval_ms));
+            let now: Vec<_> = files.iter().map(modified).collect();
+            if now == stamps {
+                continue;
+            }
+            stamps = now;
+        }
+
+        // Re-parse every watched file; parse errors block this round
+        let mut snippets = Vec::new();
+        let mut parse_ok = true;
+        for file in files {
+            let Ok(source) = fs::read_to_string(file) else {
+                eprintln!("Error reading {}", file.display());
+                parse_ok = false;
+                continue;
+            };
+            match parse(&source) {
+                Ok(covenant_ast::Program::Snippets { snippets: parsed, .. }) => snippets.extend(parsed),
+                Ok(covenant_ast::Program::Legacy { .. }) => {}
+                Err(e) => {
+                    report_parse_error(&source, file, &e);
+                    parse_ok = false;
+                }
+            }
+        }
+        if !parse_ok {
+            first_run = false;
+            continue;
+        }
+
+        let changed = if first_run {
+            snippets.iter().map(|s| s.id.clone()).collect()
+        } else {
+            changed_snippets(&previous, &snippets)
+        };
+        first_run = false;
+        if changed.is_empty() {
+            continue;
+        }
+
+        // Update the graph in place; rebuild it on the first round or when
+        // the update reports an error, so the errors are reported in full
+        let updated = graph.as_mut().and_then(|graph| {
+            let old: BTreeSet<&str> = previous.iter().map(|s| s.id.as_str()).collect();
+            let new: BTreeSet<&str> = snippets.iter().map(|s| s.id.as_str()).collect();
+            let (modified, added): (Vec<_>, Vec<_>) = snippets
+                .iter()
+                .filter(|s| changed.contains(&s.id))
+                .cloned()
+                .partition(|s| old.contains(s.id.as_str()));
+            let removed: Vec<_> = changed
+                .iter()
+                .filter(|id| !new.contains(id.as_str()))
+                .filter_map(|id| graph.id_of(id))
+                .collect();
+            graph.apply_change(&added, &removed, &modified).ok()
+        });
+        match updated {
+            Some(result) => {
+                deferred.retain(|err| match err {
+                    SymbolError::UndefinedReference { referrer, .. } => {
+                        !changed.contains(referrer) && result.resolved.binary_search(referrer).is_err()
+                    }
+                    _ => true,
+                });
+                deferred.extend(result.deferred_errors);
+            }
+            None => match build_from_snippets(&snippets) {
+                Ok(result) => {
+                    graph = Some(result.graph);
+                    deferred = result.deferred_errors;
+                }
+                Err(errors) => {
+                    for err in &errors {
+                        eprintln!("  {}: {}", err.code(), err);
+                    }
+                    graph = None;
+                    previous = snippets;
+                    continue;
+                }
+            },
+        }
+        let Some(graph) = graph.as_ref() else { continue };
+        let plan = graph.recheck_plan(&changed);
+
+        println!("-- {} changed, {} to re-check", changed.len(), plan.len());
+        for (distance, batch) in plan.batches.iter().enumerate() {
+            if batch.is_empty() {
+                continue;
+            }
+
+            let mut problems = 0;
+            for err in &deferred {
+                if let SymbolError::UndefinedReference { referrer, .. } = err {
+                    if batch.contains(referrer) {
+                        println!("  warning [{}]: {}", referrer, err);
+                        problems += 1;
+                    }
+                }
+            }
+            for violation in &check_effects_for(graph, batch).violations {
+                // Snippets come from several files, so only byte offsets are known
+                report_effect_error_concise(violation, None);
+                problems += 1;
+            }
+
+            let label = if distance == 0 { "changed".to_string() } else { format!("dependents (distance {})", distance) };
+            println!("  {} {}: {} snippet(s), {} problem(s)", if problems == 0 { "✓" } else { "✗" }, label, batch.len(), problems);
+            let _ = std::io::stdout().flush();
+        }
+
+        // Whole-program type check last, once the targeted feedback is out
+        let program = covenant_ast::Program::Snippets { snippets: snippets.clone(), span: covenant_ast::Span::dummy() };
+        match check(&program) {
+            Ok(_) => println!("  ✓ type check"),
+            Err(errors) => {
+                println!("  ✗ type check: {} error(s)", errors.len());
+                for err in errors {
+                    println!("    {}", err);
+                }
+            }
+        }
+        let _ = std::io::stdout().flush();
+
+        previous = snippets;
+    }
+}
+
+/// Members of `module` across all input files, exiting if it is not a
+/// module snippet
+fn module_scope(files: &[PathBuf], module: Option<&str>) -> Option<BTreeSet<String>> {
+    let module = module?;
+    match load_symbol_graph(files).module_members(module) {
+        Some(members) => Some(members),
+        None => {
+            eprintln!("'{}' is not a module snippet", module);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Read the input files as a project, exiting if a file or `covenant.json`
+/// can't be read
+fn load_project(files: &[PathBuf]) -> Project {
+    match Project::load(files) {
+        Ok(project) => project,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Directory, under the project root, holding symbol graphs cached in the
+/// binary form
+const GRAPH_CACHE_DIR: &str = ".covenant/cache";
+
+/// Cached graphs kept per project; older ones are pruned
+const GRAPH_CACHE_ENTRIES: usize = 8;
+
+/// Parse all files and build a single symbol graph over their snippets
+///
+/// Inside a project (a directory with `covenant.json`), graphs are cached
+/// under its root by the files' paths and contents, the config and the
+/// compiler version, so repeated queries over an unchanged project skip
+/// parsing. An unreadable cache entry is rebuilt.
+fn load_symbol_graph(files: &[PathBuf]) -> covenant_symbols::SymbolGraph {
+    let cache_path = graph_cache_path(files);
+    let cached = cache_path
+        .as_ref()
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|bytes| covenant_symbols::SymbolGraph::from_binary(&bytes).ok());
+    if let Some(graph) = cached {
+        return graph;
+    }
+
+    let project = load_project(files);
+    match project.graph() {
+        Ok(graph) => {
+            let graph = graph.into_symbol_graph();
+            if let Some(path) = &cache_path {
+                // Caching is best effort; a failed write only costs a rebuild
+                let written = path.parent().map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::write(path, graph.to_binary()));
+                if written.is_ok() {
+                    prune_graph_cache(path);
+                }
+            }
+            graph
+        }
+        Err(DriverError::Diagnostics(diagnostics)) => {
+            for diagnostic in &diagnostics {
+                let source = project
+                    .files()
+                    .iter()
+                    .find(|f| diagnostic.file.as_ref() == Some(&f.path))
+                    .map_or("", |f| f.source.as_str());
+                report_diagnostic(source, diagnostic);
+            }
+            std::process::exit(1);
         }
         Err(e) => {
-            eprintln!("Error running {}: {}", runtime, e);
-            eprintln!("Make sure {} is installed and in your PATH",
-                if runtime == "deno" { "Deno" } else { "Node.js" });
+            eprintln!("{}", e);
             std::process::exit(1);
         }
     }
 }
 
+/// Cache file for the graph over `files`, or None outside a project or
+/// when a file cannot be read
+fn graph_cache_path(files: &[PathBuf]) -> Option<PathBuf> {
+    use sha2::{Digest, Sha256};
+
+    let config_path = find_project_config_path(files.first()?)?;
+    let config = fs::read(&config_path).ok()?;
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+    hasher.update((config.len() as u64).to_le_bytes());
+    hasher.update(&config);
+    for file in files {
+        let source = fs::read(file).ok()?;
+        hasher.update(file.to_string_lossy().as_bytes());
+        hasher.update((source.len() as u64).to_le_bytes());
+        hasher.update(&source);
+    }
+    let root = config_path.parent()?;
+    Some(root.join(GRAPH_CACHE_DIR).join(format!("graph-{:x}.bin", hasher.finalize())))
+}
+
+/// Remove all but the newest [`GRAPH_CACHE_ENTRIES`] graphs in the cache
+/// holding `latest`
+fn prune_graph_cache(latest: &Path) {
+    let Some(Ok(entries)) = latest.parent().map(fs::read_dir) else {
+        return;
+    };
+    let mut graphs: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("graph-"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    graphs.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    for (_, path) in graphs.into_iter().skip(GRAPH_CACHE_ENTRIES) {
+        if path != latest {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn cmd_sym_find(pattern: &str, files: &[PathBuf], limit: usize) {
+    let graph = load_symbol_graph(files);
+    let matches = graph.search(pattern);
+
+    if matches.is_empty() {
+        println!("No symbols match '{}'", pattern);
+        return;
+    }
+
+    for m in matches.iter().take(limit) {
+        println!("  {} ({:?}) - {:?} [{}]", m.name, m.kind, m.match_kind, m.score);
+    }
+    if matches.len() > limit {
+        println!("  ... and {} more", matches.len() - limit);
+    }
+}
+
+fn cmd_sym_uses(type_name: &str, files: &[PathBuf]) {
+    let graph = load_symbol_graph(files);
+
+    let Some(ty) = graph.resolve_type(type_name).and_then(|id| graph.get(id)) else {
+        eprintln!("Unknown type '{}'", type_name);
+        std::process::exit(1);
+    };
+
+    println!("Type: {}", ty.name);
+
+    let embeds = graph.embedded_types_of(&ty.name);
+    if !embeds.is_empty() {
+        println!();
+        println!("Embeds:");
+        for s in embeds {
+            println!("  {}", s.name);
+        }
+    }
+
+    let embedded_by = graph.types_embedding(&ty.name);
+    println!();
+    println!("Embedded by:");
+    if embedded_by.is_empty() {
+        println!("  (none)");
+    }
+    for s in embedded_by {
+        println!("  {}", s.name);
+    }
+
+    let referencing = graph.functions_referencing(&ty.name);
+    println!();
+    println!("Referenced by functions:");
+    if referencing.is_empty() {
+        println!("  (none)");
+    }
+    for s in referencing {
+        println!("  {}", s.name);
+    }
+}
+
+fn cmd_sym_type_order(files: &[PathBuf]) {
+    let graph = load_symbol_graph(files);
+    let order = graph.type_dependency_order();
+
+    for (i, name) in order.order.iter().enumerate() {
+        println!("{:>4}. {}", i + 1, name);
+    }
+
+    if !order.cyclic.is_empty() {
+        println!();
+        println!("Recursive (cyclic) types:");
+        for name in &order.cyclic {
+            println!("  {}", name);
+        }
+    }
+}
+
 fn report_parse_error(source: &str, file: &PathBuf, error: &covenant_parser::ParseError) {
     let span = error.span();
-    Report::build(ReportKind::Error, file.to_string_lossy().to_string(), span.start)
+    let mut report = Report::build(ReportKind::Error, file.to_string_lossy().to_string(), span.start);
+    if let Some(code) = error.code() {
+        report = report.with_code(code);
+    }
+    report
         .with_message(error.to_string())
         .with_label(
             Label::new((file.to_string_lossy().to_string(), span.start..span.end))
@@ -1202,3 +3013,30 @@ fn report_parse_error(source: &str, file: &PathBuf, error: &covenant_parser::Par
         .eprint((file.to_string_lossy().to_string(), Source::from(source)))
         .unwrap();
 }
+
+/// Render a driver diagnostic, pointing into `source` when it has a span
+fn report_diagnostic(source: &str, diagnostic: &covenant_driver::Diagnostic) {
+    let (Some(file), Some(span)) = (&diagnostic.file, diagnostic.span) else {
+        eprintln!("{}", diagnostic);
+        return;
+    };
+    let file = file.to_string_lossy().to_string();
+    let kind = if diagnostic.is_error() { ReportKind::Error } else { ReportKind::Warning };
+    let mut report = Report::build(kind, file.clone(), span.start);
+    if let Some(code) = &diagnostic.code {
+        report = report.with_code(code);
+    }
+    if let Some(help) = &diagnostic.help {
+        report = report.with_help(help);
+    }
+    report
+        .with_message(&diagnostic.message)
+        .with_label(
+            Label::new((file.clone(), span.start..span.end))
+                .with_message(&diagnostic.message)
+                .with_color(Color::Red),
+        )
+        .finish()
+        .eprint((file, Source::from(source)))
+        .unwrap();
+}
@@ -0,0 +1,312 @@
+//! Host runners for `covenant run`
+//!
+//! A compiled module runs under Deno (`runtime/host/run.deno.ts`) or under
+//! Node.js or Bun (`runtime/host/run.mjs`). Without `--runner`, Deno is used
+//! when installed and Node.js otherwise.
+//!
+//! Deno runs with only the permissions the program's declared effects call
+//! for, so the runtime enforces the same capabilities the checker does:
+//! `filesystem` grants reads and writes (scoped to its `path` when given),
+//! `network`, `http_server` and `database` grant network access, `process`
+//! grants subprocesses, and `os` grants environment and system info. The
+//! runner itself may always read the module and write the audit log and
+//! workflow journal, and reach the OTLP collector it exports traces to.
+//! Node.js and Bun have no permission flags, so under them a program can
+//! reach anything the runner provides.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::str::FromStr;
+
+use covenant_ast::{Literal, Program, Section};
+
+/// JavaScript runtime a module runs under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runner {
+    Deno,
+    Node,
+    Bun,
+}
+
+impl Runner {
+    pub const ALL: [Runner; 3] = [Runner::Deno, Runner::Node, Runner::Bun];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Runner::Deno => "deno",
+            Runner::Node => "node",
+            Runner::Bun => "bun",
+        }
+    }
+
+    /// The runtime's name in messages
+    fn display_name(self) -> &'static str {
+        match self {
+            Runner::Deno => "Deno",
+            Runner::Node => "Node.js",
+            Runner::Bun => "Bun",
+        }
+    }
+
+    /// The runner script under `runtime/host`
+    fn script(self) -> &'static str {
+        match self {
+            Runner::Deno => "run.deno.ts",
+            Runner::Node | Runner::Bun => "run.mjs",
+        }
+    }
+}
+
+impl fmt::Display for Runner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Runner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Runner::ALL
+            .into_iter()
+            .find(|r| r.as_str() == s)
+            .ok_or_else(|| format!("Invalid runner '{}'. Valid runners: deno, node, bun", s))
+    }
+}
+
+fn command_exists(cmd: &str) -> bool {
+    Command::new(cmd)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+/// Find a runner script, relative to the executable or the current directory
+fn find_script(name: &str) -> Option<PathBuf> {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()));
+
+    let paths = [
+        exe_dir.map(|d| d.join("../../runtime/host").join(name)).unwrap_or_default(),
+        Path::new("runtime/host").join(name),
+        Path::new("../runtime/host").join(name),
+    ];
+    paths.into_iter().find(|p| p.exists())
+}
+
+/// The directory holding the Deno runner script and its modules
+pub fn deno_host_dir() -> Option<PathBuf> {
+    find_script(Runner::Deno.script()).and_then(|script| script.parent().map(Path::to_path_buf))
+}
+
+/// The runner to use and its script, exiting when neither can be found
+fn resolve(requested: Option<Runner>) -> (Runner, PathBuf) {
+    if let Some(runner) = requested {
+        return match find_script(runner.script()) {
+            Some(script) => (runner, script),
+            None => {
+                eprintln!("Error: Could not find runtime/host/{}", runner.script());
+                eprintln!("Make sure you're running from the covenant project directory");
+                std::process::exit(1);
+            }
+        };
+    }
+
+    // Try Deno first, fall back to Node.js
+    let deno_runner = find_script(Runner::Deno.script());
+    let node_runner = find_script(Runner::Node.script());
+    match (deno_runner, node_runner) {
+        (Some(script), _) if command_exists("deno") => (Runner::Deno, script),
+        (_, Some(script)) => (Runner::Node, script),
+        (Some(_), None) => {
+            eprintln!("Error: Deno is not installed and run.mjs was not found");
+            eprintln!("Install Deno (https://deno.land) or ensure run.mjs exists");
+            std::process::exit(1);
+        }
+        (None, None) => {
+            eprintln!("Error: Could not find runtime/host/run.deno.ts or runtime/host/run.mjs");
+            eprintln!("Make sure you're running from the covenant project directory");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The command that runs a compiled module under `requested`, or the
+/// default runner. `deno_flags` are the permissions Deno runs with.
+pub fn runner_command(wasm: &Path, requested: Option<Runner>, deno_flags: &[String]) -> (Runner, Command) {
+    let (runner, script) = resolve(requested);
+    let mut command = Command::new(runner.as_str());
+    if runner == Runner::Deno {
+        command.arg("run").args(deno_flags);
+    }
+    command.arg(&script).arg(wasm);
+    (runner, command)
+}
+
+/// The Deno permission flags for running `program` from `wasm`, where the
+/// runner also writes the files in `writes` (the audit log and journal)
+pub fn deno_permissions(program: &Program, wasm: &Path, writes: &[PathBuf]) -> Vec<String> {
+    let mut reads: BTreeSet<String> = BTreeSet::new();
+    let mut written: BTreeSet<String> = BTreeSet::new();
+    reads.insert(wasm.display().to_string());
+    for path in writes {
+        reads.insert(path.display().to_string());
+        written.insert(path.display().to_string());
+    }
+
+    let mut unscoped_fs = false;
+    let mut grants: BTreeSet<&str> = BTreeSet::new();
+    let snippets = match program {
+        Program::Snippets { snippets, .. } => snippets.as_slice(),
+        Program::Legacy { .. } => &[],
+    };
+    let effects = snippets
+        .iter()
+        .flat_map(|snippet| snippet.sections.iter())
+        .filter_map(|section| match section {
+            Section::Effects(effects) => Some(effects.effects.iter()),
+            _ => None,
+        })
+        .flatten();
+    for effect in effects {
+        match effect.name.as_str() {
+            "filesystem" => match effect.get_param("path").map(|p| &p.value) {
+                Some(Literal::String(path)) => {
+                    reads.insert(path.clone());
+                    written.insert(path.clone());
+                }
+                _ => unscoped_fs = true,
+            },
+            "network" | "http_server" | "database" => {
+                grants.insert("--allow-net");
+            }
+            "process" => {
+                grants.insert("--allow-run");
+            }
+            "os" => {
+                grants.insert("--allow-env");
+                grants.insert("--allow-sys");
+            }
+            _ => {}
+        }
+    }
+
+    let mut flags = Vec::new();
+    if unscoped_fs {
+        flags.push("--allow-read".to_string());
+        flags.push("--allow-write".to_string());
+    } else {
+        flags.push(format!("--allow-read={}", reads.into_iter().collect::<Vec<_>>().join(",")));
+        if !written.is_empty() {
+            flags.push(format!("--allow-write={}", written.into_iter().collect::<Vec<_>>().join(",")));
+        }
+    }
+    flags.extend(grants.into_iter().map(str::to_string));
+    flags
+}
+
+/// Let the runner reach the OTLP collector at `endpoint`, unless the
+/// program's effects already grant network access
+pub fn allow_otlp_endpoint(flags: &mut Vec<String>, endpoint: &str) {
+    if flags.iter().any(|flag| flag == "--allow-net") {
+        return;
+    }
+    let authority = endpoint.split_once("://").map_or(endpoint, |(_, rest)| rest);
+    let host = authority.split('/').next().unwrap_or(authority);
+    flags.push(format!("--allow-net={}", host));
+}
+
+/// Exit with the runner's status unless it succeeded
+pub fn exit_on_runner_failure(runner: Runner, status: std::io::Result<ExitStatus>) {
+    match status {
+        Ok(s) if s.success() => {}
+        Ok(s) => {
+            std::process::exit(s.code().unwrap_or(1));
+        }
+        Err(e) => {
+            eprintln!("Error running {}: {}", runner, e);
+            eprintln!("Make sure {} is installed and in your PATH", runner.display_name());
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(effects: &str) -> Program {
+        let source = format!(
+            r#"
+snippet id="app.main" kind="fn"
+effects
+{}
+end
+signature
+  fn name="main"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+"#,
+            effects
+        );
+        covenant_parser::parse(&source).expect("parses")
+    }
+
+    #[test]
+    fn test_deno_permissions_follow_effects() {
+        let wasm = Path::new("/tmp/app.wasm");
+        let flags = deno_permissions(&program("  effect console"), wasm, &[]);
+        assert_eq!(flags, vec!["--allow-read=/tmp/app.wasm"]);
+
+        let journal = PathBuf::from(".covenant/workflows/run-1.jsonl");
+        let flags = deno_permissions(
+            &program("  effect filesystem(path=\"/data\")\n  effect network\n  effect os"),
+            wasm,
+            &[journal],
+        );
+        assert_eq!(
+            flags,
+            vec![
+                "--allow-read=.covenant/workflows/run-1.jsonl,/data,/tmp/app.wasm",
+                "--allow-write=.covenant/workflows/run-1.jsonl,/data",
+                "--allow-env",
+                "--allow-net",
+                "--allow-sys",
+            ]
+        );
+
+        // An unscoped filesystem effect grants the whole filesystem
+        let flags = deno_permissions(&program("  effect filesystem\n  effect process"), wasm, &[]);
+        assert_eq!(flags, vec!["--allow-read", "--allow-write", "--allow-run"]);
+    }
+
+    #[test]
+    fn test_otlp_endpoint_permission() {
+        let mut flags = vec!["--allow-read=a.wasm".to_string()];
+        allow_otlp_endpoint(&mut flags, "http://localhost:4318/v1/traces");
+        assert_eq!(flags, vec!["--allow-read=a.wasm", "--allow-net=localhost:4318"]);
+
+        let mut flags = vec!["--allow-net".to_string()];
+        allow_otlp_endpoint(&mut flags, "http://collector:4318");
+        assert_eq!(flags, vec!["--allow-net"]);
+    }
+
+    #[test]
+    fn test_runner_names() {
+        assert_eq!("bun".parse::<Runner>(), Ok(Runner::Bun));
+        assert!("python".parse::<Runner>().unwrap_err().contains("deno, node, bun"));
+    }
+}
@@ -0,0 +1,103 @@
+//! Project templates for `covenant new`
+//!
+//! Templates are embedded at build time from `templates/`. `{{name}}`,
+//! `{{module}}` and `{{template}}` placeholders are substituted on write.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Template names accepted by `covenant new --template`
+pub const TEMPLATES: &[&str] = &["service", "cli", "library"];
+
+const COMMON: &[(&str, &str)] = &[
+    ("covenant.json", include_str!("../templates/common/covenant.json")),
+    ("README.md", include_str!("../templates/common/README.md")),
+    (".github/workflows/covenant.yml", include_str!("../templates/common/.github/workflows/covenant.yml")),
+];
+
+const SERVICE: &[(&str, &str)] = &[
+    ("src/main.cov", include_str!("../templates/service/main.cov")),
+];
+
+const CLI: &[(&str, &str)] = &[
+    ("src/main.cov", include_str!("../templates/cli/main.cov")),
+];
+
+const LIBRARY: &[(&str, &str)] = &[
+    ("src/lib.cov", include_str!("../templates/library/lib.cov")),
+];
+
+/// Files (relative path, contents) generated for `template`, or `None` if
+/// the template is unknown
+pub fn render(name: &str, template: &str) -> Option<Vec<(PathBuf, String)>> {
+    let files = match template {
+        "service" => SERVICE,
+        "cli" => CLI,
+        "library" => LIBRARY,
+        _ => return None,
+    };
+    let module = module_name(name);
+    let substitute = |text: &str| {
+        text.replace("{{name}}", name)
+            .replace("{{module}}", &module)
+            .replace("{{template}}", template)
+    };
+
+    Some(COMMON.iter().chain(files)
+        .map(|(path, contents)| (PathBuf::from(substitute(path)), substitute(contents)))
+        .collect())
+}
+
+/// Write the rendered template into a new directory `root`
+///
+/// Refuses to touch an existing directory so a typo can't clobber a project.
+pub fn write(root: &Path, files: &[(PathBuf, String)]) -> io::Result<()> {
+    if root.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", root.display()),
+        ));
+    }
+    for (path, contents) in files {
+        let path = root.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+/// Snippet ID prefix for a project name: `my-app` becomes `my_app`
+fn module_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates_check_cleanly() {
+        for template in TEMPLATES {
+            let files = render("my-app", template).unwrap();
+            assert!(files.iter().any(|(path, _)| path == Path::new("covenant.json")));
+
+            for (path, contents) in files.iter().filter(|(p, _)| p.extension().is_some_and(|e| e == "cov")) {
+                assert!(!contents.contains("{{"), "{}: unsubstituted placeholder", path.display());
+                assert!(contents.contains("my_app."), "{}: module prefix", path.display());
+
+                let program = covenant_parser::parse(contents)
+                    .unwrap_or_else(|e| panic!("{} ({}): {}", path.display(), template, e));
+                covenant_checker::check(&program)
+                    .unwrap_or_else(|e| panic!("{} ({}): {:?}", path.display(), template, e));
+                let graph = covenant_symbols::build_symbol_graph(&program).unwrap().graph;
+                assert!(covenant_checker::check_effects(&graph).violations.is_empty());
+            }
+        }
+        assert!(render("my-app", "web").is_none());
+    }
+}
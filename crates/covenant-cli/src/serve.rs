@@ -0,0 +1,442 @@
+//! Scheduled functions for `covenant serve`
+//!
+//! Functions declaring `trigger schedule="..."` in their metadata are run
+//! through the host runner (`--entry=<fn>`) whenever their cron schedule
+//! comes due, with the effects compiled into the module. A status endpoint
+//! reports each function's last and next run, `/healthz` answers liveness
+//! probes, and `/metrics` exports each function's run counts, latencies,
+//! failures and effectful extern calls in the Prometheus text format. Extern
+//! calls are counted from the audit log the runner writes for each run.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use covenant_ast::printer::ToCov;
+use covenant_ast::{Program, Section, SignatureKind};
+use covenant_checker::{format_utc, snippet_schedule, CronSchedule};
+use serde_json::json;
+
+/// A function run on a schedule
+#[derive(Debug, Clone)]
+pub struct ScheduledFunction {
+    /// Snippet ID
+    pub snippet: String,
+    /// Exported function name passed to the runner
+    pub function: String,
+    /// The cron expression as written
+    pub schedule: String,
+    pub cron: CronSchedule,
+    /// Declared effects, e.g. `filesystem(path="/data")`
+    pub effects: Vec<String>,
+    /// Unix seconds of the next run, if the schedule ever fires again
+    pub next_run: Option<i64>,
+    pub last_run: Option<LastRun>,
+    pub metrics: RunMetrics,
+}
+
+/// The outcome of a function's most recent run
+#[derive(Debug, Clone, PartialEq)]
+pub struct LastRun {
+    pub started: i64,
+    pub finished: i64,
+    pub success: bool,
+    /// Runner exit code; None if it could not be started or was killed
+    pub exit_code: Option<i32>,
+}
+
+/// Upper bounds, in seconds, of the run duration histogram's buckets
+pub const LATENCY_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Counters over every run of a function since `serve` started
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunMetrics {
+    pub runs: u64,
+    pub failures: u64,
+    /// Runs that took at most each of [`LATENCY_BUCKETS`], cumulatively
+    pub latency_buckets: [u64; LATENCY_BUCKETS.len()],
+    /// Total seconds spent running
+    pub latency_sum: f64,
+    /// Effectful extern calls by the extern's snippet ID
+    pub effect_calls: BTreeMap<String, u64>,
+}
+
+impl RunMetrics {
+    /// Count a run that took `seconds` and made the extern calls in `audit`,
+    /// the lines of its audit log
+    pub fn record(&mut self, seconds: f64, success: bool, audit: &str) {
+        self.runs += 1;
+        if !success {
+            self.failures += 1;
+        }
+        for (bucket, bound) in self.latency_buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.latency_sum += seconds;
+        for line in audit.lines() {
+            let record: serde_json::Value = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(_) => continue,
+            };
+            if let Some(name) = record["extern"].as_str() {
+                *self.effect_calls.entry(name.to_string()).or_default() += 1;
+            }
+        }
+    }
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Scheduled functions in a checked program, with next runs after `now`
+pub fn scheduled_functions(program: &Program, now: i64) -> Vec<ScheduledFunction> {
+    let Program::Snippets { snippets, .. } = program else {
+        return Vec::new();
+    };
+
+    snippets
+        .iter()
+        .filter_map(|snippet| {
+            let schedule = snippet_schedule(snippet)?;
+            // The checker has already rejected invalid expressions
+            let cron = CronSchedule::parse(schedule).ok()?;
+            let function = snippet.sections.iter().find_map(|section| match section {
+                Section::Signature(sig) => match &sig.kind {
+                    SignatureKind::Function(f) => Some(f.name.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })?;
+            let effects = snippet
+                .sections
+                .iter()
+                .filter_map(|section| match section {
+                    Section::Effects(effects) => Some(effects.effects.iter()),
+                    _ => None,
+                })
+                .flatten()
+                .map(|effect| {
+                    if effect.params.is_empty() {
+                        return effect.name.clone();
+                    }
+                    let params: Vec<String> = effect
+                        .params
+                        .iter()
+                        .map(|p| format!("{}={}", p.name, p.value.to_cov(0)))
+                        .collect();
+                    format!("{}({})", effect.name, params.join(", "))
+                })
+                .collect();
+
+            Some(ScheduledFunction {
+                snippet: snippet.id.clone(),
+                function,
+                schedule: schedule.to_string(),
+                next_run: cron.next_after(now),
+                cron,
+                effects,
+                last_run: None,
+                metrics: RunMetrics::default(),
+            })
+        })
+        .collect()
+}
+
+/// Status endpoint body
+pub fn status_json(functions: &[ScheduledFunction]) -> serde_json::Value {
+    let functions: Vec<serde_json::Value> = functions
+        .iter()
+        .map(|f| {
+            json!({
+                "snippet": f.snippet,
+                "function": f.function,
+                "schedule": f.schedule,
+                "effects": f.effects,
+                "next_run": f.next_run.map(format_utc),
+                "last_run": f.last_run.as_ref().map(|run| json!({
+                    "started": format_utc(run.started),
+                    "finished": format_utc(run.finished),
+                    "status": if run.success { "succeeded" } else { "failed" },
+                    "exit_code": run.exit_code,
+                })),
+            })
+        })
+        .collect();
+    json!({ "functions": functions })
+}
+
+/// Metrics endpoint body, in the Prometheus text exposition format
+pub fn metrics_text(functions: &[ScheduledFunction]) -> String {
+    let mut out = String::new();
+    let counter = |out: &mut String, name: &str, help: &str, value: fn(&RunMetrics) -> u64| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        for f in functions {
+            let _ = writeln!(out, "{}{{snippet=\"{}\"}} {}", name, label(&f.snippet), value(&f.metrics));
+        }
+    };
+    counter(&mut out, "covenant_runs_total", "Runs of each scheduled function.", |m| m.runs);
+    counter(&mut out, "covenant_run_failures_total", "Runs that exited unsuccessfully.", |m| m.failures);
+
+    out.push_str("# HELP covenant_run_duration_seconds How long each run took.\n");
+    out.push_str("# TYPE covenant_run_duration_seconds histogram\n");
+    for f in functions {
+        let snippet = label(&f.snippet);
+        for (count, bound) in f.metrics.latency_buckets.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(
+                out,
+                "covenant_run_duration_seconds_bucket{{snippet=\"{}\",le=\"{}\"}} {}",
+                snippet, bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "covenant_run_duration_seconds_bucket{{snippet=\"{}\",le=\"+Inf\"}} {}",
+            snippet, f.metrics.runs
+        );
+        let _ = writeln!(out, "covenant_run_duration_seconds_sum{{snippet=\"{}\"}} {}", snippet, f.metrics.latency_sum);
+        let _ = writeln!(out, "covenant_run_duration_seconds_count{{snippet=\"{}\"}} {}", snippet, f.metrics.runs);
+    }
+
+    out.push_str("# HELP covenant_effect_calls_total Effectful extern calls made by each function's runs.\n");
+    out.push_str("# TYPE covenant_effect_calls_total counter\n");
+    for f in functions {
+        for (name, count) in &f.metrics.effect_calls {
+            let _ = writeln!(
+                out,
+                "covenant_effect_calls_total{{snippet=\"{}\",extern=\"{}\"}} {}",
+                label(&f.snippet),
+                label(name),
+                count
+            );
+        }
+    }
+    out
+}
+
+/// Escape a Prometheus label value
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Answer status requests until the process exits
+///
+/// `GET /status` returns [`status_json`], `GET /healthz` returns `ok` and
+/// `GET /metrics` returns [`metrics_text`]; every other path is a 404.
+pub fn serve_status(listener: TcpListener, functions: Arc<Mutex<Vec<ScheduledFunction>>>) {
+    for stream in listener.incoming().flatten() {
+        if let Err(e) = respond(stream, &functions) {
+            eprintln!("[serve] status request failed: {}", e);
+        }
+    }
+}
+
+fn respond(mut stream: TcpStream, functions: &Mutex<Vec<ScheduledFunction>>) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+
+    const JSON: &str = "application/json";
+    const TEXT: &str = "text/plain; version=0.0.4";
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/status")) => {
+            let functions = functions.lock().unwrap_or_else(|e| e.into_inner());
+            ("200 OK", JSON, serde_json::to_string_pretty(&status_json(&functions)).unwrap_or_default())
+        }
+        (Some("GET"), Some("/healthz")) => ("200 OK", TEXT, "ok\n".to_string()),
+        (Some("GET"), Some("/metrics")) => {
+            let functions = functions.lock().unwrap_or_else(|e| e.into_inner());
+            ("200 OK", TEXT, metrics_text(&functions))
+        }
+        _ => ("404 Not Found", JSON, json!({ "error": "not found" }).to_string()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Run due functions forever, one at a time, in schedule order; `permissions`
+/// are the Deno flags the runner gets when Deno runs them, and each run's
+/// extern calls are logged to `audit` for the metrics
+pub fn run_scheduler(
+    wasm: &Path,
+    permissions: &[String],
+    audit: &Path,
+    otlp: Option<&str>,
+    functions: &Mutex<Vec<ScheduledFunction>>,
+) -> ! {
+    loop {
+        let now = unix_now();
+        let due = {
+            let functions = functions.lock().unwrap_or_else(|e| e.into_inner());
+            let mut due: Vec<(i64, usize)> = functions
+                .iter()
+                .enumerate()
+                .filter_map(|(i, f)| f.next_run.filter(|next| *next <= now).map(|next| (next, i)))
+                .collect();
+            due.sort();
+            due
+        };
+
+        for (_, index) in due {
+            let (snippet, function, effects) = {
+                let functions = functions.lock().unwrap_or_else(|e| e.into_inner());
+                let f = &functions[index];
+                (f.snippet.clone(), f.function.clone(), f.effects.join(", "))
+            };
+            let effects = if effects.is_empty() { "none".to_string() } else { effects };
+            eprintln!("[serve] {} running {} (effects: {})", format_utc(unix_now()), snippet, effects);
+
+            let started = unix_now();
+            let clock = Instant::now();
+            if let Err(e) = fs::write(audit, "") {
+                eprintln!("[serve] could not reset the audit log: {}", e);
+            }
+            let (runtime, mut command) = crate::runner::runner_command(wasm, None, permissions);
+            command.arg(format!("--entry={}", function)).arg(format!("--audit={}", audit.display()));
+            if let Some(otlp) = otlp {
+                command.arg(format!("--otlp={}", otlp));
+            }
+            let exit = command.status();
+            let finished = unix_now();
+            let seconds = clock.elapsed().as_secs_f64();
+
+            let last_run = match exit {
+                Ok(status) => LastRun { started, finished, success: status.success(), exit_code: status.code() },
+                Err(e) => {
+                    eprintln!("[serve] could not start {}: {}", runtime, e);
+                    LastRun { started, finished, success: false, exit_code: None }
+                }
+            };
+            if !last_run.success {
+                eprintln!("[serve] {} failed", snippet);
+            }
+
+            // Runs missed while this one was busy are skipped, not queued
+            let mut functions = functions.lock().unwrap_or_else(|e| e.into_inner());
+            let f = &mut functions[index];
+            f.next_run = f.cron.next_after(finished);
+            f.metrics.record(seconds, last_run.success, &fs::read_to_string(audit).unwrap_or_default());
+            f.last_run = Some(last_run);
+        }
+
+        std::thread::sleep(Duration::from_millis(500));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01T00:00:00Z
+    const NEW_YEAR_2024: i64 = 1_704_067_200;
+
+    const SOURCE: &str = r#"
+snippet id="reports.hourly" kind="fn"
+metadata
+  trigger schedule="0 * * * *"
+end
+effects
+  effect filesystem(path="/tmp")
+end
+signature
+  fn name="hourly_report"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+
+snippet id="math.double" kind="fn"
+signature
+  fn name="double"
+    param name="x" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="x"
+    as="_"
+  end
+end
+end
+"#;
+
+    #[test]
+    fn test_scheduled_functions() {
+        let program = covenant_parser::parse(SOURCE).expect("parse failed");
+        let functions = scheduled_functions(&program, NEW_YEAR_2024);
+
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].snippet, "reports.hourly");
+        assert_eq!(functions[0].function, "hourly_report");
+        assert_eq!(functions[0].effects, vec![r#"filesystem(path="/tmp")"#]);
+        assert_eq!(functions[0].next_run, Some(NEW_YEAR_2024 + 3600));
+    }
+
+    #[test]
+    fn test_status_json() {
+        let program = covenant_parser::parse(SOURCE).expect("parse failed");
+        let mut functions = scheduled_functions(&program, NEW_YEAR_2024);
+        functions[0].last_run = Some(LastRun {
+            started: NEW_YEAR_2024,
+            finished: NEW_YEAR_2024 + 2,
+            success: true,
+            exit_code: Some(0),
+        });
+
+        let status = status_json(&functions);
+        let function = &status["functions"][0];
+        assert_eq!(function["schedule"], "0 * * * *");
+        assert_eq!(function["next_run"], "2024-01-01T01:00:00Z");
+        assert_eq!(function["last_run"]["finished"], "2024-01-01T00:00:02Z");
+        assert_eq!(function["last_run"]["status"], "succeeded");
+    }
+
+    #[test]
+    fn test_metrics_text() {
+        let program = covenant_parser::parse(SOURCE).expect("parse failed");
+        let mut functions = scheduled_functions(&program, NEW_YEAR_2024);
+        let audit = concat!(
+            r#"{"extern":"std.fs.write","args_hash":"a1","snippet":"reports.hourly","step":"s1"}"#,
+            "\n",
+            r#"{"extern":"std.fs.write","args_hash":"b2","snippet":"reports.hourly","step":"s1"}"#,
+            "\n",
+        );
+        functions[0].metrics.record(0.2, true, audit);
+        functions[0].metrics.record(3.0, false, "");
+
+        let metrics = metrics_text(&functions);
+        assert!(metrics.contains("# TYPE covenant_runs_total counter\ncovenant_runs_total{snippet=\"reports.hourly\"} 2"));
+        assert!(metrics.contains("covenant_run_failures_total{snippet=\"reports.hourly\"} 1\n"));
+        assert!(metrics.contains("covenant_run_duration_seconds_bucket{snippet=\"reports.hourly\",le=\"0.1\"} 0\n"));
+        assert!(metrics.contains("covenant_run_duration_seconds_bucket{snippet=\"reports.hourly\",le=\"0.25\"} 1\n"));
+        assert!(metrics.contains("covenant_run_duration_seconds_bucket{snippet=\"reports.hourly\",le=\"+Inf\"} 2\n"));
+        assert!(metrics.contains("covenant_run_duration_seconds_sum{snippet=\"reports.hourly\"} 3.2\n"));
+        assert!(metrics.contains("covenant_effect_calls_total{snippet=\"reports.hourly\",extern=\"std.fs.write\"} 2\n"));
+    }
+}
@@ -0,0 +1,119 @@
+//! End-to-end tests for `covenant path` on snippet sources
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// app.main reaches app.db directly through app.repo and through app.api
+/// then app.repo
+const SOURCE: &str = r#"snippet id="app.main" kind="fn"
+signature
+  fn name="main"
+    returns type="Int"
+  end
+end
+body
+  step id="c0" kind="call"
+    fn="app.api"
+    as="r0"
+  end
+  step id="c1" kind="call"
+    fn="app.repo"
+    as="r1"
+  end
+  step id="s" kind="return"
+    lit=1
+    as="_"
+  end
+end
+end
+
+snippet id="app.api" kind="fn"
+signature
+  fn name="api"
+    returns type="Int"
+  end
+end
+body
+  step id="c0" kind="call"
+    fn="app.repo"
+    as="r0"
+  end
+  step id="s" kind="return"
+    lit=1
+    as="_"
+  end
+end
+end
+
+snippet id="app.repo" kind="fn"
+signature
+  fn name="repo"
+    returns type="Int"
+  end
+end
+body
+  step id="c0" kind="call"
+    fn="app.db"
+    as="r0"
+  end
+  step id="s" kind="return"
+    lit=1
+    as="_"
+  end
+end
+end
+
+snippet id="app.db" kind="fn"
+signature
+  fn name="db"
+    returns type="Int"
+  end
+end
+body
+  step id="s" kind="return"
+    lit=1
+    as="_"
+  end
+end
+end
+"#;
+
+/// Write a project holding `SOURCE` as app.cov
+fn project() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("covenant-path-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("app.cov"), SOURCE).unwrap();
+    dir
+}
+
+fn covenant_path(dir: &PathBuf, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_covenant"))
+        .arg("path")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_path_over_snippet_source() {
+    let dir = project();
+
+    let all = covenant_path(&dir, &["app.main", "app.db", "app.cov"]);
+    assert_eq!(
+        all,
+        "Results:\n  1. app.main -> app.repo -> app.db\n     effects: (pure)\n  \
+         2. app.main -> app.api -> app.repo -> app.db\n     effects: (pure)\n"
+    );
+
+    let shortest = covenant_path(&dir, &["app.main", "app.db", "app.cov", "-k", "1"]);
+    assert_eq!(shortest, "Results:\n  1. app.main -> app.repo -> app.db\n     effects: (pure)\n");
+
+    let none = covenant_path(&dir, &["app.db", "app.main", "app.cov"]);
+    assert_eq!(none, "No call path from app.db to app.main\n");
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,207 @@
+//! Compares the scalar, SIMD and relaxed SIMD lowerings of numeric vector
+//! operations.
+//!
+//! Run with `cargo bench -p covenant-codegen --bench numeric`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use covenant_codegen::{SnippetWasmCompiler, WasmFeature, WasmFeatures};
+use wasmtime::{Config, Engine, Instance, Linker, Module, Store, Val};
+
+const VECTOR_LEN: usize = 16_384;
+const MATRIX_SIZE: usize = 64;
+const ITERATIONS: u32 = 50;
+
+/// Inputs are written here, above anything the functions allocate
+const INPUT_OFFSET: usize = 512 * 1024;
+
+const SOURCE: &str = r#"
+snippet id="bench.dot" kind="fn"
+signature
+  fn name="dot"
+    param name="a" type="List<Float>"
+    param name="b" type="List<Float>"
+    returns type="Array<Float, 1>"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=dot
+    input var="a"
+    input var="b"
+    as="sum"
+  end
+  step id="s2" kind="compute"
+    op=array_of
+    input var="sum"
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+
+snippet id="bench.add" kind="fn"
+signature
+  fn name="add"
+    param name="a" type="List<Float>"
+    param name="b" type="List<Float>"
+    returns type="List<Float>"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=vec_add
+    input var="a"
+    input var="b"
+    as="sum"
+  end
+  step id="s2" kind="return"
+    from="sum"
+    as="_"
+  end
+end
+end
+
+snippet id="bench.matmul" kind="fn"
+signature
+  fn name="matmul"
+    param name="a" type="List<List<Float>>"
+    param name="b" type="List<List<Float>>"
+    returns type="List<List<Float>>"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=matmul
+    input var="a"
+    input var="b"
+    as="product"
+  end
+  step id="s2" kind="return"
+    from="product"
+    as="_"
+  end
+end
+end
+"#;
+
+fn compile(features: WasmFeatures) -> Vec<u8> {
+    let program = covenant_parser::parse(SOURCE).expect("benchmark program parses");
+    let checked = covenant_checker::check(&program).expect("benchmark program checks");
+    let covenant_ast::Program::Snippets { snippets, .. } = &program else {
+        unreachable!("snippet program")
+    };
+    SnippetWasmCompiler::new(&checked.symbols)
+        .with_features(features)
+        .compile_snippets(snippets)
+        .expect("benchmark program compiles")
+}
+
+fn instantiate(engine: &Engine, module: &Module) -> (Store<()>, Instance) {
+    let mut store = Store::new(engine, ());
+    let mut linker = Linker::new(engine);
+    for import in module.imports() {
+        if let wasmtime::ExternType::Func(func_ty) = import.ty() {
+            linker
+                .func_new(import.module(), import.name(), func_ty, |_caller, _params, results| {
+                    for result in results.iter_mut() {
+                        *result = Val::I64(0);
+                    }
+                    Ok(())
+                })
+                .unwrap();
+        }
+    }
+    let instance = linker.instantiate(&mut store, module).expect("module instantiates");
+    (store, instance)
+}
+
+/// Writes arrays in the layout the compiler uses: an i32 count followed by
+/// 8-byte elements
+struct Writer<'a> {
+    store: &'a mut Store<()>,
+    instance: &'a Instance,
+    at: usize,
+}
+
+impl Writer<'_> {
+    fn array(&mut self, elements: impl ExactSizeIterator<Item = u64>) -> i64 {
+        let memory = self.instance.get_memory(&mut *self.store, "memory").expect("memory export");
+        let base = self.at;
+        let len = elements.len();
+        let mut bytes = (len as u32).to_le_bytes().to_vec();
+        for element in elements {
+            bytes.extend_from_slice(&element.to_le_bytes());
+        }
+        memory.write(&mut *self.store, base, &bytes).expect("input fits in memory");
+        self.at += bytes.len();
+        ((base as i64) << 32) | len as i64
+    }
+
+    fn vector(&mut self, len: usize) -> i64 {
+        self.array((0..len).map(|i| (i as f64 * 0.5).to_bits()))
+    }
+
+    fn matrix(&mut self, size: usize) -> i64 {
+        let rows: Vec<i64> = (0..size).map(|_| self.vector(size)).collect();
+        self.array(rows.into_iter().map(|row| row as u64))
+    }
+}
+
+fn vectors(writer: &mut Writer) -> (i64, i64) {
+    (writer.vector(VECTOR_LEN), writer.vector(VECTOR_LEN))
+}
+
+fn matrices(writer: &mut Writer) -> (i64, i64) {
+    (writer.matrix(MATRIX_SIZE), writer.matrix(MATRIX_SIZE))
+}
+
+/// Average time of one call, each on a fresh instance so allocations do not
+/// accumulate
+fn time(engine: &Engine, module: &Module, name: &str, inputs: impl Fn(&mut Writer) -> (i64, i64)) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let (mut store, instance) = instantiate(engine, module);
+        let args = inputs(&mut Writer { store: &mut store, instance: &instance, at: INPUT_OFFSET });
+        let func = instance.get_typed_func::<(i64, i64), i64>(&mut store, name).expect("function export");
+        let start = Instant::now();
+        black_box(func.call(&mut store, args).expect("call succeeds"));
+        total += start.elapsed();
+    }
+    total / ITERATIONS
+}
+
+fn main() {
+    let mut config = Config::new();
+    config.wasm_simd(true).wasm_relaxed_simd(true);
+    let engine = Engine::new(&config).expect("engine");
+    let scalar = Module::new(&engine, compile(WasmFeatures::none())).expect("scalar module");
+    let simd = Module::new(&engine, compile(WasmFeatures::none().with(WasmFeature::Simd))).expect("SIMD module");
+    let relaxed =
+        Module::new(&engine, compile(WasmFeatures::none().with(WasmFeature::RelaxedSimd))).expect("relaxed SIMD module");
+
+    let cases: [(&str, String, fn(&mut Writer) -> (i64, i64)); 3] = [
+        ("dot", format!("{} elements", VECTOR_LEN), vectors),
+        ("add", format!("{} elements", VECTOR_LEN), vectors),
+        ("matmul", format!("{}x{}", MATRIX_SIZE, MATRIX_SIZE), matrices),
+    ];
+
+    for (name, size, inputs) in cases {
+        let scalar_time = time(&engine, &scalar, name, inputs);
+        println!("{} ({})", name, size);
+        println!("  scalar       {:>10.2?}", scalar_time);
+        for (label, module) in [("simd", &simd), ("relaxed-simd", &relaxed)] {
+            let elapsed = time(&engine, module, name, inputs);
+            println!(
+                "  {:<12} {:>10.2?}   speedup {:.2}x",
+                label,
+                elapsed,
+                scalar_time.as_secs_f64() / elapsed.as_secs_f64()
+            );
+        }
+    }
+}
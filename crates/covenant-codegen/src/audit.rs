@@ -0,0 +1,183 @@
+//! Audit table for effectful extern calls
+//!
+//! Compiled modules record which externs declare effects and where they are
+//! called from, so a host can log every effectful call with its caller. The
+//! table is embedded as JSON in the [`AUDIT_SECTION`] custom section. Before
+//! each audited call the module stores the call site's index in the exported
+//! [`AUDIT_SITE_EXPORT`] global, which the host reads from inside the import.
+
+use std::collections::BTreeMap;
+
+use covenant_ast::{Section, Sensitive, SignatureKind, Snippet};
+use serde::{Deserialize, Serialize};
+
+/// Name of the custom section holding the audit table
+pub const AUDIT_SECTION: &str = "covenant.audit";
+
+/// Name of the exported i32 global holding the current call site index
+pub const AUDIT_SITE_EXPORT: &str = "_cov_audit_site";
+
+/// Effectful externs and the call sites that reach them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditTable {
+    /// Audited externs by import name (e.g., "fs.read_file")
+    pub externs: BTreeMap<String, AuditExtern>,
+    /// Call sites, indexed by the value of the site global
+    pub sites: Vec<AuditSite>,
+}
+
+/// An extern that declares effects
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditExtern {
+    /// Snippet ID of the extern
+    pub snippet: String,
+    /// Declared effect names
+    pub effects: Vec<String>,
+    /// The binding's `contract` metadata (e.g., "axios.get@1")
+    pub contract: Option<String>,
+    /// How each argument is passed: "string" and "fat_pointer" take a
+    /// (ptr, len) pair, "int" and "bool" a single i32
+    pub params: Vec<String>,
+    /// How the result is returned: "string", "list" and "fat_pointer" as an
+    /// i64 fat pointer, "int" and "bool" as an i64, "unit" not at all
+    pub returns: String,
+    /// The `rate_limit` metadata (e.g., "100/m"), enforced by the host
+    pub rate_limit: Option<String>,
+    /// The `circuit_breaker` metadata (e.g., "5/30s"), enforced by the host
+    pub circuit_breaker: Option<String>,
+    /// Indexes into `params` of the arguments the host must redact: params
+    /// marked `sensitive`, and structs with sensitive fields
+    #[serde(default)]
+    pub sensitive: Vec<usize>,
+}
+
+/// A call step that invokes an audited extern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditSite {
+    /// Snippet ID of the calling function
+    pub snippet: String,
+    /// ID of the call step
+    pub step: String,
+    /// Import name of the extern
+    pub import: String,
+}
+
+impl AuditExtern {
+    /// Audit entry for an extern snippet, or `None` if it declares no effects
+    pub fn from_snippet(snippet: &Snippet, params: Vec<String>, returns: String, sensitive: &Sensitive) -> Option<Self> {
+        let effects: Vec<String> = snippet
+            .sections
+            .iter()
+            .filter_map(|section| match section {
+                Section::Effects(effects) => Some(effects.effects.iter().map(|e| e.name.clone())),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        if effects.is_empty() {
+            return None;
+        }
+
+        let metadata = |key: &str| {
+            snippet
+                .sections
+                .iter()
+                .filter_map(|section| match section {
+                    Section::Metadata(metadata) => metadata.entries.iter().find(|e| e.key == key),
+                    _ => None,
+                })
+                .map(|entry| entry.value.clone())
+                .next()
+        };
+
+        let sensitive_params = snippet
+            .sections
+            .iter()
+            .find_map(|section| match section {
+                Section::Signature(sig) => match &sig.kind {
+                    SignatureKind::Function(f) => Some(&f.params),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, param)| param.sensitive || sensitive.is_type(&param.ty))
+            .map(|(index, _)| index)
+            .collect();
+
+        Some(Self {
+            snippet: snippet.id.clone(),
+            effects,
+            contract: metadata("contract"),
+            params,
+            returns,
+            rate_limit: metadata("rate_limit"),
+            circuit_breaker: metadata("circuit_breaker"),
+            sensitive: sensitive_params,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_snippet(source: &str) -> Snippet {
+        match covenant_parser::parse(source).unwrap() {
+            covenant_ast::Program::Snippets { mut snippets, .. } => snippets.remove(0),
+            _ => panic!("expected snippets"),
+        }
+    }
+
+    #[test]
+    fn test_extern_with_effects_and_contract() {
+        let snippet = first_snippet(
+            r#"
+snippet id="http.get" kind="extern"
+effects
+  effect network
+end
+signature
+  fn name="get"
+    param name="url" type="String"
+    param name="token" type="String" sensitive
+    returns type="String"
+  end
+end
+metadata
+  contract="axios.get@1"
+  rate_limit="100/m"
+end
+end
+"#,
+        );
+        let params = vec!["string".to_string(), "string".to_string()];
+        let audit = AuditExtern::from_snippet(&snippet, params, "string".to_string(), &Sensitive::default()).unwrap();
+        assert_eq!(audit.snippet, "http.get");
+        assert_eq!(audit.effects, vec!["network"]);
+        assert_eq!(audit.contract.as_deref(), Some("axios.get@1"));
+        assert_eq!(audit.returns, "string");
+        assert_eq!(audit.rate_limit.as_deref(), Some("100/m"));
+        assert_eq!(audit.circuit_breaker, None);
+        assert_eq!(audit.sensitive, vec![1]);
+    }
+
+    #[test]
+    fn test_pure_extern_is_not_audited() {
+        let snippet = first_snippet(
+            r#"
+snippet id="text.upper" kind="extern"
+signature
+  fn name="upper"
+    param name="s" type="String"
+    returns type="String"
+  end
+end
+end
+"#,
+        );
+        assert!(AuditExtern::from_snippet(&snippet, Vec::new(), "string".to_string(), &Sensitive::default()).is_none());
+    }
+}
@@ -0,0 +1,67 @@
+//! OCI container images
+//!
+//! `covenant package --oci` (or `--target oci`) writes a build context for
+//! an OCI image that runs the module under the Deno host runner. The image
+//! starts from a pinned Deno base image, so rebuilding from the same project
+//! and `covenant.json` gives the same image. The Deno permissions the
+//! program's effects call for are baked into the entrypoint, so the container
+//! gets the same capabilities `covenant run` grants; arguments passed to
+//! `docker run` (such as `--entry=<fn>`) are appended after the module.
+//!
+//! The image was first planned around a wasmtime-based runtime configured
+//! from `covenant.toml`. Only the JavaScript host runners implement the
+//! runtime imports, though, so the image embeds the Deno runner, and it is
+//! configured from `covenant.json` like every other command. A wasmtime
+//! host can replace the entrypoint once one exists.
+
+use std::fmt::Write;
+
+use crate::host_bindgen::js_string;
+
+/// The build file's name within the context
+pub const CONTAINERFILE: &str = "Containerfile";
+
+/// The base image when `covenant.json` names none
+pub const DEFAULT_BASE_IMAGE: &str = "denoland/deno:distroless-2.1.4";
+
+/// Where the module and the host runner live in the image
+pub const IMAGE_APP_DIR: &str = "/app";
+
+/// Generate a Containerfile running `wasm_file` under the Deno host runner
+/// copied into `host/`, with `deno_flags` as its permissions
+pub fn emit_containerfile(base: &str, wasm_file: &str, deno_flags: &[String]) -> String {
+    let mut entrypoint = vec!["deno".to_string(), "run".to_string()];
+    entrypoint.extend(deno_flags.iter().cloned());
+    entrypoint.push(format!("{}/host/run.deno.ts", IMAGE_APP_DIR));
+    entrypoint.push(format!("{}/{}", IMAGE_APP_DIR, wasm_file));
+    let entrypoint: Vec<String> = entrypoint.iter().map(|arg| js_string(arg)).collect();
+
+    let mut out = String::new();
+    out.push_str("# Generated by `covenant package --target oci`. Build with\n");
+    out.push_str("# `docker build -f Containerfile .` or `podman build .`.\n");
+    let _ = writeln!(out, "FROM {}", base);
+    let _ = writeln!(out, "WORKDIR {}", IMAGE_APP_DIR);
+    out.push_str("COPY host/ host/\n");
+    let _ = writeln!(out, "COPY {} {}", wasm_file, wasm_file);
+    // Type check and cache the runner at build time rather than on each start
+    let _ = writeln!(out, "RUN [\"deno\", \"cache\", \"{}/host/run.deno.ts\"]", IMAGE_APP_DIR);
+    let _ = writeln!(out, "ENTRYPOINT [{}]", entrypoint.join(", "));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_containerfile_bakes_in_permissions() {
+        let flags = vec!["--allow-read=/app/app.wasm,/data".to_string(), "--allow-net".to_string()];
+        let containerfile = emit_containerfile(DEFAULT_BASE_IMAGE, "app.wasm", &flags);
+        assert!(containerfile.contains("FROM denoland/deno:distroless-2.1.4\nWORKDIR /app\n"));
+        assert!(containerfile.contains("COPY host/ host/\nCOPY app.wasm app.wasm\n"));
+        assert!(containerfile.ends_with(
+            "ENTRYPOINT [\"deno\", \"run\", \"--allow-read=/app/app.wasm,/data\", \"--allow-net\", \
+             \"/app/host/run.deno.ts\", \"/app/app.wasm\"]\n"
+        ));
+    }
+}
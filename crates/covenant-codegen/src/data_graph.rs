@@ -148,6 +148,7 @@ impl DataGraph {
                 id_to_index.insert(id.clone(), idx);
                 let kind_str = match snippet.kind {
                     SnippetKind::Function => "fn",
+                    SnippetKind::Workflow => "workflow",
                     SnippetKind::Struct => "struct",
                     SnippetKind::Database => "database",
                     SnippetKind::Extern => "extern",
@@ -157,6 +158,10 @@ impl DataGraph {
                     SnippetKind::ExternImpl => "extern_impl",
                     SnippetKind::Test => "test",
                     SnippetKind::Data => "data", // Won't happen due to continue above
+                    SnippetKind::Subscriber => "subscriber",
+                    SnippetKind::Const => "const",
+                    SnippetKind::Interface => "interface",
+                    SnippetKind::Alias => "alias",
                 };
                 let metadata = vec![("kind".to_string(), kind_str.to_string())];
                 nodes.push(DataNode {
@@ -341,9 +346,11 @@ mod tests {
             id: id.to_string(),
             kind: SnippetKind::Data,
             notes: vec![],
+            doc: None,
             sections,
             implements: None,
             platform: None,
+            node_id: None,
             span: Span::default(),
         }
     }
@@ -3,6 +3,10 @@
 //! This module provides structures and functions for serializing symbol
 //! metadata into a format suitable for embedding in WASM modules.
 
+use std::collections::BTreeMap;
+
+use covenant_ast::{EffectDecl, Literal};
+use covenant_ast::printer::ToCov;
 use covenant_checker::EffectCheckResult;
 use covenant_symbols::SymbolGraph;
 use serde::{Deserialize, Serialize};
@@ -54,6 +58,69 @@ pub struct EmbeddableSymbol {
     /// Used by the host to gate imports based on declared effects.
     #[serde(default)]
     pub required_capabilities: Vec<String>,
+
+    /// Declared effects with their resource parameters, e.g.
+    /// `network.http(host="api.stripe.com")`.
+    /// The host confines filesystem paths and HTTP hosts to these scopes;
+    /// a declaration without parameters leaves the effect unrestricted.
+    #[serde(default)]
+    pub effect_scopes: Vec<EffectScope>,
+}
+
+/// A declared effect and the resources it is limited to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectScope {
+    /// Effect name (e.g., "filesystem.read")
+    pub effect: String,
+
+    /// Parameter values by name; strings are stored unquoted
+    pub params: BTreeMap<String, String>,
+}
+
+impl From<&EffectDecl> for EffectScope {
+    fn from(decl: &EffectDecl) -> Self {
+        let params = decl
+            .params
+            .iter()
+            .map(|param| {
+                let value = match &param.value {
+                    Literal::Int(i) => i.to_string(),
+                    Literal::Float(f) => f.to_string(),
+                    Literal::Decimal(text) | Literal::BigInt(text) => text.clone(),
+                    Literal::DateTime(seconds) => covenant_ast::time::format_datetime(*seconds),
+                    Literal::Duration(seconds) => covenant_ast::time::format_duration(*seconds),
+                    Literal::String(s) => s.clone(),
+                    Literal::Bool(b) => b.to_string(),
+                    Literal::None => "none".to_string(),
+                    Literal::List(_) | Literal::Map(_) | Literal::Set(_) | Literal::Tuple(_) => {
+                        param.value.to_cov(0)
+                    }
+                };
+                (param.name.clone(), value)
+            })
+            .collect();
+        Self { effect: decl.name.clone(), params }
+    }
+}
+
+/// Symbol metadata as embedded in the WASM data section
+///
+/// The `schema_version` lets hosts upgrade metadata from older modules; see
+/// `covenant_ast::migrate_symbol_metadata_json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddedMetadata<'a> {
+    pub schema_version: u32,
+    pub symbols: &'a [EmbeddableSymbol],
+}
+
+impl<'a> EmbeddedMetadata<'a> {
+    /// Wrap symbols at the current metadata schema version
+    pub fn new(symbols: &'a [EmbeddableSymbol]) -> Self {
+        Self {
+            schema_version: covenant_ast::SYMBOL_METADATA_VERSION,
+            symbols,
+        }
+    }
 }
 
 /// Build embeddable symbols from a SymbolGraph and EffectCheckResult
@@ -80,6 +147,7 @@ pub fn build_embeddable_symbols(
                 .iter()
                 .map(|e| e.name.clone())
                 .collect();
+            let effect_scopes = sym.declared_effects.iter().map(EffectScope::from).collect();
 
             EmbeddableSymbol {
                 id: sym.name.clone(),
@@ -103,6 +171,7 @@ pub fn build_embeddable_symbols(
                 tests: sym.tests.clone(),
                 covers: sym.covers.clone(),
                 required_capabilities,
+                effect_scopes,
             }
         })
         .collect()
@@ -120,10 +189,22 @@ fn effects_to_capabilities(effects: &[String]) -> Vec<String> {
             // Core effects
             "database" => {
                 capabilities.push("db.execute_query".to_string());
+                capabilities.push("db.register_target".to_string());
             }
-            "network" => {
+            "network" | "network.http" => {
                 capabilities.push("http.fetch".to_string());
             }
+            "filesystem.read" => {
+                capabilities.push("fs.read".to_string());
+                capabilities.push("fs.exists".to_string());
+                capabilities.push("fs.read_dir".to_string());
+            }
+            "filesystem.write" => {
+                capabilities.push("fs.write".to_string());
+                capabilities.push("fs.delete".to_string());
+                capabilities.push("fs.create_dir".to_string());
+                capabilities.push("fs.remove_dir".to_string());
+            }
             "filesystem" => {
                 capabilities.push("fs.read".to_string());
                 capabilities.push("fs.write".to_string());
@@ -139,6 +220,23 @@ fn effects_to_capabilities(effects: &[String]) -> Vec<String> {
                 capabilities.push("console.eprintln".to_string());
                 capabilities.push("console.eprint".to_string());
             }
+            "events" => {
+                capabilities.push("events.publish".to_string());
+                capabilities.push("events.subscribe".to_string());
+            }
+            "locale" => {
+                capabilities.push("locale.upper".to_string());
+                capabilities.push("locale.lower".to_string());
+                capabilities.push("locale.casefold".to_string());
+                capabilities.push("locale.normalize".to_string());
+                capabilities.push("locale.collate".to_string());
+            }
+            "random" => {
+                capabilities.push("crypto.uuid_v4".to_string());
+                capabilities.push("crypto.random_int".to_string());
+                capabilities.push("crypto.random_float".to_string());
+                capabilities.push("crypto.random_bytes".to_string());
+            }
 
             // Standard library effects
             "std.storage" => {
@@ -209,6 +307,10 @@ mod tests {
                 "console.print".to_string(),
                 "console.println".to_string(),
             ],
+            effect_scopes: vec![EffectScope {
+                effect: "console".to_string(),
+                params: BTreeMap::new(),
+            }],
         };
 
         let json = serde_json::to_string(&symbol).unwrap();
@@ -219,17 +321,53 @@ mod tests {
         assert_eq!(parsed.tests, vec!["T-001"]);
         assert_eq!(parsed.required_capabilities.len(), 4);
         assert!(parsed.required_capabilities.contains(&"console.println".to_string()));
+        assert_eq!(parsed.effect_scopes, symbol.effect_scopes);
+    }
+
+    #[test]
+    fn test_effect_scopes_default_when_missing() {
+        let json = r#"{"id": "a", "kind": "fn", "line": 1, "calls": [], "references": [],
+            "called_by": [], "referenced_by": [], "effects": [], "effect_closure": [],
+            "requirements": [], "tests": [], "covers": []}"#;
+        let parsed: EmbeddableSymbol = serde_json::from_str(json).unwrap();
+        assert!(parsed.effect_scopes.is_empty());
+    }
+
+    #[test]
+    fn test_effect_scope_from_decl() {
+        let program = covenant_parser::parse(
+            "snippet id=\"a.b\" kind=\"extern\"\neffects\n  effect network.http(host=\"*.stripe.com\", retries=3)\nend\nend\n",
+        )
+        .unwrap();
+        let covenant_ast::Program::Snippets { snippets, .. } = program else {
+            panic!("expected snippets");
+        };
+        let covenant_ast::Section::Effects(section) = &snippets[0].sections[0] else {
+            panic!("expected effects section");
+        };
+
+        let scope = EffectScope::from(&section.effects[0]);
+        assert_eq!(scope.effect, "network.http");
+        assert_eq!(scope.params["host"], "*.stripe.com");
+        assert_eq!(scope.params["retries"], "3");
+    }
+
+    #[test]
+    fn test_embedded_metadata_is_versioned() {
+        let json = serde_json::to_value(EmbeddedMetadata::new(&[])).unwrap();
+        assert_eq!(json["schema_version"], covenant_ast::SYMBOL_METADATA_VERSION);
+        assert!(json["symbols"].as_array().unwrap().is_empty());
     }
 
     #[test]
     fn test_effects_to_capabilities() {
         // Database effect
         let caps = effects_to_capabilities(&["database".to_string()]);
-        assert_eq!(caps, vec!["db.execute_query"]);
+        assert_eq!(caps, vec!["db.execute_query", "db.register_target"]);
 
         // Multiple effects
         let caps = effects_to_capabilities(&["database".to_string(), "network".to_string()]);
-        assert_eq!(caps, vec!["db.execute_query", "http.fetch"]);
+        assert_eq!(caps, vec!["db.execute_query", "db.register_target", "http.fetch"]);
 
         // Console effect expands to multiple capabilities
         let caps = effects_to_capabilities(&["console".to_string()]);
@@ -237,6 +375,18 @@ mod tests {
         assert!(caps.contains(&"console.println".to_string()));
         assert!(caps.contains(&"console.print".to_string()));
 
+        let caps = effects_to_capabilities(&["locale".to_string()]);
+        assert_eq!(caps, vec!["locale.casefold", "locale.collate", "locale.lower", "locale.normalize", "locale.upper"]);
+
+        let caps = effects_to_capabilities(&["random".to_string()]);
+        assert_eq!(caps, vec!["crypto.random_bytes", "crypto.random_float", "crypto.random_int", "crypto.uuid_v4"]);
+
+        // Child effects grant a subset of their parent's imports
+        let caps = effects_to_capabilities(&["filesystem.read".to_string()]);
+        assert_eq!(caps, vec!["fs.exists", "fs.read", "fs.read_dir"]);
+        let caps = effects_to_capabilities(&["network.http".to_string()]);
+        assert_eq!(caps, vec!["http.fetch"]);
+
         // Unknown effect maps to itself
         let caps = effects_to_capabilities(&["custom.effect".to_string()]);
         assert_eq!(caps, vec!["custom.effect"]);
@@ -0,0 +1,133 @@
+//! Event subscriptions for the host's event bus
+//!
+//! Subscriber snippets are not compiled to functions. Instead, compiled
+//! modules list each subscription in the [`EVENTS_SECTION`] custom section
+//! as JSON, and the host registers the named handler exports on its event
+//! bus when the module is instantiated. `events.publish` then calls every
+//! handler subscribed to the published event type with the event value.
+
+use covenant_ast::{Section, SignatureKind, Snippet, SnippetKind};
+use serde::{Deserialize, Serialize};
+
+/// Name of the custom section holding the subscription table
+pub const EVENTS_SECTION: &str = "covenant.events";
+
+/// Subscriptions declared by a program's subscriber snippets
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventTable {
+    /// Subscriptions in source order, which is also dispatch order
+    pub subscriptions: Vec<Subscription>,
+}
+
+/// A handler export bound to an event type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    /// Snippet ID of the subscriber
+    pub subscriber: String,
+    /// Event struct name, without its module qualifier (e.g., "OrderPlaced")
+    pub event: String,
+    /// Export name of the handler function
+    pub handler: String,
+}
+
+impl EventTable {
+    /// Collect the subscriptions of every subscriber snippet
+    ///
+    /// A handler may be named by snippet ID or function name; either way the
+    /// table records the function name, which is what the module exports.
+    pub fn from_snippets(snippets: &[Snippet]) -> Self {
+        let subscriptions = snippets
+            .iter()
+            .filter(|s| s.kind == SnippetKind::Subscriber)
+            .flat_map(|s| s.sections.iter().map(move |section| (s, section)))
+            .filter_map(|(snippet, section)| match section {
+                Section::Subscribe(subscribe) => Some(Subscription {
+                    subscriber: snippet.id.clone(),
+                    event: event_name(&subscribe.event).to_string(),
+                    handler: handler_export(snippets, &subscribe.handler),
+                }),
+                _ => None,
+            })
+            .collect();
+        Self { subscriptions }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+}
+
+/// The name an event type is dispatched under: its struct name
+pub fn event_name(event_type: &str) -> &str {
+    event_type.rsplit('.').next().unwrap_or(event_type)
+}
+
+/// The export name of the function `handler` refers to
+fn handler_export(snippets: &[Snippet], handler: &str) -> String {
+    snippets
+        .iter()
+        .filter(|s| s.kind.is_function())
+        .flat_map(|s| s.sections.iter().map(move |section| (s, section)))
+        .find_map(|(snippet, section)| match section {
+            Section::Signature(sig) => match &sig.kind {
+                SignatureKind::Function(f) if snippet.id == handler || f.name == handler => Some(f.name.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .unwrap_or_else(|| handler.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::snippets;
+
+    #[test]
+    fn test_subscription_uses_handler_export_name() {
+        let snippets = snippets(
+            r#"
+snippet id="orders.notify" kind="fn"
+signature
+  fn name="notify"
+    param name="evt" type="OrderPlaced"
+    returns type="Unit"
+  end
+end
+end
+
+snippet id="orders.on_placed" kind="subscriber"
+subscribe
+  event type="orders.OrderPlaced"
+  handler fn="orders.notify"
+end
+end
+"#,
+        );
+        let table = EventTable::from_snippets(&snippets);
+        assert_eq!(
+            table.subscriptions,
+            vec![Subscription {
+                subscriber: "orders.on_placed".to_string(),
+                event: "OrderPlaced".to_string(),
+                handler: "notify".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_no_subscribers_is_empty() {
+        let snippets = snippets(
+            r#"
+snippet id="orders.OrderPlaced" kind="struct"
+signature
+  struct name="OrderPlaced"
+    field name="id" type="Int"
+  end
+end
+end
+"#,
+        );
+        assert!(EventTable::from_snippets(&snippets).is_empty());
+    }
+}
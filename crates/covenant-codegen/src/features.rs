@@ -0,0 +1,174 @@
+//! WebAssembly proposals a compiled module may use
+//!
+//! Modules are plain MVP WebAssembly unless a feature is enabled, and each
+//! feature only unlocks instructions: the compiler uses them where they
+//! help and falls back to MVP code elsewhere. The enabled features are
+//! recorded in the [`BUILD_INFO_SECTION`] custom section, so a host can tell
+//! what a module needs before instantiating it.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::MemoryLimits;
+
+/// Name of the custom section holding the build info
+pub const BUILD_INFO_SECTION: &str = "covenant.build";
+
+/// A WebAssembly proposal beyond the MVP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WasmFeature {
+    /// 128-bit SIMD: numeric vector operations take two elements per pass
+    Simd,
+    /// Relaxed SIMD: numeric vector loops fuse multiply-adds, whose results
+    /// can differ in the last bit between hosts. Implies `simd`.
+    RelaxedSimd,
+    /// Bulk memory: arrays and structs are copied with `memory.copy`
+    BulkMemory,
+    /// Garbage collection: allowed for hosts that support it, though no
+    /// compiled code uses it yet
+    Gc,
+}
+
+impl WasmFeature {
+    pub const ALL: [WasmFeature; 4] =
+        [WasmFeature::Simd, WasmFeature::RelaxedSimd, WasmFeature::BulkMemory, WasmFeature::Gc];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WasmFeature::Simd => "simd",
+            WasmFeature::RelaxedSimd => "relaxed-simd",
+            WasmFeature::BulkMemory => "bulk-memory",
+            WasmFeature::Gc => "gc",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl fmt::Display for WasmFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WasmFeature {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WasmFeature::ALL
+            .into_iter()
+            .find(|feature| feature.as_str() == s)
+            .ok_or_else(|| format!("Invalid WASM feature '{}'. Valid features: simd, relaxed-simd, bulk-memory, gc", s))
+    }
+}
+
+/// The set of features a module may use; empty by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WasmFeatures(u8);
+
+impl WasmFeatures {
+    /// MVP WebAssembly only
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Enable a feature, and any feature it implies
+    pub fn with(mut self, feature: WasmFeature) -> Self {
+        self.0 |= feature.bit();
+        if feature == WasmFeature::RelaxedSimd {
+            self.0 |= WasmFeature::Simd.bit();
+        }
+        self
+    }
+
+    pub fn contains(self, feature: WasmFeature) -> bool {
+        self.0 & feature.bit() != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The enabled features, in [`WasmFeature::ALL`] order
+    pub fn iter(self) -> impl Iterator<Item = WasmFeature> {
+        WasmFeature::ALL.into_iter().filter(move |feature| self.contains(*feature))
+    }
+}
+
+impl FromIterator<WasmFeature> for WasmFeatures {
+    fn from_iter<I: IntoIterator<Item = WasmFeature>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::none(), Self::with)
+    }
+}
+
+impl fmt::Display for WasmFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.iter().map(WasmFeature::as_str).collect();
+        f.write_str(&names.join(","))
+    }
+}
+
+/// Parse a comma-separated list such as `simd,bulk-memory`; an empty list
+/// enables nothing
+impl FromStr for WasmFeatures {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(WasmFeature::from_str)
+            .collect()
+    }
+}
+
+/// How a module was built, embedded as JSON in the [`BUILD_INFO_SECTION`]
+/// custom section
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// Version of the compiler that built the module
+    pub compiler: String,
+    /// Features the module may use
+    pub features: Vec<WasmFeature>,
+    /// Most 64 KiB pages the memory may grow to
+    pub max_memory_pages: u32,
+}
+
+impl BuildInfo {
+    pub fn new(features: WasmFeatures, memory: MemoryLimits) -> Self {
+        Self {
+            compiler: env!("CARGO_PKG_VERSION").to_string(),
+            features: features.iter().collect(),
+            max_memory_pages: memory.max_pages,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features_parse_and_display() {
+        let features: WasmFeatures = "bulk-memory, simd".parse().unwrap();
+        assert!(features.contains(WasmFeature::Simd));
+        assert!(features.contains(WasmFeature::BulkMemory));
+        assert!(!features.contains(WasmFeature::Gc));
+        assert_eq!(features.to_string(), "simd,bulk-memory");
+
+        assert!("".parse::<WasmFeatures>().unwrap().is_empty());
+        assert!("simd,threads".parse::<WasmFeatures>().unwrap_err().contains("'threads'"));
+    }
+
+    #[test]
+    fn test_relaxed_simd_implies_simd() {
+        let features = WasmFeatures::none().with(WasmFeature::RelaxedSimd);
+        assert_eq!(features.iter().collect::<Vec<_>>(), [WasmFeature::Simd, WasmFeature::RelaxedSimd]);
+        assert_eq!(serde_json::to_string(&BuildInfo::new(features, MemoryLimits::default()).features).unwrap(), r#"["simd","relaxed-simd"]"#);
+    }
+}
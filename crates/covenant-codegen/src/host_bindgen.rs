@@ -0,0 +1,476 @@
+//! Host glue generated from extern contracts
+//!
+//! `covenant emit-host --target <deno|node|bun>` writes a JavaScript module providing
+//! every extern import a program's modules can have: the stdlib's
+//! extern-abstract snippets and the program's own extern and
+//! extern-abstract snippets. Each import decodes its WASM arguments with the
+//! calling convention codegen uses for it, checks the host granted the
+//! extern's effects, calls the implementation registered for the extern's
+//! contract, and encodes the result, so the glue cannot drift from the
+//! externs it binds.
+//!
+//! An extern's contract is the `contract` metadata of the program's
+//! extern-impl for the platform, else the extern's own `contract`, else its
+//! snippet ID. The std contracts (`console.*`, `fs.*`) have default
+//! implementations on the runtime's own APIs: `Deno.*` under Deno, and
+//! `node:fs` under Node.js and Bun, which implements it natively.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use covenant_ast::{ReturnType, Section, Snippet, SnippetKind, TypeKind};
+use serde::{Deserialize, Serialize};
+
+use crate::snippet_wasm::{
+    extern_import_name, extern_return_audit_name, find_function_signature, stdlib_extern_abstracts,
+    type_to_extern_param_kind,
+};
+use crate::{ALLOC_EXPORT, INIT_EXPORT};
+
+/// JavaScript runtime host glue is generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostRuntime {
+    Deno,
+    Node,
+    Bun,
+}
+
+impl HostRuntime {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HostRuntime::Deno => "deno",
+            HostRuntime::Node => "node",
+            HostRuntime::Bun => "bun",
+        }
+    }
+}
+
+/// How the host provides one extern import
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HostBinding {
+    /// Import module (e.g. "fs")
+    pub module: String,
+    /// Import name within the module (e.g. "read_file")
+    pub name: String,
+    /// Snippet ID of the extern
+    pub snippet: String,
+    /// Contract the host's implementation is registered under
+    pub contract: String,
+    /// Effects the host must grant before the extern runs
+    pub effects: Vec<String>,
+    /// Params in order
+    pub params: Vec<HostParam>,
+    /// How the result is returned, named as in the audit table
+    pub returns: String,
+}
+
+/// A param of an extern import
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HostParam {
+    pub name: String,
+    /// How the argument is passed, named as in the audit table: "string" and
+    /// "fat_pointer" take a (ptr, len) pair, "int" and "bool" a single i32
+    pub kind: String,
+}
+
+/// The extern imports of a program's modules on `platform`, by import name
+pub fn host_bindings(snippets: &[Snippet], platform: &str) -> Vec<HostBinding> {
+    let impl_contracts: HashMap<&str, String> = snippets
+        .iter()
+        .filter(|s| s.kind == SnippetKind::ExternImpl && s.platform.as_deref() == Some(platform))
+        .filter_map(|s| Some((s.implements.as_deref()?, contract(s)?)))
+        .collect();
+
+    let stdlib = stdlib_extern_abstracts();
+    let externs = stdlib.iter().chain(
+        snippets
+            .iter()
+            .filter(|s| s.kind == SnippetKind::ExternAbstract || s.kind == SnippetKind::Extern),
+    );
+
+    // The program's externs replace stdlib externs with the same import
+    let mut bindings = BTreeMap::new();
+    for snippet in externs {
+        let Some(sig) = find_function_signature(snippet) else { continue };
+        let (module, name) = extern_import_name(&snippet.id);
+        let returns = if returns_unit(&sig.returns) { "unit" } else { extern_return_audit_name(sig) };
+        let binding = HostBinding {
+            module: module.to_string(),
+            name: name.to_string(),
+            snippet: snippet.id.clone(),
+            contract: impl_contracts
+                .get(snippet.id.as_str())
+                .cloned()
+                .or_else(|| contract(snippet))
+                .unwrap_or_else(|| snippet.id.clone()),
+            effects: snippet
+                .sections
+                .iter()
+                .filter_map(|section| match section {
+                    Section::Effects(effects) => Some(effects.effects.iter().map(|e| e.name.clone())),
+                    _ => None,
+                })
+                .flatten()
+                .collect(),
+            params: sig
+                .params
+                .iter()
+                .map(|param| HostParam {
+                    name: param.name.clone(),
+                    kind: type_to_extern_param_kind(&param.ty).audit_name().to_string(),
+                })
+                .collect(),
+            returns: returns.to_string(),
+        };
+        bindings.insert(format!("{}.{}", module, name), binding);
+    }
+    bindings.into_values().collect()
+}
+
+/// The `contract` metadata of a snippet
+fn contract(snippet: &Snippet) -> Option<String> {
+    snippet
+        .sections
+        .iter()
+        .find_map(|section| match section {
+            Section::Metadata(metadata) => metadata.entries.iter().find(|e| e.key == "contract"),
+            _ => None,
+        })
+        .map(|entry| entry.value.clone())
+}
+
+/// Whether an extern's import has no result, as for a `Unit` or absent return
+fn returns_unit(returns: &Option<ReturnType>) -> bool {
+    let first = match returns {
+        None => return true,
+        Some(ReturnType::Single { ty, .. }) => ty,
+        Some(ReturnType::Collection { of }) => of,
+        Some(ReturnType::Union { types }) => match types.first() {
+            Some(member) => &member.ty,
+            None => return true,
+        },
+    };
+    matches!(&first.kind, TypeKind::Named(path) if path.name() == "Unit")
+}
+
+/// Helpers shared by every generated import: argument decoding, result
+/// encoding and the capability check
+const PRELUDE: &str = r#"/** Thrown when an extern runs without an effect the host granted */
+export class CapabilityError extends Error {}
+
+/**
+ * Build the imports for a module.
+ *
+ * `impls` maps each contract to a function taking the decoded arguments and
+ * returning the result: a string, an array of strings for lists, a number
+ * for Int and Float, or a boolean. Other fat pointers are passed and returned
+ * as their UTF-8 text. Contracts missing from `impls` fall back to
+ * `STD_IMPLS`. `options.effects` lists the effects the host grants; without
+ * it every effect is granted.
+ *
+ * Call `bind(instance)` once the module is instantiated, before its entry
+ * function; it also runs the module's initialization.
+ */
+export function createHost(impls, options = {}) {
+  const granted = options.effects ? new Set(options.effects) : null;
+  let memory = null;
+  let alloc = (_size) => {
+    throw new Error('[host] Allocation before the module is bound');
+  };
+
+  const readStr = (ptr, len) => new TextDecoder().decode(new Uint8Array(memory.buffer, ptr, len));
+  const writeStr = (s) => {
+    const bytes = new TextEncoder().encode(s);
+    const ptr = alloc(bytes.length);
+    new Uint8Array(memory.buffer, ptr, bytes.length).set(bytes);
+    return (BigInt(ptr) << 32n) | BigInt(bytes.length);
+  };
+  // [count:i32][fat_ptr_1:i64]...[fat_ptr_n:i64]
+  const writeStrArray = (parts) => {
+    const fatPtrs = parts.map((part) => writeStr(encodeText(part)));
+    const size = 4 + fatPtrs.length * 8;
+    const ptr = alloc(size);
+    const view = new DataView(memory.buffer);
+    view.setInt32(ptr, fatPtrs.length, true);
+    fatPtrs.forEach((fatPtr, i) => view.setBigInt64(ptr + 4 + i * 8, fatPtr, true));
+    return (BigInt(ptr) << 32n) | BigInt(size);
+  };
+  const encodeText = (value) => (typeof value === 'string' ? value : JSON.stringify(value));
+  const floatBits = (value) => {
+    const view = new DataView(new ArrayBuffer(8));
+    view.setFloat64(0, value, true);
+    return view.getBigInt64(0, true);
+  };
+
+  const requireEffects = (name, effects) => {
+    if (!granted) return;
+    for (const effect of effects) {
+      if (!granted.has(effect)) {
+        throw new CapabilityError(`[host] ${name} needs the '${effect}' effect, which the host does not grant`);
+      }
+    }
+  };
+  const implementation = (contract) => {
+    const impl = impls[contract] ?? STD_IMPLS[contract];
+    if (typeof impl !== 'function') {
+      throw new Error(`[host] No implementation for contract '${contract}'`);
+    }
+    return impl;
+  };
+"#;
+
+/// The std contracts on Deno's APIs
+const DENO_STD_IMPLS: &str = r#"/** Implementations of the std contracts on Deno's APIs */
+export const STD_IMPLS = {
+  'console.println': (message) => console.log(message),
+  'console.print': (message) => Deno.stdout.writeSync(new TextEncoder().encode(message)),
+  'console.error': (message) => console.error(message),
+  'console.info': (message) => console.info(message),
+  'console.debug': (message) => console.debug(message),
+  'console.warn': (message) => console.warn(message),
+  'fs.read_file': (path) => Deno.readTextFileSync(path),
+  'fs.write_file': (path, content) => Deno.writeTextFileSync(path, content),
+  'fs.mkdir': (path, recursive) => Deno.mkdirSync(path, { recursive }),
+  'fs.read_dir': (path) => [...Deno.readDirSync(path)].filter((entry) => entry.isFile).map((entry) => entry.name),
+  'fs.exists': (path) => {
+    try {
+      Deno.statSync(path);
+      return true;
+    } catch {
+      return false;
+    }
+  },
+  'fs.remove': (path) => Deno.removeSync(path),
+  'fs.stat': (path) => {
+    const info = Deno.statSync(path);
+    return { size: info.size, isFile: info.isFile, isDirectory: info.isDirectory, modified: info.mtime?.getTime() ?? 0 };
+  },
+  'fs.copy': (src, dst) => Deno.copyFileSync(src, dst),
+  'fs.rename': (src, dst) => Deno.renameSync(src, dst),
+};
+
+"#;
+
+/// The std contracts on `node:fs`, which Bun implements too
+const NODE_STD_IMPLS: &str = r#"import * as fs from 'node:fs';
+
+/** Implementations of the std contracts on `node:fs` and `process` */
+export const STD_IMPLS = {
+  'console.println': (message) => console.log(message),
+  'console.print': (message) => process.stdout.write(message),
+  'console.error': (message) => console.error(message),
+  'console.info': (message) => console.info(message),
+  'console.debug': (message) => console.debug(message),
+  'console.warn': (message) => console.warn(message),
+  'fs.read_file': (path) => fs.readFileSync(path, 'utf8'),
+  'fs.write_file': (path, content) => fs.writeFileSync(path, content),
+  'fs.mkdir': (path, recursive) => fs.mkdirSync(path, { recursive }),
+  'fs.read_dir': (path) =>
+    fs.readdirSync(path, { withFileTypes: true }).filter((entry) => entry.isFile()).map((entry) => entry.name),
+  'fs.exists': (path) => fs.existsSync(path),
+  'fs.remove': (path) => fs.rmSync(path),
+  'fs.stat': (path) => {
+    const info = fs.statSync(path);
+    return { size: info.size, isFile: info.isFile(), isDirectory: info.isDirectory(), modified: Math.trunc(info.mtimeMs) };
+  },
+  'fs.copy': (src, dst) => fs.copyFileSync(src, dst),
+  'fs.rename': (src, dst) => fs.renameSync(src, dst),
+};
+
+"#;
+
+/// Words a generated param name cannot be
+const JS_RESERVED: &[&str] = &[
+    "arguments", "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default",
+    "delete", "do", "else", "enum", "eval", "export", "extends", "false", "finally", "for", "function", "if",
+    "implements", "import", "in", "instanceof", "interface", "let", "new", "null", "package", "private",
+    "protected", "public", "return", "static", "super", "switch", "this", "throw", "true", "try", "typeof",
+    "var", "void", "while", "with", "yield",
+];
+
+fn js_param(name: &str) -> String {
+    if JS_RESERVED.contains(&name) { format!("{}_", name) } else { name.to_string() }
+}
+
+pub(crate) fn js_string(text: &str) -> String {
+    serde_json::to_string(text).expect("strings serialize")
+}
+
+/// Generate the host module for `bindings` on `runtime`
+pub fn emit_host(bindings: &[HostBinding], runtime: HostRuntime) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "// Generated by `covenant emit-host --target {}`. Do not edit: regenerate",
+        runtime.as_str()
+    );
+    out.push_str("// it after changing an extern or extern-impl snippet.\n\n");
+    out.push_str(match runtime {
+        HostRuntime::Deno => DENO_STD_IMPLS,
+        HostRuntime::Node | HostRuntime::Bun => NODE_STD_IMPLS,
+    });
+
+    out.push_str("/** Every extern import, with the contract and effects it needs */\n");
+    out.push_str("export const BINDINGS = [\n");
+    for binding in bindings {
+        let effects: Vec<String> = binding.effects.iter().map(|e| js_string(e)).collect();
+        let _ = writeln!(
+            out,
+            "  {{ import: {}, snippet: {}, contract: {}, effects: [{}] }},",
+            js_string(&format!("{}.{}", binding.module, binding.name)),
+            js_string(&binding.snippet),
+            js_string(&binding.contract),
+            effects.join(", "),
+        );
+    }
+    out.push_str("];\n\n");
+
+    out.push_str(PRELUDE);
+    out.push_str("\n  const imports = {\n");
+    let mut modules: BTreeMap<&str, Vec<&HostBinding>> = BTreeMap::new();
+    for binding in bindings {
+        modules.entry(binding.module.as_str()).or_default().push(binding);
+    }
+    for (module, bindings) in modules {
+        let _ = writeln!(out, "    {}: {{", js_string(module));
+        for binding in bindings {
+            emit_import(&mut out, binding);
+        }
+        out.push_str("    },\n");
+    }
+    out.push_str("  };\n\n");
+
+    let _ = write!(
+        out,
+        r#"  return {{
+    imports,
+    bind(instance) {{
+      memory = instance.exports.memory;
+      const covAlloc = instance.exports.{alloc};
+      alloc = (size) => covAlloc(size) >>> 0;
+      instance.exports.{init}?.();
+    }},
+  }};
+}}
+"#,
+        alloc = ALLOC_EXPORT,
+        init = INIT_EXPORT,
+    );
+    out
+}
+
+/// One import: decode the arguments, check effects, call, encode the result
+fn emit_import(out: &mut String, binding: &HostBinding) {
+    let mut wasm_params = Vec::new();
+    let mut args = Vec::new();
+    for param in &binding.params {
+        let name = js_param(&param.name);
+        match param.kind.as_str() {
+            "string" | "fat_pointer" => {
+                wasm_params.push(format!("{}_ptr", param.name));
+                wasm_params.push(format!("{}_len", param.name));
+                args.push(format!("readStr({0}_ptr, {0}_len)", param.name));
+            }
+            "bool" => {
+                args.push(format!("{} !== 0", name));
+                wasm_params.push(name);
+            }
+            _ => {
+                args.push(name.clone());
+                wasm_params.push(name);
+            }
+        }
+    }
+
+    let import = format!("{}.{}", binding.module, binding.name);
+    let effects: Vec<String> = binding.effects.iter().map(|e| js_string(e)).collect();
+    let call = format!("implementation({})({})", js_string(&binding.contract), args.join(", "));
+    let result = match binding.returns.as_str() {
+        "unit" => format!("{};", call),
+        "string" | "fat_pointer" => format!("return writeStr(encodeText({}));", call),
+        "list" => format!("return writeStrArray({});", call),
+        "int" => format!("return BigInt({});", call),
+        "bool" => format!("return {} ? 1n : 0n;", call),
+        "float" => format!("return floatBits({});", call),
+        _ => format!("return {};", call),
+    };
+
+    let _ = writeln!(out, "      {}({}) {{", js_string(&binding.name), wasm_params.join(", "));
+    if !binding.effects.is_empty() {
+        let _ = writeln!(out, "        requireEffects({}, [{}]);", js_string(&import), effects.join(", "));
+    }
+    let _ = writeln!(out, "        {}", result);
+    out.push_str("      },\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::snippets;
+
+    const GREETER: &str = r#"
+snippet id="greet.say" kind="extern-abstract"
+effects
+  effect console
+end
+signature
+  fn name="say"
+    param name="message" type="String"
+    param name="loud" type="Bool"
+    returns type="Int"
+  end
+end
+end
+
+snippet id="greet.say.node" kind="extern-impl" implements="greet.say" platform="node"
+metadata
+  contract="greeter.say@1"
+end
+end
+"#;
+
+    #[test]
+    fn test_bindings_use_the_platform_contract() {
+        let bindings = host_bindings(&snippets(GREETER), "node");
+        let say = bindings.iter().find(|b| b.snippet == "greet.say").expect("bound");
+        assert_eq!((say.module.as_str(), say.name.as_str()), ("greet", "say"));
+        assert_eq!(say.contract, "greeter.say@1");
+        assert_eq!(say.effects, vec!["console"]);
+        let kinds: Vec<&str> = say.params.iter().map(|p| p.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["string", "bool"]);
+        assert_eq!(say.returns, "int");
+
+        // Without an impl for the platform, the snippet ID is the contract
+        let bindings = host_bindings(&snippets(GREETER), "wasi");
+        let say = bindings.iter().find(|b| b.snippet == "greet.say").expect("bound");
+        assert_eq!(say.contract, "greet.say");
+
+        // Stdlib externs are always bound
+        let read = bindings.iter().find(|b| b.snippet == "fs.read_file").expect("stdlib bound");
+        assert_eq!(read.effects, vec!["filesystem"]);
+    }
+
+    #[test]
+    fn test_node_host_marshals_and_checks_effects() {
+        let js = emit_host(&host_bindings(&snippets(GREETER), "node"), HostRuntime::Node);
+        assert!(js.contains("\"say\"(message_ptr, message_len, loud) {"), "{}", js);
+        assert!(js.contains("requireEffects(\"greet.say\", [\"console\"]);"), "{}", js);
+        assert!(js.contains(
+            "return BigInt(implementation(\"greeter.say@1\")(readStr(message_ptr, message_len), loud !== 0));"
+        ), "{}", js);
+        assert!(js.contains("{ import: \"greet.say\", snippet: \"greet.say\", contract: \"greeter.say@1\", effects: [\"console\"] },"));
+    }
+
+    #[test]
+    fn test_std_contracts_use_the_runtime_apis() {
+        let bindings = host_bindings(&snippets(GREETER), "deno");
+        let deno = emit_host(&bindings, HostRuntime::Deno);
+        assert!(deno.starts_with("// Generated by `covenant emit-host --target deno`"));
+        assert!(deno.contains("'fs.read_file': (path) => Deno.readTextFileSync(path),"), "{}", deno);
+        assert!(!deno.contains("node:fs"));
+
+        let bun = emit_host(&bindings, HostRuntime::Bun);
+        assert!(bun.contains("import * as fs from 'node:fs';"));
+        assert!(bun.contains("const impl = impls[contract] ?? STD_IMPLS[contract];"));
+    }
+}
@@ -0,0 +1,173 @@
+//! Module initialization
+//!
+//! Every module exports [`INIT_EXPORT`], which a host calls once after
+//! instantiating it and before calling anything else. It registers each
+//! database snippet with the host as a query target, by the name queries
+//! give in `target=`, its dialect and its connection. A target registers
+//! after the targets it declares a `depends_on` relation to, and otherwise
+//! in source order, so a host can resolve one target's connection through
+//! another (a read replica through its primary, say).
+//!
+//! It is an export rather than the WASM start function because the host's
+//! imports read strings from the module's memory, which a host can only
+//! reach once instantiation has finished. Calling it again does nothing.
+//!
+//! Const snippets need no initialization: each is a module global holding
+//! its value, set when the module is instantiated, so a String or
+//! collection constant sits in the data segment once however often it is
+//! referenced.
+
+use covenant_ast::{RelationKind, Section, Snippet, SnippetKind};
+use wasm_encoder::ValType;
+
+use crate::CodegenError;
+
+/// `_initialize()`: register the module's query targets with the host. The
+/// name follows the WASI reactor convention that hosts already call.
+pub const INIT_EXPORT: &str = "_initialize";
+
+/// `db.register_target(name, dialect, connection)`, each a (ptr, len) String
+pub const REGISTER_TARGET_IMPORT: (&str, &str) = ("db", "register_target");
+
+/// Global set once the module has been initialized
+pub(crate) const INIT_GLOBAL: u32 = 3;
+
+/// First global holding the value of a const snippet
+pub(crate) const FIRST_CONSTANT_GLOBAL: u32 = 4;
+
+/// Relation type ordering one target after another
+const DEPENDS_ON: &str = "depends_on";
+
+/// A database snippet registered with the host as a query target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTarget {
+    /// Snippet ID of the database
+    pub id: String,
+    /// Name queries give in `target=`: the last segment of the snippet ID
+    pub name: String,
+    /// `dialect` from the metadata, or empty
+    pub dialect: String,
+    /// `connection` from the metadata, or empty
+    pub connection: String,
+}
+
+impl QueryTarget {
+    fn from_snippet(snippet: &Snippet) -> Self {
+        let metadata = |key: &str| {
+            snippet
+                .sections
+                .iter()
+                .find_map(|section| match section {
+                    Section::Metadata(metadata) => metadata.entries.iter().find(|e| e.key == key),
+                    _ => None,
+                })
+                .map(|entry| entry.value.clone())
+                .unwrap_or_default()
+        };
+        Self {
+            id: snippet.id.clone(),
+            name: snippet.id.rsplit('.').next().unwrap_or(&snippet.id).to_string(),
+            dialect: metadata("dialect"),
+            connection: metadata("connection"),
+        }
+    }
+
+    /// Signature of the registration import
+    pub(crate) fn import_type() -> (Vec<ValType>, Vec<ValType>) {
+        (vec![ValType::I32; 6], vec![])
+    }
+}
+
+/// The database snippets of `snippets` in registration order: each after
+/// the databases it depends on, otherwise in source order
+pub fn query_targets(snippets: &[Snippet]) -> Result<Vec<QueryTarget>, CodegenError> {
+    let databases: Vec<&Snippet> = snippets.iter().filter(|s| s.kind == SnippetKind::Database).collect();
+    let index_of = |id: &str| databases.iter().position(|s| s.id == id);
+
+    // dependencies[i]: databases that must register before database i.
+    // Relations to anything but another database play no part.
+    let mut dependencies: Vec<Vec<usize>> = vec![Vec::new(); databases.len()];
+    for (i, snippet) in databases.iter().enumerate() {
+        for section in &snippet.sections {
+            let Section::Relations(relations) = section else { continue };
+            for rel in relations.relations.iter().filter(|r| r.rel_type.as_deref() == Some(DEPENDS_ON)) {
+                let Some(other) = index_of(&rel.target) else { continue };
+                match rel.kind {
+                    RelationKind::To => dependencies[i].push(other),
+                    RelationKind::From => dependencies[other].push(i),
+                }
+            }
+        }
+    }
+
+    // Repeatedly take the first database in source order whose
+    // dependencies have all registered
+    let mut registered = vec![false; databases.len()];
+    let mut order = Vec::with_capacity(databases.len());
+    while order.len() < databases.len() {
+        let next = (0..databases.len())
+            .find(|&i| !registered[i] && dependencies[i].iter().all(|&d| registered[d] || d == i));
+        let Some(next) = next else {
+            let snippets = (0..databases.len())
+                .filter(|&i| !registered[i])
+                .map(|i| databases[i].id.clone())
+                .collect();
+            return Err(CodegenError::TargetDependencyCycle { snippets });
+        };
+        registered[next] = true;
+        order.push(QueryTarget::from_snippet(databases[next]));
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::snippets;
+
+    const DATABASES: &str = r#"
+snippet id="db.replica" kind="database"
+metadata
+  dialect="postgres"
+  connection="env:REPLICA_URL"
+end
+relations
+  rel to="db.primary" type=depends_on
+end
+end
+
+snippet id="db.primary" kind="database"
+metadata
+  dialect="postgres"
+  connection="env:PRIMARY_URL"
+end
+end
+
+snippet id="db.audit" kind="database"
+metadata
+  connection="env:AUDIT_URL"
+end
+end
+"#;
+
+    #[test]
+    fn test_targets_register_after_their_dependencies() {
+        let targets = query_targets(&snippets(DATABASES)).unwrap();
+        let names: Vec<&str> = targets.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["primary", "replica", "audit"]);
+        assert_eq!(targets[1].connection, "env:REPLICA_URL");
+        assert_eq!(targets[2].dialect, "");
+    }
+
+    #[test]
+    fn test_target_dependency_cycle() {
+        let source = DATABASES.replace(
+            "  connection=\"env:PRIMARY_URL\"\nend\n",
+            "  connection=\"env:PRIMARY_URL\"\nend\nrelations\n  rel from=\"db.replica\" type=depends_on\n  rel to=\"db.replica\" type=depends_on\nend\n",
+        );
+        match query_targets(&snippets(&source)) {
+            Err(CodegenError::TargetDependencyCycle { snippets }) => assert_eq!(snippets, ["db.replica", "db.primary"]),
+            other => panic!("Expected a cycle, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! AWS Lambda packages
+//!
+//! `covenant package --target lambda` wraps the module in a Node.js handler
+//! for the managed `nodejs20.x` runtime, so no custom bootstrap is needed.
+//! The handler calls one entry function per invocation: each param is read
+//! from the event field of the same name, and a `Json` param named `event`
+//! receives the whole event. The result is returned as the invocation's
+//! JSON response. Like the Worker shim, the handler calls the entry through
+//! the module's reflection table and instantiates the module per
+//! invocation; the compiled module is shared across warm invocations.
+//!
+//! Alongside the handler this generates a SAM template and a Terraform
+//! snippet deploying the package's zip.
+
+use std::fmt::Write;
+
+use covenant_ast::{FunctionSignature, ReturnType, Section, SignatureKind, Snippet, SnippetKind, Type, TypeKind};
+
+use crate::host_bindgen::js_string;
+use crate::{CodegenError, ALLOC_EXPORT, FUNCTIONS_EXPORT, INIT_EXPORT, REFLECT_EXPORT};
+
+/// The handler's file name within the zip
+pub const LAMBDA_SHIM: &str = "index.mjs";
+
+/// The managed runtime the handler targets
+pub const LAMBDA_RUNTIME: &str = "nodejs20.x";
+
+/// Types an entry param can be read from the event as
+const PARAM_TYPES: &[&str] = &["String", "Int", "Bool", "Float", "Json"];
+
+/// Types an entry result can be returned as
+const RETURN_TYPES: &[&str] = &["String", "Int", "Bool", "Float", "Json", "Unit"];
+
+/// The function a Lambda package invokes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LambdaEntry {
+    /// Snippet ID of the entry function
+    pub snippet: String,
+}
+
+/// Find the entry function: the snippet with ID `entry`, or the function
+/// named `main` when no entry is given. Its params and result must map to
+/// event and response JSON.
+pub fn lambda_entry(snippets: &[Snippet], entry: Option<&str>) -> Result<LambdaEntry, CodegenError> {
+    let found = snippets.iter().filter(|s| s.kind == SnippetKind::Function).find(|s| match entry {
+        Some(entry) => s.id == entry,
+        None => function(s).is_some_and(|f| f.name == "main"),
+    });
+    let Some(snippet) = found else {
+        return Err(CodegenError::EntryNotFound { entry: entry.unwrap_or("main").to_string() });
+    };
+    let unsupported = |reason: String| CodegenError::UnsupportedEntry { snippet: snippet.id.clone(), reason };
+
+    let Some(f) = function(snippet) else {
+        return Err(unsupported("it has no function signature".to_string()));
+    };
+    if let Some(param) = f.params.iter().find(|param| !is_named(&param.ty, PARAM_TYPES)) {
+        return Err(unsupported(format!(
+            "param '{}' must be String, Int, Bool, Float or Json to be read from the event",
+            param.name
+        )));
+    }
+    let returns_json = match &f.returns {
+        None => true,
+        Some(ReturnType::Single { ty, .. }) => is_named(ty, RETURN_TYPES),
+        Some(_) => false,
+    };
+    if !returns_json {
+        return Err(unsupported(format!(
+            "function '{}' must return String, Int, Bool, Float, Json or Unit",
+            f.name
+        )));
+    }
+
+    Ok(LambdaEntry { snippet: snippet.id.clone() })
+}
+
+fn function(snippet: &Snippet) -> Option<&FunctionSignature> {
+    snippet.sections.iter().find_map(|section| match section {
+        Section::Signature(sig) => match &sig.kind {
+            SignatureKind::Function(f) => Some(f),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+fn is_named(ty: &Type, names: &[&str]) -> bool {
+    matches!(&ty.kind, TypeKind::Named(path) if names.contains(&path.name()))
+}
+
+/// Invocation handling: event decoding and result encoding
+const LAMBDA_HANDLER: &str = r#"/** The none sentinel for optional results */
+const NONE = -(1n << 63n);
+
+/**
+ * Build the Lambda handler around a compiled module.
+ *
+ * `extraImports` provides imports the handler does not, by module and name,
+ * or is a function of `{ event, context, readStr, writeStr }` returning
+ * them. Any other import throws when called.
+ */
+export function createHandler(module, extraImports = {}) {
+  return async function handler(event, context) {
+    let memory = null;
+    let alloc = (_size) => {
+      throw new Error('[lambda] Allocation before the module is instantiated');
+    };
+    const readStr = (ptr, len) => new TextDecoder().decode(new Uint8Array(memory.buffer, ptr, len));
+    const readFat = (fat) => readStr(Number(BigInt.asUintN(64, fat) >> 32n), Number(fat & 0xffffffffn));
+    const writeStr = (s) => {
+      const bytes = new TextEncoder().encode(s);
+      const ptr = alloc(bytes.length);
+      new Uint8Array(memory.buffer, ptr, bytes.length).set(bytes);
+      return (BigInt(ptr) << 32n) | BigInt(bytes.length);
+    };
+
+    const provided = {
+      mem: { alloc: (size) => alloc(size) },
+      console: {
+        println: (ptr, len) => console.log(readStr(ptr, len)),
+        print: (ptr, len) => console.log(readStr(ptr, len)),
+        error: (ptr, len) => console.error(readStr(ptr, len)),
+        info: (ptr, len) => console.info(readStr(ptr, len)),
+        debug: (ptr, len) => console.debug(readStr(ptr, len)),
+        warn: (ptr, len) => console.warn(readStr(ptr, len)),
+      },
+      db: {
+        register_target: () => {},
+        execute_query: () => {
+          console.error('[lambda] Database queries are not supported yet');
+          return 0;
+        },
+      },
+    };
+    const extra = typeof extraImports === 'function' ? extraImports({ event, context, readStr, writeStr }) : extraImports;
+    const imports = {};
+    for (const { module: name, name: field, kind } of WebAssembly.Module.imports(module)) {
+      if (kind !== 'function') continue;
+      imports[name] ??= {};
+      imports[name][field] = extra[name]?.[field] ?? provided[name]?.[field] ?? (() => {
+        throw new Error(`[lambda] Import '${name}.${field}' is not provided`);
+      });
+    }
+
+    const instance = await WebAssembly.instantiate(module, imports);
+    memory = instance.exports.memory;
+    const covAlloc = instance.exports.__ALLOC__;
+    alloc = (size) => covAlloc(size) >>> 0;
+    instance.exports.__INIT__?.();
+
+    const table = JSON.parse(readFat(instance.exports.__REFLECT__()));
+    const fn = table.functions.find((f) => f.id === ENTRY.snippet);
+    const fields = event !== null && typeof event === 'object' ? event : {};
+    const args = fn.params.map((param) => {
+      const value = param.name === 'event' && param.type === 'Json' ? event : fields[param.name];
+      if (value === undefined) throw new TypeError(`[lambda] Event field '${param.name}' is missing`);
+      switch (param.type) {
+        case 'String':
+          if (typeof value !== 'string') throw new TypeError(`[lambda] Event field '${param.name}' must be a string`);
+          return writeStr(value);
+        case 'Int':
+          if (!Number.isInteger(value) && !(typeof value === 'string' && /^-?[0-9]+$/.test(value))) {
+            throw new TypeError(`[lambda] Event field '${param.name}' must be an integer`);
+          }
+          return BigInt.asIntN(64, BigInt(value));
+        case 'Bool':
+          if (typeof value !== 'boolean') throw new TypeError(`[lambda] Event field '${param.name}' must be true or false`);
+          return value ? 1n : 0n;
+        case 'Float':
+          if (typeof value !== 'number') throw new TypeError(`[lambda] Event field '${param.name}' must be a number`);
+          return value;
+        default:
+          return writeStr(JSON.stringify(value));
+      }
+    });
+
+    const result = instance.exports.__FUNCTIONS__.get(fn.slot)(...args);
+    if (!fn.returns || fn.returns.type === 'Unit') return null;
+    if (fn.returns.type.endsWith('?') && result === NONE) return null;
+    switch (fn.returns.type.replace(/\?$/, '')) {
+      case 'String':
+        return readFat(result);
+      case 'Json':
+        return JSON.parse(readFat(result));
+      case 'Int':
+        return Number.isSafeInteger(Number(result)) ? Number(result) : result.toString();
+      case 'Bool':
+        return Boolean(result);
+      default:
+        return result;
+    }
+  };
+}
+"#;
+
+/// Generate the Lambda handler module, invoking `entry` in the module
+/// `wasm_file` (relative to the handler)
+pub fn emit_lambda(entry: &LambdaEntry, wasm_file: &str) -> String {
+    let mut out = String::new();
+    out.push_str("// Generated by `covenant package --target lambda`. Do not edit: repackage\n");
+    out.push_str("// after changing the entry function's signature.\n\n");
+    out.push_str("import { readFileSync } from 'node:fs';\n\n");
+
+    out.push_str("/** The function each invocation calls */\n");
+    let _ = writeln!(out, "export const ENTRY = {{ snippet: {} }};\n", js_string(&entry.snippet));
+    out.push_str("// Compiled once per execution environment, instantiated per invocation\n");
+    let _ = writeln!(
+        out,
+        "const wasmModule = new WebAssembly.Module(readFileSync(new URL({}, import.meta.url)));\n",
+        js_string(&format!("./{}", wasm_file))
+    );
+
+    out.push_str(
+        &LAMBDA_HANDLER
+            .replace("__ALLOC__", ALLOC_EXPORT)
+            .replace("__INIT__", INIT_EXPORT)
+            .replace("__REFLECT__", REFLECT_EXPORT)
+            .replace("__FUNCTIONS__", FUNCTIONS_EXPORT),
+    );
+    out.push_str("\nexport const handler = createHandler(wasmModule);\n");
+    out
+}
+
+/// Lambda function names are letters, digits, dashes and underscores
+fn function_name(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '-' }).collect()
+}
+
+/// Generate a SAM template deploying the package `zip_file` as the
+/// function `name`
+pub fn emit_sam_template(name: &str, zip_file: &str) -> String {
+    let name = function_name(name);
+    // CloudFormation logical IDs are alphanumeric
+    let logical: String = name.split(['-', '_']).map(capitalize).collect();
+    let mut out = String::new();
+    out.push_str("# Generated by `covenant package --target lambda`. Deploy with\n");
+    out.push_str("# `sam deploy --guided`.\n");
+    out.push_str("AWSTemplateFormatVersion: '2010-09-09'\n");
+    out.push_str("Transform: AWS::Serverless-2016-10-31\n");
+    out.push_str("Resources:\n");
+    let _ = writeln!(out, "  {}Function:", logical);
+    out.push_str("    Type: AWS::Serverless::Function\n");
+    out.push_str("    Properties:\n");
+    let _ = writeln!(out, "      FunctionName: {}", name);
+    let _ = writeln!(out, "      CodeUri: {}", zip_file);
+    let _ = writeln!(out, "      Handler: {}", handler_name());
+    let _ = writeln!(out, "      Runtime: {}", LAMBDA_RUNTIME);
+    out.push_str("      MemorySize: 256\n");
+    out.push_str("      Timeout: 30\n");
+    out
+}
+
+/// Generate a Terraform snippet deploying the package `zip_file` as the
+/// function `name`, with a role that may only write logs
+pub fn emit_terraform(name: &str, zip_file: &str) -> String {
+    let name = function_name(name);
+    // Terraform resource names may not contain dashes in references
+    let resource = name.replace('-', "_");
+    let mut out = String::new();
+    out.push_str("# Generated by `covenant package --target lambda`.\n\n");
+    let _ = writeln!(out, "resource \"aws_iam_role\" \"{}\" {{", resource);
+    let _ = writeln!(out, "  name = {}", js_string(&format!("{}-role", name)));
+    out.push_str("  assume_role_policy = jsonencode({\n");
+    out.push_str("    Version = \"2012-10-17\"\n");
+    out.push_str("    Statement = [{\n");
+    out.push_str("      Action    = \"sts:AssumeRole\"\n");
+    out.push_str("      Effect    = \"Allow\"\n");
+    out.push_str("      Principal = { Service = \"lambda.amazonaws.com\" }\n");
+    out.push_str("    }]\n");
+    out.push_str("  })\n");
+    out.push_str("}\n\n");
+    let _ = writeln!(out, "resource \"aws_iam_role_policy_attachment\" \"{}_logs\" {{", resource);
+    let _ = writeln!(out, "  role       = aws_iam_role.{}.name", resource);
+    out.push_str("  policy_arn = \"arn:aws:iam::aws:policy/service-role/AWSLambdaBasicExecutionRole\"\n");
+    out.push_str("}\n\n");
+    let _ = writeln!(out, "resource \"aws_lambda_function\" \"{}\" {{", resource);
+    let _ = writeln!(out, "  function_name    = {}", js_string(&name));
+    let _ = writeln!(out, "  filename         = {}", js_string(zip_file));
+    let _ = writeln!(out, "  source_code_hash = filebase64sha256({})", js_string(zip_file));
+    let _ = writeln!(out, "  handler          = {}", js_string(&handler_name()));
+    let _ = writeln!(out, "  runtime          = {}", js_string(LAMBDA_RUNTIME));
+    let _ = writeln!(out, "  role             = aws_iam_role.{}.arn", resource);
+    out.push_str("  memory_size      = 256\n");
+    out.push_str("  timeout          = 30\n");
+    out.push_str("}\n");
+    out
+}
+
+/// The handler setting: the shim's module name and its export
+fn handler_name() -> String {
+    format!("{}.handler", LAMBDA_SHIM.trim_end_matches(".mjs"))
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::snippets;
+
+    const HANDLERS: &str = r#"
+snippet id="app.main" kind="fn"
+signature
+  fn name="main"
+    param name="name" type="String"
+    param name="count" type="Int"
+    returns type="Json"
+  end
+end
+end
+
+snippet id="app.users" kind="fn"
+signature
+  fn name="users"
+    param name="ids" type="List<Int>"
+    returns type="Int"
+  end
+end
+end
+"#;
+
+    #[test]
+    fn test_entry_maps_to_the_handler() {
+        let snippets = snippets(HANDLERS);
+        let entry = lambda_entry(&snippets, None).unwrap();
+        assert_eq!(entry.snippet, "app.main");
+
+        let shim = emit_lambda(&entry, "app.wasm");
+        assert!(shim.contains("export const ENTRY = { snippet: \"app.main\" };"));
+        assert!(shim.contains("readFileSync(new URL(\"./app.wasm\", import.meta.url))"));
+        assert!(shim.contains("instance.exports.cov_reflect()"));
+        assert!(shim.ends_with("export const handler = createHandler(wasmModule);\n"));
+
+        assert!(matches!(lambda_entry(&snippets, Some("app.missing")), Err(CodegenError::EntryNotFound { .. })));
+        assert!(matches!(
+            lambda_entry(&snippets, Some("app.users")),
+            Err(CodegenError::UnsupportedEntry { snippet, .. }) if snippet == "app.users"
+        ));
+    }
+
+    #[test]
+    fn test_deployment_templates() {
+        let sam = emit_sam_template("my_api", "my_api.zip");
+        assert!(sam.contains("  MyApiFunction:\n    Type: AWS::Serverless::Function\n"));
+        assert!(sam.contains("      CodeUri: my_api.zip\n      Handler: index.handler\n      Runtime: nodejs20.x\n"));
+
+        let terraform = emit_terraform("my.api", "my.api.zip");
+        assert!(terraform.contains("resource \"aws_lambda_function\" \"my_api\" {"));
+        assert!(terraform.contains("  function_name    = \"my-api\"\n"));
+        assert!(terraform.contains("  source_code_hash = filebase64sha256(\"my.api.zip\")\n"));
+        assert!(terraform.contains("  role             = aws_iam_role.my_api.arn\n"));
+    }
+}
@@ -5,14 +5,40 @@
 mod ir;
 mod wasm;
 mod snippet_wasm;
+#[cfg(test)]
+mod test_support;
+pub mod audit;
+pub mod container;
 pub mod data_graph;
 pub mod embeddable;
+pub mod events;
+pub mod features;
 pub mod gai_codegen;
+pub mod host_bindgen;
+pub mod init;
+pub mod lambda;
+pub mod memory;
+pub mod reflection;
+pub mod worker;
 
 pub use ir::*;
 pub use wasm::*;
 pub use snippet_wasm::SnippetWasmCompiler;
-pub use embeddable::{EmbeddableSymbol, build_embeddable_symbols};
+pub use embeddable::{EmbeddableSymbol, EmbeddedMetadata, build_embeddable_symbols};
+pub use audit::{AuditExtern, AuditSite, AuditTable, AUDIT_SECTION, AUDIT_SITE_EXPORT};
+pub use container::{emit_containerfile, CONTAINERFILE, DEFAULT_BASE_IMAGE, IMAGE_APP_DIR};
+pub use events::{EventTable, Subscription, EVENTS_SECTION};
+pub use features::{BuildInfo, WasmFeature, WasmFeatures, BUILD_INFO_SECTION};
+pub use host_bindgen::{emit_host, host_bindings, HostBinding, HostParam, HostRuntime};
+pub use init::{query_targets, QueryTarget, INIT_EXPORT};
+pub use lambda::{
+    emit_lambda, emit_sam_template, emit_terraform, lambda_entry, LambdaEntry, LAMBDA_RUNTIME, LAMBDA_SHIM,
+};
+pub use memory::{MemoryLimits, ALLOC_EXPORT, RESET_EXPORT, STATS_EXPORT};
+pub use reflection::{FunctionInfo, ParamInfo, ReflectionTable, TypeInfo, FUNCTIONS_EXPORT, REFLECT_EXPORT};
+pub use worker::{
+    emit_worker, emit_wrangler_toml, worker_routes, WorkerBinding, WorkerBindingKind, WorkerRoute, WORKER_SHIM,
+};
 
 use covenant_ast::Program;
 use covenant_checker::SymbolTable;
@@ -37,6 +63,27 @@ pub enum CodegenError {
 
     #[error("serialization failed: {0}")]
     SerializationFailed(String),
+
+    #[error("'{kind}' step outside of a loop")]
+    LoopControlOutsideLoop { kind: String },
+
+    #[error("cannot call '{name}' with {arity} argument(s): no lambda takes that many")]
+    UnsupportedClosureCall { name: String, arity: usize },
+
+    #[error("static data needs {needed} memory pages, more than the limit of {max}")]
+    MemoryLimitTooLow { needed: u32, max: u32 },
+
+    #[error("database targets depend on each other in a cycle: {}", snippets.join(", "))]
+    TargetDependencyCycle { snippets: Vec<String> },
+
+    #[error("entry function '{entry}' not found")]
+    EntryNotFound { entry: String },
+
+    #[error("'{snippet}' cannot be an entry point: {reason}")]
+    UnsupportedEntry { snippet: String, reason: String },
+
+    #[error("cannot match '{on}' by type: union values carry no runtime tag to dispatch on")]
+    UnsupportedTypeMatch { on: String },
 }
 
 /// Compile a program to WASM
@@ -58,7 +105,8 @@ pub fn compile_pure(program: &Program, symbols: &SymbolTable) -> Result<Vec<u8>,
     compile(program, symbols)
 }
 
-/// Compile a program to WASM with embedded symbol metadata
+/// Compile a program to WASM with embedded symbol metadata, using only the
+/// given proposals beyond the MVP and growing memory only up to `memory`
 ///
 /// This function builds embeddable symbols from the SymbolGraph and EffectCheckResult,
 /// then embeds them as JSON in the WASM data section alongside the normal data graph.
@@ -67,6 +115,8 @@ pub fn compile_with_symbols(
     symbols: &SymbolTable,
     symbol_graph: &covenant_symbols::SymbolGraph,
     effect_result: &covenant_checker::EffectCheckResult,
+    features: WasmFeatures,
+    memory: MemoryLimits,
 ) -> Result<Vec<u8>, CodegenError> {
     match program {
         Program::Legacy { declarations, .. } => {
@@ -76,7 +126,9 @@ pub fn compile_with_symbols(
         }
         Program::Snippets { snippets, .. } => {
             let embeddable = build_embeddable_symbols(symbol_graph, effect_result);
-            let mut compiler = SnippetWasmCompiler::new(symbols);
+            let mut compiler = SnippetWasmCompiler::new(symbols)
+                .with_features(features)
+                .with_memory_limits(memory);
             compiler.compile_snippets_with_symbols(snippets, &embeddable)
         }
     }
@@ -0,0 +1,237 @@
+//! Linear memory limits and the heap allocator
+//!
+//! Every module gets a bump allocator over its linear memory. Compiled code
+//! and the host both allocate through it: the host calls the exported
+//! [`ALLOC_EXPORT`] to place strings it returns to the module. The heap
+//! starts after the data segment and grows the memory as needed up to
+//! [`MemoryLimits::max_pages`]; an allocation past the limit traps, so a
+//! host can cap the memory of each instance by compiling with a lower limit.
+//!
+//! [`STATS_EXPORT`] reports the bytes allocated and the peak, and
+//! [`RESET_EXPORT`] frees the whole heap, for hosts that reuse an instance
+//! across requests.
+
+use wasm_encoder::{Function, Instruction, ValType};
+
+/// Bytes in a WebAssembly page
+pub const PAGE_SIZE: u32 = 65536;
+
+/// Pages in a full 32-bit memory (4 GiB)
+pub const MAX_PAGES: u32 = 65536;
+
+/// Pages a module may grow to unless a limit is given (16 MiB)
+pub const DEFAULT_MAX_PAGES: u32 = 256;
+
+/// Pages a module starts with (1 MiB), or fewer under a lower limit
+pub const INITIAL_PAGES: u32 = 16;
+
+/// `cov_alloc(size: i32) -> i32`: allocate `size` bytes, 8-byte aligned
+pub const ALLOC_EXPORT: &str = "cov_alloc";
+
+/// `cov_memory_stats() -> i64`: bytes allocated in the high 32 bits, the
+/// peak in the low 32 bits
+pub const STATS_EXPORT: &str = "cov_memory_stats";
+
+/// `cov_heap_reset()`: free every allocation. Pointers into the heap are
+/// invalid afterwards.
+pub const RESET_EXPORT: &str = "cov_heap_reset";
+
+/// Global holding the address of the next allocation
+pub(crate) const HEAP_GLOBAL: u32 = 0;
+
+/// Global holding the most heap bytes allocated at once
+pub(crate) const PEAK_GLOBAL: u32 = 2;
+
+/// How large a module's memory may grow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLimits {
+    /// Most pages the memory may grow to
+    pub max_pages: u32,
+}
+
+impl MemoryLimits {
+    /// Limit memory to `max_pages` pages, from 1 to [`MAX_PAGES`]
+    pub fn with_max_pages(max_pages: u32) -> Result<Self, String> {
+        if !(1..=MAX_PAGES).contains(&max_pages) {
+            return Err(format!(
+                "Invalid memory limit of {} pages. The limit must be from 1 to {} pages of 64 KiB.",
+                max_pages, MAX_PAGES
+            ));
+        }
+        Ok(Self { max_pages })
+    }
+
+    /// Pages the memory starts with when the data segment takes `data_len`
+    /// bytes, or `None` if the data does not fit under the limit
+    pub fn initial_pages(&self, data_len: u32) -> Option<u32> {
+        let data_pages = data_len.div_ceil(PAGE_SIZE);
+        (data_pages <= self.max_pages).then(|| data_pages.max(INITIAL_PAGES.min(self.max_pages)))
+    }
+}
+
+impl Default for MemoryLimits {
+    fn default() -> Self {
+        Self { max_pages: DEFAULT_MAX_PAGES }
+    }
+}
+
+/// Function indices of the allocator
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Allocator {
+    pub alloc: u32,
+    pub stats: u32,
+    pub reset: u32,
+}
+
+impl Allocator {
+    /// Number of allocator functions
+    pub const FUNCTION_COUNT: u32 = 3;
+
+    /// The allocator functions at `base` and the indices after it
+    pub fn at(base: u32) -> Self {
+        Self { alloc: base, stats: base + 1, reset: base + 2 }
+    }
+
+    /// Signatures of the allocator functions, in index order
+    pub fn function_types() -> [(Vec<ValType>, Vec<ValType>); 3] {
+        [
+            (vec![ValType::I32], vec![ValType::I32]),
+            (vec![], vec![ValType::I64]),
+            (vec![], vec![]),
+        ]
+    }
+
+    /// Bodies of the allocator functions, in index order, for a heap
+    /// starting at `heap_start`
+    pub fn functions(heap_start: u32) -> [Function; 3] {
+        [gen_alloc(heap_start), gen_stats(heap_start), gen_reset(heap_start)]
+    }
+}
+
+/// The first heap address after `data_len` bytes of data
+pub(crate) fn heap_start(data_len: u32) -> u32 {
+    data_len.next_multiple_of(8)
+}
+
+/// `cov_alloc(size: i32) -> i32`
+fn gen_alloc(heap_start: u32) -> Function {
+    // Param: local 0 = size; local 1 = result, local 2 = end of the allocation
+    let mut func = Function::new(vec![(2, ValType::I32)]);
+
+    // size = (size + 7) & ~7
+    func.instruction(&Instruction::LocalGet(0));
+    func.instruction(&Instruction::I32Const(7));
+    func.instruction(&Instruction::I32Add);
+    func.instruction(&Instruction::I32Const(-8));
+    func.instruction(&Instruction::I32And);
+    func.instruction(&Instruction::LocalSet(0));
+
+    // result = heap; end = result + size, trapping if it wraps
+    func.instruction(&Instruction::GlobalGet(HEAP_GLOBAL));
+    func.instruction(&Instruction::LocalTee(1));
+    func.instruction(&Instruction::LocalGet(0));
+    func.instruction(&Instruction::I32Add);
+    func.instruction(&Instruction::LocalTee(2));
+    func.instruction(&Instruction::LocalGet(1));
+    func.instruction(&Instruction::I32LtU);
+    func.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+    func.instruction(&Instruction::Unreachable);
+    func.instruction(&Instruction::End);
+
+    // Grow the memory when end passes it, in i64 so a full memory's size
+    // does not wrap. Growing past the maximum fails, and the allocation traps.
+    func.instruction(&Instruction::LocalGet(2));
+    func.instruction(&Instruction::I64ExtendI32U);
+    func.instruction(&Instruction::MemorySize(0));
+    func.instruction(&Instruction::I64ExtendI32U);
+    func.instruction(&Instruction::I64Const(16));
+    func.instruction(&Instruction::I64Shl);
+    func.instruction(&Instruction::I64GtU);
+    func.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+    func.instruction(&Instruction::LocalGet(2));
+    func.instruction(&Instruction::I64ExtendI32U);
+    func.instruction(&Instruction::I64Const(PAGE_SIZE as i64 - 1));
+    func.instruction(&Instruction::I64Add);
+    func.instruction(&Instruction::I64Const(16));
+    func.instruction(&Instruction::I64ShrU);
+    func.instruction(&Instruction::I32WrapI64);
+    func.instruction(&Instruction::MemorySize(0));
+    func.instruction(&Instruction::I32Sub);
+    func.instruction(&Instruction::MemoryGrow(0));
+    func.instruction(&Instruction::I32Const(-1));
+    func.instruction(&Instruction::I32Eq);
+    func.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+    func.instruction(&Instruction::Unreachable);
+    func.instruction(&Instruction::End);
+    func.instruction(&Instruction::End);
+
+    func.instruction(&Instruction::LocalGet(2));
+    func.instruction(&Instruction::GlobalSet(HEAP_GLOBAL));
+
+    // peak = max(peak, end - heap_start)
+    func.instruction(&Instruction::LocalGet(2));
+    func.instruction(&Instruction::I32Const(heap_start as i32));
+    func.instruction(&Instruction::I32Sub);
+    func.instruction(&Instruction::LocalTee(2));
+    func.instruction(&Instruction::GlobalGet(PEAK_GLOBAL));
+    func.instruction(&Instruction::I32GtU);
+    func.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+    func.instruction(&Instruction::LocalGet(2));
+    func.instruction(&Instruction::GlobalSet(PEAK_GLOBAL));
+    func.instruction(&Instruction::End);
+
+    func.instruction(&Instruction::LocalGet(1));
+    func.instruction(&Instruction::End);
+    func
+}
+
+/// `cov_memory_stats() -> i64`
+fn gen_stats(heap_start: u32) -> Function {
+    let mut func = Function::new(vec![]);
+
+    // ((heap - heap_start) << 32) | peak
+    func.instruction(&Instruction::GlobalGet(HEAP_GLOBAL));
+    func.instruction(&Instruction::I32Const(heap_start as i32));
+    func.instruction(&Instruction::I32Sub);
+    func.instruction(&Instruction::I64ExtendI32U);
+    func.instruction(&Instruction::I64Const(32));
+    func.instruction(&Instruction::I64Shl);
+    func.instruction(&Instruction::GlobalGet(PEAK_GLOBAL));
+    func.instruction(&Instruction::I64ExtendI32U);
+    func.instruction(&Instruction::I64Or);
+    func.instruction(&Instruction::End);
+    func
+}
+
+/// `cov_heap_reset()`; the peak is kept
+fn gen_reset(heap_start: u32) -> Function {
+    let mut func = Function::new(vec![]);
+    func.instruction(&Instruction::I32Const(heap_start as i32));
+    func.instruction(&Instruction::GlobalSet(HEAP_GLOBAL));
+    func.instruction(&Instruction::End);
+    func
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_limits_range() {
+        assert_eq!(MemoryLimits::default().max_pages, DEFAULT_MAX_PAGES);
+        assert!(MemoryLimits::with_max_pages(MAX_PAGES).is_ok());
+        assert!(MemoryLimits::with_max_pages(0).unwrap_err().contains("from 1 to 65536"));
+        assert!(MemoryLimits::with_max_pages(MAX_PAGES + 1).is_err());
+    }
+
+    #[test]
+    fn test_initial_pages_fit_the_data() {
+        let limits = MemoryLimits::default();
+        assert_eq!(limits.initial_pages(0), Some(INITIAL_PAGES));
+        assert_eq!(limits.initial_pages(20 * PAGE_SIZE + 1), Some(21));
+
+        let small = MemoryLimits::with_max_pages(4).unwrap();
+        assert_eq!(small.initial_pages(100), Some(4));
+        assert_eq!(small.initial_pages(4 * PAGE_SIZE + 1), None);
+    }
+}
@@ -0,0 +1,127 @@
+//! Runtime reflection over a module's functions
+//!
+//! Every module exports [`REFLECT_EXPORT`], which returns a fat pointer
+//! (`offset << 32 | len`) to a JSON [`ReflectionTable`] describing each
+//! exported function: its name, snippet ID, the Covenant and WASM types of
+//! its params and result, and its slot in the exported function table
+//! [`FUNCTIONS_EXPORT`]. A host can list a module's functions and call one
+//! through `table.get(slot)` without knowing its exports ahead of time.
+//!
+//! The embedded symbol metadata describes the program for tools; this table
+//! only describes how to call it, and is present even when symbols are not
+//! embedded.
+
+use covenant_ast::printer::ToCov;
+use covenant_ast::ReturnType;
+use serde::{Deserialize, Serialize};
+use wasm_encoder::ValType;
+
+/// `cov_reflect() -> i64`: fat pointer to the reflection table's JSON
+pub const REFLECT_EXPORT: &str = "cov_reflect";
+
+/// The module's funcref table. Lifted lambdas take the first slots and the
+/// exported functions follow, at the slots the reflection table gives.
+pub const FUNCTIONS_EXPORT: &str = "cov_functions";
+
+/// Version of the reflection table's JSON
+pub const REFLECTION_VERSION: u32 = 1;
+
+/// The exported functions of a module, in export order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReflectionTable {
+    pub version: u32,
+    pub functions: Vec<FunctionInfo>,
+}
+
+/// How to call one exported function
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionInfo {
+    /// Export name
+    pub name: String,
+    /// Snippet ID (e.g. "math.factorial")
+    pub id: String,
+    /// Slot in the exported function table
+    pub slot: u32,
+    /// WASM params in order; Unit params take none
+    pub params: Vec<ParamInfo>,
+    /// The result, or `None` for Unit
+    pub returns: Option<TypeInfo>,
+}
+
+/// A param of an exported function
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamInfo {
+    pub name: String,
+    #[serde(flatten)]
+    pub ty: TypeInfo,
+}
+
+/// A value's Covenant type and the WASM type carrying it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeInfo {
+    /// Covenant type as written in source (e.g. "Int", "String?", "User[]")
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// "i64" or "f64"; Strings, lists and structs are i64 fat pointers
+    pub wasm: String,
+}
+
+impl TypeInfo {
+    pub(crate) fn new(ty: String, wasm: ValType) -> Self {
+        let wasm = match wasm {
+            ValType::I32 => "i32",
+            ValType::I64 => "i64",
+            ValType::F32 => "f32",
+            ValType::F64 => "f64",
+            ValType::V128 => "v128",
+            ValType::Ref(_) => "ref",
+        };
+        Self { ty, wasm: wasm.to_string() }
+    }
+}
+
+/// The Covenant text of a return type
+pub(crate) fn return_type_text(ret: &ReturnType) -> String {
+    match ret {
+        ReturnType::Single { ty, optional: true } => format!("{}?", ty.to_cov(0)),
+        ReturnType::Single { ty, optional: false } => ty.to_cov(0),
+        ReturnType::Collection { of } => format!("{}[]", of.to_cov(0)),
+        ReturnType::Union { types } => types
+            .iter()
+            .map(|member| {
+                let text = member.ty.to_cov(0);
+                if member.optional { format!("{}?", text) } else { text }
+            })
+            .collect::<Vec<_>>()
+            .join(" | "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use covenant_ast::{Span, Type, TypeKind, TypePath, UnionMember};
+
+    fn named(name: &str) -> Type {
+        Type { kind: TypeKind::Named(TypePath::simple(name, Span::default())), span: Span::default() }
+    }
+
+    #[test]
+    fn test_return_type_text() {
+        assert_eq!(return_type_text(&ReturnType::Single { ty: named("Int"), optional: true }), "Int?");
+        assert_eq!(return_type_text(&ReturnType::Collection { of: named("User") }), "User[]");
+        let union = ReturnType::Union {
+            types: vec![
+                UnionMember { ty: named("User"), optional: false },
+                UnionMember { ty: named("DbError"), optional: true },
+            ],
+        };
+        assert_eq!(return_type_text(&union), "User | DbError?");
+    }
+
+    #[test]
+    fn test_params_serialize_flat() {
+        let param = ParamInfo { name: "n".into(), ty: TypeInfo::new("Int".into(), ValType::I64) };
+        assert_eq!(serde_json::to_value(&param).unwrap(), serde_json::json!({"name": "n", "type": "Int", "wasm": "i64"}));
+    }
+}
@@ -9,21 +9,31 @@
 
 use std::collections::HashMap;
 use wasm_encoder::{
-    BlockType, CodeSection, DataSection, ExportKind, ExportSection, Function, FunctionSection,
-    GlobalSection, GlobalType, ImportSection, Instruction, MemArg, MemorySection, MemoryType,
-    Module, TypeSection, ValType,
+    BlockType, CodeSection, DataSection, ElementSection, Elements, ExportKind, ExportSection,
+    Function, FunctionSection, GlobalSection, GlobalType, ImportSection, Instruction, MemArg,
+    CustomSection, MemorySection, MemoryType, Module, RefType, TableSection, TableType, TypeSection, ValType,
 };
 use covenant_ast::{
-    BindSource, BindStep, CallStep, ComputeStep, Condition, EffectsSection, ForStep, FunctionSignature,
-    InputSource, IfStep, Literal, MatchPattern, MatchStep, Operation, OrderClause, QueryContent,
-    QueryStep, ReturnStep, ReturnType, ReturnValue, Section, SignatureKind, Snippet, SnippetKind,
+    BindSource, BindStep, CallArg, CallStep, ComputeStep, Condition, EffectsSection, ForStep, FunctionSignature,
+    Input, InputSource, IfStep, InvariantCheck, LambdaStep, Literal, MatchPattern, MatchStep, Operation, OrderClause, QueryContent,
+    QueryStep, ReturnStep, ReturnType, ReturnValue, Section, Sensitive, SignatureKind, Snippet, SnippetKind,
     SnippetOrderDirection, Step, StepKind, StructConstruction, TraverseDepth, TraverseDirection,
-    TraverseStep, Type, TypeKind,
+    Span, TraverseStep, Type, TypeKind, UsingStep, Visitor, walk_step,
 };
+use covenant_ast::printer::ToCov;
 use covenant_checker::SymbolTable;
 use crate::CodegenError;
+use crate::audit::{AuditExtern, AuditSite, AuditTable, AUDIT_SECTION, AUDIT_SITE_EXPORT};
+use crate::events::{EventTable, EVENTS_SECTION};
+use crate::features::{BuildInfo, WasmFeature, WasmFeatures, BUILD_INFO_SECTION};
 use crate::data_graph::DataGraph;
 use crate::gai_codegen::{self, GraphLayout, GaiFunctionIndices, GAI_FUNCTION_COUNT};
+use crate::init::{self, QueryTarget, FIRST_CONSTANT_GLOBAL, INIT_EXPORT, INIT_GLOBAL, REGISTER_TARGET_IMPORT};
+use crate::memory::{self, Allocator, MemoryLimits, ALLOC_EXPORT, PEAK_GLOBAL, RESET_EXPORT, STATS_EXPORT};
+use crate::reflection::{
+    return_type_text, FunctionInfo, ParamInfo, ReflectionTable, TypeInfo, FUNCTIONS_EXPORT, REFLECTION_VERSION,
+    REFLECT_EXPORT,
+};
 
 // ===== Memory Layout Types =====
 
@@ -216,8 +226,31 @@ pub struct SnippetWasmCompiler<'a> {
     data_segment: DataSegmentBuilder,
     /// Struct layouts by type name
     struct_layouts: HashMap<String, StructLayout>,
+    /// Values of const snippets by snippet ID
+    constants: HashMap<String, Literal>,
+    /// Global holding each const snippet's value, by snippet ID
+    constant_globals: HashMap<String, u32>,
+    /// Target types of alias snippets by alias name, represented as the target
+    type_aliases: HashMap<String, Type>,
+    /// Field types of newtype structs by type name; a newtype value is its
+    /// field's value, with no struct allocated
+    newtypes: HashMap<String, Type>,
+    /// Unit aliases from types sections, represented as their base value
+    unit_types: std::collections::HashSet<String>,
     /// Maps local variable names to their struct type name (for field access)
     local_types: HashMap<String, String>,
+    /// Invariant checks by struct type name, run after each construct step
+    invariants: HashMap<String, Vec<InvariantCheck>>,
+    /// Number of WASM labels (block/loop/if) open around the current step
+    label_depth: u32,
+    /// Label depths of the (break, continue) targets of enclosing for loops, innermost last
+    loop_labels: Vec<(u32, u32)>,
+    /// Resources held by enclosing using steps, outermost first
+    usings: Vec<HeldResource>,
+    /// Lambdas lifted into their own functions
+    lambdas: LambdaTable,
+    /// Locals bound to closures created by lambda steps
+    closure_locals: std::collections::HashSet<String>,
     /// Runtime function indices (set after imports are processed)
     runtime: RuntimeFunctions,
     /// Generic extern-abstract imports: snippet ID → ExternImport
@@ -232,6 +265,109 @@ pub struct SnippetWasmCompiler<'a> {
     symbol_metadata_offset: Option<u32>,
     /// Symbol metadata JSON length in bytes
     symbol_metadata_len: Option<u32>,
+    /// Effectful externs and their call sites, embedded for host audit logs
+    audit: AuditTable,
+    /// Sensitive fields and params of the program, redacted from the audit table's arguments
+    sensitive: Sensitive,
+    /// Snippet ID of the function being compiled
+    current_snippet: String,
+    /// ID of the step being compiled
+    current_step: String,
+    /// Proposals beyond the MVP the module may use
+    features: WasmFeatures,
+    /// The v128 scratch local of the function being compiled, declared when
+    /// SIMD is on and the function uses numeric vector operations
+    vector_local: Option<u32>,
+    /// How large the memory may grow
+    memory_limits: MemoryLimits,
+    /// Function indices of the heap allocator
+    allocator: Allocator,
+    /// Database targets the init function registers, in order
+    targets: Vec<QueryTarget>,
+    /// Function index of the init function
+    init: u32,
+    /// Exported functions in table slot order, after the lambdas
+    reflected_functions: Vec<u32>,
+    /// The reflection table's JSON, placed in the data segment after the
+    /// function bodies so their strings keep the low offsets
+    reflection_json: Vec<u8>,
+    /// Offset and length of the reflection table's JSON in the data segment
+    reflection_table: (u32, u32),
+    /// Function index of the reflection function
+    reflect: u32,
+}
+
+/// Global holding the index of the audited call site about to run
+const AUDIT_SITE_GLOBAL: u32 = 1;
+
+/// Lambda steps lifted out of function bodies into WASM functions that are
+/// called indirectly through a funcref table.
+///
+/// A lifted function takes the closure pointer followed by the lambda's
+/// arguments (all i64) and returns an i64; its table slot equals its position
+/// in `arities`.
+#[derive(Debug, Default)]
+struct LambdaTable {
+    /// Table slot of each lambda step, keyed by the step's address
+    slots: HashMap<usize, u32>,
+    /// Parameter count per slot
+    arities: Vec<usize>,
+    /// Lifted function per slot, filled in as enclosing functions compile
+    bodies: Vec<Option<Function>>,
+    /// Type index of the closure type with no arguments; arity `n` is at `type_base + n`
+    type_base: u32,
+}
+
+impl LambdaTable {
+    fn slot_key(lambda: &LambdaStep) -> usize {
+        lambda as *const LambdaStep as usize
+    }
+
+    fn max_arity(&self) -> Option<usize> {
+        self.arities.iter().copied().max()
+    }
+}
+
+/// A resource acquired by an enclosing using step, released on every exit
+#[derive(Debug, Clone)]
+struct HeldResource {
+    /// ID of the using step, recorded as the audit site of the release call
+    step: String,
+    /// Binding holding the resource
+    var: String,
+    /// Function called with the resource to release it
+    release: String,
+    /// Number of enclosing for loops when the resource was acquired
+    loop_level: usize,
+}
+
+/// What an Int add/sub/mul produces when the result does not fit in 64 bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Overflow {
+    /// Trap, like division by zero
+    Trap,
+    /// Clamp to the Int bound in the direction of the overflow
+    Saturate,
+    /// Produce none (the i64::MIN sentinel)
+    Checked,
+}
+
+/// The runtime value of a literal: an i64, or an f64 for Floats
+#[derive(Debug, Clone, Copy)]
+enum LiteralValue {
+    I64(i64),
+    F64(f64),
+}
+
+/// Add the audit site global; -1 until the first audited call
+fn add_audit_site_global(globals: &mut GlobalSection) {
+    globals.global(
+        GlobalType {
+            val_type: ValType::I32,
+            mutable: true,
+        },
+        &wasm_encoder::ConstExpr::i32_const(-1),
+    );
 }
 
 /// Describes a registered extern-abstract import
@@ -245,7 +381,7 @@ struct ExternImport {
 
 /// How an extern parameter maps to WASM calling convention
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum ExternParamKind {
+pub(crate) enum ExternParamKind {
     /// String: i64 fat pointer on stack, unpacked to (i32 ptr, i32 len) for host call
     String,
     /// Int: i64 on stack, wrapped to i32 for host call
@@ -256,6 +392,18 @@ enum ExternParamKind {
     FatPointer,
 }
 
+impl ExternParamKind {
+    /// Name of the calling convention in the audit table
+    pub(crate) fn audit_name(self) -> &'static str {
+        match self {
+            ExternParamKind::String => "string",
+            ExternParamKind::Int => "int",
+            ExternParamKind::Bool => "bool",
+            ExternParamKind::FatPointer => "fat_pointer",
+        }
+    }
+}
+
 /// Runtime function indices for core operations
 #[derive(Debug, Default, Clone)]
 struct RuntimeFunctions {
@@ -263,8 +411,26 @@ struct RuntimeFunctions {
     mem_alloc: Option<u32>,
     /// Database query execution: db.execute_query(sql_ptr, sql_len, param_count) -> result_ptr
     db_execute_query: Option<u32>,
+    /// Query target registration, called by the init function:
+    /// db.register_target(name_ptr, name_len, dialect_ptr, dialect_len, conn_ptr, conn_len)
+    db_register_target: Option<u32>,
     /// HTTP fetch: http.fetch(url_ptr, url_len) -> response_ptr
     http_fetch: Option<u32>,
+    /// Regex ops: regex.match / regex.find_all / regex.capture / regex.replace, registered
+    /// only when a body uses them
+    regex_match: Option<u32>,
+    regex_find_all: Option<u32>,
+    regex_capture: Option<u32>,
+    regex_replace: Option<u32>,
+    /// Encoding ops: encoding.base64_encode, encoding.hex_decode, ..., by
+    /// op, registered only when a body uses them
+    encoding: HashMap<Operation, u32>,
+    /// Decimal and BigInt ops: decimal.add, bigint.cmp, ..., by op,
+    /// registered only when a body uses them
+    exact: HashMap<Operation, u32>,
+    /// Json ops: json_doc.parse, json_doc.get, ..., by op, registered only
+    /// when a body uses them
+    json: HashMap<Operation, u32>,
 }
 
 impl<'a> SnippetWasmCompiler<'a> {
@@ -277,7 +443,18 @@ impl<'a> SnippetWasmCompiler<'a> {
             imports: ImportTracker::new(),
             data_segment: DataSegmentBuilder::new(),
             struct_layouts: HashMap::new(),
+            constants: HashMap::new(),
+            constant_globals: HashMap::new(),
+            type_aliases: HashMap::new(),
+            newtypes: HashMap::new(),
+            unit_types: std::collections::HashSet::new(),
             local_types: HashMap::new(),
+            invariants: HashMap::new(),
+            label_depth: 0,
+            loop_labels: Vec::new(),
+            usings: Vec::new(),
+            lambdas: LambdaTable::default(),
+            closure_locals: std::collections::HashSet::new(),
             runtime: RuntimeFunctions::default(),
             extern_imports: HashMap::new(),
             gai_indices: None,
@@ -285,24 +462,56 @@ impl<'a> SnippetWasmCompiler<'a> {
             void_functions: std::collections::HashSet::new(),
             symbol_metadata_offset: None,
             symbol_metadata_len: None,
+            audit: AuditTable::default(),
+            sensitive: Sensitive::default(),
+            current_snippet: String::new(),
+            current_step: String::new(),
+            features: WasmFeatures::none(),
+            vector_local: None,
+            memory_limits: MemoryLimits::default(),
+            allocator: Allocator::default(),
+            targets: Vec::new(),
+            init: 0,
+            reflected_functions: Vec::new(),
+            reflection_json: Vec::new(),
+            reflection_table: (0, 0),
+            reflect: 0,
         }
     }
 
-    /// Compile snippets to WASM
+    /// Allow the module to use the given proposals; without them it is MVP
+    /// WebAssembly, and the host running it must support each one enabled
+    pub fn with_features(mut self, features: WasmFeatures) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Let the memory grow only up to `limits`; allocating past them traps
+    pub fn with_memory_limits(mut self, limits: MemoryLimits) -> Self {
+        self.memory_limits = limits;
+        self
+    }
+
+    /// Lower and compile snippets to WASM
     pub fn compile_snippets(&mut self, snippets: &[Snippet]) -> Result<Vec<u8>, CodegenError> {
+        let snippets = &*covenant_lower::lower_snippets(snippets);
         let mut module = Module::new();
 
-        // Register struct layouts from struct snippets
+        // Register struct layouts from struct snippets and const values
         for snippet in snippets {
-            if snippet.kind == SnippetKind::Struct {
-                self.register_struct_layout(snippet);
+            self.register_unit_types(snippet);
+            match snippet.kind {
+                SnippetKind::Struct => self.register_struct_layout(snippet),
+                SnippetKind::Const => self.register_constant(snippet),
+                SnippetKind::Alias => self.register_type_alias(snippet),
+                _ => {}
             }
         }
 
         // Collect all function snippets (both pure and effectful)
         let functions: Vec<&Snippet> = snippets
             .iter()
-            .filter(|s| s.kind == SnippetKind::Function)
+            .filter(|s| s.kind.is_function())
             .collect();
 
         // Check for data snippets - if present, build the graph and embed it
@@ -328,13 +537,18 @@ impl<'a> SnippetWasmCompiler<'a> {
         // First pass: collect all effects and register imports
         let all_effects = collect_all_effects(&functions);
         self.register_effect_imports(&all_effects);
+        self.register_query_targets(snippets)?;
 
         // Register all extern-abstract imports (stdlib + user-defined)
         self.register_extern_abstracts();
         self.register_user_extern_abstracts(snippets);
+        self.register_regex_imports(&functions);
+        self.register_encoding_imports(&functions);
+        self.register_exact_imports(&functions);
+        self.register_json_imports(&functions);
 
-        // Pre-scan for string literals to determine if we need memory
-        let has_strings = functions.iter().any(|s| snippet_has_string_literals(s));
+        // Assign table slots to lambdas before any body is compiled
+        self.collect_lambdas(&functions);
 
         // Determine how many GAI functions we need
         let gai_count = if self.graph_layout.is_some() { GAI_FUNCTION_COUNT } else { 0 };
@@ -374,6 +588,19 @@ impl<'a> SnippetWasmCompiler<'a> {
             }
         }
 
+        // Add closure types for lifted lambdas
+        self.add_lambda_types(&mut types);
+
+        // Add allocator types, then the init function's
+        let allocator_type_base = types.len();
+        for (params, results) in Allocator::function_types() {
+            types.function(params, results);
+        }
+        let init_type = types.len();
+        types.function(vec![], vec![]);
+        let reflect_type = types.len();
+        types.function(vec![], vec![ValType::I64]);
+
         module.section(&types);
 
         // Import section (if there are any imports)
@@ -420,7 +647,6 @@ impl<'a> SnippetWasmCompiler<'a> {
                 find_by_id: gai_base_idx + 8,
                 content_contains: gai_base_idx + 9,
                 get_rel_type_name: gai_base_idx + 10,
-                alloc: gai_base_idx + 11,
             });
         }
 
@@ -435,44 +661,47 @@ impl<'a> SnippetWasmCompiler<'a> {
         for i in 0..gai_count {
             func_section.function(gai_type_base + i);
         }
-        module.section(&func_section);
-
-        // Memory section - always export memory when compiling functions or data
-        let needs_memory = !functions.is_empty()
-            || self.graph_layout.is_some()
-            || !self.data_segment.is_empty()
-            || !all_effects.is_empty()
-            || has_strings;
-        if needs_memory {
-            let mut memory = MemorySection::new();
-            // 1 page = 64KB, start with 16 pages (1MB)
-            memory.memory(MemoryType {
-                minimum: 16,
-                maximum: Some(256), // 16MB max
-                memory64: false,
-                shared: false,
-            });
-            module.section(&memory);
+        // Lifted lambdas follow the GAI functions, then the allocator, the
+        // init function and the reflection function
+        let lambda_base_idx = gai_base_idx + gai_count;
+        self.add_lambda_functions(&mut func_section);
+        self.allocator = Allocator::at(lambda_base_idx + self.lambdas.arities.len() as u32);
+        for i in 0..Allocator::FUNCTION_COUNT {
+            func_section.function(allocator_type_base + i);
         }
+        self.init = self.allocator.reset + 1;
+        func_section.function(init_type);
+        self.reflect = self.init + 1;
+        func_section.function(reflect_type);
+        module.section(&func_section);
+        self.add_reflection_table(&functions, import_count)?;
+        module.section(&self.function_table_section());
 
-        // Pre-allocate strings used by traverse steps before calculating heap pointer.
-        // This ensures the heap doesn't overlap with string data in the data segment.
+        // Pre-allocate strings used by traverse steps
         self.pre_allocate_step_strings(snippets);
 
-        // Global section for heap pointer
-        if needs_memory {
-            let mut globals = GlobalSection::new();
-            // Heap pointer starts after all data (graph + strings + traverse strings)
-            let heap_start = self.data_segment.len() as i32;
-            globals.global(
-                GlobalType {
-                    val_type: ValType::I32,
-                    mutable: true,
-                },
-                &wasm_encoder::ConstExpr::i32_const(heap_start),
-            );
-            module.section(&globals);
+        // Code section (user functions + GAI functions + lambdas + allocator
+        // + init + reflection),
+        // compiled before the memory is sized so that every string the
+        // bodies add to the data segment sits below the heap
+        let mut codes = CodeSection::new();
+        for snippet in &functions {
+            let wasm_func = self.compile_function_snippet(snippet)?;
+            codes.function(&wasm_func);
+        }
+        // Add GAI function bodies
+        if let Some(ref layout) = self.graph_layout {
+            let gai_funcs = gai_codegen::generate_gai_functions(layout);
+            for gai_func in gai_funcs {
+                codes.function(&gai_func);
+            }
         }
+        self.add_lambda_bodies(&mut codes);
+        let init_func = self.gen_init_function();
+        self.place_reflection_table();
+        self.add_memory(&mut module, &mut codes)?;
+        codes.function(&init_func);
+        codes.function(&self.gen_reflect_function());
 
         // Export section
         let mut exports = ExportSection::new();
@@ -494,27 +723,14 @@ impl<'a> SnippetWasmCompiler<'a> {
             exports.export("cov_find_by_id", ExportKind::Func, gai.find_by_id);
             exports.export("cov_content_contains", ExportKind::Func, gai.content_contains);
             exports.export("cov_get_rel_type_name", ExportKind::Func, gai.get_rel_type_name);
-            exports.export("cov_alloc", ExportKind::Func, gai.alloc);
-        }
-        // Export memory if present
-        if needs_memory {
-            exports.export("memory", ExportKind::Memory, 0);
         }
+        self.export_memory(&mut exports);
         module.section(&exports);
 
-        // Code section (user functions + GAI functions)
-        let mut codes = CodeSection::new();
-        for snippet in &functions {
-            let wasm_func = self.compile_function_snippet(snippet)?;
-            codes.function(&wasm_func);
-        }
-        // Add GAI function bodies
-        if let Some(ref layout) = self.graph_layout {
-            let gai_funcs = gai_codegen::generate_gai_functions(layout);
-            for gai_func in gai_funcs {
-                codes.function(&gai_func);
-            }
-        }
+        // Element section pointing the function table at the lifted lambdas
+        // and the exported functions
+        module.section(&self.function_element_section(lambda_base_idx));
+
         module.section(&codes);
 
         // Data section (graph data + string constants, already combined in data_segment)
@@ -529,6 +745,14 @@ impl<'a> SnippetWasmCompiler<'a> {
             module.section(&data);
         }
 
+        if let Some(audit) = self.audit_section()? {
+            module.section(&audit);
+        }
+        if let Some(events) = events_section(snippets)? {
+            module.section(&events);
+        }
+        module.section(&self.build_info_section()?);
+
         Ok(module.finish())
     }
 
@@ -536,24 +760,106 @@ impl<'a> SnippetWasmCompiler<'a> {
     ///
     /// This is similar to `compile_snippets` but also embeds symbol metadata as JSON
     /// in the data section and exports a `_cov_get_symbol_metadata()` function.
+    /// Record a const snippet's value and give it a global, which references
+    /// to it read
+    fn register_constant(&mut self, snippet: &Snippet) {
+        for section in &snippet.sections {
+            if let Section::Signature(sig) = section {
+                if let SignatureKind::Const(const_sig) = &sig.kind {
+                    self.constants.insert(snippet.id.clone(), const_sig.value.clone());
+                    let global = FIRST_CONSTANT_GLOBAL + self.constant_globals.len() as u32;
+                    self.constant_globals.entry(snippet.id.clone()).or_insert(global);
+                }
+            }
+        }
+    }
+
+    /// The value of the const snippet named by `of` (or `of.field`), unless
+    /// a local shadows it
+    fn constant_value(&self, of: &str, field: Option<&str>) -> Option<Literal> {
+        if self.locals.contains_key(of) {
+            return None;
+        }
+        match field {
+            Some(field) => self.constants.get(&format!("{}.{}", of, field)).cloned(),
+            None => self.constants.get(of).cloned(),
+        }
+    }
+
+    /// The global of the const snippet named by `of` (or `of.field`), unless
+    /// a local shadows it
+    fn constant_global(&self, of: &str, field: Option<&str>) -> Option<u32> {
+        if self.locals.contains_key(of) {
+            return None;
+        }
+        match field {
+            Some(field) => self.constant_globals.get(&format!("{}.{}", of, field)).copied(),
+            None => self.constant_globals.get(of).copied(),
+        }
+    }
+
+    /// Register the database snippets as query targets when a body queries
+    /// a database, importing the host function the init function calls
+    fn register_query_targets(&mut self, snippets: &[Snippet]) -> Result<(), CodegenError> {
+        if self.runtime.db_execute_query.is_none() {
+            return Ok(());
+        }
+        self.targets = init::query_targets(snippets)?;
+        if !self.targets.is_empty() {
+            let (module, name) = REGISTER_TARGET_IMPORT;
+            let (params, results) = QueryTarget::import_type();
+            self.runtime.db_register_target = Some(self.imports.add_import(module, name, params, results));
+        }
+        Ok(())
+    }
+
+    /// The init function: once, register each query target with the host
+    fn gen_init_function(&mut self) -> Function {
+        let mut func = Function::new(vec![]);
+        func.instruction(&Instruction::GlobalGet(INIT_GLOBAL));
+        func.instruction(&Instruction::If(BlockType::Empty));
+        func.instruction(&Instruction::Return);
+        func.instruction(&Instruction::End);
+        func.instruction(&Instruction::I32Const(1));
+        func.instruction(&Instruction::GlobalSet(INIT_GLOBAL));
+
+        if let Some(register) = self.runtime.db_register_target {
+            for target in std::mem::take(&mut self.targets) {
+                for text in [&target.name, &target.dialect, &target.connection] {
+                    let offset = self.data_segment.add_string(text);
+                    func.instruction(&Instruction::I32Const(offset as i32));
+                    func.instruction(&Instruction::I32Const(text.len() as i32));
+                }
+                func.instruction(&Instruction::Call(register));
+            }
+        }
+        func.instruction(&Instruction::End);
+        func
+    }
+
     pub fn compile_snippets_with_symbols(
         &mut self,
         snippets: &[Snippet],
         embeddable_symbols: &[crate::EmbeddableSymbol],
     ) -> Result<Vec<u8>, CodegenError> {
+        let snippets = &*covenant_lower::lower_snippets(snippets);
         let mut module = Module::new();
 
-        // Register struct layouts from struct snippets
+        // Register struct layouts from struct snippets and const values
         for snippet in snippets {
-            if snippet.kind == SnippetKind::Struct {
-                self.register_struct_layout(snippet);
+            self.register_unit_types(snippet);
+            match snippet.kind {
+                SnippetKind::Struct => self.register_struct_layout(snippet),
+                SnippetKind::Const => self.register_constant(snippet),
+                SnippetKind::Alias => self.register_type_alias(snippet),
+                _ => {}
             }
         }
 
         // Collect all function snippets (both pure and effectful)
         let functions: Vec<&Snippet> = snippets
             .iter()
-            .filter(|s| s.kind == SnippetKind::Function)
+            .filter(|s| s.kind.is_function())
             .collect();
 
         // Check for data snippets - if present, build the graph and embed it
@@ -572,7 +878,7 @@ impl<'a> SnippetWasmCompiler<'a> {
         }
 
         // Serialize and embed symbol metadata JSON
-        let symbol_json = serde_json::to_vec(embeddable_symbols)
+        let symbol_json = serde_json::to_vec(&crate::EmbeddedMetadata::new(embeddable_symbols))
             .map_err(|e| CodegenError::SerializationFailed(e.to_string()))?;
 
         // Record offset before appending
@@ -585,13 +891,18 @@ impl<'a> SnippetWasmCompiler<'a> {
         // First pass: collect all effects and register imports
         let all_effects = collect_all_effects(&functions);
         self.register_effect_imports(&all_effects);
+        self.register_query_targets(snippets)?;
 
         // Register all extern-abstract imports (stdlib + user-defined)
         self.register_extern_abstracts();
         self.register_user_extern_abstracts(snippets);
+        self.register_regex_imports(&functions);
+        self.register_encoding_imports(&functions);
+        self.register_exact_imports(&functions);
+        self.register_json_imports(&functions);
 
-        // Pre-scan for string literals to determine if we need memory
-        let has_strings = functions.iter().any(|s| snippet_has_string_literals(s));
+        // Assign table slots to lambdas before any body is compiled
+        self.collect_lambdas(&functions);
 
         // Determine how many GAI functions we need
         let gai_count = if self.graph_layout.is_some() { GAI_FUNCTION_COUNT } else { 0 };
@@ -635,6 +946,19 @@ impl<'a> SnippetWasmCompiler<'a> {
         let symbol_metadata_type_idx = types.len();
         types.function(vec![], vec![ValType::I64]);
 
+        // Add closure types for lifted lambdas
+        self.add_lambda_types(&mut types);
+
+        // Add allocator types, then the init function's
+        let allocator_type_base = types.len();
+        for (params, results) in Allocator::function_types() {
+            types.function(params, results);
+        }
+        let init_type = types.len();
+        types.function(vec![], vec![]);
+        let reflect_type = types.len();
+        types.function(vec![], vec![ValType::I64]);
+
         module.section(&types);
 
         // Import section (if there are any imports)
@@ -681,7 +1005,6 @@ impl<'a> SnippetWasmCompiler<'a> {
                 find_by_id: gai_base_idx + 8,
                 content_contains: gai_base_idx + 9,
                 get_rel_type_name: gai_base_idx + 10,
-                alloc: gai_base_idx + 11,
             });
         }
 
@@ -701,44 +1024,50 @@ impl<'a> SnippetWasmCompiler<'a> {
         }
         // Symbol metadata function type
         func_section.function(symbol_metadata_type_idx);
-        module.section(&func_section);
-
-        // Memory section - always export memory when compiling functions or data
-        let needs_memory = !functions.is_empty()
-            || self.graph_layout.is_some()
-            || !self.data_segment.is_empty()
-            || !all_effects.is_empty()
-            || has_strings;
-        if needs_memory {
-            let mut memory = MemorySection::new();
-            // 1 page = 64KB, start with 16 pages (1MB)
-            memory.memory(MemoryType {
-                minimum: 16,
-                maximum: Some(256), // 16MB max
-                memory64: false,
-                shared: false,
-            });
-            module.section(&memory);
+        // Lifted lambdas follow the symbol metadata function, then the
+        // allocator, the init function and the reflection function
+        let lambda_base_idx = symbol_metadata_func_idx + 1;
+        self.add_lambda_functions(&mut func_section);
+        self.allocator = Allocator::at(lambda_base_idx + self.lambdas.arities.len() as u32);
+        for i in 0..Allocator::FUNCTION_COUNT {
+            func_section.function(allocator_type_base + i);
         }
+        self.init = self.allocator.reset + 1;
+        func_section.function(init_type);
+        self.reflect = self.init + 1;
+        func_section.function(reflect_type);
+        module.section(&func_section);
+        self.add_reflection_table(&functions, import_count)?;
+        module.section(&self.function_table_section());
 
-        // Pre-allocate strings used by traverse steps before calculating heap pointer.
-        // This ensures the heap doesn't overlap with string data in the data segment.
+        // Pre-allocate strings used by traverse steps
         self.pre_allocate_step_strings(snippets);
 
-        // Global section for heap pointer
-        if needs_memory {
-            let mut globals = GlobalSection::new();
-            // Heap pointer starts after all data (graph + strings + symbol JSON + traverse strings)
-            let heap_start = self.data_segment.len() as i32;
-            globals.global(
-                GlobalType {
-                    val_type: ValType::I32,
-                    mutable: true,
-                },
-                &wasm_encoder::ConstExpr::i32_const(heap_start),
-            );
-            module.section(&globals);
+        // Code section (user functions + GAI functions + symbol metadata
+        // function + lambdas + allocator + init + reflection), compiled before the memory is
+        // sized so that every string the bodies add to the data segment sits
+        // below the heap
+        let mut codes = CodeSection::new();
+        for snippet in &functions {
+            let wasm_func = self.compile_function_snippet(snippet)?;
+            codes.function(&wasm_func);
+        }
+        // Add GAI function bodies
+        if let Some(ref layout) = self.graph_layout {
+            let gai_funcs = gai_codegen::generate_gai_functions(layout);
+            for gai_func in gai_funcs {
+                codes.function(&gai_func);
+            }
         }
+        // Add symbol metadata function body
+        let symbol_metadata_func = self.gen_symbol_metadata_function();
+        codes.function(&symbol_metadata_func);
+        self.add_lambda_bodies(&mut codes);
+        let init_func = self.gen_init_function();
+        self.place_reflection_table();
+        self.add_memory(&mut module, &mut codes)?;
+        codes.function(&init_func);
+        codes.function(&self.gen_reflect_function());
 
         // Export section
         let mut exports = ExportSection::new();
@@ -760,32 +1089,16 @@ impl<'a> SnippetWasmCompiler<'a> {
             exports.export("cov_find_by_id", ExportKind::Func, gai.find_by_id);
             exports.export("cov_content_contains", ExportKind::Func, gai.content_contains);
             exports.export("cov_get_rel_type_name", ExportKind::Func, gai.get_rel_type_name);
-            exports.export("cov_alloc", ExportKind::Func, gai.alloc);
         }
         // Export symbol metadata function
         exports.export("_cov_get_symbol_metadata", ExportKind::Func, symbol_metadata_func_idx);
-        // Export memory if present
-        if needs_memory {
-            exports.export("memory", ExportKind::Memory, 0);
-        }
+        self.export_memory(&mut exports);
         module.section(&exports);
 
-        // Code section (user functions + GAI functions + symbol metadata function)
-        let mut codes = CodeSection::new();
-        for snippet in &functions {
-            let wasm_func = self.compile_function_snippet(snippet)?;
-            codes.function(&wasm_func);
-        }
-        // Add GAI function bodies
-        if let Some(ref layout) = self.graph_layout {
-            let gai_funcs = gai_codegen::generate_gai_functions(layout);
-            for gai_func in gai_funcs {
-                codes.function(&gai_func);
-            }
-        }
-        // Add symbol metadata function body
-        let symbol_metadata_func = self.gen_symbol_metadata_function();
-        codes.function(&symbol_metadata_func);
+        // Element section pointing the function table at the lifted lambdas
+        // and the exported functions
+        module.section(&self.function_element_section(lambda_base_idx));
+
         module.section(&codes);
 
         // Data section (graph data + string constants + symbol JSON, already combined in data_segment)
@@ -800,9 +1113,266 @@ impl<'a> SnippetWasmCompiler<'a> {
             module.section(&data);
         }
 
+        if let Some(audit) = self.audit_section()? {
+            module.section(&audit);
+        }
+        if let Some(events) = events_section(snippets)? {
+            module.section(&events);
+        }
+        module.section(&self.build_info_section()?);
+
         Ok(module.finish())
     }
 
+    /// Assign a table slot to every lambda step in the function bodies
+    fn collect_lambdas(&mut self, functions: &[&Snippet]) {
+        let mut pending: Vec<&Step> = functions.iter()
+            .filter_map(|s| find_body_section(s))
+            .flat_map(|body| body.steps.iter())
+            .collect();
+        while let Some(step) = pending.pop() {
+            if let StepKind::Lambda(lambda) = &step.kind {
+                let slot = self.lambdas.arities.len() as u32;
+                self.lambdas.slots.insert(LambdaTable::slot_key(lambda), slot);
+                self.lambdas.arities.push(lambda.params.len());
+                self.lambdas.bodies.push(None);
+            }
+            for nested in step.nested_steps() {
+                pending.extend(nested);
+            }
+        }
+    }
+
+    /// Add one closure type per arity up to the largest lambda's:
+    /// (closure, args...) -> i64
+    fn add_lambda_types(&mut self, types: &mut TypeSection) {
+        self.lambdas.type_base = types.len();
+        if let Some(max_arity) = self.lambdas.max_arity() {
+            for arity in 0..=max_arity {
+                types.function(vec![ValType::I64; arity + 1], vec![ValType::I64]);
+            }
+        }
+    }
+
+    fn add_lambda_functions(&self, func_section: &mut FunctionSection) {
+        for &arity in &self.lambdas.arities {
+            func_section.function(self.lambdas.type_base + arity as u32);
+        }
+    }
+
+    /// The function table: the lambdas' closure slots, then the exported
+    /// functions
+    fn function_table_section(&self) -> TableSection {
+        let size = (self.lambdas.arities.len() + self.reflected_functions.len()) as u32;
+        let mut tables = TableSection::new();
+        tables.table(TableType {
+            element_type: RefType::FUNCREF,
+            minimum: size,
+            maximum: Some(size),
+        });
+        tables
+    }
+
+    fn function_element_section(&self, lambda_base_idx: u32) -> ElementSection {
+        let indices: Vec<u32> = (0..self.lambdas.arities.len() as u32)
+            .map(|slot| lambda_base_idx + slot)
+            .chain(self.reflected_functions.iter().copied())
+            .collect();
+        let mut elements = ElementSection::new();
+        elements.active(None, &wasm_encoder::ConstExpr::i32_const(0), Elements::Functions(&indices));
+        elements
+    }
+
+    /// Describe the exported functions, giving each a table slot after the
+    /// lambdas; the description goes in the data segment once the bodies
+    /// are compiled
+    fn add_reflection_table(&mut self, functions: &[&Snippet], import_count: u32) -> Result<(), CodegenError> {
+        let mut table = ReflectionTable { version: REFLECTION_VERSION, functions: Vec::new() };
+        for (i, snippet) in functions.iter().enumerate() {
+            let Some(sig) = find_function_signature(snippet) else { continue };
+            let params = sig
+                .params
+                .iter()
+                .filter_map(|p| {
+                    let wasm = self.type_to_valtype(&p.ty)?;
+                    Some(ParamInfo { name: p.name.clone(), ty: TypeInfo::new(p.ty.to_cov(0), wasm) })
+                })
+                .collect();
+            let returns = sig.returns.as_ref().and_then(|ret| {
+                let wasm = self.return_type_to_valtype(ret)?;
+                Some(TypeInfo::new(return_type_text(ret), wasm))
+            });
+            table.functions.push(FunctionInfo {
+                name: sig.name.clone(),
+                id: snippet.id.clone(),
+                slot: (self.lambdas.arities.len() + self.reflected_functions.len()) as u32,
+                params,
+                returns,
+            });
+            self.reflected_functions.push(import_count + i as u32);
+        }
+
+        self.reflection_json =
+            serde_json::to_vec(&table).map_err(|e| CodegenError::SerializationFailed(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Put the reflection table's JSON at the end of the data segment
+    fn place_reflection_table(&mut self) {
+        let json = std::mem::take(&mut self.reflection_json);
+        self.reflection_table = (self.data_segment.len() as u32, json.len() as u32);
+        self.data_segment.append_raw(&json);
+    }
+
+    /// The reflection function: a fat pointer to the reflection table
+    fn gen_reflect_function(&self) -> Function {
+        let (offset, len) = self.reflection_table;
+        let mut func = Function::new(vec![]);
+        func.instruction(&Instruction::I64Const(((offset as i64) << 32) | len as i64));
+        func.instruction(&Instruction::End);
+        func
+    }
+
+    /// Emit the lifted lambda bodies in slot order. Lambdas in steps that
+    /// are not compiled (e.g. inside a transaction) get a trapping body.
+    fn add_lambda_bodies(&mut self, codes: &mut CodeSection) {
+        for body in std::mem::take(&mut self.lambdas.bodies) {
+            let body = body.unwrap_or_else(|| {
+                let mut func = Function::new(vec![]);
+                func.instruction(&Instruction::Unreachable);
+                func.instruction(&Instruction::End);
+                func
+            });
+            codes.function(&body);
+        }
+    }
+
+    /// The audit table as a custom section, if any effectful extern is called
+    fn audit_section(&self) -> Result<Option<CustomSection<'static>>, CodegenError> {
+        if self.audit.sites.is_empty() {
+            return Ok(None);
+        }
+        // Only externs that are actually called
+        let table = AuditTable {
+            externs: self.audit.externs.iter()
+                .filter(|(import, _)| self.audit.sites.iter().any(|site| &site.import == *import))
+                .map(|(import, audit)| (import.clone(), audit.clone()))
+                .collect(),
+            sites: self.audit.sites.clone(),
+        };
+        let data = serde_json::to_vec(&table)
+            .map_err(|e| CodegenError::SerializationFailed(e.to_string()))?;
+        Ok(Some(CustomSection {
+            name: AUDIT_SECTION.into(),
+            data: data.into(),
+        }))
+    }
+
+    /// Add the memory, sized for the finished data segment, and the globals
+    /// (heap pointer, audit site, heap peak), then the allocator bodies
+    fn add_memory(&mut self, module: &mut Module, codes: &mut CodeSection) -> Result<(), CodegenError> {
+        // Const values go in the data segment first, so they sit below the heap
+        let constants = self.constant_global_values()?;
+        let data_len = self.data_segment.len() as u32;
+        let max = self.memory_limits.max_pages;
+        let initial = self.memory_limits.initial_pages(data_len).ok_or(CodegenError::MemoryLimitTooLow {
+            needed: data_len.div_ceil(memory::PAGE_SIZE),
+            max,
+        })?;
+        let mut memories = MemorySection::new();
+        memories.memory(MemoryType {
+            minimum: initial as u64,
+            maximum: Some(max as u64),
+            memory64: false,
+            shared: false,
+        });
+        module.section(&memories);
+
+        // The heap starts after all data (graph + strings + symbol JSON)
+        let heap_start = memory::heap_start(data_len);
+        let mut globals = GlobalSection::new();
+        globals.global(
+            GlobalType {
+                val_type: ValType::I32,
+                mutable: true,
+            },
+            &wasm_encoder::ConstExpr::i32_const(heap_start as i32),
+        );
+        add_audit_site_global(&mut globals);
+        debug_assert_eq!(globals.len(), PEAK_GLOBAL);
+        globals.global(
+            GlobalType {
+                val_type: ValType::I32,
+                mutable: true,
+            },
+            &wasm_encoder::ConstExpr::i32_const(0),
+        );
+        debug_assert_eq!(globals.len(), INIT_GLOBAL);
+        globals.global(
+            GlobalType {
+                val_type: ValType::I32,
+                mutable: true,
+            },
+            &wasm_encoder::ConstExpr::i32_const(0),
+        );
+        for (val_type, init) in constants {
+            globals.global(GlobalType { val_type, mutable: false }, &init);
+        }
+        module.section(&globals);
+
+        for func in Allocator::functions(heap_start) {
+            codes.function(&func);
+        }
+        Ok(())
+    }
+
+    /// Export the memory, the audit site global, the allocator, the init
+    /// function and the reflection function and table
+    fn export_memory(&self, exports: &mut ExportSection) {
+        exports.export("memory", ExportKind::Memory, 0);
+        exports.export(AUDIT_SITE_EXPORT, ExportKind::Global, AUDIT_SITE_GLOBAL);
+        exports.export(ALLOC_EXPORT, ExportKind::Func, self.allocator.alloc);
+        exports.export(STATS_EXPORT, ExportKind::Func, self.allocator.stats);
+        exports.export(RESET_EXPORT, ExportKind::Func, self.allocator.reset);
+        exports.export(INIT_EXPORT, ExportKind::Func, self.init);
+        exports.export(REFLECT_EXPORT, ExportKind::Func, self.reflect);
+        exports.export(FUNCTIONS_EXPORT, ExportKind::Table, 0);
+    }
+
+    /// The type and initial value of each const snippet's global, in
+    /// global index order
+    fn constant_global_values(&mut self) -> Result<Vec<(ValType, wasm_encoder::ConstExpr)>, CodegenError> {
+        let mut ids: Vec<(u32, String)> = self.constant_globals.iter().map(|(id, g)| (*g, id.clone())).collect();
+        ids.sort();
+        let mut values = Vec::with_capacity(ids.len());
+        for (_, id) in ids {
+            let value = self.literal_value(&self.constants[&id].clone())?;
+            values.push(match value {
+                LiteralValue::I64(n) => (ValType::I64, wasm_encoder::ConstExpr::i64_const(n)),
+                LiteralValue::F64(n) => (ValType::F64, wasm_encoder::ConstExpr::f64_const(n)),
+            });
+        }
+        Ok(values)
+    }
+
+    /// Allocate the number of bytes on the stack (i32) from the heap,
+    /// leaving the address as an i64
+    fn compile_alloc(&self, func: &mut Function) {
+        func.instruction(&Instruction::Call(self.allocator.alloc));
+        func.instruction(&Instruction::I64ExtendI32U);
+    }
+
+    /// The build info as a custom section, recording the enabled features
+    /// and the memory limit
+    fn build_info_section(&self) -> Result<CustomSection<'static>, CodegenError> {
+        let data = serde_json::to_vec(&BuildInfo::new(self.features, self.memory_limits))
+            .map_err(|e| CodegenError::SerializationFailed(e.to_string()))?;
+        Ok(CustomSection {
+            name: BUILD_INFO_SECTION.into(),
+            data: data.into(),
+        })
+    }
+
     /// Generate the _cov_get_symbol_metadata function body
     ///
     /// Returns a fat pointer (i64) encoding: (offset << 32) | length
@@ -826,7 +1396,7 @@ impl<'a> SnippetWasmCompiler<'a> {
     /// the data segment.
     fn pre_allocate_step_strings(&mut self, snippets: &[Snippet]) {
         for snippet in snippets {
-            if snippet.kind != SnippetKind::Function {
+            if !snippet.kind.is_function() {
                 continue;
             }
 
@@ -922,32 +1492,86 @@ impl<'a> SnippetWasmCompiler<'a> {
         }
     }
 
-    /// Register all extern-abstract snippets from stdlib sources.
-    /// Parses each source, finds ExternAbstract snippets, and registers them as WASM imports.
-    fn register_extern_abstracts(&mut self) {
-        const STDLIB_SOURCES: &[&str] = &[
-            include_str!("../../../runtime/std/console/console.cov"),
-            include_str!("../../../runtime/std/filesystem/fs.cov"),
-            include_str!("../../../runtime/std/path/path.cov"),
-            include_str!("../../../runtime/std/text/text.cov"),
-            include_str!("../../../runtime/std/text/regex.cov"),
-            include_str!("../../../runtime/std/list/list.cov"),
-        ];
+    /// Register the host's regex engine when any body uses a regex op.
+    /// Strings are passed as (ptr, len) pairs; match returns 1 or 0,
+    /// find_all and capture a List<String> and replace a String.
+    fn register_regex_imports(&mut self, functions: &[&Snippet]) {
+        let uses_regex = functions.iter()
+            .filter_map(|s| find_body_section(s))
+            .any(|body| steps_use_op(&body.steps, is_regex_op));
+        if !uses_regex {
+            return;
+        }
+        let strings = |n: usize| vec![ValType::I32; 2 * n];
+        self.runtime.regex_match = Some(self.imports.add_import("regex", "match", strings(2), vec![ValType::I64]));
+        self.runtime.regex_find_all = Some(self.imports.add_import("regex", "find_all", strings(2), vec![ValType::I64]));
+        self.runtime.regex_capture = Some(self.imports.add_import("regex", "capture", strings(2), vec![ValType::I64]));
+        self.runtime.regex_replace = Some(self.imports.add_import("regex", "replace", strings(3), vec![ValType::I64]));
+    }
 
-        // Ensure mem.alloc is available for any extern that returns String/List
-        self.ensure_mem_alloc();
+    /// Register the host's codecs when any body uses an encoding op. Each
+    /// takes one String or Bytes as (ptr, len) and returns a fat pointer;
+    /// decoders return 0 (the DecodeError member) on malformed input.
+    fn register_encoding_imports(&mut self, functions: &[&Snippet]) {
+        let uses_encoding = functions.iter()
+            .filter_map(|s| find_body_section(s))
+            .any(|body| steps_use_op(&body.steps, |op| encoding_import_name(op).is_some()));
+        if !uses_encoding {
+            return;
+        }
+        for op in ENCODING_OPS {
+            if let Some(name) = encoding_import_name(op) {
+                let idx = self.imports.add_import("encoding", name, vec![ValType::I32, ValType::I32], vec![ValType::I64]);
+                self.runtime.encoding.insert(op, idx);
+            }
+        }
+    }
 
-        let mut snippets_to_register = Vec::new();
-        for source in STDLIB_SOURCES {
-            if let Ok(covenant_ast::Program::Snippets { snippets, .. }) = covenant_parser::parse(source) {
-                for snippet in snippets {
-                    if snippet.kind == SnippetKind::ExternAbstract {
-                        snippets_to_register.push(snippet);
-                    }
-                }
+    /// Register the host's Json operations when any body uses one. Documents
+    /// and keys are passed as (ptr, len) pairs of their text and results are
+    /// fat pointers; `parse` returns 0 (the DecodeError member) on malformed
+    /// input and `get` none when there is no such key. The module is
+    /// `json_doc` so it cannot collide with `json.*` extern snippets.
+    fn register_json_imports(&mut self, functions: &[&Snippet]) {
+        let uses_json = functions.iter()
+            .filter_map(|s| find_body_section(s))
+            .any(|body| steps_use_op(&body.steps, |op| json_import_name(op).is_some()));
+        if !uses_json {
+            return;
+        }
+        for op in JSON_OPS {
+            if let Some((name, texts)) = json_import_name(op) {
+                let idx = self.imports.add_import("json_doc", name, vec![ValType::I32; 2 * texts], vec![ValType::I64]);
+                self.runtime.json.insert(op, idx);
+            }
+        }
+    }
+
+    /// Register the host's Decimal and BigInt arithmetic when any body uses
+    /// it. Values are passed as (ptr, len) pairs of their canonical text,
+    /// decimal places as an i32 and Ints as an i64; results are fat
+    /// pointers, or an i64 -1, 0 or 1 for comparisons.
+    fn register_exact_imports(&mut self, functions: &[&Snippet]) {
+        let uses_exact = functions.iter()
+            .filter_map(|s| find_body_section(s))
+            .any(|body| steps_use_op(&body.steps, |op| exact_import_name(op).is_some()));
+        if !uses_exact {
+            return;
+        }
+        for op in EXACT_OPS {
+            if let Some((module, name)) = exact_import_name(op) {
+                let idx = self.imports.add_import(module, name, exact_import_params(op), vec![ValType::I64]);
+                self.runtime.exact.insert(op, idx);
             }
         }
-        for snippet in snippets_to_register {
+    }
+
+    /// Register all extern-abstract snippets from stdlib sources as WASM imports
+    fn register_extern_abstracts(&mut self) {
+        // Ensure mem.alloc is available for any extern that returns String/List
+        self.ensure_mem_alloc();
+
+        for snippet in stdlib_extern_abstracts() {
             self.register_single_extern(&snippet);
         }
     }
@@ -956,14 +1580,7 @@ impl<'a> SnippetWasmCompiler<'a> {
     /// Splits snippet ID on last dot to derive (module, function) for the import.
     fn register_single_extern(&mut self, snippet: &Snippet) {
         let id = &snippet.id;
-
-        // Split ID on last dot: "text.concat" → ("text", "concat")
-        // "std.text.regex_test" → ("std.text", "regex_test")
-        // "to_uppercase" (no dot) → ("extern", "to_uppercase")
-        let (module, func_name) = match id.rfind('.') {
-            Some(pos) => (&id[..pos], &id[pos + 1..]),
-            None => ("extern", id.as_str()),
-        };
+        let (module, func_name) = extern_import_name(id);
 
         // Get function signature to determine param types
         let sig = match find_function_signature(snippet) {
@@ -997,6 +1614,12 @@ impl<'a> SnippetWasmCompiler<'a> {
             vec![ValType::I64]
         };
 
+        let params = param_kinds.iter().map(|kind| kind.audit_name().to_string()).collect();
+        let returns = if wasm_results.is_empty() { "unit" } else { extern_return_audit_name(sig) };
+        if let Some(audit) = AuditExtern::from_snippet(snippet, params, returns.to_string(), &self.sensitive) {
+            self.audit.externs.insert(format!("{}.{}", module, func_name), audit);
+        }
+
         let func_index = self.imports.add_import(module, func_name, wasm_params, wasm_results);
         let ext_import = ExternImport {
             func_index,
@@ -1011,6 +1634,7 @@ impl<'a> SnippetWasmCompiler<'a> {
 
     /// Also register user-defined extern-abstract and extern snippets from the program
     fn register_user_extern_abstracts(&mut self, snippets: &[Snippet]) {
+        self.sensitive = Sensitive::from_snippets(snippets);
         for snippet in snippets {
             if snippet.kind == SnippetKind::ExternAbstract || snippet.kind == SnippetKind::Extern {
                 self.register_single_extern(snippet);
@@ -1018,43 +1642,90 @@ impl<'a> SnippetWasmCompiler<'a> {
         }
     }
 
-    /// Register a struct snippet's layout for field access
-    fn register_struct_layout(&mut self, snippet: &Snippet) {
-        // Find struct signature
+    /// Record the type an alias snippet names
+    fn register_type_alias(&mut self, snippet: &Snippet) {
         for section in &snippet.sections {
             if let Section::Signature(sig) = section {
-                if let SignatureKind::Struct(struct_sig) = &sig.kind {
-                    let mut fields = HashMap::new();
-                    for (i, field) in struct_sig.fields.iter().enumerate() {
-                        fields.insert(field.name.clone(), FieldLayout {
-                            offset: (i as u32) * 8,
-                            size: 8,
-                            wasm_type: WasmType::I64,
-                        });
-                    }
-                    self.struct_layouts.insert(struct_sig.name.clone(), StructLayout {
-                        size: (struct_sig.fields.len() as u32) * 8,
-                        alignment: 8,
-                        fields,
-                    });
+                if let SignatureKind::Alias(alias_sig) = &sig.kind {
+                    self.type_aliases.insert(alias_sig.name.clone(), alias_sig.ty.clone());
                 }
             }
         }
     }
 
-    /// Compile a single function snippet
-    fn compile_function_snippet(&mut self, snippet: &Snippet) -> Result<Function, CodegenError> {
-        let sig = find_function_signature(snippet)
-            .ok_or_else(|| CodegenError::UndefinedFunction {
-                name: snippet.id.clone(),
-            })?;
-
-        let body = find_body_section(snippet);
-
-        // Reset locals
+    /// Register a struct snippet's layout for field access
+    /// Record the unit aliases declared in a snippet's types section
+    fn register_unit_types(&mut self, snippet: &Snippet) {
+        for section in &snippet.sections {
+            if let Section::Types(types) = section {
+                for decl in types.types.iter().filter(|d| d.unit.is_some()) {
+                    self.unit_types.insert(decl.name.clone());
+                }
+            }
+        }
+    }
+
+    fn register_struct_layout(&mut self, snippet: &Snippet) {
+        // Find struct signature
+        for section in &snippet.sections {
+            if let Section::Signature(sig) = section {
+                if let SignatureKind::Struct(struct_sig) = &sig.kind {
+                    match &struct_sig.fields[..] {
+                        [field] if struct_sig.newtype => {
+                            // No layout: field reads of an unlaid-out value
+                            // yield the value itself
+                            self.newtypes.insert(struct_sig.name.clone(), field.ty.clone());
+                        }
+                        _ => {
+                            let mut fields = HashMap::new();
+                            for (i, field) in struct_sig.fields.iter().enumerate() {
+                                fields.insert(field.name.clone(), FieldLayout {
+                                    offset: (i as u32) * 8,
+                                    size: 8,
+                                    wasm_type: WasmType::I64,
+                                });
+                            }
+                            self.struct_layouts.insert(struct_sig.name.clone(), StructLayout {
+                                size: (struct_sig.fields.len() as u32) * 8,
+                                alignment: 8,
+                                fields,
+                            });
+                        }
+                    }
+
+                    let checks: Vec<InvariantCheck> = snippet.sections.iter()
+                        .filter_map(|s| match s {
+                            Section::Invariant(invariant) => Some(invariant.checks.iter().cloned()),
+                            _ => None,
+                        })
+                        .flatten()
+                        .collect();
+                    if !checks.is_empty() {
+                        self.invariants.insert(struct_sig.name.clone(), checks);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Compile a single function snippet
+    fn compile_function_snippet(&mut self, snippet: &Snippet) -> Result<Function, CodegenError> {
+        let sig = find_function_signature(snippet)
+            .ok_or_else(|| CodegenError::UndefinedFunction {
+                name: snippet.id.clone(),
+            })?;
+
+        let body = find_body_section(snippet);
+        self.current_snippet = snippet.id.clone();
+
+        // Reset locals
         self.locals.clear();
         self.local_count = 0;
         self.local_types.clear();
+        self.closure_locals.clear();
+        self.label_depth = 0;
+        self.loop_labels.clear();
+        self.usings.clear();
 
         // Add parameters as locals and track their struct types
         for param in &sig.params {
@@ -1076,7 +1747,8 @@ impl<'a> SnippetWasmCompiler<'a> {
             0
         };
 
-        let mut wasm_func = Function::new(vec![(additional_locals, ValType::I64)]);
+        let first_vector_local = sig.params.len() as u32 + additional_locals;
+        let mut wasm_func = Function::new(self.function_locals(additional_locals, first_vector_local, body.map(|b| b.steps.as_slice())));
 
         // Compile body steps
         if let Some(body) = body {
@@ -1110,7 +1782,7 @@ impl<'a> SnippetWasmCompiler<'a> {
             }
             // Count nested steps and special cases
             match &step.kind {
-                StepKind::Compute(_) => {}
+                StepKind::Compute(compute) => count += compute_scratch_locals(compute.op),
                 StepKind::If(if_step) => {
                     count += self.count_step_bindings(&if_step.then_steps);
                     if let Some(else_steps) = &if_step.else_steps {
@@ -1135,9 +1807,15 @@ impl<'a> SnippetWasmCompiler<'a> {
                     // Extern calls need a temp local per argument for fat pointer unpacking
                     count += call.args.len() as u32;
                 }
-                StepKind::Construct(_) => {
-                    // Struct construction needs a temp local for the pointer
-                    count += 1;
+                StepKind::Using(using) => {
+                    // The resource local plus the argument temps of the acquire and release calls
+                    count += 1 + using.acquire.args.len() as u32 + 1;
+                    count += self.count_step_bindings(&using.steps);
+                }
+                StepKind::Construct(_) | StepKind::Lambda(_) => {
+                    // Struct and closure construction need a temp local for the pointer,
+                    // plus one holding a constructed struct while its invariants are checked
+                    count += 2;
                 }
                 StepKind::Return(ret) => {
                     // Return with struct construction needs a temp local for the pointer
@@ -1179,6 +1857,7 @@ impl<'a> SnippetWasmCompiler<'a> {
 
     /// Compile a single step
     fn compile_step(&mut self, step: &Step, func: &mut Function) -> Result<(), CodegenError> {
+        self.current_step = step.id.clone();
         match &step.kind {
             StepKind::Compute(compute) => {
                 self.compile_compute_step(compute, func)?;
@@ -1201,8 +1880,17 @@ impl<'a> SnippetWasmCompiler<'a> {
                     func.instruction(&Instruction::Drop);
                 }
             }
+            StepKind::Pipeline(_) => unreachable!("pipelines are lowered before code generation"),
             StepKind::Return(ret) => {
                 self.compile_return_step(ret, func)?;
+                // The return value stays on the stack below the release calls
+                self.release_resources(0, func)?;
+                func.instruction(&Instruction::Return);
+            }
+            StepKind::Raise(raise) => {
+                // Raised errors use the union representation of returned variants
+                func.instruction(&Instruction::I64Const(variant_tag(&raise.variant.ty)));
+                self.release_resources(0, func)?;
                 func.instruction(&Instruction::Return);
             }
             StepKind::If(if_step) => {
@@ -1224,6 +1912,39 @@ impl<'a> SnippetWasmCompiler<'a> {
             StepKind::For(for_step) => {
                 self.compile_for_step(for_step, func)?;
             }
+            StepKind::Using(using) => {
+                self.compile_using_step(using, &step.id, &step.output_binding, func)?;
+            }
+            StepKind::Assert(assert) => {
+                self.compile_check(assert.op, &assert.inputs, assert.message.as_deref(), func)?;
+                if step.output_binding != "_" {
+                    func.instruction(&Instruction::I64Const(0));
+                    let local = self.allocate_local(&step.output_binding);
+                    func.instruction(&Instruction::LocalSet(local));
+                }
+            }
+            StepKind::Lambda(lambda) => {
+                self.compile_lambda_step(lambda, func)?;
+                if step.output_binding != "_" {
+                    let local = self.allocate_local(&step.output_binding);
+                    func.instruction(&Instruction::LocalSet(local));
+                    self.closure_locals.insert(step.output_binding.clone());
+                } else {
+                    func.instruction(&Instruction::Drop);
+                }
+            }
+            StepKind::Break(_) => {
+                let (break_label, _) = *self.loop_labels.last()
+                    .ok_or_else(|| CodegenError::LoopControlOutsideLoop { kind: "break".to_string() })?;
+                self.release_resources(self.loop_labels.len(), func)?;
+                func.instruction(&Instruction::Br(self.label_depth - break_label));
+            }
+            StepKind::Continue(_) => {
+                let (_, continue_label) = *self.loop_labels.last()
+                    .ok_or_else(|| CodegenError::LoopControlOutsideLoop { kind: "continue".to_string() })?;
+                self.release_resources(self.loop_labels.len(), func)?;
+                func.instruction(&Instruction::Br(self.label_depth - continue_label));
+            }
             StepKind::Query(query) => {
                 self.compile_query_step(query, func)?;
                 // Store result if not discarded
@@ -1240,14 +1961,45 @@ impl<'a> SnippetWasmCompiler<'a> {
                     TypeKind::Named(path) => path.name().to_string(),
                     _ => format!("{:?}", construct.ty.kind),
                 };
+                if self.unit_types.contains(&type_name) {
+                    // Wrapping a value in its unit type leaves the value as is
+                    let value = construct.fields.iter().find(|f| f.name == "value")
+                        .ok_or_else(|| CodegenError::UndefinedFunction { name: format!("{}.value", type_name) })?;
+                    self.compile_input(&value.value, func)?;
+                    if step.output_binding != "_" {
+                        let local = self.allocate_local(&step.output_binding);
+                        func.instruction(&Instruction::LocalSet(local));
+                    } else {
+                        func.instruction(&Instruction::Drop);
+                    }
+                    return Ok(());
+                }
+                if self.newtypes.contains_key(&type_name) {
+                    // A newtype is its single field's value, checked against
+                    // the invariants like any struct
+                    match (construct.fields.first(), &construct.from_base) {
+                        (Some(field), _) => self.compile_input(&field.value, func)?,
+                        (None, Some(base)) => self.compile_input(&InputSource::Var(base.clone()), func)?,
+                        (None, None) => return Err(CodegenError::UndefinedFunction { name: format!("{} field", type_name) }),
+                    }
+                    self.compile_invariant_checks(&type_name, func)?;
+                    if step.output_binding != "_" {
+                        let local = self.allocate_local(&step.output_binding);
+                        func.instruction(&Instruction::LocalSet(local));
+                    } else {
+                        func.instruction(&Instruction::Drop);
+                    }
+                    return Ok(());
+                }
                 if !self.struct_layouts.contains_key(&type_name) {
                     let layout = Self::compute_struct_layout(construct);
                     self.struct_layouts.insert(type_name.clone(), layout);
                 }
                 if step.output_binding != "_" {
-                    self.local_types.insert(step.output_binding.clone(), type_name);
+                    self.local_types.insert(step.output_binding.clone(), type_name.clone());
                 }
-                self.compile_construct_step(construct, func)?;
+                self.compile_construct_step(construct, &type_name, func)?;
+                self.compile_invariant_checks(&type_name, func)?;
                 // Store result if not discarded
                 if step.output_binding != "_" {
                     let local = self.allocate_local(&step.output_binding);
@@ -1288,11 +2040,13 @@ impl<'a> SnippetWasmCompiler<'a> {
                 // TODO: Parallel execution requires runtime support.
                 // For now, we compile branches sequentially and collect results.
                 // A proper implementation would use host functions for concurrent execution.
+                let loop_labels = std::mem::take(&mut self.loop_labels);
                 for branch in &parallel.branches {
                     for nested_step in &branch.steps {
                         self.compile_step(nested_step, func)?;
                     }
                 }
+                self.loop_labels = loop_labels;
                 // Generate a placeholder value for the results
                 if step.output_binding != "_" {
                     func.instruction(&Instruction::I64Const(0));
@@ -1304,11 +2058,13 @@ impl<'a> SnippetWasmCompiler<'a> {
                 // TODO: Race execution requires runtime support.
                 // For now, we compile the first branch as the "winner".
                 // A proper implementation would use host functions for race semantics.
+                let loop_labels = std::mem::take(&mut self.loop_labels);
                 if let Some(first_branch) = race.branches.first() {
                     for nested_step in &first_branch.steps {
                         self.compile_step(nested_step, func)?;
                     }
                 }
+                self.loop_labels = loop_labels;
                 // Generate a placeholder value for the result
                 if step.output_binding != "_" {
                     func.instruction(&Instruction::I64Const(0));
@@ -1347,7 +2103,12 @@ impl<'a> SnippetWasmCompiler<'a> {
         // Compile each case as an if-else chain
         for (i, case) in match_step.cases.iter().enumerate() {
             match &case.pattern {
-                MatchPattern::Variant { variant: _, bindings } => {
+                MatchPattern::Type { .. } => {
+                    // A union holds its member's value as is, so nothing at
+                    // runtime tells which member it is
+                    return Err(CodegenError::UnsupportedTypeMatch { on: match_step.on.clone() });
+                }
+                MatchPattern::Variant { bindings, .. } => {
                     // Load the value (or its tag)
                     func.instruction(&Instruction::LocalGet(match_local));
 
@@ -1357,12 +2118,13 @@ impl<'a> SnippetWasmCompiler<'a> {
                     func.instruction(&Instruction::I64Const(tag_value));
                     func.instruction(&Instruction::I64Eq);
                     func.instruction(&Instruction::If(BlockType::Empty));
+                    self.label_depth += 1;
 
                     // Set up bindings for destructured values
                     // For now, we assume single binding gets the value
-                    if !bindings.is_empty() {
+                    if let Some(binding) = bindings.first() {
                         func.instruction(&Instruction::LocalGet(match_local));
-                        let binding_local = self.allocate_local(&bindings[0]);
+                        let binding_local = self.allocate_local(binding);
                         func.instruction(&Instruction::LocalSet(binding_local));
                     }
 
@@ -1390,6 +2152,7 @@ impl<'a> SnippetWasmCompiler<'a> {
         for (i, case) in match_step.cases.iter().enumerate() {
             if !matches!(case.pattern, MatchPattern::Wildcard) {
                 func.instruction(&Instruction::End);
+                self.label_depth -= 1;
             }
             // Only close non-wildcard cases that aren't the last
             if i < num_cases - 1 && matches!(case.pattern, MatchPattern::Variant { .. }) {
@@ -1406,6 +2169,161 @@ impl<'a> SnippetWasmCompiler<'a> {
         Ok(())
     }
 
+    /// Compile a using step
+    ///
+    /// The acquired resource is stored in the step's `var` local and the
+    /// release call is emitted at the end of the body. While the body
+    /// compiles, the resource stays on the `usings` stack so that `return`,
+    /// `break` and `continue` release it before branching out. A trap aborts
+    /// the whole instance, so nothing is released on that path.
+    fn compile_using_step(
+        &mut self,
+        using: &UsingStep,
+        step_id: &str,
+        output_binding: &str,
+        func: &mut Function,
+    ) -> Result<(), CodegenError> {
+        self.compile_call_step(&using.acquire, func)?;
+        let resource = self.allocate_local(&using.var);
+        if self.call_has_return_value(&using.acquire.fn_name) {
+            func.instruction(&Instruction::LocalSet(resource));
+        }
+
+        let held = HeldResource {
+            step: step_id.to_string(),
+            var: using.var.clone(),
+            release: using.release.clone(),
+            loop_level: self.loop_labels.len(),
+        };
+        self.usings.push(held.clone());
+        let result = using.steps.iter().try_for_each(|step| self.compile_step(step, func));
+        self.usings.pop();
+        result?;
+        self.compile_release(&held, func)?;
+
+        // The step's value is the last body step's binding
+        if output_binding != "_" {
+            let last = using.steps.last()
+                .and_then(|step| self.locals.get(&step.output_binding).copied());
+            match last {
+                Some(local) => func.instruction(&Instruction::LocalGet(local)),
+                None => func.instruction(&Instruction::I64Const(0)),
+            };
+            let local = self.allocate_local(output_binding);
+            func.instruction(&Instruction::LocalSet(local));
+        }
+        Ok(())
+    }
+
+    /// Release the resources of enclosing using steps, innermost first,
+    /// that were acquired inside `loop_level` or more for loops
+    fn release_resources(&mut self, loop_level: usize, func: &mut Function) -> Result<(), CodegenError> {
+        let held: Vec<HeldResource> = self.usings.iter()
+            .filter(|held| held.loop_level >= loop_level)
+            .rev()
+            .cloned()
+            .collect();
+        for resource in &held {
+            self.compile_release(resource, func)?;
+        }
+        Ok(())
+    }
+
+    /// Call a resource's release function with it, discarding any result
+    fn compile_release(&mut self, held: &HeldResource, func: &mut Function) -> Result<(), CodegenError> {
+        let call = CallStep {
+            fn_name: held.release.clone(),
+            type_args: vec![],
+            args: vec![CallArg {
+                name: "resource".to_string(),
+                source: InputSource::Var(held.var.clone()),
+                span: Span::default(),
+            }],
+            handle: None,
+            expects: vec![],
+            span: Span::default(),
+        };
+        let current_step = std::mem::replace(&mut self.current_step, held.step.clone());
+        let result = self.compile_call_step(&call, func);
+        self.current_step = current_step;
+        result?;
+        if self.call_has_return_value(&held.release) {
+            func.instruction(&Instruction::Drop);
+        }
+        Ok(())
+    }
+
+    /// Trap unless the condition `op` over `inputs` holds, first printing
+    /// `message` to stderr with `console.error` if there is one
+    ///
+    /// Assert steps are removed by the optimizer at O2 and above, and so are
+    /// struct invariant sections, so release builds contain no checks.
+    fn compile_check(
+        &mut self,
+        op: Operation,
+        inputs: &[Input],
+        message: Option<&str>,
+        func: &mut Function,
+    ) -> Result<(), CodegenError> {
+        let condition = ComputeStep { op, inputs: inputs.to_vec(), span: Span::default() };
+        self.compile_compute_step(&condition, func)?;
+        func.instruction(&Instruction::I64Eqz);
+        func.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+        self.label_depth += 1;
+
+        if let Some(message) = message {
+            let report = CallStep {
+                fn_name: "console.error".to_string(),
+                type_args: vec![],
+                args: vec![CallArg {
+                    name: "message".to_string(),
+                    source: InputSource::Lit(Literal::String(message.to_string())),
+                    span: Span::default(),
+                }],
+                handle: None,
+                expects: vec![],
+                span: Span::default(),
+            };
+            self.compile_call_step(&report, func)?;
+        }
+        func.instruction(&Instruction::Unreachable);
+
+        func.instruction(&Instruction::End);
+        self.label_depth -= 1;
+        Ok(())
+    }
+
+    /// Check the invariants of `type_name` against the struct pointer on
+    /// top of the stack, leaving the pointer in place
+    ///
+    /// Each check reads its `input var=` operands as fields of the struct.
+    fn compile_invariant_checks(&mut self, type_name: &str, func: &mut Function) -> Result<(), CodegenError> {
+        let Some(checks) = self.invariants.get(type_name).cloned() else {
+            return Ok(());
+        };
+        const SUBJECT: &str = "__invariant_subject";
+        let subject = self.allocate_local(SUBJECT);
+        self.local_types.insert(SUBJECT.to_string(), type_name.to_string());
+        func.instruction(&Instruction::LocalSet(subject));
+
+        let result = checks.iter().try_for_each(|check| {
+            let inputs: Vec<Input> = check.inputs.iter()
+                .map(|input| match &input.source {
+                    InputSource::Var(field) => Input {
+                        source: InputSource::Field { of: SUBJECT.to_string(), field: field.clone() },
+                        span: input.span,
+                    },
+                    _ => input.clone(),
+                })
+                .collect();
+            self.compile_check(check.op, &inputs, check.message.as_deref(), func)
+        });
+        self.local_types.remove(SUBJECT);
+        result?;
+        func.instruction(&Instruction::LocalGet(subject));
+        Ok(())
+    }
+
     /// Compile a for loop step
     ///
     /// For loops iterate over collections. We compile to a WASM loop with
@@ -1483,10 +2401,19 @@ impl<'a> SnippetWasmCompiler<'a> {
         }));
         func.instruction(&Instruction::LocalSet(item_local));
 
-        // Compile loop body
+        // Compile loop body inside a block so `continue` can branch to the
+        // increment; `break` branches to the end of the inner block
+        func.instruction(&Instruction::Block(BlockType::Empty)); // body block for continue
+        let break_label = self.label_depth + 2;
+        let continue_label = self.label_depth + 4;
+        self.label_depth += 4;
+        self.loop_labels.push((break_label, continue_label));
         for step in &for_step.steps {
             self.compile_step(step, func)?;
         }
+        self.loop_labels.pop();
+        self.label_depth -= 4;
+        func.instruction(&Instruction::End); // end body block
 
         // Increment index
         func.instruction(&Instruction::LocalGet(index_local));
@@ -1527,37 +2454,69 @@ impl<'a> SnippetWasmCompiler<'a> {
 
     /// Compile a struct construction step using linear memory allocation.
     ///
-    /// Allocates space on the heap (bump allocator via global 0), stores each
-    /// field at its computed offset, and leaves the struct pointer (as i64) on the stack.
+    /// Allocates space on the heap, stores each
+    /// field at its offset in the layout of `type_name`, and leaves the struct
+    /// pointer (as i64) on the stack. With `from_base`, fields that are not
+    /// listed are copied from the base struct.
     ///
     /// All locals are i64, so the pointer is stored as i64 (zero-extended from i32).
     /// For memory operations, we wrap i64 back to i32.
     fn compile_construct_step(
         &mut self,
         construct: &StructConstruction,
+        type_name: &str,
         func: &mut Function,
     ) -> Result<(), CodegenError> {
-        let struct_size = (construct.fields.len() as u32) * 8;
+        let layout = self.struct_layouts.get(type_name).cloned()
+            .unwrap_or_else(|| Self::compute_struct_layout(construct));
+        let mut offsets: Vec<(String, u32)> = layout.fields.iter()
+            .map(|(name, field)| (name.clone(), field.offset))
+            .collect();
+        offsets.sort_by_key(|(_, offset)| *offset);
+
+        let struct_size = layout.size;
         let ptr_local = self.allocate_local("__struct_ptr");
 
-        // Bump-allocate: ptr = heap_ptr; heap_ptr += size
-        // GlobalGet(0) returns i32, extend to i64 for our local
-        func.instruction(&Instruction::GlobalGet(0));
-        func.instruction(&Instruction::I64ExtendI32U);
-        func.instruction(&Instruction::LocalTee(ptr_local));
-        // Compute new heap_ptr: wrap back to i32, add size, set global
-        func.instruction(&Instruction::I32WrapI64);
         func.instruction(&Instruction::I32Const(struct_size as i32));
-        func.instruction(&Instruction::I32Add);
-        func.instruction(&Instruction::GlobalSet(0));
+        self.compile_alloc(func);
+        func.instruction(&Instruction::LocalSet(ptr_local));
+
+        // Unlisted fields come from the base struct, which has the same
+        // layout: with bulk memory the whole struct is copied up front,
+        // otherwise each field is copied on its own
+        let copy_fields = match &construct.from_base {
+            Some(base) if self.features.contains(WasmFeature::BulkMemory) => {
+                func.instruction(&Instruction::LocalGet(ptr_local));
+                func.instruction(&Instruction::I32WrapI64);
+                self.compile_input(&InputSource::Var(base.clone()), func)?;
+                func.instruction(&Instruction::I32WrapI64);
+                func.instruction(&Instruction::I32Const(struct_size as i32));
+                func.instruction(&Instruction::MemoryCopy { src_mem: 0, dst_mem: 0 });
+                false
+            }
+            from_base => from_base.is_some(),
+        };
 
         // Store each field at its offset
-        for (i, field) in construct.fields.iter().enumerate() {
-            let offset = (i as u32) * 8;
+        for (name, offset) in offsets {
+            let listed = construct.fields.iter().find(|f| f.name == name);
+            if listed.is_none() && !copy_fields {
+                continue;
+            }
             // Get ptr as i32 for memory address
             func.instruction(&Instruction::LocalGet(ptr_local));
             func.instruction(&Instruction::I32WrapI64);
-            self.compile_input(&field.value, func)?;
+            if let Some(field) = listed {
+                self.compile_input(&field.value, func)?;
+            } else if let (true, Some(base)) = (copy_fields, &construct.from_base) {
+                self.compile_input(&InputSource::Var(base.clone()), func)?;
+                func.instruction(&Instruction::I32WrapI64);
+                func.instruction(&Instruction::I64Load(MemArg {
+                    offset: offset as u64,
+                    align: 3,
+                    memory_index: 0,
+                }));
+            }
             func.instruction(&Instruction::I64Store(MemArg {
                 offset: offset as u64,
                 align: 3, // 2^3 = 8 byte alignment
@@ -1675,19 +2634,12 @@ impl<'a> SnippetWasmCompiler<'a> {
         func.instruction(&Instruction::LocalSet(total_nodes));
 
         // Step 2: Allocate result array (max size = total_nodes * 4 bytes per i32 index)
-        // result_ptr = heap_ptr; heap_ptr += total_nodes * 4
-        // Global(0) is i32, extend to i64 for our local
-        func.instruction(&Instruction::GlobalGet(0)); // heap_ptr (i32)
-        func.instruction(&Instruction::I64ExtendI32U);
-        func.instruction(&Instruction::LocalTee(result_ptr));
-        // For heap calculation, work in i32
-        func.instruction(&Instruction::I32WrapI64);
         func.instruction(&Instruction::LocalGet(total_nodes));
         func.instruction(&Instruction::I32WrapI64);
         func.instruction(&Instruction::I32Const(4));
         func.instruction(&Instruction::I32Mul);
-        func.instruction(&Instruction::I32Add);
-        func.instruction(&Instruction::GlobalSet(0)); // Update heap_ptr
+        self.compile_alloc(func);
+        func.instruction(&Instruction::LocalSet(result_ptr));
 
         // Initialize result_count = 0 (extend to i64)
         func.instruction(&Instruction::I32Const(0));
@@ -1844,10 +2796,24 @@ impl<'a> SnippetWasmCompiler<'a> {
         func.instruction(&Instruction::I64Const(0));
         func.instruction(&Instruction::Else);
 
-        // Allocate result array on heap
-        // For simplicity, allocate space for up to 256 results (256 * 4 bytes = 1024)
-        func.instruction(&Instruction::GlobalGet(0)); // heap_ptr (i32)
-        func.instruction(&Instruction::I64ExtendI32U);
+        // Allocate result array on heap: each relation in the traversed
+        // directions yields at most one result
+        let count_fns = match traverse.direction {
+            TraverseDirection::Outgoing => vec![gai.get_outgoing_count],
+            TraverseDirection::Incoming => vec![gai.get_incoming_count],
+            TraverseDirection::Both => vec![gai.get_outgoing_count, gai.get_incoming_count],
+        };
+        for (i, count_fn) in count_fns.into_iter().enumerate() {
+            func.instruction(&Instruction::LocalGet(start_node));
+            func.instruction(&Instruction::I32WrapI64);
+            func.instruction(&Instruction::Call(count_fn));
+            if i > 0 {
+                func.instruction(&Instruction::I32Add);
+            }
+        }
+        func.instruction(&Instruction::I32Const(4));
+        func.instruction(&Instruction::I32Mul);
+        self.compile_alloc(func);
         func.instruction(&Instruction::LocalSet(result_ptr));
 
         // Initialize result_count = 0
@@ -1873,15 +2839,6 @@ impl<'a> SnippetWasmCompiler<'a> {
             }
         }
 
-        // Update heap pointer (result_ptr + result_count * 4)
-        func.instruction(&Instruction::LocalGet(result_ptr));
-        func.instruction(&Instruction::I32WrapI64);
-        func.instruction(&Instruction::LocalGet(result_count));
-        func.instruction(&Instruction::I32WrapI64);
-        func.instruction(&Instruction::I32Const(4));
-        func.instruction(&Instruction::I32Mul);
-        func.instruction(&Instruction::I32Add);
-        func.instruction(&Instruction::GlobalSet(0));
 
         // Return fat pointer (result_ptr << 32 | result_count)
         func.instruction(&Instruction::LocalGet(result_ptr));
@@ -2990,8 +3947,139 @@ impl<'a> SnippetWasmCompiler<'a> {
         Ok(())
     }
 
+    /// Compile an Int add, sub or mul whose operands are on the stack,
+    /// handling overflow as `overflow` says
+    fn compile_int_arithmetic(&mut self, op: Operation, overflow: Overflow, func: &mut Function) {
+        let lhs = self.allocate_local("__arith_lhs");
+        let rhs = self.allocate_local("__arith_rhs");
+        let result = self.allocate_local("__arith_result");
+        func.instruction(&Instruction::LocalSet(rhs));
+        func.instruction(&Instruction::LocalTee(lhs));
+        func.instruction(&Instruction::LocalGet(rhs));
+        func.instruction(&match op {
+            Operation::Add => Instruction::I64Add,
+            Operation::Sub => Instruction::I64Sub,
+            _ => Instruction::I64Mul,
+        });
+        func.instruction(&Instruction::LocalSet(result));
+
+        // Overflow flag (i32)
+        match op {
+            // Both operands have the sign the result lacks: (lhs ^ r) & (rhs ^ r) < 0
+            Operation::Add => {
+                func.instruction(&Instruction::LocalGet(lhs));
+                func.instruction(&Instruction::LocalGet(result));
+                func.instruction(&Instruction::I64Xor);
+                func.instruction(&Instruction::LocalGet(rhs));
+                func.instruction(&Instruction::LocalGet(result));
+                func.instruction(&Instruction::I64Xor);
+                func.instruction(&Instruction::I64And);
+                func.instruction(&Instruction::I64Const(0));
+                func.instruction(&Instruction::I64LtS);
+            }
+            // Operand signs differ and the result lost lhs's sign: (lhs ^ rhs) & (lhs ^ r) < 0
+            Operation::Sub => {
+                func.instruction(&Instruction::LocalGet(lhs));
+                func.instruction(&Instruction::LocalGet(rhs));
+                func.instruction(&Instruction::I64Xor);
+                func.instruction(&Instruction::LocalGet(lhs));
+                func.instruction(&Instruction::LocalGet(result));
+                func.instruction(&Instruction::I64Xor);
+                func.instruction(&Instruction::I64And);
+                func.instruction(&Instruction::I64Const(0));
+                func.instruction(&Instruction::I64LtS);
+            }
+            // lhs == 0: never; lhs == -1: only rhs == MIN; otherwise r / lhs != rhs
+            _ => {
+                func.instruction(&Instruction::LocalGet(lhs));
+                func.instruction(&Instruction::I64Eqz);
+                func.instruction(&Instruction::If(BlockType::Result(ValType::I32)));
+                func.instruction(&Instruction::I32Const(0));
+                func.instruction(&Instruction::Else);
+                func.instruction(&Instruction::LocalGet(lhs));
+                func.instruction(&Instruction::I64Const(-1));
+                func.instruction(&Instruction::I64Eq);
+                func.instruction(&Instruction::If(BlockType::Result(ValType::I32)));
+                func.instruction(&Instruction::LocalGet(rhs));
+                func.instruction(&Instruction::I64Const(i64::MIN));
+                func.instruction(&Instruction::I64Eq);
+                func.instruction(&Instruction::Else);
+                func.instruction(&Instruction::LocalGet(result));
+                func.instruction(&Instruction::LocalGet(lhs));
+                func.instruction(&Instruction::I64DivS);
+                func.instruction(&Instruction::LocalGet(rhs));
+                func.instruction(&Instruction::I64Ne);
+                func.instruction(&Instruction::End);
+                func.instruction(&Instruction::End);
+            }
+        }
+
+        if overflow == Overflow::Trap {
+            func.instruction(&Instruction::If(BlockType::Empty));
+            func.instruction(&Instruction::Unreachable);
+            func.instruction(&Instruction::End);
+            func.instruction(&Instruction::LocalGet(result));
+            return;
+        }
+
+        func.instruction(&Instruction::If(BlockType::Result(ValType::I64)));
+        if overflow == Overflow::Saturate {
+            // Add and sub overflow toward lhs's sign; mul toward the sign of the true product
+            func.instruction(&Instruction::I64Const(i64::MIN));
+            func.instruction(&Instruction::I64Const(i64::MAX));
+            func.instruction(&Instruction::LocalGet(lhs));
+            if op == Operation::Mul {
+                func.instruction(&Instruction::LocalGet(rhs));
+                func.instruction(&Instruction::I64Xor);
+            }
+            func.instruction(&Instruction::I64Const(0));
+            func.instruction(&Instruction::I64LtS);
+            func.instruction(&Instruction::Select);
+        } else {
+            func.instruction(&Instruction::I64Const(i64::MIN));
+        }
+        func.instruction(&Instruction::Else);
+        func.instruction(&Instruction::LocalGet(result));
+        func.instruction(&Instruction::End);
+    }
+
     /// Compile a compute step
     fn compile_compute_step(&mut self, compute: &ComputeStep, func: &mut Function) -> Result<(), CodegenError> {
+        let regex_import = match compute.op {
+            Operation::RegexMatch => Some(self.runtime.regex_match),
+            Operation::RegexFindAll => Some(self.runtime.regex_find_all),
+            Operation::RegexCapture => Some(self.runtime.regex_capture),
+            Operation::RegexReplace => Some(self.runtime.regex_replace),
+            _ => None,
+        };
+        if let Some(import) = regex_import {
+            let idx = import.ok_or(CodegenError::UnsupportedExpression)?;
+            return self.compile_host_op(compute, idx, func);
+        }
+        if encoding_import_name(compute.op).is_some() {
+            let idx = *self.runtime.encoding.get(&compute.op).ok_or(CodegenError::UnsupportedExpression)?;
+            return self.compile_host_op(compute, idx, func);
+        }
+        if exact_import_name(compute.op).is_some() {
+            let idx = *self.runtime.exact.get(&compute.op).ok_or(CodegenError::UnsupportedExpression)?;
+            return self.compile_exact_op(compute, idx, func);
+        }
+        if json_import_name(compute.op).is_some() {
+            let idx = *self.runtime.json.get(&compute.op).ok_or(CodegenError::UnsupportedExpression)?;
+            return self.compile_host_op(compute, idx, func);
+        }
+        if matches!(compute.op, Operation::DecFormat | Operation::BigFormat | Operation::JsonStringify) {
+            // The runtime value already is the canonical text
+            let input = compute.inputs.first().ok_or(CodegenError::UnsupportedExpression)?;
+            return self.compile_input(&input.source, func);
+        }
+        if is_array_op(compute.op) {
+            return self.compile_array_op(compute, func);
+        }
+        if is_numeric_op(compute.op) {
+            return self.compile_numeric_op(compute, func);
+        }
+
         // Push inputs onto stack
         for input in &compute.inputs {
             self.compile_input(&input.source, func)?;
@@ -3000,11 +4088,22 @@ impl<'a> SnippetWasmCompiler<'a> {
         // Emit operation instruction
         // Note: Comparison operations return i32, we extend to i64 for uniform storage
         match compute.op {
-            Operation::Add => { func.instruction(&Instruction::I64Add); }
-            Operation::Sub => { func.instruction(&Instruction::I64Sub); }
-            Operation::Mul => { func.instruction(&Instruction::I64Mul); }
+            // add/sub/mul trap on overflow; release builds get the wrapping
+            // forms from the optimizer
+            Operation::Add => self.compile_int_arithmetic(Operation::Add, Overflow::Trap, func),
+            Operation::Sub => self.compile_int_arithmetic(Operation::Sub, Overflow::Trap, func),
+            Operation::Mul => self.compile_int_arithmetic(Operation::Mul, Overflow::Trap, func),
             Operation::Div => { func.instruction(&Instruction::I64DivS); }
             Operation::Mod => { func.instruction(&Instruction::I64RemS); }
+            Operation::AddWrapping => { func.instruction(&Instruction::I64Add); }
+            Operation::SubWrapping => { func.instruction(&Instruction::I64Sub); }
+            Operation::MulWrapping => { func.instruction(&Instruction::I64Mul); }
+            Operation::AddSaturating => self.compile_int_arithmetic(Operation::Add, Overflow::Saturate, func),
+            Operation::SubSaturating => self.compile_int_arithmetic(Operation::Sub, Overflow::Saturate, func),
+            Operation::MulSaturating => self.compile_int_arithmetic(Operation::Mul, Overflow::Saturate, func),
+            Operation::AddChecked => self.compile_int_arithmetic(Operation::Add, Overflow::Checked, func),
+            Operation::SubChecked => self.compile_int_arithmetic(Operation::Sub, Overflow::Checked, func),
+            Operation::MulChecked => self.compile_int_arithmetic(Operation::Mul, Overflow::Checked, func),
             // Comparison ops return i32, extend to i64
             Operation::Equals => {
                 func.instruction(&Instruction::I64Eq);
@@ -3043,10 +4142,12 @@ impl<'a> SnippetWasmCompiler<'a> {
                 func.instruction(&Instruction::I64ExtendI32U);
             }
             Operation::Neg => {
-                // Negate: compute 0 - x
-                // First swap the operand with 0
+                // Negate: compute 0 - x, trapping on MIN
+                let tmp = self.allocate_local("__neg_tmp");
+                func.instruction(&Instruction::LocalSet(tmp));
                 func.instruction(&Instruction::I64Const(0));
-                func.instruction(&Instruction::I64Sub);
+                func.instruction(&Instruction::LocalGet(tmp));
+                self.compile_int_arithmetic(Operation::Sub, Overflow::Trap, func);
             }
 
             // Bitwise operations
@@ -3064,7 +4165,7 @@ impl<'a> SnippetWasmCompiler<'a> {
 
             // Numeric operations
             Operation::Abs => {
-                // abs(x) = if x < 0 then -x else x
+                // abs(x) = if x < 0 then -x else x, trapping on MIN
                 let tmp = self.allocate_local("__abs_tmp");
                 func.instruction(&Instruction::LocalTee(tmp));
                 func.instruction(&Instruction::I64Const(0));
@@ -3072,7 +4173,7 @@ impl<'a> SnippetWasmCompiler<'a> {
                 func.instruction(&Instruction::If(BlockType::Result(ValType::I64)));
                 func.instruction(&Instruction::I64Const(0));
                 func.instruction(&Instruction::LocalGet(tmp));
-                func.instruction(&Instruction::I64Sub);
+                self.compile_int_arithmetic(Operation::Sub, Overflow::Trap, func);
                 func.instruction(&Instruction::Else);
                 func.instruction(&Instruction::LocalGet(tmp));
                 func.instruction(&Instruction::End);
@@ -3154,6 +4255,42 @@ impl<'a> SnippetWasmCompiler<'a> {
                 func.instruction(&Instruction::End);
             }
 
+            // Durations are i64 seconds and DateTimes i64 Unix seconds, so their
+            // arithmetic is Int arithmetic that traps on overflow
+            Operation::DurSeconds | Operation::DurTotalSeconds => {}
+            Operation::DurMinutes | Operation::DurHours | Operation::DurDays => {
+                let seconds = match compute.op {
+                    Operation::DurMinutes => 60,
+                    Operation::DurHours => 60 * 60,
+                    _ => 24 * 60 * 60,
+                };
+                func.instruction(&Instruction::I64Const(seconds));
+                self.compile_int_arithmetic(Operation::Mul, Overflow::Trap, func);
+            }
+            Operation::DurAdd | Operation::DtAdd => self.compile_int_arithmetic(Operation::Add, Overflow::Trap, func),
+            Operation::DurSub | Operation::DtSub | Operation::DtDiff => {
+                self.compile_int_arithmetic(Operation::Sub, Overflow::Trap, func)
+            }
+            Operation::DurScale => self.compile_int_arithmetic(Operation::Mul, Overflow::Trap, func),
+
+            // None is the i64::MIN sentinel
+            Operation::IsSome | Operation::IsNone => {
+                func.instruction(&Instruction::I64Const(i64::MIN));
+                func.instruction(&if compute.op == Operation::IsSome { Instruction::I64Ne } else { Instruction::I64Eq });
+                func.instruction(&Instruction::I64ExtendI32U);
+            }
+            Operation::UnwrapOr => {
+                let default = self.allocate_local("__unwrap_default");
+                let value = self.allocate_local("__unwrap_value");
+                func.instruction(&Instruction::LocalSet(default));
+                func.instruction(&Instruction::LocalTee(value));
+                func.instruction(&Instruction::LocalGet(default));
+                func.instruction(&Instruction::LocalGet(value));
+                func.instruction(&Instruction::I64Const(i64::MIN));
+                func.instruction(&Instruction::I64Ne);
+                func.instruction(&Instruction::Select);
+            }
+
             // All other operations are not yet supported in WASM codegen
             _ => {
                 return Err(CodegenError::UnsupportedExpression);
@@ -3163,32 +4300,749 @@ impl<'a> SnippetWasmCompiler<'a> {
         Ok(())
     }
 
-    /// Compile a call step
-    fn compile_call_step(&mut self, call: &CallStep, func: &mut Function) -> Result<(), CodegenError> {
-        // Check for runtime/builtin functions first
-        if let Some(idx) = self.try_compile_runtime_call(call, func)? {
-            func.instruction(&Instruction::Call(idx));
-            return Ok(());
-        }
+    /// Compile a fixed-size array operation
+    ///
+    /// Arrays share the list layout, so `for` iterates them: the value is a
+    /// fat pointer (base << 32 | len) to an i32 count followed by i64
+    /// elements. Indexes trap when out of range, except constant indexes,
+    /// which the checker has proven in range; those compile to a load or
+    /// store at a fixed offset.
+    fn compile_array_op(&mut self, compute: &ComputeStep, func: &mut Function) -> Result<(), CodegenError> {
+        let input = |i: usize| compute.inputs.get(i).map(|input| &input.source).ok_or(CodegenError::UnsupportedExpression);
+        match compute.op {
+            Operation::ArrayOf | Operation::ArrayFill => {
+                let len = if compute.op == Operation::ArrayOf {
+                    compute.inputs.len() as i64
+                } else {
+                    self.constant_int(input(1)?).ok_or(CodegenError::UnsupportedExpression)?
+                };
+                let len_local = self.allocate_local("__array_len");
+                func.instruction(&Instruction::I64Const(len));
+                func.instruction(&Instruction::LocalSet(len_local));
+                let base = self.compile_array_alloc("__array_base", len_local, func);
+
+                if compute.op == Operation::ArrayOf {
+                    for (i, element) in compute.inputs.iter().enumerate() {
+                        func.instruction(&Instruction::LocalGet(base));
+                        func.instruction(&Instruction::I32WrapI64);
+                        self.compile_element_input(&element.source, func)?;
+                        func.instruction(&Instruction::I64Store(array_element(i as u64 * 8)));
+                    }
+                } else {
+                    let value = self.allocate_local("__array_value");
+                    self.compile_element_input(input(0)?, func)?;
+                    func.instruction(&Instruction::LocalSet(value));
+                    let i = self.compile_array_loop_start("__array_i", len_local, func);
+                    self.compile_element_address(base, i, func);
+                    func.instruction(&Instruction::LocalGet(value));
+                    func.instruction(&Instruction::I64Store(array_element(0)));
+                    Self::compile_array_loop_end(i, func);
+                }
 
-        // Regular user-defined function call
-        // Push arguments onto stack
-        for arg in &call.args {
-            self.compile_input(&arg.source, func)?;
-        }
+                // value = base << 32 | len
+                func.instruction(&Instruction::LocalGet(base));
+                func.instruction(&Instruction::I64Const(32));
+                func.instruction(&Instruction::I64Shl);
+                func.instruction(&Instruction::LocalGet(len_local));
+                func.instruction(&Instruction::I64Or);
+            }
+            Operation::ArrayGet | Operation::ArraySet => {
+                let array = self.allocate_local("__array_src");
+                self.compile_input(input(0)?, func)?;
+                func.instruction(&Instruction::LocalSet(array));
+                let index = self.compile_array_index(array, input(1)?, func)?;
+
+                let target = if compute.op == Operation::ArrayGet {
+                    self.compile_array_base(array, func);
+                    array
+                } else {
+                    // Copy the elements into a new array, then replace one
+                    let len_local = self.allocate_local("__array_len");
+                    func.instruction(&Instruction::LocalGet(array));
+                    func.instruction(&Instruction::I64Const(0xffff_ffff));
+                    func.instruction(&Instruction::I64And);
+                    func.instruction(&Instruction::LocalSet(len_local));
+                    let base = self.compile_array_alloc("__array_base", len_local, func);
+                    let src_base = self.allocate_local("__array_src_base");
+                    self.compile_array_base(array, func);
+                    func.instruction(&Instruction::I64ExtendI32U);
+                    func.instruction(&Instruction::LocalSet(src_base));
+                    if self.features.contains(WasmFeature::BulkMemory) {
+                        // memory.copy(base + 4, src_base + 4, len * 8)
+                        for local in [base, src_base] {
+                            func.instruction(&Instruction::LocalGet(local));
+                            func.instruction(&Instruction::I32WrapI64);
+                            func.instruction(&Instruction::I32Const(4));
+                            func.instruction(&Instruction::I32Add);
+                        }
+                        func.instruction(&Instruction::LocalGet(len_local));
+                        func.instruction(&Instruction::I32WrapI64);
+                        func.instruction(&Instruction::I32Const(3));
+                        func.instruction(&Instruction::I32Shl);
+                        func.instruction(&Instruction::MemoryCopy { src_mem: 0, dst_mem: 0 });
+                    } else {
+                        let i = self.compile_array_loop_start("__array_i", len_local, func);
+                        self.compile_element_address(base, i, func);
+                        self.compile_element_address(src_base, i, func);
+                        func.instruction(&Instruction::I64Load(array_element(0)));
+                        func.instruction(&Instruction::I64Store(array_element(0)));
+                        Self::compile_array_loop_end(i, func);
+                    }
 
-        // Get function index
-        let idx = self.function_indices.get(&call.fn_name)
-            .ok_or_else(|| CodegenError::UndefinedFunction { name: call.fn_name.clone() })?;
+                    func.instruction(&Instruction::LocalGet(base));
+                    func.instruction(&Instruction::I32WrapI64);
+                    base
+                };
 
-        func.instruction(&Instruction::Call(*idx));
+                let offset = match index {
+                    ArrayIndex::Constant(index) => index,
+                    ArrayIndex::Local(index) => {
+                        func.instruction(&Instruction::LocalGet(index));
+                        func.instruction(&Instruction::I32WrapI64);
+                        func.instruction(&Instruction::I32Const(3));
+                        func.instruction(&Instruction::I32Shl);
+                        func.instruction(&Instruction::I32Add);
+                        0
+                    }
+                };
+                if compute.op == Operation::ArrayGet {
+                    func.instruction(&Instruction::I64Load(array_element(offset)));
+                } else {
+                    self.compile_element_input(input(2)?, func)?;
+                    func.instruction(&Instruction::I64Store(array_element(offset)));
+                    // The new array keeps the source's length
+                    func.instruction(&Instruction::LocalGet(target));
+                    func.instruction(&Instruction::I64Const(32));
+                    func.instruction(&Instruction::I64Shl);
+                    func.instruction(&Instruction::LocalGet(array));
+                    func.instruction(&Instruction::I64Const(0xffff_ffff));
+                    func.instruction(&Instruction::I64And);
+                    func.instruction(&Instruction::I64Or);
+                }
+            }
+            Operation::ArrayLen => {
+                self.compile_input(input(0)?, func)?;
+                func.instruction(&Instruction::I64Const(0xffff_ffff));
+                func.instruction(&Instruction::I64And);
+            }
+            _ => return Err(CodegenError::UnsupportedExpression),
+        }
         Ok(())
     }
 
-    /// Try to compile a call to an extern-abstract function.
-    /// Returns Some(import_index) if this is a registered extern function, None otherwise.
-    fn try_compile_runtime_call(&mut self, call: &CallStep, func: &mut Function) -> Result<Option<u32>, CodegenError> {
-        // Look up the function name in registered extern imports
+    /// Bump-allocate an array of the length in `len` and write its count,
+    /// returning the local named `base` that holds its base address
+    fn compile_array_alloc(&mut self, base: &str, len: u32, func: &mut Function) -> u32 {
+        let base = self.allocate_local(base);
+        // base = alloc(4 + len * 8)
+        func.instruction(&Instruction::LocalGet(len));
+        func.instruction(&Instruction::I32WrapI64);
+        func.instruction(&Instruction::I32Const(3));
+        func.instruction(&Instruction::I32Shl);
+        func.instruction(&Instruction::I32Const(4));
+        func.instruction(&Instruction::I32Add);
+        self.compile_alloc(func);
+        func.instruction(&Instruction::LocalSet(base));
+
+        func.instruction(&Instruction::LocalGet(base));
+        func.instruction(&Instruction::I32WrapI64);
+        func.instruction(&Instruction::LocalGet(len));
+        func.instruction(&Instruction::I32WrapI64);
+        func.instruction(&Instruction::I32Store(MemArg {
+            offset: 0,
+            align: 2,
+            memory_index: 0,
+        }));
+        base
+    }
+
+    /// Push the base address (i32) of the array value in `array`
+    fn compile_array_base(&mut self, array: u32, func: &mut Function) {
+        func.instruction(&Instruction::LocalGet(array));
+        func.instruction(&Instruction::I64Const(32));
+        func.instruction(&Instruction::I64ShrU);
+        func.instruction(&Instruction::I32WrapI64);
+    }
+
+    /// Resolve an index into the array value in `array`. A constant index
+    /// becomes a byte offset past the first element; any other index is
+    /// stored in a local after checking it against the length, trapping
+    /// when it is out of range.
+    fn compile_array_index(&mut self, array: u32, index: &InputSource, func: &mut Function) -> Result<ArrayIndex, CodegenError> {
+        if let Some(index) = self.constant_int(index).and_then(|i| u64::try_from(i).ok()) {
+            return Ok(ArrayIndex::Constant(index * 8));
+        }
+        let local = self.allocate_local("__array_idx");
+        self.compile_input(index, func)?;
+        func.instruction(&Instruction::LocalTee(local));
+        // Unsigned, so negative indexes are out of range too
+        func.instruction(&Instruction::LocalGet(array));
+        func.instruction(&Instruction::I64Const(0xffff_ffff));
+        func.instruction(&Instruction::I64And);
+        func.instruction(&Instruction::I64GeU);
+        func.instruction(&Instruction::If(BlockType::Empty));
+        func.instruction(&Instruction::Unreachable);
+        func.instruction(&Instruction::End);
+        Ok(ArrayIndex::Local(local))
+    }
+
+    /// Push the address (i32) of element `index` of the array whose base
+    /// address is in `base`; loads and stores add the element offset
+    fn compile_element_address(&mut self, base: u32, index: u32, func: &mut Function) {
+        func.instruction(&Instruction::LocalGet(base));
+        func.instruction(&Instruction::I32WrapI64);
+        func.instruction(&Instruction::LocalGet(index));
+        func.instruction(&Instruction::I32WrapI64);
+        func.instruction(&Instruction::I32Const(3));
+        func.instruction(&Instruction::I32Shl);
+        func.instruction(&Instruction::I32Add);
+    }
+
+    /// Open a loop counting from 0 up to the value of `len`, returning the
+    /// counter local named `counter`; close it with
+    /// [`Self::compile_array_loop_end`]
+    fn compile_array_loop_start(&mut self, counter: &str, len: u32, func: &mut Function) -> u32 {
+        let i = self.allocate_local(counter);
+        func.instruction(&Instruction::I64Const(0));
+        func.instruction(&Instruction::LocalSet(i));
+        func.instruction(&Instruction::Block(BlockType::Empty));
+        func.instruction(&Instruction::Loop(BlockType::Empty));
+        func.instruction(&Instruction::LocalGet(i));
+        func.instruction(&Instruction::LocalGet(len));
+        func.instruction(&Instruction::I64GeU);
+        func.instruction(&Instruction::BrIf(1));
+        i
+    }
+
+    fn compile_array_loop_end(i: u32, func: &mut Function) {
+        func.instruction(&Instruction::LocalGet(i));
+        func.instruction(&Instruction::I64Const(1));
+        func.instruction(&Instruction::I64Add);
+        func.instruction(&Instruction::LocalSet(i));
+        func.instruction(&Instruction::Br(0));
+        func.instruction(&Instruction::End);
+        func.instruction(&Instruction::End);
+    }
+    /// Push an input as an array element. Float literals are pushed as
+    /// their bits, the way Float elements are stored.
+    fn compile_element_input(&mut self, source: &InputSource, func: &mut Function) -> Result<(), CodegenError> {
+        if let InputSource::Lit(Literal::Float(n)) = source {
+            func.instruction(&Instruction::I64Const(n.to_bits() as i64));
+            return Ok(());
+        }
+        self.compile_input(source, func)
+    }
+
+    /// Push the length (i64) of the array value in `array`
+    fn compile_array_len(array: u32, func: &mut Function) {
+        func.instruction(&Instruction::LocalGet(array));
+        func.instruction(&Instruction::I64Const(0xffff_ffff));
+        func.instruction(&Instruction::I64And);
+    }
+
+    /// Trap unless the array value in `array` has the length in `len`
+    fn compile_length_check(array: u32, len: u32, func: &mut Function) {
+        Self::compile_array_len(array, func);
+        func.instruction(&Instruction::LocalGet(len));
+        func.instruction(&Instruction::I64Ne);
+        func.instruction(&Instruction::If(BlockType::Empty));
+        func.instruction(&Instruction::Unreachable);
+        func.instruction(&Instruction::End);
+    }
+
+    /// Store the base address of the array value in `array` in the local
+    /// named `base`, for [`Self::compile_element_address`]
+    fn compile_base_local(&mut self, base: &str, array: u32, func: &mut Function) -> u32 {
+        let base = self.allocate_local(base);
+        func.instruction(&Instruction::LocalGet(array));
+        func.instruction(&Instruction::I64Const(32));
+        func.instruction(&Instruction::I64ShrU);
+        func.instruction(&Instruction::LocalSet(base));
+        base
+    }
+
+    /// Push the array value (base << 32 | len) of the array at `base`
+    fn compile_array_value(base: u32, len: u32, func: &mut Function) {
+        func.instruction(&Instruction::LocalGet(base));
+        func.instruction(&Instruction::I64Const(32));
+        func.instruction(&Instruction::I64Shl);
+        func.instruction(&Instruction::LocalGet(len));
+        func.instruction(&Instruction::I64Or);
+    }
+
+    /// Compile a numeric vector operation. Vectors and matrix rows use the
+    /// array layout with Float elements stored as f64s; scalar Floats are
+    /// held as their bits. Lengths that do not match trap.
+    fn compile_numeric_op(&mut self, compute: &ComputeStep, func: &mut Function) -> Result<(), CodegenError> {
+        let input = |i: usize| compute.inputs.get(i).map(|input| &input.source).ok_or(CodegenError::UnsupportedExpression);
+        let a = self.allocate_local("__vec_a");
+        self.compile_input(input(0)?, func)?;
+        func.instruction(&Instruction::LocalSet(a));
+        let b = self.allocate_local("__vec_b");
+        self.compile_element_input(input(1)?, func)?;
+        func.instruction(&Instruction::LocalSet(b));
+
+        match compute.op {
+            Operation::Dot => self.compile_dot(a, b, func),
+            Operation::Matmul => self.compile_matmul(a, b, func),
+            op => self.compile_elementwise(op, a, b, func),
+        }
+        Ok(())
+    }
+
+    /// `vec_add`, `vec_sub` and `vec_scale` into a new vector
+    fn compile_elementwise(&mut self, op: Operation, a: u32, b: u32, func: &mut Function) {
+        let len = self.allocate_local("__vec_len");
+        Self::compile_array_len(a, func);
+        func.instruction(&Instruction::LocalSet(len));
+        let scale = op == Operation::VecScale;
+        if !scale {
+            Self::compile_length_check(b, len, func);
+        }
+        let out = self.compile_array_alloc("__array_base", len, func);
+        let a_base = self.compile_base_local("__vec_a_base", a, func);
+        let b_base = if scale { b } else { self.compile_base_local("__vec_b_base", b, func) };
+
+        self.compile_vector_loop(len, func, |this, i, lanes, func| {
+            this.compile_element_address(out, i, func);
+            this.compile_element_address(a_base, i, func);
+            func.instruction(&lanes.load());
+            if scale {
+                func.instruction(&Instruction::LocalGet(b));
+                func.instruction(&Instruction::F64ReinterpretI64);
+                lanes.splat(func);
+            } else {
+                this.compile_element_address(b_base, i, func);
+                func.instruction(&lanes.load());
+            }
+            func.instruction(&lanes.arithmetic(op));
+            func.instruction(&lanes.store());
+        });
+        Self::compile_array_value(out, len, func);
+    }
+
+    /// `dot`: the sum of products, as the bits of a Float. With SIMD, pairs
+    /// of products are summed in the vector local, which changes the order
+    /// of the additions and so can change the last bits of the result.
+    fn compile_dot(&mut self, a: u32, b: u32, func: &mut Function) {
+        let len = self.allocate_local("__vec_len");
+        Self::compile_array_len(a, func);
+        func.instruction(&Instruction::LocalSet(len));
+        Self::compile_length_check(b, len, func);
+        let a_base = self.compile_base_local("__vec_a_base", a, func);
+        let b_base = self.compile_base_local("__vec_b_base", b, func);
+
+        // 0.0 has all bits clear
+        let sum = self.allocate_local("__dot_sum");
+        func.instruction(&Instruction::I64Const(0));
+        func.instruction(&Instruction::LocalSet(sum));
+        let vector = self.vector_local;
+        if let Some(vector) = vector {
+            func.instruction(&Instruction::V128Const(0));
+            func.instruction(&Instruction::LocalSet(vector));
+        }
+
+        self.compile_vector_loop(len, func, |this, i, lanes, func| {
+            this.compile_element_address(a_base, i, func);
+            func.instruction(&lanes.load());
+            this.compile_element_address(b_base, i, func);
+            func.instruction(&lanes.load());
+            lanes.multiply(func);
+            match (lanes, vector) {
+                (Lanes::Two { .. }, Some(vector)) => {
+                    func.instruction(&Instruction::LocalGet(vector));
+                    lanes.accumulate(func);
+                    func.instruction(&Instruction::LocalSet(vector));
+                }
+                _ => {
+                    func.instruction(&Instruction::LocalGet(sum));
+                    func.instruction(&Instruction::F64ReinterpretI64);
+                    lanes.accumulate(func);
+                    func.instruction(&Instruction::I64ReinterpretF64);
+                    func.instruction(&Instruction::LocalSet(sum));
+                }
+            }
+        });
+
+        func.instruction(&Instruction::LocalGet(sum));
+        func.instruction(&Instruction::F64ReinterpretI64);
+        if let Some(vector) = vector {
+            for lane in 0..2 {
+                func.instruction(&Instruction::LocalGet(vector));
+                func.instruction(&Instruction::F64x2ExtractLane(lane));
+                func.instruction(&Instruction::F64Add);
+            }
+        }
+        func.instruction(&Instruction::I64ReinterpretF64);
+    }
+
+    /// `matmul` of an M×K matrix `a` by a K×N matrix `b`. Each row of the
+    /// result is accumulated as `out[r] += a[r][k] * b[k]` for each k, so the
+    /// innermost loop runs along rows and vectorizes.
+    fn compile_matmul(&mut self, a: u32, b: u32, func: &mut Function) {
+        let rows = self.allocate_local("__mat_rows");
+        Self::compile_array_len(a, func);
+        func.instruction(&Instruction::LocalSet(rows));
+        let inner = self.allocate_local("__mat_inner");
+        Self::compile_array_len(b, func);
+        func.instruction(&Instruction::LocalSet(inner));
+        let a_base = self.compile_base_local("__mat_a_base", a, func);
+        let b_base = self.compile_base_local("__mat_b_base", b, func);
+
+        // The result has as many columns as b's first row, or none when b
+        // has no rows
+        let cols = self.allocate_local("__mat_cols");
+        func.instruction(&Instruction::LocalGet(inner));
+        func.instruction(&Instruction::I64Eqz);
+        func.instruction(&Instruction::If(BlockType::Result(ValType::I64)));
+        func.instruction(&Instruction::I64Const(0));
+        func.instruction(&Instruction::Else);
+        func.instruction(&Instruction::LocalGet(b_base));
+        func.instruction(&Instruction::I32WrapI64);
+        func.instruction(&Instruction::I64Load(array_element(0)));
+        func.instruction(&Instruction::I64Const(0xffff_ffff));
+        func.instruction(&Instruction::I64And);
+        func.instruction(&Instruction::End);
+        func.instruction(&Instruction::LocalSet(cols));
+
+        let out = self.compile_array_alloc("__mat_out", rows, func);
+        let r = self.compile_array_loop_start("__mat_r", rows, func);
+        {
+            let a_row = self.load_row("__mat_a_row", a_base, r, func);
+            Self::compile_length_check(a_row, inner, func);
+            let a_row_base = self.compile_base_local("__mat_a_row_base", a_row, func);
+            let out_row = self.compile_array_alloc("__mat_out_row", cols, func);
+            self.compile_vector_loop(cols, func, |this, j, lanes, func| {
+                this.compile_element_address(out_row, j, func);
+                lanes.zero(func);
+                func.instruction(&lanes.store());
+            });
+
+            let k = self.compile_array_loop_start("__mat_k", inner, func);
+            {
+                let a_rk = self.allocate_local("__mat_a_rk");
+                self.compile_element_address(a_row_base, k, func);
+                func.instruction(&Instruction::I64Load(array_element(0)));
+                func.instruction(&Instruction::LocalSet(a_rk));
+                let b_row = self.load_row("__mat_b_row", b_base, k, func);
+                Self::compile_length_check(b_row, cols, func);
+                let b_row_base = self.compile_base_local("__mat_b_row_base", b_row, func);
+                self.compile_vector_loop(cols, func, |this, j, lanes, func| {
+                    this.compile_element_address(out_row, j, func);
+                    func.instruction(&Instruction::LocalGet(a_rk));
+                    func.instruction(&Instruction::F64ReinterpretI64);
+                    lanes.splat(func);
+                    this.compile_element_address(b_row_base, j, func);
+                    func.instruction(&lanes.load());
+                    lanes.multiply(func);
+                    this.compile_element_address(out_row, j, func);
+                    func.instruction(&lanes.load());
+                    lanes.accumulate(func);
+                    func.instruction(&lanes.store());
+                });
+            }
+            Self::compile_array_loop_end(k, func);
+
+            self.compile_element_address(out, r, func);
+            Self::compile_array_value(out_row, cols, func);
+            func.instruction(&Instruction::I64Store(array_element(0)));
+        }
+        Self::compile_array_loop_end(r, func);
+        Self::compile_array_value(out, rows, func);
+    }
+
+    /// Load row `index` of the matrix whose base address is in `base` into
+    /// the local named `row`
+    fn load_row(&mut self, row: &str, base: u32, index: u32, func: &mut Function) -> u32 {
+        let row = self.allocate_local(row);
+        self.compile_element_address(base, index, func);
+        func.instruction(&Instruction::I64Load(array_element(0)));
+        func.instruction(&Instruction::LocalSet(row));
+        row
+    }
+
+    /// Run `body` for each element index below the value of `len`. With
+    /// SIMD, a first loop takes two elements per pass and a second finishes
+    /// an odd element; `body` gets the index of the first element and how
+    /// many it handles.
+    fn compile_vector_loop(
+        &mut self,
+        len: u32,
+        func: &mut Function,
+        mut body: impl FnMut(&mut Self, u32, Lanes, &mut Function),
+    ) {
+        let i = self.allocate_local("__vec_i");
+        func.instruction(&Instruction::I64Const(0));
+        func.instruction(&Instruction::LocalSet(i));
+        let passes = if self.vector_local.is_some() {
+            vec![Lanes::Two { fused: self.features.contains(WasmFeature::RelaxedSimd) }, Lanes::One]
+        } else {
+            vec![Lanes::One]
+        };
+        for lanes in passes {
+            // Stop when fewer than `lanes` elements are left
+            func.instruction(&Instruction::Block(BlockType::Empty));
+            func.instruction(&Instruction::Loop(BlockType::Empty));
+            func.instruction(&Instruction::LocalGet(i));
+            func.instruction(&Instruction::I64Const(lanes.width()));
+            func.instruction(&Instruction::I64Add);
+            func.instruction(&Instruction::LocalGet(len));
+            func.instruction(&Instruction::I64GtU);
+            func.instruction(&Instruction::BrIf(1));
+            body(self, i, lanes, func);
+            func.instruction(&Instruction::LocalGet(i));
+            func.instruction(&Instruction::I64Const(lanes.width()));
+            func.instruction(&Instruction::I64Add);
+            func.instruction(&Instruction::LocalSet(i));
+            func.instruction(&Instruction::Br(0));
+            func.instruction(&Instruction::End);
+            func.instruction(&Instruction::End);
+        }
+    }
+
+    /// Local declarations for a function body: `scratch` i64 locals, then a
+    /// v128 local at index `vector_local` when SIMD is on and `steps` use
+    /// numeric vector operations
+    fn function_locals(&mut self, scratch: u32, vector_local: u32, steps: Option<&[Step]>) -> Vec<(u32, ValType)> {
+        let mut locals = vec![(scratch, ValType::I64)];
+        self.vector_local = None;
+        if self.features.contains(WasmFeature::Simd) && steps.is_some_and(uses_numeric_ops) {
+            locals.push((1, ValType::V128));
+            self.vector_local = Some(vector_local);
+        }
+        locals
+    }
+
+    /// An Int input known at compile time: a literal or a const snippet
+    fn constant_int(&self, source: &InputSource) -> Option<i64> {
+        let value = match source {
+            InputSource::Lit(lit) => Some(lit.clone()),
+            InputSource::Var(name) => self.constant_value(name, None),
+            InputSource::Field { of, field } => self.constant_value(of, Some(field)),
+        };
+        match value {
+            Some(Literal::Int(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Compile a regex or encoding op as a call to its host import, passing
+    /// each String or Bytes input as (ptr, len)
+    fn compile_host_op(&mut self, compute: &ComputeStep, import: u32, func: &mut Function) -> Result<(), CodegenError> {
+        for (i, input) in compute.inputs.iter().enumerate() {
+            self.compile_input(&input.source, func)?;
+            let temp = self.allocate_local(&format!("__host_arg_{}", i));
+            func.instruction(&Instruction::LocalSet(temp));
+            // ptr = fat_ptr >> 32
+            func.instruction(&Instruction::LocalGet(temp));
+            func.instruction(&Instruction::I64Const(32));
+            func.instruction(&Instruction::I64ShrU);
+            func.instruction(&Instruction::I32WrapI64);
+            // len = fat_ptr & 0xFFFFFFFF
+            func.instruction(&Instruction::LocalGet(temp));
+            func.instruction(&Instruction::I32WrapI64);
+        }
+        func.instruction(&Instruction::Call(import));
+        Ok(())
+    }
+
+    /// Call a Decimal or BigInt import. Text inputs are split into (ptr, len)
+    /// pairs like other host ops; `dec_from_int` and `big_from_int` pass
+    /// their Int, and the places of `dec_div` and `dec_round` go as an i32.
+    fn compile_exact_op(&mut self, compute: &ComputeStep, import: u32, func: &mut Function) -> Result<(), CodegenError> {
+        if matches!(compute.op, Operation::DecFromInt | Operation::BigFromInt) {
+            let input = compute.inputs.first().ok_or(CodegenError::UnsupportedExpression)?;
+            self.compile_input(&input.source, func)?;
+            func.instruction(&Instruction::Call(import));
+            return Ok(());
+        }
+        let places = match compute.op {
+            Operation::DecDiv => Some(2),
+            Operation::DecRound => Some(1),
+            _ => None,
+        };
+        for (i, input) in compute.inputs.iter().enumerate() {
+            self.compile_input(&input.source, func)?;
+            if places == Some(i) {
+                func.instruction(&Instruction::I32WrapI64);
+                continue;
+            }
+            let temp = self.allocate_local(&format!("__host_arg_{}", i));
+            func.instruction(&Instruction::LocalSet(temp));
+            func.instruction(&Instruction::LocalGet(temp));
+            func.instruction(&Instruction::I64Const(32));
+            func.instruction(&Instruction::I64ShrU);
+            func.instruction(&Instruction::I32WrapI64);
+            func.instruction(&Instruction::LocalGet(temp));
+            func.instruction(&Instruction::I32WrapI64);
+        }
+        func.instruction(&Instruction::Call(import));
+        Ok(())
+    }
+
+    /// Compile a call step
+    fn compile_call_step(&mut self, call: &CallStep, func: &mut Function) -> Result<(), CodegenError> {
+        // A closure in scope shadows functions of the same name; other locals
+        // (e.g. function-valued parameters) are called only if no function matches
+        if let Some(&closure) = self.locals.get(&call.fn_name) {
+            let is_function = self.function_indices.contains_key(&call.fn_name)
+                || self.extern_imports.contains_key(&call.fn_name);
+            if self.closure_locals.contains(&call.fn_name) || !is_function {
+                return self.compile_closure_call(closure, call, func);
+            }
+        }
+
+        // Check for runtime/builtin functions first
+        if let Some(idx) = self.try_compile_runtime_call(call, func)? {
+            func.instruction(&Instruction::Call(idx));
+            return Ok(());
+        }
+
+        // Regular user-defined function call
+        // Push arguments onto stack
+        for arg in &call.args {
+            self.compile_input(&arg.source, func)?;
+        }
+
+        // Get function index
+        let idx = self.function_indices.get(&call.fn_name)
+            .ok_or_else(|| CodegenError::UndefinedFunction { name: call.fn_name.clone() })?;
+
+        func.instruction(&Instruction::Call(*idx));
+        Ok(())
+    }
+
+    /// Call the closure in `closure` indirectly through the lambda table
+    fn compile_closure_call(&mut self, closure: u32, call: &CallStep, func: &mut Function) -> Result<(), CodegenError> {
+        let arity = call.args.len();
+        if self.lambdas.max_arity().is_none_or(|max| arity > max) {
+            return Err(CodegenError::UnsupportedClosureCall { name: call.fn_name.clone(), arity });
+        }
+
+        // Lifted lambdas take the closure first, then the arguments
+        func.instruction(&Instruction::LocalGet(closure));
+        for arg in &call.args {
+            self.compile_input(&arg.source, func)?;
+        }
+
+        // The closure record starts with the lambda's table slot
+        func.instruction(&Instruction::LocalGet(closure));
+        func.instruction(&Instruction::I32WrapI64);
+        func.instruction(&Instruction::I64Load(MemArg { offset: 0, align: 3, memory_index: 0 }));
+        func.instruction(&Instruction::I32WrapI64);
+        func.instruction(&Instruction::CallIndirect {
+            ty: self.lambdas.type_base + arity as u32,
+            table: 0,
+        });
+        Ok(())
+    }
+
+    /// Create a closure for a lambda and compile the lambda as a lifted function.
+    ///
+    /// The closure is a heap record holding the lambda's table slot followed by
+    /// the enclosing body's named locals, captured by value. The lifted function
+    /// loads them back into locals of the same names before running the body.
+    fn compile_lambda_step(&mut self, lambda: &LambdaStep, func: &mut Function) -> Result<(), CodegenError> {
+        let slot = *self.lambdas.slots.get(&LambdaTable::slot_key(lambda))
+            .ok_or_else(|| CodegenError::UndefinedFunction { name: "lambda".to_string() })?;
+
+        let mut captures: Vec<(String, u32)> = self.locals.iter()
+            .filter(|(name, _)| !name.starts_with("__"))
+            .map(|(name, &idx)| (name.clone(), idx))
+            .collect();
+        captures.sort_by_key(|&(_, idx)| idx);
+
+        // Allocate the closure record
+        let record_size = 8 * (captures.len() as i32 + 1);
+        let ptr_local = self.allocate_local("__closure_ptr");
+        func.instruction(&Instruction::I32Const(record_size));
+        self.compile_alloc(func);
+        func.instruction(&Instruction::LocalSet(ptr_local));
+
+        func.instruction(&Instruction::LocalGet(ptr_local));
+        func.instruction(&Instruction::I32WrapI64);
+        func.instruction(&Instruction::I64Const(slot as i64));
+        func.instruction(&Instruction::I64Store(MemArg { offset: 0, align: 3, memory_index: 0 }));
+        for (i, (_, local)) in captures.iter().enumerate() {
+            func.instruction(&Instruction::LocalGet(ptr_local));
+            func.instruction(&Instruction::I32WrapI64);
+            func.instruction(&Instruction::LocalGet(*local));
+            func.instruction(&Instruction::I64Store(MemArg {
+                offset: 8 * (i as u64 + 1),
+                align: 3,
+                memory_index: 0,
+            }));
+        }
+        func.instruction(&Instruction::LocalGet(ptr_local));
+
+        // Compile the body with a fresh set of locals: closure, params, captures
+        let outer_locals = std::mem::take(&mut self.locals);
+        let outer_local_count = std::mem::replace(&mut self.local_count, 0);
+        let outer_local_types = std::mem::take(&mut self.local_types);
+        let outer_closure_locals = std::mem::take(&mut self.closure_locals);
+        let outer_label_depth = std::mem::replace(&mut self.label_depth, 0);
+        let outer_loop_labels = std::mem::take(&mut self.loop_labels);
+        let outer_usings = std::mem::take(&mut self.usings);
+
+        let closure_local = self.allocate_local("__closure");
+        for param in &lambda.params {
+            self.allocate_local(&param.name);
+        }
+        let body_locals = captures.len() as u32 + self.count_step_bindings(&lambda.steps);
+        let outer_vector_local = self.vector_local;
+        let first_vector_local = self.local_count + body_locals;
+        let mut lifted = Function::new(self.function_locals(body_locals, first_vector_local, Some(&lambda.steps)));
+
+        let result = (|| {
+            for (i, (name, _)) in captures.iter().enumerate() {
+                // Parameters shadow captured bindings of the same name
+                if lambda.params.iter().any(|p| &p.name == name) {
+                    continue;
+                }
+                let local = self.allocate_local(name);
+                lifted.instruction(&Instruction::LocalGet(closure_local));
+                lifted.instruction(&Instruction::I32WrapI64);
+                lifted.instruction(&Instruction::I64Load(MemArg {
+                    offset: 8 * (i as u64 + 1),
+                    align: 3,
+                    memory_index: 0,
+                }));
+                lifted.instruction(&Instruction::LocalSet(local));
+                if let Some(ty) = outer_local_types.get(name) {
+                    self.local_types.insert(name.clone(), ty.clone());
+                }
+                if outer_closure_locals.contains(name) {
+                    self.closure_locals.insert(name.clone());
+                }
+            }
+
+            for step in &lambda.steps {
+                self.compile_step(step, &mut lifted)?;
+            }
+            Ok(())
+        })();
+
+        self.locals = outer_locals;
+        self.local_count = outer_local_count;
+        self.local_types = outer_local_types;
+        self.closure_locals = outer_closure_locals;
+        self.label_depth = outer_label_depth;
+        self.loop_labels = outer_loop_labels;
+        self.usings = outer_usings;
+        self.vector_local = outer_vector_local;
+        result?;
+
+        // Lambdas without a trailing return yield 0
+        lifted.instruction(&Instruction::I64Const(0));
+        lifted.instruction(&Instruction::End);
+        self.lambdas.bodies[slot as usize] = Some(lifted);
+        Ok(())
+    }
+
+    /// Try to compile a call to an extern-abstract function.
+    /// Returns Some(import_index) if this is a registered extern function, None otherwise.
+    fn try_compile_runtime_call(&mut self, call: &CallStep, func: &mut Function) -> Result<Option<u32>, CodegenError> {
+        // Look up the function name in registered extern imports
         let ext = match self.extern_imports.get(&call.fn_name) {
             Some(ext) => ext.clone(),
             None => return Ok(None),
@@ -3220,11 +5074,32 @@ impl<'a> SnippetWasmCompiler<'a> {
             }
         }
 
+        // Tell the host which call site is about to run an effectful extern
+        let (module, name, _, _) = &self.imports.imports[ext.func_index as usize];
+        let import = format!("{}.{}", module, name);
+        if self.audit.externs.contains_key(&import) {
+            let site = self.audit.sites.len() as i32;
+            self.audit.sites.push(AuditSite {
+                snippet: self.current_snippet.clone(),
+                step: self.current_step.clone(),
+                import,
+            });
+            func.instruction(&Instruction::I32Const(site));
+            func.instruction(&Instruction::GlobalSet(AUDIT_SITE_GLOBAL));
+        }
+
         Ok(Some(ext.func_index))
     }
 
     /// Compile a return step
     fn compile_return_step(&mut self, ret: &ReturnStep, func: &mut Function) -> Result<(), CodegenError> {
+        if let ReturnValue::Var(name) = &ret.value {
+            if let Some(global) = self.constant_global(name, None) {
+                func.instruction(&Instruction::GlobalGet(global));
+                return Ok(());
+            }
+        }
+
         match &ret.value {
             ReturnValue::Var(name) => {
                 let local = self.locals.get(name)
@@ -3239,14 +5114,9 @@ impl<'a> SnippetWasmCompiler<'a> {
                 let struct_size = (s.fields.len() as u32) * 8;
                 let ptr_local = self.allocate_local("__ret_struct_ptr");
 
-                // Bump-allocate: ptr = heap_ptr; heap_ptr += size
-                func.instruction(&Instruction::GlobalGet(0));
-                func.instruction(&Instruction::I64ExtendI32U);
-                func.instruction(&Instruction::LocalTee(ptr_local));
-                func.instruction(&Instruction::I32WrapI64);
                 func.instruction(&Instruction::I32Const(struct_size as i32));
-                func.instruction(&Instruction::I32Add);
-                func.instruction(&Instruction::GlobalSet(0));
+                self.compile_alloc(func);
+                func.instruction(&Instruction::LocalSet(ptr_local));
 
                 // Store each field
                 for (i, field) in s.fields.iter().enumerate() {
@@ -3265,11 +5135,7 @@ impl<'a> SnippetWasmCompiler<'a> {
                 func.instruction(&Instruction::LocalGet(ptr_local));
             }
             ReturnValue::Variant(v) => {
-                // For variants, use a tag-based encoding:
-                // Return a tagged value (tag in high bits, payload in low bits)
-                // Simple approach: hash the variant name to get a tag value
-                let tag: i64 = v.ty.bytes().map(|b| b as i64).sum();
-                func.instruction(&Instruction::I64Const(tag));
+                func.instruction(&Instruction::I64Const(variant_tag(&v.ty)));
             }
         }
         Ok(())
@@ -3285,6 +5151,7 @@ impl<'a> SnippetWasmCompiler<'a> {
 
         // Emit if block
         func.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+        self.label_depth += 1;
 
         // Compile then steps
         for step in &if_step.then_steps {
@@ -3300,11 +5167,22 @@ impl<'a> SnippetWasmCompiler<'a> {
         }
 
         func.instruction(&Instruction::End);
+        self.label_depth -= 1;
         Ok(())
     }
 
     /// Compile a bind step
     fn compile_bind_step(&mut self, bind: &BindStep, func: &mut Function) -> Result<(), CodegenError> {
+        let constant = match &bind.source {
+            BindSource::Var(name) => self.constant_global(name, None),
+            BindSource::Field { of, field } => self.constant_global(of, Some(field)),
+            _ => None,
+        };
+        if let Some(global) = constant {
+            func.instruction(&Instruction::GlobalGet(global));
+            return Ok(());
+        }
+
         match &bind.source {
             BindSource::Var(name) => {
                 let local = self.locals.get(name)
@@ -3346,12 +5224,25 @@ impl<'a> SnippetWasmCompiler<'a> {
                     func.instruction(&Instruction::LocalGet(local));
                 }
             }
+            BindSource::Variant(v) => {
+                func.instruction(&Instruction::I64Const(variant_tag(&v.ty)));
+            }
         }
         Ok(())
     }
 
     /// Compile an input source
     fn compile_input(&mut self, source: &InputSource, func: &mut Function) -> Result<(), CodegenError> {
+        let constant = match source {
+            InputSource::Var(name) => self.constant_global(name, None),
+            InputSource::Field { of, field } => self.constant_global(of, Some(field)),
+            InputSource::Lit(_) => None,
+        };
+        if let Some(global) = constant {
+            func.instruction(&Instruction::GlobalGet(global));
+            return Ok(());
+        }
+
         match source {
             InputSource::Var(name) => {
                 let local = self.locals.get(name)
@@ -3398,29 +5289,39 @@ impl<'a> SnippetWasmCompiler<'a> {
 
     /// Compile a literal value
     fn compile_literal(&mut self, lit: &Literal, func: &mut Function) -> Result<(), CodegenError> {
-        match lit {
-            Literal::Int(n) => {
-                func.instruction(&Instruction::I64Const(*n));
-            }
-            Literal::Float(n) => {
-                func.instruction(&Instruction::F64Const(*n));
-            }
-            Literal::Bool(b) => {
-                func.instruction(&Instruction::I64Const(if *b { 1 } else { 0 }));
-            }
-            Literal::None => {
-                // Represent None as sentinel value i64::MIN to distinguish from valid 0
-                func.instruction(&Instruction::I64Const(i64::MIN));
-            }
+        match self.literal_value(lit)? {
+            LiteralValue::I64(n) => func.instruction(&Instruction::I64Const(n)),
+            LiteralValue::F64(n) => func.instruction(&Instruction::F64Const(n)),
+        };
+        Ok(())
+    }
+
+    /// The runtime value of a literal, adding any text it needs to the data
+    /// segment
+    fn literal_value(&mut self, lit: &Literal) -> Result<LiteralValue, CodegenError> {
+        Ok(match lit {
+            Literal::Int(n) => LiteralValue::I64(*n),
+            Literal::Float(n) => LiteralValue::F64(*n),
+            Literal::Bool(b) => LiteralValue::I64(if *b { 1 } else { 0 }),
+            // Represent None as sentinel value i64::MIN to distinguish from valid 0
+            Literal::None => LiteralValue::I64(i64::MIN),
             Literal::String(s) => {
                 // Store string in data segment and return fat pointer (offset << 32 | len)
                 let offset = self.data_segment.add_string(s);
                 let len = s.len() as i64;
-                let packed = ((offset as i64) << 32) | len;
-                func.instruction(&Instruction::I64Const(packed));
+                LiteralValue::I64(((offset as i64) << 32) | len)
             }
-        }
-        Ok(())
+            // Decimals and BigInts live as their canonical text, which the
+            // host's decimal and bigint imports read and write
+            Literal::Decimal(text) | Literal::BigInt(text) => self.literal_value(&Literal::String(text.clone()))?,
+            // DateTimes are Unix seconds and Durations whole seconds
+            Literal::DateTime(seconds) | Literal::Duration(seconds) => LiteralValue::I64(*seconds),
+            Literal::List(_) | Literal::Map(_) | Literal::Set(_) | Literal::Tuple(_) => {
+                // Collections are stored as JSON text, passed as a fat pointer
+                let json = literal_to_json(lit).to_string();
+                self.literal_value(&Literal::String(json))?
+            }
+        })
     }
 
     /// Check if a function signature returns Unit (no WASM return value)
@@ -3467,7 +5368,12 @@ impl<'a> SnippetWasmCompiler<'a> {
                 "Float" => Some(ValType::F64),
                 "Bool" => Some(ValType::I64),
                 "String" => Some(ValType::I64), // Fat pointer (offset << 32 | len)
+                "Decimal" | "BigInt" => Some(ValType::I64), // Fat pointer to the canonical text
                 "Unit" => None, // Unit means no return value in WASM
+                // Aliases are represented as the type they name, and newtypes
+                // as their field
+                name if self.type_aliases.contains_key(name) => self.type_to_valtype(&self.type_aliases[name]),
+                name if self.newtypes.contains_key(name) => self.type_to_valtype(&self.newtypes[name]),
                 // All other named types (enums, structs) are represented as i64
                 // Enums use tag values, structs use packed fields or pointers
                 _ => Some(ValType::I64),
@@ -3528,7 +5434,7 @@ fn find_effects_section(snippet: &Snippet) -> Option<&EffectsSection> {
 }
 
 /// Find the function signature in a snippet
-fn find_function_signature(snippet: &Snippet) -> Option<&FunctionSignature> {
+pub(crate) fn find_function_signature(snippet: &Snippet) -> Option<&FunctionSignature> {
     for section in &snippet.sections {
         if let Section::Signature(sig) = section {
             if let SignatureKind::Function(fn_sig) = &sig.kind {
@@ -3549,71 +5455,207 @@ fn find_body_section(snippet: &Snippet) -> Option<&covenant_ast::BodySection> {
     None
 }
 
-/// Check if a snippet contains any string literals
-fn snippet_has_string_literals(snippet: &Snippet) -> bool {
-    if let Some(body) = find_body_section(snippet) {
-        return steps_have_string_literals(&body.steps);
+/// Check if any step, at any depth, is a compute op satisfying `is_op`
+fn steps_use_op(steps: &[Step], is_op: fn(Operation) -> bool) -> bool {
+    steps.iter().any(|step| {
+        matches!(&step.kind, StepKind::Compute(compute) if is_op(compute.op))
+            || step.nested_steps().iter().any(|nested| steps_use_op(nested, is_op))
+    })
+}
+
+fn is_regex_op(op: Operation) -> bool {
+    matches!(
+        op,
+        Operation::RegexMatch | Operation::RegexFindAll | Operation::RegexCapture | Operation::RegexReplace
+    )
+}
+
+/// Encoding ops, in import registration order
+const ENCODING_OPS: [Operation; 8] = [
+    Operation::BytesToBase64,
+    Operation::BytesFromBase64,
+    Operation::BytesToBase64Url,
+    Operation::BytesFromBase64Url,
+    Operation::BytesToHex,
+    Operation::BytesFromHex,
+    Operation::UrlEncode,
+    Operation::UrlDecode,
+];
+
+/// Decimal and BigInt ops with a host import, in import registration order
+const EXACT_OPS: [Operation; 14] = [
+    Operation::DecAdd,
+    Operation::DecSub,
+    Operation::DecMul,
+    Operation::DecDiv,
+    Operation::DecRound,
+    Operation::DecCmp,
+    Operation::DecFromInt,
+    Operation::BigAdd,
+    Operation::BigSub,
+    Operation::BigMul,
+    Operation::BigDiv,
+    Operation::BigMod,
+    Operation::BigCmp,
+    Operation::BigFromInt,
+];
+
+/// The `decimal` or `bigint` import implementing a Decimal or BigInt op;
+/// `dec_format` and `big_format` need none
+fn exact_import_name(op: Operation) -> Option<(&'static str, &'static str)> {
+    match op {
+        Operation::DecAdd => Some(("decimal", "add")),
+        Operation::DecSub => Some(("decimal", "sub")),
+        Operation::DecMul => Some(("decimal", "mul")),
+        Operation::DecDiv => Some(("decimal", "div")),
+        Operation::DecRound => Some(("decimal", "round")),
+        Operation::DecCmp => Some(("decimal", "cmp")),
+        Operation::DecFromInt => Some(("decimal", "from_int")),
+        Operation::BigAdd => Some(("bigint", "add")),
+        Operation::BigSub => Some(("bigint", "sub")),
+        Operation::BigMul => Some(("bigint", "mul")),
+        Operation::BigDiv => Some(("bigint", "div")),
+        Operation::BigMod => Some(("bigint", "mod")),
+        Operation::BigCmp => Some(("bigint", "cmp")),
+        Operation::BigFromInt => Some(("bigint", "from_int")),
+        _ => None,
     }
-    false
 }
 
-/// Check if steps contain any string literals
-fn steps_have_string_literals(steps: &[Step]) -> bool {
-    for step in steps {
-        match &step.kind {
-            StepKind::Return(ret) => {
-                if let ReturnValue::Lit(Literal::String(_)) = &ret.value {
-                    return true;
-                }
-            }
-            StepKind::Bind(bind) => {
-                if let BindSource::Lit(Literal::String(_)) = &bind.source {
-                    return true;
-                }
-            }
-            StepKind::Compute(compute) => {
-                for input in &compute.inputs {
-                    if let InputSource::Lit(Literal::String(_)) = &input.source {
-                        return true;
-                    }
-                }
-            }
-            StepKind::If(if_step) => {
-                if steps_have_string_literals(&if_step.then_steps) {
-                    return true;
-                }
-                if let Some(else_steps) = &if_step.else_steps {
-                    if steps_have_string_literals(else_steps) {
-                        return true;
-                    }
-                }
-            }
-            StepKind::Match(match_step) => {
-                for case in &match_step.cases {
-                    if steps_have_string_literals(&case.steps) {
-                        return true;
-                    }
-                }
-            }
-            StepKind::For(for_step) => {
-                if steps_have_string_literals(&for_step.steps) {
-                    return true;
-                }
-            }
-            _ => {}
+/// Parameters of a Decimal or BigInt import
+fn exact_import_params(op: Operation) -> Vec<ValType> {
+    match op {
+        Operation::DecFromInt | Operation::BigFromInt => vec![ValType::I64],
+        Operation::DecRound => vec![ValType::I32; 3],
+        Operation::DecDiv => vec![ValType::I32; 5],
+        _ => vec![ValType::I32; 4],
+    }
+}
+
+/// Json ops with a host import, in import registration order
+const JSON_OPS: [Operation; 4] = [
+    Operation::JsonParse,
+    Operation::JsonGet,
+    Operation::JsonSet,
+    Operation::JsonKeys,
+];
+
+/// The `json_doc` import implementing a Json op and how many text inputs it
+/// takes; `json_stringify` needs none
+fn json_import_name(op: Operation) -> Option<(&'static str, usize)> {
+    match op {
+        Operation::JsonParse => Some(("parse", 1)),
+        Operation::JsonGet => Some(("get", 2)),
+        Operation::JsonSet => Some(("set", 3)),
+        Operation::JsonKeys => Some(("keys", 1)),
+        _ => None,
+    }
+}
+
+/// The `encoding` import implementing an encoding op
+fn encoding_import_name(op: Operation) -> Option<&'static str> {
+    match op {
+        Operation::BytesToBase64 => Some("base64_encode"),
+        Operation::BytesFromBase64 => Some("base64_decode"),
+        Operation::BytesToBase64Url => Some("base64url_encode"),
+        Operation::BytesFromBase64Url => Some("base64url_decode"),
+        Operation::BytesToHex => Some("hex_encode"),
+        Operation::BytesFromHex => Some("hex_decode"),
+        Operation::UrlEncode => Some("url_encode"),
+        Operation::UrlDecode => Some("url_decode"),
+        _ => None,
+    }
+}
+
+/// Collection literals are passed to the host as JSON text, like maps
+/// built at runtime. Map keys that are not strings use their literal text.
+fn literal_to_json(lit: &Literal) -> serde_json::Value {
+    use serde_json::Value;
+    match lit {
+        Literal::Int(n) => Value::from(*n),
+        Literal::Float(n) => Value::from(*n),
+        Literal::String(s) => Value::from(s.as_str()),
+        // Text keeps every digit, which a JSON number would not promise
+        Literal::Decimal(text) | Literal::BigInt(text) => Value::from(text.as_str()),
+        // Seconds, as the same values built at runtime
+        Literal::DateTime(seconds) | Literal::Duration(seconds) => Value::from(*seconds),
+        Literal::Bool(b) => Value::from(*b),
+        Literal::None => Value::Null,
+        Literal::List(items) | Literal::Set(items) | Literal::Tuple(items) => {
+            Value::Array(items.iter().map(literal_to_json).collect())
         }
+        Literal::Map(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Literal::String(s) => s.clone(),
+                        other => other.to_cov(0),
+                    };
+                    (key, literal_to_json(value))
+                })
+                .collect(),
+        ),
     }
-    false
 }
 
+/// The subscriber table as a custom section, if the program has subscribers
+fn events_section(snippets: &[Snippet]) -> Result<Option<CustomSection<'static>>, CodegenError> {
+    let table = EventTable::from_snippets(snippets);
+    if table.is_empty() {
+        return Ok(None);
+    }
+    let data = serde_json::to_vec(&table)
+        .map_err(|e| CodegenError::SerializationFailed(e.to_string()))?;
+    Ok(Some(CustomSection {
+        name: EVENTS_SECTION.into(),
+        data: data.into(),
+    }))
+}
+
+/// Stdlib sources declaring the extern-abstract snippets every module may import
+const STDLIB_SOURCES: &[&str] = &[
+    include_str!("../../../runtime/std/console/console.cov"),
+    include_str!("../../../runtime/std/filesystem/fs.cov"),
+    include_str!("../../../runtime/std/path/path.cov"),
+    include_str!("../../../runtime/std/text/text.cov"),
+    include_str!("../../../runtime/std/text/regex.cov"),
+    include_str!("../../../runtime/std/text/locale.cov"),
+    include_str!("../../../runtime/std/crypto/crypto.cov"),
+    include_str!("../../../runtime/std/list/list.cov"),
+    include_str!("../../../runtime/std/events/events.cov"),
+];
+
+/// The extern-abstract snippets of the stdlib sources
+pub(crate) fn stdlib_extern_abstracts() -> Vec<Snippet> {
+    STDLIB_SOURCES
+        .iter()
+        .filter_map(|source| match covenant_parser::parse(source) {
+            Ok(covenant_ast::Program::Snippets { snippets, .. }) => Some(snippets),
+            _ => None,
+        })
+        .flatten()
+        .filter(|snippet| snippet.kind == SnippetKind::ExternAbstract)
+        .collect()
+}
+
+/// The (module, function) an extern is imported as, from splitting its ID
+/// on the last dot: "text.concat" → ("text", "concat"), "std.text.regex_test"
+/// → ("std.text", "regex_test"), "to_uppercase" → ("extern", "to_uppercase")
+pub(crate) fn extern_import_name(id: &str) -> (&str, &str) {
+    match id.rfind('.') {
+        Some(pos) => (&id[..pos], &id[pos + 1..]),
+        None => ("extern", id),
+    }
+}
 
 /// Map a Covenant type to the extern parameter calling convention
-fn type_to_extern_param_kind(ty: &Type) -> ExternParamKind {
+pub(crate) fn type_to_extern_param_kind(ty: &Type) -> ExternParamKind {
     match &ty.kind {
         TypeKind::Named(path) => {
             let name = path.segments.last().map(|s| s.as_str()).unwrap_or("");
             match name {
-                "String" => ExternParamKind::String,
+                "String" | "Decimal" | "BigInt" => ExternParamKind::String,
                 "Int" => ExternParamKind::Int,
                 "Bool" => ExternParamKind::Bool,
                 // Any, List, Map, etc. use fat pointer convention
@@ -3625,8 +5667,178 @@ fn type_to_extern_param_kind(ty: &Type) -> ExternParamKind {
     }
 }
 
+/// How an extern's (non-Unit) result is passed, as named in the audit table.
+/// Lists are a fat pointer to a table of string fat pointers, so hosts
+/// decode them separately from other fat pointers; Floats are the f64's bits.
+pub(crate) fn extern_return_audit_name(sig: &FunctionSignature) -> &'static str {
+    let ty = match &sig.returns {
+        Some(ReturnType::Single { ty, .. }) => ty,
+        Some(ReturnType::Collection { .. }) => return "list",
+        _ => return ExternParamKind::FatPointer.audit_name(),
+    };
+    match &ty.kind {
+        TypeKind::List(_) => "list",
+        TypeKind::Named(path) if path.segments.last().is_some_and(|s| s == "List") => "list",
+        TypeKind::Named(path) if path.name() == "Float" => "float",
+        _ => type_to_extern_param_kind(ty).audit_name(),
+    }
+}
+
+/// Where an array operation's index lives
+enum ArrayIndex {
+    /// Byte offset of a constant index from the first element
+    Constant(u64),
+    /// Local holding an index checked against the length
+    Local(u32),
+}
+
+/// How many Float elements one pass of a vector loop handles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lanes {
+    One,
+    /// Both lanes of an f64x2, adding products with relaxed SIMD's fused
+    /// multiply-add (which rounds once instead of twice) when `fused`
+    Two { fused: bool },
+}
+
+impl Lanes {
+    fn width(self) -> i64 {
+        match self {
+            Lanes::One => 1,
+            Lanes::Two { .. } => 2,
+        }
+    }
+
+    fn fused(self) -> bool {
+        self == Lanes::Two { fused: true }
+    }
+
+    fn load(self) -> Instruction<'static> {
+        match self {
+            Lanes::One => Instruction::F64Load(array_element(0)),
+            Lanes::Two { .. } => Instruction::V128Load(array_element(0)),
+        }
+    }
+
+    fn store(self) -> Instruction<'static> {
+        match self {
+            Lanes::One => Instruction::F64Store(array_element(0)),
+            Lanes::Two { .. } => Instruction::V128Store(array_element(0)),
+        }
+    }
+
+    /// The instruction for an elementwise operation
+    fn arithmetic(self, op: Operation) -> Instruction<'static> {
+        match (self, op) {
+            (Lanes::One, Operation::VecAdd) => Instruction::F64Add,
+            (Lanes::One, Operation::VecSub) => Instruction::F64Sub,
+            (Lanes::One, _) => Instruction::F64Mul,
+            (Lanes::Two { .. }, Operation::VecAdd) => Instruction::F64x2Add,
+            (Lanes::Two { .. }, Operation::VecSub) => Instruction::F64x2Sub,
+            (Lanes::Two { .. }, _) => Instruction::F64x2Mul,
+        }
+    }
+
+    /// Widen the f64 on the stack to every lane
+    fn splat(self, func: &mut Function) {
+        if self != Lanes::One {
+            func.instruction(&Instruction::F64x2Splat);
+        }
+    }
+
+    fn zero(self, func: &mut Function) {
+        func.instruction(&match self {
+            Lanes::One => Instruction::F64Const(0.0),
+            Lanes::Two { .. } => Instruction::V128Const(0),
+        });
+    }
+
+    /// Multiply the two operands on the stack; with a fused multiply-add
+    /// the product is left to [`Lanes::accumulate`]
+    fn multiply(self, func: &mut Function) {
+        if !self.fused() {
+            func.instruction(&self.arithmetic(Operation::VecScale));
+        }
+    }
+
+    /// Add the product below the accumulator on the stack to it
+    fn accumulate(self, func: &mut Function) {
+        func.instruction(&match self {
+            _ if self.fused() => Instruction::F64x2RelaxedMadd,
+            Lanes::One => Instruction::F64Add,
+            Lanes::Two { .. } => Instruction::F64x2Add,
+        });
+    }
+}
+
+fn is_array_op(op: Operation) -> bool {
+    matches!(op, Operation::ArrayOf | Operation::ArrayFill | Operation::ArrayGet | Operation::ArraySet | Operation::ArrayLen)
+}
+
+fn is_numeric_op(op: Operation) -> bool {
+    matches!(op, Operation::VecAdd | Operation::VecSub | Operation::VecScale | Operation::Dot | Operation::Matmul)
+}
+
+/// Whether any of the steps, nested steps included, is a numeric vector
+/// operation
+fn uses_numeric_ops(steps: &[Step]) -> bool {
+    #[derive(Default)]
+    struct Finder(bool);
+
+    impl Visitor for Finder {
+        fn visit_step(&mut self, step: &Step) {
+            if let StepKind::Compute(compute) = &step.kind {
+                self.0 |= is_numeric_op(compute.op);
+            }
+            walk_step(self, step);
+        }
+    }
+
+    let mut finder = Finder::default();
+    finder.visit_steps(steps);
+    finder.0
+}
+
+/// Memory argument for an array element, `offset` bytes past the first
+fn array_element(offset: u64) -> MemArg {
+    MemArg {
+        // Past the i32 count
+        offset: 4 + offset,
+        align: 3,
+        memory_index: 0,
+    }
+}
+
+/// Scratch locals a compute operation allocates beyond its output binding
+fn compute_scratch_locals(op: Operation) -> u32 {
+    match op {
+        Operation::Add | Operation::Sub | Operation::Mul
+        | Operation::AddSaturating | Operation::SubSaturating | Operation::MulSaturating
+        | Operation::AddChecked | Operation::SubChecked | Operation::MulChecked => 3,
+        // Duration and DateTime arithmetic is trapping Int arithmetic
+        Operation::DurMinutes | Operation::DurHours | Operation::DurDays
+        | Operation::DurAdd | Operation::DurSub | Operation::DurScale
+        | Operation::DtAdd | Operation::DtSub | Operation::DtDiff => 3,
+        // The operand plus trapping subtraction from zero
+        Operation::Neg | Operation::Abs => 4,
+        Operation::UnwrapOr => 2,
+        Operation::ArrayOf | Operation::ArrayGet => 2,
+        Operation::ArrayFill => 4,
+        Operation::ArraySet => 6,
+        Operation::VecAdd | Operation::VecSub | Operation::VecScale | Operation::Dot => 7,
+        Operation::Matmul => 17,
+        // One per text input to a Decimal or BigInt import
+        Operation::DecRound => 1,
+        op if exact_import_name(op).is_some() && !matches!(op, Operation::DecFromInt | Operation::BigFromInt) => 2,
+        // One per text input to a Json import
+        Operation::JsonParse | Operation::JsonKeys => 1,
+        Operation::JsonGet => 2,
+        Operation::JsonSet => 3,
+        _ => 0,
+    }
+}
+
 /// Compute a deterministic tag value for a variant name
-#[allow(dead_code)]
 fn variant_tag(variant: &str) -> i64 {
     // Simple hash: sum of byte values
     // In a real implementation, this would use the type registry
@@ -3727,9 +5939,18 @@ fn literal_to_sql(lit: &Literal) -> String {
     match lit {
         Literal::Int(n) => n.to_string(),
         Literal::Float(n) => n.to_string(),
+        // Exact numeric literals, which SQL reads without rounding
+        Literal::Decimal(text) | Literal::BigInt(text) => text.clone(),
+        Literal::DateTime(seconds) => format!("'{}'", covenant_ast::time::format_datetime(*seconds)),
+        Literal::Duration(seconds) => seconds.to_string(),
         Literal::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
         Literal::String(s) => format!("'{}'", s.replace('\'', "''")),
         Literal::None => "NULL".to_string(),
+        // Lists, sets and tuples become row values, as used by IN
+        Literal::List(items) | Literal::Set(items) | Literal::Tuple(items) => {
+            format!("({})", items.iter().map(literal_to_sql).collect::<Vec<_>>().join(", "))
+        }
+        Literal::Map(_) => format!("'{}'", literal_to_json(lit).to_string().replace('\'', "''")),
     }
 }
 
@@ -3791,6 +6012,21 @@ mod tests {
         assert_eq!(literal_to_sql(&Literal::Bool(false)), "FALSE");
         assert_eq!(literal_to_sql(&Literal::String("test".to_string())), "'test'");
         assert_eq!(literal_to_sql(&Literal::None), "NULL");
+        assert_eq!(literal_to_sql(&Literal::DateTime(1_704_067_200)), "'2024-01-01T00:00:00Z'");
+        assert_eq!(literal_to_sql(&Literal::Duration(300)), "300");
+        assert_eq!(
+            literal_to_sql(&Literal::List(vec![Literal::Int(1), Literal::String("a".to_string())])),
+            "(1, 'a')"
+        );
+    }
+
+    #[test]
+    fn test_collection_literals_to_json() {
+        let map = Literal::Map(vec![
+            (Literal::String("env".to_string()), Literal::List(vec![Literal::Int(1)])),
+            (Literal::Int(2), Literal::Tuple(vec![Literal::Bool(true), Literal::None])),
+        ]);
+        assert_eq!(literal_to_json(&map).to_string(), r#"{"2":[true,null],"env":[1]}"#);
     }
 
     #[test]
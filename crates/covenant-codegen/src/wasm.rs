@@ -213,6 +213,21 @@ impl<'a> WasmCompiler<'a> {
                         ty: "String".to_string(),
                     })
                 }
+                Literal::Decimal(_) => Err(CodegenError::UnsupportedType {
+                    ty: "Decimal".to_string(),
+                }),
+                Literal::BigInt(_) => Err(CodegenError::UnsupportedType {
+                    ty: "BigInt".to_string(),
+                }),
+                Literal::DateTime(seconds) | Literal::Duration(seconds) => {
+                    func.instruction(&Instruction::I64Const(*seconds));
+                    Ok(())
+                }
+                Literal::List(_) | Literal::Map(_) | Literal::Set(_) | Literal::Tuple(_) => {
+                    Err(CodegenError::UnsupportedType {
+                        ty: "collection literal".to_string(),
+                    })
+                }
             },
             ExprKind::Ident(name) => {
                 if let Some(&idx) = self.locals.get(name) {
@@ -0,0 +1,393 @@
+//! Cloudflare Workers bundles
+//!
+//! `covenant compile --target worker` writes the module with a JavaScript
+//! shim and a `wrangler.toml`, ready for `wrangler deploy`. The shim is the
+//! Worker's fetch handler: it matches each request against the program's
+//! route triggers, instantiates the module, reads the handler's params from
+//! the request and turns its result into the response, calling the handler
+//! through the module's reflection table so the shim needs no knowledge of
+//! its exports ahead of time. Routes are tried in source order and anything
+//! unmatched is a 404.
+//!
+//! Each database target becomes a binding named after the target, upper
+//! cased: a KV namespace for the `kv` dialect and a D1 database, which is
+//! SQLite, for any other. The shim resolves the bindings from the Worker's
+//! `env` as `_initialize` registers the targets. Like the other runners it
+//! does not yet execute queries, which the module makes synchronously while
+//! D1 and KV are asynchronous.
+
+use std::fmt::Write;
+
+use covenant_ast::Snippet;
+use covenant_checker::{snippet_route, Route};
+
+use crate::host_bindgen::js_string;
+use crate::{QueryTarget, ALLOC_EXPORT, FUNCTIONS_EXPORT, INIT_EXPORT, REFLECT_EXPORT};
+
+/// Compatibility date written to `wrangler.toml`
+pub const COMPATIBILITY_DATE: &str = "2025-01-01";
+
+/// The shim's file name within the bundle, and the Worker's entry point
+pub const WORKER_SHIM: &str = "worker.mjs";
+
+/// A route trigger, served by a snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerRoute {
+    /// Upper-case method, or `None` to match any
+    pub method: Option<String>,
+    /// Path pattern, e.g. `/users/:id`
+    pub path: String,
+    /// Snippet ID of the handler
+    pub snippet: String,
+}
+
+/// Storage a Worker binding gives a database target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerBindingKind {
+    D1,
+    Kv,
+}
+
+/// The Worker binding a database target uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerBinding {
+    /// Name queries give in `target=`
+    pub target: String,
+    /// Name of the binding in the Worker's `env`
+    pub binding: String,
+    pub kind: WorkerBindingKind,
+}
+
+impl WorkerBinding {
+    pub fn for_target(target: &QueryTarget) -> Self {
+        let binding = target
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect();
+        let kind = if target.dialect.eq_ignore_ascii_case("kv") {
+            WorkerBindingKind::Kv
+        } else {
+            WorkerBindingKind::D1
+        };
+        Self { target: target.name.clone(), binding, kind }
+    }
+}
+
+/// The route triggers of `snippets`, in source order. Routes that do not
+/// parse are skipped; the checker reports them.
+pub fn worker_routes(snippets: &[Snippet]) -> Vec<WorkerRoute> {
+    snippets
+        .iter()
+        .filter_map(|snippet| {
+            let route = Route::parse(snippet_route(snippet)?).ok()?;
+            Some(WorkerRoute { method: route.method, path: route.path, snippet: snippet.id.clone() })
+        })
+        .collect()
+}
+
+/// Request handling shared by every shim: matching, argument decoding and
+/// response encoding
+const WORKER_RUNTIME: &str = r#"/** The none sentinel for optional results */
+const NONE = -(1n << 63n);
+
+/** Path captures if `route` matches the request, else null */
+function matchRoute(route, method, path) {
+  if (route.method && route.method !== method && !(route.method === 'GET' && method === 'HEAD')) return null;
+  const pattern = route.path.split('/');
+  const parts = path.split('/');
+  if (pattern.length !== parts.length) return null;
+  const captures = {};
+  for (let i = 0; i < pattern.length; i++) {
+    if (pattern[i].startsWith(':')) {
+      captures[pattern[i].slice(1)] = decodeURIComponent(parts[i]);
+    } else if (pattern[i] !== parts[i]) {
+      return null;
+    }
+  }
+  return captures;
+}
+
+/** Thrown for a request the handler cannot be called with */
+class BadRequest extends Error {}
+
+/**
+ * Build the Worker's fetch handler around a compiled module.
+ *
+ * `extraImports` provides imports the shim does not, by module and name,
+ * or is a function of `{ env, targets, readStr, writeStr }` returning them,
+ * so they can reach the request's bindings through the registered targets.
+ * Any other import throws when called.
+ */
+export function createWorker(module, extraImports = {}) {
+  async function handle(request, env, route, captures) {
+    const targets = new Map();
+    let memory = null;
+    let alloc = (_size) => {
+      throw new Error('[worker] Allocation before the module is instantiated');
+    };
+    const readStr = (ptr, len) => new TextDecoder().decode(new Uint8Array(memory.buffer, ptr, len));
+    const readFat = (fat) => readStr(Number(BigInt.asUintN(64, fat) >> 32n), Number(fat & 0xffffffffn));
+    const writeStr = (s) => {
+      const bytes = new TextEncoder().encode(s);
+      const ptr = alloc(bytes.length);
+      new Uint8Array(memory.buffer, ptr, bytes.length).set(bytes);
+      return (BigInt(ptr) << 32n) | BigInt(bytes.length);
+    };
+
+    const provided = {
+      mem: { alloc: (size) => alloc(size) },
+      console: {
+        println: (ptr, len) => console.log(readStr(ptr, len)),
+        print: (ptr, len) => console.log(readStr(ptr, len)),
+        error: (ptr, len) => console.error(readStr(ptr, len)),
+        info: (ptr, len) => console.info(readStr(ptr, len)),
+        debug: (ptr, len) => console.debug(readStr(ptr, len)),
+        warn: (ptr, len) => console.warn(readStr(ptr, len)),
+      },
+      db: {
+        register_target: (np, nl, dp, dl, cp, cl) => {
+          const name = readStr(np, nl);
+          const binding = BINDINGS[name];
+          targets.set(name, {
+            dialect: readStr(dp, dl),
+            connection: readStr(cp, cl),
+            binding: binding ? env[binding.binding] : undefined,
+          });
+        },
+        execute_query: () => {
+          console.error('[worker] Database queries are not supported yet');
+          return 0;
+        },
+      },
+    };
+    const extra = typeof extraImports === 'function' ? extraImports({ env, targets, readStr, writeStr }) : extraImports;
+    const imports = {};
+    for (const { module: name, name: field, kind } of WebAssembly.Module.imports(module)) {
+      if (kind !== 'function') continue;
+      imports[name] ??= {};
+      imports[name][field] = extra[name]?.[field] ?? provided[name]?.[field] ?? (() => {
+        throw new Error(`[worker] Import '${name}.${field}' is not provided`);
+      });
+    }
+
+    const instance = await WebAssembly.instantiate(module, imports);
+    memory = instance.exports.memory;
+    const covAlloc = instance.exports.__ALLOC__;
+    alloc = (size) => covAlloc(size) >>> 0;
+    instance.exports.__INIT__?.();
+
+    const table = JSON.parse(readFat(instance.exports.__REFLECT__()));
+    const fn = table.functions.find((f) => f.id === route.snippet);
+    const query = new URL(request.url).searchParams;
+    const readsBody = fn.params.some((p) => p.name === 'body' && !(p.name in captures) && !query.has(p.name));
+    const body = readsBody ? await request.text() : null;
+    const args = fn.params.map((param) => {
+      const text = captures[param.name] ?? query.get(param.name) ?? (param.name === 'body' ? body : null);
+      if (text === null) throw new BadRequest(`missing parameter '${param.name}'`);
+      switch (param.type) {
+        case 'String':
+          return writeStr(text);
+        case 'Int':
+          if (!/^-?[0-9]+$/.test(text)) throw new BadRequest(`parameter '${param.name}' must be an Int`);
+          return BigInt.asIntN(64, BigInt(text));
+        case 'Bool':
+          if (text !== 'true' && text !== 'false') throw new BadRequest(`parameter '${param.name}' must be true or false`);
+          return text === 'true' ? 1n : 0n;
+        default:
+          throw new BadRequest(`parameter '${param.name}' has unsupported type ${param.type}`);
+      }
+    });
+
+    const result = instance.exports.__FUNCTIONS__.get(fn.slot)(...args);
+    if (!fn.returns || fn.returns.type === 'Unit') return new Response(null, { status: 204 });
+    const type = fn.returns.type.replace(/\?$/, '');
+    if (fn.returns.type.endsWith('?') && result === NONE) return new Response('Not Found', { status: 404 });
+    switch (type) {
+      case 'String':
+        return new Response(readFat(result), { headers: { 'content-type': 'text/plain; charset=utf-8' } });
+      case 'Json':
+        return new Response(readFat(result), { headers: { 'content-type': 'application/json' } });
+      default:
+        return new Response(null, { status: Number(result) });
+    }
+  }
+
+  return {
+    async fetch(request, env) {
+      const url = new URL(request.url);
+      for (const route of ROUTES) {
+        const captures = matchRoute(route, request.method, url.pathname);
+        if (!captures) continue;
+        try {
+          return await handle(request, env, route, captures);
+        } catch (err) {
+          if (err instanceof BadRequest) return new Response(`Bad Request: ${err.message}`, { status: 400 });
+          console.error(`[worker] ${route.snippet} failed:`, err);
+          return new Response('Internal Server Error', { status: 500 });
+        }
+      }
+      return new Response('Not Found', { status: 404 });
+    },
+  };
+}
+"#;
+
+/// Generate the Worker's entry point, serving `routes` from the module in
+/// `wasm_file` (relative to the shim) with `bindings` for its targets
+pub fn emit_worker(routes: &[WorkerRoute], bindings: &[WorkerBinding], wasm_file: &str) -> String {
+    let mut out = String::new();
+    out.push_str("// Generated by `covenant compile --target worker`. Do not edit: recompile\n");
+    out.push_str("// after changing a route trigger or database snippet.\n\n");
+    let _ = writeln!(out, "import wasmModule from {};\n", js_string(&format!("./{}", wasm_file)));
+
+    out.push_str("/** Route triggers, tried in order */\n");
+    out.push_str("export const ROUTES = [\n");
+    for route in routes {
+        let method = route.method.as_deref().map(js_string).unwrap_or_else(|| "null".to_string());
+        let _ = writeln!(
+            out,
+            "  {{ method: {}, path: {}, snippet: {} }},",
+            method,
+            js_string(&route.path),
+            js_string(&route.snippet),
+        );
+    }
+    out.push_str("];\n\n");
+
+    out.push_str("/** The binding each database target resolves to, by target name */\n");
+    out.push_str("export const BINDINGS = {\n");
+    for binding in bindings {
+        let kind = match binding.kind {
+            WorkerBindingKind::D1 => "d1",
+            WorkerBindingKind::Kv => "kv",
+        };
+        let _ = writeln!(
+            out,
+            "  {}: {{ binding: {}, kind: {} }},",
+            js_string(&binding.target),
+            js_string(&binding.binding),
+            js_string(kind),
+        );
+    }
+    out.push_str("};\n\n");
+
+    out.push_str(
+        &WORKER_RUNTIME
+            .replace("__ALLOC__", ALLOC_EXPORT)
+            .replace("__INIT__", INIT_EXPORT)
+            .replace("__REFLECT__", REFLECT_EXPORT)
+            .replace("__FUNCTIONS__", FUNCTIONS_EXPORT),
+    );
+    out.push_str("\nexport default createWorker(wasmModule);\n");
+    out
+}
+
+/// Generate a `wrangler.toml` for the Worker `name`, declaring `bindings`.
+/// D1 database and KV namespace IDs are left for the user to fill in.
+pub fn emit_wrangler_toml(name: &str, bindings: &[WorkerBinding]) -> String {
+    // Worker names are lower-case letters, digits and dashes
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let mut out = String::new();
+    out.push_str("# Generated by `covenant compile --target worker`. Fill in the D1 database\n");
+    out.push_str("# and KV namespace IDs, then run `wrangler deploy`.\n");
+    let _ = writeln!(out, "name = {}", js_string(&name));
+    let _ = writeln!(out, "main = {}", js_string(WORKER_SHIM));
+    let _ = writeln!(out, "compatibility_date = {}", js_string(COMPATIBILITY_DATE));
+    for binding in bindings {
+        match binding.kind {
+            WorkerBindingKind::D1 => {
+                out.push_str("\n[[d1_databases]]\n");
+                let _ = writeln!(out, "binding = {}", js_string(&binding.binding));
+                let _ = writeln!(out, "database_name = {}", js_string(&binding.target));
+                let _ = writeln!(out, "database_id = \"\" # wrangler d1 create {}", binding.target);
+            }
+            WorkerBindingKind::Kv => {
+                out.push_str("\n[[kv_namespaces]]\n");
+                let _ = writeln!(out, "binding = {}", js_string(&binding.binding));
+                let _ = writeln!(out, "id = \"\" # wrangler kv namespace create {}", binding.binding);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::snippets;
+
+    const SERVICE: &str = r#"
+snippet id="users.get" kind="fn"
+metadata
+  trigger route="GET /users/:id"
+end
+signature
+  fn name="get_user"
+    param name="id" type="Int"
+    returns type="String"
+  end
+end
+end
+
+snippet id="app.health" kind="fn"
+metadata
+  trigger route="/health"
+end
+signature
+  fn name="health"
+    returns type="Int"
+  end
+end
+end
+"#;
+
+    fn target(name: &str, dialect: &str) -> QueryTarget {
+        QueryTarget {
+            id: format!("app.{}", name),
+            name: name.to_string(),
+            dialect: dialect.to_string(),
+            connection: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_routes_map_to_the_fetch_handler() {
+        let routes = worker_routes(&snippets(SERVICE));
+        assert_eq!(
+            routes,
+            vec![
+                WorkerRoute { method: Some("GET".into()), path: "/users/:id".into(), snippet: "users.get".into() },
+                WorkerRoute { method: None, path: "/health".into(), snippet: "app.health".into() },
+            ]
+        );
+
+        let shim = emit_worker(&routes, &[], "service.wasm");
+        assert!(shim.contains("import wasmModule from \"./service.wasm\";"));
+        assert!(shim.contains("{ method: \"GET\", path: \"/users/:id\", snippet: \"users.get\" },"));
+        assert!(shim.contains("{ method: null, path: \"/health\", snippet: \"app.health\" }"));
+        assert!(shim.contains("instance.exports.cov_reflect()"));
+        assert!(shim.ends_with("export default createWorker(wasmModule);\n"));
+    }
+
+    #[test]
+    fn test_targets_map_to_d1_and_kv_bindings() {
+        let bindings: Vec<WorkerBinding> =
+            [target("users", "sqlite"), target("session-cache", "kv")].iter().map(WorkerBinding::for_target).collect();
+        assert_eq!(bindings[0].binding, "USERS");
+        assert_eq!(bindings[0].kind, WorkerBindingKind::D1);
+        assert_eq!(bindings[1].binding, "SESSION_CACHE");
+        assert_eq!(bindings[1].kind, WorkerBindingKind::Kv);
+
+        let shim = emit_worker(&[], &bindings, "service.wasm");
+        assert!(shim.contains("\"users\": { binding: \"USERS\", kind: \"d1\" },"));
+
+        let toml = emit_wrangler_toml("My_Service", &bindings);
+        assert!(toml.contains("name = \"my-service\"\nmain = \"worker.mjs\"\n"));
+        assert!(toml.contains("[[d1_databases]]\nbinding = \"USERS\"\ndatabase_name = \"users\"\n"));
+        assert!(toml.contains("[[kv_namespaces]]\nbinding = \"SESSION_CACHE\"\n"));
+    }
+}
@@ -2,10 +2,16 @@
 //!
 //! Tests the full pipeline: parse -> check -> codegen -> execute
 
+use covenant_codegen::{SnippetWasmCompiler, WasmFeature, WasmFeatures};
 use wasmtime::{Engine, Instance, Linker, Module, Store};
 
 /// Helper to compile source code to WASM and instantiate it
 fn compile_and_instantiate(source: &str) -> (Store<()>, Instance) {
+    compile_and_instantiate_with(source, WasmFeatures::none())
+}
+
+/// Compile source code to WASM that may use `features`, and instantiate it
+fn compile_and_instantiate_with(source: &str, features: WasmFeatures) -> (Store<()>, Instance) {
     // Parse
     let program = covenant_parser::parse(source)
         .expect("Failed to parse");
@@ -15,7 +21,12 @@ fn compile_and_instantiate(source: &str) -> (Store<()>, Instance) {
         .expect("Type checking failed");
 
     // Compile to WASM
-    let wasm_bytes = covenant_codegen::compile(&program, &check_result.symbols)
+    let covenant_ast::Program::Snippets { snippets, .. } = &program else {
+        panic!("Expected Snippets program");
+    };
+    let wasm_bytes = SnippetWasmCompiler::new(&check_result.symbols)
+        .with_features(features)
+        .compile_snippets(snippets)
         .expect("WASM compilation failed");
 
     // Instantiate with wasmtime
@@ -876,298 +887,1203 @@ end
     assert_eq!(modulo.call(&mut store, (7, 4)).unwrap(), 3);
 }
 
-// ============================================================================
-// More TDD Tests - These MUST FAIL until implemented
-// ============================================================================
-
-// === Match Step Tests (FAILS: match not implemented in codegen) ===
-
-#[test]
-fn test_compile_match_simple() {
-    // TDD: Match expressions are not yet implemented in codegen
-    // This tests matching on a simple enum-like discriminant
-    let source = r#"
-snippet id="types.Status" kind="enum"
+const OVERFLOW_OPS: &str = r#"
+snippet id="math.OP" kind="fn"
 signature
-  enum name="Status"
-    variant name="Active"
-    end
-    variant name="Inactive"
-    end
+  fn name="OP"
+    param name="a" type="Int"
+    param name="b" type="Int"
+    returns type="Int"RETURNS
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=OP
+    input var="a"
+    input var="b"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
   end
 end
 end
+"#;
 
-snippet id="test.check_status" kind="fn"
+fn overflow_op(op: &str, optional: bool) -> (Store<()>, wasmtime::TypedFunc<(i64, i64), i64>) {
+    let source = OVERFLOW_OPS.replace("OP", op).replace("RETURNS", if optional { " optional" } else { "" });
+    let (mut store, instance) = compile_and_instantiate(&source);
+    let func = instance
+        .get_typed_func::<(i64, i64), i64>(&mut store, op)
+        .expect("Failed to get function");
+    (store, func)
+}
+
+#[test]
+fn test_compile_arithmetic_overflow_traps() {
+    for op in ["add", "mul"] {
+        let (mut store, func) = overflow_op(op, false);
+        assert!(func.call(&mut store, (3, 2)).unwrap() > 0);
+        assert!(func.call(&mut store, (i64::MAX, 2)).is_err(), "{} should trap", op);
+    }
+    let (mut store, sub) = overflow_op("sub", false);
+    assert_eq!(sub.call(&mut store, (3, 2)).unwrap(), 1);
+    assert!(sub.call(&mut store, (i64::MIN, 1)).is_err());
+}
+
+#[test]
+fn test_compile_neg_and_abs_trap_on_min() {
+    for op in ["neg", "abs"] {
+        let source = format!(
+            r#"
+snippet id="math.{op}" kind="fn"
 signature
-  fn name="check_status"
-    param name="status" type="Status"
+  fn name="{op}"
+    param name="a" type="Int"
     returns type="Int"
   end
 end
 body
-  step id="s1" kind="match"
-    on="status"
-    case variant type="Status::Active"
-      step id="c1" kind="return"
-        lit=1
-        as="_"
-      end
-    end
-    case variant type="Status::Inactive"
-      step id="c2" kind="return"
-        lit=0
-        as="_"
-      end
-    end
+  step id="s1" kind="compute"
+    op={op}
+    input var="a"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
     as="_"
   end
 end
 end
-"#;
-    let (mut store, instance) = compile_and_instantiate(source);
-    let check = instance
-        .get_typed_func::<i64, i64>(&mut store, "check_status")
-        .expect("Failed to get 'check_status' function");
-    assert_eq!(check.call(&mut store, 0).unwrap(), 1);  // Active
-    assert_eq!(check.call(&mut store, 1).unwrap(), 0);  // Inactive
+"#
+        );
+        let (mut store, instance) = compile_and_instantiate(&source);
+        let func = instance
+            .get_typed_func::<i64, i64>(&mut store, op)
+            .expect("Failed to get function");
+        assert_eq!(func.call(&mut store, 5).unwrap(), if op == "neg" { -5 } else { 5 });
+        assert_eq!(func.call(&mut store, -7).unwrap(), 7);
+        assert_eq!(func.call(&mut store, i64::MIN + 1).unwrap(), i64::MAX);
+        assert!(func.call(&mut store, i64::MIN).is_err(), "{} of MIN should trap", op);
+    }
 }
 
-// === For Loop Tests ===
+#[test]
+fn test_compile_overflow_explicit_ops() {
+    let (mut store, add_wrapping) = overflow_op("add_wrapping", false);
+    assert_eq!(add_wrapping.call(&mut store, (i64::MAX, 1)).unwrap(), i64::MIN);
+
+    let (mut store, sub_saturating) = overflow_op("sub_saturating", false);
+    assert_eq!(sub_saturating.call(&mut store, (i64::MIN, 1)).unwrap(), i64::MIN);
+    assert_eq!(sub_saturating.call(&mut store, (5, 7)).unwrap(), -2);
+
+    let (mut store, mul_saturating) = overflow_op("mul_saturating", false);
+    assert_eq!(mul_saturating.call(&mut store, (i64::MAX, -2)).unwrap(), i64::MIN);
+    assert_eq!(mul_saturating.call(&mut store, (-1, i64::MIN)).unwrap(), i64::MAX);
+
+    // none is the i64::MIN sentinel
+    let (mut store, mul_checked) = overflow_op("mul_checked", true);
+    assert_eq!(mul_checked.call(&mut store, (i64::MAX, 2)).unwrap(), i64::MIN);
+    assert_eq!(mul_checked.call(&mut store, (-4, 5)).unwrap(), -20);
+}
 
 #[test]
-fn test_compile_for_loop_sum() {
-    // For loop that sums items from a list
-    // List is passed as fat pointer: (ptr << 32) | length
-    // We'll test with a simple list in memory
+fn test_compile_duration_arithmetic() {
     let source = r#"
-snippet id="math.sum_list" kind="fn"
+snippet id="time.cooldown" kind="fn"
 signature
-  fn name="sum_list"
-    param name="items" type="List<Int>"
+  fn name="cooldown"
+    param name="minutes" type="Int"
+    param name="grace" type="Duration"
     returns type="Int"
   end
 end
 body
-  step id="s1" kind="bind"
-    lit=0
+  step id="s1" kind="compute"
+    op=dur_minutes
+    input var="minutes"
+    as="span"
+  end
+  step id="s2" kind="compute"
+    op=dur_add
+    input var="span"
+    input var="grace"
     as="total"
   end
-  step id="s2" kind="for"
-    var="item" in="items"
-    step id="s2a" kind="compute"
-      op=add
-      input var="total"
-      input var="item"
-      as="total"
-    end
-    as="_"
+  step id="s3" kind="compute"
+    op=dur_total_seconds
+    input var="total"
+    as="result"
   end
-  step id="s3" kind="return"
-    from="total"
+  step id="s4" kind="return"
+    from="result"
     as="_"
   end
 end
 end
 "#;
     let (mut store, instance) = compile_and_instantiate(source);
-    let sum_list = instance
-        .get_typed_func::<i64, i64>(&mut store, "sum_list")
-        .expect("Failed to get 'sum_list' function");
-
-    // Get memory to write test arrays
-    // For loops expect fat pointers: (ptr << 32) | length
-    // Memory layout at ptr: [count:i32][item0:i64][item1:i64]...
-    let memory = instance.get_memory(&mut store, "memory")
-        .expect("Failed to get memory");
-
-    // Helper: write array to memory at given offset, return fat pointer
-    fn write_array(memory: &wasmtime::Memory, store: &mut wasmtime::Store<()>,
-                   offset: u32, items: &[i64]) -> i64 {
-        let data = memory.data_mut(store);
-        // Write count (i32)
-        data[offset as usize..offset as usize + 4]
-            .copy_from_slice(&(items.len() as i32).to_le_bytes());
-        // Write items (i64 each)
-        for (i, item) in items.iter().enumerate() {
-            let item_offset = offset as usize + 4 + i * 8;
-            data[item_offset..item_offset + 8]
-                .copy_from_slice(&item.to_le_bytes());
-        }
-        // Return fat pointer: (ptr << 32) | len
-        ((offset as i64) << 32) | (items.len() as i64)
-    }
-
-    // Test 1: [0, 1, 2] -> sum = 3
-    let ptr1 = write_array(&memory, &mut store, 1024, &[0, 1, 2]);
-    assert_eq!(sum_list.call(&mut store, ptr1).unwrap(), 3);
-
-    // Test 2: [0, 1, 2, 3, 4] -> sum = 10
-    let ptr2 = write_array(&memory, &mut store, 2048, &[0, 1, 2, 3, 4]);
-    assert_eq!(sum_list.call(&mut store, ptr2).unwrap(), 10);
-
-    // Test 3: [0] -> sum = 0
-    let ptr3 = write_array(&memory, &mut store, 3072, &[0]);
-    assert_eq!(sum_list.call(&mut store, ptr3).unwrap(), 0);
-
-    // Test 4: empty array -> sum = 0 (null pointer skips loop)
-    let ptr4 = write_array(&memory, &mut store, 4096, &[]);
-    assert_eq!(sum_list.call(&mut store, ptr4).unwrap(), 0);
+    let cooldown = instance
+        .get_typed_func::<(i64, i64), i64>(&mut store, "cooldown")
+        .expect("Failed to get 'cooldown' function");
+    assert_eq!(cooldown.call(&mut store, (2, 30)).unwrap(), 150);
+    assert_eq!(cooldown.call(&mut store, (-1, 0)).unwrap(), -60);
+    assert!(cooldown.call(&mut store, (i64::MAX / 2, 0)).is_err(), "minutes past the Int range should trap");
 }
 
-// === String Type Tests (FAILS: String not implemented in WASM) ===
-
 #[test]
-fn test_compile_string_return() {
-    // TDD: String handling not implemented in WASM codegen
+fn test_compile_datetime_literals() {
     let source = r#"
-snippet id="test.get_greeting" kind="fn"
+snippet id="time.since" kind="fn"
 signature
-  fn name="get_greeting"
-    returns type="String"
+  fn name="since"
+    param name="now" type="DateTime"
+    returns type="Int"
   end
 end
 body
-  step id="s1" kind="return"
-    lit="Hello, World!"
+  step id="s1" kind="compute"
+    op=dt_diff
+    input var="now"
+    input lit=datetime("2024-01-01T02:00:00+02:00")
+    as="age"
+  end
+  step id="s2" kind="compute"
+    op=dur_sub
+    input var="age"
+    input lit=duration("1h30m")
+    as="left"
+  end
+  step id="s3" kind="compute"
+    op=dur_total_seconds
+    input var="left"
+    as="result"
+  end
+  step id="s4" kind="return"
+    from="result"
     as="_"
   end
 end
 end
 "#;
     let (mut store, instance) = compile_and_instantiate(source);
-    let get_greeting = instance
-        .get_typed_func::<(), i64>(&mut store, "get_greeting")
-        .expect("Failed to get 'get_greeting' function");
-
-    // String returned as fat pointer: (offset << 32) | length
-    let result = get_greeting.call(&mut store, ()).unwrap();
-    let offset = (result >> 32) as u32;
-    let len = (result & 0xFFFFFFFF) as u32;
-
-    // Verify the string encoding
-    assert_eq!(offset, 0); // First string starts at offset 0
-    assert_eq!(len, 13);   // "Hello, World!" is 13 characters
-
-    // We can also read the actual string from memory
-    let memory = instance.get_memory(&mut store, "memory")
-        .expect("Failed to get memory");
-    let data = memory.data(&store);
-    let string_bytes = &data[offset as usize..(offset + len) as usize];
-    assert_eq!(std::str::from_utf8(string_bytes).unwrap(), "Hello, World!");
+    let since = instance
+        .get_typed_func::<i64, i64>(&mut store, "since")
+        .expect("Failed to get 'since' function");
+    // 2024-01-01T00:00:00Z is 1704067200
+    assert_eq!(since.call(&mut store, 1_704_067_200 + 86_400).unwrap(), 86_400 - 5_400);
 }
 
-// === Struct Tests ===
-
 #[test]
-fn test_compile_struct_construction() {
+fn test_compile_const_snippets_as_globals() {
     let source = r#"
-snippet id="types.Point" kind="struct"
+snippet id="consts.GREETING" kind="const"
 signature
-  struct name="Point"
-    field name="x" type="Int"
-    field name="y" type="Int"
-  end
+  const name="GREETING" type="String" lit="hello there"
 end
 end
 
-snippet id="test.make_point" kind="fn"
+snippet id="app.greet" kind="fn"
 signature
-  fn name="make_point"
-    param name="x" type="Int"
-    param name="y" type="Int"
-    returns type="Point"
+  fn name="greet"
+    returns type="String"
   end
 end
 body
-  step id="s1" kind="construct"
-    type="Point"
-    field name="x" from="x"
-    field name="y" from="y"
-    as="result"
+  step id="s1" kind="bind"
+    from="consts.GREETING"
+    as="g"
   end
   step id="s2" kind="return"
-    from="result"
+    from="consts.GREETING"
     as="_"
   end
 end
 end
 "#;
     let (mut store, instance) = compile_and_instantiate(source);
-    let make_point = instance
-        .get_typed_func::<(i64, i64), i64>(&mut store, "make_point")
-        .expect("Failed to get 'make_point' function");
-
-    // Result is a pointer (as i64) to memory where fields are stored
-    let result = make_point.call(&mut store, (10, 20)).unwrap();
-    let ptr = result as u32 as usize;
-
-    // Read fields from memory: each field is i64 (8 bytes), field 0 at offset 0, field 1 at offset 8
-    let memory = instance.get_memory(&mut store, "memory")
-        .expect("Failed to get memory");
-    let data = memory.data(&store);
-    let x = i64::from_le_bytes(data[ptr..ptr+8].try_into().unwrap());
-    let y = i64::from_le_bytes(data[ptr+8..ptr+16].try_into().unwrap());
-    assert_eq!(x, 10);
-    assert_eq!(y, 20);
-
-    // Test with different values
-    let result2 = make_point.call(&mut store, (100, 200)).unwrap();
-    let ptr2 = result2 as u32 as usize;
-    let data2 = memory.data(&store);
-    let x2 = i64::from_le_bytes(data2[ptr2..ptr2+8].try_into().unwrap());
-    let y2 = i64::from_le_bytes(data2[ptr2+8..ptr2+16].try_into().unwrap());
-    assert_eq!(x2, 100);
-    assert_eq!(y2, 200);
-
-    // Verify second allocation is after first (bump allocator)
-    assert_eq!(ptr2, ptr + 16); // Point is 2 fields * 8 bytes = 16 bytes
+    let init = instance
+        .get_typed_func::<(), ()>(&mut store, covenant_codegen::INIT_EXPORT)
+        .expect("Failed to get the init function");
+    init.call(&mut store, ()).unwrap();
+    init.call(&mut store, ()).unwrap();
+
+    let greet = instance
+        .get_typed_func::<(), i64>(&mut store, "greet")
+        .expect("Failed to get 'greet' function");
+    let value = greet.call(&mut store, ()).unwrap() as u64;
+    let memory = instance.get_memory(&mut store, "memory").expect("memory export");
+    let (offset, len) = ((value >> 32) as usize, (value & 0xffff_ffff) as usize);
+    assert_eq!(&memory.data(&store)[offset..offset + len], b"hello there");
 }
 
 #[test]
-fn test_compile_struct_three_fields() {
+fn test_reflection_table_describes_exports() {
     let source = r#"
-snippet id="types.Vec3" kind="struct"
+snippet id="math.add" kind="fn"
 signature
-  struct name="Vec3"
-    field name="x" type="Int"
-    field name="y" type="Int"
-    field name="z" type="Int"
+  fn name="add"
+    param name="a" type="Int"
+    param name="b" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=add
+    input var="a"
+    input var="b"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
   end
 end
 end
 
-snippet id="test.make_vec3" kind="fn"
+snippet id="text.echo" kind="fn"
 signature
-  fn name="make_vec3"
-    param name="x" type="Int"
-    param name="y" type="Int"
-    param name="z" type="Int"
-    returns type="Vec3"
+  fn name="echo"
+    param name="s" type="String"
+    returns type="String" optional
   end
 end
 body
-  step id="s1" kind="construct"
-    type="Vec3"
-    field name="x" from="x"
-    field name="y" from="y"
-    field name="z" from="z"
-    as="result"
-  end
-  step id="s2" kind="return"
-    from="result"
+  step id="s1" kind="return"
+    from="s"
     as="_"
   end
 end
 end
 "#;
     let (mut store, instance) = compile_and_instantiate(source);
-    let make_vec3 = instance
-        .get_typed_func::<(i64, i64, i64), i64>(&mut store, "make_vec3")
-        .expect("Failed to get 'make_vec3' function");
+    let reflect = instance
+        .get_typed_func::<(), i64>(&mut store, covenant_codegen::REFLECT_EXPORT)
+        .expect("Failed to get the reflection function");
+    let value = reflect.call(&mut store, ()).unwrap() as u64;
+    let memory = instance.get_memory(&mut store, "memory").expect("memory export");
+    let (offset, len) = ((value >> 32) as usize, (value & 0xffff_ffff) as usize);
+    let table: covenant_codegen::ReflectionTable =
+        serde_json::from_slice(&memory.data(&store)[offset..offset + len]).expect("reflection JSON");
+
+    let names: Vec<&str> = table.functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["add", "echo"]);
+    assert_eq!(table.functions[0].params[1].name, "b");
+    assert_eq!(table.functions[1].id, "text.echo");
+    assert_eq!(table.functions[1].returns.as_ref().unwrap().ty, "String?");
+
+    // Call add through its table slot
+    let functions = instance
+        .get_table(&mut store, covenant_codegen::FUNCTIONS_EXPORT)
+        .expect("function table export");
+    let add = functions
+        .get(&mut store, table.functions[0].slot)
+        .and_then(|r| r.as_func().flatten().cloned())
+        .expect("add in the table");
+    let add = add.typed::<(i64, i64), i64>(&store).unwrap();
+    assert_eq!(add.call(&mut store, (2, 3)).unwrap(), 5);
+}
 
-    let result = make_vec3.call(&mut store, (1, 2, 3)).unwrap();
-    let ptr = result as u32 as usize;
+#[test]
+fn test_compile_optional_ops() {
+    let source = r#"
+snippet id="math.or_default" kind="fn"
+signature
+  fn name="or_default"
+    param name="x" type="Optional<Int>"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=is_some
+    input var="x"
+    as="present"
+  end
+  step id="s2" kind="if"
+    condition="present"
+    then
+      step id="s2a" kind="compute"
+        op=add
+        input var="x"
+        input lit=1
+        as="next"
+      end
+      step id="s2b" kind="return"
+        from="next"
+        as="_"
+      end
+    end
+    else
+      step id="s2c" kind="compute"
+        op=unwrap_or
+        input var="x"
+        input lit=-1
+        as="fallback"
+      end
+      step id="s2d" kind="return"
+        from="fallback"
+        as="_"
+      end
+    end
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let or_default = instance
+        .get_typed_func::<i64, i64>(&mut store, "or_default")
+        .expect("Failed to get 'or_default' function");
+    assert_eq!(or_default.call(&mut store, 41).unwrap(), 42);
+    // none is passed as the i64::MIN sentinel
+    assert_eq!(or_default.call(&mut store, i64::MIN).unwrap(), -1);
+}
+
+#[test]
+fn test_compile_unit_alias_as_base_value() {
+    let source = r#"
+snippet id="billing.types" kind="module"
+types
+  alias name="Cents" type="Int" unit="USD"
+end
+end
+
+snippet id="billing.line_total" kind="fn"
+signature
+  fn name="line_total"
+    param name="unit_price" type="Int"
+    param name="qty" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="construct"
+    type="Cents"
+    field name="value" from="unit_price"
+    as="price"
+  end
+  step id="s2" kind="compute"
+    op=mul
+    input var="price"
+    input var="qty"
+    as="total"
+  end
+  step id="s3" kind="bind"
+    from="total.value"
+    as="cents"
+  end
+  step id="s4" kind="return"
+    from="cents"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let line_total = instance
+        .get_typed_func::<(i64, i64), i64>(&mut store, "line_total")
+        .expect("Failed to get 'line_total' function");
+    assert_eq!(line_total.call(&mut store, (250, 3)).unwrap(), 750);
+}
+
+#[test]
+fn test_compile_newtype_as_field_value() {
+    let source = r#"
+snippet id="ids.UserId" kind="struct"
+signature
+  struct name="UserId" newtype
+    field name="raw" type="Int"
+  end
+end
+end
+
+snippet id="ids.next_id" kind="fn"
+signature
+  fn name="next_id"
+    param name="id" type="UserId"
+    returns type="UserId"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=add
+    input var="id.raw"
+    input lit=1
+    as="raw"
+  end
+  step id="s2" kind="construct"
+    type="UserId"
+    field name="raw" from="raw"
+    as="next"
+  end
+  step id="s3" kind="return"
+    from="next"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    // The parameter and result are the raw Int, not a pointer to a struct
+    let next_id = instance
+        .get_typed_func::<i64, i64>(&mut store, "next_id")
+        .expect("Failed to get 'next_id' function");
+    assert_eq!(next_id.call(&mut store, 41).unwrap(), 42);
+}
+
+#[test]
+fn test_compile_fixed_size_arrays() {
+    let source = r#"
+snippet id="m.second" kind="fn"
+signature
+  fn name="second"
+    param name="a" type="Int"
+    param name="b" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=array_of
+    input var="a"
+    input var="b"
+    input lit=3
+    as="xs"
+  end
+  step id="s2" kind="compute"
+    op=array_get
+    input var="xs"
+    input lit=1
+    as="x"
+  end
+  step id="s3" kind="return"
+    from="x"
+    as="_"
+  end
+end
+end
+
+snippet id="m.pick" kind="fn"
+signature
+  fn name="pick"
+    param name="i" type="Int"
+    param name="v" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=array_fill
+    input lit=7
+    input lit=4
+    as="xs"
+  end
+  step id="s2" kind="compute"
+    op=array_set
+    input var="xs"
+    input lit=2
+    input var="v"
+    as="ys"
+  end
+  step id="s3" kind="compute"
+    op=array_get
+    input var="ys"
+    input var="i"
+    as="x"
+  end
+  step id="s4" kind="compute"
+    op=array_len
+    input var="ys"
+    as="n"
+  end
+  step id="s5" kind="compute"
+    op=mul
+    input var="x"
+    input var="n"
+    as="r"
+  end
+  step id="s6" kind="return"
+    from="r"
+    as="_"
+  end
+end
+end
+"#;
+    // With bulk memory, array_set copies the array with memory.copy
+    for features in [WasmFeatures::none(), WasmFeatures::none().with(WasmFeature::BulkMemory)] {
+        let (mut store, instance) = compile_and_instantiate_with(source, features);
+        let second = instance
+            .get_typed_func::<(i64, i64), i64>(&mut store, "second")
+            .expect("Failed to get 'second' function");
+        assert_eq!(second.call(&mut store, (10, 20)).unwrap(), 20);
+
+        // pick(i, v) sets element 2 of [7, 7, 7, 7] to v and returns element i
+        // times the length
+        let pick = instance
+            .get_typed_func::<(i64, i64), i64>(&mut store, "pick")
+            .expect("Failed to get 'pick' function");
+        assert_eq!(pick.call(&mut store, (2, 5)).unwrap(), 20);
+        assert_eq!(pick.call(&mut store, (0, 5)).unwrap(), 28);
+        assert!(pick.call(&mut store, (4, 5)).is_err(), "an index past the end should trap");
+        assert!(pick.call(&mut store, (-1, 5)).is_err(), "a negative index should trap");
+    }
+}
+
+/// The f64 elements of the array value `value` in the instance's memory
+fn read_floats(store: &mut Store<()>, instance: &Instance, value: i64) -> Vec<f64> {
+    let memory = instance.get_memory(&mut *store, "memory").expect("memory export");
+    let data = memory.data(&*store);
+    let base = (value as u64 >> 32) as usize;
+    let len = (value as u64 & 0xffff_ffff) as usize;
+    (0..len)
+        .map(|i| {
+            let at = base + 4 + 8 * i;
+            f64::from_le_bytes(data[at..at + 8].try_into().unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn test_compile_numeric_vector_ops() {
+    let source = r#"
+snippet id="m.vectors" kind="fn"
+signature
+  fn name="vectors"
+    returns type="Array<Float, 1>"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=array_fill
+    input lit=1.5
+    input lit=5
+    as="a"
+  end
+  step id="s2" kind="compute"
+    op=array_of
+    input lit=1.0
+    input lit=2.0
+    input lit=3.0
+    input lit=4.0
+    input lit=5.0
+    as="b"
+  end
+  step id="s3" kind="compute"
+    op=vec_add
+    input var="a"
+    input var="b"
+    as="c"
+  end
+  step id="s4" kind="compute"
+    op=vec_scale
+    input var="c"
+    input lit=2.0
+    as="d"
+  end
+  step id="s5" kind="compute"
+    op=dot
+    input var="d"
+    input var="b"
+    as="s"
+  end
+  step id="s6" kind="compute"
+    op=array_of
+    input var="s"
+    as="r"
+  end
+  step id="s7" kind="return"
+    from="r"
+    as="_"
+  end
+end
+end
+
+snippet id="m.product" kind="fn"
+signature
+  fn name="product"
+    returns type="Array<Array<Float, 3>, 2>"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=array_of
+    input lit=1.0
+    input lit=2.0
+    input lit=3.0
+    as="a0"
+  end
+  step id="s2" kind="compute"
+    op=array_of
+    input lit=4.0
+    input lit=5.0
+    input lit=6.0
+    as="a1"
+  end
+  step id="s3" kind="compute"
+    op=array_of
+    input var="a0"
+    input var="a1"
+    as="a"
+  end
+  step id="s4" kind="compute"
+    op=array_of
+    input lit=1.0
+    input lit=0.0
+    input lit=1.0
+    as="b0"
+  end
+  step id="s5" kind="compute"
+    op=array_of
+    input lit=0.0
+    input lit=1.0
+    input lit=0.0
+    as="b1"
+  end
+  step id="s6" kind="compute"
+    op=array_fill
+    input lit=2.0
+    input lit=3
+    as="b2"
+  end
+  step id="s7" kind="compute"
+    op=array_of
+    input var="b0"
+    input var="b1"
+    input var="b2"
+    as="b"
+  end
+  step id="s8" kind="compute"
+    op=matmul
+    input var="a"
+    input var="b"
+    as="c"
+  end
+  step id="s9" kind="return"
+    from="c"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+
+    // dot(2 * ([1.5; 5] + [1, 2, 3, 4, 5]), [1, 2, 3, 4, 5])
+    let vectors = instance
+        .get_typed_func::<(), i64>(&mut store, "vectors")
+        .expect("Failed to get 'vectors' function");
+    let result = vectors.call(&mut store, ()).unwrap();
+    assert_eq!(read_floats(&mut store, &instance, result), [155.0]);
+
+    let product = instance
+        .get_typed_func::<(), i64>(&mut store, "product")
+        .expect("Failed to get 'product' function");
+    let result = product.call(&mut store, ()).unwrap();
+    // The outer array holds row values, read here as the bits of f64s
+    let rows: Vec<Vec<f64>> = read_floats(&mut store, &instance, result)
+        .into_iter()
+        .map(|row| read_floats(&mut store, &instance, row.to_bits() as i64))
+        .collect();
+    assert_eq!(rows, [[7.0, 8.0, 7.0], [16.0, 17.0, 16.0]]);
+}
+
+// ============================================================================
+// More TDD Tests - These MUST FAIL until implemented
+// ============================================================================
+
+// === Match Step Tests (FAILS: match not implemented in codegen) ===
+
+#[test]
+fn test_compile_match_simple() {
+    // TDD: Match expressions are not yet implemented in codegen
+    // This tests matching on a simple enum-like discriminant
+    let source = r#"
+snippet id="types.Status" kind="enum"
+signature
+  enum name="Status"
+    variant name="Active"
+    end
+    variant name="Inactive"
+    end
+  end
+end
+end
+
+snippet id="test.check_status" kind="fn"
+signature
+  fn name="check_status"
+    param name="status" type="Status"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="match"
+    on="status"
+    case variant type="Status::Active"
+      step id="c1" kind="return"
+        lit=1
+        as="_"
+      end
+    end
+    case variant type="Status::Inactive"
+      step id="c2" kind="return"
+        lit=0
+        as="_"
+      end
+    end
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let check = instance
+        .get_typed_func::<i64, i64>(&mut store, "check_status")
+        .expect("Failed to get 'check_status' function");
+    assert_eq!(check.call(&mut store, 0).unwrap(), 1);  // Active
+    assert_eq!(check.call(&mut store, 1).unwrap(), 0);  // Inactive
+}
+
+// === For Loop Tests ===
+
+/// Write an Int array to memory at `offset` and return its fat pointer.
+/// For loops expect fat pointers: (ptr << 32) | length, with the memory
+/// layout at ptr: [count:i32][item0:i64][item1:i64]...
+fn write_array(memory: &wasmtime::Memory, store: &mut wasmtime::Store<()>,
+               offset: u32, items: &[i64]) -> i64 {
+    let data = memory.data_mut(store);
+    // Write count (i32)
+    data[offset as usize..offset as usize + 4]
+        .copy_from_slice(&(items.len() as i32).to_le_bytes());
+    // Write items (i64 each)
+    for (i, item) in items.iter().enumerate() {
+        let item_offset = offset as usize + 4 + i * 8;
+        data[item_offset..item_offset + 8]
+            .copy_from_slice(&item.to_le_bytes());
+    }
+    // Return fat pointer: (ptr << 32) | len
+    ((offset as i64) << 32) | (items.len() as i64)
+}
+
+#[test]
+fn test_compile_for_loop_sum() {
+    // For loop that sums items from a list
+    // List is passed as fat pointer: (ptr << 32) | length
+    // We'll test with a simple list in memory
+    let source = r#"
+snippet id="math.sum_list" kind="fn"
+signature
+  fn name="sum_list"
+    param name="items" type="List<Int>"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="bind"
+    lit=0
+    as="total"
+  end
+  step id="s2" kind="for"
+    var="item" in="items"
+    step id="s2a" kind="compute"
+      op=add
+      input var="total"
+      input var="item"
+      as="total"
+    end
+    as="_"
+  end
+  step id="s3" kind="return"
+    from="total"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let sum_list = instance
+        .get_typed_func::<i64, i64>(&mut store, "sum_list")
+        .expect("Failed to get 'sum_list' function");
+
+    // Get memory to write test arrays
+    let memory = instance.get_memory(&mut store, "memory")
+        .expect("Failed to get memory");
+
+    // Test 1: [0, 1, 2] -> sum = 3
+    let ptr1 = write_array(&memory, &mut store, 1024, &[0, 1, 2]);
+    assert_eq!(sum_list.call(&mut store, ptr1).unwrap(), 3);
+
+    // Test 2: [0, 1, 2, 3, 4] -> sum = 10
+    let ptr2 = write_array(&memory, &mut store, 2048, &[0, 1, 2, 3, 4]);
+    assert_eq!(sum_list.call(&mut store, ptr2).unwrap(), 10);
+
+    // Test 3: [0] -> sum = 0
+    let ptr3 = write_array(&memory, &mut store, 3072, &[0]);
+    assert_eq!(sum_list.call(&mut store, ptr3).unwrap(), 0);
+
+    // Test 4: empty array -> sum = 0 (null pointer skips loop)
+    let ptr4 = write_array(&memory, &mut store, 4096, &[]);
+    assert_eq!(sum_list.call(&mut store, ptr4).unwrap(), 0);
+}
+
+#[test]
+fn test_compile_for_loop_break_and_continue() {
+    // Sum the positive items, skipping negatives, until the first zero
+    let source = r#"
+snippet id="math.sum_until_zero" kind="fn"
+signature
+  fn name="sum_until_zero"
+    param name="items" type="List<Int>"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="bind"
+    lit=0
+    as="total"
+  end
+  step id="s2" kind="for"
+    var="item" in="items"
+    step id="s2a" kind="compute"
+      op=equals
+      input var="item"
+      input lit=0
+      as="is_zero"
+    end
+    step id="s2b" kind="if"
+      condition="is_zero"
+      then
+        step id="s2c" kind="break"
+          as="_"
+        end
+      end
+      as="_"
+    end
+    step id="s2d" kind="compute"
+      op=less
+      input var="item"
+      input lit=0
+      as="is_negative"
+    end
+    step id="s2e" kind="if"
+      condition="is_negative"
+      then
+        step id="s2f" kind="continue"
+          as="_"
+        end
+      end
+      as="_"
+    end
+    step id="s2g" kind="compute"
+      op=add
+      input var="total"
+      input var="item"
+      as="total"
+    end
+    as="_"
+  end
+  step id="s3" kind="return"
+    from="total"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let sum_until_zero = instance
+        .get_typed_func::<i64, i64>(&mut store, "sum_until_zero")
+        .expect("Failed to get 'sum_until_zero' function");
+    let memory = instance.get_memory(&mut store, "memory")
+        .expect("Failed to get memory");
+
+    // Negatives are skipped and the loop stops at the first zero
+    let ptr1 = write_array(&memory, &mut store, 1024, &[3, -1, 4, 0, 100]);
+    assert_eq!(sum_until_zero.call(&mut store, ptr1).unwrap(), 7);
+
+    // Without a zero, every positive item is summed
+    let ptr2 = write_array(&memory, &mut store, 2048, &[-5, 1, 2, -3]);
+    assert_eq!(sum_until_zero.call(&mut store, ptr2).unwrap(), 3);
+}
+
+// === String Type Tests (FAILS: String not implemented in WASM) ===
+
+#[test]
+fn test_compile_string_return() {
+    // TDD: String handling not implemented in WASM codegen
+    let source = r#"
+snippet id="test.get_greeting" kind="fn"
+signature
+  fn name="get_greeting"
+    returns type="String"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit="Hello, World!"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let get_greeting = instance
+        .get_typed_func::<(), i64>(&mut store, "get_greeting")
+        .expect("Failed to get 'get_greeting' function");
+
+    // String returned as fat pointer: (offset << 32) | length
+    let result = get_greeting.call(&mut store, ()).unwrap();
+    let offset = (result >> 32) as u32;
+    let len = (result & 0xFFFFFFFF) as u32;
+
+    // Verify the string encoding
+    assert_eq!(offset, 0); // First string starts at offset 0
+    assert_eq!(len, 13);   // "Hello, World!" is 13 characters
+
+    // We can also read the actual string from memory
+    let memory = instance.get_memory(&mut store, "memory")
+        .expect("Failed to get memory");
+    let data = memory.data(&store);
+    let string_bytes = &data[offset as usize..(offset + len) as usize];
+    assert_eq!(std::str::from_utf8(string_bytes).unwrap(), "Hello, World!");
+}
+
+// === Struct Tests ===
+
+#[test]
+fn test_compile_struct_construction() {
+    let source = r#"
+snippet id="types.Point" kind="struct"
+signature
+  struct name="Point"
+    field name="x" type="Int"
+    field name="y" type="Int"
+  end
+end
+end
+
+snippet id="test.make_point" kind="fn"
+signature
+  fn name="make_point"
+    param name="x" type="Int"
+    param name="y" type="Int"
+    returns type="Point"
+  end
+end
+body
+  step id="s1" kind="construct"
+    type="Point"
+    field name="x" from="x"
+    field name="y" from="y"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let make_point = instance
+        .get_typed_func::<(i64, i64), i64>(&mut store, "make_point")
+        .expect("Failed to get 'make_point' function");
+
+    // Result is a pointer (as i64) to memory where fields are stored
+    let result = make_point.call(&mut store, (10, 20)).unwrap();
+    let ptr = result as u32 as usize;
+
+    // Read fields from memory: each field is i64 (8 bytes), field 0 at offset 0, field 1 at offset 8
+    let memory = instance.get_memory(&mut store, "memory")
+        .expect("Failed to get memory");
+    let data = memory.data(&store);
+    let x = i64::from_le_bytes(data[ptr..ptr+8].try_into().unwrap());
+    let y = i64::from_le_bytes(data[ptr+8..ptr+16].try_into().unwrap());
+    assert_eq!(x, 10);
+    assert_eq!(y, 20);
+
+    // Test with different values
+    let result2 = make_point.call(&mut store, (100, 200)).unwrap();
+    let ptr2 = result2 as u32 as usize;
+    let data2 = memory.data(&store);
+    let x2 = i64::from_le_bytes(data2[ptr2..ptr2+8].try_into().unwrap());
+    let y2 = i64::from_le_bytes(data2[ptr2+8..ptr2+16].try_into().unwrap());
+    assert_eq!(x2, 100);
+    assert_eq!(y2, 200);
+
+    // Verify second allocation is after first (bump allocator)
+    assert_eq!(ptr2, ptr + 16); // Point is 2 fields * 8 bytes = 16 bytes
+}
+
+#[test]
+fn test_allocator_stats_reset_and_limit() {
+    let source = r#"
+snippet id="types.Point" kind="struct"
+signature
+  struct name="Point"
+    field name="x" type="Int"
+    field name="y" type="Int"
+  end
+end
+end
+
+snippet id="test.make_point" kind="fn"
+signature
+  fn name="make_point"
+    param name="x" type="Int"
+    param name="y" type="Int"
+    returns type="Point"
+  end
+end
+body
+  step id="s1" kind="construct"
+    type="Point"
+    field name="x" from="x"
+    field name="y" from="y"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let make_point = instance.get_typed_func::<(i64, i64), i64>(&mut store, "make_point").unwrap();
+    let alloc = instance.get_typed_func::<i32, i32>(&mut store, "cov_alloc").unwrap();
+    let stats = instance.get_typed_func::<(), i64>(&mut store, "cov_memory_stats").unwrap();
+    let reset = instance.get_typed_func::<(), ()>(&mut store, "cov_heap_reset").unwrap();
+    let read_stats = |store: &mut Store<()>| {
+        let packed = stats.call(store, ()).unwrap() as u64;
+        (packed >> 32, packed & 0xFFFF_FFFF)
+    };
+
+    // Structs and host allocations share the heap, 8-byte aligned
+    let first = make_point.call(&mut store, (1, 2)).unwrap();
+    let host = alloc.call(&mut store, 3).unwrap();
+    let second = make_point.call(&mut store, (3, 4)).unwrap();
+    assert_eq!(host as i64, first + 16);
+    assert_eq!(second, first + 24);
+    assert_eq!(read_stats(&mut store), (40, 40));
+
+    // Resetting frees everything but keeps the peak
+    reset.call(&mut store, ()).unwrap();
+    assert_eq!(read_stats(&mut store), (0, 40));
+    assert_eq!(make_point.call(&mut store, (5, 6)).unwrap(), first);
+
+    // The memory grows on demand, up to the 256 page default
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    assert_eq!(memory.size(&store), 16);
+    alloc.call(&mut store, 2 << 20).unwrap();
+    assert!(memory.size(&store) > 16);
+    assert!(alloc.call(&mut store, 16 << 20).is_err());
+}
+
+#[test]
+fn test_compile_struct_construction_from_base() {
+    let source = r#"
+snippet id="types.Vec3" kind="struct"
+signature
+  struct name="Vec3"
+    field name="x" type="Int"
+    field name="y" type="Int"
+    field name="z" type="Int"
+  end
+end
+end
+
+snippet id="test.with_y" kind="fn"
+signature
+  fn name="with_y"
+    param name="x" type="Int"
+    param name="y" type="Int"
+    returns type="Vec3"
+  end
+end
+body
+  step id="s1" kind="construct"
+    type="Vec3"
+    field name="z" lit=3
+    field name="y" lit=2
+    field name="x" from="x"
+    as="base"
+  end
+  step id="s2" kind="construct"
+    type="Vec3"
+    from_base="base"
+    field name="y" from="y"
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+    // With bulk memory, the base struct is copied with memory.copy
+    for features in [WasmFeatures::none(), WasmFeatures::none().with(WasmFeature::BulkMemory)] {
+        let (mut store, instance) = compile_and_instantiate_with(source, features);
+        let with_y = instance
+            .get_typed_func::<(i64, i64), i64>(&mut store, "with_y")
+            .expect("Failed to get 'with_y' function");
+
+        let ptr = with_y.call(&mut store, (1, 20)).unwrap() as u32 as usize;
+        let memory = instance.get_memory(&mut store, "memory")
+            .expect("Failed to get memory");
+        let data = memory.data(&store);
+        let field = |i: usize| i64::from_le_bytes(data[ptr + i * 8..ptr + i * 8 + 8].try_into().unwrap());
+        // Fields sit in declaration order, whatever order the construct lists them in
+        assert_eq!((field(0), field(1), field(2)), (1, 20, 3));
+    }
+}
+
+#[test]
+fn test_compile_struct_three_fields() {
+    let source = r#"
+snippet id="types.Vec3" kind="struct"
+signature
+  struct name="Vec3"
+    field name="x" type="Int"
+    field name="y" type="Int"
+    field name="z" type="Int"
+  end
+end
+end
+
+snippet id="test.make_vec3" kind="fn"
+signature
+  fn name="make_vec3"
+    param name="x" type="Int"
+    param name="y" type="Int"
+    param name="z" type="Int"
+    returns type="Vec3"
+  end
+end
+body
+  step id="s1" kind="construct"
+    type="Vec3"
+    field name="x" from="x"
+    field name="y" from="y"
+    field name="z" from="z"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let make_vec3 = instance
+        .get_typed_func::<(i64, i64, i64), i64>(&mut store, "make_vec3")
+        .expect("Failed to get 'make_vec3' function");
+
+    let result = make_vec3.call(&mut store, (1, 2, 3)).unwrap();
+    let ptr = result as u32 as usize;
 
     let memory = instance.get_memory(&mut store, "memory")
         .expect("Failed to get memory");
@@ -1362,3 +2278,189 @@ end
     assert_eq!(maybe_double.call(&mut store, (5, 0)).unwrap(), i64::MIN);
     assert_eq!(maybe_double.call(&mut store, (0, 0)).unwrap(), i64::MIN);
 }
+
+#[test]
+fn test_compile_lambda_captures_binding() {
+    let source = r#"
+snippet id="test.lambda" kind="fn"
+signature
+  fn name="add_offset"
+    param name="n" type="Int"
+    param name="offset" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="lambda"
+    param name="x" type="Int"
+    returns type="Int"
+    step id="s1a" kind="compute"
+      op=add
+      input var="x"
+      input var="offset"
+      as="shifted"
+    end
+    step id="s1b" kind="return"
+      from="shifted"
+      as="_"
+    end
+    as="shift"
+  end
+  step id="s2" kind="call"
+    fn="shift"
+    arg name="x" from="n"
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let add_offset = instance
+        .get_typed_func::<(i64, i64), i64>(&mut store, "add_offset")
+        .expect("Failed to get 'add_offset' function");
+
+    assert_eq!(add_offset.call(&mut store, (3, 4)).unwrap(), 7);
+    assert_eq!(add_offset.call(&mut store, (10, -20)).unwrap(), -10);
+}
+
+#[test]
+fn test_compile_lambda_passed_to_function() {
+    let source = r#"
+snippet id="test.apply_twice" kind="fn"
+signature
+  fn name="apply_twice"
+    param name="f" type="(Int) -> Int"
+    param name="x" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="f"
+    arg name="x" from="x"
+    as="once"
+  end
+  step id="s2" kind="call"
+    fn="f"
+    arg name="x" from="once"
+    as="twice"
+  end
+  step id="s3" kind="return"
+    from="twice"
+    as="_"
+  end
+end
+end
+
+snippet id="test.quadruple" kind="fn"
+signature
+  fn name="quadruple"
+    param name="n" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="lambda"
+    param name="x" type="Int"
+    step id="s1a" kind="compute"
+      op=mul
+      input var="x"
+      input lit=2
+      as="doubled"
+    end
+    step id="s1b" kind="return"
+      from="doubled"
+      as="_"
+    end
+    as="double"
+  end
+  step id="s2" kind="call"
+    fn="apply_twice"
+    arg name="f" from="double"
+    arg name="x" from="n"
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#;
+    let (mut store, instance) = compile_and_instantiate(source);
+    let quadruple = instance
+        .get_typed_func::<i64, i64>(&mut store, "quadruple")
+        .expect("Failed to get 'quadruple' function");
+
+    assert_eq!(quadruple.call(&mut store, 3).unwrap(), 12);
+    assert_eq!(quadruple.call(&mut store, -1).unwrap(), -4);
+}
+
+#[test]
+fn test_type_match_on_union_is_rejected() {
+    // Union values hold their member's value untagged, so a `case type=`
+    // arm has nothing to dispatch on: checking must reject it before
+    // codegen could pick an arm by position
+    let source = r#"
+snippet id="test.pick" kind="fn"
+signature
+  fn name="pick"
+    param name="n" type="Int"
+    returns union
+      type="Int"
+      type="Bool"
+    end
+  end
+end
+body
+  step id="s1" kind="return"
+    from="n"
+    as="_"
+  end
+end
+end
+
+snippet id="test.classify" kind="fn"
+signature
+  fn name="classify"
+    param name="n" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="pick"
+    arg name="n" from="n"
+    as="picked"
+  end
+  step id="s2" kind="match"
+    on="picked"
+    case type="Int"
+      step id="s2a" kind="return"
+        lit=1
+        as="_"
+      end
+    end
+    case type="Bool"
+      step id="s2b" kind="return"
+        lit=2
+        as="_"
+      end
+    end
+    as="_"
+  end
+end
+end
+"#;
+    let program = covenant_parser::parse(source).expect("Failed to parse");
+    let errors = covenant_checker::check(&program).err().expect("Type checking should fail");
+    assert!(
+        matches!(errors.as_slice(), [covenant_checker::CheckError::UnsupportedTypeMatch { on }] if on == "picked"),
+        "Expected an unsupported type match, got: {:?}",
+        errors
+    );
+}
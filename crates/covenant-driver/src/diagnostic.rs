@@ -0,0 +1,192 @@
+//! Structured diagnostics
+//!
+//! Every stage of the pipeline reports problems in its own error type. The
+//! driver converts them all to [`Diagnostic`], which carries the stable error
+//! code, the file, and the source span so tools can render or serialize them
+//! uniformly.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use covenant_ast::Span;
+use serde::Serialize;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// The pipeline stage that produced a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    Parse,
+    Limits,
+    Symbols,
+    Effects,
+    Types,
+    Requirements,
+    Optimize,
+    Codegen,
+}
+
+/// A problem found in a project
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub stage: Stage,
+    /// Stable error code (e.g. "E-PARSE-001"), if the error has one
+    pub code: Option<String>,
+    pub message: String,
+    /// File the diagnostic belongs to, if it is tied to one
+    pub file: Option<PathBuf>,
+    /// Byte range in the file, if known
+    pub span: Option<Span>,
+    /// Suggested fix
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(stage: Stage, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            stage,
+            code: None,
+            message: message.into(),
+            file: None,
+            span: None,
+            help: None,
+        }
+    }
+
+    pub fn warning(stage: Stage, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(stage, message)
+        }
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn with_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// Attach a span; dummy spans (`0..0`) are treated as unknown
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = (span != Span::dummy()).then_some(span);
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    pub(crate) fn from_parse_error(error: &covenant_parser::ParseError) -> Self {
+        let diagnostic = Diagnostic::error(Stage::Parse, error.to_string()).with_span(error.span());
+        match error.code() {
+            Some(code) => diagnostic.with_code(code),
+            None => diagnostic,
+        }
+    }
+
+    pub(crate) fn from_limit_warning(warning: &covenant_checker::LimitWarning) -> Self {
+        Diagnostic::warning(Stage::Limits, warning.message())
+            .with_code(warning.code())
+            .with_span(warning.span)
+            .with_help(warning.suggestion())
+    }
+
+    pub(crate) fn from_conversion_warning(warning: &covenant_checker::ConversionWarning) -> Self {
+        Diagnostic::warning(Stage::Types, warning.message())
+            .with_code(warning.code())
+            .with_span(warning.span)
+            .with_help(warning.suggestion())
+    }
+
+    pub(crate) fn from_sensitive_flow_warning(warning: &covenant_checker::SensitiveFlowWarning) -> Self {
+        Diagnostic::warning(Stage::Effects, warning.message())
+            .with_code(warning.code())
+            .with_span(warning.span)
+            .with_help(warning.suggestion())
+    }
+
+    /// Symbol errors that block graph building are errors; deferred ones
+    /// (e.g. undefined references) are warnings
+    pub(crate) fn from_symbol_error(error: &covenant_symbols::SymbolError, severity: Severity) -> Self {
+        Diagnostic {
+            severity,
+            ..Diagnostic::error(Stage::Symbols, error.to_string())
+        }
+        .with_code(error.code())
+        .with_span(error.span())
+    }
+
+    /// A symbol error located in its file; a duplicate ID points to the
+    /// first definition in its help
+    pub(crate) fn from_project_error(error: &covenant_symbols::ProjectError, severity: Severity) -> Self {
+        let mut diagnostic = Diagnostic::from_symbol_error(&error.error, severity);
+        diagnostic.file = error.file.clone();
+        match &error.first {
+            Some((file, _)) => diagnostic.with_help(format!("first defined in {}", file.display())),
+            None => diagnostic,
+        }
+    }
+
+    pub(crate) fn from_effect_error(error: &covenant_checker::EffectError) -> Self {
+        let diagnostic = covenant_checker::explain_effect_violation(error, &Default::default());
+        let help = diagnostic.suggestions.first().map(|s| s.description());
+        let diagnostic = Diagnostic::error(Stage::Effects, diagnostic.message())
+            .with_code(diagnostic.code)
+            .with_span(diagnostic.span);
+        match help {
+            Some(help) => diagnostic.with_help(help),
+            None => diagnostic,
+        }
+    }
+
+    pub(crate) fn from_check_error(error: covenant_checker::CheckError) -> Self {
+        let message = error.to_string();
+        let checked = covenant_checker::Diagnostic::from(error);
+        Diagnostic::error(Stage::Types, message)
+            .with_code(checked.code)
+            .with_span(checked.span)
+    }
+
+    pub(crate) fn from_requirement_error(error: &covenant_requirements::RequirementError) -> Self {
+        let diagnostic = match error.severity() {
+            covenant_requirements::Severity::Error => Diagnostic::error(Stage::Requirements, error.to_string()),
+            _ => Diagnostic::warning(Stage::Requirements, error.to_string()),
+        };
+        diagnostic.with_code(error.code()).with_span(error.span())
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}", severity)?;
+        if let Some(code) = &self.code {
+            write!(f, "[{}]", code)?;
+        }
+        if let Some(file) = &self.file {
+            write!(f, " {}", file.display())?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
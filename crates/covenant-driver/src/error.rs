@@ -0,0 +1,34 @@
+//! Errors loading a project
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::{Diagnostic, Platform, WasmFeature};
+
+/// Errors raised by the driver outside of diagnostics in the code itself
+#[derive(Debug, Error)]
+pub enum DriverError {
+    #[error("Error reading {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Error reading {}: {message}", path.display())]
+    Config { path: PathBuf, message: String },
+
+    #[error("Target '{platform}' does not support the WASM feature '{feature}'")]
+    UnsupportedFeature { platform: Platform, feature: WasmFeature },
+
+    #[error("Cannot generate host glue for '{platform}'; only deno, node and bun are supported")]
+    UnsupportedHost { platform: Platform },
+
+    #[error("{} error(s)", .0.len())]
+    Diagnostics(Vec<Diagnostic>),
+}
+
+impl From<Vec<Diagnostic>> for DriverError {
+    fn from(diagnostics: Vec<Diagnostic>) -> Self {
+        DriverError::Diagnostics(diagnostics)
+    }
+}
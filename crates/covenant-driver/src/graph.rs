@@ -0,0 +1,149 @@
+//! The project's symbol graph
+
+use std::collections::BTreeMap;
+
+use covenant_ast::printer::ToCov;
+use covenant_ast::{EffectDecl, Literal};
+use covenant_symbols::{SymbolGraph, SymbolInfo, SymbolKind};
+
+/// A symbol in a project, with its forward and backward references
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Symbol {
+    /// Snippet ID (e.g. "math.add")
+    pub name: String,
+    /// Snippet kind as written in source (e.g. "fn", "struct")
+    pub kind: String,
+    pub calls: Vec<String>,
+    pub called_by: Vec<String>,
+    pub references: Vec<String>,
+    pub referenced_by: Vec<String>,
+    /// Subscribers to the events this symbol publishes
+    pub notifies: Vec<String>,
+    /// Publishers of the event this subscriber listens for
+    pub notified_by: Vec<String>,
+    /// The interface or extern-abstract this symbol implements
+    pub implements: Option<String>,
+    /// For interfaces: the fn and struct snippets implementing it
+    pub implemented_by: Vec<String>,
+    /// Declared effects
+    pub effects: Vec<String>,
+    /// Declared effects with their parameters, in declaration order
+    pub declared_effects: Vec<DeclaredEffect>,
+}
+
+/// An effect a symbol declares, e.g. `network.http host="api.example.com"`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct DeclaredEffect {
+    pub name: String,
+    /// Parameter values as written, with strings unquoted
+    pub params: BTreeMap<String, String>,
+}
+
+impl From<&EffectDecl> for DeclaredEffect {
+    fn from(effect: &EffectDecl) -> Self {
+        let params = effect
+            .params
+            .iter()
+            .map(|param| {
+                let value = match &param.value {
+                    Literal::String(text) => text.clone(),
+                    other => other.to_cov(0),
+                };
+                (param.name.clone(), value)
+            })
+            .collect();
+        Self { name: effect.name.clone(), params }
+    }
+}
+
+/// The symbol graph over all snippets in a project
+#[derive(Debug)]
+pub struct ProjectGraph {
+    graph: SymbolGraph,
+}
+
+impl ProjectGraph {
+    pub(crate) fn new(graph: SymbolGraph) -> Self {
+        Self { graph }
+    }
+
+    /// Number of symbols
+    pub fn len(&self) -> usize {
+        self.graph.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.graph.is_empty()
+    }
+
+    /// Look up a symbol by snippet ID
+    pub fn symbol(&self, name: &str) -> Option<Symbol> {
+        self.graph.get_by_name(name).map(|info| self.to_symbol(info))
+    }
+
+    /// All symbols, sorted by name
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let mut symbols: Vec<Symbol> = self.graph.iter().map(|info| self.to_symbol(info)).collect();
+        symbols.sort_by(|a, b| a.name.cmp(&b.name));
+        symbols
+    }
+
+    /// The underlying compiler graph
+    ///
+    /// Not covered by the driver's stability guarantee: its interface follows
+    /// `covenant-symbols`.
+    pub fn as_symbol_graph(&self) -> &SymbolGraph {
+        &self.graph
+    }
+
+    /// Take the underlying compiler graph; see [`ProjectGraph::as_symbol_graph`]
+    pub fn into_symbol_graph(self) -> SymbolGraph {
+        self.graph
+    }
+
+    fn to_symbol(&self, info: &SymbolInfo) -> Symbol {
+        let names = |ids: &std::collections::HashSet<covenant_symbols::SymbolId>| {
+            sorted(ids.iter().filter_map(|id| self.graph.get(*id)).map(|s| s.name.clone()))
+        };
+        Symbol {
+            name: info.name.clone(),
+            kind: kind_name(info.kind).to_string(),
+            calls: sorted(info.calls.iter().cloned()),
+            called_by: names(&info.called_by),
+            references: sorted(info.references.iter().cloned()),
+            referenced_by: names(&info.referenced_by),
+            notifies: names(&info.notifies),
+            notified_by: names(&info.notified_by),
+            implements: info.implements.clone(),
+            implemented_by: names(&info.implemented_by),
+            effects: info.declared_effects.iter().map(|e| e.name.clone()).collect(),
+            declared_effects: info.declared_effects.iter().map(DeclaredEffect::from).collect(),
+        }
+    }
+}
+
+fn sorted(names: impl Iterator<Item = String>) -> Vec<String> {
+    let mut names: Vec<String> = names.collect();
+    names.sort();
+    names
+}
+
+fn kind_name(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::Function => "fn",
+        SymbolKind::Workflow => "workflow",
+        SymbolKind::Struct => "struct",
+        SymbolKind::Enum => "enum",
+        SymbolKind::Module => "module",
+        SymbolKind::Database => "database",
+        SymbolKind::Extern => "extern",
+        SymbolKind::ExternAbstract => "extern-abstract",
+        SymbolKind::ExternImpl => "extern-impl",
+        SymbolKind::Test => "test",
+        SymbolKind::Data => "data",
+        SymbolKind::Subscriber => "subscriber",
+        SymbolKind::Const => "const",
+        SymbolKind::Interface => "interface",
+        SymbolKind::Alias => "alias",
+    }
+}
@@ -0,0 +1,42 @@
+//! Covenant Driver - Stable API for Covenant projects
+//!
+//! Tools that parse, check, or compile Covenant code should depend on this
+//! crate rather than on the individual compiler crates, whose interfaces
+//! change as the compiler evolves. The driver exposes the pipeline as a
+//! [`Project`] and reports every problem as a structured [`Diagnostic`]:
+//!
+//! ```no_run
+//! use covenant_driver::{Platform, Project, Target};
+//!
+//! let project = Project::load(&["src/main.cov"])?;
+//! let report = project.check();
+//! for diagnostic in report.diagnostics() {
+//!     eprintln!("{}", diagnostic);
+//! }
+//! if report.is_ok() {
+//!     let artifact = project.compile(Target::new(Platform::Node))?;
+//!     std::fs::write("main.wasm", &artifact.wasm)?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Everything exported here follows semver. [`ProjectGraph::as_symbol_graph`]
+//! is the one escape hatch into compiler internals and is not covered.
+
+mod diagnostic;
+mod error;
+mod graph;
+mod project;
+mod target;
+mod zip;
+
+pub use diagnostic::*;
+pub use error::*;
+pub use graph::*;
+pub use project::*;
+pub use target::*;
+
+pub use covenant_ast::{Limits, Program, Span};
+pub use covenant_checker::{EffectDef, EffectPolicy, EffectRegistry};
+pub use covenant_codegen::{MemoryLimits, WasmFeature, WasmFeatures, LAMBDA_SHIM, WORKER_SHIM};
+pub use covenant_llm::{LlmMode, LlmPolicy};
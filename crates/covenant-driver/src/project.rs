@@ -0,0 +1,585 @@
+//! Projects: a set of source files checked and compiled together
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use covenant_ast::{Limits, Program, Snippet, Span};
+use covenant_checker::{
+    check_effect_names, check_effect_policy, check_effects, check_limits, check_sensitive_flows, check_with_limits,
+    EffectDef, EffectError, EffectPolicy, EffectRegistry,
+};
+use covenant_codegen::{HostRuntime, WorkerBinding};
+use covenant_llm::LlmPolicy;
+use covenant_optimizer::{optimize_program, OptLevel, OptSettings};
+use covenant_symbols::{build_from_snippets_with_cycle_mode, build_symbol_graph, CycleMode, ProjectError, ProjectSymbols};
+
+use crate::{
+    Artifact, Diagnostic, DriverError, LambdaPackage, OciPackage, Platform, ProjectGraph, Severity, Stage, Target,
+    WorkerBundle,
+};
+
+/// Name of the per-project configuration file
+pub const PROJECT_CONFIG: &str = "covenant.json";
+
+/// A source file in a project
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub source: String,
+}
+
+/// A set of Covenant source files and the settings to check them with
+#[derive(Debug, Clone)]
+pub struct Project {
+    files: Vec<SourceFile>,
+    limits: Limits,
+    effects: EffectRegistry,
+    validate_requirements: bool,
+    base_image: Option<String>,
+    cycle_mode: CycleMode,
+    llm_policy: LlmPolicy,
+}
+
+impl Project {
+    /// Read the given files
+    ///
+    /// Settings come from the nearest `covenant.json` above the first file:
+    /// structural limits, e.g. `{"limits": {"max_nesting_depth": 32}}`, where
+    /// missing keys keep their defaults, and custom effects, e.g.
+    /// `{"effects": {"payments": {"description": "...", "parent": "network"}}}`,
+    /// which extend the standard library's, and the effect policy, e.g.
+    /// `{"policy": {"network": {"host": ["api.example.com"]}}}`, the base of
+    /// packaged OCI images, e.g. `{"image": {"base": "denoland/deno:2.1.4"}}`,
+    /// whether annotated functions may recurse mutually,
+    /// `{"cycles": "groups"}` (the default is `"reject"`), and where LLM
+    /// calls may go, e.g. `{"llm": {"mode": "offline"}}` (see [`LlmPolicy`]).
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Result<Self, DriverError> {
+        let files = paths
+            .iter()
+            .map(|path| {
+                let path = path.as_ref().to_path_buf();
+                match fs::read_to_string(&path) {
+                    Ok(source) => Ok(SourceFile { path, source }),
+                    Err(source) => Err(DriverError::Io { path, source }),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let config = match files.first() {
+            Some(file) => find_project_config(&file.path)?,
+            None => ProjectConfig::default(),
+        };
+
+        Ok(Self::from_sources(files)
+            .with_limits(config.limits)
+            .with_effects(config.effects)
+            .with_base_image(config.base_image)
+            .with_cycle_mode(config.cycle_mode)
+            .with_llm_policy(config.llm_policy))
+    }
+
+    /// A project over in-memory sources, with default settings
+    pub fn from_sources(files: Vec<SourceFile>) -> Self {
+        Self {
+            files,
+            limits: Limits::default(),
+            effects: EffectRegistry::std(),
+            validate_requirements: false,
+            base_image: None,
+            cycle_mode: CycleMode::Reject,
+            llm_policy: LlmPolicy::default(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Effects the project may declare
+    pub fn with_effects(mut self, effects: EffectRegistry) -> Self {
+        self.effects = effects;
+        self
+    }
+
+    /// Base image of packaged OCI images, or `None` for the default
+    pub fn with_base_image(mut self, base_image: Option<String>) -> Self {
+        self.base_image = base_image;
+        self
+    }
+
+    /// How mutual recursion is checked (see [`CycleMode`])
+    pub fn with_cycle_mode(mut self, cycle_mode: CycleMode) -> Self {
+        self.cycle_mode = cycle_mode;
+        self
+    }
+
+    /// Where commands working on the project may call an LLM
+    pub fn with_llm_policy(mut self, llm_policy: LlmPolicy) -> Self {
+        self.llm_policy = llm_policy;
+        self
+    }
+
+    /// Also validate requirement coverage when checking
+    pub fn with_requirement_validation(mut self, enabled: bool) -> Self {
+        self.validate_requirements = enabled;
+        self
+    }
+
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn effects(&self) -> &EffectRegistry {
+        &self.effects
+    }
+
+    pub fn llm_policy(&self) -> &LlmPolicy {
+        &self.llm_policy
+    }
+
+    /// Parse, build the symbol graph for, and type check each file
+    ///
+    /// One symbol graph is built over every file that parses, so calls and
+    /// type references across files resolve, and each of its diagnostics is
+    /// reported in the file it points into. Type checking is per file; a
+    /// failure in one file does not stop the others.
+    pub fn check(&self) -> CheckReport {
+        let mut programs = Vec::with_capacity(self.files.len());
+        let mut project = covenant_symbols::Project::new().with_cycle_mode(self.cycle_mode);
+        for file in &self.files {
+            let program = self.parse_file(file);
+            match &program {
+                Ok(program) => project.add_file(&file.path, program),
+                Err(_) => project.add_snippets(&file.path, Vec::new()),
+            };
+            programs.push(program);
+        }
+        let symbols = project.build();
+        let effect_errors: Vec<EffectError> = match &symbols {
+            Ok(symbols) => {
+                let mut errors = check_effect_names(&symbols.graph, &self.effects);
+                errors.extend(check_effect_policy(&symbols.graph, &self.effects));
+                errors
+            }
+            Err(_) => Vec::new(),
+        };
+
+        CheckReport {
+            files: self
+                .files
+                .iter()
+                .zip(programs)
+                .enumerate()
+                .map(|(index, (file, program))| {
+                    let file_id = covenant_symbols::FileId(index as u32);
+                    self.check_file(file, file_id, program, &symbols, &effect_errors)
+                })
+                .collect(),
+        }
+    }
+
+    fn check_file(
+        &self,
+        file: &SourceFile,
+        file_id: covenant_symbols::FileId,
+        program: Result<Program, Diagnostic>,
+        symbols: &Result<ProjectSymbols, Vec<ProjectError>>,
+        effect_errors: &[EffectError],
+    ) -> FileReport {
+        let mut report = FileReport {
+            path: file.path.clone(),
+            diagnostics: Vec::new(),
+            summary: None,
+        };
+
+        let program = match program {
+            Ok(program) => program,
+            Err(diagnostic) => {
+                report.diagnostics.push(diagnostic);
+                return report;
+            }
+        };
+
+        // Size limits and sensitive values reaching logs are advisory; report them before anything else
+        if let Program::Snippets { snippets, .. } = &program {
+            report.push_all(check_limits(snippets, &self.limits).iter().map(Diagnostic::from_limit_warning));
+            report.push_all(check_sensitive_flows(snippets).iter().map(Diagnostic::from_sensitive_flow_warning));
+        }
+
+        let in_file = |error: &&ProjectError| error.file.as_ref() == Some(&file.path);
+        let symbol_count = match symbols {
+            Ok(symbols) => {
+                report.push_all(
+                    symbols.deferred_errors.iter().filter(in_file).map(|e| Diagnostic::from_project_error(e, Severity::Warning)),
+                );
+                report.push_all(
+                    effect_errors
+                        .iter()
+                        .filter(|e| symbols.path_of(e.function()) == Some(file.path.as_path()))
+                        .map(Diagnostic::from_effect_error),
+                );
+                symbols.symbols_in(file_id).len()
+            }
+            // Files the project's errors do not point into are still type checked
+            Err(errors) => {
+                let errors: Vec<&ProjectError> = errors.iter().filter(in_file).collect();
+                if !errors.is_empty() {
+                    report.push_all(errors.into_iter().map(|e| Diagnostic::from_project_error(e, Severity::Error)));
+                    return report;
+                }
+                match &program {
+                    Program::Snippets { snippets, .. } => snippets.len(),
+                    Program::Legacy { .. } => 0,
+                }
+            }
+        };
+
+        let result = match check_with_limits(&program, self.limits) {
+            Ok(result) => result,
+            Err(errors) => {
+                report.push_all(errors.into_iter().map(Diagnostic::from_check_error));
+                return report;
+            }
+        };
+        report.push_all(result.warnings.iter().map(Diagnostic::from_conversion_warning));
+
+        let requirement_coverage = self.validate_requirements.then(|| {
+            let coverage = covenant_requirements::validate_program(&program, None);
+            report.push_all(coverage.errors.iter().map(Diagnostic::from_requirement_error));
+            coverage.summary.coverage_percent
+        });
+
+        report.summary = Some(CheckSummary {
+            symbols: symbol_count,
+            functions: result.symbols.functions().count(),
+            pure_functions: result.symbols.functions().filter(|s| result.effects.is_pure(s.id)).count(),
+            requirement_coverage,
+        });
+        report
+    }
+
+    /// Build one symbol graph over the snippets of every file
+    pub fn graph(&self) -> Result<ProjectGraph, DriverError> {
+        let mut project = covenant_symbols::Project::new().with_cycle_mode(self.cycle_mode);
+        let mut errors = Vec::new();
+        for file in &self.files {
+            match self.parse_file(file) {
+                Ok(program) => {
+                    project.add_file(&file.path, &program);
+                }
+                Err(diagnostic) => errors.push(diagnostic),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors.into());
+        }
+
+        project
+            .build()
+            .map(|symbols| ProjectGraph::new(symbols.graph))
+            .map_err(|errors| {
+                errors.iter().map(|e| Diagnostic::from_project_error(e, Severity::Error)).collect::<Vec<_>>().into()
+            })
+    }
+
+    /// Check, optimize, and compile every file into one WASM module with
+    /// embedded symbol metadata
+    pub fn compile(&self, target: Target) -> Result<Artifact, DriverError> {
+        if let Some(feature) = target.unsupported_feature() {
+            return Err(DriverError::UnsupportedFeature { platform: target.platform, feature });
+        }
+
+        let program = match self.files.as_slice() {
+            [file] => self.parse_file(file).map_err(|d| vec![d])?,
+            _ => Program::Snippets {
+                snippets: self.parse_snippets()?,
+                span: Span::dummy(),
+            },
+        };
+        // The optimizer works on core steps
+        let mut program = covenant_lower::lower(&program).into_owned();
+
+        let result = check_with_limits(&program, self.limits)
+            .map_err(|errors| errors.into_iter().map(Diagnostic::from_check_error).collect::<Vec<_>>())?;
+
+        let mut warnings: Vec<Diagnostic> = result.warnings.iter().map(Diagnostic::from_conversion_warning).collect();
+        if target.opt_level > 0 {
+            let settings = OptSettings {
+                level: match target.opt_level {
+                    1 => OptLevel::O1,
+                    2 => OptLevel::O2,
+                    _ => OptLevel::O3,
+                },
+                emit_warnings: true,
+            };
+            if let Program::Snippets { snippets, .. } = &mut program {
+                let opt_result = optimize_program(snippets, &settings);
+                warnings.extend(opt_result.warnings.into_iter().map(|w| {
+                    Diagnostic::warning(Stage::Optimize, w.message).with_code(w.code)
+                }));
+            }
+        }
+
+        // Symbol metadata is best effort: a graph that fails to build embeds nothing
+        let graph = match &program {
+            Program::Snippets { snippets, .. } => build_from_snippets_with_cycle_mode(snippets, self.cycle_mode),
+            Program::Legacy { .. } => build_symbol_graph(&program),
+        }
+        .map(|r| r.graph)
+        .unwrap_or_default();
+        let effect_result = check_effects(&graph);
+
+        let wasm = covenant_codegen::compile_with_symbols(&program, &result.symbols, &graph, &effect_result, target.features, target.memory)
+            .map_err(|e| vec![Diagnostic::error(Stage::Codegen, e.to_string())])?;
+
+        Ok(Artifact { wasm, warnings })
+    }
+
+    /// Compile the project into a Cloudflare Worker named `name`: the module
+    /// (`<name>.wasm`), a fetch handler serving its route triggers, and a
+    /// `wrangler.toml` binding its database targets to D1 or KV
+    pub fn worker_bundle(&self, target: Target, name: &str) -> Result<WorkerBundle, DriverError> {
+        let artifact = self.compile(target)?;
+        let snippets = self.parse_snippets()?;
+        let bindings: Vec<WorkerBinding> = covenant_codegen::query_targets(&snippets)
+            .map_err(|e| vec![Diagnostic::error(Stage::Codegen, e.to_string())])?
+            .iter()
+            .map(WorkerBinding::for_target)
+            .collect();
+        let routes = covenant_codegen::worker_routes(&snippets);
+        Ok(WorkerBundle {
+            shim: covenant_codegen::emit_worker(&routes, &bindings, &format!("{}.wasm", name)),
+            wrangler: covenant_codegen::emit_wrangler_toml(name, &bindings),
+            artifact,
+        })
+    }
+
+    /// Compile the project into an AWS Lambda function named `name`, invoking
+    /// the snippet `entry` (default: the function named `main`). The zip
+    /// holds the module (`<name>.wasm`) and a Node.js handler; the SAM and
+    /// Terraform snippets deploy it from `<name>.zip`.
+    pub fn lambda_package(&self, target: Target, name: &str, entry: Option<&str>) -> Result<LambdaPackage, DriverError> {
+        let snippets = self.parse_snippets()?;
+        let entry = covenant_codegen::lambda_entry(&snippets, entry)
+            .map_err(|e| vec![Diagnostic::error(Stage::Codegen, e.to_string())])?;
+        let artifact = self.compile(target)?;
+        let wasm_file = format!("{}.wasm", name);
+        let zip_file = format!("{}.zip", name);
+        let shim = covenant_codegen::emit_lambda(&entry, &wasm_file);
+        let zip = crate::zip::zip_stored(&[
+            (covenant_codegen::LAMBDA_SHIM, shim.as_bytes()),
+            (&wasm_file, &artifact.wasm),
+        ]);
+        Ok(LambdaPackage {
+            sam: covenant_codegen::emit_sam_template(name, &zip_file),
+            terraform: covenant_codegen::emit_terraform(name, &zip_file),
+            shim,
+            zip,
+            artifact,
+        })
+    }
+
+    /// Compile the project into the build context of an OCI image named
+    /// `name`, running the module under Deno with `deno_flags` as its
+    /// permissions. The context also needs the Deno host runner in `host/`.
+    pub fn oci_package(&self, target: Target, name: &str, deno_flags: &[String]) -> Result<OciPackage, DriverError> {
+        let artifact = self.compile(target)?;
+        let wasm_file = format!("{}.wasm", name);
+        let base = self.base_image.as_deref().unwrap_or(covenant_codegen::DEFAULT_BASE_IMAGE);
+        Ok(OciPackage {
+            containerfile: covenant_codegen::emit_containerfile(base, &wasm_file, deno_flags),
+            wasm_path: format!("{}/{}", covenant_codegen::IMAGE_APP_DIR, wasm_file),
+            artifact,
+        })
+    }
+
+    /// Generate the JavaScript host glue providing the project's extern
+    /// imports on `platform`, from its extern and extern-impl snippets
+    pub fn host_glue(&self, platform: Platform) -> Result<String, DriverError> {
+        let runtime = match platform {
+            Platform::Deno => HostRuntime::Deno,
+            Platform::Node => HostRuntime::Node,
+            Platform::Bun => HostRuntime::Bun,
+            Platform::Browser | Platform::Wasi | Platform::Worker => {
+                return Err(DriverError::UnsupportedHost { platform })
+            }
+        };
+        let snippets = self.parse_snippets()?;
+        let bindings = covenant_codegen::host_bindings(&snippets, platform.as_str());
+        Ok(covenant_codegen::emit_host(&bindings, runtime))
+    }
+
+    fn parse_file(&self, file: &SourceFile) -> Result<Program, Diagnostic> {
+        covenant_parser::parse_with_limits(&file.source, self.limits)
+            .map_err(|e| Diagnostic::from_parse_error(&e).with_file(&file.path))
+    }
+
+    /// Parse every file, skipping legacy-syntax files, which have no snippets
+    fn parse_snippets(&self) -> Result<Vec<Snippet>, DriverError> {
+        let mut snippets = Vec::new();
+        let mut errors = Vec::new();
+        for file in &self.files {
+            match self.parse_file(file) {
+                Ok(Program::Snippets { snippets: parsed, .. }) => snippets.extend(parsed),
+                Ok(Program::Legacy { .. }) => {}
+                Err(diagnostic) => errors.push(diagnostic),
+            }
+        }
+        if errors.is_empty() {
+            Ok(snippets)
+        } else {
+            Err(errors.into())
+        }
+    }
+}
+
+/// Settings read from `covenant.json`
+#[derive(Debug)]
+struct ProjectConfig {
+    limits: Limits,
+    effects: EffectRegistry,
+    base_image: Option<String>,
+    cycle_mode: CycleMode,
+    llm_policy: LlmPolicy,
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        Self {
+            limits: Limits::default(),
+            effects: EffectRegistry::std(),
+            base_image: None,
+            cycle_mode: CycleMode::Reject,
+            llm_policy: LlmPolicy::default(),
+        }
+    }
+}
+
+/// The LLM policy of the nearest `covenant.json` at or above `dir`, for
+/// commands that call an LLM without reading a project's files
+pub fn find_llm_policy(dir: &Path) -> Result<LlmPolicy, DriverError> {
+    Ok(find_config_above(fs::canonicalize(dir).ok())?.llm_policy)
+}
+
+/// The nearest `covenant.json` at or above `file`'s directory; its
+/// directory is the project root
+pub fn find_project_config_path(file: &Path) -> Option<PathBuf> {
+    let start = fs::canonicalize(file)
+        .ok()
+        .and_then(|f| f.parent().map(|p| p.to_path_buf()))
+        .or_else(|| std::env::current_dir().ok());
+    config_path_above(start)
+}
+
+/// Settings from the nearest `covenant.json` at or above `file`'s directory
+fn find_project_config(file: &Path) -> Result<ProjectConfig, DriverError> {
+    read_config(find_project_config_path(file))
+}
+
+fn find_config_above(start: Option<PathBuf>) -> Result<ProjectConfig, DriverError> {
+    read_config(config_path_above(start))
+}
+
+fn config_path_above(start: Option<PathBuf>) -> Option<PathBuf> {
+    start.iter().flat_map(|dir| dir.ancestors()).map(|dir| dir.join(PROJECT_CONFIG)).find(|path| path.is_file())
+}
+
+fn read_config(config_path: Option<PathBuf>) -> Result<ProjectConfig, DriverError> {
+    let Some(config_path) = config_path else {
+        return Ok(ProjectConfig::default());
+    };
+    parse_project_config(&config_path).map_err(|message| DriverError::Config { path: config_path, message })
+}
+
+fn parse_project_config(path: &Path) -> Result<ProjectConfig, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let config: serde_json::Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+
+    let limits = match config.get("limits") {
+        Some(limits) => serde_json::from_value(limits.clone()).map_err(|e| e.to_string())?,
+        None => Limits::default(),
+    };
+    let custom: BTreeMap<String, EffectDef> = match config.get("effects") {
+        Some(effects) => serde_json::from_value(effects.clone()).map_err(|e| e.to_string())?,
+        None => BTreeMap::new(),
+    };
+    let policy = match config.get("policy") {
+        Some(policy) => serde_json::from_value(policy.clone()).map_err(|e| e.to_string())?,
+        None => EffectPolicy::new(),
+    };
+    let effects = EffectRegistry::std()
+        .with_effects(custom)
+        .map_err(|e| e.to_string())?
+        .with_policy(policy);
+
+    let base_image = match config.get("image").and_then(|image| image.get("base")) {
+        Some(base) => Some(base.as_str().ok_or("image.base must be a string")?.to_string()),
+        None => None,
+    };
+
+    let cycle_mode = match config.get("cycles").map(|cycles| cycles.as_str()) {
+        None | Some(Some("reject")) => CycleMode::Reject,
+        Some(Some("groups")) => CycleMode::AnnotatedGroups,
+        Some(_) => return Err("cycles must be \"reject\" or \"groups\"".to_string()),
+    };
+
+    let llm_policy = match config.get("llm") {
+        Some(llm) => serde_json::from_value(llm.clone()).map_err(|e| format!("llm: {}", e))?,
+        None => LlmPolicy::default(),
+    };
+
+    Ok(ProjectConfig { limits, effects, base_image, cycle_mode, llm_policy })
+}
+
+/// Result of checking a project
+#[derive(Debug, Clone)]
+pub struct CheckReport {
+    pub files: Vec<FileReport>,
+}
+
+impl CheckReport {
+    /// Whether no file has an error
+    pub fn is_ok(&self) -> bool {
+        self.files.iter().all(FileReport::is_ok)
+    }
+
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.files.iter().flat_map(|f| f.diagnostics.iter())
+    }
+}
+
+/// Result of checking one file
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Present when the file type checked
+    pub summary: Option<CheckSummary>,
+}
+
+impl FileReport {
+    pub fn is_ok(&self) -> bool {
+        !self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+
+    fn push_all(&mut self, diagnostics: impl Iterator<Item = Diagnostic>) {
+        let path = &self.path;
+        self.diagnostics.extend(diagnostics.map(|d| d.with_file(path)));
+    }
+}
+
+/// Counts for a file that type checked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CheckSummary {
+    pub symbols: usize,
+    pub functions: usize,
+    pub pure_functions: usize,
+    /// Percentage of requirements covered by tests, when validated
+    pub requirement_coverage: Option<f64>,
+}
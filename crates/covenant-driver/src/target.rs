@@ -0,0 +1,153 @@
+//! Compilation targets
+
+use std::fmt;
+use std::str::FromStr;
+
+use covenant_codegen::{MemoryLimits, WasmFeature, WasmFeatures};
+
+/// Host platform the compiled module runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Platform {
+    #[default]
+    Deno,
+    Node,
+    Bun,
+    Browser,
+    Wasi,
+    /// Cloudflare Workers, deployed as a bundle with a fetch handler
+    Worker,
+}
+
+impl Platform {
+    pub const ALL: [Platform; 6] = [
+        Platform::Deno,
+        Platform::Node,
+        Platform::Bun,
+        Platform::Browser,
+        Platform::Wasi,
+        Platform::Worker,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Platform::Deno => "deno",
+            Platform::Node => "node",
+            Platform::Bun => "bun",
+            Platform::Browser => "browser",
+            Platform::Wasi => "wasi",
+            Platform::Worker => "worker",
+        }
+    }
+
+    /// Whether current releases of the platform's runtime run modules using
+    /// `feature` without extra flags. Safari and Bun (JavaScriptCore) lack
+    /// relaxed SIMD, and Wasmtime leaves GC off by default.
+    pub fn supports(self, feature: WasmFeature) -> bool {
+        match feature {
+            WasmFeature::Simd | WasmFeature::BulkMemory => true,
+            WasmFeature::RelaxedSimd => !matches!(self, Platform::Browser | Platform::Bun),
+            WasmFeature::Gc => self != Platform::Wasi,
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Platform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Platform::ALL
+            .into_iter()
+            .find(|p| p.as_str() == s)
+            .ok_or_else(|| format!("Invalid target '{}'. Valid targets: deno, node, bun, browser, wasi, worker", s))
+    }
+}
+
+/// What to compile a project to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Target {
+    pub platform: Platform,
+    /// Optimization level, 0 (none) to 3 (aggressive)
+    pub opt_level: u8,
+    /// Proposals beyond the MVP the module may use
+    pub features: WasmFeatures,
+    /// How large the module's memory may grow
+    pub memory: MemoryLimits,
+}
+
+impl Target {
+    /// Target a platform without optimization
+    pub fn new(platform: Platform) -> Self {
+        Self { platform, opt_level: 0, features: WasmFeatures::none(), memory: MemoryLimits::default() }
+    }
+
+    /// Set the optimization level; levels above 3 are treated as 3
+    pub fn with_opt_level(mut self, opt_level: u8) -> Self {
+        self.opt_level = opt_level.min(3);
+        self
+    }
+
+    /// Allow the module to use the given proposals
+    pub fn with_features(mut self, features: WasmFeatures) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Cap the module's memory; allocating past the limit traps
+    pub fn with_memory_limits(mut self, memory: MemoryLimits) -> Self {
+        self.memory = memory;
+        self
+    }
+
+    /// The first enabled feature the platform does not support
+    pub fn unsupported_feature(&self) -> Option<WasmFeature> {
+        self.features.iter().find(|feature| !self.platform.supports(*feature))
+    }
+}
+
+/// A compiled WASM module
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub wasm: Vec<u8>,
+    /// Warnings raised while compiling (e.g. by the optimizer)
+    pub warnings: Vec<crate::Diagnostic>,
+}
+
+/// A compiled module packaged as a Cloudflare Worker
+#[derive(Debug, Clone)]
+pub struct WorkerBundle {
+    pub artifact: Artifact,
+    /// `worker.mjs`, the Worker's entry point
+    pub shim: String,
+    /// `wrangler.toml`, with the D1 and KV IDs left to fill in
+    pub wrangler: String,
+}
+
+/// A compiled module packaged as an AWS Lambda function
+#[derive(Debug, Clone)]
+pub struct LambdaPackage {
+    pub artifact: Artifact,
+    /// `index.mjs`, the Node.js handler
+    pub shim: String,
+    /// The deployable zip: the handler and the module
+    pub zip: Vec<u8>,
+    /// `template.yaml`, a SAM template deploying the zip
+    pub sam: String,
+    /// `main.tf`, a Terraform snippet deploying the zip
+    pub terraform: String,
+}
+
+/// A compiled module packaged as the build context of an OCI image
+#[derive(Debug, Clone)]
+pub struct OciPackage {
+    pub artifact: Artifact,
+    /// `Containerfile`, running the module under the Deno host runner
+    pub containerfile: String,
+    /// Path of the module in the image
+    pub wasm_path: String,
+}
@@ -0,0 +1,71 @@
+//! Minimal zip archives for deployment packages
+//!
+//! Entries are stored uncompressed with a fixed timestamp, so packaging the
+//! same files twice gives the same bytes and deployment tools that hash the
+//! archive see no change.
+
+/// DOS date for 1980-01-01, the earliest a zip can record
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Version 2.0, needed to extract plain stored files
+const VERSION: u16 = 20;
+
+/// Made by Unix, so extractors apply the permissions below
+const VERSION_MADE_BY: u16 = (3 << 8) | VERSION;
+
+/// Regular file, rw-r--r--
+const FILE_ATTRIBUTES: u32 = 0o100644 << 16;
+
+/// Write `files` (name, contents) to a zip archive, in order
+pub(crate) fn zip_stored(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, data) in files {
+        let offset = out.len() as u32;
+        let crc = crc32fast::hash(data);
+
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        entry_fields(&mut out, name, data, crc);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central.extend_from_slice(&VERSION_MADE_BY.to_le_bytes());
+        central.extend_from_slice(&VERSION.to_le_bytes());
+        entry_fields(&mut central, name, data, crc);
+        central.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        central.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        central.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        central.extend_from_slice(&FILE_ATTRIBUTES.to_le_bytes());
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // this disk
+    out.extend_from_slice(&0u16.to_le_bytes()); // disk with the directory
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // comment length
+    out
+}
+
+/// The fields local and central headers share, from the flags through the
+/// extra field length
+fn entry_fields(out: &mut Vec<u8>, name: &str, data: &[u8], crc: u32) {
+    out.extend_from_slice(&0u16.to_le_bytes()); // flags
+    out.extend_from_slice(&0u16.to_le_bytes()); // method: stored
+    out.extend_from_slice(&0u16.to_le_bytes()); // time
+    out.extend_from_slice(&DOS_DATE.to_le_bytes());
+    out.extend_from_slice(&crc.to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes()); // compressed size
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+}
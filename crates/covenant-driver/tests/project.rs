@@ -0,0 +1,471 @@
+//! Integration tests for the driver API
+//!
+//! Tests loading projects from disk, checking, graph building, and compiling
+
+use std::fs;
+use std::path::PathBuf;
+
+use covenant_driver::{
+    find_llm_policy, DriverError, LlmPolicy, MemoryLimits, Platform, Project, Severity, SourceFile, Stage, Target,
+    WasmFeature, WasmFeatures,
+};
+
+const ADD: &str = r#"
+snippet id="math.add" kind="fn"
+
+signature
+  fn name="add"
+    param name="a" type="Int"
+    param name="b" type="Int"
+    returns type="Int"
+  end
+end
+
+body
+  step id="s1" kind="compute"
+    op=add
+    input var="a"
+    input var="b"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+
+end
+"#;
+
+const DOUBLE: &str = r#"
+snippet id="math.double" kind="fn"
+
+signature
+  fn name="double"
+    param name="x" type="Int"
+    returns type="Int"
+  end
+end
+
+body
+  step id="s1" kind="call"
+    fn="math.add"
+    arg name="a" from="x"
+    arg name="b" from="x"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+
+end
+"#;
+
+const BAD_STEP: &str = r#"
+snippet id="math.bad" kind="fn"
+body
+  step id="s1" kind="bogus"
+  end
+end
+end
+"#;
+
+const PAYMENTS: &str = r#"
+snippet id="billing.charge" kind="fn"
+
+effects
+  effect EFFECT
+end
+
+signature
+  fn name="charge"
+    returns type="Int"
+  end
+end
+
+body
+  step id="s1" kind="return"
+    lit=1
+    as="_"
+  end
+end
+
+end
+"#;
+
+fn source(path: &str, source: &str) -> SourceFile {
+    SourceFile {
+        path: PathBuf::from(path),
+        source: source.to_string(),
+    }
+}
+
+#[test]
+fn test_load_and_check() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("add.cov");
+    fs::write(&path, ADD).unwrap();
+
+    let project = Project::load(&[&path]).unwrap();
+    let report = project.check();
+
+    assert!(report.is_ok());
+    assert_eq!(report.files.len(), 1);
+    let summary = report.files[0].summary.expect("file type checks");
+    assert_eq!(summary.symbols, 1);
+    assert!(summary.pure_functions <= summary.functions);
+    assert_eq!(summary.requirement_coverage, None);
+}
+
+#[test]
+fn test_load_missing_file() {
+    let result = Project::load(&["does/not/exist.cov"]);
+    assert!(matches!(result, Err(DriverError::Io { .. })));
+}
+
+#[test]
+fn test_parse_error_is_structured() {
+    let project = Project::from_sources(vec![source("bad.cov", BAD_STEP), source("add.cov", ADD)]);
+    let report = project.check();
+
+    assert!(!report.is_ok());
+    assert!(report.files[1].is_ok(), "one failing file does not stop the others");
+
+    let errors: Vec<_> = report.diagnostics().filter(|d| d.severity == Severity::Error).collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].stage, Stage::Parse);
+    assert_eq!(errors[0].file, Some(PathBuf::from("bad.cov")));
+    assert!(errors[0].span.is_some());
+}
+
+#[test]
+fn test_graph_spans_files() {
+    let project = Project::from_sources(vec![source("add.cov", ADD), source("double.cov", DOUBLE)]);
+    let graph = project.graph().unwrap();
+
+    assert_eq!(graph.len(), 2);
+    let add = graph.symbol("math.add").unwrap();
+    assert_eq!(add.kind, "fn");
+    assert_eq!(add.called_by, vec!["math.double".to_string()]);
+    assert_eq!(graph.symbol("math.double").unwrap().calls, vec!["math.add".to_string()]);
+}
+
+#[test]
+fn test_graph_reports_duplicates_across_files() {
+    let project = Project::from_sources(vec![source("add.cov", ADD), source("copy.cov", ADD)]);
+    match project.graph() {
+        Err(DriverError::Diagnostics(diagnostics)) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].code.as_deref(), Some("E-SYMBOL-002"));
+            assert_eq!(diagnostics[0].file.as_deref(), Some(std::path::Path::new("copy.cov")));
+            assert_eq!(diagnostics[0].help.as_deref(), Some("first defined in add.cov"));
+        }
+        other => panic!("expected diagnostics, got {:?}", other.map(|graph| graph.len())),
+    }
+}
+
+#[test]
+fn test_compile_to_wasm() {
+    let project = Project::from_sources(vec![source("add.cov", ADD), source("double.cov", DOUBLE)]);
+    let artifact = project.compile(Target::new(Platform::Node).with_opt_level(2)).unwrap();
+    assert!(artifact.wasm.starts_with(b"\0asm"));
+}
+
+#[test]
+fn test_compile_records_wasm_features() {
+    let project = Project::from_sources(vec![source("add.cov", ADD)]);
+    let features: WasmFeatures = "simd,bulk-memory".parse().unwrap();
+    let artifact = project.compile(Target::new(Platform::Node).with_features(features)).unwrap();
+
+    let contains = |needle: &[u8]| artifact.wasm.windows(needle.len()).any(|w| w == needle);
+    assert!(contains(b"covenant.build"));
+    assert!(contains(br#""features":["simd","bulk-memory"]"#));
+}
+
+#[test]
+fn test_compile_records_memory_limit() {
+    let project = Project::from_sources(vec![source("add.cov", ADD)]);
+    let artifact = project.compile(Target::new(Platform::Node)).unwrap();
+    let contains = |wasm: &[u8], needle: &[u8]| wasm.windows(needle.len()).any(|w| w == needle);
+    assert!(contains(&artifact.wasm, br#""max_memory_pages":256"#));
+
+    let limited = Target::new(Platform::Node).with_memory_limits(MemoryLimits::with_max_pages(32).unwrap());
+    let artifact = project.compile(limited).unwrap();
+    assert!(contains(&artifact.wasm, br#""max_memory_pages":32"#));
+}
+
+#[test]
+fn test_compile_rejects_unsupported_wasm_feature() {
+    let project = Project::from_sources(vec![source("add.cov", ADD)]);
+    let target = Target::new(Platform::Wasi).with_features(WasmFeatures::none().with(WasmFeature::Gc));
+    assert!(matches!(
+        project.compile(target),
+        Err(DriverError::UnsupportedFeature { platform: Platform::Wasi, feature: WasmFeature::Gc })
+    ));
+}
+
+#[test]
+fn test_host_glue_binds_project_externs() {
+    let example = include_str!("../../../examples/platform-abstraction/platform-abstraction.cov");
+    let project = Project::from_sources(vec![source("platform.cov", example)]);
+    let glue = project.host_glue(Platform::Node).unwrap();
+    assert!(glue.contains(r#"requireEffects("console.println", ["console"]);"#));
+    assert!(glue.contains(r#"implementation("console.log@1")(readStr(msg_ptr, msg_len));"#));
+    let deno = project.host_glue(Platform::Deno).unwrap();
+    assert!(deno.contains("Deno.readTextFileSync"));
+    assert!(matches!(
+        project.host_glue(Platform::Wasi),
+        Err(DriverError::UnsupportedHost { platform: Platform::Wasi })
+    ));
+}
+
+#[test]
+fn test_worker_bundle() {
+    let service = r#"
+snippet id="app.users" kind="database"
+metadata
+  dialect="sqlite"
+end
+end
+
+snippet id="users.get" kind="fn"
+metadata
+  trigger route="GET /users/:id"
+end
+signature
+  fn name="get_user"
+    param name="id" type="Int"
+    returns type="String"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit="user"
+    as="_"
+  end
+end
+end
+"#;
+    let project = Project::from_sources(vec![source("service.cov", service)]);
+    let bundle = project.worker_bundle(Target::new(Platform::Worker), "service").unwrap();
+    assert!(bundle.artifact.wasm.starts_with(b"\0asm"));
+    assert!(bundle.shim.contains("import wasmModule from \"./service.wasm\";"));
+    assert!(bundle.shim.contains("path: \"/users/:id\", snippet: \"users.get\""));
+    assert!(bundle.wrangler.contains("[[d1_databases]]\nbinding = \"USERS\""));
+}
+
+#[test]
+fn test_lambda_package() {
+    let handler = r#"
+snippet id="app.greet" kind="fn"
+signature
+  fn name="greet"
+    param name="name" type="String"
+    returns type="String"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="name"
+    as="_"
+  end
+end
+end
+"#;
+    let project = Project::from_sources(vec![source("greet.cov", handler)]);
+    let package = project.lambda_package(Target::new(Platform::Node), "greet", Some("app.greet")).unwrap();
+    assert!(package.shim.contains("export const ENTRY = { snippet: \"app.greet\" };"));
+    assert!(package.zip.starts_with(b"PK\x03\x04"));
+    assert!(package.zip.windows(9).any(|w| w == b"index.mjs"));
+    assert!(package.zip.windows(10).any(|w| w == b"greet.wasm"));
+    assert!(package.sam.contains("CodeUri: greet.zip"));
+    assert!(package.terraform.contains("filename         = \"greet.zip\""));
+
+    // The entry defaults to `main`, which this project lacks
+    assert!(project.lambda_package(Target::new(Platform::Node), "greet", None).is_err());
+}
+
+#[test]
+fn test_oci_package() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("add.cov");
+    fs::write(&path, ADD).unwrap();
+    let flags = vec!["--allow-read=/app/add.wasm".to_string()];
+
+    let package = Project::load(&[&path]).unwrap().oci_package(Target::new(Platform::Deno), "add", &flags).unwrap();
+    assert_eq!(package.wasm_path, "/app/add.wasm");
+    assert!(package.containerfile.contains("FROM denoland/deno:distroless-2.1.4\n"));
+    assert!(package.containerfile.contains(
+        "ENTRYPOINT [\"deno\", \"run\", \"--allow-read=/app/add.wasm\", \"/app/host/run.deno.ts\", \"/app/add.wasm\"]"
+    ));
+
+    // The base image comes from covenant.json
+    fs::write(dir.path().join("covenant.json"), r#"{"image": {"base": "registry.local/deno:pinned"}}"#).unwrap();
+    let package = Project::load(&[&path]).unwrap().oci_package(Target::new(Platform::Deno), "add", &flags).unwrap();
+    assert!(package.containerfile.contains("FROM registry.local/deno:pinned\n"));
+
+    fs::write(dir.path().join("covenant.json"), r#"{"image": {"base": 2}}"#).unwrap();
+    assert!(matches!(Project::load(&[&path]), Err(DriverError::Config { .. })));
+}
+
+#[test]
+fn test_compile_reports_diagnostics() {
+    let project = Project::from_sources(vec![source("bad.cov", BAD_STEP)]);
+    match project.compile(Target::default()) {
+        Err(DriverError::Diagnostics(diagnostics)) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].stage, Stage::Parse);
+        }
+        other => panic!("expected diagnostics, got {:?}", other.map(|a| a.wasm.len())),
+    }
+}
+
+#[test]
+fn test_project_config_limits() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("covenant.json"), r#"{"limits": {"max_nesting_depth": 3}}"#).unwrap();
+    let path = dir.path().join("add.cov");
+    fs::write(&path, ADD).unwrap();
+
+    let project = Project::load(&[&path]).unwrap();
+    assert_eq!(project.limits().max_nesting_depth, 3);
+}
+
+#[test]
+fn test_invalid_project_config() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("covenant.json"), "{ not json").unwrap();
+    let path = dir.path().join("add.cov");
+    fs::write(&path, ADD).unwrap();
+
+    assert!(matches!(Project::load(&[&path]), Err(DriverError::Config { .. })));
+}
+
+#[test]
+fn test_unknown_effect_is_reported() {
+    let project = Project::from_sources(vec![source("billing.cov", &PAYMENTS.replace("EFFECT", "netwrok"))]);
+    let report = project.check();
+
+    let errors: Vec<_> = report.diagnostics().filter(|d| d.is_error()).collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].stage, Stage::Effects);
+    assert_eq!(errors[0].code.as_deref(), Some("E-EFFECT-004"));
+    assert!(errors[0].help.as_deref().unwrap().contains("network"));
+    assert!(errors[0].span.is_some());
+}
+
+#[test]
+fn test_project_config_effects() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("covenant.json"),
+        r#"{"effects": {"payments": {"description": "Charge cards", "parent": "network"}}}"#,
+    )
+    .unwrap();
+    let path = dir.path().join("billing.cov");
+    fs::write(&path, PAYMENTS.replace("EFFECT", "payments")).unwrap();
+
+    let project = Project::load(&[&path]).unwrap();
+    assert!(project.effects().covers("network", "payments"));
+    assert!(project.check().is_ok());
+
+    fs::write(dir.path().join("covenant.json"), r#"{"effects": {"payments": {"parent": "netwrok"}}}"#).unwrap();
+    assert!(matches!(Project::load(&[&path]), Err(DriverError::Config { .. })));
+}
+
+#[test]
+fn test_project_config_policy() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("covenant.json"), r#"{"policy": {"network": {"host": ["*.stripe.com"]}}}"#).unwrap();
+    let path = dir.path().join("billing.cov");
+    fs::write(&path, PAYMENTS.replace("EFFECT", r#"network.http host="api.stripe.com""#)).unwrap();
+    let project = Project::load(&[&path]).unwrap();
+    assert!(project.check().is_ok());
+
+    let charge = project.graph().unwrap().symbol("billing.charge").unwrap();
+    assert_eq!(charge.effects, vec!["network.http".to_string()]);
+    assert_eq!(charge.declared_effects[0].params.get("host").map(String::as_str), Some("api.stripe.com"));
+
+    fs::write(&path, PAYMENTS.replace("EFFECT", r#"network.http host="example.org""#)).unwrap();
+    let report = Project::load(&[&path]).unwrap().check();
+    let errors: Vec<_> = report.diagnostics().filter(|d| d.is_error()).collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code.as_deref(), Some("E-EFFECT-005"));
+}
+
+#[test]
+fn test_project_config_cycles() {
+    let func = |name: &str, callee: &str, metadata: &str| {
+        format!(
+            r#"
+snippet id="{name}" kind="fn"
+{metadata}
+signature
+  fn name="{name}"
+    param name="n" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="{callee}"
+    arg name="n" from="n"
+    as="r"
+  end
+  step id="s2" kind="return"
+    from="r"
+    as="_"
+  end
+end
+end
+"#
+        )
+    };
+    let group = "metadata\n  allow cycle group=\"parity\"\nend";
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("parity.cov");
+    fs::write(&path, func("is_even", "is_odd", group) + &func("is_odd", "is_even", "")).unwrap();
+
+    // Mutual recursion is rejected unless the project opts in
+    let report = Project::load(&[&path]).unwrap().check();
+    assert_eq!(report.diagnostics().find(|d| d.is_error()).unwrap().code.as_deref(), Some("E-SYMBOL-003"));
+
+    fs::write(dir.path().join("covenant.json"), r#"{"cycles": "groups"}"#).unwrap();
+    let report = Project::load(&[&path]).unwrap().check();
+    let errors: Vec<_> = report.diagnostics().filter(|d| d.is_error()).collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code.as_deref(), Some("E-SYMBOL-005"));
+    assert!(errors[0].message.contains("is_odd -> is_even -> is_odd"), "{}", errors[0].message);
+
+    fs::write(&path, func("is_even", "is_odd", group) + &func("is_odd", "is_even", group)).unwrap();
+    assert!(Project::load(&[&path]).unwrap().check().is_ok());
+
+    fs::write(dir.path().join("covenant.json"), r#"{"cycles": "sometimes"}"#).unwrap();
+    assert!(matches!(Project::load(&[&path]), Err(DriverError::Config { .. })));
+}
+
+#[test]
+fn test_llm_policy_from_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("add.cov");
+    fs::write(&path, ADD).unwrap();
+    assert_eq!(Project::load(&[&path]).unwrap().llm_policy(), &LlmPolicy::default());
+
+    fs::write(dir.path().join("covenant.json"), r#"{"llm": {"mode": "offline"}}"#).unwrap();
+    assert!(Project::load(&[&path]).unwrap().llm_policy().is_offline());
+    assert!(find_llm_policy(dir.path()).unwrap().is_offline());
+
+    // A misspelt setting must not silently allow calls
+    fs::write(dir.path().join("covenant.json"), r#"{"llm": {"mdoe": "offline"}}"#).unwrap();
+    assert!(matches!(Project::load(&[&path]), Err(DriverError::Config { .. })));
+}
+
+#[test]
+fn test_platform_from_str() {
+    for platform in Platform::ALL {
+        assert_eq!(platform.as_str().parse::<Platform>(), Ok(platform));
+    }
+    assert!("jvm".parse::<Platform>().is_err());
+}
@@ -0,0 +1,443 @@
+//! The example corpus
+//!
+//! Descriptions double as generation prompts, so they describe what to
+//! build rather than how the example file is laid out.
+
+use crate::{Example, Expected};
+
+pub(crate) const EXAMPLES: &[Example] = &[
+    Example {
+        name: "cross-platform-storage/cross-platform-storage",
+        description: r#"Cross-platform storage:
+- Extern stubs for std.storage.kv and std.storage.doc
+- Functions that save and load values by key and store JSON documents
+- Code that works identically on browser, Node.js, and WASI
+"#,
+        source: include_str!("../../../examples/cross-platform-storage/cross-platform-storage.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["std.storage"],
+        },
+    },
+    Example {
+        name: "database-queries/advanced-sql",
+        description: r#"Complex SQL query:
+- Postgres dialect with JOIN, GROUP BY, subqueries
+- Multiple params with different types
+- Complex return type with aggregations
+"#,
+        source: include_str!("../../../examples/database-queries/advanced-sql.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["database"],
+        },
+    },
+    Example {
+        name: "database-queries/database-access",
+        description: r#"Database access with:
+- User struct with id: Int, name: String, email: String
+- 'database' effect
+- get_user function: takes id: Int, returns union of User (optional) or DbError
+- Uses a query step with target="users", select all, where equals field="id" var="id"
+"#,
+        source: include_str!("../../../examples/database-queries/database-access.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["database"],
+        },
+    },
+    Example {
+        name: "database-queries/database-dialects",
+        description: r#"SQL dialect queries:
+- Query step with dialect="postgres" (or mysql, sqlserver, sqlite)
+- Uses body ... end block with raw SQL
+- params section for parameter binding
+- returns annotation for result type
+"#,
+        source: include_str!("../../../examples/database-queries/database-dialects.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["database"],
+        },
+    },
+    Example {
+        name: "database-queries/database-module",
+        description: r#"Database module with schema:
+- snippet kind="database" with dialect="postgres"
+- Schema section defining users table with id, email, name fields
+- connection="env:DATABASE_URL"
+"#,
+        source: include_str!("../../../examples/database-queries/database-module.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["database"],
+        },
+    },
+    Example {
+        name: "error-handling/error-handling",
+        description: r#"Error handling with:
+1. ParseError enum with variants: InvalidFormat(message: String), MissingField(name: String), OutOfRange(field: String, value: Int)
+2. Config struct with fields: host: String, port: Int, debug: Bool
+3. parse_config pure function that takes input: String and returns union of Config or ParseError
+"#,
+        source: include_str!("../../../examples/error-handling/error-handling.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &[],
+        },
+    },
+    Example {
+        name: "extern-bindings/extern-bindings",
+        description: r#"External function bindings:
+- snippet kind="extern" for declaring external functions
+- http.get binding with network effect, cost_hint=moderate, latency_hint=slow
+- Declares contract="axios.get@1"
+"#,
+        source: include_str!("../../../examples/extern-bindings/extern-bindings.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console", "database", "filesystem", "network", "random"],
+        },
+    },
+    Example {
+        name: "file-io/file-io",
+        description: r#"A file transformation function that:
+- Has 'filesystem' and 'console' effects
+- main function returns union of Unit or IoError
+- Reads from "input.txt", converts to uppercase, writes to "output.txt"
+- Prints "Done!" when complete
+"#,
+        source: include_str!("../../../examples/file-io/file-io.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console", "filesystem"],
+        },
+    },
+    Example {
+        name: "http-server/http-server",
+        description: r#"HTTP server with:
+- Request struct with: method: String, path: String, headers: Map<String, String>, body: String
+- Response struct with: status: Int, body: String
+- Extern server.listen, server.requests, server.respond bindings with an 'http_server' effect
+- Pure handle_request function that takes Request and returns Response, choosing the response by path
+- main function with 'http_server' and 'console' effects that serves requests in a loop
+"#,
+        source: include_str!("../../../examples/http-server/http-server.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console", "http_server"],
+        },
+    },
+    Example {
+        name: "json/build",
+        description: r#"JSON building:
+- Struct definitions for a small record
+- Functions that construct struct values with step kind="construct"
+"#,
+        source: include_str!("../../../examples/json/build.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &[],
+        },
+    },
+    Example {
+        name: "json/parse",
+        description: r#"JSON parsing:
+- Extern stubs for std.json functions
+- Functions that parse a JSON string and extract fields from the result
+- Union return types for parse failures
+"#,
+        source: include_str!("../../../examples/json/parse.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &[],
+        },
+    },
+    Example {
+        name: "json/validation",
+        description: r#"JSON validation:
+- Extern stubs for std.json functions
+- Functions that check a parsed JSON value has the expected fields and types
+- Returns a validation result instead of failing
+"#,
+        source: include_str!("../../../examples/json/validation.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console"],
+        },
+    },
+    Example {
+        name: "metaprogramming/metaprogramming",
+        description: r#"Querying and mutating the project AST:
+- 'meta' effect for introspection and source changes
+- Function that queries target="project" for functions with the 'database' effect, using the Covenant query dialect (not SQL)
+- Function that inserts a new node into the project with step kind="insert"
+"#,
+        source: include_str!("../../../examples/metaprogramming/metaprogramming.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["meta"],
+        },
+    },
+    Example {
+        name: "platform-abstraction/platform-abstraction",
+        description: r#"Platform abstraction:
+- snippet kind="extern-abstract" declaring a console.println interface
+- extern-impl snippets implementing it for several platforms
+- A function calling the abstract interface
+"#,
+        source: include_str!("../../../examples/platform-abstraction/platform-abstraction.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console"],
+        },
+    },
+    Example {
+        name: "query-system/doc-ingestion",
+        description: r#"Documentation ingestion:
+- Scans a directory with fs.read_dir and loops over entries with step kind="for"
+- Reads each file, transforms its text with string operations, and builds paths
+- Writes the generated data nodes to an output file
+"#,
+        source: include_str!("../../../examples/query-system/doc-ingestion.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console", "filesystem"],
+        },
+    },
+    Example {
+        name: "query-system/embedded-query",
+        description: r#"Embedded queries:
+- Functions querying kind="data" nodes with target="project"
+- ORDER BY and LIMIT clauses
+"#,
+        source: include_str!("../../../examples/query-system/embedded-query.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["meta"],
+        },
+    },
+    Example {
+        name: "query-system/parameterized-query",
+        description: r#"Parameterized project queries:
+- Query functions that take a String parameter
+- WHERE clauses using var="param_name" instead of a literal
+"#,
+        source: include_str!("../../../examples/query-system/parameterized-query.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["meta"],
+        },
+    },
+    Example {
+        name: "query-system/performance-benchmark",
+        description: r#"Data/documentation nodes:
+- Many snippet kind="data" entries with a note and content section
+- Functions querying them with target="project"
+- Sized for benchmarking query execution over ~100 nodes
+"#,
+        source: include_str!("../../../examples/query-system/performance-benchmark.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["meta"],
+        },
+    },
+    Example {
+        name: "query-system/rag-query",
+        description: r#"Retrieval for LLM context:
+- Keyword search over data node content with contains field="content"
+- Relation traversal to expand search hits with neighbouring nodes
+- Ancestor/descendant hierarchy traversal
+"#,
+        source: include_str!("../../../examples/query-system/rag-query.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["meta"],
+        },
+    },
+    Example {
+        name: "query-system/relation-traversal",
+        description: r#"Relation traversal:
+- Single-hop traversal between data nodes filtered by relation type
+- Outgoing and incoming directions
+- Starts from a generated index node
+"#,
+        source: include_str!("../../../examples/query-system/relation-traversal.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["meta"],
+        },
+    },
+    Example {
+        name: "structured-concurrency/structured-concurrency",
+        description: r#"Structured concurrency:
+- step kind="parallel" running several I/O calls at once and collecting results
+- step kind="race" returning the first call to finish
+- Extern stubs for the network calls involved
+"#,
+        source: include_str!("../../../examples/structured-concurrency/structured-concurrency.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["database", "network"],
+        },
+    },
+    Example {
+        name: "syntax-fundamentals/effects",
+        description: r#"Effects demonstration:
+- upload_file function combining 'filesystem', 'network', 'console' effects: reads a local file and posts it to a remote server
+- A module mixing purity: a pure format_date helper, a 'console' log_date function, and a 'datetime' fetch_current_date function
+- Effects propagating transitively through calls
+"#,
+        source: include_str!("../../../examples/syntax-fundamentals/effects.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console", "datetime", "filesystem", "network"],
+        },
+    },
+    Example {
+        name: "syntax-fundamentals/hello-world",
+        description: r#"A simple hello world function that:
+- Has a 'console' effect
+- Has a main function with no parameters that returns Unit
+- Calls println with the message "Hello, world!"
+"#,
+        source: include_str!("../../../examples/syntax-fundamentals/hello-world.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console"],
+        },
+    },
+    Example {
+        name: "syntax-fundamentals/higher-order",
+        description: r#"Higher-order functions:
+- map function that takes a list and a function, applies the function to each element
+- filter function that takes a list and a predicate, returns matching elements
+- Uses step kind="call" with function parameters
+"#,
+        source: include_str!("../../../examples/syntax-fundamentals/higher-order.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &[],
+        },
+    },
+    Example {
+        name: "syntax-fundamentals/pattern-matching",
+        description: r#"Pattern matching with match steps:
+- Result enum with Ok(value: T) and Err(error: E) variants
+- process function that matches on a Result
+- Uses step kind="match" with case handlers
+"#,
+        source: include_str!("../../../examples/syntax-fundamentals/pattern-matching.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &[],
+        },
+    },
+    Example {
+        name: "syntax-fundamentals/pure-functions",
+        description: r#"Three pure math functions (no effects section):
+1. add: Takes two Int parameters (a, b), returns their sum
+2. factorial: Takes Int n, returns factorial using recursion with if/else for n <= 1 base case
+3. double: Takes Int x, returns x multiplied by 2
+"#,
+        source: include_str!("../../../examples/syntax-fundamentals/pure-functions.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &[],
+        },
+    },
+    Example {
+        name: "syntax-fundamentals/regex",
+        description: r#"Regex operations:
+- regex_replace and regex_replace_all via std.text extern calls
+- Pure functions that clean up strings with patterns
+"#,
+        source: include_str!("../../../examples/syntax-fundamentals/regex.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console"],
+        },
+    },
+    Example {
+        name: "syntax-fundamentals/text-operations",
+        description: r#"Text operations:
+- String functions (upper, lower, trim, concat, contains) as extern calls
+- Pure functions, no effects section
+"#,
+        source: include_str!("../../../examples/syntax-fundamentals/text-operations.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console"],
+        },
+    },
+    Example {
+        name: "syntax-fundamentals/decimal",
+        description: r#"Decimal and BigInt:
+- Decimal literals and exact dec_ ops that keep their places
+- dec_div and dec_round to a fixed number of places
+- big_ ops on integers past the Int range
+"#,
+        source: include_str!("../../../examples/syntax-fundamentals/decimal.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["console"],
+        },
+    },
+    Example {
+        name: "using-bindings/using-bindings",
+        description: r#"Using external bindings:
+- Function that uses the http.get extern binding
+- Makes an HTTP request and parses the response
+- Demonstrates calling external tools
+"#,
+        source: include_str!("../../../examples/using-bindings/using-bindings.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["filesystem", "network"],
+        },
+    },
+    Example {
+        name: "workflows/order-fulfillment",
+        description: r#"Durable order fulfillment:
+- A kind="workflow" snippet that reserves stock, charges a card, and creates a shipping label
+- Extern stubs for the inventory, payment, and shipping services
+- Effectful calls made in a fixed order so a crashed run can resume from its checkpoints
+"#,
+        source: include_str!("../../../examples/workflows/order-fulfillment.cov"),
+        expected: Expected {
+            checks: true,
+            compiles: true,
+            effects: &["database", "network"],
+        },
+    },
+];
@@ -0,0 +1,229 @@
+//! Model evaluation records
+//!
+//! A model is evaluated by generating a program for each example's
+//! description and validating the result. Reports serialize to JSON so
+//! runs can be stored and compared later.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{validate_source, Example};
+
+/// Outcome for a single example
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvalRecord {
+    pub example: String,
+    /// The model returned a response
+    pub generated: bool,
+    pub parsed: bool,
+    pub checked: bool,
+    pub compiled: bool,
+    /// Declared effects match the example's
+    pub effects_match: bool,
+    pub error: Option<String>,
+    /// Generated program text
+    pub code: Option<String>,
+}
+
+impl EvalRecord {
+    /// Validate a generated program against `example`
+    pub fn from_generated(example: &Example, code: &str) -> Self {
+        let validation = validate_source(code);
+        Self {
+            example: example.name.to_string(),
+            generated: true,
+            parsed: validation.parsed,
+            checked: validation.checked,
+            compiled: validation.compiled,
+            effects_match: validation.parsed && validation.effects == example.expected.effects,
+            error: validation.error,
+            code: Some(code.to_string()),
+        }
+    }
+
+    /// Record a failed generation request
+    pub fn generation_failed(example: &Example, error: impl Into<String>) -> Self {
+        Self {
+            example: example.name.to_string(),
+            error: Some(error.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the record passed `stage`
+    pub fn passed(&self, stage: Stage) -> bool {
+        match stage {
+            Stage::Generated => self.generated,
+            Stage::Parsed => self.parsed,
+            Stage::Checked => self.checked,
+            Stage::Compiled => self.compiled,
+            Stage::EffectsMatch => self.effects_match,
+        }
+    }
+}
+
+/// Pipeline stages counted in an evaluation summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Generated,
+    Parsed,
+    Checked,
+    Compiled,
+    EffectsMatch,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::Generated,
+        Stage::Parsed,
+        Stage::Checked,
+        Stage::Compiled,
+        Stage::EffectsMatch,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Generated => "generated",
+            Stage::Parsed => "parsed",
+            Stage::Checked => "checked",
+            Stage::Compiled => "compiled",
+            Stage::EffectsMatch => "effects_match",
+        }
+    }
+}
+
+/// Per-stage pass counts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvalSummary {
+    pub total: usize,
+    pub generated: usize,
+    pub parsed: usize,
+    pub checked: usize,
+    pub compiled: usize,
+    pub effects_match: usize,
+}
+
+impl EvalSummary {
+    pub fn count(&self, stage: Stage) -> usize {
+        match stage {
+            Stage::Generated => self.generated,
+            Stage::Parsed => self.parsed,
+            Stage::Checked => self.checked,
+            Stage::Compiled => self.compiled,
+            Stage::EffectsMatch => self.effects_match,
+        }
+    }
+
+    /// Pass rate for `stage` as a percentage of all examples
+    pub fn rate(&self, stage: Stage) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.count(stage) as f64 / self.total as f64 * 100.0
+        }
+    }
+}
+
+/// A full evaluation run for one model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub provider: String,
+    pub model: String,
+    pub summary: EvalSummary,
+    pub results: Vec<EvalRecord>,
+}
+
+impl EvalReport {
+    pub fn new(provider: impl Into<String>, model: impl Into<String>, results: Vec<EvalRecord>) -> Self {
+        let mut summary = EvalSummary { total: results.len(), ..EvalSummary::default() };
+        for record in &results {
+            summary.generated += record.generated as usize;
+            summary.parsed += record.parsed as usize;
+            summary.checked += record.checked as usize;
+            summary.compiled += record.compiled as usize;
+            summary.effects_match += record.effects_match as usize;
+        }
+        Self {
+            provider: provider.into(),
+            model: model.into(),
+            summary,
+            results,
+        }
+    }
+
+    fn record(&self, example: &str) -> Option<&EvalRecord> {
+        self.results.iter().find(|r| r.example == example)
+    }
+}
+
+/// Difference between two evaluation runs
+#[derive(Debug, Clone, Default)]
+pub struct EvalComparison {
+    /// (stage, baseline rate, candidate rate) for every stage
+    pub rates: Vec<(Stage, f64, f64)>,
+    /// Examples that type check in the baseline but not in the candidate
+    pub regressions: Vec<String>,
+    /// Examples that type check in the candidate but not in the baseline
+    pub improvements: Vec<String>,
+}
+
+/// Compare a candidate run against a baseline
+///
+/// Only examples present in both runs count towards regressions and
+/// improvements; rates are taken from each run's own summary.
+pub fn compare_reports(baseline: &EvalReport, candidate: &EvalReport) -> EvalComparison {
+    let mut comparison = EvalComparison {
+        rates: Stage::ALL.iter()
+            .map(|&stage| (stage, baseline.summary.rate(stage), candidate.summary.rate(stage)))
+            .collect(),
+        ..EvalComparison::default()
+    };
+
+    for base in &baseline.results {
+        let Some(cand) = candidate.record(&base.example) else { continue };
+        match (base.checked, cand.checked) {
+            (true, false) => comparison.regressions.push(base.example.clone()),
+            (false, true) => comparison.improvements.push(base.example.clone()),
+            _ => {}
+        }
+    }
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_solutions_pass_every_stage() {
+        let example = crate::get("syntax-fundamentals/hello-world").unwrap();
+        let record = EvalRecord::from_generated(example, example.source);
+        assert!(Stage::ALL.iter().all(|&stage| record.passed(stage)), "{:?}", record.error);
+
+        let failed = EvalRecord::generation_failed(example, "HTTP error");
+        let report = EvalReport::new("anthropic", "model-a", vec![record, failed]);
+        assert_eq!(report.summary.total, 2);
+        assert_eq!(report.summary.rate(Stage::Checked), 50.0);
+    }
+
+    #[test]
+    fn test_compare_reports() {
+        let hello = crate::get("syntax-fundamentals/hello-world").unwrap();
+        let pure = crate::get("syntax-fundamentals/pure-functions").unwrap();
+
+        let baseline = EvalReport::new("openai", "a", vec![
+            EvalRecord::from_generated(hello, hello.source),
+            EvalRecord::from_generated(pure, "not covenant"),
+        ]);
+        let candidate = EvalReport::new("openai", "b", vec![
+            EvalRecord::generation_failed(hello, "timeout"),
+            EvalRecord::from_generated(pure, pure.source),
+        ]);
+
+        let comparison = compare_reports(&baseline, &candidate);
+        assert_eq!(comparison.regressions, vec![hello.name.to_string()]);
+        assert_eq!(comparison.improvements, vec![pure.name.to_string()]);
+
+        let (_, base_rate, cand_rate) = comparison.rates[0];
+        assert_eq!((base_rate, cand_rate), (100.0, 50.0));
+    }
+}
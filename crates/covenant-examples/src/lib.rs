@@ -0,0 +1,56 @@
+//! Covenant Example Corpus
+//!
+//! The programs under `examples/`, embedded at build time together with a
+//! natural-language description of each and the properties it is expected
+//! to have. The AI generation tests prompt models with the descriptions;
+//! `covenant validate-examples` and external evaluation harnesses use
+//! [`validate`] to run programs through parse, check, and compile, and
+//! `covenant eval-model` records model runs as [`EvalReport`]s.
+
+mod corpus;
+mod eval;
+mod validate;
+
+pub use eval::*;
+pub use validate::*;
+
+/// An example program with its description and expected properties
+#[derive(Debug)]
+pub struct Example {
+    /// Stable identifier: the path under `examples/` without extension
+    pub name: &'static str,
+    /// What the program does, phrased as a generation prompt
+    pub description: &'static str,
+    /// Program text
+    pub source: &'static str,
+    pub expected: Expected,
+}
+
+/// Properties an example (or a program generated from its description)
+/// is expected to have
+#[derive(Debug, Clone, Copy)]
+pub struct Expected {
+    /// Passes the type checker
+    pub checks: bool,
+    /// Compiles to WASM
+    pub compiles: bool,
+    /// Effects declared across the file, sorted
+    pub effects: &'static [&'static str],
+}
+
+impl Example {
+    /// Path of the example relative to the repository root
+    pub fn path(&self) -> String {
+        format!("examples/{}.cov", self.name)
+    }
+}
+
+/// Every example in the corpus
+pub fn all() -> &'static [Example] {
+    corpus::EXAMPLES
+}
+
+/// Look up an example by name
+pub fn get(name: &str) -> Option<&'static Example> {
+    all().iter().find(|example| example.name == name)
+}
@@ -0,0 +1,147 @@
+//! Run programs through the compiler pipeline
+
+use serde::Serialize;
+
+use covenant_ast::{Program, Section};
+use covenant_checker::{check, check_effects};
+use covenant_codegen::{compile_with_symbols, MemoryLimits, WasmFeatures};
+use covenant_symbols::{build_symbol_graph, SymbolGraph};
+
+use crate::{Example, Expected};
+
+/// How far a program got through parse → check → compile
+///
+/// Later stages only run when earlier ones succeed, so `compiled` implies
+/// `checked` implies `parsed`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Validation {
+    pub parsed: bool,
+    pub checked: bool,
+    pub compiled: bool,
+    /// Effects declared across the program, sorted
+    pub effects: Vec<String>,
+    /// Error from the first failing stage
+    pub error: Option<String>,
+}
+
+impl Validation {
+    /// Differences between this result and `expected`, empty if it matches
+    pub fn mismatches(&self, expected: &Expected) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        if !self.parsed {
+            mismatches.push("does not parse".to_string());
+        }
+        if self.checked != expected.checks {
+            mismatches.push(format!("expected checks={}, got {}", expected.checks, self.checked));
+        }
+        if self.compiled != expected.compiles {
+            mismatches.push(format!("expected compiles={}, got {}", expected.compiles, self.compiled));
+        }
+        if self.parsed && self.effects != expected.effects {
+            mismatches.push(format!("expected effects {:?}, got {:?}", expected.effects, self.effects));
+        }
+        mismatches
+    }
+}
+
+/// Validate an example from the corpus
+pub fn validate(example: &Example) -> Validation {
+    validate_source(example.source)
+}
+
+/// Validate arbitrary program text, e.g. a model's output for a description
+pub fn validate_source(source: &str) -> Validation {
+    let mut result = Validation::default();
+
+    let program = match covenant_parser::parse(source) {
+        Ok(program) => program,
+        Err(e) => {
+            result.error = Some(format!("parse: {}", e));
+            return result;
+        }
+    };
+    result.parsed = true;
+    result.effects = declared_effects(&program);
+
+    let checked = match check(&program) {
+        Ok(checked) => checked,
+        Err(errors) => {
+            let messages: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+            result.error = Some(format!("check: {}", messages.join("; ")));
+            return result;
+        }
+    };
+    result.checked = true;
+
+    // Mirror `covenant compile`: symbol graph errors don't block codegen
+    let graph = build_symbol_graph(&program)
+        .map(|r| r.graph)
+        .unwrap_or_else(|_| SymbolGraph::new());
+    let effects = check_effects(&graph);
+
+    match compile_with_symbols(&program, &checked.symbols, &graph, &effects, WasmFeatures::none(), MemoryLimits::default()) {
+        Ok(_) => result.compiled = true,
+        Err(e) => result.error = Some(format!("compile: {}", e)),
+    }
+    result
+}
+
+fn declared_effects(program: &Program) -> Vec<String> {
+    let Program::Snippets { snippets, .. } = program else {
+        return Vec::new();
+    };
+
+    let mut effects: Vec<String> = snippets.iter()
+        .flat_map(|snippet| &snippet.sections)
+        .filter_map(|section| match section {
+            Section::Effects(effects) => Some(effects.effects.iter().map(|e| e.name.clone())),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    effects.sort();
+    effects.dedup();
+    effects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_examples_match_expectations() {
+        for example in crate::all() {
+            let result = validate(example);
+            let mismatches = result.mismatches(&example.expected);
+            assert!(mismatches.is_empty(), "{}: {:?} ({:?})", example.name, mismatches, result.error);
+        }
+    }
+
+    #[test]
+    fn test_corpus_covers_examples_directory() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+        let mut on_disk = Vec::new();
+        for dir in std::fs::read_dir(&root).unwrap().filter_map(|e| e.ok()) {
+            let Ok(files) = std::fs::read_dir(dir.path()) else { continue };
+            for file in files.filter_map(|e| e.ok()).map(|e| e.path()) {
+                if file.extension().is_some_and(|ext| ext == "cov") {
+                    let name = file.strip_prefix(&root).unwrap().with_extension("");
+                    on_disk.push(name.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+        on_disk.sort();
+
+        let mut corpus: Vec<_> = crate::all().iter().map(|e| e.name.to_string()).collect();
+        corpus.sort();
+        assert_eq!(corpus, on_disk);
+    }
+
+    #[test]
+    fn test_generated_code_failure_is_reported() {
+        let result = validate_source("snippet id=\"broken\" kind=\"fn\"");
+        assert!(!result.parsed);
+        assert!(result.error.unwrap().starts_with("parse:"));
+    }
+}
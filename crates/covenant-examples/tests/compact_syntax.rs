@@ -0,0 +1,41 @@
+//! Round-trip tests for the compact snippet syntax over the example corpus
+
+use covenant_ast::printer::{to_cov, to_cov_compact};
+use covenant_parser::{parse, parse_compact};
+
+#[test]
+fn test_compact_round_trip_matches_canonical() {
+    let mut checked = 0;
+    for example in covenant_examples::all() {
+        let Ok(program) = parse(example.source) else { continue };
+        let canonical = to_cov(&program);
+        // Compare against the canonical text re-parsed the same way, so
+        // printer quirks shared by both paths don't count as differences
+        let Ok(reparsed) = parse(&canonical) else { continue };
+
+        let compact = to_cov_compact(&program);
+        let expanded = parse_compact(&compact)
+            .unwrap_or_else(|e| panic!("{}: compact output does not parse: {:?}\n{}", example.name, e, compact));
+        assert_eq!(to_cov(&expanded), to_cov(&reparsed), "{}: compact round-trip changed the AST", example.name);
+        checked += 1;
+    }
+    assert!(checked > 0, "no snippet examples were round-tripped");
+}
+
+#[test]
+fn test_compact_is_shorter_than_canonical() {
+    for example in covenant_examples::all() {
+        let Ok(program) = parse(example.source) else { continue };
+        assert!(
+            to_cov_compact(&program).len() < to_cov(&program).len(),
+            "{}: compact output is not shorter",
+            example.name
+        );
+    }
+}
+
+#[test]
+fn test_compact_syntax_needs_compact_mode() {
+    let program = parse(covenant_examples::all()[0].source).unwrap();
+    assert!(parse(&to_cov_compact(&program)).is_err());
+}
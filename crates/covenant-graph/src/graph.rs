@@ -1,7 +1,9 @@
 //! Graph building utilities
 
+use std::collections::{HashMap, HashSet};
+
 use covenant_ast::*;
-use covenant_checker::SymbolTable;
+use covenant_checker::{SymbolKind, SymbolTable};
 use crate::ReferenceGraph;
 
 /// Visitor that builds the reference graph from an AST
@@ -27,13 +29,91 @@ impl<'a> GraphBuilder<'a> {
                     self.visit_declaration(decl);
                 }
             }
-            Program::Snippets { .. } => {
-                // TODO: Build graph from snippet-mode programs
+            Program::Snippets { snippets, .. } => {
+                self.visit_snippets(snippets);
             }
         }
         self.graph
     }
 
+    /// Record the calls each function snippet's body makes
+    ///
+    /// A call may name a snippet by ID or by its function's short name, and
+    /// the checker defines a symbol for each; edges always join the
+    /// snippet-ID symbols, so both spellings give the same edge.
+    fn visit_snippets(&mut self, snippets: &[Snippet]) {
+        let mut canonical: HashMap<&str, &str> = HashMap::new();
+        for snippet in snippets {
+            if let Some(name) = function_name(snippet) {
+                canonical.insert(name, &snippet.id);
+            }
+        }
+        for snippet in snippets {
+            canonical.insert(&snippet.id, &snippet.id);
+        }
+
+        for snippet in snippets {
+            let Some(caller) = self.symbols.lookup(&snippet.id) else { continue };
+            if !matches!(caller.kind, SymbolKind::Function { .. }) {
+                continue;
+            }
+            self.current_function = Some(caller.id);
+            for section in &snippet.sections {
+                if let Section::Body(body) = section {
+                    self.visit_steps(&body.steps, &canonical);
+                }
+            }
+            self.current_function = None;
+        }
+    }
+
+    /// Record the calls in `steps` and the steps nested in them: call
+    /// steps and their handle blocks, pipeline stages, and `using`
+    /// acquire and release functions
+    ///
+    /// Covenant queries name no functions, so query steps add no edges.
+    fn visit_steps(&mut self, steps: &[Step], canonical: &HashMap<&str, &str>) {
+        let Some(caller) = self.current_function else { return };
+        let mut calls: Vec<(&str, &Step, Span)> = Vec::new();
+        let mut lambdas: HashSet<&str> = HashSet::new();
+
+        // A worklist rather than recursion, so deep nesting cannot overflow
+        let mut pending: Vec<&Step> = steps.iter().collect();
+        while let Some(step) = pending.pop() {
+            match &step.kind {
+                StepKind::Call(call) => calls.push((&call.fn_name, step, step.span)),
+                StepKind::Pipeline(pipeline) => {
+                    calls.extend(pipeline.stages.iter().map(|stage| (stage.call.fn_name.as_str(), step, stage.span)));
+                }
+                StepKind::Using(using) => {
+                    calls.push((&using.acquire.fn_name, step, step.span));
+                    calls.push((&using.release, step, step.span));
+                }
+                StepKind::Lambda(_) => {
+                    lambdas.insert(&step.output_binding);
+                }
+                _ => {}
+            }
+            for nested in step.nested_steps() {
+                pending.extend(nested);
+            }
+        }
+
+        // Sites in source order, whatever order the worklist found them in
+        calls.sort_by_key(|(_, _, span)| span.start);
+        for (name, step, span) in calls {
+            // Calling a lambda bound in this body is not a call to a symbol
+            if lambdas.contains(name) {
+                continue;
+            }
+            let name = canonical.get(name).copied().unwrap_or(name);
+            if let Some(callee) = self.symbols.lookup(name) {
+                let site = CallSite { step: Some(step.id.clone()), span };
+                self.graph.add_call_site(caller, callee.id, site);
+            }
+        }
+    }
+
     fn visit_declaration(&mut self, decl: &Declaration) {
         match &decl.kind {
             DeclarationKind::Function(f) => {
@@ -84,7 +164,8 @@ impl<'a> GraphBuilder<'a> {
                 if let ExprKind::Ident(name) = &callee.kind {
                     if let Some(caller) = self.current_function {
                         if let Some(callee_symbol) = self.symbols.lookup(name) {
-                            self.graph.add_call(caller, callee_symbol.id);
+                            let site = CallSite { step: None, span: expr.span };
+                            self.graph.add_call_site(caller, callee_symbol.id, site);
                         }
                     }
                 }
@@ -169,3 +250,135 @@ impl<'a> GraphBuilder<'a> {
         }
     }
 }
+
+/// The name in a function snippet's signature
+fn function_name(snippet: &Snippet) -> Option<&str> {
+    snippet.sections.iter().find_map(|section| match section {
+        Section::Signature(SignatureSection { kind: SignatureKind::Function(sig), .. }) => Some(sig.name.as_str()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"snippet id="app.Failure" kind="enum"
+signature
+  enum name="Failure"
+    variant name="Bad"
+    end
+  end
+end
+end
+
+snippet id="app.parse" kind="fn"
+signature
+  fn name="parse"
+    param name="s" type="String"
+    returns union
+      type="Int"
+      type="Failure"
+    end
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=1
+    as="_"
+  end
+end
+end
+
+snippet id="app.fallback" kind="fn"
+signature
+  fn name="fallback"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+
+snippet id="app.main" kind="fn"
+signature
+  fn name="main"
+    param name="name" type="String"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="pipeline" from="name"
+    stage fn="text.trim"
+    stage fn="text.lower"
+    as="clean"
+  end
+  step id="s2" kind="call"
+    fn="app.parse"
+    arg name="s" from="clean"
+    as="n"
+    handle
+      case type="Failure"
+        step id="s2a" kind="call"
+          fn="fallback"
+          as="f"
+        end
+        step id="s2b" kind="return"
+          from="f"
+          as="_"
+        end
+      end
+    end
+  end
+  step id="s3" kind="call"
+    fn="app.fallback"
+    as="g"
+  end
+  step id="s4" kind="return"
+    from="g"
+    as="_"
+  end
+end
+end
+"#;
+
+    #[test]
+    fn test_snippet_call_edges() {
+        let program = covenant_parser::parse(SOURCE).unwrap();
+        let result = covenant_checker::check(&program).unwrap();
+        let graph = GraphBuilder::new(&result.symbols).build(&program);
+        let id = |name: &str| result.symbols.lookup(name).unwrap().id;
+        let names = |ids: HashSet<SymbolId>| {
+            let mut names: Vec<String> =
+                ids.into_iter().filter_map(|id| result.symbols.get(id)).map(|s| s.name.clone()).collect();
+            names.sort();
+            names
+        };
+
+        let main = id("app.main");
+        assert_eq!(names(graph.callees_of(main)), vec!["app.fallback", "app.parse", "text.lower", "text.trim"]);
+        assert_eq!(names(graph.callers_of(id("app.fallback"))), vec!["app.main"]);
+
+        // `fallback` in the handle block and `app.fallback` are one edge
+        let steps: Vec<_> = graph.call_sites(main, id("app.fallback")).iter().map(|s| s.step.clone()).collect();
+        assert_eq!(steps, vec![Some("s2a".to_string()), Some("s3".to_string())]);
+        assert_eq!(graph.call_count(main, id("text.trim")), 1);
+        assert!(graph.callees_of(id("app.parse")).is_empty());
+    }
+
+    #[test]
+    fn test_snippet_call_paths() {
+        let program = covenant_parser::parse(SOURCE).unwrap();
+        let result = covenant_checker::check(&program).unwrap();
+        let graph = GraphBuilder::new(&result.symbols).build(&program);
+        let id = |name: &str| result.symbols.lookup(name).unwrap().id;
+
+        let path = graph.shortest_call_path(id("app.main"), id("app.fallback")).unwrap();
+        assert_eq!(path.symbols, vec![id("app.main"), id("app.fallback")]);
+        assert!(graph.call_paths(id("app.parse"), id("app.fallback"), None).is_empty());
+    }
+}
@@ -4,13 +4,15 @@
 //! a query interface for navigating the codebase.
 
 mod graph;
+mod paths;
 mod query;
 
 pub use graph::*;
+pub use paths::*;
 pub use query::*;
 
 use std::collections::{HashMap, HashSet};
-use covenant_ast::{SymbolId, EffectId, AstMetadata};
+use covenant_ast::{SymbolId, EffectId, AstMetadata, CallEdge, CallSite, Program};
 use covenant_checker::{SymbolTable, EffectTable};
 
 /// The complete reference graph for a program
@@ -20,6 +22,8 @@ pub struct ReferenceGraph {
     pub calls: HashMap<SymbolId, HashSet<SymbolId>>,
     /// Backward edges: who calls this symbol?
     pub called_by: HashMap<SymbolId, HashSet<SymbolId>>,
+    /// Where each (caller, callee) call happens, in source order
+    pub call_sites: HashMap<(SymbolId, SymbolId), Vec<CallSite>>,
     /// Forward type refs: what types does this symbol reference?
     pub references: HashMap<SymbolId, HashSet<SymbolId>>,
     /// Backward type refs: what references this type?
@@ -39,12 +43,42 @@ impl ReferenceGraph {
         self.called_by.entry(callee).or_default().insert(caller);
     }
 
+    /// Add a call edge, recording where the call happens
+    pub fn add_call_site(&mut self, caller: SymbolId, callee: SymbolId, site: CallSite) {
+        self.add_call(caller, callee);
+        self.call_sites.entry((caller, callee)).or_default().push(site);
+    }
+
+    /// Where `caller` calls `callee`; empty for edges added without a site
+    pub fn call_sites(&self, caller: SymbolId, callee: SymbolId) -> &[CallSite] {
+        self.call_sites.get(&(caller, callee)).map_or(&[], Vec::as_slice)
+    }
+
+    /// How many times `caller` calls `callee`, counting an edge added
+    /// without a site as one call
+    pub fn call_count(&self, caller: SymbolId, callee: SymbolId) -> usize {
+        match self.call_sites(caller, callee).len() {
+            0 if self.calls.get(&caller).is_some_and(|callees| callees.contains(&callee)) => 1,
+            count => count,
+        }
+    }
+
     /// Add a type reference edge
     pub fn add_reference(&mut self, referrer: SymbolId, referenced: SymbolId) {
         self.references.entry(referrer).or_default().insert(referenced);
         self.referenced_by.entry(referenced).or_default().insert(referrer);
     }
 
+    /// Copy per-symbol effects from the checker's effect table
+    pub fn record_effects(&mut self, symbols: &SymbolTable, effects: &EffectTable) {
+        for symbol in symbols.iter() {
+            let effect_set = effects.effects_of(symbol.id);
+            if !effect_set.is_empty() {
+                self.effects.insert(symbol.id, effect_set);
+            }
+        }
+    }
+
     /// Get all symbols that call the given symbol
     pub fn callers_of(&self, symbol: SymbolId) -> HashSet<SymbolId> {
         self.called_by.get(&symbol).cloned().unwrap_or_default()
@@ -71,11 +105,21 @@ impl ReferenceGraph {
         let referenced_by: Vec<SymbolId> = self.referenced_by.get(&symbol).cloned().unwrap_or_default().into_iter().collect();
         let effects: Vec<EffectId> = self.effects.get(&symbol).cloned().unwrap_or_default().into_iter().collect();
         let is_pure = effects.is_empty();
+        let call_sites = calls
+            .iter()
+            .map(|&callee| CallEdge { symbol: callee, sites: self.call_sites(symbol, callee).to_vec() })
+            .collect();
+        let caller_sites = called_by
+            .iter()
+            .map(|&caller| CallEdge { symbol: caller, sites: self.call_sites(caller, symbol).to_vec() })
+            .collect();
 
         AstMetadata {
             id: Some(symbol),
             calls,
             called_by,
+            call_sites,
+            caller_sites,
             references,
             referenced_by,
             effects,
@@ -85,20 +129,44 @@ impl ReferenceGraph {
     }
 }
 
-/// Build a reference graph from the symbol and effect tables
-pub fn build_graph(symbols: &SymbolTable, effects: &EffectTable) -> ReferenceGraph {
-    let mut graph = ReferenceGraph::new();
+/// Build a reference graph for a checked program: its call edges, found by
+/// walking the AST, and the effects from the effect table
+pub fn build_graph(program: &Program, symbols: &SymbolTable, effects: &EffectTable) -> ReferenceGraph {
+    let mut graph = GraphBuilder::new(symbols).build(program);
 
     // Copy effect information
-    for symbol in symbols.iter() {
-        let effect_set = effects.effects_of(symbol.id);
-        if !effect_set.is_empty() {
-            graph.effects.insert(symbol.id, effect_set);
-        }
-    }
-
-    // TODO: Build call graph from AST traversal
-    // For now, the checker would need to record calls during type checking
+    graph.record_effects(symbols, effects);
 
     graph
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use covenant_ast::Span;
+
+    fn site(step: &str, start: usize) -> CallSite {
+        CallSite { step: Some(step.to_string()), span: Span::new(start, start + 4) }
+    }
+
+    #[test]
+    fn test_call_sites_in_metadata() {
+        let (a, b, c) = (SymbolId(0), SymbolId(1), SymbolId(2));
+        let mut graph = ReferenceGraph::new();
+        graph.add_call_site(a, b, site("s1", 10));
+        graph.add_call_site(a, b, site("s4", 40));
+        graph.add_call(c, b);
+
+        assert_eq!(graph.call_count(a, b), 2);
+        assert_eq!(graph.call_count(c, b), 1);
+        assert_eq!(graph.call_count(b, a), 0);
+
+        let metadata = graph.metadata_for(a, false);
+        assert_eq!(metadata.call_sites, vec![CallEdge { symbol: b, sites: vec![site("s1", 10), site("s4", 40)] }]);
+
+        let mut callers = graph.metadata_for(b, false).caller_sites;
+        callers.sort_by_key(|edge| edge.symbol.0);
+        assert_eq!(callers[0].sites.len(), 2);
+        assert_eq!(callers[1], CallEdge { symbol: c, sites: vec![] });
+    }
+}
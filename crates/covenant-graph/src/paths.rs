@@ -0,0 +1,143 @@
+//! Call-path finding between two symbols
+//!
+//! Enumerates simple call paths (no repeated symbols) through the forward
+//! call edges of a `ReferenceGraph`, shortest first, and accumulates the
+//! effects of every symbol along each path.
+
+use std::collections::{HashSet, VecDeque};
+use covenant_ast::{EffectId, SymbolId};
+use crate::ReferenceGraph;
+
+/// A single call path from a source symbol to a target symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallPath {
+    /// Symbols along the path, starting with the source and ending with the target
+    pub symbols: Vec<SymbolId>,
+    /// Union of the effects of every symbol on the path
+    pub effects: HashSet<EffectId>,
+}
+
+impl CallPath {
+    /// Number of call edges in the path
+    pub fn len(&self) -> usize {
+        self.symbols.len().saturating_sub(1)
+    }
+
+    /// Whether the path has no call edges (source == target)
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ReferenceGraph {
+    /// Find call paths from `from` to `to`, shortest first
+    ///
+    /// Paths never visit the same symbol twice, so recursion does not
+    /// produce infinite results. With `limit = Some(k)` only the `k`
+    /// shortest paths are returned; with `None` every simple path is.
+    pub fn call_paths(&self, from: SymbolId, to: SymbolId, limit: Option<usize>) -> Vec<CallPath> {
+        let mut paths = Vec::new();
+        if limit == Some(0) {
+            return paths;
+        }
+
+        let mut queue: VecDeque<Vec<SymbolId>> = VecDeque::new();
+        queue.push_back(vec![from]);
+
+        while let Some(path) = queue.pop_front() {
+            let last = *path.last().expect("paths are never empty");
+
+            if last == to {
+                paths.push(self.to_call_path(path));
+                if limit.is_some_and(|k| paths.len() >= k) {
+                    break;
+                }
+                continue;
+            }
+
+            let mut callees: Vec<SymbolId> = self.callees_of(last).into_iter().collect();
+            callees.sort_by_key(|id| id.0);
+
+            for callee in callees {
+                if path.contains(&callee) {
+                    continue;
+                }
+                let mut next = path.clone();
+                next.push(callee);
+                queue.push_back(next);
+            }
+        }
+
+        paths
+    }
+
+    /// Shortest call path from `from` to `to`, if any
+    pub fn shortest_call_path(&self, from: SymbolId, to: SymbolId) -> Option<CallPath> {
+        self.call_paths(from, to, Some(1)).into_iter().next()
+    }
+
+    fn to_call_path(&self, symbols: Vec<SymbolId>) -> CallPath {
+        let effects = symbols
+            .iter()
+            .filter_map(|id| self.effects.get(id))
+            .flatten()
+            .copied()
+            .collect();
+        CallPath { symbols, effects }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u32) -> SymbolId {
+        SymbolId(n)
+    }
+
+    /// 0 -> 1 -> 3, 0 -> 2 -> 3, 0 -> 3, 3 -> 0 (back edge)
+    fn diamond() -> ReferenceGraph {
+        let mut graph = ReferenceGraph::new();
+        graph.add_call(id(0), id(1));
+        graph.add_call(id(0), id(2));
+        graph.add_call(id(0), id(3));
+        graph.add_call(id(1), id(3));
+        graph.add_call(id(2), id(3));
+        graph.add_call(id(3), id(0));
+        graph.effects.insert(id(2), [EffectId(7)].into_iter().collect());
+        graph.effects.insert(id(3), [EffectId(9)].into_iter().collect());
+        graph
+    }
+
+    #[test]
+    fn test_all_paths_shortest_first() {
+        let paths = diamond().call_paths(id(0), id(3), None);
+        let shapes: Vec<Vec<u32>> = paths
+            .iter()
+            .map(|p| p.symbols.iter().map(|s| s.0).collect())
+            .collect();
+
+        assert_eq!(shapes, vec![vec![0, 3], vec![0, 1, 3], vec![0, 2, 3]]);
+    }
+
+    #[test]
+    fn test_limit_and_effects() {
+        let graph = diamond();
+        let paths = graph.call_paths(id(0), id(3), Some(2));
+        assert_eq!(paths.len(), 2);
+
+        let through_two = graph.call_paths(id(0), id(3), None).pop().unwrap();
+        assert_eq!(through_two.len(), 2);
+        assert!(through_two.effects.contains(&EffectId(7)));
+        assert!(through_two.effects.contains(&EffectId(9)));
+    }
+
+    #[test]
+    fn test_no_path() {
+        let graph = diamond();
+        assert!(graph.shortest_call_path(id(1), id(4)).is_none());
+
+        let around = graph.shortest_call_path(id(1), id(2)).unwrap();
+        assert_eq!(around.symbols, vec![id(1), id(3), id(0), id(2)]);
+    }
+}
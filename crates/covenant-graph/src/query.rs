@@ -1,5 +1,8 @@
 //! Query interface for the reference graph
 
+use std::collections::BTreeMap;
+
+use covenant_ast::{CallSite, CancellationToken, Cancelled};
 use covenant_checker::{SymbolTable, Symbol, SymbolKind};
 use crate::ReferenceGraph;
 use serde::{Deserialize, Serialize};
@@ -60,6 +63,12 @@ pub struct SymbolInfo {
     pub effects: Vec<String>,
     pub calls: Vec<String>,
     pub called_by: Vec<String>,
+    /// Where this symbol calls each of `calls`, by callee name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub call_sites: BTreeMap<String, Vec<CallSite>>,
+    /// Where each of `called_by` calls this symbol, by caller name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub caller_sites: BTreeMap<String, Vec<CallSite>>,
     pub is_pure: bool,
 }
 
@@ -69,9 +78,35 @@ pub fn execute_query(
     symbols: &SymbolTable,
     graph: &ReferenceGraph,
 ) -> QueryResult {
+    match run_query(query, symbols, graph, None) {
+        Ok(result) => result,
+        Err(Cancelled) => unreachable!("query without a token cannot be cancelled"),
+    }
+}
+
+/// Execute a query, stopping early if `token` is cancelled
+pub fn execute_query_with_cancel(
+    query: &Query,
+    symbols: &SymbolTable,
+    graph: &ReferenceGraph,
+    token: &CancellationToken,
+) -> Result<QueryResult, Cancelled> {
+    run_query(query, symbols, graph, Some(token))
+}
+
+fn run_query(
+    query: &Query,
+    symbols: &SymbolTable,
+    graph: &ReferenceGraph,
+    cancel: Option<&CancellationToken>,
+) -> Result<QueryResult, Cancelled> {
     let mut results = Vec::new();
 
     for symbol in symbols.iter() {
+        if let Some(token) = cancel {
+            token.check()?;
+        }
+
         // Filter by table
         let matches_table = match query.from {
             Table::Functions => matches!(symbol.kind, SymbolKind::Function { .. }),
@@ -96,7 +131,7 @@ pub fn execute_query(
         }
     }
 
-    QueryResult { symbols: results }
+    Ok(QueryResult { symbols: results })
 }
 
 fn evaluate_filter(
@@ -158,17 +193,25 @@ fn symbol_to_info(symbol: &Symbol, symbols: &SymbolTable, graph: &ReferenceGraph
         vec![]
     };
 
-    let calls: Vec<String> = graph
-        .callees_of(symbol.id)
-        .iter()
-        .filter_map(|&id| symbols.get(id).map(|s| s.name.clone()))
-        .collect();
+    let mut calls = Vec::new();
+    let mut call_sites = BTreeMap::new();
+    for callee in graph.callees_of(symbol.id).into_iter().filter_map(|id| symbols.get(id)) {
+        let sites = graph.call_sites(symbol.id, callee.id);
+        if !sites.is_empty() {
+            call_sites.insert(callee.name.clone(), sites.to_vec());
+        }
+        calls.push(callee.name.clone());
+    }
 
-    let called_by: Vec<String> = graph
-        .callers_of(symbol.id)
-        .iter()
-        .filter_map(|&id| symbols.get(id).map(|s| s.name.clone()))
-        .collect();
+    let mut called_by = Vec::new();
+    let mut caller_sites = BTreeMap::new();
+    for caller in graph.callers_of(symbol.id).into_iter().filter_map(|id| symbols.get(id)) {
+        let sites = graph.call_sites(caller.id, symbol.id);
+        if !sites.is_empty() {
+            caller_sites.insert(caller.name.clone(), sites.to_vec());
+        }
+        called_by.push(caller.name.clone());
+    }
 
     let is_pure = graph.effects.get(&symbol.id).map(|e| e.is_empty()).unwrap_or(true);
 
@@ -179,6 +222,8 @@ fn symbol_to_info(symbol: &Symbol, symbols: &SymbolTable, graph: &ReferenceGraph
         effects,
         calls,
         called_by,
+        call_sites,
+        caller_sites,
         is_pure,
     }
 }
@@ -252,3 +297,101 @@ fn extract_quoted_string(s: &str) -> Option<String> {
     let end = s[start + 1..].find('\'')?;
     Some(s[start + 1..start + 1 + end].to_string())
 }
+
+/// A call path query between two symbols
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathsQuery {
+    pub from: String,
+    pub to: String,
+    /// Most calls a path may take
+    pub max_depth: Option<usize>,
+    /// Only the shortest path
+    pub shortest: bool,
+}
+
+/// Parse `paths from X to Y [max depth N]` or `shortest path from X to Y`
+///
+/// Symbol names keep their case and may be quoted with `'`.
+pub fn parse_paths_query(input: &str) -> Option<PathsQuery> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let keyword = |i: usize, expected: &str| words.get(i).is_some_and(|w| w.eq_ignore_ascii_case(expected));
+    let name = |i: usize| words.get(i).map(|w| w.trim_matches('\'').to_string()).filter(|w| !w.is_empty());
+
+    let (shortest, rest) = if keyword(0, "paths") {
+        (false, 1)
+    } else if keyword(0, "shortest") && keyword(1, "path") {
+        (true, 2)
+    } else {
+        return None;
+    };
+    if !keyword(rest, "from") || !keyword(rest + 2, "to") {
+        return None;
+    }
+    let (from, to) = (name(rest + 1)?, name(rest + 3)?);
+
+    let tail = rest + 4;
+    let max_depth = match words.len() - tail {
+        0 => None,
+        3 if !shortest && keyword(tail, "max") && keyword(tail + 1, "depth") => Some(words[tail + 2].parse().ok()?),
+        _ => return None,
+    };
+    Some(PathsQuery { from, to, max_depth, shortest })
+}
+
+/// A query for the references to a symbol
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferencesQuery {
+    pub to: String,
+    /// Only references of this kind (e.g. "call", "type_reference")
+    pub kind: Option<String>,
+}
+
+/// Parse `references to X [kind K]`
+///
+/// The symbol name keeps its case and may be quoted with `'`.
+pub fn parse_references_query(input: &str) -> Option<ReferencesQuery> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let keyword = |i: usize, expected: &str| words.get(i).is_some_and(|w| w.eq_ignore_ascii_case(expected));
+    if !keyword(0, "references") || !keyword(1, "to") {
+        return None;
+    }
+    let to = words.get(2).map(|w| w.trim_matches('\'').to_string()).filter(|w| !w.is_empty())?;
+    let kind = match words.len() {
+        3 => None,
+        5 if keyword(3, "kind") => Some(words[4].to_ascii_lowercase()),
+        _ => return None,
+    };
+    Some(ReferencesQuery { to, kind })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_paths_query() {
+        let query = parse_paths_query("paths from app.main to 'db.Users' max depth 4").unwrap();
+        assert_eq!(
+            query,
+            PathsQuery { from: "app.main".into(), to: "db.Users".into(), max_depth: Some(4), shortest: false }
+        );
+
+        let query = parse_paths_query("SHORTEST PATH FROM a TO b").unwrap();
+        assert!(query.shortest && query.max_depth.is_none());
+
+        assert!(parse_paths_query("paths from a").is_none());
+        assert!(parse_paths_query("paths from a to b max depth many").is_none());
+        assert!(parse_paths_query("select * from functions").is_none());
+    }
+
+    #[test]
+    fn test_parse_references_query() {
+        let query = parse_references_query("references to 'app.User' kind CALL").unwrap();
+        assert_eq!(query, ReferencesQuery { to: "app.User".into(), kind: Some("call".into()) });
+        assert_eq!(parse_references_query("REFERENCES TO a").unwrap().kind, None);
+
+        assert!(parse_references_query("references to").is_none());
+        assert!(parse_references_query("references to a of kind call").is_none());
+        assert!(parse_references_query("paths from a to b").is_none());
+    }
+}
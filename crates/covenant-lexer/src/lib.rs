@@ -6,8 +6,10 @@
 //! - `!=` is inequality
 
 mod token;
+mod trivia;
 
 pub use token::*;
+pub use trivia::*;
 
 use logos::Logos;
 use covenant_ast::Span;
@@ -230,6 +232,15 @@ string""""#;
         assert_eq!(tokens[0].kind, TokenKind::TripleString);
     }
 
+    #[test]
+    fn test_unterminated_triple_quoted_string() {
+        // Must fail to lex rather than overflow the stack backtracking
+        let source = format!("doc \"\"\"{}", "abc\n".repeat(50));
+        let tokens = tokenize(&source);
+        assert!(tokens.iter().any(|t| t.kind == TokenKind::Error));
+        assert!(tokens.iter().all(|t| t.kind != TokenKind::TripleString));
+    }
+
     // === Number Tests ===
 
     #[test]
@@ -256,6 +267,19 @@ string""""#;
         assert_eq!(tokens[2].text(source), "123.456");
     }
 
+    #[test]
+    fn test_decimal_and_bigint_formats() {
+        let source = "19.99d 5d 123n 1.5 7";
+        let tokens = tokenize(source);
+        assert_eq!(tokens[0].kind, TokenKind::Decimal);
+        assert_eq!(tokens[0].text(source), "19.99d");
+        assert_eq!(tokens[1].kind, TokenKind::Decimal);
+        assert_eq!(tokens[2].kind, TokenKind::BigInt);
+        assert_eq!(tokens[2].text(source), "123n");
+        assert_eq!(tokens[3].kind, TokenKind::Float);
+        assert_eq!(tokens[4].kind, TokenKind::Int);
+    }
+
     #[test]
     fn test_negative_number_is_two_tokens() {
         // Negative numbers are represented as Minus followed by Int
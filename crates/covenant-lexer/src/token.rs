@@ -277,8 +277,16 @@ pub enum TokenKind {
     #[regex(r"[0-9]+\.[0-9]+")]
     Float,
 
+    /// Exact decimal: `19.99d`, `5d`
+    #[regex(r"[0-9]+(\.[0-9]+)?d")]
+    Decimal,
+
+    /// Arbitrary-precision integer: `123n`
+    #[regex(r"[0-9]+n")]
+    BigInt,
+
     // Triple-quoted strings (multi-line) - higher priority
-    #[regex(r#""""([^"]*|"[^"]|""[^"])*""""#, priority = 3)]
+    #[regex(r#""""([^"]|"[^"]|""[^"])*""""#, priority = 3)]
     TripleString,
 
     #[regex(r#""([^"\\]|\\.)*""#)]
@@ -487,6 +495,8 @@ impl TokenKind {
             TokenKind::Question => "'?'",
             TokenKind::Int => "integer",
             TokenKind::Float => "float",
+            TokenKind::Decimal => "decimal",
+            TokenKind::BigInt => "big integer",
             TokenKind::TripleString => "triple-quoted string",
             TokenKind::String => "string",
             TokenKind::Ident => "identifier",
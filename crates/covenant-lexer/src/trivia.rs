@@ -0,0 +1,171 @@
+//! Tokenization that keeps comments and whitespace
+//!
+//! `tokenize` skips trivia. `tokenize_with_trivia` attaches it to the tokens
+//! instead, so tools that rewrite source can put every comment back. A
+//! token's trailing trivia runs to the end of its line; everything after
+//! that belongs to the next token (or to the Eof token at the end).
+
+use covenant_ast::Span;
+
+use crate::{tokenize, Token};
+
+/// What a piece of trivia is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces, tabs and form feeds within a line
+    Whitespace,
+    /// A line break, `\n` or `\r\n`
+    Newline,
+    /// `// ...` up to, but not including, the line break
+    LineComment,
+}
+
+/// A comment or run of whitespace between tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub span: Span,
+}
+
+impl Trivia {
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.span.start..self.span.end]
+    }
+}
+
+/// A token with the trivia around it
+#[derive(Debug, Clone)]
+pub struct TriviaToken {
+    pub token: Token,
+    /// Trivia on the lines before the token
+    pub leading: Vec<Trivia>,
+    /// Trivia after the token, up to the end of its line
+    pub trailing: Vec<Trivia>,
+}
+
+impl TriviaToken {
+    /// Leading trivia, token and trailing trivia, as written
+    pub fn full_text<'a>(&self, source: &'a str) -> &'a str {
+        let start = self.leading.first().map_or(self.token.span.start, |t| t.span.start);
+        let end = self.trailing.last().map_or(self.token.span.end, |t| t.span.end);
+        &source[start..end]
+    }
+}
+
+/// Tokenize a source string, keeping comments and whitespace as trivia.
+/// Concatenating the `full_text` of every token gives back the source.
+pub fn tokenize_with_trivia(source: &str) -> Vec<TriviaToken> {
+    let tokens = tokenize(source);
+    let mut result: Vec<TriviaToken> = Vec::with_capacity(tokens.len());
+    let mut pos = 0;
+
+    for token in tokens {
+        let mut gap = split_trivia(source, pos, token.span.start).into_iter().peekable();
+        if let Some(previous) = result.last_mut() {
+            while let Some(trivia) = gap.next_if(|t| t.kind != TriviaKind::Newline) {
+                previous.trailing.push(trivia);
+            }
+        }
+        pos = token.span.end;
+        result.push(TriviaToken { token, leading: gap.collect(), trailing: Vec::new() });
+    }
+
+    result
+}
+
+/// Split the text between two tokens into trivia
+fn split_trivia(source: &str, start: usize, end: usize) -> Vec<Trivia> {
+    let bytes = source.as_bytes();
+    let mut trivia = Vec::new();
+    let mut pos = start;
+
+    while pos < end {
+        let (kind, len) = match bytes[pos] {
+            b'\n' => (TriviaKind::Newline, 1),
+            b'\r' if bytes.get(pos + 1) == Some(&b'\n') => (TriviaKind::Newline, 2),
+            b'/' => {
+                let len = source[pos..end].find('\n').unwrap_or(end - pos);
+                // A `\r\n` ending belongs to the line break
+                let len = if source[pos..pos + len].ends_with('\r') { len - 1 } else { len };
+                (TriviaKind::LineComment, len)
+            }
+            _ => {
+                let rest = &source[pos..end];
+                let len = rest
+                    .char_indices()
+                    .find(|&(i, c)| c == '\n' || c == '/' || rest[i..].starts_with("\r\n"))
+                    .map_or(rest.len(), |(i, _)| i);
+                (TriviaKind::Whitespace, len)
+            }
+        };
+        trivia.push(Trivia { kind, span: Span::new(pos, pos + len) });
+        pos += len;
+    }
+
+    trivia
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenKind;
+
+    fn kinds(trivia: &[Trivia]) -> Vec<TriviaKind> {
+        trivia.iter().map(|t| t.kind).collect()
+    }
+
+    #[test]
+    fn test_trivia_round_trips_the_source() {
+        let sources = [
+            "",
+            "   \n\t",
+            "// only a comment",
+            "snippet id=\"a.b\" kind=\"fn\" // trailing\n\n  // leading\n  body\r\n  end // last\r\nend\n",
+            "x/y // a / b\n",
+        ];
+        for source in sources {
+            let tokens = tokenize_with_trivia(source);
+            let rebuilt: String = tokens.iter().map(|t| t.full_text(source)).collect();
+            assert_eq!(rebuilt, source);
+        }
+    }
+
+    #[test]
+    fn test_comments_attach_to_their_lines() {
+        let source = "step // about step\n  // about end\n  end\n";
+        let tokens = tokenize_with_trivia(source);
+        assert_eq!(tokens.len(), 3);
+
+        assert_eq!(tokens[0].token.kind, TokenKind::Step);
+        assert_eq!(kinds(&tokens[0].trailing), vec![TriviaKind::Whitespace, TriviaKind::LineComment]);
+        assert_eq!(tokens[0].trailing[1].text(source), "// about step");
+
+        assert_eq!(tokens[1].token.kind, TokenKind::End);
+        assert_eq!(
+            kinds(&tokens[1].leading),
+            vec![TriviaKind::Newline, TriviaKind::Whitespace, TriviaKind::LineComment, TriviaKind::Newline, TriviaKind::Whitespace]
+        );
+        assert_eq!(tokens[1].leading[2].text(source), "// about end");
+        assert!(tokens[1].trailing.is_empty());
+
+        assert_eq!(tokens[2].token.kind, TokenKind::Eof);
+        assert_eq!(kinds(&tokens[2].leading), vec![TriviaKind::Newline]);
+    }
+
+    #[test]
+    fn test_crlf_is_one_newline() {
+        let source = "a // note\r\nb";
+        let tokens = tokenize_with_trivia(source);
+        assert_eq!(tokens[0].trailing[1].text(source), "// note");
+        assert_eq!(kinds(&tokens[1].leading), vec![TriviaKind::Newline]);
+        assert_eq!(tokens[1].leading[0].text(source), "\r\n");
+    }
+
+    #[test]
+    fn test_tokens_match_tokenize() {
+        let source = "let x // comment\n= 5";
+        let plain: Vec<TokenKind> = tokenize(source).iter().map(|t| t.kind).collect();
+        let with_trivia: Vec<TokenKind> = tokenize_with_trivia(source).iter().map(|t| t.token.kind).collect();
+        assert_eq!(plain, with_trivia);
+    }
+}
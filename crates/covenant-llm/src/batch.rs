@@ -0,0 +1,207 @@
+//! Explaining a whole project
+//!
+//! [`ExplainGenerator::explain_batch`] explains many snippets with up to
+//! `jobs` requests in flight; the client retries any the provider turns
+//! away for rate limiting. The caller records each finished explanation
+//! in a [`BatchState`] and saves it, so a batch stopped partway (by a
+//! crash, Ctrl-C or `--max-cost`) resumes where it left off: snippets
+//! explained since their last change are skipped.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+use covenant_ast::Snippet;
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::explain::{ExplainError, ExplainGenerator};
+use crate::types::{Explanation, Verbosity};
+
+/// Where `covenant explain --project` keeps its batch state
+pub const BATCH_STATE_PATH: &str = ".covenant/explain-batch.json";
+
+/// Progress of a batch: its files and the explanations finished so far
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchState {
+    /// Source files the batch covers
+    pub files: Vec<PathBuf>,
+    /// Finished explanations, by snippet ID
+    pub done: BTreeMap<String, Explanation>,
+}
+
+impl BatchState {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        Self { files, done: BTreeMap::new() }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Write the state, replacing the old file only once the new one is
+    /// complete so an interrupted save loses nothing
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, json)?;
+        fs::rename(&partial, path)
+    }
+
+    /// The finished explanation of `snippet`, unless it changed since
+    pub fn get(&self, snippet: &Snippet) -> Option<&Explanation> {
+        let explanation = self.done.get(&snippet.id)?;
+        (explanation.snippet_hash.as_deref() == Some(snippet.content_hash().as_str())).then_some(explanation)
+    }
+}
+
+/// A snippet to explain, with the code to show the LLM for it
+#[derive(Debug, Clone, Copy)]
+pub struct BatchItem<'a> {
+    pub snippet: &'a Snippet,
+    pub code: &'a str,
+}
+
+impl ExplainGenerator {
+    /// Explain `items` with up to `jobs` requests at once, handing each
+    /// result to `on_result` as it finishes
+    ///
+    /// Results come in completion order. Returning [`ControlFlow::Break`]
+    /// stops the batch, abandoning the requests still in flight.
+    pub async fn explain_batch<'a>(
+        &self,
+        items: &[BatchItem<'a>],
+        verbosity: Verbosity,
+        jobs: usize,
+        mut on_result: impl FnMut(BatchItem<'a>, Result<Explanation, ExplainError>) -> ControlFlow<()>,
+    ) {
+        let mut results = stream::iter(items.iter().copied())
+            .map(|item| async move { (item, self.explain(item.snippet, item.code, verbosity).await) })
+            .buffer_unordered(jobs.max(1));
+        while let Some((item, result)) = results.next().await {
+            if on_result(item, result).is_break() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::snippets;
+    use crate::{LlmClient, LlmPolicy, Provider};
+
+    const SOURCE: &str = r#"snippet id="math.one" kind="fn"
+signature
+  fn name="one"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=1
+    as="_"
+  end
+end
+end
+
+snippet id="math.two" kind="fn"
+signature
+  fn name="two"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=2
+    as="_"
+  end
+end
+end
+"#;
+
+    fn explanation(snippet: &Snippet) -> Explanation {
+        serde_json::from_value(serde_json::json!({
+            "snippet_id": snippet.id,
+            "kind": "fn",
+            "summary": "Returns a constant",
+            "generated_at": "2026-01-01T00:00:00Z",
+            "snippet_hash": snippet.content_hash(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_state_roundtrip_and_staleness() {
+        let dir = std::env::temp_dir().join(format!("covenant-batch-{}", std::process::id()));
+        let path = dir.join("explain-batch.json");
+        let snippets = snippets(SOURCE);
+
+        let mut state = BatchState::new(vec![PathBuf::from("math.cov")]);
+        state.done.insert(snippets[0].id.clone(), explanation(&snippets[0]));
+        state.save(&path).unwrap();
+
+        let loaded = BatchState::load(&path).unwrap();
+        assert_eq!(loaded.files, vec![PathBuf::from("math.cov")]);
+        assert!(loaded.get(&snippets[0]).is_some());
+        assert!(loaded.get(&snippets[1]).is_none());
+
+        // An edited snippet is explained again
+        let edited = self::snippets(&SOURCE.replace("lit=1", "lit=10"));
+        assert!(loaded.get(&edited[0]).is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batch_stops_on_break() {
+        let snippets = snippets(SOURCE);
+        let items: Vec<BatchItem> = snippets.iter().map(|snippet| BatchItem { snippet, code: SOURCE }).collect();
+        // Offline clients fail every request without reaching the network
+        let client = LlmClient::with_config(Provider::Anthropic, "key".to_string(), None);
+        let generator = ExplainGenerator::new(LlmClient { policy: LlmPolicy::offline(), ..client });
+
+        let mut seen = Vec::new();
+        generator
+            .explain_batch(&items, Verbosity::Standard, 2, |item, result| {
+                assert!(result.is_err());
+                seen.push(item.snippet.id.clone());
+                ControlFlow::Break(())
+            })
+            .await;
+        assert_eq!(seen.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_batch_releases_budget() {
+        // A server that accepts requests and never answers them
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let snippets = snippets(SOURCE);
+        let items: Vec<BatchItem> = snippets.iter().map(|snippet| BatchItem { snippet, code: SOURCE }).collect();
+        let client = LlmClient::with_config(Provider::Anthropic, "key".to_string(), None)
+            .with_base_url(format!("http://{}", address))
+            .with_max_cost(10.0)
+            .unwrap();
+        let generator = ExplainGenerator::new(client);
+
+        // Stopping the batch drops both requests while they wait on the server
+        let batch = generator.explain_batch(&items, Verbosity::Standard, 2, |_, _| ControlFlow::Continue(()));
+        let stopped = tokio::time::timeout(std::time::Duration::from_millis(200), batch).await;
+        assert!(stopped.is_err());
+        assert_eq!(generator.llm().reserved(), 0.0);
+    }
+}
@@ -145,6 +145,7 @@ mod tests {
             kind: "fn".to_string(),
             summary: "Test function".to_string(),
             detailed_description: None,
+            doc: None,
             parameters: Vec::new(),
             return_value: None,
             effects_summary: None,
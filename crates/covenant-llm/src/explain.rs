@@ -1,7 +1,6 @@
 //! Explanation generator for Covenant snippets
 
 use chrono::Utc;
-use sha2::{Digest, Sha256};
 
 use covenant_ast::{
     BodySection, EffectsSection, FunctionSignature, RequiresSection, ReturnType, Section,
@@ -11,7 +10,7 @@ use covenant_ast::{
 
 use crate::cache::ExplanationCache;
 use crate::prompts::{build_explain_prompt, EXPLAIN_SYSTEM_PROMPT};
-use crate::types::{Explanation, ExplainFormat, Pattern, SnippetMetadata, Verbosity};
+use crate::types::{Explanation, ExplainFormat, Pattern, SnippetMetadata, StepExplanation, Verbosity};
 use crate::{LlmClient, LlmError};
 
 /// Generator for AI explanations of Covenant code
@@ -47,14 +46,22 @@ impl ExplainGenerator {
         }
     }
 
+    /// The client explanations are generated with, for its usage
+    pub fn llm(&self) -> &LlmClient {
+        &self.llm
+    }
+
     /// Generate an explanation for a snippet
+    ///
+    /// `code` is sent to the LLM as is; pass the source through
+    /// [`crate::redact_sensitive`] first so it holds no sensitive literals.
     pub async fn explain(
         &self,
         snippet: &Snippet,
         code: &str,
         verbosity: Verbosity,
     ) -> Result<Explanation, ExplainError> {
-        let content_hash = Self::hash_content(code);
+        let content_hash = snippet.content_hash();
 
         // Check cache first
         if let Some(ref cache) = self.cache {
@@ -81,6 +88,9 @@ impl ExplainGenerator {
         // Parse response as JSON
         let mut explanation: Explanation = self.parse_response(&response, &meta)?;
 
+        // Author-written docs take precedence over generated descriptions
+        apply_docs(&mut explanation, &meta);
+
         // Fill in metadata fields
         explanation.snippet_id = snippet.id.clone();
         explanation.kind = snippet_kind_str(snippet.kind);
@@ -108,6 +118,9 @@ impl ExplainGenerator {
             step_kinds: Vec::new(),
             requirements: Vec::new(),
             test_count: 0,
+            doc: snippet.doc.as_ref().map(|d| d.content.clone()),
+            param_docs: Vec::new(),
+            step_docs: Vec::new(),
         };
 
         for section in &snippet.sections {
@@ -125,11 +138,16 @@ impl ExplainGenerator {
                             .map(|p| (p.name.clone(), type_to_string(&p.ty)))
                             .collect();
                         meta.return_type = returns.as_ref().map(return_type_to_string);
+                        meta.param_docs = params
+                            .iter()
+                            .filter_map(|p| Some((p.name.clone(), p.doc.as_ref()?.content.clone())))
+                            .collect();
                     }
                 }
                 Section::Body(BodySection { steps, .. }) => {
                     meta.step_count = steps.len();
                     meta.step_kinds = steps.iter().map(|s| step_kind_str(&s.kind)).collect();
+                    meta.step_docs = step_docs(steps);
                 }
                 Section::Requires(RequiresSection { requirements, .. }) => {
                     meta.requirements = requirements
@@ -241,6 +259,7 @@ impl ExplainGenerator {
                         detailed_description: partial["detailed_description"]
                             .as_str()
                             .map(String::from),
+                        doc: None,
                         parameters: Vec::new(),
                         return_value: None,
                         effects_summary: partial["effects_summary"].as_str().map(String::from),
@@ -265,13 +284,6 @@ impl ExplainGenerator {
             }
         }
     }
-
-    /// Compute content hash for caching
-    fn hash_content(content: &str) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(content.as_bytes());
-        format!("{:x}", hasher.finalize())
-    }
 }
 
 /// Format explanation for output
@@ -292,6 +304,10 @@ fn format_as_text(exp: &Explanation) -> String {
 
     out.push_str(&format!("{}\n\n", exp.summary));
 
+    if let Some(ref doc) = exp.doc {
+        out.push_str(&format!("{}\n\n", doc));
+    }
+
     if let Some(ref desc) = exp.detailed_description {
         out.push_str(&format!("{}\n\n", desc));
     }
@@ -329,6 +345,11 @@ fn format_as_markdown(exp: &Explanation) -> String {
     out.push_str(&format!("**Kind:** {}\n\n", exp.kind));
     out.push_str(&format!("{}\n\n", exp.summary));
 
+    if let Some(ref doc) = exp.doc {
+        out.push_str("## Documentation\n\n");
+        out.push_str(&format!("{}\n\n", doc));
+    }
+
     if let Some(ref desc) = exp.detailed_description {
         out.push_str("## Description\n\n");
         out.push_str(&format!("{}\n\n", desc));
@@ -364,9 +385,49 @@ fn format_as_compact(exp: &Explanation) -> String {
 
 // Helper functions
 
+/// Step docs by step ID in body order, nested steps after their parent
+fn step_docs(steps: &[Step]) -> Vec<(String, String)> {
+    let mut docs = Vec::new();
+    for step in steps {
+        if let Some(doc) = &step.doc {
+            docs.push((step.id.clone(), doc.content.clone()));
+        }
+        for nested in step.nested_steps() {
+            docs.extend(step_docs(nested));
+        }
+    }
+    docs
+}
+
+/// Put author-written docs into an explanation, replacing generated
+/// descriptions of documented params and steps
+fn apply_docs(exp: &mut Explanation, meta: &SnippetMetadata) {
+    exp.doc = meta.doc.clone();
+
+    for (name, doc) in &meta.param_docs {
+        if let Some(param) = exp.parameters.iter_mut().find(|p| &p.name == name) {
+            param.description = doc.clone();
+        }
+    }
+
+    for (step_id, doc) in &meta.step_docs {
+        match exp.step_explanations.iter_mut().find(|s| &s.step_id == step_id) {
+            Some(step) => step.what = doc.clone(),
+            None => exp.step_explanations.push(StepExplanation {
+                step_id: step_id.clone(),
+                what: doc.clone(),
+                why: None,
+                produces: None,
+                data_flow: None,
+            }),
+        }
+    }
+}
+
 fn snippet_kind_str(kind: SnippetKind) -> String {
     match kind {
         SnippetKind::Function => "fn".to_string(),
+        SnippetKind::Workflow => "workflow".to_string(),
         SnippetKind::Struct => "struct".to_string(),
         SnippetKind::Enum => "enum".to_string(),
         SnippetKind::Module => "module".to_string(),
@@ -376,6 +437,10 @@ fn snippet_kind_str(kind: SnippetKind) -> String {
         SnippetKind::ExternImpl => "extern-impl".to_string(),
         SnippetKind::Test => "test".to_string(),
         SnippetKind::Data => "data".to_string(),
+        SnippetKind::Subscriber => "subscriber".to_string(),
+        SnippetKind::Const => "const".to_string(),
+        SnippetKind::Interface => "interface".to_string(),
+        SnippetKind::Alias => "alias".to_string(),
     }
 }
 
@@ -383,12 +448,19 @@ fn step_kind_str(kind: &StepKind) -> String {
     match kind {
         StepKind::Compute(_) => "compute".to_string(),
         StepKind::Call(_) => "call".to_string(),
+        StepKind::Pipeline(_) => "pipeline".to_string(),
         StepKind::Query(_) => "query".to_string(),
         StepKind::Bind(_) => "bind".to_string(),
         StepKind::Return(_) => "return".to_string(),
+        StepKind::Raise(_) => "raise".to_string(),
         StepKind::If(_) => "if".to_string(),
         StepKind::Match(_) => "match".to_string(),
         StepKind::For(_) => "for".to_string(),
+        StepKind::Break(_) => "break".to_string(),
+        StepKind::Continue(_) => "continue".to_string(),
+        StepKind::Lambda(_) => "lambda".to_string(),
+        StepKind::Using(_) => "using".to_string(),
+        StepKind::Assert(_) => "assert".to_string(),
         StepKind::Insert(_) => "insert".to_string(),
         StepKind::Update(_) => "update".to_string(),
         StepKind::Delete(_) => "delete".to_string(),
@@ -2,20 +2,46 @@
 //!
 //! This crate provides:
 //! - LLM client for OpenAI and Anthropic APIs
+//! - Project policy limiting which providers and endpoints it may call
+//! - Token accounting, cost estimates and a spending cap
 //! - Explanation generation from Covenant AST
 //! - Caching layer for explanations
+//! - Concurrent, resumable batches of explanations for whole projects
 
+pub mod batch;
 mod cache;
 pub mod explain;
+mod policy;
 mod prompts;
+mod redact;
+#[cfg(test)]
+mod test_support;
 mod types;
+mod usage;
 
+pub use batch::{BatchItem, BatchState, BATCH_STATE_PATH};
 pub use cache::ExplanationCache;
 pub use explain::{ExplainGenerator, format_explanation};
+pub use policy::{LlmMode, LlmPolicy, PolicyViolation};
+pub use redact::{redact_sensitive, REDACTED};
 pub use types::*;
+pub use usage::{Pricing, Usage};
 
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use usage::{AnthropicUsage, OpenAIUsage};
+
+/// Most tokens a completion may use
+const MAX_TOKENS: u32 = 4096;
+
+/// Times a rate-limited request is retried before giving up
+const MAX_RETRIES: u32 = 4;
+
+/// Units of the reserved budget per dollar
+const MICROS_PER_DOLLAR: f64 = 1_000_000.0;
 
 /// LLM API client for generating Covenant code and explanations
 pub struct LlmClient {
@@ -23,14 +49,79 @@ pub struct LlmClient {
     api_key: String,
     model: String,
     provider: Provider,
+    base_url: String,
+    policy: LlmPolicy,
+    usage: Mutex<Usage>,
+    /// Worst-case cost of the requests in flight in micro-dollars, held
+    /// against the limit; whole units so releases cancel holds exactly
+    reserved: Mutex<u64>,
+    max_cost: Option<f64>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Provider {
     OpenAI,
     Anthropic,
 }
 
+impl Provider {
+    /// Parse a provider name as given on the command line
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "openai" => Some(Provider::OpenAI),
+            "anthropic" => Some(Provider::Anthropic),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Provider::OpenAI => "openai",
+            Provider::Anthropic => "anthropic",
+        }
+    }
+
+    /// Environment variable holding the provider's API key
+    pub fn api_key_var(&self) -> &'static str {
+        match self {
+            Provider::OpenAI => "OPENAI_API_KEY",
+            Provider::Anthropic => "ANTHROPIC_API_KEY",
+        }
+    }
+
+    /// Environment variable overriding the provider's base URL, e.g. to go
+    /// through a gateway
+    pub fn base_url_var(&self) -> &'static str {
+        match self {
+            Provider::OpenAI => "OPENAI_BASE_URL",
+            Provider::Anthropic => "ANTHROPIC_BASE_URL",
+        }
+    }
+
+    pub fn default_base_url(&self) -> &'static str {
+        match self {
+            Provider::OpenAI => "https://api.openai.com/v1",
+            Provider::Anthropic => "https://api.anthropic.com",
+        }
+    }
+
+    fn default_model(&self) -> &'static str {
+        match self {
+            Provider::OpenAI => "gpt-4o",
+            Provider::Anthropic => "claude-sonnet-4-20250514",
+        }
+    }
+
+    /// Path of the completion endpoint under the base URL
+    fn endpoint_path(&self) -> &'static str {
+        match self {
+            Provider::OpenAI => "/chat/completions",
+            Provider::Anthropic => "/v1/messages",
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct OpenAIRequest {
     model: String,
@@ -42,11 +133,28 @@ struct OpenAIRequest {
 #[derive(Debug, Serialize)]
 struct AnthropicRequest {
     model: String,
-    system: String,
+    system: Vec<SystemBlock>,
     messages: Vec<Message>,
     max_tokens: u32,
 }
 
+/// A system prompt block, marked for Anthropic's prompt cache: system
+/// prompts are the same across a batch, so later requests read them back
+/// at a tenth of the price
+#[derive(Debug, Serialize)]
+struct SystemBlock {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    text: String,
+    cache_control: CacheControl,
+}
+
+#[derive(Debug, Serialize)]
+struct CacheControl {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Message {
     role: String,
@@ -56,6 +164,8 @@ struct Message {
 #[derive(Debug, Deserialize)]
 struct OpenAIResponse {
     choices: Vec<OpenAIChoice>,
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -66,6 +176,8 @@ struct OpenAIChoice {
 #[derive(Debug, Deserialize)]
 struct AnthropicResponse {
     content: Vec<AnthropicContent>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -86,54 +198,117 @@ pub enum LlmError {
     NoResponse,
     #[error("Generation failed: {0}")]
     Generation(String),
+    #[error("{0}")]
+    Policy(#[from] PolicyViolation),
+    #[error("Stopping before the next request could take spending past ${max_cost} (${spent:.4} spent)")]
+    BudgetExceeded { spent: f64, max_cost: f64 },
+    #[error("No prices known for model '{0}', so a cost limit can't be enforced")]
+    UnknownPricing(String),
+    #[error("Rate limited by the provider; gave up after {0} retries")]
+    RateLimited(u32),
 }
 
 impl LlmClient {
     /// Create a new client, auto-detecting provider from environment
     pub fn new() -> Result<Self, LlmError> {
-        // Try Anthropic first (preferred)
-        if let Ok(api_key) = env::var("ANTHROPIC_API_KEY") {
-            return Ok(Self {
-                client: reqwest::Client::new(),
-                api_key,
-                model: "claude-sonnet-4-20250514".to_string(),
-                provider: Provider::Anthropic,
-            });
-        }
+        Self::from_policy(&LlmPolicy::default())
+    }
 
-        // Try OpenAI
-        if let Ok(api_key) = env::var("OPENAI_API_KEY") {
-            return Ok(Self {
-                client: reqwest::Client::new(),
-                api_key,
-                model: "gpt-4o".to_string(),
-                provider: Provider::OpenAI,
-            });
+    /// Create a client under `policy`, auto-detecting the provider from the
+    /// environment among those it allows
+    pub fn from_policy(policy: &LlmPolicy) -> Result<Self, LlmError> {
+        if policy.is_offline() {
+            return Err(PolicyViolation::Offline.into());
         }
-
-        Err(LlmError::NoApiKey)
+        // Anthropic is preferred
+        let provider = [Provider::Anthropic, Provider::OpenAI]
+            .into_iter()
+            .filter(|provider| policy.allows_provider(*provider))
+            .find(|provider| env::var(provider.api_key_var()).is_ok())
+            .ok_or(LlmError::NoApiKey)?;
+        Self::for_provider(provider, None)?.with_policy(policy.clone())
     }
 
     /// Create a client with explicit configuration
     pub fn with_config(provider: Provider, api_key: String, model: Option<String>) -> Self {
-        let default_model = match provider {
-            Provider::Anthropic => "claude-sonnet-4-20250514".to_string(),
-            Provider::OpenAI => "gpt-4o".to_string(),
-        };
-
         Self {
             client: reqwest::Client::new(),
             api_key,
-            model: model.unwrap_or(default_model),
+            model: model.unwrap_or_else(|| provider.default_model().to_string()),
             provider,
+            base_url: provider.default_base_url().to_string(),
+            policy: LlmPolicy::default(),
+            usage: Mutex::new(Usage::default()),
+            reserved: Mutex::new(0),
+            max_cost: None,
         }
     }
 
+    /// Create a client for a specific provider, reading its key and any
+    /// base URL override from the environment
+    pub fn for_provider(provider: Provider, model: Option<String>) -> Result<Self, LlmError> {
+        let api_key = env::var(provider.api_key_var()).map_err(|_| LlmError::NoApiKey)?;
+        let client = Self::with_config(provider, api_key, model);
+        Ok(match env::var(provider.base_url_var()) {
+            Ok(base_url) => client.with_base_url(base_url),
+            Err(_) => client,
+        })
+    }
+
+    /// Send requests under `base_url` instead of the provider's own
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Restrict calls to what `policy` allows, failing if it forbids this
+    /// client's provider or endpoint
+    pub fn with_policy(mut self, policy: LlmPolicy) -> Result<Self, LlmError> {
+        policy.check(self.provider, &self.endpoint())?;
+        self.policy = policy;
+        Ok(self)
+    }
+
+    /// Fail any request that could take the client's total spending past
+    /// `max_cost` dollars, judged by [`Pricing::estimate`]
+    pub fn with_max_cost(mut self, max_cost: f64) -> Result<Self, LlmError> {
+        if self.pricing().is_none() {
+            return Err(LlmError::UnknownPricing(self.model.clone()));
+        }
+        self.max_cost = Some(max_cost);
+        Ok(self)
+    }
+
+    /// Tokens used by this client's requests so far
+    pub fn usage(&self) -> Usage {
+        *self.usage.lock().unwrap()
+    }
+
+    /// Prices of the client's model, if known
+    pub fn pricing(&self) -> Option<Pricing> {
+        Pricing::for_model(&self.model)
+    }
+
+    /// Dollars spent so far, if the model's prices are known
+    pub fn cost(&self) -> Option<f64> {
+        Some(self.pricing()?.cost(&self.usage()))
+    }
+
+    /// URL requests are sent to
+    pub fn endpoint(&self) -> String {
+        format!("{}{}", self.base_url, self.provider.endpoint_path())
+    }
+
     /// Get the provider being used
     pub fn provider(&self) -> Provider {
         self.provider
     }
 
+    /// Get the model being used
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
     /// Generate Covenant code from a description
     pub async fn generate_code(&self, description: &str) -> Result<String, LlmError> {
         let system_prompt = prompts::CODE_GENERATION_PROMPT;
@@ -154,14 +329,63 @@ impl LlmClient {
         self.call(system_prompt, user_prompt).await
     }
 
+    /// Send a request, retrying with backoff while the provider rate
+    /// limits it
+    ///
+    /// Concurrent calls share one budget: each holds its worst-case cost
+    /// until its usage is recorded, so together they stay under the limit.
+    /// The hold is released when the call ends, including when its future
+    /// is dropped mid-request.
     async fn call(&self, system: &str, user: &str) -> Result<String, LlmError> {
-        match self.provider {
-            Provider::OpenAI => self.call_openai(system, user).await,
-            Provider::Anthropic => self.call_anthropic(system, user).await,
+        self.policy.check(self.provider, &self.endpoint())?;
+        let _reservation = self.reserve(system.len() + user.len())?;
+        let mut retries = 0;
+        let result = loop {
+            let attempt = match self.provider {
+                Provider::OpenAI => self.call_openai(system, user).await,
+                Provider::Anthropic => self.call_anthropic(system, user).await,
+            };
+            match attempt {
+                Err(Retry::After(delay)) if retries < MAX_RETRIES => {
+                    retries += 1;
+                    let backoff = Duration::from_secs(1 << retries);
+                    tokio::time::sleep(delay.unwrap_or(backoff)).await;
+                }
+                Err(Retry::After(_)) => break Err(LlmError::RateLimited(retries)),
+                Err(Retry::Fail(e)) => break Err(e),
+                Ok(text) => break Ok(text),
+            }
+        };
+        result
+    }
+
+    /// Hold the worst-case cost of a request with `prompt_chars` characters
+    /// of prompt against the cost limit, failing if it doesn't fit
+    fn reserve(&self, prompt_chars: usize) -> Result<Reservation<'_>, LlmError> {
+        let (Some(max_cost), Some(pricing)) = (self.max_cost, self.pricing()) else {
+            return Ok(Reservation { client: self, micros: 0 });
+        };
+        let mut reserved = self.reserved.lock().unwrap();
+        let spent = pricing.cost(&self.usage());
+        // Round the hold up so it never covers less than the estimate
+        let estimate = (pricing.estimate(prompt_chars, MAX_TOKENS) * MICROS_PER_DOLLAR).ceil() as u64;
+        if spent + (*reserved + estimate) as f64 / MICROS_PER_DOLLAR > max_cost {
+            return Err(LlmError::BudgetExceeded { spent, max_cost });
         }
+        *reserved += estimate;
+        Ok(Reservation { client: self, micros: estimate })
     }
 
-    async fn call_openai(&self, system: &str, user: &str) -> Result<String, LlmError> {
+    /// Dollars held for requests in flight
+    pub fn reserved(&self) -> f64 {
+        *self.reserved.lock().unwrap() as f64 / MICROS_PER_DOLLAR
+    }
+
+    fn record(&self, usage: Usage) {
+        self.usage.lock().unwrap().add(usage);
+    }
+
+    async fn call_openai(&self, system: &str, user: &str) -> Result<String, Retry> {
         let request = OpenAIRequest {
             model: self.model.clone(),
             messages: vec![
@@ -175,63 +399,113 @@ impl LlmClient {
                 },
             ],
             temperature: 0.0,
-            max_tokens: 4096,
+            max_tokens: MAX_TOKENS,
         };
 
         let response = self
             .client
-            .post("https://api.openai.com/v1/chat/completions")
+            .post(self.endpoint())
             .header("Authorization", format!("Bearer {}", self.api_key))
             .json(&request)
             .send()
-            .await?;
+            .await
+            .map_err(LlmError::from)?;
+        rate_limited(&response)?;
 
         let response: OpenAIResponse = response
             .json()
             .await
             .map_err(|e| LlmError::Json(e.to_string()))?;
+        self.record(response.usage.map(Usage::from).unwrap_or(Usage { requests: 1, ..Usage::default() }));
 
         response
             .choices
             .first()
             .map(|c| c.message.content.clone())
-            .ok_or(LlmError::NoResponse)
+            .ok_or(Retry::Fail(LlmError::NoResponse))
     }
 
-    async fn call_anthropic(&self, system: &str, user: &str) -> Result<String, LlmError> {
+    async fn call_anthropic(&self, system: &str, user: &str) -> Result<String, Retry> {
         let request = AnthropicRequest {
             model: self.model.clone(),
-            system: system.to_string(),
+            system: vec![SystemBlock {
+                kind: "text",
+                text: system.to_string(),
+                cache_control: CacheControl { kind: "ephemeral" },
+            }],
             messages: vec![Message {
                 role: "user".to_string(),
                 content: user.to_string(),
             }],
-            max_tokens: 4096,
+            max_tokens: MAX_TOKENS,
         };
 
         let response = self
             .client
-            .post("https://api.anthropic.com/v1/messages")
+            .post(self.endpoint())
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
             .json(&request)
             .send()
-            .await?;
+            .await
+            .map_err(LlmError::from)?;
+        rate_limited(&response)?;
 
         let response: AnthropicResponse = response
             .json()
             .await
             .map_err(|e| LlmError::Json(e.to_string()))?;
+        self.record(response.usage.map(Usage::from).unwrap_or(Usage { requests: 1, ..Usage::default() }));
 
         response
             .content
             .first()
             .map(|c| c.text.clone())
-            .ok_or(LlmError::NoResponse)
+            .ok_or(Retry::Fail(LlmError::NoResponse))
     }
 }
 
+/// A request's worst-case cost held against the client's limit, released
+/// when dropped
+struct Reservation<'a> {
+    client: &'a LlmClient,
+    micros: u64,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        *self.client.reserved.lock().unwrap() -= self.micros;
+    }
+}
+
+/// Outcome of a failed request attempt
+enum Retry {
+    /// Rate limited: try again after the delay the provider asked for, if any
+    After(Option<Duration>),
+    Fail(LlmError),
+}
+
+impl From<LlmError> for Retry {
+    fn from(e: LlmError) -> Self {
+        Retry::Fail(e)
+    }
+}
+
+/// Fail with [`Retry::After`] if `response` says the provider is rate
+/// limiting or overloaded (429, or Anthropic's 529)
+fn rate_limited(response: &reqwest::Response) -> Result<(), Retry> {
+    if !matches!(response.status().as_u16(), 429 | 529) {
+        return Ok(());
+    }
+    let retry_after = response
+        .headers()
+        .get("retry-after")
+        .and_then(|value| value.to_str().ok()?.parse::<u64>().ok())
+        .map(Duration::from_secs);
+    Err(Retry::After(retry_after))
+}
+
 /// Extract code blocks from markdown-formatted response
 pub fn extract_code(response: &str) -> String {
     // Try to find code block
@@ -247,3 +521,66 @@ pub fn extract_code(response: &str) -> String {
     // Return as-is if no code block found
     response.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_code() {
+        let response = "Here is the code:\n```covenant\nsnippet id=\"test\" kind=\"fn\"\nend\n```\nDone!";
+        let code = extract_code(response);
+        assert!(code.contains("snippet"));
+        assert!(!code.contains("```"));
+    }
+
+    #[test]
+    fn test_provider_names() {
+        assert!(matches!(Provider::from_name("Anthropic"), Some(Provider::Anthropic)));
+        assert_eq!(Provider::OpenAI.api_key_var(), "OPENAI_API_KEY");
+        assert!(Provider::from_name("local").is_none());
+    }
+
+    #[test]
+    fn test_client_policy() {
+        let client = LlmClient::with_config(Provider::Anthropic, "key".to_string(), None);
+        assert_eq!(client.endpoint(), "https://api.anthropic.com/v1/messages");
+
+        let gateway =
+            LlmPolicy { endpoints: Some(vec!["https://llm-gateway.internal".to_string()]), ..LlmPolicy::default() };
+        assert!(matches!(
+            client.with_policy(gateway.clone()),
+            Err(LlmError::Policy(PolicyViolation::Endpoint(_)))
+        ));
+        let client = LlmClient::with_config(Provider::Anthropic, "key".to_string(), None)
+            .with_base_url("https://llm-gateway.internal/");
+        assert_eq!(client.endpoint(), "https://llm-gateway.internal/v1/messages");
+        assert!(client.with_policy(gateway).is_ok());
+
+        assert!(matches!(
+            LlmClient::from_policy(&LlmPolicy::offline()),
+            Err(LlmError::Policy(PolicyViolation::Offline))
+        ));
+    }
+
+    #[test]
+    fn test_max_cost() {
+        let client = LlmClient::with_config(Provider::Anthropic, "key".to_string(), None);
+        let client = client.with_max_cost(0.10).unwrap();
+        assert_eq!(client.cost(), Some(0.0));
+
+        let reservation = client.reserve(4000).unwrap();
+        assert!(client.reserved() > 0.0);
+        assert!(client.reserve(4000).is_err(), "requests in flight count against the limit");
+        drop(reservation);
+        assert_eq!(client.reserved(), 0.0);
+
+        // $0.09 spent leaves too little for a full completion
+        client.record(Usage { requests: 1, completion_tokens: 6000, ..Usage::default() });
+        let exceeded = client.reserve(4000);
+        assert!(matches!(exceeded, Err(LlmError::BudgetExceeded { max_cost, .. }) if max_cost == 0.10));
+
+        let unknown = LlmClient::with_config(Provider::OpenAI, "key".to_string(), Some("local-llama".to_string()));
+        assert!(matches!(unknown.with_max_cost(1.0), Err(LlmError::UnknownPricing(_))));
+    }
+}
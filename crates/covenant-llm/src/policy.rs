@@ -0,0 +1,136 @@
+//! Where LLM calls may go
+//!
+//! Regulated projects can't send source to just any model. A project's
+//! `covenant.json` limits outbound calls under `llm`:
+//!
+//! ```json
+//! { "llm": { "mode": "offline" } }
+//! { "llm": { "providers": ["anthropic"], "endpoints": ["https://llm-gateway.internal/"] } }
+//! ```
+//!
+//! `"offline"` forbids every call; otherwise `providers` and `endpoints`,
+//! when given, list the only providers and URL prefixes calls may use.
+//! [`LlmClient`](crate::LlmClient) checks its policy before each request,
+//! so no prompt leaves the machine against it, and fails with
+//! [`PolicyViolation`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::Provider;
+
+/// Whether the project may call out to an LLM at all
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmMode {
+    #[default]
+    Online,
+    Offline,
+}
+
+/// Providers and endpoints LLM calls may use
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LlmPolicy {
+    #[serde(default)]
+    pub mode: LlmMode,
+    /// Allowed providers, or `None` for any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub providers: Option<Vec<Provider>>,
+    /// Allowed URL prefixes, or `None` for any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoints: Option<Vec<String>>,
+}
+
+/// An LLM call the policy forbids
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyViolation {
+    #[error("LLM calls are disabled: the project sets llm.mode to \"offline\"")]
+    Offline,
+    #[error("LLM provider '{0}' is not in the project's llm.providers")]
+    Provider(&'static str),
+    #[error("LLM endpoint '{0}' is not in the project's llm.endpoints")]
+    Endpoint(String),
+}
+
+impl LlmPolicy {
+    /// A policy forbidding every call
+    pub fn offline() -> Self {
+        Self { mode: LlmMode::Offline, ..Self::default() }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.mode == LlmMode::Offline
+    }
+
+    /// Whether calls may use `provider`, ignoring endpoints
+    pub fn allows_provider(&self, provider: Provider) -> bool {
+        !self.is_offline() && self.providers.as_ref().is_none_or(|providers| providers.contains(&provider))
+    }
+
+    /// Check a call to `provider` at `endpoint`
+    pub fn check(&self, provider: Provider, endpoint: &str) -> Result<(), PolicyViolation> {
+        if self.is_offline() {
+            return Err(PolicyViolation::Offline);
+        }
+        if !self.allows_provider(provider) {
+            return Err(PolicyViolation::Provider(provider.name()));
+        }
+        match &self.endpoints {
+            Some(endpoints) if !endpoints.iter().any(|allowed| covers(allowed, endpoint)) => {
+                Err(PolicyViolation::Endpoint(endpoint.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Whether the allowed prefix `allowed` covers `endpoint`, at a path
+/// boundary: `https://api.example.com` covers `https://api.example.com/v1`
+/// but not `https://api.example.com.evil.net`
+fn covers(allowed: &str, endpoint: &str) -> bool {
+    match endpoint.strip_prefix(allowed) {
+        Some(rest) => allowed.ends_with('/') || rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_forbids_everything() {
+        let policy = LlmPolicy::offline();
+        assert_eq!(
+            policy.check(Provider::Anthropic, "https://api.anthropic.com/v1/messages"),
+            Err(PolicyViolation::Offline)
+        );
+        assert!(!policy.allows_provider(Provider::OpenAI));
+    }
+
+    #[test]
+    fn test_providers_and_endpoints() {
+        let policy: LlmPolicy = serde_json::from_str(
+            r#"{"providers": ["anthropic"], "endpoints": ["https://llm-gateway.internal"]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.check(Provider::Anthropic, "https://llm-gateway.internal/v1/messages"), Ok(()));
+        assert_eq!(
+            policy.check(Provider::OpenAI, "https://llm-gateway.internal/chat/completions"),
+            Err(PolicyViolation::Provider("openai"))
+        );
+        assert!(matches!(
+            policy.check(Provider::Anthropic, "https://llm-gateway.internal.evil.net/v1/messages"),
+            Err(PolicyViolation::Endpoint(_))
+        ));
+        assert!(LlmPolicy::default().check(Provider::OpenAI, "https://api.openai.com/v1/chat/completions").is_ok());
+    }
+
+    #[test]
+    fn test_rejects_unknown_settings() {
+        assert!(serde_json::from_str::<LlmPolicy>(r#"{"mode": "airgapped"}"#).is_err());
+        assert!(serde_json::from_str::<LlmPolicy>(r#"{"mdoe": "offline"}"#).is_err());
+        assert!(serde_json::from_str::<LlmPolicy>(r#"{"providers": ["local"]}"#).is_err());
+    }
+}
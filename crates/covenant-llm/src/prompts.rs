@@ -60,6 +60,35 @@ end
 - Optional: `type="User" optional`
 - Union returns: `returns union type="Success" type="Error" end`
 
+## Example - Pure Function
+
+```
+snippet id="math.add" kind="fn"
+
+signature
+  fn name="add"
+    param name="a" type="Int"
+    param name="b" type="Int"
+    returns type="Int"
+  end
+end
+
+body
+  step id="s1" kind="compute"
+    op=add
+    input var="a"
+    input var="b"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+
+end
+```
+
 Output ONLY valid Covenant code. No explanations, no markdown formatting.
 "#;
 
@@ -147,6 +176,8 @@ pub fn build_explain_prompt(
             .join(", ")
     };
 
+    let docs_str = format_docs(meta);
+
     let verbosity_instruction = match verbosity {
         Verbosity::Minimal => {
             "Provide minimal output: summary only, parameter names without descriptions, no step explanations."
@@ -170,7 +201,7 @@ pub fn build_explain_prompt(
 - Returns: {returns}
 - Step count: {step_count}
 - Patterns detected: {patterns}
-
+{docs}
 ## Verbosity
 {verbosity_instruction}
 
@@ -187,10 +218,30 @@ Generate a JSON explanation following the schema in your instructions."#,
         returns = return_str,
         step_count = meta.step_count,
         patterns = patterns_str,
+        docs = docs_str,
         code = code
     )
 }
 
+/// Author-written docs as a prompt section, empty when there are none
+fn format_docs(meta: &SnippetMetadata) -> String {
+    if meta.doc.is_none() && meta.param_docs.is_empty() && meta.step_docs.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::from("\n## Author Documentation\nStay consistent with these; they are authoritative.\n");
+    if let Some(doc) = &meta.doc {
+        out.push_str(&format!("- Snippet: {}\n", doc));
+    }
+    for (name, doc) in &meta.param_docs {
+        out.push_str(&format!("- Param `{}`: {}\n", name, doc));
+    }
+    for (step_id, doc) in &meta.step_docs {
+        out.push_str(&format!("- Step `{}`: {}\n", step_id, doc));
+    }
+    out
+}
+
 /// Step kind to default verb phrase mapping
 #[allow(dead_code)]
 pub fn step_kind_phrase(kind: &str) -> &'static str {
@@ -0,0 +1,230 @@
+//! Keeping secrets out of prompts
+//!
+//! Source sent to the LLM may spell out the values of `sensitive` params
+//! and fields, typically in tests: `arg name="token" lit="sk-live-..."`.
+//! [`redact_sensitive`] replaces each such literal with [`REDACTED`] before
+//! the source goes into a prompt. A literal counts when it is passed
+//! straight to a sensitive param or field, bound by a `bind` step or const
+//! snippet whose binding is passed to one, or expected of a sensitive field
+//! in a call's result.
+
+use std::collections::HashMap;
+
+use covenant_ast::{
+    walk_step, BindSource, CallStep, InputSource, ReturnType, Section, Sensitive, SignatureKind, Snippet, Span,
+    Step, StepKind, Type, TypeKind, Visitor,
+};
+
+/// Replaces a sensitive literal in prompts
+pub const REDACTED: &str = "[REDACTED]";
+
+/// `source` with the literal values of sensitive params and fields replaced
+/// by [`REDACTED`]; `snippets` must be parsed from `source`
+pub fn redact_sensitive(source: &str, snippets: &[Snippet]) -> String {
+    let sensitive = Sensitive::from_snippets(snippets);
+    if sensitive.is_empty() {
+        return source.to_string();
+    }
+
+    let mut returns = HashMap::new();
+    let mut constants = HashMap::new();
+    for snippet in snippets {
+        for section in &snippet.sections {
+            let Section::Signature(sig) = section else { continue };
+            match &sig.kind {
+                SignatureKind::Function(f) => {
+                    if let Some(ReturnType::Single { ty, .. }) = &f.returns {
+                        returns.insert(snippet.id.clone(), ty.clone());
+                        returns.entry(f.name.clone()).or_insert_with(|| ty.clone());
+                    }
+                }
+                SignatureKind::Const(c) => {
+                    constants.insert(snippet.id.clone(), c.span);
+                    constants.insert(c.name.clone(), c.span);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut literals = SensitiveLiterals {
+        sensitive: &sensitive,
+        returns: &returns,
+        constants: &constants,
+        bound: HashMap::new(),
+        spans: Vec::new(),
+    };
+    for snippet in snippets {
+        literals.bound.clear();
+        literals.visit_snippet(snippet);
+    }
+
+    let mut spans = literals.spans;
+    spans.sort_by_key(|span| span.start);
+    spans.dedup();
+    let mut redacted = source.to_string();
+    for span in spans.iter().rev() {
+        if let Some(value) = literal_value(source, *span) {
+            redacted.replace_range(value, &format!("\"{}\"", REDACTED));
+        }
+    }
+    redacted
+}
+
+/// Spans of the nodes holding sensitive literals
+struct SensitiveLiterals<'a> {
+    sensitive: &'a Sensitive,
+    returns: &'a HashMap<String, Type>,
+    constants: &'a HashMap<String, Span>,
+    /// Spans of the `bind` steps binding a literal, by binding
+    bound: HashMap<String, Span>,
+    spans: Vec<Span>,
+}
+
+impl SensitiveLiterals<'_> {
+    /// Record the literal `source` holds or was bound from, if any
+    fn redact(&mut self, source: &InputSource, span: Span) {
+        match source {
+            InputSource::Lit(_) => self.spans.push(span),
+            InputSource::Var(var) => {
+                if let Some(span) = self.bound.get(var).or_else(|| self.constants.get(var)) {
+                    self.spans.push(*span);
+                }
+            }
+            InputSource::Field { .. } => {}
+        }
+    }
+
+    fn visit_call_site(&mut self, call: &CallStep) {
+        for arg in &call.args {
+            if self.sensitive.is_param(&call.fn_name, &arg.name) {
+                self.redact(&arg.source, arg.span);
+            }
+        }
+        let result = self.returns.get(&call.fn_name).and_then(|ty| self.sensitive.struct_of(ty));
+        if let Some(result) = result {
+            for expect in &call.expects {
+                let field = expect.field.rsplit('.').next().unwrap_or(&expect.field);
+                if self.sensitive.is_field(result, field) {
+                    self.spans.push(expect.span);
+                }
+            }
+        }
+    }
+}
+
+impl Visitor for SensitiveLiterals<'_> {
+    fn visit_step(&mut self, step: &Step) {
+        match &step.kind {
+            StepKind::Bind(bind) => {
+                if let BindSource::Lit(_) = bind.source {
+                    self.bound.insert(step.output_binding.clone(), step.span);
+                }
+            }
+            StepKind::Call(call) => self.visit_call_site(call),
+            StepKind::Construct(construct) => {
+                if let TypeKind::Named(path) = &construct.ty.kind {
+                    for field in &construct.fields {
+                        if self.sensitive.is_field(path.name(), &field.name) {
+                            self.redact(&field.value, field.span);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        walk_step(self, step);
+    }
+}
+
+/// Byte range of the value of the first `lit=` inside `span`
+fn literal_value(source: &str, span: Span) -> Option<std::ops::Range<usize>> {
+    let text = source.get(span.start..span.end.min(source.len()))?;
+    let start = span.start + text.find("lit=")? + "lit=".len();
+    let rest = &source[start..];
+    let len = if let Some(block) = rest.strip_prefix("\"\"\"") {
+        block.find("\"\"\"")? + 6
+    } else if let Some(string) = rest.strip_prefix('"') {
+        let mut escaped = false;
+        let close = string.char_indices().find(|&(_, c)| {
+            let close = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            close
+        })?;
+        close.0 + 2
+    } else {
+        rest.find(char::is_whitespace).unwrap_or(rest.len())
+    };
+    Some(start..start + len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::snippets;
+
+    const SOURCE: &str = r#"snippet id="auth.login" kind="fn"
+signature
+  fn name="login"
+    param name="user" type="String"
+    param name="token" type="String" sensitive
+    returns type="Bool"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=true
+    as="_"
+  end
+end
+end
+
+snippet id="auth.API_KEY" kind="const"
+signature
+  const name="API_KEY" type="String" lit="sk-const-secret"
+end
+end
+
+snippet id="auth.tests" kind="test"
+tests
+  test id="T-1" kind="unit"
+    step id="t1" kind="call"
+      fn="auth.login"
+      arg name="user" lit="alice"
+      arg name="token" lit="sk-\"quoted\"-secret"
+      as="ok"
+    end
+    step id="t2" kind="bind"
+      lit="sk-bound-secret"
+      as="key"
+    end
+    step id="t3" kind="call"
+      fn="auth.login"
+      arg name="user" lit="bob"
+      arg name="token" from="key"
+      as="ok2"
+    end
+    step id="t4" kind="call"
+      fn="auth.login"
+      arg name="user" lit="carol"
+      arg name="token" from="API_KEY"
+      as="ok3"
+    end
+  end
+end
+end
+"#;
+
+    #[test]
+    fn test_redacts_sensitive_literals() {
+        let snippets = snippets(SOURCE);
+        let redacted = redact_sensitive(SOURCE, &snippets);
+
+        assert!(!redacted.contains("secret"), "{}", redacted);
+        assert_eq!(redacted.matches(REDACTED).count(), 3);
+        for user in ["alice", "bob", "carol"] {
+            assert!(redacted.contains(user));
+        }
+        assert!(covenant_parser::parse(&redacted).is_ok());
+    }
+}
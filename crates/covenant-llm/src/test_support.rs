@@ -0,0 +1,11 @@
+//! Fixtures shared by the unit tests
+
+use covenant_ast::{Program, Snippet};
+
+/// The snippets of a source file
+pub(crate) fn snippets(source: &str) -> Vec<Snippet> {
+    match covenant_parser::parse(source).unwrap() {
+        Program::Snippets { snippets, .. } => snippets,
+        _ => panic!("expected snippets"),
+    }
+}
@@ -22,6 +22,10 @@ pub struct Explanation {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detailed_description: Option<String>,
 
+    /// The snippet's author-written `doc` block, verbatim
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+
     /// Explanations for each parameter (for fn kind)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub parameters: Vec<ParamExplanation>,
@@ -280,4 +284,7 @@ pub struct SnippetMetadata {
     pub step_kinds: Vec<String>,
     pub requirements: Vec<(String, Option<String>)>, // (id, text)
     pub test_count: usize,
+    pub doc: Option<String>,
+    pub param_docs: Vec<(String, String)>, // (param name, doc)
+    pub step_docs: Vec<(String, String)>,  // (step id, doc)
 }
@@ -0,0 +1,194 @@
+//! Token accounting
+//!
+//! [`LlmClient`](crate::LlmClient) adds up the tokens each response reports
+//! as a [`Usage`], so a command can print what a batch cost. Prompt tokens
+//! are split by how the provider bills them: read from the prompt cache,
+//! written to it, or neither. [`Pricing`] turns a usage into dollars for
+//! the models it knows, and estimates the most a request can cost, which
+//! is how the client keeps a batch under `--max-cost`.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Tokens used by a client's requests so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub requests: u64,
+    /// Prompt tokens billed at the full input rate
+    pub prompt_tokens: u64,
+    /// Prompt tokens read from the provider's prompt cache
+    pub cache_read_tokens: u64,
+    /// Prompt tokens written to the provider's prompt cache
+    pub cache_write_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl Usage {
+    /// All prompt tokens, cached or not
+    pub fn total_prompt_tokens(&self) -> u64 {
+        self.prompt_tokens + self.cache_read_tokens + self.cache_write_tokens
+    }
+
+    pub(crate) fn add(&mut self, other: Usage) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} request{}, {} prompt tokens ({} cached), {} completion tokens",
+            self.requests,
+            if self.requests == 1 { "" } else { "s" },
+            self.total_prompt_tokens(),
+            self.cache_read_tokens,
+            self.completion_tokens
+        )
+    }
+}
+
+/// Dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pricing {
+    pub prompt: f64,
+    pub cache_read: f64,
+    pub cache_write: f64,
+    pub completion: f64,
+}
+
+/// List prices by model name prefix; longer prefixes first where one
+/// prefix extends another
+const PRICES: &[(&str, Pricing)] = &[
+    ("claude-opus-4", Pricing { prompt: 15.0, cache_read: 1.5, cache_write: 18.75, completion: 75.0 }),
+    ("claude-sonnet-4", Pricing { prompt: 3.0, cache_read: 0.3, cache_write: 3.75, completion: 15.0 }),
+    ("claude-3-7-sonnet", Pricing { prompt: 3.0, cache_read: 0.3, cache_write: 3.75, completion: 15.0 }),
+    ("claude-3-5-sonnet", Pricing { prompt: 3.0, cache_read: 0.3, cache_write: 3.75, completion: 15.0 }),
+    ("claude-3-5-haiku", Pricing { prompt: 0.8, cache_read: 0.08, cache_write: 1.0, completion: 4.0 }),
+    ("gpt-4o-mini", Pricing { prompt: 0.15, cache_read: 0.075, cache_write: 0.15, completion: 0.6 }),
+    ("gpt-4o", Pricing { prompt: 2.5, cache_read: 1.25, cache_write: 2.5, completion: 10.0 }),
+    ("gpt-4.1-mini", Pricing { prompt: 0.4, cache_read: 0.1, cache_write: 0.4, completion: 1.6 }),
+    ("gpt-4.1", Pricing { prompt: 2.0, cache_read: 0.5, cache_write: 2.0, completion: 8.0 }),
+];
+
+impl Pricing {
+    /// Prices for `model`, if known
+    pub fn for_model(model: &str) -> Option<Self> {
+        PRICES.iter().find(|(prefix, _)| model.starts_with(prefix)).map(|(_, pricing)| *pricing)
+    }
+
+    /// Cost of `usage` in dollars
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt
+            + usage.cache_read_tokens as f64 * self.cache_read
+            + usage.cache_write_tokens as f64 * self.cache_write
+            + usage.completion_tokens as f64 * self.completion)
+            / 1_000_000.0
+    }
+
+    /// The most a request with `prompt_chars` characters of prompt and up to
+    /// `max_tokens` of completion should cost: the prompt at about four
+    /// characters a token, uncached and rounded up, and a full completion
+    pub fn estimate(&self, prompt_chars: usize, max_tokens: u32) -> f64 {
+        let prompt_tokens = prompt_chars.div_ceil(4) as u64;
+        let uncached = prompt_tokens as f64 * self.prompt.max(self.cache_write);
+        (uncached + max_tokens as f64 * self.completion) / 1_000_000.0
+    }
+}
+
+/// `usage` of an Anthropic response
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+impl From<AnthropicUsage> for Usage {
+    fn from(usage: AnthropicUsage) -> Self {
+        Self {
+            requests: 1,
+            prompt_tokens: usage.input_tokens,
+            cache_read_tokens: usage.cache_read_input_tokens,
+            cache_write_tokens: usage.cache_creation_input_tokens,
+            completion_tokens: usage.output_tokens,
+        }
+    }
+}
+
+/// `usage` of an OpenAI response, whose prompt tokens include cached ones
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct OpenAIUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+    #[serde(default)]
+    prompt_tokens_details: Option<OpenAIPromptDetails>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct OpenAIPromptDetails {
+    #[serde(default)]
+    cached_tokens: u64,
+}
+
+impl From<OpenAIUsage> for Usage {
+    fn from(usage: OpenAIUsage) -> Self {
+        let cached = usage.prompt_tokens_details.map_or(0, |details| details.cached_tokens);
+        Self {
+            requests: 1,
+            prompt_tokens: usage.prompt_tokens.saturating_sub(cached),
+            cache_read_tokens: cached,
+            cache_write_tokens: 0,
+            completion_tokens: usage.completion_tokens,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_usage() {
+        let anthropic: AnthropicUsage = serde_json::from_str(
+            r#"{"input_tokens": 50, "output_tokens": 300, "cache_read_input_tokens": 2000}"#,
+        )
+        .unwrap();
+        let openai: OpenAIUsage = serde_json::from_str(
+            r#"{"prompt_tokens": 2050, "completion_tokens": 300, "prompt_tokens_details": {"cached_tokens": 2000}}"#,
+        )
+        .unwrap();
+
+        let mut usage = Usage::from(anthropic);
+        assert_eq!(usage, Usage::from(openai));
+        usage.add(Usage::from(OpenAIUsage { prompt_tokens: 10, ..OpenAIUsage::default() }));
+        assert_eq!(usage.total_prompt_tokens(), 2060);
+        assert_eq!(usage.to_string(), "2 requests, 2060 prompt tokens (2000 cached), 300 completion tokens");
+    }
+
+    #[test]
+    fn test_pricing() {
+        let sonnet = Pricing::for_model("claude-sonnet-4-20250514").unwrap();
+        let usage = Usage { prompt_tokens: 1_000_000, completion_tokens: 100_000, ..Usage::default() };
+        assert!((sonnet.cost(&usage) - 4.5).abs() < 1e-9);
+
+        assert_eq!(Pricing::for_model("gpt-4o-mini-2024-07-18").unwrap().completion, 0.6);
+        assert_eq!(Pricing::for_model("gpt-4o").unwrap().completion, 10.0);
+        assert!(Pricing::for_model("local-llama").is_none());
+
+        // 4000 characters is about 1000 tokens, written to the cache at worst
+        assert!((sonnet.estimate(4000, 1000) - (1000.0 * 3.75 + 1000.0 * 15.0) / 1_000_000.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,216 @@
+//! Covenant Lowering - Expands surface syntax into core steps
+//!
+//! The parser keeps syntactic conveniences as written, so a parsed program
+//! prints back the way it was authored. Lowering runs between parsing and
+//! checking and rewrites them into the core steps the checker and code
+//! generator handle:
+//!
+//! - **Pipelines**: a `pipeline` step becomes one `call` step per stage
+//!
+//! Construct spreads (`from_base`) are not lowered: the checker needs the
+//! base binding itself to check it has the constructed type.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! let program = covenant_parser::parse(source)?;
+//! let lowered = covenant_lower::lower(&program);
+//! let result = covenant_checker::check(&lowered)?;
+//! ```
+//!
+//! Lowering is idempotent, and returns the input borrowed when there is
+//! nothing to lower.
+
+use std::borrow::Cow;
+
+use covenant_ast::{
+    walk_snippet, walk_step, walk_steps_mut, CallArg, InputSource, Program, Snippet, Step,
+    StepKind, Visitor, VisitorMut,
+};
+
+/// Lower every snippet of a program
+pub fn lower(program: &Program) -> Cow<'_, Program> {
+    match program {
+        Program::Snippets { snippets, span } => match lower_snippets(snippets) {
+            Cow::Borrowed(_) => Cow::Borrowed(program),
+            Cow::Owned(snippets) => Cow::Owned(Program::Snippets { snippets, span: *span }),
+        },
+        Program::Legacy { .. } => Cow::Borrowed(program),
+    }
+}
+
+/// Lower a list of snippets
+pub fn lower_snippets(snippets: &[Snippet]) -> Cow<'_, [Snippet]> {
+    let mut finder = FindSurface::default();
+    for snippet in snippets {
+        finder.visit_snippet(snippet);
+    }
+    if !finder.found {
+        return Cow::Borrowed(snippets);
+    }
+
+    let mut lowered = snippets.to_vec();
+    for snippet in &mut lowered {
+        Lowering.visit_snippet_mut(snippet);
+    }
+    Cow::Owned(lowered)
+}
+
+/// Whether any step needs lowering
+#[derive(Default)]
+struct FindSurface {
+    found: bool,
+}
+
+impl Visitor for FindSurface {
+    fn visit_snippet(&mut self, snippet: &Snippet) {
+        if !self.found {
+            walk_snippet(self, snippet);
+        }
+    }
+
+    fn visit_step(&mut self, step: &Step) {
+        if matches!(step.kind, StepKind::Pipeline(_)) {
+            self.found = true;
+        } else {
+            walk_step(self, step);
+        }
+    }
+}
+
+/// Rewrites surface steps in every block of steps
+struct Lowering;
+
+impl VisitorMut for Lowering {
+    fn visit_steps_mut(&mut self, steps: &mut Vec<Step>) {
+        let mut lowered = Vec::with_capacity(steps.len());
+        for step in steps.drain(..) {
+            lowered.extend(lower_step(step));
+        }
+        *steps = lowered;
+        walk_steps_mut(self, steps);
+    }
+}
+
+/// The core steps for a step, expanding a pipeline into call steps
+///
+/// Intermediate results are bound to `<id>_<n>` by steps `<id>.<n>`; the
+/// last stage keeps the pipeline's id, doc, node id and `as` binding.
+fn lower_step(step: Step) -> Vec<Step> {
+    let StepKind::Pipeline(pipeline) = step.kind else {
+        return vec![step];
+    };
+    let last = pipeline.stages.len();
+    let mut doc = step.doc;
+    let mut threaded = pipeline.from;
+    let mut steps = Vec::with_capacity(last);
+
+    for (n, stage) in pipeline.stages.into_iter().enumerate().map(|(i, stage)| (i + 1, stage)) {
+        let mut call = stage.call;
+        call.args.insert(0, CallArg {
+            name: stage.into,
+            source: threaded,
+            span: call.span,
+        });
+        let (id, binding, node_id, span, step_doc) = if n == last {
+            (step.id.clone(), step.output_binding.clone(), step.node_id, step.span, doc.take())
+        } else {
+            (format!("{}.{}", step.id, n), format!("{}_{}", step.id, n), None, call.span, None)
+        };
+        threaded = InputSource::Var(binding.clone());
+        steps.push(Step {
+            id,
+            kind: StepKind::Call(call),
+            output_binding: binding,
+            doc: step_doc,
+            node_id,
+            span,
+        });
+    }
+
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use covenant_ast::printer::ToCov;
+    use covenant_ast::Section;
+
+    const PIPELINE: &str = r#"
+snippet id="test.slug" kind="fn"
+signature
+  fn name="slug"
+    param name="title" type="String"
+    returns type="String"
+  end
+end
+body
+  step id="s1" kind="if"
+    condition="flag"
+    then
+      step id="s1.1" kind="pipeline" from="title"
+        stage fn="text.trim"
+        stage fn="text.replace_all" into="s"
+          arg name="from" lit=" "
+          arg name="to" lit="-"
+        stage fn="text.lower"
+        as="slug"
+      end
+    end
+    as="_"
+  end
+  step id="s2" kind="return"
+    from="slug"
+    as="_"
+  end
+end
+end
+"#;
+
+    fn steps(program: &Program) -> &[Step] {
+        let Program::Snippets { snippets, .. } = program else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| match s {
+            Section::Body(body) => Some(body),
+            _ => None,
+        });
+        &body.expect("body section").steps
+    }
+
+    #[test]
+    fn test_pipeline_expands_to_calls() {
+        let program = covenant_parser::parse(PIPELINE).unwrap();
+        let lowered = lower(&program);
+        let StepKind::If(branch) = &steps(&lowered)[0].kind else {
+            panic!("Expected if step");
+        };
+
+        let ids: Vec<&str> = branch.then_steps.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["s1.1.1", "s1.1.2", "s1.1"]);
+        let bindings: Vec<&str> = branch.then_steps.iter().map(|s| s.output_binding.as_str()).collect();
+        assert_eq!(bindings, ["s1.1_1", "s1.1_2", "slug"]);
+
+        let calls: Vec<_> = branch.then_steps.iter().map(|step| match &step.kind {
+            StepKind::Call(call) => call,
+            other => panic!("Expected call step, got {:?}", other),
+        }).collect();
+        assert!(matches!(&calls[0].args[0].source, InputSource::Var(v) if v == "title"));
+        assert_eq!(calls[1].args.len(), 3);
+        assert_eq!(calls[1].args[0].name, "s");
+        assert!(matches!(&calls[1].args[0].source, InputSource::Var(v) if v == "s1.1_1"));
+        assert_eq!(calls[2].args[0].name, "value");
+        assert!(matches!(&calls[2].args[0].source, InputSource::Var(v) if v == "s1.1_2"));
+    }
+
+    #[test]
+    fn test_lowering_is_idempotent() {
+        let program = covenant_parser::parse(PIPELINE).unwrap();
+        let lowered = lower(&program).into_owned();
+        let again = lower(&lowered);
+
+        assert!(matches!(again, Cow::Borrowed(_)));
+        assert_eq!(again.to_cov(0), lowered.to_cov(0));
+    }
+}
@@ -5,17 +5,17 @@
 
 use std::collections::HashSet;
 
-use covenant_ast::{Step, StepKind};
+use covenant_ast::{walk_step, Step, StepKind, Visitor};
 
 /// Compute the set of reachable step IDs
 ///
 /// A step is unreachable if it comes after an unconditional return statement.
 /// Control flow through if/match is analyzed to detect when all branches return.
 pub fn compute_reachable(steps: &[Step]) -> HashSet<String> {
-    let mut reachable = HashSet::new();
+    let mut marker = ReachableMarker::default();
 
     for step in steps {
-        reachable.insert(step.id.clone());
+        marker.visit_step(step);
 
         // Check if this step definitely terminates (unconditional return)
         if definitely_returns(&step.kind) {
@@ -24,20 +24,13 @@ pub fn compute_reachable(steps: &[Step]) -> HashSet<String> {
         }
     }
 
-    // Also mark nested steps as reachable
-    for step in steps {
-        if reachable.contains(&step.id) {
-            mark_nested_reachable(&step.kind, &mut reachable);
-        }
-    }
-
-    reachable
+    marker.reachable
 }
 
 /// Check if a step kind definitely returns (terminates the function)
 fn definitely_returns(kind: &StepKind) -> bool {
     match kind {
-        StepKind::Return(_) => true,
+        StepKind::Return(_) | StepKind::Raise(_) => true,
         StepKind::If(if_step) => {
             // If returns definitely only if BOTH branches return
             let then_returns = branch_returns(&if_step.then_steps);
@@ -55,6 +48,8 @@ fn definitely_returns(kind: &StepKind) -> bool {
                 .iter()
                 .all(|case| branch_returns(&case.steps))
         }
+        // The resource is released on the way out
+        StepKind::Using(using) => branch_returns(&using.steps),
         _ => false,
     }
 }
@@ -64,47 +59,30 @@ fn branch_returns(steps: &[Step]) -> bool {
     steps.iter().any(|s| definitely_returns(&s.kind))
 }
 
-/// Mark nested steps as reachable
-fn mark_nested_reachable(kind: &StepKind, reachable: &mut HashSet<String>) {
-    match kind {
-        StepKind::If(if_step) => {
-            mark_branch_reachable(&if_step.then_steps, reachable);
-            if let Some(else_steps) = &if_step.else_steps {
-                mark_branch_reachable(else_steps, reachable);
-            }
-        }
-        StepKind::Match(match_step) => {
-            for case in &match_step.cases {
-                mark_branch_reachable(&case.steps, reachable);
-            }
-        }
-        StepKind::For(for_step) => {
-            mark_branch_reachable(&for_step.steps, reachable);
-        }
-        StepKind::Transaction(txn) => {
-            mark_branch_reachable(&txn.steps, reachable);
-        }
-        StepKind::Call(call) => {
-            if let Some(handle) = &call.handle {
-                for case in &handle.cases {
-                    mark_branch_reachable(&case.steps, reachable);
-                }
-            }
-        }
-        _ => {}
-    }
+/// Marks each visited step, and the nested steps it can reach, as reachable
+#[derive(Default)]
+struct ReachableMarker {
+    reachable: HashSet<String>,
 }
 
-/// Mark steps in a branch as reachable (until a return)
-fn mark_branch_reachable(steps: &[Step], reachable: &mut HashSet<String>) {
-    for step in steps {
-        reachable.insert(step.id.clone());
-        mark_nested_reachable(&step.kind, reachable);
+impl Visitor for ReachableMarker {
+    /// Mark steps in a nested block as reachable (until a return, break, or continue)
+    fn visit_steps(&mut self, steps: &[Step]) {
+        for step in steps {
+            self.visit_step(step);
 
-        if definitely_returns(&step.kind) {
-            break;
+            if definitely_returns(&step.kind)
+                || matches!(step.kind, StepKind::Break(_) | StepKind::Continue(_))
+            {
+                break;
+            }
         }
     }
+
+    fn visit_step(&mut self, step: &Step) {
+        self.reachable.insert(step.id.clone());
+        walk_step(self, step);
+    }
 }
 
 #[cfg(test)]
@@ -125,6 +103,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: "_".into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -137,6 +117,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: binding.into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -185,6 +167,8 @@ mod tests {
                     span: make_span(),
                 }),
                 output_binding: "_".into(),
+                doc: None,
+                node_id: None,
                 span: make_span(),
             },
             make_bind_step("s2", "x"), // unreachable - both branches return
@@ -210,6 +194,8 @@ mod tests {
                     span: make_span(),
                 }),
                 output_binding: "_".into(),
+                doc: None,
+                node_id: None,
                 span: make_span(),
             },
             make_bind_step("s2", "x"), // reachable - else branch doesn't return
@@ -233,6 +219,8 @@ mod tests {
                     span: make_span(),
                 }),
                 output_binding: "_".into(),
+                doc: None,
+                node_id: None,
                 span: make_span(),
             },
             make_bind_step("s2", "x"), // reachable - might skip then branch
@@ -269,6 +257,8 @@ mod tests {
                     span: make_span(),
                 }),
                 output_binding: "_".into(),
+                doc: None,
+                node_id: None,
                 span: make_span(),
             },
             make_bind_step("s2", "x"), // unreachable - all cases return
@@ -305,6 +295,8 @@ mod tests {
                     span: make_span(),
                 }),
                 output_binding: "_".into(),
+                doc: None,
+                node_id: None,
                 span: make_span(),
             },
             make_bind_step("s2", "x"), // reachable
@@ -5,10 +5,7 @@
 
 use std::collections::{HashMap, HashSet};
 
-use covenant_ast::{
-    BindSource, CallStep, ComputeStep, ConditionKind, InputSource,
-    QueryContent, ReturnStep, ReturnValue, Step, StepKind,
-};
+use covenant_ast::{walk_step, Step, Visitor};
 
 /// Result of usage analysis on a function body
 #[derive(Debug, Clone, Default)]
@@ -21,248 +18,61 @@ pub struct UsageAnalysis {
     pub consumes: HashMap<String, HashSet<String>>,
 }
 
-/// Analyze binding usage in a list of steps
+/// Analyze binding usage in a list of steps, nested steps included
 pub fn analyze_usage(steps: &[Step]) -> UsageAnalysis {
-    let mut analysis = UsageAnalysis::default();
+    let mut collector = UsageCollector::default();
+    collector.visit_steps(steps);
+    collector.analysis
+}
+
+/// Attributes each binding read to the innermost step containing it
+#[derive(Default)]
+struct UsageCollector {
+    analysis: UsageAnalysis,
+    /// IDs of the steps being visited, innermost last
+    enclosing: Vec<String>,
+}
 
-    for step in steps {
+impl Visitor for UsageCollector {
+    fn visit_step(&mut self, step: &Step) {
         // Record what this step produces
         if !step.output_binding.is_empty() && step.output_binding != "_" {
-            analysis
+            self.analysis
                 .produces
                 .insert(step.id.clone(), step.output_binding.clone());
         }
+        self.analysis.consumes.entry(step.id.clone()).or_default();
 
-        // Find all bindings this step consumes
-        let mut consumed = HashSet::new();
-        collect_consumed_bindings(&step.kind, &mut consumed);
-
-        // Record consumption
-        analysis.consumes.insert(step.id.clone(), consumed.clone());
-
-        // Build reverse map
-        for binding in consumed {
-            analysis
-                .used_by
-                .entry(binding)
-                .or_default()
-                .insert(step.id.clone());
-        }
-
-        // Recursively analyze nested steps (if, match, for, transaction)
-        analyze_nested_steps(&step.kind, &mut analysis);
-    }
-
-    analysis
-}
-
-/// Collect all variable bindings consumed by a step kind
-fn collect_consumed_bindings(kind: &StepKind, consumed: &mut HashSet<String>) {
-    match kind {
-        StepKind::Compute(compute) => {
-            collect_from_compute(compute, consumed);
-        }
-        StepKind::Call(call) => {
-            collect_from_call(call, consumed);
-        }
-        StepKind::Query(query) => {
-            // Collect from query parameters and conditions
-            match &query.content {
-                QueryContent::Covenant(cov) => {
-                    if let Some(cond) = &cov.where_clause {
-                        collect_from_condition(&cond.kind, consumed);
-                    }
-                }
-                QueryContent::Dialect(dialect) => {
-                    for param in &dialect.params {
-                        consumed.insert(param.from.clone());
-                    }
-                }
-            }
-        }
-        StepKind::Bind(bind) => {
-            collect_from_bind_source(&bind.source, consumed);
-        }
-        StepKind::Return(ret) => {
-            collect_from_return(ret, consumed);
-        }
-        StepKind::If(if_step) => {
-            // Condition is a binding reference or field access
-            match &if_step.condition {
-                InputSource::Var(name) => { consumed.insert(name.clone()); }
-                InputSource::Field { of, .. } => { consumed.insert(of.clone()); }
-                InputSource::Lit(_) => {}
-            }
-        }
-        StepKind::Match(match_step) => {
-            // The value being matched on
-            consumed.insert(match_step.on.clone());
-        }
-        StepKind::For(for_step) => {
-            // The collection being iterated
-            consumed.insert(for_step.collection.clone());
-        }
-        StepKind::Insert(insert) => {
-            for assignment in &insert.assignments {
-                collect_from_input_source(&assignment.value, consumed);
-            }
-        }
-        StepKind::Update(update) => {
-            for assignment in &update.assignments {
-                collect_from_input_source(&assignment.value, consumed);
-            }
-            if let Some(cond) = &update.where_clause {
-                collect_from_condition(&cond.kind, consumed);
-            }
-        }
-        StepKind::Delete(delete) => {
-            if let Some(cond) = &delete.where_clause {
-                collect_from_condition(&cond.kind, consumed);
-            }
-        }
-        StepKind::Transaction(_) => {
-            // Transaction itself doesn't consume bindings directly
-            // (nested steps are handled separately)
-        }
-        StepKind::Traverse(traverse) => {
-            consumed.insert(traverse.from.clone());
-        }
-        StepKind::Construct(construct) => {
-            for field in &construct.fields {
-                collect_from_input_source(&field.value, consumed);
-            }
-        }
-        StepKind::Parallel(_) | StepKind::Race(_) => {
-            // Parallel and Race steps don't directly consume bindings;
-            // nested steps within branches are handled separately
-        }
-    }
-}
-
-fn collect_from_compute(compute: &ComputeStep, consumed: &mut HashSet<String>) {
-    for input in &compute.inputs {
-        collect_from_input_source(&input.source, consumed);
-    }
-}
-
-fn collect_from_call(call: &CallStep, consumed: &mut HashSet<String>) {
-    for arg in &call.args {
-        collect_from_input_source(&arg.source, consumed);
-    }
-}
-
-fn collect_from_return(ret: &ReturnStep, consumed: &mut HashSet<String>) {
-    match &ret.value {
-        ReturnValue::Var(name) => {
-            consumed.insert(name.clone());
-        }
-        ReturnValue::Lit(_) => {}
-        ReturnValue::Struct(s) => {
-            for field in &s.fields {
-                collect_from_input_source(&field.value, consumed);
-            }
-        }
-        ReturnValue::Variant(v) => {
-            for field in &v.fields {
-                collect_from_input_source(&field.value, consumed);
-            }
-        }
+        // Bindings read by nested steps belong to those steps
+        self.enclosing.push(step.id.clone());
+        walk_step(self, step);
+        self.enclosing.pop();
     }
-}
 
-fn collect_from_input_source(source: &InputSource, consumed: &mut HashSet<String>) {
-    match source {
-        InputSource::Var(name) => {
-            consumed.insert(name.clone());
-        }
-        InputSource::Lit(_) => {}
-        InputSource::Field { of, .. } => {
-            consumed.insert(of.clone());
-        }
-    }
-}
-
-fn collect_from_bind_source(source: &BindSource, consumed: &mut HashSet<String>) {
-    match source {
-        BindSource::Var(name) => {
-            consumed.insert(name.clone());
-        }
-        BindSource::Lit(_) => {}
-        BindSource::Field { of, .. } => {
-            consumed.insert(of.clone());
-        }
-    }
-}
-
-fn collect_from_condition(kind: &ConditionKind, consumed: &mut HashSet<String>) {
-    match kind {
-        ConditionKind::Equals { value, .. }
-        | ConditionKind::Contains { value, .. }
-        | ConditionKind::NotEquals { value, .. } => {
-            collect_from_input_source(value, consumed);
-        }
-        ConditionKind::And(left, right) | ConditionKind::Or(left, right) => {
-            collect_from_condition(&left.kind, consumed);
-            collect_from_condition(&right.kind, consumed);
-        }
-        ConditionKind::RelTo { .. } | ConditionKind::RelFrom { .. } => {}
-    }
-}
-
-/// Recursively analyze nested steps in control flow constructs
-fn analyze_nested_steps(kind: &StepKind, analysis: &mut UsageAnalysis) {
-    match kind {
-        StepKind::If(if_step) => {
-            let nested = analyze_usage(&if_step.then_steps);
-            merge_analysis(analysis, &nested);
-            if let Some(else_steps) = &if_step.else_steps {
-                let nested = analyze_usage(else_steps);
-                merge_analysis(analysis, &nested);
-            }
-        }
-        StepKind::Match(match_step) => {
-            for case in &match_step.cases {
-                let nested = analyze_usage(&case.steps);
-                merge_analysis(analysis, &nested);
-            }
-        }
-        StepKind::For(for_step) => {
-            let nested = analyze_usage(&for_step.steps);
-            merge_analysis(analysis, &nested);
-        }
-        StepKind::Transaction(txn) => {
-            let nested = analyze_usage(&txn.steps);
-            merge_analysis(analysis, &nested);
-        }
-        StepKind::Call(call) => {
-            if let Some(handle) = &call.handle {
-                for case in &handle.cases {
-                    let nested = analyze_usage(&case.steps);
-                    merge_analysis(analysis, &nested);
-                }
-            }
-        }
-        _ => {}
-    }
-}
-
-/// Merge nested analysis into parent
-fn merge_analysis(parent: &mut UsageAnalysis, nested: &UsageAnalysis) {
-    for (binding, steps) in &nested.used_by {
-        parent
+    fn visit_binding_ref(&mut self, name: &str) {
+        let Some(step_id) = self.enclosing.last() else {
+            return;
+        };
+        self.analysis
+            .consumes
+            .entry(step_id.clone())
+            .or_default()
+            .insert(name.to_string());
+        self.analysis
             .used_by
-            .entry(binding.clone())
+            .entry(name.to_string())
             .or_default()
-            .extend(steps.clone());
+            .insert(step_id.clone());
     }
-    parent.produces.extend(nested.produces.clone());
-    parent.consumes.extend(nested.consumes.clone());
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use covenant_ast::{BindStep, IfStep, Input, Literal, Operation, Span};
+    use covenant_ast::{
+        BindSource, BindStep, Branch, ComputeStep, IfStep, Input, InputSource, Literal, Operation,
+        ParallelStep, ReturnStep, ReturnValue, Span, StepKind,
+    };
 
     fn make_span() -> Span {
         Span::dummy()
@@ -287,6 +97,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: "result".into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }];
 
@@ -308,6 +120,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: "_".into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }];
 
@@ -327,6 +141,8 @@ mod tests {
                     span: make_span(),
                 }),
                 output_binding: "unused".into(),
+                doc: None,
+                node_id: None,
                 span: make_span(),
             },
             Step {
@@ -336,6 +152,8 @@ mod tests {
                     span: make_span(),
                 }),
                 output_binding: "_".into(),
+                doc: None,
+                node_id: None,
                 span: make_span(),
             },
         ];
@@ -364,12 +182,16 @@ mod tests {
                         span: make_span(),
                     }),
                     output_binding: "nested_result".into(),
+                    doc: None,
+                    node_id: None,
                     span: make_span(),
                 }],
                 else_steps: None,
                 span: make_span(),
             }),
             output_binding: "_".into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }];
 
@@ -383,4 +205,41 @@ mod tests {
             Some(&"nested_result".to_string())
         );
     }
+
+    #[test]
+    fn test_parallel_branch_usage() {
+        let steps = vec![Step {
+            id: "s1".into(),
+            kind: StepKind::Parallel(ParallelStep {
+                branches: vec![Branch {
+                    id: "b1".into(),
+                    steps: vec![Step {
+                        id: "s1.1".into(),
+                        kind: StepKind::Return(ReturnStep {
+                            value: ReturnValue::Var("fetched".into()),
+                            span: make_span(),
+                        }),
+                        output_binding: "_".into(),
+                        doc: None,
+                        node_id: None,
+                        span: make_span(),
+                    }],
+                    span: make_span(),
+                }],
+                on_error: None,
+                timeout: None,
+                span: make_span(),
+            }),
+            output_binding: "results".into(),
+            doc: None,
+            node_id: None,
+            span: make_span(),
+        }];
+
+        let analysis = analyze_usage(&steps);
+
+        // Reads inside a branch belong to the nested step, not the parallel step
+        assert!(analysis.used_by.get("fetched").unwrap().contains("s1.1"));
+        assert!(analysis.consumes.get("s1").unwrap().is_empty());
+    }
 }
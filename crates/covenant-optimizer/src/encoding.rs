@@ -0,0 +1,209 @@
+//! Byte encodings used by the encoding compute ops
+//!
+//! Compile-time counterparts of the host's `encoding.*` imports
+//! (runtime/host/src/encoding.ts). Both sides are checked against the
+//! shared vectors in runtime/host/src/encoding.vectors.json.
+//!
+//! - Base64 is RFC 4648 §4 with required padding; Base64url is §5, encoded
+//!   without padding and decoded with or without it
+//! - Hex encodes lowercase and decodes either case
+//! - URL encoding percent-encodes every UTF-8 byte outside RFC 3986's
+//!   unreserved set (`A-Z a-z 0-9 - . _ ~`) as uppercase `%XX`
+//!
+//! Decoders are strict: they return `None` for characters outside the
+//! alphabet, misplaced or wrong padding, non-zero trailing bits, odd hex
+//! lengths, truncated `%` escapes and percent-decoded text that is not UTF-8.
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encode bytes as padded Base64
+pub fn base64_encode(bytes: &[u8]) -> String {
+    encode_base64_with(bytes, BASE64, true)
+}
+
+/// Decode padded Base64
+pub fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    decode_base64_with(text, BASE64, true)
+}
+
+/// Encode bytes as unpadded Base64url
+pub fn base64url_encode(bytes: &[u8]) -> String {
+    encode_base64_with(bytes, BASE64_URL, false)
+}
+
+/// Decode Base64url, padded or not
+pub fn base64url_decode(text: &str) -> Option<Vec<u8>> {
+    decode_base64_with(text, BASE64_URL, false)
+}
+
+/// Encode bytes as lowercase hex
+pub fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode hex digits of either case, two per byte
+pub fn hex_decode(text: &str) -> Option<Vec<u8>> {
+    let digits = text.as_bytes();
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| Some(hex_value(pair[0])? << 4 | hex_value(pair[1])?))
+        .collect()
+}
+
+/// Percent-encode a string's UTF-8 bytes, keeping only unreserved characters
+pub fn url_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for &b in text.as_bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// Decode `%XX` escapes; other characters, `+` included, pass through
+pub fn url_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hi = hex_value(*bytes.get(i + 1)?)?;
+            let lo = hex_value(*bytes.get(i + 2)?)?;
+            out.push(hi << 4 | lo);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|v| v as u8)
+}
+
+fn encode_base64_with(bytes: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().fold(0u32, |acc, &b| acc << 8 | b as u32) << (8 * (3 - chunk.len()));
+        let sextets = chunk.len() + 1;
+        for i in 0..4 {
+            if i < sextets {
+                out.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else if pad {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode_base64_with(text: &str, alphabet: &[u8; 64], padding_required: bool) -> Option<Vec<u8>> {
+    let chars = text.as_bytes();
+    let padding = chars.iter().rev().take(2).take_while(|&&c| c == b'=').count();
+    let body = &chars[..chars.len() - padding];
+
+    // Padding, when present, must complete the final quantum exactly
+    if (padding > 0 || padding_required) && !chars.len().is_multiple_of(4) {
+        return None;
+    }
+    if body.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(body.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &c in body {
+        let value = alphabet.iter().position(|&a| a == c)? as u32;
+        acc = acc << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    // Canonical encodings leave the unused low bits zero
+    (acc == 0).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const VECTORS: &str = include_str!("../../../runtime/host/src/encoding.vectors.json");
+
+    fn vectors(codec: &str) -> Value {
+        let all: Value = serde_json::from_str(VECTORS).expect("vectors parse");
+        all[codec].clone()
+    }
+
+    fn pairs(value: &Value) -> Vec<(String, String)> {
+        value.as_array().unwrap_or(&Vec::new()).iter()
+            .map(|pair| (pair[0].as_str().unwrap().to_string(), pair[1].as_str().unwrap().to_string()))
+            .collect()
+    }
+
+    fn strings(value: &Value) -> Vec<String> {
+        value.as_array().unwrap_or(&Vec::new()).iter()
+            .map(|s| s.as_str().unwrap().to_string())
+            .collect()
+    }
+
+    /// Check a bytes codec: `roundtrip` and `decode` pairs hold hex bytes and
+    /// encoded text, `invalid` lists text the decoder rejects
+    fn check_bytes_codec(codec: &str, encode: fn(&[u8]) -> String, decode: fn(&str) -> Option<Vec<u8>>) {
+        let v = vectors(codec);
+        for (bytes_hex, text) in pairs(&v["roundtrip"]) {
+            let bytes = hex_decode(&bytes_hex).unwrap();
+            assert_eq!(encode(&bytes), text, "{} encode {}", codec, bytes_hex);
+            assert_eq!(decode(&text), Some(bytes), "{} decode {:?}", codec, text);
+        }
+        for (text, bytes_hex) in pairs(&v["decode"]) {
+            assert_eq!(decode(&text), hex_decode(&bytes_hex), "{} decode {:?}", codec, text);
+        }
+        for text in strings(&v["invalid"]) {
+            assert_eq!(decode(&text), None, "{} should reject {:?}", codec, text);
+        }
+    }
+
+    #[test]
+    fn test_base64_vectors() {
+        check_bytes_codec("base64", base64_encode, base64_decode);
+    }
+
+    #[test]
+    fn test_base64url_vectors() {
+        check_bytes_codec("base64url", base64url_encode, base64url_decode);
+    }
+
+    #[test]
+    fn test_hex_vectors() {
+        check_bytes_codec("hex", hex_encode, hex_decode);
+    }
+
+    #[test]
+    fn test_url_vectors() {
+        let v = vectors("url");
+        for (text, encoded) in pairs(&v["roundtrip"]) {
+            assert_eq!(url_encode(&text), encoded, "url encode {:?}", text);
+            assert_eq!(url_decode(&encoded), Some(text.clone()), "url decode {:?}", encoded);
+        }
+        for (encoded, text) in pairs(&v["decode"]) {
+            assert_eq!(url_decode(&encoded), Some(text), "url decode {:?}", encoded);
+        }
+        for encoded in strings(&v["invalid"]) {
+            assert_eq!(url_decode(&encoded), None, "url should reject {:?}", encoded);
+        }
+    }
+}
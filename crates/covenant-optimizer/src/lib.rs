@@ -6,8 +6,9 @@
 //! # Optimization Passes
 //!
 //! - **Dead Code Elimination**: Removes unreachable steps and warns about unused bindings
-//! - **Constant Folding**: Evaluates constant expressions at compile time
+//! - **Constant Folding**: Inlines `const` snippets and evaluates constant expressions at compile time
 //! - **Unused Binding Detection**: Warns about assigned-but-never-read bindings
+//! - **Debug Check Stripping**: Removes assert steps and struct invariants at O2 and above
 //!
 //! # Usage
 //!
@@ -26,14 +27,20 @@
 //! ```
 
 pub mod analysis;
+pub mod encoding;
 pub mod passes;
 
 pub use passes::{
     ConstantFolding, DeadCodeElimination, OptContext, OptLevel, OptSettings, OptWarning,
-    OptimizationPass, PassResult, UnusedBindingDetection,
+    OptimizationPass, PassResult, StripDebugChecks, UnusedBindingDetection,
 };
 
-use covenant_ast::Step;
+use std::collections::{HashMap, HashSet};
+
+use covenant_ast::{
+    Literal, MatchPattern, Section, SignatureKind, SignatureSection, Snippet, SnippetKind, Step,
+    StepKind,
+};
 
 /// Result of running all optimization passes
 #[derive(Debug, Clone, Default)]
@@ -55,8 +62,13 @@ pub struct OptResult {
 pub fn optimize(steps: &mut Vec<Step>, settings: &OptSettings) -> OptResult {
     let ctx = OptContext {
         settings: settings.clone(),
+        constants: HashMap::new(),
     };
+    run_passes(steps, &ctx)
+}
 
+fn run_passes(steps: &mut Vec<Step>, ctx: &OptContext) -> OptResult {
+    let settings = &ctx.settings;
     let mut result = OptResult::default();
 
     // Select passes based on optimization level
@@ -67,6 +79,7 @@ pub fn optimize(steps: &mut Vec<Step>, settings: &OptSettings) -> OptResult {
             Box::new(UnusedBindingDetection),
         ],
         OptLevel::O2 | OptLevel::O3 => vec![
+            Box::new(StripDebugChecks),
             Box::new(ConstantFolding),
             Box::new(DeadCodeElimination),
             Box::new(UnusedBindingDetection),
@@ -75,7 +88,7 @@ pub fn optimize(steps: &mut Vec<Step>, settings: &OptSettings) -> OptResult {
 
     // Run each pass in sequence
     for pass in passes {
-        let pass_result = pass.run(steps, &ctx);
+        let pass_result = pass.run(steps, ctx);
         result.modified |= pass_result.modified;
         if settings.emit_warnings {
             result.warnings.extend(pass_result.warnings);
@@ -85,6 +98,118 @@ pub fn optimize(steps: &mut Vec<Step>, settings: &OptSettings) -> OptResult {
     result
 }
 
+/// Optimize every function body of a snippet
+///
+/// At O2 and above this also removes struct invariant sections, so that
+/// construct steps are not checked in release builds.
+pub fn optimize_snippet(snippet: &mut Snippet, settings: &OptSettings) -> OptResult {
+    optimize_snippet_with_constants(snippet, settings, &HashMap::new())
+}
+
+/// Optimize every snippet of a program
+///
+/// The values of `const` snippets are collected first, so constant folding
+/// can inline them into the bodies that reference them.
+pub fn optimize_program(snippets: &mut [Snippet], settings: &OptSettings) -> OptResult {
+    let constants: HashMap<String, Literal> = snippets
+        .iter()
+        .filter(|snippet| snippet.kind == SnippetKind::Const)
+        .filter_map(|snippet| {
+            snippet.sections.iter().find_map(|section| match section {
+                Section::Signature(SignatureSection { kind: SignatureKind::Const(c), .. }) => {
+                    Some((snippet.id.clone(), c.value.clone()))
+                }
+                _ => None,
+            })
+        })
+        .collect();
+
+    let mut result = OptResult::default();
+    for snippet in snippets.iter_mut() {
+        let snippet_result = optimize_snippet_with_constants(snippet, settings, &constants);
+        result.modified |= snippet_result.modified;
+        result.warnings.extend(snippet_result.warnings);
+    }
+    result
+}
+
+fn optimize_snippet_with_constants(
+    snippet: &mut Snippet,
+    settings: &OptSettings,
+    constants: &HashMap<String, Literal>,
+) -> OptResult {
+    let mut result = OptResult::default();
+
+    // A param or binding named like a constant (or its namespace) shadows it
+    let bound = bound_names(snippet);
+    let ctx = OptContext {
+        settings: settings.clone(),
+        constants: constants
+            .iter()
+            .filter(|(id, _)| !bound.contains(id.split('.').next().unwrap_or(id)))
+            .map(|(id, value)| (id.clone(), value.clone()))
+            .collect(),
+    };
+
+    if settings.level >= OptLevel::O2 {
+        let original_len = snippet.sections.len();
+        snippet.sections.retain(|s| !matches!(s, Section::Invariant(_)));
+        result.modified |= snippet.sections.len() != original_len;
+    }
+
+    for section in snippet.sections.iter_mut() {
+        if let Section::Body(body) = section {
+            let body_result = run_passes(&mut body.steps, &ctx);
+            result.modified |= body_result.modified;
+            result.warnings.extend(body_result.warnings);
+        }
+    }
+
+    result
+}
+
+/// Every name a snippet binds: params, step outputs, loop and resource
+/// variables, lambda params and match bindings
+fn bound_names(snippet: &Snippet) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for section in &snippet.sections {
+        match section {
+            Section::Signature(SignatureSection { kind: SignatureKind::Function(f), .. }) => {
+                names.extend(f.params.iter().map(|p| p.name.clone()));
+            }
+            Section::Body(body) => collect_bound_names(&body.steps, &mut names),
+            _ => {}
+        }
+    }
+    names
+}
+
+fn collect_bound_names(steps: &[Step], names: &mut HashSet<String>) {
+    for step in steps {
+        names.insert(step.output_binding.clone());
+        match &step.kind {
+            StepKind::For(f) => {
+                names.insert(f.var.clone());
+            }
+            StepKind::Using(u) => {
+                names.insert(u.var.clone());
+            }
+            StepKind::Lambda(l) => names.extend(l.params.iter().map(|p| p.name.clone())),
+            StepKind::Match(m) => {
+                for case in &m.cases {
+                    if let MatchPattern::Variant { bindings, .. } = &case.pattern {
+                        names.extend(bindings.iter().cloned());
+                    }
+                }
+            }
+            _ => {}
+        }
+        for nested in step.nested_steps() {
+            collect_bound_names(nested, names);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,6 +231,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: binding.into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -125,6 +252,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: binding.into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -137,6 +266,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: "_".into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -149,6 +280,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: "_".into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -330,4 +463,67 @@ mod tests {
             other => panic!("Expected Bind after folding, got {:?}", other),
         }
     }
+
+    const CONST_PROGRAM: &str = r#"
+snippet id="consts.LIMIT" kind="const"
+signature
+  const name="LIMIT" type="Int" lit=3
+end
+end
+
+snippet id="app.double_limit" kind="fn"
+signature
+  fn name="double_limit"
+    param name="PARAM" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=mul
+    input var="consts.LIMIT"
+    input lit=2
+    as="doubled"
+  end
+  step id="s2" kind="return"
+    from="doubled"
+    as="_"
+  end
+end
+end
+"#;
+
+    fn optimize_const_program(param: &str) -> Vec<Step> {
+        let source = CONST_PROGRAM.replace("PARAM", param);
+        let Ok(covenant_ast::Program::Snippets { mut snippets, .. }) = covenant_parser::parse(&source) else {
+            panic!("expected snippets");
+        };
+        let settings = OptSettings {
+            level: OptLevel::O2,
+            emit_warnings: true,
+        };
+        optimize_program(&mut snippets, &settings);
+        snippets[1].sections.iter().find_map(|section| match section {
+            Section::Body(body) => Some(body.steps.clone()),
+            _ => None,
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_optimize_program_inlines_and_folds_constants() {
+        let steps = optimize_const_program("n");
+        match &steps[0].kind {
+            StepKind::Bind(bind) => match &bind.source {
+                BindSource::Lit(Literal::Int(6)) => {}
+                other => panic!("Expected Int(6), got {:?}", other),
+            },
+            other => panic!("Expected Bind after folding, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_param_shadows_constant_namespace() {
+        let steps = optimize_const_program("consts");
+        assert!(matches!(steps[0].kind, StepKind::Compute(_)));
+    }
 }
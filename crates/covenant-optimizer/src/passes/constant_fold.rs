@@ -1,11 +1,15 @@
 //! Constant folding pass
 //!
 //! Evaluates operations on literal values at compile time, replacing compute
-//! steps with bind steps containing the result.
+//! steps with bind steps containing the result. References to const snippets
+//! are inlined first, so expressions over named constants fold too.
 
-use covenant_ast::{BindSource, BindStep, ComputeStep, InputSource, Operation, Step, StepKind};
-use covenant_ast::Literal;
+use std::collections::HashMap;
 
+use covenant_ast::{BindSource, BindStep, ComputeStep, InputSource, Operation, ReturnValue, Step, StepKind};
+use covenant_ast::{time, Literal};
+
+use crate::encoding;
 use crate::passes::{OptContext, OptimizationPass, PassResult};
 
 /// Constant folding optimization pass
@@ -19,8 +23,8 @@ impl OptimizationPass for ConstantFolding {
         "constant-folding"
     }
 
-    fn run(&self, steps: &mut Vec<Step>, _ctx: &OptContext) -> PassResult {
-        let mut modified = false;
+    fn run(&self, steps: &mut Vec<Step>, ctx: &OptContext) -> PassResult {
+        let mut modified = inline_constants(steps, &ctx.constants);
 
         for step in steps.iter_mut() {
             if let StepKind::Compute(compute) = &step.kind {
@@ -42,6 +46,70 @@ impl OptimizationPass for ConstantFolding {
     }
 }
 
+/// Replace references to const snippets with their values, in nested steps too
+fn inline_constants(steps: &mut [Step], constants: &HashMap<String, Literal>) -> bool {
+    if constants.is_empty() {
+        return false;
+    }
+    let mut modified = false;
+
+    for step in steps.iter_mut() {
+        match &mut step.kind {
+            StepKind::Compute(compute) => {
+                for input in &mut compute.inputs {
+                    modified |= inline_input(&mut input.source, constants);
+                }
+            }
+            StepKind::Call(call) => {
+                for arg in &mut call.args {
+                    modified |= inline_input(&mut arg.source, constants);
+                }
+            }
+            StepKind::Bind(bind) => {
+                let value = match &bind.source {
+                    BindSource::Var(name) => constants.get(name),
+                    BindSource::Field { of, field } => constants.get(&format!("{}.{}", of, field)),
+                    _ => None,
+                };
+                if let Some(lit) = value {
+                    bind.source = BindSource::Lit(lit.clone());
+                    modified = true;
+                }
+            }
+            StepKind::Return(ret) => {
+                if let ReturnValue::Var(name) = &ret.value {
+                    if let Some(lit) = constants.get(name) {
+                        ret.value = ReturnValue::Lit(lit.clone());
+                        modified = true;
+                    }
+                }
+            }
+            _ => {}
+        }
+        for nested in step.nested_steps_mut() {
+            modified |= inline_constants(nested, constants);
+        }
+    }
+
+    modified
+}
+
+/// Replace an input naming a const snippet with its value
+fn inline_input(source: &mut InputSource, constants: &HashMap<String, Literal>) -> bool {
+    let value = match source {
+        InputSource::Var(name) => constants.get(name.as_str()),
+        InputSource::Field { of, field } => constants.get(&format!("{}.{}", of, field)),
+        InputSource::Lit(_) => None,
+    };
+    match value {
+        Some(lit) => {
+            *source = InputSource::Lit(lit.clone());
+            true
+        }
+        None => false,
+    }
+}
+
 /// Try to fold a compute step into a literal
 fn try_fold(compute: &ComputeStep) -> Option<Literal> {
     // Collect all inputs - only fold if ALL are literals
@@ -65,17 +133,30 @@ fn try_fold(compute: &ComputeStep) -> Option<Literal> {
 /// Fold an operation with literal inputs
 fn fold_operation(op: Operation, inputs: &[&Literal]) -> Option<Literal> {
     match op {
-        // Arithmetic (binary)
-        Operation::Add => fold_binary_arithmetic(inputs, |a, b| a + b, |a, b| a + b),
-        Operation::Sub => fold_binary_arithmetic(inputs, |a, b| a - b, |a, b| a - b),
-        Operation::Mul => fold_binary_arithmetic(inputs, |a, b| a * b, |a, b| a * b),
+        // Arithmetic (binary). Int overflow is left unfolded so it traps at
+        // runtime, the same as division by zero
+        Operation::Add => fold_binary_arithmetic_checked(inputs, i64::checked_add, |a, b| Some(a + b)),
+        Operation::Sub => fold_binary_arithmetic_checked(inputs, i64::checked_sub, |a, b| Some(a - b)),
+        Operation::Mul => fold_binary_arithmetic_checked(inputs, i64::checked_mul, |a, b| Some(a * b)),
         Operation::Div => fold_binary_arithmetic_checked(inputs,
-            |a, b| if b != 0 { Some(a / b) } else { None },
+            i64::checked_div,
             |a, b| if b != 0.0 { Some(a / b) } else { None }),
         Operation::Mod => fold_binary_arithmetic_checked(inputs,
-            |a, b| if b != 0 { Some(a % b) } else { None },
+            i64::checked_rem,
             |_, _| None), // Mod for floats not supported
 
+        // Overflow-explicit Int arithmetic. Release builds lower plain
+        // add/sub/mul to the wrapping forms, so those still fold floats
+        Operation::AddWrapping => fold_binary_arithmetic_checked(inputs, |a, b| Some(a.wrapping_add(b)), |a, b| Some(a + b)),
+        Operation::SubWrapping => fold_binary_arithmetic_checked(inputs, |a, b| Some(a.wrapping_sub(b)), |a, b| Some(a - b)),
+        Operation::MulWrapping => fold_binary_arithmetic_checked(inputs, |a, b| Some(a.wrapping_mul(b)), |a, b| Some(a * b)),
+        Operation::AddSaturating => fold_int_arithmetic(inputs, |a, b| Literal::Int(a.saturating_add(b))),
+        Operation::SubSaturating => fold_int_arithmetic(inputs, |a, b| Literal::Int(a.saturating_sub(b))),
+        Operation::MulSaturating => fold_int_arithmetic(inputs, |a, b| Literal::Int(a.saturating_mul(b))),
+        Operation::AddChecked => fold_int_arithmetic(inputs, |a, b| a.checked_add(b).map_or(Literal::None, Literal::Int)),
+        Operation::SubChecked => fold_int_arithmetic(inputs, |a, b| a.checked_sub(b).map_or(Literal::None, Literal::Int)),
+        Operation::MulChecked => fold_int_arithmetic(inputs, |a, b| a.checked_mul(b).map_or(Literal::None, Literal::Int)),
+
         // Comparison
         Operation::Equals => fold_equals(inputs),
         Operation::NotEquals => fold_not_equals(inputs),
@@ -95,26 +176,154 @@ fn fold_operation(op: Operation, inputs: &[&Literal]) -> Option<Literal> {
         Operation::Min => fold_min_max(inputs, true),
         Operation::Max => fold_min_max(inputs, false),
 
+        // Conversions: to_int truncates like trunc; round is half away from zero
+        Operation::ToInt | Operation::Trunc => fold_to_int(inputs, f64::trunc),
+        Operation::Floor => fold_to_int(inputs, f64::floor),
+        Operation::Ceil => fold_to_int(inputs, f64::ceil),
+        Operation::Round => fold_to_int(inputs, f64::round),
+        Operation::ToFloat => fold_to_float(inputs),
+
+        // Collections
+        Operation::MapLen | Operation::SetLen => fold_len(inputs),
+        Operation::MapIsEmpty | Operation::SetIsEmpty => {
+            fold_len(inputs).map(|len| Literal::Bool(len == Literal::Int(0)))
+        }
+        Operation::MapHas | Operation::SetHas => fold_has(inputs),
+        Operation::MapKeys => fold_map_entries(inputs, |key, _| key),
+        Operation::MapValues => fold_map_entries(inputs, |_, value| value),
+        Operation::SetToList => match inputs {
+            [Literal::Set(items)] => Some(Literal::List(distinct(items))),
+            _ => None,
+        },
+
+        // Optional
+        Operation::IsSome | Operation::IsNone => match inputs {
+            [value] => Some(Literal::Bool((**value == Literal::None) == (op == Operation::IsNone))),
+            _ => None,
+        },
+        Operation::UnwrapOr => match inputs {
+            [Literal::None, default] => Some((*default).clone()),
+            [value, _] => Some((*value).clone()),
+            _ => None,
+        },
+
+        // Encoding. Bytes have no literal form, so only the String codecs
+        // fold; malformed input is left for the runtime's DecodeError
+        Operation::UrlEncode => match inputs {
+            [Literal::String(s)] => Some(Literal::String(encoding::url_encode(s))),
+            _ => None,
+        },
+        Operation::UrlDecode => match inputs {
+            [Literal::String(s)] => encoding::url_decode(s).map(Literal::String),
+            _ => None,
+        },
+
+        // Durations and DateTimes fold to their literals. Results past the
+        // Int range of seconds are left to trap at runtime, and DateTimes
+        // outside the years a literal can write are left unfolded
+        Operation::DurSeconds => fold_duration_unit(inputs, 1),
+        Operation::DurMinutes => fold_duration_unit(inputs, 60),
+        Operation::DurHours => fold_duration_unit(inputs, 60 * 60),
+        Operation::DurDays => fold_duration_unit(inputs, 24 * 60 * 60),
+        Operation::DurTotalSeconds => match inputs {
+            [Literal::Duration(seconds)] => Some(Literal::Int(*seconds)),
+            _ => None,
+        },
+        Operation::DurAdd | Operation::DurSub => match inputs {
+            [Literal::Duration(a), Literal::Duration(b)] => {
+                let sum = if op == Operation::DurAdd { a.checked_add(*b) } else { a.checked_sub(*b) };
+                sum.map(Literal::Duration)
+            }
+            _ => None,
+        },
+        Operation::DurScale => match inputs {
+            [Literal::Duration(seconds), Literal::Int(factor)] => seconds.checked_mul(*factor).map(Literal::Duration),
+            _ => None,
+        },
+        Operation::DurFormat => match inputs {
+            [Literal::Duration(seconds)] => Some(Literal::String(format_duration(*seconds))),
+            _ => None,
+        },
+        Operation::DtAdd | Operation::DtSub => match inputs {
+            [Literal::DateTime(at), Literal::Duration(by)] => {
+                let moved = if op == Operation::DtAdd { at.checked_add(*by) } else { at.checked_sub(*by) };
+                moved
+                    .filter(|seconds| (time::MIN_DATETIME..=time::MAX_DATETIME).contains(seconds))
+                    .map(Literal::DateTime)
+            }
+            _ => None,
+        },
+        Operation::DtDiff => match inputs {
+            [Literal::DateTime(a), Literal::DateTime(b)] => a.checked_sub(*b).map(Literal::Duration),
+            _ => None,
+        },
+        Operation::DtUnix => match inputs {
+            [Literal::DateTime(seconds)] => Some(Literal::Int(*seconds)),
+            _ => None,
+        },
+        Operation::DtYear | Operation::DtMonth | Operation::DtDay |
+        Operation::DtHour | Operation::DtMinute | Operation::DtSecond => match inputs {
+            [Literal::DateTime(seconds)] => Some(Literal::Int(datetime_field(op, *seconds))),
+            _ => None,
+        },
+
         // String operations removed — now extern-abstract calls, not compute ops
         // Everything else can't be folded (or isn't worth the complexity)
         _ => None,
     }
 }
 
-fn fold_binary_arithmetic<F, G>(inputs: &[&Literal], int_op: F, float_op: G) -> Option<Literal>
+fn fold_duration_unit(inputs: &[&Literal], unit_seconds: i64) -> Option<Literal> {
+    match inputs {
+        [Literal::Int(count)] => count.checked_mul(unit_seconds).map(Literal::Duration),
+        _ => None,
+    }
+}
+
+/// A calendar field of a DateTime in UTC
+fn datetime_field(op: Operation, seconds: i64) -> i64 {
+    let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    let (year, month, day) = time::civil_from_days(days);
+    match op {
+        Operation::DtYear => year,
+        Operation::DtMonth => month,
+        Operation::DtDay => day,
+        Operation::DtHour => time / 3_600,
+        Operation::DtMinute => time / 60 % 60,
+        _ => time % 60,
+    }
+}
+
+/// ISO 8601 text for a Duration of `seconds`, in days, hours, minutes and
+/// seconds (`P1DT2H30M`, `PT0S`), with a leading `-` when negative
+fn format_duration(seconds: i64) -> String {
+    let total = seconds.unsigned_abs();
+    let (days, hours, minutes, secs) = (total / 86_400, total / 3_600 % 24, total / 60 % 60, total % 60);
+
+    let mut text = String::from(if seconds < 0 { "-P" } else { "P" });
+    if days > 0 {
+        text.push_str(&format!("{}D", days));
+    }
+    if hours > 0 || minutes > 0 || secs > 0 || days == 0 {
+        text.push('T');
+        for (value, unit) in [(hours, 'H'), (minutes, 'M')] {
+            if value > 0 {
+                text.push_str(&format!("{}{}", value, unit));
+            }
+        }
+        if secs > 0 || total == 0 {
+            text.push_str(&format!("{}S", secs));
+        }
+    }
+    text
+}
+
+fn fold_int_arithmetic<F>(inputs: &[&Literal], int_op: F) -> Option<Literal>
 where
-    F: Fn(i64, i64) -> i64,
-    G: Fn(f64, f64) -> f64,
+    F: Fn(i64, i64) -> Literal,
 {
-    if inputs.len() != 2 {
-        return None;
-    }
-    match (inputs[0], inputs[1]) {
-        (Literal::Int(a), Literal::Int(b)) => Some(Literal::Int(int_op(*a, *b))),
-        (Literal::Float(a), Literal::Float(b)) => Some(Literal::Float(float_op(*a, *b))),
-        // Mixed int/float - promote to float
-        (Literal::Int(a), Literal::Float(b)) => Some(Literal::Float(float_op(*a as f64, *b))),
-        (Literal::Float(a), Literal::Int(b)) => Some(Literal::Float(float_op(*a, *b as f64))),
+    match inputs {
+        [Literal::Int(a), Literal::Int(b)] => Some(int_op(*a, *b)),
         _ => None,
     }
 }
@@ -130,6 +339,9 @@ where
     match (inputs[0], inputs[1]) {
         (Literal::Int(a), Literal::Int(b)) => int_op(*a, *b).map(Literal::Int),
         (Literal::Float(a), Literal::Float(b)) => float_op(*a, *b).map(Literal::Float),
+        // Mixed int/float - promote to float
+        (Literal::Int(a), Literal::Float(b)) => float_op(*a as f64, *b).map(Literal::Float),
+        (Literal::Float(a), Literal::Int(b)) => float_op(*a, *b as f64).map(Literal::Float),
         _ => None,
     }
 }
@@ -138,14 +350,46 @@ fn fold_equals(inputs: &[&Literal]) -> Option<Literal> {
     if inputs.len() != 2 {
         return None;
     }
-    let result = match (inputs[0], inputs[1]) {
+    literal_equals(inputs[0], inputs[1]).map(Literal::Bool)
+}
+
+/// Compare two literals, or None if they are of different kinds
+///
+/// Sets and maps compare without regard to order.
+fn literal_equals(a: &Literal, b: &Literal) -> Option<bool> {
+    let result = match (a, b) {
         (Literal::Int(a), Literal::Int(b)) => a == b,
         (Literal::Float(a), Literal::Float(b)) => (a - b).abs() < f64::EPSILON,
         (Literal::Bool(a), Literal::Bool(b)) => a == b,
+        (Literal::DateTime(a), Literal::DateTime(b)) | (Literal::Duration(a), Literal::Duration(b)) => a == b,
         (Literal::String(a), Literal::String(b)) => a == b,
+        (Literal::List(a), Literal::List(b)) | (Literal::Tuple(a), Literal::Tuple(b)) => {
+            if a.len() != b.len() {
+                return Some(false);
+            }
+            for (x, y) in a.iter().zip(b) {
+                if !literal_equals(x, y)? {
+                    return Some(false);
+                }
+            }
+            true
+        }
+        (Literal::Set(a), Literal::Set(b)) => {
+            let (a, b) = (distinct(a), distinct(b));
+            a.len() == b.len() && a.iter().all(|x| contains(&b, x))
+        }
+        (Literal::Map(a), Literal::Map(b)) => {
+            let (a, b) = (distinct_entries(a), distinct_entries(b));
+            a.len() == b.len()
+                && a.iter().all(|(key, value)| {
+                    b.iter().any(|(k, v)| {
+                        literal_equals(key, k) == Some(true) && literal_equals(value, v) == Some(true)
+                    })
+                })
+        }
         _ => return None,
     };
-    Some(Literal::Bool(result))
+    Some(result)
 }
 
 fn fold_not_equals(inputs: &[&Literal]) -> Option<Literal> {
@@ -169,6 +413,9 @@ where
         (Literal::Float(a), Literal::Float(b)) => Some(Literal::Bool(cmp(*a, *b))),
         (Literal::Int(a), Literal::Float(b)) => Some(Literal::Bool(cmp(*a as f64, *b))),
         (Literal::Float(a), Literal::Int(b)) => Some(Literal::Bool(cmp(*a, *b as f64))),
+        (Literal::DateTime(a), Literal::DateTime(b)) | (Literal::Duration(a), Literal::Duration(b)) => {
+            Some(Literal::Bool(cmp(*a as f64, *b as f64)))
+        }
         _ => None,
     }
 }
@@ -204,7 +451,8 @@ fn fold_negation(inputs: &[&Literal]) -> Option<Literal> {
         return None;
     }
     match inputs[0] {
-        Literal::Int(a) => Some(Literal::Int(-*a)),
+        // -i64::MIN overflows, which traps at runtime
+        Literal::Int(a) => a.checked_neg().map(Literal::Int),
         Literal::Float(a) => Some(Literal::Float(-*a)),
         _ => None,
     }
@@ -215,12 +463,35 @@ fn fold_abs(inputs: &[&Literal]) -> Option<Literal> {
         return None;
     }
     match inputs[0] {
-        Literal::Int(a) => Some(Literal::Int(a.abs())),
+        Literal::Int(a) => a.checked_abs().map(Literal::Int),
         Literal::Float(a) => Some(Literal::Float(a.abs())),
         _ => None,
     }
 }
 
+/// Fold a Float to Int conversion, leaving NaN and results out of Int range
+/// unfolded so they trap at runtime
+fn fold_to_int(inputs: &[&Literal], rounding: fn(f64) -> f64) -> Option<Literal> {
+    match inputs {
+        [Literal::Int(a)] => Some(Literal::Int(*a)),
+        [Literal::Float(a)] => {
+            let rounded = rounding(*a);
+            // -2^63 converts exactly; 2^63 is one past i64::MAX
+            (rounded >= i64::MIN as f64 && rounded < -(i64::MIN as f64)).then_some(Literal::Int(rounded as i64))
+        }
+        _ => None,
+    }
+}
+
+/// Fold an Int to Float conversion, rounding to the nearest Float like the runtime
+fn fold_to_float(inputs: &[&Literal]) -> Option<Literal> {
+    match inputs {
+        [Literal::Int(a)] => Some(Literal::Float(*a as f64)),
+        [Literal::Float(a)] => Some(Literal::Float(*a)),
+        _ => None,
+    }
+}
+
 fn fold_min_max(inputs: &[&Literal], is_min: bool) -> Option<Literal> {
     if inputs.len() != 2 {
         return None;
@@ -236,6 +507,63 @@ fn fold_min_max(inputs: &[&Literal], is_min: bool) -> Option<Literal> {
     }
 }
 
+fn fold_len(inputs: &[&Literal]) -> Option<Literal> {
+    match inputs {
+        [Literal::Set(items)] => Some(Literal::Int(distinct(items).len() as i64)),
+        [Literal::Map(entries)] => Some(Literal::Int(distinct_entries(entries).len() as i64)),
+        _ => None,
+    }
+}
+
+fn fold_has(inputs: &[&Literal]) -> Option<Literal> {
+    match inputs {
+        [Literal::Set(items), item] => Some(Literal::Bool(contains(items, item))),
+        [Literal::Map(entries), key] => {
+            Some(Literal::Bool(entries.iter().any(|(k, _)| literal_equals(k, key) == Some(true))))
+        }
+        _ => None,
+    }
+}
+
+fn fold_map_entries<F>(inputs: &[&Literal], pick: F) -> Option<Literal>
+where
+    F: Fn(Literal, Literal) -> Literal,
+{
+    match inputs {
+        [Literal::Map(entries)] => Some(Literal::List(
+            distinct_entries(entries).into_iter().map(|(k, v)| pick(k, v)).collect(),
+        )),
+        _ => None,
+    }
+}
+
+fn contains(items: &[Literal], item: &Literal) -> bool {
+    items.iter().any(|x| literal_equals(x, item) == Some(true))
+}
+
+/// Set elements with duplicates removed, in first-seen order
+fn distinct(items: &[Literal]) -> Vec<Literal> {
+    let mut seen: Vec<Literal> = Vec::new();
+    for item in items {
+        if !contains(&seen, item) {
+            seen.push(item.clone());
+        }
+    }
+    seen
+}
+
+/// Map entries with repeated keys resolved to the last value, in first-seen order
+fn distinct_entries(entries: &[(Literal, Literal)]) -> Vec<(Literal, Literal)> {
+    let mut seen: Vec<(Literal, Literal)> = Vec::new();
+    for (key, value) in entries {
+        match seen.iter_mut().find(|(k, _)| literal_equals(k, key) == Some(true)) {
+            Some(entry) => entry.1 = value.clone(),
+            None => seen.push((key.clone(), value.clone())),
+        }
+    }
+    seen
+}
+
 // String folding functions removed — string operations are now extern-abstract calls
 
 #[cfg(test)]
@@ -262,6 +590,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: binding.into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -272,6 +602,7 @@ mod tests {
                 level: crate::passes::OptLevel::O2,
                 emit_warnings: true,
             },
+            constants: Default::default(),
         }
     }
 
@@ -429,6 +760,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fold_set_equals_ignores_order() {
+        let mut steps = vec![make_compute_step(
+            "s1",
+            "result",
+            Operation::Equals,
+            vec![
+                Literal::Set(vec![Literal::Int(1), Literal::Int(2)]),
+                Literal::Set(vec![Literal::Int(2), Literal::Int(1), Literal::Int(2)]),
+            ],
+        )];
+
+        let pass = ConstantFolding;
+        let result = pass.run(&mut steps, &make_ctx());
+
+        assert!(result.modified);
+        match &steps[0].kind {
+            StepKind::Bind(bind) => match &bind.source {
+                BindSource::Lit(Literal::Bool(true)) => {}
+                other => panic!("Expected Bool(true), got {:?}", other),
+            },
+            _ => panic!("Expected Bind"),
+        }
+    }
+
+    #[test]
+    fn test_fold_map_ops() {
+        let map = Literal::Map(vec![
+            (Literal::String("a".into()), Literal::Int(1)),
+            (Literal::String("b".into()), Literal::Int(2)),
+            (Literal::String("a".into()), Literal::Int(3)),
+        ]);
+        let mut steps = vec![
+            make_compute_step("s1", "len", Operation::MapLen, vec![map.clone()]),
+            make_compute_step("s2", "has", Operation::MapHas, vec![map.clone(), Literal::String("b".into())]),
+            make_compute_step("s3", "values", Operation::MapValues, vec![map]),
+        ];
+
+        let pass = ConstantFolding;
+        pass.run(&mut steps, &make_ctx());
+
+        let folded: Vec<&Literal> = steps
+            .iter()
+            .map(|step| match &step.kind {
+                StepKind::Bind(BindStep { source: BindSource::Lit(lit), .. }) => lit,
+                other => panic!("Expected Bind, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(folded[0], &Literal::Int(2));
+        assert_eq!(folded[1], &Literal::Bool(true));
+        assert_eq!(folded[2], &Literal::List(vec![Literal::Int(3), Literal::Int(2)]));
+    }
+
     // test_fold_string_concat removed — Concat is now an extern-abstract call
 
     #[test]
@@ -450,6 +834,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: "result".into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }];
 
@@ -474,4 +860,215 @@ mod tests {
 
         assert!(!result.modified); // Division by zero is not folded
     }
+
+    #[test]
+    fn test_fold_overflowing_add_no_fold() {
+        let mut steps = vec![make_compute_step(
+            "s1",
+            "result",
+            Operation::Add,
+            vec![Literal::Int(i64::MAX), Literal::Int(1)],
+        )];
+
+        let pass = ConstantFolding;
+        let result = pass.run(&mut steps, &make_ctx());
+
+        assert!(!result.modified); // Left to trap at runtime
+    }
+
+    #[test]
+    fn test_fold_overflowing_neg_and_abs_no_fold() {
+        for op in [Operation::Neg, Operation::Abs] {
+            let mut steps = vec![make_compute_step("s1", "result", op, vec![Literal::Int(i64::MIN)])];
+
+            let result = ConstantFolding.run(&mut steps, &make_ctx());
+
+            assert!(!result.modified, "{:?} of i64::MIN must be left to trap at runtime", op);
+        }
+        assert_eq!(fold_operation(Operation::Neg, &[&Literal::Int(i64::MIN + 1)]), Some(Literal::Int(i64::MAX)));
+        assert_eq!(fold_operation(Operation::Abs, &[&Literal::Int(i64::MIN + 1)]), Some(Literal::Int(i64::MAX)));
+    }
+
+    #[test]
+    fn test_fold_overflow_explicit_ops() {
+        let cases = [
+            (Operation::AddWrapping, Literal::Int(i64::MIN)),
+            (Operation::AddSaturating, Literal::Int(i64::MAX)),
+            (Operation::AddChecked, Literal::None),
+            (Operation::SubChecked, Literal::Int(i64::MAX - 1)),
+        ];
+        for (op, expected) in cases {
+            let mut steps = vec![make_compute_step(
+                "s1",
+                "result",
+                op,
+                vec![Literal::Int(i64::MAX), Literal::Int(1)],
+            )];
+
+            ConstantFolding.run(&mut steps, &make_ctx());
+
+            match &steps[0].kind {
+                StepKind::Bind(bind) => match &bind.source {
+                    BindSource::Lit(lit) => assert_eq!(lit, &expected, "{:?}", op),
+                    other => panic!("Expected literal, got {:?}", other),
+                },
+                other => panic!("Expected Bind, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold_conversions() {
+        let cases = [
+            (Operation::ToInt, Literal::Float(-2.7), Literal::Int(-2)),
+            (Operation::Trunc, Literal::Float(2.7), Literal::Int(2)),
+            (Operation::Floor, Literal::Float(-2.5), Literal::Int(-3)),
+            (Operation::Ceil, Literal::Float(-2.5), Literal::Int(-2)),
+            (Operation::Round, Literal::Float(2.5), Literal::Int(3)),
+            (Operation::Round, Literal::Float(-2.5), Literal::Int(-3)),
+            (Operation::ToInt, Literal::Float(-9223372036854775808.0), Literal::Int(i64::MIN)),
+            (Operation::ToFloat, Literal::Int(3), Literal::Float(3.0)),
+            (Operation::ToFloat, Literal::Int(i64::MAX), Literal::Float(9223372036854775808.0)),
+        ];
+        for (op, input, expected) in cases {
+            let mut steps = vec![make_compute_step("s1", "result", op, vec![input])];
+
+            ConstantFolding.run(&mut steps, &make_ctx());
+
+            match &steps[0].kind {
+                StepKind::Bind(bind) => match &bind.source {
+                    BindSource::Lit(lit) => assert_eq!(lit, &expected, "{:?}", op),
+                    other => panic!("Expected literal, got {:?}", other),
+                },
+                other => panic!("Expected Bind, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold_durations() {
+        let cases = [
+            (Operation::DurMinutes, vec![Literal::Int(90)], Literal::Duration(5_400)),
+            (Operation::DurDays, vec![Literal::Int(-2)], Literal::Duration(-172_800)),
+            (Operation::DurScale, vec![Literal::Duration(3_600), Literal::Int(3)], Literal::Duration(10_800)),
+            (Operation::DurSub, vec![Literal::Duration(60), Literal::Duration(90)], Literal::Duration(-30)),
+            (Operation::DurTotalSeconds, vec![Literal::Duration(300)], Literal::Int(300)),
+            (Operation::DtDiff, vec![Literal::DateTime(1_000), Literal::DateTime(400)], Literal::Duration(600)),
+            (Operation::DurFormat, vec![Literal::Duration(0)], Literal::String("PT0S".into())),
+            (Operation::DurFormat, vec![Literal::Duration(5_400)], Literal::String("PT1H30M".into())),
+            (Operation::DurFormat, vec![Literal::Duration(86_400)], Literal::String("P1D".into())),
+            (Operation::DurFormat, vec![Literal::Duration(-93_784)], Literal::String("-P1DT2H3M4S".into())),
+        ];
+        for (op, inputs, expected) in cases {
+            let mut steps = vec![make_compute_step("s1", "result", op, inputs)];
+
+            ConstantFolding.run(&mut steps, &make_ctx());
+
+            match &steps[0].kind {
+                StepKind::Bind(bind) => match &bind.source {
+                    BindSource::Lit(lit) => assert_eq!(lit, &expected, "{:?}", op),
+                    other => panic!("Expected literal, got {:?}", other),
+                },
+                other => panic!("Expected Bind, got {:?}", other),
+            }
+        }
+
+        // A Duration past the Int range is left to trap at runtime
+        let mut steps = vec![make_compute_step("s1", "result", Operation::DurDays, vec![Literal::Int(i64::MAX / 1_000)])];
+        assert!(!ConstantFolding.run(&mut steps, &make_ctx()).modified);
+    }
+
+    #[test]
+    fn test_fold_datetimes() {
+        let new_year = Literal::DateTime(1_704_067_200); // 2024-01-01T00:00:00Z
+        let cases = [
+            (Operation::DtAdd, vec![new_year.clone(), Literal::Duration(5_400)], Literal::DateTime(1_704_072_600)),
+            (Operation::DtSub, vec![new_year.clone(), Literal::Duration(1)], Literal::DateTime(1_704_067_199)),
+            (Operation::DtYear, vec![Literal::DateTime(1_704_067_199)], Literal::Int(2023)),
+            (Operation::DtMonth, vec![Literal::DateTime(951_782_400)], Literal::Int(2)),
+            (Operation::DtDay, vec![Literal::DateTime(951_782_400)], Literal::Int(29)),
+            (Operation::DtMinute, vec![Literal::DateTime(1_704_072_600)], Literal::Int(30)),
+            (Operation::DtUnix, vec![new_year.clone()], Literal::Int(1_704_067_200)),
+            (Operation::Less, vec![Literal::DateTime(0), new_year.clone()], Literal::Bool(true)),
+            (Operation::Equals, vec![Literal::Duration(60), Literal::Duration(60)], Literal::Bool(true)),
+        ];
+        for (op, inputs, expected) in cases {
+            let mut steps = vec![make_compute_step("s1", "result", op, inputs)];
+
+            ConstantFolding.run(&mut steps, &make_ctx());
+
+            match &steps[0].kind {
+                StepKind::Bind(bind) => match &bind.source {
+                    BindSource::Lit(lit) => assert_eq!(lit, &expected, "{:?}", op),
+                    other => panic!("Expected literal, got {:?}", other),
+                },
+                other => panic!("Expected Bind, got {:?}", other),
+            }
+        }
+
+        // A DateTime before the years a literal can write is left to the runtime
+        let inputs = vec![Literal::DateTime(time::MIN_DATETIME), Literal::Duration(1)];
+        let mut steps = vec![make_compute_step("s1", "result", Operation::DtSub, inputs)];
+        assert!(!ConstantFolding.run(&mut steps, &make_ctx()).modified);
+    }
+
+    #[test]
+    fn test_fold_optionals() {
+        let cases = [
+            (Operation::IsSome, vec![Literal::None], Literal::Bool(false)),
+            (Operation::IsSome, vec![Literal::Int(0)], Literal::Bool(true)),
+            (Operation::IsNone, vec![Literal::None], Literal::Bool(true)),
+            (Operation::UnwrapOr, vec![Literal::None, Literal::Int(7)], Literal::Int(7)),
+            (Operation::UnwrapOr, vec![Literal::Int(3), Literal::Int(7)], Literal::Int(3)),
+        ];
+        for (op, inputs, expected) in cases {
+            let mut steps = vec![make_compute_step("s1", "result", op, inputs)];
+
+            ConstantFolding.run(&mut steps, &make_ctx());
+
+            match &steps[0].kind {
+                StepKind::Bind(bind) => match &bind.source {
+                    BindSource::Lit(lit) => assert_eq!(lit, &expected, "{:?}", op),
+                    other => panic!("Expected literal, got {:?}", other),
+                },
+                other => panic!("Expected Bind, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold_out_of_range_to_int_no_fold() {
+        for input in [9223372036854775808.0, f64::NAN, f64::INFINITY] {
+            let mut steps = vec![make_compute_step("s1", "result", Operation::ToInt, vec![Literal::Float(input)])];
+
+            let result = ConstantFolding.run(&mut steps, &make_ctx());
+
+            assert!(!result.modified, "{} should be left to trap at runtime", input);
+        }
+    }
+
+    #[test]
+    fn test_fold_url_encoding() {
+        let mut steps = vec![
+            make_compute_step("s1", "encoded", Operation::UrlEncode, vec![Literal::String("a b/é".into())]),
+            make_compute_step("s2", "decoded", Operation::UrlDecode, vec![Literal::String("a%20b%2F%C3%A9".into())]),
+        ];
+
+        ConstantFolding.run(&mut steps, &make_ctx());
+
+        let folded: Vec<_> = steps.iter().map(|step| match &step.kind {
+            StepKind::Bind(BindStep { source: BindSource::Lit(lit), .. }) => lit.clone(),
+            other => panic!("Expected literal Bind, got {:?}", other),
+        }).collect();
+        assert_eq!(folded, vec![Literal::String("a%20b%2F%C3%A9".into()), Literal::String("a b/é".into())]);
+    }
+
+    #[test]
+    fn test_fold_malformed_url_decode_no_fold() {
+        let mut steps = vec![make_compute_step("s1", "decoded", Operation::UrlDecode, vec![Literal::String("%zz".into())])];
+
+        let result = ConstantFolding.run(&mut steps, &make_ctx());
+
+        assert!(!result.modified, "malformed input should decode to DecodeError at runtime");
+    }
 }
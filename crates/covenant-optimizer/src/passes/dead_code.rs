@@ -79,12 +79,14 @@ pub fn step_has_effects(kind: &StepKind) -> bool {
     matches!(
         kind,
         StepKind::Call(_)
+            | StepKind::Pipeline(_)
             | StepKind::Query(_)
             | StepKind::Insert(_)
             | StepKind::Update(_)
             | StepKind::Delete(_)
             | StepKind::Transaction(_)
             | StepKind::Traverse(_)
+            | StepKind::Using(_)
     )
 }
 
@@ -106,6 +108,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: "_".into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -118,6 +122,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: binding.into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -140,6 +146,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: binding.into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -150,6 +158,7 @@ mod tests {
                 level,
                 emit_warnings: true,
             },
+            constants: Default::default(),
         }
     }
 
@@ -212,6 +221,8 @@ mod tests {
                     span: make_span(),
                 }),
                 output_binding: "_".into(),
+                doc: None,
+                node_id: None,
                 span: make_span(),
             },
         ];
@@ -0,0 +1,146 @@
+//! Debug check stripping pass
+//!
+//! Assert steps and integer overflow traps are checked in the dev profile
+//! only. At O2 and above this pass removes asserts and turns `add`, `sub`
+//! and `mul` into their wrapping forms, nested steps included, so release
+//! builds pay nothing for them. Struct invariant sections are removed by
+//! [`crate::optimize_snippet`], since they live outside function bodies.
+
+use covenant_ast::{Operation, Step, StepKind};
+
+use crate::passes::{OptContext, OptimizationPass, PassResult};
+
+/// Removes assert steps and overflow traps
+pub struct StripDebugChecks;
+
+impl OptimizationPass for StripDebugChecks {
+    fn name(&self) -> &'static str {
+        "strip-debug-checks"
+    }
+
+    fn run(&self, steps: &mut Vec<Step>, _ctx: &OptContext) -> PassResult {
+        PassResult {
+            modified: strip_checks(steps),
+            warnings: vec![],
+        }
+    }
+}
+
+/// Remove assert steps and overflow traps from `steps` and every nested step list
+fn strip_checks(steps: &mut Vec<Step>) -> bool {
+    let original_len = steps.len();
+    steps.retain(|s| !matches!(s.kind, StepKind::Assert(_)));
+    let mut modified = steps.len() != original_len;
+    for step in steps.iter_mut() {
+        if let StepKind::Compute(compute) = &mut step.kind {
+            if let Some(wrapping) = wrapping_op(compute.op) {
+                compute.op = wrapping;
+                modified = true;
+            }
+        }
+        for nested in step.nested_steps_mut() {
+            modified |= strip_checks(nested);
+        }
+    }
+    modified
+}
+
+/// The wrapping form of a trapping arithmetic operation
+fn wrapping_op(op: Operation) -> Option<Operation> {
+    match op {
+        Operation::Add => Some(Operation::AddWrapping),
+        Operation::Sub => Some(Operation::SubWrapping),
+        Operation::Mul => Some(Operation::MulWrapping),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use covenant_ast::{AssertStep, ComputeStep, IfStep, Input, InputSource, Literal, Span};
+    use crate::passes::{OptLevel, OptSettings};
+
+    fn make_assert_step(id: &str) -> Step {
+        Step {
+            id: id.into(),
+            kind: StepKind::Assert(AssertStep {
+                op: Operation::Equals,
+                inputs: vec![
+                    Input { source: InputSource::Lit(Literal::Int(1)), span: Span::dummy() },
+                    Input { source: InputSource::Lit(Literal::Int(1)), span: Span::dummy() },
+                ],
+                message: None,
+                span: Span::dummy(),
+            }),
+            output_binding: "_".into(),
+            doc: None,
+            node_id: None,
+            span: Span::dummy(),
+        }
+    }
+
+    #[test]
+    fn test_strips_nested_asserts() {
+        let mut steps = vec![
+            make_assert_step("s1"),
+            Step {
+                id: "s2".into(),
+                kind: StepKind::If(IfStep {
+                    condition: InputSource::Lit(Literal::Bool(true)),
+                    then_steps: vec![make_assert_step("s2a")],
+                    else_steps: None,
+                    span: Span::dummy(),
+                }),
+                output_binding: "_".into(),
+                doc: None,
+                node_id: None,
+                span: Span::dummy(),
+            },
+        ];
+        let ctx = OptContext {
+            settings: OptSettings { level: OptLevel::O2, emit_warnings: true },
+            constants: Default::default(),
+        };
+
+        let result = StripDebugChecks.run(&mut steps, &ctx);
+
+        assert!(result.modified);
+        assert_eq!(steps.len(), 1);
+        let StepKind::If(if_step) = &steps[0].kind else {
+            panic!("expected if step");
+        };
+        assert!(if_step.then_steps.is_empty());
+    }
+
+    #[test]
+    fn test_lowers_trapping_arithmetic_to_wrapping() {
+        let mut steps = vec![Step {
+            id: "s1".into(),
+            kind: StepKind::Compute(ComputeStep {
+                op: Operation::Mul,
+                inputs: vec![
+                    Input { source: InputSource::Var("x".into()), span: Span::dummy() },
+                    Input { source: InputSource::Lit(Literal::Int(2)), span: Span::dummy() },
+                ],
+                span: Span::dummy(),
+            }),
+            output_binding: "y".into(),
+            doc: None,
+            node_id: None,
+            span: Span::dummy(),
+        }];
+        let ctx = OptContext {
+            settings: OptSettings { level: OptLevel::O2, emit_warnings: true },
+            constants: Default::default(),
+        };
+
+        let result = StripDebugChecks.run(&mut steps, &ctx);
+
+        assert!(result.modified);
+        let StepKind::Compute(compute) = &steps[0].kind else {
+            panic!("expected compute step");
+        };
+        assert_eq!(compute.op, Operation::MulWrapping);
+    }
+}
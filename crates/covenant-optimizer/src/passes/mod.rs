@@ -4,13 +4,17 @@
 
 pub mod constant_fold;
 pub mod dead_code;
+pub mod debug_checks;
 pub mod unused_binding;
 
 pub use constant_fold::ConstantFolding;
 pub use dead_code::DeadCodeElimination;
+pub use debug_checks::StripDebugChecks;
 pub use unused_binding::UnusedBindingDetection;
 
-use covenant_ast::Step;
+use std::collections::HashMap;
+
+use covenant_ast::{Literal, Step};
 
 /// Optimization level controlling which passes run
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
@@ -20,7 +24,7 @@ pub enum OptLevel {
     O0,
     /// Basic optimizations (dead code detection, warnings only)
     O1,
-    /// Standard optimizations (constant folding + dead code removal)
+    /// Standard optimizations (constant folding + dead code removal), without debug checks
     O2,
     /// Aggressive optimizations (all passes)
     O3,
@@ -48,6 +52,8 @@ impl Default for OptSettings {
 #[derive(Debug, Clone)]
 pub struct OptContext {
     pub settings: OptSettings,
+    /// Values of the const snippets the body may inline, by snippet ID
+    pub constants: HashMap<String, Literal>,
 }
 
 /// Result of running an optimization pass
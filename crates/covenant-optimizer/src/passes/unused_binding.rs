@@ -82,6 +82,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: binding.into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -91,11 +93,15 @@ mod tests {
             id: id.into(),
             kind: StepKind::Call(CallStep {
                 fn_name: "some.function".into(),
+                type_args: vec![],
                 args: vec![],
                 handle: None,
+                expects: vec![],
                 span: make_span(),
             }),
             output_binding: binding.into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -108,6 +114,8 @@ mod tests {
                 span: make_span(),
             }),
             output_binding: "_".into(),
+            doc: None,
+            node_id: None,
             span: make_span(),
         }
     }
@@ -118,6 +126,7 @@ mod tests {
                 level: crate::passes::OptLevel::O1,
                 emit_warnings: true,
             },
+            constants: Default::default(),
         }
     }
 
@@ -46,6 +46,24 @@ pub enum ParseError {
 
     #[error("unexpected section: {section}")]
     UnexpectedSection { section: String, span: Span },
+
+    #[error("invalid isolation level: {level} (expected read_uncommitted, read_committed, repeatable_read, or serializable)")]
+    InvalidIsolationLevel { level: String, span: Span },
+
+    #[error("invalid {kind} literal \"{text}\": {reason}")]
+    InvalidTimeLiteral { kind: String, text: String, reason: String, span: Span },
+
+    #[error("parsing cancelled")]
+    Cancelled { span: Span },
+
+    #[error("steps nested deeper than the limit of {limit}; extract inner steps into a separate snippet")]
+    NestingTooDeep { limit: usize, span: Span },
+
+    #[error("'{kind}' step outside of a loop")]
+    LoopControlOutsideLoop { kind: String, span: Span },
+
+    #[error("expect field=\"{field}\" does not name the call's result binding '{binding}'")]
+    ExpectationNotOnResult { field: String, binding: String, span: Span },
 }
 
 impl ParseError {
@@ -62,6 +80,21 @@ impl ParseError {
             ParseError::InvalidStepKind { span, .. } => *span,
             ParseError::InvalidOperation { span, .. } => *span,
             ParseError::UnexpectedSection { span, .. } => *span,
+            ParseError::InvalidIsolationLevel { span, .. } => *span,
+            ParseError::InvalidTimeLiteral { span, .. } => *span,
+            ParseError::Cancelled { span } => *span,
+            ParseError::NestingTooDeep { span, .. } => *span,
+            ParseError::LoopControlOutsideLoop { span, .. } => *span,
+            ParseError::ExpectationNotOnResult { span, .. } => *span,
+        }
+    }
+
+    /// Error code for errors that have one
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            ParseError::NestingTooDeep { .. } => Some("E-LIMIT-001"),
+            ParseError::LoopControlOutsideLoop { .. } => Some("E-LOOP-001"),
+            _ => None,
         }
     }
 
@@ -0,0 +1,118 @@
+//! Machine-readable grammar artifacts
+//!
+//! Exports the snippet syntax as EBNF and the AST as a JSON Schema so that
+//! constrained decoders and external validators track the implemented grammar.
+
+use covenant_ast::VersionedProgram;
+
+/// EBNF grammar of the snippet syntax
+pub const EBNF: &str = include_str!("../../../docs/design/grammar.ebnf");
+
+/// Output format for grammar artifacts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrammarFormat {
+    #[default]
+    Ebnf,
+    JsonSchema,
+}
+
+impl std::str::FromStr for GrammarFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ebnf" => Ok(GrammarFormat::Ebnf),
+            "json-schema" | "jsonschema" => Ok(GrammarFormat::JsonSchema),
+            _ => Err(format!("Unknown format: {}. Expected: ebnf or json-schema", s)),
+        }
+    }
+}
+
+/// JSON Schema describing the serialized, versioned `Program` AST
+pub fn json_schema() -> serde_json::Value {
+    let schema = schemars::schema_for!(VersionedProgram);
+    serde_json::to_value(schema).expect("schema serializes to JSON")
+}
+
+/// Render the grammar in the requested format
+pub fn render(format: GrammarFormat) -> String {
+    match format {
+        GrammarFormat::Ebnf => EBNF.to_string(),
+        GrammarFormat::JsonSchema => {
+            serde_json::to_string_pretty(&json_schema()).expect("schema serializes to JSON")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, ParseError};
+
+    /// Quoted alternatives of an EBNF production (`name = "a" | "b" ;`)
+    fn production_alternatives(name: &str) -> Vec<String> {
+        let start = EBNF
+            .lines()
+            .position(|line| line.split_whitespace().next() == Some(name))
+            .unwrap_or_else(|| panic!("production {} not found", name));
+        let mut alternatives = Vec::new();
+        for line in EBNF.lines().skip(start) {
+            alternatives.extend(line.split('"').skip(1).step_by(2).map(str::to_string));
+            if line.trim_end().ends_with(';') {
+                break;
+            }
+        }
+        alternatives
+    }
+
+    #[test]
+    fn test_snippet_kinds_match_parser() {
+        for kind in production_alternatives("snippet_kind") {
+            let source = format!("snippet id=\"test.kind\" kind=\"{}\"\nend\n", kind);
+            let result = parse(&source);
+            assert!(
+                !matches!(result, Err(ParseError::InvalidSnippetKind { .. })),
+                "grammar lists snippet kind '{}' but the parser rejects it",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn test_step_kinds_match_parser() {
+        let parse_step = |kind: &str| {
+            parse(&format!(
+                "snippet id=\"test.kind\" kind=\"fn\"\nbody\n  step id=\"s1\" kind=\"{}\"\n  end\nend\nend\n",
+                kind
+            ))
+        };
+        assert!(matches!(parse_step("bogus"), Err(ParseError::InvalidStepKind { .. })));
+
+        let kinds = production_alternatives("step_kind");
+        assert!(kinds.contains(&"return".to_string()));
+        for kind in kinds {
+            assert!(
+                !matches!(parse_step(&kind), Err(ParseError::InvalidStepKind { .. })),
+                "grammar lists step kind '{}' but the parser rejects it",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_schema_describes_snippets() {
+        let schema = json_schema();
+        let definitions = schema["definitions"].as_object().expect("schema definitions");
+        assert!(schema["properties"]["schema_version"].is_object());
+        for name in ["Program", "Snippet", "Step", "StepKind", "ReturnValue", "BindSource"] {
+            assert!(definitions.contains_key(name), "missing definition for {}", name);
+        }
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("ebnf".parse::<GrammarFormat>(), Ok(GrammarFormat::Ebnf));
+        assert_eq!("json-schema".parse::<GrammarFormat>(), Ok(GrammarFormat::JsonSchema));
+        assert!("yaml".parse::<GrammarFormat>().is_err());
+    }
+}
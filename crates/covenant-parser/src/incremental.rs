@@ -0,0 +1,236 @@
+//! Incremental reparsing
+//!
+//! Editors reparse on every keystroke. Rather than re-lexing a whole file,
+//! [`reparse`] re-lexes and re-parses only the snippets an edit touches and
+//! reuses the others, shifting the spans of those after the edit.
+
+use std::ops::Range;
+
+use covenant_ast::{Program, Snippet, Span};
+use covenant_lexer::{tokenize, TokenKind};
+
+use crate::{parse, ParseError, Parser};
+
+/// Reparse a snippet program after an edit
+///
+/// `edit` is the byte range of `old`'s source that was replaced, and
+/// `new_source` is the whole text after the edit. The result is the same as
+/// `parse(new_source)`: whenever the incremental parse fails or could differ
+/// from a full parse (legacy programs, edits that leave no snippets, parse
+/// errors) this falls back to parsing the whole file.
+pub fn reparse(old: &Program, edit: Range<usize>, new_source: &str) -> Result<Program, ParseError> {
+    match reparse_snippets(old, edit, new_source) {
+        Some(program) => Ok(program),
+        None => parse(new_source),
+    }
+}
+
+fn reparse_snippets(old: &Program, edit: Range<usize>, new_source: &str) -> Option<Program> {
+    let Program::Snippets { snippets, span } = old else {
+        return None;
+    };
+    // The program span ends at the end-of-input token, i.e. the old source length
+    let old_len = span.end;
+    if snippets.is_empty() || edit.start > edit.end || edit.end > old_len {
+        return None;
+    }
+    let delta = new_source.len() as isize - old_len as isize;
+
+    // A snippet's span runs on through the token after its `end`, so edits to
+    // the whitespace after a snippet or to the next snippet's keyword touch it.
+    // Edits before the first snippet may turn leading text into tokens.
+    let touches = |s: &Snippet| s.span.start <= edit.end && edit.start <= s.span.end;
+    let first = if edit.start < snippets[0].span.start {
+        0
+    } else {
+        snippets.iter().position(touches)?
+    };
+    let last = snippets.iter().rposition(touches).unwrap_or(first).max(first);
+
+    let region_start = if first == 0 { 0 } else { snippets[first].span.start };
+    let next = snippets.get(last + 1);
+    let region_end = next.map_or(old_len, |s| s.span.start).checked_add_signed(delta)?;
+    if region_end < region_start || region_end > new_source.len() {
+        return None;
+    }
+
+    let mut tokens = tokenize(new_source.get(region_start..region_end)?);
+    for token in &mut tokens {
+        token.span = token.span.shifted(region_start as isize);
+    }
+    // In a full parse the last reparsed snippet's span ends at the next
+    // snippet's keyword, so the end-of-input token stands in for it
+    if let (Some(next), Some(eof)) = (next, tokens.last_mut()) {
+        let start = next.span.start.checked_add_signed(delta)?;
+        eof.span = Span::new(start, start + "snippet".len());
+    }
+
+    if first == 0 && tokens[0].kind != TokenKind::Snippet {
+        return None;
+    }
+    let program_start = if first == 0 { tokens[0].span.start } else { span.start };
+
+    let mut parser = Parser::new(new_source, tokens);
+    let reparsed = parser.parse_snippet_list().ok()?;
+
+    let mut result = Vec::with_capacity(snippets.len() - (last + 1 - first) + reparsed.len());
+    result.extend(snippets[..first].iter().cloned());
+    result.extend(reparsed);
+    result.extend(snippets[last + 1..].iter().cloned().map(|mut snippet| {
+        snippet.shift_spans(delta);
+        snippet
+    }));
+    if result.is_empty() {
+        return None;
+    }
+
+    Some(Program::Snippets {
+        snippets: result,
+        span: Span::new(program_start, new_source.len()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"// Math helpers
+snippet id="math.add" kind="fn"
+signature
+  fn name="add"
+    param name="a" type="Int"
+    param name="b" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=add
+    input var="a"
+    input var="b"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+
+snippet id="math.double" kind="fn"
+signature
+  fn name="double"
+    param name="x" type="Int"
+    returns type="List<Int>"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="math.add"
+    arg name="a" from="x"
+    arg name="b" from="x"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+
+snippet id="math.zero" kind="fn"
+body
+  step id="s1" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+"#;
+
+    /// Apply an edit and check the incremental result against a full parse
+    fn assert_reparse_matches(source: &str, range: Range<usize>, replacement: &str) -> Result<Program, ParseError> {
+        let old = parse(source).expect("original parses");
+        let mut new_source = source.to_string();
+        new_source.replace_range(range.clone(), replacement);
+
+        let incremental = reparse(&old, range, &new_source);
+        let full = parse(&new_source);
+        assert_eq!(format!("{:?}", incremental), format!("{:?}", full), "edit produced {:?}", new_source);
+        incremental
+    }
+
+    fn offset_of(needle: &str) -> usize {
+        SOURCE.find(needle).expect("needle in source")
+    }
+
+    #[test]
+    fn test_edit_inside_snippet_reuses_others() {
+        let at = offset_of("lit=0");
+        let old = parse(SOURCE).unwrap();
+        let new_source = SOURCE.replacen("lit=0", "lit=10", 1);
+        let edit = at + 4..at + 5;
+        assert!(reparse_snippets(&old, edit.clone(), &new_source).is_some(), "edit is handled incrementally");
+        assert_reparse_matches(SOURCE, edit, "10").unwrap();
+    }
+
+    #[test]
+    fn test_edit_shifts_following_snippets() {
+        let at = offset_of("math.add\" kind");
+        assert_reparse_matches(SOURCE, at..at + 8, "math.addition").unwrap();
+        assert_reparse_matches(SOURCE, at..at + 8, "m").unwrap();
+    }
+
+    #[test]
+    fn test_edit_between_snippets() {
+        let at = offset_of("\n\nsnippet id=\"math.zero\"");
+        assert_reparse_matches(SOURCE, at..at + 1, "\n// between\n").unwrap();
+        assert_reparse_matches(SOURCE, at..at + 1, "").unwrap();
+        // Joining `end` and `snippet` into one word breaks both snippets
+        assert_reparse_matches(SOURCE, at..at + 2, "").unwrap_err();
+    }
+
+    #[test]
+    fn test_edit_before_first_snippet() {
+        assert_reparse_matches(SOURCE, 0..15, "").unwrap();
+        assert_reparse_matches(SOURCE, 0..0, "\n\n").unwrap();
+    }
+
+    #[test]
+    fn test_edit_at_end_of_file() {
+        let len = SOURCE.len();
+        assert_reparse_matches(SOURCE, len..len, "\nsnippet id=\"math.one\" kind=\"fn\"\nend\n").unwrap();
+    }
+
+    #[test]
+    fn test_deleting_and_inserting_snippets() {
+        let start = offset_of("snippet id=\"math.double\"");
+        let end = offset_of("snippet id=\"math.zero\"");
+        let program = assert_reparse_matches(SOURCE, start..end, "").unwrap();
+        assert!(matches!(program, Program::Snippets { ref snippets, .. } if snippets.len() == 2));
+
+        let double = &SOURCE[start..end];
+        let program = assert_reparse_matches(SOURCE, end..end, double).unwrap();
+        assert!(matches!(program, Program::Snippets { ref snippets, .. } if snippets.len() == 4));
+
+        let program = assert_reparse_matches(SOURCE, 0..SOURCE.len(), "").unwrap();
+        assert!(matches!(program, Program::Legacy { .. }));
+    }
+
+    #[test]
+    fn test_broken_edit_reports_full_parse_error() {
+        let at = offset_of("kind=\"compute\"");
+        assert_reparse_matches(SOURCE, at + 6..at + 13, "bogus").unwrap_err();
+
+        // An unterminated string would swallow the following snippets
+        let at = offset_of("lit=0");
+        assert_reparse_matches(SOURCE, at..at, "\"").unwrap_err();
+    }
+
+    #[test]
+    fn test_every_single_character_deletion_matches_full_parse() {
+        for at in 0..SOURCE.len() {
+            let _ = assert_reparse_matches(SOURCE, at..at + 1, "");
+        }
+    }
+}
@@ -7,12 +7,15 @@
 //! - Query expressions with SQL-like syntax
 
 mod error;
+pub mod grammar;
+mod incremental;
 mod parser;
 
 pub use error::*;
+pub use incremental::*;
 pub use parser::*;
 
-use covenant_ast::Program;
+use covenant_ast::{CancellationToken, Limits, Program};
 use covenant_lexer::tokenize;
 
 /// Parse a source string into a Program AST
@@ -22,10 +25,48 @@ pub fn parse(source: &str) -> Result<Program, ParseError> {
     parser.parse_program()
 }
 
+/// Parse a source string written in either the canonical or the compact
+/// snippet syntax (see `covenant_ast::printer::to_cov_compact`)
+pub fn parse_compact(source: &str) -> Result<Program, ParseError> {
+    let tokens = tokenize(source);
+    let mut parser = Parser::new(source, tokens).with_compact_syntax();
+    parser.parse_program()
+}
+
+/// Parse a source string with custom structural limits
+pub fn parse_with_limits(source: &str, limits: Limits) -> Result<Program, ParseError> {
+    let tokens = tokenize(source);
+    let mut parser = Parser::new(source, tokens).with_limits(limits);
+    parser.parse_program()
+}
+
+/// Parse a source string, stopping early with `ParseError::Cancelled` if
+/// `token` is cancelled
+pub fn parse_with_cancel(source: &str, token: &CancellationToken) -> Result<Program, ParseError> {
+    token
+        .check()
+        .map_err(|_| ParseError::Cancelled { span: covenant_ast::Span::dummy() })?;
+    let tokens = tokenize(source);
+    let mut parser = Parser::new(source, tokens).with_cancellation(token.clone());
+    parser.parse_program()
+}
+
+/// Parse a source string, collecting errors instead of stopping at the first
+///
+/// Snippet programs recover at each `snippet` keyword, so the program holds
+/// every snippet that parsed alongside the errors of those that did not.
+/// Meant for tooling and property tests; `parse` remains the entry point
+/// for compilation.
+pub fn parse_tolerant(source: &str) -> (Option<Program>, Vec<ParseError>) {
+    let tokens = tokenize(source);
+    let mut parser = Parser::new(source, tokens);
+    parser.parse_program_tolerant()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use covenant_ast::Section;
+    use covenant_ast::{InputSource, Literal, MatchPattern, Section, StepKind, TypeKind};
 
     #[test]
     fn test_parse_hello_world() {
@@ -171,6 +212,113 @@ end
         assert!(result.is_ok(), "Failed to parse enum snippet: {:?}", result.err());
     }
 
+    #[test]
+    fn test_parse_const_snippet() {
+        use covenant_ast::{Literal, SignatureKind, SnippetKind};
+
+        let source = r#"
+snippet id="consts.MAX_RETRIES" kind="const"
+
+signature
+  const name="MAX_RETRIES" type="Int" lit=3
+end
+
+end
+"#;
+        let program = parse(source).expect("Failed to parse const snippet");
+        let Program::Snippets { snippets, .. } = program else {
+            panic!("expected snippets");
+        };
+        assert_eq!(snippets[0].kind, SnippetKind::Const);
+        let Some(Section::Signature(sig)) = snippets[0].sections.first() else {
+            panic!("expected a signature section");
+        };
+        let SignatureKind::Const(c) = &sig.kind else {
+            panic!("expected a const signature");
+        };
+        assert_eq!(c.name, "MAX_RETRIES");
+        assert_eq!(c.value, Literal::Int(3));
+    }
+
+    #[test]
+    fn test_parse_alias_snippet() {
+        use covenant_ast::{SignatureKind, SnippetKind};
+
+        let source = r#"
+snippet id="ids.UserId" kind="alias"
+
+signature
+  alias name="UserId" type="Int"
+end
+
+end
+"#;
+        let program = parse(source).expect("Failed to parse alias snippet");
+        let Program::Snippets { snippets, .. } = program else {
+            panic!("expected snippets");
+        };
+        assert_eq!(snippets[0].kind, SnippetKind::Alias);
+        let Some(Section::Signature(sig)) = snippets[0].sections.first() else {
+            panic!("expected a signature section");
+        };
+        let SignatureKind::Alias(a) = &sig.kind else {
+            panic!("expected an alias signature");
+        };
+        assert_eq!(a.name, "UserId");
+        let covenant_ast::TypeKind::Named(path) = &a.ty.kind else {
+            panic!("expected a named type");
+        };
+        assert_eq!(path.name(), "Int");
+    }
+
+    #[test]
+    fn test_parse_interface_snippet() {
+        use covenant_ast::{SignatureKind, SnippetKind};
+
+        let source = r#"
+snippet id="shapes.Shape" kind="interface"
+
+signature
+  interface name="Shape"
+    fn name="area"
+      param name="shape" type="Self"
+      returns type="Float"
+    end
+    fn name="label"
+      param name="shape" type="Self"
+      returns type="String"
+    end
+  end
+end
+
+end
+
+snippet id="shapes.Circle" kind="struct" implements="shapes.Shape"
+
+signature
+  struct name="Circle"
+    field name="radius" type="Float"
+  end
+end
+
+end
+"#;
+        let program = parse(source).expect("Failed to parse interface snippet");
+        let Program::Snippets { snippets, .. } = program else {
+            panic!("expected snippets");
+        };
+        assert_eq!(snippets[0].kind, SnippetKind::Interface);
+        let Some(Section::Signature(sig)) = snippets[0].sections.first() else {
+            panic!("expected a signature section");
+        };
+        let SignatureKind::Interface(iface) = &sig.kind else {
+            panic!("expected an interface signature");
+        };
+        let methods: Vec<&str> = iface.methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(methods, ["area", "label"]);
+        assert_eq!(snippets[1].implements.as_deref(), Some("shapes.Shape"));
+    }
+
     // === Step Kind Tests ===
 
     #[test]
@@ -302,6 +450,36 @@ end
         assert!(result.is_ok(), "Failed to parse match step: {:?}", result.err());
     }
 
+    #[test]
+    fn test_parse_match_type_arms() {
+        let source = r#"
+snippet id="test.fn" kind="fn"
+body
+  step id="s1" kind="match"
+    on="parsed"
+    case type="Int"
+      step id="s1a" kind="return"
+        from="parsed"
+        as="_"
+      end
+    end
+    case wildcard
+    end
+    as="_"
+  end
+end
+end
+"#;
+        let Program::Snippets { snippets, .. } = parse(source).unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let Some(Section::Body(body)) = snippets[0].sections.first() else { panic!("expected body") };
+        let StepKind::Match(match_step) = &body.steps[0].kind else { panic!("expected match") };
+        let MatchPattern::Type { ty } = &match_step.cases[0].pattern else { panic!("expected type arm") };
+        assert!(matches!(&ty.kind, TypeKind::Named(path) if path.name() == "Int"));
+        assert!(matches!(match_step.cases[1].pattern, MatchPattern::Wildcard));
+    }
+
     #[test]
     fn test_parse_for_step() {
         let source = r#"
@@ -483,6 +661,123 @@ end
         assert!(result.is_ok(), "Failed to parse union type: {:?}", result.err());
     }
 
+    #[test]
+    fn test_parse_collection_literals() {
+        use covenant_ast::{BindSource, Literal, StepKind};
+
+        let source = r#"
+snippet id="test.fn" kind="fn"
+signature
+  fn name="test_fn"
+  end
+end
+body
+  step id="s1" kind="bind"
+    lit=[1, 2, 3]
+    as="list"
+  end
+  step id="s2" kind="bind"
+    lit={"a": 1, "b": 2,}
+    as="map"
+  end
+  step id="s3" kind="bind"
+    lit={"x", "y"}
+    as="set"
+  end
+  step id="s4" kind="bind"
+    lit=(1, "a")
+    as="tuple"
+  end
+  step id="s5" kind="bind"
+    lit={}
+    as="empty"
+  end
+end
+end
+"#;
+        let program = parse(source).expect("parse failed");
+        let Program::Snippets { snippets, .. } = program else {
+            panic!("Expected Snippets program");
+        };
+        let Some(Section::Body(body)) = snippets[0].sections.iter().find(|s| matches!(s, Section::Body(_))) else {
+            panic!("Expected body section");
+        };
+        let literals: Vec<&Literal> = body
+            .steps
+            .iter()
+            .map(|step| match &step.kind {
+                StepKind::Bind(bind) => match &bind.source {
+                    BindSource::Lit(lit) => lit,
+                    other => panic!("Expected literal, got {:?}", other),
+                },
+                other => panic!("Expected bind, got {:?}", other),
+            })
+            .collect();
+
+        assert_eq!(literals[0], &Literal::List(vec![Literal::Int(1), Literal::Int(2), Literal::Int(3)]));
+        assert_eq!(
+            literals[1],
+            &Literal::Map(vec![
+                (Literal::String("a".into()), Literal::Int(1)),
+                (Literal::String("b".into()), Literal::Int(2)),
+            ])
+        );
+        assert_eq!(literals[2], &Literal::Set(vec![Literal::String("x".into()), Literal::String("y".into())]));
+        assert_eq!(literals[3], &Literal::Tuple(vec![Literal::Int(1), Literal::String("a".into())]));
+        assert_eq!(literals[4], &Literal::Map(vec![]));
+    }
+
+    #[test]
+    fn test_parse_datetime_and_duration_literals() {
+        use covenant_ast::{BindSource, Literal, StepKind};
+
+        let source = r#"
+snippet id="test.fn" kind="fn"
+signature
+  fn name="test_fn"
+  end
+end
+body
+  step id="s1" kind="bind"
+    lit=datetime("2024-01-01T02:00:00+02:00")
+    as="at"
+  end
+  step id="s2" kind="bind"
+    lit=[duration("5m"), duration("-1h30m")]
+    as="spans"
+  end
+end
+end
+"#;
+        let program = parse(source).expect("parse failed");
+        let Program::Snippets { snippets, .. } = program else {
+            panic!("Expected Snippets program");
+        };
+        let Some(Section::Body(body)) = snippets[0].sections.iter().find(|s| matches!(s, Section::Body(_))) else {
+            panic!("Expected body section");
+        };
+        let literals: Vec<&Literal> = body
+            .steps
+            .iter()
+            .map(|step| match &step.kind {
+                StepKind::Bind(bind) => match &bind.source {
+                    BindSource::Lit(lit) => lit,
+                    other => panic!("Expected literal, got {:?}", other),
+                },
+                other => panic!("Expected bind, got {:?}", other),
+            })
+            .collect();
+
+        assert_eq!(literals[0], &Literal::DateTime(1_704_067_200));
+        assert_eq!(literals[1], &Literal::List(vec![Literal::Duration(300), Literal::Duration(-5_400)]));
+
+        let invalid = source.replace("duration(\"5m\")", "duration(\"5w\")");
+        match parse(&invalid) {
+            Err(ParseError::InvalidTimeLiteral { kind, text, .. }) => assert_eq!((kind.as_str(), text.as_str()), ("duration", "5w")),
+            other => panic!("Expected InvalidTimeLiteral, got {:?}", other),
+        }
+    }
+
     // === Section Tests ===
 
     #[test]
@@ -850,7 +1145,6 @@ end
     // === Transaction Step Tests ===
 
     #[test]
-    #[ignore = "Transaction step parsing not yet implemented"]
     fn test_parse_transaction_step_basic() {
         let source = r#"
 snippet id="db.transfer" kind="fn"
@@ -901,10 +1195,28 @@ end
 "#;
         let result = parse(source);
         assert!(result.is_ok(), "Failed to parse transaction step: {:?}", result.err());
+
+        let Program::Snippets { snippets, .. } = result.unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        let covenant_ast::StepKind::Transaction(tx) = &body.steps[0].kind else {
+            panic!("Expected transaction step");
+        };
+        assert_eq!(tx.target.as_deref(), Some("bank_db"));
+        assert!(tx.isolation.is_none());
+        assert_eq!(tx.steps.len(), 2);
+        for step in &tx.steps {
+            let covenant_ast::StepKind::Query(query) = &step.kind else {
+                panic!("Expected nested query step");
+            };
+            assert_eq!(query.target, "bank_db");
+        }
     }
 
     #[test]
-    #[ignore = "Transaction step parsing not yet implemented"]
     fn test_parse_transaction_with_isolation_level() {
         let source = r#"
 snippet id="db.critical_update" kind="fn"
@@ -944,6 +1256,24 @@ end
 "#;
         let result = parse(source);
         assert!(result.is_ok(), "Failed to parse transaction with isolation level: {:?}", result.err());
+
+        let Program::Snippets { snippets, .. } = result.unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        let covenant_ast::StepKind::Transaction(tx) = &body.steps[0].kind else {
+            panic!("Expected transaction step");
+        };
+        assert_eq!(tx.target.as_deref(), Some("main_db"));
+        assert_eq!(tx.isolation, Some(covenant_ast::IsolationLevel::Serializable));
+
+        let invalid = source.replace("serializable", "snapshot");
+        assert!(matches!(
+            parse(&invalid),
+            Err(ParseError::InvalidIsolationLevel { ref level, .. }) if level == "snapshot"
+        ));
     }
 
     // === Traverse Step Tests ===
@@ -1081,6 +1411,201 @@ end
         assert!(result.is_ok(), "Failed to parse construct with literal fields: {:?}", result.err());
     }
 
+    #[test]
+    fn test_parse_construct_from_base() {
+        let source = r#"
+snippet id="config.with_timeout" kind="fn"
+signature
+  fn name="with_timeout"
+    param name="config" type="Config"
+    returns type="Config"
+  end
+end
+body
+  step id="s1" kind="construct"
+    type="Config"
+    from_base="config"
+    field name="timeout" lit=60
+    as="updated"
+  end
+end
+end
+"#;
+        let result = parse(source);
+        assert!(result.is_ok(), "Failed to parse construct with from_base: {:?}", result.err());
+
+        let Program::Snippets { snippets, .. } = result.unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        let covenant_ast::StepKind::Construct(construct) = &body.steps[0].kind else {
+            panic!("Expected construct step");
+        };
+        assert_eq!(construct.from_base.as_deref(), Some("config"));
+        assert_eq!(construct.fields.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_call_with_type_args() {
+        let source = r#"
+snippet id="test.pick" kind="fn"
+signature
+  fn name="pick"
+    param name="scores" type="List<Int>"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="first_or"
+    type arg="Int"
+    type arg="List<String>"
+    arg name="items" from="scores"
+    as="top"
+  end
+end
+end
+"#;
+        let result = parse(source);
+        assert!(result.is_ok(), "Failed to parse call with type args: {:?}", result.err());
+
+        let Program::Snippets { snippets, .. } = result.unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        let covenant_ast::StepKind::Call(call) = &body.steps[0].kind else {
+            panic!("Expected call step");
+        };
+        assert_eq!(call.type_args.len(), 2);
+        let covenant_ast::TypeKind::Named(path) = &call.type_args[0].kind else {
+            panic!("Expected named type arg");
+        };
+        assert_eq!(path.name(), "Int");
+        assert_eq!(call.args.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_pipeline_keeps_stages() {
+        use covenant_ast::{InputSource, StepKind};
+        use covenant_ast::printer::ToCov;
+
+        let source = r#"
+snippet id="test.slug" kind="fn"
+signature
+  fn name="slug"
+    param name="title" type="String"
+    returns type="String"
+  end
+end
+body
+  step id="s1" kind="pipeline" from="title"
+    stage fn="text.trim"
+    stage fn="text.replace_all" into="s"
+      arg name="from" lit=" "
+      arg name="to" lit="-"
+    stage fn="text.lower"
+    as="slug"
+  end
+  step id="s2" kind="return"
+    from="slug"
+    as="_"
+  end
+end
+end
+"#;
+        let Program::Snippets { snippets, .. } = parse(source).expect("Failed to parse pipeline") else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+
+        assert_eq!(body.steps.len(), 2);
+        let StepKind::Pipeline(pipeline) = &body.steps[0].kind else {
+            panic!("Expected pipeline step, got {:?}", body.steps[0].kind);
+        };
+        assert_eq!(body.steps[0].output_binding, "slug");
+        assert!(matches!(&pipeline.from, InputSource::Var(v) if v == "title"));
+        let stages: Vec<(&str, &str)> = pipeline.stages.iter()
+            .map(|stage| (stage.call.fn_name.as_str(), stage.into.as_str()))
+            .collect();
+        assert_eq!(stages, [("text.trim", "value"), ("text.replace_all", "s"), ("text.lower", "value")]);
+        assert_eq!(pipeline.stages[1].call.args.len(), 2);
+
+        // Pipelines print back as written
+        let printed = snippets[0].to_cov(0);
+        assert!(printed.contains("stage fn=\"text.replace_all\" into=\"s\""), "{}", printed);
+        let reparsed = parse(&printed).expect("Failed to reparse printed pipeline");
+        assert_eq!(reparsed.to_cov(0), printed);
+    }
+
+    #[test]
+    fn test_parse_pipeline_without_stages_is_error() {
+        let source = r#"
+snippet id="test.empty" kind="fn"
+body
+  step id="s1" kind="pipeline" from="x"
+    as="y"
+  end
+end
+end
+"#;
+        assert!(parse(source).is_err());
+    }
+
+    #[test]
+    fn test_parse_call_expectations() {
+        let source = r#"
+snippet id="test.sum" kind="fn"
+tests
+  test id="T-001" kind="unit" covers="R-001"
+    step id="t1" kind="call"
+      fn="math.sum"
+      arg name="xs" lit=[2, 3]
+      expect field="total" lit=5
+      expect field="total.label" lit="five"
+      as="total"
+    end
+  end
+end
+end
+"#;
+        let program = parse(source).expect("parse failed");
+        let Program::Snippets { snippets, .. } = program else { panic!("expected snippets") };
+        let Some(Section::Tests(tests)) = snippets[0].sections.first() else { panic!("expected tests") };
+        let StepKind::Call(call) = &tests.tests[0].steps[0].kind else { panic!("expected call") };
+        let expects: Vec<_> = call.expects.iter().map(|e| (e.field.as_str(), e.value.clone())).collect();
+        assert_eq!(expects, vec![
+            ("total", Literal::Int(5)),
+            ("total.label", Literal::String("five".to_string())),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_expectation_must_name_result() {
+        for binding in ["other", "_"] {
+            let source = format!(r#"
+snippet id="test.sum" kind="fn"
+tests
+  test id="T-001" kind="unit"
+    step id="t1" kind="call"
+      fn="math.sum"
+      expect field="total" lit=5
+      as="{}"
+    end
+  end
+end
+end
+"#, binding);
+            let err = parse(&source).expect_err("expect on another binding should not parse");
+            assert!(matches!(err, ParseError::ExpectationNotOnResult { ref field, .. } if field == "total"), "{:?}", err);
+        }
+    }
+
     // === Bind Step Tests ===
 
     #[test]
@@ -1424,7 +1949,6 @@ end
     // === Database Binding Tests ===
 
     #[test]
-    #[ignore = "Database snippet kind parsing not yet implemented"]
     fn test_parse_database_snippet() {
         let source = r#"
 snippet id="db.main_db" kind="database"
@@ -1453,6 +1977,21 @@ end
 "#;
         let result = parse(source);
         assert!(result.is_ok(), "Failed to parse database snippet: {:?}", result.err());
+
+        let Program::Snippets { snippets, .. } = result.unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        assert_eq!(snippets[0].kind, covenant_ast::SnippetKind::Database);
+        let schema = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Schema(schema) = s { Some(schema) } else { None }
+        }).expect("schema section not found");
+
+        assert_eq!(schema.tables.len(), 2);
+        let users = schema.table("users").unwrap();
+        assert_eq!(users.primary_key().map(|f| f.name.as_str()).collect::<Vec<_>>(), vec!["id"]);
+
+        let fk = schema.table("orders").unwrap().field("user_id").unwrap().foreign_key.clone().unwrap();
+        assert_eq!((fk.table.as_str(), fk.field.as_str()), ("users", "id"));
     }
 
     // === Data Snippet Tests ===
@@ -1716,7 +2255,6 @@ end
     }
 
     #[test]
-    #[ignore = "Return with variant syntax not yet implemented"]
     fn test_parse_return_with_variant() {
         let source = r#"
 snippet id="test.variant" kind="fn"
@@ -1741,6 +2279,86 @@ end
         assert!(result.is_ok(), "Return with variant should parse: {:?}", result.err());
     }
 
+    #[test]
+    fn test_parse_raise_step() {
+        let source = r#"
+snippet id="test.raise" kind="fn"
+signature
+  fn name="get_result"
+    returns union
+      type="Int"
+      type="Error"
+    end
+  end
+end
+body
+  step id="s1" kind="raise"
+    variant type="Error"
+      field name="message" lit="Something went wrong"
+    end
+    as="_"
+  end
+end
+end
+"#;
+        let result = parse(source);
+        assert!(result.is_ok(), "Raise step should parse: {:?}", result.err());
+
+        let Program::Snippets { snippets, .. } = result.unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        let covenant_ast::StepKind::Raise(raise) = &body.steps[0].kind else {
+            panic!("Expected raise step");
+        };
+        assert_eq!(raise.variant.ty, "Error");
+        assert_eq!(raise.variant.fields.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_bind_with_variant() {
+        let source = r#"
+snippet id="test.bind_variant" kind="fn"
+signature
+  fn name="make_error"
+    returns type="Error"
+  end
+end
+body
+  step id="s1" kind="bind"
+    variant type="Error"
+      field name="message" lit="Something went wrong"
+    end
+    as="err"
+  end
+  step id="s2" kind="return"
+    from="err"
+    as="_"
+  end
+end
+end
+"#;
+        let result = parse(source);
+        assert!(result.is_ok(), "Bind with variant should parse: {:?}", result.err());
+
+        let Program::Snippets { snippets, .. } = result.unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        let covenant_ast::StepKind::Bind(bind) = &body.steps[0].kind else {
+            panic!("Expected bind step");
+        };
+        let covenant_ast::BindSource::Variant(v) = &bind.source else {
+            panic!("Expected variant bind source");
+        };
+        assert_eq!(v.ty, "Error");
+        assert_eq!(v.fields.len(), 1);
+    }
+
     #[test]
     fn test_parse_none_literal() {
         let source = r#"
@@ -1838,7 +2456,6 @@ end
     }
 
     #[test]
-    #[ignore = "Test section with assert step kind not yet implemented"]
     fn test_parse_test_section() {
         let source = r#"
 snippet id="math.add" kind="fn"
@@ -2010,4 +2627,561 @@ end
             panic!("Expected Snippets program");
         }
     }
+
+    #[test]
+    fn test_parse_effect_attributes() {
+        let source = r#"
+snippet id="api.fetch" kind="fn"
+
+effects
+  effect network.http host="api.example.com" port=443
+  effect filesystem(path="/data")
+  effect console
+end
+
+signature
+  fn name="fetch"
+    returns type="Unit"
+  end
+end
+
+end
+"#;
+        let result = parse(source);
+        assert!(result.is_ok(), "Failed to parse effect attributes: {:?}", result.err());
+        let program = result.unwrap();
+        if let Program::Snippets { snippets, .. } = program {
+            let effects_section = snippets[0].sections.iter().find_map(|s| {
+                if let Section::Effects(e) = s { Some(e) } else { None }
+            }).expect("effects section not found");
+            let http = &effects_section.effects[0];
+            assert_eq!(http.name, "network.http");
+            assert_eq!(http.get_param("host").map(|p| &p.value), Some(&Literal::String("api.example.com".into())));
+            assert_eq!(http.get_param("port").map(|p| &p.value), Some(&Literal::Int(443)));
+            // Attributes and parentheses parse to the same parameters
+            assert_eq!(effects_section.effects[1].params.len(), 1);
+            assert_eq!(effects_section.effects[2].name, "console");
+            assert!(!effects_section.effects[2].has_params());
+        } else {
+            panic!("Expected Snippets program");
+        }
+    }
+
+    #[test]
+    fn test_parse_with_cancel() {
+        let source = r#"
+snippet id="a" kind="fn"
+signature
+  fn name="a"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=1
+    as="_"
+  end
+end
+end
+"#;
+        let token = CancellationToken::new();
+        assert!(parse_with_cancel(source, &token).is_ok());
+
+        token.cancel();
+        let result = parse_with_cancel(source, &token);
+        assert!(matches!(result, Err(ParseError::Cancelled { .. })));
+    }
+
+    fn nested_ifs(depth: usize) -> String {
+        let mut steps = String::from("step id=\"r\" kind=\"return\"\n lit=1\n as=\"_\"\nend\n");
+        for i in 0..depth {
+            steps = format!("step id=\"if{i}\" kind=\"if\"\n condition=\"c\"\n then\n{steps}end\n as=\"_\"\nend\n");
+        }
+        format!("snippet id=\"deep\" kind=\"fn\"\nsignature\n fn name=\"deep\"\n param name=\"c\" type=\"Bool\"\n returns type=\"Int\"\n end\nend\nbody\n{steps}end\nend\n")
+    }
+
+    #[test]
+    fn test_nesting_depth_limit() {
+        let limits = Limits { max_nesting_depth: 8, ..Default::default() };
+        assert!(parse_with_limits(&nested_ifs(7), limits).is_ok());
+
+        let err = parse_with_limits(&nested_ifs(8), limits).unwrap_err();
+        assert!(matches!(err, ParseError::NestingTooDeep { limit: 8, .. }));
+        assert_eq!(err.code(), Some("E-LIMIT-001"));
+    }
+
+    #[test]
+    fn test_default_limit_rejects_pathological_nesting() {
+        let err = parse(&nested_ifs(500)).unwrap_err();
+        assert!(matches!(err, ParseError::NestingTooDeep { .. }));
+    }
+
+    /// A function whose body is `prefix`, a for loop over `items` containing
+    /// `loop_body`, then a return
+    fn loop_fn(prefix: &str, loop_body: &str) -> String {
+        format!(r#"
+snippet id="test.loop" kind="fn"
+signature
+  fn name="first_positive"
+    param name="items" type="List<Int>"
+    returns type="Int"
+  end
+end
+body
+{prefix}
+  step id="s1" kind="for"
+    var="item" in="items"
+{loop_body}
+    as="_"
+  end
+  step id="s2" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+"#)
+    }
+
+    #[test]
+    fn test_parse_break_and_continue_in_loop() {
+        let source = loop_fn("", r#"
+    step id="s1a" kind="compute"
+      op=less
+      input var="item"
+      input lit=0
+      as="negative"
+    end
+    step id="s1b" kind="if"
+      condition="negative"
+      then
+        step id="s1c" kind="continue"
+          as="_"
+        end
+      end
+      else
+        step id="s1d" kind="break"
+          as="_"
+        end
+      end
+      as="_"
+    end"#);
+        let result = parse(&source);
+        assert!(result.is_ok(), "Break/continue in loop should parse: {:?}", result.err());
+
+        let Program::Snippets { snippets, .. } = result.unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        let covenant_ast::StepKind::For(for_step) = &body.steps[0].kind else {
+            panic!("Expected for step");
+        };
+        let covenant_ast::StepKind::If(if_step) = &for_step.steps[1].kind else {
+            panic!("Expected if step");
+        };
+        assert!(matches!(if_step.then_steps[0].kind, covenant_ast::StepKind::Continue(_)));
+        assert!(matches!(if_step.else_steps.as_ref().unwrap()[0].kind, covenant_ast::StepKind::Break(_)));
+    }
+
+    #[test]
+    fn test_break_outside_loop_is_error() {
+        let source = loop_fn(r#"
+  step id="s0" kind="break"
+    as="_"
+  end"#, "");
+        let err = parse(&source).unwrap_err();
+        assert!(matches!(err, ParseError::LoopControlOutsideLoop { ref kind, .. } if kind == "break"));
+        assert_eq!(err.code(), Some("E-LOOP-001"));
+    }
+
+    #[test]
+    fn test_continue_in_parallel_branch_is_error() {
+        let source = loop_fn("", r#"
+    step id="s1a" kind="parallel"
+      branch id="b1"
+        step id="s1b" kind="continue"
+          as="_"
+        end
+      end
+      as="_"
+    end"#);
+        let err = parse(&source).unwrap_err();
+        assert!(matches!(err, ParseError::LoopControlOutsideLoop { ref kind, .. } if kind == "continue"));
+    }
+
+    #[test]
+    fn test_parse_lambda_step() {
+        let source = loop_fn(r#"
+  step id="s0" kind="lambda"
+    param name="x" type="Int"
+    returns type="Int"
+    step id="l1" kind="compute"
+      op=mul
+      input var="x"
+      input lit=2
+      as="doubled"
+    end
+    step id="l2" kind="return"
+      from="doubled"
+      as="_"
+    end
+    as="double"
+  end"#, "");
+        let result = parse(&source);
+        assert!(result.is_ok(), "Lambda step should parse: {:?}", result.err());
+
+        let Program::Snippets { snippets, .. } = result.unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        let covenant_ast::StepKind::Lambda(lambda) = &body.steps[0].kind else {
+            panic!("Expected lambda step");
+        };
+        assert_eq!(body.steps[0].output_binding, "double");
+        assert_eq!(lambda.params.len(), 1);
+        assert_eq!(lambda.params[0].name, "x");
+        assert!(lambda.returns.is_some());
+        assert_eq!(lambda.steps.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_using_step() {
+        let source = loop_fn(r#"
+  step id="s0" kind="using"
+    var="conn"
+    acquire fn="db.open"
+      arg name="url" lit="postgres://localhost"
+    release fn="db.close"
+    step id="u1" kind="call"
+      fn="db.ping"
+      arg name="conn" from="conn"
+      as="ok"
+    end
+    as="pinged"
+  end"#, "");
+        let result = parse(&source);
+        assert!(result.is_ok(), "Using step should parse: {:?}", result.err());
+
+        let Program::Snippets { snippets, .. } = result.unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        let covenant_ast::StepKind::Using(using) = &body.steps[0].kind else {
+            panic!("Expected using step");
+        };
+        assert_eq!(body.steps[0].output_binding, "pinged");
+        assert_eq!(using.var, "conn");
+        assert_eq!(using.acquire.fn_name, "db.open");
+        assert_eq!(using.acquire.args.len(), 1);
+        assert_eq!(using.release, "db.close");
+        assert_eq!(using.steps.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_struct_invariant() {
+        let source = r#"
+snippet id="shop.Range" kind="struct"
+signature
+  struct name="Range"
+    field name="min" type="Int"
+    field name="max" type="Int"
+  end
+end
+invariant
+  check op=less_eq
+    input var="min"
+    input var="max"
+    message="min must not exceed max"
+  check op=greater_eq input var="min" input lit=0
+end
+end
+"#;
+        let Program::Snippets { snippets, .. } = parse(source).unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let invariant = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Invariant(i) = s { Some(i) } else { None }
+        }).expect("invariant section not found");
+        assert_eq!(invariant.checks.len(), 2);
+        assert_eq!(invariant.checks[0].op, covenant_ast::Operation::LessEq);
+        assert_eq!(invariant.checks[0].message.as_deref(), Some("min must not exceed max"));
+        assert!(invariant.checks[1].message.is_none());
+    }
+
+    #[test]
+    fn test_parse_unit_alias() {
+        let source = r#"
+snippet id="billing.types" kind="module"
+types
+  struct name="Invoice"
+    field name="total" type="Money"
+  end
+  alias name="Money" type="Float" unit="USD"
+  alias name="Count" type="Int"
+end
+end
+"#;
+        let Program::Snippets { snippets, .. } = parse(source).unwrap() else {
+            panic!("Expected Snippets program");
+        };
+        let Some(Section::Types(types)) = snippets[0].sections.first() else { panic!("expected types") };
+        assert_eq!(types.types.len(), 3);
+        assert!(types.types[0].definition.is_some());
+        assert_eq!(types.types[1].name, "Money");
+        assert_eq!(types.types[1].unit.as_deref(), Some("USD"));
+        assert!(types.types[1].definition.is_none());
+        assert!(types.types[2].unit.is_none());
+    }
+
+    #[test]
+    fn test_invariant_outside_struct_is_error() {
+        let source = r#"
+snippet id="shop.total" kind="fn"
+invariant
+  check op=greater_eq input var="n" input lit=0
+end
+end
+"#;
+        let err = parse(source).unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedSection { .. }));
+    }
+
+    #[test]
+    fn test_break_in_lambda_inside_loop_is_error() {
+        let source = loop_fn("", r#"
+    step id="s1a" kind="lambda"
+      step id="s1b" kind="break"
+        as="_"
+      end
+      as="f"
+    end"#);
+        let err = parse(&source).unwrap_err();
+        assert!(matches!(err, ParseError::LoopControlOutsideLoop { ref kind, .. } if kind == "break"));
+    }
+
+    #[test]
+    fn test_parse_compact_syntax() {
+        let source = r#"
+snippet "math.add" fn
+signature
+  fn "add"
+    param "a" "Int"
+    param "b" "Int"
+    returns "Int"
+  end
+end
+body
+  step "s1" compute
+    op=add input "a" input "b"
+    as="sum"
+  end
+  step "s2" return
+    from="sum"
+  end
+end
+end
+"#;
+        assert!(parse(source).is_err());
+        let result = parse_compact(source);
+        let Ok(Program::Snippets { snippets, .. }) = result else {
+            panic!("Expected snippets, got {:?}", result.err());
+        };
+        assert_eq!(snippets[0].id, "math.add");
+        assert_eq!(snippets[0].kind, covenant_ast::SnippetKind::Function);
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        let covenant_ast::StepKind::Compute(compute) = &body.steps[0].kind else {
+            panic!("Expected compute step");
+        };
+        assert!(matches!(&compute.inputs[0].source, covenant_ast::InputSource::Var(v) if v == "a"));
+        assert_eq!(body.steps[0].output_binding, "sum");
+        assert_eq!(body.steps[1].output_binding, "_");
+    }
+
+    #[test]
+    fn test_parse_compact_accepts_canonical_syntax() {
+        let source = loop_fn("", "");
+        assert!(parse_compact(&source).is_ok());
+    }
+
+    #[test]
+    fn test_parse_module_contains() {
+        let source = r#"
+snippet id="auth" kind="module"
+  contains
+    member id="auth.login"
+    member id="auth.session"
+  end
+end
+"#;
+        let Ok(Program::Snippets { snippets, .. }) = parse(source) else {
+            panic!("Expected snippets");
+        };
+        let Some(Section::Contains(contains)) = snippets[0].sections.first() else {
+            panic!("Expected contains section");
+        };
+        let members: Vec<_> = contains.members.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(members, ["auth.login", "auth.session"]);
+    }
+
+    #[test]
+    fn test_contains_outside_module_is_error() {
+        let source = r#"
+snippet id="auth.login" kind="fn"
+  contains
+    member id="auth.session"
+  end
+end
+"#;
+        assert!(matches!(parse(source), Err(ParseError::UnexpectedSection { .. })));
+    }
+
+    #[test]
+    fn test_parse_subscriber() {
+        let source = r#"
+snippet id="orders.on_placed" kind="subscriber"
+  subscribe
+    event type="OrderPlaced"
+    handler fn="notify_warehouse"
+  end
+end
+"#;
+        let Ok(Program::Snippets { snippets, .. }) = parse(source) else {
+            panic!("Expected snippets");
+        };
+        assert_eq!(snippets[0].kind, covenant_ast::SnippetKind::Subscriber);
+        let Some(Section::Subscribe(subscribe)) = snippets[0].sections.first() else {
+            panic!("Expected subscribe section");
+        };
+        assert_eq!(subscribe.event, "OrderPlaced");
+        assert_eq!(subscribe.handler, "notify_warehouse");
+    }
+
+    #[test]
+    fn test_subscribe_outside_subscriber_is_error() {
+        let source = r#"
+snippet id="orders.place" kind="fn"
+  subscribe
+    event type="OrderPlaced"
+    handler fn="notify_warehouse"
+  end
+end
+"#;
+        assert!(matches!(parse(source), Err(ParseError::UnexpectedSection { .. })));
+    }
+
+    #[test]
+    fn test_parse_doc_blocks() {
+        let source = r#"
+snippet id="math.double" kind="fn"
+  note "internal"
+  doc """Doubles a number"""
+  signature
+    fn name="double"
+      doc """The number to double"""
+      param name="x" type="Int"
+      returns type="Int"
+    end
+  end
+  body
+    doc """Add x to itself"""
+    step id="s1" kind="compute"
+      op=add
+      input var="x"
+      input var="x"
+      as="result"
+    end
+    step id="s2" kind="return"
+      from="result"
+      as="_"
+    end
+  end
+end
+"#;
+        let Ok(Program::Snippets { snippets, .. }) = parse(source) else {
+            panic!("Expected snippets");
+        };
+        let snippet = &snippets[0];
+        assert_eq!(snippet.notes.len(), 1);
+        let doc = snippet.doc.as_ref().expect("snippet doc");
+        assert_eq!(doc.content, "Doubles a number");
+        assert!(source[doc.span.start..].starts_with(r#"doc """Doubles"#));
+
+        let sig = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Signature(sig) = s { Some(sig) } else { None }
+        }).expect("signature section not found");
+        let covenant_ast::SignatureKind::Function(f) = &sig.kind else {
+            panic!("Expected function signature");
+        };
+        assert_eq!(f.params[0].doc.as_ref().unwrap().content, "The number to double");
+
+        let body = snippets[0].sections.iter().find_map(|s| {
+            if let Section::Body(b) = s { Some(b) } else { None }
+        }).expect("body section not found");
+        assert_eq!(body.steps[0].doc.as_ref().unwrap().content, "Add x to itself");
+        assert!(body.steps[1].doc.is_none());
+    }
+
+    #[test]
+    fn test_doc_name_still_usable_as_identifier() {
+        let source = r#"
+snippet id="docs.render" kind="fn"
+  signature
+    fn name="render"
+      param name="doc" type="String"
+      returns type="String"
+    end
+  end
+  body
+    step id="s1" kind="return"
+      from="doc"
+      as="_"
+    end
+  end
+end
+"#;
+        assert!(parse(source).is_ok());
+    }
+
+    #[test]
+    fn test_parse_negative_literals_status_and_escapes() {
+        let source = r#"
+snippet id="a.b" kind="fn"
+  requires
+    req id="R-1"
+      text "ends in \\n, not a newline"
+      status approved
+    end
+  end
+  body
+    step id="s1" kind="compute"
+      op=add
+      input lit=-1
+      input lit=-9223372036854775808
+      input lit=-2.5
+      as="x"
+    end
+  end
+end
+"#;
+        let program = parse(source).expect("parse failed");
+        let Program::Snippets { snippets, .. } = program else { panic!("expected snippets") };
+        let Some(Section::Requires(requires)) = snippets[0].sections.first() else { panic!("expected requires") };
+        assert_eq!(requires.requirements[0].text.as_deref(), Some("ends in \\n, not a newline"));
+        assert_eq!(requires.requirements[0].status, Some(covenant_ast::ReqStatus::Approved));
+
+        let Some(Section::Body(body)) = snippets[0].sections.get(1) else { panic!("expected body") };
+        let StepKind::Compute(compute) = &body.steps[0].kind else { panic!("expected compute") };
+        let values: Vec<_> = compute.inputs.iter()
+            .map(|i| match &i.source { InputSource::Lit(lit) => lit.clone(), _ => panic!("expected lit") })
+            .collect();
+        assert_eq!(values, vec![Literal::Int(-1), Literal::Int(i64::MIN), Literal::Float(-2.5)]);
+    }
 }
@@ -9,6 +9,16 @@ pub struct Parser<'a> {
     source: &'a str,
     tokens: Vec<Token>,
     pos: usize,
+    cancel: Option<CancellationToken>,
+    limits: Limits,
+    /// Current step nesting depth
+    depth: usize,
+    /// Targets of the enclosing transaction steps, innermost last
+    transaction_targets: Vec<String>,
+    /// Number of `for` bodies enclosing the step being parsed
+    loop_depth: usize,
+    /// Accept the compact syntax (positional header attributes, implicit `as="_"`)
+    compact: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -17,9 +27,33 @@ impl<'a> Parser<'a> {
             source,
             tokens,
             pos: 0,
+            cancel: None,
+            limits: Limits::default(),
+            depth: 0,
+            transaction_targets: Vec::new(),
+            loop_depth: 0,
+            compact: false,
         }
     }
 
+    /// Override the default structural limits
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Also accept the compact syntax emitted by `to_cov_compact`
+    pub fn with_compact_syntax(mut self) -> Self {
+        self.compact = true;
+        self
+    }
+
+    /// Poll `token` while parsing and stop with `ParseError::Cancelled` once it is set
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
     // === Utilities ===
 
     fn current(&self) -> &Token {
@@ -40,11 +74,19 @@ impl<'a> Parser<'a> {
     }
 
     fn advance(&mut self) -> &Token {
+        // Return the token we just passed; EOF is never passed
+        let passed = self.pos.min(self.tokens.len() - 1);
         if self.pos < self.tokens.len() - 1 {
             self.pos += 1;
         }
-        // Return the token we just passed
-        &self.tokens[self.pos - 1]
+        &self.tokens[passed]
+    }
+
+    fn check_cancelled(&self) -> Result<(), ParseError> {
+        match &self.cancel {
+            Some(token) if token.is_cancelled() => Err(ParseError::Cancelled { span: self.span() }),
+            _ => Ok(()),
+        }
     }
 
     fn at(&self, kind: TokenKind) -> bool {
@@ -55,6 +97,21 @@ impl<'a> Parser<'a> {
         kinds.contains(&self.peek())
     }
 
+    /// Check for a `doc """..."""` block (`doc` stays usable as a name elsewhere)
+    fn at_doc(&self) -> bool {
+        self.at(TokenKind::Ident) && self.peek_ahead(1) == TokenKind::TripleString && self.peek_text() == "doc"
+    }
+
+    /// Check for a step, possibly preceded by its doc block
+    fn at_step(&self) -> bool {
+        self.at(TokenKind::Step) || (self.at_doc() && self.peek_ahead(2) == TokenKind::Step)
+    }
+
+    /// Check for a param, possibly preceded by its doc block
+    fn at_param(&self) -> bool {
+        self.at(TokenKind::Param) || (self.at_doc() && self.peek_ahead(2) == TokenKind::Param)
+    }
+
     /// Check if current token can be used as an identifier (including contextual keywords)
     fn at_ident_like(&self) -> bool {
         matches!(self.peek(), TokenKind::Ident | TokenKind::Id | TokenKind::Type)
@@ -74,6 +131,20 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Consume a contextual keyword that lexes as a plain identifier
+    fn consume_word(&mut self, word: &str) -> Result<(), ParseError> {
+        if self.at(TokenKind::Ident) && self.peek_text() == word {
+            self.advance();
+            Ok(())
+        } else {
+            Err(ParseError::Unexpected {
+                expected: format!("'{}'", word),
+                found: self.peek(),
+                span: self.span(),
+            })
+        }
+    }
+
     fn consume(&mut self, kind: TokenKind) -> Result<&Token, ParseError> {
         if self.at(kind) {
             Ok(self.advance())
@@ -153,12 +224,27 @@ impl<'a> Parser<'a> {
     fn parse_string_literal_from_text(&self, text: &str) -> String {
         // Remove surrounding quotes and unescape
         let inner = &text[1..text.len() - 1];
-        inner
-            .replace("\\n", "\n")
-            .replace("\\r", "\r")
-            .replace("\\t", "\t")
-            .replace("\\\"", "\"")
-            .replace("\\\\", "\\")
+        let mut result = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('t') => result.push('\t'),
+                // `\"` and `\\` drop the backslash; unknown escapes keep it
+                Some(other @ ('"' | '\\')) => result.push(other),
+                Some(other) => {
+                    result.push('\\');
+                    result.push(other);
+                }
+                None => result.push('\\'),
+            }
+        }
+        result
     }
 
     // === Program ===
@@ -180,6 +266,7 @@ impl<'a> Parser<'a> {
         let mut declarations = Vec::new();
 
         while !self.at(TokenKind::Eof) {
+            self.check_cancelled()?;
             declarations.push(self.parse_declaration()?);
         }
 
@@ -191,11 +278,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_snippet_program(&mut self, start: Span) -> Result<Program, ParseError> {
-        let mut snippets = Vec::new();
-
-        while !self.at(TokenKind::Eof) {
-            snippets.push(self.parse_snippet()?);
-        }
+        let snippets = self.parse_snippet_list()?;
 
         let end = self.span();
         Ok(Program::Snippets {
@@ -204,6 +287,69 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parse snippets until the end of input, without the program wrapper
+    ///
+    /// Used by incremental reparsing, whose tokens cover only part of a file.
+    pub(crate) fn parse_snippet_list(&mut self) -> Result<Vec<Snippet>, ParseError> {
+        let mut snippets = Vec::new();
+        while !self.at(TokenKind::Eof) {
+            self.check_cancelled()?;
+            snippets.push(self.parse_snippet()?);
+        }
+        Ok(snippets)
+    }
+
+    /// Parse a program, recovering from errors at snippet boundaries
+    ///
+    /// A snippet that fails to parse is skipped up to the next `snippet`
+    /// keyword and its error recorded, so one broken snippet does not hide
+    /// the rest of the file. Legacy programs have no recovery points and
+    /// give `None` on their first error, as does cancellation.
+    pub fn parse_program_tolerant(&mut self) -> (Option<Program>, Vec<ParseError>) {
+        let start = self.span();
+        if !self.at(TokenKind::Snippet) {
+            return match self.parse_legacy_program(start) {
+                Ok(program) => (Some(program), Vec::new()),
+                Err(err) => (None, vec![err]),
+            };
+        }
+
+        let mut snippets = Vec::new();
+        let mut errors = Vec::new();
+        while !self.at(TokenKind::Eof) {
+            if let Err(err) = self.check_cancelled() {
+                errors.push(err);
+                return (None, errors);
+            }
+            match self.parse_snippet() {
+                Ok(snippet) => snippets.push(snippet),
+                Err(err @ ParseError::Cancelled { .. }) => {
+                    errors.push(err);
+                    return (None, errors);
+                }
+                Err(err) => {
+                    errors.push(err);
+                    self.recover_to_snippet();
+                }
+            }
+        }
+
+        let end = self.span();
+        (Some(Program::Snippets { snippets, span: start.merge(end) }), errors)
+    }
+
+    /// Skip to the next `snippet` keyword after a failed snippet, dropping
+    /// the nesting state the failed parse left behind
+    fn recover_to_snippet(&mut self) {
+        self.depth = 0;
+        self.loop_depth = 0;
+        self.transaction_targets.clear();
+        // A failed snippet has consumed at least its own `snippet` keyword
+        while !self.at(TokenKind::Snippet) && !self.at(TokenKind::Eof) {
+            self.advance();
+        }
+    }
+
     // === Declarations ===
 
     fn parse_declaration(&mut self) -> Result<Declaration, ParseError> {
@@ -1985,25 +2131,41 @@ impl<'a> Parser<'a> {
         let id = self.parse_attribute("id")?;
         let kind = self.parse_snippet_kind()?;
 
-        // For extern-impl snippets: parse implements="..." and platform="..."
-        let (implements, platform) = if kind == SnippetKind::ExternImpl {
-            let implements = self.parse_optional_attribute("implements")?;
-            let platform = self.parse_optional_attribute("platform")?;
-            (implements, platform)
-        } else {
-            (None, None)
+        // For extern-impl snippets: parse implements="..." and platform="...";
+        // fn, workflow and struct snippets may implement an interface
+        let (implements, platform) = match kind {
+            SnippetKind::ExternImpl => {
+                let implements = self.parse_optional_attribute("implements")?;
+                let platform = self.parse_optional_attribute("platform")?;
+                (implements, platform)
+            }
+            SnippetKind::Function | SnippetKind::Workflow | SnippetKind::Struct => {
+                (self.parse_optional_attribute("implements")?, None)
+            }
+            _ => (None, None),
         };
 
-        // Parse optional notes
+        // Parse optional notes and doc block
         let mut notes = Vec::new();
-        while self.at(TokenKind::Note) {
-            notes.push(self.parse_note()?);
+        let mut doc = None;
+        while self.at(TokenKind::Note) || self.at_doc() {
+            if self.at(TokenKind::Note) {
+                notes.push(self.parse_note()?);
+            } else if doc.is_none() {
+                doc = self.parse_doc()?;
+            } else {
+                return Err(ParseError::Unexpected {
+                    expected: "at most one doc block per snippet".to_string(),
+                    found: self.peek(),
+                    span: self.span(),
+                });
+            }
         }
 
         // Parse sections (order-independent)
         let mut sections = Vec::new();
         while !self.at(TokenKind::End) && !self.at(TokenKind::Eof) {
-            sections.push(self.parse_section()?);
+            sections.push(self.parse_section(kind)?);
         }
 
         self.consume(TokenKind::End)?;
@@ -2013,14 +2175,30 @@ impl<'a> Parser<'a> {
             id,
             kind,
             notes,
+            doc,
             sections,
             implements,
             platform,
+            node_id: None,
             span: start.merge(end),
         })
     }
 
     fn parse_attribute(&mut self, expected_name: &str) -> Result<String, ParseError> {
+        if self.compact {
+            // Compact syntax: `"value"` stands for `name="value"`, and kinds may be bare words
+            if self.at_any(&[TokenKind::String, TokenKind::TripleString]) {
+                return self.consume_string_literal();
+            }
+            if expected_name == "kind" && !self.at(TokenKind::Kind) && self.peek_ahead(1) != TokenKind::Eq {
+                let word = self.peek_text();
+                if !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    self.advance();
+                    return Ok(word);
+                }
+            }
+        }
+
         // Handle attribute names that are also keywords
         let attr_name = match self.peek() {
             TokenKind::Id => { self.advance(); "id".to_string() }
@@ -2079,6 +2257,7 @@ impl<'a> Parser<'a> {
         let kind_str = self.parse_attribute("kind")?;
         match kind_str.as_str() {
             "fn" => Ok(SnippetKind::Function),
+            "workflow" => Ok(SnippetKind::Workflow),
             "struct" => Ok(SnippetKind::Struct),
             "enum" => Ok(SnippetKind::Enum),
             "module" => Ok(SnippetKind::Module),
@@ -2088,6 +2267,10 @@ impl<'a> Parser<'a> {
             "extern-impl" => Ok(SnippetKind::ExternImpl),
             "test" => Ok(SnippetKind::Test),
             "data" => Ok(SnippetKind::Data),
+            "subscriber" => Ok(SnippetKind::Subscriber),
+            "const" => Ok(SnippetKind::Const),
+            "interface" => Ok(SnippetKind::Interface),
+            "alias" => Ok(SnippetKind::Alias),
             _ => Err(ParseError::InvalidSnippetKind {
                 kind: kind_str,
                 span: self.span(),
@@ -2126,8 +2309,49 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_section(&mut self) -> Result<Section, ParseError> {
+    /// Parse a `doc """..."""` block if one comes next
+    fn parse_doc(&mut self) -> Result<Option<Doc>, ParseError> {
+        if !self.at_doc() {
+            return Ok(None);
+        }
+        let start = self.span();
+        self.advance();
+        let content = self.consume_string_literal()?;
+        let end = self.span();
+
+        Ok(Some(Doc {
+            content,
+            span: start.merge(end),
+        }))
+    }
+
+    fn parse_section(&mut self, kind: SnippetKind) -> Result<Section, ParseError> {
         match self.peek() {
+            TokenKind::Contains if kind == SnippetKind::Module => {
+                Ok(Section::Contains(self.parse_contains_section()?))
+            }
+            TokenKind::Contains => Err(ParseError::UnexpectedSection {
+                section: "'contains' outside a module snippet".to_string(),
+                span: self.span(),
+            }),
+            TokenKind::Ident if self.peek_text() == "subscribe" => {
+                if kind != SnippetKind::Subscriber {
+                    return Err(ParseError::UnexpectedSection {
+                        section: "'subscribe' outside a subscriber snippet".to_string(),
+                        span: self.span(),
+                    });
+                }
+                Ok(Section::Subscribe(self.parse_subscribe_section()?))
+            }
+            TokenKind::Ident if self.peek_text() == "invariant" => {
+                if kind != SnippetKind::Struct {
+                    return Err(ParseError::UnexpectedSection {
+                        section: "'invariant' outside a struct snippet".to_string(),
+                        span: self.span(),
+                    });
+                }
+                Ok(Section::Invariant(self.parse_invariant_section()?))
+            }
             TokenKind::Signature => Ok(Section::Signature(self.parse_signature_section()?)),
             TokenKind::Body => Ok(Section::Body(self.parse_body_section()?)),
             TokenKind::Effects => Ok(Section::Effects(self.parse_effects_section()?)),
@@ -2153,9 +2377,18 @@ impl<'a> Parser<'a> {
             TokenKind::Fn => SignatureKind::Function(self.parse_function_signature()?),
             TokenKind::Struct => SignatureKind::Struct(self.parse_struct_signature()?),
             TokenKind::Enum => SignatureKind::Enum(self.parse_enum_signature()?),
+            TokenKind::Ident if self.peek_text() == "const" => {
+                SignatureKind::Const(self.parse_const_signature()?)
+            }
+            TokenKind::Ident if self.peek_text() == "interface" => {
+                SignatureKind::Interface(self.parse_interface_signature()?)
+            }
+            TokenKind::Ident if self.peek_text() == "alias" => {
+                SignatureKind::Alias(self.parse_alias_signature()?)
+            }
             _ => {
                 return Err(ParseError::Unexpected {
-                    expected: "'fn', 'struct', or 'enum'".to_string(),
+                    expected: "'fn', 'struct', 'enum', 'const', 'interface', or 'alias'".to_string(),
                     found: self.peek(),
                     span: self.span(),
                 })
@@ -2183,7 +2416,7 @@ impl<'a> Parser<'a> {
 
         while !self.at(TokenKind::End) && !self.at(TokenKind::Eof) {
             match self.peek() {
-                TokenKind::Param => params.push(self.parse_param_decl()?),
+                TokenKind::Param | TokenKind::Ident if self.at_param() => params.push(self.parse_param_decl()?),
                 TokenKind::Returns => returns = Some(self.parse_returns_decl()?),
                 TokenKind::Generic => generics.push(self.parse_generic_param()?),
                 _ => break,
@@ -2214,15 +2447,19 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_param_decl(&mut self) -> Result<ParamDecl, ParseError> {
+        let doc = self.parse_doc()?;
         let start = self.span();
         self.consume(TokenKind::Param)?;
         let name = self.parse_attribute("name")?;
         let ty = self.parse_attribute_type("type")?;
+        let sensitive = self.parse_sensitive_flag();
         let end = self.span();
 
         Ok(ParamDecl {
             name,
             ty,
+            sensitive,
+            doc,
             span: start.merge(end),
         })
     }
@@ -2298,6 +2535,10 @@ impl<'a> Parser<'a> {
         let start = self.span();
         self.consume(TokenKind::Struct)?;
         let name = self.parse_attribute("name")?;
+        let newtype = self.at(TokenKind::Ident) && self.peek_text() == "newtype";
+        if newtype {
+            self.advance();
+        }
 
         let mut fields = Vec::new();
         while self.at(TokenKind::Field) {
@@ -2310,6 +2551,7 @@ impl<'a> Parser<'a> {
         Ok(StructSignature {
             name,
             fields,
+            newtype,
             span: start.merge(end),
         })
     }
@@ -2326,6 +2568,8 @@ impl<'a> Parser<'a> {
         let mut auto = false;
         let mut unique = false;
         let mut optional = false;
+        let mut foreign_key = None;
+        let mut sensitive = false;
 
         // Handle keyword flags first
         loop {
@@ -2386,6 +2630,20 @@ impl<'a> Parser<'a> {
                         optional = true;
                     }
                 }
+                "sensitive" => sensitive = self.parse_sensitive_flag(),
+                "foreign_key" | "references" => {
+                    let fk_start = self.span();
+                    self.advance();
+                    self.consume(TokenKind::Eq)?;
+                    let target = self.consume_string_literal()?;
+                    let fk_span = fk_start.merge(self.span());
+                    // "users.id" -> table "users", field "id"; bare "users" means its "id"
+                    let (table, field) = match target.rsplit_once('.') {
+                        Some((table, field)) => (table.to_string(), field.to_string()),
+                        None => (target, "id".to_string()),
+                    };
+                    foreign_key = Some(ForeignKey { table, field, span: fk_span });
+                }
                 _ => break,
             }
         }
@@ -2399,10 +2657,32 @@ impl<'a> Parser<'a> {
             auto,
             unique,
             optional,
+            foreign_key,
+            sensitive,
             span: start.merge(end),
         })
     }
 
+    /// Parse an optional `sensitive` or `sensitive=true|false` flag
+    fn parse_sensitive_flag(&mut self) -> bool {
+        if !(self.at(TokenKind::Ident) && self.peek_text() == "sensitive") {
+            return false;
+        }
+        self.advance();
+        if !self.at(TokenKind::Eq) {
+            return true;
+        }
+        self.advance();
+        if self.at(TokenKind::False) {
+            self.advance();
+            return false;
+        }
+        if self.at(TokenKind::True) {
+            self.advance();
+        }
+        true
+    }
+
     fn parse_enum_signature(&mut self) -> Result<EnumSignature, ParseError> {
         let start = self.span();
         self.consume(TokenKind::Enum)?;
@@ -2423,6 +2703,58 @@ impl<'a> Parser<'a> {
         })
     }
 
+    fn parse_alias_signature(&mut self) -> Result<AliasSignature, ParseError> {
+        let start = self.span();
+        self.consume(TokenKind::Ident)?; // "alias"
+        let name = self.parse_attribute("name")?;
+        let ty = self.parse_attribute_type("type")?;
+        let end = self.span();
+
+        Ok(AliasSignature {
+            name,
+            ty,
+            span: start.merge(end),
+        })
+    }
+
+    fn parse_const_signature(&mut self) -> Result<ConstSignature, ParseError> {
+        let start = self.span();
+        self.consume(TokenKind::Ident)?; // "const"
+        let name = self.parse_attribute("name")?;
+        let ty = self.parse_attribute_type("type")?;
+        self.consume(TokenKind::Lit)?;
+        self.consume(TokenKind::Eq)?;
+        let value = self.parse_literal()?;
+        let end = self.span();
+
+        Ok(ConstSignature {
+            name,
+            ty,
+            value,
+            span: start.merge(end),
+        })
+    }
+
+    fn parse_interface_signature(&mut self) -> Result<InterfaceSignature, ParseError> {
+        let start = self.span();
+        self.consume(TokenKind::Ident)?; // "interface"
+        let name = self.parse_attribute("name")?;
+
+        let mut methods = Vec::new();
+        while self.at(TokenKind::Fn) {
+            methods.push(self.parse_function_signature()?);
+        }
+
+        self.consume(TokenKind::End)?; // interface end
+        let end = self.span();
+
+        Ok(InterfaceSignature {
+            name,
+            methods,
+            span: start.merge(end),
+        })
+    }
+
     fn parse_variant_decl(&mut self) -> Result<SnippetVariantDecl, ParseError> {
         let start = self.span();
         self.consume(TokenKind::Ident)?; // "variant"
@@ -2454,7 +2786,7 @@ impl<'a> Parser<'a> {
         self.consume(TokenKind::Body)?;
 
         let mut steps = Vec::new();
-        while self.at(TokenKind::Step) {
+        while self.at_step() {
             steps.push(self.parse_step()?);
         }
 
@@ -2468,6 +2800,24 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_step(&mut self) -> Result<Step, ParseError> {
+        self.check_cancelled()?;
+
+        // Nested steps recurse through here; bail out before the stack does
+        if self.depth >= self.limits.max_nesting_depth {
+            return Err(ParseError::NestingTooDeep {
+                limit: self.limits.max_nesting_depth,
+                span: self.span(),
+            });
+        }
+
+        self.depth += 1;
+        let step = self.parse_step_inner();
+        self.depth -= 1;
+        step
+    }
+
+    fn parse_step_inner(&mut self) -> Result<Step, ParseError> {
+        let doc = self.parse_doc()?;
         let start = self.span();
         self.consume(TokenKind::Step)?;
 
@@ -2475,7 +2825,9 @@ impl<'a> Parser<'a> {
         let step_kind_str = self.parse_attribute("kind")?;
 
         let kind = match step_kind_str.as_str() {
+            "pipeline" => StepKind::Pipeline(self.parse_pipeline_step()?),
             "return" => StepKind::Return(self.parse_return_step()?),
+            "raise" => StepKind::Raise(self.parse_raise_step()?),
             "compute" => StepKind::Compute(self.parse_compute_step()?),
             "call" => StepKind::Call(self.parse_call_step()?),
             "bind" => StepKind::Bind(self.parse_bind_step()?),
@@ -2486,6 +2838,11 @@ impl<'a> Parser<'a> {
             "update" => StepKind::Update(self.parse_update_step()?),
             "delete" => StepKind::Delete(self.parse_delete_step()?),
             "for" => StepKind::For(self.parse_for_step()?),
+            "break" => StepKind::Break(BreakStep { span: self.parse_loop_control("break")? }),
+            "continue" => StepKind::Continue(ContinueStep { span: self.parse_loop_control("continue")? }),
+            "lambda" => StepKind::Lambda(self.parse_lambda_step()?),
+            "using" => StepKind::Using(self.parse_using_step()?),
+            "assert" => StepKind::Assert(self.parse_assert_step()?),
             "transaction" => StepKind::Transaction(self.parse_transaction_step()?),
             "traverse" => StepKind::Traverse(self.parse_traverse_step()?),
             "construct" => StepKind::Construct(self.parse_construct_step()?),
@@ -2504,7 +2861,20 @@ impl<'a> Parser<'a> {
             self.advance();
         }
 
-        let output_binding = self.parse_attribute("as")?;
+        let output_binding = self.parse_output_binding()?;
+
+        // An expectation checks the call's result, so must name its binding
+        if let StepKind::Call(call) = &kind {
+            for expect in &call.expects {
+                if output_binding == "_" || expect.field.split('.').next() != Some(output_binding.as_str()) {
+                    return Err(ParseError::ExpectationNotOnResult {
+                        field: expect.field.clone(),
+                        binding: output_binding,
+                        span: expect.span,
+                    });
+                }
+            }
+        }
 
         // Parse optional handle block (only valid for call steps)
         let kind = if self.at(TokenKind::Handle) {
@@ -2532,10 +2902,71 @@ impl<'a> Parser<'a> {
             id,
             kind,
             output_binding,
+            doc,
+            node_id: None,
             span: start.merge(end),
         })
     }
 
+    /// Parse a step's `as` attribute
+    fn parse_output_binding(&mut self) -> Result<String, ParseError> {
+        // Compact syntax leaves the discarded binding implicit
+        if self.compact && self.at_any(&[TokenKind::End, TokenKind::Handle]) {
+            Ok("_".to_string())
+        } else {
+            self.parse_attribute("as")
+        }
+    }
+
+    /// Parse a pipeline step's `from` and stages; lowering expands it into
+    /// call steps
+    fn parse_pipeline_step(&mut self) -> Result<PipelineStep, ParseError> {
+        let start = self.span();
+        let from = Self::string_to_input_source(self.parse_attribute("from")?);
+
+        let mut stages = Vec::new();
+        while self.at(TokenKind::Ident) && self.peek_text() == "stage" {
+            stages.push(self.parse_pipeline_stage()?);
+        }
+        if stages.is_empty() {
+            return Err(ParseError::Unexpected {
+                expected: "'stage'".to_string(),
+                found: self.peek(),
+                span: self.span(),
+            });
+        }
+
+        let end = self.span();
+        Ok(PipelineStep {
+            from,
+            stages,
+            span: start.merge(end),
+        })
+    }
+
+    /// Parse `stage fn="..." [into="..."]` with the call's type args and args;
+    /// `into` names the argument the threaded value is passed as
+    fn parse_pipeline_stage(&mut self) -> Result<PipelineStage, ParseError> {
+        let start = self.span();
+        self.consume(TokenKind::Ident)?; // "stage"
+        let fn_name = self.parse_attribute("fn")?;
+
+        let into = if self.at(TokenKind::Into) {
+            self.advance();
+            self.consume(TokenKind::Eq)?;
+            self.consume_string_literal()?
+        } else {
+            "value".to_string()
+        };
+
+        let call = self.parse_call_args(fn_name, start)?;
+        Ok(PipelineStage {
+            into,
+            span: call.span,
+            call,
+        })
+    }
+
     fn parse_return_step(&mut self) -> Result<ReturnStep, ParseError> {
         let start = self.span();
 
@@ -2567,6 +2998,27 @@ impl<'a> Parser<'a> {
         })
     }
 
+    fn parse_raise_step(&mut self) -> Result<RaiseStep, ParseError> {
+        let start = self.span();
+
+        // variant type="NotFound" field name="id" from="id" end
+        if !(self.at(TokenKind::Ident) && self.peek_text() == "variant") {
+            return Err(ParseError::Unexpected {
+                expected: "'variant'".to_string(),
+                found: self.peek(),
+                span: self.span(),
+            });
+        }
+        let variant = self.parse_variant_construction()?;
+
+        let end = self.span();
+
+        Ok(RaiseStep {
+            variant,
+            span: start.merge(end),
+        })
+    }
+
     fn parse_struct_construction(&mut self) -> Result<StructConstruction, ParseError> {
         let start = self.span();
         self.consume(TokenKind::Struct)?;
@@ -2583,6 +3035,7 @@ impl<'a> Parser<'a> {
 
         Ok(StructConstruction {
             ty,
+            from_base: None,
             fields,
             span: start.merge(end),
         })
@@ -2599,7 +3052,11 @@ impl<'a> Parser<'a> {
             fields.push(self.parse_inline_field_assignment()?);
         }
 
-        self.consume(TokenKind::End)?;
+        // The closing `end` may be omitted when the fields run straight into
+        // the step's `as=` (`variant type="Error" field ... as="_" end`)
+        if !self.at(TokenKind::As) {
+            self.consume(TokenKind::End)?;
+        }
         let end = self.span();
 
         Ok(VariantConstruction {
@@ -2695,6 +3152,15 @@ impl<'a> Parser<'a> {
                     // Arithmetic
                     "mod" => Ok(Operation::Mod),
                     "neg" => Ok(Operation::Neg),
+                    "add_wrapping" => Ok(Operation::AddWrapping),
+                    "sub_wrapping" => Ok(Operation::SubWrapping),
+                    "mul_wrapping" => Ok(Operation::MulWrapping),
+                    "add_saturating" => Ok(Operation::AddSaturating),
+                    "sub_saturating" => Ok(Operation::SubSaturating),
+                    "mul_saturating" => Ok(Operation::MulSaturating),
+                    "add_checked" => Ok(Operation::AddChecked),
+                    "sub_checked" => Ok(Operation::SubChecked),
+                    "mul_checked" => Ok(Operation::MulChecked),
 
                     // String operations — removed (now extern-abstract calls)
 
@@ -2727,6 +3193,12 @@ impl<'a> Parser<'a> {
                     "parse_int" => Ok(Operation::ParseInt),
                     "parse_float" => Ok(Operation::ParseFloat),
 
+                    // Regex operations
+                    "regex_match" => Ok(Operation::RegexMatch),
+                    "regex_find_all" => Ok(Operation::RegexFindAll),
+                    "regex_capture" => Ok(Operation::RegexCapture),
+                    "regex_replace" => Ok(Operation::RegexReplace),
+
                     // List operations — removed (now extern-abstract calls)
 
                     // Map operations (map_get removed — now extern-abstract call)
@@ -2740,6 +3212,20 @@ impl<'a> Parser<'a> {
                     "map_merge" => Ok(Operation::MapMerge),
                     "map_is_empty" => Ok(Operation::MapIsEmpty),
 
+                    // Fixed-size array operations
+                    "array_of" => Ok(Operation::ArrayOf),
+                    "array_fill" => Ok(Operation::ArrayFill),
+                    "array_get" => Ok(Operation::ArrayGet),
+                    "array_set" => Ok(Operation::ArraySet),
+                    "array_len" => Ok(Operation::ArrayLen),
+
+                    // Numeric vector operations
+                    "vec_add" => Ok(Operation::VecAdd),
+                    "vec_sub" => Ok(Operation::VecSub),
+                    "vec_scale" => Ok(Operation::VecScale),
+                    "dot" => Ok(Operation::Dot),
+                    "matmul" => Ok(Operation::Matmul),
+
                     // Set operations
                     "set_len" => Ok(Operation::SetLen),
                     "set_has" => Ok(Operation::SetHas),
@@ -2754,6 +3240,11 @@ impl<'a> Parser<'a> {
                     "set_is_empty" => Ok(Operation::SetIsEmpty),
                     "set_to_list" => Ok(Operation::SetToList),
 
+                    // Optional operations
+                    "is_some" => Ok(Operation::IsSome),
+                    "is_none" => Ok(Operation::IsNone),
+                    "unwrap_or" => Ok(Operation::UnwrapOr),
+
                     // DateTime operations
                     "dt_year" => Ok(Operation::DtYear),
                     "dt_month" => Ok(Operation::DtMonth),
@@ -2763,12 +3254,49 @@ impl<'a> Parser<'a> {
                     "dt_second" => Ok(Operation::DtSecond),
                     "dt_weekday" => Ok(Operation::DtWeekday),
                     "dt_unix" => Ok(Operation::DtUnix),
-                    "dt_add_days" => Ok(Operation::DtAddDays),
-                    "dt_add_hours" => Ok(Operation::DtAddHours),
-                    "dt_add_minutes" => Ok(Operation::DtAddMinutes),
-                    "dt_add_seconds" => Ok(Operation::DtAddSeconds),
+                    "dt_add" => Ok(Operation::DtAdd),
+                    "dt_sub" => Ok(Operation::DtSub),
                     "dt_diff" => Ok(Operation::DtDiff),
                     "dt_format" => Ok(Operation::DtFormat),
+                    // dt_add_days etc. — removed (now dt_add with a Duration)
+
+                    // Duration operations
+                    "dur_seconds" => Ok(Operation::DurSeconds),
+                    "dur_minutes" => Ok(Operation::DurMinutes),
+                    "dur_hours" => Ok(Operation::DurHours),
+                    "dur_days" => Ok(Operation::DurDays),
+                    "dur_total_seconds" => Ok(Operation::DurTotalSeconds),
+                    "dur_add" => Ok(Operation::DurAdd),
+                    "dur_sub" => Ok(Operation::DurSub),
+                    "dur_scale" => Ok(Operation::DurScale),
+                    "dur_format" => Ok(Operation::DurFormat),
+
+                    // Decimal operations
+                    "dec_add" => Ok(Operation::DecAdd),
+                    "dec_sub" => Ok(Operation::DecSub),
+                    "dec_mul" => Ok(Operation::DecMul),
+                    "dec_div" => Ok(Operation::DecDiv),
+                    "dec_round" => Ok(Operation::DecRound),
+                    "dec_cmp" => Ok(Operation::DecCmp),
+                    "dec_from_int" => Ok(Operation::DecFromInt),
+                    "dec_format" => Ok(Operation::DecFormat),
+
+                    // BigInt operations
+                    "big_add" => Ok(Operation::BigAdd),
+                    "big_sub" => Ok(Operation::BigSub),
+                    "big_mul" => Ok(Operation::BigMul),
+                    "big_div" => Ok(Operation::BigDiv),
+                    "big_mod" => Ok(Operation::BigMod),
+                    "big_cmp" => Ok(Operation::BigCmp),
+                    "big_from_int" => Ok(Operation::BigFromInt),
+                    "big_format" => Ok(Operation::BigFormat),
+
+                    // Json operations
+                    "json_parse" => Ok(Operation::JsonParse),
+                    "json_stringify" => Ok(Operation::JsonStringify),
+                    "json_get" => Ok(Operation::JsonGet),
+                    "json_set" => Ok(Operation::JsonSet),
+                    "json_keys" => Ok(Operation::JsonKeys),
 
                     // Bytes operations
                     "bytes_len" => Ok(Operation::BytesLen),
@@ -2780,6 +3308,14 @@ impl<'a> Parser<'a> {
                     "bytes_to_hex" => Ok(Operation::BytesToHex),
                     "bytes_is_empty" => Ok(Operation::BytesIsEmpty),
 
+                    // Encoding operations
+                    "bytes_from_base64" => Ok(Operation::BytesFromBase64),
+                    "bytes_to_base64url" => Ok(Operation::BytesToBase64Url),
+                    "bytes_from_base64url" => Ok(Operation::BytesFromBase64Url),
+                    "bytes_from_hex" => Ok(Operation::BytesFromHex),
+                    "url_encode" => Ok(Operation::UrlEncode),
+                    "url_decode" => Ok(Operation::UrlDecode),
+
                     _ => Err(ParseError::InvalidOperation {
                         name: op_name,
                         span: self.span(),
@@ -2819,6 +3355,10 @@ impl<'a> Parser<'a> {
         self.consume(TokenKind::Input)?;
 
         let source = match self.peek() {
+            // Compact syntax: `input "x"` is `input var="x"`
+            TokenKind::String if self.compact => {
+                Self::string_to_input_source(self.consume_string_literal()?)
+            }
             TokenKind::Var => {
                 self.advance();
                 self.consume(TokenKind::Eq)?;
@@ -2859,6 +3399,40 @@ impl<'a> Parser<'a> {
     fn parse_call_step(&mut self) -> Result<CallStep, ParseError> {
         let start = self.span();
         let fn_name = self.parse_attribute("fn")?;
+        let mut call = self.parse_call_args(fn_name, start)?;
+        while self.at(TokenKind::Ident) && self.peek_text() == "expect" {
+            call.expects.push(self.parse_expectation()?);
+        }
+        Ok(call)
+    }
+
+    /// Parse `expect field="result" lit=5`
+    fn parse_expectation(&mut self) -> Result<Expectation, ParseError> {
+        let start = self.span();
+        self.consume(TokenKind::Ident)?; // "expect"
+        self.consume(TokenKind::Field)?;
+        self.consume(TokenKind::Eq)?;
+        let field = self.consume_string_literal()?;
+        self.consume(TokenKind::Lit)?;
+        self.consume(TokenKind::Eq)?;
+        let value = self.parse_literal()?;
+        let end = self.span();
+
+        Ok(Expectation {
+            field,
+            value,
+            span: start.merge(end),
+        })
+    }
+
+    /// Parse the type args and args that follow a call's `fn` attribute
+    fn parse_call_args(&mut self, fn_name: String, start: Span) -> Result<CallStep, ParseError> {
+        // type arg="Int"
+        let mut type_args = Vec::new();
+        while self.at(TokenKind::Type) {
+            self.advance();
+            type_args.push(self.parse_attribute_type("arg")?);
+        }
 
         let mut args = Vec::new();
         while self.at(TokenKind::Ident) && self.peek_text() == "arg" {
@@ -2870,8 +3444,10 @@ impl<'a> Parser<'a> {
         // Note: handle block is parsed by parse_step and injected separately
         Ok(CallStep {
             fn_name,
+            type_args,
             args,
             handle: None,
+            expects: vec![],
             span: start.merge(end),
         })
     }
@@ -2900,7 +3476,7 @@ impl<'a> Parser<'a> {
         let error_type = self.parse_attribute("type")?;
 
         let mut steps = Vec::new();
-        while self.at(TokenKind::Step) {
+        while self.at_step() {
             steps.push(self.parse_step()?);
         }
 
@@ -2937,7 +3513,7 @@ impl<'a> Parser<'a> {
             let of = self.consume_string_literal()?;
             InputSource::Field { of, field }
         } else if self.at(TokenKind::Fn) {
-            // fn="|f| equals(f.name, field_name)" - lambda syntax
+            // fn="pred" - a function-valued binding, e.g. from a lambda step
             self.advance();
             self.consume(TokenKind::Eq)?;
             InputSource::Var(self.consume_string_literal()?)
@@ -2983,9 +3559,11 @@ impl<'a> Parser<'a> {
             self.advance();
             self.consume(TokenKind::Eq)?;
             BindSource::Lit(self.parse_literal()?)
+        } else if self.at(TokenKind::Ident) && self.peek_text() == "variant" {
+            BindSource::Variant(self.parse_variant_construction()?)
         } else {
             return Err(ParseError::Unexpected {
-                expected: "'field', 'var', 'from', or 'lit'".to_string(),
+                expected: "'field', 'var', 'from', 'lit', or 'variant'".to_string(),
                 found: self.peek(),
                 span: self.span(),
             });
@@ -3009,7 +3587,7 @@ impl<'a> Parser<'a> {
         // then ... end
         self.consume(TokenKind::Ident)?; // "then" is not a keyword
         let mut then_steps = Vec::new();
-        while self.at(TokenKind::Step) {
+        while self.at_step() {
             then_steps.push(self.parse_step()?);
         }
         self.consume(TokenKind::End)?;
@@ -3018,7 +3596,7 @@ impl<'a> Parser<'a> {
         let else_steps = if self.at(TokenKind::Else) {
             self.advance();
             let mut steps = Vec::new();
-            while self.at(TokenKind::Step) {
+            while self.at_step() {
                 steps.push(self.parse_step()?);
             }
             self.consume(TokenKind::End)?;
@@ -3062,8 +3640,10 @@ impl<'a> Parser<'a> {
         let start = self.span();
         self.consume(TokenKind::Ident)?; // "case"
 
-        // Parse pattern: variant type="Json::Null" bindings=("s") OR wildcard
-        let pattern = if self.at(TokenKind::Ident) {
+        // Parse pattern: variant type="Json::Null" bindings=("s") OR type="Int" OR wildcard
+        let pattern = if self.at(TokenKind::Type) {
+            MatchPattern::Type { ty: self.parse_attribute_type("type")? }
+        } else if self.at(TokenKind::Ident) {
             match self.peek_text().as_str() {
                 "variant" => {
                     self.advance();
@@ -3084,7 +3664,7 @@ impl<'a> Parser<'a> {
                 }
                 _ => {
                     return Err(ParseError::Unexpected {
-                        expected: "'variant' or 'wildcard'".to_string(),
+                        expected: "'variant', 'type' or 'wildcard'".to_string(),
                         found: self.peek(),
                         span: self.span(),
                     });
@@ -3092,7 +3672,7 @@ impl<'a> Parser<'a> {
             }
         } else {
             return Err(ParseError::Unexpected {
-                expected: "'variant' or 'wildcard'".to_string(),
+                expected: "'variant', 'type' or 'wildcard'".to_string(),
                 found: self.peek(),
                 span: self.span(),
             });
@@ -3100,7 +3680,7 @@ impl<'a> Parser<'a> {
 
         // Parse steps until end
         let mut steps = Vec::new();
-        while self.at(TokenKind::Step) {
+        while self.at_step() {
             steps.push(self.parse_step()?);
         }
 
@@ -3141,8 +3721,11 @@ impl<'a> Parser<'a> {
             None
         };
 
-        // target="project" or target="db"
-        let target = self.parse_attribute("target")?;
+        // target="project" or target="db"; optional inside a transaction
+        let target = match self.transaction_targets.last().cloned() {
+            Some(inherited) => self.parse_optional_attribute("target")?.unwrap_or(inherited),
+            None => self.parse_attribute("target")?,
+        };
 
         // If we have a dialect, parse SQL body
         if dialect.is_some() {
@@ -3382,7 +3965,7 @@ impl<'a> Parser<'a> {
                         // type= (where type is a keyword)
                         self.consume(TokenKind::Type)?;
                         self.consume(TokenKind::Eq)?;
-                        let rel_type = self.consume_text(TokenKind::Ident)?;
+                        let rel_type = self.consume_relation_type()?;
                         Ok(ConditionKind::RelTo { target, rel_type })
                     }
                     "rel_from" => {
@@ -3395,7 +3978,7 @@ impl<'a> Parser<'a> {
                         // type= (where type is a keyword)
                         self.consume(TokenKind::Type)?;
                         self.consume(TokenKind::Eq)?;
-                        let rel_type = self.consume_text(TokenKind::Ident)?;
+                        let rel_type = self.consume_relation_type()?;
                         Ok(ConditionKind::RelFrom { source, rel_type })
                     }
                     "not_equals" => {
@@ -3644,6 +4227,13 @@ impl<'a> Parser<'a> {
         // type="Point"
         let ty = self.parse_attribute_type("type")?;
 
+        // from_base="p" (optional record update)
+        let from_base = if self.at(TokenKind::Ident) && self.peek_text() == "from_base" {
+            Some(self.parse_attribute("from_base")?)
+        } else {
+            None
+        };
+
         // field name="x" from="x"
         let mut fields = Vec::new();
         while self.at(TokenKind::Field) {
@@ -3654,6 +4244,7 @@ impl<'a> Parser<'a> {
 
         Ok(StructConstruction {
             ty,
+            from_base,
             fields,
             span: start.merge(end),
         })
@@ -3670,9 +4261,11 @@ impl<'a> Parser<'a> {
 
         // Parse nested steps
         let mut steps = Vec::new();
-        while self.at(TokenKind::Step) {
+        self.loop_depth += 1;
+        while self.at_step() {
             steps.push(self.parse_step()?);
         }
+        self.loop_depth -= 1;
 
         let end = self.span();
 
@@ -3684,34 +4277,158 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_transaction_step(&mut self) -> Result<TransactionStep, ParseError> {
+    fn parse_lambda_step(&mut self) -> Result<LambdaStep, ParseError> {
         let start = self.span();
 
-        // Optional isolation="serializable"
-        let isolation = if self.at(TokenKind::Ident) && self.peek_text() == "isolation" {
+        // param name="x" type="Int" ... returns type="Int"
+        let mut params = Vec::new();
+        while self.at_param() {
+            params.push(self.parse_param_decl()?);
+        }
+        let returns = if self.at(TokenKind::Returns) {
             self.advance();
-            self.consume(TokenKind::Eq)?;
-            let level_str = self.consume_string_literal()?;
-            Some(match level_str.as_str() {
-                "read_uncommitted" => IsolationLevel::ReadUncommitted,
-                "read_committed" => IsolationLevel::ReadCommitted,
-                "repeatable_read" => IsolationLevel::RepeatableRead,
-                "serializable" => IsolationLevel::Serializable,
-                _ => IsolationLevel::ReadCommitted,
-            })
+            Some(self.parse_attribute_type("type")?)
         } else {
             None
         };
 
-        // Parse nested steps
+        // The body runs when the lambda is called, outside any loop around its creation
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        let mut steps = Vec::new();
+        while self.at_step() {
+            steps.push(self.parse_step()?);
+        }
+        self.loop_depth = loop_depth;
+
+        let end = self.span();
+
+        Ok(LambdaStep {
+            params,
+            returns,
+            steps,
+            span: start.merge(end),
+        })
+    }
+
+    fn parse_using_step(&mut self) -> Result<UsingStep, ParseError> {
+        let start = self.span();
+
+        // var="conn"
+        let var = self.parse_attribute("var")?;
+
+        // acquire fn="db.open" arg name="url" from="url"
+        self.consume_word("acquire")?;
+        let acquire = self.parse_call_step()?;
+
+        // release fn="db.close"
+        self.consume_word("release")?;
+        let release = self.parse_attribute("fn")?;
+
         let mut steps = Vec::new();
-        while self.at(TokenKind::Step) {
+        while self.at_step() {
             steps.push(self.parse_step()?);
         }
 
         let end = self.span();
 
+        Ok(UsingStep {
+            var,
+            acquire,
+            release,
+            steps,
+            span: start.merge(end),
+        })
+    }
+
+    fn parse_assert_step(&mut self) -> Result<AssertStep, ParseError> {
+        let start = self.span();
+
+        // op=less input var="n" input lit=10, like a compute step
+        let ComputeStep { op, inputs, .. } = self.parse_compute_step()?;
+        let message = self.parse_message()?;
+
+        let end = self.span();
+
+        Ok(AssertStep {
+            op,
+            inputs,
+            message,
+            span: start.merge(end),
+        })
+    }
+
+    /// Parse an optional `message="..."` attribute
+    fn parse_message(&mut self) -> Result<Option<String>, ParseError> {
+        if self.at(TokenKind::Ident) && self.peek_text() == "message" {
+            Ok(Some(self.parse_attribute("message")?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// `break` and `continue` have no body and are only valid inside a `for` body
+    fn parse_loop_control(&mut self, kind: &str) -> Result<Span, ParseError> {
+        let span = self.span();
+        if self.loop_depth == 0 {
+            return Err(ParseError::LoopControlOutsideLoop {
+                kind: kind.to_string(),
+                span,
+            });
+        }
+        Ok(span)
+    }
+
+    fn parse_transaction_step(&mut self) -> Result<TransactionStep, ParseError> {
+        let start = self.span();
+
+        // Optional target="main_db" and isolation="serializable", in either order
+        let mut target = None;
+        let mut isolation = None;
+        loop {
+            if target.is_none() {
+                if let Some(t) = self.parse_optional_attribute("target")? {
+                    target = Some(t);
+                    continue;
+                }
+            }
+            if isolation.is_none() && self.at(TokenKind::Ident) && self.peek_text() == "isolation" {
+                self.advance();
+                self.consume(TokenKind::Eq)?;
+                let level_span = self.span();
+                let level_str = self.consume_string_literal()?;
+                isolation = Some(match level_str.as_str() {
+                    "read_uncommitted" => IsolationLevel::ReadUncommitted,
+                    "read_committed" => IsolationLevel::ReadCommitted,
+                    "repeatable_read" => IsolationLevel::RepeatableRead,
+                    "serializable" => IsolationLevel::Serializable,
+                    _ => {
+                        return Err(ParseError::InvalidIsolationLevel {
+                            level: level_str,
+                            span: level_span,
+                        })
+                    }
+                });
+                continue;
+            }
+            break;
+        }
+
+        // Parse nested steps; queries inside inherit the transaction target
+        if let Some(t) = &target {
+            self.transaction_targets.push(t.clone());
+        }
+        let mut steps = Vec::new();
+        while self.at_step() {
+            steps.push(self.parse_step()?);
+        }
+        if target.is_some() {
+            self.transaction_targets.pop();
+        }
+
+        let end = self.span();
+
         Ok(TransactionStep {
+            target,
             isolation,
             steps,
             span: start.merge(end),
@@ -3882,11 +4599,14 @@ impl<'a> Parser<'a> {
         // branch id="b1"
         let id = self.parse_attribute("id")?;
 
-        // Parse nested steps
+        // Parse nested steps. Branches run concurrently, so `break` and
+        // `continue` cannot reach a loop enclosing the parallel/race step.
         let mut steps = Vec::new();
-        while self.at(TokenKind::Step) {
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        while self.at_step() {
             steps.push(self.parse_step()?);
         }
+        self.loop_depth = loop_depth;
 
         self.consume(TokenKind::End)?;
         let end = self.span();
@@ -3940,11 +4660,12 @@ impl<'a> Parser<'a> {
             name.push_str(&segment);
         }
 
-        // Parse optional parameters: effect filesystem(path="/data")
+        // Parse optional parameters, in parentheses: effect filesystem(path="/data"),
+        // or as attributes: effect network.http host="api.example.com"
         let params = if self.at(TokenKind::LParen) {
             self.parse_effect_params()?
         } else {
-            Vec::new()
+            self.parse_effect_attributes()?
         };
 
         let end = self.span();
@@ -3992,6 +4713,24 @@ impl<'a> Parser<'a> {
         Ok(params)
     }
 
+    /// Parse effect parameters written as attributes: key=value key2=value2
+    fn parse_effect_attributes(&mut self) -> Result<Vec<EffectParam>, ParseError> {
+        let mut params = Vec::new();
+        while self.at(TokenKind::Ident) && self.peek_ahead(1) == TokenKind::Eq {
+            let param_start = self.span();
+            let param_name = self.consume_text(TokenKind::Ident)?;
+            self.consume(TokenKind::Eq)?;
+            let value = self.parse_literal()?;
+            let param_end = self.span();
+            params.push(EffectParam {
+                name: param_name,
+                value,
+                span: param_start.merge(param_end),
+            });
+        }
+        Ok(params)
+    }
+
     fn parse_metadata_section(&mut self) -> Result<MetadataSection, ParseError> {
         let start = self.span();
         self.consume(TokenKind::Metadata)?;
@@ -4018,6 +4757,17 @@ impl<'a> Parser<'a> {
             TokenKind::Database => { self.advance(); "database".to_string() }
             TokenKind::Connection => { self.advance(); "connection".to_string() }
             TokenKind::Dialect => { self.advance(); "dialect".to_string() }
+            // Triggers name their kind: trigger schedule="0 * * * *"
+            TokenKind::Ident if self.peek_text() == "trigger" => {
+                self.advance();
+                format!("trigger.{}", self.consume_text(TokenKind::Ident)?)
+            }
+            // Allowances name what they allow: allow cycle group="parser"
+            TokenKind::Ident if self.peek_text() == "allow" => {
+                self.advance();
+                let what = self.consume_text(TokenKind::Ident)?;
+                format!("allow.{}.{}", what, self.consume_text(TokenKind::Ident)?)
+            }
             TokenKind::Ident => self.consume_text(TokenKind::Ident)?,
             _ => {
                 return Err(ParseError::unexpected(
@@ -4086,6 +4836,7 @@ impl<'a> Parser<'a> {
         // Parse req contents until "end"
         let mut text = None;
         let mut priority = None;
+        let mut status = None;
 
         while !self.at(TokenKind::End) && !self.at(TokenKind::Eof) {
             if self.at(TokenKind::Ident) && self.peek_text() == "text" {
@@ -4101,6 +4852,16 @@ impl<'a> Parser<'a> {
                     "low" => Some(Priority::Low),
                     _ => None,
                 };
+            } else if self.at(TokenKind::Ident) && self.peek_text() == "status" {
+                self.advance();
+                let s_str = self.consume_text(TokenKind::Ident)?;
+                status = match s_str.as_str() {
+                    "draft" => Some(ReqStatus::Draft),
+                    "approved" => Some(ReqStatus::Approved),
+                    "implemented" => Some(ReqStatus::Implemented),
+                    "tested" => Some(ReqStatus::Tested),
+                    _ => None,
+                };
             } else {
                 break;
             }
@@ -4113,7 +4874,7 @@ impl<'a> Parser<'a> {
             id,
             text,
             priority,
-            status: None,
+            status,
             span: start.merge(end),
         })
     }
@@ -4159,7 +4920,7 @@ impl<'a> Parser<'a> {
 
         // Parse test steps
         let mut steps = Vec::new();
-        while self.at(TokenKind::Step) {
+        while self.at_step() {
             steps.push(self.parse_step()?);
         }
 
@@ -4236,6 +4997,82 @@ impl<'a> Parser<'a> {
         })
     }
 
+    fn parse_contains_section(&mut self) -> Result<ContainsSection, ParseError> {
+        let start = self.span();
+        self.consume(TokenKind::Contains)?;
+
+        // member id="auth.login"
+        let mut members = Vec::new();
+        while self.at(TokenKind::Ident) && self.peek_text() == "member" {
+            let member_start = self.span();
+            self.advance();
+            let id = self.parse_attribute("id")?;
+            members.push(MemberDecl {
+                id,
+                span: member_start.merge(self.span()),
+            });
+        }
+
+        self.consume(TokenKind::End)?;
+        let end = self.span();
+
+        Ok(ContainsSection {
+            members,
+            span: start.merge(end),
+        })
+    }
+
+    fn parse_subscribe_section(&mut self) -> Result<SubscribeSection, ParseError> {
+        let start = self.span();
+        self.advance(); // "subscribe" is not a keyword
+
+        // event type="OrderPlaced"
+        self.consume_word("event")?;
+        let event = self.parse_attribute("type")?;
+
+        // handler fn="notify_warehouse"
+        self.consume_word("handler")?;
+        let handler = self.parse_attribute("fn")?;
+
+        self.consume(TokenKind::End)?;
+        let end = self.span();
+
+        Ok(SubscribeSection {
+            event,
+            handler,
+            span: start.merge(end),
+        })
+    }
+
+    fn parse_invariant_section(&mut self) -> Result<InvariantSection, ParseError> {
+        let start = self.span();
+        self.advance(); // "invariant" is not a keyword
+
+        // check op=less_eq input var="min" input var="max" message="..."
+        let mut checks = Vec::new();
+        while self.at(TokenKind::Ident) && self.peek_text() == "check" {
+            let check_start = self.span();
+            self.advance();
+            let ComputeStep { op, inputs, .. } = self.parse_compute_step()?;
+            let message = self.parse_message()?;
+            let check_end = self.span();
+            checks.push(InvariantCheck {
+                op,
+                inputs,
+                message,
+                span: check_start.merge(check_end),
+            });
+        }
+
+        self.consume(TokenKind::End)?;
+        let end = self.span();
+
+        Ok(InvariantSection {
+            checks,
+            span: start.merge(end),
+        })
+    }
+
     fn parse_content_section(&mut self) -> Result<ContentSection, ParseError> {
         let start = self.span();
         self.consume(TokenKind::Content)?;
@@ -4343,39 +5180,34 @@ impl<'a> Parser<'a> {
         self.consume(TokenKind::Types)?;
 
         let mut types = Vec::new();
-        while self.at(TokenKind::Struct) || self.at(TokenKind::Enum) {
-            if self.at(TokenKind::Struct) {
-                // Parse struct as a type declaration
+        loop {
+            if self.at(TokenKind::Ident) && self.peek_text() == "alias" {
+                types.push(self.parse_alias_decl()?);
+                continue;
+            }
+            let (name, type_span, definition) = if self.at(TokenKind::Struct) {
                 let struct_sig = self.parse_struct_signature()?;
-                let type_span = struct_sig.span;
-                types.push(TypeDecl {
-                    name: struct_sig.name.clone(),
-                    ty: Type {
-                        kind: TypeKind::Named(TypePath {
-                            segments: vec![struct_sig.name],
-                            generics: Vec::new(),
-                            span: type_span,
-                        }),
-                        span: type_span,
-                    },
-                    span: type_span,
-                });
+                (struct_sig.name.clone(), struct_sig.span, SignatureKind::Struct(struct_sig))
             } else if self.at(TokenKind::Enum) {
                 let enum_sig = self.parse_enum_signature()?;
-                let type_span = enum_sig.span;
-                types.push(TypeDecl {
-                    name: enum_sig.name.clone(),
-                    ty: Type {
-                        kind: TypeKind::Named(TypePath {
-                            segments: vec![enum_sig.name],
-                            generics: Vec::new(),
-                            span: type_span,
-                        }),
+                (enum_sig.name.clone(), enum_sig.span, SignatureKind::Enum(enum_sig))
+            } else {
+                break;
+            };
+            types.push(TypeDecl {
+                name: name.clone(),
+                ty: Type {
+                    kind: TypeKind::Named(TypePath {
+                        segments: vec![name],
+                        generics: Vec::new(),
                         span: type_span,
-                    },
+                    }),
                     span: type_span,
-                });
-            }
+                },
+                definition: Some(definition),
+                unit: None,
+                span: type_span,
+            });
         }
 
         self.consume(TokenKind::End)?;
@@ -4387,6 +5219,24 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// `alias name="Money" type="Float" unit="USD"`
+    fn parse_alias_decl(&mut self) -> Result<TypeDecl, ParseError> {
+        let start = self.span();
+        self.advance(); // alias
+        let name = self.parse_attribute("name")?;
+        let ty = self.parse_attribute_type("type")?;
+        let unit = self.parse_optional_attribute("unit")?;
+        let end = self.span();
+
+        Ok(TypeDecl {
+            name,
+            ty,
+            definition: None,
+            unit,
+            span: start.merge(end),
+        })
+    }
+
     // === Helpers ===
 
     fn parse_literal(&mut self) -> Result<Literal, ParseError> {
@@ -4401,6 +5251,37 @@ impl<'a> Parser<'a> {
                 let value: f64 = text.parse().unwrap_or(0.0);
                 Ok(Literal::Float(value))
             }
+            TokenKind::Decimal => {
+                let text = self.advance_text();
+                Ok(Literal::Decimal(canonical_number(false, &text[..text.len() - 1])))
+            }
+            TokenKind::BigInt => {
+                let text = self.advance_text();
+                Ok(Literal::BigInt(canonical_number(false, &text[..text.len() - 1])))
+            }
+            TokenKind::Minus if matches!(self.peek_ahead(1), TokenKind::Decimal | TokenKind::BigInt) => {
+                self.advance();
+                let is_decimal = self.at(TokenKind::Decimal);
+                let text = self.advance_text();
+                let value = canonical_number(true, &text[..text.len() - 1]);
+                Ok(if is_decimal { Literal::Decimal(value) } else { Literal::BigInt(value) })
+            }
+            TokenKind::Minus if matches!(self.peek_ahead(1), TokenKind::Int | TokenKind::Float) => {
+                // The sign lexes separately; parse it with the digits so i64::MIN fits
+                self.advance();
+                let is_float = self.at(TokenKind::Float);
+                let text = format!("-{}", self.advance_text());
+                if is_float {
+                    Ok(Literal::Float(text.parse().unwrap_or(0.0)))
+                } else {
+                    Ok(Literal::Int(text.parse().unwrap_or(0)))
+                }
+            }
+            TokenKind::Ident
+                if matches!(self.peek_text().as_str(), "datetime" | "duration") && self.peek_ahead(1) == TokenKind::LParen =>
+            {
+                self.parse_time_literal()
+            }
             TokenKind::String | TokenKind::TripleString => {
                 let value = self.advance_string_literal();
                 Ok(Literal::String(value))
@@ -4418,41 +5299,51 @@ impl<'a> Parser<'a> {
                 Ok(Literal::None)
             }
             TokenKind::LBracket => {
-                // Array literal - consume and represent as string for now
-                // [1, 2, 3] or []
-                let mut contents = String::from("[");
+                // List literal: [1, 2, 3] or []
                 self.advance(); // consume [
-                while !self.at(TokenKind::RBracket) && !self.at(TokenKind::Eof) {
-                    let text = self.advance_text();
-                    contents.push_str(&text);
-                    if self.at(TokenKind::Comma) {
-                        contents.push_str(", ");
-                        self.advance();
-                    }
-                }
-                self.consume(TokenKind::RBracket)?;
-                contents.push(']');
-                Ok(Literal::String(contents))
+                let items = self.parse_literal_items(TokenKind::RBracket)?;
+                Ok(Literal::List(items))
+            }
+            TokenKind::LParen => {
+                // Tuple literal: (1, "a")
+                self.advance(); // consume (
+                let items = self.parse_literal_items(TokenKind::RParen)?;
+                Ok(Literal::Tuple(items))
             }
             TokenKind::LBrace => {
-                // Object/map literal - consume and represent as string for now
-                // {"key": "value"} or {}
-                let mut contents = String::from("{");
+                // Map literal {"key": "value"} or set literal {1, 2}; {} is an empty map
                 self.advance(); // consume {
-                while !self.at(TokenKind::RBrace) && !self.at(TokenKind::Eof) {
-                    let text = self.advance_text();
-                    contents.push_str(&text);
-                    if self.at(TokenKind::Colon) {
-                        contents.push_str(": ");
-                        self.advance();
-                    } else if self.at(TokenKind::Comma) {
-                        contents.push_str(", ");
+                if self.at(TokenKind::RBrace) {
+                    self.advance();
+                    return Ok(Literal::Map(vec![]));
+                }
+                let first = self.parse_literal()?;
+                if !self.at(TokenKind::Colon) {
+                    let mut items = vec![first];
+                    if self.at(TokenKind::Comma) {
                         self.advance();
+                        items.extend(self.parse_literal_items(TokenKind::RBrace)?);
+                    } else {
+                        self.consume(TokenKind::RBrace)?;
+                    }
+                    return Ok(Literal::Set(items));
+                }
+                let mut entries = Vec::new();
+                let mut key = first;
+                loop {
+                    self.consume(TokenKind::Colon)?;
+                    entries.push((key, self.parse_literal()?));
+                    if !self.at(TokenKind::Comma) {
+                        break;
+                    }
+                    self.advance();
+                    if self.at(TokenKind::RBrace) {
+                        break;
                     }
+                    key = self.parse_literal()?;
                 }
                 self.consume(TokenKind::RBrace)?;
-                contents.push('}');
-                Ok(Literal::String(contents))
+                Ok(Literal::Map(entries))
             }
             _ => Err(ParseError::Unexpected {
                 expected: "literal".to_string(),
@@ -4461,4 +5352,60 @@ impl<'a> Parser<'a> {
             }),
         }
     }
+
+    /// `datetime("2024-01-01T00:00:00Z")` or `duration("5m")`, with the text
+    /// checked here so a bad literal is reported where it is written
+    fn parse_time_literal(&mut self) -> Result<Literal, ParseError> {
+        let kind = self.advance_text();
+        self.consume(TokenKind::LParen)?;
+        if !self.at(TokenKind::String) {
+            return Err(ParseError::unexpected("string", self.peek(), self.span()));
+        }
+        let span = self.span();
+        let text = self.advance_string_literal();
+        self.consume(TokenKind::RParen)?;
+
+        let value = if kind == "datetime" {
+            covenant_ast::time::parse_datetime(&text).map(Literal::DateTime)
+        } else {
+            covenant_ast::time::parse_duration(&text).map(Literal::Duration)
+        };
+        value.map_err(|reason| ParseError::InvalidTimeLiteral { kind, text, reason, span })
+    }
+
+    /// Parse comma-separated literals up to and including `close`
+    ///
+    /// A trailing comma is allowed, so `(1,)` is a one-element tuple.
+    fn parse_literal_items(&mut self, close: TokenKind) -> Result<Vec<Literal>, ParseError> {
+        let mut items = Vec::new();
+        while !self.at(close) {
+            items.push(self.parse_literal()?);
+            if !self.at(TokenKind::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        self.consume(close)?;
+        Ok(items)
+    }
+}
+
+/// The canonical text of a Decimal or BigInt literal's digits: leading zeros
+/// dropped, the fraction kept as written (`1.50` has two places), and no
+/// sign on zero
+fn canonical_number(negative: bool, digits: &str) -> String {
+    let (whole, fraction) = match digits.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (digits, None),
+    };
+    let whole = match whole.trim_start_matches('0') {
+        "" => "0",
+        trimmed => trimmed,
+    };
+    let is_zero = whole == "0" && fraction.is_none_or(|f| f.bytes().all(|b| b == b'0'));
+    let sign = if negative && !is_zero { "-" } else { "" };
+    match fraction {
+        Some(fraction) => format!("{}{}.{}", sign, whole, fraction),
+        None => format!("{}{}", sign, whole),
+    }
 }
@@ -0,0 +1,446 @@
+//! Round trips through the canonical printer
+//!
+//! Every snippet program in `examples/` and the standard library must print
+//! to text that parses back to the same program, and printing that text again
+//! must not change it. The same holds for programs loaded from JSON, so tools
+//! can edit the JSON form and print it as snippet text.
+//!
+//! Programs from `covenant_ast::generate` cover the constructs the corpus
+//! does not, checking `parse(print(ast)) == ast` directly.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use arbitrary::Unstructured;
+use covenant_ast::generate;
+use covenant_ast::printer::{to_cov, to_cov_compact};
+use covenant_ast::Program;
+use covenant_parser::{parse, parse_compact, parse_tolerant};
+use serde_json::Value;
+
+/// Constructs the example corpus does not use
+const EVERY_CONSTRUCT: &str = r#"
+snippet id="a.b" kind="fn"
+  note lang="en" "hello"
+  doc """Sums the inputs.
+  Stops at the first break."""
+  requires
+    req id="R-1"
+      text "Must work"
+      priority high
+    end
+  end
+  metadata
+    tags=["a", "b"]
+    flag=true
+    author=someone
+    count=3
+    trigger schedule="0 * * * *"
+    allow cycle group="parser"
+  end
+  relations
+    rel to="x.y" type=describes
+    rel from="z"
+  end
+  signature
+    fn name="b"
+      doc """Values to sum"""
+      param name="xs" type="List<Int>"
+      returns collection of="Int"
+    end
+  end
+  body
+    doc """Walk the inputs"""
+    step id="s1" kind="for"
+      var="x" in="xs"
+      step id="s1a" kind="break"
+        as="_"
+      end
+      as="_"
+    end
+    step id="s2" kind="parallel"
+      on_error="fail_fast"
+      timeout="5s"
+      branch id="b1"
+        step id="b1s" kind="call"
+          fn="f"
+          arg name="a" lit=1
+          arg name="m" lit={"k": [1, 2.5], 3: (true, none)}
+          arg name="s" lit={"x", "y"}
+          arg name="t" lit=(1,)
+          as="r"
+        end
+      end
+      as="_"
+    end
+    step id="s3" kind="traverse"
+      target="project"
+      from="n"
+      follow type=contains
+      depth=unbounded
+      direction=both
+      as="t"
+    end
+    step id="s4" kind="transaction"
+      target="db"
+      isolation="serializable"
+      step id="s4a" kind="query"
+        dialect="postgres"
+        body
+          SELECT * FROM users WHERE id = :id
+        end
+        params
+          param name="id" from="x"
+        end
+        returns collection of="User"
+        as="rows"
+      end
+      as="_"
+    end
+    step id="s5" kind="lambda"
+      param name="y" type="Int"
+      returns type="Bool"
+      step id="l1" kind="return"
+        lit=true
+        as="_"
+      end
+      as="pred"
+    end
+    step id="s5u" kind="using"
+      var="conn"
+      acquire fn="db.open"
+        arg name="url" lit="postgres://x"
+      release fn="db.close"
+      step id="u1" kind="call"
+        fn="db.ping"
+        type arg="Conn"
+        arg name="c" from="conn"
+        as="ok"
+      end
+      as="pinged"
+    end
+    step id="s5a" kind="assert"
+      op=less input var="pinged" input lit=10
+      message="ping \"count\" too high"
+      as="_"
+    end
+    step id="s5c" kind="construct"
+      type="R"
+      from_base="r0"
+      field name="hi" lit=9
+      as="r1"
+    end
+    step id="s6" kind="match"
+      on="v"
+      case variant type="Opt::Some" bindings=("a", "b")
+        step id="m1" kind="return"
+          from="a"
+          as="_"
+        end
+      end
+      case variant type="Opt::Bad"
+        step id="m3" kind="raise"
+          variant type="E::A"
+            field name="q" lit=1
+          end
+          as="_"
+        end
+      end
+      case wildcard
+        step id="m2" kind="return"
+          lit=none
+          as="_"
+        end
+      end
+      as="_"
+    end
+    step id="s7" kind="race"
+      timeout="1s"
+      on_timeout="cancel"
+      branch id="r1"
+      end
+      as="_"
+    end
+    step id="s8" kind="update"
+      target="db.t"
+      set field="a" lit=1
+      where
+        or
+          equals field="a" var="x.y"
+          contains field="b" lit="q"
+          rel_from source=doc type=described_by
+        end
+      end
+      as="_"
+    end
+  end
+  tests
+    test id="t1" kind="unit" covers="R-1"
+      step id="ts" kind="bind"
+        variant type="E::A"
+          field name="q" lit=1
+        end
+        as="_"
+      end
+    end
+  end
+end
+
+snippet id="a" kind="module"
+  contains
+    member id="a.b"
+  end
+end
+
+snippet id="a.on_t" kind="subscriber"
+  subscribe
+    event type="T"
+    handler fn="a.b"
+  end
+end
+
+snippet id="a.total" kind="fn"
+  signature
+    fn name="total"
+      returns type="Int"
+    end
+  end
+  tests
+    test id="T-1" kind="golden" covers="R-1"
+      step id="t1" kind="call"
+        fn="a.total"
+        expect field="r" lit=3
+        expect field="r.parts" lit=["a", "b"]
+        as="r"
+      end
+    end
+  end
+end
+
+snippet id="a.LIMIT" kind="const"
+  signature
+    const name="LIMIT" type="Int" lit=3
+  end
+end
+
+snippet id="a.Count" kind="alias"
+  signature
+    alias name="Count" type="Int"
+  end
+end
+
+snippet id="a.Slot" kind="struct"
+  signature
+    struct name="Slot" newtype
+      field name="index" type="Int"
+    end
+  end
+end
+
+snippet id="a.Ranged" kind="interface"
+  signature
+    interface name="Ranged"
+      fn name="width"
+        param name="r" type="Self"
+        returns type="Int"
+      end
+    end
+  end
+end
+
+snippet id="a.R" kind="struct" implements="a.Ranged"
+  signature
+    struct name="R"
+      field name="lo" type="Int"
+      field name="hi" type="Int"
+    end
+  end
+  invariant
+    check op=less_eq input var="lo" input var="hi" message="lo <= hi"
+    check op=greater_eq input var="lo" input lit=0
+  end
+end
+"#;
+
+fn cov_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            cov_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "cov") {
+            files.push(path);
+        }
+    }
+}
+
+/// Each snippet as JSON with every span removed and its sections sorted,
+/// so programs parsed from differently laid out text compare equal
+fn normalized_snippets(program: &Program) -> Vec<Value> {
+    fn strip(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                map.remove("span");
+                map.values_mut().for_each(strip);
+                // The printer puts sections in canonical order
+                if let Some(Value::Array(sections)) = map.get_mut("sections") {
+                    sections.sort_by_key(|section| section.to_string());
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(strip),
+            _ => {}
+        }
+    }
+    let Program::Snippets { snippets, .. } = program else {
+        return Vec::new();
+    };
+    snippets
+        .iter()
+        .map(|snippet| {
+            let mut value = serde_json::to_value(snippet).unwrap();
+            strip(&mut value);
+            value
+        })
+        .collect()
+}
+
+/// Format `source` and check the result parses to the same program and
+/// formats to itself
+fn assert_round_trips(source: &str, name: &str) {
+    let program = parse(source).unwrap_or_else(|e| panic!("{}: {}", name, e));
+    let formatted = to_cov(&program);
+    let reparsed = parse(&formatted)
+        .unwrap_or_else(|e| panic!("{}: formatted output does not parse: {}\n{}", name, e, formatted));
+
+    let (before, after) = (normalized_snippets(&program), normalized_snippets(&reparsed));
+    assert_eq!(before.len(), after.len(), "{}: formatting changed the snippet count", name);
+    for (before, after) in before.iter().zip(&after) {
+        assert_eq!(after, before, "{}: formatting changed snippet {}", name, before["id"]);
+    }
+    assert_eq!(to_cov(&reparsed), formatted, "{}: formatting is not idempotent", name);
+
+    let compact = parse_compact(&to_cov_compact(&program))
+        .unwrap_or_else(|e| panic!("{}: compact output does not parse: {}", name, e));
+    assert_eq!(normalized_snippets(&compact), before, "{}: compact formatting changed the program", name);
+
+    // JSON -> AST -> snippet text -> AST
+    let from_json = Program::from_json(&program.to_json())
+        .unwrap_or_else(|e| panic!("{}: JSON output does not load: {}", name, e));
+    assert_eq!(
+        serde_json::to_value(&from_json).unwrap(),
+        serde_json::to_value(&program).unwrap(),
+        "{}: JSON round trip changed the program",
+        name
+    );
+    let printed = parse(&to_cov(&from_json))
+        .unwrap_or_else(|e| panic!("{}: program loaded from JSON does not print: {}", name, e));
+    assert_eq!(normalized_snippets(&printed), before, "{}: printing JSON changed the program", name);
+}
+
+#[test]
+fn test_every_construct_round_trips() {
+    assert_round_trips(EVERY_CONSTRUCT, "EVERY_CONSTRUCT");
+}
+
+#[test]
+fn test_corpus_round_trips_through_printer() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let mut files = Vec::new();
+    cov_files(&root.join("examples"), &mut files);
+    cov_files(&root.join("runtime/std"), &mut files);
+    files.sort();
+
+    let mut formatted_files = 0;
+    for path in &files {
+        let source = fs::read_to_string(path).unwrap();
+        // Files the parser rejects are covered by their own tests
+        if !matches!(parse(&source), Ok(Program::Snippets { .. })) {
+            continue;
+        }
+
+        assert_round_trips(&source, &path.display().to_string());
+        formatted_files += 1;
+    }
+    assert!(formatted_files > 0);
+}
+
+/// Deterministic bytes for the generators (splitmix64), so failures reproduce
+fn seeded_bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    let mut bytes = Vec::with_capacity(len);
+    while bytes.len() < len {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        bytes.extend_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+    }
+    bytes.truncate(len);
+    bytes
+}
+
+#[test]
+fn test_generated_programs_round_trip() {
+    for seed in 0..300 {
+        let bytes = seeded_bytes(seed, 4096);
+        let program = generate::program(&mut Unstructured::new(&bytes)).unwrap();
+        let printed = to_cov(&program);
+        let name = format!("seed {}", seed);
+
+        let parsed = parse(&printed)
+            .unwrap_or_else(|e| panic!("{}: printed program does not parse: {}\n{}", name, e, printed));
+        assert_eq!(
+            normalized_snippets(&parsed),
+            normalized_snippets(&program),
+            "{}: parse(print(ast)) != ast\n{}",
+            name,
+            printed
+        );
+        assert_round_trips(&printed, &name);
+    }
+}
+
+#[test]
+fn test_tolerant_parse_never_panics_on_truncated_programs() {
+    for seed in 0..40 {
+        let bytes = seeded_bytes(seed, 4096);
+        let printed = to_cov(&generate::program(&mut Unstructured::new(&bytes)).unwrap());
+        let cuts: Vec<usize> = printed.char_indices().map(|(i, _)| i).step_by(7).collect();
+        for cut in cuts {
+            let (program, errors) = parse_tolerant(&printed[..cut]);
+            assert!(program.is_some() || !errors.is_empty(), "seed {} cut at {}", seed, cut);
+        }
+    }
+}
+
+#[test]
+fn test_tolerant_parse_recovers_at_snippet_boundaries() {
+    let source = r#"
+snippet id="a.good" kind="fn"
+end
+
+snippet id="a.broken" kind="fn"
+  body
+    step id="s1" kind="bogus"
+  end
+end
+
+snippet id="a.also_good" kind="data"
+  content
+    "ok"
+  end
+end
+"#;
+    assert!(parse(source).is_err());
+
+    let (program, errors) = parse_tolerant(source);
+    let Some(Program::Snippets { snippets, .. }) = program else {
+        panic!("expected a snippet program");
+    };
+    let ids: Vec<&str> = snippets.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, ["a.good", "a.also_good"]);
+    assert_eq!(errors.len(), 1);
+
+    let (program, errors) = parse_tolerant(EVERY_CONSTRUCT);
+    assert!(program.is_some());
+    assert!(errors.is_empty());
+}
@@ -1,7 +1,7 @@
 //! Extract requirements and tests from Covenant AST
 
 use std::collections::HashMap;
-use covenant_ast::{Program, Snippet, Section, Priority, ReqStatus};
+use covenant_ast::{Program, Snippet, Section, Priority, ReqStatus, Step, StepKind};
 use crate::{RequirementInfo, TestInfo, RequirementError};
 
 /// Result of extraction phase
@@ -69,6 +69,7 @@ fn extract_from_snippet(
                         id: test.id.clone(),
                         kind: test.kind,
                         covers: test.covers.clone(),
+                        checks: count_checks(&test.steps),
                         snippet_id: snippet.id.clone(),
                         span: test.span,
                     };
@@ -90,6 +91,18 @@ fn extract_from_snippet(
     }
 }
 
+/// Count assert steps and call `expect` clauses, at any depth
+fn count_checks(steps: &[Step]) -> usize {
+    steps.iter().map(|step| {
+        let own = match &step.kind {
+            StepKind::Assert(_) => 1,
+            StepKind::Call(call) => call.expects.len(),
+            _ => 0,
+        };
+        own + step.nested_steps().into_iter().map(count_checks).sum::<usize>()
+    }).sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -184,6 +197,47 @@ end
         assert_eq!(t1.covers, vec!["R-001".to_string()]);
     }
 
+    #[test]
+    fn test_extract_counts_asserts_and_expectations() {
+        let source = r#"
+snippet id="test.fn" kind="fn"
+
+tests
+  test id="T-001" kind="unit" covers="R-001"
+    step id="t1" kind="call"
+      fn="math.add"
+      arg name="a" lit=2
+      arg name="b" lit=3
+      expect field="result" lit=5
+      as="result"
+    end
+    step id="t2" kind="assert"
+      op=equals
+      input var="result"
+      input lit=5
+      as="_"
+    end
+  end
+  test id="T-002" kind="golden" covers="R-001"
+    step id="t1" kind="call"
+      fn="math.add"
+      arg name="a" lit=1
+      arg name="b" lit=1
+      expect field="sum" lit=2
+      as="sum"
+    end
+  end
+end
+
+end
+"#;
+        let program = parse(source).unwrap();
+        let extraction = extract(&program);
+
+        assert_eq!(extraction.tests.get("T-001").unwrap().checks, 2);
+        assert_eq!(extraction.tests.get("T-002").unwrap().checks, 1);
+    }
+
     #[test]
     fn test_extract_from_multiple_snippets() {
         let source = r#"
@@ -43,6 +43,9 @@ pub struct TestInfo {
     pub kind: TestKind,
     /// Requirement IDs this test claims to cover
     pub covers: Vec<String>,
+    /// Checks the test makes: assert steps plus `expect` clauses on calls
+    #[serde(default)]
+    pub checks: usize,
     /// Parent snippet ID where this test is defined
     pub snippet_id: String,
     /// Source span for error reporting
@@ -275,6 +275,7 @@ mod tests {
             id: "T-001".to_string(),
             kind: TestKind::Unit,
             covers: vec!["R-001".to_string()],
+            checks: 1,
             snippet_id: "test.fn".to_string(),
             span: Span { start: 0, end: 0 },
         });
@@ -204,6 +204,7 @@ mod tests {
             id: id.to_string(),
             kind: TestKind::Unit,
             covers: covers.into_iter().map(String::from).collect(),
+            checks: 0,
             snippet_id: "test.fn".to_string(),
             span: Span { start: 0, end: 0 },
         }
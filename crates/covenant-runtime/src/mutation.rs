@@ -2,9 +2,19 @@
 //!
 //! This module implements the `mutation` interface from WIT.
 //! It provides operations to modify Covenant source code at runtime.
+//!
+//! A mutator created for a running program can hot-reload snippets: an
+//! updated snippet replaces the running one when its signature and effects
+//! are unchanged and the program still checks. Anything else needs a restart.
+
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::store::SymbolStore;
 use crate::types::RuntimeSymbol;
+use covenant_ast::printer::snippet_to_cov;
+use covenant_ast::{Program, Section, Snippet, Span};
+use covenant_checker::{check, check_effects_for, explain_effect_violation};
+use covenant_symbols::{build_from_snippets, changed_snippets};
 use serde::{Deserialize, Serialize};
 
 /// Result of a mutation operation
@@ -75,14 +85,32 @@ impl CompileResult {
 
 /// Mutator that handles snippet updates and recompilation
 pub struct Mutator {
-    // In a real implementation, this would hold a reference to the compiler
-    // For now, we just provide the interface structure
+    /// Snippets of the running program by ID, for hot reloading
+    snippets: BTreeMap<String, Snippet>,
 }
 
 impl Mutator {
     /// Create a new mutator
     pub fn new() -> Self {
-        Self {}
+        Self {
+            snippets: BTreeMap::new(),
+        }
+    }
+
+    /// Create a mutator that can hot-reload the snippets of a running program
+    pub fn for_program(program: &Program) -> Self {
+        let snippets = match program {
+            Program::Snippets { snippets, .. } => {
+                snippets.iter().map(|s| (s.id.clone(), s.clone())).collect()
+            }
+            Program::Legacy { .. } => BTreeMap::new(),
+        };
+        Self { snippets }
+    }
+
+    /// The running version of a snippet
+    pub fn snippet(&self, id: &str) -> Option<&Snippet> {
+        self.snippets.get(id)
     }
 
     /// Parse and validate a snippet without modifying the symbol store
@@ -154,6 +182,92 @@ impl Mutator {
         MutationResult::ok(version)
     }
 
+    /// Swap updated snippets into the running program
+    ///
+    /// Every snippet in `source` must already be running with the same
+    /// signature and effects, since callers were compiled against them. The
+    /// changed snippets and their dependents are re-checked before anything
+    /// is swapped; on any error the running program is left as it was.
+    pub fn reload_snippet(&mut self, store: &mut SymbolStore, source: &str) -> MutationResult {
+        let updated = match covenant_parser::parse(source) {
+            Ok(Program::Snippets { snippets, .. }) if !snippets.is_empty() => snippets,
+            Ok(_) => return MutationResult::err(vec!["Source must contain snippet declarations".into()]),
+            Err(e) => return MutationResult::err(vec![e.to_string()]),
+        };
+
+        let mut errors = Vec::new();
+        for snippet in &updated {
+            let Some(running) = self.snippets.get(&snippet.id) else {
+                errors.push(format!("Snippet '{}' is not running; restart to add it", snippet.id));
+                continue;
+            };
+            if signature_of(running) != signature_of(snippet) {
+                errors.push(format!("Signature of '{}' changed; restart to apply", snippet.id));
+            }
+            if effects_of(running) != effects_of(snippet) {
+                errors.push(format!("Effects of '{}' changed; restart to apply", snippet.id));
+            }
+        }
+        if !errors.is_empty() {
+            return MutationResult::err(errors);
+        }
+
+        let mut next = self.snippets.clone();
+        for snippet in updated {
+            next.insert(snippet.id.clone(), snippet);
+        }
+        let old: Vec<Snippet> = self.snippets.values().cloned().collect();
+        let new: Vec<Snippet> = next.values().cloned().collect();
+        let changed = changed_snippets(&old, &new);
+        if changed.is_empty() {
+            return MutationResult::ok(store.version()).with_warning("No snippets changed");
+        }
+
+        // Re-validate the changed snippets and their dependents first
+        let symbol_result = match build_from_snippets(&new) {
+            Ok(result) => result,
+            Err(errors) => return MutationResult::err(errors.iter().map(|e| e.to_string()).collect()),
+        };
+        let graph = &symbol_result.graph;
+        let plan: Vec<String> = graph.recheck_plan(&changed).names().cloned().collect();
+        let violations = check_effects_for(graph, &plan).violations;
+        if !violations.is_empty() {
+            return MutationResult::err(
+                violations
+                    .iter()
+                    .map(|v| {
+                        let diagnostic = explain_effect_violation(v, &Default::default());
+                        format!("{}: {}", diagnostic.code, diagnostic.message())
+                    })
+                    .collect(),
+            );
+        }
+        let program = Program::Snippets { snippets: new, span: Span::dummy() };
+        if let Err(errors) = check(&program) {
+            return MutationResult::err(errors.iter().map(|e| e.to_string()).collect());
+        }
+
+        // Swap, keeping each symbol's effect closure: its effects are unchanged
+        let mut version = store.version();
+        for id in &changed {
+            let Some(info) = graph.get_by_name(id) else { continue };
+            let mut symbol = RuntimeSymbol::from(info);
+            if let Some(running) = store.get(id) {
+                symbol.file = running.file.clone();
+                symbol.effect_closure = running.effect_closure.clone();
+            }
+            version = store.upsert(symbol);
+        }
+        store.recompute_backward_refs();
+        self.snippets = next;
+
+        let mut result = MutationResult::ok(version);
+        for err in &symbol_result.deferred_errors {
+            result = result.with_warning(err.to_string());
+        }
+        result
+    }
+
     /// Delete a snippet from the symbol store
     pub fn delete_snippet(&self, store: &mut SymbolStore, id: &str) -> bool {
         let deleted = store.delete(id);
@@ -199,6 +313,33 @@ impl Mutator {
     }
 }
 
+/// A snippet's signature in canonical form
+fn signature_of(snippet: &Snippet) -> String {
+    let mut signature = snippet.clone();
+    signature.notes.clear();
+    signature.sections.retain(|section| matches!(section, Section::Signature(_)));
+    snippet_to_cov(&signature)
+}
+
+/// A snippet's declared effects with their parameters, ignoring order
+fn effects_of(snippet: &Snippet) -> BTreeSet<String> {
+    snippet
+        .sections
+        .iter()
+        .filter_map(|section| match section {
+            Section::Effects(effects) => Some(&effects.effects),
+            _ => None,
+        })
+        .flatten()
+        .map(|effect| {
+            let mut params: Vec<String> =
+                effect.params.iter().map(|p| format!("{}={:?}", p.name, p.value)).collect();
+            params.sort();
+            format!("{}({})", effect.name, params.join(","))
+        })
+        .collect()
+}
+
 impl Default for Mutator {
     fn default() -> Self {
         Self::new()
@@ -261,6 +402,105 @@ mod tests {
         assert!(symbol.effect_closure.contains(&"database".to_string()));
     }
 
+    const RUNNING: &str = r#"
+snippet id="app.greeting" kind="fn"
+signature
+  fn name="greeting"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=1
+    as="_"
+  end
+end
+end
+
+snippet id="app.save" kind="extern"
+effects
+  effect database
+end
+signature
+  fn name="save"
+    returns type="Int"
+  end
+end
+end
+"#;
+
+    fn running() -> (Mutator, SymbolStore) {
+        let program = covenant_parser::parse(RUNNING).unwrap();
+        let mut store = SymbolStore::new();
+        if let Program::Snippets { snippets, .. } = &program {
+            store.load_from_graph(&build_from_snippets(snippets).unwrap().graph);
+        }
+        (Mutator::for_program(&program), store)
+    }
+
+    fn greeting(returns: &str, effects: &str, body: &str) -> String {
+        format!(
+            "snippet id=\"app.greeting\" kind=\"fn\"\n{}\nsignature\n  fn name=\"greeting\"\n    returns type=\"{}\"\n  end\nend\nbody\n{}\nend\nend\n",
+            effects, returns, body
+        )
+    }
+
+    const RETURN_TWO: &str = "  step id=\"s1\" kind=\"return\"\n    lit=2\n    as=\"_\"\n  end";
+
+    #[test]
+    fn test_reload_swaps_body() {
+        let (mut mutator, mut store) = running();
+        let version = store.version();
+
+        let result = mutator.reload_snippet(&mut store, &greeting("Int", "", RETURN_TWO));
+        assert!(result.success, "Errors: {:?}", result.errors);
+        assert!(result.new_version > version);
+        assert!(snippet_to_cov(mutator.snippet("app.greeting").unwrap()).contains("lit=2"));
+    }
+
+    #[test]
+    fn test_reload_rejects_changed_signature() {
+        let (mut mutator, mut store) = running();
+
+        let result = mutator.reload_snippet(&mut store, &greeting("Bool", "", RETURN_TWO));
+        assert!(!result.success);
+        assert!(result.errors[0].contains("Signature of 'app.greeting' changed"));
+        assert!(snippet_to_cov(mutator.snippet("app.greeting").unwrap()).contains("lit=1"));
+    }
+
+    #[test]
+    fn test_reload_rejects_changed_effects() {
+        let (mut mutator, mut store) = running();
+        let effects = "effects\n  effect network\nend";
+
+        let result = mutator.reload_snippet(&mut store, &greeting("Int", effects, RETURN_TWO));
+        assert!(!result.success);
+        assert!(result.errors[0].contains("Effects of 'app.greeting' changed"));
+    }
+
+    #[test]
+    fn test_reload_rejects_new_snippet() {
+        let (mut mutator, mut store) = running();
+        let source = greeting("Int", "", RETURN_TWO).replace("app.greeting", "app.other");
+
+        let result = mutator.reload_snippet(&mut store, &source);
+        assert!(!result.success);
+        assert!(result.errors[0].contains("restart to add it"));
+    }
+
+    #[test]
+    fn test_reload_revalidates_before_swapping() {
+        let (mut mutator, mut store) = running();
+        let version = store.version();
+        let calls_effectful = "  step id=\"s1\" kind=\"call\"\n    fn=\"app.save\"\n    as=\"n\"\n  end";
+
+        let result = mutator.reload_snippet(&mut store, &greeting("Int", "", calls_effectful));
+        assert!(!result.success);
+        assert!(result.errors[0].starts_with("E-EFFECT-001"), "Errors: {:?}", result.errors);
+        assert_eq!(store.version(), version);
+        assert!(snippet_to_cov(mutator.snippet("app.greeting").unwrap()).contains("lit=1"));
+    }
+
     #[test]
     fn test_delete_snippet() {
         let mut store = SymbolStore::new();
@@ -161,10 +161,16 @@ impl SymbolStore {
     /// This method parses JSON-serialized symbol metadata extracted from
     /// a WASM module's data section via the `_cov_get_symbol_metadata` export.
     ///
-    /// The JSON format is an array of symbol objects matching the RuntimeSymbol
-    /// structure (or the EmbeddableSymbol format from covenant-codegen).
+    /// The JSON format is an object with a `schema_version` and a `symbols`
+    /// array of objects matching the RuntimeSymbol structure (or the
+    /// EmbeddableSymbol format from covenant-codegen). Metadata from older
+    /// modules is migrated to the current version first.
     pub fn load_from_json(&mut self, json_bytes: &[u8]) -> Result<(), RuntimeError> {
-        let symbols: Vec<RuntimeSymbol> = serde_json::from_slice(json_bytes)
+        let value: serde_json::Value = serde_json::from_slice(json_bytes)
+            .map_err(|e| RuntimeError::DeserializationFailed(e.to_string()))?;
+        let mut metadata = covenant_ast::migrate_symbol_metadata_json(value)
+            .map_err(|e| RuntimeError::DeserializationFailed(e.to_string()))?;
+        let symbols: Vec<RuntimeSymbol> = serde_json::from_value(metadata["symbols"].take())
             .map_err(|e| RuntimeError::DeserializationFailed(e.to_string()))?;
 
         self.symbols.clear();
@@ -270,4 +276,29 @@ mod tests {
         store.delete("nonexistent");
         assert_eq!(store.version(), 3);
     }
+
+    #[test]
+    fn test_load_from_json_versioned() {
+        let mut store = SymbolStore::new();
+        let symbols = vec![RuntimeSymbol::new("a.b", "fn")];
+        let json = serde_json::json!({ "schema_version": 1, "symbols": symbols });
+        store.load_from_json(json.to_string().as_bytes()).unwrap();
+        assert!(store.get("a.b").is_some());
+    }
+
+    #[test]
+    fn test_load_from_json_migrates_unversioned_array() {
+        let mut store = SymbolStore::new();
+        let json = serde_json::to_vec(&vec![RuntimeSymbol::new("a.b", "fn")]).unwrap();
+        store.load_from_json(&json).unwrap();
+        assert!(store.get("a.b").is_some());
+    }
+
+    #[test]
+    fn test_load_from_json_rejects_newer_version() {
+        let mut store = SymbolStore::new();
+        let json = br#"{"schema_version":999,"symbols":[]}"#;
+        let err = store.load_from_json(json).unwrap_err();
+        assert_eq!(err.code(), "E-RT-008");
+    }
 }
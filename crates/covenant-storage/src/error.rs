@@ -52,6 +52,12 @@ pub enum StorageError {
     #[error("Invalid JSON in AST field: {0}")]
     InvalidJson(String),
 
+    #[error("Invalid AST field: {0}")]
+    InvalidAst(String),
+
     #[error("Invalid file path: {0}")]
     InvalidPath(String),
+
+    #[error("Storage schema version {found} is newer than the supported version {supported}")]
+    UnsupportedSchemaVersion { found: u64, supported: u64 },
 }
@@ -0,0 +1,129 @@
+//! Stored explanations
+//!
+//! An explanation generated for a snippet is kept as a data node that
+//! `describes` it, so documentation views can show it without asking the
+//! LLM again. The node's content hash is the hash of the snippet as it was
+//! when explained: once the snippet changes the explanation is stale, still
+//! worth showing but no longer to be trusted.
+
+use serde::{Deserialize, Serialize};
+
+use crate::node::Note;
+use crate::{Node, Relation, Result, SnippetKind, StorageError, StorageProvider};
+
+/// Relation from an explanation node to its snippet
+pub const EXPLANATION_RELATION: &str = "describes";
+
+/// Note language tag of an explanation node's record
+const RECORD_LANG: &str = "json";
+
+/// An explanation of a snippet, with what produced it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredExplanation {
+    /// Snippet ID of the explained snippet
+    pub snippet: String,
+    /// Source file the snippet was read from
+    pub source_file: String,
+    /// Content hash of the snippet when explained (`Snippet::content_hash`)
+    pub snippet_hash: String,
+    /// Model that wrote the explanation
+    pub model: String,
+    /// Version of the explanation generator
+    pub generator_version: Option<String>,
+    /// The explanation as JSON text
+    pub explanation: String,
+}
+
+impl StoredExplanation {
+    /// ID of the node storing the explanation of `snippet`
+    pub fn node_id(snippet: &str) -> String {
+        format!("explanation:{}", snippet)
+    }
+
+    /// Whether the snippet changed since it was explained, given its
+    /// current content hash
+    pub fn is_stale(&self, snippet_hash: &str) -> bool {
+        self.snippet_hash != snippet_hash
+    }
+
+    /// The explanation as a data node linked to its snippet
+    pub fn to_node(&self) -> Result<Node> {
+        let mut node = Node::new(Self::node_id(&self.snippet), SnippetKind::Data);
+        node.source_file = self.source_file.clone();
+        node.content_hash = self.snippet_hash.clone();
+        node.relations.push(Relation::new(&self.snippet, EXPLANATION_RELATION));
+        let record = serde_json::to_string(self).map_err(|e| StorageError::InvalidJson(e.to_string()))?;
+        node.notes.push(Note { lang: Some(RECORD_LANG.to_string()), content: record });
+        Ok(node)
+    }
+
+    /// Read an explanation back from its node
+    pub fn from_node(node: &Node) -> Result<Self> {
+        let record = node
+            .notes
+            .iter()
+            .find(|note| note.lang.as_deref() == Some(RECORD_LANG))
+            .ok_or_else(|| StorageError::InvalidJson(format!("{} holds no explanation", node.id)))?;
+        serde_json::from_str(&record.content).map_err(|e| StorageError::InvalidJson(e.to_string()))
+    }
+}
+
+/// Persistence for explanations, in any node storage
+pub trait ExplanationStore {
+    /// The stored explanation of a snippet, stale or not
+    fn get_explanation(&self, snippet: &str) -> Result<Option<StoredExplanation>>;
+
+    /// Store a snippet's explanation, replacing any earlier one
+    fn put_explanation(&mut self, explanation: &StoredExplanation) -> Result<()>;
+}
+
+impl<S: StorageProvider> ExplanationStore for S {
+    fn get_explanation(&self, snippet: &str) -> Result<Option<StoredExplanation>> {
+        self.get(&StoredExplanation::node_id(snippet))?.as_ref().map(StoredExplanation::from_node).transpose()
+    }
+
+    fn put_explanation(&mut self, explanation: &StoredExplanation) -> Result<()> {
+        let node = explanation.to_node()?;
+        self.put(&node.id, &node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStorage;
+
+    fn explanation() -> StoredExplanation {
+        StoredExplanation {
+            snippet: "math.double".to_string(),
+            source_file: "math.cov".to_string(),
+            snippet_hash: "abc".to_string(),
+            model: "claude-sonnet-4-20250514".to_string(),
+            generator_version: Some("0.1.0".to_string()),
+            explanation: r#"{"summary":"Doubles a number"}"#.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_explanation_round_trip() {
+        let mut storage = InMemoryStorage::new();
+        storage.put_explanation(&explanation()).unwrap();
+
+        assert_eq!(storage.get_explanation("math.double").unwrap(), Some(explanation()));
+        assert!(storage.get_explanation("math.half").unwrap().is_none());
+
+        // The node describes its snippet
+        let described = storage.query_by_relation("math.double", EXPLANATION_RELATION).unwrap();
+        assert_eq!(described.len(), 1);
+        assert_eq!(described[0].id, "explanation:math.double");
+        assert_eq!(described[0].kind, SnippetKind::Data);
+        assert_eq!(described[0].content_hash, "abc");
+    }
+
+    #[test]
+    fn test_staleness() {
+        let explanation = explanation();
+        assert!(!explanation.is_stale("abc"));
+        assert!(explanation.is_stale("abd"));
+    }
+}
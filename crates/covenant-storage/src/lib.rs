@@ -13,15 +13,19 @@
 //! - Layer 3: Transactions with ACID guarantees
 
 mod error;
+mod explanation;
 mod node;
 mod provider;
 mod memory;
 mod redb_storage;
 mod sync;
+mod workflow;
 
 pub use error::{StorageError, Result};
+pub use explanation::{ExplanationStore, StoredExplanation, EXPLANATION_RELATION};
 pub use node::{Node, SnippetKind, Relation};
 pub use provider::{StorageProvider, Transaction, InvariantViolation};
 pub use memory::InMemoryStorage;
 pub use redb_storage::RedbStorage;
 pub use sync::StorageSync;
+pub use workflow::{Checkpoint, RunStatus, WorkflowRun, WorkflowStore};
@@ -1,7 +1,7 @@
 //! In-memory storage implementation for testing
 
-use crate::{Node, Result, SnippetKind, StorageProvider, Transaction, InvariantViolation};
-use std::collections::{HashMap, HashSet};
+use crate::{Node, Result, SnippetKind, StorageProvider, Transaction, InvariantViolation, WorkflowRun, WorkflowStore};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 
 /// In-memory storage implementation
@@ -13,6 +13,7 @@ pub struct InMemoryStorage {
     kind_index: Arc<RwLock<HashMap<SnippetKind, HashSet<String>>>>,
     effect_index: Arc<RwLock<HashMap<String, HashSet<String>>>>,
     relation_index: Arc<RwLock<HashMap<(String, String), HashSet<String>>>>,
+    runs: Arc<RwLock<BTreeMap<String, WorkflowRun>>>,
 }
 
 impl InMemoryStorage {
@@ -23,6 +24,7 @@ impl InMemoryStorage {
             kind_index: Arc::new(RwLock::new(HashMap::new())),
             effect_index: Arc::new(RwLock::new(HashMap::new())),
             relation_index: Arc::new(RwLock::new(HashMap::new())),
+            runs: Arc::new(RwLock::new(BTreeMap::new())),
         }
     }
 
@@ -288,6 +290,21 @@ impl StorageProvider for InMemoryStorage {
     }
 }
 
+impl WorkflowStore for InMemoryStorage {
+    fn get_run(&self, id: &str) -> Result<Option<WorkflowRun>> {
+        Ok(self.runs.read().unwrap().get(id).cloned())
+    }
+
+    fn put_run(&mut self, run: &WorkflowRun) -> Result<()> {
+        self.runs.write().unwrap().insert(run.id.clone(), run.clone());
+        Ok(())
+    }
+
+    fn list_runs(&self) -> Result<Vec<WorkflowRun>> {
+        Ok(self.runs.read().unwrap().values().cloned().collect())
+    }
+}
+
 /// In-memory transaction
 struct InMemoryTransaction<'a> {
     storage: &'a mut InMemoryStorage,
@@ -358,6 +375,35 @@ mod tests {
         assert!(storage.get("test.func").unwrap().is_none());
     }
 
+    #[test]
+    fn test_stored_snippet_keyed_by_content_hash() {
+        let snippet = covenant_ast::Snippet {
+            id: "test.func".into(),
+            kind: covenant_ast::SnippetKind::Function,
+            notes: Vec::new(),
+            doc: None,
+            sections: Vec::new(),
+            implements: None,
+            platform: None,
+            node_id: None,
+            span: covenant_ast::Span::new(0, 40),
+        };
+        let mut storage = InMemoryStorage::new();
+        let mut node = Node::new("test.func", SnippetKind::Function);
+        node.set_snippet(&snippet);
+        storage.put("test.func", &node).unwrap();
+        let stored = storage.get("test.func").unwrap().unwrap();
+
+        // Moving the snippet within its file leaves it current
+        let mut moved = snippet.clone();
+        moved.shift_spans(25);
+        assert!(stored.is_current(&moved));
+
+        let mut edited = snippet;
+        edited.implements = Some("test.Shape".into());
+        assert!(!stored.is_current(&edited));
+    }
+
     #[test]
     fn test_query_by_kind() {
         let mut storage = InMemoryStorage::new();
@@ -401,4 +447,35 @@ mod tests {
         let violations = storage.verify_invariants().unwrap();
         assert!(violations.len() > 0);
     }
+
+    #[test]
+    fn test_workflow_runs() {
+        use crate::{Checkpoint, RunStatus};
+
+        let mut storage = InMemoryStorage::new();
+        let run = WorkflowRun {
+            id: "run-1".to_string(),
+            workflow: "orders.fulfill".to_string(),
+            source_file: "orders.cov".to_string(),
+            status: RunStatus::Running,
+            checkpoints: Vec::new(),
+            error: None,
+        };
+        storage.put_run(&run).unwrap();
+
+        let checkpoint = Checkpoint {
+            timestamp: "2024-01-01T00:00:00.000Z".to_string(),
+            extern_id: "fs.write_file".to_string(),
+            snippet: Some("orders.fulfill".to_string()),
+            step: Some("s1".to_string()),
+            args_hash: "ab".to_string(),
+            result: "1".to_string(),
+        };
+        storage.append_checkpoint("run-1", checkpoint.clone()).unwrap();
+
+        let stored = storage.get_run("run-1").unwrap().unwrap();
+        assert_eq!(stored.checkpoints, vec![checkpoint.clone()]);
+        assert_eq!(storage.list_runs().unwrap().len(), 1);
+        assert!(storage.append_checkpoint("missing", checkpoint).is_err());
+    }
 }
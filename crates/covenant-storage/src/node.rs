@@ -23,11 +23,12 @@ pub struct Node {
     /// Line number where snippet ends
     pub line_end: usize,
 
-    /// SHA-256 hash of source content
+    /// Content hash of the stored snippet (`Snippet::content_hash`)
     pub content_hash: String,
 
-    /// Full AST as JSON string (from covenant-ast types)
-    pub ast: String,
+    /// Program AST in the binary form (`Program::to_binary`), or empty for
+    /// a node without one
+    pub ast: Vec<u8>,
 
     /// Functions/symbols this calls
     pub calls: Vec<String>,
@@ -71,7 +72,7 @@ impl Node {
             line_start: 0,
             line_end: 0,
             content_hash: String::new(),
-            ast: "null".to_string(),
+            ast: Vec::new(),
             calls: Vec::new(),
             called_by: Vec::new(),
             references: Vec::new(),
@@ -104,32 +105,81 @@ impl Node {
             .collect()
     }
 
-    /// Get the AST as a parsed JSON value
+    /// Get the AST as versioned program JSON
     ///
-    /// This parses the JSON string into a `serde_json::Value` for manipulation.
+    /// This decodes the stored program into a `serde_json::Value` for manipulation.
     /// Most queries won't need to call this - they can use the metadata fields directly.
     pub fn get_ast(&self) -> crate::error::Result<serde_json::Value> {
-        serde_json::from_str(&self.ast)
+        let json = covenant_ast::program_to_json(&self.get_program()?);
+        serde_json::from_str(&json)
             .map_err(|e| crate::error::StorageError::InvalidJson(e.to_string()))
     }
 
-    /// Set the AST from a JSON value
-    ///
-    /// This serializes the value to a JSON string and stores it.
+    /// Set the AST from program JSON of any supported schema version
     pub fn set_ast(&mut self, value: &serde_json::Value) -> crate::error::Result<()> {
-        self.ast = serde_json::to_string(value)
-            .map_err(|e| crate::error::StorageError::Serialization(
-                bincode::Error::from(bincode::ErrorKind::Custom(e.to_string()))
-            ))?;
+        let program = covenant_ast::program_from_json(&value.to_string())
+            .map_err(|e| crate::error::StorageError::InvalidJson(e.to_string()))?;
+        self.set_program(&program);
         Ok(())
     }
 
-    /// Set the AST directly from a JSON string
+    /// Get the AST as a program
+    pub fn get_program(&self) -> crate::error::Result<covenant_ast::Program> {
+        covenant_ast::Program::from_binary(&self.ast)
+            .map_err(|e| crate::error::StorageError::InvalidAst(e.to_string()))
+    }
+
+    /// Set the AST from a program, tagged with the current schema version
+    pub fn set_program(&mut self, program: &covenant_ast::Program) {
+        self.ast = program.to_binary();
+    }
+
+    /// Store a snippet as this node's AST, along with its content hash
+    pub fn set_snippet(&mut self, snippet: &covenant_ast::Snippet) {
+        self.content_hash = snippet.content_hash();
+        self.set_program(&covenant_ast::Program::Snippets {
+            snippets: vec![snippet.clone()],
+            span: snippet.span,
+        });
+    }
+
+    /// Whether the node already stores `snippet`'s content, so syncing it
+    /// again can be skipped
+    pub fn is_current(&self, snippet: &covenant_ast::Snippet) -> bool {
+        self.content_hash == snippet.content_hash()
+    }
+
+    /// Upgrade a bare program AST to the versioned envelope (storage
+    /// schema version 0 to 1, when ASTs were stored as JSON)
     ///
-    /// This is useful when the compiler already has a JSON string from serializing
-    /// covenant-ast types. Avoids double-parsing.
-    pub fn set_ast_json(&mut self, json: String) {
-        self.ast = json;
+    /// Returns whether the AST changed. ASTs that are not programs are left as is.
+    pub(crate) fn migrate_ast(&mut self) -> crate::error::Result<bool> {
+        let Ok(value) = serde_json::from_slice::<serde_json::Value>(&self.ast) else {
+            return Ok(false);
+        };
+        let is_bare_program = value
+            .as_object()
+            .is_some_and(|o| o.len() == 1 && (o.contains_key("Snippets") || o.contains_key("Legacy")));
+        if !is_bare_program {
+            return Ok(false);
+        }
+        let migrated = covenant_ast::migrate_program_json(value)
+            .map_err(|e| crate::error::StorageError::InvalidJson(e.to_string()))?;
+        self.ast = migrated.to_string().into_bytes();
+        Ok(true)
+    }
+
+    /// Re-encode a JSON program AST in the binary form (storage schema
+    /// version 1 to 2). ASTs that are not programs, such as the `null` of a
+    /// node without one, become empty.
+    pub(crate) fn encode_ast(&mut self) {
+        let program = std::str::from_utf8(&self.ast)
+            .ok()
+            .and_then(|json| covenant_ast::program_from_json(json).ok());
+        match program {
+            Some(program) => self.set_program(&program),
+            None => self.ast.clear(),
+        }
     }
 }
 
@@ -154,6 +204,10 @@ pub enum SnippetKind {
     Requirement,
     /// Test
     Test,
+    /// Workflow (a function with checkpointed effectful steps)
+    Workflow,
+    /// Event subscriber (a handler bound to an event type)
+    Subscriber,
 }
 
 /// A bidirectional relation between nodes
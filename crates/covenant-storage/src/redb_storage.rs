@@ -1,7 +1,8 @@
 //! redb-based persistent storage implementation
 
-use crate::{Node, Result, SnippetKind, StorageProvider, Transaction, InvariantViolation};
-use redb::{Database, ReadableTable, TableDefinition};
+use crate::{Node, Result, SnippetKind, StorageProvider, Transaction, InvariantViolation, WorkflowRun, WorkflowStore};
+use covenant_ast::STORAGE_SCHEMA_VERSION;
+use redb::{Database, ReadableTable, ReadableTableMetadata, TableDefinition};
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
@@ -11,6 +12,10 @@ const KIND_INDEX: TableDefinition<&str, &[u8]> = TableDefinition::new("kind_inde
 const EFFECT_INDEX: TableDefinition<&str, &[u8]> = TableDefinition::new("effect_index");
 const RELATION_INDEX: TableDefinition<&str, &[u8]> = TableDefinition::new("relation_index");
 const VERSION_TABLE: TableDefinition<&str, u64> = TableDefinition::new("version");
+const WORKFLOW_RUNS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("workflow_runs");
+
+/// Key in the version table holding the database's schema version
+const SCHEMA_VERSION_KEY: &str = "schema";
 
 /// redb-based persistent storage
 ///
@@ -41,6 +46,8 @@ impl RedbStorage {
             write_txn.open_table(EFFECT_INDEX)?;
             write_txn.open_table(RELATION_INDEX)?;
             write_txn.open_table(VERSION_TABLE)?;
+            write_txn.open_table(WORKFLOW_RUNS_TABLE)?;
+            Self::migrate_in_txn(&write_txn)?;
         }
         write_txn.commit()?;
 
@@ -52,6 +59,81 @@ impl RedbStorage {
         &self.path
     }
 
+    /// Get the schema version of the database
+    pub fn schema_version(&self) -> Result<u64> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(VERSION_TABLE)?;
+        let version = table.get(SCHEMA_VERSION_KEY)?.map(|v| v.value());
+        Ok(version.unwrap_or(0))
+    }
+
+    /// Upgrade the database to the current schema version
+    ///
+    /// Databases written before schema versioning have no version entry and
+    /// are version 0, unless they hold no nodes yet.
+    fn migrate_in_txn(write_txn: &redb::WriteTransaction) -> Result<()> {
+        let mut version_table = write_txn.open_table(VERSION_TABLE)?;
+        let stored = version_table.get(SCHEMA_VERSION_KEY)?.map(|v| v.value());
+        let mut version = match stored {
+            Some(version) => version,
+            None if write_txn.open_table(NODES_TABLE)?.is_empty()? => STORAGE_SCHEMA_VERSION as u64,
+            None => 0,
+        };
+
+        let supported = STORAGE_SCHEMA_VERSION as u64;
+        if version > supported {
+            return Err(crate::StorageError::UnsupportedSchemaVersion { found: version, supported });
+        }
+
+        while version < supported {
+            match version {
+                // Version 1 stores program ASTs in the versioned envelope
+                0 => {
+                    let mut nodes_table = write_txn.open_table(NODES_TABLE)?;
+                    let entries = Self::node_entries(&nodes_table)?;
+
+                    for (id, bytes) in entries {
+                        let mut node: Node = bincode::deserialize(&bytes)?;
+                        if node.migrate_ast()? {
+                            let bytes = bincode::serialize(&node)?;
+                            nodes_table.insert(id.as_str(), bytes.as_slice())?;
+                        }
+                    }
+                }
+                // Version 2 stores ASTs in the binary form. The AST field
+                // keeps its encoding (a length and bytes), so earlier nodes
+                // decode with their JSON as the bytes.
+                1 => {
+                    let mut nodes_table = write_txn.open_table(NODES_TABLE)?;
+                    let entries = Self::node_entries(&nodes_table)?;
+
+                    for (id, bytes) in entries {
+                        let mut node: Node = bincode::deserialize(&bytes)?;
+                        node.encode_ast();
+                        let bytes = bincode::serialize(&node)?;
+                        nodes_table.insert(id.as_str(), bytes.as_slice())?;
+                    }
+                }
+                _ => unreachable!("no migration from storage schema version {}", version),
+            }
+            version += 1;
+        }
+
+        if stored != Some(version) {
+            version_table.insert(SCHEMA_VERSION_KEY, version)?;
+        }
+        Ok(())
+    }
+
+    /// Every stored node as its ID and encoded bytes
+    fn node_entries(nodes_table: &redb::Table<&str, &[u8]>) -> Result<Vec<(String, Vec<u8>)>> {
+        let entries = nodes_table
+            .iter()?
+            .map(|r| r.map(|(k, v)| (k.value().to_string(), v.value().to_vec())))
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(entries)
+    }
+
     /// Update secondary indexes for a node
     fn update_indexes_in_txn(
         &self,
@@ -476,6 +558,41 @@ impl StorageProvider for RedbStorage {
     }
 }
 
+impl WorkflowStore for RedbStorage {
+    fn get_run(&self, id: &str) -> Result<Option<WorkflowRun>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(WORKFLOW_RUNS_TABLE)?;
+
+        match table.get(id)? {
+            Some(bytes) => Ok(Some(bincode::deserialize(bytes.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn put_run(&mut self, run: &WorkflowRun) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let bytes = bincode::serialize(run)?;
+            let mut table = write_txn.open_table(WORKFLOW_RUNS_TABLE)?;
+            table.insert(run.id.as_str(), bytes.as_slice())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn list_runs(&self) -> Result<Vec<WorkflowRun>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(WORKFLOW_RUNS_TABLE)?;
+
+        let mut runs = Vec::new();
+        for entry in table.iter()? {
+            let (_, bytes) = entry?;
+            runs.push(bincode::deserialize(bytes.value())?);
+        }
+        Ok(runs)
+    }
+}
+
 /// redb transaction
 struct RedbTransaction<'a> {
     storage: &'a mut RedbStorage,
@@ -590,4 +707,122 @@ mod tests {
         let structs = storage.query_by_kind(SnippetKind::Struct).unwrap();
         assert_eq!(structs.len(), 1);
     }
+
+    /// Write a node the way releases before schema versioning did: no version
+    /// entry and a bare program AST
+    fn write_unversioned(db_path: &Path, program: &serde_json::Value) {
+        let mut storage = RedbStorage::new(db_path).unwrap();
+        let mut node = Node::new("legacy.func", SnippetKind::Function);
+        node.ast = program.to_string().into_bytes();
+        storage.put("legacy.func", &node).unwrap();
+
+        let write_txn = storage.db.begin_write().unwrap();
+        write_txn.open_table(VERSION_TABLE).unwrap().remove(SCHEMA_VERSION_KEY).unwrap();
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_new_database_has_current_schema_version() {
+        let dir = tempdir().unwrap();
+        let storage = RedbStorage::new(dir.path().join("test.redb")).unwrap();
+        assert_eq!(storage.schema_version().unwrap(), STORAGE_SCHEMA_VERSION as u64);
+    }
+
+    #[test]
+    fn test_unversioned_database_is_migrated() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        let program = covenant_ast::Program::Snippets {
+            snippets: Vec::new(),
+            span: covenant_ast::Span::dummy(),
+        };
+        write_unversioned(&db_path, &serde_json::to_value(&program).unwrap());
+
+        let storage = RedbStorage::new(&db_path).unwrap();
+        assert_eq!(storage.schema_version().unwrap(), STORAGE_SCHEMA_VERSION as u64);
+
+        let node = storage.get("legacy.func").unwrap().unwrap();
+        assert_eq!(node.get_ast().unwrap()["schema_version"], covenant_ast::AST_SCHEMA_VERSION);
+        assert!(matches!(node.get_program().unwrap(), covenant_ast::Program::Snippets { .. }));
+    }
+
+    #[test]
+    fn test_program_ast_survives_reopening() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        let program = covenant_ast::Program::Snippets {
+            snippets: Vec::new(),
+            span: covenant_ast::Span::new(0, 12),
+        };
+        {
+            let mut storage = RedbStorage::new(&db_path).unwrap();
+            let mut node = Node::new("app.main", SnippetKind::Function);
+            node.set_program(&program);
+            storage.put("app.main", &node).unwrap();
+        }
+
+        let storage = RedbStorage::new(&db_path).unwrap();
+        let node = storage.get("app.main").unwrap().unwrap();
+        assert_eq!(node.ast, program.to_binary());
+        assert!(matches!(
+            node.get_program().unwrap(),
+            covenant_ast::Program::Snippets { span, .. } if span == covenant_ast::Span::new(0, 12)
+        ));
+    }
+
+    #[test]
+    fn test_newer_database_is_rejected() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        {
+            let storage = RedbStorage::new(&db_path).unwrap();
+            let write_txn = storage.db.begin_write().unwrap();
+            write_txn
+                .open_table(VERSION_TABLE)
+                .unwrap()
+                .insert(SCHEMA_VERSION_KEY, STORAGE_SCHEMA_VERSION as u64 + 1)
+                .unwrap();
+            write_txn.commit().unwrap();
+        }
+
+        assert!(matches!(
+            RedbStorage::new(&db_path),
+            Err(crate::StorageError::UnsupportedSchemaVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_workflow_runs_persist() {
+        use crate::{Checkpoint, RunStatus};
+
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        {
+            let mut storage = RedbStorage::new(&db_path).unwrap();
+            let run = WorkflowRun {
+                id: "run-1".to_string(),
+                workflow: "orders.fulfill".to_string(),
+                source_file: "orders.cov".to_string(),
+                status: RunStatus::Running,
+                checkpoints: Vec::new(),
+                error: None,
+            };
+            storage.put_run(&run).unwrap();
+            let checkpoint = Checkpoint {
+                timestamp: "2024-01-01T00:00:00.000Z".to_string(),
+                extern_id: "fs.read_file".to_string(),
+                snippet: None,
+                step: None,
+                args_hash: "ab".to_string(),
+                result: "null".to_string(),
+            };
+            storage.append_checkpoint("run-1", checkpoint).unwrap();
+        }
+
+        let storage = RedbStorage::new(&db_path).unwrap();
+        let runs = storage.list_runs().unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].status, RunStatus::Running);
+        assert_eq!(runs[0].checkpoints[0].extern_id, "fs.read_file");
+    }
 }
@@ -0,0 +1,149 @@
+//! Workflow run records
+//!
+//! A run of a `kind="workflow"` snippet keeps a checkpoint for every
+//! effectful extern call it has completed, in call order. Resuming a run
+//! replays the workflow from the start and answers those calls from their
+//! checkpoints, so effects that already happened are not repeated.
+//!
+//! Runners append checkpoints to a JSONL journal as each call returns; the
+//! CLI copies them into storage with [`Checkpoint::from_journal_line`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, StorageError};
+
+/// A single run of a workflow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowRun {
+    /// Unique run ID
+    pub id: String,
+    /// Snippet ID of the workflow
+    pub workflow: String,
+    /// Source file the workflow was compiled from
+    pub source_file: String,
+    pub status: RunStatus,
+    /// Completed effectful calls, in call order
+    pub checkpoints: Vec<Checkpoint>,
+    /// Why the last attempt failed, if it did
+    pub error: Option<String>,
+}
+
+/// Where a run stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunStatus {
+    /// Started and not yet finished; may have crashed
+    Running,
+    /// Returned from the workflow
+    Completed,
+    /// The runner exited with an error
+    Failed,
+}
+
+impl std::fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunStatus::Running => write!(f, "running"),
+            RunStatus::Completed => write!(f, "completed"),
+            RunStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// A completed effectful extern call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// ISO 8601 time the call returned
+    pub timestamp: String,
+    /// Snippet ID of the extern
+    #[serde(rename = "extern")]
+    pub extern_id: String,
+    /// Calling snippet and step, if the module reported them
+    pub snippet: Option<String>,
+    pub step: Option<String>,
+    /// Hash of the decoded arguments, checked on replay
+    pub args_hash: String,
+    /// The decoded result as JSON text
+    pub result: String,
+}
+
+/// A journal line: a checkpoint whose result is inline JSON
+#[derive(Serialize, Deserialize)]
+struct JournalLine {
+    timestamp: String,
+    #[serde(rename = "extern")]
+    extern_id: String,
+    snippet: Option<String>,
+    step: Option<String>,
+    args_hash: String,
+    result: serde_json::Value,
+}
+
+impl Checkpoint {
+    /// Parse a line of a runner's checkpoint journal
+    pub fn from_journal_line(line: &str) -> Result<Self> {
+        let line: JournalLine =
+            serde_json::from_str(line).map_err(|e| StorageError::InvalidJson(e.to_string()))?;
+        Ok(Self {
+            timestamp: line.timestamp,
+            extern_id: line.extern_id,
+            snippet: line.snippet,
+            step: line.step,
+            args_hash: line.args_hash,
+            result: line.result.to_string(),
+        })
+    }
+
+    /// Format as a journal line (without the trailing newline)
+    pub fn to_journal_line(&self) -> Result<String> {
+        let result =
+            serde_json::from_str(&self.result).map_err(|e| StorageError::InvalidJson(e.to_string()))?;
+        let line = JournalLine {
+            timestamp: self.timestamp.clone(),
+            extern_id: self.extern_id.clone(),
+            snippet: self.snippet.clone(),
+            step: self.step.clone(),
+            args_hash: self.args_hash.clone(),
+            result,
+        };
+        serde_json::to_string(&line).map_err(|e| StorageError::InvalidJson(e.to_string()))
+    }
+}
+
+/// Persistence for workflow runs
+pub trait WorkflowStore {
+    /// Get a run by ID
+    fn get_run(&self, id: &str) -> Result<Option<WorkflowRun>>;
+
+    /// Store a run (insert or update)
+    fn put_run(&mut self, run: &WorkflowRun) -> Result<()>;
+
+    /// All runs, ordered by ID
+    fn list_runs(&self) -> Result<Vec<WorkflowRun>>;
+
+    /// Record a completed call at the end of a run's checkpoints
+    fn append_checkpoint(&mut self, id: &str, checkpoint: Checkpoint) -> Result<()> {
+        let mut run = self.get_run(id)?.ok_or_else(|| StorageError::NotFound(id.to_string()))?;
+        run.checkpoints.push(checkpoint);
+        self.put_run(&run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_line_round_trip() {
+        let line = r#"{"timestamp":"2024-01-01T00:00:00.000Z","extern":"fs.read_file","snippet":"orders.fulfill","step":"s1","args_hash":"ab","result":"contents"}"#;
+        let checkpoint = Checkpoint::from_journal_line(line).unwrap();
+        assert_eq!(checkpoint.extern_id, "fs.read_file");
+        assert_eq!(checkpoint.step.as_deref(), Some("s1"));
+        assert_eq!(checkpoint.result, r#""contents""#);
+        assert_eq!(checkpoint.to_journal_line().unwrap(), line);
+    }
+
+    #[test]
+    fn test_invalid_journal_line() {
+        assert!(Checkpoint::from_journal_line("{\"timestamp\":").is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Compares the JSON and binary forms of a large program and its symbol
+//! graph.
+//!
+//! Run with `cargo bench -p covenant-symbols --bench serialization`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use covenant_ast::Program;
+use covenant_symbols::{build_symbol_graph, SymbolGraph};
+
+const SNIPPETS: usize = 2_000;
+const ITERATIONS: u32 = 20;
+
+/// A program of chained functions, each calling the one before it
+fn source() -> String {
+    let mut source = String::new();
+    for i in 0..SNIPPETS {
+        let callee = if i == 0 { "math.f0".to_string() } else { format!("math.f{}", i - 1) };
+        source.push_str(&format!(
+            r#"
+snippet id="math.f{i}" kind="fn"
+signature
+  fn name="f{i}"
+    param name="a" type="Int"
+    param name="b" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=add
+    input var="a"
+    input lit={i}
+    as="sum"
+  end
+  step id="s2" kind="call"
+    fn="{callee}"
+    arg name="a" from="sum"
+    arg name="b" from="b"
+    as="result"
+  end
+  step id="s3" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#
+        ));
+    }
+    source
+}
+
+fn time<T>(mut f: impl FnMut() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn report(name: &str, json_len: usize, binary_len: usize, timings: [Duration; 4]) {
+    let [json_encode, json_decode, binary_encode, binary_decode] = timings;
+    println!("{}", name);
+    println!("  size    json {:>10} B   binary {:>10} B", json_len, binary_len);
+    println!("  encode  json {:>10.2?}   binary {:>10.2?}", json_encode, binary_encode);
+    println!("  decode  json {:>10.2?}   binary {:>10.2?}", json_decode, binary_decode);
+}
+
+fn main() {
+    let program = covenant_parser::parse(&source()).expect("generated program parses");
+
+    let json = program.to_json();
+    let binary = program.to_binary();
+    report(&format!("program ({} snippets)", SNIPPETS), json.len(), binary.len(), [
+        time(|| program.to_json()),
+        time(|| Program::from_json(&json).unwrap()),
+        time(|| program.to_binary()),
+        time(|| Program::from_binary(&binary).unwrap()),
+    ]);
+
+    let graph = build_symbol_graph(&program).expect("generated program resolves").graph;
+    let json = serde_json::to_string(&graph).unwrap();
+    let binary = graph.to_binary();
+    report(&format!("symbol graph ({} symbols)", graph.len()), json.len(), binary.len(), [
+        time(|| serde_json::to_string(&graph).unwrap()),
+        time(|| serde_json::from_str::<SymbolGraph>(&json).unwrap()),
+        time(|| graph.to_binary()),
+        time(|| SymbolGraph::from_binary(&binary).unwrap()),
+    ]);
+}
@@ -0,0 +1,120 @@
+//! Transitive closure queries
+//!
+//! Walks the call graph outward from a symbol, breadth-first, so results
+//! come nearest first and a depth limit cuts them off cleanly. Every symbol
+//! is visited once, so mutual recursion terminates; a symbol reached back
+//! through a cycle is its own transitive caller or callee.
+//!
+//! [`SymbolGraph::reachable_from`] computes the live set for dead-code
+//! reporting: the entry points and everything they can reach.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::{SymbolGraph, SymbolId};
+
+impl SymbolGraph {
+    /// Symbols that call `id` directly or through other callers, nearest
+    /// first, up to `max_depth` calls away (`None` for no limit)
+    pub fn transitive_callers(&self, id: SymbolId, max_depth: Option<usize>) -> Vec<SymbolId> {
+        self.walk(id, max_depth, |symbol| {
+            let mut callers: Vec<SymbolId> =
+                self.get(symbol).map(|s| s.called_by.iter().copied().collect()).unwrap_or_default();
+            callers.sort_unstable();
+            callers
+        })
+    }
+
+    /// Symbols `id` calls directly or through its callees, nearest first,
+    /// up to `max_depth` calls away (`None` for no limit). Unresolved calls
+    /// are skipped.
+    pub fn transitive_callees(&self, id: SymbolId, max_depth: Option<usize>) -> Vec<SymbolId> {
+        self.walk(id, max_depth, |symbol| self.resolved_callees(symbol))
+    }
+
+    /// The live set: `entry_points` and every symbol they reach by calling
+    /// it, referencing or embedding it as a type, or publishing an event it
+    /// subscribes to
+    pub fn reachable_from(&self, entry_points: &[SymbolId]) -> HashSet<SymbolId> {
+        let mut live: HashSet<SymbolId> = entry_points.iter().copied().filter(|id| self.get(*id).is_some()).collect();
+        let mut stack: Vec<SymbolId> = live.iter().copied().collect();
+        while let Some(id) = stack.pop() {
+            let Some(symbol) = self.get(id) else { continue };
+            let named = symbol.calls.iter().chain(&symbol.references).chain(&symbol.embeds);
+            let next = named.filter_map(|name| self.id_of(name)).chain(symbol.notifies.iter().copied());
+            for next in next {
+                if live.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        live
+    }
+
+    /// Breadth-first walk from `start` over `edges`, excluding `start`
+    /// unless a cycle leads back to it
+    fn walk(
+        &self,
+        start: SymbolId,
+        max_depth: Option<usize>,
+        edges: impl Fn(SymbolId) -> Vec<SymbolId>,
+    ) -> Vec<SymbolId> {
+        let mut seen: HashSet<SymbolId> = HashSet::new();
+        let mut order = Vec::new();
+        let mut queue: VecDeque<(SymbolId, usize)> = VecDeque::from([(start, 0)]);
+        while let Some((id, depth)) = queue.pop_front() {
+            if max_depth.is_some_and(|max| depth >= max) {
+                continue;
+            }
+            for next in edges(id) {
+                if seen.insert(next) {
+                    order.push(next);
+                    queue.push_back((next, depth + 1));
+                }
+            }
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::graph;
+    use crate::{SymbolGraph, SymbolId};
+
+    fn names(graph: &SymbolGraph, ids: impl IntoIterator<Item = SymbolId>) -> Vec<String> {
+        ids.into_iter().map(|id| graph.get(id).unwrap().name.clone()).collect()
+    }
+
+    #[test]
+    fn test_transitive_calls_with_depth() {
+        let graph = graph(&[("main", &["a"]), ("a", &["b", "c"]), ("b", &["c"]), ("c", &[]), ("unused", &["c"])]);
+        let id = |name| graph.id_of(name).unwrap();
+
+        assert_eq!(names(&graph, graph.transitive_callees(id("main"), None)), vec!["a", "b", "c"]);
+        assert_eq!(names(&graph, graph.transitive_callees(id("main"), Some(1))), vec!["a"]);
+        assert!(graph.transitive_callees(id("main"), Some(0)).is_empty());
+
+        let callers = names(&graph, graph.transitive_callers(id("c"), None));
+        assert_eq!(callers, vec!["a", "b", "unused", "main"]);
+    }
+
+    #[test]
+    fn test_recursion_terminates() {
+        // Mutual recursion is a cycle error, so build the graph with self-recursion
+        let graph = graph(&[("main", &["loop"]), ("loop", &["loop", "leaf"]), ("leaf", &[])]);
+        let id = |name| graph.id_of(name).unwrap();
+
+        assert_eq!(names(&graph, graph.transitive_callees(id("main"), None)), vec!["loop", "leaf"]);
+        assert_eq!(names(&graph, graph.transitive_callers(id("loop"), None)), vec!["main", "loop"]);
+    }
+
+    #[test]
+    fn test_reachable_from_entry_points() {
+        let graph = graph(&[("main", &["a"]), ("a", &[]), ("dead", &["a"]), ("dead_too", &["dead"])]);
+        let live = graph.reachable_from(&[graph.id_of("main").unwrap()]);
+
+        let mut live = names(&graph, live);
+        live.sort();
+        assert_eq!(live, vec!["a", "main"]);
+    }
+}
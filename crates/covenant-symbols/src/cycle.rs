@@ -2,10 +2,34 @@
 //!
 //! Validates I4 (acyclicity) - no circular calls between different functions.
 //! Self-recursion (a function calling itself) IS allowed.
-//! Mutual recursion (A calls B, B calls A) is NOT allowed.
-
-use crate::{SymbolError, SymbolGraph, SymbolId};
-use std::collections::HashMap;
+//! Mutual recursion (A calls B, B calls A) is NOT allowed, unless the graph
+//! is built with [`CycleMode::AnnotatedGroups`] and every function in the
+//! strongly connected component carries the same annotation:
+//!
+//! ```text
+//! metadata
+//!   allow cycle group="parser"
+//! end
+//! ```
+
+use crate::{SymbolError, SymbolGraph, SymbolId, SymbolInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+/// Metadata key for a cycle group (written `allow cycle group=...`)
+pub const CYCLE_GROUP_KEY: &str = "allow.cycle.group";
+
+/// How cycles between different functions are treated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CycleMode {
+    /// Every cycle is an error (E-SYMBOL-003)
+    #[default]
+    Reject,
+    /// A strongly connected component whose members all name the same
+    /// `allow cycle group=` may recurse; any other cycle is an error
+    /// (E-SYMBOL-005)
+    AnnotatedGroups,
+}
 
 /// Visit state for DFS cycle detection
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -25,19 +49,23 @@ impl CycleDetector {
     /// Check for circular calls in the symbol graph
     /// Returns errors for each cycle detected (excluding self-recursion)
     pub fn detect_cycles(graph: &SymbolGraph) -> Vec<SymbolError> {
+        let roots: Vec<SymbolId> = graph.iter().map(|s| s.id).collect();
+        Self::detect_cycles_from(graph, &roots)
+    }
+
+    /// Check for circular calls reachable from `roots`
+    ///
+    /// After an edit to an acyclic graph, any new cycle runs through an
+    /// added or modified symbol, so only those need to be searched from.
+    pub fn detect_cycles_from(graph: &SymbolGraph, roots: &[SymbolId]) -> Vec<SymbolError> {
         let mut errors = Vec::new();
         let mut state: HashMap<SymbolId, VisitState> = HashMap::new();
         let mut path: Vec<SymbolId> = Vec::new();
 
-        // Initialize all symbols as not visited
-        for symbol in graph.iter() {
-            state.insert(symbol.id, VisitState::NotVisited);
-        }
-
-        // DFS from each unvisited symbol
-        for symbol in graph.iter() {
-            if state[&symbol.id] == VisitState::NotVisited {
-                if let Some(cycle) = Self::visit(graph, symbol.id, &mut state, &mut path) {
+        // DFS from each unvisited root
+        for &root in roots {
+            if state.get(&root).copied().unwrap_or(VisitState::NotVisited) == VisitState::NotVisited {
+                if let Some(cycle) = Self::visit(graph, root, &mut state, &mut path) {
                     errors.push(cycle);
                 }
             }
@@ -46,6 +74,166 @@ impl CycleDetector {
         errors
     }
 
+    /// Check for cycles reachable from `roots` under `mode`
+    pub fn detect(graph: &SymbolGraph, roots: &[SymbolId], mode: CycleMode) -> Vec<SymbolError> {
+        match mode {
+            CycleMode::Reject => Self::detect_cycles_from(graph, roots),
+            CycleMode::AnnotatedGroups => Self::detect_unannotated_from(graph, roots),
+        }
+    }
+
+    /// Check that every cycle in the symbol graph lies within one annotated
+    /// group, reporting each component that does not
+    pub fn detect_unannotated_cycles(graph: &SymbolGraph) -> Vec<SymbolError> {
+        let roots: Vec<SymbolId> = graph.iter().map(|s| s.id).collect();
+        Self::detect_unannotated_from(graph, &roots)
+    }
+
+    /// Check that every cycle reachable from `roots` lies within one
+    /// annotated group, reporting each component that does not
+    pub fn detect_unannotated_from(graph: &SymbolGraph, roots: &[SymbolId]) -> Vec<SymbolError> {
+        let mut errors = Vec::new();
+        for component in Self::cyclic_components(graph, roots) {
+            let members: Vec<&SymbolInfo> = component.iter().filter_map(|id| graph.get(*id)).collect();
+            let groups: BTreeSet<&str> = members.iter().filter_map(|s| s.cycle_group.as_deref()).collect();
+            let unannotated: Vec<String> =
+                members.iter().filter(|s| s.cycle_group.is_none()).map(|s| s.name.clone()).collect();
+            if unannotated.is_empty() && groups.len() == 1 {
+                continue;
+            }
+
+            // Start at a member that needs the annotation, so the span points there
+            let start = members
+                .iter()
+                .find(|s| s.cycle_group.is_none())
+                .or(members.first())
+                .map(|s| s.id)
+                .expect("cyclic components have members");
+            let cycle = Self::cycle_through(graph, &component, start);
+            errors.push(SymbolError::UnannotatedCycle {
+                cycle: cycle
+                    .iter()
+                    .filter_map(|id| graph.get(*id).map(|s| s.name.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(" -> "),
+                unannotated,
+                groups: groups.into_iter().map(str::to_string).collect(),
+                span: graph.get(start).map(|s| s.span).unwrap_or_default(),
+            });
+        }
+        errors
+    }
+
+    /// Strongly connected components of the call graph reachable from
+    /// `roots` with more than one member, each sorted by name (Tarjan's
+    /// algorithm; self-calls are ignored)
+    pub fn cyclic_components(graph: &SymbolGraph, roots: &[SymbolId]) -> Vec<Vec<SymbolId>> {
+        struct State {
+            index: usize,
+            indices: HashMap<SymbolId, usize>,
+            lowlink: HashMap<SymbolId, usize>,
+            stack: Vec<SymbolId>,
+            on_stack: HashSet<SymbolId>,
+            components: Vec<Vec<SymbolId>>,
+        }
+
+        fn visit(graph: &SymbolGraph, state: &mut State, node: SymbolId) {
+            state.indices.insert(node, state.index);
+            state.lowlink.insert(node, state.index);
+            state.index += 1;
+            state.stack.push(node);
+            state.on_stack.insert(node);
+
+            for callee in graph.resolved_callees(node) {
+                if callee == node {
+                    continue;
+                }
+                if !state.indices.contains_key(&callee) {
+                    visit(graph, state, callee);
+                    let low = state.lowlink[&node].min(state.lowlink[&callee]);
+                    state.lowlink.insert(node, low);
+                } else if state.on_stack.contains(&callee) {
+                    let low = state.lowlink[&node].min(state.indices[&callee]);
+                    state.lowlink.insert(node, low);
+                }
+            }
+
+            if state.lowlink[&node] == state.indices[&node] {
+                let mut component = Vec::new();
+                while let Some(member) = state.stack.pop() {
+                    state.on_stack.remove(&member);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                if component.len() > 1 {
+                    component.sort_by_key(|id| graph.get(*id).map(|s| s.name.clone()));
+                    state.components.push(component);
+                }
+            }
+        }
+
+        let mut state = State {
+            index: 0,
+            indices: HashMap::new(),
+            lowlink: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            components: Vec::new(),
+        };
+        for &root in roots {
+            if graph.get(root).is_some() && !state.indices.contains_key(&root) {
+                visit(graph, &mut state, root);
+            }
+        }
+        state.components
+    }
+
+    /// A closed walk from `start` through every member of `component` and
+    /// back, taking the shortest calls between members
+    fn cycle_through(graph: &SymbolGraph, component: &[SymbolId], start: SymbolId) -> Vec<SymbolId> {
+        let in_component: HashSet<SymbolId> = component.iter().copied().collect();
+        // The calls after `from` on a shortest path to `to`, within the component
+        let shortest = |from: SymbolId, to: SymbolId| -> Vec<SymbolId> {
+            let mut parents: HashMap<SymbolId, SymbolId> = HashMap::new();
+            let mut queue = VecDeque::from([from]);
+            while let Some(id) = queue.pop_front() {
+                for callee in graph.resolved_callees(id) {
+                    if !in_component.contains(&callee) {
+                        continue;
+                    }
+                    if callee == to {
+                        let mut path = vec![to];
+                        let mut at = id;
+                        while at != from {
+                            path.push(at);
+                            at = parents[&at];
+                        }
+                        path.reverse();
+                        return path;
+                    }
+                    if callee != from && !parents.contains_key(&callee) {
+                        parents.insert(callee, id);
+                        queue.push_back(callee);
+                    }
+                }
+            }
+            Vec::new()
+        };
+
+        let mut walk = vec![start];
+        for &member in component {
+            if !walk.contains(&member) {
+                let last = *walk.last().expect("walk is not empty");
+                walk.extend(shortest(last, member));
+            }
+        }
+        let last = *walk.last().expect("walk is not empty");
+        walk.extend(shortest(last, start));
+        walk
+    }
+
     /// DFS visit, returns Some(error) if cycle detected
     fn visit(
         graph: &SymbolGraph,
@@ -67,8 +255,8 @@ impl CycleDetector {
                     continue;
                 }
 
-                match state.get(&callee_id) {
-                    Some(VisitState::InProgress) => {
+                match state.get(&callee_id).copied().unwrap_or(VisitState::NotVisited) {
+                    VisitState::InProgress => {
                         // Cycle detected! Build the cycle path
                         let cycle_start = path.iter().position(|&id| id == callee_id)?;
                         let cycle_path: Vec<String> = path[cycle_start..]
@@ -87,7 +275,7 @@ impl CycleDetector {
                             span: node_span,
                         });
                     }
-                    Some(VisitState::NotVisited) => {
+                    VisitState::NotVisited => {
                         if let Some(err) = Self::visit(graph, callee_id, state, path) {
                             return Some(err);
                         }
@@ -107,7 +295,7 @@ impl CycleDetector {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{SymbolInfo, SymbolKind};
+    use crate::SymbolKind;
     use covenant_ast::Span;
 
     fn make_span() -> Span {
@@ -206,4 +394,55 @@ mod tests {
         let errors = CycleDetector::detect_cycles(&graph);
         assert!(errors.is_empty(), "Self-recursion with other calls should be allowed");
     }
+
+    fn function(name: &str, calls: &[&str], group: Option<&str>) -> SymbolInfo {
+        let mut symbol = SymbolInfo::new(name.into(), SymbolKind::Function, make_span());
+        symbol.calls.extend(calls.iter().map(|c| c.to_string()));
+        symbol.cycle_group = group.map(str::to_string);
+        symbol
+    }
+
+    #[test]
+    fn test_annotated_group_allowed() {
+        let mut graph = SymbolGraph::new();
+        graph.insert(function("expr", &["term"], Some("parser"))).unwrap();
+        graph.insert(function("term", &["atom"], Some("parser"))).unwrap();
+        graph.insert(function("atom", &["expr", "lex"], Some("parser"))).unwrap();
+        graph.insert(function("lex", &[], None)).unwrap();
+
+        assert!(CycleDetector::detect_unannotated_cycles(&graph).is_empty());
+        assert_eq!(CycleDetector::detect_cycles(&graph).len(), 1, "Strict mode still rejects the cycle");
+    }
+
+    #[test]
+    fn test_unannotated_member_lists_full_cycle() {
+        // Two overlapping cycles, expr -> term -> expr and expr -> atom -> expr, form one component
+        let mut graph = SymbolGraph::new();
+        graph.insert(function("expr", &["term", "atom"], Some("parser"))).unwrap();
+        graph.insert(function("term", &["expr"], Some("parser"))).unwrap();
+        graph.insert(function("atom", &["expr"], None)).unwrap();
+
+        let errors = CycleDetector::detect_unannotated_cycles(&graph);
+        assert_eq!(errors.len(), 1);
+        let SymbolError::UnannotatedCycle { cycle, unannotated, .. } = &errors[0] else {
+            panic!("Expected UnannotatedCycle error, got {:?}", errors[0]);
+        };
+        assert_eq!(cycle, "atom -> expr -> term -> expr -> atom");
+        assert_eq!(unannotated, &vec!["atom".to_string()]);
+        assert_eq!(errors[0].code(), "E-SYMBOL-005");
+    }
+
+    #[test]
+    fn test_different_groups_rejected() {
+        let mut graph = SymbolGraph::new();
+        graph.insert(function("a", &["b"], Some("x"))).unwrap();
+        graph.insert(function("b", &["a"], Some("y"))).unwrap();
+
+        let errors = CycleDetector::detect_unannotated_cycles(&graph);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "cycle a -> b -> a is not allowed: its members are in different groups (x, y)"
+        );
+    }
 }
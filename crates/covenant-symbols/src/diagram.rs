@@ -0,0 +1,319 @@
+//! Symbol graph diagrams
+//!
+//! [`SymbolGraph::to_dot`] and [`SymbolGraph::to_mermaid`] draw the graph
+//! for GraphViz and Mermaid: a node per symbol, grouped by module, and an
+//! edge labelled with its [`ReferenceKind`] wherever one symbol refers to
+//! another (the same records [`SymbolGraph::references_to`] returns).
+//!
+//! A whole project is rarely worth drawing. [`DiagramOptions`] keeps the
+//! symbols under a module prefix, only the edge kinds asked for, and the
+//! symbols up to `depth` edges away from those, in either direction, so a
+//! diagram of `app.orders` at depth 1 also shows what it uses and what
+//! uses it.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fmt::Write;
+
+use crate::{ReferenceKind, SymbolGraph, SymbolId, SymbolInfo, SymbolKind, ROOT_MODULE};
+
+/// Which part of the graph a diagram or export shows
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagramOptions {
+    /// Only symbols in this module or below it, e.g. `app.orders`
+    pub module_prefix: Option<String>,
+    /// Only edges of these kinds; empty for every kind
+    pub edge_kinds: Vec<ReferenceKind>,
+    /// Also show symbols this many edges away from those in the module
+    pub depth: usize,
+}
+
+/// The symbols and edges a diagram shows, in a stable order
+pub(crate) struct Diagram<'a> {
+    /// Symbols by module, modules and symbols sorted by name
+    pub(crate) modules: BTreeMap<&'a str, Vec<&'a SymbolInfo>>,
+    /// (from, to, kind), sorted by names then kind
+    pub(crate) edges: Vec<(&'a SymbolInfo, &'a SymbolInfo, ReferenceKind)>,
+}
+
+impl SymbolGraph {
+    /// The graph as a GraphViz `digraph`
+    pub fn to_dot(&self, options: &DiagramOptions) -> String {
+        let diagram = self.diagram(options);
+        let mut out = String::from("digraph symbols {\n  rankdir=LR;\n  node [fontname=\"Helvetica\"];\n");
+        for (module, symbols) in &diagram.modules {
+            let indent = if *module == ROOT_MODULE { "  " } else { "    " };
+            if *module != ROOT_MODULE {
+                let _ = writeln!(out, "  subgraph \"cluster_{}\" {{", dot_escape(module));
+                let _ = writeln!(out, "    label=\"{}\";", dot_escape(module));
+            }
+            for symbol in symbols {
+                let (shape, style) = dot_shape(symbol.kind);
+                let _ = write!(out, "{}\"{}\" [shape={}", indent, dot_escape(&symbol.name), shape);
+                if let Some(style) = style {
+                    let _ = write!(out, ", style={}", style);
+                }
+                out.push_str("];\n");
+            }
+            if *module != ROOT_MODULE {
+                out.push_str("  }\n");
+            }
+        }
+        for (from, to, kind) in &diagram.edges {
+            let (from, to) = (dot_escape(&from.name), dot_escape(&to.name));
+            let _ = write!(out, "  \"{}\" -> \"{}\" [label=\"{}\"", from, to, kind);
+            if *kind != ReferenceKind::Call {
+                out.push_str(", style=dashed");
+            }
+            out.push_str("];\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// The graph as a Mermaid flowchart
+    pub fn to_mermaid(&self, options: &DiagramOptions) -> String {
+        let diagram = self.diagram(options);
+        // Symbol IDs contain dots, which Mermaid node IDs can't
+        let ids: BTreeMap<SymbolId, usize> =
+            diagram.modules.values().flatten().enumerate().map(|(index, symbol)| (symbol.id, index)).collect();
+
+        let mut out = String::from("flowchart LR\n");
+        for (index, (module, symbols)) in diagram.modules.iter().enumerate() {
+            let indent = if *module == ROOT_MODULE { "  " } else { "    " };
+            if *module != ROOT_MODULE {
+                let _ = writeln!(out, "  subgraph m{}[\"{}\"]", index, mermaid_escape(module));
+            }
+            for symbol in symbols {
+                let (open, close) = mermaid_shape(symbol.kind);
+                let name = mermaid_escape(&symbol.name);
+                let _ = writeln!(out, "{}n{}{}\"{}\"{}", indent, ids[&symbol.id], open, name, close);
+            }
+            if *module != ROOT_MODULE {
+                out.push_str("  end\n");
+            }
+        }
+        for (from, to, kind) in &diagram.edges {
+            let arrow = if *kind == ReferenceKind::Call { "-->" } else { "-.->" };
+            let _ = writeln!(out, "  n{} {}|{}| n{}", ids[&from.id], arrow, kind, ids[&to.id]);
+        }
+        out
+    }
+
+    /// The part of the graph `options` selects
+    pub(crate) fn diagram(&self, options: &DiagramOptions) -> Diagram<'_> {
+        let mut edges: Vec<(SymbolId, SymbolId, ReferenceKind)> = Vec::new();
+        for symbol in self.iter() {
+            for reference in self.references_to(symbol.id) {
+                if options.edge_kinds.is_empty() || options.edge_kinds.contains(&reference.kind) {
+                    edges.push((reference.from, symbol.id, reference.kind));
+                }
+            }
+        }
+
+        let in_module = |name: &str| match options.module_prefix.as_deref() {
+            None => true,
+            Some(prefix) => name.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('.')),
+        };
+        let mut shown: HashSet<SymbolId> = self.iter().filter(|s| in_module(&s.name)).map(|s| s.id).collect();
+
+        let mut neighbours: BTreeMap<SymbolId, BTreeSet<SymbolId>> = BTreeMap::new();
+        for (from, to, _) in &edges {
+            neighbours.entry(*from).or_default().insert(*to);
+            neighbours.entry(*to).or_default().insert(*from);
+        }
+        let mut queue: VecDeque<(SymbolId, usize)> = shown.iter().map(|id| (*id, 0)).collect();
+        while let Some((id, distance)) = queue.pop_front() {
+            if distance == options.depth {
+                continue;
+            }
+            for next in neighbours.get(&id).into_iter().flatten() {
+                if shown.insert(*next) {
+                    queue.push_back((*next, distance + 1));
+                }
+            }
+        }
+
+        let mut modules: BTreeMap<&str, Vec<&SymbolInfo>> = BTreeMap::new();
+        for symbol in self.iter().filter(|s| shown.contains(&s.id)) {
+            modules.entry(self.module_name_of(&symbol.name)).or_default().push(symbol);
+        }
+        for symbols in modules.values_mut() {
+            symbols.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+
+        let mut edges: Vec<_> = edges
+            .into_iter()
+            .filter(|(from, to, _)| shown.contains(from) && shown.contains(to))
+            .filter_map(|(from, to, kind)| Some((self.get(from)?, self.get(to)?, kind)))
+            .collect();
+        edges.sort_by(|a, b| (&a.0.name, &a.1.name, a.2).cmp(&(&b.0.name, &b.1.name, b.2)));
+
+        Diagram { modules, edges }
+    }
+}
+
+fn dot_shape(kind: SymbolKind) -> (&'static str, Option<&'static str>) {
+    match kind {
+        SymbolKind::Function | SymbolKind::Subscriber => ("box", None),
+        SymbolKind::Workflow => ("box", Some("bold")),
+        SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Alias => ("ellipse", None),
+        SymbolKind::Interface => ("ellipse", Some("dashed")),
+        SymbolKind::Extern | SymbolKind::ExternAbstract | SymbolKind::ExternImpl => ("hexagon", None),
+        SymbolKind::Database | SymbolKind::Data => ("cylinder", None),
+        SymbolKind::Module => ("folder", None),
+        SymbolKind::Test => ("note", None),
+        SymbolKind::Const => ("plaintext", None),
+    }
+}
+
+fn mermaid_shape(kind: SymbolKind) -> (&'static str, &'static str) {
+    match kind {
+        SymbolKind::Function | SymbolKind::Workflow | SymbolKind::Subscriber | SymbolKind::Module => ("[", "]"),
+        SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Alias | SymbolKind::Interface => ("([", "])"),
+        SymbolKind::Extern | SymbolKind::ExternAbstract | SymbolKind::ExternImpl => ("{{", "}}"),
+        SymbolKind::Database | SymbolKind::Data => ("[(", ")]"),
+        SymbolKind::Test => ("[/", "/]"),
+        SymbolKind::Const => ("(", ")"),
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_symbol_graph;
+
+    const SOURCE: &str = r#"snippet id="app.orders.Order" kind="struct"
+signature
+  struct name="Order"
+    field name="id" type="Int"
+  end
+end
+end
+
+snippet id="app.orders.place" kind="fn"
+signature
+  fn name="place"
+    param name="id" type="Int"
+    returns type="app.orders.Order"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="app.billing.charge"
+    arg name="id" from="id"
+    as="_"
+  end
+  step id="s2" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+
+snippet id="app.billing.charge" kind="fn"
+signature
+  fn name="charge"
+    param name="id" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="app.billing.audit"
+    arg name="id" from="id"
+    as="_"
+  end
+  step id="s2" kind="return"
+    from="id"
+    as="_"
+  end
+end
+end
+
+snippet id="app.billing.audit" kind="fn"
+signature
+  fn name="audit"
+    param name="id" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="id"
+    as="_"
+  end
+end
+end
+"#;
+
+    fn graph() -> SymbolGraph {
+        build_symbol_graph(&covenant_parser::parse(SOURCE).unwrap()).unwrap().graph
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = graph().to_dot(&DiagramOptions::default());
+        assert!(dot.starts_with("digraph symbols {"));
+        assert!(dot.contains("subgraph \"cluster_app.orders\" {"));
+        assert!(dot.contains("\"app.orders.Order\" [shape=ellipse];"));
+        assert!(dot.contains("\"app.orders.place\" -> \"app.billing.charge\" [label=\"call\"];"));
+        assert!(dot.contains(
+            "\"app.orders.place\" -> \"app.orders.Order\" [label=\"type_reference\", style=dashed];"
+        ));
+    }
+
+    #[test]
+    fn test_mermaid() {
+        let mermaid = graph().to_mermaid(&DiagramOptions::default());
+        assert_eq!(
+            mermaid,
+            r#"flowchart LR
+  subgraph m0["app.billing"]
+    n0["app.billing.audit"]
+    n1["app.billing.charge"]
+  end
+  subgraph m1["app.orders"]
+    n2(["app.orders.Order"])
+    n3["app.orders.place"]
+  end
+  n1 -->|call| n0
+  n3 -->|call| n1
+  n3 -.->|type_reference| n2
+"#
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let graph = graph();
+        let names = |options: DiagramOptions| -> Vec<String> {
+            graph.diagram(&options).modules.values().flatten().map(|s| s.name.clone()).collect()
+        };
+
+        let orders = DiagramOptions { module_prefix: Some("app.orders".into()), ..DiagramOptions::default() };
+        assert_eq!(names(orders.clone()), vec!["app.orders.Order", "app.orders.place"]);
+        assert_eq!(
+            names(DiagramOptions { depth: 1, ..orders.clone() }),
+            vec!["app.billing.charge", "app.orders.Order", "app.orders.place"]
+        );
+        assert_eq!(
+            names(DiagramOptions { depth: 1, edge_kinds: vec![ReferenceKind::TypeReference], ..orders }),
+            vec!["app.orders.Order", "app.orders.place"]
+        );
+        // A prefix matches whole module names only
+        let partial = DiagramOptions { module_prefix: Some("app.ord".into()), ..DiagramOptions::default() };
+        assert!(names(partial).is_empty());
+
+        let calls = DiagramOptions { edge_kinds: vec![ReferenceKind::Call], ..DiagramOptions::default() };
+        let calls_only = graph.to_dot(&calls);
+        assert!(!calls_only.contains("type_reference"));
+    }
+}
@@ -27,6 +27,28 @@ pub enum SymbolError {
         span: Span,
     },
 
+    /// E-SYMBOL-004: Snippet listed as a member by two modules (hard error)
+    #[error("'{member}' is a member of both '{first}' and '{second}'")]
+    MultipleModules {
+        member: String,
+        first: String,
+        second: String,
+        span: Span,
+    },
+
+    /// E-SYMBOL-005: Cycle not covered by one `allow cycle` group (hard
+    /// error, only when annotated groups are allowed)
+    #[error("cycle {cycle} is not allowed: {}", cycle_detail(unannotated, groups))]
+    UnannotatedCycle {
+        /// Full cycle through every member, e.g., "a -> b -> c -> a"
+        cycle: String,
+        /// Members without an `allow cycle group=` annotation
+        unannotated: Vec<String>,
+        /// Distinct groups the annotated members name
+        groups: Vec<String>,
+        span: Span,
+    },
+
     /// E-REL-001: Relation target not found (hard error)
     #[error("relation target not found: {target}")]
     RelationTargetNotFound {
@@ -35,6 +57,20 @@ pub enum SymbolError {
         /// The symbol containing the relation
         from_symbol: String,
     },
+
+    /// E-QUERY-002: Table not declared in the target database's schema (hard error)
+    #[error("table '{table}' is not declared in database '{database}'")]
+    UnknownTable {
+        table: String,
+        database: String,
+        span: Span,
+        /// The symbol containing the query or foreign key
+        referrer: String,
+    },
+
+    /// E-CANCEL-001: Graph building was cancelled (hard error)
+    #[error("symbol graph building cancelled")]
+    Cancelled,
 }
 
 impl SymbolError {
@@ -44,7 +80,11 @@ impl SymbolError {
             SymbolError::UndefinedReference { span, .. } => *span,
             SymbolError::DuplicateId { span, .. } => *span,
             SymbolError::CircularImport { span, .. } => *span,
+            SymbolError::MultipleModules { span, .. } => *span,
+            SymbolError::UnannotatedCycle { span, .. } => *span,
             SymbolError::RelationTargetNotFound { span, .. } => *span,
+            SymbolError::UnknownTable { span, .. } => *span,
+            SymbolError::Cancelled => Span::dummy(),
         }
     }
 
@@ -54,7 +94,11 @@ impl SymbolError {
             SymbolError::UndefinedReference { .. } => false, // Soft in Phase 2
             SymbolError::DuplicateId { .. } => true,
             SymbolError::CircularImport { .. } => true,
+            SymbolError::MultipleModules { .. } => true,
+            SymbolError::UnannotatedCycle { .. } => true,
             SymbolError::RelationTargetNotFound { .. } => true,
+            SymbolError::UnknownTable { .. } => true,
+            SymbolError::Cancelled => true,
         }
     }
 
@@ -64,7 +108,20 @@ impl SymbolError {
             SymbolError::UndefinedReference { .. } => "E-SYMBOL-001",
             SymbolError::DuplicateId { .. } => "E-SYMBOL-002",
             SymbolError::CircularImport { .. } => "E-SYMBOL-003",
+            SymbolError::MultipleModules { .. } => "E-SYMBOL-004",
+            SymbolError::UnannotatedCycle { .. } => "E-SYMBOL-005",
             SymbolError::RelationTargetNotFound { .. } => "E-REL-001",
+            SymbolError::UnknownTable { .. } => "E-QUERY-002",
+            SymbolError::Cancelled => "E-CANCEL-001",
         }
     }
 }
+
+/// Why a cycle's members do not form one group
+fn cycle_detail(unannotated: &[String], groups: &[String]) -> String {
+    if unannotated.is_empty() {
+        format!("its members are in different groups ({})", groups.join(", "))
+    } else {
+        format!("{} not annotated with `allow cycle group=`", unannotated.join(", "))
+    }
+}
@@ -0,0 +1,214 @@
+//! Symbol graph export for graph databases
+//!
+//! [`SymbolGraph::to_json_ld`] and [`SymbolGraph::to_cypher`] write the
+//! nodes and edges the diagrams draw, selected by the same
+//! [`DiagramOptions`], in forms graph databases load: JSON-LD, whose node
+//! objects expand to RDF triples, and Cypher `CREATE` statements for Neo4j
+//! (`cypher-shell < graph.cypher`).
+//!
+//! Every symbol becomes a node with its ID, kind, module, file, declared
+//! effects, requirements and doc, and every reference an edge named after its
+//! [`ReferenceKind`]: `call` in JSON-LD, `CALL` in Cypher.
+
+use std::fmt::Write;
+
+use serde_json::{json, Map, Value};
+
+use crate::{DiagramOptions, ReferenceKind, SymbolGraph, SymbolInfo};
+
+/// Prefix of symbol IRIs in JSON-LD
+const SYMBOL_IRI: &str = "urn:covenant:symbol:";
+
+/// Vocabulary of kinds and properties in JSON-LD
+const VOCAB_IRI: &str = "urn:covenant:vocab#";
+
+impl SymbolGraph {
+    /// The graph as a JSON-LD document
+    pub fn to_json_ld(&self, options: &DiagramOptions) -> String {
+        let diagram = self.diagram(options);
+
+        let mut context = Map::new();
+        context.insert("@vocab".into(), json!(VOCAB_IRI));
+        context.insert("sym".into(), json!(SYMBOL_IRI));
+        for kind in ReferenceKind::ALL {
+            context.insert(kind.as_str().into(), json!({ "@type": "@id" }));
+        }
+
+        let mut nodes = Vec::new();
+        for (module, symbols) in &diagram.modules {
+            for symbol in symbols {
+                let mut node = Map::new();
+                node.insert("@id".into(), json!(format!("sym:{}", symbol.name)));
+                node.insert("@type".into(), json!(format!("{:?}", symbol.kind)));
+                for (key, value) in properties(symbol, module) {
+                    node.insert(key.into(), value);
+                }
+                for (from, to, kind) in &diagram.edges {
+                    if from.id == symbol.id {
+                        let targets = node.entry(kind.as_str()).or_insert_with(|| json!([]));
+                        if let Value::Array(targets) = targets {
+                            targets.push(json!(format!("sym:{}", to.name)));
+                        }
+                    }
+                }
+                nodes.push(Value::Object(node));
+            }
+        }
+
+        let document = json!({ "@context": context, "@graph": nodes });
+        serde_json::to_string_pretty(&document).expect("JSON-LD serializes")
+    }
+
+    /// The graph as Cypher statements, one per line: an index on symbol
+    /// IDs, a `CREATE` per symbol, then a `MATCH ... CREATE` per edge
+    pub fn to_cypher(&self, options: &DiagramOptions) -> String {
+        let diagram = self.diagram(options);
+        let mut out = String::from("CREATE INDEX symbol_id IF NOT EXISTS FOR (s:Symbol) ON (s.id);\n");
+        for (module, symbols) in &diagram.modules {
+            for symbol in symbols {
+                let fields: Vec<String> = std::iter::once(("id", json!(symbol.name)))
+                    .chain(properties(symbol, module))
+                    .map(|(key, value)| format!("{}: {}", key, cypher_value(&value)))
+                    .collect();
+                let _ = writeln!(out, "CREATE (:Symbol:{:?} {{{}}});", symbol.kind, fields.join(", "));
+            }
+        }
+        for (from, to, kind) in &diagram.edges {
+            let _ = writeln!(
+                out,
+                "MATCH (a:Symbol {{id: {}}}), (b:Symbol {{id: {}}}) CREATE (a)-[:{}]->(b);",
+                cypher_string(&from.name),
+                cypher_string(&to.name),
+                kind.as_str().to_ascii_uppercase()
+            );
+        }
+        out
+    }
+}
+
+/// A symbol's properties besides its ID and kind, leaving out empty ones
+fn properties(symbol: &SymbolInfo, module: &str) -> Vec<(&'static str, Value)> {
+    let mut properties = vec![("module", json!(module))];
+    if let Some(file) = &symbol.file {
+        properties.push(("file", json!(file.display().to_string())));
+    }
+    let effects: Vec<&str> = symbol.declared_effects.iter().map(|effect| effect.name.as_str()).collect();
+    if !effects.is_empty() {
+        properties.push(("effects", json!(effects)));
+    }
+    if !symbol.requirements.is_empty() {
+        properties.push(("requirements", json!(symbol.requirements)));
+    }
+    if let Some(doc) = &symbol.doc {
+        properties.push(("doc", json!(doc)));
+    }
+    properties
+}
+
+/// A string, or list of strings, as a Cypher literal
+fn cypher_value(value: &Value) -> String {
+    match value {
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(cypher_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::String(text) => cypher_string(text),
+        other => other.to_string(),
+    }
+}
+
+fn cypher_string(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('\'', "\\'").replace('\n', "\\n");
+    format!("'{}'", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_symbol_graph;
+
+    const SOURCE: &str = r#"snippet id="app.User" kind="struct"
+signature
+  struct name="User"
+    field name="id" type="Int"
+  end
+end
+end
+
+snippet id="app.load" kind="fn"
+effects
+  effect database
+end
+signature
+  fn name="load"
+    param name="id" type="Int"
+    returns type="app.User"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="app.make"
+    arg name="id" from="id"
+    as="user"
+  end
+  step id="s2" kind="return"
+    from="user"
+    as="_"
+  end
+end
+end
+
+snippet id="app.make" kind="fn"
+signature
+  fn name="make"
+    param name="id" type="Int"
+    returns type="app.User"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+"#;
+
+    fn graph() -> SymbolGraph {
+        build_symbol_graph(&covenant_parser::parse(SOURCE).unwrap()).unwrap().graph
+    }
+
+    #[test]
+    fn test_json_ld() {
+        let document: Value = serde_json::from_str(&graph().to_json_ld(&DiagramOptions::default())).unwrap();
+        assert_eq!(document["@context"]["call"], json!({ "@type": "@id" }));
+
+        let nodes = document["@graph"].as_array().unwrap();
+        let load = nodes.iter().find(|node| node["@id"] == "sym:app.load").unwrap();
+        assert_eq!(load["@type"], "Function");
+        assert_eq!(load["module"], "app");
+        assert_eq!(load["effects"], json!(["database"]));
+        assert_eq!(load["call"], json!(["sym:app.make"]));
+        assert_eq!(load["type_reference"], json!(["sym:app.User"]));
+        assert_eq!(nodes.len(), 3);
+    }
+
+    #[test]
+    fn test_cypher() {
+        let cypher = graph().to_cypher(&DiagramOptions::default());
+        let lines: Vec<&str> = cypher.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "CREATE INDEX symbol_id IF NOT EXISTS FOR (s:Symbol) ON (s.id);",
+                "CREATE (:Symbol:Struct {id: 'app.User', module: 'app'});",
+                "CREATE (:Symbol:Function {id: 'app.load', module: 'app', effects: ['database']});",
+                "CREATE (:Symbol:Function {id: 'app.make', module: 'app'});",
+                "MATCH (a:Symbol {id: 'app.load'}), (b:Symbol {id: 'app.User'}) CREATE (a)-[:TYPE_REFERENCE]->(b);",
+                "MATCH (a:Symbol {id: 'app.load'}), (b:Symbol {id: 'app.make'}) CREATE (a)-[:CALL]->(b);",
+                "MATCH (a:Symbol {id: 'app.make'}), (b:Symbol {id: 'app.User'}) CREATE (a)-[:TYPE_REFERENCE]->(b);",
+            ]
+        );
+        assert_eq!(cypher_string("it's \\ here"), r"'it\'s \\ here'");
+    }
+}
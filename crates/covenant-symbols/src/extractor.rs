@@ -2,22 +2,29 @@
 //!
 //! Extracts symbols and forward references from snippets.
 
-use crate::{RelationRef, SymbolError, SymbolGraph, SymbolInfo, SymbolKind};
+use crate::{CYCLE_GROUP_KEY, RelationRef, SymbolError, SymbolGraph, SymbolInfo, SymbolKind, TableRef};
 use covenant_ast::{
-    BodySection, EffectDecl, EffectsSection, RelationsSection, RequiresSection, ReturnType, ReturnValue,
+    BodySection, CallSite, CancellationToken, EffectDecl, EffectsSection, InputSource, Literal, QueryContent, RelationsSection, RequiresSection, ReturnType, ReturnValue,
     Section, SignatureKind, SignatureSection, Snippet, SnippetKind, Step, StepKind, TestsSection,
     Type, TypeKind,
 };
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
 /// Extracts symbols and forward references from snippets (Pass 1)
 pub struct SymbolExtractor {
     errors: Vec<SymbolError>,
+    cancel: Option<CancellationToken>,
 }
 
 impl SymbolExtractor {
     pub fn new() -> Self {
-        Self { errors: Vec::new() }
+        Self { errors: Vec::new(), cancel: None }
+    }
+
+    /// Stop extracting (with `SymbolError::Cancelled`) once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
     }
 
     /// Extract all symbols from snippets into a symbol graph
@@ -25,6 +32,10 @@ impl SymbolExtractor {
         let mut graph = SymbolGraph::new();
 
         for snippet in snippets {
+            if self.cancel.as_ref().is_some_and(CancellationToken::is_cancelled) {
+                self.errors.push(SymbolError::Cancelled);
+                break;
+            }
             let symbol = self.extract_snippet(snippet);
             if let Err(e) = graph.insert(symbol) {
                 self.errors.push(e);
@@ -35,22 +46,22 @@ impl SymbolExtractor {
     }
 
     /// Extract a single snippet into a SymbolInfo
-    fn extract_snippet(&self, snippet: &Snippet) -> SymbolInfo {
+    pub(crate) fn extract_snippet(&self, snippet: &Snippet) -> SymbolInfo {
         let mut symbol = SymbolInfo::new(
             snippet.id.clone(),
             SymbolKind::from(snippet.kind),
             snippet.span,
         );
 
-        // Handle extern-impl specific fields
+        symbol.doc = snippet.doc.as_ref().map(|d| d.content.clone());
+
+        // The extern-abstract or interface this snippet implements
+        symbol.implements = snippet.implements.clone();
+        if let Some(ref implements) = snippet.implements {
+            symbol.references.insert(implements.clone());
+        }
         if snippet.kind == SnippetKind::ExternImpl {
-            symbol.implements = snippet.implements.clone();
             symbol.target_platform = snippet.platform.clone();
-
-            // Add the abstract snippet as a reference
-            if let Some(ref implements) = snippet.implements {
-                symbol.references.insert(implements.clone());
-            }
         }
 
         // Process each section
@@ -61,30 +72,77 @@ impl SymbolExtractor {
                 }
                 Section::Signature(sig) => {
                     let type_refs = self.extract_signature_types(sig);
+                    if matches!(sig.kind, SignatureKind::Struct(_) | SignatureKind::Enum(_) | SignatureKind::Alias(_)) {
+                        symbol.embeds.extend(type_refs.iter().cloned());
+                    }
                     symbol.references.extend(type_refs);
+                    if let SignatureKind::Function(fn_sig) = &sig.kind {
+                        symbol.generics = fn_sig.generics.iter().map(|g| g.name.clone()).collect();
+                        symbol.param_docs = fn_sig
+                            .params
+                            .iter()
+                            .filter_map(|p| Some((p.name.clone(), p.doc.as_ref()?.content.clone())))
+                            .collect();
+                    }
                 }
                 Section::Body(body) => {
+                    symbol.step_docs = self.extract_step_docs(&body.steps);
                     let (body_calls, body_refs) = self.extract_body_refs(body);
-                    symbol.calls.extend(body_calls);
+                    add_calls(&mut symbol, body_calls);
                     symbol.references.extend(body_refs);
+                    symbol.table_refs.extend(self.extract_query_tables(&body.steps));
+                    symbol.publishes = self.extract_published_events(&body.steps);
+                    symbol.references.extend(symbol.publishes.iter().cloned());
+                }
+                Section::Schema(schema) => {
+                    for table in &schema.tables {
+                        let fields = table.fields.iter().map(|f| f.name.clone()).collect();
+                        symbol.tables.insert(table.name.clone(), fields);
+
+                        for fk in table.fields.iter().filter_map(|f| f.foreign_key.as_ref()) {
+                            symbol.table_refs.push(TableRef {
+                                database: snippet.id.clone(),
+                                table: fk.table.clone(),
+                                span: fk.span,
+                            });
+                        }
+                    }
                 }
                 Section::Relations(rels) => {
                     symbol.relations_to = self.extract_relations(rels);
                 }
+                Section::Contains(contains) => {
+                    symbol.members.extend(contains.members.iter().map(|m| m.id.clone()));
+                }
+                Section::Subscribe(subscribe) => {
+                    symbol.subscribes = Some(subscribe.event.clone());
+                    symbol.references.insert(subscribe.event.clone());
+                    symbol.calls.insert(subscribe.handler.clone());
+                }
                 Section::Requires(reqs) => {
                     symbol.requirements = self.extract_requirements(reqs);
                 }
+                Section::Metadata(metadata) => {
+                    if let Some(entry) = metadata.entries.iter().find(|e| e.key == CYCLE_GROUP_KEY) {
+                        symbol.cycle_group = Some(entry.value.clone());
+                    }
+                }
                 Section::Tests(tests) => {
                     let (test_ids, covers, test_calls, test_refs) = self.extract_tests_full(tests);
                     symbol.tests = test_ids;
                     symbol.covers = covers;
-                    symbol.calls.extend(test_calls);
+                    add_calls(&mut symbol, test_calls);
                     symbol.references.extend(test_refs);
                 }
                 _ => {} // Other sections handled in later passes
             }
         }
 
+        // Generic params are placeholders, not types to resolve
+        for generic in &symbol.generics {
+            symbol.references.remove(generic);
+        }
+
         symbol
     }
 
@@ -93,6 +151,23 @@ impl SymbolExtractor {
         effects.effects.clone()
     }
 
+    /// Collect step docs by step ID, walking nested step lists
+    fn extract_step_docs(&self, steps: &[Step]) -> BTreeMap<String, String> {
+        let mut docs = BTreeMap::new();
+        let mut pending: Vec<&[Step]> = vec![steps];
+
+        while let Some(list) = pending.pop() {
+            for step in list {
+                if let Some(doc) = &step.doc {
+                    docs.insert(step.id.clone(), doc.content.clone());
+                }
+                pending.extend(step.nested_steps());
+            }
+        }
+
+        docs
+    }
+
     /// Extract requirement IDs from requires section
     fn extract_requirements(&self, reqs: &RequiresSection) -> Vec<String> {
         reqs.requirements.iter().map(|r| r.id.clone()).collect()
@@ -102,10 +177,10 @@ impl SymbolExtractor {
     fn extract_tests_full(
         &self,
         tests: &TestsSection,
-    ) -> (Vec<String>, Vec<String>, HashSet<String>, HashSet<String>) {
+    ) -> (Vec<String>, Vec<String>, CallSites, HashSet<String>) {
         let mut test_ids = Vec::new();
         let mut covers = Vec::new();
-        let mut calls = HashSet::new();
+        let mut calls = CallSites::new();
         let mut refs = HashSet::new();
 
         for test in &tests.tests {
@@ -113,7 +188,9 @@ impl SymbolExtractor {
             covers.extend(test.covers.iter().cloned());
 
             let (test_calls, test_refs) = self.extract_steps_refs(&test.steps);
-            calls.extend(test_calls);
+            for (name, sites) in test_calls {
+                calls.entry(name).or_default().extend(sites);
+            }
             refs.extend(test_refs);
         }
 
@@ -149,6 +226,29 @@ impl SymbolExtractor {
                     }
                 }
             }
+            SignatureKind::Const(const_sig) => {
+                self.collect_type_refs(&const_sig.ty, &mut refs);
+            }
+            SignatureKind::Alias(alias_sig) => {
+                self.collect_type_refs(&alias_sig.ty, &mut refs);
+            }
+            SignatureKind::Interface(iface) => {
+                for method in &iface.methods {
+                    let mut method_refs = HashSet::new();
+                    for param in &method.params {
+                        self.collect_type_refs(&param.ty, &mut method_refs);
+                    }
+                    if let Some(ret) = &method.returns {
+                        self.collect_return_type_refs(ret, &mut method_refs);
+                    }
+                    // `Self` and the method's generic params are placeholders
+                    method_refs.remove("Self");
+                    for generic in &method.generics {
+                        method_refs.remove(&generic.name);
+                    }
+                    refs.extend(method_refs);
+                }
+            }
         }
 
         refs
@@ -208,94 +308,147 @@ impl SymbolExtractor {
     }
 
     /// Extract call and type references from body
-    fn extract_body_refs(&self, body: &BodySection) -> (HashSet<String>, HashSet<String>) {
+    fn extract_body_refs(&self, body: &BodySection) -> (CallSites, HashSet<String>) {
         self.extract_steps_refs(&body.steps)
     }
 
-    /// Extract references from a list of steps
-    fn extract_steps_refs(&self, steps: &[Step]) -> (HashSet<String>, HashSet<String>) {
-        let mut calls = HashSet::new();
+    /// Extract references from a list of steps, including nested steps
+    ///
+    /// Uses an explicit worklist rather than recursion so deeply nested
+    /// bodies cannot overflow the stack.
+    fn extract_steps_refs(&self, steps: &[Step]) -> (CallSites, HashSet<String>) {
+        let mut calls = CallSites::new();
         let mut refs = HashSet::new();
+        let mut lambdas = HashSet::new();
 
-        for step in steps {
+        let mut pending: Vec<&Step> = steps.iter().collect();
+        while let Some(step) = pending.pop() {
             self.extract_step_refs(step, &mut calls, &mut refs);
+            if matches!(step.kind, StepKind::Lambda(_)) {
+                lambdas.insert(step.output_binding.clone());
+            }
+            for nested in step.nested_steps() {
+                pending.extend(nested);
+            }
+        }
+
+        // Calling a lambda bound in this body is not a reference to another snippet
+        calls.retain(|name, _| !lambdas.contains(name));
+        for sites in calls.values_mut() {
+            sites.sort_by_key(|site| site.span.start);
         }
 
         (calls, refs)
     }
 
-    /// Extract references from a single step
+    /// Tables read by Covenant queries against a database target
+    fn extract_query_tables(&self, steps: &[Step]) -> Vec<TableRef> {
+        let mut tables = Vec::new();
+
+        let mut pending: Vec<&Step> = steps.iter().collect();
+        while let Some(step) = pending.pop() {
+            if let StepKind::Query(query) = &step.kind {
+                if let QueryContent::Covenant(cov) = &query.content {
+                    if query.target != "project" && !cov.from.is_empty() {
+                        tables.push(TableRef {
+                            database: query.target.clone(),
+                            table: cov.from.clone(),
+                            span: query.span,
+                        });
+                    }
+                }
+            }
+            for nested in step.nested_steps() {
+                pending.extend(nested);
+            }
+        }
+
+        tables
+    }
+
+    /// Event types named by `events.publish` calls with a literal `event_type`
+    fn extract_published_events(&self, steps: &[Step]) -> HashSet<String> {
+        let mut events = HashSet::new();
+
+        let mut pending: Vec<&Step> = steps.iter().collect();
+        while let Some(step) = pending.pop() {
+            if let StepKind::Call(call) = &step.kind {
+                if call.fn_name == PUBLISH_FN {
+                    let event_type = call.args.iter().find(|a| a.name == "event_type");
+                    if let Some(InputSource::Lit(Literal::String(name))) = event_type.map(|a| &a.source) {
+                        events.insert(name.clone());
+                    }
+                }
+            }
+            for nested in step.nested_steps() {
+                pending.extend(nested);
+            }
+        }
+
+        events
+    }
+
+    /// Extract references introduced by a single step (not its nested steps)
     fn extract_step_refs(
         &self,
         step: &Step,
-        calls: &mut HashSet<String>,
+        calls: &mut CallSites,
         refs: &mut HashSet<String>,
     ) {
+        let mut call_at = |name: &str, span| {
+            calls.entry(name.to_string()).or_default().push(CallSite { step: Some(step.id.clone()), span });
+        };
         match &step.kind {
             StepKind::Call(call) => {
-                calls.insert(call.fn_name.clone());
-                // Check handle block for nested calls
+                call_at(&call.fn_name, step.span);
+                for ty in &call.type_args {
+                    self.collect_type_refs(ty, refs);
+                }
                 if let Some(handle) = &call.handle {
                     for case in &handle.cases {
                         // Handle case error_type may be a type reference
                         if !is_primitive_type(&case.error_type) {
                             refs.insert(case.error_type.clone());
                         }
-                        let (nested_calls, nested_refs) = self.extract_steps_refs(&case.steps);
-                        calls.extend(nested_calls);
-                        refs.extend(nested_refs);
                     }
                 }
             }
-            StepKind::If(if_step) => {
-                let (then_calls, then_refs) = self.extract_steps_refs(&if_step.then_steps);
-                calls.extend(then_calls);
-                refs.extend(then_refs);
-
-                if let Some(else_steps) = &if_step.else_steps {
-                    let (else_calls, else_refs) = self.extract_steps_refs(else_steps);
-                    calls.extend(else_calls);
-                    refs.extend(else_refs);
-                }
-            }
-            StepKind::Match(match_step) => {
-                for case in &match_step.cases {
-                    let (case_calls, case_refs) = self.extract_steps_refs(&case.steps);
-                    calls.extend(case_calls);
-                    refs.extend(case_refs);
+            StepKind::Pipeline(pipeline) => {
+                for stage in &pipeline.stages {
+                    call_at(&stage.call.fn_name, stage.span);
+                    for ty in &stage.call.type_args {
+                        self.collect_type_refs(ty, refs);
+                    }
                 }
             }
-            StepKind::For(for_step) => {
-                let (body_calls, body_refs) = self.extract_steps_refs(&for_step.steps);
-                calls.extend(body_calls);
-                refs.extend(body_refs);
+            StepKind::Using(using) => {
+                // Body steps are visited by extract_steps_refs
+                call_at(&using.acquire.fn_name, step.span);
+                call_at(&using.release, step.span);
             }
             StepKind::Transaction(tx) => {
-                let (tx_calls, tx_refs) = self.extract_steps_refs(&tx.steps);
-                calls.extend(tx_calls);
-                refs.extend(tx_refs);
+                // Transaction target is a database binding reference
+                if let Some(target) = &tx.target {
+                    refs.insert(target.clone());
+                }
             }
+            // Nested steps are visited by extract_steps_refs
+            StepKind::If(_)
+            | StepKind::Match(_)
+            | StepKind::For(_)
+            | StepKind::Parallel(_)
+            | StepKind::Race(_) => {}
             StepKind::Return(ret) => {
                 // Check for struct/variant type references in return
                 match &ret.value {
                     ReturnValue::Struct(s) => {
                         self.collect_type_refs(&s.ty, refs);
                     }
-                    ReturnValue::Variant(v) => {
-                        // Extract type from variant name (e.g., "ParseError::MissingField")
-                        // or just use the full type name
-                        if !is_primitive_type(&v.ty) {
-                            // Try to extract the enum name from variant path
-                            if let Some(type_name) = v.ty.split("::").next() {
-                                if !is_primitive_type(type_name) {
-                                    refs.insert(type_name.to_string());
-                                }
-                            }
-                        }
-                    }
+                    ReturnValue::Variant(v) => collect_variant_refs(&v.ty, refs),
                     _ => {}
                 }
             }
+            StepKind::Raise(raise) => collect_variant_refs(&raise.variant.ty, refs),
             StepKind::Query(query) => {
                 // Query target might be a database binding reference
                 if !query.target.is_empty() && query.target != "project" {
@@ -327,24 +480,17 @@ impl SymbolExtractor {
                 // Struct construction references the struct type
                 self.collect_type_refs(&construct.ty, refs);
             }
-            // Compute and Bind don't introduce new calls or type refs
-            StepKind::Compute(_) | StepKind::Bind(_) => {}
-            StepKind::Parallel(parallel) => {
-                // Recurse into branches
-                for branch in &parallel.branches {
-                    let (branch_calls, branch_refs) = self.extract_steps_refs(&branch.steps);
-                    calls.extend(branch_calls);
-                    refs.extend(branch_refs);
+            StepKind::Lambda(lambda) => {
+                for param in &lambda.params {
+                    self.collect_type_refs(&param.ty, refs);
                 }
-            }
-            StepKind::Race(race) => {
-                // Recurse into branches
-                for branch in &race.branches {
-                    let (branch_calls, branch_refs) = self.extract_steps_refs(&branch.steps);
-                    calls.extend(branch_calls);
-                    refs.extend(branch_refs);
+                if let Some(ret) = &lambda.returns {
+                    self.collect_type_refs(ret, refs);
                 }
             }
+            // Compute, Bind, assert and loop control don't introduce new calls or type refs
+            StepKind::Compute(_) | StepKind::Bind(_) | StepKind::Break(_) | StepKind::Continue(_)
+            | StepKind::Assert(_) => {}
         }
     }
 
@@ -369,11 +515,35 @@ impl Default for SymbolExtractor {
     }
 }
 
+/// Call sites by callee name
+type CallSites = BTreeMap<String, Vec<CallSite>>;
+
+/// Add `calls` to the symbol's calls and their sites
+fn add_calls(symbol: &mut SymbolInfo, calls: CallSites) {
+    for (name, sites) in calls {
+        symbol.calls.insert(name.clone());
+        symbol.call_sites.entry(name).or_default().extend(sites);
+    }
+}
+
+/// Runtime event bus contract that publishes an event to its subscribers
+const PUBLISH_FN: &str = "events.publish";
+
 /// Check if a type name is a primitive
 fn is_primitive_type(name: &str) -> bool {
     matches!(name, "Int" | "Float" | "Bool" | "String" | "None" | "Void")
 }
 
+/// Reference the type a variant belongs to: the enum of "ParseError::MissingField",
+/// or the whole name when it has no enum path
+fn collect_variant_refs(variant: &str, refs: &mut HashSet<String>) {
+    if let Some(type_name) = variant.split("::").next() {
+        if !is_primitive_type(type_name) {
+            refs.insert(type_name.to_string());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,7 @@
 //! Symbol graph data structure
 
-use crate::{SymbolError, SymbolId, SymbolInfo, SymbolKind};
+use crate::{CycleMode, SymbolError, SymbolId, SymbolInfo, SymbolKind};
+use covenant_ast::{decode_binary, encode_binary, BinaryArtifact, SchemaError, SYMBOL_GRAPH_VERSION};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -18,7 +19,8 @@ pub struct InvariantStatus {
 /// The complete symbol graph for a program
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct SymbolGraph {
-    /// All symbols indexed by numeric ID
+    /// All symbols indexed by numeric ID. A slot whose name no longer maps
+    /// to it in `by_name` held a symbol removed by `apply_change`.
     symbols: Vec<SymbolInfo>,
 
     /// Name to ID mapping for lookup
@@ -26,6 +28,10 @@ pub struct SymbolGraph {
 
     /// Validated invariants
     pub invariants: InvariantStatus,
+
+    /// How the graph was checked for cycles, and how changes are
+    #[serde(default)]
+    pub cycle_mode: CycleMode,
 }
 
 impl SymbolGraph {
@@ -34,6 +40,16 @@ impl SymbolGraph {
         Self::default()
     }
 
+    /// Serialize in the compact binary form, for caches
+    pub fn to_binary(&self) -> Vec<u8> {
+        encode_binary(BinaryArtifact::SymbolGraph, SYMBOL_GRAPH_VERSION, self)
+    }
+
+    /// Load a graph written by [`SymbolGraph::to_binary`]
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, SchemaError> {
+        decode_binary(BinaryArtifact::SymbolGraph, SYMBOL_GRAPH_VERSION, bytes)
+    }
+
     /// Get a symbol by name
     pub fn get_by_name(&self, name: &str) -> Option<&SymbolInfo> {
         self.by_name.get(name).map(|id| &self.symbols[id.0 as usize])
@@ -48,12 +64,20 @@ impl SymbolGraph {
 
     /// Get a symbol by ID
     pub fn get(&self, id: SymbolId) -> Option<&SymbolInfo> {
-        self.symbols.get(id.0 as usize)
+        self.symbols.get(id.0 as usize).filter(|s| self.is_live(s))
     }
 
     /// Get a mutable symbol by ID
     pub fn get_mut(&mut self, id: SymbolId) -> Option<&mut SymbolInfo> {
-        self.symbols.get_mut(id.0 as usize)
+        let by_name = &self.by_name;
+        self.symbols
+            .get_mut(id.0 as usize)
+            .filter(|s| by_name.get(&s.name) == Some(&s.id))
+    }
+
+    /// Whether a slot holds a symbol still in the graph
+    fn is_live(&self, symbol: &SymbolInfo) -> bool {
+        self.by_name.get(&symbol.name) == Some(&symbol.id)
     }
 
     /// Check if a symbol exists by name
@@ -66,6 +90,44 @@ impl SymbolGraph {
         self.by_name.get(name).copied()
     }
 
+    /// Resolve a type name to a symbol ID
+    ///
+    /// Type references in signatures usually use the short name ("User")
+    /// while the defining snippet has a qualified ID ("types.User"). An exact
+    /// ID match wins; otherwise the name resolves if exactly one type
+    /// symbol's last segment matches it.
+    pub fn resolve_type(&self, name: &str) -> Option<SymbolId> {
+        if let Some(id) = self.id_of(name) {
+            return Some(id);
+        }
+
+        let mut candidates = self
+            .types()
+            .filter(|s| s.name.rsplit('.').next() == Some(name));
+        match (candidates.next(), candidates.next()) {
+            (Some(only), None) => Some(only.id),
+            _ => None,
+        }
+    }
+
+    /// Resolve a database name (e.g., a query's `target="app_db"`) to a symbol
+    ///
+    /// Same rules as `resolve_type`, restricted to database snippets.
+    pub fn resolve_database(&self, name: &str) -> Option<SymbolId> {
+        if let Some(id) = self.id_of(name) {
+            return Some(id);
+        }
+
+        let mut candidates = self
+            .iter()
+            .filter(|s| s.kind == SymbolKind::Database)
+            .filter(|s| s.name.rsplit('.').next() == Some(name));
+        match (candidates.next(), candidates.next()) {
+            (Some(only), None) => Some(only.id),
+            _ => None,
+        }
+    }
+
     /// Insert a new symbol, returning error if duplicate
     pub fn insert(&mut self, mut symbol: SymbolInfo) -> Result<SymbolId, SymbolError> {
         if self.by_name.contains_key(&symbol.name) {
@@ -82,43 +144,60 @@ impl SymbolGraph {
         Ok(id)
     }
 
+    /// Replace the symbol at `id`, keeping its ID; the name must not change
+    pub(crate) fn replace(&mut self, id: SymbolId, mut symbol: SymbolInfo) {
+        debug_assert_eq!(self.id_of(&symbol.name), Some(id));
+        symbol.id = id;
+        self.symbols[id.0 as usize] = symbol;
+    }
+
+    /// Remove the symbol at `id`. Its slot stays empty, so the IDs of the
+    /// remaining symbols do not change.
+    pub(crate) fn remove(&mut self, id: SymbolId) -> Option<SymbolInfo> {
+        let symbol = self.get(id)?;
+        let mut tombstone = SymbolInfo::new(symbol.name.clone(), symbol.kind, symbol.span);
+        tombstone.id = id;
+        self.by_name.remove(&tombstone.name);
+        Some(std::mem::replace(&mut self.symbols[id.0 as usize], tombstone))
+    }
+
     /// Iterate over all symbols
     pub fn iter(&self) -> impl Iterator<Item = &SymbolInfo> {
-        self.symbols.iter()
+        self.symbols.iter().filter(|s| self.is_live(s))
     }
 
     /// Iterate over all symbols mutably
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut SymbolInfo> {
-        self.symbols.iter_mut()
+        let by_name = &self.by_name;
+        self.symbols
+            .iter_mut()
+            .filter(move |s| by_name.get(&s.name) == Some(&s.id))
     }
 
     /// Get the number of symbols in the graph
     pub fn len(&self) -> usize {
-        self.symbols.len()
+        self.by_name.len()
     }
 
     /// Check if the graph is empty
     pub fn is_empty(&self) -> bool {
-        self.symbols.is_empty()
+        self.by_name.is_empty()
     }
 
     /// Get all function symbols
     pub fn functions(&self) -> impl Iterator<Item = &SymbolInfo> {
-        self.symbols
-            .iter()
-            .filter(|s| s.kind == SymbolKind::Function)
+        self.iter()
+            .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Workflow))
     }
 
-    /// Get all type symbols (Struct, Enum)
+    /// Get all type symbols (Struct, Enum, Alias)
     pub fn types(&self) -> impl Iterator<Item = &SymbolInfo> {
-        self.symbols
-            .iter()
-            .filter(|s| matches!(s.kind, SymbolKind::Struct | SymbolKind::Enum))
+        self.iter().filter(|s| s.is_type())
     }
 
     /// Get all extern symbols
     pub fn externs(&self) -> impl Iterator<Item = &SymbolInfo> {
-        self.symbols.iter().filter(|s| s.kind == SymbolKind::Extern)
+        self.iter().filter(|s| s.kind == SymbolKind::Extern)
     }
 
     /// Get callers of a symbol (by name)
@@ -140,6 +219,42 @@ impl SymbolGraph {
             .unwrap_or_default()
     }
 
+    /// Get subscribers notified by events a symbol publishes (by name)
+    pub fn subscribers_of(&self, name: &str) -> Vec<String> {
+        self.get_by_name(name)
+            .map(|s| {
+                s.notifies
+                    .iter()
+                    .filter_map(|id| self.get(*id).map(|s| s.name.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get publishers of the event a subscriber listens for (by name)
+    pub fn publishers_of(&self, name: &str) -> Vec<String> {
+        self.get_by_name(name)
+            .map(|s| {
+                s.notified_by
+                    .iter()
+                    .filter_map(|id| self.get(*id).map(|s| s.name.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the fn and struct snippets implementing an interface (by name)
+    pub fn implementations_of(&self, name: &str) -> Vec<String> {
+        self.get_by_name(name)
+            .map(|s| {
+                s.implemented_by
+                    .iter()
+                    .filter_map(|id| self.get(*id).map(|s| s.name.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Get all symbol names
     pub fn names(&self) -> impl Iterator<Item = &String> {
         self.by_name.keys()
@@ -147,7 +262,7 @@ impl SymbolGraph {
 
     /// Collect all symbols with unresolved references
     pub fn unresolved_symbols(&self) -> Vec<&SymbolInfo> {
-        self.symbols.iter().filter(|s| s.has_unresolved()).collect()
+        self.iter().filter(|s| s.has_unresolved()).collect()
     }
 }
 
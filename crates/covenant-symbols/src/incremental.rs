@@ -0,0 +1,411 @@
+//! Incremental updates and priority-ordered re-checking
+//!
+//! [`SymbolGraph::apply_change`] updates a graph for added, removed and
+//! modified snippets without rebuilding it. The affected region is the
+//! changed symbols plus every symbol that mentions one of their names, or
+//! the short name of one (a reference to `User` may resolve to a changed
+//! `types.User`, or stop resolving once a second `User` exists). Only the
+//! region's references are unlinked, re-resolved and re-validated; the rest
+//! of the graph, including the IDs of unchanged symbols, is left alone.
+//!
+//! When snippets change, the changed snippets are re-checked first, then
+//! their dependents in order of distance: direct callers and type users,
+//! then their callers, and so on. Consumers (watch mode, editors) can
+//! report diagnostics batch by batch so feedback for the edited code is not
+//! held back by re-checking the rest of the project.
+
+use crate::cycle::CycleDetector;
+use crate::extractor::SymbolExtractor;
+use crate::resolver::BackwardResolver;
+use crate::{
+    validate_bidirectionality, validate_relation_bidirectionality, SymbolError, SymbolGraph, SymbolId, SymbolInfo,
+};
+use covenant_ast::Snippet;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// The outcome of [`SymbolGraph::apply_change`]
+#[derive(Debug, Default)]
+pub struct ChangeResult {
+    /// Symbols whose references were re-resolved, sorted. Deferred errors
+    /// previously reported for them are replaced by `deferred_errors`.
+    pub resolved: Vec<String>,
+    /// Soft errors raised while re-resolving (undefined references)
+    pub deferred_errors: Vec<SymbolError>,
+}
+
+/// Snippets to re-check, grouped by priority
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecheckPlan {
+    /// Batch 0 holds the changed snippets; batch `n` holds dependents at
+    /// distance `n`. Names within a batch are sorted.
+    pub batches: Vec<Vec<String>>,
+}
+
+impl RecheckPlan {
+    /// Total number of snippets in the plan
+    pub fn len(&self) -> usize {
+        self.batches.iter().map(Vec::len).sum()
+    }
+
+    /// Whether nothing needs re-checking
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All snippet names in priority order
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.batches.iter().flatten()
+    }
+}
+
+/// Snippet IDs that were added, removed, or modified between two versions
+///
+/// Snippets are compared by their content hashes, so edits that only move
+/// a snippet within the file (changing its span) are ignored.
+pub fn changed_snippets(old: &[Snippet], new: &[Snippet]) -> Vec<String> {
+    let fingerprint = |snippets: &[Snippet]| -> HashMap<String, String> {
+        snippets
+            .iter()
+            .map(|s| (s.id.clone(), s.content_hash()))
+            .collect()
+    };
+    let old = fingerprint(old);
+    let new = fingerprint(new);
+
+    let mut changed: BTreeSet<String> = BTreeSet::new();
+    for (id, text) in &new {
+        if old.get(id) != Some(text) {
+            changed.insert(id.clone());
+        }
+    }
+    for id in old.keys() {
+        if !new.contains_key(id) {
+            changed.insert(id.clone());
+        }
+    }
+    changed.into_iter().collect()
+}
+
+impl SymbolGraph {
+    /// Plan a priority-ordered re-check after `changed` snippets were edited
+    ///
+    /// Names not present in the graph (removed snippets) are not re-checked
+    /// themselves, but the symbols that still refer to them are.
+    pub fn recheck_plan(&self, changed: &[String]) -> RecheckPlan {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut batches = Vec::new();
+
+        let mut first: Vec<String> = changed
+            .iter()
+            .filter(|name| self.contains(name))
+            .cloned()
+            .collect();
+        first.sort();
+        first.dedup();
+        seen.extend(changed.iter().cloned());
+
+        let mut frontier: Vec<String> = changed.to_vec();
+        if !first.is_empty() {
+            batches.push(first);
+        }
+
+        while !frontier.is_empty() {
+            let mut next: BTreeSet<String> = BTreeSet::new();
+            for name in &frontier {
+                for dependent in self.dependents_of(name) {
+                    if !seen.contains(&dependent) {
+                        next.insert(dependent);
+                    }
+                }
+            }
+
+            if next.is_empty() {
+                break;
+            }
+            if batches.is_empty() {
+                // Only removed snippets changed; their dependents come first
+                batches.push(Vec::new());
+            }
+            seen.extend(next.iter().cloned());
+            frontier = next.iter().cloned().collect();
+            batches.push(next.into_iter().collect());
+        }
+
+        RecheckPlan { batches }
+    }
+
+    /// Symbols that directly depend on `name` (callers, type users, embedders)
+    pub fn dependents_of(&self, name: &str) -> Vec<String> {
+        match self.get_by_name(name) {
+            Some(symbol) => symbol
+                .called_by
+                .iter()
+                .chain(&symbol.referenced_by)
+                .chain(&symbol.embedded_by)
+                .filter(|id| **id != symbol.id)
+                .filter_map(|id| self.get(*id).map(|s| s.name.clone()))
+                .collect(),
+            // Removed symbol: find whoever still mentions it
+            None => self
+                .iter()
+                .filter(|s| {
+                    s.calls.contains(name)
+                        || s.references.contains(name)
+                        || s.unresolved_calls.contains(name)
+                        || s.unresolved_references.contains(name)
+                })
+                .map(|s| s.name.clone())
+                .collect(),
+        }
+    }
+}
+
+impl SymbolGraph {
+    /// Update the graph for an edit: `added` snippets are inserted,
+    /// `removed` symbols dropped and `modified` snippets replace the symbols
+    /// with their IDs (a modified snippet not in the graph is added).
+    ///
+    /// Backward references and invariants are recomputed only for the
+    /// affected region (see the module docs). Removed symbols leave their
+    /// slot empty, so the IDs of the others stay valid.
+    ///
+    /// Returns the hard errors, as [`crate::build_from_snippets`] would, if
+    /// the edit is invalid. An added ID already in the graph is rejected
+    /// before anything changes; after any other hard error, such as a new
+    /// call cycle, the graph holds the edit with the broken invariant
+    /// marked, and should be rebuilt once the source is fixed.
+    pub fn apply_change(
+        &mut self,
+        added: &[Snippet],
+        removed: &[SymbolId],
+        modified: &[Snippet],
+    ) -> Result<ChangeResult, Vec<SymbolError>> {
+        let removed: BTreeSet<SymbolId> = removed.iter().copied().filter(|id| self.get(*id).is_some()).collect();
+        let removed_names: HashSet<String> = removed.iter().filter_map(|id| self.get(*id)).map(|s| s.name.clone()).collect();
+        let (replaced, inserted): (Vec<&Snippet>, Vec<&Snippet>) = modified
+            .iter()
+            .partition(|s| self.contains(&s.id) && !removed_names.contains(&s.id));
+        let inserted: Vec<&Snippet> = added.iter().chain(inserted).collect();
+
+        let mut seen: HashSet<&str> = HashSet::new();
+        let duplicates: Vec<SymbolError> = inserted
+            .iter()
+            .chain(&replaced)
+            .filter(|s| {
+                let taken = self.contains(&s.id) && !removed_names.contains(&s.id) && !replaced.iter().any(|r| r.id == s.id);
+                !seen.insert(s.id.as_str()) || taken
+            })
+            .map(|s| SymbolError::DuplicateId { id: s.id.clone(), span: s.span })
+            .collect();
+        if !duplicates.is_empty() {
+            return Err(duplicates);
+        }
+
+        let extractor = SymbolExtractor::new();
+        let replaced: Vec<SymbolInfo> = replaced.iter().map(|s| extractor.extract_snippet(s)).collect();
+        let inserted: Vec<SymbolInfo> = inserted.iter().map(|s| extractor.extract_snippet(s)).collect();
+
+        // The region, found before the edit so its old references unlink
+        // against the targets they resolved to
+        let names: HashSet<&str> = removed_names
+            .iter()
+            .map(String::as_str)
+            .chain(replaced.iter().chain(&inserted).map(|s| s.name.as_str()))
+            .collect();
+        let short_names: HashSet<&str> = names.iter().filter_map(|name| name.rsplit('.').next()).collect();
+        let mut region: BTreeSet<SymbolId> = self
+            .iter()
+            .filter(|s| names.contains(s.name.as_str()) || mentions(s, &names, &short_names))
+            .map(|s| s.id)
+            .collect();
+        // Modules sharing a member with a region module decide together
+        // which one owns it
+        let members: HashSet<&str> = region
+            .iter()
+            .filter_map(|id| self.get(*id))
+            .chain(replaced.iter().chain(&inserted))
+            .flat_map(|s| s.members.iter().map(String::as_str))
+            .collect();
+        if !members.is_empty() {
+            region.extend(
+                self.iter()
+                    .filter(|s| s.members.iter().any(|m| members.contains(m.as_str())))
+                    .map(|s| s.id),
+            );
+        }
+        let old_region: Vec<SymbolId> = region.iter().copied().collect();
+        BackwardResolver::unlink(self, &old_region);
+
+        for id in &removed {
+            self.remove(*id);
+            region.remove(id);
+        }
+        let mut changed = Vec::with_capacity(replaced.len() + inserted.len());
+        for symbol in replaced {
+            let Some(id) = self.id_of(&symbol.name) else { continue };
+            self.replace(id, symbol);
+            changed.push(id);
+        }
+        for symbol in inserted {
+            if let Ok(id) = self.insert(symbol) {
+                changed.push(id);
+            }
+        }
+        region.extend(changed.iter().copied());
+
+        let region: Vec<SymbolId> = region.into_iter().collect();
+        let errors = BackwardResolver::link(self, &region);
+
+        let cycle_errors = CycleDetector::detect(self, &changed, self.cycle_mode);
+        let symbols = || region.iter().filter_map(|id| self.get(*id));
+        let bidirectional = validate_bidirectionality(self, symbols());
+        let relations_bidirectional = validate_relation_bidirectionality(self, symbols());
+        self.invariants.i1_bidirectionality &= bidirectional;
+        self.invariants.i5_relation_bidirectionality &= relations_bidirectional;
+        if !cycle_errors.is_empty() {
+            self.invariants.i4_acyclicity = false;
+            return Err(cycle_errors);
+        }
+
+        let (deferred, hard): (Vec<_>, Vec<_>) = errors.into_iter().partition(|e| !e.is_hard_error());
+        if !hard.is_empty() {
+            return Err(hard);
+        }
+
+        let mut resolved: Vec<String> = region.iter().filter_map(|id| self.get(*id)).map(|s| s.name.clone()).collect();
+        resolved.sort();
+        Ok(ChangeResult { resolved, deferred_errors: deferred })
+    }
+}
+
+/// Whether any of `symbol`'s forward references may resolve to one of
+/// `names`. Type and database references also match by short name.
+fn mentions(symbol: &SymbolInfo, names: &HashSet<&str>, short_names: &HashSet<&str>) -> bool {
+    let exact = |name: &String| names.contains(name.as_str());
+    let typed = |name: &String| names.contains(name.as_str()) || short_names.contains(name.as_str());
+    symbol.calls.iter().any(exact)
+        || symbol.references.iter().any(typed)
+        || symbol.embeds.iter().any(typed)
+        || symbol.publishes.iter().any(typed)
+        || symbol.subscribes.as_ref().is_some_and(typed)
+        || symbol.implements.as_ref().is_some_and(exact)
+        || symbol.relations_to.iter().any(|r| exact(&r.target))
+        || symbol.members.iter().any(exact)
+        || symbol.table_refs.iter().any(|t| typed(&t.database))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_symbol_graph;
+    use crate::test_support::{func, func_returning, snippets};
+
+    #[test]
+    fn test_changed_snippets_detects_edits_and_removals() {
+        let old = snippets(&[func("a", &[]), func("b", &[]), func("c", &[])].concat());
+        let new = snippets(&[func("b", &[]), func_returning("a", &[], 9), func("d", &[])].concat());
+
+        assert_eq!(changed_snippets(&old, &new), vec!["a", "c", "d"]);
+    }
+
+    #[test]
+    fn test_recheck_plan_orders_by_distance() {
+        let source = [
+            func("leaf", &[]),
+            func("mid", &["leaf"]),
+            func("top", &["mid"]),
+            func("other", &[]),
+        ]
+        .concat();
+        let program = covenant_parser::parse(&source).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+
+        let plan = graph.recheck_plan(&["leaf".to_string()]);
+        assert_eq!(plan.batches, vec![vec!["leaf"], vec!["mid"], vec!["top"]]);
+        assert_eq!(plan.len(), 3);
+    }
+
+    #[test]
+    fn test_recheck_plan_for_removed_snippet() {
+        let source = [func("caller", &["gone"]), func("top", &["caller"])].concat();
+        let program = covenant_parser::parse(&source).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+
+        let plan = graph.recheck_plan(&["gone".to_string()]);
+        assert_eq!(plan.batches, vec![vec![], vec!["caller"], vec!["top"]]);
+    }
+
+    fn callers(graph: &SymbolGraph, name: &str) -> Vec<String> {
+        let mut callers = graph.callers_of(name);
+        callers.sort();
+        callers
+    }
+
+    #[test]
+    fn test_apply_change_resolves_added_callee() {
+        let mut graph = build_symbol_graph(&covenant_parser::parse(&func("caller", &["helper"])).unwrap())
+            .unwrap()
+            .graph;
+        assert_eq!(graph.unresolved_symbols().len(), 1);
+
+        let result = graph.apply_change(&snippets(&func("helper", &[])), &[], &[]).unwrap();
+
+        assert_eq!(result.resolved, vec!["caller", "helper"]);
+        assert!(result.deferred_errors.is_empty());
+        assert_eq!(callers(&graph, "helper"), vec!["caller"]);
+        assert!(graph.unresolved_symbols().is_empty());
+        assert!(graph.invariants.i1_bidirectionality);
+    }
+
+    #[test]
+    fn test_apply_change_removal_keeps_ids() {
+        let source = [func("helper", &[]), func("caller", &["helper"]), func("other", &[])].concat();
+        let mut graph = build_symbol_graph(&covenant_parser::parse(&source).unwrap()).unwrap().graph;
+        let helper = graph.id_of("helper").unwrap();
+        let other = graph.id_of("other").unwrap();
+
+        let result = graph.apply_change(&[], &[helper], &[]).unwrap();
+
+        assert_eq!(result.resolved, vec!["caller"]);
+        assert!(matches!(
+            result.deferred_errors.as_slice(),
+            [SymbolError::UndefinedReference { name, referrer, .. }] if name == "helper" && referrer == "caller"
+        ));
+        assert_eq!(graph.len(), 2);
+        assert!(graph.get(helper).is_none());
+        assert_eq!(graph.id_of("other"), Some(other));
+        assert!(graph.get_by_name("caller").unwrap().unresolved_calls.contains("helper"));
+    }
+
+    #[test]
+    fn test_apply_change_matches_rebuild() {
+        let before = [func("a", &[]), func("b", &["a"]), func("c", &["b"])].concat();
+        let after = [func("a", &[]), func("b", &[]), func("c", &["a"]), func("d", &["c"])].concat();
+        let mut graph = build_symbol_graph(&covenant_parser::parse(&before).unwrap()).unwrap().graph;
+
+        let modified = snippets(&[func("b", &[]), func("c", &["a"])].concat());
+        graph.apply_change(&snippets(&func("d", &["c"])), &[], &modified).unwrap();
+
+        let rebuilt = build_symbol_graph(&covenant_parser::parse(&after).unwrap()).unwrap().graph;
+        for name in ["a", "b", "c", "d"] {
+            assert_eq!(callers(&graph, name), callers(&rebuilt, name), "callers of {name}");
+            assert_eq!(graph.callees_of(name), rebuilt.callees_of(name), "callees of {name}");
+        }
+        assert!(graph.invariants.i1_bidirectionality);
+    }
+
+    #[test]
+    fn test_apply_change_rejects_duplicate_and_cycle() {
+        let mut graph = build_symbol_graph(&covenant_parser::parse(&func("a", &["b"])).unwrap())
+            .unwrap()
+            .graph;
+
+        let errors = graph.apply_change(&snippets(&func("a", &[])), &[], &[]).unwrap_err();
+        assert!(matches!(errors.as_slice(), [SymbolError::DuplicateId { id, .. }] if id == "a"));
+        assert_eq!(graph.len(), 1);
+
+        let errors = graph.apply_change(&snippets(&func("b", &["a"])), &[], &[]).unwrap_err();
+        assert!(matches!(errors.as_slice(), [SymbolError::CircularImport { .. }]));
+        assert!(!graph.invariants.i4_acyclicity);
+    }
+}
@@ -29,18 +29,40 @@
 //! assert!(result.graph.contains("math.add"));
 //! ```
 
+mod closure;
 mod cycle;
+mod diagram;
 mod error;
+mod export;
 mod extractor;
 mod graph;
+mod incremental;
+mod modules;
+mod paths;
+mod project;
+mod references;
+mod rename;
 mod resolver;
+mod search;
 mod symbol;
+#[cfg(test)]
+mod test_support;
+mod type_deps;
 
+pub use cycle::{CycleMode, CYCLE_GROUP_KEY};
+pub use diagram::DiagramOptions;
 pub use error::SymbolError;
 pub use graph::{InvariantStatus, SymbolGraph, SymbolResult};
-pub use symbol::{RelationRef, SymbolId, SymbolInfo, SymbolKind};
-
-use covenant_ast::{Program, Snippet};
+pub use incremental::{changed_snippets, ChangeResult, RecheckPlan};
+pub use modules::{module_of, ModuleGraph, ModuleNode, ROOT_MODULE};
+pub use project::{FileId, Project, ProjectError, ProjectSymbols};
+pub use references::{Reference, ReferenceKind};
+pub use rename::{rename, RenameError, TextEdit};
+pub use search::{MatchKind, SearchMatch};
+pub use symbol::{RelationRef, SymbolId, SymbolInfo, SymbolKind, TableRef};
+pub use type_deps::TypeOrder;
+
+use covenant_ast::{CancellationToken, Program, Snippet};
 use cycle::CycleDetector;
 use extractor::SymbolExtractor;
 use resolver::BackwardResolver;
@@ -72,8 +94,46 @@ pub fn build_symbol_graph(program: &Program) -> Result<SymbolResult, Vec<SymbolE
 
 /// Build a symbol graph directly from snippets
 pub fn build_from_snippets(snippets: &[Snippet]) -> Result<SymbolResult, Vec<SymbolError>> {
+    build(snippets, None, CycleMode::Reject)
+}
+
+/// Build a symbol graph from snippets, stopping early with
+/// `SymbolError::Cancelled` if `token` is cancelled
+pub fn build_from_snippets_with_cancel(
+    snippets: &[Snippet],
+    token: &CancellationToken,
+) -> Result<SymbolResult, Vec<SymbolError>> {
+    build(snippets, Some(token), CycleMode::Reject)
+}
+
+/// Build a symbol graph from snippets, treating cycles per `cycles`
+///
+/// With [`CycleMode::AnnotatedGroups`], functions that all carry the same
+/// `allow cycle group=` annotation may call each other recursively. The
+/// graph keeps the mode, so later incremental changes are checked the same
+/// way.
+pub fn build_from_snippets_with_cycle_mode(
+    snippets: &[Snippet],
+    cycles: CycleMode,
+) -> Result<SymbolResult, Vec<SymbolError>> {
+    build(snippets, None, cycles)
+}
+
+fn build(
+    snippets: &[Snippet],
+    cancel: Option<&CancellationToken>,
+    cycles: CycleMode,
+) -> Result<SymbolResult, Vec<SymbolError>> {
+    let check_cancelled = || match cancel {
+        Some(token) if token.is_cancelled() => Err(vec![SymbolError::Cancelled]),
+        _ => Ok(()),
+    };
+
     // Pass 1: Extract symbols and forward references
-    let extractor = SymbolExtractor::new();
+    let mut extractor = SymbolExtractor::new();
+    if let Some(token) = cancel {
+        extractor = extractor.with_cancellation(token.clone());
+    }
     let (mut graph, mut all_errors) = extractor.extract(snippets);
 
     // Check for hard errors from extraction (duplicate IDs)
@@ -87,26 +147,32 @@ pub fn build_from_snippets(snippets: &[Snippet]) -> Result<SymbolResult, Vec<Sym
     }
 
     // Pass 2: Compute backward references
+    check_cancelled()?;
     let resolution_errors = BackwardResolver::resolve(&mut graph);
     all_errors.extend(resolution_errors);
 
     // Validate I4: Acyclicity
-    let cycle_errors = CycleDetector::detect_cycles(&graph);
+    check_cancelled()?;
+    graph.cycle_mode = cycles;
+    let cycle_errors = match cycles {
+        CycleMode::Reject => CycleDetector::detect_cycles(&graph),
+        CycleMode::AnnotatedGroups => CycleDetector::detect_unannotated_cycles(&graph),
+    };
     if !cycle_errors.is_empty() {
         // Cycles are hard errors
         return Err(cycle_errors);
     }
 
     // Validate I1: Bidirectionality (should always pass if resolver works correctly)
-    let i1_valid = validate_bidirectionality(&graph);
+    let i1_valid = validate_bidirectionality(&graph, graph.iter());
 
     // Validate I5: Relation bidirectionality
-    let i5_valid = validate_relation_bidirectionality(&graph);
+    let i5_valid = validate_relation_bidirectionality(&graph, graph.iter());
 
     // Update invariant status
     graph.invariants = InvariantStatus {
         i1_bidirectionality: i1_valid,
-        i4_acyclicity: true, // Passed if we got here
+        i4_acyclicity: true, // Passed if we got here (annotated cycle groups excepted)
         i5_relation_bidirectionality: i5_valid,
     };
 
@@ -124,9 +190,12 @@ pub fn build_from_snippets(snippets: &[Snippet]) -> Result<SymbolResult, Vec<Sym
     })
 }
 
-/// Validate I1: If A calls B, B's called_by includes A
-fn validate_bidirectionality(graph: &SymbolGraph) -> bool {
-    for symbol in graph.iter() {
+/// Validate I1 for `symbols`: If A calls B, B's called_by includes A
+pub(crate) fn validate_bidirectionality<'a>(
+    graph: &SymbolGraph,
+    symbols: impl IntoIterator<Item = &'a SymbolInfo>,
+) -> bool {
+    for symbol in symbols {
         for callee_name in &symbol.calls {
             if let Some(callee) = graph.get_by_name(callee_name) {
                 if !callee.called_by.contains(&symbol.id) {
@@ -139,9 +208,12 @@ fn validate_bidirectionality(graph: &SymbolGraph) -> bool {
     true
 }
 
-/// Validate I5: Relations have proper inverses
-fn validate_relation_bidirectionality(graph: &SymbolGraph) -> bool {
-    for symbol in graph.iter() {
+/// Validate I5 for `symbols`: Relations have proper inverses
+pub(crate) fn validate_relation_bidirectionality<'a>(
+    graph: &SymbolGraph,
+    symbols: impl IntoIterator<Item = &'a SymbolInfo>,
+) -> bool {
+    for symbol in symbols {
         for rel in &symbol.relations_to {
             if let Some(target) = graph.get_by_name(&rel.target) {
                 let has_inverse = target
@@ -179,9 +251,11 @@ mod tests {
             id: "test.foo".into(),
             kind: covenant_ast::SnippetKind::Function,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -190,15 +264,37 @@ mod tests {
         assert!(result.graph.contains("test.foo"));
     }
 
+    #[test]
+    fn test_cancelled_build() {
+        let snippet = Snippet {
+            id: "test.foo".into(),
+            kind: covenant_ast::SnippetKind::Function,
+            notes: vec![],
+            doc: None,
+            sections: vec![],
+            implements: None,
+            platform: None,
+            node_id: None,
+            span: make_span(),
+        };
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let errors = build_from_snippets_with_cancel(&[snippet], &token).unwrap_err();
+        assert!(matches!(errors.as_slice(), [SymbolError::Cancelled]));
+    }
+
     #[test]
     fn test_duplicate_id_error() {
         let snippet1 = Snippet {
             id: "test.foo".into(),
             kind: covenant_ast::SnippetKind::Function,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -206,9 +302,11 @@ mod tests {
             id: "test.foo".into(), // Duplicate
             kind: covenant_ast::SnippetKind::Function,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -226,9 +324,11 @@ mod tests {
             id: "mymod.myfn".into(),
             kind: covenant_ast::SnippetKind::Function,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -245,9 +345,11 @@ mod tests {
             id: "test.fn".into(),
             kind: covenant_ast::SnippetKind::Function,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -263,9 +365,11 @@ mod tests {
             id: "types.User".into(),
             kind: covenant_ast::SnippetKind::Struct,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -280,9 +384,11 @@ mod tests {
             id: "types.Result".into(),
             kind: covenant_ast::SnippetKind::Enum,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -297,9 +403,11 @@ mod tests {
             id: "io.print".into(),
             kind: covenant_ast::SnippetKind::Extern,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -314,9 +422,11 @@ mod tests {
             id: "app.main".into(),
             kind: covenant_ast::SnippetKind::Function,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -324,9 +434,11 @@ mod tests {
             id: "types.User".into(),
             kind: covenant_ast::SnippetKind::Struct,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -334,9 +446,11 @@ mod tests {
             id: "io.print".into(),
             kind: covenant_ast::SnippetKind::Extern,
             notes: vec![],
+            doc: None,
             sections: vec![],
             implements: None,
             platform: None,
+            node_id: None,
             span: make_span(),
         };
 
@@ -357,6 +471,95 @@ mod tests {
         build_symbol_graph(&program)
     }
 
+    // === Database Schema Tests ===
+
+    fn schema_source(query_table: &str) -> String {
+        format!(
+            r#"
+snippet id="db.app_db" kind="database"
+schema
+  table name="users"
+    field name="id" type="Int" primary_key=true
+  end
+  table name="orders"
+    field name="id" type="Int" primary_key=true
+    field name="user_id" type="Int" foreign_key="users.id"
+  end
+end
+end
+
+snippet id="app.list" kind="fn"
+effects
+  effect database
+end
+signature
+  fn name="list"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="query"
+    target="app_db"
+    select all
+    from="{query_table}"
+    as="rows"
+  end
+  step id="s2" kind="return"
+    from="rows"
+    as="_"
+  end
+end
+end
+"#
+        )
+    }
+
+    #[test]
+    fn test_database_schema_tables_extracted() {
+        let result = build_graph_from_source(&schema_source("users")).unwrap();
+        let db = result.graph.get_by_name("db.app_db").unwrap();
+        assert_eq!(db.tables.keys().collect::<Vec<_>>(), vec!["orders", "users"]);
+
+        // Query target resolves to the database by its short name
+        let list_id = result.graph.id_of("app.list").unwrap();
+        assert!(db.referenced_by.contains(&list_id));
+        assert!(result.deferred_errors.is_empty(), "{:?}", result.deferred_errors);
+    }
+
+    #[test]
+    fn test_graph_binary_round_trip() {
+        let graph = build_graph_from_source(&schema_source("users")).unwrap().graph;
+        let decoded = SymbolGraph::from_binary(&graph.to_binary()).unwrap();
+
+        assert_eq!(decoded.len(), graph.len());
+        let db = decoded.get_by_name("db.app_db").unwrap();
+        assert_eq!(db.tables.keys().collect::<Vec<_>>(), vec!["orders", "users"]);
+        assert_eq!(decoded.callers_of("db.app_db"), graph.callers_of("db.app_db"));
+        assert!(decoded.id_of("app.list").is_some_and(|id| db.referenced_by.contains(&id)));
+    }
+
+    #[test]
+    fn test_query_against_undeclared_table() {
+        let errors = build_graph_from_source(&schema_source("invoices")).unwrap_err();
+        assert!(matches!(
+            errors.as_slice(),
+            [SymbolError::UnknownTable { table, database, .. }]
+                if table == "invoices" && database == "db.app_db"
+        ));
+        assert_eq!(errors[0].code(), "E-QUERY-002");
+    }
+
+    #[test]
+    fn test_foreign_key_to_undeclared_table() {
+        let source = schema_source("users").replace("foreign_key=\"users.id\"", "foreign_key=\"customers.id\"");
+        let errors = build_graph_from_source(&source).unwrap_err();
+        assert!(matches!(
+            errors.as_slice(),
+            [SymbolError::UnknownTable { table, referrer, .. }]
+                if table == "customers" && referrer == "db.app_db"
+        ));
+    }
+
     // === I1 Bidirectionality Tests ===
 
     #[test]
@@ -820,6 +1023,50 @@ end
         assert!(result.is_err(), "Mutual recursion should be detected as cycle");
     }
 
+    #[test]
+    fn test_i4_annotated_mutual_recursion_allowed() {
+        let func = |name: &str, callee: &str| {
+            format!(
+                r#"
+snippet id="{name}" kind="fn"
+metadata
+  allow cycle group="parity"
+end
+signature
+  fn name="{name}"
+    param name="n" type="Int"
+    returns type="Bool"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="{callee}"
+    arg name="n" from="n"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+"#
+            )
+        };
+        let source = func("is_even", "is_odd") + &func("is_odd", "is_even");
+        let snippets = match covenant_parser::parse(&source).expect("parse failed") {
+            Program::Snippets { snippets, .. } => snippets,
+            Program::Legacy { .. } => unreachable!(),
+        };
+
+        // Strict by default
+        assert!(build_from_snippets(&snippets).is_err());
+
+        let result = build_from_snippets_with_cycle_mode(&snippets, CycleMode::AnnotatedGroups).unwrap();
+        assert_eq!(result.graph.get_by_name("is_even").unwrap().cycle_group.as_deref(), Some("parity"));
+        assert_eq!(result.graph.cycle_mode, CycleMode::AnnotatedGroups);
+    }
+
     #[test]
     fn test_i4_no_cycle_in_dag() {
         // Diamond dependency: A -> B, A -> C, B -> D, C -> D (no cycle)
@@ -991,6 +1238,69 @@ end
         );
     }
 
+    #[test]
+    fn test_call_type_args_are_references() {
+        let source = r#"
+snippet id="types.User" kind="struct"
+signature
+  struct name="User"
+    field name="id" type="Int"
+  end
+end
+end
+
+snippet id="util.first_or" kind="fn"
+signature
+  fn name="first_or"
+    generic name="T"
+    param name="items" type="List<T>"
+    param name="fallback" type="T"
+    returns type="T"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="fallback"
+    as="_"
+  end
+end
+end
+
+snippet id="app.pick" kind="fn"
+signature
+  fn name="pick"
+    param name="users" type="List<User>"
+    param name="guest" type="User"
+    returns type="User"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="first_or"
+    type arg="User"
+    arg name="items" from="users"
+    arg name="fallback" from="guest"
+    as="user"
+  end
+  step id="s2" kind="return"
+    from="user"
+    as="_"
+  end
+end
+end
+"#;
+        let result = build_graph_from_source(source).expect("should build graph");
+
+        let first_or = result.graph.get_by_name("util.first_or").unwrap();
+        assert_eq!(first_or.generics, vec!["T".to_string()]);
+        // Generic params are not references to types
+        assert!(!first_or.references.contains("T"));
+
+        let pick = result.graph.get_by_name("app.pick").unwrap();
+        assert!(pick.references.contains("User"));
+        assert!(pick.calls.contains("first_or"));
+    }
+
     // === Unresolved Reference Tests ===
 
     #[test]
@@ -1091,8 +1401,192 @@ end
         // This depends on implementation but we can verify the full name works
     }
 
+    #[test]
+    fn test_doc_extraction() {
+        let source = r#"
+snippet id="math.abs" kind="fn"
+doc """Absolute value"""
+signature
+  fn name="abs"
+    doc """Any integer"""
+    param name="x" type="Int"
+    param name="y" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="if"
+    condition="neg"
+    then
+      doc """Flip the sign"""
+      step id="s1a" kind="return"
+        lit=0
+        as="_"
+      end
+    end
+    as="_"
+  end
+end
+end
+"#;
+        let result = build_graph_from_source(source).expect("should build graph");
+        let symbol = result.graph.get_by_name("math.abs").unwrap();
+
+        assert_eq!(symbol.doc.as_deref(), Some("Absolute value"));
+        assert_eq!(symbol.param_docs.get("x").map(String::as_str), Some("Any integer"));
+        assert!(!symbol.param_docs.contains_key("y"));
+        assert_eq!(symbol.step_docs.get("s1a").map(String::as_str), Some("Flip the sign"));
+        assert!(!symbol.step_docs.contains_key("s1"));
+    }
+
+    fn module(id: &str, members: &[&str]) -> String {
+        let members: String = members.iter().map(|m| format!("    member id=\"{}\"\n", m)).collect();
+        format!("snippet id=\"{}\" kind=\"module\"\n  contains\n{}  end\nend\n", id, members)
+    }
+
+    fn empty_fn(id: &str) -> String {
+        format!("snippet id=\"{}\" kind=\"fn\"\nend\n", id)
+    }
+
+    #[test]
+    fn test_module_hierarchy() {
+        let source = [
+            module("app", &["app.auth", "app.main"]),
+            module("app.auth", &["login", "logout"]),
+            empty_fn("app.main"),
+            empty_fn("login"),
+            empty_fn("logout"),
+        ]
+        .concat();
+        let graph = build_graph_from_source(&source).expect("should build graph").graph;
+
+        assert_eq!(graph.get_by_name("login").unwrap().parent_module.as_deref(), Some("app.auth"));
+        assert_eq!(graph.enclosing_modules("login"), vec!["app.auth", "app"]);
+        let members: Vec<_> = graph.module_members("app").unwrap().into_iter().collect();
+        assert_eq!(members, vec!["app.auth", "app.main", "login", "logout"]);
+        assert!(graph.module_members("login").is_none());
+
+        // Declared membership overrides the ID prefix when grouping modules
+        assert_eq!(graph.module_name_of("login"), "app.auth");
+        assert!(graph.module_graph().modules["app.auth"].symbols.contains("logout"));
+    }
+
+    #[test]
+    fn test_member_of_two_modules_is_error() {
+        let source = [module("a", &["shared"]), module("b", &["shared"]), empty_fn("shared")].concat();
+        let errors = build_graph_from_source(&source).unwrap_err();
+        assert!(errors.iter().any(|e| e.code() == "E-SYMBOL-004"));
+    }
+
+    #[test]
+    fn test_module_nesting_cycle_is_error() {
+        let source = [module("a", &["b"]), module("b", &["a"])].concat();
+        let errors = build_graph_from_source(&source).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], SymbolError::CircularImport { cycle, .. } if cycle == "a -> b -> a"));
+    }
+
+    #[test]
+    fn test_undefined_member_is_deferred() {
+        let result = build_graph_from_source(&module("a", &["a.missing"])).expect("should build graph");
+        assert!(result.deferred_errors.iter().any(|e| matches!(
+            e,
+            SymbolError::UndefinedReference { name, referrer, .. } if name == "a.missing" && referrer == "a"
+        )));
+    }
+
     // === Cross-Module References ===
 
+    #[test]
+    fn test_publisher_subscriber_edges() {
+        let source = r#"
+snippet id="orders.OrderPlaced" kind="struct"
+signature
+  struct name="OrderPlaced"
+    field name="id" type="Int"
+  end
+end
+end
+
+snippet id="orders.place" kind="fn"
+body
+  step id="s1" kind="call"
+    fn="events.publish"
+    arg name="event_type" lit="OrderPlaced"
+    arg name="event" from="order"
+    as="_"
+  end
+end
+end
+
+snippet id="orders.notify" kind="fn"
+end
+
+snippet id="orders.on_placed" kind="subscriber"
+subscribe
+  event type="orders.OrderPlaced"
+  handler fn="orders.notify"
+end
+end
+"#;
+        let result = build_graph_from_source(source).expect("should build graph");
+        let graph = &result.graph;
+
+        let subscriber = graph.get_by_name("orders.on_placed").unwrap();
+        assert_eq!(subscriber.kind, SymbolKind::Subscriber);
+        assert_eq!(subscriber.subscribes.as_deref(), Some("orders.OrderPlaced"));
+        assert!(subscriber.calls.contains("orders.notify"));
+
+        assert!(graph.get_by_name("orders.place").unwrap().publishes.contains("OrderPlaced"));
+        assert_eq!(graph.subscribers_of("orders.place"), vec!["orders.on_placed".to_string()]);
+        assert_eq!(graph.publishers_of("orders.on_placed"), vec!["orders.place".to_string()]);
+        assert_eq!(graph.callers_of("orders.notify"), vec!["orders.on_placed".to_string()]);
+    }
+
+    #[test]
+    fn test_interface_implementation_edges() {
+        let source = r#"
+snippet id="shapes.Shape" kind="interface"
+signature
+  interface name="Shape"
+    fn name="area"
+      param name="shape" type="Self"
+      returns type="Float"
+    end
+  end
+end
+end
+
+snippet id="shapes.Circle" kind="struct" implements="shapes.Shape"
+signature
+  struct name="Circle"
+    field name="radius" type="Float"
+  end
+end
+end
+
+snippet id="shapes.circle_area" kind="fn" implements="shapes.Shape"
+signature
+  fn name="area"
+    param name="shape" type="Circle"
+    returns type="Float"
+  end
+end
+end
+"#;
+        let result = build_graph_from_source(source).expect("should build graph");
+        let graph = &result.graph;
+        assert!(result.deferred_errors.is_empty(), "{:?}", result.deferred_errors);
+
+        let interface = graph.get_by_name("shapes.Shape").unwrap();
+        assert_eq!(interface.kind, SymbolKind::Interface);
+        assert_eq!(graph.get_by_name("shapes.Circle").unwrap().implements.as_deref(), Some("shapes.Shape"));
+
+        let mut implementations = graph.implementations_of("shapes.Shape");
+        implementations.sort();
+        assert_eq!(implementations, vec!["shapes.Circle".to_string(), "shapes.circle_area".to_string()]);
+    }
+
     #[test]
     fn test_cross_module_call_reference() {
         let source = r#"
@@ -0,0 +1,313 @@
+//! Module-level dependency graph
+//!
+//! Aggregates symbol-level call and type edges up to modules and reports
+//! module cycles, layers, and fan-in/fan-out. A symbol's module is the
+//! module snippet whose `contains` section lists it, or else the part of its
+//! snippet ID before the last dot. Module cycles are legal at the symbol level as long as
+//! different functions are involved, but they usually signal a layering
+//! problem worth surfacing.
+
+use crate::{SymbolGraph, SymbolKind};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Module name used for snippet IDs without a dot
+pub const ROOT_MODULE: &str = "(root)";
+
+/// A module and its aggregated dependencies
+#[derive(Debug, Clone, Default)]
+pub struct ModuleNode {
+    /// Module name (e.g., "app.users")
+    pub name: String,
+    /// Snippet IDs defined in this module
+    pub symbols: BTreeSet<String>,
+    /// Modules this module calls into or references types from
+    pub depends_on: BTreeSet<String>,
+    /// Modules that depend on this module
+    pub depended_on_by: BTreeSet<String>,
+    /// Number of symbol-level edges from this module to each dependency
+    pub edge_counts: BTreeMap<String, usize>,
+    /// Layer index (0 = depends on nothing); modules in a cycle share a layer
+    pub layer: usize,
+}
+
+impl ModuleNode {
+    /// Number of distinct modules depending on this one
+    pub fn fan_in(&self) -> usize {
+        self.depended_on_by.len()
+    }
+
+    /// Number of distinct modules this one depends on
+    pub fn fan_out(&self) -> usize {
+        self.depends_on.len()
+    }
+}
+
+/// Module dependency graph derived from a symbol graph
+#[derive(Debug, Clone, Default)]
+pub struct ModuleGraph {
+    /// All modules, keyed by name
+    pub modules: BTreeMap<String, ModuleNode>,
+    /// Module-level cycles (strongly connected components with >1 module)
+    pub cycles: Vec<Vec<String>>,
+}
+
+impl ModuleGraph {
+    /// Modules grouped by layer, lowest layer first
+    pub fn layers(&self) -> Vec<Vec<&ModuleNode>> {
+        let depth = self.modules.values().map(|m| m.layer + 1).max().unwrap_or(0);
+        let mut layers = vec![Vec::new(); depth];
+        for module in self.modules.values() {
+            layers[module.layer].push(module);
+        }
+        layers
+    }
+
+    /// Whether the module graph is acyclic
+    pub fn is_acyclic(&self) -> bool {
+        self.cycles.is_empty()
+    }
+}
+
+/// Module name of a snippet ID
+pub fn module_of(id: &str) -> &str {
+    match id.rsplit_once('.') {
+        Some((module, _)) => module,
+        None => ROOT_MODULE,
+    }
+}
+
+impl SymbolGraph {
+    /// Module a symbol belongs to: the module snippet listing it, or else the
+    /// part of its ID before the last dot
+    pub fn module_name_of<'a>(&'a self, name: &'a str) -> &'a str {
+        self.get_by_name(name)
+            .and_then(|symbol| symbol.parent_module.as_deref())
+            .unwrap_or_else(|| module_of(name))
+    }
+
+    /// Members of a module snippet, including the members of nested modules,
+    /// or `None` if `module` is not a module snippet
+    pub fn module_members(&self, module: &str) -> Option<BTreeSet<String>> {
+        let symbol = self.get_by_name(module).filter(|s| s.kind == SymbolKind::Module)?;
+        let mut members = BTreeSet::new();
+        let mut pending: Vec<&String> = symbol.members.iter().collect();
+        while let Some(member) = pending.pop() {
+            if member == module || !members.insert(member.clone()) {
+                continue;
+            }
+            if let Some(nested) = self.get_by_name(member) {
+                pending.extend(&nested.members);
+            }
+        }
+        Some(members)
+    }
+
+    /// Module snippets enclosing a symbol, innermost first
+    pub fn enclosing_modules(&self, name: &str) -> Vec<String> {
+        let mut modules: Vec<String> = Vec::new();
+        let mut current = self.get_by_name(name).and_then(|s| s.parent_module.as_ref());
+        while let Some(module) = current {
+            if module == name || modules.contains(module) {
+                break;
+            }
+            modules.push(module.clone());
+            current = self.get_by_name(module).and_then(|s| s.parent_module.as_ref());
+        }
+        modules
+    }
+
+    /// Aggregate symbol edges into a module dependency graph
+    pub fn module_graph(&self) -> ModuleGraph {
+        let mut modules: BTreeMap<String, ModuleNode> = BTreeMap::new();
+
+        for symbol in self.iter() {
+            let name = self.module_name_of(&symbol.name);
+            modules
+                .entry(name.to_string())
+                .or_insert_with(|| ModuleNode { name: name.to_string(), ..Default::default() })
+                .symbols
+                .insert(symbol.name.clone());
+        }
+
+        for symbol in self.iter() {
+            let from = self.module_name_of(&symbol.name).to_string();
+
+            let call_targets = symbol.calls.iter().filter_map(|c| self.id_of(c));
+            let type_targets = symbol.references.iter().filter_map(|r| self.resolve_type(r));
+
+            for target_id in call_targets.chain(type_targets) {
+                let Some(target) = self.get(target_id) else { continue };
+                let to = self.module_name_of(&target.name).to_string();
+                if to == from {
+                    continue;
+                }
+
+                if let Some(node) = modules.get_mut(&from) {
+                    node.depends_on.insert(to.clone());
+                    *node.edge_counts.entry(to.clone()).or_insert(0) += 1;
+                }
+                if let Some(node) = modules.get_mut(&to) {
+                    node.depended_on_by.insert(from.clone());
+                }
+            }
+        }
+
+        let components = strongly_connected(&modules);
+        let cycles = components.iter().filter(|c| c.len() > 1).cloned().collect();
+        assign_layers(&mut modules, &components);
+
+        ModuleGraph { modules, cycles }
+    }
+}
+
+/// Tarjan's SCC algorithm over module names, returned in dependency order
+/// (a component appears after every component it depends on)
+fn strongly_connected(modules: &BTreeMap<String, ModuleNode>) -> Vec<Vec<String>> {
+    struct State<'a> {
+        modules: &'a BTreeMap<String, ModuleNode>,
+        index: usize,
+        indices: BTreeMap<&'a str, usize>,
+        lowlink: BTreeMap<&'a str, usize>,
+        stack: Vec<&'a str>,
+        on_stack: BTreeSet<&'a str>,
+        components: Vec<Vec<String>>,
+    }
+
+    fn visit<'a>(state: &mut State<'a>, name: &'a str) {
+        state.indices.insert(name, state.index);
+        state.lowlink.insert(name, state.index);
+        state.index += 1;
+        state.stack.push(name);
+        state.on_stack.insert(name);
+
+        let modules = state.modules;
+        for dep in &modules[name].depends_on {
+            let dep = dep.as_str();
+            if !state.indices.contains_key(dep) {
+                visit(state, dep);
+                let low = state.lowlink[name].min(state.lowlink[dep]);
+                state.lowlink.insert(name, low);
+            } else if state.on_stack.contains(dep) {
+                let low = state.lowlink[name].min(state.indices[dep]);
+                state.lowlink.insert(name, low);
+            }
+        }
+
+        if state.lowlink[name] == state.indices[name] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack.remove(member);
+                component.push(member.to_string());
+                if member == name {
+                    break;
+                }
+            }
+            component.sort();
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        modules,
+        index: 0,
+        indices: BTreeMap::new(),
+        lowlink: BTreeMap::new(),
+        stack: Vec::new(),
+        on_stack: BTreeSet::new(),
+        components: Vec::new(),
+    };
+
+    for name in modules.keys() {
+        if !state.indices.contains_key(name.as_str()) {
+            visit(&mut state, name);
+        }
+    }
+
+    state.components
+}
+
+/// Assign layers over the condensation: a component sits one layer above
+/// the highest component it depends on
+fn assign_layers(modules: &mut BTreeMap<String, ModuleNode>, components: &[Vec<String>]) {
+    let mut layer_of: BTreeMap<String, usize> = BTreeMap::new();
+
+    for component in components {
+        let members: BTreeSet<&String> = component.iter().collect();
+        let layer = component
+            .iter()
+            .flat_map(|m| modules[m].depends_on.iter())
+            .filter(|dep| !members.contains(dep))
+            .map(|dep| layer_of[dep] + 1)
+            .max()
+            .unwrap_or(0);
+
+        for member in component {
+            layer_of.insert(member.clone(), layer);
+        }
+    }
+
+    for (name, layer) in layer_of {
+        if let Some(node) = modules.get_mut(&name) {
+            node.layer = layer;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SymbolInfo, SymbolKind};
+    use covenant_ast::Span;
+
+    fn graph_with_calls(edges: &[(&str, &[&str])]) -> SymbolGraph {
+        let mut graph = SymbolGraph::new();
+        for (name, calls) in edges {
+            let mut sym = SymbolInfo::new(name.to_string(), SymbolKind::Function, Span::dummy());
+            sym.calls.extend(calls.iter().map(|c| c.to_string()));
+            graph.insert(sym).unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn test_module_of() {
+        assert_eq!(module_of("app.users.get"), "app.users");
+        assert_eq!(module_of("main"), ROOT_MODULE);
+    }
+
+    #[test]
+    fn test_layers_and_fan() {
+        let graph = graph_with_calls(&[
+            ("app.main", &["svc.users.get", "svc.orders.list"]),
+            ("svc.users.get", &["db.query"]),
+            ("svc.orders.list", &["db.query", "svc.users.get"]),
+            ("db.query", &[]),
+        ]);
+        let modules = graph.module_graph();
+
+        assert!(modules.is_acyclic());
+        assert_eq!(modules.modules["db"].layer, 0);
+        assert_eq!(modules.modules["svc.users"].layer, 1);
+        assert_eq!(modules.modules["svc.orders"].layer, 2);
+        assert_eq!(modules.modules["app"].layer, 3);
+        assert_eq!(modules.modules["db"].fan_in(), 2);
+        assert_eq!(modules.modules["app"].fan_out(), 2);
+    }
+
+    #[test]
+    fn test_module_cycle_with_acyclic_symbols() {
+        // a.x -> b.y and b.z -> a.w: no symbol cycle, but modules a and b form one
+        let graph = graph_with_calls(&[
+            ("a.x", &["b.y"]),
+            ("b.y", &[]),
+            ("b.z", &["a.w"]),
+            ("a.w", &[]),
+            ("c.top", &["a.x"]),
+        ]);
+        let modules = graph.module_graph();
+
+        assert_eq!(modules.cycles, vec![vec!["a".to_string(), "b".to_string()]]);
+        assert_eq!(modules.modules["a"].layer, modules.modules["b"].layer);
+        assert_eq!(modules.modules["c"].layer, 1);
+    }
+}
@@ -0,0 +1,119 @@
+//! Call paths between symbols
+//!
+//! Answers "how does `main` end up calling `db.query`?" by enumerating the
+//! call chains from one symbol to another, shortest first. A chain never
+//! visits a symbol twice, so recursion cannot make the search endless.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{SymbolGraph, SymbolId};
+
+impl SymbolGraph {
+    /// Every call chain from `from` to `to`, shortest first, each starting
+    /// with `from` and ending with `to`
+    ///
+    /// Chains are limited to `max_depth` calls when given; without a limit
+    /// every simple chain is returned, which can be many in a dense graph.
+    pub fn call_paths(&self, from: SymbolId, to: SymbolId, max_depth: Option<usize>) -> Vec<Vec<SymbolId>> {
+        let mut paths = Vec::new();
+        let mut queue: VecDeque<Vec<SymbolId>> = VecDeque::from([vec![from]]);
+        while let Some(path) = queue.pop_front() {
+            let last = *path.last().expect("paths are never empty");
+            if last == to {
+                paths.push(path);
+                continue;
+            }
+            if max_depth.is_some_and(|max| path.len() > max) {
+                continue;
+            }
+            for callee in self.resolved_callees(last) {
+                if !path.contains(&callee) {
+                    let mut next = path.clone();
+                    next.push(callee);
+                    queue.push_back(next);
+                }
+            }
+        }
+        paths
+    }
+
+    /// The shortest call chain from `from` to `to`, if `from` reaches `to`
+    pub fn shortest_call_path(&self, from: SymbolId, to: SymbolId) -> Option<Vec<SymbolId>> {
+        // Breadth-first with parent links, so each symbol is expanded once
+        let mut parents: HashMap<SymbolId, SymbolId> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(id) = queue.pop_front() {
+            if id == to {
+                let mut path = vec![to];
+                while let Some(parent) = path.last().and_then(|last| parents.get(last)) {
+                    path.push(*parent);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for callee in self.resolved_callees(id) {
+                if callee != from && !parents.contains_key(&callee) {
+                    parents.insert(callee, id);
+                    queue.push_back(callee);
+                }
+            }
+        }
+        None
+    }
+
+    /// The symbols `id` calls that are in the graph, in ID order
+    pub(crate) fn resolved_callees(&self, id: SymbolId) -> Vec<SymbolId> {
+        let mut callees: Vec<SymbolId> = self
+            .get(id)
+            .map(|s| s.calls.iter().filter_map(|name| self.id_of(name)).collect())
+            .unwrap_or_default();
+        callees.sort_unstable();
+        callees
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_support, SymbolGraph, SymbolId};
+
+    fn graph() -> SymbolGraph {
+        // main -> api -> repo -> db, main -> repo, main -> log -> log
+        let funcs: [(&str, &[&str]); 5] = [
+            ("main", &["api", "repo", "log"]),
+            ("api", &["repo"]),
+            ("repo", &["db"]),
+            ("db", &[]),
+            ("log", &["log"]),
+        ];
+        test_support::graph(&funcs)
+    }
+
+    fn names(graph: &SymbolGraph, path: &[SymbolId]) -> String {
+        path.iter().map(|id| graph.get(*id).unwrap().name.as_str()).collect::<Vec<_>>().join(" -> ")
+    }
+
+    #[test]
+    fn test_call_paths_shortest_first() {
+        let graph = graph();
+        let id = |name| graph.id_of(name).unwrap();
+
+        let paths: Vec<String> =
+            graph.call_paths(id("main"), id("db"), None).iter().map(|p| names(&graph, p)).collect();
+        assert_eq!(paths, vec!["main -> repo -> db", "main -> api -> repo -> db"]);
+
+        let paths = graph.call_paths(id("main"), id("db"), Some(2));
+        assert_eq!(paths.len(), 1);
+        assert!(graph.call_paths(id("db"), id("main"), None).is_empty());
+    }
+
+    #[test]
+    fn test_shortest_call_path() {
+        let graph = graph();
+        let id = |name| graph.id_of(name).unwrap();
+
+        let path = graph.shortest_call_path(id("main"), id("db")).unwrap();
+        assert_eq!(names(&graph, &path), "main -> repo -> db");
+        assert_eq!(graph.shortest_call_path(id("main"), id("main")), Some(vec![id("main")]));
+        assert_eq!(graph.shortest_call_path(id("log"), id("db")), None);
+    }
+}
@@ -0,0 +1,300 @@
+//! Multi-file projects
+//!
+//! A [`Project`] collects the snippets of many files and builds one symbol
+//! graph over all of them, so a call into another file resolves like any
+//! other. Spans are byte offsets into a single file, so the project records
+//! which file each symbol came from and locates every error in a file: the
+//! file of the symbol the error is about, such as the referrer of an
+//! undefined reference.
+//!
+//! Duplicate IDs are checked across the whole project before the graph is
+//! built, and each duplicate is reported with the location of the first
+//! definition.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use covenant_ast::{Program, Snippet, Span};
+
+use crate::{build_from_snippets_with_cycle_mode, CycleMode, SymbolError, SymbolGraph, SymbolId};
+
+/// Index of a file in a [`Project`], in the order files were added
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(pub u32);
+
+/// Snippets from many files, built into one symbol graph
+#[derive(Debug, Default)]
+pub struct Project {
+    files: Vec<PathBuf>,
+    snippets: Vec<Snippet>,
+    /// The file each snippet came from, parallel to `snippets`
+    snippet_files: Vec<FileId>,
+    cycle_mode: CycleMode,
+}
+
+impl Project {
+    /// Create an empty project
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treat cycles per `mode` when building (see [`CycleMode`])
+    pub fn with_cycle_mode(mut self, mode: CycleMode) -> Self {
+        self.cycle_mode = mode;
+        self
+    }
+
+    /// Add a parsed file. Legacy programs have no snippets and add none.
+    pub fn add_file(&mut self, path: impl Into<PathBuf>, program: &Program) -> FileId {
+        let snippets = match program {
+            Program::Snippets { snippets, .. } => snippets.clone(),
+            Program::Legacy { .. } => Vec::new(),
+        };
+        self.add_snippets(path, snippets)
+    }
+
+    /// Add the snippets of one file
+    pub fn add_snippets(&mut self, path: impl Into<PathBuf>, snippets: Vec<Snippet>) -> FileId {
+        let file = FileId(self.files.len() as u32);
+        self.files.push(path.into());
+        self.snippet_files.extend(std::iter::repeat_n(file, snippets.len()));
+        self.snippets.extend(snippets);
+        file
+    }
+
+    /// Paths of the files, indexed by [`FileId`]
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Path of a file in the project
+    pub fn path(&self, file: FileId) -> Option<&Path> {
+        self.files.get(file.0 as usize).map(PathBuf::as_path)
+    }
+
+    /// Every file's snippets, in the order they were added
+    pub fn snippets(&self) -> &[Snippet] {
+        &self.snippets
+    }
+
+    /// Build one symbol graph over every file
+    ///
+    /// Returns the hard errors, located in their files, if any snippet ID is
+    /// defined twice or the graph cannot be built.
+    pub fn build(&self) -> Result<ProjectSymbols, Vec<ProjectError>> {
+        let duplicates = self.duplicate_ids();
+        if !duplicates.is_empty() {
+            return Err(duplicates);
+        }
+
+        // IDs are unique, so each name has one file
+        let symbol_files: HashMap<&str, FileId> = self
+            .snippets
+            .iter()
+            .zip(&self.snippet_files)
+            .map(|(snippet, file)| (snippet.id.as_str(), *file))
+            .collect();
+        let locate = |error: SymbolError| {
+            let span_of = |name: &str| self.snippets.iter().find(|s| s.id == name).map(|s| s.span);
+            let file = error_symbol(&error, span_of)
+                .and_then(|name| symbol_files.get(name.as_str()))
+                .map(|file| self.files[file.0 as usize].clone());
+            ProjectError { error, file, first: None }
+        };
+
+        match build_from_snippets_with_cycle_mode(&self.snippets, self.cycle_mode) {
+            Ok(mut result) => {
+                for symbol in result.graph.iter_mut() {
+                    symbol.file = symbol_files
+                        .get(symbol.name.as_str())
+                        .map(|file| self.files[file.0 as usize].clone());
+                }
+                Ok(ProjectSymbols {
+                    symbol_files: result
+                        .graph
+                        .iter()
+                        .filter_map(|symbol| Some((symbol.id, *symbol_files.get(symbol.name.as_str())?)))
+                        .collect(),
+                    deferred_errors: result.deferred_errors.into_iter().map(locate).collect(),
+                    graph: result.graph,
+                    files: self.files.clone(),
+                })
+            }
+            Err(errors) => Err(errors.into_iter().map(locate).collect()),
+        }
+    }
+
+    /// Every definition of an ID after its first, in any file
+    fn duplicate_ids(&self) -> Vec<ProjectError> {
+        let mut first: HashMap<&str, (FileId, Span)> = HashMap::new();
+        let mut errors = Vec::new();
+        for (snippet, file) in self.snippets.iter().zip(&self.snippet_files) {
+            match first.get(snippet.id.as_str()) {
+                Some((first_file, first_span)) => errors.push(ProjectError {
+                    error: SymbolError::DuplicateId { id: snippet.id.clone(), span: snippet.span },
+                    file: Some(self.files[file.0 as usize].clone()),
+                    first: Some((self.files[first_file.0 as usize].clone(), *first_span)),
+                }),
+                None => {
+                    first.insert(&snippet.id, (*file, snippet.span));
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// The symbol whose file `error`'s span points into
+///
+/// A cycle's span is one of its members', found by comparing against each
+/// member's span with `span_of`.
+fn error_symbol(error: &SymbolError, span_of: impl Fn(&str) -> Option<Span>) -> Option<String> {
+    match error {
+        SymbolError::UndefinedReference { referrer, .. } | SymbolError::UnknownTable { referrer, .. } => {
+            Some(referrer.clone())
+        }
+        SymbolError::DuplicateId { id, .. } => Some(id.clone()),
+        SymbolError::MultipleModules { second, .. } => Some(second.clone()),
+        SymbolError::RelationTargetNotFound { from_symbol, .. } => Some(from_symbol.clone()),
+        SymbolError::CircularImport { cycle, span } | SymbolError::UnannotatedCycle { cycle, span, .. } => {
+            let members: Vec<&str> = cycle.split(" -> ").collect();
+            let member = members.iter().find(|name| span_of(name) == Some(*span)).or(members.first());
+            member.map(|name| name.to_string())
+        }
+        SymbolError::Cancelled => None,
+    }
+}
+
+/// A symbol graph built over a project, with the file of each symbol
+#[derive(Debug)]
+pub struct ProjectSymbols {
+    /// The graph over every file's snippets
+    pub graph: SymbolGraph,
+    /// Soft errors (undefined references), located in their files
+    pub deferred_errors: Vec<ProjectError>,
+    files: Vec<PathBuf>,
+    symbol_files: HashMap<SymbolId, FileId>,
+}
+
+impl ProjectSymbols {
+    /// The file a symbol was defined in
+    pub fn file_of(&self, id: SymbolId) -> Option<FileId> {
+        self.symbol_files.get(&id).copied()
+    }
+
+    /// The path of the file a symbol was defined in, by name
+    pub fn path_of(&self, name: &str) -> Option<&Path> {
+        let file = self.file_of(self.graph.id_of(name)?)?;
+        self.files.get(file.0 as usize).map(PathBuf::as_path)
+    }
+
+    /// Names of the symbols defined in `file`, sorted
+    pub fn symbols_in(&self, file: FileId) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .graph
+            .iter()
+            .filter(|symbol| self.file_of(symbol.id) == Some(file))
+            .map(|symbol| symbol.name.as_str())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// A symbol error with the file its span points into
+#[derive(Debug, Clone)]
+pub struct ProjectError {
+    pub error: SymbolError,
+    /// File of the span, or `None` for errors about no symbol
+    /// (cancellation)
+    pub file: Option<PathBuf>,
+    /// For a duplicate ID, the file and span of its first definition
+    pub first: Option<(PathBuf, Span)>,
+}
+
+impl ProjectError {
+    /// The span of the error within `file`
+    pub fn span(&self) -> Span {
+        self.error.span()
+    }
+
+    /// Error code for machine-readable output
+    pub fn code(&self) -> &'static str {
+        self.error.code()
+    }
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        write!(f, "{}", self.error)?;
+        if let Some((file, _)) = &self.first {
+            write!(f, " (first defined in {})", file.display())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::func;
+
+    fn project(files: &[(&str, String)]) -> Project {
+        let mut project = Project::new();
+        for (path, source) in files {
+            project.add_file(*path, &covenant_parser::parse(source).unwrap());
+        }
+        project
+    }
+
+    #[test]
+    fn test_cross_file_calls_resolve() {
+        let project = project(&[
+            ("app.cov", func("app.main", &["util.helper"])),
+            ("util.cov", [func("util.helper", &[]), func("util.other", &["util.missing"])].concat()),
+        ]);
+        let symbols = project.build().unwrap();
+
+        assert_eq!(symbols.graph.callers_of("util.helper"), vec!["app.main"]);
+        assert_eq!(symbols.path_of("app.main"), Some(Path::new("app.cov")));
+        assert_eq!(symbols.symbols_in(FileId(1)), vec!["util.helper", "util.other"]);
+
+        let [undefined] = symbols.deferred_errors.as_slice() else {
+            panic!("expected one deferred error, got {:?}", symbols.deferred_errors);
+        };
+        assert_eq!(undefined.file.as_deref(), Some(Path::new("util.cov")));
+        assert_eq!(&project.snippets()[2].span, &undefined.span());
+    }
+
+    #[test]
+    fn test_duplicate_ids_across_files() {
+        let project = project(&[("a.cov", func("shared", &[])), ("b.cov", func("shared", &[]))]);
+        let errors = project.build().unwrap_err();
+
+        let [duplicate] = errors.as_slice() else {
+            panic!("expected one error, got {:?}", errors);
+        };
+        assert_eq!(duplicate.code(), "E-SYMBOL-002");
+        assert_eq!(duplicate.file.as_deref(), Some(Path::new("b.cov")));
+        assert_eq!(duplicate.first.as_ref().map(|(file, _)| file.as_path()), Some(Path::new("a.cov")));
+        assert_eq!(duplicate.to_string(), "b.cov: duplicate symbol ID: shared (first defined in a.cov)");
+    }
+
+    #[test]
+    fn test_cycle_located_in_file_of_its_span() {
+        // The padding in b.cov gives the two snippets different spans
+        let project = project(&[("a.cov", func("a", &["b"])), ("b.cov", func("pad", &[]) + &func("b", &["a"]))]);
+        let errors = project.build().unwrap_err();
+
+        let [cycle] = errors.as_slice() else {
+            panic!("expected one error, got {:?}", errors);
+        };
+        let owner = if cycle.span() == project.snippets()[0].span { "a.cov" } else { "b.cov" };
+        assert!(cycle.span() == project.snippets()[0].span || cycle.span() == project.snippets()[2].span);
+        assert_eq!(cycle.file.as_deref(), Some(Path::new(owner)));
+    }
+}
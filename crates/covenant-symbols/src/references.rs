@@ -0,0 +1,309 @@
+//! Find all references to a symbol
+//!
+//! Collects every backward edge into a symbol as a [`Reference`] record
+//! saying what kind of reference it is and where it is: the referring
+//! snippet's span, in the file it came from when the graph was built by a
+//! [`crate::Project`]. Editors use these for "find references"; the query
+//! engine answers `references to X` with them. Call references also list
+//! each calling step, so a symbol called three times from one snippet
+//! shows all three.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::PathBuf;
+
+use covenant_ast::{CallSite, Span};
+use serde::{Deserialize, Serialize};
+
+use crate::{SymbolGraph, SymbolId, SymbolKind};
+
+/// How one symbol refers to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    /// A call step (`fn="..."`), in a body or a test
+    Call,
+    /// A type named in a signature, field or alias
+    TypeReference,
+    /// A `rel to=` or `rel from=` declaration
+    Relation,
+    /// A test whose `covers=` names the symbol or one of its requirements
+    TestCoverage,
+    /// An extern-impl binding this extern-abstract for a platform
+    ExternContract,
+    /// A fn or struct snippet implementing this interface
+    Implementation,
+}
+
+impl ReferenceKind {
+    pub const ALL: [ReferenceKind; 6] = [
+        ReferenceKind::Call,
+        ReferenceKind::TypeReference,
+        ReferenceKind::Relation,
+        ReferenceKind::TestCoverage,
+        ReferenceKind::ExternContract,
+        ReferenceKind::Implementation,
+    ];
+
+    /// Parse a name as given by [`ReferenceKind::as_str`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+
+    /// Name used in query output and JSON
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReferenceKind::Call => "call",
+            ReferenceKind::TypeReference => "type_reference",
+            ReferenceKind::Relation => "relation",
+            ReferenceKind::TestCoverage => "test_coverage",
+            ReferenceKind::ExternContract => "extern_contract",
+            ReferenceKind::Implementation => "implementation",
+        }
+    }
+}
+
+impl fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// One reference to a symbol
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reference {
+    pub kind: ReferenceKind,
+    /// The referring symbol
+    pub from: SymbolId,
+    /// File of the referring snippet, when known
+    pub file: Option<PathBuf>,
+    /// Span of the referring snippet
+    pub span: Span,
+    /// For calls: the calling steps, in source order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub call_sites: Vec<CallSite>,
+}
+
+impl SymbolGraph {
+    /// Where `from` calls `to`, in source order; empty when it doesn't, or
+    /// calls it outside a step
+    pub fn call_sites(&self, from: SymbolId, to: SymbolId) -> &[CallSite] {
+        let (Some(from), Some(to)) = (self.get(from), self.get(to)) else { return &[] };
+        from.call_sites.get(&to.name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every reference to `id`, ordered by file, position and kind
+    ///
+    /// A symbol referring to `id` in several ways (calling it and naming
+    /// it as a type, say) gives one record per kind.
+    pub fn references_to(&self, id: SymbolId) -> Vec<Reference> {
+        let Some(target) = self.get(id) else { return Vec::new() };
+        let mut found: BTreeSet<(ReferenceKind, SymbolId)> = BTreeSet::new();
+
+        found.extend(target.called_by.iter().map(|from| (ReferenceKind::Call, *from)));
+        found.extend(
+            target.referenced_by.iter().chain(&target.embedded_by).map(|from| (ReferenceKind::TypeReference, *from)),
+        );
+        found.extend(
+            target.relations_from.iter().filter_map(|rel| Some((ReferenceKind::Relation, self.id_of(&rel.target)?))),
+        );
+        found.extend(target.implemented_by.iter().map(|from| (ReferenceKind::Implementation, *from)));
+
+        for symbol in self.iter().filter(|s| s.id != id) {
+            let covered = symbol.covers.iter().any(|c| *c == target.name || target.requirements.contains(c));
+            if covered {
+                found.insert((ReferenceKind::TestCoverage, symbol.id));
+            }
+            if symbol.kind == SymbolKind::ExternImpl && symbol.implements.as_deref() == Some(target.name.as_str()) {
+                found.insert((ReferenceKind::ExternContract, symbol.id));
+            }
+        }
+
+        let mut references: Vec<Reference> = found
+            .into_iter()
+            .filter_map(|(kind, from)| {
+                let symbol = self.get(from)?;
+                let call_sites = match kind {
+                    ReferenceKind::Call => self.call_sites(from, id).to_vec(),
+                    _ => Vec::new(),
+                };
+                Some(Reference { kind, from, file: symbol.file.clone(), span: symbol.span, call_sites })
+            })
+            .collect();
+        references.sort_by(|a, b| (&a.file, a.span.start, a.kind).cmp(&(&b.file, b.span.start, b.kind)));
+        references
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_symbol_graph, Project};
+
+    const SOURCE: &str = r#"snippet id="app.User" kind="struct"
+signature
+  struct name="User"
+    field name="id" type="Int"
+  end
+end
+requires
+  req id="R-USER-1"
+    text "Users have IDs"
+    priority high
+  end
+end
+end
+
+snippet id="app.load" kind="fn"
+signature
+  fn name="load"
+    param name="id" type="Int"
+    returns type="app.User"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="app.make"
+    arg name="id" from="id"
+    as="user"
+  end
+  step id="s2" kind="return"
+    from="user"
+    as="_"
+  end
+end
+relations
+  rel to="app.User" type=describes
+end
+end
+
+snippet id="app.make" kind="fn"
+signature
+  fn name="make"
+    param name="id" type="Int"
+    returns type="app.User"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=0
+    as="_"
+  end
+end
+end
+
+snippet id="app.tests" kind="test"
+tests
+  test id="T-1" kind="unit" covers="R-USER-1"
+    step id="t1" kind="call"
+      fn="app.load"
+      arg name="id" lit=1
+      as="user"
+    end
+  end
+end
+end
+"#;
+
+    fn kinds(graph: &SymbolGraph, name: &str) -> Vec<(String, ReferenceKind)> {
+        graph
+            .references_to(graph.id_of(name).unwrap())
+            .into_iter()
+            .map(|r| (graph.get(r.from).unwrap().name.clone(), r.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_references_by_kind() {
+        let graph = build_symbol_graph(&covenant_parser::parse(SOURCE).unwrap()).unwrap().graph;
+
+        assert_eq!(
+            kinds(&graph, "app.User"),
+            vec![
+                ("app.load".to_string(), ReferenceKind::TypeReference),
+                ("app.load".to_string(), ReferenceKind::Relation),
+                ("app.make".to_string(), ReferenceKind::TypeReference),
+                ("app.tests".to_string(), ReferenceKind::TestCoverage),
+            ]
+        );
+        assert_eq!(kinds(&graph, "app.load"), vec![("app.tests".to_string(), ReferenceKind::Call)]);
+        assert_eq!(kinds(&graph, "app.tests"), vec![]);
+    }
+
+    #[test]
+    fn test_call_references_list_their_steps() {
+        let source = r#"snippet id="app.twice" kind="fn"
+signature
+  fn name="twice"
+    param name="id" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="app.make"
+    arg name="id" from="id"
+    as="a"
+  end
+  step id="s2" kind="if"
+    condition="a"
+    then
+      step id="s2a" kind="call"
+        fn="app.make"
+        arg name="id" from="id"
+        as="b"
+      end
+    end
+    as="_"
+  end
+  step id="s3" kind="return"
+    from="a"
+    as="_"
+  end
+end
+end
+
+snippet id="app.make" kind="fn"
+signature
+  fn name="make"
+    param name="id" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="id"
+    as="_"
+  end
+end
+end
+"#;
+        let graph = build_symbol_graph(&covenant_parser::parse(source).unwrap()).unwrap().graph;
+        let (twice, make) = (graph.id_of("app.twice").unwrap(), graph.id_of("app.make").unwrap());
+
+        let steps: Vec<Option<&str>> = graph.call_sites(twice, make).iter().map(|s| s.step.as_deref()).collect();
+        assert_eq!(steps, vec![Some("s1"), Some("s2a")]);
+        let sites = graph.call_sites(twice, make);
+        assert!(sites[0].span.start < sites[1].span.start);
+        assert!(graph.call_sites(make, twice).is_empty());
+
+        let references = graph.references_to(make);
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].call_sites, sites);
+    }
+
+    #[test]
+    fn test_references_name_their_file() {
+        let (types, code) = SOURCE.split_at(SOURCE.find("snippet id=\"app.load\"").unwrap());
+        let mut project = Project::new();
+        project.add_file("types.cov", &covenant_parser::parse(types).unwrap());
+        project.add_file("code.cov", &covenant_parser::parse(code).unwrap());
+        let graph = project.build().unwrap().graph;
+
+        let references = graph.references_to(graph.id_of("app.User").unwrap());
+        assert_eq!(references.len(), 4);
+        assert!(references.iter().all(|r| r.file == Some(PathBuf::from("code.cov"))));
+        let load = graph.get_by_name("app.load").unwrap();
+        assert_eq!(references[0].span, load.span);
+    }
+}
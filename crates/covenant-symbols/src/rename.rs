@@ -0,0 +1,253 @@
+//! Rename a symbol and the references to it
+//!
+//! [`rename`] finds the snippets that name a symbol through the graph (its
+//! callers, the snippets declaring a relation to it, and tests covering
+//! it), then locates each reference in their source. The result is a set
+//! of [`TextEdit`]s for the CLI or an editor to apply; nothing is written.
+
+use std::collections::HashSet;
+
+use covenant_ast::{walk_call, CallStep, Program, RelationKind, Section, Span, Visitor};
+use thiserror::Error;
+
+use crate::SymbolGraph;
+
+/// Replace the text in `span` with `new_text`
+///
+/// An empty span is an insertion; an empty `new_text` is a deletion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub span: Span,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    pub fn replace(span: Span, new_text: impl Into<String>) -> Self {
+        Self { span, new_text: new_text.into() }
+    }
+
+    pub fn insert(offset: usize, new_text: impl Into<String>) -> Self {
+        Self::replace(Span::new(offset, offset), new_text)
+    }
+}
+
+/// Why a rename cannot be made
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RenameError {
+    #[error("unknown symbol `{0}`")]
+    UnknownSymbol(String),
+
+    #[error("a symbol named `{0}` already exists")]
+    NameTaken(String),
+}
+
+/// Edits renaming `old_id` to `new_id` in `source`: the snippet's `id=`,
+/// every `fn=` call to it, relation `to=`/`from=` targets naming it, and
+/// test `covers=` links to it
+///
+/// `program` must be the parse of `source`. `graph` may span the whole
+/// project, so a new ID taken in another file is rejected; references from
+/// other files are edited by calling this once per file.
+pub fn rename(
+    graph: &SymbolGraph,
+    program: &Program,
+    source: &str,
+    old_id: &str,
+    new_id: &str,
+) -> Result<Vec<TextEdit>, RenameError> {
+    if graph.contains(new_id) {
+        return Err(RenameError::NameTaken(new_id.to_string()));
+    }
+    let symbol = graph
+        .get_by_name(old_id)
+        .ok_or_else(|| RenameError::UnknownSymbol(old_id.to_string()))?;
+    let Program::Snippets { snippets, .. } = program else {
+        return Ok(Vec::new());
+    };
+
+    let mut referrers: HashSet<&str> = HashSet::from([old_id]);
+    referrers.extend(symbol.called_by.iter().filter_map(|id| graph.get(*id)).map(|s| s.name.as_str()));
+    referrers.extend(symbol.relations_from.iter().map(|rel| rel.target.as_str()));
+    referrers.extend(graph.iter().filter(|s| s.covers.iter().any(|c| c == old_id)).map(|s| s.name.as_str()));
+
+    let mut edits = Vec::new();
+    for snippet in snippets.iter().filter(|s| referrers.contains(s.id.as_str())) {
+        if snippet.id == old_id {
+            edits.extend(attribute_value_edit(source, snippet.span, "id", old_id, new_id));
+        }
+
+        let mut calls = CallSpans { callee: old_id, spans: Vec::new() };
+        calls.visit_snippet(snippet);
+        for span in calls.spans {
+            edits.extend(attribute_value_edit(source, span, "fn", old_id, new_id));
+        }
+
+        for section in &snippet.sections {
+            match section {
+                Section::Relations(relations) => {
+                    for rel in relations.relations.iter().filter(|rel| rel.target == old_id) {
+                        let attr = match rel.kind {
+                            RelationKind::To => "to",
+                            RelationKind::From => "from",
+                        };
+                        edits.extend(attribute_value_edit(source, rel.span, attr, old_id, new_id));
+                    }
+                }
+                Section::Tests(tests) => {
+                    for test in tests.tests.iter().filter(|test| test.covers.iter().any(|c| c == old_id)) {
+                        edits.extend(attribute_value_edit(source, test.span, "covers", old_id, new_id));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    edits.sort_by_key(|edit| edit.span.start);
+    Ok(edits)
+}
+
+/// Spans of the calls to `callee`, in bodies and tests alike
+struct CallSpans<'a> {
+    callee: &'a str,
+    spans: Vec<Span>,
+}
+
+impl Visitor for CallSpans<'_> {
+    fn visit_call(&mut self, call: &CallStep) {
+        if call.fn_name == self.callee {
+            self.spans.push(call.span);
+        }
+        walk_call(self, call);
+    }
+}
+
+/// Edit replacing the first `attr="value"` inside `within` with `new`
+///
+/// A node's own attributes precede any nested nodes, so the first match
+/// belongs to the node itself.
+fn attribute_value_edit(source: &str, within: Span, attr: &str, value: &str, new: &str) -> Option<TextEdit> {
+    let end = within.end.min(source.len());
+    let needle = format!("{}=\"{}\"", attr, value);
+    let found = source.get(within.start..end)?.find(&needle)?;
+    let start = within.start + found + attr.len() + 2;
+    Some(TextEdit::replace(Span::new(start, start + value.len()), new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_symbol_graph;
+
+    const SOURCE: &str = r#"snippet id="math.double" kind="fn"
+signature
+  fn name="double"
+    param name="x" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="compute"
+    op=add
+    input var="x"
+    input var="x"
+    as="result"
+  end
+  step id="s2" kind="return"
+    from="result"
+    as="_"
+  end
+end
+end
+
+snippet id="math.quad" kind="fn"
+signature
+  fn name="quad"
+    param name="x" type="Int"
+    returns type="Int"
+  end
+end
+body
+  step id="s1" kind="call"
+    fn="math.double"
+    arg name="x" from="x"
+    as="twice"
+  end
+  step id="s2" kind="if"
+    condition="positive"
+    then
+      step id="s2a" kind="call"
+        fn="math.double"
+        arg name="x" from="twice"
+        as="result"
+      end
+    end
+    as="_"
+  end
+  step id="s3" kind="return"
+    from="twice"
+    as="_"
+  end
+end
+relations
+  rel to="math.double" type=uses
+end
+end
+
+snippet id="math.tests" kind="test"
+tests
+  test id="T-1" kind="unit" covers="math.double"
+    step id="t1" kind="call"
+      fn="math.double"
+      arg name="x" lit=2
+      as="four"
+    end
+  end
+end
+end
+"#;
+
+    /// `source` with `edits` applied, which must not overlap
+    fn apply(source: &str, edits: &[TextEdit]) -> String {
+        let mut result = source.to_string();
+        for edit in edits.iter().rev() {
+            result.replace_range(edit.span.start..edit.span.end, &edit.new_text);
+        }
+        result
+    }
+
+    #[test]
+    fn test_rename_updates_every_reference() {
+        let program = covenant_parser::parse(SOURCE).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+        let edits = rename(&graph, &program, SOURCE, "math.double", "math.twice").unwrap();
+        assert_eq!(edits.len(), 6);
+
+        let renamed = apply(SOURCE, &edits);
+        assert!(!renamed.contains("math.double"));
+        assert!(renamed.contains("snippet id=\"math.twice\""));
+        assert_eq!(renamed.matches("fn=\"math.twice\"").count(), 3);
+        assert!(renamed.contains("rel to=\"math.twice\""));
+        assert!(renamed.contains("covers=\"math.twice\""));
+
+        let program = covenant_parser::parse(&renamed).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+        let mut callers = graph.callers_of("math.twice");
+        callers.sort();
+        assert_eq!(callers, vec!["math.quad", "math.tests"]);
+    }
+
+    #[test]
+    fn test_rename_rejects_unknown_and_taken_names() {
+        let program = covenant_parser::parse(SOURCE).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+        assert_eq!(
+            rename(&graph, &program, SOURCE, "math.double", "math.quad"),
+            Err(RenameError::NameTaken("math.quad".to_string()))
+        );
+        assert_eq!(
+            rename(&graph, &program, SOURCE, "math.half", "math.third"),
+            Err(RenameError::UnknownSymbol("math.half".to_string()))
+        );
+    }
+}
@@ -1,8 +1,9 @@
 //! Pass 2: Backward reference resolution
 //!
-//! Computes called_by, referenced_by, and relations_from from forward references.
+//! Computes called_by, referenced_by, relations_from, publisher/subscriber
+//! and interface/implementation edges from forward references.
 
-use crate::{RelationRef, SymbolError, SymbolGraph, SymbolId};
+use crate::{RelationRef, SymbolError, SymbolGraph, SymbolId, SymbolKind};
 use std::collections::HashSet;
 
 /// Inverse relation type mapping
@@ -41,88 +42,337 @@ pub struct BackwardResolver;
 impl BackwardResolver {
     /// Compute backward references for all symbols
     pub fn resolve(graph: &mut SymbolGraph) -> Vec<SymbolError> {
+        let ids: Vec<SymbolId> = graph.iter().map(|s| s.id).collect();
+        Self::link(graph, &ids)
+    }
+
+    /// Add the backward references of the forward references of `ids`
+    /// (sorted), and of the events they publish or subscribe to
+    pub fn link(graph: &mut SymbolGraph, ids: &[SymbolId]) -> Vec<SymbolError> {
         let mut errors = Vec::new();
+        for &id in ids {
+            errors.extend(Self::link_references(graph, id));
+        }
 
-        // Collect all symbol IDs and their forward references
-        // We need to collect first to avoid borrow issues
-        let forwards: Vec<(
-            SymbolId,
-            String,
-            HashSet<String>,
-            HashSet<String>,
-            Vec<RelationRef>,
-            covenant_ast::Span,
-        )> = graph
-            .iter()
-            .map(|s| {
-                (
-                    s.id,
-                    s.name.clone(),
-                    s.calls.clone(),
-                    s.references.clone(),
-                    s.relations_to.clone(),
-                    s.span,
-                )
-            })
-            .collect();
+        Self::resolve_events(graph, ids);
+        Self::resolve_implementations(graph, ids);
+        errors.extend(Self::resolve_members(graph, ids));
+        errors.extend(Self::validate_table_refs(graph, ids));
+        errors
+    }
 
-        // Process each symbol's forward references
-        for (caller_id, caller_name, calls, references, relations, span) in forwards {
-            // Resolve calls -> called_by
-            for callee_name in &calls {
-                if let Some(callee_id) = graph.id_of(callee_name) {
-                    if let Some(callee_mut) = graph.get_mut(callee_id) {
-                        callee_mut.called_by.insert(caller_id);
-                    }
-                } else {
-                    // Mark as unresolved (soft error)
-                    if let Some(caller_mut) = graph.get_mut(caller_id) {
-                        caller_mut.unresolved_calls.insert(callee_name.clone());
+    /// Remove every backward reference `link` added for `ids`, and their
+    /// unresolved references, so they can be linked again
+    pub fn unlink(graph: &mut SymbolGraph, ids: &[SymbolId]) {
+        for &id in ids {
+            let Some(symbol) = graph.get(id) else { continue };
+            let name = symbol.name.clone();
+            let calls: Vec<SymbolId> = symbol.calls.iter().filter_map(|c| graph.id_of(c)).collect();
+            let references: Vec<SymbolId> = symbol
+                .references
+                .iter()
+                .filter_map(|r| graph.resolve_type(r).or_else(|| graph.resolve_database(r)))
+                .collect();
+            let embeds: Vec<SymbolId> = symbol.embeds.iter().filter_map(|e| graph.resolve_type(e)).collect();
+            let relations: Vec<SymbolId> = symbol.relations_to.iter().filter_map(|r| graph.id_of(&r.target)).collect();
+            let notifies: Vec<SymbolId> = symbol.notifies.iter().copied().collect();
+            let notified_by: Vec<SymbolId> = symbol.notified_by.iter().copied().collect();
+            let interface = symbol.implements.as_ref().and_then(|i| graph.id_of(i));
+            let members = symbol.members.clone();
+
+            for callee in calls {
+                if let Some(callee) = graph.get_mut(callee) {
+                    callee.called_by.remove(&id);
+                }
+            }
+            for target in references {
+                if let Some(target) = graph.get_mut(target) {
+                    target.referenced_by.remove(&id);
+                }
+            }
+            for target in embeds {
+                if let Some(target) = graph.get_mut(target) {
+                    target.embedded_by.remove(&id);
+                }
+            }
+            for target in relations {
+                if let Some(target) = graph.get_mut(target) {
+                    target.relations_from.retain(|r| r.target != name);
+                }
+            }
+            for subscriber in notifies {
+                if let Some(subscriber) = graph.get_mut(subscriber) {
+                    subscriber.notified_by.remove(&id);
+                }
+            }
+            for publisher in notified_by {
+                if let Some(publisher) = graph.get_mut(publisher) {
+                    publisher.notifies.remove(&id);
+                }
+            }
+            if let Some(interface) = interface.and_then(|i| graph.get_mut(i)) {
+                interface.implemented_by.remove(&id);
+            }
+            for member in members {
+                if let Some(member) = graph.get_by_name_mut(&member) {
+                    if member.parent_module.as_ref() == Some(&name) {
+                        member.parent_module = None;
                     }
-                    errors.push(SymbolError::UndefinedReference {
-                        name: callee_name.clone(),
-                        span,
-                        referrer: caller_name.clone(),
+                }
+            }
+
+            if let Some(symbol) = graph.get_mut(id) {
+                symbol.notifies.clear();
+                symbol.notified_by.clear();
+                symbol.unresolved_calls.clear();
+                symbol.unresolved_references.clear();
+            }
+        }
+    }
+
+    /// Resolve one symbol's calls, type references, embedded types and
+    /// relations into backward references on their targets
+    fn link_references(graph: &mut SymbolGraph, caller_id: SymbolId) -> Vec<SymbolError> {
+        let mut errors = Vec::new();
+        let Some(symbol) = graph.get(caller_id) else {
+            return errors;
+        };
+        let caller_name = symbol.name.clone();
+        let calls = symbol.calls.clone();
+        let references = symbol.references.clone();
+        let embeds = symbol.embeds.clone();
+        let relations = symbol.relations_to.clone();
+        let span = symbol.span;
+
+        // Resolve calls -> called_by
+        for callee_name in &calls {
+            if let Some(callee_id) = graph.id_of(callee_name) {
+                if let Some(callee_mut) = graph.get_mut(callee_id) {
+                    callee_mut.called_by.insert(caller_id);
+                }
+            } else {
+                // Mark as unresolved (soft error)
+                if let Some(caller_mut) = graph.get_mut(caller_id) {
+                    caller_mut.unresolved_calls.insert(callee_name.clone());
+                }
+                errors.push(SymbolError::UndefinedReference {
+                    name: callee_name.clone(),
+                    span,
+                    referrer: caller_name.clone(),
+                });
+            }
+        }
+
+        // Resolve references -> referenced_by
+        for ref_name in &references {
+            let resolved = graph
+                .resolve_type(ref_name)
+                .or_else(|| graph.resolve_database(ref_name));
+            if let Some(ref_id) = resolved {
+                if let Some(ref_mut) = graph.get_mut(ref_id) {
+                    ref_mut.referenced_by.insert(caller_id);
+                }
+            } else {
+                // Mark as unresolved (soft error)
+                if let Some(caller_mut) = graph.get_mut(caller_id) {
+                    caller_mut.unresolved_references.insert(ref_name.clone());
+                }
+                errors.push(SymbolError::UndefinedReference {
+                    name: ref_name.clone(),
+                    span,
+                    referrer: caller_name.clone(),
+                });
+            }
+        }
+
+        // Resolve embedded field types -> embedded_by
+        for type_name in &embeds {
+            if let Some(type_id) = graph.resolve_type(type_name) {
+                if type_id == caller_id {
+                    continue;
+                }
+                if let Some(type_mut) = graph.get_mut(type_id) {
+                    type_mut.embedded_by.insert(caller_id);
+                }
+            }
+            // Unresolved field types are already reported via references
+        }
+
+        // Resolve relations -> relations_from (with inverse types)
+        for rel in &relations {
+            if let Some(target_id) = graph.id_of(&rel.target) {
+                let inverse_type = get_inverse_relation(&rel.relation_type);
+
+                if let Some(target_mut) = graph.get_mut(target_id) {
+                    target_mut.relations_from.push(RelationRef {
+                        target: caller_name.clone(),
+                        relation_type: inverse_type,
+                        direction: covenant_ast::RelationKind::From,
                     });
                 }
+            } else {
+                errors.push(SymbolError::RelationTargetNotFound {
+                    target: rel.target.clone(),
+                    span,
+                    from_symbol: caller_name.clone(),
+                });
             }
+        }
 
-            // Resolve references -> referenced_by
-            for ref_name in &references {
-                if let Some(ref_id) = graph.id_of(ref_name) {
-                    if let Some(ref_mut) = graph.get_mut(ref_id) {
-                        ref_mut.referenced_by.insert(caller_id);
-                    }
-                } else {
-                    // Mark as unresolved (soft error)
-                    if let Some(caller_mut) = graph.get_mut(caller_id) {
-                        caller_mut.unresolved_references.insert(ref_name.clone());
-                    }
+        errors
+    }
+
+    /// Connect each publisher to the subscribers of the events it publishes
+    ///
+    /// Event types match by resolved struct symbol, so `OrderPlaced` and
+    /// `orders.OrderPlaced` name the same event. Unresolvable event types are
+    /// already reported via references. Only pairs with a publisher or
+    /// subscriber among `ids` are connected.
+    fn resolve_events(graph: &mut SymbolGraph, ids: &[SymbolId]) {
+        let region: HashSet<SymbolId> = ids.iter().copied().collect();
+        let subscribers: Vec<(SymbolId, SymbolId)> = graph
+            .iter()
+            .filter_map(|s| Some((s.id, graph.resolve_type(s.subscribes.as_ref()?)?)))
+            .collect();
+        let publishers: Vec<(SymbolId, Vec<SymbolId>)> = graph
+            .iter()
+            .filter(|s| !s.publishes.is_empty())
+            .map(|s| (s.id, s.publishes.iter().filter_map(|e| graph.resolve_type(e)).collect()))
+            .collect();
+
+        for (publisher, events) in &publishers {
+            for (subscriber, event) in &subscribers {
+                if !events.contains(event) || !(region.contains(publisher) || region.contains(subscriber)) {
+                    continue;
+                }
+                if let Some(p) = graph.get_mut(*publisher) {
+                    p.notifies.insert(*subscriber);
+                }
+                if let Some(s) = graph.get_mut(*subscriber) {
+                    s.notified_by.insert(*publisher);
+                }
+            }
+        }
+    }
+
+    /// Record each fn and struct snippet among `ids` that implements an
+    /// interface
+    ///
+    /// Interfaces match by snippet ID. Missing interfaces are already
+    /// reported via references; whether the target is an interface and the
+    /// signatures conform is checked by the type checker.
+    fn resolve_implementations(graph: &mut SymbolGraph, ids: &[SymbolId]) {
+        let implementations: Vec<(SymbolId, SymbolId)> = ids
+            .iter()
+            .filter_map(|id| graph.get(*id))
+            .filter(|s| s.kind != SymbolKind::ExternImpl)
+            .filter_map(|s| Some((s.id, graph.id_of(s.implements.as_ref()?)?)))
+            .collect();
+
+        for (implementation, interface) in implementations {
+            if let Some(i) = graph.get_mut(interface) {
+                if i.kind == SymbolKind::Interface {
+                    i.implemented_by.insert(implementation);
+                }
+            }
+        }
+    }
+
+    /// Record the module each `contains` member of the modules among `ids`
+    /// belongs to
+    ///
+    /// Missing members are undefined references. A snippet belongs to at
+    /// most one module, and modules may not contain each other in a cycle.
+    fn resolve_members(graph: &mut SymbolGraph, ids: &[SymbolId]) -> Vec<SymbolError> {
+        let mut errors = Vec::new();
+
+        let modules: Vec<(String, Vec<String>, covenant_ast::Span)> = ids
+            .iter()
+            .filter_map(|id| graph.get(*id))
+            .filter(|s| !s.members.is_empty())
+            .map(|s| (s.name.clone(), s.members.clone(), s.span))
+            .collect();
+
+        for (module, members, span) in &modules {
+            for member in members {
+                let Some(symbol) = graph.get_by_name_mut(member) else {
                     errors.push(SymbolError::UndefinedReference {
-                        name: ref_name.clone(),
-                        span,
-                        referrer: caller_name.clone(),
+                        name: member.clone(),
+                        span: *span,
+                        referrer: module.clone(),
                     });
+                    continue;
+                };
+                match &symbol.parent_module {
+                    Some(first) if first != module => errors.push(SymbolError::MultipleModules {
+                        member: member.clone(),
+                        first: first.clone(),
+                        second: module.clone(),
+                        span: *span,
+                    }),
+                    _ => symbol.parent_module = Some(module.clone()),
                 }
             }
+        }
 
-            // Resolve relations -> relations_from (with inverse types)
-            for rel in &relations {
-                if let Some(target_id) = graph.id_of(&rel.target) {
-                    let inverse_type = get_inverse_relation(&rel.relation_type);
-
-                    if let Some(target_mut) = graph.get_mut(target_id) {
-                        target_mut.relations_from.push(RelationRef {
-                            target: caller_name.clone(),
-                            relation_type: inverse_type,
-                            direction: covenant_ast::RelationKind::From,
+        // Walk up from each module; report each cycle once, as walked from
+        // its smallest name
+        let mut reported: HashSet<String> = HashSet::new();
+        for (module, _, _) in &modules {
+            let mut chain = vec![module.clone()];
+            let mut current = graph.get_by_name(module).and_then(|s| s.parent_module.clone());
+            while let Some(parent) = current {
+                if parent == *module {
+                    let smallest = (0..chain.len()).min_by_key(|&i| &chain[i]).unwrap_or(0);
+                    chain.rotate_left(smallest);
+                    let first = chain[0].clone();
+                    if reported.insert(first.clone()) {
+                        let span = graph.get_by_name(&first).map(|s| s.span).unwrap_or_else(covenant_ast::Span::dummy);
+                        chain.push(first);
+                        chain.reverse();
+                        errors.push(SymbolError::CircularImport {
+                            cycle: chain.join(" -> "),
+                            span,
                         });
                     }
-                } else {
-                    errors.push(SymbolError::RelationTargetNotFound {
-                        target: rel.target.clone(),
-                        span,
-                        from_symbol: caller_name.clone(),
+                    break;
+                }
+                if chain.contains(&parent) {
+                    break;
+                }
+                current = graph.get_by_name(&parent).and_then(|s| s.parent_module.clone());
+                chain.push(parent);
+            }
+        }
+
+        errors
+    }
+
+    /// Check the query sources and foreign keys of `ids` against declared
+    /// database schemas
+    ///
+    /// Databases without a `schema` section declare no tables and are not
+    /// validated; unresolvable databases are already reported as undefined
+    /// references.
+    fn validate_table_refs(graph: &SymbolGraph, ids: &[SymbolId]) -> Vec<SymbolError> {
+        let mut errors = Vec::new();
+
+        for symbol in ids.iter().filter_map(|id| graph.get(*id)) {
+            for table_ref in &symbol.table_refs {
+                let Some(database) = graph
+                    .resolve_database(&table_ref.database)
+                    .and_then(|id| graph.get(id))
+                    .filter(|db| db.kind == SymbolKind::Database && !db.tables.is_empty())
+                else {
+                    continue;
+                };
+
+                if !database.tables.contains_key(&table_ref.table) {
+                    errors.push(SymbolError::UnknownTable {
+                        table: table_ref.table.clone(),
+                        database: database.name.clone(),
+                        span: table_ref.span,
+                        referrer: symbol.name.clone(),
                     });
                 }
             }
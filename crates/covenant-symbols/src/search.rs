@@ -0,0 +1,242 @@
+//! Workspace symbol search
+//!
+//! Matches a pattern against symbol ids (e.g., "app.users.get_user") and
+//! short names (the last dotted segment, e.g., "get_user"). Patterns that
+//! contain `*` or `?` are treated as globs; anything else is matched as a
+//! case-insensitive fuzzy subsequence. Results are ranked best-first.
+
+use crate::{SymbolGraph, SymbolId, SymbolKind};
+use serde::{Deserialize, Serialize};
+
+/// How a search result matched the pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchKind {
+    /// Pattern equals the id or short name
+    Exact,
+    /// Id or short name starts with the pattern
+    Prefix,
+    /// Pattern occurs contiguously inside the id or short name
+    Substring,
+    /// Glob pattern matched the id or short name
+    Glob,
+    /// Pattern characters occur in order, possibly with gaps
+    Fuzzy,
+}
+
+/// A single ranked search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchMatch {
+    /// ID of the matched symbol
+    pub id: SymbolId,
+    /// The symbol's snippet ID
+    pub name: String,
+    /// Kind of the matched symbol
+    pub kind: SymbolKind,
+    /// How the pattern matched
+    pub match_kind: MatchKind,
+    /// Ranking score (higher is better)
+    pub score: u32,
+}
+
+const SCORE_EXACT: u32 = 1000;
+const SCORE_PREFIX: u32 = 800;
+const SCORE_SUBSTRING: u32 = 600;
+const SCORE_GLOB: u32 = 500;
+const SCORE_FUZZY_MAX: u32 = 499;
+
+impl SymbolGraph {
+    /// Search symbols by glob or fuzzy pattern, returning ranked matches
+    ///
+    /// An empty pattern matches nothing. Ties are broken by symbol name so
+    /// the output is deterministic.
+    pub fn search(&self, pattern: &str) -> Vec<SearchMatch> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Vec::new();
+        }
+
+        let pattern = pattern.to_lowercase();
+        let is_glob = pattern.contains('*') || pattern.contains('?');
+
+        let mut matches: Vec<SearchMatch> = self
+            .iter()
+            .filter_map(|symbol| {
+                let full = symbol.name.to_lowercase();
+                let short = short_name(&full);
+
+                let best = if is_glob {
+                    [full.as_str(), short]
+                        .iter()
+                        .any(|candidate| glob_match(&pattern, candidate))
+                        .then_some((MatchKind::Glob, SCORE_GLOB))
+                } else {
+                    let a = rank(&pattern, &full);
+                    let b = rank(&pattern, short);
+                    match (a, b) {
+                        (Some(a), Some(b)) => Some(if b.1 > a.1 { b } else { a }),
+                        (a, b) => a.or(b),
+                    }
+                };
+
+                best.map(|(match_kind, score)| SearchMatch {
+                    id: symbol.id,
+                    name: symbol.name.clone(),
+                    kind: symbol.kind,
+                    match_kind,
+                    score,
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+        matches
+    }
+}
+
+/// The last dotted segment of a snippet ID
+fn short_name(id: &str) -> &str {
+    id.rsplit('.').next().unwrap_or(id)
+}
+
+/// Rank a non-glob pattern against a single candidate string
+fn rank(pattern: &str, candidate: &str) -> Option<(MatchKind, u32)> {
+    // Shorter candidates rank higher within the same tier
+    let slack = (candidate.len() - pattern.len().min(candidate.len())).min(99) as u32;
+
+    if candidate == pattern {
+        Some((MatchKind::Exact, SCORE_EXACT))
+    } else if candidate.starts_with(pattern) {
+        Some((MatchKind::Prefix, SCORE_PREFIX - slack))
+    } else if candidate.contains(pattern) {
+        Some((MatchKind::Substring, SCORE_SUBSTRING - slack))
+    } else {
+        fuzzy_score(pattern, candidate).map(|score| (MatchKind::Fuzzy, score))
+    }
+}
+
+/// Score a subsequence match, rewarding consecutive runs and word starts
+fn fuzzy_score(pattern: &str, candidate: &str) -> Option<u32> {
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut score: u32 = 0;
+    let mut pos = 0;
+    let mut prev_match: Option<usize> = None;
+
+    for pc in pattern.chars() {
+        let found = (pos..chars.len()).find(|&i| chars[i] == pc)?;
+
+        score += 1;
+        if prev_match == Some(found.wrapping_sub(1)) {
+            score += 5;
+        }
+        if found == 0 || matches!(chars[found - 1], '.' | '_' | '-') {
+            score += 3;
+        }
+
+        prev_match = Some(found);
+        pos = found + 1;
+    }
+
+    let gaps = (chars.len() - pattern.chars().count()) as u32;
+    Some((100 + score * 4).saturating_sub(gaps).clamp(1, SCORE_FUZZY_MAX))
+}
+
+/// Match a glob pattern (`*` = any run, `?` = any single char)
+fn glob_match(pattern: &str, candidate: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let c: Vec<char> = candidate.chars().collect();
+
+    let (mut pi, mut ci) = (0, 0);
+    let mut star: Option<usize> = None;
+    let mut star_ci = 0;
+
+    while ci < c.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == c[ci]) {
+            pi += 1;
+            ci += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            star_ci = ci;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            star_ci += 1;
+            ci = star_ci;
+        } else {
+            return false;
+        }
+    }
+
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SymbolInfo;
+    use covenant_ast::Span;
+
+    fn graph_of(names: &[&str]) -> SymbolGraph {
+        let mut graph = SymbolGraph::new();
+        for name in names {
+            graph
+                .insert(SymbolInfo::new(name.to_string(), SymbolKind::Function, Span::dummy()))
+                .unwrap();
+        }
+        graph
+    }
+
+    fn names(matches: &[SearchMatch]) -> Vec<&str> {
+        matches.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("app.*", "app.main"));
+        assert!(glob_match("*.get_?ser", "db.get_user"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("app.*", "lib.main"));
+        assert!(!glob_match("get_?", "get_user"));
+    }
+
+    #[test]
+    fn test_exact_ranks_above_prefix_and_fuzzy() {
+        let graph = graph_of(&["app.get_user", "app.get_user_by_email", "app.greet_user"]);
+        let results = graph.search("get_user");
+
+        assert_eq!(
+            names(&results),
+            vec!["app.get_user", "app.get_user_by_email", "app.greet_user"]
+        );
+        assert_eq!(results[0].match_kind, MatchKind::Exact);
+        assert_eq!(results[1].match_kind, MatchKind::Prefix);
+        assert_eq!(results[2].match_kind, MatchKind::Fuzzy);
+    }
+
+    #[test]
+    fn test_fuzzy_subsequence() {
+        let graph = graph_of(&["db.insert_order", "db.delete_order", "ui.render"]);
+        let results = graph.search("insord");
+
+        assert_eq!(names(&results), vec!["db.insert_order"]);
+        assert_eq!(results[0].match_kind, MatchKind::Fuzzy);
+    }
+
+    #[test]
+    fn test_glob_search_over_ids() {
+        let graph = graph_of(&["app.main", "app.util.helper", "lib.main"]);
+        let results = graph.search("app.*");
+
+        assert_eq!(names(&results), vec!["app.main", "app.util.helper"]);
+        assert!(results.iter().all(|m| m.match_kind == MatchKind::Glob));
+    }
+
+    #[test]
+    fn test_case_insensitive_and_empty() {
+        let graph = graph_of(&["types.User"]);
+        assert_eq!(names(&graph.search("user")), vec!["types.User"]);
+        assert!(graph.search("   ").is_empty());
+    }
+}
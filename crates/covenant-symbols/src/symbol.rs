@@ -1,17 +1,20 @@
 //! Symbol information and types
 
-use covenant_ast::{EffectDecl, Span, SnippetKind};
+use covenant_ast::{CallSite, EffectDecl, Span, SnippetKind};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
 
 /// Unique identifier for a symbol in the graph
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct SymbolId(pub u32);
 
 /// Kind of symbol
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SymbolKind {
     Function,
+    /// Function whose effectful steps are checkpointed
+    Workflow,
     Struct,
     Enum,
     Module,
@@ -23,12 +26,21 @@ pub enum SymbolKind {
     ExternImpl,
     Test,
     Data,
+    /// Handler bound to an event type
+    Subscriber,
+    /// Typed compile-time constant
+    Const,
+    /// Method signatures implemented by fn and struct snippets
+    Interface,
+    /// Another name for a type
+    Alias,
 }
 
 impl From<SnippetKind> for SymbolKind {
     fn from(kind: SnippetKind) -> Self {
         match kind {
             SnippetKind::Function => SymbolKind::Function,
+            SnippetKind::Workflow => SymbolKind::Workflow,
             SnippetKind::Struct => SymbolKind::Struct,
             SnippetKind::Enum => SymbolKind::Enum,
             SnippetKind::Module => SymbolKind::Module,
@@ -38,6 +50,10 @@ impl From<SnippetKind> for SymbolKind {
             SnippetKind::ExternImpl => SymbolKind::ExternImpl,
             SnippetKind::Test => SymbolKind::Test,
             SnippetKind::Data => SymbolKind::Data,
+            SnippetKind::Subscriber => SymbolKind::Subscriber,
+            SnippetKind::Const => SymbolKind::Const,
+            SnippetKind::Interface => SymbolKind::Interface,
+            SnippetKind::Alias => SymbolKind::Alias,
         }
     }
 }
@@ -53,6 +69,17 @@ pub struct RelationRef {
     pub direction: covenant_ast::RelationKind,
 }
 
+/// A reference to a table that must exist in a database's schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableRef {
+    /// Database the table belongs to (a query `target=` or the schema's own ID)
+    pub database: String,
+    /// Table name
+    pub table: String,
+    /// Source location of the referring query or field
+    pub span: Span,
+}
+
 /// Information about a symbol extracted from the AST
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolInfo {
@@ -68,10 +95,19 @@ pub struct SymbolInfo {
     /// Source location
     pub span: Span,
 
+    /// File the snippet came from, for graphs built by a [`crate::Project`]
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+
     // === Forward References (extracted in Pass 1) ===
     /// Function/method calls this symbol makes
     pub calls: HashSet<String>,
 
+    /// Where each of `calls` is made, in source order; calls made outside
+    /// a step (a subscriber's handler) have no sites
+    #[serde(default)]
+    pub call_sites: BTreeMap<String, Vec<CallSite>>,
+
     /// Type references (parameter types, return types, field types)
     pub references: HashSet<String>,
 
@@ -81,6 +117,27 @@ pub struct SymbolInfo {
     /// Relations declared (from relations section)
     pub relations_to: Vec<RelationRef>,
 
+    /// Types embedded as struct fields or enum variant fields, or named by
+    /// an alias
+    pub embeds: HashSet<String>,
+
+    /// For module symbols: member snippet IDs (from contains section)
+    pub members: Vec<String>,
+
+    /// Event types published via `events.publish` with a literal event type
+    pub publishes: HashSet<String>,
+
+    /// For function symbols: generic param names, in declaration order
+    pub generics: Vec<String>,
+
+    /// For subscriber symbols: the event type (from subscribe section)
+    pub subscribes: Option<String>,
+
+    /// The group named by `allow cycle group=` in the metadata, whose
+    /// members may call each other recursively
+    #[serde(default)]
+    pub cycle_group: Option<String>,
+
     // === Backward References (computed in Pass 2) ===
     /// Symbols that call this symbol
     pub called_by: HashSet<SymbolId>,
@@ -91,6 +148,21 @@ pub struct SymbolInfo {
     /// Inverse relations (computed from relations_to)
     pub relations_from: Vec<RelationRef>,
 
+    /// Types that embed this type as a field
+    pub embedded_by: HashSet<SymbolId>,
+
+    /// Module symbol whose contains section lists this symbol
+    pub parent_module: Option<String>,
+
+    /// Subscribers to events this symbol publishes
+    pub notifies: HashSet<SymbolId>,
+
+    /// Publishers of the event this subscriber listens for
+    pub notified_by: HashSet<SymbolId>,
+
+    /// For interfaces: the fn and struct snippets that implement it
+    pub implemented_by: HashSet<SymbolId>,
+
     // === Requirements & Tests (extracted in Pass 1) ===
     /// Requirements declared in this snippet (from requires section)
     pub requirements: Vec<String>,
@@ -108,12 +180,30 @@ pub struct SymbolInfo {
     /// Unresolved type references (for deferred error handling)
     pub unresolved_references: HashSet<String>,
 
-    // === Platform Abstraction (for extern-abstract and extern-impl) ===
-    /// For extern-impl: the abstract snippet ID this implements
+    // === Platform Abstraction and Interfaces ===
+    /// For extern-impl: the abstract snippet ID this implements; for fn,
+    /// workflow and struct symbols: the interface they implement
     pub implements: Option<String>,
 
     /// For extern-impl: the target platform
     pub target_platform: Option<String>,
+
+    // === Database Schema ===
+    /// For database symbols: declared tables and their field names
+    pub tables: BTreeMap<String, Vec<String>>,
+
+    /// Tables this symbol expects to exist (query sources, foreign keys)
+    pub table_refs: Vec<TableRef>,
+
+    // === Documentation ===
+    /// The snippet's `doc` block
+    pub doc: Option<String>,
+
+    /// Signature param docs, by param name
+    pub param_docs: BTreeMap<String, String>,
+
+    /// Body step docs (nested steps included), by step ID
+    pub step_docs: BTreeMap<String, String>,
 }
 
 impl SymbolInfo {
@@ -124,13 +214,26 @@ impl SymbolInfo {
             name,
             kind,
             span,
+            file: None,
             calls: HashSet::new(),
+            call_sites: BTreeMap::new(),
             references: HashSet::new(),
             declared_effects: Vec::new(),
             relations_to: Vec::new(),
+            embeds: HashSet::new(),
+            members: Vec::new(),
+            publishes: HashSet::new(),
+            generics: Vec::new(),
+            subscribes: None,
+            cycle_group: None,
             called_by: HashSet::new(),
             referenced_by: HashSet::new(),
             relations_from: Vec::new(),
+            embedded_by: HashSet::new(),
+            parent_module: None,
+            notifies: HashSet::new(),
+            notified_by: HashSet::new(),
+            implemented_by: HashSet::new(),
             requirements: Vec::new(),
             tests: Vec::new(),
             covers: Vec::new(),
@@ -138,20 +241,25 @@ impl SymbolInfo {
             unresolved_references: HashSet::new(),
             implements: None,
             target_platform: None,
+            tables: BTreeMap::new(),
+            table_refs: Vec::new(),
+            doc: None,
+            param_docs: BTreeMap::new(),
+            step_docs: BTreeMap::new(),
         }
     }
 
-    /// Check if this symbol is a callable (function or extern)
+    /// Check if this symbol is a callable (function, workflow or extern)
     pub fn is_callable(&self) -> bool {
         matches!(
             self.kind,
-            SymbolKind::Function | SymbolKind::Extern | SymbolKind::ExternAbstract
+            SymbolKind::Function | SymbolKind::Workflow | SymbolKind::Extern | SymbolKind::ExternAbstract
         )
     }
 
     /// Check if this symbol is a type definition
     pub fn is_type(&self) -> bool {
-        matches!(self.kind, SymbolKind::Struct | SymbolKind::Enum)
+        matches!(self.kind, SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Alias)
     }
 
     /// Check if this symbol has any unresolved references
@@ -0,0 +1,38 @@
+//! Fixtures shared by the unit tests
+
+use covenant_ast::{Program, Snippet};
+
+use crate::{build_symbol_graph, SymbolGraph};
+
+/// The snippets of a source file
+pub(crate) fn snippets(source: &str) -> Vec<Snippet> {
+    match covenant_parser::parse(source).unwrap() {
+        Program::Snippets { snippets, .. } => snippets,
+        _ => panic!("expected snippets"),
+    }
+}
+
+/// A function snippet calling each of `callees` in turn, then returning 1
+pub(crate) fn func(id: &str, callees: &[&str]) -> String {
+    func_returning(id, callees, 1)
+}
+
+/// A function snippet calling each of `callees` in turn, then returning `lit`
+pub(crate) fn func_returning(id: &str, callees: &[&str], lit: i64) -> String {
+    let calls: String = callees
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("  step id=\"c{i}\" kind=\"call\"\n    fn=\"{c}\"\n    as=\"r{i}\"\n  end\n"))
+        .collect();
+    let name = id.rsplit('.').next().unwrap_or(id);
+    format!(
+        "snippet id=\"{id}\" kind=\"fn\"\nsignature\n  fn name=\"{name}\"\n    returns type=\"Int\"\n  end\nend\nbody\n{calls}  step id=\"s\" kind=\"return\"\n    lit={lit}\n    as=\"_\"\n  end\nend\nend\n"
+    )
+}
+
+
+/// The symbol graph of functions given as `(id, callees)`
+pub(crate) fn graph(funcs: &[(&str, &[&str])]) -> SymbolGraph {
+    let source: String = funcs.iter().map(|(id, callees)| func(id, callees)).collect();
+    build_symbol_graph(&covenant_parser::parse(&source).unwrap()).unwrap().graph
+}
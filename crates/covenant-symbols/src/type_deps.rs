@@ -0,0 +1,241 @@
+//! Type-centric queries over the symbol graph
+//!
+//! Answers "who uses this type?" via `referenced_by`, "who embeds this
+//! type?" via the struct-field `embedded_by` edges, and orders types so
+//! every type comes after the types it embeds.
+
+use crate::{SymbolGraph, SymbolId, SymbolInfo};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Topological ordering of type definitions
+#[derive(Debug, Clone, Default)]
+pub struct TypeOrder {
+    /// Type names, dependencies first
+    pub order: Vec<String>,
+    /// Types that participate in an embedding cycle (recursive types),
+    /// sorted by name. These are not included in `order`.
+    pub cyclic: Vec<String>,
+}
+
+impl SymbolGraph {
+    /// Functions (and other callables) whose signatures or bodies reference a type
+    pub fn functions_referencing(&self, type_name: &str) -> Vec<&SymbolInfo> {
+        self.related_by(type_name, |s| &s.referenced_by, SymbolInfo::is_callable)
+    }
+
+    /// Types that embed the given type as a struct or variant field
+    pub fn types_embedding(&self, type_name: &str) -> Vec<&SymbolInfo> {
+        self.related_by(type_name, |s| &s.embedded_by, SymbolInfo::is_type)
+    }
+
+    /// Types embedded directly by the given type, resolved to symbols
+    pub fn embedded_types_of(&self, type_name: &str) -> Vec<&SymbolInfo> {
+        let mut result: Vec<&SymbolInfo> = self
+            .resolve_type(type_name)
+            .and_then(|id| self.get(id))
+            .map(|s| {
+                s.embeds
+                    .iter()
+                    .filter_map(|name| self.resolve_type(name))
+                    .filter(|id| *id != s.id)
+                    .filter_map(|id| self.get(id))
+                    .collect()
+            })
+            .unwrap_or_default();
+        result.sort_by(|a, b| a.name.cmp(&b.name));
+        result.dedup_by(|a, b| a.id == b.id);
+        result
+    }
+
+    /// Order all type symbols so that embedded types precede their embedders
+    ///
+    /// Uses Kahn's algorithm with name-ordered tie breaking so the result is
+    /// stable across runs.
+    pub fn type_dependency_order(&self) -> TypeOrder {
+        let types: Vec<&SymbolInfo> = self.types().collect();
+
+        // Number of not-yet-emitted types each type embeds
+        let mut pending: HashMap<SymbolId, usize> = HashMap::new();
+        for ty in &types {
+            let deps: BTreeSet<SymbolId> = ty
+                .embeds
+                .iter()
+                .filter_map(|name| self.resolve_type(name))
+                .filter(|id| *id != ty.id)
+                .collect();
+            pending.insert(ty.id, deps.len());
+        }
+
+        let mut ready: BTreeSet<(String, SymbolId)> = types
+            .iter()
+            .filter(|t| pending[&t.id] == 0)
+            .map(|t| (t.name.clone(), t.id))
+            .collect();
+
+        let mut order = Vec::with_capacity(types.len());
+        while let Some(next) = ready.pop_first() {
+            let (name, id) = next;
+            order.push(name);
+
+            for dependent in &self.get(id).map(|s| &s.embedded_by).cloned().unwrap_or_default() {
+                if let Some(count) = pending.get_mut(dependent) {
+                    *count -= 1;
+                    if *count == 0 {
+                        if let Some(sym) = self.get(*dependent) {
+                            ready.insert((sym.name.clone(), sym.id));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut cyclic: Vec<String> = types
+            .iter()
+            .filter(|t| pending[&t.id] > 0)
+            .map(|t| t.name.clone())
+            .collect();
+        cyclic.sort();
+
+        TypeOrder { order, cyclic }
+    }
+
+    fn related_by<'a>(
+        &'a self,
+        type_name: &str,
+        edges: impl Fn(&SymbolInfo) -> &HashSet<SymbolId>,
+        keep: impl Fn(&SymbolInfo) -> bool,
+    ) -> Vec<&'a SymbolInfo> {
+        let Some(target) = self.resolve_type(type_name).and_then(|id| self.get(id)) else {
+            return Vec::new();
+        };
+
+        let mut result: Vec<&SymbolInfo> = edges(target)
+            .iter()
+            .filter_map(|id| self.get(*id))
+            .filter(|s| keep(s))
+            .collect();
+        result.sort_by(|a, b| a.name.cmp(&b.name));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build_symbol_graph;
+
+    const SOURCE: &str = r#"
+snippet id="types.Address" kind="struct"
+signature
+  struct name="Address"
+    field name="city" type="String"
+  end
+end
+end
+
+snippet id="types.User" kind="struct"
+signature
+  struct name="User"
+    field name="name" type="String"
+    field name="home" type="Address"
+  end
+end
+end
+
+snippet id="types.Order" kind="struct"
+signature
+  struct name="Order"
+    field name="buyer" type="User"
+    field name="ship_to" type="Address"
+  end
+end
+end
+
+snippet id="app.get_user" kind="fn"
+signature
+  fn name="get_user"
+    param name="id" type="Int"
+    returns type="User"
+  end
+end
+body
+  step id="s1" kind="return"
+    lit=none
+    as="_"
+  end
+end
+end
+"#;
+
+    fn names<'a>(symbols: &[&'a crate::SymbolInfo]) -> Vec<&'a str> {
+        symbols.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_functions_referencing_type() {
+        let program = covenant_parser::parse(SOURCE).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+
+        assert_eq!(names(&graph.functions_referencing("User")), vec!["app.get_user"]);
+        assert_eq!(names(&graph.functions_referencing("types.User")), vec!["app.get_user"]);
+        assert!(graph.functions_referencing("Address").is_empty());
+    }
+
+    #[test]
+    fn test_types_embedding_type() {
+        let program = covenant_parser::parse(SOURCE).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+
+        assert_eq!(
+            names(&graph.types_embedding("Address")),
+            vec!["types.Order", "types.User"]
+        );
+        assert_eq!(
+            names(&graph.embedded_types_of("Order")),
+            vec!["types.Address", "types.User"]
+        );
+    }
+
+    #[test]
+    fn test_type_dependency_order() {
+        let program = covenant_parser::parse(SOURCE).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+        let order = graph.type_dependency_order();
+
+        assert_eq!(order.order, vec!["types.Address", "types.User", "types.Order"]);
+        assert!(order.cyclic.is_empty());
+    }
+
+    #[test]
+    fn test_alias_references() {
+        let source = format!("{}{}", SOURCE, r#"
+snippet id="types.Buyer" kind="alias"
+signature
+  alias name="Buyer" type="User"
+end
+end
+
+snippet id="app.buyer_name" kind="fn"
+signature
+  fn name="buyer_name"
+    param name="buyer" type="Buyer"
+    returns type="String"
+  end
+end
+body
+  step id="s1" kind="return"
+    from="buyer.name"
+    as="_"
+  end
+end
+end
+"#);
+        let program = covenant_parser::parse(&source).unwrap();
+        let graph = build_symbol_graph(&program).unwrap().graph;
+
+        assert_eq!(names(&graph.functions_referencing("Buyer")), vec!["app.buyer_name"]);
+        assert_eq!(names(&graph.embedded_types_of("Buyer")), vec!["types.User"]);
+        let order = graph.type_dependency_order().order;
+        let position = |name: &str| order.iter().position(|n| n == name).unwrap();
+        assert!(position("types.User") < position("types.Buyer"));
+    }
+}
@@ -14,10 +14,11 @@
  * ```
  */
 
+import { asciiLower, asciiUpper } from "./src/locale.ts";
+
 export class CovenantQueryRunner {
   private instance: WebAssembly.Instance | null = null;
   private memory: WebAssembly.Memory | null = null;
-  private heapPtr = 0x10000; // Simple bump allocator
 
   /**
    * Read a UTF-8 string from WASM memory
@@ -33,8 +34,7 @@ export class CovenantQueryRunner {
    */
   private writeStr(s: string): bigint {
     const encoded = new TextEncoder().encode(s);
-    const ptr = this.heapPtr;
-    this.heapPtr += (encoded.length + 7) & ~7; // 8-byte aligned
+    const ptr = this.alloc(encoded.length);
     if (this.memory) {
       new Uint8Array(this.memory.buffer, ptr, encoded.length).set(encoded);
     }
@@ -62,21 +62,24 @@ export class CovenantQueryRunner {
    * Returns a fat pointer to the collection
    */
   private writeStringCollection(items: string[]): bigint {
+    if (!this.memory) return 0n;
+
+    // Write each string first: allocating may grow the memory and detach
+    // any view of it
+    const fatPtrs = items.map((item) => this.writeStr(item));
+
     // Allocate space for count + item pointers
     const headerSize = 4 + items.length * 8;
-    const headerPtr = this.heapPtr;
-    this.heapPtr += (headerSize + 7) & ~7; // 8-byte align
-
-    if (!this.memory) return 0n;
+    const headerPtr = this.alloc(headerSize);
 
     const view = new DataView(this.memory.buffer);
 
     // Write count
     view.setInt32(headerPtr, items.length, true); // little-endian
 
-    // Write each string and its fat pointer
+    // Write each fat pointer
     for (let i = 0; i < items.length; i++) {
-      const fatPtr = this.writeStr(items[i]);
+      const fatPtr = fatPtrs[i];
       const offset = headerPtr + 4 + i * 8;
       // Write i64 fat pointer as two i32s (little-endian)
       view.setInt32(offset, Number(fatPtr & 0xFFFFFFFFn), true);
@@ -98,11 +101,7 @@ export class CovenantQueryRunner {
     // Provide required imports (minimal stubs for functions we don't use)
     const imports: WebAssembly.Imports = {
       mem: {
-        alloc: (size: number): number => {
-          const ptr = this.heapPtr;
-          this.heapPtr += (size + 7) & ~7; // 8-byte aligned
-          return ptr;
-        },
+        alloc: (size: number): number => this.alloc(size),
       },
       console: {
         println: (ptr: number, len: number) => {
@@ -129,11 +128,11 @@ export class CovenantQueryRunner {
       text: {
         upper: (sPtr: number, sLen: number): bigint => {
           const s = this.readStr(sPtr, sLen);
-          return this.writeStr(s.toUpperCase());
+          return this.writeStr(asciiUpper(s));
         },
         lower: (sPtr: number, sLen: number): bigint => {
           const s = this.readStr(sPtr, sLen);
-          return this.writeStr(s.toLowerCase());
+          return this.writeStr(asciiLower(s));
         },
         trim: (sPtr: number, sLen: number): bigint => {
           const s = this.readStr(sPtr, sLen);
@@ -299,13 +298,12 @@ export class CovenantQueryRunner {
         },
         append: (collPtr: number, itemFatPtr: bigint): bigint => {
           if (!this.memory) return 0n;
-          const view = new DataView(this.memory.buffer);
-          const count = collPtr ? view.getInt32(collPtr, true) : 0;
+          const count = collPtr ? new DataView(this.memory.buffer).getInt32(collPtr, true) : 0;
 
-          // Allocate new collection
+          // Allocate new collection; the memory may grow, so view it after
           const newSize = 4 + (count + 1) * 8;
-          const newPtr = this.heapPtr;
-          this.heapPtr += (newSize + 7) & ~7;
+          const newPtr = this.alloc(newSize);
+          const view = new DataView(this.memory.buffer);
 
           // Write new count
           view.setInt32(newPtr, count + 1, true);
@@ -683,7 +681,7 @@ export class CovenantQueryRunner {
    * @returns Pointer to allocated memory
    */
   alloc(size: number): number {
-    return this.call("cov_alloc", size) as number;
+    return (this.call("cov_alloc", size) as number) >>> 0;
   }
 
   /**
@@ -1,23 +1,73 @@
 /**
  * Covenant WASM runner for Deno
  *
- * Usage: deno run --allow-read --allow-write run.deno.ts <file.wasm>
+ * Usage: deno run --allow-read --allow-write run.deno.ts <file.wasm> [--audit=<log.jsonl>] [--journal=<checkpoints.jsonl>] [--entry=<fn>] [--seed=<n>] [--otlp=<endpoint>] [--memory-stats]
  *
  * Provides the runtime imports required by Covenant-compiled WASM:
- * - mem.alloc(size) - allocate memory (simple bump allocator)
+ * - mem.alloc(size) - allocate memory from the module's heap (see src/memory.ts)
  * - console.* - console output functions
  * - text.* - string operations
  * - "std.text".* - regex operations
+ * - locale.* - locale-aware text operations (see src/locale.ts)
+ * - regex.* - regex compute ops on a linear-time engine (see src/regex.ts)
+ * - crypto.* - hashes, UUIDs and random numbers (see src/crypto.ts)
+ * - encoding.* - Base64, hex and URL encoding compute ops (see src/encoding.ts)
+ * - json_doc.* - Json compute ops (see src/json.ts)
  * - list.* - list operations
  * - fs.* - filesystem operations
  * - path.* - path operations
+ * - events.* - in-process event bus (see src/events.ts)
+ *
+ * With --audit, every effectful extern call is appended to the given file
+ * as one JSON line (see src/audit.ts).
+ *
+ * With --journal, the module runs as a workflow: calls recorded in the given
+ * checkpoint file are replayed, and each new effectful call is appended to it
+ * (see src/workflow.ts).
+ *
+ * With --entry, the named exported function is called instead of main
+ * (covenant serve uses this to run scheduled functions).
+ *
+ * With --seed, crypto.uuid_v4, random_int, random_float and random_bytes
+ * use a deterministic generator seeded with the given number.
+ *
+ * With --otlp, the run is exported as an OpenTelemetry trace to the given
+ * OTLP/HTTP endpoint, with spans per step group and effectful call (see
+ * src/otel.ts).
+ *
+ * Externs declaring rate_limit or circuit_breaker metadata are shed when
+ * over their limits (see src/shedding.ts); each shed call is reported on
+ * stderr.
+ *
+ * With --memory-stats, the module's heap usage is written to stderr as one
+ * JSON line when the entry function returns.
  */
 
+import { createHash } from 'node:crypto';
+import { AuditRecord, auditImports, readAuditTable } from './src/audit.ts';
+import { Checkpoint, CheckpointValue, journalImports, readJournal } from './src/workflow.ts';
+import { Tracer, exportSpans, traceImports } from './src/otel.ts';
+import { ShedError, shedImports } from './src/shedding.ts';
+import { EventBus, readEventTable } from './src/events.ts';
+import { cryptoImports, floatBits, floatFromBits, randomSourceFromArgs } from './src/crypto.ts';
+import { bigintImports, decimalImports } from './src/decimal.ts';
+import { encodingImports } from './src/encoding.ts';
+import { jsonImports } from './src/json.ts';
+import { asciiLower, asciiUpper, localeImports } from './src/locale.ts';
+import { QueryTarget, initializeModule, registerTargetImport } from './src/init.ts';
+import { moduleAllocator, readMemoryStats } from './src/memory.ts';
+import { regexImports } from './src/regex.ts';
+
 const wasmPath = Deno.args[0];
 if (!wasmPath) {
-  console.error('Usage: deno run --allow-read --allow-write run.deno.ts <file.wasm>');
+  console.error('Usage: deno run --allow-read --allow-write run.deno.ts <file.wasm> [--audit=<log.jsonl>] [--journal=<checkpoints.jsonl>] [--entry=<fn>] [--seed=<n>] [--otlp=<endpoint>] [--memory-stats]');
   Deno.exit(1);
 }
+const auditPath = Deno.args.find(arg => arg.startsWith('--audit='))?.slice('--audit='.length);
+const journalPath = Deno.args.find(arg => arg.startsWith('--journal='))?.slice('--journal='.length);
+const entryName = Deno.args.find(arg => arg.startsWith('--entry='))?.slice('--entry='.length) ?? 'main';
+const otlpEndpoint = Deno.args.find(arg => arg.startsWith('--otlp='))?.slice('--otlp='.length);
+const memoryStats = Deno.args.includes('--memory-stats');
 
 // Read the WASM file
 const wasmBytes = await Deno.readFile(wasmPath);
@@ -25,8 +75,13 @@ const wasmBytes = await Deno.readFile(wasmPath);
 // Memory will be set after instantiation (exported from WASM module)
 let memory: WebAssembly.Memory | null = null;
 
-// Simple bump allocator state
-let heapPtr = 0x10000; // Start allocations after typical data segment
+// Database targets registered by the module's _initialize export, by name
+const queryTargets = new Map<string, QueryTarget>();
+
+// Allocates from the module's heap, set after instantiation
+let alloc = (_size: number): number => {
+  throw new Error('[runtime] Allocation before the module is instantiated');
+};
 
 // ===== String helpers for WASM ↔ host communication =====
 
@@ -37,15 +92,24 @@ function readStr(ptr: number, len: number): string {
   return new TextDecoder().decode(bytes);
 }
 
-/** Write a string into WASM memory, returns i64 fat pointer as bigint: (offset << 32) | len */
-function writeStr(s: string): bigint {
-  const encoded = new TextEncoder().encode(s);
-  const ptr = heapPtr;
-  heapPtr += (encoded.length + 7) & ~7; // 8-byte aligned
+/** Copy bytes out of WASM memory */
+function readBytes(ptr: number, len: number): Uint8Array {
+  if (!memory || len === 0) return new Uint8Array(0);
+  return new Uint8Array(memory.buffer, ptr, len).slice();
+}
+
+/** Write bytes into WASM memory, returns i64 fat pointer as bigint: (offset << 32) | len */
+function writeBytes(bytes: Uint8Array): bigint {
+  const ptr = alloc(bytes.length);
   if (memory) {
-    new Uint8Array(memory.buffer, ptr, encoded.length).set(encoded);
+    new Uint8Array(memory.buffer, ptr, bytes.length).set(bytes);
   }
-  return (BigInt(ptr) << 32n) | BigInt(encoded.length);
+  return (BigInt(ptr) << 32n) | BigInt(bytes.length);
+}
+
+/** Write a string into WASM memory as UTF-8, returns its fat pointer */
+function writeStr(s: string): bigint {
+  return writeBytes(new TextEncoder().encode(s));
 }
 
 /** Write an array of strings into WASM memory as [count:i32][fat_ptr_1:i64]...[fat_ptr_n:i64] */
@@ -55,8 +119,7 @@ function writeStrArray(parts: string[]): bigint {
 
   // Write header: 4 bytes for count + 8 bytes per fat pointer
   const headerSize = 4 + fatPtrs.length * 8;
-  const headerPtr = heapPtr;
-  heapPtr += (headerSize + 7) & ~7;
+  const headerPtr = alloc(headerSize);
 
   if (memory) {
     const view = new DataView(memory.buffer);
@@ -85,11 +148,7 @@ function readStrArray(ptr: number, _len: number): string[] {
 
 const imports: WebAssembly.Imports = {
   mem: {
-    alloc: (size: number): number => {
-      const ptr = heapPtr;
-      heapPtr += (size + 7) & ~7;
-      return ptr;
-    }
+    alloc: (size: number): number => alloc(size),
   },
   console: {
     println: (ptr: number, len: number) => { console.log(readStr(ptr, len)); },
@@ -100,9 +159,9 @@ const imports: WebAssembly.Imports = {
     warn: (ptr: number, len: number) => { console.warn(readStr(ptr, len)); },
   },
   text: {
-    // Unary -> String
-    upper: (ptr: number, len: number): bigint => writeStr(readStr(ptr, len).toUpperCase()),
-    lower: (ptr: number, len: number): bigint => writeStr(readStr(ptr, len).toLowerCase()),
+    // Unary -> String (case conversion is ASCII-only; see locale.* for Unicode)
+    upper: (ptr: number, len: number): bigint => writeStr(asciiUpper(readStr(ptr, len))),
+    lower: (ptr: number, len: number): bigint => writeStr(asciiLower(readStr(ptr, len))),
     trim: (ptr: number, len: number): bigint => writeStr(readStr(ptr, len).trim()),
     trim_start: (ptr: number, len: number): bigint => writeStr(readStr(ptr, len).trimStart()),
     trim_end: (ptr: number, len: number): bigint => writeStr(readStr(ptr, len).trimEnd()),
@@ -149,6 +208,13 @@ const imports: WebAssembly.Imports = {
     pad_end: (ptr: number, len: number, targetLen: number, fp: number, fl: number): bigint =>
       writeStr(readStr(ptr, len).padEnd(targetLen, readStr(fp, fl))),
   },
+  locale: localeImports(readStr, writeStr),
+  regex: regexImports(readStr, writeStr, writeStrArray),
+  crypto: cryptoImports(readStr, writeStr, writeBytes, randomSourceFromArgs(Deno.args)),
+  encoding: encodingImports(readBytes, writeBytes),
+  decimal: decimalImports(readStr, writeStr),
+  bigint: bigintImports(readStr, writeStr),
+  json_doc: jsonImports(readStr, writeStr, writeStrArray),
   "std.text": {
     regex_test: (pp: number, pl: number, ip: number, il: number): bigint => {
       try { return new RegExp(readStr(pp, pl)).test(readStr(ip, il)) ? 1n : 0n; }
@@ -369,6 +435,7 @@ const imports: WebAssembly.Imports = {
       console.error('[runtime] Database queries not supported in Deno runner');
       return 0;
     },
+    register_target: registerTargetImport(readStr, queryTargets),
   },
   http: {
     fetch: (_url_ptr: number, _url_len: number): number => {
@@ -379,8 +446,68 @@ const imports: WebAssembly.Imports = {
 };
 
 try {
+  // Compile first so the audit table can be read before linking
+  const module = await WebAssembly.compile(wasmBytes);
+  const auditTable = readAuditTable(module);
+  let auditedInstance: WebAssembly.Instance | null = null;
+  const context = {
+    memory: () => memory,
+    instance: () => auditedInstance,
+    hash: (text: string) => createHash('sha256').update(text).digest('hex'),
+  };
+  const events = new EventBus(() => auditedInstance);
+  for (const { event, handler } of readEventTable(module)?.subscriptions ?? []) {
+    events.subscribe(event, handler);
+  }
+  let linked: WebAssembly.Imports = { ...imports, events: events.imports(readStr) };
+  if (auditTable && auditPath) {
+    linked = auditImports(linked, auditTable, (record: AuditRecord) => {
+      Deno.writeTextFileSync(auditPath, JSON.stringify(record) + '\n', { append: true });
+    }, context);
+  }
+  const tracer = otlpEndpoint ? new Tracer(entryName, { 'covenant.module': wasmPath }) : null;
+  if (tracer) {
+    linked = traceImports(linked, auditTable, tracer, { instance: () => auditedInstance, readStr, targets: queryTargets });
+  }
+  // Shed outside the audit log and trace, so they only see calls that reach the host
+  if (auditTable) {
+    linked = shedImports(linked, auditTable, (err: ShedError) => console.error(`[runtime] shed ${err.message}`));
+  }
+  // Journal outside the audit log, trace and limits, so replayed calls are not logged or counted again
+  if (auditTable && journalPath) {
+    let history: Checkpoint[] = [];
+    try {
+      history = readJournal(Deno.readTextFileSync(journalPath));
+    } catch (err) {
+      if (!(err instanceof Deno.errors.NotFound)) throw err;
+    }
+    linked = journalImports(linked, auditTable, history, (checkpoint: Checkpoint) => {
+      Deno.writeTextFileSync(journalPath, JSON.stringify(checkpoint) + '\n', { append: true });
+    }, {
+      ...context,
+      readResult: (kind: string, raw: unknown): CheckpointValue => {
+        if (kind === 'unit') return null;
+        if (kind === 'int' || kind === 'bool') return Number(raw);
+        if (kind === 'float') return floatFromBits(raw as bigint);
+        const fat = raw as bigint;
+        if (fat === 0n) return null;
+        const [ptr, len] = [Number(fat >> 32n), Number(fat & 0xFFFFFFFFn)];
+        return kind === 'list' ? readStrArray(ptr, len) : readStr(ptr, len);
+      },
+      writeResult: (kind: string, value: CheckpointValue): unknown => {
+        if (kind === 'unit') return undefined;
+        if (kind === 'int' || kind === 'bool') return BigInt(value as number);
+        if (kind === 'float') return floatBits(value as number);
+        if (value === null) return 0n;
+        return kind === 'list' ? writeStrArray(value as string[]) : writeStr(value as string);
+      },
+    });
+  }
+
   // Instantiate the WASM module
-  const { instance } = await WebAssembly.instantiate(wasmBytes, imports);
+  const instance = await WebAssembly.instantiate(module, linked);
+  auditedInstance = instance;
+  alloc = moduleAllocator(instance);
 
   // Get the exported memory
   memory = instance.exports.memory as WebAssembly.Memory;
@@ -389,9 +516,12 @@ try {
     Deno.exit(1);
   }
 
-  // Find and call the main function
-  const main = instance.exports.main as (() => void) | undefined;
-  if (!main) {
+  // Register the module's query targets
+  initializeModule(instance);
+
+  // Find and call the entry function
+  const main = instance.exports[entryName] as (() => void) | undefined;
+  if (typeof main !== 'function') {
     const exports = Object.keys(instance.exports).filter(k =>
       typeof instance.exports[k] === 'function'
     );
@@ -401,12 +531,22 @@ try {
       Deno.exit(1);
     }
 
-    console.error(`[runtime] No 'main' function found. Available: ${exports.join(', ')}`);
+    console.error(`[runtime] No '${entryName}' function found. Available: ${exports.join(', ')}`);
     Deno.exit(1);
   }
 
-  // Call main
-  main();
+  // Call the entry function, exporting its trace whether or not it fails
+  try {
+    main();
+  } catch (err) {
+    if (tracer) await exportSpans(otlpEndpoint!, tracer.finish(err), 'covenant');
+    throw err;
+  }
+  if (tracer) await exportSpans(otlpEndpoint!, tracer.finish(), 'covenant');
+
+  if (memoryStats) {
+    console.error(JSON.stringify({ memory: readMemoryStats(instance) }));
+  }
 
 } catch (err) {
   console.error('[runtime] Error:', (err as Error).message);
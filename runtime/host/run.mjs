@@ -2,29 +2,67 @@
 /**
  * Covenant WASM runner for Node.js
  *
- * Usage: node run.mjs <file.wasm>
+ * Usage: node run.mjs <file.wasm> [--audit=<log.jsonl>] [--journal=<checkpoints.jsonl>] [--entry=<fn>] [--seed=<n>] [--otlp=<endpoint>] [--memory-stats]
  *
  * Provides the runtime imports required by Covenant-compiled WASM:
- * - mem.alloc(size) - allocate memory (simple bump allocator)
+ * - mem.alloc(size) - allocate memory from the module's heap (mirrors src/memory.ts)
  * - console.* - console output functions
  * - text.* - string operations
  * - "std.text".* - regex operations
+ * - locale.* - locale-aware text operations
+ * - regex.* - regex compute ops
+ * - crypto.* - hashes, UUIDs and random numbers
+ * - encoding.* - Base64, hex and URL encoding compute ops
+ * - decimal.*, bigint.* - Decimal and BigInt compute ops
+ * - json_doc.* - Json compute ops
  * - list.* - list operations
  * - map.* - map operations
  * - fs.* - filesystem operations
  * - path.* - path operations
+ * - events.* - in-process event bus
+ *
+ * With --audit, every effectful extern call is appended to the given file
+ * as one JSON line (the format is described in src/audit.ts).
+ *
+ * With --journal, the module runs as a workflow: calls recorded in the given
+ * checkpoint file are replayed, and each new effectful call is appended to it
+ * (the format is described in src/workflow.ts).
+ *
+ * With --entry, the named exported function is called instead of main
+ * (covenant serve uses this to run scheduled functions).
+ *
+ * With --seed, crypto.uuid_v4, random_int, random_float and random_bytes
+ * use a deterministic generator seeded with the given number.
+ *
+ * With --otlp, the run is exported as an OpenTelemetry trace to the given
+ * OTLP/HTTP endpoint (the spans are described in src/otel.ts).
+ *
+ * Externs declaring rate_limit or circuit_breaker metadata are shed when
+ * over their limits (the policies are described in src/shedding.ts); each
+ * shed call is reported on stderr.
+ *
+ * With --memory-stats, the module's heap usage is written to stderr as one
+ * JSON line when the entry function returns (the format is described in
+ * src/memory.ts).
  */
 
 import { readFile, writeFile, mkdir, readdir, stat, rm, copyFile, rename } from 'fs/promises';
-import { existsSync } from 'fs';
+import { existsSync, appendFileSync, readFileSync } from 'fs';
+import { createHash } from 'crypto';
 import { argv, stdout, stderr } from 'process';
 import { join, extname, basename, dirname, isAbsolute } from 'path';
 
 const wasmPath = argv[2];
 if (!wasmPath) {
-  console.error('Usage: node run.mjs <file.wasm>');
+  console.error('Usage: node run.mjs <file.wasm> [--audit=<log.jsonl>] [--journal=<checkpoints.jsonl>] [--entry=<fn>] [--seed=<n>] [--otlp=<endpoint>] [--memory-stats]');
   process.exit(1);
 }
+const auditPath = argv.find(arg => arg.startsWith('--audit='))?.slice('--audit='.length);
+const journalPath = argv.find(arg => arg.startsWith('--journal='))?.slice('--journal='.length);
+const entryName = argv.find(arg => arg.startsWith('--entry='))?.slice('--entry='.length) ?? 'main';
+const seed = argv.find(arg => arg.startsWith('--seed='))?.slice('--seed='.length);
+const otlpEndpoint = argv.find(arg => arg.startsWith('--otlp='))?.slice('--otlp='.length);
+const memoryStats = argv.includes('--memory-stats');
 
 // Read the WASM file
 const wasmBytes = await readFile(wasmPath);
@@ -32,8 +70,26 @@ const wasmBytes = await readFile(wasmPath);
 // Memory will be set after instantiation (exported from WASM module)
 let memory = null;
 
-// Simple bump allocator state
-let heapPtr = 0x10000; // Start allocations after typical data segment
+// Database targets registered by the module's _initialize export, by name
+const queryTargets = new Map();
+
+// Allocates from the module's heap through its cov_alloc export, set after
+// instantiation, so host writes count toward the module's memory limit
+let alloc = (_size) => {
+  throw new Error('[runtime] Allocation before the module is instantiated');
+};
+
+/** Heap usage of the instance, from its cov_memory_stats export */
+function readMemoryStats(instance) {
+  const stats = instance.exports.cov_memory_stats;
+  if (!stats || !instance.exports.memory) return null;
+  const packed = BigInt.asUintN(64, stats());
+  return {
+    allocated: Number(packed >> 32n),
+    peak: Number(packed & 0xffffffffn),
+    pages: instance.exports.memory.buffer.byteLength / 65536,
+  };
+}
 
 // ===== String helpers for WASM <-> host communication =====
 
@@ -44,15 +100,34 @@ function readStr(ptr, len) {
   return new TextDecoder().decode(bytes);
 }
 
-/** Write a string into WASM memory, returns i64 fat pointer as bigint: (offset << 32) | len */
-function writeStr(s) {
-  const encoded = new TextEncoder().encode(s);
-  const ptr = heapPtr;
-  heapPtr += (encoded.length + 7) & ~7; // 8-byte aligned
+/** Copy bytes out of WASM memory */
+function readBytes(ptr, len) {
+  if (!memory || len === 0) return new Uint8Array(0);
+  return new Uint8Array(memory.buffer, ptr, len).slice();
+}
+
+/** Write bytes into WASM memory, returns i64 fat pointer as bigint: (offset << 32) | len */
+function writeBytes(bytes) {
+  const ptr = alloc(bytes.length);
   if (memory) {
-    new Uint8Array(memory.buffer, ptr, encoded.length).set(encoded);
+    new Uint8Array(memory.buffer, ptr, bytes.length).set(bytes);
   }
-  return (BigInt(ptr) << 32n) | BigInt(encoded.length);
+  return (BigInt(ptr) << 32n) | BigInt(bytes.length);
+}
+
+/** Write a string into WASM memory as UTF-8, returns its fat pointer */
+function writeStr(s) {
+  return writeBytes(new TextEncoder().encode(s));
+}
+
+/** Translate a checked regex pattern to an equivalent host RegExp */
+function hostRegExp(pattern, flags) {
+  const source = pattern
+    .replace(/\\A/g, '^')
+    .replace(/\\z/g, '$')
+    .replace(/\\x\{/g, '\\u{')
+    .replace(/\(\?P</g, '(?<');
+  return new RegExp(source, 'u' + flags);
 }
 
 /** Write an array of strings into WASM memory as [count:i32][fat_ptr_1:i64]...[fat_ptr_n:i64] */
@@ -62,8 +137,7 @@ function writeStrArray(parts) {
 
   // Write header: 4 bytes for count + 8 bytes per fat pointer
   const headerSize = 4 + fatPtrs.length * 8;
-  const headerPtr = heapPtr;
-  heapPtr += (headerSize + 7) & ~7;
+  const headerPtr = alloc(headerSize);
 
   if (memory) {
     const view = new DataView(memory.buffer);
@@ -90,13 +164,288 @@ function readStrArray(ptr, _len) {
   return result;
 }
 
+// ===== Crypto (mirrors src/crypto.ts) =====
+
+const rotl = (x, n) => ((x << n) | (x >>> (32 - n))) >>> 0;
+const rotr = (x, n) => ((x >>> n) | (x << (32 - n))) >>> 0;
+
+/** 32-bit random words: xoshiro128** seeded with splitmix32 for --seed, else crypto.getRandomValues */
+const nextUint32 = (() => {
+  if (seed === undefined) {
+    const buffer = new Uint32Array(64);
+    let next = buffer.length;
+    return () => {
+      if (next === buffer.length) {
+        crypto.getRandomValues(buffer);
+        next = 0;
+      }
+      return buffer[next++];
+    };
+  }
+  let x = Number(seed) >>> 0;
+  const splitmix = () => {
+    x = (x + 0x9e3779b9) >>> 0;
+    let z = x;
+    z = Math.imul(z ^ (z >>> 16), 0x85ebca6b);
+    z = Math.imul(z ^ (z >>> 13), 0xc2b2ae35);
+    return (z ^ (z >>> 16)) >>> 0;
+  };
+  const s = new Uint32Array([splitmix(), splitmix(), splitmix(), splitmix()]);
+  return () => {
+    const result = Math.imul(rotl(Math.imul(s[1], 5), 7), 9) >>> 0;
+    const t = s[1] << 9;
+    s[2] ^= s[0];
+    s[3] ^= s[1];
+    s[1] ^= s[2];
+    s[0] ^= s[3];
+    s[2] ^= t;
+    s[3] = rotl(s[3], 11);
+    return result;
+  };
+})();
+
+function randomBytes(length) {
+  const bytes = new Uint8Array(Math.max(0, length));
+  for (let i = 0; i < bytes.length; i += 4) {
+    const word = nextUint32();
+    for (let j = 0; j < 4 && i + j < bytes.length; j++) {
+      bytes[i + j] = (word >>> (8 * j)) & 0xff;
+    }
+  }
+  return bytes;
+}
+
+const toHex = (bytes) => Array.from(bytes, (b) => b.toString(16).padStart(2, '0')).join('');
+
+const floatBits = (value) => {
+  const view = new DataView(new ArrayBuffer(8));
+  view.setFloat64(0, value, true);
+  return view.getBigInt64(0, true);
+};
+
+const floatFromBits = (bits) => {
+  const view = new DataView(new ArrayBuffer(8));
+  view.setBigInt64(0, bits, true);
+  return view.getFloat64(0, true);
+};
+
+const BLAKE3_IV = new Uint32Array([
+  0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+]);
+const BLAKE3_PERMUTATION = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+function blake3Compress(cv, block, counter, blockLen, flags) {
+  const s = new Uint32Array([
+    ...cv, BLAKE3_IV[0], BLAKE3_IV[1], BLAKE3_IV[2], BLAKE3_IV[3],
+    counter >>> 0, Math.floor(counter / 0x100000000), blockLen, flags,
+  ]);
+  const g = (a, b, c, d, mx, my) => {
+    s[a] = s[a] + s[b] + mx; s[d] = rotr(s[d] ^ s[a], 16);
+    s[c] = s[c] + s[d]; s[b] = rotr(s[b] ^ s[c], 12);
+    s[a] = s[a] + s[b] + my; s[d] = rotr(s[d] ^ s[a], 8);
+    s[c] = s[c] + s[d]; s[b] = rotr(s[b] ^ s[c], 7);
+  };
+  let m = block;
+  for (let round = 0; round < 7; round++) {
+    g(0, 4, 8, 12, m[0], m[1]); g(1, 5, 9, 13, m[2], m[3]);
+    g(2, 6, 10, 14, m[4], m[5]); g(3, 7, 11, 15, m[6], m[7]);
+    g(0, 5, 10, 15, m[8], m[9]); g(1, 6, 11, 12, m[10], m[11]);
+    g(2, 7, 8, 13, m[12], m[13]); g(3, 4, 9, 14, m[14], m[15]);
+    m = Uint32Array.from(BLAKE3_PERMUTATION, (i) => m[i]);
+  }
+  return Uint32Array.from({ length: 8 }, (_, i) => s[i] ^ s[i + 8]);
+}
+
+function blake3(input) {
+  const chunkCv = (chunk, counter, root) => {
+    let cv = BLAKE3_IV;
+    const blocks = Math.max(1, Math.ceil(chunk.length / 64));
+    for (let i = 0; i < blocks; i++) {
+      const bytes = new Uint8Array(64);
+      const part = chunk.subarray(i * 64, (i + 1) * 64);
+      bytes.set(part);
+      const view = new DataView(bytes.buffer);
+      const words = Uint32Array.from({ length: 16 }, (_, w) => view.getUint32(4 * w, true));
+      const last = i === blocks - 1;
+      const flags = (i === 0 ? 1 : 0) | (last ? 2 : 0) | (last && root ? 8 : 0);
+      cv = blake3Compress(cv, words, counter, part.length, flags);
+    }
+    return cv;
+  };
+  const parentCv = (left, right, root) =>
+    blake3Compress(BLAKE3_IV, Uint32Array.from([...left, ...right]), 0, 64, 4 | (root ? 8 : 0));
+
+  const chunks = Math.max(1, Math.ceil(input.length / 1024));
+  const chunk = (i) => input.subarray(i * 1024, (i + 1) * 1024);
+  const stack = [];
+  for (let i = 0; i < chunks - 1; i++) {
+    let cv = chunkCv(chunk(i), i, false);
+    for (let total = i + 1; (total & 1) === 0; total >>= 1) {
+      cv = parentCv(stack.pop(), cv, false);
+    }
+    stack.push(cv);
+  }
+  let out = chunkCv(chunk(chunks - 1), chunks - 1, stack.length === 0);
+  while (stack.length > 0) {
+    out = parentCv(stack.pop(), out, stack.length === 0);
+  }
+  const bytes = new Uint8Array(32);
+  const view = new DataView(bytes.buffer);
+  out.forEach((word, i) => view.setUint32(4 * i, word, true));
+  return toHex(bytes);
+}
+
+function randomInt(min, max) {
+  if (max <= min) return min;
+  const span = BigInt(max) - BigInt(min) + 1n;
+  const limit = (1n << 64n) - ((1n << 64n) % span);
+  for (;;) {
+    const word = (BigInt(nextUint32()) << 32n) | BigInt(nextUint32());
+    if (word < limit) return Number(BigInt(min) + (word % span));
+  }
+}
+
+function uuidV4() {
+  const bytes = randomBytes(16);
+  bytes[6] = (bytes[6] & 0x0f) | 0x40;
+  bytes[8] = (bytes[8] & 0x3f) | 0x80;
+  const hex = toHex(bytes);
+  return `${hex.slice(0, 8)}-${hex.slice(8, 12)}-${hex.slice(12, 16)}-${hex.slice(16, 20)}-${hex.slice(20)}`;
+}
+
+// ===== Encodings (mirrors src/encoding.ts) =====
+
+const BASE64 = 'ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/';
+const BASE64_URL = 'ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_';
+
+const hexValue = (digit) => /^[0-9A-Fa-f]$/.test(digit) ? parseInt(digit, 16) : -1;
+
+function encodeBase64With(bytes, alphabet, pad) {
+  let out = '';
+  for (let i = 0; i < bytes.length; i += 3) {
+    const chunk = bytes.subarray(i, i + 3);
+    let n = 0;
+    for (const b of chunk) n = (n << 8) | b;
+    n <<= 8 * (3 - chunk.length);
+    for (let j = 0; j < 4; j++) {
+      if (j <= chunk.length) out += alphabet[(n >> (18 - 6 * j)) & 0x3f];
+      else if (pad) out += '=';
+    }
+  }
+  return out;
+}
+
+/** Strict decode: null on bad characters, bad padding or non-zero trailing bits */
+function decodeBase64With(text, alphabet, paddingRequired) {
+  let padding = 0;
+  while (padding < 2 && text[text.length - 1 - padding] === '=') padding++;
+  const body = text.slice(0, text.length - padding);
+  if ((padding > 0 || paddingRequired) && text.length % 4 !== 0) return null;
+  if (body.length % 4 === 1) return null;
+
+  const out = [];
+  let acc = 0;
+  let bits = 0;
+  for (const c of body) {
+    const value = alphabet.indexOf(c);
+    if (value < 0) return null;
+    acc = (acc << 6) | value;
+    bits += 6;
+    if (bits >= 8) {
+      bits -= 8;
+      out.push(acc >> bits);
+      acc &= (1 << bits) - 1;
+    }
+  }
+  return acc === 0 ? new Uint8Array(out) : null;
+}
+
+function hexDecode(text) {
+  if (text.length % 2 !== 0) return null;
+  const out = new Uint8Array(text.length / 2);
+  for (let i = 0; i < out.length; i++) {
+    const hi = hexValue(text[2 * i]);
+    const lo = hexValue(text[2 * i + 1]);
+    if (hi < 0 || lo < 0) return null;
+    out[i] = (hi << 4) | lo;
+  }
+  return out;
+}
+
+/** Percent-encode UTF-8 bytes, keeping only RFC 3986 unreserved characters */
+function urlEncode(bytes) {
+  let out = '';
+  for (const b of bytes) {
+    const c = String.fromCharCode(b);
+    out += /[A-Za-z0-9\-._~]/.test(c) ? c : '%' + b.toString(16).toUpperCase().padStart(2, '0');
+  }
+  return out;
+}
+
+function urlDecode(bytes) {
+  const out = [];
+  for (let i = 0; i < bytes.length; i++) {
+    if (bytes[i] !== 0x25) {
+      out.push(bytes[i]);
+      continue;
+    }
+    const hi = i + 1 < bytes.length ? hexValue(String.fromCharCode(bytes[i + 1])) : -1;
+    const lo = i + 2 < bytes.length ? hexValue(String.fromCharCode(bytes[i + 2])) : -1;
+    if (hi < 0 || lo < 0) return null;
+    out.push((hi << 4) | lo);
+    i += 2;
+  }
+  try {
+    return new TextDecoder('utf-8', { fatal: true }).decode(new Uint8Array(out));
+  } catch {
+    return null;
+  }
+}
+
+// ===== Decimals (mirrors src/decimal.ts) =====
+
+function parseDecimal(text) {
+  const negative = text.startsWith('-');
+  const [whole, fraction = ''] = (negative ? text.slice(1) : text).split('.');
+  const units = BigInt(whole + fraction);
+  return { units: negative ? -units : units, places: fraction.length };
+}
+
+function formatDecimal({ units, places }) {
+  const negative = units < 0n;
+  const digits = (negative ? -units : units).toString().padStart(places + 1, '0');
+  const whole = digits.slice(0, digits.length - places);
+  const text = places === 0 ? whole : `${whole}.${digits.slice(digits.length - places)}`;
+  return negative ? `-${text}` : text;
+}
+
+const pow10 = (n) => 10n ** BigInt(n);
+const widen = (d, places) => d.units * pow10(places - d.places);
+const sign = (n) => n > 0n ? 1n : n < 0n ? -1n : 0n;
+
+function divRound(n, d) {
+  if (d === 0n) throw new RangeError('division by zero');
+  if (d < 0n) [n, d] = [-n, -d];
+  const q = n / d;
+  const r = n - q * d;
+  const twice = 2n * (r < 0n ? -r : r);
+  if (twice > d || (twice === d && q % 2n !== 0n)) return r < 0n ? q - 1n : q + 1n;
+  return q;
+}
+
+function decAdd(a, b) {
+  const places = Math.max(a.places, b.places);
+  return { units: widen(a, places) + widen(b, places), places };
+}
+
+function decRound(a, places) {
+  if (places >= a.places) return { units: widen(a, places), places };
+  return { units: divRound(a.units, pow10(a.places - places)), places };
+}
+
 const imports = {
   mem: {
-    alloc: (size) => {
-      const ptr = heapPtr;
-      heapPtr += (size + 7) & ~7;
-      return ptr;
-    }
+    alloc: (size) => alloc(size),
   },
   console: {
     println: (ptr, len) => { console.log(readStr(ptr, len)); },
@@ -107,9 +456,9 @@ const imports = {
     warn: (ptr, len) => { console.warn(readStr(ptr, len)); },
   },
   text: {
-    // Unary -> String
-    upper: (ptr, len) => writeStr(readStr(ptr, len).toUpperCase()),
-    lower: (ptr, len) => writeStr(readStr(ptr, len).toLowerCase()),
+    // Unary -> String (case conversion is ASCII-only; see locale.* for Unicode)
+    upper: (ptr, len) => writeStr(readStr(ptr, len).replace(/[a-z]/g, (c) => c.toUpperCase())),
+    lower: (ptr, len) => writeStr(readStr(ptr, len).replace(/[A-Z]/g, (c) => c.toLowerCase())),
     trim: (ptr, len) => writeStr(readStr(ptr, len).trim()),
     trim_start: (ptr, len) => writeStr(readStr(ptr, len).trimStart()),
     trim_end: (ptr, len) => writeStr(readStr(ptr, len).trimEnd()),
@@ -154,6 +503,58 @@ const imports = {
     pad_end: (ptr, len, targetLen, fp, fl) =>
       writeStr(readStr(ptr, len).padEnd(targetLen, readStr(fp, fl))),
   },
+  // Locale-aware text (mirrors src/locale.ts)
+  locale: {
+    upper: (sp, sl, tp, tl) => {
+      const s = readStr(sp, sl);
+      try { return writeStr(s.toLocaleUpperCase(readStr(tp, tl) || undefined)); }
+      catch { return writeStr(s.toUpperCase()); }
+    },
+    lower: (sp, sl, tp, tl) => {
+      const s = readStr(sp, sl);
+      try { return writeStr(s.toLocaleLowerCase(readStr(tp, tl) || undefined)); }
+      catch { return writeStr(s.toLowerCase()); }
+    },
+    casefold: (sp, sl) => writeStr(readStr(sp, sl).toUpperCase().toLowerCase()),
+    normalize: (sp, sl, fp, fl) => {
+      const s = readStr(sp, sl);
+      const form = readStr(fp, fl);
+      return writeStr(['NFC', 'NFD', 'NFKC', 'NFKD'].includes(form) ? s.normalize(form) : s);
+    },
+    collate: (ap, al, bp, bl, tp, tl) => {
+      let collator;
+      try { collator = new Intl.Collator(readStr(tp, tl) || undefined); }
+      catch { collator = new Intl.Collator(); }
+      return BigInt(Math.sign(collator.compare(readStr(ap, al), readStr(bp, bl))));
+    },
+  },
+  // Regex compute ops. run.deno.ts uses the linear-time engine in
+  // src/regex.ts; this plain-JS runner translates the checked subset to the
+  // host RegExp, which gives the same results but may backtrack.
+  regex: {
+    match: (sp, sl, pp, pl) => hostRegExp(readStr(pp, pl), '').test(readStr(sp, sl)) ? 1n : 0n,
+    find_all: (sp, sl, pp, pl) => {
+      // Skip an empty match right after a previous match, as src/regex.ts does
+      const found = [];
+      let lastEnd = -1;
+      for (const m of readStr(sp, sl).matchAll(hostRegExp(readStr(pp, pl), 'g'))) {
+        if (m[0] === '' && m.index === lastEnd) continue;
+        found.push(m[0]);
+        lastEnd = m.index + m[0].length;
+      }
+      return writeStrArray(found);
+    },
+    capture: (sp, sl, pp, pl) => {
+      const m = readStr(sp, sl).match(hostRegExp(readStr(pp, pl), ''));
+      return writeStrArray(m ? [...m].map(g => g ?? '') : []);
+    },
+    replace: (sp, sl, pp, pl, rp, rl) => {
+      const replacement = readStr(rp, rl)
+        .replace(/\$\{(\d+)\}/g, '$$$1')
+        .replace(/\$\{(\w+)\}/g, '$$<$1>');
+      return writeStr(readStr(sp, sl).replace(hostRegExp(readStr(pp, pl), 'g'), replacement));
+    },
+  },
   "std.text": {
     regex_test: (pp, pl, ip, il) => {
       try { return new RegExp(readStr(pp, pl)).test(readStr(ip, il)) ? 1n : 0n; }
@@ -360,6 +761,15 @@ const imports = {
       console.error('[runtime] Database queries not supported in Node.js runner');
       return 0;
     },
+    // Called by the module's _initialize export for each database snippet
+    register_target: (name_ptr, name_len, dialect_ptr, dialect_len, conn_ptr, conn_len) => {
+      const name = readStr(name_ptr, name_len);
+      queryTargets.set(name, {
+        name,
+        dialect: readStr(dialect_ptr, dialect_len),
+        connection: readStr(conn_ptr, conn_len),
+      });
+    },
     query: (_conn, _sql_ptr, _sql_len) => {
       console.error('[db.query stub] called');
       return 0n;
@@ -370,15 +780,16 @@ const imports = {
     },
   },
   crypto: {
-    sha256: (ptr, len) => {
-      const input = readStr(ptr, len);
-      console.error(`[crypto.sha256 stub] ${input.substring(0, 20)}...`);
-      return writeStr('0'.repeat(64)); // Return mock hash
-    },
-    random_bytes: (length) => {
-      console.error(`[crypto.random_bytes stub] ${length} bytes`);
-      return writeStr('0'.repeat(Number(length) * 2)); // Return mock hex
+    sha256: (ptr, len) => writeStr(createHash('sha256').update(readStr(ptr, len)).digest('hex')),
+    blake3: (ptr, len) => writeStr(blake3(new TextEncoder().encode(readStr(ptr, len)))),
+    uuid_v4: () => writeStr(uuidV4()),
+    random_int: (min, max) => BigInt(randomInt(min, max)),
+    random_float: () => {
+      const high = nextUint32() >>> 5;
+      const low = nextUint32() >>> 6;
+      return floatBits((high * 67108864 + low) / 9007199254740992);
     },
+    random_bytes: (length) => writeBytes(randomBytes(length)),
     verify_bcrypt: (_hash_ptr, _hash_len, _input_ptr, _input_len) => {
       console.error('[crypto.verify_bcrypt stub] called');
       return 1n; // Return true (verified)
@@ -389,6 +800,108 @@ const imports = {
       return writeStr('$2b$10$mockhash'); // Return mock bcrypt hash
     },
   },
+  // Decoders return 0, the DecodeError member, on malformed input
+  encoding: (() => {
+    const text = (p, l) => new TextDecoder().decode(readBytes(p, l));
+    const orError = (bytes) => bytes === null ? 0n : writeBytes(bytes);
+    return {
+      base64_encode: (p, l) => writeStr(encodeBase64With(readBytes(p, l), BASE64, true)),
+      base64_decode: (p, l) => orError(decodeBase64With(text(p, l), BASE64, true)),
+      base64url_encode: (p, l) => writeStr(encodeBase64With(readBytes(p, l), BASE64_URL, false)),
+      base64url_decode: (p, l) => orError(decodeBase64With(text(p, l), BASE64_URL, false)),
+      hex_encode: (p, l) => writeStr(toHex(readBytes(p, l))),
+      hex_decode: (p, l) => orError(hexDecode(text(p, l))),
+      url_encode: (p, l) => writeStr(urlEncode(readBytes(p, l))),
+      url_decode: (p, l) => {
+        const decoded = urlDecode(readBytes(p, l));
+        return decoded === null ? 0n : writeStr(decoded);
+      },
+    };
+  })(),
+  decimal: (() => {
+    const dec = (p, l) => parseDecimal(readStr(p, l));
+    const out = (d) => writeStr(formatDecimal(d));
+    return {
+      add: (ap, al, bp, bl) => out(decAdd(dec(ap, al), dec(bp, bl))),
+      sub: (ap, al, bp, bl) => {
+        const b = dec(bp, bl);
+        return out(decAdd(dec(ap, al), { units: -b.units, places: b.places }));
+      },
+      mul: (ap, al, bp, bl) => {
+        const [a, b] = [dec(ap, al), dec(bp, bl)];
+        return out({ units: a.units * b.units, places: a.places + b.places });
+      },
+      div: (ap, al, bp, bl, places) => {
+        const [a, b] = [dec(ap, al), dec(bp, bl)];
+        return out({ units: divRound(a.units * pow10(places + b.places), b.units * pow10(a.places)), places });
+      },
+      round: (ap, al, places) => out(decRound(dec(ap, al), places)),
+      cmp: (ap, al, bp, bl) => {
+        const [a, b] = [dec(ap, al), dec(bp, bl)];
+        const places = Math.max(a.places, b.places);
+        return sign(widen(a, places) - widen(b, places));
+      },
+      from_int: (n) => out({ units: n, places: 0 }),
+    };
+  })(),
+  bigint: (() => {
+    const big = (p, l) => BigInt(readStr(p, l));
+    const out = (n) => writeStr(n.toString());
+    const nonZero = (n) => {
+      if (n === 0n) throw new RangeError('division by zero');
+      return n;
+    };
+    return {
+      add: (ap, al, bp, bl) => out(big(ap, al) + big(bp, bl)),
+      sub: (ap, al, bp, bl) => out(big(ap, al) - big(bp, bl)),
+      mul: (ap, al, bp, bl) => out(big(ap, al) * big(bp, bl)),
+      div: (ap, al, bp, bl) => out(big(ap, al) / nonZero(big(bp, bl))),
+      mod: (ap, al, bp, bl) => out(big(ap, al) % nonZero(big(bp, bl))),
+      cmp: (ap, al, bp, bl) => sign(big(ap, al) - big(bp, bl)),
+      from_int: (n) => out(n),
+    };
+  })(),
+  // Json ops (mirrors src/json.ts): documents cross as their compact text
+  json_doc: (() => {
+    const doc = (p, l) => JSON.parse(readStr(p, l));
+    const out = (value) => writeStr(JSON.stringify(value));
+    const isObject = (value) => value !== null && typeof value === 'object' && !Array.isArray(value);
+    const index = (key) => (/^(0|[1-9][0-9]*)$/.test(key) ? Number(key) : null);
+    return {
+      parse: (p, l) => {
+        try {
+          return out(doc(p, l));
+        } catch {
+          return 0n; // the DecodeError member
+        }
+      },
+      get: (jp, jl, kp, kl) => {
+        const value = doc(jp, jl);
+        const key = readStr(kp, kl);
+        const found = Array.isArray(value)
+          ? (index(key) === null ? undefined : value[index(key)])
+          : (isObject(value) && Object.hasOwn(value, key) ? value[key] : undefined);
+        return found === undefined ? -(1n << 63n) : out(found);
+      },
+      set: (jp, jl, kp, kl, vp, vl) => {
+        const value = doc(jp, jl);
+        const key = readStr(kp, kl);
+        const item = doc(vp, vl);
+        if (Array.isArray(value)) {
+          const i = index(key);
+          if (i === null || i > value.length) return out(value);
+          const copy = [...value];
+          copy[i] = item;
+          return out(copy);
+        }
+        return out(isObject(value) ? { ...value, [key]: item } : value);
+      },
+      keys: (p, l) => {
+        const value = doc(p, l);
+        return writeStrArray(isObject(value) ? Object.keys(value) : []);
+      },
+    };
+  })(),
   http: {
     get: (urlFatPtr) => {
       // Fat pointer: high 32 bits = ptr, low 32 bits = len
@@ -411,8 +924,380 @@ const imports = {
       return 0;
     },
   },
+  events: {
+    // The event value arrives as its i64 split into (high, low) halves
+    publish: (typePtr, typeLen, hi, lo) => {
+      const eventType = readStr(typePtr, typeLen);
+      const event = (BigInt(hi >>> 0) << 32n) | BigInt(lo >>> 0);
+      publishEvent(eventType, event);
+    },
+    subscribe: (typePtr, typeLen, handlerPtr, handlerLen) => {
+      subscribeEvent(readStr(typePtr, typeLen), readStr(handlerPtr, handlerLen));
+    },
+  },
 };
 
+// ===== Event bus (mirrors src/events.ts) =====
+
+/** Handler export names by event struct name, in registration order */
+const subscriptions = new Map();
+
+function subscribeEvent(eventType, handler) {
+  const name = eventType.split('.').pop();
+  if (!subscriptions.has(name)) subscriptions.set(name, []);
+  subscriptions.get(name).push(handler);
+}
+
+function publishEvent(eventType, event) {
+  for (const handler of subscriptions.get(eventType.split('.').pop()) ?? []) {
+    const fn = instance.exports[handler];
+    if (typeof fn !== 'function') {
+      console.error(`[runtime] event handler '${handler}' is not exported`);
+      continue;
+    }
+    fn(event);
+  }
+}
+
+/** Register the subscriptions listed in the module's covenant.events section */
+function eventSubscriptions(module) {
+  for (const section of WebAssembly.Module.customSections(module, 'covenant.events')) {
+    const table = JSON.parse(new TextDecoder().decode(section));
+    for (const { event, handler } of table.subscriptions) {
+      subscribeEvent(event, handler);
+    }
+  }
+}
+
+// ===== Audit log (mirrors src/audit.ts) =====
+
+let instance = null;
+
+/** Wrap the externs listed in the module's covenant.audit section to log each call */
+function auditImports(module) {
+  const sections = WebAssembly.Module.customSections(module, 'covenant.audit');
+  if (!auditPath || sections.length === 0) return;
+  const table = JSON.parse(new TextDecoder().decode(sections[0]));
+
+  for (const [importName, ext] of Object.entries(table.externs)) {
+    const lastDot = importName.lastIndexOf('.');
+    const moduleName = importName.substring(0, lastDot);
+    const funcName = importName.substring(lastDot + 1);
+    const original = imports[moduleName]?.[funcName];
+    if (typeof original !== 'function') continue;
+
+    imports[moduleName][funcName] = (...args) => {
+      const siteIndex = instance?.exports._cov_audit_site?.value;
+      const site = siteIndex === undefined ? undefined : table.sites[siteIndex];
+      // Decode (ptr, len) pairs to strings and single i32s to numbers
+      const values = [];
+      let next = 0;
+      for (const param of ext.params) {
+        if (param === 'string' || param === 'fat_pointer') {
+          values.push(readStr(args[next], args[next + 1]));
+          next += 2;
+        } else {
+          values.push(Number(args[next]));
+          next += 1;
+        }
+      }
+      // Sensitive arguments are redacted before hashing
+      for (const index of ext.sensitive ?? []) {
+        values[index] = '[REDACTED]';
+      }
+      const record = {
+        timestamp: new Date().toISOString(),
+        extern: ext.snippet,
+        effects: ext.effects,
+        contract: ext.contract,
+        args_hash: createHash('sha256').update(JSON.stringify(values)).digest('hex'),
+        snippet: site?.snippet ?? null,
+        step: site?.step ?? null,
+      };
+      appendFileSync(auditPath, JSON.stringify(record) + '\n');
+      return original(...args);
+    };
+  }
+}
+
+// ===== OpenTelemetry traces (mirrors src/otel.ts) =====
+
+const traceId = randomHex(16);
+const spans = [];
+const rootSpan = otlpEndpoint ? openSpan(`covenant.run ${entryName}`, 1, undefined, { 'covenant.entry': entryName, 'covenant.module': wasmPath }) : null;
+let stepGroup = null;
+
+function randomHex(bytes) {
+  return Array.from(crypto.getRandomValues(new Uint8Array(bytes)), b => b.toString(16).padStart(2, '0')).join('');
+}
+
+function nowNanos() {
+  return BigInt(Math.round((performance.timeOrigin + performance.now()) * 1e6));
+}
+
+function openSpan(name, kind, parentSpanId, attributes) {
+  return { spanId: randomHex(8), parentSpanId, name, kind, start: nowNanos(), attributes };
+}
+
+function closeSpan(span, end, error) {
+  spans.push({
+    traceId,
+    spanId: span.spanId,
+    ...(span.parentSpanId ? { parentSpanId: span.parentSpanId } : {}),
+    name: span.name,
+    kind: span.kind,
+    startTimeUnixNano: span.start.toString(),
+    endTimeUnixNano: end.toString(),
+    attributes: Object.entries(span.attributes).map(([key, value]) => ({
+      key,
+      value: Array.isArray(value) ? { arrayValue: { values: value.map(v => ({ stringValue: v })) } } : { stringValue: value },
+    })),
+    status: error === undefined ? { code: 1 } : { code: 2, message: error?.message ?? String(error) },
+  });
+}
+
+function closeStepGroup() {
+  if (stepGroup) closeSpan(stepGroup.span, stepGroup.end);
+  stepGroup = null;
+}
+
+/** Replace `imports[moduleName][funcName]` with a version recorded as a span */
+function traceImport(moduleName, funcName, describe, redact = false) {
+  const original = imports[moduleName]?.[funcName];
+  if (typeof original !== 'function') return;
+  imports[moduleName][funcName] = (...args) => {
+    const { name, snippet, attributes } = describe(args);
+    const group = snippet ?? '(unknown)';
+    if (stepGroup?.snippet !== group) {
+      closeStepGroup();
+      const span = openSpan(group, 1, rootSpan.spanId, { 'covenant.snippet': group });
+      stepGroup = { snippet: group, span, end: span.start };
+    }
+    const span = openSpan(name, 3, stepGroup.span.spanId, attributes);
+    try {
+      const result = original(...args);
+      closeSpan(span, stepGroup.end = nowNanos());
+      return result;
+    } catch (err) {
+      closeSpan(span, stepGroup.end = nowNanos(), redact ? new Error('[REDACTED]') : err);
+      throw err;
+    }
+  };
+}
+
+/** Record each audited extern call and database query as a span */
+function traceImports(module) {
+  if (!otlpEndpoint) return;
+  const sections = WebAssembly.Module.customSections(module, 'covenant.audit');
+  const table = sections.length > 0 ? JSON.parse(new TextDecoder().decode(sections[0])) : { externs: {}, sites: [] };
+
+  for (const [importName, ext] of Object.entries(table.externs)) {
+    const lastDot = importName.lastIndexOf('.');
+    traceImport(importName.substring(0, lastDot), importName.substring(lastDot + 1), () => {
+      const siteIndex = instance?.exports._cov_audit_site?.value;
+      const site = siteIndex === undefined ? undefined : table.sites[siteIndex];
+      return {
+        name: ext.snippet,
+        snippet: site?.snippet ?? null,
+        attributes: {
+          'covenant.snippet': site?.snippet ?? '',
+          'covenant.step': site?.step ?? '',
+          'covenant.extern': ext.snippet,
+          'covenant.contract': ext.contract ?? '',
+          'covenant.effects': ext.effects,
+        },
+      };
+    }, (ext.sensitive ?? []).length > 0);
+  }
+
+  traceImport('db', 'execute_query', (args) => {
+    const attributes = { 'db.statement': readStr(args[0], args[1]) };
+    if (queryTargets.size === 1) {
+      const [target] = queryTargets.values();
+      attributes['db.name'] = target.name;
+      if (target.dialect) attributes['db.system'] = target.dialect;
+    }
+    return { name: 'db.query', snippet: null, attributes };
+  });
+}
+
+/** End the run's trace and post it to the collector, reporting failures without throwing */
+async function exportTrace(error) {
+  if (!rootSpan) return;
+  closeStepGroup();
+  closeSpan(rootSpan, nowNanos(), error);
+  const url = otlpEndpoint.endsWith('/v1/traces') ? otlpEndpoint : otlpEndpoint.replace(/\/+$/, '') + '/v1/traces';
+  const body = {
+    resourceSpans: [{
+      resource: { attributes: [{ key: 'service.name', value: { stringValue: 'covenant' } }] },
+      scopeSpans: [{ scope: { name: 'covenant.runtime' }, spans }],
+    }],
+  };
+  try {
+    const response = await fetch(url, { method: 'POST', headers: { 'content-type': 'application/json' }, body: JSON.stringify(body) });
+    if (!response.ok) console.error(`[runtime] OTLP export to ${url} failed: ${response.status} ${response.statusText}`);
+  } catch (err) {
+    console.error(`[runtime] OTLP export to ${url} failed: ${err.message}`);
+  }
+}
+
+// ===== Rate limits and circuit breakers (mirrors src/shedding.ts) =====
+
+/** Parse `<count>/<duration>` ("100/m", "5/30s") into the count and milliseconds */
+function parsePolicy(text) {
+  const match = /^\s*(\d+)\s*\/\s*(\d*)([smh])\s*$/.exec(text ?? '');
+  if (!match) return null;
+  const unitMs = { s: 1000, m: 60_000, h: 3_600_000 }[match[3]];
+  return { count: Number(match[1]), windowMs: Number(match[2] || '1') * unitMs };
+}
+
+/**
+ * Shed calls to externs over their rate limit or with an open circuit
+ * breaker: the call returns 0 (the ShedError member) without reaching the
+ * host. Wraps the audit log and trace, so they only see calls that ran.
+ */
+function shedImports(module) {
+  const sections = WebAssembly.Module.customSections(module, 'covenant.audit');
+  if (sections.length === 0) return;
+  const table = JSON.parse(new TextDecoder().decode(sections[0]));
+
+  for (const [importName, ext] of Object.entries(table.externs)) {
+    const rate = parsePolicy(ext.rate_limit);
+    const breaker = parsePolicy(ext.circuit_breaker);
+    if (!rate && !breaker) continue;
+    const lastDot = importName.lastIndexOf('.');
+    const moduleName = importName.substring(0, lastDot);
+    const funcName = importName.substring(lastDot + 1);
+    const original = imports[moduleName]?.[funcName];
+    if (typeof original !== 'function') continue;
+
+    const admitted = [];
+    let failures = 0;
+    let openUntil = 0;
+    const record = (failed) => {
+      if (!breaker) return;
+      failures = failed ? failures + 1 : 0;
+      if (failures >= breaker.count) {
+        // Open; after the cooldown one trial call closes it or opens it again
+        openUntil = Date.now() + breaker.windowMs;
+        failures = breaker.count - 1;
+      }
+    };
+
+    imports[moduleName][funcName] = (...args) => {
+      const now = Date.now();
+      if (breaker && now < openUntil) {
+        console.error(`[runtime] shed ${ext.snippet}: circuit open after ${breaker.count} consecutive failures`);
+        return 0n;
+      }
+      if (rate) {
+        while (admitted.length > 0 && admitted[0] <= now - rate.windowMs) admitted.shift();
+        if (admitted.length >= rate.count) {
+          console.error(`[runtime] shed ${ext.snippet}: rate limit ${ext.rate_limit} exceeded`);
+          return 0n;
+        }
+        admitted.push(now);
+      }
+      let result;
+      try {
+        result = original(...args);
+      } catch (err) {
+        record(true);
+        throw err;
+      }
+      record(result === 0n);
+      return result;
+    };
+  }
+}
+
+// ===== Workflow checkpoints (mirrors src/workflow.ts) =====
+
+/** Decode a raw extern result per the audit table's `returns` kind */
+function readResult(kind, raw) {
+  if (kind === 'unit') return null;
+  if (kind === 'int' || kind === 'bool') return Number(raw);
+  if (kind === 'float') return floatFromBits(raw);
+  if (raw === 0n) return null;
+  const [ptr, len] = [Number(raw >> 32n), Number(raw & 0xFFFFFFFFn)];
+  return kind === 'list' ? readStrArray(ptr, len) : readStr(ptr, len);
+}
+
+/** Encode a recorded result as the raw value the module expects */
+function writeResult(kind, value) {
+  if (kind === 'unit') return undefined;
+  if (kind === 'int' || kind === 'bool') return BigInt(value);
+  if (kind === 'float') return floatBits(value);
+  if (value === null) return 0n;
+  return kind === 'list' ? writeStrArray(value) : writeStr(value);
+}
+
+/**
+ * Answer effectful extern calls from the checkpoint journal, then append each
+ * new call to it. Wraps the audit log and limits, so replayed calls are not
+ * logged or counted again.
+ */
+function journalImports(module) {
+  const sections = WebAssembly.Module.customSections(module, 'covenant.audit');
+  if (!journalPath || sections.length === 0) return;
+  const table = JSON.parse(new TextDecoder().decode(sections[0]));
+  const history = existsSync(journalPath)
+    ? readFileSync(journalPath, 'utf-8').split('\n').filter(line => line.trim()).map(line => JSON.parse(line))
+    : [];
+  let next = 0;
+
+  for (const [importName, ext] of Object.entries(table.externs)) {
+    const lastDot = importName.lastIndexOf('.');
+    const moduleName = importName.substring(0, lastDot);
+    const funcName = importName.substring(lastDot + 1);
+    const original = imports[moduleName]?.[funcName];
+    if (typeof original !== 'function') continue;
+
+    imports[moduleName][funcName] = (...args) => {
+      const values = [];
+      let arg = 0;
+      for (const param of ext.params) {
+        if (param === 'string' || param === 'fat_pointer') {
+          values.push(readStr(args[arg], args[arg + 1]));
+          arg += 2;
+        } else {
+          values.push(Number(args[arg]));
+          arg += 1;
+        }
+      }
+      const argsHash = createHash('sha256').update(JSON.stringify(values)).digest('hex');
+
+      if (next < history.length) {
+        const recorded = history[next];
+        if (recorded.extern !== ext.snippet || recorded.args_hash !== argsHash) {
+          throw new Error(
+            `workflow replay diverged at checkpoint ${next + 1}: ` +
+            `recorded ${recorded.extern} at step ${recorded.step ?? '?'}, but the workflow ` +
+            (recorded.extern === ext.snippet ? 'passed different arguments' : `called ${ext.snippet}`)
+          );
+        }
+        next += 1;
+        return writeResult(ext.returns, recorded.result);
+      }
+
+      const siteIndex = instance?.exports._cov_audit_site?.value;
+      const site = siteIndex === undefined ? undefined : table.sites[siteIndex];
+      const raw = original(...args);
+      const checkpoint = {
+        timestamp: new Date().toISOString(),
+        extern: ext.snippet,
+        snippet: site?.snippet ?? null,
+        step: site?.step ?? null,
+        args_hash: argsHash,
+        result: readResult(ext.returns, raw),
+      };
+      appendFileSync(journalPath, JSON.stringify(checkpoint) + '\n');
+      next += 1;
+      return raw;
+    };
+  }
+}
+
 // Create a Proxy-based fallback for dynamically added extern imports
 const proxyHandler = {
   get(target, prop) {
@@ -434,8 +1319,16 @@ const proxyHandler = {
 const proxiedImports = new Proxy(imports, proxyHandler);
 
 try {
+  // Compile first so the audit table can be read before linking
+  const module = await WebAssembly.compile(wasmBytes);
+  auditImports(module);
+  traceImports(module);
+  shedImports(module);
+  journalImports(module);
+  eventSubscriptions(module);
+
   // Instantiate the WASM module
-  const { instance } = await WebAssembly.instantiate(wasmBytes, proxiedImports);
+  instance = await WebAssembly.instantiate(module, proxiedImports);
 
   // Get the exported memory
   memory = instance.exports.memory;
@@ -443,10 +1336,15 @@ try {
     console.error('[runtime] WASM module does not export memory');
     process.exit(1);
   }
+  const covAlloc = instance.exports.cov_alloc;
+  alloc = (size) => covAlloc(size) >>> 0;
+
+  // Register the module's query targets
+  instance.exports._initialize?.();
 
-  // Find and call the main function
-  const main = instance.exports.main;
-  if (!main) {
+  // Find and call the entry function
+  const main = instance.exports[entryName];
+  if (typeof main !== 'function') {
     // Try to find any exported function if there's no main
     const exports = Object.keys(instance.exports).filter(k =>
       typeof instance.exports[k] === 'function'
@@ -457,12 +1355,22 @@ try {
       process.exit(1);
     }
 
-    console.error(`[runtime] No 'main' function found. Available: ${exports.join(', ')}`);
+    console.error(`[runtime] No '${entryName}' function found. Available: ${exports.join(', ')}`);
     process.exit(1);
   }
 
-  // Call main
-  main();
+  // Call the entry function, exporting its trace whether or not it fails
+  try {
+    main();
+  } catch (err) {
+    await exportTrace(err);
+    throw err;
+  }
+  await exportTrace();
+
+  if (memoryStats) {
+    console.error(JSON.stringify({ memory: readMemoryStats(instance) }));
+  }
 
 } catch (err) {
   console.error('[runtime] Error:', err.message);
@@ -1,21 +1,31 @@
 /**
  * Node-compatible Covenant WASM runner (for testing when Deno is unavailable)
  *
- * Usage: npx tsx run.node.ts <file.wasm>
+ * Usage: npx tsx run.node.ts <file.wasm> [--seed=<n>]
  */
 
 import { readFileSync } from 'fs';
+import { cryptoImports, randomSourceFromArgs } from './src/crypto.ts';
+import { bigintImports, decimalImports } from './src/decimal.ts';
+import { encodingImports } from './src/encoding.ts';
+import { jsonImports } from './src/json.ts';
+import { asciiLower, asciiUpper } from './src/locale.ts';
+import { initializeModule } from './src/init.ts';
+import { moduleAllocator } from './src/memory.ts';
+import { regexImports } from './src/regex.ts';
 
 const wasmPath = process.argv[2];
 if (!wasmPath) {
-  console.error('Usage: npx tsx run.node.ts <file.wasm>');
+  console.error('Usage: npx tsx run.node.ts <file.wasm> [--seed=<n>]');
   process.exit(1);
 }
 
 const wasmBytes = readFileSync(wasmPath);
 
 let memory: WebAssembly.Memory | null = null;
-let heapPtr = 0x10000;
+let alloc = (_size: number): number => {
+  throw new Error('[runtime] Allocation before the module is instantiated');
+};
 
 function readStr(ptr: number, len: number): string {
   if (!memory || len === 0) return '';
@@ -23,21 +33,27 @@ function readStr(ptr: number, len: number): string {
   return new TextDecoder().decode(bytes);
 }
 
-function writeStr(s: string): bigint {
-  const encoded = new TextEncoder().encode(s);
-  const ptr = heapPtr;
-  heapPtr += (encoded.length + 7) & ~7;
+function readBytes(ptr: number, len: number): Uint8Array {
+  if (!memory || len === 0) return new Uint8Array(0);
+  return new Uint8Array(memory.buffer, ptr, len).slice();
+}
+
+function writeBytes(bytes: Uint8Array): bigint {
+  const ptr = alloc(bytes.length);
   if (memory) {
-    new Uint8Array(memory.buffer, ptr, encoded.length).set(encoded);
+    new Uint8Array(memory.buffer, ptr, bytes.length).set(bytes);
   }
-  return (BigInt(ptr) << 32n) | BigInt(encoded.length);
+  return (BigInt(ptr) << 32n) | BigInt(bytes.length);
+}
+
+function writeStr(s: string): bigint {
+  return writeBytes(new TextEncoder().encode(s));
 }
 
 function writeStrArray(parts: string[]): bigint {
   const fatPtrs: bigint[] = parts.map(s => writeStr(s));
   const headerSize = 4 + fatPtrs.length * 8;
-  const headerPtr = heapPtr;
-  heapPtr += (headerSize + 7) & ~7;
+  const headerPtr = alloc(headerSize);
   if (memory) {
     const view = new DataView(memory.buffer);
     view.setInt32(headerPtr, fatPtrs.length, true);
@@ -71,15 +87,11 @@ const imports: WebAssembly.Imports = {
     }
   },
   covenant_mem: {
-    alloc: (size: number): number => {
-      const ptr = heapPtr;
-      heapPtr += (size + 7) & ~7;
-      return ptr;
-    }
+    alloc: (size: number): number => alloc(size),
   },
   covenant_text: {
-    upper: (ptr: number, len: number): bigint => writeStr(readStr(ptr, len).toUpperCase()),
-    lower: (ptr: number, len: number): bigint => writeStr(readStr(ptr, len).toLowerCase()),
+    upper: (ptr: number, len: number): bigint => writeStr(asciiUpper(readStr(ptr, len))),
+    lower: (ptr: number, len: number): bigint => writeStr(asciiLower(readStr(ptr, len))),
     trim: (ptr: number, len: number): bigint => writeStr(readStr(ptr, len).trim()),
     trim_start: (ptr: number, len: number): bigint => writeStr(readStr(ptr, len).trimStart()),
     trim_end: (ptr: number, len: number): bigint => writeStr(readStr(ptr, len).trimEnd()),
@@ -138,14 +150,22 @@ const imports: WebAssembly.Imports = {
       try { return writeStrArray(readStr(ip, il).split(new RegExp(readStr(pp, pl)))); }
       catch { return writeStrArray([readStr(ip, il)]); }
     },
-  }
+  },
+  regex: regexImports(readStr, writeStr, writeStrArray),
+  crypto: cryptoImports(readStr, writeStr, writeBytes, randomSourceFromArgs(process.argv)),
+  encoding: encodingImports(readBytes, writeBytes),
+  decimal: decimalImports(readStr, writeStr),
+  bigint: bigintImports(readStr, writeStr),
+  json_doc: jsonImports(readStr, writeStr, writeStrArray),
 };
 
 (async () => {
   try {
     const { instance } = await WebAssembly.instantiate(wasmBytes, imports);
     memory = instance.exports.memory as WebAssembly.Memory;
+    alloc = moduleAllocator(instance);
     if (!memory) { console.error('[runtime] No memory export'); process.exit(1); }
+    initializeModule(instance);
     const main = instance.exports.main as (() => void) | undefined;
     if (!main) { console.error('[runtime] No main export'); process.exit(1); }
     main();
@@ -0,0 +1,179 @@
+/**
+ * Audit Log of Effectful Extern Calls
+ *
+ * The compiler embeds an audit table in each module: the externs that
+ * declare effects, and every call step that invokes one. Before such a call
+ * the module stores the call site's index in the `_cov_audit_site` global.
+ * `auditImports` wraps those externs so every invocation is reported to a
+ * sink along with its caller.
+ *
+ * ```
+ * covenant.audit section ──► readAuditTable() ──► auditImports(imports)
+ *                                                        │ per call
+ *                                                        ▼
+ *                          AuditRecord { extern, effects, contract,
+ *                                        args_hash, snippet, step, timestamp }
+ * ```
+ *
+ * Arguments are recorded only as a hash, so logs can show which calls saw
+ * the same input without retaining the input itself. Arguments the table
+ * marks sensitive are replaced with `REDACTED` before hashing, so a hash of
+ * a guessable secret cannot be checked against candidates.
+ */
+
+/** Custom section holding the audit table (see crates/covenant-codegen/src/audit.rs) */
+export const AUDIT_SECTION = "covenant.audit";
+
+/** Exported i32 global holding the index of the call site about to run */
+export const AUDIT_SITE_EXPORT = "_cov_audit_site";
+
+/** Stands in for a sensitive argument or a message that may contain one */
+export const REDACTED = "[REDACTED]";
+
+/** An extern that declares effects. Mirrors the Rust AuditExtern struct. */
+export interface AuditExtern {
+  snippet: string;
+  effects: string[];
+  contract: string | null;
+  /** "string" and "fat_pointer" take (ptr, len); "int" and "bool" one i32; "float" its bits as one i64 */
+  params: string[];
+  /** "string", "list" and "fat_pointer" return an i64 fat pointer; "int" and "bool" an i64; "float" its bits as an i64; "unit" nothing */
+  returns: string;
+  /** Declared rate limit and circuit breaker, enforced by shedding.ts */
+  rate_limit?: string | null;
+  circuit_breaker?: string | null;
+  /** Indexes into `params` of sensitive arguments, which are never recorded */
+  sensitive?: number[];
+}
+
+/** A call step that invokes an audited extern. Mirrors the Rust AuditSite struct. */
+export interface AuditSite {
+  snippet: string;
+  step: string;
+  import: string;
+}
+
+/** Audit table embedded by the compiler */
+export interface AuditTable {
+  externs: Record<string, AuditExtern>;
+  sites: AuditSite[];
+}
+
+/** One effectful extern invocation, written as a JSONL line by the runners */
+export interface AuditRecord {
+  /** ISO 8601 time of the call */
+  timestamp: string;
+  /** Snippet ID of the extern */
+  extern: string;
+  effects: string[];
+  contract: string | null;
+  /** Hash of the decoded arguments as a JSON array */
+  args_hash: string;
+  /** Calling snippet and step, if the module reported them */
+  snippet: string | null;
+  step: string | null;
+}
+
+export type AuditSink = (record: AuditRecord) => void;
+
+/** How the host reaches the running module and hashes arguments */
+export interface AuditContext {
+  memory: () => WebAssembly.Memory | null;
+  instance: () => WebAssembly.Instance | null;
+  hash: (text: string) => string;
+}
+
+/**
+ * Read the audit table from a compiled module.
+ * Returns null for modules that call no effectful extern.
+ */
+export function readAuditTable(module: WebAssembly.Module): AuditTable | null {
+  const sections = WebAssembly.Module.customSections(module, AUDIT_SECTION);
+  if (sections.length === 0) {
+    return null;
+  }
+  return JSON.parse(new TextDecoder().decode(sections[0])) as AuditTable;
+}
+
+/**
+ * Wrap the audited externs in `imports` so each call is reported to `sink`
+ * before it runs. Imports the table does not list are returned unchanged.
+ */
+export function auditImports(
+  imports: WebAssembly.Imports,
+  table: AuditTable,
+  sink: AuditSink,
+  context: AuditContext
+): WebAssembly.Imports {
+  const audited: WebAssembly.Imports = { ...imports };
+
+  for (const [importName, ext] of Object.entries(table.externs)) {
+    const lastDot = importName.lastIndexOf(".");
+    const moduleName = importName.substring(0, lastDot);
+    const funcName = importName.substring(lastDot + 1);
+    const original = imports[moduleName]?.[funcName];
+    if (typeof original !== "function") {
+      continue;
+    }
+
+    audited[moduleName] = {
+      ...audited[moduleName],
+      [funcName]: (...args: unknown[]) => {
+        const site = currentSite(table, context.instance());
+        sink({
+          timestamp: new Date().toISOString(),
+          extern: ext.snippet,
+          effects: ext.effects,
+          contract: ext.contract,
+          args_hash: context.hash(JSON.stringify(redactArgs(ext, decodeArgs(ext, args, context.memory())))),
+          snippet: site?.snippet ?? null,
+          step: site?.step ?? null,
+        });
+        return (original as Function)(...args);
+      },
+    };
+  }
+
+  return audited;
+}
+
+/** The call site the module reported before calling the import */
+export function currentSite(
+  table: AuditTable,
+  instance: WebAssembly.Instance | null
+): AuditSite | undefined {
+  const global = instance?.exports[AUDIT_SITE_EXPORT] as WebAssembly.Global | undefined;
+  return global ? table.sites[global.value as number] : undefined;
+}
+
+/** Decode raw WASM arguments into strings and numbers per the extern's params */
+export function decodeArgs(
+  ext: AuditExtern,
+  args: unknown[],
+  memory: WebAssembly.Memory | null
+): Array<string | number> {
+  const values: Array<string | number> = [];
+  let next = 0;
+  for (const param of ext.params) {
+    if (param === "string" || param === "fat_pointer") {
+      const ptr = Number(args[next]);
+      const len = Number(args[next + 1]);
+      next += 2;
+      values.push(
+        memory && len > 0
+          ? new TextDecoder().decode(new Uint8Array(memory.buffer, ptr, len))
+          : ""
+      );
+    } else {
+      values.push(Number(args[next]));
+      next += 1;
+    }
+  }
+  return values;
+}
+
+/** `values` with the extern's sensitive arguments replaced by `REDACTED` */
+export function redactArgs(ext: AuditExtern, values: Array<string | number>): Array<string | number> {
+  const sensitive = ext.sensitive ?? [];
+  return values.map((value, index) => (sensitive.includes(index) ? REDACTED : value));
+}
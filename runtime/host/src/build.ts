@@ -0,0 +1,31 @@
+/**
+ * Build Info
+ *
+ * Every compiled module records the compiler version, the WebAssembly
+ * proposals it may use and its memory limit. Hosts can read it before
+ * instantiating a module to report a missing feature clearly instead of
+ * failing to compile it.
+ */
+
+/** Custom section holding the build info (see crates/covenant-codegen/src/features.rs) */
+export const BUILD_INFO_SECTION = "covenant.build";
+
+/** A WebAssembly proposal beyond the MVP. Mirrors the Rust WasmFeature enum. */
+export type WasmFeature = "simd" | "relaxed-simd" | "bulk-memory" | "gc";
+
+/** How a module was built. Mirrors the Rust BuildInfo struct. */
+export interface BuildInfo {
+  compiler: string;
+  features: WasmFeature[];
+  /** Most 64 KiB pages the memory may grow to (see src/memory.ts) */
+  max_memory_pages: number;
+}
+
+/** Read the build info from a compiled module, if it has any */
+export function readBuildInfo(module: WebAssembly.Module): BuildInfo | null {
+  const sections = WebAssembly.Module.customSections(module, BUILD_INFO_SECTION);
+  if (sections.length === 0) {
+    return null;
+  }
+  return JSON.parse(new TextDecoder().decode(sections[0])) as BuildInfo;
+}
@@ -21,7 +21,15 @@
  *                                   ▼               ▼
  *                            Provided           Denied
  *                            (real impl)       (throws error)
+ *                                   │
+ *                                   ▼
+ *                        isResourceAllowed() (per call)
  * ```
+ *
+ * Effects may also carry resource parameters, e.g.
+ * `filesystem.read(path="/data")` or `network.http(host="*.stripe.com")`.
+ * Provided filesystem and HTTP imports check each path or URL host against
+ * the module's effect scopes before doing any I/O.
  */
 
 /**
@@ -42,6 +50,45 @@ export interface EmbeddableSymbol {
   tests: string[];
   covers: string[];
   required_capabilities: string[];
+  /** Absent in metadata from older compilers */
+  effect_scopes?: EffectScope[];
+}
+
+/**
+ * A declared effect and its resource parameters.
+ * This mirrors the Rust EffectScope struct.
+ */
+export interface EffectScope {
+  effect: string;
+  params: Record<string, string>;
+}
+
+/**
+ * Current schema version of embedded symbol metadata.
+ *
+ * This must be kept in sync with SYMBOL_METADATA_VERSION in
+ * crates/covenant-ast/src/schema.rs
+ */
+export const SYMBOL_METADATA_VERSION = 1;
+
+/**
+ * Extract the symbols from embedded metadata of any supported version.
+ *
+ * Version 0 (modules built before metadata was versioned) is a bare array;
+ * later versions wrap it as `{ schema_version, symbols }`.
+ */
+export function symbolsFromMetadata(metadata: unknown): EmbeddableSymbol[] {
+  if (Array.isArray(metadata)) {
+    return metadata as EmbeddableSymbol[];
+  }
+  const versioned = metadata as { schema_version?: number; symbols?: EmbeddableSymbol[] };
+  const version = versioned.schema_version ?? 0;
+  if (version > SYMBOL_METADATA_VERSION) {
+    throw new Error(
+      `symbol metadata schema version ${version} is newer than the supported version ${SYMBOL_METADATA_VERSION}`
+    );
+  }
+  return versioned.symbols ?? [];
 }
 
 /**
@@ -57,6 +104,9 @@ export interface CapabilityManifest {
   /** Required WASM imports derived from the effect closure */
   requiredImports: Set<string>;
 
+  /** Declared effects with their resource parameters */
+  effectScopes: EffectScope[];
+
   /** Module symbols for debugging/introspection */
   symbols: EmbeddableSymbol[];
 }
@@ -69,7 +119,7 @@ export interface CapabilityManifest {
  */
 export const EFFECT_TO_IMPORTS: Record<string, string[]> = {
   // Core effects
-  database: ["db.execute_query"],
+  database: ["db.execute_query", "db.register_target"],
   network: ["http.fetch"],
   filesystem: [
     "fs.read",
@@ -86,6 +136,20 @@ export const EFFECT_TO_IMPORTS: Record<string, string[]> = {
     "console.eprintln",
     "console.eprint",
   ],
+  events: ["events.publish", "events.subscribe"],
+  locale: [
+    "locale.upper",
+    "locale.lower",
+    "locale.casefold",
+    "locale.normalize",
+    "locale.collate",
+  ],
+  random: [
+    "crypto.uuid_v4",
+    "crypto.random_int",
+    "crypto.random_float",
+    "crypto.random_bytes",
+  ],
 
   // Standard library effects
   "std.storage": [
@@ -105,6 +169,11 @@ export const EFFECT_TO_IMPORTS: Record<string, string[]> = {
   "std.time": ["std.time.now", "std.time.sleep"],
   "std.random": ["std.random.int", "std.random.float", "std.random.bytes"],
   "std.crypto": ["std.crypto.hash", "std.crypto.sign", "std.crypto.verify"],
+
+  // Narrower children of core effects
+  "network.http": ["http.fetch"],
+  "filesystem.read": ["fs.read", "fs.exists", "fs.read_dir"],
+  "filesystem.write": ["fs.write", "fs.delete", "fs.create_dir", "fs.remove_dir"],
 };
 
 /**
@@ -116,7 +185,8 @@ export const IMPORT_TO_EFFECT: Record<string, string> = Object.entries(
 ).reduce(
   (acc, [effect, imports]) => {
     for (const imp of imports) {
-      acc[imp] = effect;
+      // Name the broadest effect (listed first) in error messages
+      acc[imp] ??= effect;
     }
     return acc;
   },
@@ -148,6 +218,7 @@ export function emptyManifest(): CapabilityManifest {
   return {
     declaredEffects: new Set(),
     requiredImports: new Set(),
+    effectScopes: [],
     symbols: [],
   };
 }
@@ -163,6 +234,7 @@ export function buildManifestFromSymbols(
 ): CapabilityManifest {
   const declaredEffects = new Set<string>();
   const requiredImports = new Set<string>();
+  const effectScopes: EffectScope[] = [];
 
   for (const symbol of symbols) {
     // Collect declared effects
@@ -174,11 +246,14 @@ export function buildManifestFromSymbols(
     for (const cap of symbol.required_capabilities) {
       requiredImports.add(cap);
     }
+
+    effectScopes.push(...(symbol.effect_scopes ?? []));
   }
 
   return {
     declaredEffects,
     requiredImports,
+    effectScopes,
     symbols,
   };
 }
@@ -202,6 +277,112 @@ export function isImportAllowed(
   return manifest.requiredImports.has(importName);
 }
 
+/**
+ * The resource parameter checked by each import module, e.g. the path
+ * argument of `fs.read` is checked against `path` parameters.
+ */
+export const IMPORT_RESOURCE_PARAMS: Record<string, string> = {
+  fs: "path",
+  http: "host",
+};
+
+/**
+ * Check if a resource is within the module's effect scopes.
+ *
+ * Only scopes of effects granting the import count. The resource is allowed
+ * when any of them leaves the parameter out (unrestricted) or covers the
+ * value, and when no such scope exists (metadata without effect scopes).
+ * Matching mirrors `param_value_subsumes` in
+ * crates/covenant-checker/src/effects.rs.
+ *
+ * @param manifest - The module's capability manifest
+ * @param importName - The WASM import name (e.g., "fs.read")
+ * @param param - The resource parameter (e.g., "path")
+ * @param value - The resource the call touches (e.g., "/data/users.json")
+ */
+export function isResourceAllowed(
+  manifest: CapabilityManifest,
+  importName: string,
+  param: string,
+  value: string
+): boolean {
+  const scopes = manifest.effectScopes.filter((scope) =>
+    EFFECT_TO_IMPORTS[scope.effect]?.includes(importName)
+  );
+  if (scopes.length === 0) {
+    return true;
+  }
+
+  return scopes.some((scope) => {
+    const allowed = scope.params[param];
+    if (allowed === undefined) {
+      return true;
+    }
+    switch (param) {
+      case "path":
+        return pathWithin(allowed, normalizePath(value));
+      case "host":
+        return hostMatches(allowed, value);
+      default:
+        return allowed === value;
+    }
+  });
+}
+
+/**
+ * Whether `path` is `dir` or lies beneath it.
+ * "/data" covers "/data/users" but not "/database".
+ */
+export function pathWithin(dir: string, path: string): boolean {
+  if (!path.startsWith(dir)) {
+    return false;
+  }
+  const rest = path.substring(dir.length);
+  return rest === "" || dir.endsWith("/") || rest.startsWith("/");
+}
+
+/**
+ * Resolve "." and ".." segments so "/data/../etc" cannot pass as "/data".
+ */
+export function normalizePath(path: string): string {
+  const segments: string[] = [];
+  for (const segment of path.split("/")) {
+    if (segment === "..") {
+      segments.pop();
+    } else if (segment !== "." && segment !== "") {
+      segments.push(segment);
+    }
+  }
+  const joined = segments.join("/");
+  return path.startsWith("/") ? `/${joined}` : joined;
+}
+
+/**
+ * Whether a host pattern covers a host.
+ * "*.stripe.com" covers "api.stripe.com" but not "stripe.com".
+ */
+export function hostMatches(pattern: string, host: string): boolean {
+  pattern = pattern.toLowerCase();
+  host = host.toLowerCase();
+  if (pattern.startsWith("*.")) {
+    const suffix = pattern.substring(1);
+    return host.length > suffix.length && host.endsWith(suffix);
+  }
+  return pattern === host;
+}
+
+/**
+ * Get a human-readable error message for a resource outside the effect scopes.
+ */
+export function getDeniedResourceError(
+  importName: string,
+  param: string,
+  value: string
+): string {
+  const effect = IMPORT_TO_EFFECT[importName] ?? importName;
+  return `Import '${importName}' denied: ${param} '${value}' is outside the declared '${effect}' scopes`;
+}
+
 /**
  * Get a human-readable error message for a denied import.
  */
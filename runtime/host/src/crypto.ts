@@ -0,0 +1,308 @@
+/**
+ * Hashing, UUIDs and Random Numbers
+ *
+ * Implements the `crypto` std module (runtime/std/crypto/crypto.cov).
+ *
+ * ```
+ * crypto.sha256(s)        ──► SHA-256 of the UTF-8 bytes, lowercase hex
+ * crypto.blake3(s)        ──► BLAKE3-256 of the UTF-8 bytes, lowercase hex
+ * crypto.uuid_v4()        ──► "xxxxxxxx-xxxx-4xxx-yxxx-xxxxxxxxxxxx"
+ * crypto.random_int(a, b) ──► uniform in [a, b]
+ * crypto.random_float()   ──► uniform in [0, 1)
+ * crypto.random_bytes(n)  ──► n bytes
+ * ```
+ *
+ * Web Crypto's `digest` is asynchronous and has no BLAKE3, so both hashes
+ * are implemented here to be callable from synchronous WASM imports.
+ *
+ * The random functions draw from a {@link RandomSource}: the host's CSPRNG
+ * by default, or a deterministic generator when the runner is given a seed
+ * (`--seed=<n>`), so tests and reproductions see the same values on every
+ * run.
+ */
+
+// ===== Random sources =====
+
+/** Source of uniformly distributed 32-bit words */
+export interface RandomSource {
+  nextUint32(): number;
+}
+
+/** The host's cryptographically secure generator (crypto.getRandomValues) */
+export function secureRandom(): RandomSource {
+  const buffer = new Uint32Array(64);
+  let next = buffer.length;
+  return {
+    nextUint32() {
+      if (next === buffer.length) {
+        crypto.getRandomValues(buffer);
+        next = 0;
+      }
+      return buffer[next++];
+    },
+  };
+}
+
+/**
+ * A deterministic generator (xoshiro128**) whose state is expanded from
+ * `seed` with splitmix32. Not suitable for secrets.
+ */
+export function seededRandom(seed: number): RandomSource {
+  let x = seed >>> 0;
+  const splitmix = () => {
+    x = (x + 0x9e3779b9) >>> 0;
+    let z = x;
+    z = Math.imul(z ^ (z >>> 16), 0x85ebca6b);
+    z = Math.imul(z ^ (z >>> 13), 0xc2b2ae35);
+    return (z ^ (z >>> 16)) >>> 0;
+  };
+  const s = new Uint32Array([splitmix(), splitmix(), splitmix(), splitmix()]);
+  return {
+    nextUint32() {
+      const result = Math.imul(rotl(Math.imul(s[1], 5), 7), 9) >>> 0;
+      const t = s[1] << 9;
+      s[2] ^= s[0];
+      s[3] ^= s[1];
+      s[1] ^= s[2];
+      s[0] ^= s[3];
+      s[2] ^= t;
+      s[3] = rotl(s[3], 11);
+      return result;
+    },
+  };
+}
+
+/** The seeded generator for a `--seed=<n>` argument, else the secure one */
+export function randomSourceFromArgs(args: string[]): RandomSource {
+  const seed = args.find((arg) => arg.startsWith("--seed="))?.slice("--seed=".length);
+  return seed === undefined ? secureRandom() : seededRandom(Number(seed));
+}
+
+function rotl(x: number, n: number): number {
+  return ((x << n) | (x >>> (32 - n))) >>> 0;
+}
+
+function rotr(x: number, n: number): number {
+  return ((x >>> n) | (x << (32 - n))) >>> 0;
+}
+
+/** Uniform Int in [min, max] without modulo bias; `min` when max < min */
+export function randomInt(random: RandomSource, min: number, max: number): number {
+  if (max <= min) return min;
+  const span = BigInt(max) - BigInt(min) + 1n;
+  const limit = (1n << 64n) - ((1n << 64n) % span);
+  for (;;) {
+    const word = (BigInt(random.nextUint32()) << 32n) | BigInt(random.nextUint32());
+    if (word < limit) return Number(BigInt(min) + (word % span));
+  }
+}
+
+/** Uniform Float in [0, 1) with 53 random bits */
+export function randomFloat(random: RandomSource): number {
+  const high = random.nextUint32() >>> 5;
+  const low = random.nextUint32() >>> 6;
+  return (high * 67108864 + low) / 9007199254740992;
+}
+
+export function randomBytes(random: RandomSource, length: number): Uint8Array {
+  const bytes = new Uint8Array(Math.max(0, length));
+  for (let i = 0; i < bytes.length; i += 4) {
+    const word = random.nextUint32();
+    for (let j = 0; j < 4 && i + j < bytes.length; j++) {
+      bytes[i + j] = (word >>> (8 * j)) & 0xff;
+    }
+  }
+  return bytes;
+}
+
+/** A version 4 (random) UUID in lowercase hyphenated form */
+export function uuidV4(random: RandomSource): string {
+  const bytes = randomBytes(random, 16);
+  bytes[6] = (bytes[6] & 0x0f) | 0x40;
+  bytes[8] = (bytes[8] & 0x3f) | 0x80;
+  const hex = toHex(bytes);
+  return `${hex.slice(0, 8)}-${hex.slice(8, 12)}-${hex.slice(12, 16)}-${hex.slice(16, 20)}-${hex.slice(20)}`;
+}
+
+export function toHex(bytes: Uint8Array): string {
+  return Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+}
+
+/** The bits of a Float as the i64 the module receives for a Float result */
+export function floatBits(value: number): bigint {
+  const view = new DataView(new ArrayBuffer(8));
+  view.setFloat64(0, value, true);
+  return view.getBigInt64(0, true);
+}
+
+/** The Float whose bits are `bits` (inverse of {@link floatBits}) */
+export function floatFromBits(bits: bigint): number {
+  const view = new DataView(new ArrayBuffer(8));
+  view.setBigInt64(0, bits, true);
+  return view.getFloat64(0, true);
+}
+
+// ===== SHA-256 (FIPS 180-4) =====
+
+const SHA256_K = new Uint32Array([
+  0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+  0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+  0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+  0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+  0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+  0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+  0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+  0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+]);
+
+/** Initial hash value of SHA-256, also BLAKE3's IV */
+const IV = new Uint32Array([
+  0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+]);
+
+export function sha256(input: Uint8Array): string {
+  // Pad with 0x80, zeros, and the bit length as a big-endian u64
+  const padded = new Uint8Array((((input.length + 8) >> 6) + 1) << 6);
+  padded.set(input);
+  padded[input.length] = 0x80;
+  const view = new DataView(padded.buffer);
+  view.setUint32(padded.length - 8, Math.floor(input.length / 0x20000000));
+  view.setUint32(padded.length - 4, (input.length << 3) >>> 0);
+
+  const h = new Uint32Array(IV);
+  const w = new Uint32Array(64);
+  for (let offset = 0; offset < padded.length; offset += 64) {
+    for (let i = 0; i < 16; i++) w[i] = view.getUint32(offset + 4 * i);
+    for (let i = 16; i < 64; i++) {
+      const s0 = rotr(w[i - 15], 7) ^ rotr(w[i - 15], 18) ^ (w[i - 15] >>> 3);
+      const s1 = rotr(w[i - 2], 17) ^ rotr(w[i - 2], 19) ^ (w[i - 2] >>> 10);
+      w[i] = w[i - 16] + s0 + w[i - 7] + s1;
+    }
+    let [a, b, c, d, e, f, g, hh] = h;
+    for (let i = 0; i < 64; i++) {
+      const t1 = (hh + (rotr(e, 6) ^ rotr(e, 11) ^ rotr(e, 25)) + ((e & f) ^ (~e & g)) + SHA256_K[i] + w[i]) >>> 0;
+      const t2 = ((rotr(a, 2) ^ rotr(a, 13) ^ rotr(a, 22)) + ((a & b) ^ (a & c) ^ (b & c))) >>> 0;
+      [hh, g, f, e, d, c, b, a] = [g, f, e, (d + t1) >>> 0, c, b, a, (t1 + t2) >>> 0];
+    }
+    h[0] += a; h[1] += b; h[2] += c; h[3] += d;
+    h[4] += e; h[5] += f; h[6] += g; h[7] += hh;
+  }
+  return Array.from(h, (word) => word.toString(16).padStart(8, "0")).join("");
+}
+
+// ===== BLAKE3 (hash mode, 32-byte output) =====
+
+const CHUNK_LEN = 1024;
+const BLOCK_LEN = 64;
+const CHUNK_START = 1;
+const CHUNK_END = 2;
+const PARENT = 4;
+const ROOT = 8;
+const MSG_PERMUTATION = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+function g(s: Uint32Array, a: number, b: number, c: number, d: number, mx: number, my: number) {
+  s[a] = s[a] + s[b] + mx;
+  s[d] = rotr(s[d] ^ s[a], 16);
+  s[c] = s[c] + s[d];
+  s[b] = rotr(s[b] ^ s[c], 12);
+  s[a] = s[a] + s[b] + my;
+  s[d] = rotr(s[d] ^ s[a], 8);
+  s[c] = s[c] + s[d];
+  s[b] = rotr(s[b] ^ s[c], 7);
+}
+
+/** The compression function, returning the first 8 words of its output */
+function compress(cv: Uint32Array, block: Uint32Array, counter: number, blockLen: number, flags: number): Uint32Array {
+  const s = new Uint32Array([
+    cv[0], cv[1], cv[2], cv[3], cv[4], cv[5], cv[6], cv[7],
+    IV[0], IV[1], IV[2], IV[3],
+    counter >>> 0, Math.floor(counter / 0x100000000), blockLen, flags,
+  ]);
+  let m = block;
+  for (let round = 0; round < 7; round++) {
+    g(s, 0, 4, 8, 12, m[0], m[1]);
+    g(s, 1, 5, 9, 13, m[2], m[3]);
+    g(s, 2, 6, 10, 14, m[4], m[5]);
+    g(s, 3, 7, 11, 15, m[6], m[7]);
+    g(s, 0, 5, 10, 15, m[8], m[9]);
+    g(s, 1, 6, 11, 12, m[10], m[11]);
+    g(s, 2, 7, 8, 13, m[12], m[13]);
+    g(s, 3, 4, 9, 14, m[14], m[15]);
+    m = Uint32Array.from(MSG_PERMUTATION, (i) => m[i]);
+  }
+  for (let i = 0; i < 8; i++) s[i] ^= s[i + 8];
+  return s.subarray(0, 8);
+}
+
+/** Little-endian words of a block, zero-padded to 64 bytes */
+function blockWords(bytes: Uint8Array): Uint32Array {
+  const padded = new Uint8Array(BLOCK_LEN);
+  padded.set(bytes);
+  const view = new DataView(padded.buffer);
+  return Uint32Array.from({ length: 16 }, (_, i) => view.getUint32(4 * i, true));
+}
+
+/** Chaining value of chunk number `counter`; `root` flags its last block */
+function chunkCv(chunk: Uint8Array, counter: number, root: boolean): Uint32Array {
+  let cv = new Uint32Array(IV);
+  const blocks = Math.max(1, Math.ceil(chunk.length / BLOCK_LEN));
+  for (let i = 0; i < blocks; i++) {
+    const bytes = chunk.subarray(i * BLOCK_LEN, (i + 1) * BLOCK_LEN);
+    const last = i === blocks - 1;
+    const flags = (i === 0 ? CHUNK_START : 0) | (last ? CHUNK_END : 0) | (last && root ? ROOT : 0);
+    cv = new Uint32Array(compress(cv, blockWords(bytes), counter, bytes.length, flags));
+  }
+  return cv;
+}
+
+function parentCv(left: Uint32Array, right: Uint32Array, root: boolean): Uint32Array {
+  const block = new Uint32Array(16);
+  block.set(left);
+  block.set(right, 8);
+  return new Uint32Array(compress(IV, block, 0, BLOCK_LEN, PARENT | (root ? ROOT : 0)));
+}
+
+export function blake3(input: Uint8Array): string {
+  const chunks = Math.max(1, Math.ceil(input.length / CHUNK_LEN));
+  const chunk = (i: number) => input.subarray(i * CHUNK_LEN, (i + 1) * CHUNK_LEN);
+
+  // Merge completed subtrees as soon as a chunk count makes them whole
+  const stack: Uint32Array[] = [];
+  for (let i = 0; i < chunks - 1; i++) {
+    let cv = chunkCv(chunk(i), i, false);
+    for (let total = i + 1; (total & 1) === 0; total >>= 1) {
+      cv = parentCv(stack.pop()!, cv, false);
+    }
+    stack.push(cv);
+  }
+
+  let out = chunkCv(chunk(chunks - 1), chunks - 1, stack.length === 0);
+  while (stack.length > 0) {
+    out = parentCv(stack.pop()!, out, stack.length === 0);
+  }
+
+  const bytes = new Uint8Array(32);
+  const view = new DataView(bytes.buffer);
+  out.forEach((word, i) => view.setUint32(4 * i, word, true));
+  return toHex(bytes);
+}
+
+// ===== Imports =====
+
+/** The `crypto` import module, reading and writing values through the given helpers */
+export function cryptoImports(
+  readStr: (ptr: number, len: number) => string,
+  writeStr: (s: string) => bigint,
+  writeBytes: (bytes: Uint8Array) => bigint,
+  random: RandomSource,
+): Record<string, (...args: number[]) => bigint> {
+  const utf8 = (ptr: number, len: number) => new TextEncoder().encode(readStr(ptr, len));
+  return {
+    sha256: (p, l) => writeStr(sha256(utf8(p, l))),
+    blake3: (p, l) => writeStr(blake3(utf8(p, l))),
+    uuid_v4: () => writeStr(uuidV4(random)),
+    random_int: (min, max) => BigInt(randomInt(random, min, max)),
+    random_float: () => floatBits(randomFloat(random)),
+    random_bytes: (length) => writeBytes(randomBytes(random, length)),
+  };
+}
@@ -0,0 +1,139 @@
+/**
+ * Decimal and BigInt Arithmetic
+ *
+ * Implements the `dec_` and `big_` compute ops. Values cross the module
+ * boundary as their canonical text (`-19.90`, `18446744073709551616`), the
+ * same text the parser keeps for literals, and are computed here with
+ * JavaScript BigInt: a Decimal is a count of units at some number of places.
+ *
+ * Decimals keep their places: sums have the places of the wider input and
+ * products their total, so `1.10d` times `3d` is `3.30`. Division and
+ * rounding take the places of the result and round half to even. Dividing
+ * by zero throws, which traps the calling module like Int division by zero.
+ */
+
+/** `units` / 10^`places` */
+export interface Decimal {
+  units: bigint;
+  places: number;
+}
+
+export function parseDecimal(text: string): Decimal {
+  const negative = text.startsWith("-");
+  const [whole, fraction = ""] = (negative ? text.slice(1) : text).split(".");
+  const units = BigInt(whole + fraction);
+  return { units: negative ? -units : units, places: fraction.length };
+}
+
+export function formatDecimal({ units, places }: Decimal): string {
+  const negative = units < 0n;
+  const digits = (negative ? -units : units).toString().padStart(places + 1, "0");
+  const whole = digits.slice(0, digits.length - places);
+  const text = places === 0 ? whole : `${whole}.${digits.slice(digits.length - places)}`;
+  return negative ? `-${text}` : text;
+}
+
+const pow10 = (n: number) => 10n ** BigInt(n);
+
+/** The units of `d` at `places`, which must be at least its own */
+function widen(d: Decimal, places: number): bigint {
+  return d.units * pow10(places - d.places);
+}
+
+/** `n / d` rounded half to even */
+function divRound(n: bigint, d: bigint): bigint {
+  if (d === 0n) throw new RangeError("division by zero");
+  if (d < 0n) [n, d] = [-n, -d];
+  const q = n / d;
+  const r = n - q * d;
+  const twice = 2n * (r < 0n ? -r : r);
+  if (twice > d || (twice === d && q % 2n !== 0n)) {
+    return r < 0n ? q - 1n : q + 1n;
+  }
+  return q;
+}
+
+export function decAdd(a: Decimal, b: Decimal): Decimal {
+  const places = Math.max(a.places, b.places);
+  return { units: widen(a, places) + widen(b, places), places };
+}
+
+export function decSub(a: Decimal, b: Decimal): Decimal {
+  return decAdd(a, { units: -b.units, places: b.places });
+}
+
+export function decMul(a: Decimal, b: Decimal): Decimal {
+  return { units: a.units * b.units, places: a.places + b.places };
+}
+
+/** `a / b` at `places`, rounded half to even */
+export function decDiv(a: Decimal, b: Decimal, places: number): Decimal {
+  if (b.units === 0n) throw new RangeError("division by zero");
+  return { units: divRound(a.units * pow10(places + b.places), b.units * pow10(a.places)), places };
+}
+
+/** `a` at `places`, rounded half to even when that drops digits */
+export function decRound(a: Decimal, places: number): Decimal {
+  if (places >= a.places) return { units: widen(a, places), places };
+  return { units: divRound(a.units, pow10(a.places - places)), places };
+}
+
+/** -1, 0 or 1; places do not matter, so `1.5` equals `1.50` */
+export function decCmp(a: Decimal, b: Decimal): number {
+  const places = Math.max(a.places, b.places);
+  const [x, y] = [widen(a, places), widen(b, places)];
+  return x < y ? -1 : x > y ? 1 : 0;
+}
+
+/** Truncates toward zero, like Int division */
+export function bigDiv(a: bigint, b: bigint): bigint {
+  if (b === 0n) throw new RangeError("division by zero");
+  return a / b;
+}
+
+/** Has the sign of `a`, like Int remainder */
+export function bigMod(a: bigint, b: bigint): bigint {
+  if (b === 0n) throw new RangeError("division by zero");
+  return a % b;
+}
+
+const sign = (n: number | bigint) => BigInt(n > 0 ? 1 : n < 0 ? -1 : 0);
+
+// ===== Imports =====
+
+/** The `decimal` import module, reading and writing values through the given helpers */
+export function decimalImports(
+  readStr: (ptr: number, len: number) => string,
+  writeStr: (s: string) => bigint,
+) {
+  const dec = (ptr: number, len: number) => parseDecimal(readStr(ptr, len));
+  const out = (d: Decimal) => writeStr(formatDecimal(d));
+  return {
+    add: (ap: number, al: number, bp: number, bl: number) => out(decAdd(dec(ap, al), dec(bp, bl))),
+    sub: (ap: number, al: number, bp: number, bl: number) => out(decSub(dec(ap, al), dec(bp, bl))),
+    mul: (ap: number, al: number, bp: number, bl: number) => out(decMul(dec(ap, al), dec(bp, bl))),
+    div: (ap: number, al: number, bp: number, bl: number, places: number) =>
+      out(decDiv(dec(ap, al), dec(bp, bl), places)),
+    round: (ap: number, al: number, places: number) => out(decRound(dec(ap, al), places)),
+    cmp: (ap: number, al: number, bp: number, bl: number) => sign(decCmp(dec(ap, al), dec(bp, bl))),
+    from_int: (n: bigint) => out({ units: n, places: 0 }),
+  };
+}
+
+/** The `bigint` import module, reading and writing values through the given helpers */
+export function bigintImports(
+  readStr: (ptr: number, len: number) => string,
+  writeStr: (s: string) => bigint,
+) {
+  const big = (ptr: number, len: number) => BigInt(readStr(ptr, len));
+  const out = (n: bigint) => writeStr(n.toString());
+  return {
+    add: (ap: number, al: number, bp: number, bl: number) => out(big(ap, al) + big(bp, bl)),
+    sub: (ap: number, al: number, bp: number, bl: number) => out(big(ap, al) - big(bp, bl)),
+    mul: (ap: number, al: number, bp: number, bl: number) => out(big(ap, al) * big(bp, bl)),
+    div: (ap: number, al: number, bp: number, bl: number) => out(bigDiv(big(ap, al), big(bp, bl))),
+    mod: (ap: number, al: number, bp: number, bl: number) => out(bigMod(big(ap, al), big(bp, bl))),
+    cmp: (ap: number, al: number, bp: number, bl: number) => sign(big(ap, al) - big(bp, bl)),
+    from_int: (n: bigint) => out(n),
+  };
+}
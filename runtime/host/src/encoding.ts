@@ -0,0 +1,155 @@
+/**
+ * Encodings
+ *
+ * Implements the encoding compute ops: `bytes_to_base64` /
+ * `bytes_from_base64`, the `base64url` pair, `bytes_to_hex` /
+ * `bytes_from_hex`, and `url_encode` / `url_decode`.
+ *
+ * - Base64 is RFC 4648 §4 with required padding; Base64url is §5, encoded
+ *   without padding and decoded with or without it
+ * - Hex encodes lowercase and decodes either case
+ * - URL encoding percent-encodes every UTF-8 byte outside RFC 3986's
+ *   unreserved set (`A-Z a-z 0-9 - . _ ~`) as uppercase `%XX`
+ *
+ * Decoders are strict and return null on malformed input, which the imports
+ * report as 0, the DecodeError member of the op's result union. The
+ * compiler's constant folder has the same codecs
+ * (crates/covenant-optimizer/src/encoding.rs); both are checked against
+ * encoding.vectors.json.
+ */
+
+const BASE64 = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+export function base64Encode(bytes: Uint8Array): string {
+  return encodeBase64With(bytes, BASE64, true);
+}
+
+export function base64Decode(text: string): Uint8Array | null {
+  return decodeBase64With(text, BASE64, true);
+}
+
+export function base64UrlEncode(bytes: Uint8Array): string {
+  return encodeBase64With(bytes, BASE64_URL, false);
+}
+
+export function base64UrlDecode(text: string): Uint8Array | null {
+  return decodeBase64With(text, BASE64_URL, false);
+}
+
+export function hexEncode(bytes: Uint8Array): string {
+  return Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+}
+
+export function hexDecode(text: string): Uint8Array | null {
+  if (text.length % 2 !== 0) return null;
+  const out = new Uint8Array(text.length / 2);
+  for (let i = 0; i < out.length; i++) {
+    const hi = hexValue(text[2 * i]!);
+    const lo = hexValue(text[2 * i + 1]!);
+    if (hi < 0 || lo < 0) return null;
+    out[i] = (hi << 4) | lo;
+  }
+  return out;
+}
+
+/** Percent-encode UTF-8 bytes, keeping only unreserved characters */
+export function urlEncode(bytes: Uint8Array): string {
+  let out = "";
+  for (const b of bytes) {
+    const c = String.fromCharCode(b);
+    out += /[A-Za-z0-9\-._~]/.test(c) ? c : "%" + b.toString(16).toUpperCase().padStart(2, "0");
+  }
+  return out;
+}
+
+/** Decode `%XX` escapes; other characters, `+` included, pass through */
+export function urlDecode(bytes: Uint8Array): string | null {
+  const out: number[] = [];
+  for (let i = 0; i < bytes.length; i++) {
+    if (bytes[i] !== 0x25) {
+      out.push(bytes[i]!);
+      continue;
+    }
+    const hi = i + 1 < bytes.length ? hexValue(String.fromCharCode(bytes[i + 1]!)) : -1;
+    const lo = i + 2 < bytes.length ? hexValue(String.fromCharCode(bytes[i + 2]!)) : -1;
+    if (hi < 0 || lo < 0) return null;
+    out.push((hi << 4) | lo);
+    i += 2;
+  }
+  try {
+    return new TextDecoder("utf-8", { fatal: true }).decode(new Uint8Array(out));
+  } catch {
+    return null;
+  }
+}
+
+function hexValue(digit: string): number {
+  return /^[0-9A-Fa-f]$/.test(digit) ? parseInt(digit, 16) : -1;
+}
+
+function encodeBase64With(bytes: Uint8Array, alphabet: string, pad: boolean): string {
+  let out = "";
+  for (let i = 0; i < bytes.length; i += 3) {
+    const chunk = bytes.subarray(i, i + 3);
+    let n = 0;
+    for (const b of chunk) n = (n << 8) | b;
+    n <<= 8 * (3 - chunk.length);
+    for (let j = 0; j < 4; j++) {
+      if (j <= chunk.length) out += alphabet[(n >> (18 - 6 * j)) & 0x3f];
+      else if (pad) out += "=";
+    }
+  }
+  return out;
+}
+
+function decodeBase64With(text: string, alphabet: string, paddingRequired: boolean): Uint8Array | null {
+  let padding = 0;
+  while (padding < 2 && text[text.length - 1 - padding] === "=") padding++;
+  const body = text.slice(0, text.length - padding);
+
+  // Padding, when present, must complete the final quantum exactly
+  if ((padding > 0 || paddingRequired) && text.length % 4 !== 0) return null;
+  if (body.length % 4 === 1) return null;
+
+  const out: number[] = [];
+  let acc = 0;
+  let bits = 0;
+  for (const c of body) {
+    const value = alphabet.indexOf(c);
+    if (value < 0) return null;
+    acc = (acc << 6) | value;
+    bits += 6;
+    if (bits >= 8) {
+      bits -= 8;
+      out.push(acc >> bits);
+      acc &= (1 << bits) - 1;
+    }
+  }
+  // Canonical encodings leave the unused low bits zero
+  return acc === 0 ? new Uint8Array(out) : null;
+}
+
+// ===== Imports =====
+
+/** The `encoding` import module, reading and writing values through the given helpers */
+export function encodingImports(
+  readBytes: (ptr: number, len: number) => Uint8Array,
+  writeBytes: (bytes: Uint8Array) => bigint,
+): Record<string, (ptr: number, len: number) => bigint> {
+  const utf8 = (s: string) => writeBytes(new TextEncoder().encode(s));
+  const orError = (bytes: Uint8Array | null) => (bytes === null ? 0n : writeBytes(bytes));
+  return {
+    base64_encode: (p, l) => utf8(base64Encode(readBytes(p, l))),
+    base64_decode: (p, l) => orError(base64Decode(new TextDecoder().decode(readBytes(p, l)))),
+    base64url_encode: (p, l) => utf8(base64UrlEncode(readBytes(p, l))),
+    base64url_decode: (p, l) => orError(base64UrlDecode(new TextDecoder().decode(readBytes(p, l)))),
+    hex_encode: (p, l) => utf8(hexEncode(readBytes(p, l))),
+    hex_decode: (p, l) => orError(hexDecode(new TextDecoder().decode(readBytes(p, l)))),
+    url_encode: (p, l) => utf8(urlEncode(readBytes(p, l))),
+    url_decode: (p, l) => {
+      const text = urlDecode(readBytes(p, l));
+      return text === null ? 0n : utf8(text);
+    },
+  };
+}
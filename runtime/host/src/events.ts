@@ -0,0 +1,89 @@
+/**
+ * In-Process Event Bus
+ *
+ * Subscriber snippets compile to entries in the module's events table: an
+ * event struct name and the exported handler to call. The runners register
+ * those entries before instantiation, and `events.publish` calls every
+ * handler for the published type with the event value.
+ *
+ * ```
+ * covenant.events section ──► readEventTable() ──► EventBus.subscribe()
+ * events.publish(type, event) ──► EventBus.publish() ──► exports[handler](event)
+ * ```
+ *
+ * Event values are passed through untouched: the module hands its i64 value
+ * to the host split into (high, low) i32 halves, and the bus rejoins them
+ * before calling the handler.
+ */
+
+/** Custom section holding the events table (see crates/covenant-codegen/src/events.rs) */
+export const EVENTS_SECTION = "covenant.events";
+
+/** A handler bound to an event type. Mirrors the Rust Subscription struct. */
+export interface Subscription {
+  subscriber: string;
+  /** Event struct name without its module qualifier */
+  event: string;
+  /** Export name of the handler function */
+  handler: string;
+}
+
+/** Events table embedded by the compiler */
+export interface EventTable {
+  subscriptions: Subscription[];
+}
+
+/** Read the events table from a compiled module, if it has subscribers */
+export function readEventTable(module: WebAssembly.Module): EventTable | null {
+  const sections = WebAssembly.Module.customSections(module, EVENTS_SECTION);
+  if (sections.length === 0) {
+    return null;
+  }
+  return JSON.parse(new TextDecoder().decode(sections[0])) as EventTable;
+}
+
+/** Dispatches published events to handler exports in registration order */
+export class EventBus {
+  private handlers = new Map<string, string[]>();
+
+  constructor(private instance: () => WebAssembly.Instance | null) {}
+
+  /** Call `handler` for each event of `eventType` (qualified names allowed) */
+  subscribe(eventType: string, handler: string): void {
+    const name = eventName(eventType);
+    const handlers = this.handlers.get(name) ?? [];
+    handlers.push(handler);
+    this.handlers.set(name, handlers);
+  }
+
+  /** Call every handler subscribed to `eventType` with `event` */
+  publish(eventType: string, event: bigint): void {
+    const exports = this.instance()?.exports ?? {};
+    for (const handler of this.handlers.get(eventName(eventType)) ?? []) {
+      const fn = exports[handler];
+      if (typeof fn !== "function") {
+        console.error(`[runtime] event handler '${handler}' is not exported`);
+        continue;
+      }
+      (fn as (event: bigint) => void)(event);
+    }
+  }
+
+  /** The `events` import module, decoding (ptr, len) strings with `readStr` */
+  imports(readStr: (ptr: number, len: number) => string): Record<string, (...args: number[]) => void> {
+    return {
+      publish: (typePtr, typeLen, hi, lo) => {
+        const event = (BigInt(hi >>> 0) << 32n) | BigInt(lo >>> 0);
+        this.publish(readStr(typePtr, typeLen), event);
+      },
+      subscribe: (typePtr, typeLen, handlerPtr, handlerLen) => {
+        this.subscribe(readStr(typePtr, typeLen), readStr(handlerPtr, handlerLen));
+      },
+    };
+  }
+}
+
+/** Events dispatch on the struct name, so `orders.OrderPlaced` is `OrderPlaced` */
+function eventName(eventType: string): string {
+  return eventType.split(".").pop() ?? eventType;
+}
@@ -0,0 +1,43 @@
+/**
+ * Module Initialization
+ *
+ * Every compiled module exports `_initialize`, which a host calls once after
+ * instantiating it and before its entry function. It registers each
+ * database snippet as a query target through `db.register_target`, a target
+ * after those it has a `depends_on` relation to, so a host can resolve one
+ * target's connection through another. Calling it again does nothing.
+ */
+
+/** Init export (see crates/covenant-codegen/src/init.rs) */
+export const INIT_EXPORT = "_initialize";
+
+/** A database a module's queries name in `target=` */
+export interface QueryTarget {
+  /** Last segment of the database snippet's ID */
+  name: string;
+  /** `dialect` from the snippet's metadata, or empty */
+  dialect: string;
+  /** `connection` from the snippet's metadata, or empty */
+  connection: string;
+}
+
+/** `db.register_target`, collecting the targets into `targets` by name */
+export function registerTargetImport(
+  readStr: (ptr: number, len: number) => string,
+  targets: Map<string, QueryTarget>,
+): (...args: number[]) => void {
+  return (namePtr, nameLen, dialectPtr, dialectLen, connPtr, connLen) => {
+    const name = readStr(namePtr, nameLen);
+    targets.set(name, {
+      name,
+      dialect: readStr(dialectPtr, dialectLen),
+      connection: readStr(connPtr, connLen),
+    });
+  };
+}
+
+/** Run the instance's initialization, if it has any */
+export function initializeModule(instance: WebAssembly.Instance): void {
+  const init = instance.exports[INIT_EXPORT] as (() => void) | undefined;
+  init?.();
+}
@@ -0,0 +1,79 @@
+/**
+ * Json Documents
+ *
+ * Implements the `json_` compute ops. A `Json` value crosses the module
+ * boundary as the document's compact text, which is also its runtime value,
+ * so `json_stringify` needs no import. The imports are the `json_doc` module,
+ * apart from the `json.*` extern snippets a program may declare.
+ *
+ * - `parse` returns 0, the DecodeError member of the op's result
+ *   union, on malformed input
+ * - `get` and `set` take the key as a String; on an array it must
+ *   spell an index (`"0"`, `"12"`). `get` returns none (the i64::MIN
+ *   sentinel) when there is no such field or element
+ * - `keys` returns an object's keys, and no keys for anything else
+ */
+
+/** The none sentinel for optional results */
+const NONE = -(1n << 63n);
+
+type Document = null | boolean | number | string | Document[] | { [key: string]: Document };
+
+const isObject = (doc: Document): doc is { [key: string]: Document } =>
+  doc !== null && typeof doc === "object" && !Array.isArray(doc);
+
+/** The array index a key spells, or null */
+function arrayIndex(key: string): number | null {
+  return /^(0|[1-9][0-9]*)$/.test(key) ? Number(key) : null;
+}
+
+/** The field or element at `key`, or undefined */
+export function jsonGet(doc: Document, key: string): Document | undefined {
+  if (Array.isArray(doc)) {
+    const index = arrayIndex(key);
+    return index === null ? undefined : doc[index];
+  }
+  return isObject(doc) && Object.hasOwn(doc, key) ? doc[key] : undefined;
+}
+
+/** A copy of `doc` with the field or element at `key` set to `value` */
+export function jsonSet(doc: Document, key: string, value: Document): Document {
+  if (Array.isArray(doc)) {
+    const index = arrayIndex(key);
+    if (index === null || index > doc.length) return doc;
+    const copy = [...doc];
+    copy[index] = value;
+    return copy;
+  }
+  return isObject(doc) ? { ...doc, [key]: value } : doc;
+}
+
+// ===== Imports =====
+
+/** The `json_doc` import module, reading and writing values through the given helpers */
+export function jsonImports(
+  readStr: (ptr: number, len: number) => string,
+  writeStr: (s: string) => bigint,
+  writeStrArray: (parts: string[]) => bigint,
+): Record<string, (...args: number[]) => bigint> {
+  const doc = (p: number, l: number) => JSON.parse(readStr(p, l)) as Document;
+  const out = (value: Document) => writeStr(JSON.stringify(value));
+  return {
+    parse: (p, l) => {
+      try {
+        return out(doc(p, l));
+      } catch {
+        return 0n;
+      }
+    },
+    get: (jp, jl, kp, kl) => {
+      const found = jsonGet(doc(jp, jl), readStr(kp, kl));
+      return found === undefined ? NONE : out(found);
+    },
+    set: (jp, jl, kp, kl, vp, vl) => out(jsonSet(doc(jp, jl), readStr(kp, kl), doc(vp, vl))),
+    keys: (p, l) => {
+      const value = doc(p, l);
+      return writeStrArray(isObject(value) ? Object.keys(value) : []);
+    },
+  };
+}
@@ -30,24 +30,36 @@ import {
   EmbeddableSymbol,
   DEFAULT_ENFORCEMENT_OPTIONS,
   buildManifestFromSymbols,
+  symbolsFromMetadata,
   emptyManifest,
   isImportAllowed,
+  isResourceAllowed,
+  getDeniedResourceError,
+  IMPORT_RESOURCE_PARAMS,
   createDeniedStub,
   parseImportName,
   EFFECT_TO_IMPORTS,
   IMPORT_TO_EFFECT,
 } from "./capabilities.ts";
+import { AuditSink, auditImports, readAuditTable } from "./audit.ts";
+import { QueryTarget, initializeModule, registerTargetImport } from "./init.ts";
+import { ReflectionTable, readReflection } from "./reflection.ts";
 
 // Re-export capability types for external use
 export type {
   CapabilityManifest,
   CapabilityEnforcementOptions,
+  EffectScope,
   EmbeddableSymbol,
 } from "./capabilities.ts";
 
+export type { AuditRecord, AuditSink } from "./audit.ts";
+
 export {
   EFFECT_TO_IMPORTS,
   IMPORT_TO_EFFECT,
+  SYMBOL_METADATA_VERSION,
+  symbolsFromMetadata,
 } from "./capabilities.ts";
 
 // Type definitions matching the WIT interfaces
@@ -123,11 +135,33 @@ interface QueryModule {
 interface MutationModule {
   parseSnippet(source: string): MutationResult;
   updateSnippet(id: string, source: string): MutationResult;
+  reloadSnippet(source: string): MutationResult;
   deleteSnippet(id: string): boolean;
   compileSnippet(id: string): CompileResult;
   recompileSnippet(id: string, source: string): CompileResult;
 }
 
+/**
+ * Host name of a URL; unparseable URLs have none, so only an unrestricted
+ * scope admits them.
+ */
+function hostOf(url: string): string {
+  try {
+    return new URL(url).hostname;
+  } catch {
+    return "";
+  }
+}
+
+/**
+ * Where to report effectful extern calls, and how to hash their arguments
+ * (e.g. SHA-256 hex via node:crypto)
+ */
+export interface AuditOptions {
+  sink: AuditSink;
+  hash: (text: string) => string;
+}
+
 /**
  * Main host class that loads and manages Covenant WASM modules
  */
@@ -135,15 +169,21 @@ export class CovenantHost {
   private modules = new Map<string, WebAssembly.Instance>();
   private memory: WebAssembly.Memory | null = null;
   private manifests = new Map<string, CapabilityManifest>();
+  private queryTargets = new Map<string, QueryTarget>();
   private enforcementOptions: CapabilityEnforcementOptions;
+  private audit: AuditOptions | null;
 
   // Module interfaces (populated after loading)
   public symbols: SymbolsModule | null = null;
   public query: QueryModule | null = null;
   public mutation: MutationModule | null = null;
 
-  constructor(options?: Partial<CapabilityEnforcementOptions>) {
+  constructor(
+    options?: Partial<CapabilityEnforcementOptions>,
+    audit?: AuditOptions
+  ) {
     this.enforcementOptions = { ...DEFAULT_ENFORCEMENT_OPTIONS, ...options };
+    this.audit = audit ?? null;
   }
 
   /**
@@ -170,18 +210,29 @@ export class CovenantHost {
     // Now build filtered imports based on the manifest
     const filteredImports = this.buildFilteredImports(name, manifest);
 
+    // Report effectful extern calls if auditing
+    const auditTable = this.audit ? readAuditTable(wasmModule) : null;
+    let auditedInstance: WebAssembly.Instance | null = null;
+    const linkedImports =
+      this.audit && auditTable
+        ? auditImports(filteredImports, auditTable, this.audit.sink, {
+            memory: () => this.memory,
+            instance: () => auditedInstance,
+            hash: this.audit.hash,
+          })
+        : filteredImports;
+
     // Instantiate with filtered imports
     const instance = await WebAssembly.instantiate(
       wasmModule,
-      filteredImports
+      linkedImports
     );
+    auditedInstance = instance;
     this.modules.set(name, instance);
 
-    // Initialize if the module has an _initialize export
-    const init = instance.exports._initialize as (() => void) | undefined;
-    if (init) {
-      init();
-    }
+    // Initialize if the module has an _initialize export, registering its
+    // query targets
+    initializeModule(instance);
 
     // Update module interfaces
     this.updateModuleInterfaces();
@@ -194,6 +245,22 @@ export class CovenantHost {
     return this.manifests.get(moduleName);
   }
 
+  /**
+   * Get the functions a loaded module exports, with their types and table
+   * slots
+   */
+  getReflection(moduleName: string): ReflectionTable | null {
+    const instance = this.modules.get(moduleName);
+    return instance ? readReflection(instance) : null;
+  }
+
+  /**
+   * Get a database target registered by a loaded module
+   */
+  getQueryTarget(name: string): QueryTarget | undefined {
+    return this.queryTargets.get(name);
+  }
+
   /**
    * Initialize the runtime by loading core modules in dependency order
    */
@@ -234,7 +301,10 @@ export class CovenantHost {
   }
 
   /**
-   * Update a snippet and trigger recompilation
+   * Hot-reload a running snippet and trigger recompilation
+   *
+   * Fails without touching the running program if the snippet's signature
+   * or effects changed, or if it or its dependents no longer check.
    */
   async reloadSnippet(
     snippetId: string,
@@ -250,8 +320,8 @@ export class CovenantHost {
       return parseResult;
     }
 
-    // 2. Update symbol store
-    const updateResult = this.mutation.updateSnippet(snippetId, newSource);
+    // 2. Re-check and swap into the symbol store
+    const updateResult = this.mutation.reloadSnippet(newSource);
     if (!updateResult.success) {
       return updateResult;
     }
@@ -377,12 +447,12 @@ export class CovenantHost {
       ),
       http: this.filterImportObject(
         "http",
-        this.buildNetworkImport(),
+        this.scopeImportObject("http", this.buildNetworkImport(), manifest),
         manifest
       ),
       fs: this.filterImportObject(
         "fs",
-        this.buildFilesystemEffectImport(),
+        this.scopeImportObject("fs", this.buildFilesystemEffectImport(), manifest),
         manifest
       ),
       console: this.filterImportObject(
@@ -467,6 +537,46 @@ export class CovenantHost {
     return filtered;
   }
 
+  /**
+   * Confine resource-taking imports to the manifest's effect scopes.
+   * Each function's first argument is a (ptr, len) string naming the
+   * resource: a path for `fs`, a URL for `http` (checked by host).
+   */
+  private scopeImportObject(
+    modulePrefix: string,
+    realImports: Record<string, Function>,
+    manifest: CapabilityManifest
+  ): Record<string, Function> {
+    const param = IMPORT_RESOURCE_PARAMS[modulePrefix];
+    const scoped: Record<string, Function> = {};
+
+    for (const [funcName, impl] of Object.entries(realImports)) {
+      const importName = `${modulePrefix}.${funcName}`;
+
+      scoped[funcName] = (ptr: number, len: number, ...rest: unknown[]) => {
+        const resource = this.readString(ptr, len);
+        const value = param === "host" ? hostOf(resource) : resource;
+
+        // Non-strict mode: out-of-scope resources reach the real implementation
+        if (
+          this.enforcementOptions.strict &&
+          !isResourceAllowed(manifest, importName, param, value)
+        ) {
+          const message = getDeniedResourceError(importName, param, value);
+          if (this.enforcementOptions.warnOnly) {
+            console.warn(`[capability] ${message}`);
+            this.enforcementOptions.onDenied?.(importName);
+            return 0;
+          }
+          throw new Error(message);
+        }
+        return impl(ptr, len, ...rest);
+      };
+    }
+
+    return scoped;
+  }
+
   /**
    * Extract capability manifest from a WASM instance's embedded metadata.
    *
@@ -503,7 +613,7 @@ export class CovenantHost {
       // Read JSON from memory
       const bytes = new Uint8Array(memory.buffer, offset, length);
       const json = new TextDecoder().decode(bytes);
-      const symbols: EmbeddableSymbol[] = JSON.parse(json);
+      const symbols: EmbeddableSymbol[] = symbolsFromMetadata(JSON.parse(json));
 
       return buildManifestFromSymbols(symbols);
     } catch (error) {
@@ -616,6 +726,7 @@ export class CovenantHost {
   private buildDatabaseStubs(): Record<string, Function> {
     return {
       execute_query: () => 0,
+      register_target: () => {},
     };
   }
 
@@ -757,6 +868,10 @@ export class CovenantHost {
         // TODO: Implement actual database execution
         return 0;
       },
+      register_target: registerTargetImport(
+        (ptr, len) => this.readString(ptr, len),
+        this.queryTargets
+      ),
     };
   }
 
@@ -1229,3 +1344,4 @@ async function main() {
 if (typeof require !== "undefined" && require.main === module) {
   main();
 }
+
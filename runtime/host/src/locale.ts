@@ -0,0 +1,94 @@
+/**
+ * Locale-Aware Text
+ *
+ * Implements the `locale` std module (runtime/std/text/locale.cov) on the
+ * host's Intl and String.prototype.normalize, which carry ICU's case
+ * mapping, normalization and collation tables. Results depend on the
+ * host's Unicode and CLDR data, so these imports are gated by the `locale`
+ * effect.
+ *
+ * `text.upper` and `text.lower` stay pure: they map ASCII letters only and
+ * give the same result on every host.
+ *
+ * ```
+ * locale.upper(s, tag) ──► s.toLocaleUpperCase(tag)
+ * locale.casefold(s)   ──► s.toUpperCase().toLowerCase()
+ * locale.normalize(s)  ──► s.normalize(form)
+ * locale.collate(a, b) ──► Intl.Collator(tag).compare(a, b)
+ * ```
+ *
+ * Invalid locale tags fall back to the root locale and unknown
+ * normalization forms leave the input unchanged, matching the safe defaults
+ * of the regex functions.
+ */
+
+/** Normalization forms accepted by `locale.normalize` */
+export const NORMALIZATION_FORMS = ["NFC", "NFD", "NFKC", "NFKD"];
+
+/** Uppercase ASCII letters only; every other character is unchanged */
+export function asciiUpper(s: string): string {
+  return s.replace(/[a-z]/g, (c) => String.fromCharCode(c.charCodeAt(0) - 32));
+}
+
+/** Lowercase ASCII letters only; every other character is unchanged */
+export function asciiLower(s: string): string {
+  return s.replace(/[A-Z]/g, (c) => String.fromCharCode(c.charCodeAt(0) + 32));
+}
+
+/** Uppercase with the rules of `tag` (e.g. "tr" maps i to İ) */
+export function localeUpper(s: string, tag: string): string {
+  try {
+    return s.toLocaleUpperCase(tag || undefined);
+  } catch {
+    return s.toUpperCase();
+  }
+}
+
+/** Lowercase with the rules of `tag` (e.g. "tr" maps I to ı) */
+export function localeLower(s: string, tag: string): string {
+  try {
+    return s.toLocaleLowerCase(tag || undefined);
+  } catch {
+    return s.toLowerCase();
+  }
+}
+
+/**
+ * Locale-independent case folding for caseless comparison. Upper- then
+ * lowercasing applies the full case mappings, so "Straße" and "STRASSE"
+ * both fold to "strasse" and final sigma folds to σ.
+ */
+export function casefold(s: string): string {
+  return s.toUpperCase().toLowerCase();
+}
+
+/** Normalize to `form` (NFC, NFD, NFKC or NFKD) */
+export function normalize(s: string, form: string): string {
+  return NORMALIZATION_FORMS.includes(form) ? s.normalize(form) : s;
+}
+
+/** Compare with the collation of `tag`: -1, 0 or 1 */
+export function collate(a: string, b: string, tag: string): number {
+  let collator: Intl.Collator;
+  try {
+    collator = new Intl.Collator(tag || undefined);
+  } catch {
+    collator = new Intl.Collator();
+  }
+  return Math.sign(collator.compare(a, b));
+}
+
+/** The `locale` import module, reading and writing strings through the given helpers */
+export function localeImports(
+  readStr: (ptr: number, len: number) => string,
+  writeStr: (s: string) => bigint,
+): Record<string, (...args: number[]) => bigint> {
+  return {
+    upper: (sp, sl, tp, tl) => writeStr(localeUpper(readStr(sp, sl), readStr(tp, tl))),
+    lower: (sp, sl, tp, tl) => writeStr(localeLower(readStr(sp, sl), readStr(tp, tl))),
+    casefold: (sp, sl) => writeStr(casefold(readStr(sp, sl))),
+    normalize: (sp, sl, fp, fl) => writeStr(normalize(readStr(sp, sl), readStr(fp, fl))),
+    collate: (ap, al, bp, bl, tp, tl) =>
+      BigInt(collate(readStr(ap, al), readStr(bp, bl), readStr(tp, tl))),
+  };
+}
@@ -0,0 +1,46 @@
+/**
+ * Module Memory
+ *
+ * Every compiled module allocates from one heap through its `cov_alloc`
+ * export, and its memory can only grow up to the limit it was compiled with
+ * (`covenant compile --max-memory-pages`); an allocation past the limit
+ * traps. Hosts allocate the strings they return through the same export, so
+ * those count toward the limit and the stats too.
+ */
+
+/** Allocator exports (see crates/covenant-codegen/src/memory.rs) */
+export const ALLOC_EXPORT = "cov_alloc";
+export const STATS_EXPORT = "cov_memory_stats";
+export const RESET_EXPORT = "cov_heap_reset";
+
+/** Heap usage of a module instance */
+export interface MemoryStats {
+  /** Bytes allocated since instantiation or the last heap reset */
+  allocated: number;
+  /** Most bytes allocated at once */
+  peak: number;
+  /** Current size of the memory in 64 KiB pages */
+  pages: number;
+}
+
+/** Allocate `size` bytes, 8-byte aligned, from the instance's heap */
+export function moduleAllocator(instance: WebAssembly.Instance): (size: number) => number {
+  const alloc = instance.exports[ALLOC_EXPORT] as (size: number) => number;
+  // i32 results are signed in JavaScript
+  return (size) => alloc(size) >>> 0;
+}
+
+/** Read the heap usage of an instance, if it has an allocator */
+export function readMemoryStats(instance: WebAssembly.Instance): MemoryStats | null {
+  const stats = instance.exports[STATS_EXPORT] as (() => bigint) | undefined;
+  const memory = instance.exports.memory as WebAssembly.Memory | undefined;
+  if (!stats || !memory) {
+    return null;
+  }
+  const packed = BigInt.asUintN(64, stats());
+  return {
+    allocated: Number(packed >> 32n),
+    peak: Number(packed & 0xffffffffn),
+    pages: memory.buffer.byteLength / 65536,
+  };
+}
@@ -0,0 +1,272 @@
+/**
+ * OpenTelemetry Traces of a Run
+ *
+ * With an OTLP endpoint, the runner records each execution of the entry
+ * function as one trace and posts it to the collector when the run ends.
+ * The spans come from the same audit table as the audit log (see
+ * audit.ts), so production telemetry points back to snippet and step IDs:
+ *
+ * ```
+ * covenant.run <entry>                      root span, one per execution
+ * └── <calling snippet>                     step group: consecutive effectful
+ *     ├── <extern snippet>                  calls made from one snippet
+ *     └── db.query                          query against a target
+ * ```
+ *
+ * Extern spans carry `covenant.snippet` and `covenant.step` for the call
+ * site, plus `covenant.extern`, `covenant.contract` and `covenant.effects`.
+ * Query spans carry `db.statement`, and `db.system` and `db.name` when the
+ * module registered a single query target. Arguments are never recorded,
+ * and a call with sensitive arguments that throws records `REDACTED` as
+ * its status message, since host errors often quote their input.
+ *
+ * Traces are sent as OTLP/HTTP JSON to `<endpoint>/v1/traces`.
+ */
+
+import { AuditTable, REDACTED, currentSite } from "./audit.ts";
+import { QueryTarget } from "./init.ts";
+
+/** Path the OTLP/HTTP receiver accepts traces on */
+export const OTLP_TRACES_PATH = "/v1/traces";
+
+/** Scope name reported for every span */
+export const TRACER_SCOPE = "covenant.runtime";
+
+/** OTLP span kinds (opentelemetry.proto.trace.v1.Span.SpanKind) */
+const SPAN_KIND_INTERNAL = 1;
+const SPAN_KIND_CLIENT = 3;
+
+/** OTLP status codes (opentelemetry.proto.trace.v1.Status.StatusCode) */
+const STATUS_OK = 1;
+const STATUS_ERROR = 2;
+
+type AttributeValue = string | number | string[];
+
+/** A span as encoded in OTLP JSON */
+export interface OtlpSpan {
+  traceId: string;
+  spanId: string;
+  parentSpanId?: string;
+  name: string;
+  kind: number;
+  startTimeUnixNano: string;
+  endTimeUnixNano: string;
+  attributes: Array<{ key: string; value: Record<string, unknown> }>;
+  status: { code: number; message?: string };
+}
+
+/** A span that has started and not yet ended */
+interface OpenSpan {
+  spanId: string;
+  parentSpanId?: string;
+  name: string;
+  kind: number;
+  start: bigint;
+  attributes: Record<string, AttributeValue>;
+}
+
+/** Collects the spans of one execution */
+export class Tracer {
+  readonly traceId = randomHex(16);
+  private readonly finished: OtlpSpan[] = [];
+  private readonly root: OpenSpan;
+  /** The open step group and the snippet whose calls it holds */
+  private group: { snippet: string; span: OpenSpan; end: bigint } | null = null;
+
+  constructor(entry: string, attributes: Record<string, AttributeValue> = {}) {
+    this.root = this.open(`covenant.run ${entry}`, SPAN_KIND_INTERNAL, undefined, {
+      "covenant.entry": entry,
+      ...attributes,
+    });
+  }
+
+  /**
+   * Start a span for an effectful call made from `snippet`, inside that
+   * snippet's step group. A call from another snippet closes the group.
+   */
+  startCall(name: string, snippet: string | null, attributes: Record<string, AttributeValue>): OpenSpan {
+    const groupName = snippet ?? "(unknown)";
+    if (this.group?.snippet !== groupName) {
+      this.closeGroup();
+      const span = this.open(groupName, SPAN_KIND_INTERNAL, this.root.spanId, { "covenant.snippet": groupName });
+      this.group = { snippet: groupName, span, end: span.start };
+    }
+    return this.open(name, SPAN_KIND_CLIENT, this.group!.span.spanId, attributes);
+  }
+
+  /** End a call span, failed if `error` is given */
+  endCall(span: OpenSpan, error?: unknown): void {
+    const end = now();
+    this.close(span, end, error);
+    if (this.group) {
+      this.group.end = end;
+    }
+  }
+
+  /** End the execution, failed if `error` is given, and return every span */
+  finish(error?: unknown): OtlpSpan[] {
+    this.closeGroup();
+    this.close(this.root, now(), error);
+    return this.finished;
+  }
+
+  private open(
+    name: string,
+    kind: number,
+    parentSpanId: string | undefined,
+    attributes: Record<string, AttributeValue>
+  ): OpenSpan {
+    return { spanId: randomHex(8), parentSpanId, name, kind, start: now(), attributes };
+  }
+
+  private closeGroup(): void {
+    if (this.group) {
+      this.close(this.group.span, this.group.end);
+      this.group = null;
+    }
+  }
+
+  private close(span: OpenSpan, end: bigint, error?: unknown): void {
+    this.finished.push({
+      traceId: this.traceId,
+      spanId: span.spanId,
+      ...(span.parentSpanId ? { parentSpanId: span.parentSpanId } : {}),
+      name: span.name,
+      kind: span.kind,
+      startTimeUnixNano: span.start.toString(),
+      endTimeUnixNano: end.toString(),
+      attributes: Object.entries(span.attributes).map(([key, value]) => ({ key, value: encodeValue(value) })),
+      status: error === undefined
+        ? { code: STATUS_OK }
+        : { code: STATUS_ERROR, message: error instanceof Error ? error.message : String(error) },
+    });
+  }
+}
+
+/** How the tracer reaches the running module and its query targets */
+export interface TraceContext {
+  instance: () => WebAssembly.Instance | null;
+  readStr: (ptr: number, len: number) => string;
+  targets: Map<string, QueryTarget>;
+}
+
+/**
+ * Wrap the audited externs in `imports`, and `db.execute_query`, so each
+ * call becomes a span of `tracer`. Other imports are returned unchanged.
+ */
+export function traceImports(
+  imports: WebAssembly.Imports,
+  table: AuditTable | null,
+  tracer: Tracer,
+  context: TraceContext
+): WebAssembly.Imports {
+  const traced: WebAssembly.Imports = { ...imports };
+
+  const wrap = (
+    moduleName: string,
+    funcName: string,
+    span: (args: unknown[]) => { name: string; snippet: string | null; attributes: Record<string, AttributeValue> },
+    redact = false
+  ) => {
+    const original = imports[moduleName]?.[funcName];
+    if (typeof original !== "function") {
+      return;
+    }
+    traced[moduleName] = {
+      ...traced[moduleName],
+      [funcName]: (...args: unknown[]) => {
+        const { name, snippet, attributes } = span(args);
+        const open = tracer.startCall(name, snippet, attributes);
+        try {
+          const result = (original as Function)(...args);
+          tracer.endCall(open);
+          return result;
+        } catch (e) {
+          tracer.endCall(open, redact ? new Error(REDACTED) : e);
+          throw e;
+        }
+      },
+    };
+  };
+
+  for (const [importName, ext] of Object.entries(table?.externs ?? {})) {
+    const lastDot = importName.lastIndexOf(".");
+    wrap(importName.substring(0, lastDot), importName.substring(lastDot + 1), () => {
+      const site = table ? currentSite(table, context.instance()) : undefined;
+      return {
+        name: ext.snippet,
+        snippet: site?.snippet ?? null,
+        attributes: {
+          "covenant.snippet": site?.snippet ?? "",
+          "covenant.step": site?.step ?? "",
+          "covenant.extern": ext.snippet,
+          "covenant.contract": ext.contract ?? "",
+          "covenant.effects": ext.effects,
+        },
+      };
+    }, (ext.sensitive ?? []).length > 0);
+  }
+
+  wrap("db", "execute_query", (args) => {
+    const attributes: Record<string, AttributeValue> = {
+      "db.statement": context.readStr(Number(args[0]), Number(args[1])),
+    };
+    // Queries do not name their target at runtime; with one target it is unambiguous
+    if (context.targets.size === 1) {
+      const [target] = context.targets.values();
+      attributes["db.name"] = target.name;
+      if (target.dialect) {
+        attributes["db.system"] = target.dialect;
+      }
+    }
+    return { name: "db.query", snippet: null, attributes };
+  });
+
+  return traced;
+}
+
+/** The OTLP/HTTP JSON request body for `spans` */
+export function otlpRequest(spans: OtlpSpan[], serviceName: string): unknown {
+  return {
+    resourceSpans: [{
+      resource: { attributes: [{ key: "service.name", value: { stringValue: serviceName } }] },
+      scopeSpans: [{ scope: { name: TRACER_SCOPE }, spans }],
+    }],
+  };
+}
+
+/** Post `spans` to the collector at `endpoint`, reporting failures without throwing */
+export async function exportSpans(endpoint: string, spans: OtlpSpan[], serviceName: string): Promise<void> {
+  const url = endpoint.endsWith(OTLP_TRACES_PATH)
+    ? endpoint
+    : endpoint.replace(/\/+$/, "") + OTLP_TRACES_PATH;
+  try {
+    const response = await fetch(url, {
+      method: "POST",
+      headers: { "content-type": "application/json" },
+      body: JSON.stringify(otlpRequest(spans, serviceName)),
+    });
+    if (!response.ok) {
+      console.error(`[runtime] OTLP export to ${url} failed: ${response.status} ${response.statusText}`);
+    }
+    await response.body?.cancel();
+  } catch (e) {
+    console.error(`[runtime] OTLP export to ${url} failed: ${e instanceof Error ? e.message : e}`);
+  }
+}
+
+function encodeValue(value: AttributeValue): Record<string, unknown> {
+  if (Array.isArray(value)) {
+    return { arrayValue: { values: value.map((v) => ({ stringValue: v })) } };
+  }
+  return typeof value === "number" ? { intValue: String(value) } : { stringValue: value };
+}
+
+/** Current time in nanoseconds since the Unix epoch */
+function now(): bigint {
+  return BigInt(Math.round((performance.timeOrigin + performance.now()) * 1e6));
+}
+
+function randomHex(bytes: number): string {
+  return Array.from(crypto.getRandomValues(new Uint8Array(bytes)), (b) => b.toString(16).padStart(2, "0")).join("");
+}
@@ -0,0 +1,60 @@
+/**
+ * Function Reflection
+ *
+ * Every compiled module describes its exported functions through its
+ * `cov_reflect` export, a fat pointer to JSON giving each function's name,
+ * snippet ID, param and result types, and slot in the `cov_functions`
+ * table. Hosts use it to list and call functions they were not built for.
+ */
+
+/** Reflection exports (see crates/covenant-codegen/src/reflection.rs) */
+export const REFLECT_EXPORT = "cov_reflect";
+export const FUNCTIONS_EXPORT = "cov_functions";
+
+/** A value's Covenant type and the WASM type carrying it */
+export interface TypeInfo {
+  /** Covenant type as written in source, e.g. "Int" or "String?" */
+  type: string;
+  /** "i64" or "f64"; Strings, lists and structs are i64 fat pointers */
+  wasm: string;
+}
+
+export interface ParamInfo extends TypeInfo {
+  name: string;
+}
+
+/** How to call one exported function */
+export interface FunctionInfo {
+  name: string;
+  id: string;
+  slot: number;
+  params: ParamInfo[];
+  /** null for Unit */
+  returns: TypeInfo | null;
+}
+
+export interface ReflectionTable {
+  version: number;
+  functions: FunctionInfo[];
+}
+
+/** Read the instance's reflection table, if it has one */
+export function readReflection(instance: WebAssembly.Instance): ReflectionTable | null {
+  const reflect = instance.exports[REFLECT_EXPORT] as (() => bigint) | undefined;
+  const memory = instance.exports.memory as WebAssembly.Memory | undefined;
+  if (!reflect || !memory) {
+    return null;
+  }
+  const packed = BigInt.asUintN(64, reflect());
+  const bytes = new Uint8Array(memory.buffer, Number(packed >> 32n), Number(packed & 0xffffffffn));
+  return JSON.parse(new TextDecoder().decode(bytes)) as ReflectionTable;
+}
+
+/** The function a reflection entry describes, called through the table */
+export function reflectedFunction(
+  instance: WebAssembly.Instance,
+  info: FunctionInfo,
+): (...args: (bigint | number)[]) => unknown {
+  const table = instance.exports[FUNCTIONS_EXPORT] as WebAssembly.Table;
+  return table.get(info.slot) as (...args: (bigint | number)[]) => unknown;
+}
@@ -0,0 +1,512 @@
+/**
+ * Regex Engine
+ *
+ * Implements the `regex_match`, `regex_find_all`, `regex_capture` and
+ * `regex_replace` compute ops with a Pike VM: the pattern is compiled to a small instruction
+ * program and every thread advances in lockstep over the input, so matching
+ * takes time linear in the input for a given pattern. There is no
+ * backtracking, and so no catastrophic patterns.
+ *
+ * The checker accepts only the subset implemented here (see
+ * crates/covenant-checker/src/regex.rs), so patterns reaching the host are
+ * well formed:
+ *
+ * ```
+ * literals, escapes, .          [a-z_], [^0-9]        \d \w \s \D \W \S
+ * (a)  (?:a)  (?<name>a)  a|b   * + ? {n} {n,} {n,m}   lazy forms (*? ...)
+ * ^ $ \A \z (start/end of text) \b \B
+ * ```
+ *
+ * Classes and word boundaries are ASCII, `.` matches anything but `\n`,
+ * and positions count Unicode code points. Searches are leftmost-first,
+ * like Rust's `regex` crate.
+ */
+
+type Pred = (c: string) => boolean;
+
+type Node =
+  | { t: "empty" }
+  | { t: "char"; pred: Pred }
+  | { t: "assert"; kind: "start" | "end" | "word" | "notword" }
+  | { t: "group"; index: number | null; node: Node }
+  | { t: "repeat"; node: Node; min: number; max: number; greedy: boolean }
+  | { t: "concat"; nodes: Node[] }
+  | { t: "alt"; nodes: Node[] };
+
+type Inst =
+  | { op: "char"; pred: Pred }
+  | { op: "split"; x: number; y: number }
+  | { op: "jmp"; to: number }
+  | { op: "save"; slot: number }
+  | { op: "assert"; kind: "start" | "end" | "word" | "notword" }
+  | { op: "match" };
+
+/** A compiled pattern */
+export interface Regex {
+  prog: Inst[];
+  /** Capture groups, not counting the whole match */
+  groups: number;
+  /** Group index of each named group */
+  names: Map<string, number>;
+}
+
+const isDigit: Pred = (c) => c >= "0" && c <= "9";
+const isWord: Pred = (c) => isDigit(c) || (c >= "a" && c <= "z") || (c >= "A" && c <= "Z") || c === "_";
+const isSpace: Pred = (c) => c === " " || (c >= "\t" && c <= "\r");
+const not = (pred: Pred): Pred => (c) => !pred(c);
+
+const SIMPLE_ESCAPES: Record<string, string> = {
+  n: "\n", t: "\t", r: "\r", f: "\f", v: "\v", a: "\x07",
+};
+
+class Parser {
+  private pos = 0;
+  groups = 0;
+  names = new Map<string, number>();
+
+  constructor(private readonly src: string[]) {}
+
+  parse(): Node {
+    const node = this.alternation();
+    if (this.pos < this.src.length) throw new Error(`unexpected ')' at ${this.pos}`);
+    return node;
+  }
+
+  private peek(): string | undefined {
+    return this.src[this.pos];
+  }
+
+  private eat(s: string): boolean {
+    if (this.src[this.pos] === s) {
+      this.pos++;
+      return true;
+    }
+    return false;
+  }
+
+  private alternation(): Node {
+    const nodes = [this.concat()];
+    while (this.eat("|")) nodes.push(this.concat());
+    return nodes.length === 1 ? nodes[0] : { t: "alt", nodes };
+  }
+
+  private concat(): Node {
+    const nodes: Node[] = [];
+    while (this.pos < this.src.length && this.peek() !== "|" && this.peek() !== ")") {
+      nodes.push(this.repetition(this.atom()));
+    }
+    if (nodes.length === 0) return { t: "empty" };
+    return nodes.length === 1 ? nodes[0] : { t: "concat", nodes };
+  }
+
+  private repetition(node: Node): Node {
+    for (;;) {
+      let range: [number, number] | null;
+      if (this.eat("*")) range = [0, -1];
+      else if (this.eat("+")) range = [1, -1];
+      else if (this.eat("?")) range = [0, 1];
+      else if ((range = this.counted())) this.skipCounted();
+      else return node;
+      const [min, max] = range;
+      const greedy = !this.eat("?");
+      node = { t: "repeat", node, min, max, greedy };
+    }
+  }
+
+  /** Parse `{n}`, `{n,}` or `{n,m}` at the cursor without consuming it */
+  private counted(): [number, number] | null {
+    if (this.peek() !== "{") return null;
+    const m = /^\{(\d+)(,(\d*))?\}/.exec(this.src.slice(this.pos, this.pos + 24).join(""));
+    if (!m) return null;
+    const min = Number(m[1]);
+    const max = m[2] === undefined ? min : m[3] === "" ? -1 : Number(m[3]);
+    return [min, max];
+  }
+
+  private skipCounted() {
+    while (this.src[this.pos++] !== "}");
+  }
+
+  private atom(): Node {
+    const c = this.src[this.pos++];
+    switch (c) {
+      case ".":
+        return { t: "char", pred: (x) => x !== "\n" };
+      case "^":
+        return { t: "assert", kind: "start" };
+      case "$":
+        return { t: "assert", kind: "end" };
+      case "[":
+        return { t: "char", pred: this.bracketed() };
+      case "(":
+        return this.group();
+      case "\\": {
+        const e = this.src[this.pos++];
+        if (e === "A") return { t: "assert", kind: "start" };
+        if (e === "z") return { t: "assert", kind: "end" };
+        if (e === "b") return { t: "assert", kind: "word" };
+        if (e === "B") return { t: "assert", kind: "notword" };
+        return { t: "char", pred: this.escape(e) };
+      }
+      default:
+        return { t: "char", pred: (x) => x === c };
+    }
+  }
+
+  private group(): Node {
+    let index: number | null = null;
+    if (this.eat("?")) {
+      if (this.eat("<") || (this.eat("P") && this.eat("<"))) {
+        let name = "";
+        while (!this.eat(">")) name += this.src[this.pos++];
+        index = ++this.groups;
+        this.names.set(name, index);
+      } else if (!this.eat(":")) {
+        throw new Error(`unsupported group at ${this.pos}`);
+      }
+    } else {
+      index = ++this.groups;
+    }
+    const node = this.alternation();
+    if (!this.eat(")")) throw new Error("unclosed group");
+    return { t: "group", index, node };
+  }
+
+  /** Predicate for the escape `\e` (after the backslash) */
+  private escape(e: string): Pred {
+    switch (e) {
+      case "d": return isDigit;
+      case "D": return not(isDigit);
+      case "w": return isWord;
+      case "W": return not(isWord);
+      case "s": return isSpace;
+      case "S": return not(isSpace);
+    }
+    const ch = this.escapedChar(e);
+    return (x) => x === ch;
+  }
+
+  /** The character denoted by `\e`: a hex escape, a control escape or `e` itself */
+  private escapedChar(e: string): string {
+    if (e === "x" || e === "u" || e === "U") {
+      let hex = "";
+      if (this.eat("{")) {
+        while (!this.eat("}")) hex += this.src[this.pos++];
+      } else {
+        const digits = e === "x" ? 2 : e === "u" ? 4 : 8;
+        hex = this.src.slice(this.pos, this.pos + digits).join("");
+        this.pos += digits;
+      }
+      return String.fromCodePoint(parseInt(hex, 16));
+    }
+    return SIMPLE_ESCAPES[e] ?? e;
+  }
+
+  /** Parse a bracketed class after its `[` */
+  private bracketed(): Pred {
+    const negated = this.eat("^");
+    const items: Pred[] = [];
+    let first = true;
+    while (first || this.peek() !== "]") {
+      if (this.pos >= this.src.length) throw new Error("unclosed class");
+      first = false;
+      let lo: string;
+      const c = this.src[this.pos++];
+      if (c === "\\") {
+        const e = this.src[this.pos++];
+        if ("dDwWsS".includes(e)) {
+          items.push(this.escape(e));
+          continue;
+        }
+        lo = this.escapedChar(e);
+      } else {
+        lo = c;
+      }
+      if (this.peek() === "-" && this.src[this.pos + 1] !== "]" && this.pos + 1 < this.src.length) {
+        this.pos++;
+        const h = this.src[this.pos++];
+        const hi = h === "\\" ? this.escapedChar(this.src[this.pos++]) : h;
+        const [a, b] = [lo.codePointAt(0)!, hi.codePointAt(0)!];
+        items.push((x) => x.codePointAt(0)! >= a && x.codePointAt(0)! <= b);
+      } else {
+        items.push((x) => x === lo);
+      }
+    }
+    this.pos++;
+    const inClass: Pred = (x) => items.some((item) => item(x));
+    return negated ? not(inClass) : inClass;
+  }
+}
+
+class Compiler {
+  prog: Inst[] = [];
+
+  private emit(inst: Inst): number {
+    this.prog.push(inst);
+    return this.prog.length - 1;
+  }
+
+  compile(node: Node) {
+    switch (node.t) {
+      case "empty":
+        return;
+      case "char":
+        this.emit({ op: "char", pred: node.pred });
+        return;
+      case "assert":
+        this.emit({ op: "assert", kind: node.kind });
+        return;
+      case "group":
+        if (node.index === null) return this.compile(node.node);
+        this.emit({ op: "save", slot: 2 * node.index });
+        this.compile(node.node);
+        this.emit({ op: "save", slot: 2 * node.index + 1 });
+        return;
+      case "concat":
+        for (const n of node.nodes) this.compile(n);
+        return;
+      case "alt": {
+        // split L1, next; L1: a; jmp end; next: split L2, ...
+        const jumps: number[] = [];
+        node.nodes.forEach((n, i) => {
+          if (i < node.nodes.length - 1) {
+            const split = this.emit({ op: "split", x: 0, y: 0 });
+            this.compile(n);
+            jumps.push(this.emit({ op: "jmp", to: 0 }));
+            this.setSplit(split, split + 1, this.prog.length);
+          } else {
+            this.compile(n);
+          }
+        });
+        for (const j of jumps) (this.prog[j] as { to: number }).to = this.prog.length;
+        return;
+      }
+      case "repeat":
+        return this.repeat(node.node, node.min, node.max, node.greedy);
+    }
+  }
+
+  private setSplit(at: number, body: number, skip: number) {
+    const inst = this.prog[at] as { x: number; y: number };
+    inst.x = body;
+    inst.y = skip;
+  }
+
+  /** Prefer `body` when greedy and `skip` when lazy */
+  private split(at: number, body: number, skip: number, greedy: boolean) {
+    if (greedy) this.setSplit(at, body, skip);
+    else this.setSplit(at, skip, body);
+  }
+
+  private repeat(node: Node, min: number, max: number, greedy: boolean) {
+    for (let i = 0; i < min; i++) this.compile(node);
+    if (max === -1) {
+      // L: split body, end; body: node; jmp L
+      const loop = this.emit({ op: "split", x: 0, y: 0 });
+      this.compile(node);
+      this.emit({ op: "jmp", to: loop });
+      this.split(loop, loop + 1, this.prog.length, greedy);
+      return;
+    }
+    // Each optional copy: split body, end; body: node
+    const splits: number[] = [];
+    for (let i = min; i < max; i++) {
+      splits.push(this.emit({ op: "split", x: 0, y: 0 }));
+      this.compile(node);
+    }
+    for (const s of splits) this.split(s, s + 1, this.prog.length, greedy);
+  }
+}
+
+/** Compile a pattern the checker has validated */
+export function compile(pattern: string): Regex {
+  const parser = new Parser(Array.from(pattern));
+  const node = parser.parse();
+  const compiler = new Compiler();
+  compiler.compile({ t: "group", index: 0, node });
+  compiler.prog.push({ op: "match" });
+  return { prog: compiler.prog, groups: parser.groups, names: parser.names };
+}
+
+type Slots = (number | undefined)[];
+
+interface Thread {
+  pc: number;
+  slots: Slots;
+}
+
+/**
+ * Find the leftmost-first match at or after code point `start`, returning
+ * the capture slots (start and end of each group) or null
+ */
+function search(re: Regex, text: string[], start: number): Slots | null {
+  const { prog } = re;
+  const nslots = 2 * (re.groups + 1);
+  let clist: Thread[] = [];
+  let matched: Slots | null = null;
+  // Generation marks: a pc is added to a list at most once per position
+  const seen = new Array<number>(prog.length).fill(-1);
+
+  const add = (list: Thread[], pc: number, pos: number, slots: Slots) => {
+    if (seen[pc] === pos) return;
+    seen[pc] = pos;
+    const inst = prog[pc];
+    switch (inst.op) {
+      case "jmp":
+        return add(list, inst.to, pos, slots);
+      case "split":
+        add(list, inst.x, pos, slots);
+        return add(list, inst.y, pos, slots);
+      case "save": {
+        const copy = slots.slice();
+        copy[inst.slot] = pos;
+        return add(list, pc + 1, pos, copy);
+      }
+      case "assert":
+        if (assertionHolds(inst.kind, text, pos)) add(list, pc + 1, pos, slots);
+        return;
+      default:
+        list.push({ pc, slots });
+    }
+  };
+
+  for (let pos = start; pos <= text.length; pos++) {
+    // The new thread starting here has the lowest priority
+    if (matched === null) add(clist, 0, pos, new Array(nslots));
+    if (clist.length === 0) {
+      if (matched !== null) break;
+      continue;
+    }
+    const nlist: Thread[] = [];
+    const c = text[pos];
+    for (const thread of clist) {
+      const inst = prog[thread.pc];
+      if (inst.op === "match") {
+        // Lower-priority threads can only produce less preferred matches
+        matched = thread.slots;
+        break;
+      }
+      if (inst.op === "char" && c !== undefined && inst.pred(c)) {
+        add(nlist, thread.pc + 1, pos + 1, thread.slots);
+      }
+    }
+    clist = nlist;
+  }
+  return matched;
+}
+
+function assertionHolds(kind: string, text: string[], pos: number): boolean {
+  switch (kind) {
+    case "start":
+      return pos === 0;
+    case "end":
+      return pos === text.length;
+    default: {
+      const before = pos > 0 && isWord(text[pos - 1]);
+      const after = pos < text.length && isWord(text[pos]);
+      return (before !== after) === (kind === "word");
+    }
+  }
+}
+
+const cache = new Map<string, Regex>();
+
+function cached(pattern: string): Regex {
+  let re = cache.get(pattern);
+  if (!re) {
+    re = compile(pattern);
+    cache.set(pattern, re);
+  }
+  return re;
+}
+
+/** Whether `pattern` matches anywhere in `s` */
+export function regexMatch(s: string, pattern: string): boolean {
+  return search(cached(pattern), Array.from(s), 0) !== null;
+}
+
+/**
+ * The capture slots of every match, left to right and not overlapping. An
+ * empty match right after a previous match is skipped.
+ */
+function* matches(re: Regex, text: string[]): Generator<Slots> {
+  let pos = 0;
+  let lastEnd = -1;
+  while (pos <= text.length) {
+    const slots = search(re, text, pos);
+    if (!slots) return;
+    const [start, end] = [slots[0]!, slots[1]!];
+    if (start === end && start === lastEnd) {
+      pos = start + 1;
+      continue;
+    }
+    yield slots;
+    lastEnd = end;
+    pos = end > start ? end : end + 1;
+  }
+}
+
+/** The text of every match of `pattern` in `s` */
+export function regexFindAll(s: string, pattern: string): string[] {
+  const text = Array.from(s);
+  return Array.from(matches(cached(pattern), text), (slots) => text.slice(slots[0], slots[1]).join(""));
+}
+
+/**
+ * The first match and its groups: `[whole, group1, ...]`, with "" for a
+ * group that did not take part, or `[]` if there is no match
+ */
+export function regexCapture(s: string, pattern: string): string[] {
+  const text = Array.from(s);
+  const slots = search(cached(pattern), text, 0);
+  if (!slots) return [];
+  const groups: string[] = [];
+  for (let i = 0; i < slots.length; i += 2) {
+    const [a, b] = [slots[i], slots[i + 1]];
+    groups.push(a === undefined || b === undefined ? "" : text.slice(a, b).join(""));
+  }
+  return groups;
+}
+
+/**
+ * Replace every match with `replacement`, where `$n` and `${name}` insert a
+ * group and `$$` inserts `$`. An empty match right after a previous match
+ * is skipped.
+ */
+export function regexReplace(s: string, pattern: string, replacement: string): string {
+  const re = cached(pattern);
+  const text = Array.from(s);
+  let out = "";
+  let copied = 0;
+  for (const slots of matches(re, text)) {
+    out += text.slice(copied, slots[0]).join("") + expand(replacement, re, text, slots);
+    copied = slots[1]!;
+  }
+  return out + text.slice(copied).join("");
+}
+
+function expand(replacement: string, re: Regex, text: string[], slots: Slots): string {
+  return replacement.replace(/\$(\$|\d+|\{(\w+)\})/g, (whole, ref: string, braced?: string) => {
+    if (ref === "$") return "$";
+    const name = braced ?? ref;
+    const index = /^\d+$/.test(name) ? Number(name) : re.names.get(name);
+    if (index === undefined || index > re.groups) return "";
+    const [a, b] = [slots[2 * index], slots[2 * index + 1]];
+    return a === undefined || b === undefined ? "" : text.slice(a, b).join("");
+  });
+}
+
+/** The `regex` import module, reading and writing strings through the given helpers */
+export function regexImports(
+  readStr: (ptr: number, len: number) => string,
+  writeStr: (s: string) => bigint,
+  writeStrArray: (parts: string[]) => bigint,
+): Record<string, (...args: number[]) => bigint> {
+  return {
+    match: (sp, sl, pp, pl) => (regexMatch(readStr(sp, sl), readStr(pp, pl)) ? 1n : 0n),
+    find_all: (sp, sl, pp, pl) => writeStrArray(regexFindAll(readStr(sp, sl), readStr(pp, pl))),
+    capture: (sp, sl, pp, pl) => writeStrArray(regexCapture(readStr(sp, sl), readStr(pp, pl))),
+    replace: (sp, sl, pp, pl, rp, rl) =>
+      writeStr(regexReplace(readStr(sp, sl), readStr(pp, pl), readStr(rp, rl))),
+  };
+}
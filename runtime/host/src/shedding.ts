@@ -0,0 +1,149 @@
+/**
+ * Rate Limits and Circuit Breakers
+ *
+ * Externs can declare `rate_limit` and `circuit_breaker` metadata, which
+ * the compiler copies into the audit table (see audit.ts). `shedImports`
+ * enforces them per extern:
+ *
+ * - `rate_limit="100/m"` admits at most 100 calls in any minute
+ * - `circuit_breaker="5/30s"` opens after 5 consecutive failures, sheds
+ *   every call for 30 seconds, then lets one trial call through; a trial
+ *   that fails opens it again
+ *
+ * A call fails when the host throws or returns the error member of the
+ * result union (0). A shed call does not reach the host: it returns 0,
+ * which the module sees as the extern's `ShedError` member (the checker
+ * requires externs with limits to declare it), and is reported to the sink
+ * as a `ShedError`.
+ */
+
+import { AuditTable } from "./audit.ts";
+
+/** Why a call was shed, reported to the sink */
+export class ShedError extends Error {
+  constructor(
+    /** Snippet ID of the extern */
+    readonly extern: string,
+    /** The policy that shed the call: "rate_limit" or "circuit_breaker" */
+    readonly policy: "rate_limit" | "circuit_breaker",
+    /** The policy as declared, e.g. "100/m" */
+    readonly limit: string
+  ) {
+    super(
+      policy === "rate_limit"
+        ? `${extern}: rate limit ${limit} exceeded`
+        : `${extern}: circuit open after ${limit.split("/")[0]} consecutive failures`
+    );
+    this.name = "ShedError";
+  }
+}
+
+export type ShedSink = (error: ShedError) => void;
+
+/** The result a shed call returns: the error member of the result union */
+const SHED_RESULT = 0n;
+
+/** Parse `<count>/<duration>` ("100/m", "5/30s") into the count and milliseconds */
+export function parsePolicy(text: string): { count: number; windowMs: number } | null {
+  const match = /^\s*(\d+)\s*\/\s*(\d*)([smh])\s*$/.exec(text);
+  if (!match) {
+    return null;
+  }
+  const unitMs = { s: 1000, m: 60_000, h: 3_600_000 }[match[3] as "s" | "m" | "h"];
+  return { count: Number(match[1]), windowMs: Number(match[2] || "1") * unitMs };
+}
+
+/** Admission state of one extern */
+class Limiter {
+  /** Start times of the calls admitted within the current window */
+  private readonly admitted: number[] = [];
+  private failures = 0;
+  private openUntil = 0;
+
+  constructor(
+    private readonly rate: { count: number; windowMs: number } | null,
+    private readonly breaker: { count: number; windowMs: number } | null
+  ) {}
+
+  /** The policy that sheds a call at `now`, or null to admit it */
+  admit(now: number): "rate_limit" | "circuit_breaker" | null {
+    if (this.breaker && now < this.openUntil) {
+      return "circuit_breaker";
+    }
+    if (this.rate) {
+      while (this.admitted.length > 0 && this.admitted[0] <= now - this.rate.windowMs) {
+        this.admitted.shift();
+      }
+      if (this.admitted.length >= this.rate.count) {
+        return "rate_limit";
+      }
+      this.admitted.push(now);
+    }
+    return null;
+  }
+
+  /** Record the outcome of an admitted call that ended at `now` */
+  record(failed: boolean, now: number): void {
+    if (!this.breaker) {
+      return;
+    }
+    this.failures = failed ? this.failures + 1 : 0;
+    if (this.failures >= this.breaker.count) {
+      // Open; after the cooldown one trial call closes it or opens it again
+      this.openUntil = now + this.breaker.windowMs;
+      this.failures = this.breaker.count - 1;
+    }
+  }
+}
+
+/**
+ * Wrap the externs in `imports` that declare a rate limit or circuit
+ * breaker so calls over the limit are shed and reported to `sink`. Other
+ * imports are returned unchanged.
+ */
+export function shedImports(
+  imports: WebAssembly.Imports,
+  table: AuditTable,
+  sink: ShedSink,
+  now: () => number = Date.now
+): WebAssembly.Imports {
+  const shed: WebAssembly.Imports = { ...imports };
+
+  for (const [importName, ext] of Object.entries(table.externs)) {
+    const rate = ext.rate_limit ? parsePolicy(ext.rate_limit) : null;
+    const breaker = ext.circuit_breaker ? parsePolicy(ext.circuit_breaker) : null;
+    if (!rate && !breaker) {
+      continue;
+    }
+    const lastDot = importName.lastIndexOf(".");
+    const moduleName = importName.substring(0, lastDot);
+    const funcName = importName.substring(lastDot + 1);
+    const original = imports[moduleName]?.[funcName];
+    if (typeof original !== "function") {
+      continue;
+    }
+
+    const limiter = new Limiter(rate, breaker);
+    shed[moduleName] = {
+      ...shed[moduleName],
+      [funcName]: (...args: unknown[]) => {
+        const policy = limiter.admit(now());
+        if (policy) {
+          sink(new ShedError(ext.snippet, policy, (policy === "rate_limit" ? ext.rate_limit : ext.circuit_breaker)!));
+          return SHED_RESULT;
+        }
+        let result: unknown;
+        try {
+          result = (original as Function)(...args);
+        } catch (e) {
+          limiter.record(true, now());
+          throw e;
+        }
+        limiter.record(result === SHED_RESULT, now());
+        return result;
+      },
+    };
+  }
+
+  return shed;
+}
@@ -0,0 +1,124 @@
+/**
+ * Workflow Checkpoint Journal
+ *
+ * A workflow run records every effectful extern call it completes as a
+ * checkpoint. Resuming a run executes the workflow again from the start and
+ * answers its effectful calls from the checkpoints, in order, instead of
+ * performing them again. Once the checkpoints run out the calls are live.
+ *
+ * ```
+ * extern call ──► checkpoint left? ──yes──► extern and args match? ──► recorded result
+ *                        │ no                      │ no
+ *                        ▼                         ▼
+ *          original extern, then append      ReplayDivergedError
+ * ```
+ *
+ * The externs and call sites come from the audit table (see audit.ts). The
+ * checker keeps workflows replay safe, so the same calls happen in the same
+ * order on every run.
+ */
+
+import { AuditContext, AuditTable, currentSite, decodeArgs } from "./audit.ts";
+
+/** A recorded result: strings, numbers, string lists, or null */
+export type CheckpointValue = string | number | string[] | null;
+
+/** One completed effectful call, written as a JSONL line by the runners */
+export interface Checkpoint {
+  /** ISO 8601 time the call returned */
+  timestamp: string;
+  /** Snippet ID of the extern */
+  extern: string;
+  /** Calling snippet and step, if the module reported them */
+  snippet: string | null;
+  step: string | null;
+  /** Hash of the decoded arguments, checked on replay */
+  args_hash: string;
+  result: CheckpointValue;
+}
+
+export type CheckpointSink = (checkpoint: Checkpoint) => void;
+
+/** How the host moves results in and out of the running module */
+export interface JournalContext extends AuditContext {
+  /** Decode a raw extern result per the extern's `returns` kind */
+  readResult: (kind: string, raw: unknown) => CheckpointValue;
+  /** Encode a recorded result as the raw value the module expects */
+  writeResult: (kind: string, value: CheckpointValue) => unknown;
+}
+
+/** A resumed run made a different call than the one its checkpoint records */
+export class ReplayDivergedError extends Error {
+  constructor(index: number, expected: Checkpoint, actual: string) {
+    super(
+      `workflow replay diverged at checkpoint ${index + 1}: ` +
+        `recorded ${expected.extern} at step ${expected.step ?? "?"}, but the workflow ` +
+        (actual === expected.extern ? "passed different arguments" : `called ${actual}`)
+    );
+    this.name = "ReplayDivergedError";
+  }
+}
+
+/** Parse a checkpoint journal (one JSON object per line) */
+export function readJournal(text: string): Checkpoint[] {
+  return text
+    .split("\n")
+    .filter((line) => line.trim() !== "")
+    .map((line) => JSON.parse(line) as Checkpoint);
+}
+
+/**
+ * Wrap the audited externs in `imports` so calls are answered from
+ * `history` first, and each live call is reported to `sink` after it returns.
+ */
+export function journalImports(
+  imports: WebAssembly.Imports,
+  table: AuditTable,
+  history: Checkpoint[],
+  sink: CheckpointSink,
+  context: JournalContext
+): WebAssembly.Imports {
+  const journaled: WebAssembly.Imports = { ...imports };
+  let next = 0;
+
+  for (const [importName, ext] of Object.entries(table.externs)) {
+    const lastDot = importName.lastIndexOf(".");
+    const moduleName = importName.substring(0, lastDot);
+    const funcName = importName.substring(lastDot + 1);
+    const original = imports[moduleName]?.[funcName];
+    if (typeof original !== "function") {
+      continue;
+    }
+
+    journaled[moduleName] = {
+      ...journaled[moduleName],
+      [funcName]: (...args: unknown[]) => {
+        const argsHash = context.hash(JSON.stringify(decodeArgs(ext, args, context.memory())));
+
+        if (next < history.length) {
+          const recorded = history[next];
+          if (recorded.extern !== ext.snippet || recorded.args_hash !== argsHash) {
+            throw new ReplayDivergedError(next, recorded, ext.snippet);
+          }
+          next += 1;
+          return context.writeResult(ext.returns, recorded.result);
+        }
+
+        const site = currentSite(table, context.instance());
+        const raw = (original as Function)(...args);
+        sink({
+          timestamp: new Date().toISOString(),
+          extern: ext.snippet,
+          snippet: site?.snippet ?? null,
+          step: site?.step ?? null,
+          args_hash: argsHash,
+          result: context.readResult(ext.returns, raw),
+        });
+        next += 1;
+        return raw;
+      },
+    };
+  }
+
+  return journaled;
+}
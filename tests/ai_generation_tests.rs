@@ -1,8 +0,0 @@
-//! AI Generation Tests
-//!
-//! Run with: cargo test --test ai_generation_tests -- --ignored
-//! Requires OPENAI_API_KEY or ANTHROPIC_API_KEY environment variable
-
-mod ai_generation;
-
-pub use ai_generation::*;